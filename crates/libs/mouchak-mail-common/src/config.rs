@@ -1,167 +1,1420 @@
-use config::{Config, File};
 use serde::{Deserialize, Serialize};
-use std::env;
 
+pub mod loader;
+
+pub use loader::{CONFIG_KEYS, ConfigLoader, ConfigSource, ResolvedConfig, ResolvedKey};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub mcp: McpConfig,
+    #[serde(default)]
+    pub escalation: EscalationConfig,
+    #[serde(default)]
+    pub quota: QuotaConfig,
+    #[serde(default)]
+    pub push: PushConfig,
+    #[serde(default)]
+    pub tracing: TracingConfig,
+    #[serde(default)]
+    pub notifier: NotifierConfig,
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    #[serde(default)]
+    pub event_bridge: EventBridgeConfig,
+    #[serde(default)]
+    pub github: GithubConfig,
+    #[serde(default)]
+    pub tickets: TicketsConfig,
+    #[serde(default)]
+    pub tool_call_log: ToolCallLogConfig,
+    #[serde(default)]
+    pub archive_gc: ArchiveGcConfig,
+    #[serde(default)]
+    pub db_maintenance: DbMaintenanceConfig,
+    #[serde(default)]
+    pub message_body: MessageBodyConfig,
+    #[serde(default)]
+    pub thread_archival: ThreadArchivalConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    #[serde(default)]
+    pub email_bridge: EmailBridgeConfig,
+    #[serde(default)]
+    pub exports: ExportsConfig,
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    #[serde(default)]
+    pub disk_watch: DiskWatchConfig,
+    #[serde(default)]
+    pub attachments: AttachmentsConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub auth_hmac: Option<String>,
+    /// Enable serving embedded web UI (when compiled with with-web-ui feature)
+    #[serde(default = "default_serve_ui")]
+    pub serve_ui: bool,
+}
+
+fn default_serve_ui() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EscalationMode {
+    #[default]
+    Log,
+    FileReservation,
+    Overseer,
+    /// Remind only the recipients that have not acknowledged yet
+    Remind,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EscalationConfig {
+    #[serde(default)]
+    pub ack_ttl_enabled: bool,
+    #[serde(default = "default_ack_ttl_seconds")]
+    pub ack_ttl_seconds: u64,
+    #[serde(default)]
+    pub escalation_enabled: bool,
+    #[serde(default)]
+    pub escalation_mode: EscalationMode,
+    #[serde(default = "default_scan_interval_seconds")]
+    pub scan_interval_seconds: u64,
+}
+
+fn default_ack_ttl_seconds() -> u64 {
+    1800 // 30 minutes
+}
+
+fn default_scan_interval_seconds() -> u64 {
+    300 // 5 minutes
+}
+
+impl Default for EscalationConfig {
+    fn default() -> Self {
+        Self {
+            ack_ttl_enabled: false,
+            ack_ttl_seconds: default_ack_ttl_seconds(),
+            escalation_enabled: false,
+            escalation_mode: EscalationMode::default(),
+            scan_interval_seconds: default_scan_interval_seconds(),
+        }
+    }
+}
+
+impl EscalationConfig {
+    pub fn from_env() -> Self {
+        Self {
+            ack_ttl_enabled: parse_bool_env("ACK_TTL_ENABLED"),
+            ack_ttl_seconds: std::env::var("ACK_TTL_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_else(default_ack_ttl_seconds),
+            escalation_enabled: parse_bool_env("ACK_ESCALATION_ENABLED"),
+            escalation_mode: std::env::var("ACK_ESCALATION_MODE")
+                .ok()
+                .and_then(|m| match m.to_lowercase().as_str() {
+                    "log" => Some(EscalationMode::Log),
+                    "file_reservation" => Some(EscalationMode::FileReservation),
+                    "overseer" => Some(EscalationMode::Overseer),
+                    "remind" => Some(EscalationMode::Remind),
+                    _ => None,
+                })
+                .unwrap_or_default(),
+            scan_interval_seconds: std::env::var("ACK_SCAN_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_else(default_scan_interval_seconds),
+        }
+    }
+}
+
+/// Project identity resolution mode for slug generation.
+///
+/// Controls how project slugs are computed to ensure privacy-safe identifiers.
+/// See Python reference: `_compute_project_slug()` in mouchak_mail/app.py
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProjectIdentityMode {
+    /// Default: plain slugify (backward compatible but may leak path info)
+    #[default]
+    Dir,
+    /// Use Git remote URL to derive slug: `{repo_name}-{sha1_hash[:10]}`
+    /// Most privacy-safe option - derives from remote origin URL
+    GitRemote,
+    /// Use Git repository root directory name + hash
+    GitToplevel,
+    /// Use Git common dir (for worktrees) + hash
+    GitCommonDir,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct McpConfig {
+    pub transport: String,
+    pub port: u16,
+    /// Enable worktree-related features (build slots, pre-commit guard)
+    #[serde(default)]
+    pub worktrees_enabled: bool,
+    /// Enable git identity features
+    #[serde(default)]
+    pub git_identity_enabled: bool,
+    /// Project identity resolution mode for slug generation
+    #[serde(default)]
+    pub project_identity_mode: ProjectIdentityMode,
+    /// Remote name to use for git-remote mode (default: "origin")
+    #[serde(default = "default_project_identity_remote")]
+    pub project_identity_remote: String,
+    /// Post a journal of each session's significant tool calls at session end
+    #[serde(default)]
+    pub session_journal_enabled: bool,
+    /// Thread that session journals are posted to (default: "session-journal")
+    #[serde(default = "default_session_journal_thread")]
+    pub session_journal_thread: String,
+}
+
+fn default_project_identity_remote() -> String {
+    "origin".to_string()
+}
+
+fn default_session_journal_thread() -> String {
+    "session-journal".to_string()
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct QuotaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_quota_attachments_limit_bytes")]
+    pub attachments_limit_bytes: u64,
+    #[serde(default = "default_quota_inbox_limit_count")]
+    pub inbox_limit_count: u64,
+    /// Messages an agent may send in total; 0 means unlimited
+    #[serde(default)]
+    pub agent_messages_limit_count: u64,
+    /// Attachment bytes an agent may upload in total; 0 means unlimited
+    #[serde(default)]
+    pub agent_attachments_limit_bytes: u64,
+}
+
+fn default_quota_attachments_limit_bytes() -> u64 {
+    100 * 1024 * 1024 // 100 MB
+}
+
+fn default_quota_inbox_limit_count() -> u64 {
+    1000
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            attachments_limit_bytes: default_quota_attachments_limit_bytes(),
+            inbox_limit_count: default_quota_inbox_limit_count(),
+            agent_messages_limit_count: 0,
+            agent_attachments_limit_bytes: 0,
+        }
+    }
+}
+
+/// Web Push notifications for human supervisors.
+///
+/// When `vapid_private_key` is unset, a key pair is generated and stored in
+/// the database on first use.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PushConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Contact URI sent to push services in the VAPID `sub` claim
+    #[serde(default = "default_push_vapid_subject")]
+    pub vapid_subject: String,
+    /// Base64url-encoded P-256 private key
+    #[serde(default)]
+    pub vapid_private_key: Option<String>,
+    #[serde(default = "default_push_scan_interval_seconds")]
+    pub scan_interval_seconds: u64,
+}
+
+fn default_push_vapid_subject() -> String {
+    "mailto:admin@localhost".to_string()
+}
+
+fn default_push_scan_interval_seconds() -> u64 {
+    15
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vapid_subject: default_push_vapid_subject(),
+            vapid_private_key: None,
+            scan_interval_seconds: default_push_scan_interval_seconds(),
+        }
+    }
+}
+
+/// Event that can trigger the local notifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifierEvent {
+    /// A message sent with `urgent` importance
+    UrgentMessage,
+    /// An overdue acknowledgment was escalated
+    Escalation,
+}
+
+impl NotifierEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UrgentMessage => "urgent_message",
+            Self::Escalation => "escalation",
+        }
+    }
+}
+
+/// Local desktop notifier.
+///
+/// Runs `command` on the configured events, e.g.
+/// `notify-send "{title}" "{body}"`. The template is split into arguments
+/// like a shell would, then `{placeholder}`s are substituted in each
+/// argument; no shell is involved, so message text cannot inject commands.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NotifierConfig {
+    /// Command line template; unset disables the notifier
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Comma-separated events: `urgent_message`, `escalation`
+    #[serde(default = "default_notifier_events")]
+    pub events: String,
+}
+
+fn default_notifier_events() -> String {
+    "urgent_message,escalation".to_string()
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            events: default_notifier_events(),
+        }
+    }
+}
+
+impl NotifierConfig {
+    /// Whether a command is configured and `event` is one of `events`.
+    pub fn notifies(&self, event: NotifierEvent) -> bool {
+        self.command
+            .as_deref()
+            .is_some_and(|c| !c.trim().is_empty())
+            && self
+                .events
+                .split(',')
+                .any(|e| e.trim().eq_ignore_ascii_case(event.as_str()))
+    }
+}
+
+/// Operator WASM plugins.
+///
+/// Plugins run on message events and can add MCP tools. They only take
+/// effect in builds with the `wasm-plugins` feature of `mouchak-mail-core`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PluginsConfig {
+    /// Comma-separated `.wasm` files or directories of them; empty disables plugins
+    #[serde(default)]
+    pub paths: String,
+    /// Instructions a plugin may execute per call before it is stopped
+    #[serde(default = "default_plugin_fuel")]
+    pub fuel: u64,
+    /// Maximum linear memory per plugin instance, in MiB
+    #[serde(default = "default_plugin_memory_mb")]
+    pub memory_mb: u64,
+}
+
+fn default_plugin_fuel() -> u64 {
+    50_000_000
+}
+
+fn default_plugin_memory_mb() -> u64 {
+    32
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            paths: String::new(),
+            fuel: default_plugin_fuel(),
+            memory_mb: default_plugin_memory_mb(),
+        }
+    }
+}
+
+impl PluginsConfig {
+    /// The configured paths, trimmed and without empty entries.
+    pub fn path_list(&self) -> Vec<&str> {
+        self.paths
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+}
+
+/// Event bridge to a message queue.
+///
+/// Events appended to the event log are also published to NATS subjects or
+/// a Redis stream, so other services can follow them without polling.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EventBridgeConfig {
+    /// `nats://[user:pass@]host:port` or `redis://[:pass@]host:port[/db]`; empty disables the bridge
+    #[serde(default)]
+    pub url: String,
+    /// NATS subject prefix (`<prefix>.<kind>`) or Redis stream key
+    #[serde(default = "default_event_bridge_prefix")]
+    pub prefix: String,
+    /// Approximate maximum length of the Redis stream (`XADD MAXLEN ~`)
+    #[serde(default = "default_event_bridge_max_len")]
+    pub max_len: u64,
+}
+
+fn default_event_bridge_prefix() -> String {
+    "mouchak".to_string()
+}
+
+fn default_event_bridge_max_len() -> u64 {
+    100_000
+}
+
+impl Default for EventBridgeConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            prefix: default_event_bridge_prefix(),
+            max_len: default_event_bridge_max_len(),
+        }
+    }
+}
+
+/// GitHub issue and PR cross-posting.
+///
+/// Threads are mirrored to the repository mapped to their project; without a
+/// token nothing is posted or synced.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GithubConfig {
+    /// Token with access to the issues of mapped repositories
+    #[serde(default)]
+    pub token: Option<String>,
+    /// REST API base URL (GitHub Enterprise: `https://<host>/api/v3`)
+    #[serde(default = "default_github_api_url")]
+    pub api_url: String,
+    /// How often mirrored threads are synced in both directions
+    #[serde(default = "default_github_sync_interval_seconds")]
+    pub sync_interval_seconds: u64,
+}
+
+fn default_github_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+fn default_github_sync_interval_seconds() -> u64 {
+    120
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            api_url: default_github_api_url(),
+            sync_interval_seconds: default_github_sync_interval_seconds(),
+        }
+    }
+}
+
+impl GithubConfig {
+    /// The configured token, if it is not blank.
+    pub fn token(&self) -> Option<&str> {
+        self.token
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+    }
+}
+
+/// Jira and Linear ticket linking.
+///
+/// Linked tickets show their cached title and status; a provider without
+/// credentials still accepts links but never fetches metadata.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TicketsConfig {
+    /// Jira site URL, e.g. `https://acme.atlassian.net`
+    #[serde(default)]
+    pub jira_url: Option<String>,
+    /// Account email for Jira API token authentication
+    #[serde(default)]
+    pub jira_email: Option<String>,
+    /// Jira API token
+    #[serde(default)]
+    pub jira_token: Option<String>,
+    /// Linear personal API key
+    #[serde(default)]
+    pub linear_api_key: Option<String>,
+    /// Linear GraphQL endpoint
+    #[serde(default = "default_linear_api_url")]
+    pub linear_api_url: String,
+    /// How old cached ticket metadata may get before it is fetched again
+    #[serde(default = "default_ticket_refresh_interval_seconds")]
+    pub refresh_interval_seconds: u64,
+}
+
+fn default_linear_api_url() -> String {
+    "https://api.linear.app/graphql".to_string()
+}
+
+fn default_ticket_refresh_interval_seconds() -> u64 {
+    300
+}
+
+impl Default for TicketsConfig {
+    fn default() -> Self {
+        Self {
+            jira_url: None,
+            jira_email: None,
+            jira_token: None,
+            linear_api_key: None,
+            linear_api_url: default_linear_api_url(),
+            refresh_interval_seconds: default_ticket_refresh_interval_seconds(),
+        }
+    }
+}
+
+impl TicketsConfig {
+    /// Jira site URL, email and token, if all are set.
+    pub fn jira(&self) -> Option<(&str, &str, &str)> {
+        Some((
+            non_blank(&self.jira_url)?.trim_end_matches('/'),
+            non_blank(&self.jira_email)?,
+            non_blank(&self.jira_token)?,
+        ))
+    }
+
+    /// The Linear API key, if it is set.
+    pub fn linear_api_key(&self) -> Option<&str> {
+        non_blank(&self.linear_api_key)
+    }
+
+    /// Whether metadata can be fetched from any provider.
+    pub fn any_provider(&self) -> bool {
+        self.jira().is_some() || self.linear_api_key().is_some()
+    }
+}
+
+fn non_blank(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Archiving of tool calls into the Git archive.
+///
+/// Each record carries the tool name, caller, duration and result status but
+/// no arguments. Records are buffered and committed together, so the archive
+/// gets one commit per batch rather than per call.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ToolCallLogConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Buffered records that trigger a commit
+    #[serde(default = "default_tool_call_log_batch_size")]
+    pub batch_size: usize,
+    /// Longest time a buffered record waits for its commit
+    #[serde(default = "default_tool_call_log_flush_interval_seconds")]
+    pub flush_interval_seconds: u64,
+}
+
+fn default_tool_call_log_batch_size() -> usize {
+    100
+}
+
+fn default_tool_call_log_flush_interval_seconds() -> u64 {
+    60
+}
+
+impl Default for ToolCallLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            batch_size: default_tool_call_log_batch_size(),
+            flush_interval_seconds: default_tool_call_log_flush_interval_seconds(),
+        }
+    }
+}
+
+/// Scheduled compaction of the Git archive.
+///
+/// The server runs `git gc` on the archive every `interval_seconds` once
+/// enough loose objects have accumulated; `archive gc` runs it by hand.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ArchiveGcConfig {
+    /// How often the archive is checked; 0 disables the job
+    #[serde(default)]
+    pub interval_seconds: u64,
+    /// Loose objects needed before a scheduled run compacts
+    #[serde(default = "default_archive_gc_min_loose_objects")]
+    pub min_loose_objects: u64,
+    /// Run `git gc --aggressive`
+    #[serde(default)]
+    pub aggressive: bool,
+}
+
+fn default_archive_gc_min_loose_objects() -> u64 {
+    1000
+}
+
+impl Default for ArchiveGcConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: 0,
+            min_loose_objects: default_archive_gc_min_loose_objects(),
+            aggressive: false,
+        }
+    }
+}
+
+/// Scheduled SQLite maintenance.
+///
+/// The server checks integrity and runs `ANALYZE` every `interval_seconds`,
+/// and `VACUUM` once free pages make up `vacuum_free_ratio` of the file;
+/// `db maintain` runs the same by hand.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DbMaintenanceConfig {
+    /// How often maintenance runs; 0 disables the job
+    #[serde(default)]
+    pub interval_seconds: u64,
+    /// Free page ratio (0.0-1.0) at which the database is vacuumed
+    #[serde(default = "default_db_vacuum_free_ratio")]
+    pub vacuum_free_ratio: f64,
+}
+
+fn default_db_vacuum_free_ratio() -> f64 {
+    0.2
+}
+
+impl Default for DbMaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: 0,
+            vacuum_free_ratio: default_db_vacuum_free_ratio(),
+        }
+    }
+}
+
+/// Size cap for message bodies stored inline.
+///
+/// A body larger than `max_inline_bytes` is stored separately; the message
+/// keeps its first `preview_bytes` and a note to fetch the rest with
+/// `get_message(full=true)`, so inbox payloads stay small.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MessageBodyConfig {
+    /// Largest body kept inline; 0 disables offloading
+    #[serde(default = "default_message_body_max_inline_bytes")]
+    pub max_inline_bytes: u64,
+    /// Size of the preview kept in place of an offloaded body
+    #[serde(default = "default_message_body_preview_bytes")]
+    pub preview_bytes: u64,
+}
+
+fn default_message_body_max_inline_bytes() -> u64 {
+    64 * 1024
+}
+
+fn default_message_body_preview_bytes() -> u64 {
+    4 * 1024
+}
+
+impl Default for MessageBodyConfig {
+    fn default() -> Self {
+        Self {
+            max_inline_bytes: default_message_body_max_inline_bytes(),
+            preview_bytes: default_message_body_preview_bytes(),
+        }
+    }
+}
+
+/// Automatic archival of inactive threads.
+///
+/// Every `interval_seconds` the server archives threads without messages
+/// for `inactive_days`, hiding them from the default `list_threads`. A new
+/// reply reactivates an archived thread.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ThreadArchivalConfig {
+    /// Days without messages before a thread is archived; 0 disables archival
+    #[serde(default)]
+    pub inactive_days: u64,
+    /// Post a closing summary message to the thread when archiving it
+    #[serde(default)]
+    pub closing_summary: bool,
+    /// How often inactive threads are looked for
+    #[serde(default = "default_thread_archival_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+fn default_thread_archival_interval_seconds() -> u64 {
+    3600
+}
+
+impl Default for ThreadArchivalConfig {
+    fn default() -> Self {
+        Self {
+            inactive_days: 0,
+            closing_summary: false,
+            interval_seconds: default_thread_archival_interval_seconds(),
+        }
+    }
+}
+
+/// What retention does with messages past their project's limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionAction {
+    /// Remove from the database; their copies in the git archive stay
+    #[default]
+    Archive,
+    /// Remove from the database and from the git archive
+    Delete,
+}
+
+impl RetentionAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Archive => "archive",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+impl std::str::FromStr for RetentionAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "archive" => Ok(Self::Archive),
+            "delete" => Ok(Self::Delete),
+            other => Err(format!(
+                "Unknown retention action '{}': expected archive or delete",
+                other
+            )),
+        }
+    }
+}
+
+/// Message retention.
+///
+/// Every `interval_seconds` the server purges messages older than
+/// `max_age_days`, then the oldest messages of projects holding more than
+/// `max_messages`. Projects can override both limits and the action. Each
+/// purge is recorded in the project's git archive under `retention/`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RetentionConfig {
+    /// Days a message is kept; 0 keeps messages regardless of age
+    #[serde(default)]
+    pub max_age_days: u64,
+    /// Messages a project keeps; 0 means no cap
+    #[serde(default)]
+    pub max_messages: u64,
+    /// What happens to purged messages
+    #[serde(default)]
+    pub action: RetentionAction,
+    /// How often limits are enforced; 0 disables the background run
+    #[serde(default = "default_retention_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+fn default_retention_interval_seconds() -> u64 {
+    86400
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: 0,
+            max_messages: 0,
+            action: RetentionAction::default(),
+            interval_seconds: default_retention_interval_seconds(),
+        }
+    }
+}
+
+/// Webhook delivery.
+///
+/// Every `scan_interval_seconds` the server turns new event log records
+/// into deliveries for the webhooks subscribed to them and POSTs the due
+/// ones. A failed delivery is retried after `backoff_base_seconds`, doubling
+/// each time up to `backoff_max_seconds`, and given up after `max_attempts`.
+///
+/// Webhook URLs may not point at loopback, private or
+/// `HTTP_INTERNAL_NETWORKS` addresses, checked when a webhook is created
+/// and against the addresses its host resolves to on delivery, unless
+/// `allow_private_urls` is set for receivers on the server's own network.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WebhooksConfig {
+    /// Run the delivery job
+    #[serde(default = "default_webhooks_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_webhooks_scan_interval_seconds")]
+    pub scan_interval_seconds: u64,
+    /// Attempts before a delivery is marked failed
+    #[serde(default = "default_webhooks_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    #[serde(default = "default_webhooks_backoff_base_seconds")]
+    pub backoff_base_seconds: u64,
+    /// Longest delay between retries
+    #[serde(default = "default_webhooks_backoff_max_seconds")]
+    pub backoff_max_seconds: u64,
+    /// How long a receiver has to answer
+    #[serde(default = "default_webhooks_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// Let webhook URLs point at loopback, private and internal addresses
+    #[serde(default)]
+    pub allow_private_urls: bool,
+}
+
+fn default_webhooks_enabled() -> bool {
+    true
+}
+
+fn default_webhooks_scan_interval_seconds() -> u64 {
+    5
+}
+
+fn default_webhooks_max_attempts() -> u32 {
+    8
+}
+
+fn default_webhooks_backoff_base_seconds() -> u64 {
+    10
+}
+
+fn default_webhooks_backoff_max_seconds() -> u64 {
+    3600
+}
+
+fn default_webhooks_timeout_seconds() -> u64 {
+    10
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_webhooks_enabled(),
+            scan_interval_seconds: default_webhooks_scan_interval_seconds(),
+            max_attempts: default_webhooks_max_attempts(),
+            backoff_base_seconds: default_webhooks_backoff_base_seconds(),
+            backoff_max_seconds: default_webhooks_backoff_max_seconds(),
+            timeout_seconds: default_webhooks_timeout_seconds(),
+            allow_private_urls: false,
+        }
+    }
+}
+
+impl WebhooksConfig {
+    /// Seconds to wait before retrying a delivery that failed `attempts`
+    /// times: the base delay doubled per earlier failure, capped.
+    pub fn retry_delay_seconds(&self, attempts: u32) -> u64 {
+        let doublings = attempts.saturating_sub(1).min(32);
+        self.backoff_base_seconds
+            .saturating_mul(1u64 << doublings)
+            .min(self.backoff_max_seconds)
+    }
+}
+
+/// Email bridge for human supervisors.
+///
+/// Forwards messages of projects that opted in (see the `email_bridge`
+/// project setting) to real mailboxes over SMTP. A message is forwarded when
+/// its importance is one of `importance`, or when its subject contains one
+/// of `subject_keywords`. Unset `smtp_host` disables the bridge.
+///
+/// Mail posted to `/api/ingest/email` comes back in as messages; only
+/// senders listed in `inbound_aliases` are accepted.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EmailBridgeConfig {
+    /// SMTP relay; unset disables the bridge
+    #[serde(default)]
+    pub smtp_host: Option<String>,
+    #[serde(default = "default_email_bridge_smtp_port")]
+    pub smtp_port: u16,
+    /// `starttls`, `tls` (implicit TLS, usually port 465) or `none`
+    #[serde(default = "default_email_bridge_smtp_security")]
+    pub smtp_security: String,
+    /// Login for SMTP `AUTH`; unset sends without authenticating
+    #[serde(default)]
+    pub smtp_username: Option<String>,
+    #[serde(default)]
+    pub smtp_password: Option<String>,
+    /// Sender address of forwarded mail
+    #[serde(default = "default_email_bridge_from")]
+    pub from: String,
+    /// Comma-separated addresses for projects that name no recipients
+    #[serde(default)]
+    pub to: String,
+    /// Comma-separated importance levels that are forwarded
+    #[serde(default = "default_email_bridge_importance")]
+    pub importance: String,
+    /// Comma-separated subject keywords that are forwarded (any case)
+    #[serde(default)]
+    pub subject_keywords: String,
+    #[serde(default = "default_email_bridge_scan_interval_seconds")]
+    pub scan_interval_seconds: u64,
+    /// How long one SMTP session may take
+    #[serde(default = "default_email_bridge_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// Comma-separated `address=agent` pairs naming the agent that inbound
+    /// mail from (or to) an address belongs to
+    #[serde(default)]
+    pub inbound_aliases: String,
+}
+
+fn default_email_bridge_smtp_port() -> u16 {
+    587
+}
+
+fn default_email_bridge_smtp_security() -> String {
+    "starttls".to_string()
+}
+
+fn default_email_bridge_from() -> String {
+    "mouchak-mail@localhost".to_string()
+}
+
+fn default_email_bridge_importance() -> String {
+    "urgent".to_string()
+}
+
+fn default_email_bridge_scan_interval_seconds() -> u64 {
+    30
+}
+
+fn default_email_bridge_timeout_seconds() -> u64 {
+    30
+}
+
+impl Default for EmailBridgeConfig {
+    fn default() -> Self {
+        Self {
+            smtp_host: None,
+            smtp_port: default_email_bridge_smtp_port(),
+            smtp_security: default_email_bridge_smtp_security(),
+            smtp_username: None,
+            smtp_password: None,
+            from: default_email_bridge_from(),
+            to: String::new(),
+            importance: default_email_bridge_importance(),
+            subject_keywords: String::new(),
+            scan_interval_seconds: default_email_bridge_scan_interval_seconds(),
+            timeout_seconds: default_email_bridge_timeout_seconds(),
+            inbound_aliases: String::new(),
+        }
+    }
+}
+
+impl EmailBridgeConfig {
+    /// The configured SMTP host, if it is not blank.
+    pub fn smtp_host(&self) -> Option<&str> {
+        self.smtp_host
+            .as_deref()
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+    }
+
+    /// Domain of the `Message-ID`s of forwarded mail: the domain of `from`,
+    /// or `localhost`.
+    pub fn message_id_domain(&self) -> &str {
+        self.from
+            .trim()
+            .rsplit_once('@')
+            .map(|(_, d)| d)
+            .filter(|d| !d.is_empty())
+            .unwrap_or("localhost")
+    }
+
+    /// Whether a message with this importance and subject is forwarded.
+    pub fn forwards(&self, importance: &str, subject: &str) -> bool {
+        if self
+            .importance
+            .split(',')
+            .any(|i| i.trim().eq_ignore_ascii_case(importance))
+        {
+            return true;
+        }
+        let subject = subject.to_lowercase();
+        self.subject_keywords
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .any(|k| subject.contains(&k.to_lowercase()))
+    }
+}
+
+/// Asynchronous bulk exports (`/api/exports`).
+///
+/// Queued export jobs are run one at a time by a worker that looks for them
+/// every `poll_interval_seconds`; 0 disables the worker. Finished files are
+/// kept under `<data>/exports` for `retention_hours`.
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct AppConfig {
-    pub server: ServerConfig,
-    pub mcp: McpConfig,
-    #[serde(default)]
-    pub escalation: EscalationConfig,
-    #[serde(default)]
-    pub quota: QuotaConfig,
+pub struct ExportsConfig {
+    #[serde(default = "default_exports_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// Most messages one export job may include
+    #[serde(default = "default_exports_max_messages")]
+    pub max_messages: i64,
+    /// How long finished jobs and their files are kept
+    #[serde(default = "default_exports_retention_hours")]
+    pub retention_hours: u64,
+}
+
+fn default_exports_poll_interval_seconds() -> u64 {
+    5
+}
+
+fn default_exports_max_messages() -> i64 {
+    10_000
+}
+
+fn default_exports_retention_hours() -> u64 {
+    24
+}
+
+impl Default for ExportsConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_seconds: default_exports_poll_interval_seconds(),
+            max_messages: default_exports_max_messages(),
+            retention_hours: default_exports_retention_hours(),
+        }
+    }
 }
 
+/// Real-time event streaming over `/api/ws`.
+///
+/// Event log records are fanned out to connected WebSocket clients through
+/// an in-process buffer of `event_buffer` records; a client that falls
+/// further behind is told how many it missed.
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct ServerConfig {
-    pub host: String,
-    pub port: u16,
-    pub auth_hmac: Option<String>,
-    /// Enable serving embedded web UI (when compiled with with-web-ui feature)
-    #[serde(default = "default_serve_ui")]
-    pub serve_ui: bool,
+pub struct WebSocketConfig {
+    /// Serve `/api/ws`
+    #[serde(default = "default_websocket_enabled")]
+    pub enabled: bool,
+    /// Records buffered for slow clients before they miss events
+    #[serde(default = "default_websocket_event_buffer")]
+    pub event_buffer: usize,
+    /// Seconds between keep-alive pings sent to each client
+    #[serde(default = "default_websocket_ping_interval_seconds")]
+    pub ping_interval_seconds: u64,
+    /// How often expired file reservations are announced
+    #[serde(default = "default_websocket_expiry_interval_seconds")]
+    pub expiry_interval_seconds: u64,
 }
 
-fn default_serve_ui() -> bool {
+fn default_websocket_enabled() -> bool {
     true
 }
 
+fn default_websocket_event_buffer() -> usize {
+    1024
+}
+
+fn default_websocket_ping_interval_seconds() -> u64 {
+    30
+}
+
+fn default_websocket_expiry_interval_seconds() -> u64 {
+    30
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_websocket_enabled(),
+            event_buffer: default_websocket_event_buffer(),
+            ping_interval_seconds: default_websocket_ping_interval_seconds(),
+            expiry_interval_seconds: default_websocket_expiry_interval_seconds(),
+        }
+    }
+}
+
+/// Free space monitoring of the data directory.
+///
+/// Every `interval_seconds` the server checks the free space of the volume
+/// holding the database and archive. Below `warn_free_mb` overseers are
+/// alerted; below `read_only_free_mb` the server stops accepting writes
+/// until free space climbs back above `warn_free_mb`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DiskWatchConfig {
+    /// How often free space is checked; 0 disables the watcher
+    #[serde(default = "default_disk_watch_interval_seconds")]
+    pub interval_seconds: u64,
+    /// Free space (MB) below which overseers are warned
+    #[serde(default = "default_disk_watch_warn_free_mb")]
+    pub warn_free_mb: u64,
+    /// Free space (MB) below which the server turns read-only
+    #[serde(default = "default_disk_watch_read_only_free_mb")]
+    pub read_only_free_mb: u64,
+}
+
+fn default_disk_watch_interval_seconds() -> u64 {
+    30
+}
+
+fn default_disk_watch_warn_free_mb() -> u64 {
+    1024
+}
+
+fn default_disk_watch_read_only_free_mb() -> u64 {
+    256
+}
+
+impl Default for DiskWatchConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: default_disk_watch_interval_seconds(),
+            warn_free_mb: default_disk_watch_warn_free_mb(),
+            read_only_free_mb: default_disk_watch_read_only_free_mb(),
+        }
+    }
+}
+
+/// Where attachment content is stored.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
 #[serde(rename_all = "snake_case")]
-pub enum EscalationMode {
+pub enum AttachmentBackend {
+    /// Files under `<data>/attachments`
     #[default]
-    Log,
-    FileReservation,
-    Overseer,
+    Fs,
+    /// An S3-compatible object store (AWS S3, MinIO, R2, ...)
+    S3,
 }
 
+/// Attachment storage.
+///
+/// Metadata always lives in the database; `backend` picks where the content
+/// goes. Attachments already stored on disk stay readable after switching
+/// to `s3`.
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct EscalationConfig {
+pub struct AttachmentsConfig {
     #[serde(default)]
-    pub ack_ttl_enabled: bool,
-    #[serde(default = "default_ack_ttl_seconds")]
-    pub ack_ttl_seconds: u64,
+    pub backend: AttachmentBackend,
+    /// Largest attachment accepted, in bytes
+    #[serde(default = "default_attachments_max_bytes")]
+    pub max_bytes: u64,
+    /// Object store URL, e.g. `https://s3.eu-west-1.amazonaws.com` or `http://minio:9000`
+    #[serde(default = "default_attachments_s3_endpoint")]
+    pub s3_endpoint: String,
+    /// Region used to sign requests
+    #[serde(default = "default_attachments_s3_region")]
+    pub s3_region: String,
     #[serde(default)]
-    pub escalation_enabled: bool,
+    pub s3_bucket: String,
+    /// Key prefix for stored objects, e.g. `mouchak/`
     #[serde(default)]
-    pub escalation_mode: EscalationMode,
-    #[serde(default = "default_scan_interval_seconds")]
-    pub scan_interval_seconds: u64,
+    pub s3_prefix: String,
+    #[serde(default)]
+    pub s3_access_key_id: Option<String>,
+    #[serde(default)]
+    pub s3_secret_access_key: Option<String>,
+    /// Address buckets as `<endpoint>/<bucket>` instead of `<bucket>.<endpoint host>`
+    #[serde(default = "default_attachments_s3_path_style")]
+    pub s3_path_style: bool,
 }
 
-fn default_ack_ttl_seconds() -> u64 {
-    1800 // 30 minutes
+fn default_attachments_max_bytes() -> u64 {
+    10 * 1024 * 1024 // 10 MB
 }
 
-fn default_scan_interval_seconds() -> u64 {
-    300 // 5 minutes
+fn default_attachments_s3_endpoint() -> String {
+    "https://s3.amazonaws.com".to_string()
 }
 
-impl Default for EscalationConfig {
+fn default_attachments_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_attachments_s3_path_style() -> bool {
+    true
+}
+
+impl Default for AttachmentsConfig {
     fn default() -> Self {
         Self {
-            ack_ttl_enabled: false,
-            ack_ttl_seconds: default_ack_ttl_seconds(),
-            escalation_enabled: false,
-            escalation_mode: EscalationMode::default(),
-            scan_interval_seconds: default_scan_interval_seconds(),
+            backend: AttachmentBackend::default(),
+            max_bytes: default_attachments_max_bytes(),
+            s3_endpoint: default_attachments_s3_endpoint(),
+            s3_region: default_attachments_s3_region(),
+            s3_bucket: String::new(),
+            s3_prefix: String::new(),
+            s3_access_key_id: None,
+            s3_secret_access_key: None,
+            s3_path_style: default_attachments_s3_path_style(),
         }
     }
 }
 
-impl EscalationConfig {
-    pub fn from_env() -> Self {
+/// Database the metadata is kept in.
+///
+/// SQLite by default, in the data directory. A `postgres://` (or
+/// `postgresql://`) `url` selects Postgres instead, for deployments with
+/// more concurrent writers than one database file serves well; any other
+/// `url` keeps SQLite. BMC uses of the connection that take `slow_query_ms`
+/// or longer are logged as slow queries.
+///
+/// With Postgres, `replica_urls` lists read replicas: searches, listings
+/// and exports read from them while they have caught up with this
+/// process's writes, and from the primary otherwise.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DatabaseConfig {
+    /// Postgres connection URL, e.g. `postgres://mail:secret@db/mouchak`;
+    /// TLS is used unless it sets `sslmode=disable`
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Most connections the Postgres pool opens
+    #[serde(default = "default_database_pool_size")]
+    pub pool_size: usize,
+    /// Comma-separated Postgres read replica URLs; each gets a pool of
+    /// `pool_size` connections
+    #[serde(default)]
+    pub replica_urls: String,
+    /// Slow query threshold in milliseconds; 0 disables the log
+    #[serde(default = "default_database_slow_query_ms")]
+    pub slow_query_ms: u64,
+}
+
+fn default_database_pool_size() -> usize {
+    16
+}
+
+fn default_database_slow_query_ms() -> u64 {
+    500
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
         Self {
-            ack_ttl_enabled: parse_bool_env("ACK_TTL_ENABLED"),
-            ack_ttl_seconds: std::env::var("ACK_TTL_SECONDS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or_else(default_ack_ttl_seconds),
-            escalation_enabled: parse_bool_env("ACK_ESCALATION_ENABLED"),
-            escalation_mode: std::env::var("ACK_ESCALATION_MODE")
-                .ok()
-                .and_then(|m| match m.to_lowercase().as_str() {
-                    "log" => Some(EscalationMode::Log),
-                    "file_reservation" => Some(EscalationMode::FileReservation),
-                    "overseer" => Some(EscalationMode::Overseer),
-                    _ => None,
-                })
-                .unwrap_or_default(),
-            scan_interval_seconds: std::env::var("ACK_SCAN_INTERVAL_SECONDS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or_else(default_scan_interval_seconds),
+            url: None,
+            pool_size: default_database_pool_size(),
+            replica_urls: String::new(),
+            slow_query_ms: default_database_slow_query_ms(),
         }
     }
 }
 
-/// Project identity resolution mode for slug generation.
-///
-/// Controls how project slugs are computed to ensure privacy-safe identifiers.
-/// See Python reference: `_compute_project_slug()` in mouchak_mail/app.py
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
-#[serde(rename_all = "kebab-case")]
-pub enum ProjectIdentityMode {
-    /// Default: plain slugify (backward compatible but may leak path info)
-    #[default]
-    Dir,
-    /// Use Git remote URL to derive slug: `{repo_name}-{sha1_hash[:10]}`
-    /// Most privacy-safe option - derives from remote origin URL
-    GitRemote,
-    /// Use Git repository root directory name + hash
-    GitToplevel,
-    /// Use Git common dir (for worktrees) + hash
-    GitCommonDir,
+impl DatabaseConfig {
+    /// The configured replica URLs, trimmed and without empty entries.
+    pub fn replica_url_list(&self) -> Vec<&str> {
+        self.replica_urls
+            .split(',')
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .collect()
+    }
 }
 
+/// Browser-facing HTTP policies (CORS, the Content-Security-Policy and
+/// compression) and connection tuning: HTTP/2 and keep-alive.
+///
+/// All lists are comma-separated. The server validates them at startup and
+/// refuses to start on a malformed entry.
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct McpConfig {
-    pub transport: String,
-    pub port: u16,
-    /// Enable worktree-related features (build slots, pre-commit guard)
+pub struct HttpConfig {
+    /// Origins (`https://app.example.com`) that may call the API from a
+    /// browser; `*` allows any
+    #[serde(default = "default_http_cors_allowed_origins")]
+    pub cors_allowed_origins: String,
+    /// Extra CSP sources for scripts, styles and connections, e.g. a
+    /// self-hosted asset domain
     #[serde(default)]
-    pub worktrees_enabled: bool,
-    /// Enable git identity features
-    #[serde(default)]
-    pub git_identity_enabled: bool,
-    /// Project identity resolution mode for slug generation
+    pub csp_extra_sources: String,
+    /// Origins that may embed the UI in a frame; empty forbids framing
     #[serde(default)]
-    pub project_identity_mode: ProjectIdentityMode,
-    /// Remote name to use for git-remote mode (default: "origin")
-    #[serde(default = "default_project_identity_remote")]
-    pub project_identity_remote: String,
+    pub frame_ancestors: String,
+    /// Response encodings offered to clients (`br`, `gzip`); empty disables
+    /// compression
+    #[serde(default = "default_http_compression")]
+    pub compression: String,
+    /// Responses smaller than this are sent uncompressed
+    #[serde(default = "default_http_compression_min_bytes")]
+    pub compression_min_bytes: u16,
+    /// Accept HTTP/2 (h2c with prior knowledge) next to HTTP/1.1
+    #[serde(default = "default_http_http2")]
+    pub http2: bool,
+    /// Keep HTTP/1.1 connections open between requests
+    #[serde(default = "default_http_keep_alive")]
+    pub keep_alive: bool,
+    /// Close HTTP/1.1 connections that send no request for this long, and
+    /// ping HTTP/2 ones this often unless `http2_keep_alive_interval_seconds`
+    /// is set; 0 waits forever
+    #[serde(default = "default_http_idle_timeout_seconds")]
+    pub idle_timeout_seconds: u64,
+    /// Ping idle HTTP/2 connections this often; 0 falls back to
+    /// `idle_timeout_seconds`
+    #[serde(default = "default_http_http2_keep_alive_interval_seconds")]
+    pub http2_keep_alive_interval_seconds: u64,
+    /// Close an HTTP/2 connection whose ping is not answered in time
+    #[serde(default = "default_http_http2_keep_alive_timeout_seconds")]
+    pub http2_keep_alive_timeout_seconds: u64,
+    /// Requests a client may have in flight on one HTTP/2 connection
+    #[serde(default = "default_http_http2_max_concurrent_streams")]
+    pub http2_max_concurrent_streams: u32,
 }
 
-fn default_project_identity_remote() -> String {
-    "origin".to_string()
+fn default_http_cors_allowed_origins() -> String {
+    "*".to_string()
+}
+
+fn default_http_compression() -> String {
+    "br,gzip".to_string()
+}
+
+fn default_http_compression_min_bytes() -> u16 {
+    1024
+}
+
+fn default_http_http2() -> bool {
+    true
+}
+
+fn default_http_keep_alive() -> bool {
+    true
+}
+
+fn default_http_idle_timeout_seconds() -> u64 {
+    120
+}
+
+fn default_http_http2_keep_alive_interval_seconds() -> u64 {
+    30
+}
+
+fn default_http_http2_keep_alive_timeout_seconds() -> u64 {
+    20
+}
+
+fn default_http_http2_max_concurrent_streams() -> u32 {
+    256
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            cors_allowed_origins: default_http_cors_allowed_origins(),
+            csp_extra_sources: String::new(),
+            frame_ancestors: String::new(),
+            compression: default_http_compression(),
+            compression_min_bytes: default_http_compression_min_bytes(),
+            http2: default_http_http2(),
+            keep_alive: default_http_keep_alive(),
+            idle_timeout_seconds: default_http_idle_timeout_seconds(),
+            http2_keep_alive_interval_seconds: default_http_http2_keep_alive_interval_seconds(),
+            http2_keep_alive_timeout_seconds: default_http_http2_keep_alive_timeout_seconds(),
+            http2_max_concurrent_streams: default_http_http2_max_concurrent_streams(),
+        }
+    }
+}
+
+/// How tool-call traces are sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceSampler {
+    /// Record every tool call
+    #[default]
+    Always,
+    /// Record a `sample_ratio` fraction of tool calls, chosen by trace ID
+    Ratio,
+    /// Follow the enclosing span (e.g. the HTTP request); use the ratio at the root
+    ParentBased,
 }
 
+/// Tool-call tracing.
+///
+/// Sampled calls get a `tool_call` span carrying `tool`, `trace_id`,
+/// `project_slug` and `agent_name`, so traces can be filtered by project.
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct QuotaConfig {
+pub struct TracingConfig {
     #[serde(default)]
-    pub enabled: bool,
-    #[serde(default = "default_quota_attachments_limit_bytes")]
-    pub attachments_limit_bytes: u64,
-    #[serde(default = "default_quota_inbox_limit_count")]
-    pub inbox_limit_count: u64,
+    pub sampler: TraceSampler,
+    /// Fraction of root traces kept by `ratio` and `parent_based` (0.0-1.0)
+    #[serde(default = "default_trace_sample_ratio")]
+    pub sample_ratio: f64,
 }
 
-fn default_quota_attachments_limit_bytes() -> u64 {
-    100 * 1024 * 1024 // 100 MB
+fn default_trace_sample_ratio() -> f64 {
+    1.0
 }
 
-fn default_quota_inbox_limit_count() -> u64 {
-    1000
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            sampler: TraceSampler::default(),
+            sample_ratio: default_trace_sample_ratio(),
+        }
+    }
 }
 
-impl Default for QuotaConfig {
-    fn default() -> Self {
+impl TracingConfig {
+    /// Create config from environment variables (for standalone MCP usage)
+    pub fn from_env() -> Self {
         Self {
-            enabled: false,
-            attachments_limit_bytes: default_quota_attachments_limit_bytes(),
-            inbox_limit_count: default_quota_inbox_limit_count(),
+            sampler: std::env::var("TRACE_SAMPLER")
+                .ok()
+                .and_then(|s| match s.to_lowercase().as_str() {
+                    "always" => Some(TraceSampler::Always),
+                    "ratio" => Some(TraceSampler::Ratio),
+                    "parent_based" => Some(TraceSampler::ParentBased),
+                    _ => None,
+                })
+                .unwrap_or_default(),
+            sample_ratio: std::env::var("TRACE_SAMPLE_RATIO")
+                .ok()
+                .and_then(|r| r.parse().ok())
+                .unwrap_or_else(default_trace_sample_ratio),
+        }
+    }
+
+    /// Decides whether a trace is recorded.
+    ///
+    /// `parent_sampled` is `None` for root traces. Ratio decisions depend
+    /// only on `trace_id`, so every service sampling the same trace agrees.
+    pub fn should_sample(&self, parent_sampled: Option<bool>, trace_id: u128) -> bool {
+        match self.sampler {
+            TraceSampler::Always => true,
+            TraceSampler::Ratio => self.ratio_sample(trace_id),
+            TraceSampler::ParentBased => {
+                parent_sampled.unwrap_or_else(|| self.ratio_sample(trace_id))
+            }
         }
     }
+
+    fn ratio_sample(&self, trace_id: u128) -> bool {
+        if self.sample_ratio >= 1.0 {
+            return true;
+        }
+        if self.sample_ratio <= 0.0 || self.sample_ratio.is_nan() {
+            return false;
+        }
+        // Low 64 bits of the trace ID, as a fraction of the u64 range
+        let threshold = (self.sample_ratio * u64::MAX as f64) as u64;
+        (trace_id as u64) < threshold
+    }
 }
 
 impl McpConfig {
@@ -193,15 +1446,23 @@ impl McpConfig {
                 .unwrap_or_default(),
             project_identity_remote: std::env::var("PROJECT_IDENTITY_REMOTE")
                 .unwrap_or_else(|_| default_project_identity_remote()),
+            session_journal_enabled: parse_bool_env("SESSION_JOURNAL_ENABLED"),
+            session_journal_thread: std::env::var("SESSION_JOURNAL_THREAD")
+                .unwrap_or_else(|_| default_session_journal_thread()),
         }
     }
 }
 
 /// Parse boolean environment variable with truthy value detection
 fn parse_bool_env(key: &str) -> bool {
-    std::env::var(key)
-        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "t" | "y"))
-        .unwrap_or(false)
+    std::env::var(key).map(|v| is_truthy(&v)).unwrap_or(false)
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "1" | "true" | "yes" | "t" | "y"
+    )
 }
 
 impl Default for AppConfig {
@@ -220,103 +1481,48 @@ impl Default for AppConfig {
                 git_identity_enabled: false,
                 project_identity_mode: ProjectIdentityMode::default(),
                 project_identity_remote: default_project_identity_remote(),
+                session_journal_enabled: false,
+                session_journal_thread: default_session_journal_thread(),
             },
             escalation: EscalationConfig::default(),
             quota: QuotaConfig::default(),
+            push: PushConfig::default(),
+            tracing: TracingConfig::default(),
+            notifier: NotifierConfig::default(),
+            plugins: PluginsConfig::default(),
+            event_bridge: EventBridgeConfig::default(),
+            github: GithubConfig::default(),
+            tickets: TicketsConfig::default(),
+            tool_call_log: ToolCallLogConfig::default(),
+            archive_gc: ArchiveGcConfig::default(),
+            db_maintenance: DbMaintenanceConfig::default(),
+            message_body: MessageBodyConfig::default(),
+            thread_archival: ThreadArchivalConfig::default(),
+            retention: RetentionConfig::default(),
+            webhooks: WebhooksConfig::default(),
+            email_bridge: EmailBridgeConfig::default(),
+            exports: ExportsConfig::default(),
+            websocket: WebSocketConfig::default(),
+            disk_watch: DiskWatchConfig::default(),
+            attachments: AttachmentsConfig::default(),
+            database: DatabaseConfig::default(),
+            http: HttpConfig::default(),
         }
     }
 }
 
 impl AppConfig {
-    /// Load configuration with 12-factor app compliant env var support.
+    /// Load configuration from defaults, config files and environment.
     ///
-    /// Priority order (highest to lowest):
-    /// 1. `PORT` / `HOST` env vars (12-factor standard)
-    /// 2. Config files (`config/default.toml`, `config/{run_mode}.toml`)
-    /// 3. Hardcoded defaults (port 8765)
+    /// See [`loader`] for the precedence rules and environment variables.
+    /// Binaries that take config-affecting CLI flags use [`AppConfig::loader`].
     pub fn load() -> Result<Self, config::ConfigError> {
-        let run_mode = env::var("RUN_MODE").unwrap_or_else(|_| "development".into());
-
-        let mut builder = Config::builder()
-            // Start with defaults
-            .set_default("server.host", "0.0.0.0")?
-            .set_default("server.port", 8765)?
-            .set_default("server.serve_ui", true)?
-            .set_default("mcp.transport", "stdio")?
-            .set_default("mcp.port", 3000)?
-            .set_default("mcp.worktrees_enabled", false)?
-            .set_default("mcp.git_identity_enabled", false)?
-            .set_default("mcp.project_identity_mode", "dir")?
-            .set_default("mcp.project_identity_remote", "origin")?
-            .set_default("escalation.ack_ttl_enabled", false)?
-            .set_default("escalation.ack_ttl_seconds", 1800_i64)?
-            .set_default("escalation.escalation_enabled", false)?
-            .set_default("escalation.escalation_mode", "log")?
-            .set_default("escalation.scan_interval_seconds", 300_i64)?
-            // Merge in config files
-            .add_source(File::with_name("config/default").required(false))
-            .add_source(File::with_name(&format!("config/{}", run_mode)).required(false));
-
-        // Add user config file from ~/.mouchak-mail/config.toml
-        if let Ok(home) = env::var("HOME") {
-            let path = std::path::Path::new(&home)
-                .join(".mouchak-mail")
-                .join("config.toml");
-            builder = builder.add_source(File::from(path).required(false));
-        }
-
-        // 12-factor app standard: PORT and HOST env vars
-        if let Ok(port) = env::var("PORT") {
-            if let Ok(p) = port.parse::<i64>() {
-                builder = builder.set_override("server.port", p)?;
-            }
-        }
-        if let Ok(host) = env::var("HOST") {
-            builder = builder.set_override("server.host", host)?;
-        }
-
-        if parse_bool_env("ACK_TTL_ENABLED") {
-            builder = builder.set_override("escalation.ack_ttl_enabled", true)?;
-        }
-        if let Ok(ttl) = env::var("ACK_TTL_SECONDS") {
-            if let Ok(secs) = ttl.parse::<i64>() {
-                builder = builder.set_override("escalation.ack_ttl_seconds", secs)?;
-            }
-        }
-        if parse_bool_env("ACK_ESCALATION_ENABLED") {
-            builder = builder.set_override("escalation.escalation_enabled", true)?;
-        }
-        if let Ok(mode) = env::var("ACK_ESCALATION_MODE") {
-            builder = builder.set_override("escalation.escalation_mode", mode)?;
-        }
-        if let Ok(interval) = env::var("ACK_SCAN_INTERVAL_SECONDS") {
-            if let Ok(secs) = interval.parse::<i64>() {
-                builder = builder.set_override("escalation.scan_interval_seconds", secs)?;
-            }
-        }
-
-        if parse_bool_env("QUOTA_ENABLED") {
-            builder = builder.set_override("quota.enabled", true)?;
-        }
-        if let Ok(bytes) = env::var("QUOTA_ATTACHMENTS_LIMIT_BYTES") {
-            if let Ok(limit) = bytes.parse::<u64>() {
-                builder = builder.set_override("quota.attachments_limit_bytes", limit)?;
-            }
-        }
-        if let Ok(count) = env::var("QUOTA_INBOX_LIMIT_COUNT") {
-            if let Ok(limit) = count.parse::<u64>() {
-                builder = builder.set_override("quota.inbox_limit_count", limit)?;
-            }
-        }
-
-        if let Ok(mode) = env::var("PROJECT_IDENTITY_MODE") {
-            builder = builder.set_override("mcp.project_identity_mode", mode)?;
-        }
-        if let Ok(remote) = env::var("PROJECT_IDENTITY_REMOTE") {
-            builder = builder.set_override("mcp.project_identity_remote", remote)?;
-        }
+        Self::loader().load()
+    }
 
-        builder.build()?.try_deserialize()
+    /// Layered loader, for adding CLI flag overrides before loading.
+    pub fn loader() -> ConfigLoader {
+        ConfigLoader::new()
     }
 }
 
@@ -372,6 +1578,8 @@ mod tests {
             git_identity_enabled: false,
             project_identity_mode: ProjectIdentityMode::default(),
             project_identity_remote: "origin".into(),
+            session_journal_enabled: false,
+            session_journal_thread: "session-journal".into(),
         };
         assert!(!config.worktrees_active());
 
@@ -460,4 +1668,58 @@ mod tests {
             std::env::remove_var("ACK_ESCALATION_MODE");
         }
     }
+
+    #[test]
+    fn test_trace_sampling() {
+        let always = TracingConfig::default();
+        assert!(always.should_sample(None, 0));
+        assert!(always.should_sample(Some(false), u128::MAX));
+
+        let ratio = TracingConfig {
+            sampler: TraceSampler::Ratio,
+            sample_ratio: 0.25,
+        };
+        assert!(ratio.should_sample(None, 1));
+        assert!(!ratio.should_sample(None, u64::MAX as u128));
+        assert!(!ratio.should_sample(Some(true), u64::MAX as u128));
+        let sampled = (0..1000u128)
+            .filter(|i| ratio.should_sample(None, i.wrapping_mul(0x9E37_79B9_7F4A_7C15)))
+            .count();
+        assert!((150..350).contains(&sampled), "sampled {}", sampled);
+
+        let parent_based = TracingConfig {
+            sampler: TraceSampler::ParentBased,
+            sample_ratio: 0.0,
+        };
+        assert!(parent_based.should_sample(Some(true), u128::MAX));
+        assert!(!parent_based.should_sample(Some(false), 0));
+        assert!(!parent_based.should_sample(None, 0));
+    }
+
+    #[test]
+    fn test_email_bridge_forwards() {
+        let bridge = EmailBridgeConfig::default();
+        assert!(bridge.smtp_host().is_none());
+        assert!(bridge.forwards("urgent", "Build"));
+        assert!(bridge.forwards("URGENT", "Build"));
+        assert!(!bridge.forwards("high", "Build"));
+
+        let rules = EmailBridgeConfig {
+            smtp_host: Some(" smtp.example.com ".to_string()),
+            importance: "urgent, high".to_string(),
+            subject_keywords: "outage, Needs Human".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(rules.smtp_host(), Some("smtp.example.com"));
+        assert_eq!(bridge.message_id_domain(), "localhost");
+        let bridge = EmailBridgeConfig {
+            from: "bridge@mail.example.com".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(bridge.message_id_domain(), "mail.example.com");
+        assert!(rules.forwards("high", "Build"));
+        assert!(rules.forwards("normal", "Prod OUTAGE in eu-west"));
+        assert!(rules.forwards("low", "[needs human] approve deploy"));
+        assert!(!rules.forwards("normal", "Build green"));
+    }
 }
@@ -0,0 +1,740 @@
+//! Layered configuration loading shared by every binary.
+//!
+//! Precedence, lowest to highest:
+//!
+//! 1. Built-in defaults ([`AppConfig::default`])
+//! 2. Config files, in order: `config/default`, `config/{RUN_MODE}`,
+//!    `~/.mouchak-mail/config.toml`; in portable mode only
+//!    `<dir>/config.toml` (see [`crate::paths`])
+//! 3. Environment variables: `MOUCHAK_<SECTION>__<KEY>` for every key in
+//!    [`CONFIG_KEYS`], plus the per-key aliases listed there
+//! 4. CLI flags, passed in with [`ConfigLoader::cli_override`]
+//!
+//! `mouchak-mail config sources` prints which layer each key came from.
+
+use super::{AppConfig, is_truthy};
+use config::{Config, ConfigError, File, Value};
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// How an environment value is parsed before it overrides a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyKind {
+    /// `1`, `true`, `yes`, `t`, `y` (any case) are true; anything else is false
+    Bool,
+    Int,
+    Float,
+    String,
+}
+
+/// A configuration key and the environment variables that override it.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigKey {
+    /// Dotted key, e.g. `server.port`
+    pub key: &'static str,
+    /// Variables read after the canonical `MOUCHAK_<SECTION>__<KEY>`, in order
+    pub env_aliases: &'static [&'static str],
+    pub kind: KeyKind,
+    /// Value is redacted by `config sources`
+    pub secret: bool,
+}
+
+impl ConfigKey {
+    const fn new(key: &'static str, env_aliases: &'static [&'static str], kind: KeyKind) -> Self {
+        Self {
+            key,
+            env_aliases,
+            kind,
+            secret: false,
+        }
+    }
+
+    const fn secret(self) -> Self {
+        Self {
+            secret: true,
+            ..self
+        }
+    }
+
+    /// Canonical environment variable, e.g. `MOUCHAK_SERVER__PORT`.
+    pub fn canonical_env(&self) -> String {
+        format!("MOUCHAK_{}", self.key.to_uppercase().replace('.', "__"))
+    }
+
+    /// Every variable that overrides this key, highest priority first.
+    pub fn env_vars(&self) -> Vec<String> {
+        std::iter::once(self.canonical_env())
+            .chain(self.env_aliases.iter().map(|alias| alias.to_string()))
+            .collect()
+    }
+}
+
+/// Every configuration key with its environment overrides.
+///
+/// The aliases are the variable names each setting was read from before the
+/// canonical names existed; they keep working, but the canonical name wins
+/// when both are set.
+pub const CONFIG_KEYS: &[ConfigKey] = &[
+    ConfigKey::new("server.host", &["HOST"], KeyKind::String),
+    ConfigKey::new("server.port", &["PORT"], KeyKind::Int),
+    ConfigKey::new("server.auth_hmac", &[], KeyKind::String).secret(),
+    ConfigKey::new("server.serve_ui", &[], KeyKind::Bool),
+    ConfigKey::new("mcp.transport", &[], KeyKind::String),
+    ConfigKey::new("mcp.port", &[], KeyKind::Int),
+    ConfigKey::new(
+        "mcp.worktrees_enabled",
+        &["WORKTREES_ENABLED"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new(
+        "mcp.git_identity_enabled",
+        &["GIT_IDENTITY_ENABLED"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new(
+        "mcp.project_identity_mode",
+        &["PROJECT_IDENTITY_MODE"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "mcp.project_identity_remote",
+        &["PROJECT_IDENTITY_REMOTE"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "mcp.session_journal_enabled",
+        &["SESSION_JOURNAL_ENABLED"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new(
+        "mcp.session_journal_thread",
+        &["SESSION_JOURNAL_THREAD"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "escalation.ack_ttl_enabled",
+        &["ACK_TTL_ENABLED"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new(
+        "escalation.ack_ttl_seconds",
+        &["ACK_TTL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "escalation.escalation_enabled",
+        &["ACK_ESCALATION_ENABLED"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new(
+        "escalation.escalation_mode",
+        &["ACK_ESCALATION_MODE"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "escalation.scan_interval_seconds",
+        &["ACK_SCAN_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new("quota.enabled", &["QUOTA_ENABLED"], KeyKind::Bool),
+    ConfigKey::new(
+        "quota.attachments_limit_bytes",
+        &["QUOTA_ATTACHMENTS_LIMIT_BYTES"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "quota.inbox_limit_count",
+        &["QUOTA_INBOX_LIMIT_COUNT"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "quota.agent_messages_limit_count",
+        &["QUOTA_AGENT_MESSAGES_LIMIT_COUNT"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "quota.agent_attachments_limit_bytes",
+        &["QUOTA_AGENT_ATTACHMENTS_LIMIT_BYTES"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new("push.enabled", &["WEB_PUSH_ENABLED"], KeyKind::Bool),
+    ConfigKey::new("push.vapid_subject", &["VAPID_SUBJECT"], KeyKind::String),
+    ConfigKey::new(
+        "push.vapid_private_key",
+        &["VAPID_PRIVATE_KEY"],
+        KeyKind::String,
+    )
+    .secret(),
+    ConfigKey::new(
+        "push.scan_interval_seconds",
+        &["WEB_PUSH_SCAN_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new("tracing.sampler", &["TRACE_SAMPLER"], KeyKind::String),
+    ConfigKey::new(
+        "tracing.sample_ratio",
+        &["TRACE_SAMPLE_RATIO"],
+        KeyKind::Float,
+    ),
+    ConfigKey::new("notifier.command", &["NOTIFIER_COMMAND"], KeyKind::String),
+    ConfigKey::new("notifier.events", &["NOTIFIER_EVENTS"], KeyKind::String),
+    ConfigKey::new("plugins.paths", &["PLUGINS_PATHS"], KeyKind::String),
+    ConfigKey::new("plugins.fuel", &["PLUGINS_FUEL"], KeyKind::Int),
+    ConfigKey::new("plugins.memory_mb", &["PLUGINS_MEMORY_MB"], KeyKind::Int),
+    ConfigKey::new("event_bridge.url", &["EVENT_BRIDGE_URL"], KeyKind::String),
+    ConfigKey::new(
+        "event_bridge.prefix",
+        &["EVENT_BRIDGE_PREFIX"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "event_bridge.max_len",
+        &["EVENT_BRIDGE_MAX_LEN"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new("github.token", &["GITHUB_TOKEN"], KeyKind::String).secret(),
+    ConfigKey::new("github.api_url", &["GITHUB_API_URL"], KeyKind::String),
+    ConfigKey::new(
+        "github.sync_interval_seconds",
+        &["GITHUB_SYNC_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new("tickets.jira_url", &["JIRA_URL"], KeyKind::String),
+    ConfigKey::new("tickets.jira_email", &["JIRA_EMAIL"], KeyKind::String),
+    ConfigKey::new("tickets.jira_token", &["JIRA_API_TOKEN"], KeyKind::String).secret(),
+    ConfigKey::new(
+        "tickets.linear_api_key",
+        &["LINEAR_API_KEY"],
+        KeyKind::String,
+    )
+    .secret(),
+    ConfigKey::new(
+        "tickets.linear_api_url",
+        &["LINEAR_API_URL"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "tickets.refresh_interval_seconds",
+        &["TICKET_REFRESH_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "tool_call_log.enabled",
+        &["TOOL_CALL_LOG_ENABLED"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new(
+        "tool_call_log.batch_size",
+        &["TOOL_CALL_LOG_BATCH_SIZE"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "tool_call_log.flush_interval_seconds",
+        &["TOOL_CALL_LOG_FLUSH_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "archive_gc.interval_seconds",
+        &["ARCHIVE_GC_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "archive_gc.min_loose_objects",
+        &["ARCHIVE_GC_MIN_LOOSE_OBJECTS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "archive_gc.aggressive",
+        &["ARCHIVE_GC_AGGRESSIVE"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new(
+        "db_maintenance.interval_seconds",
+        &["DB_MAINTENANCE_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "db_maintenance.vacuum_free_ratio",
+        &["DB_VACUUM_FREE_RATIO"],
+        KeyKind::Float,
+    ),
+    ConfigKey::new(
+        "message_body.max_inline_bytes",
+        &["MESSAGE_BODY_MAX_INLINE_BYTES"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "message_body.preview_bytes",
+        &["MESSAGE_BODY_PREVIEW_BYTES"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "thread_archival.inactive_days",
+        &["THREAD_ARCHIVAL_INACTIVE_DAYS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "thread_archival.closing_summary",
+        &["THREAD_ARCHIVAL_CLOSING_SUMMARY"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new(
+        "thread_archival.interval_seconds",
+        &["THREAD_ARCHIVAL_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new("retention.max_age_days", &[], KeyKind::Int),
+    ConfigKey::new("retention.max_messages", &[], KeyKind::Int),
+    ConfigKey::new("retention.action", &[], KeyKind::String),
+    ConfigKey::new("retention.interval_seconds", &[], KeyKind::Int),
+    ConfigKey::new("webhooks.enabled", &[], KeyKind::Bool),
+    ConfigKey::new("webhooks.scan_interval_seconds", &[], KeyKind::Int),
+    ConfigKey::new("webhooks.max_attempts", &[], KeyKind::Int),
+    ConfigKey::new("webhooks.backoff_base_seconds", &[], KeyKind::Int),
+    ConfigKey::new("webhooks.backoff_max_seconds", &[], KeyKind::Int),
+    ConfigKey::new("webhooks.timeout_seconds", &[], KeyKind::Int),
+    ConfigKey::new("webhooks.allow_private_urls", &[], KeyKind::Bool),
+    ConfigKey::new("email_bridge.smtp_host", &[], KeyKind::String),
+    ConfigKey::new("email_bridge.smtp_port", &[], KeyKind::Int),
+    ConfigKey::new("email_bridge.smtp_security", &[], KeyKind::String),
+    ConfigKey::new("email_bridge.smtp_username", &[], KeyKind::String),
+    ConfigKey::new("email_bridge.smtp_password", &[], KeyKind::String).secret(),
+    ConfigKey::new("email_bridge.from", &[], KeyKind::String),
+    ConfigKey::new("email_bridge.to", &[], KeyKind::String),
+    ConfigKey::new("email_bridge.importance", &[], KeyKind::String),
+    ConfigKey::new("email_bridge.subject_keywords", &[], KeyKind::String),
+    ConfigKey::new("email_bridge.scan_interval_seconds", &[], KeyKind::Int),
+    ConfigKey::new("email_bridge.timeout_seconds", &[], KeyKind::Int),
+    ConfigKey::new("email_bridge.inbound_aliases", &[], KeyKind::String),
+    ConfigKey::new("exports.poll_interval_seconds", &[], KeyKind::Int),
+    ConfigKey::new("exports.max_messages", &[], KeyKind::Int),
+    ConfigKey::new("exports.retention_hours", &[], KeyKind::Int),
+    ConfigKey::new("websocket.enabled", &["WEBSOCKET_ENABLED"], KeyKind::Bool),
+    ConfigKey::new(
+        "websocket.event_buffer",
+        &["WEBSOCKET_EVENT_BUFFER"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "websocket.ping_interval_seconds",
+        &["WEBSOCKET_PING_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "websocket.expiry_interval_seconds",
+        &["WEBSOCKET_EXPIRY_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "disk_watch.interval_seconds",
+        &["DISK_WATCH_INTERVAL_SECONDS"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "disk_watch.warn_free_mb",
+        &["DISK_WATCH_WARN_FREE_MB"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "disk_watch.read_only_free_mb",
+        &["DISK_WATCH_READ_ONLY_FREE_MB"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "attachments.backend",
+        &["ATTACHMENTS_BACKEND"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "attachments.max_bytes",
+        &["ATTACHMENTS_MAX_BYTES"],
+        KeyKind::Int,
+    ),
+    ConfigKey::new(
+        "attachments.s3_endpoint",
+        &["ATTACHMENTS_S3_ENDPOINT"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "attachments.s3_region",
+        &["ATTACHMENTS_S3_REGION", "AWS_REGION"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "attachments.s3_bucket",
+        &["ATTACHMENTS_S3_BUCKET"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "attachments.s3_prefix",
+        &["ATTACHMENTS_S3_PREFIX"],
+        KeyKind::String,
+    ),
+    ConfigKey::new(
+        "attachments.s3_access_key_id",
+        &["ATTACHMENTS_S3_ACCESS_KEY_ID", "AWS_ACCESS_KEY_ID"],
+        KeyKind::String,
+    )
+    .secret(),
+    ConfigKey::new(
+        "attachments.s3_secret_access_key",
+        &["ATTACHMENTS_S3_SECRET_ACCESS_KEY", "AWS_SECRET_ACCESS_KEY"],
+        KeyKind::String,
+    )
+    .secret(),
+    ConfigKey::new(
+        "attachments.s3_path_style",
+        &["ATTACHMENTS_S3_PATH_STYLE"],
+        KeyKind::Bool,
+    ),
+    ConfigKey::new("database.url", &["DATABASE_URL"], KeyKind::String).secret(),
+    ConfigKey::new("database.pool_size", &[], KeyKind::Int),
+    ConfigKey::new(
+        "database.replica_urls",
+        &["DATABASE_REPLICA_URLS"],
+        KeyKind::String,
+    )
+    .secret(),
+    ConfigKey::new("database.slow_query_ms", &[], KeyKind::Int),
+    ConfigKey::new(
+        "http.cors_allowed_origins",
+        &["CORS_ALLOWED_ORIGINS"],
+        KeyKind::String,
+    ),
+    ConfigKey::new("http.csp_extra_sources", &[], KeyKind::String),
+    ConfigKey::new("http.frame_ancestors", &[], KeyKind::String),
+    ConfigKey::new("http.compression", &[], KeyKind::String),
+    ConfigKey::new("http.compression_min_bytes", &[], KeyKind::Int),
+    ConfigKey::new("http.http2", &[], KeyKind::Bool),
+    ConfigKey::new("http.keep_alive", &[], KeyKind::Bool),
+    ConfigKey::new("http.idle_timeout_seconds", &[], KeyKind::Int),
+    ConfigKey::new("http.http2_keep_alive_interval_seconds", &[], KeyKind::Int),
+    ConfigKey::new("http.http2_keep_alive_timeout_seconds", &[], KeyKind::Int),
+    ConfigKey::new("http.http2_max_concurrent_streams", &[], KeyKind::Int),
+];
+
+/// Layer a configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "layer", content = "name", rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    /// Config file, as named in the search list
+    File(String),
+    /// Environment variable
+    Env(String),
+    /// Command-line flag
+    Cli(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(name) => write!(f, "file {}", name),
+            Self::Env(var) => write!(f, "env {}", var),
+            Self::Cli(flag) => write!(f, "cli {}", flag),
+        }
+    }
+}
+
+/// The effective value of one key and where it came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedKey {
+    pub key: &'static str,
+    /// Effective value; secrets are shown as `<redacted>`
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+/// A loaded configuration with the source of every key.
+#[derive(Debug, Clone)]
+pub struct ResolvedConfig {
+    pub config: AppConfig,
+    pub sources: Vec<ResolvedKey>,
+}
+
+struct CliOverride {
+    key: &'static str,
+    flag: &'static str,
+    value: Value,
+}
+
+/// Builds an [`AppConfig`] from defaults, files, environment and CLI flags.
+#[derive(Default)]
+pub struct ConfigLoader {
+    cli: Vec<CliOverride>,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides `key` with the value of a CLI flag.
+    ///
+    /// `flag` is only used to report the source, e.g. `--port`.
+    pub fn cli_override(
+        mut self,
+        key: &'static str,
+        flag: &'static str,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.cli.push(CliOverride {
+            key,
+            flag,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Loads the configuration.
+    pub fn load(&self) -> Result<AppConfig, ConfigError> {
+        Ok(self.resolve()?.config)
+    }
+
+    /// Loads the configuration and records the source of every key.
+    pub fn resolve(&self) -> Result<ResolvedConfig, ConfigError> {
+        self.resolve_with_env(crate::paths::portable_dir(), |var| std::env::var(var).ok())
+    }
+
+    fn resolve_with_env(
+        &self,
+        portable_dir: Option<&Path>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<ResolvedConfig, ConfigError> {
+        let mut sources: Vec<(&'static str, ConfigSource)> = CONFIG_KEYS
+            .iter()
+            .map(|k| (k.key, ConfigSource::Default))
+            .collect();
+        let mut set_source = |key: &str, source: ConfigSource| {
+            if let Some(entry) = sources.iter_mut().find(|(k, _)| *k == key) {
+                entry.1 = source;
+            }
+        };
+
+        let mut builder = Config::builder().add_source(Config::try_from(&AppConfig::default())?);
+
+        for name in config_files(portable_dir, &env) {
+            // Parse each file alone to learn which keys it sets
+            let file = Config::builder()
+                .add_source(File::with_name(&name).required(false))
+                .build()?;
+            for key in CONFIG_KEYS {
+                if file.get::<Value>(key.key).is_ok() {
+                    set_source(key.key, ConfigSource::File(name.clone()));
+                }
+            }
+            builder = builder.add_source(File::with_name(&name).required(false));
+        }
+
+        for key in CONFIG_KEYS {
+            let found = key
+                .env_vars()
+                .into_iter()
+                .find_map(|var| env(&var).map(|raw| (var, raw)));
+            let Some((var, raw)) = found else {
+                continue;
+            };
+            match parse_env_value(key.kind, &raw) {
+                Some(value) => {
+                    builder = builder.set_override(key.key, value)?;
+                    set_source(key.key, ConfigSource::Env(var));
+                }
+                None => tracing::warn!("Ignoring {}={:?}: not a valid {:?}", var, raw, key.kind),
+            }
+        }
+
+        for cli in &self.cli {
+            builder = builder.set_override(cli.key, cli.value.clone())?;
+            set_source(cli.key, ConfigSource::Cli(cli.flag.to_string()));
+        }
+
+        let merged = builder.build()?;
+        let sources = CONFIG_KEYS
+            .iter()
+            .zip(sources)
+            .map(|(key, (_, source))| ResolvedKey {
+                key: key.key,
+                value: display_value(&merged, key),
+                source,
+            })
+            .collect();
+
+        Ok(ResolvedConfig {
+            config: merged.try_deserialize()?,
+            sources,
+        })
+    }
+}
+
+/// Config files in the order they are merged.
+fn config_files(portable_dir: Option<&Path>, env: &impl Fn(&str) -> Option<String>) -> Vec<String> {
+    if let Some(dir) = portable_dir {
+        return vec![
+            dir.join(crate::paths::CONFIG_FILE)
+                .to_string_lossy()
+                .into_owned(),
+        ];
+    }
+    let run_mode = env("RUN_MODE").unwrap_or_else(|| "development".into());
+    let mut files = vec!["config/default".to_string(), format!("config/{}", run_mode)];
+    if let Some(home) = env("HOME") {
+        files.push(
+            std::path::Path::new(&home)
+                .join(".mouchak-mail")
+                .join("config.toml")
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+    files
+}
+
+fn parse_env_value(kind: KeyKind, raw: &str) -> Option<Value> {
+    let raw = raw.trim();
+    match kind {
+        KeyKind::Bool => Some(is_truthy(raw).into()),
+        KeyKind::Int => raw.parse::<i64>().ok().map(Value::from),
+        KeyKind::Float => raw.parse::<f64>().ok().map(Value::from),
+        KeyKind::String => Some(raw.to_string().into()),
+    }
+}
+
+fn display_value(config: &Config, key: &ConfigKey) -> String {
+    match config.get::<Option<String>>(key.key) {
+        Ok(None) | Err(_) => "(unset)".to_string(),
+        Ok(Some(_)) if key.secret => "<redacted>".to_string(),
+        Ok(Some(value)) => value,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn resolve(loader: &ConfigLoader, vars: &[(&str, &str)]) -> ResolvedConfig {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        loader
+            .resolve_with_env(None, |var| vars.get(var).cloned())
+            .unwrap()
+    }
+
+    fn source_of(resolved: &ResolvedConfig, key: &str) -> ConfigSource {
+        resolved
+            .sources
+            .iter()
+            .find(|r| r.key == key)
+            .map(|r| r.source.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_defaults_when_nothing_is_set() {
+        let resolved = resolve(&ConfigLoader::new(), &[]);
+        assert_eq!(resolved.config.server.port, 8765);
+        assert_eq!(resolved.config.mcp.transport, "stdio");
+        assert_eq!(resolved.sources.len(), CONFIG_KEYS.len());
+        assert!(
+            resolved
+                .sources
+                .iter()
+                .all(|r| r.source == ConfigSource::Default)
+        );
+    }
+
+    #[test]
+    fn test_canonical_env_beats_alias_and_cli_beats_env() {
+        let resolved = resolve(
+            &ConfigLoader::new(),
+            &[("PORT", "9000"), ("MOUCHAK_SERVER__PORT", "9100")],
+        );
+        assert_eq!(resolved.config.server.port, 9100);
+        assert_eq!(
+            source_of(&resolved, "server.port"),
+            ConfigSource::Env("MOUCHAK_SERVER__PORT".into())
+        );
+
+        let resolved = resolve(&ConfigLoader::new(), &[("PORT", "9000")]);
+        assert_eq!(resolved.config.server.port, 9000);
+
+        let loader = ConfigLoader::new().cli_override("server.port", "--port", 9200_i64);
+        let resolved = resolve(&loader, &[("MOUCHAK_SERVER__PORT", "9100")]);
+        assert_eq!(resolved.config.server.port, 9200);
+        assert_eq!(
+            source_of(&resolved, "server.port"),
+            ConfigSource::Cli("--port".into())
+        );
+    }
+
+    #[test]
+    fn test_env_values_are_typed() {
+        let resolved = resolve(
+            &ConfigLoader::new(),
+            &[
+                ("ACK_TTL_ENABLED", "yes"),
+                ("MOUCHAK_MCP__PORT", "not-a-port"),
+                ("TRACE_SAMPLE_RATIO", "0.5"),
+                ("VAPID_PRIVATE_KEY", "secret"),
+            ],
+        );
+        assert!(resolved.config.escalation.ack_ttl_enabled);
+        // Unparseable values are ignored rather than failing startup
+        assert_eq!(resolved.config.mcp.port, 3000);
+        assert_eq!(source_of(&resolved, "mcp.port"), ConfigSource::Default);
+        assert_eq!(resolved.config.tracing.sample_ratio, 0.5);
+
+        let key = resolved
+            .sources
+            .iter()
+            .find(|r| r.key == "push.vapid_private_key")
+            .unwrap();
+        assert_eq!(key.value, "<redacted>");
+    }
+
+    #[test]
+    fn test_portable_dir_reads_only_its_config_file() {
+        let dir = std::env::temp_dir().join(format!("mouchak-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("config.toml");
+        std::fs::write(&file, "[server]\nport = 9300\n").unwrap();
+
+        let files = config_files(Some(&dir), &|var: &str| {
+            (var == "HOME").then(|| "/home/someone".to_string())
+        });
+        assert_eq!(files, [file.to_string_lossy().into_owned()]);
+
+        let resolved = ConfigLoader::new()
+            .resolve_with_env(Some(&dir), |_| None)
+            .unwrap();
+        assert_eq!(resolved.config.server.port, 9300);
+        assert_eq!(
+            source_of(&resolved, "server.port"),
+            ConfigSource::File(file.to_string_lossy().into_owned())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_every_config_field_has_a_key() {
+        let defaults = serde_json::to_value(AppConfig::default()).unwrap();
+        let mut fields = Vec::new();
+        for (section, value) in defaults.as_object().unwrap() {
+            for field in value.as_object().unwrap().keys() {
+                fields.push(format!("{}.{}", section, field));
+            }
+        }
+        for field in fields {
+            assert!(
+                CONFIG_KEYS.iter().any(|k| k.key == field),
+                "{} is missing from CONFIG_KEYS",
+                field
+            );
+        }
+    }
+}
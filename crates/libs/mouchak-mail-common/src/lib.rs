@@ -1,5 +1,7 @@
 pub mod config;
 pub mod error;
+pub mod net;
+pub mod paths;
 pub mod robot;
 pub mod tracing;
 
@@ -0,0 +1,302 @@
+//! IP networks and the hosts the server may call back.
+//!
+//! [`IpNet`] lists are parsed from comma-separated CIDRs such as
+//! `10.0.0.0/8, 192.168.1.7, fd00::/8`. The server matches clients against
+//! them (see its `client_ip` module), and [`check_public_host`] keeps
+//! URLs that users register for callbacks, such as push endpoints and
+//! webhooks, off loopback, private and [`INTERNAL_NETWORKS_ENV`] addresses,
+//! so they cannot make the server send requests to internal services.
+//!
+//! NIST Control: SC-7 (Boundary Protection)
+
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use tracing::warn;
+
+/// Environment variable listing networks internal to the deployment: their
+/// clients skip authentication, and callbacks may not go to them.
+pub const INTERNAL_NETWORKS_ENV: &str = "HTTP_INTERNAL_NETWORKS";
+
+/// An IP network in CIDR notation; a bare address is a single-host network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    /// Whether `ip` lies in the network. IPv4-mapped IPv6 addresses
+    /// (`::ffff:10.0.0.1`) match IPv4 networks.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, canonical(ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_matches(u32::from(net).into(), u32::from(ip).into(), self.prefix, 32)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_matches(u128::from(net), u128::from(ip), self.prefix, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("Invalid network address '{}'", s))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("Invalid prefix length in '{}'", s))?,
+            None => max,
+        };
+        // An IPv4-mapped network matches the IPv4 addresses it carries
+        if let IpAddr::V6(v6) = addr
+            && let Some(v4) = v6.to_ipv4_mapped()
+            && prefix >= 96
+        {
+            return Ok(Self {
+                addr: IpAddr::V4(v4),
+                prefix: prefix - 96,
+            });
+        }
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Treats IPv4-mapped IPv6 addresses as the IPv4 address they carry.
+pub fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4),
+        v4 => v4,
+    }
+}
+
+fn prefix_matches(net: u128, ip: u128, prefix: u8, bits: u32) -> bool {
+    let host_bits = bits - u32::from(prefix);
+    host_bits >= bits || (net ^ ip) >> host_bits == 0
+}
+
+/// Parses a comma-separated list of networks.
+///
+/// # Errors
+/// Returns the first entry that is not an address or CIDR.
+pub fn parse_networks(list: &str) -> Result<Vec<IpNet>, String> {
+    list.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(IpNet::from_str)
+        .collect()
+}
+
+/// Reads a network list from the environment variable `var`.
+///
+/// Invalid entries are skipped with a warning, so a typo narrows the list
+/// rather than widening it.
+pub fn networks_from_env(var: &str) -> Vec<IpNet> {
+    let Ok(list) = std::env::var(var) else {
+        return Vec::new();
+    };
+    list.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| match entry.parse() {
+            Ok(net) => Some(net),
+            Err(e) => {
+                warn!("Ignoring {} entry: {}", var, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Whether `ip` lies in any of `networks`.
+pub fn in_networks(networks: &[IpNet], ip: IpAddr) -> bool {
+    networks.iter().any(|net| net.contains(ip))
+}
+
+/// Whether `ip` is a loopback address, including IPv4-mapped `::ffff:127.0.0.1`.
+pub fn is_loopback(ip: IpAddr) -> bool {
+    canonical(ip).is_loopback()
+}
+
+/// Whether `ip` is not reachable from the public internet: loopback,
+/// private, link-local, unique local or unspecified.
+pub fn is_private(ip: IpAddr) -> bool {
+    match canonical(ip) {
+        IpAddr::V4(v4) => {
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+        }
+        IpAddr::V6(v6) => {
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_unique_local()
+                || v6.is_unicast_link_local()
+        }
+    }
+}
+
+/// Host of the absolute URL `url`, lowercased and without brackets or a
+/// trailing dot; `None` when `url` has no `scheme://`.
+pub fn url_host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    };
+    Some(host.trim_end_matches('.').to_ascii_lowercase())
+}
+
+/// Checks that `host`, as [`url_host`] returns it, may be called back:
+/// it is not `localhost`, nor an address [`check_public_ip`] refuses.
+///
+/// Names are not resolved; callers check the addresses a name resolves to
+/// with [`check_public_ip`] when they connect.
+///
+/// # Errors
+/// Returns why the host is refused.
+pub fn check_public_host(host: &str, internal_networks: &[IpNet]) -> Result<(), &'static str> {
+    if host.is_empty() {
+        return Err("missing host");
+    }
+    if host == "localhost" || host.ends_with(".localhost") {
+        return Err("host is loopback");
+    }
+    match host.parse::<IpAddr>() {
+        Ok(ip) => check_public_ip(ip, internal_networks),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Checks that `ip` is neither private (see [`is_private`]) nor in
+/// `internal_networks`.
+///
+/// # Errors
+/// Returns why the address is refused.
+pub fn check_public_ip(ip: IpAddr, internal_networks: &[IpNet]) -> Result<(), &'static str> {
+    if is_private(ip) || in_networks(internal_networks, ip) {
+        return Err("host is a private or internal address");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ip_net_contains() {
+        let net: IpNet = "10.1.0.0/16".parse().unwrap();
+        assert!(net.contains(ip("10.1.200.3")));
+        assert!(net.contains(ip("::ffff:10.1.0.9")));
+        assert!(!net.contains(ip("10.2.0.1")));
+
+        let mapped: IpNet = "::ffff:192.168.0.0/112".parse().unwrap();
+        assert_eq!(mapped.to_string(), "192.168.0.0/16");
+        assert!(mapped.contains(ip("192.168.4.4")));
+
+        let host: IpNet = "192.168.1.7".parse().unwrap();
+        assert!(host.contains(ip("192.168.1.7")));
+        assert!(!host.contains(ip("192.168.1.8")));
+
+        let any: IpNet = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(ip("203.0.113.9")));
+        assert!(!any.contains(ip("2001:db8::1")));
+
+        let v6: IpNet = "fd00::/8".parse().unwrap();
+        assert!(v6.contains(ip("fd12:3456::1")));
+        assert!(!v6.contains(ip("fe80::1")));
+    }
+
+    #[test]
+    fn test_is_private() {
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(is_private(ip(private)), "{}", private);
+        }
+        for public in ["8.8.8.8", "172.32.0.1", "2001:4860:4860::8888"] {
+            assert!(!is_private(ip(public)), "{}", public);
+        }
+    }
+
+    #[test]
+    fn test_parse_networks() {
+        let nets = parse_networks("10.0.0.0/8, 127.0.0.1 ,,fd00::/8").unwrap();
+        assert_eq!(nets.len(), 3);
+        assert_eq!(nets[1].to_string(), "127.0.0.1/32");
+
+        assert!(parse_networks("10.0.0.0/33").is_err());
+        assert!(parse_networks("intranet").is_err());
+        assert!(parse_networks("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_public_host() {
+        let internal = parse_networks("100.64.0.0/10").unwrap();
+        for (url, host) in [
+            (
+                "https://user@Hooks.Example.com.:8443/x?y",
+                "hooks.example.com",
+            ),
+            ("http://[2001:4860::1]/push", "2001:4860::1"),
+            ("https://", ""),
+        ] {
+            assert_eq!(url_host(url).as_deref(), Some(host), "{}", url);
+        }
+        assert_eq!(url_host("hooks.example.com/x"), None);
+
+        for ok in ["hooks.example.com", "8.8.8.8", "2001:4860::1"] {
+            assert!(check_public_host(ok, &internal).is_ok(), "{}", ok);
+        }
+        for bad in [
+            "",
+            "localhost",
+            "api.localhost",
+            "127.0.0.1",
+            "169.254.169.254",
+            "10.0.0.5",
+            "::1",
+            "::ffff:127.0.0.1",
+            "100.64.1.1",
+        ] {
+            assert!(check_public_host(bad, &internal).is_err(), "{}", bad);
+        }
+    }
+}
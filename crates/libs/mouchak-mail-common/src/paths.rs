@@ -0,0 +1,183 @@
+//! Where mouchak-mail keeps its files.
+//!
+//! By default state lives under `data/` in the working directory (the
+//! database path is resolved separately, see `DATABASE_PATH`) and the user
+//! config file is `~/.mouchak-mail/config.toml`.
+//!
+//! In portable mode, enabled with `mouchak-mail --portable <dir>` or
+//! `MOUCHAK_MAIL_PORTABLE_DIR`, everything lives under one directory, so an
+//! instance can run from a USB stick or a per-repository sandbox:
+//!
+//! | Path | Contents |
+//! |------|----------|
+//! | `<dir>/config.toml` | Configuration, the only config file read |
+//! | `<dir>/mouchak_mail.db` | Database |
+//! | `<dir>/archive/` | Git archive |
+//! | `<dir>/attachments/` | Uploaded attachments |
+//! | `<dir>/exports/` | Files of finished export jobs |
+//! | `<dir>/archives/` | `archive save` backups |
+//! | `<dir>/templates/` | Project templates |
+//! | `<dir>/logs/` | Daily log files |
+//!
+//! The directory is made absolute once, so later changes of the working
+//! directory do not move any of these, and relative paths in the
+//! configuration (such as `plugins.paths`) are resolved against it with
+//! [`resolve`]. Migrations and the web UI are compiled into the binary, so
+//! nothing outside the directory is needed.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable that enables portable mode.
+pub const PORTABLE_DIR_ENV: &str = "MOUCHAK_MAIL_PORTABLE_DIR";
+
+/// File name of the database in the data directory.
+pub const DB_FILE: &str = "mouchak_mail.db";
+
+/// File name of the config file in the portable directory.
+pub const CONFIG_FILE: &str = "config.toml";
+
+static PORTABLE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Enables portable mode with `dir`, creating it if needed.
+///
+/// Must be called before anything reads a path, typically right after
+/// parsing the command line. Takes precedence over `MOUCHAK_MAIL_PORTABLE_DIR`.
+///
+/// # Returns
+/// The absolute portable directory.
+///
+/// # Errors
+/// Returns an error when the directory cannot be created, or portable mode
+/// was already resolved.
+pub fn set_portable_dir(dir: &Path) -> io::Result<PathBuf> {
+    let dir = absolute_dir(dir)?;
+    PORTABLE_DIR
+        .set(Some(dir.clone()))
+        .map_err(|_| io::Error::other("portable directory is already set"))?;
+    Ok(dir)
+}
+
+/// The portable directory, if portable mode is enabled.
+pub fn portable_dir() -> Option<&'static Path> {
+    PORTABLE_DIR
+        .get_or_init(|| {
+            let dir = std::env::var_os(PORTABLE_DIR_ENV).filter(|dir| !dir.is_empty())?;
+            match absolute_dir(Path::new(&dir)) {
+                Ok(dir) => Some(dir),
+                Err(e) => {
+                    tracing::warn!(
+                        "Ignoring {}={:?}: {}",
+                        PORTABLE_DIR_ENV,
+                        dir.to_string_lossy(),
+                        e
+                    );
+                    None
+                }
+            }
+        })
+        .as_deref()
+}
+
+/// Directory holding the database, archive and attachments: the portable
+/// directory, or `data/` in the working directory.
+pub fn data_dir() -> PathBuf {
+    match portable_dir() {
+        Some(dir) => dir.to_path_buf(),
+        None => std::env::current_dir()
+            .map(|cwd| cwd.join("data"))
+            .unwrap_or_else(|_| PathBuf::from("data")),
+    }
+}
+
+/// Database file in [`data_dir`].
+pub fn db_file() -> PathBuf {
+    data_dir().join(DB_FILE)
+}
+
+/// Root of the Git archive.
+pub fn archive_root() -> PathBuf {
+    data_dir().join("archive")
+}
+
+/// Directory of uploaded attachments.
+pub fn attachments_dir() -> PathBuf {
+    data_dir().join("attachments")
+}
+
+/// Directory of `archive save` backups.
+pub fn backups_dir() -> PathBuf {
+    data_dir().join("archives")
+}
+
+/// Directory of project template bundles (`<name>.json`).
+pub fn templates_dir() -> PathBuf {
+    data_dir().join("templates")
+}
+
+/// Directory for log files; only set in portable mode.
+pub fn log_dir() -> Option<PathBuf> {
+    portable_dir().map(|dir| dir.join("logs"))
+}
+
+/// Config file written by `config` commands: `<dir>/config.toml` in
+/// portable mode, otherwise `~/.mouchak-mail/config.toml`.
+pub fn user_config_file() -> Option<PathBuf> {
+    match portable_dir() {
+        Some(dir) => Some(dir.join(CONFIG_FILE)),
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".mouchak-mail").join(CONFIG_FILE)),
+    }
+}
+
+/// Resolves a configured path: relative paths are taken relative to the
+/// portable directory in portable mode, and left as they are otherwise.
+pub fn resolve(path: impl AsRef<Path>) -> PathBuf {
+    resolve_in(portable_dir(), path.as_ref())
+}
+
+fn resolve_in(base: Option<&Path>, path: &Path) -> PathBuf {
+    match base {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn absolute_dir(dir: &Path) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    std::path::absolute(dir)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_in() {
+        let base = Path::new("/media/usb/mouchak");
+        assert_eq!(
+            resolve_in(Some(base), Path::new("plugins/policy.wasm")),
+            PathBuf::from("/media/usb/mouchak/plugins/policy.wasm")
+        );
+        assert_eq!(
+            resolve_in(Some(base), Path::new("/opt/extra.wasm")),
+            PathBuf::from("/opt/extra.wasm")
+        );
+        assert_eq!(
+            resolve_in(None, Path::new("plugins/policy.wasm")),
+            PathBuf::from("plugins/policy.wasm")
+        );
+    }
+
+    #[test]
+    fn test_absolute_dir_creates_directory() {
+        let temp = std::env::temp_dir().join(format!("mouchak-portable-{}", std::process::id()));
+        let nested = temp.join("a").join("b");
+        let dir = absolute_dir(&nested).unwrap();
+        assert!(dir.is_absolute());
+        assert!(dir.is_dir());
+        std::fs::remove_dir_all(&temp).unwrap();
+    }
+}
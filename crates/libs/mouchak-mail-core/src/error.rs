@@ -42,6 +42,7 @@ use thiserror::Error;
 /// ## External Errors
 /// Errors from external dependencies are automatically converted using `#[from]`:
 /// - [`Error::Libsql`] - Database errors from libsql
+/// - [`Error::Postgres`] / [`Error::PostgresPool`] - Database errors on the Postgres backend
 /// - [`Error::Git2`] - Git repository errors
 /// - [`Error::SerdeJson`] - JSON serialization/deserialization errors
 /// - [`Error::Io`] - Standard I/O errors
@@ -70,6 +71,19 @@ pub enum Error {
     #[error("Libsql Error: {0}")]
     Libsql(#[from] libsql::Error),
 
+    /// Database error from Postgres.
+    ///
+    /// Automatically converted from [`tokio_postgres::Error`] via `From`;
+    /// shows the server's message, which the error's own `Display` leaves out.
+    #[error("Postgres Error: {}", crate::store::postgres::postgres_message(.0))]
+    Postgres(#[from] tokio_postgres::Error),
+
+    /// No Postgres connection could be checked out of the pool.
+    ///
+    /// Automatically converted from [`deadpool_postgres::PoolError`] via `From`.
+    #[error("Postgres Pool Error: {0}")]
+    PostgresPool(#[from] deadpool_postgres::PoolError),
+
     /// Git repository error.
     ///
     /// Automatically converted from [`git2::Error`] via `From`.
@@ -213,6 +227,44 @@ pub enum Error {
     /// The contained structure provides details about the limit and usage.
     #[error("Quota exceeded: {0}")]
     QuotaExceeded(String),
+
+    /// A contact policy refused contact: a project's policy refused
+    /// cross-project contact, or an agent's policy refused a thread watcher.
+    ///
+    /// Contains who refused contact from whom.
+    #[error("Contact denied: {0}")]
+    ContactDenied(String),
+
+    /// An operator plugin's `pre_send` hook refused a message.
+    ///
+    /// Contains the plugin name and its reason.
+    #[error("Rejected by plugin: {0}")]
+    PluginRejected(String),
+
+    /// An agent tried to send on behalf of another agent without a
+    /// delegation grant.
+    ///
+    /// Contains the delegate and the agent it tried to send for.
+    #[error("Delegation denied: {0}")]
+    DelegationDenied(String),
+
+    /// An agent tried to decide a held message it may not approve.
+    ///
+    /// Contains the agent and the message.
+    #[error("Approval denied: {0}")]
+    ApprovalDenied(String),
+
+    /// The data directory's format does not match this build.
+    ///
+    /// Contains upgrade (or downgrade) instructions for the operator.
+    #[error("Data upgrade required: {0}")]
+    UpgradeRequired(String),
+
+    /// Writes are suspended because the data volume is nearly full.
+    ///
+    /// Contains the free space left; writes resume once space is freed.
+    #[error("Server is read-only: {0}")]
+    ReadOnly(String),
 }
 
 impl Error {
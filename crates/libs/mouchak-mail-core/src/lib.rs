@@ -1,6 +1,9 @@
-//! # lib-core: Core Business Logic for Mouchak Mail
+//! # mouchak-mail-core: Core Business Logic for Mouchak Mail
 //!
-//! `lib-core` contains the core domain logic and data access for the Mouchak Mail application.
+//! `mouchak-mail-core` contains the core domain logic and data access for the Mouchak Mail
+//! application. Every binary (`mouchak-mail`, `mouchak-mail-stdio`, `mouchak-mail-http`)
+//! and the MCP and HTTP layers build on this one crate, so domain types such as the typed
+//! IDs in [`types`] are shared everywhere.
 //!
 //! This crate provides the core business logic layer for the Mouchak Mail system,
 //! following the BMC (Backend Model Controller) pattern for consistent data access.
@@ -15,13 +15,14 @@
 //! use mouchak_mail_core::model::activity::ActivityBmc;
 //! use mouchak_mail_core::model::ModelManager;
 //! use mouchak_mail_core::ctx::Ctx;
+//! use mouchak_mail_core::types::ProjectId;
 //!
 //! # async fn example() -> mouchak_mail_core::Result<()> {
 //! let mm = ModelManager::new(std::sync::Arc::new(mouchak_mail_common::config::AppConfig::default())).await?;
 //! let ctx = Ctx::root_ctx();
 //!
 //! // Get recent activity for a project
-//! let activity = ActivityBmc::list_recent(&ctx, &mm, 1, 50).await?;
+//! let activity = ActivityBmc::list_recent(&ctx, &mm, ProjectId::new(1), 50).await?;
 //! for item in activity {
 //!     println!("[{}] {}: {}", item.kind, item.created_at, item.title);
 //! }
@@ -32,6 +33,7 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::types::ProjectId;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -97,7 +99,7 @@ impl ActivityBmc {
     pub async fn list_recent(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
         limit: i64,
     ) -> Result<Vec<ActivityItem>> {
         let db = mm.db();
@@ -105,7 +107,7 @@ impl ActivityBmc {
 
         // 1. Fetch recent Messages
         let limit_val: i64 = limit;
-        let params: Vec<libsql::Value> = vec![project_id.into(), limit_val.into()];
+        let params: Vec<libsql::Value> = vec![project_id.get().into(), limit_val.into()];
 
         // We select fields manually to populate Message struct or just direct to fields we need
         // Let's use MessageBmc if available? No, MessageBmc doesn't expose list by project easily with limit sorted global?
@@ -159,7 +161,7 @@ impl ActivityBmc {
             items.push(ActivityItem {
                 id: format!("tool:{}", t.id),
                 kind: "tool".into(),
-                project_id,
+                project_id: project_id.get(),
                 agent_id: t.agent_id,
                 title: format!("Tool Used: {}", t.tool_name),
                 description: Some(format!(
@@ -172,7 +174,7 @@ impl ActivityBmc {
         }
 
         // 3. Fetch recent Agents
-        let params: Vec<libsql::Value> = vec![project_id.into(), limit_val.into()];
+        let params: Vec<libsql::Value> = vec![project_id.get().into(), limit_val.into()];
         let sql_agent = r#"
             SELECT id, project_id, name, task_description, inception_ts
             FROM agents
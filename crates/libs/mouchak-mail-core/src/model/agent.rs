@@ -41,6 +41,8 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::model::listing::{ListPage, ListQuery, ListSort};
 use crate::store::git_store;
 use crate::types::{AgentId, ProjectId};
 use crate::utils::mistake_detection::suggest_similar;
@@ -49,6 +51,25 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+/// Tables whose rows name an agent in the given column; they are deleted
+/// with the agent.
+const AGENT_TABLES: &[(&str, &str)] = &[
+    ("file_reservation_watchers", "agent_id"),
+    ("deferred_deliveries", "agent_id"),
+    ("thread_reads", "agent_id"),
+    ("reservation_sets", "agent_id"),
+    ("sla_violations", "agent_id"),
+    ("workflow_transitions", "agent_id"),
+    ("thread_watchers", "agent_id"),
+    ("watcher_deliveries", "agent_id"),
+    ("delegation_grants", "grantor_id"),
+    ("delegation_grants", "delegate_id"),
+    ("message_delegations", "delegate_id"),
+    ("held_deliveries", "agent_id"),
+    ("reply_deadlines", "agent_id"),
+    ("saved_searches", "agent_id"),
+];
+
 /// A registered AI coding agent.
 ///
 /// Agents are AI assistants that work on tasks within a project. Each agent
@@ -67,6 +88,7 @@ use std::path::PathBuf;
 /// - `last_active_ts` - Last activity timestamp
 /// - `attachments_policy` - How agent handles file attachments
 /// - `contact_policy` - Agent communication preferences
+/// - `public_id` - Stable ULID for referencing the agent outside this database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Agent {
     pub id: AgentId,
@@ -79,6 +101,8 @@ pub struct Agent {
     pub last_active_ts: NaiveDateTime,
     pub attachments_policy: String,
     pub contact_policy: String,
+    #[serde(default)]
+    pub public_id: String,
 }
 
 /// Input data for creating a new agent.
@@ -118,9 +142,10 @@ impl AgentBmc {
     /// This method:
     /// 1. Inserts the agent into the database
     /// 2. Creates a profile.json file in the Git archive
+    /// 3. Appends an `agent.registered` event to the event log
     ///
     /// # Arguments
-    /// * `_ctx` - Request context (unused currently)
+    /// * `ctx` - Request context
     /// * `mm` - ModelManager providing database and Git access
     /// * `agent_c` - Agent creation data
     ///
@@ -151,7 +176,7 @@ impl AgentBmc {
     /// let id = AgentBmc::create(&ctx, mm, agent).await.unwrap();
     /// # }
     /// ```
-    pub async fn create(_ctx: &Ctx, mm: &ModelManager, agent_c: AgentForCreate) -> Result<AgentId> {
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, agent_c: AgentForCreate) -> Result<AgentId> {
         let db = mm.db();
 
         // 1. Insert into DB
@@ -219,6 +244,22 @@ impl AgentBmc {
             "mcp-bot@localhost",
         )?;
 
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                "agent.registered",
+                Some(agent_c.project_id.get()),
+                Some(&agent_c.name),
+                serde_json::json!({
+                    "agent_id": id.get(),
+                    "program": agent_c.program,
+                    "model": agent_c.model,
+                }),
+            ),
+        )
+        .await;
+
         Ok(id)
     }
 
@@ -238,7 +279,8 @@ impl AgentBmc {
         let db = mm.db();
         let stmt = db.prepare(
             r#"
-            SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy
+            SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy,
+                   COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'agent' AND x.entity_id = agents.id), '')
             FROM agents WHERE id = ?
             "#
         ).await?;
@@ -247,7 +289,7 @@ impl AgentBmc {
         if let Some(row) = rows.next().await? {
             // Column indices: 0=id, 1=project_id, 2=name, 3=program, 4=model,
             //                 5=task_description, 6=inception_ts, 7=last_active_ts,
-            //                 8=attachments_policy, 9=contact_policy
+            //                 8=attachments_policy, 9=contact_policy, 10=public_id
             let inception_ts_str: String = row.get(6)?;
             let inception_ts = parse_timestamp(&inception_ts_str, "agent.inception_ts");
             let last_active_ts_str: String = row.get(7)?;
@@ -264,6 +306,7 @@ impl AgentBmc {
                 last_active_ts,
                 attachments_policy: row.get(8)?,
                 contact_policy: row.get(9)?,
+                public_id: row.get(10)?,
             })
         } else {
             Err(crate::Error::agent_not_found(format!("ID: {}", id)))
@@ -292,7 +335,8 @@ impl AgentBmc {
         let db = mm.db();
         let stmt = db.prepare(
             r#"
-            SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy
+            SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy,
+                   COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'agent' AND x.entity_id = agents.id), '')
             FROM agents WHERE project_id = ? AND name = ?
             "#
         ).await?;
@@ -301,7 +345,7 @@ impl AgentBmc {
         if let Some(row) = rows.next().await? {
             // Column indices: 0=id, 1=project_id, 2=name, 3=program, 4=model,
             //                 5=task_description, 6=inception_ts, 7=last_active_ts,
-            //                 8=attachments_policy, 9=contact_policy
+            //                 8=attachments_policy, 9=contact_policy, 10=public_id
             let inception_ts_str: String = row.get(6)?;
             let inception_ts = parse_timestamp(&inception_ts_str, "agent.inception_ts");
             let last_active_ts_str: String = row.get(7)?;
@@ -318,6 +362,7 @@ impl AgentBmc {
                 last_active_ts,
                 attachments_policy: row.get(8)?,
                 contact_policy: row.get(9)?,
+                public_id: row.get(10)?,
             })
         } else {
             // Fetch all agent names in this project for suggestions
@@ -368,7 +413,8 @@ impl AgentBmc {
         let db = mm.db();
         let stmt = db.prepare(
             r#"
-            SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy
+            SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy,
+                   COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'agent' AND x.entity_id = agents.id), '')
             FROM agents WHERE project_id = ? AND LOWER(name) = 'reviewer'
             "#
         ).await?;
@@ -403,6 +449,7 @@ impl AgentBmc {
                 last_active_ts,
                 attachments_policy: row.get(8)?,
                 contact_policy: row.get(9)?,
+                public_id: row.get(10)?,
             }))
         } else {
             Ok(None)
@@ -426,7 +473,8 @@ impl AgentBmc {
         let db = mm.db();
         let stmt = db.prepare(
             r#"
-            SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy
+            SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy,
+                   COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'agent' AND x.entity_id = agents.id), '')
             FROM agents WHERE project_id = ? ORDER BY name ASC
             "#
         ).await?;
@@ -436,7 +484,7 @@ impl AgentBmc {
         while let Some(row) = rows.next().await? {
             // Column indices: 0=id, 1=project_id, 2=name, 3=program, 4=model,
             //                 5=task_description, 6=inception_ts, 7=last_active_ts,
-            //                 8=attachments_policy, 9=contact_policy
+            //                 8=attachments_policy, 9=contact_policy, 10=public_id
             let inception_ts_str: String = row.get(6)?;
             let inception_ts = parse_timestamp(&inception_ts_str, "agent.inception_ts");
             let last_active_ts_str: String = row.get(7)?;
@@ -453,11 +501,91 @@ impl AgentBmc {
                 last_active_ts,
                 attachments_policy: row.get(8)?,
                 contact_policy: row.get(9)?,
+                public_id: row.get(10)?,
             });
         }
         Ok(agents)
     }
 
+    /// Lists a page of a project's agents, filtered and sorted by `query`.
+    ///
+    /// Sorts by name unless `query.sort` says otherwise.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a bad limit, cursor or `active_within`.
+    pub async fn list_page(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        query: &ListQuery,
+    ) -> Result<ListPage<Agent>> {
+        let limit = query.sql_limit()?;
+        let offset = query.sql_offset()?;
+        let active_since = query.active_since()?;
+        let order = match query.sort.unwrap_or(ListSort::Name) {
+            ListSort::Name => "name ASC, id ASC",
+            ListSort::Created => "inception_ts DESC, id DESC",
+            ListSort::LastActive => "last_active_ts DESC, id DESC",
+        };
+        let filter = r#"
+            FROM agents
+            WHERE project_id = ?1
+              AND (?2 IS NULL OR last_active_ts >= ?2)
+              AND (?3 IS NULL OR LOWER(program) = LOWER(?3))
+              AND (?4 IS NULL OR LOWER(model) = LOWER(?4))
+            "#;
+        let params = || {
+            (
+                project_id.get(),
+                active_since.clone(),
+                query.program.clone(),
+                query.model.clone(),
+            )
+        };
+
+        let db = mm.db_read();
+        let stmt = db
+            .prepare(&format!(
+                r#"
+                SELECT id, project_id, name, program, model, task_description, inception_ts, last_active_ts, attachments_policy, contact_policy,
+                   COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'agent' AND x.entity_id = agents.id), '')
+                {filter}
+                ORDER BY {order}
+                LIMIT {limit} OFFSET {offset}
+                "#
+            ))
+            .await?;
+        let mut rows = stmt.query(params()).await?;
+
+        let mut agents = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let inception_ts_str: String = row.get(6)?;
+            let last_active_ts_str: String = row.get(7)?;
+            agents.push(Agent {
+                id: AgentId::new(row.get(0)?),
+                project_id: ProjectId::new(row.get(1)?),
+                name: row.get(2)?,
+                program: row.get(3)?,
+                model: row.get(4)?,
+                task_description: row.get(5)?,
+                inception_ts: parse_timestamp(&inception_ts_str, "agent.inception_ts"),
+                last_active_ts: parse_timestamp(&last_active_ts_str, "agent.last_active_ts"),
+                attachments_policy: row.get(8)?,
+                contact_policy: row.get(9)?,
+                public_id: row.get(10)?,
+            });
+        }
+
+        let stmt = db.prepare(&format!("SELECT COUNT(*) {filter}")).await?;
+        let mut rows = stmt.query(params()).await?;
+        let total: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+
+        Ok(query.page(agents, offset, total))
+    }
+
     /// Counts the total messages sent by an agent.
     ///
     /// # Arguments
@@ -566,12 +694,13 @@ impl AgentBmc {
     /// Deletes an agent and all related data (cascade delete).
     ///
     /// Deletion order for FK constraint satisfaction:
-    /// 1. message_recipients (references agent_id)
+    /// 1. message_recipients (references agent_id), per-message rows of sent messages
     /// 2. messages (where sender_id = agent_id)
-    /// 3. file_reservations, build_slots
+    /// 3. file_reservations (and their watchers and set memberships), build_slots
     /// 4. agent_links (both sides)
     /// 5. overseer_messages
-    /// 6. agent itself
+    /// 6. other rows that reference the agent
+    /// 7. agent itself
     ///
     /// Also removes the agent directory from the Git archive.
     ///
@@ -598,19 +727,48 @@ impl AgentBmc {
             .ok_or_else(|| crate::Error::project_not_found(format!("ID: {}", agent.project_id)))?
             .get(0)?;
 
-        // 1. Delete message_recipients for this agent
+        // 1. Delete message_recipients for this agent, and the per-message rows
+        // of the messages it sent
         let stmt = db
             .prepare("DELETE FROM message_recipients WHERE agent_id = ?")
             .await?;
         stmt.execute([agent_id.get()]).await?;
+        for table in super::retention::MESSAGE_TABLES {
+            let sql = format!(
+                "DELETE FROM {} WHERE message_id IN (SELECT id FROM messages WHERE sender_id = ?)",
+                table
+            );
+            let stmt = db.prepare(&sql).await?;
+            stmt.execute([agent_id.get()]).await?;
+        }
 
-        // 2. Delete messages where this agent is sender (FTS5 trigger handles messages_fts)
+        // 2. Delete messages where this agent is sender (FTS5 triggers handle messages_fts and message_search)
         let stmt = db
             .prepare("DELETE FROM messages WHERE sender_id = ?")
             .await?;
         stmt.execute([agent_id.get()]).await?;
 
-        // 3. Delete file_reservations
+        // 3. Delete file_reservations, the watchers waiting on them and their
+        // reservation set memberships
+        let stmt = db
+            .prepare(
+                r#"
+                DELETE FROM file_reservation_watchers
+                WHERE reservation_id IN (SELECT id FROM file_reservations WHERE agent_id = ?)
+                "#,
+            )
+            .await?;
+        stmt.execute([agent_id.get()]).await?;
+        let stmt = db
+            .prepare(
+                r#"
+                DELETE FROM reservation_set_members
+                WHERE set_id IN (SELECT id FROM reservation_sets WHERE agent_id = ?1)
+                   OR reservation_id IN (SELECT id FROM file_reservations WHERE agent_id = ?1)
+                "#,
+            )
+            .await?;
+        stmt.execute([agent_id.get()]).await?;
         let stmt = db
             .prepare("DELETE FROM file_reservations WHERE agent_id = ?")
             .await?;
@@ -634,11 +792,23 @@ impl AgentBmc {
             .await?;
         stmt.execute([agent_id.get()]).await?;
 
-        // 7. Delete the agent
+        // 7. Delete the rows other tables keep for the agent
+        for (table, column) in AGENT_TABLES {
+            let sql = format!("DELETE FROM {} WHERE {} = ?", table, column);
+            let stmt = db.prepare(&sql).await?;
+            stmt.execute([agent_id.get()]).await?;
+        }
+        // Labels it applied stay on their messages
+        let stmt = db
+            .prepare("UPDATE message_labels SET applied_by = NULL WHERE applied_by = ?")
+            .await?;
+        stmt.execute([agent_id.get()]).await?;
+
+        // 8. Delete the agent
         let stmt = db.prepare("DELETE FROM agents WHERE id = ?").await?;
         stmt.execute([agent_id.get()]).await?;
 
-        // 8. Clean up Git archive
+        // 9. Clean up Git archive
         let agent_dir = mm
             .repo_root
             .join("projects")
@@ -1,6 +1,7 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::types::AgentId;
 use crate::utils::{parse_timestamp, parse_timestamp_opt};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
@@ -114,7 +115,7 @@ impl AgentCapabilityBmc {
     pub async fn list_for_agent(
         _ctx: &Ctx,
         mm: &ModelManager,
-        agent_id: i64,
+        agent_id: AgentId,
     ) -> Result<Vec<AgentCapability>> {
         let db = mm.db();
         let now = chrono::Utc::now().naive_utc();
@@ -130,7 +131,7 @@ impl AgentCapabilityBmc {
             "#,
             )
             .await?;
-        let mut rows = stmt.query((agent_id, now_str)).await?;
+        let mut rows = stmt.query((agent_id.get(), now_str)).await?;
 
         let mut capabilities = Vec::new();
         while let Some(row) = rows.next().await? {
@@ -167,7 +168,7 @@ impl AgentCapabilityBmc {
     pub async fn check(
         _ctx: &Ctx,
         mm: &ModelManager,
-        agent_id: i64,
+        agent_id: AgentId,
         capability: &str,
     ) -> Result<bool> {
         let db = mm.db();
@@ -180,7 +181,7 @@ impl AgentCapabilityBmc {
                    AND (expires_at IS NULL OR expires_at > ?)"#,
             )
             .await?;
-        let mut rows = stmt.query((agent_id, capability, now_str)).await?;
+        let mut rows = stmt.query((agent_id.get(), capability, now_str)).await?;
 
         if let Some(row) = rows.next().await? {
             let count: i64 = row.get(0)?;
@@ -201,11 +202,11 @@ impl AgentCapabilityBmc {
     ///
     /// # Returns
     /// Number of capabilities granted
-    pub async fn grant_defaults(ctx: &Ctx, mm: &ModelManager, agent_id: i64) -> Result<usize> {
+    pub async fn grant_defaults(ctx: &Ctx, mm: &ModelManager, agent_id: AgentId) -> Result<usize> {
         let mut granted = 0;
         for cap in DEFAULT_CAPABILITIES {
             let cap_c = AgentCapabilityForCreate {
-                agent_id,
+                agent_id: agent_id.get(),
                 capability: (*cap).to_string(),
                 granted_by: None,
                 expires_at: None,
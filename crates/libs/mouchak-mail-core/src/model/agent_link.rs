@@ -1,6 +1,9 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::model::project_contact_policy::ProjectContactPolicyBmc;
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, ProjectId};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
@@ -57,8 +60,12 @@ pub struct AgentLinkBmc;
 
 impl AgentLinkBmc {
     /// Request contact from agent A to agent B
+    ///
+    /// # Errors
+    /// Returns `ContactDenied` if B's project policy does not allow contact
+    /// from A's project.
     pub async fn request_contact(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         link_c: AgentLinkForCreate,
     ) -> Result<i64> {
@@ -69,6 +76,14 @@ impl AgentLinkBmc {
             ));
         }
 
+        ProjectContactPolicyBmc::check(
+            ctx,
+            mm,
+            ProjectId::new(link_c.a_project_id),
+            ProjectId::new(link_c.b_project_id),
+        )
+        .await?;
+
         let db = mm.db();
 
         let stmt = db.prepare(
@@ -127,8 +142,8 @@ impl AgentLinkBmc {
     pub async fn list_contacts(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
-        agent_id: i64,
+        project_id: ProjectId,
+        agent_id: AgentId,
     ) -> Result<Vec<AgentLink>> {
         let db = mm.db();
         let stmt = db.prepare(
@@ -144,7 +159,12 @@ impl AgentLinkBmc {
         ).await?;
 
         let mut rows = stmt
-            .query((project_id, agent_id, project_id, agent_id))
+            .query((
+                project_id.get(),
+                agent_id.get(),
+                project_id.get(),
+                agent_id.get(),
+            ))
             .await?;
         let mut links = Vec::new();
 
@@ -158,8 +178,8 @@ impl AgentLinkBmc {
     pub async fn list_pending_requests(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
-        agent_id: i64,
+        project_id: ProjectId,
+        agent_id: AgentId,
     ) -> Result<Vec<AgentLink>> {
         let db = mm.db();
         let stmt = db.prepare(
@@ -171,7 +191,7 @@ impl AgentLinkBmc {
             "#
         ).await?;
 
-        let mut rows = stmt.query((project_id, agent_id)).await?;
+        let mut rows = stmt.query((project_id.get(), agent_id.get())).await?;
         let mut links = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -180,7 +200,7 @@ impl AgentLinkBmc {
         Ok(links)
     }
 
-    fn from_row(row: libsql::Row) -> Result<AgentLink> {
+    fn from_row(row: Row) -> Result<AgentLink> {
         let created_ts_str: String = row.get(7).unwrap_or_default();
         let updated_ts_str: String = row.get(8).unwrap_or_default();
         let expires_ts_str: Option<String> = row.get(9).unwrap_or_default();
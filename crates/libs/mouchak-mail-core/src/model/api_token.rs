@@ -0,0 +1,365 @@
+//! Per-agent API tokens with scoped permissions.
+//!
+//! A token is issued to one agent and carries the scopes it may use. A scope
+//! names an access level and a resource, such as `read:inbox`,
+//! `write:messages` or `admin:reservations`. Each level includes the ones
+//! below it (`admin` > `write` > `read`), and the bare `admin` scope grants
+//! everything.
+//!
+//! The token itself is shown once, when it is created. The database keeps
+//! only its SHA-256 hash and a short prefix to recognize it in listings.
+//! [`ApiTokenBmc::authenticate`] maps a presented token to its agent and
+//! scopes; revoked and expired tokens no longer authenticate.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Prefix of every token, so they stand out in configs and logs.
+pub const TOKEN_PREFIX: &str = "mmt_";
+
+/// Resources a scope can name.
+pub const SCOPE_RESOURCES: &[&str] = &["inbox", "outbox", "messages", "reservations", "builds"];
+
+/// Characters of the token kept in the clear to recognize it.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// `last_used_ts` is rewritten at most this often, so authenticating does
+/// not turn every request into a write.
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+/// Rank of a scope's access level; higher levels include lower ones.
+fn level_rank(level: &str) -> Option<u8> {
+    match level {
+        "read" => Some(0),
+        "write" => Some(1),
+        "admin" => Some(2),
+        _ => None,
+    }
+}
+
+/// Checks a scope and returns it in canonical (lowercase) form.
+///
+/// # Errors
+/// Returns `InvalidInput` for anything but `admin` or `<level>:<resource>`.
+pub fn parse_scope(scope: &str) -> Result<String> {
+    let scope = scope.trim().to_ascii_lowercase();
+    let valid = scope == "admin"
+        || scope.split_once(':').is_some_and(|(level, resource)| {
+            level_rank(level).is_some() && SCOPE_RESOURCES.contains(&resource)
+        });
+    if !valid {
+        return Err(Error::InvalidInput(format!(
+            "Unknown scope '{}' (expected admin or read|write|admin:{})",
+            scope,
+            SCOPE_RESOURCES.join("|")
+        )));
+    }
+    Ok(scope)
+}
+
+/// Whether `granted` scopes permit an action that needs `required`.
+pub fn scopes_allow(granted: &[String], required: &str) -> bool {
+    let needed = required
+        .split_once(':')
+        .and_then(|(level, resource)| Some((level_rank(level)?, resource)));
+    granted.iter().any(|scope| {
+        scope == "admin"
+            || scope == required
+            || needed.is_some_and(|(needed_rank, needed_resource)| {
+                scope.split_once(':').is_some_and(|(level, resource)| {
+                    resource == needed_resource
+                        && level_rank(level).is_some_and(|rank| rank >= needed_rank)
+                })
+            })
+    })
+}
+
+/// SHA-256 hex digest under which a token is stored.
+fn hash_token(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// An issued API token, without its secret.
+///
+/// # Fields
+///
+/// - `token_prefix` - First characters of the token, to recognize it
+/// - `revoked_ts` - When the token was revoked; revoked tokens stay listed
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiToken {
+    pub id: i64,
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub project_id: ProjectId,
+    pub project_slug: String,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub created_ts: NaiveDateTime,
+    pub expires_ts: Option<NaiveDateTime>,
+    pub last_used_ts: Option<NaiveDateTime>,
+    pub revoked_ts: Option<NaiveDateTime>,
+}
+
+impl ApiToken {
+    /// Whether the token permits an action that needs `required`.
+    pub fn allows(&self, required: &str) -> bool {
+        scopes_allow(&self.scopes, required)
+    }
+}
+
+/// Input for issuing a token.
+///
+/// # Fields
+///
+/// - `name` - What the token is for, e.g. `ci-runner`
+/// - `scopes` - At least one scope (see [`parse_scope`])
+/// - `expires_ts` - Optional expiry; tokens do not expire by default
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiTokenForCreate {
+    pub agent_id: AgentId,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_ts: Option<NaiveDateTime>,
+}
+
+/// A newly issued token and its secret, which cannot be retrieved later.
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+const TOKEN_COLUMNS: &str = r#"
+    t.id, t.agent_id, ag.name, ag.project_id, p.slug, t.name, t.token_prefix, t.scopes,
+    t.created_ts, t.expires_ts, t.last_used_ts, t.revoked_ts
+    FROM api_tokens AS t
+    JOIN agents AS ag ON ag.id = t.agent_id
+    JOIN projects AS p ON p.id = ag.project_id
+"#;
+
+/// Backend Model Controller for per-agent API tokens.
+pub struct ApiTokenBmc;
+
+impl ApiTokenBmc {
+    /// Issues a token to an agent and returns it with its secret.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` without scopes, for an unknown scope or an
+    /// expiry that has passed, and `NotFound` for an unknown agent.
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        token_c: ApiTokenForCreate,
+    ) -> Result<IssuedToken> {
+        let mut scopes = token_c
+            .scopes
+            .iter()
+            .map(|s| parse_scope(s))
+            .collect::<Result<Vec<_>>>()?;
+        scopes.sort();
+        scopes.dedup();
+        if scopes.is_empty() {
+            return Err(Error::InvalidInput(
+                "A token needs at least one scope".into(),
+            ));
+        }
+        if token_c
+            .expires_ts
+            .is_some_and(|ts| ts <= chrono::Utc::now().naive_utc())
+        {
+            return Err(Error::InvalidInput(
+                "Token expiry must be in the future".into(),
+            ));
+        }
+
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let secret = format!("{}{}", TOKEN_PREFIX, hex::encode(bytes));
+        let prefix: String = secret.chars().take(DISPLAY_PREFIX_LEN).collect();
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO api_tokens (agent_id, name, token_hash, token_prefix, scopes, expires_ts)
+                SELECT id, ?, ?, ?, ?, ? FROM agents WHERE id = ?
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                token_c.name.trim(),
+                hash_token(&secret),
+                prefix,
+                scopes.join(" "),
+                token_c
+                    .expires_ts
+                    .map(|ts| ts.format(TS_FORMAT).to_string()),
+                token_c.agent_id.get(),
+            ))
+            .await?;
+        let id: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => return Err(Error::NotFound),
+        };
+
+        let token = Self::get(ctx, mm, id).await?;
+        Ok(IssuedToken { token, secret })
+    }
+
+    /// Gets a token by id.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if no such token exists.
+    pub async fn get(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<ApiToken> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!("SELECT {TOKEN_COLUMNS} WHERE t.id = ?"))
+            .await?;
+        let mut rows = stmt.query([id]).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Lists tokens, newest first, optionally only those of one project's
+    /// agents. Revoked and expired tokens are included.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: Option<ProjectId>,
+    ) -> Result<Vec<ApiToken>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "SELECT {TOKEN_COLUMNS} WHERE (?1 IS NULL OR ag.project_id = ?1) ORDER BY t.id DESC"
+            ))
+            .await?;
+        let mut rows = stmt.query([project_id.map(|p| p.get())]).await?;
+        let mut tokens = Vec::new();
+        while let Some(row) = rows.next().await? {
+            tokens.push(Self::from_row(&row)?);
+        }
+        Ok(tokens)
+    }
+
+    /// Revokes a token. Revoking it again keeps the first revocation time.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if no such token exists.
+    pub async fn revoke(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<ApiToken> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("UPDATE api_tokens SET revoked_ts = COALESCE(revoked_ts, ?) WHERE id = ?")
+            .await?;
+        let now = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        if stmt.execute((now, id)).await? == 0 {
+            return Err(Error::NotFound);
+        }
+        Self::get(ctx, mm, id).await
+    }
+
+    /// Maps a presented token to the live token it belongs to, recording
+    /// its use (to the minute) unless the server is read-only. Returns
+    /// `None` for unknown, revoked and expired tokens.
+    pub async fn authenticate(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        secret: &str,
+    ) -> Result<Option<ApiToken>> {
+        if !secret.starts_with(TOKEN_PREFIX) {
+            return Ok(None);
+        }
+        let now = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                r#"
+                SELECT {TOKEN_COLUMNS}
+                WHERE t.token_hash = ?1 AND t.revoked_ts IS NULL
+                  AND (t.expires_ts IS NULL OR t.expires_ts > ?2)
+                "#
+            ))
+            .await?;
+        let mut rows = stmt.query((hash_token(secret), now.as_str())).await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+        let mut token = Self::from_row(&row)?;
+        // Reads must keep working with writes suspended
+        if mm.is_read_only() {
+            return Ok(Some(token));
+        }
+        let used_now = parse_timestamp(&now, "api_tokens.last_used_ts");
+        let recent = token
+            .last_used_ts
+            .is_some_and(|last| (used_now - last).num_seconds() < LAST_USED_RESOLUTION_SECONDS);
+        if recent {
+            return Ok(Some(token));
+        }
+
+        let stmt = db
+            .prepare("UPDATE api_tokens SET last_used_ts = ? WHERE id = ?")
+            .await?;
+        stmt.execute((now.as_str(), token.id)).await?;
+        token.last_used_ts = Some(used_now);
+        Ok(Some(token))
+    }
+
+    fn from_row(row: &Row) -> Result<ApiToken> {
+        let scopes: String = row.get(7)?;
+        let created_ts: String = row.get(8)?;
+        Ok(ApiToken {
+            id: row.get(0)?,
+            agent_id: AgentId::new(row.get(1)?),
+            agent_name: row.get(2)?,
+            project_id: ProjectId::new(row.get(3)?),
+            project_slug: row.get(4)?,
+            name: row.get(5)?,
+            token_prefix: row.get(6)?,
+            scopes: scopes.split_whitespace().map(str::to_string).collect(),
+            created_ts: parse_timestamp(&created_ts, "api_tokens.created_ts"),
+            expires_ts: parse_timestamp_opt(row.get(9)?, "api_tokens.expires_ts"),
+            last_used_ts: parse_timestamp_opt(row.get(10)?, "api_tokens.last_used_ts"),
+            revoked_ts: parse_timestamp_opt(row.get(11)?, "api_tokens.revoked_ts"),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scope() {
+        assert_eq!(parse_scope(" Read:Inbox ").unwrap(), "read:inbox");
+        assert_eq!(parse_scope("admin").unwrap(), "admin");
+        assert!(parse_scope("read").is_err());
+        assert!(parse_scope("delete:inbox").is_err());
+        assert!(parse_scope("read:everything").is_err());
+    }
+
+    #[test]
+    fn test_scopes_allow_higher_levels() {
+        let scopes = vec!["read:inbox".to_string(), "admin:reservations".to_string()];
+        assert!(scopes_allow(&scopes, "read:inbox"));
+        assert!(!scopes_allow(&scopes, "write:messages"));
+        assert!(scopes_allow(&scopes, "write:reservations"));
+        assert!(scopes_allow(&scopes, "admin:reservations"));
+        assert!(!scopes_allow(&scopes, "admin"));
+        assert!(scopes_allow(&["admin".to_string()], "write:messages"));
+    }
+}
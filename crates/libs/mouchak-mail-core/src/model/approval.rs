@@ -0,0 +1,582 @@
+//! Approval-gated sends for high-impact messages.
+//!
+//! A project can define approval rules, e.g. "urgent mail to more than five
+//! recipients" or "anything mentioning deploy". A message matching a rule is
+//! stored and archived as usual, but its recipient rows are held in
+//! `held_deliveries` instead of `message_recipients`, so it reaches no inbox
+//! until someone decides:
+//!
+//! - an approver named by the rule, or any other agent of the project when
+//!   the rule names none (never the sender itself)
+//! - a human operator, through the admin API
+//!
+//! Approval delivers the held rows at once and only then announces the
+//! message (`message.sent`, urgent notifications, `post_receive` plugins);
+//! rejection drops them. Messages
+//! nobody decides on are rejected when the rule's timeout passes, by
+//! [`ApprovalBmc::expire_due`] (run periodically by the server).
+//!
+//! Every decision stays in `pending_approvals` and is logged as
+//! `message.held`, `message.approved` or `message.rejected`.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::model::focus_window::IMPORTANCE_LEVELS;
+use crate::model::message::MessageBmc;
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, ApprovalRuleId, MessageId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// `decided_by` of messages rejected because nobody decided in time.
+pub const TIMEOUT_DECIDER: &str = "timeout";
+
+/// `decided_by` of decisions made by a human operator.
+pub const HUMAN_DECIDER: &str = "human";
+
+/// Conditions under which a project's messages wait for approval.
+///
+/// Every condition that is set must hold for the rule to match.
+///
+/// # Fields
+///
+/// - `min_importance` - Importance at or above which messages are held
+/// - `min_recipients` - Number of to, cc and bcc recipients at or above
+///   which messages are held
+/// - `keyword` - Text that, found in the subject or body (ignoring case),
+///   holds the message
+/// - `approvers` - Agents who may decide; empty lets any agent but the sender
+/// - `timeout_seconds` - Time after which an undecided message is rejected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRule {
+    pub id: ApprovalRuleId,
+    pub project_id: ProjectId,
+    pub min_importance: Option<String>,
+    pub min_recipients: Option<i64>,
+    pub keyword: Option<String>,
+    pub approvers: Vec<String>,
+    pub timeout_seconds: i64,
+    pub created_ts: NaiveDateTime,
+}
+
+impl ApprovalRule {
+    /// Returns `true` if a message with these properties must wait for approval.
+    pub fn matches(&self, importance: &str, recipients: usize, subject: &str, body: &str) -> bool {
+        let level = |name: &str| IMPORTANCE_LEVELS.iter().position(|l| *l == name);
+        let importance_ok = match &self.min_importance {
+            Some(min) => level(importance) >= level(min),
+            None => true,
+        };
+        let recipients_ok = match self.min_recipients {
+            Some(min) => recipients as i64 >= min,
+            None => true,
+        };
+        let keyword_ok = match &self.keyword {
+            Some(keyword) => {
+                let keyword = keyword.to_lowercase();
+                subject.to_lowercase().contains(&keyword) || body.to_lowercase().contains(&keyword)
+            }
+            None => true,
+        };
+        importance_ok && recipients_ok && keyword_ok
+    }
+}
+
+/// Input to define an approval rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRuleForCreate {
+    pub project_id: ProjectId,
+    pub min_importance: Option<String>,
+    pub min_recipients: Option<i64>,
+    pub keyword: Option<String>,
+    #[serde(default)]
+    pub approvers: Vec<String>,
+    pub timeout_seconds: i64,
+}
+
+/// A message held by an approval rule, and its decision once made.
+///
+/// # Fields
+///
+/// - `status` - `pending`, `approved` or `rejected`
+/// - `approvers` - The rule's approvers when the message was held
+/// - `decided_by` - Approving agent, [`HUMAN_DECIDER`] or [`TIMEOUT_DECIDER`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub message_id: MessageId,
+    pub project_id: ProjectId,
+    pub rule_id: ApprovalRuleId,
+    pub sender_name: String,
+    pub subject: String,
+    pub approvers: Vec<String>,
+    pub status: String,
+    pub expires_ts: NaiveDateTime,
+    pub decided_by: Option<String>,
+    pub decided_ts: Option<NaiveDateTime>,
+    pub reason: Option<String>,
+    pub created_ts: NaiveDateTime,
+}
+
+const SELECT_PENDING: &str = r#"
+    SELECT p.message_id, p.project_id, p.rule_id, s.name, m.subject, p.approvers, p.status,
+           p.expires_ts, p.decided_by, p.decided_ts, p.reason, p.created_ts
+    FROM pending_approvals p
+    JOIN messages m ON m.id = p.message_id
+    JOIN agents s ON s.id = m.sender_id
+"#;
+
+/// Backend Model Controller for approval rules and held messages.
+pub struct ApprovalBmc;
+
+impl ApprovalBmc {
+    /// Defines an approval rule.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if the rule sets no condition, names an unknown
+    /// importance level or approver, or has no positive timeout.
+    pub async fn create_rule(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        rule_c: ApprovalRuleForCreate,
+    ) -> Result<ApprovalRuleId> {
+        let keyword = rule_c
+            .keyword
+            .as_deref()
+            .map(str::trim)
+            .filter(|k| !k.is_empty());
+        if rule_c.min_importance.is_none() && rule_c.min_recipients.is_none() && keyword.is_none() {
+            return Err(Error::InvalidInput(
+                "An approval rule needs min_importance, min_recipients or keyword".into(),
+            ));
+        }
+        if let Some(importance) = &rule_c.min_importance
+            && !IMPORTANCE_LEVELS.contains(&importance.as_str())
+        {
+            return Err(Error::InvalidInput(format!(
+                "Unknown importance '{}', expected one of: {}",
+                importance,
+                IMPORTANCE_LEVELS.join(", ")
+            )));
+        }
+        if rule_c.min_recipients.is_some_and(|n| n < 1) {
+            return Err(Error::InvalidInput(
+                "min_recipients must be at least 1".into(),
+            ));
+        }
+        if rule_c.timeout_seconds <= 0 {
+            return Err(Error::InvalidInput(
+                "Approval timeout must be a positive number of seconds".into(),
+            ));
+        }
+        for approver in &rule_c.approvers {
+            AgentBmc::get_by_name(ctx, mm, rule_c.project_id, approver)
+                .await
+                .map_err(|_| Error::InvalidInput(format!("Unknown approver '{}'", approver)))?;
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO approval_rules
+                    (project_id, min_importance, min_recipients, keyword, approvers, timeout_seconds)
+                VALUES (?, ?, ?, ?, ?, ?)
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                rule_c.project_id.get(),
+                rule_c.min_importance,
+                rule_c.min_recipients,
+                keyword,
+                rule_c.approvers.join(","),
+                rule_c.timeout_seconds,
+            ))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(ApprovalRuleId::new(row.get(0)?)),
+            None => Err(Error::InvalidInput("Failed to save approval rule".into())),
+        }
+    }
+
+    /// Lists a project's approval rules, oldest first.
+    pub async fn list_rules(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<ApprovalRule>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, min_importance, min_recipients, keyword, approvers,
+                       timeout_seconds, created_ts
+                FROM approval_rules WHERE project_id = ? ORDER BY id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut rules = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let approvers: String = row.get(5)?;
+            let created_ts: String = row.get(7)?;
+            rules.push(ApprovalRule {
+                id: ApprovalRuleId::new(row.get(0)?),
+                project_id: ProjectId::new(row.get(1)?),
+                min_importance: row.get(2)?,
+                min_recipients: row.get(3)?,
+                keyword: row.get(4)?,
+                approvers: split_names(&approvers),
+                timeout_seconds: row.get(6)?,
+                created_ts: parse_timestamp(&created_ts, "approval_rules.created_ts"),
+            });
+        }
+        Ok(rules)
+    }
+
+    /// Removes an approval rule; messages it already holds stay held.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no rule with this id.
+    pub async fn delete_rule(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        rule_id: ApprovalRuleId,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM approval_rules WHERE id = ? AND project_id = ?")
+            .await?;
+        let deleted = stmt.execute((rule_id.get(), project_id.get())).await?;
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// The approval state of a message, if it was ever held.
+    pub async fn get(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<Option<PendingApproval>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!("{} WHERE p.message_id = ?", SELECT_PENDING))
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::pending_from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Lists a project's messages still waiting for a decision, oldest first.
+    pub async fn list_pending(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<PendingApproval>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE p.project_id = ? AND p.status = 'pending' ORDER BY p.message_id",
+                SELECT_PENDING
+            ))
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut pending = Vec::new();
+        while let Some(row) = rows.next().await? {
+            pending.push(Self::pending_from_row(&row)?);
+        }
+        Ok(pending)
+    }
+
+    /// Approves or rejects a held message.
+    ///
+    /// `approver` is the deciding agent, or `None` for a human operator.
+    /// Approval delivers the message to its recipients immediately.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the message is not held, `InvalidInput` if it
+    /// was already decided, and `ApprovalDenied` if the agent is its sender
+    /// or not one of the rule's approvers.
+    pub async fn decide(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        approver: Option<AgentId>,
+        approve: bool,
+        reason: Option<&str>,
+    ) -> Result<PendingApproval> {
+        let pending = Self::get(ctx, mm, message_id)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if pending.status != "pending" {
+            return Err(Error::InvalidInput(format!(
+                "Message {} was already {}",
+                message_id, pending.status
+            )));
+        }
+        let decided_by = match approver {
+            Some(agent_id) => {
+                let agent = AgentBmc::get(ctx, mm, agent_id).await?;
+                let allowed = agent.project_id == pending.project_id
+                    && agent.name != pending.sender_name
+                    && (pending.approvers.is_empty() || pending.approvers.contains(&agent.name));
+                if !allowed {
+                    return Err(Error::ApprovalDenied(format!(
+                        "'{}' may not decide on message {}",
+                        agent.name, message_id
+                    )));
+                }
+                agent.name
+            }
+            None => HUMAN_DECIDER.to_string(),
+        };
+
+        let status = if approve { "approved" } else { "rejected" };
+        if !Self::close(mm, message_id, status, &decided_by, reason).await? {
+            return Err(Error::InvalidInput(format!(
+                "Message {} was already decided",
+                message_id
+            )));
+        }
+        if approve {
+            Self::release(mm, message_id).await?;
+        } else {
+            Self::drop_held(mm, message_id).await?;
+        }
+        Self::record_decision(ctx, mm, &pending, status, &decided_by, reason).await;
+        if approve {
+            MessageBmc::announce_released(ctx, mm, message_id).await;
+        }
+
+        Self::get(ctx, mm, message_id).await?.ok_or(Error::NotFound)
+    }
+
+    /// Rejects held messages whose rule timeout has passed.
+    ///
+    /// Intended to run periodically from a background task.
+    ///
+    /// # Returns
+    /// Number of messages rejected.
+    pub async fn expire_due(ctx: &Ctx, mm: &ModelManager) -> Result<usize> {
+        let now_str = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE p.status = 'pending' AND p.expires_ts <= ?",
+                SELECT_PENDING
+            ))
+            .await?;
+        let mut rows = stmt.query([now_str]).await?;
+        let mut due = Vec::new();
+        while let Some(row) = rows.next().await? {
+            due.push(Self::pending_from_row(&row)?);
+        }
+
+        let reason = Some("approval timed out");
+        let mut expired = 0;
+        for pending in due {
+            if Self::close(mm, pending.message_id, "rejected", TIMEOUT_DECIDER, reason).await? {
+                Self::drop_held(mm, pending.message_id).await?;
+                Self::record_decision(ctx, mm, &pending, "rejected", TIMEOUT_DECIDER, reason).await;
+                expired += 1;
+            }
+        }
+        Ok(expired)
+    }
+
+    /// The first of a project's rules matching a message, if any.
+    pub(crate) async fn matching_rule(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        importance: &str,
+        recipients: usize,
+        subject: &str,
+        body: &str,
+    ) -> Result<Option<ApprovalRule>> {
+        let rules = Self::list_rules(ctx, mm, project_id).await?;
+        Ok(rules
+            .into_iter()
+            .find(|rule| rule.matches(importance, recipients, subject, body)))
+    }
+
+    /// Holds a new message's recipient rows until it is decided.
+    pub(crate) async fn hold(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        rule: &ApprovalRule,
+        recipients: &[(i64, &str)],
+    ) -> Result<()> {
+        let db = mm.db();
+        for (agent_id, recipient_type) in recipients {
+            let stmt = db
+                .prepare(
+                    r#"
+                    INSERT OR IGNORE INTO held_deliveries (message_id, agent_id, recipient_type)
+                    VALUES (?, ?, ?)
+                    "#,
+                )
+                .await?;
+            stmt.execute((message_id.get(), *agent_id, *recipient_type))
+                .await?;
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let expires_ts = Duration::try_seconds(rule.timeout_seconds)
+            .and_then(|timeout| now.checked_add_signed(timeout))
+            .unwrap_or(NaiveDateTime::MAX);
+        let expires_str = expires_ts.format(TS_FORMAT).to_string();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO pending_approvals (message_id, project_id, rule_id, approvers, expires_ts)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            message_id.get(),
+            rule.project_id.get(),
+            rule.id.get(),
+            rule.approvers.join(","),
+            expires_str.as_str(),
+        ))
+        .await?;
+
+        if let Some(pending) = Self::get(ctx, mm, message_id).await? {
+            EventLogBmc::record(
+                ctx,
+                mm,
+                EventForCreate::new(
+                    "message.held",
+                    Some(rule.project_id.get()),
+                    Some(&pending.sender_name),
+                    serde_json::json!({
+                        "message_id": message_id,
+                        "rule_id": rule.id,
+                        "approvers": rule.approvers,
+                        "expires_ts": expires_str,
+                    }),
+                ),
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    /// Marks a pending message decided; `false` if it no longer was pending.
+    async fn close(
+        mm: &ModelManager,
+        message_id: MessageId,
+        status: &str,
+        decided_by: &str,
+        reason: Option<&str>,
+    ) -> Result<bool> {
+        let now_str = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE pending_approvals
+                SET status = ?, decided_by = ?, decided_ts = ?, reason = ?
+                WHERE message_id = ? AND status = 'pending'
+                "#,
+            )
+            .await?;
+        let updated = stmt
+            .execute((status, decided_by, now_str, reason, message_id.get()))
+            .await?;
+        Ok(updated > 0)
+    }
+
+    /// Delivers a message's held recipient rows.
+    async fn release(mm: &ModelManager, message_id: MessageId) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT OR IGNORE INTO message_recipients (message_id, agent_id, recipient_type)
+                SELECT message_id, agent_id, recipient_type FROM held_deliveries
+                WHERE message_id = ?
+                "#,
+            )
+            .await?;
+        stmt.execute([message_id.get()]).await?;
+        Self::drop_held(mm, message_id).await
+    }
+
+    async fn drop_held(mm: &ModelManager, message_id: MessageId) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM held_deliveries WHERE message_id = ?")
+            .await?;
+        stmt.execute([message_id.get()]).await?;
+        Ok(())
+    }
+
+    async fn record_decision(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        pending: &PendingApproval,
+        status: &str,
+        decided_by: &str,
+        reason: Option<&str>,
+    ) {
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                &format!("message.{}", status),
+                Some(pending.project_id.get()),
+                Some(decided_by),
+                serde_json::json!({
+                    "message_id": pending.message_id,
+                    "sender": pending.sender_name,
+                    "reason": reason,
+                }),
+            ),
+        )
+        .await;
+    }
+
+    fn pending_from_row(row: &Row) -> Result<PendingApproval> {
+        let approvers: String = row.get(5)?;
+        let expires_ts: String = row.get(7)?;
+        let decided_ts: Option<String> = row.get(9)?;
+        let created_ts: String = row.get(11)?;
+        Ok(PendingApproval {
+            message_id: MessageId::new(row.get(0)?),
+            project_id: ProjectId::new(row.get(1)?),
+            rule_id: ApprovalRuleId::new(row.get(2)?),
+            sender_name: row.get(3)?,
+            subject: row.get(4)?,
+            approvers: split_names(&approvers),
+            status: row.get(6)?,
+            expires_ts: parse_timestamp(&expires_ts, "pending_approvals.expires_ts"),
+            decided_by: row.get(8)?,
+            decided_ts: parse_timestamp_opt(decided_ts, "pending_approvals.decided_ts"),
+            reason: row.get(10)?,
+            created_ts: parse_timestamp(&created_ts, "pending_approvals.created_ts"),
+        })
+    }
+}
+
+fn split_names(names: &str) -> Vec<String> {
+    names
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
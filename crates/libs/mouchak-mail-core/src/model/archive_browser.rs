@@ -802,7 +802,7 @@ impl ArchiveBrowserBmc {
 
         // Get top 10 most changed files
         let mut file_counts: Vec<_> = file_change_counts.into_iter().collect();
-        file_counts.sort_by(|a, b| b.1.cmp(&a.1));
+        file_counts.sort_by_key(|entry| std::cmp::Reverse(entry.1));
         let most_changed_files: Vec<_> = file_counts.into_iter().take(10).collect();
 
         Ok(ActivitySummary {
@@ -0,0 +1,203 @@
+//! Git archive compaction.
+//!
+//! Every archived entity adds loose objects to the archive repository, and
+//! long-running instances accumulate thousands of them. [`ArchiveGcBmc::gc`]
+//! packs them with `git gc` and reports the space reclaimed. It runs from
+//! the `archive gc` CLI command and, when `ARCHIVE_GC_INTERVAL_SECONDS` is
+//! set, from a background job of the server.
+//!
+//! # Safety checks
+//!
+//! - `git_lock` is held, so this process makes no archive commits while
+//!   the repository is repacked, and the cross-process archive lock keeps
+//!   two compactions from overlapping.
+//! - A leftover `index.lock` or `gc.pid` means another Git process may be
+//!   working on the repository, so the run is skipped.
+//! - `git fsck --connectivity-only` must pass before anything is repacked.
+//! - Unreachable objects are pruned with Git's default grace period
+//!   (`gc.pruneExpire`, two weeks), never immediately.
+//!
+//! The `git` executable must be on `PATH`.
+
+use crate::model::ModelManager;
+use crate::store::git_store;
+use crate::{Ctx, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+use tokio::process::Command;
+use tracing::info;
+
+/// Object counts and sizes of a Git repository.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectStats {
+    pub loose_objects: u64,
+    pub loose_bytes: u64,
+    pub packs: u64,
+    /// Size of pack files and their indexes
+    pub pack_bytes: u64,
+}
+
+impl ObjectStats {
+    pub fn total_bytes(&self) -> u64 {
+        self.loose_bytes + self.pack_bytes
+    }
+}
+
+/// How to run a compaction.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GcOptions {
+    /// Skip the run while there are fewer loose objects than this
+    pub min_loose_objects: u64,
+    /// Pass `--aggressive` to `git gc` (much slower, slightly smaller packs)
+    pub aggressive: bool,
+    /// Only report what would be compacted
+    pub dry_run: bool,
+}
+
+/// Outcome of a compaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcReport {
+    pub before: ObjectStats,
+    /// Equal to `before` when nothing ran
+    pub after: ObjectStats,
+    pub reclaimed_bytes: u64,
+    /// Whether `git gc` ran
+    pub compacted: bool,
+    /// Why the run was skipped
+    pub skipped: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Backend Model Controller for archive maintenance.
+pub struct ArchiveGcBmc;
+
+impl ArchiveGcBmc {
+    /// Object counts and sizes of the archive repository.
+    pub async fn stats(_ctx: &Ctx, mm: &ModelManager) -> Result<ObjectStats> {
+        let git_dir = git_store::open_repo(&mm.repo_root)?.path().to_path_buf();
+        object_stats(&git_dir)
+    }
+
+    /// Compacts the archive repository with `git gc`.
+    ///
+    /// # Errors
+    /// Returns an error when `git` cannot be run, the integrity check fails
+    /// or `git gc` fails, and `LockTimeout` when another process holds the
+    /// archive lock.
+    pub async fn gc(_ctx: &Ctx, mm: &ModelManager, options: &GcOptions) -> Result<GcReport> {
+        let started = Instant::now();
+        let _git_guard = mm.git_lock.lock().await;
+        let _archive_guard = mm.acquire_archive_lock(Some("archive-gc".into())).await?;
+
+        let git_dir = git_store::open_repo(&mm.repo_root)?.path().to_path_buf();
+        let before = object_stats(&git_dir)?;
+        let mut report = GcReport {
+            before,
+            after: before,
+            reclaimed_bytes: 0,
+            compacted: false,
+            skipped: None,
+            duration_ms: 0,
+        };
+
+        if let Some(file) = ["index.lock", "gc.pid"]
+            .into_iter()
+            .find(|file| git_dir.join(file).exists())
+        {
+            report.skipped = Some(format!(
+                "{} exists; another Git process may be using the archive",
+                file
+            ));
+        } else if before.loose_objects < options.min_loose_objects {
+            report.skipped = Some(format!(
+                "{} loose objects, below the threshold of {}",
+                before.loose_objects, options.min_loose_objects
+            ));
+        } else if options.dry_run {
+            report.skipped = Some("dry run".to_string());
+        } else {
+            run_git(
+                &mm.repo_root,
+                &["fsck", "--connectivity-only", "--no-progress"],
+            )
+            .await
+            .map_err(|e| {
+                Error::Io(std::io::Error::other(format!(
+                    "Archive integrity check failed, not compacting: {}",
+                    e
+                )))
+            })?;
+            let mut args = vec!["gc", "--quiet"];
+            if options.aggressive {
+                args.push("--aggressive");
+            }
+            run_git(&mm.repo_root, &args).await?;
+            // Cached handles may still point at the replaced packs
+            mm.repo_cache.clear().await;
+
+            report.after = object_stats(&git_dir)?;
+            report.reclaimed_bytes = before
+                .total_bytes()
+                .saturating_sub(report.after.total_bytes());
+            report.compacted = true;
+            info!(
+                loose_before = before.loose_objects,
+                loose_after = report.after.loose_objects,
+                reclaimed_bytes = report.reclaimed_bytes,
+                "Archive compacted"
+            );
+        }
+        report.duration_ms = started.elapsed().as_millis() as u64;
+        Ok(report)
+    }
+}
+
+/// Runs `git` in `repo_root`, returning stderr as the error on failure.
+async fn run_git(repo_root: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(args)
+        .output()
+        .await?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::Io(std::io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))))
+    }
+}
+
+/// Counts loose objects (`objects/xx/...`) and packs (`objects/pack`).
+fn object_stats(git_dir: &Path) -> Result<ObjectStats> {
+    let objects = git_dir.join("objects");
+    let mut stats = ObjectStats::default();
+    if !objects.is_dir() {
+        return Ok(stats);
+    }
+    for entry in std::fs::read_dir(&objects)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name == "pack" {
+            for file in std::fs::read_dir(entry.path())? {
+                let file = file?;
+                let path = file.path();
+                if path.extension().is_some_and(|ext| ext == "pack") {
+                    stats.packs += 1;
+                }
+                stats.pack_bytes += file.metadata()?.len();
+            }
+        } else if name.len() == 2 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+            for file in std::fs::read_dir(entry.path())? {
+                stats.loose_objects += 1;
+                stats.loose_bytes += file?.metadata()?.len();
+            }
+        }
+    }
+    Ok(stats)
+}
@@ -2,12 +2,16 @@
 //!
 //! This module handles file attachments that can be shared between agents
 //! in a project. Attachment metadata is stored in the database while the
-//! actual files are stored on disk.
+//! actual files are stored in the configured
+//! [attachment store](crate::store::attachment_store).
 //!
 //! # Storage
 //!
-//! - **Database**: Stores metadata (filename, path, media type, size)
-//! - **Disk**: Actual file content at `stored_path`
+//! - **Database**: Stores metadata (filename, location, media type, size)
+//! - **Attachment store**: Actual file content at `stored_path`, a file path
+//!   on disk or an `s3://` location. Content written by [`AttachmentBmc::store`]
+//!   is deduplicated: attachments with the same content share one
+//!   [blob](crate::model::attachment_blob)
 //!
 //! # Example
 //!
@@ -35,8 +39,17 @@
 //! ```
 
 use crate::model::ModelManager;
+use crate::model::attachment_blob::{AttachmentBlobBmc, blob_key};
+use crate::model::intent_journal::{
+    ATTACHMENT_STORE, AttachmentIntent, IntentJournalBmc, STAGE_STARTED,
+};
+use crate::model::usage::UsageBmc;
+use crate::store::attachment_store::{AttachmentReader, FsAttachmentStore, StoredObject};
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, ProjectId};
 use crate::{Ctx, Result};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 use utoipa::ToSchema;
 
 /// File attachment metadata.
@@ -54,6 +67,7 @@ use utoipa::ToSchema;
 /// - `media_type` - MIME type (e.g., "application/pdf")
 /// - `size_bytes` - File size in bytes
 /// - `created_ts` - Upload timestamp
+/// - `content_hash` - BLAKE3 hash of the content, for deduplicated content
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct Attachment {
     /// Database primary key.
@@ -65,7 +79,7 @@ pub struct Attachment {
     pub agent_id: Option<i64>,
     /// Original filename.
     pub filename: String,
-    /// Path to file on disk.
+    /// Path to file on disk, or `s3://<bucket>/<key>`.
     pub stored_path: String,
     /// MIME type.
     pub media_type: String,
@@ -73,6 +87,10 @@ pub struct Attachment {
     pub size_bytes: i64,
     /// Upload timestamp.
     pub created_ts: String,
+    /// Hex BLAKE3 hash of the content; unknown for attachments recorded
+    /// before deduplication or with [`AttachmentBmc::create`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
 }
 
 /// Input data for creating an attachment record.
@@ -96,6 +114,18 @@ pub struct AttachmentForCreate {
     pub size_bytes: i64,
 }
 
+/// An attachment to write with [`AttachmentBmc::store`].
+pub struct AttachmentUpload {
+    /// Project to associate with.
+    pub project_id: i64,
+    /// Optional agent that uploaded the file.
+    pub agent_id: Option<i64>,
+    /// Original filename, without directories.
+    pub filename: String,
+    /// MIME type of the file.
+    pub media_type: String,
+}
+
 /// Backend Model Controller for Attachment operations.
 ///
 /// Manages file attachments associated with projects. Files are stored
@@ -120,12 +150,18 @@ impl AttachmentBmc {
         mm: &ModelManager,
         attachment_c: AttachmentForCreate,
     ) -> Result<i64> {
+        mm.ensure_writable()?;
+
         // Enforce Quota
         if mm.app_config.quota.enabled {
             let limit = mm.app_config.quota.attachments_limit_bytes as i64;
             if limit > 0 {
-                let current_usage =
-                    Self::get_total_project_usage(_ctx, mm, attachment_c.project_id).await?;
+                let current_usage = Self::get_total_project_usage(
+                    _ctx,
+                    mm,
+                    ProjectId::new(attachment_c.project_id),
+                )
+                .await?;
                 if current_usage + attachment_c.size_bytes > limit {
                     return Err(crate::Error::QuotaExceeded(format!(
                         "Attachments limit reached. Current: {} bytes, New: {} bytes, Limit: {} bytes",
@@ -133,6 +169,14 @@ impl AttachmentBmc {
                     )));
                 }
             }
+            if let Some(agent_id) = attachment_c.agent_id {
+                UsageBmc::check_attachment_quota(
+                    mm,
+                    AgentId::new(agent_id),
+                    attachment_c.size_bytes,
+                )
+                .await?;
+            }
         }
 
         let db = mm.db();
@@ -164,6 +208,175 @@ impl AttachmentBmc {
         }
     }
 
+    /// Streams an attachment into the configured store and records it.
+    ///
+    /// The content is written to `incoming/<uuid>` while it is hashed, then
+    /// kept as the blob of its hash, `blobs/<hash>`. Content already stored
+    /// for another attachment is reused and the fresh copy removed. If the
+    /// record cannot be created (e.g. a quota is exceeded) the blob stays
+    /// unreferenced until `gc-attachments` reclaims it.
+    ///
+    /// # Returns
+    /// The created attachment's database ID
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a filename with directories or content
+    /// larger than `ATTACHMENTS_MAX_BYTES`.
+    pub async fn store(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        upload: AttachmentUpload,
+        body: AttachmentReader,
+    ) -> Result<i64> {
+        mm.ensure_writable()?;
+        let filename = upload.filename.trim();
+        if filename.is_empty() || filename.starts_with('.') || filename.contains(['/', '\\']) {
+            return Err(crate::Error::InvalidInput(format!(
+                "Invalid attachment filename '{}'",
+                upload.filename
+            )));
+        }
+
+        let store = mm.attachment_store();
+        let key = format!("incoming/{}", uuid::Uuid::new_v4());
+        // Content written before a crash without its row is removed on recovery
+        let intent = IntentJournalBmc::begin(
+            mm,
+            ATTACHMENT_STORE,
+            &serde_json::to_value(AttachmentIntent {
+                location: store.location(&key),
+                blob: None,
+            })?,
+        )
+        .await?;
+        let object = match store
+            .put(&key, body, mm.app_config.attachments.max_bytes)
+            .await
+        {
+            Ok(object) => object,
+            Err(e) => {
+                Self::complete_intent(mm, intent).await;
+                return Err(e);
+            }
+        };
+        // On failure whatever content is left unrecorded is removed on recovery
+        let location = Self::keep_blob(mm, intent, &object).await?;
+        let size_bytes = i64::try_from(object.size_bytes).unwrap_or(i64::MAX);
+
+        let created = Self::create(
+            ctx,
+            mm,
+            AttachmentForCreate {
+                project_id: upload.project_id,
+                agent_id: upload.agent_id,
+                filename: filename.to_string(),
+                stored_path: location,
+                media_type: upload.media_type,
+                size_bytes,
+            },
+        )
+        .await;
+        let linked = match created {
+            Ok(id) => match AttachmentBlobBmc::link(mm, id, &object.content_hash).await {
+                Ok(()) => Ok(id),
+                Err(e) => {
+                    // An unlinked row would point at content gc-attachments reclaims
+                    if let Err(delete_err) = Self::delete(ctx, mm, id).await {
+                        warn!(
+                            "Failed to remove unlinked attachment {}: {}",
+                            id, delete_err
+                        );
+                    }
+                    Err(e)
+                }
+            },
+            Err(e) => Err(e),
+        };
+        Self::complete_intent(mm, intent).await;
+        linked
+    }
+
+    /// Keeps freshly written content as the blob of its hash and returns
+    /// the blob's location; the fresh copy is dropped if the blob exists.
+    async fn keep_blob(mm: &ModelManager, intent: i64, object: &StoredObject) -> Result<String> {
+        let store = mm.attachment_store();
+        if let Some(location) = AttachmentBlobBmc::claim(mm, &object.content_hash).await? {
+            store.delete(&object.location).await?;
+            return Ok(location);
+        }
+
+        let key = blob_key(&object.content_hash);
+        IntentJournalBmc::advance(
+            mm,
+            intent,
+            STAGE_STARTED,
+            &serde_json::json!({ "blob": store.location(&key) }),
+        )
+        .await?;
+        let location = store.relocate(&object.location, &key).await?;
+        AttachmentBlobBmc::register(
+            mm,
+            &object.content_hash,
+            &location,
+            i64::try_from(object.size_bytes).unwrap_or(i64::MAX),
+        )
+        .await
+    }
+
+    async fn complete_intent(mm: &ModelManager, intent: i64) {
+        if let Err(e) = IntentJournalBmc::complete(mm, intent).await {
+            warn!("Failed to complete attachment intent {}: {}", intent, e);
+        }
+    }
+
+    /// Removes content at `location`, from the configured store or, for
+    /// files written before it was switched to another backend, from disk.
+    pub(in crate::model) async fn remove_content(mm: &ModelManager, location: &str) -> Result<()> {
+        let store = mm.attachment_store();
+        if store.holds(location) {
+            store.delete(location).await
+        } else {
+            FsAttachmentStore::delete_file(location).await
+        }
+    }
+
+    /// Deletes an attachment record.
+    ///
+    /// Deduplicated content is released and reclaimed by `gc-attachments`
+    /// once no attachment uses it; other content is left in place.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the attachment doesn't exist
+    pub async fn delete(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        mm.ensure_writable()?;
+        let db = mm.db();
+        let stmt = db.prepare("DELETE FROM attachments WHERE id = ?").await?;
+        if stmt.execute([id]).await? == 0 {
+            return Err(crate::Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Opens an attachment's content for reading.
+    ///
+    /// Files stored on disk before the store was switched to another
+    /// backend are still read from disk.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the content is missing
+    pub async fn open(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        attachment: &Attachment,
+    ) -> Result<AttachmentReader> {
+        let store = mm.attachment_store();
+        if store.holds(&attachment.stored_path) {
+            store.open(&attachment.stored_path).await
+        } else {
+            FsAttachmentStore::open_file(&attachment.stored_path).await
+        }
+    }
+
     /// Retrieves an attachment by its database ID.
     ///
     /// # Arguments
@@ -178,7 +391,7 @@ impl AttachmentBmc {
     /// Returns `Error::NotFound` if attachment doesn't exist
     pub async fn get(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Attachment> {
         let db = mm.db();
-        let stmt = db.prepare("SELECT id, project_id, agent_id, filename, stored_path, media_type, size_bytes, created_ts FROM attachments WHERE id = ?").await?;
+        let stmt = db.prepare("SELECT a.id, a.project_id, a.agent_id, a.filename, a.stored_path, a.media_type, a.size_bytes, a.created_ts, h.hash FROM attachments a LEFT JOIN attachment_hashes h ON h.attachment_id = a.id WHERE a.id = ?").await?;
         let mut rows = stmt.query([id]).await?;
 
         if let Some(row) = rows.next().await? {
@@ -200,11 +413,11 @@ impl AttachmentBmc {
     pub async fn list_by_project(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<Vec<Attachment>> {
         let db = mm.db();
-        let stmt = db.prepare("SELECT id, project_id, agent_id, filename, stored_path, media_type, size_bytes, created_ts FROM attachments WHERE project_id = ? ORDER BY id DESC").await?;
-        let mut rows = stmt.query([project_id]).await?;
+        let stmt = db.prepare("SELECT a.id, a.project_id, a.agent_id, a.filename, a.stored_path, a.media_type, a.size_bytes, a.created_ts, h.hash FROM attachments a LEFT JOIN attachment_hashes h ON h.attachment_id = a.id WHERE a.project_id = ? ORDER BY a.id DESC").await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
 
         let mut res = Vec::new();
         while let Some(row) = rows.next().await? {
@@ -226,19 +439,19 @@ impl AttachmentBmc {
     pub async fn list_by_project_and_agent(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
-        agent_id: Option<i64>,
+        project_id: ProjectId,
+        agent_id: Option<AgentId>,
     ) -> Result<Vec<Attachment>> {
         let db = mm.db();
 
         let (sql, params): (&str, Vec<i64>) = match agent_id {
             Some(aid) => (
-                "SELECT id, project_id, agent_id, filename, stored_path, media_type, size_bytes, created_ts FROM attachments WHERE project_id = ? AND agent_id = ? ORDER BY id DESC",
-                vec![project_id, aid],
+                "SELECT a.id, a.project_id, a.agent_id, a.filename, a.stored_path, a.media_type, a.size_bytes, a.created_ts, h.hash FROM attachments a LEFT JOIN attachment_hashes h ON h.attachment_id = a.id WHERE a.project_id = ? AND a.agent_id = ? ORDER BY a.id DESC",
+                vec![project_id.get(), aid.get()],
             ),
             None => (
-                "SELECT id, project_id, agent_id, filename, stored_path, media_type, size_bytes, created_ts FROM attachments WHERE project_id = ? ORDER BY id DESC",
-                vec![project_id],
+                "SELECT a.id, a.project_id, a.agent_id, a.filename, a.stored_path, a.media_type, a.size_bytes, a.created_ts, h.hash FROM attachments a LEFT JOIN attachment_hashes h ON h.attachment_id = a.id WHERE a.project_id = ? ORDER BY a.id DESC",
+                vec![project_id.get()],
             ),
         };
 
@@ -255,7 +468,7 @@ impl AttachmentBmc {
         Ok(res)
     }
 
-    fn from_row(row: libsql::Row) -> Result<Attachment> {
+    fn from_row(row: Row) -> Result<Attachment> {
         Ok(Attachment {
             id: row.get(0)?,
             project_id: row.get(1)?,
@@ -265,6 +478,7 @@ impl AttachmentBmc {
             media_type: row.get(5)?,
             size_bytes: row.get(6)?,
             created_ts: row.get(7)?,
+            content_hash: row.get(8)?,
         })
     }
 
@@ -272,14 +486,14 @@ impl AttachmentBmc {
     pub async fn get_total_project_usage(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<i64> {
         let db = mm.db();
         // COALESCE ensures we get 0 instead of NULL if no rows match
         let stmt = db
             .prepare("SELECT COALESCE(SUM(size_bytes), 0) FROM attachments WHERE project_id = ?")
             .await?;
-        let mut rows = stmt.query([project_id]).await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
 
         if let Some(row) = rows.next().await? {
             Ok(row.get(0)?)
@@ -306,6 +520,7 @@ mod tests {
             media_type: "application/pdf".to_string(),
             size_bytes: 1024,
             created_ts: "2024-01-01 00:00:00".to_string(),
+            content_hash: None,
         };
         assert_eq!(attachment.agent_id, Some(42));
     }
@@ -321,6 +536,7 @@ mod tests {
             media_type: "application/pdf".to_string(),
             size_bytes: 1024,
             created_ts: "2024-01-01 00:00:00".to_string(),
+            content_hash: None,
         };
         assert!(attachment.agent_id.is_none());
     }
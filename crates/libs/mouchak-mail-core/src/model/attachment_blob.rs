@@ -0,0 +1,265 @@
+//! Content-addressed attachment blobs.
+//!
+//! [`AttachmentBmc::store`](crate::model::attachment::AttachmentBmc::store)
+//! keeps one copy of each distinct content, at `blobs/<hash>` in the
+//! attachment store, named by its BLAKE3 hash. Every attachment row links
+//! to its blob in `attachment_hashes`, and triggers keep the blob's
+//! `ref_count` equal to its number of links, so a file shared in many
+//! messages is stored once.
+//!
+//! Blobs nobody links to anymore (deleted attachments or projects, uploads
+//! rejected by a quota) are reclaimed by [`AttachmentBlobBmc::gc`], run by
+//! the `gc-attachments` CLI command. Blobs used within a grace period are
+//! kept, so an upload that just found its blob is not undercut.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::attachment::AttachmentBmc;
+use crate::store::db_statement::Row;
+use crate::utils::parse_timestamp;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use tracing::{info, warn};
+
+/// Store key of the blob holding content with `hash`.
+pub fn blob_key(hash: &str) -> String {
+    format!("blobs/{}/{}", &hash[..2.min(hash.len())], hash)
+}
+
+/// Stored content shared by attachments with the same hash.
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentBlob {
+    /// Hex BLAKE3 hash of the content
+    pub hash: String,
+    /// Where the content is stored
+    pub location: String,
+    pub size_bytes: i64,
+    /// Attachments linked to the blob
+    pub ref_count: i64,
+    pub created_ts: NaiveDateTime,
+    /// Last time an attachment was linked to or unlinked from the blob
+    pub last_used_ts: NaiveDateTime,
+}
+
+/// What [`AttachmentBlobBmc::gc`] reclaimed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GcReport {
+    /// Blobs removed, or that would be on a dry run
+    pub blobs: usize,
+    /// Their total size
+    pub bytes: i64,
+    /// Blobs whose content could not be removed; kept for the next run
+    pub failed: usize,
+    pub dry_run: bool,
+}
+
+/// Backend Model Controller for attachment blobs.
+pub struct AttachmentBlobBmc;
+
+impl AttachmentBlobBmc {
+    /// Finds the blob of `hash`, if any content with that hash is stored.
+    pub async fn get_by_hash(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        hash: &str,
+    ) -> Result<Option<AttachmentBlob>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT hash, location, size_bytes, ref_count, created_ts, last_used_ts
+                FROM attachment_blobs
+                WHERE hash = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([hash]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::from_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Marks the blob of `hash` as used and returns its location, so
+    /// [`gc`](Self::gc) leaves it alone until it is linked.
+    pub(in crate::model) async fn claim(mm: &ModelManager, hash: &str) -> Result<Option<String>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE attachment_blobs SET last_used_ts = CURRENT_TIMESTAMP
+                WHERE hash = ?
+                RETURNING location
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([hash]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Records content stored at `location`, unlinked so far.
+    ///
+    /// A concurrent upload of the same content may have recorded it first;
+    /// the recorded location is returned either way.
+    pub(in crate::model) async fn register(
+        mm: &ModelManager,
+        hash: &str,
+        location: &str,
+        size_bytes: i64,
+    ) -> Result<String> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "INSERT OR IGNORE INTO attachment_blobs (hash, location, size_bytes) VALUES (?, ?, ?)",
+            )
+            .await?;
+        stmt.execute((hash, location, size_bytes)).await?;
+        Self::claim(mm, hash)
+            .await?
+            .ok_or_else(|| Error::InvalidInput(format!("Failed to record blob {}", hash)))
+    }
+
+    /// Links an attachment to the blob of `hash`, counting a reference.
+    pub(in crate::model) async fn link(
+        mm: &ModelManager,
+        attachment_id: i64,
+        hash: &str,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("INSERT INTO attachment_hashes (attachment_id, hash) VALUES (?, ?)")
+            .await?;
+        stmt.execute((attachment_id, hash)).await?;
+        Ok(())
+    }
+
+    /// Blobs without references and unused for at least `grace_seconds`,
+    /// oldest first.
+    pub async fn list_unreferenced(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        grace_seconds: i64,
+    ) -> Result<Vec<AttachmentBlob>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT hash, location, size_bytes, ref_count, created_ts, last_used_ts
+                FROM attachment_blobs
+                WHERE ref_count <= 0 AND last_used_ts <= datetime('now', ?)
+                ORDER BY last_used_ts
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([format!("-{} seconds", grace_seconds)]).await?;
+        let mut blobs = Vec::new();
+        while let Some(row) = rows.next().await? {
+            blobs.push(Self::from_row(row)?);
+        }
+        Ok(blobs)
+    }
+
+    /// Removes blobs without references unused for `grace_seconds`; with
+    /// `dry_run` only reports what would be removed.
+    ///
+    /// # Errors
+    /// `ReadOnly` unless `dry_run` while the server is read-only.
+    pub async fn gc(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        grace_seconds: i64,
+        dry_run: bool,
+    ) -> Result<GcReport> {
+        if !dry_run {
+            mm.ensure_writable()?;
+        }
+        let mut report = GcReport {
+            dry_run,
+            ..Default::default()
+        };
+        let db = mm.db();
+        let cutoff = format!("-{} seconds", grace_seconds);
+        for blob in Self::list_unreferenced(ctx, mm, grace_seconds).await? {
+            if dry_run {
+                report.blobs += 1;
+                report.bytes += blob.size_bytes;
+                continue;
+            }
+            // The row goes first, and only while still unused, so a blob an
+            // upload claimed meanwhile is kept
+            let stmt = db
+                .prepare(
+                    r#"
+                    DELETE FROM attachment_blobs
+                    WHERE hash = ? AND ref_count <= 0 AND last_used_ts <= datetime('now', ?)
+                    "#,
+                )
+                .await?;
+            if stmt.execute((blob.hash.as_str(), cutoff.as_str())).await? == 0 {
+                continue;
+            }
+            match AttachmentBmc::remove_content(mm, &blob.location).await {
+                Ok(()) => {
+                    report.blobs += 1;
+                    report.bytes += blob.size_bytes;
+                }
+                Err(e) => {
+                    warn!("Failed to remove attachment blob {}: {}", blob.location, e);
+                    report.failed += 1;
+                    // Recorded again, unused, so the next run retries
+                    let stmt = db
+                        .prepare(
+                            r#"
+                            INSERT OR IGNORE INTO attachment_blobs (hash, location, size_bytes, created_ts, last_used_ts)
+                            VALUES (?, ?, ?, ?, ?)
+                            "#,
+                        )
+                        .await?;
+                    stmt.execute((
+                        blob.hash.as_str(),
+                        blob.location.as_str(),
+                        blob.size_bytes,
+                        blob.created_ts.format("%Y-%m-%d %H:%M:%S").to_string(),
+                        blob.last_used_ts.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    ))
+                    .await?;
+                }
+            }
+        }
+        if report.blobs > 0 && !dry_run {
+            info!(
+                "Reclaimed {} attachment blobs ({} bytes)",
+                report.blobs, report.bytes
+            );
+        }
+        Ok(report)
+    }
+
+    fn from_row(row: Row) -> Result<AttachmentBlob> {
+        let created_ts: String = row.get(4)?;
+        let last_used_ts: String = row.get(5)?;
+        Ok(AttachmentBlob {
+            hash: row.get(0)?,
+            location: row.get(1)?,
+            size_bytes: row.get(2)?,
+            ref_count: row.get(3)?,
+            created_ts: parse_timestamp(&created_ts, "created_ts"),
+            last_used_ts: parse_timestamp(&last_used_ts, "last_used_ts"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_key() {
+        let hash = blake3::hash(b"abc").to_hex().to_string();
+        assert_eq!(blob_key(&hash), format!("blobs/64/{}", hash));
+    }
+}
@@ -0,0 +1,212 @@
+//! Searchable attachment text.
+//!
+//! Text-like attachments (code, logs, markdown, config) are extracted into
+//! `attachment_texts` and indexed by the `attachment_texts_fts` FTS5 table, so
+//! [`MessageBmc::search_with_options`](crate::model::message::MessageBmc::search_with_options)
+//! can match messages by the content of their attached files.
+//!
+//! # Limits
+//!
+//! - Files with a known binary extension, a NUL byte in the first
+//!   [`BINARY_SNIFF_BYTES`], or invalid UTF-8 are skipped
+//! - Only the first [`MAX_INDEXED_BYTES`] of a file are indexed
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::types::MessageId;
+use crate::utils::parse_timestamp;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of bytes of a single attachment that are indexed.
+pub const MAX_INDEXED_BYTES: usize = 256 * 1024;
+
+/// Number of leading bytes inspected for NUL bytes (same heuristic as git).
+pub const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// Extensions that are never indexed, regardless of content.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "pdf", "zip", "gz", "tgz", "bz2", "xz",
+    "7z", "tar", "jar", "wasm", "exe", "dll", "so", "dylib", "bin", "o", "a", "class", "db",
+    "sqlite", "mp3", "mp4", "mov", "woff", "woff2", "ttf", "otf",
+];
+
+/// Extracted, indexed text of an attachment.
+///
+/// # Fields
+///
+/// - `attachment_ref` - Identifier of the stored attachment (e.g. `att_12_ab34cd56`)
+/// - `content` - Indexed text (at most [`MAX_INDEXED_BYTES`])
+/// - `size_bytes` - Size of the original file
+/// - `truncated` - Whether only a prefix of the file was indexed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentText {
+    pub id: i64,
+    pub project_id: i64,
+    pub message_id: i64,
+    pub attachment_ref: String,
+    pub filename: String,
+    pub content: String,
+    pub size_bytes: i64,
+    pub truncated: bool,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Input data for indexing an attachment.
+#[derive(Debug, Clone)]
+pub struct AttachmentTextForCreate {
+    pub project_id: i64,
+    pub message_id: i64,
+    pub attachment_ref: String,
+    pub filename: String,
+    pub content: Vec<u8>,
+}
+
+/// Returns the indexable text of a file and whether it was truncated.
+///
+/// Returns `None` for binary files.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::model::attachment_text::extract_text;
+///
+/// assert_eq!(
+///     extract_text("build.log", b"error: linker failed"),
+///     Some(("error: linker failed".to_string(), false))
+/// );
+/// assert_eq!(extract_text("logo.png", b"not really a png"), None);
+/// assert_eq!(extract_text("blob.dat", b"\x00\x01\x02"), None);
+/// ```
+pub fn extract_text(filename: &str, content: &[u8]) -> Option<(String, bool)> {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    if extension.is_some_and(|ext| BINARY_EXTENSIONS.contains(&ext.as_str())) {
+        return None;
+    }
+
+    let sniff = &content[..content.len().min(BINARY_SNIFF_BYTES)];
+    if sniff.contains(&0) {
+        return None;
+    }
+
+    let truncated = content.len() > MAX_INDEXED_BYTES;
+    let prefix = &content[..content.len().min(MAX_INDEXED_BYTES)];
+    let text = match std::str::from_utf8(prefix) {
+        Ok(text) => text,
+        // A multi-byte character cut by the cap is fine; anything else is binary
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&prefix[..e.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    Some((text.to_string(), truncated))
+}
+
+/// Backend Model Controller for searchable attachment text.
+pub struct AttachmentTextBmc;
+
+impl AttachmentTextBmc {
+    /// Extracts and indexes an attachment's text.
+    ///
+    /// # Returns
+    /// The new row ID, or `None` if the file is binary and was skipped.
+    pub async fn index(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        at_c: AttachmentTextForCreate,
+    ) -> Result<Option<i64>> {
+        let Some((text, truncated)) = extract_text(&at_c.filename, &at_c.content) else {
+            return Ok(None);
+        };
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO attachment_texts (project_id, message_id, attachment_ref, filename, content, size_bytes, truncated)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                at_c.project_id,
+                at_c.message_id,
+                at_c.attachment_ref,
+                at_c.filename,
+                text,
+                at_c.content.len() as i64,
+                truncated,
+            ))
+            .await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(Some(row.get::<i64>(0)?))
+        } else {
+            Err(crate::Error::InvalidInput(
+                "Failed to index attachment".into(),
+            ))
+        }
+    }
+
+    /// Lists the indexed attachments of a message, oldest first.
+    pub async fn list_for_message(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<Vec<AttachmentText>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, message_id, attachment_ref, filename, content, size_bytes, truncated, created_ts
+                FROM attachment_texts
+                WHERE message_id = ?
+                ORDER BY id ASC
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+
+        let mut texts = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(8)?;
+            texts.push(AttachmentText {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                message_id: row.get(2)?,
+                attachment_ref: row.get(3)?,
+                filename: row.get(4)?,
+                content: row.get(5)?,
+                size_bytes: row.get(6)?,
+                truncated: row.get(7)?,
+                created_ts: parse_timestamp(&created_ts, "attachment_texts.created_ts"),
+            });
+        }
+        Ok(texts)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_text_truncates_at_cap() {
+        let content = "é".repeat(MAX_INDEXED_BYTES);
+        let (text, truncated) = extract_text("notes.md", content.as_bytes()).unwrap();
+        assert!(truncated);
+        assert!(text.len() <= MAX_INDEXED_BYTES);
+        assert!(text.chars().all(|c| c == 'é'));
+    }
+
+    #[test]
+    fn test_extract_text_rejects_invalid_utf8() {
+        assert!(extract_text("data.txt", &[0xff, 0xfe, b'a']).is_none());
+    }
+}
@@ -0,0 +1,234 @@
+//! Acknowledgment tracking for broadcast messages.
+//!
+//! An announcement sent to many agents with `ack_required` (typically to the
+//! `broadcast` recipient) is complete once every recipient has acknowledged
+//! it. [`BroadcastStatusBmc::get`] splits a message's recipients into acked
+//! and pending, with time-to-ack statistics, and
+//! [`BroadcastStatusBmc::list_for_project`] summarises completion of a
+//! project's ack-required messages for a dashboard.
+//!
+//! `EscalationMode::Remind` uses the pending list to nag only the recipients
+//! that still owe an acknowledgment.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::message::MessageBmc;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::{parse_timestamp, parse_timestamp_opt};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// One recipient's progress on a message.
+///
+/// # Fields
+///
+/// - `recipient_type` - "to", "cc", or "bcc"
+/// - `seconds_to_ack` - Time from sending to acknowledgment, if acked
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastRecipientStatus {
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub recipient_type: String,
+    pub read_ts: Option<NaiveDateTime>,
+    pub ack_ts: Option<NaiveDateTime>,
+    pub seconds_to_ack: Option<i64>,
+}
+
+/// Time-to-ack statistics over the recipients that acknowledged, in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AckTimeStats {
+    pub min_seconds: i64,
+    pub median_seconds: i64,
+    pub mean_seconds: f64,
+    pub max_seconds: i64,
+}
+
+impl AckTimeStats {
+    /// Computes statistics over `durations`, or `None` if it is empty.
+    pub fn from_durations(durations: &[i64]) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        let mut sorted = durations.to_vec();
+        sorted.sort_unstable();
+        let n = sorted.len();
+        let median_seconds = if n % 2 == 0 {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        } else {
+            sorted[n / 2]
+        };
+        Some(Self {
+            min_seconds: sorted[0],
+            median_seconds,
+            mean_seconds: sorted.iter().sum::<i64>() as f64 / n as f64,
+            max_seconds: sorted[n - 1],
+        })
+    }
+}
+
+/// Acknowledgment status of one message.
+///
+/// # Fields
+///
+/// - `acked` - Recipients that acknowledged, fastest first
+/// - `pending` - Recipients that have not acknowledged yet, by name
+/// - `time_to_ack` - Statistics over `acked`; `None` until someone acks
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastStatus {
+    pub message_id: MessageId,
+    pub project_id: ProjectId,
+    pub sender_name: String,
+    pub subject: String,
+    pub thread_id: Option<String>,
+    pub ack_required: bool,
+    pub created_ts: NaiveDateTime,
+    pub total_recipients: usize,
+    pub acked: Vec<BroadcastRecipientStatus>,
+    pub pending: Vec<BroadcastRecipientStatus>,
+    pub time_to_ack: Option<AckTimeStats>,
+}
+
+impl BroadcastStatus {
+    /// Whether every recipient has acknowledged.
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Completion of one ack-required message, for the dashboard list.
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastSummary {
+    pub message_id: MessageId,
+    pub sender_name: String,
+    pub subject: String,
+    pub created_ts: NaiveDateTime,
+    pub total_recipients: i64,
+    pub acked_count: i64,
+}
+
+/// Backend Model Controller for broadcast acknowledgment tracking.
+pub struct BroadcastStatusBmc;
+
+impl BroadcastStatusBmc {
+    /// Reports which recipients of a message have acknowledged it.
+    ///
+    /// # Errors
+    /// Returns `MessageNotFound` if the message does not exist.
+    pub async fn get(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<BroadcastStatus> {
+        let message = MessageBmc::get(ctx, mm, message_id).await?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT mr.agent_id, a.name, mr.recipient_type, mr.read_ts, mr.ack_ts
+                FROM message_recipients AS mr
+                JOIN agents AS a ON a.id = mr.agent_id
+                WHERE mr.message_id = ?
+                ORDER BY a.name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+
+        let mut acked = Vec::new();
+        let mut pending = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let read_ts = parse_timestamp_opt(row.get(3)?, "read_ts");
+            let ack_ts = parse_timestamp_opt(row.get(4)?, "ack_ts");
+            let recipient = BroadcastRecipientStatus {
+                agent_id: AgentId::new(row.get(0)?),
+                agent_name: row.get(1)?,
+                recipient_type: row.get(2)?,
+                read_ts,
+                ack_ts,
+                seconds_to_ack: ack_ts.map(|ts| (ts - message.created_ts).num_seconds().max(0)),
+            };
+            if recipient.ack_ts.is_some() {
+                acked.push(recipient);
+            } else {
+                pending.push(recipient);
+            }
+        }
+        acked.sort_by_key(|r| r.seconds_to_ack);
+
+        let durations: Vec<i64> = acked.iter().filter_map(|r| r.seconds_to_ack).collect();
+        Ok(BroadcastStatus {
+            message_id,
+            project_id: ProjectId::new(message.project_id),
+            sender_name: message.sender_name,
+            subject: message.subject,
+            thread_id: message.thread_id,
+            ack_required: message.ack_required,
+            created_ts: message.created_ts,
+            total_recipients: acked.len() + pending.len(),
+            time_to_ack: AckTimeStats::from_durations(&durations),
+            acked,
+            pending,
+        })
+    }
+
+    /// Lists a project's ack-required messages with their completion, newest first.
+    pub async fn list_for_project(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        limit: i64,
+    ) -> Result<Vec<BroadcastSummary>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT m.id, a.name, m.subject, m.created_ts, COUNT(mr.agent_id), COUNT(mr.ack_ts)
+                FROM messages AS m
+                JOIN agents AS a ON a.id = m.sender_id
+                JOIN message_recipients AS mr ON mr.message_id = m.id
+                WHERE m.project_id = ? AND m.ack_required = 1
+                GROUP BY m.id, a.name
+                ORDER BY m.created_ts DESC, m.id DESC
+                LIMIT ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), limit)).await?;
+        let mut summaries = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(3)?;
+            summaries.push(BroadcastSummary {
+                message_id: MessageId::new(row.get(0)?),
+                sender_name: row.get(1)?,
+                subject: row.get(2)?,
+                created_ts: parse_timestamp(&created_ts, "created_ts"),
+                total_recipients: row.get(4)?,
+                acked_count: row.get(5)?,
+            });
+        }
+        Ok(summaries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ack_time_stats() {
+        assert_eq!(AckTimeStats::from_durations(&[]), None);
+
+        let stats = AckTimeStats::from_durations(&[30, 10, 20, 60]);
+        assert_eq!(
+            stats,
+            Some(AckTimeStats {
+                min_seconds: 10,
+                median_seconds: 25,
+                mean_seconds: 30.0,
+                max_seconds: 60,
+            })
+        );
+    }
+}
@@ -41,6 +41,9 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::store::db_statement::Row;
+use crate::types::ProjectId;
+use crate::utils::clock;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
@@ -112,7 +115,8 @@ impl BuildSlotBmc {
     /// The build slot ID
     ///
     /// # Errors
-    /// Returns error if slot is already held by another agent
+    /// Returns error if slot is already held by another agent, or if
+    /// `ttl_seconds` is not positive
     ///
     /// # Example
     /// ```no_run
@@ -132,8 +136,8 @@ impl BuildSlotBmc {
     /// ```
     pub async fn acquire(_ctx: &Ctx, mm: &ModelManager, slot_c: BuildSlotForCreate) -> Result<i64> {
         let db = mm.db();
-        let now = chrono::Utc::now().naive_utc();
-        let expires = now + chrono::Duration::seconds(slot_c.ttl_seconds);
+        let now = clock::server_now();
+        let expires = clock::expires_in(slot_c.ttl_seconds)?;
         let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
         let expires_str = expires.format("%Y-%m-%d %H:%M:%S").to_string();
 
@@ -200,7 +204,8 @@ impl BuildSlotBmc {
     /// New expiration timestamp
     ///
     /// # Errors
-    /// Returns error if slot doesn't exist or is already released
+    /// Returns error if slot doesn't exist or is already released, or if
+    /// `ttl_seconds` is not positive
     pub async fn renew(
         _ctx: &Ctx,
         mm: &ModelManager,
@@ -208,7 +213,7 @@ impl BuildSlotBmc {
         ttl_seconds: i64,
     ) -> Result<NaiveDateTime> {
         let db = mm.db();
-        let new_expires = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(ttl_seconds);
+        let new_expires = clock::expires_in(ttl_seconds)?;
         let expires_str = new_expires.format("%Y-%m-%d %H:%M:%S").to_string();
 
         let stmt = db
@@ -259,7 +264,7 @@ impl BuildSlotBmc {
     pub async fn list_active(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<Vec<BuildSlot>> {
         let db = mm.db();
         let now = chrono::Utc::now().naive_utc();
@@ -276,7 +281,7 @@ impl BuildSlotBmc {
             )
             .await?;
 
-        let mut rows = stmt.query((project_id, now_str.as_str())).await?;
+        let mut rows = stmt.query((project_id.get(), now_str.as_str())).await?;
         let mut slots = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -285,7 +290,7 @@ impl BuildSlotBmc {
         Ok(slots)
     }
 
-    fn from_row(row: libsql::Row) -> Result<BuildSlot> {
+    fn from_row(row: Row) -> Result<BuildSlot> {
         let created_ts_str: String = row.get(4).unwrap_or_default();
         let expires_ts_str: String = row.get(5).unwrap_or_default();
         let released_ts_str: Option<String> = row.get(6).unwrap_or_default();
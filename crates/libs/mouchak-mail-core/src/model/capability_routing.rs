@@ -0,0 +1,360 @@
+//! Capability-based message routing.
+//!
+//! Lets senders address a message to `capability:<name>` instead of explicit
+//! agent names. The address is resolved at send time against the capability
+//! registry (`agent_capabilities`), and the resolution is recorded in
+//! `message_routing` so readers can see why a message reached them.
+//!
+//! # Address Syntax
+//!
+//! | Address | Resolves to |
+//! |---------|-------------|
+//! | `capability:db-migrations` | Every agent holding the capability |
+//! | `capability:db-migrations:all` | Same as above |
+//! | `capability:db-migrations:best` | The most recently active capable agent |
+//!
+//! The sender is never resolved as its own recipient.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::parse_timestamp;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Prefix marking a recipient address as a capability lookup.
+pub const CAPABILITY_ADDRESS_PREFIX: &str = "capability:";
+
+/// How many capable agents a capability address resolves to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingMode {
+    /// Deliver to every agent holding the capability
+    All,
+    /// Deliver to the single most recently active agent
+    Best,
+}
+
+impl RoutingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Best => "best",
+        }
+    }
+}
+
+/// A parsed `capability:<name>[:all|:best]` address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityAddress {
+    pub address: String,
+    pub capability: String,
+    pub mode: RoutingMode,
+}
+
+impl CapabilityAddress {
+    /// Returns `true` if the recipient string uses the capability prefix.
+    pub fn is_capability_address(recipient: &str) -> bool {
+        recipient
+            .trim()
+            .get(..CAPABILITY_ADDRESS_PREFIX.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(CAPABILITY_ADDRESS_PREFIX))
+    }
+
+    /// Parses a capability address.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if the string lacks the prefix, names no
+    /// capability, or uses an unknown routing mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mouchak_mail_core::model::capability_routing::{CapabilityAddress, RoutingMode};
+    ///
+    /// let addr = CapabilityAddress::parse("capability:db-migrations:best").unwrap();
+    /// assert_eq!(addr.capability, "db-migrations");
+    /// assert_eq!(addr.mode, RoutingMode::Best);
+    /// assert!(CapabilityAddress::parse("BlueLake").is_err());
+    /// ```
+    pub fn parse(recipient: &str) -> Result<Self> {
+        let trimmed = recipient.trim();
+        if !Self::is_capability_address(trimmed) {
+            return Err(crate::Error::InvalidInput(format!(
+                "'{}' is not a capability address (expected '{}<name>')",
+                trimmed, CAPABILITY_ADDRESS_PREFIX
+            )));
+        }
+
+        let rest = &trimmed[CAPABILITY_ADDRESS_PREFIX.len()..];
+        let (capability, mode) = match rest.rsplit_once(':') {
+            Some((cap, "all")) => (cap, RoutingMode::All),
+            Some((cap, "best")) => (cap, RoutingMode::Best),
+            Some((_, other)) => {
+                return Err(crate::Error::InvalidInput(format!(
+                    "Unknown routing mode '{}' in '{}' (expected 'all' or 'best')",
+                    other, trimmed
+                )));
+            }
+            None => (rest, RoutingMode::All),
+        };
+
+        let capability = capability.trim();
+        if capability.is_empty() {
+            return Err(crate::Error::InvalidInput(format!(
+                "Capability address '{}' does not name a capability",
+                trimmed
+            )));
+        }
+
+        Ok(Self {
+            address: trimmed.to_string(),
+            capability: capability.to_string(),
+            mode,
+        })
+    }
+}
+
+/// The recipients a capability address resolved to at send time.
+#[derive(Debug, Clone, Serialize)]
+pub struct CapabilityResolution {
+    pub address: String,
+    pub capability: String,
+    pub mode: RoutingMode,
+    pub agent_ids: Vec<i64>,
+    pub agent_names: Vec<String>,
+}
+
+/// A stored routing record for a delivered message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageRouting {
+    pub id: i64,
+    pub message_id: i64,
+    pub address: String,
+    pub capability: String,
+    pub mode: String,
+    pub resolved_agent_ids: Vec<i64>,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Backend Model Controller for capability-based routing.
+pub struct CapabilityRoutingBmc;
+
+impl CapabilityRoutingBmc {
+    /// Resolves a capability address to agents in the project.
+    ///
+    /// Candidates are ordered by `last_active_ts` (most recent first), which
+    /// also decides the winner in [`RoutingMode::Best`].
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` listing the capabilities that do exist in the
+    /// project when no agent (other than the sender) holds the capability.
+    pub async fn resolve(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        address: &CapabilityAddress,
+        exclude_agent_id: Option<AgentId>,
+    ) -> Result<CapabilityResolution> {
+        let db = mm.db();
+        let now_str = chrono::Utc::now()
+            .naive_utc()
+            .format(crate::utils::TS_FORMAT)
+            .to_string();
+
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT a.id, a.name
+                FROM agents a
+                JOIN agent_capabilities c ON c.agent_id = a.id
+                WHERE a.project_id = ? AND c.capability = ?
+                  AND (c.expires_at IS NULL OR c.expires_at > ?)
+                ORDER BY a.last_active_ts DESC, a.id ASC
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                project_id.get(),
+                address.capability.as_str(),
+                now_str.as_str(),
+            ))
+            .await?;
+
+        let mut agent_ids = Vec::new();
+        let mut agent_names = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: i64 = row.get(0)?;
+            if Some(id) == exclude_agent_id.map(AgentId::get) {
+                continue;
+            }
+            agent_ids.push(id);
+            agent_names.push(row.get::<String>(1)?);
+        }
+
+        if agent_ids.is_empty() {
+            let known = Self::list_project_capabilities(mm, project_id, &now_str).await?;
+            let hint = if known.is_empty() {
+                "No capabilities are registered in this project".to_string()
+            } else {
+                format!("Capabilities in this project: {}", known.join(", "))
+            };
+            return Err(crate::Error::InvalidInput(format!(
+                "No agent can receive '{}': nobody else holds capability '{}'. {}",
+                address.address, address.capability, hint
+            )));
+        }
+
+        if address.mode == RoutingMode::Best {
+            agent_ids.truncate(1);
+            agent_names.truncate(1);
+        }
+
+        Ok(CapabilityResolution {
+            address: address.address.clone(),
+            capability: address.capability.clone(),
+            mode: address.mode,
+            agent_ids,
+            agent_names,
+        })
+    }
+
+    /// Resolves a mixed list of agent names and capability addresses.
+    ///
+    /// Plain names are looked up with [`AgentBmc::get_by_name`]; capability
+    /// addresses go through [`Self::resolve`]. IDs are de-duplicated while
+    /// preserving order.
+    ///
+    /// # Returns
+    /// The resolved agent IDs and one resolution per capability address.
+    pub async fn resolve_recipients(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        recipients: &[String],
+        exclude_agent_id: Option<AgentId>,
+    ) -> Result<(Vec<AgentId>, Vec<CapabilityResolution>)> {
+        let mut ids = Vec::new();
+        let mut resolutions = Vec::new();
+
+        for recipient in recipients {
+            if CapabilityAddress::is_capability_address(recipient) {
+                let address = CapabilityAddress::parse(recipient)?;
+                let resolution =
+                    Self::resolve(ctx, mm, project_id, &address, exclude_agent_id).await?;
+                for id in resolution.agent_ids.iter().copied().map(AgentId::new) {
+                    if !ids.contains(&id) {
+                        ids.push(id);
+                    }
+                }
+                resolutions.push(resolution);
+            } else {
+                let agent = AgentBmc::get_by_name(ctx, mm, project_id, recipient).await?;
+                if !ids.contains(&agent.id) {
+                    ids.push(agent.id);
+                }
+            }
+        }
+
+        Ok((ids, resolutions))
+    }
+
+    /// Records how capability addresses were resolved for a message.
+    pub async fn record(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        resolutions: &[CapabilityResolution],
+    ) -> Result<()> {
+        if resolutions.is_empty() {
+            return Ok(());
+        }
+
+        let db = mm.db();
+        for resolution in resolutions {
+            let ids_json = serde_json::to_string(&resolution.agent_ids)?;
+            let stmt = db
+                .prepare(
+                    r#"
+                    INSERT INTO message_routing (message_id, address, capability, mode, resolved_agent_ids)
+                    VALUES (?, ?, ?, ?, ?)
+                    "#,
+                )
+                .await?;
+            stmt.execute((
+                message_id.get(),
+                resolution.address.as_str(),
+                resolution.capability.as_str(),
+                resolution.mode.as_str(),
+                ids_json,
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Lists routing records for a message (empty for name-addressed mail).
+    pub async fn list_for_message(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<Vec<MessageRouting>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, message_id, address, capability, mode, resolved_agent_ids, created_ts
+                FROM message_routing
+                WHERE message_id = ?
+                ORDER BY id ASC
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+
+        let mut records = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let ids_json: String = row.get(5)?;
+            let created_ts_str: String = row.get(6)?;
+            records.push(MessageRouting {
+                id: row.get(0)?,
+                message_id: row.get(1)?,
+                address: row.get(2)?,
+                capability: row.get(3)?,
+                mode: row.get(4)?,
+                resolved_agent_ids: serde_json::from_str(&ids_json).unwrap_or_default(),
+                created_ts: parse_timestamp(&created_ts_str, "message_routing.created_ts"),
+            });
+        }
+        Ok(records)
+    }
+
+    async fn list_project_capabilities(
+        mm: &ModelManager,
+        project_id: ProjectId,
+        now_str: &str,
+    ) -> Result<Vec<String>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT DISTINCT c.capability
+                FROM agent_capabilities c
+                JOIN agents a ON a.id = c.agent_id
+                WHERE a.project_id = ? AND (c.expires_at IS NULL OR c.expires_at > ?)
+                ORDER BY c.capability ASC
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), now_str)).await?;
+
+        let mut capabilities = Vec::new();
+        while let Some(row) = rows.next().await? {
+            capabilities.push(row.get::<String>(0)?);
+        }
+        Ok(capabilities)
+    }
+}
@@ -0,0 +1,485 @@
+//! CI build status ingestion.
+//!
+//! CI systems post normalized build events (provider, repo, sha, status,
+//! url). Agents link commits to threads; an event for a linked commit is
+//! posted into each of those threads as a message from the project's `ci`
+//! agent to everyone in the thread. A failing build for a commit no thread
+//! knows about goes to the overseer inbox of the project instead, found
+//! from the event or from the project's GitHub repository mapping.
+//!
+//! Every event is recorded, and an identical redelivery (same provider,
+//! repo, sha, status and url) is recognized and posts nothing.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{AgentBmc, AgentForCreate};
+use crate::model::github_sync::validate_repo;
+use crate::model::message::{MessageBmc, MessageForCreate};
+use crate::model::overseer_message::{OverseerMessageBmc, OverseerMessageForCreate};
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::parse_timestamp;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Agent that build events are sent from.
+pub const CI_AGENT_NAME: &str = "ci";
+
+/// Normalized build status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CiStatus {
+    Pending,
+    Running,
+    Success,
+    Failure,
+    Error,
+    Cancelled,
+}
+
+impl CiStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Success => "success",
+            Self::Failure => "failure",
+            Self::Error => "error",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the build is red.
+    pub fn is_failing(&self) -> bool {
+        matches!(self, Self::Failure | Self::Error)
+    }
+}
+
+impl fmt::Display for CiStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CiStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "success" => Ok(Self::Success),
+            "failure" => Ok(Self::Failure),
+            "error" => Ok(Self::Error),
+            "cancelled" => Ok(Self::Cancelled),
+            other => Err(Error::InvalidInput(format!(
+                "Unknown CI status '{}': expected pending, running, success, failure, error or cancelled",
+                other
+            ))),
+        }
+    }
+}
+
+/// A build event as reported by a CI system.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CiEvent {
+    /// CI system, e.g. `github-actions`, `buildkite`
+    pub provider: String,
+    /// `owner/name`
+    pub repo: String,
+    /// Full commit SHA
+    pub sha: String,
+    pub status: CiStatus,
+    /// Link to the build
+    pub url: Option<String>,
+}
+
+/// A commit linked to a thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitLink {
+    pub id: i64,
+    pub project_id: i64,
+    pub thread_id: String,
+    /// SHA or SHA prefix, lowercase
+    pub sha: String,
+    /// Only events from this repository match; `None` matches any
+    pub repo: Option<String>,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Where an ingested event was posted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CiDelivery {
+    pub event_id: i64,
+    /// The event was delivered before and was skipped
+    pub duplicate: bool,
+    /// Messages posted to linked threads
+    pub thread_messages: Vec<MessageId>,
+    /// Messages posted to overseer inboxes
+    pub overseer_messages: Vec<i64>,
+}
+
+/// Checks a SHA or SHA prefix (7 to 40 hex characters) and lowercases it.
+pub fn normalize_sha(sha: &str) -> Result<String> {
+    let sha = sha.trim().to_ascii_lowercase();
+    if (7..=40).contains(&sha.len()) && sha.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(sha)
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Invalid commit SHA '{}': expected 7 to 40 hex characters",
+            sha
+        )))
+    }
+}
+
+/// Backend Model Controller for CI build events.
+pub struct CiStatusBmc;
+
+impl CiStatusBmc {
+    /// Links a commit to a thread, so build events for it are posted there.
+    ///
+    /// `sha` may be a prefix of at least 7 characters. Linking the same
+    /// commit again updates its repository.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a malformed SHA or repository and
+    /// `NotFound` if the thread has no messages.
+    pub async fn link_commit(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        sha: &str,
+        repo: Option<&str>,
+    ) -> Result<CommitLink> {
+        let sha = normalize_sha(sha)?;
+        let repo = repo.map(str::trim).filter(|r| !r.is_empty());
+        if let Some(repo) = repo {
+            validate_repo(repo)?;
+        }
+        if MessageBmc::list_by_thread(ctx, mm, project_id, thread_id)
+            .await?
+            .is_empty()
+        {
+            return Err(Error::NotFound);
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO commit_links (project_id, thread_id, sha, repo) VALUES (?, ?, ?, ?)
+                ON CONFLICT(project_id, thread_id, sha) DO UPDATE SET repo = excluded.repo
+                RETURNING id, project_id, thread_id, sha, repo, created_ts
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((project_id.get(), thread_id, sha.as_str(), repo))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Self::link_from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Commits linked to a thread.
+    pub async fn list_links(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Vec<CommitLink>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, thread_id, sha, repo, created_ts FROM commit_links
+                WHERE project_id = ? AND thread_id = ?
+                ORDER BY id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        let mut links = Vec::new();
+        while let Some(row) = rows.next().await? {
+            links.push(Self::link_from_row(&row)?);
+        }
+        Ok(links)
+    }
+
+    /// Records a build event and posts it to the threads linked to its
+    /// commit.
+    ///
+    /// Without a linked thread, a failing build is posted to the overseer
+    /// inbox of `project_id`, or of every project mapped to the event's
+    /// repository; other statuses are only recorded. With `project_id`,
+    /// only threads of that project are considered.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a malformed event, or when no thread is
+    /// linked and no project is given or mapped to the repository.
+    pub async fn ingest(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        event: &CiEvent,
+        project_id: Option<ProjectId>,
+    ) -> Result<CiDelivery> {
+        let provider = event.provider.trim();
+        if provider.is_empty() || provider.len() > 64 {
+            return Err(Error::InvalidInput(
+                "CI provider must be 1 to 64 characters".to_string(),
+            ));
+        }
+        let repo = event.repo.trim();
+        validate_repo(repo)?;
+        let sha = normalize_sha(&event.sha)?;
+        let url = event.url.as_deref().map(str::trim).unwrap_or_default();
+        if !url.is_empty() && !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(Error::InvalidInput(format!(
+                "Invalid build URL '{}': expected http(s)",
+                url
+            )));
+        }
+
+        let threads = Self::linked_threads(mm, repo, &sha, project_id).await?;
+        let projects = if threads.is_empty() {
+            let projects = match project_id {
+                Some(project_id) => vec![project_id.get()],
+                None => Self::projects_for_repo(mm, repo).await?,
+            };
+            if projects.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "Commit {} is not linked to a thread and {} is not mapped to a project",
+                    sha, repo
+                )));
+            }
+            projects
+        } else {
+            Vec::new()
+        };
+
+        let db = mm.db();
+        // Scoped so the insert is finished before any message is sent
+        let inserted: Option<i64> = {
+            let stmt = db
+                .prepare(
+                    r#"
+                    INSERT INTO ci_events (provider, repo, sha, status, url) VALUES (?, ?, ?, ?, ?)
+                    ON CONFLICT(provider, repo, sha, status, url) DO NOTHING
+                    RETURNING id
+                    "#,
+                )
+                .await?;
+            let mut rows = stmt
+                .query((provider, repo, sha.as_str(), event.status.as_str(), url))
+                .await?;
+            match rows.next().await? {
+                Some(row) => Some(row.get(0)?),
+                None => None,
+            }
+        };
+        let event_id: i64 = match inserted {
+            Some(id) => id,
+            None => {
+                let stmt = db
+                    .prepare(
+                        r#"
+                        SELECT id FROM ci_events
+                        WHERE provider = ? AND repo = ? AND sha = ? AND status = ? AND url = ?
+                        "#,
+                    )
+                    .await?;
+                let mut rows = stmt
+                    .query((provider, repo, sha.as_str(), event.status.as_str(), url))
+                    .await?;
+                let row = rows.next().await?.ok_or(Error::NotFound)?;
+                return Ok(CiDelivery {
+                    event_id: row.get(0)?,
+                    duplicate: true,
+                    ..Default::default()
+                });
+            }
+        };
+
+        let short = &sha[..7];
+        let subject = format!("[CI] {} {}: {}@{}", provider, event.status, repo, short);
+        let mut body = format!(
+            "Build **{}** for `{}` at `{}` ({}).",
+            event.status, repo, sha, provider
+        );
+        if !url.is_empty() {
+            body.push_str(&format!("\n\n[Build details]({})", url));
+        }
+
+        let importance = if event.status.is_failing() {
+            "high"
+        } else {
+            "normal"
+        };
+        let mut delivery = CiDelivery {
+            event_id,
+            ..Default::default()
+        };
+        for (thread_project_id, thread_id) in &threads {
+            let sender_id = Self::ci_agent(ctx, mm, ProjectId::new(*thread_project_id)).await?;
+            let mut recipient_ids = MessageBmc::thread_participants(
+                ctx,
+                mm,
+                ProjectId::new(*thread_project_id),
+                thread_id,
+            )
+            .await?;
+            recipient_ids.retain(|id| *id != sender_id);
+            if recipient_ids.is_empty() {
+                continue;
+            }
+            let msg_c = MessageForCreate {
+                project_id: ProjectId::new(*thread_project_id),
+                sender_id,
+                recipient_ids,
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.clone(),
+                body_md: body.clone(),
+                thread_id: Some(thread_id.clone()),
+                importance: Some(importance.to_string()),
+                ack_required: false,
+            };
+            delivery
+                .thread_messages
+                .push(MessageBmc::create(ctx, mm, msg_c).await?);
+        }
+        if event.status.is_failing() {
+            for project_id in projects {
+                let sender_id = Self::ci_agent(ctx, mm, ProjectId::new(project_id)).await?;
+                let id = OverseerMessageBmc::create(
+                    ctx,
+                    mm,
+                    OverseerMessageForCreate {
+                        project_id,
+                        sender_id: sender_id.get(),
+                        subject: subject.clone(),
+                        body_md: format!(
+                            "{}\n\nNo thread is linked to this commit; link it with `link_commit` to route its builds there.",
+                            body
+                        ),
+                        importance: "high".to_string(),
+                    },
+                )
+                .await?;
+                delivery.overseer_messages.push(id);
+            }
+        }
+
+        let stmt = db
+            .prepare("UPDATE ci_events SET delivered = ? WHERE id = ?")
+            .await?;
+        let delivered = delivery.thread_messages.len() + delivery.overseer_messages.len();
+        stmt.execute((delivered as i64, event_id)).await?;
+        Ok(delivery)
+    }
+
+    /// The project's `ci` agent, registered on first use.
+    pub async fn ci_agent(ctx: &Ctx, mm: &ModelManager, project_id: ProjectId) -> Result<AgentId> {
+        match AgentBmc::get_by_name(ctx, mm, project_id, CI_AGENT_NAME).await {
+            Ok(agent) => Ok(agent.id),
+            Err(Error::AgentNotFound { .. }) | Err(Error::NotFound) => {
+                let id = AgentBmc::create(
+                    ctx,
+                    mm,
+                    AgentForCreate {
+                        project_id,
+                        name: CI_AGENT_NAME.to_string(),
+                        program: "ci".to_string(),
+                        model: "none".to_string(),
+                        task_description: "Relays build status from CI".to_string(),
+                    },
+                )
+                .await?;
+                Ok(id)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Distinct `(project_id, thread_id)` pairs with a link matching the commit.
+    async fn linked_threads(
+        mm: &ModelManager,
+        repo: &str,
+        sha: &str,
+        project_id: Option<ProjectId>,
+    ) -> Result<Vec<(i64, String)>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT DISTINCT project_id, thread_id FROM commit_links
+                WHERE substr(?1, 1, length(sha)) = sha
+                  AND (repo IS NULL OR repo = ?2)
+                  AND (?3 IS NULL OR project_id = ?3)
+                ORDER BY project_id, thread_id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((sha, repo, project_id.map(|p| p.get()))).await?;
+        let mut threads = Vec::new();
+        while let Some(row) = rows.next().await? {
+            threads.push((row.get(0)?, row.get(1)?));
+        }
+        Ok(threads)
+    }
+
+    async fn projects_for_repo(mm: &ModelManager, repo: &str) -> Result<Vec<i64>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "SELECT project_id FROM project_github_repos WHERE repo = ? ORDER BY project_id",
+            )
+            .await?;
+        let mut rows = stmt.query([repo]).await?;
+        let mut projects = Vec::new();
+        while let Some(row) = rows.next().await? {
+            projects.push(row.get(0)?);
+        }
+        Ok(projects)
+    }
+
+    fn link_from_row(row: &Row) -> Result<CommitLink> {
+        let created_ts: String = row.get(5)?;
+        Ok(CommitLink {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            thread_id: row.get(2)?,
+            sha: row.get(3)?,
+            repo: row.get(4)?,
+            created_ts: parse_timestamp(&created_ts, "created_ts"),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_sha() {
+        assert_eq!(normalize_sha("ABCDEF1").unwrap(), "abcdef1");
+        assert!(normalize_sha(&"a".repeat(40)).is_ok());
+        assert!(normalize_sha("abcdef").is_err());
+        assert!(normalize_sha(&"a".repeat(41)).is_err());
+        assert!(normalize_sha("abcdefg").is_err());
+    }
+
+    #[test]
+    fn test_status_parse() {
+        assert_eq!("FAILURE".parse::<CiStatus>().unwrap(), CiStatus::Failure);
+        assert!(CiStatus::Error.is_failing());
+        assert!(!CiStatus::Cancelled.is_failing());
+        assert!("green".parse::<CiStatus>().is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Context packs for agent bootstrapping.
+//!
+//! A context pack is a size-bounded briefing that lets a fresh agent instance
+//! catch up in one call: unread high/urgent messages, messages awaiting its
+//! acknowledgement, active file reservations and the most recent threads.
+//!
+//! The pack is rendered as Markdown and trimmed to a token budget (estimated
+//! at [`BYTES_PER_TOKEN`] bytes per token). When over budget, items are
+//! dropped from the lowest-priority section first: threads, then
+//! reservations, then pending acknowledgements, then unread messages.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::file_reservation::FileReservationBmc;
+use crate::model::message::{MessageBmc, context_excerpt};
+use crate::model::project::ProjectBmc;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::parse_timestamp;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Token budget used when none is given.
+pub const DEFAULT_BUDGET_TOKENS: usize = 2000;
+
+/// Smallest accepted token budget.
+pub const MIN_BUDGET_TOKENS: usize = 200;
+
+/// Largest accepted token budget.
+pub const MAX_BUDGET_TOKENS: usize = 16_000;
+
+/// Rough bytes-per-token ratio used to estimate pack size.
+pub const BYTES_PER_TOKEN: usize = 4;
+
+/// Maximum items fetched per section before trimming.
+const SECTION_LIMIT: i64 = 20;
+
+/// Number of recent threads summarized.
+const THREAD_LIMIT: i64 = 5;
+
+/// A message entry in a context pack.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackMessage {
+    pub id: i64,
+    pub sender_name: String,
+    pub subject: String,
+    pub importance: String,
+    pub thread_id: Option<String>,
+    pub created_ts: NaiveDateTime,
+    pub excerpt: String,
+}
+
+/// An active file reservation in a context pack.
+///
+/// `mine` is `true` if the reservation is held by the pack's agent.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackReservation {
+    pub id: i64,
+    pub path_pattern: String,
+    pub agent_name: String,
+    pub exclusive: bool,
+    pub expires_ts: NaiveDateTime,
+    pub mine: bool,
+}
+
+/// Summary of a recent thread in a context pack.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackThread {
+    pub thread_id: String,
+    pub subject: String,
+    pub message_count: usize,
+    pub last_message_ts: NaiveDateTime,
+    pub last_sender: String,
+    pub last_excerpt: String,
+}
+
+/// A size-bounded briefing for an agent.
+///
+/// # Fields
+///
+/// - `urgent_unread` - Unread high/urgent messages, most urgent first
+/// - `pending_acks` - Messages awaiting this agent's acknowledgement, oldest first
+/// - `reservations` - Active file reservations in the project
+/// - `threads` - Most recently active threads
+/// - `omitted` - Items dropped to fit the budget
+/// - `estimated_tokens` - Estimated size of the rendered pack
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextPack {
+    pub project_slug: String,
+    pub agent_name: String,
+    pub generated_ts: NaiveDateTime,
+    pub budget_tokens: usize,
+    pub estimated_tokens: usize,
+    pub omitted: usize,
+    pub urgent_unread: Vec<PackMessage>,
+    pub pending_acks: Vec<PackMessage>,
+    pub reservations: Vec<PackReservation>,
+    pub threads: Vec<PackThread>,
+}
+
+impl ContextPack {
+    /// Renders the pack as Markdown.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# Context pack: {} @ {}\n_Generated {} (~{} of {} tokens)_",
+            self.agent_name,
+            self.project_slug,
+            self.generated_ts,
+            self.estimated_tokens,
+            self.budget_tokens
+        );
+
+        let _ = writeln!(out, "\n## Unread urgent ({})", self.urgent_unread.len());
+        for m in &self.urgent_unread {
+            let _ = writeln!(
+                out,
+                "- [{}] {} — {} ({}, {}): {}",
+                m.id, m.subject, m.sender_name, m.importance, m.created_ts, m.excerpt
+            );
+        }
+
+        let _ = writeln!(out, "\n## Awaiting your ack ({})", self.pending_acks.len());
+        for m in &self.pending_acks {
+            let _ = writeln!(
+                out,
+                "- [{}] {} — {} ({}): {}",
+                m.id, m.subject, m.sender_name, m.created_ts, m.excerpt
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "\n## Active reservations ({})",
+            self.reservations.len()
+        );
+        for r in &self.reservations {
+            let _ = writeln!(
+                out,
+                "- {} by {}{} ({}, expires {})",
+                r.path_pattern,
+                r.agent_name,
+                if r.mine { " (you)" } else { "" },
+                if r.exclusive { "exclusive" } else { "shared" },
+                r.expires_ts
+            );
+        }
+
+        let _ = writeln!(out, "\n## Recent threads ({})", self.threads.len());
+        for t in &self.threads {
+            let _ = writeln!(
+                out,
+                "- {} | {} ({} msgs, last {} by {}): {}",
+                t.thread_id,
+                t.subject,
+                t.message_count,
+                t.last_message_ts,
+                t.last_sender,
+                t.last_excerpt
+            );
+        }
+
+        if self.omitted > 0 {
+            let _ = writeln!(
+                out,
+                "\n_{} item(s) omitted to fit the budget; use list_inbox, list_reservations or list_threads for more._",
+                self.omitted
+            );
+        }
+        out
+    }
+
+    fn estimate_tokens(&self) -> usize {
+        self.render().len().div_ceil(BYTES_PER_TOKEN)
+    }
+
+    /// Drops the last item of the lowest-priority non-empty section.
+    fn drop_one(&mut self) -> bool {
+        let dropped = self.threads.pop().is_some()
+            || self.reservations.pop().is_some()
+            || self.pending_acks.pop().is_some()
+            || self.urgent_unread.pop().is_some();
+        if dropped {
+            self.omitted += 1;
+        }
+        dropped
+    }
+}
+
+/// Backend Model Controller for context packs.
+pub struct ContextPackBmc;
+
+impl ContextPackBmc {
+    /// Builds a context pack for an agent, trimmed to `budget_tokens`.
+    ///
+    /// The budget is clamped to [`MIN_BUDGET_TOKENS`]..=[`MAX_BUDGET_TOKENS`].
+    pub async fn build(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        budget_tokens: usize,
+    ) -> Result<ContextPack> {
+        let budget_tokens = budget_tokens.clamp(MIN_BUDGET_TOKENS, MAX_BUDGET_TOKENS);
+        let project = ProjectBmc::get(ctx, mm, project_id).await?;
+        let agent = AgentBmc::get(ctx, mm, agent_id).await?;
+
+        let urgent_unread = Self::list_messages(
+            mm,
+            project_id,
+            agent_id,
+            "mr.read_ts IS NULL AND m.importance IN ('high', 'urgent')",
+            "CASE m.importance WHEN 'urgent' THEN 0 ELSE 1 END, m.created_ts DESC",
+        )
+        .await?;
+        let pending_acks = Self::list_messages(
+            mm,
+            project_id,
+            agent_id,
+            "m.ack_required = 1 AND mr.ack_ts IS NULL",
+            "m.created_ts ASC",
+        )
+        .await?;
+
+        let agent_names: HashMap<AgentId, String> =
+            AgentBmc::list_all_for_project(ctx, mm, project_id)
+                .await?
+                .into_iter()
+                .map(|a| (a.id, a.name))
+                .collect();
+        let now = chrono::Utc::now().naive_utc();
+        let reservations = FileReservationBmc::list_active_for_project(ctx, mm, project_id)
+            .await?
+            .into_iter()
+            .filter(|r| r.expires_ts > now)
+            .take(SECTION_LIMIT as usize)
+            .map(|r| PackReservation {
+                id: r.id,
+                path_pattern: r.path_pattern,
+                agent_name: agent_names
+                    .get(&r.agent_id)
+                    .cloned()
+                    .unwrap_or_else(|| r.agent_id.to_string()),
+                exclusive: r.exclusive,
+                expires_ts: r.expires_ts,
+                mine: r.agent_id == agent_id,
+            })
+            .collect();
+
+        let mut threads = Vec::new();
+        for t in MessageBmc::list_threads(ctx, mm, project_id, THREAD_LIMIT, false).await? {
+            let messages = MessageBmc::list_by_thread(ctx, mm, project_id, &t.thread_id).await?;
+            let (last_sender, last_excerpt) = messages
+                .last()
+                .map(|m| (m.sender_name.clone(), context_excerpt(&m.body_md)))
+                .unwrap_or_default();
+            threads.push(PackThread {
+                thread_id: t.thread_id,
+                subject: t.subject,
+                message_count: t.message_count,
+                last_message_ts: t.last_message_ts,
+                last_sender,
+                last_excerpt,
+            });
+        }
+
+        let mut pack = ContextPack {
+            project_slug: project.slug,
+            agent_name: agent.name,
+            generated_ts: now,
+            budget_tokens,
+            estimated_tokens: 0,
+            omitted: 0,
+            urgent_unread,
+            pending_acks,
+            reservations,
+            threads,
+        };
+
+        pack.estimated_tokens = pack.estimate_tokens();
+        while pack.estimated_tokens > budget_tokens && pack.drop_one() {
+            pack.estimated_tokens = pack.estimate_tokens();
+        }
+        Ok(pack)
+    }
+
+    async fn list_messages(
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        filter: &str,
+        order_by: &str,
+    ) -> Result<Vec<PackMessage>> {
+        let db = mm.db();
+        let sql = format!(
+            r#"
+            SELECT m.id, ag.name, m.thread_id, m.subject, m.body_md, m.importance, m.created_ts
+            FROM messages AS m
+            JOIN message_recipients AS mr ON m.id = mr.message_id
+            JOIN agents AS ag ON m.sender_id = ag.id
+            WHERE mr.agent_id = ? AND m.project_id = ? AND {}
+            ORDER BY {}
+            LIMIT ?
+            "#,
+            filter, order_by
+        );
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = stmt
+            .query((agent_id.get(), project_id.get(), SECTION_LIMIT))
+            .await?;
+
+        let mut messages = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let body_md: String = row.get(4)?;
+            let created_ts: String = row.get(6)?;
+            messages.push(PackMessage {
+                id: row.get(0)?,
+                sender_name: row.get(1)?,
+                thread_id: row.get(2)?,
+                subject: row.get(3)?,
+                importance: row.get(5)?,
+                created_ts: parse_timestamp(&created_ts, "messages.created_ts"),
+                excerpt: context_excerpt(&body_md),
+            });
+        }
+        Ok(messages)
+    }
+}
@@ -0,0 +1,422 @@
+//! Per-project custom message fields.
+//!
+//! A project declares typed fields (e.g. `component`, `severity`) that
+//! senders set on messages and searches filter on. Values are stored as a
+//! JSON object per message (see
+//! [`MessageBmc::set_custom_fields`](crate::model::message::MessageBmc::set_custom_fields))
+//! and checked against the project's declarations:
+//!
+//! - Only declared fields may be set.
+//! - Values must match the declared type. Strings are accepted for numbers
+//!   and booleans when they parse (`"3"`, `"true"`), since values often
+//!   arrive from query strings.
+//! - Required fields must be present on new messages.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::types::ProjectId;
+use crate::utils::parse_timestamp;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+/// Maximum length of a field name.
+pub const MAX_FIELD_NAME_LEN: usize = 64;
+
+/// Type of a custom field's values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFieldType {
+    String,
+    Number,
+    Boolean,
+}
+
+impl CustomFieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+        }
+    }
+
+    /// Checks a value against the type, converting parseable strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mouchak_mail_core::model::custom_field::CustomFieldType;
+    /// use serde_json::json;
+    ///
+    /// assert_eq!(CustomFieldType::Number.coerce(&json!("3")), Some(json!(3)));
+    /// assert_eq!(CustomFieldType::Boolean.coerce(&json!("true")), Some(json!(true)));
+    /// assert_eq!(CustomFieldType::String.coerce(&json!(3)), None);
+    /// ```
+    pub fn coerce(&self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (Self::String, Value::String(_))
+            | (Self::Number, Value::Number(_))
+            | (Self::Boolean, Value::Bool(_)) => Some(value.clone()),
+            (Self::Number, Value::String(s)) => {
+                let s = s.trim();
+                if let Ok(i) = s.parse::<i64>() {
+                    Some(Value::from(i))
+                } else {
+                    s.parse::<f64>()
+                        .ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .map(Value::Number)
+                }
+            }
+            (Self::Boolean, Value::String(s)) => s.trim().parse::<bool>().ok().map(Value::Bool),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for CustomFieldType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "string" => Ok(Self::String),
+            "number" => Ok(Self::Number),
+            "boolean" | "bool" => Ok(Self::Boolean),
+            other => Err(Error::InvalidInput(format!(
+                "Invalid field type '{}'. Valid: string, number, boolean",
+                other
+            ))),
+        }
+    }
+}
+
+/// A field declared by a project.
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomField {
+    pub id: i64,
+    pub project_id: ProjectId,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub required: bool,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Input to declare a field, or change the type or requiredness of one.
+#[derive(Debug, Clone)]
+pub struct CustomFieldForCreate {
+    pub project_id: ProjectId,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub required: bool,
+}
+
+/// Search filter matching messages whose field equals a value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomFieldFilter {
+    pub name: String,
+    pub value: Value,
+}
+
+impl CustomFieldFilter {
+    /// JSON path of the field in a message's stored fields.
+    pub(crate) fn json_path(&self) -> String {
+        format!("$.{}", self.name)
+    }
+
+    /// The value as SQLite sees it through `json_extract`.
+    pub(crate) fn sql_value(&self) -> libsql::Value {
+        match &self.value {
+            Value::Bool(b) => libsql::Value::Integer(i64::from(*b)),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => libsql::Value::Integer(i),
+                None => libsql::Value::Real(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => libsql::Value::Text(s.clone()),
+            other => libsql::Value::Text(other.to_string()),
+        }
+    }
+}
+
+/// Checks a field name is usable as a JSON path segment.
+fn validate_field_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_FIELD_NAME_LEN
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Invalid field name '{}': use lowercase letters, digits and underscores, starting with a letter (max {} chars)",
+            name, MAX_FIELD_NAME_LEN
+        )))
+    }
+}
+
+/// Backend Model Controller for custom field declarations.
+pub struct CustomFieldBmc;
+
+impl CustomFieldBmc {
+    /// Declares a field, or updates the type and requiredness of an existing
+    /// one. Values already stored on messages are not revalidated.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an invalid name.
+    pub async fn define(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        field_c: CustomFieldForCreate,
+    ) -> Result<i64> {
+        validate_field_name(&field_c.name)?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO project_custom_fields (project_id, name, field_type, required)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT(project_id, name) DO UPDATE SET
+                    field_type = excluded.field_type,
+                    required = excluded.required
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                field_c.project_id.get(),
+                field_c.name.as_str(),
+                field_c.field_type.as_str(),
+                field_c.required,
+            ))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::InvalidInput("Failed to save custom field".into())),
+        }
+    }
+
+    /// Lists a project's fields by name.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<CustomField>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, name, field_type, required, created_ts
+                FROM project_custom_fields
+                WHERE project_id = ?
+                ORDER BY name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut fields = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let field_type: String = row.get(3)?;
+            let created_ts: String = row.get(5)?;
+            fields.push(CustomField {
+                id: row.get(0)?,
+                project_id: ProjectId::new(row.get(1)?),
+                name: row.get(2)?,
+                field_type: field_type.parse()?,
+                required: row.get(4)?,
+                created_ts: parse_timestamp(&created_ts, "created_ts"),
+            });
+        }
+        Ok(fields)
+    }
+
+    /// Removes a field declaration. Values already stored on messages stay,
+    /// but can no longer be set or searched.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no such field.
+    pub async fn delete(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        name: &str,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM project_custom_fields WHERE project_id = ? AND name = ?")
+            .await?;
+        let deleted = stmt.execute((project_id.get(), name)).await?;
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Checks values against the project's declarations and returns them
+    /// with parseable strings converted to the declared types.
+    ///
+    /// With `require_all`, fields declared as required must be present.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` naming undeclared fields, mistyped values or
+    /// missing required fields.
+    pub async fn validate(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        values: &Map<String, Value>,
+        require_all: bool,
+    ) -> Result<Map<String, Value>> {
+        let fields = Self::list(ctx, mm, project_id).await?;
+        let mut validated = Map::new();
+        for (name, value) in values {
+            let field = Self::find(&fields, name)?;
+            if value.is_null() {
+                continue;
+            }
+            let coerced = field.field_type.coerce(value).ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Field '{}' must be a {}, got {}",
+                    name,
+                    field.field_type.as_str(),
+                    value
+                ))
+            })?;
+            validated.insert(name.clone(), coerced);
+        }
+
+        if require_all {
+            let missing: Vec<&str> = fields
+                .iter()
+                .filter(|f| f.required && !validated.contains_key(&f.name))
+                .map(|f| f.name.as_str())
+                .collect();
+            if !missing.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "Missing required custom fields: {}",
+                    missing.join(", ")
+                )));
+            }
+        }
+        Ok(validated)
+    }
+
+    /// Turns `name -> value` pairs into search filters, checking each field
+    /// is declared and converting values to its type.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for undeclared fields or mistyped values.
+    pub async fn filters(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        values: &Map<String, Value>,
+    ) -> Result<Vec<CustomFieldFilter>> {
+        if values.is_empty() {
+            return Ok(Vec::new());
+        }
+        let validated = Self::validate(ctx, mm, project_id, values, false).await?;
+        Ok(validated
+            .into_iter()
+            .map(|(name, value)| CustomFieldFilter { name, value })
+            .collect())
+    }
+
+    fn find<'a>(fields: &'a [CustomField], name: &str) -> Result<&'a CustomField> {
+        fields.iter().find(|f| f.name == name).ok_or_else(|| {
+            let known: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+            Error::InvalidInput(format!(
+                "Unknown custom field '{}'. Fields in this project: {}",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ))
+        })
+    }
+}
+
+/// Parses `name:value` pairs separated by commas, as used in query strings.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::model::custom_field::parse_field_pairs;
+///
+/// let pairs = parse_field_pairs("component:db, severity:3").unwrap();
+/// assert_eq!(pairs["component"], "db");
+/// assert_eq!(pairs["severity"], "3");
+/// assert!(parse_field_pairs("component").is_err());
+/// ```
+pub fn parse_field_pairs(s: &str) -> Result<Map<String, Value>> {
+    let mut pairs = Map::new();
+    for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (name, value) = pair.split_once(':').ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Invalid field filter '{}' (expected name:value)",
+                pair
+            ))
+        })?;
+        pairs.insert(
+            name.trim().to_string(),
+            Value::String(value.trim().to_string()),
+        );
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_field_names() {
+        assert!(validate_field_name("component").is_ok());
+        assert!(validate_field_name("sev_2").is_ok());
+        assert!(validate_field_name("").is_err());
+        assert!(validate_field_name("2fast").is_err());
+        assert!(validate_field_name("Component").is_err());
+        assert!(validate_field_name("a.b").is_err());
+        assert!(validate_field_name(&"a".repeat(MAX_FIELD_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_coerce_and_sql_values() {
+        assert_eq!(
+            CustomFieldType::Number.coerce(&json!(" 2.5 ")),
+            Some(json!(2.5))
+        );
+        assert_eq!(CustomFieldType::Number.coerce(&json!("high")), None);
+        assert_eq!(CustomFieldType::Boolean.coerce(&json!(1)), None);
+        assert_eq!(
+            CustomFieldType::String.coerce(&json!("db")),
+            Some(json!("db"))
+        );
+
+        let filter = |value| CustomFieldFilter {
+            name: "x".to_string(),
+            value,
+        };
+        assert!(matches!(
+            filter(json!(true)).sql_value(),
+            libsql::Value::Integer(1)
+        ));
+        assert!(matches!(
+            filter(json!(3)).sql_value(),
+            libsql::Value::Integer(3)
+        ));
+        assert!(matches!(
+            filter(json!(2.5)).sql_value(),
+            libsql::Value::Real(r) if r == 2.5
+        ));
+        assert_eq!(filter(json!("db")).json_path(), "$.x");
+    }
+}
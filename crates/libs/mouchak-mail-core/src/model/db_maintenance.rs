@@ -0,0 +1,193 @@
+//! SQLite maintenance: integrity check, ANALYZE and VACUUM.
+//!
+//! Deleted rows leave free pages behind, so a long-lived database grows
+//! and its query plans go stale. [`DbMaintenanceBmc::maintain`] checks the
+//! database, refreshes the planner statistics and, once the share of free
+//! pages reaches a threshold, rebuilds the file with `VACUUM`. It runs from
+//! the `db maintain` CLI command and, when `DB_MAINTENANCE_INTERVAL_SECONDS`
+//! is set, from a background job of the server.
+//!
+//! A failed integrity check stops the run before anything is rewritten.
+//! `VACUUM` needs free disk space of up to the database size and blocks
+//! writers while it runs.
+//!
+//! On Postgres the size is that of the database, autovacuum keeps free
+//! space in check so none is reported, and there is no integrity check to
+//! run; `ANALYZE` and a forced `VACUUM` run as they do on SQLite.
+
+use crate::model::ModelManager;
+use crate::store::Backend;
+use crate::{Ctx, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Page counts of the database file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DbStats {
+    pub page_size: i64,
+    pub page_count: i64,
+    /// Pages on the freelist, reusable but still taking up disk space
+    pub free_pages: i64,
+}
+
+impl DbStats {
+    pub fn size_bytes(&self) -> i64 {
+        self.page_size * self.page_count
+    }
+
+    pub fn free_bytes(&self) -> i64 {
+        self.page_size * self.free_pages
+    }
+
+    /// Share of the file that is free pages (0.0-1.0).
+    pub fn free_ratio(&self) -> f64 {
+        if self.page_count == 0 {
+            0.0
+        } else {
+            self.free_pages as f64 / self.page_count as f64
+        }
+    }
+}
+
+/// How to run maintenance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintainOptions {
+    /// Free page ratio at which the database is vacuumed
+    pub vacuum_free_ratio: f64,
+    /// Vacuum regardless of the free page ratio
+    pub force_vacuum: bool,
+    /// Only check integrity and report sizes
+    pub dry_run: bool,
+}
+
+impl Default for MaintainOptions {
+    fn default() -> Self {
+        Self {
+            vacuum_free_ratio: 0.2,
+            force_vacuum: false,
+            dry_run: false,
+        }
+    }
+}
+
+/// Outcome of a maintenance run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintainReport {
+    pub before: DbStats,
+    /// Equal to `before` when nothing was rewritten
+    pub after: DbStats,
+    /// `["ok"]`, or the problems `PRAGMA integrity_check` found
+    pub integrity: Vec<String>,
+    pub analyzed: bool,
+    pub vacuumed: bool,
+    pub reclaimed_bytes: i64,
+    pub duration_ms: u64,
+}
+
+impl MaintainReport {
+    pub fn integrity_ok(&self) -> bool {
+        self.integrity == ["ok"]
+    }
+}
+
+/// Backend Model Controller for database maintenance.
+pub struct DbMaintenanceBmc;
+
+impl DbMaintenanceBmc {
+    /// Page size, page count and free pages of the database.
+    pub async fn stats(_ctx: &Ctx, mm: &ModelManager) -> Result<DbStats> {
+        if mm.db_backend() == Backend::Postgres {
+            let page_size =
+                pragma_i64(mm, "SELECT CAST(current_setting('block_size') AS BIGINT)").await?;
+            let size = pragma_i64(mm, "SELECT pg_database_size(current_database())").await?;
+            return Ok(DbStats {
+                page_size,
+                page_count: size / page_size.max(1),
+                free_pages: 0,
+            });
+        }
+        Ok(DbStats {
+            page_size: pragma_i64(mm, "PRAGMA page_size").await?,
+            page_count: pragma_i64(mm, "PRAGMA page_count").await?,
+            free_pages: pragma_i64(mm, "PRAGMA freelist_count").await?,
+        })
+    }
+
+    /// Problems found by `PRAGMA integrity_check`, or `["ok"]`.
+    pub async fn integrity_check(_ctx: &Ctx, mm: &ModelManager) -> Result<Vec<String>> {
+        if mm.db_backend() == Backend::Postgres {
+            return Ok(vec!["ok".to_string()]);
+        }
+        let stmt = mm.db().prepare("PRAGMA integrity_check").await?;
+        let mut rows = stmt.query(()).await?;
+        let mut problems = Vec::new();
+        while let Some(row) = rows.next().await? {
+            problems.push(row.get::<String>(0)?);
+        }
+        Ok(problems)
+    }
+
+    /// Checks integrity, runs `ANALYZE` and vacuums when the free page ratio
+    /// reaches `vacuum_free_ratio`.
+    ///
+    /// A failed integrity check is reported, not returned as an error, and
+    /// leaves the database untouched.
+    pub async fn maintain(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        options: &MaintainOptions,
+    ) -> Result<MaintainReport> {
+        let started = Instant::now();
+        let before = Self::stats(ctx, mm).await?;
+        let mut report = MaintainReport {
+            before,
+            after: before,
+            integrity: Self::integrity_check(ctx, mm).await?,
+            analyzed: false,
+            vacuumed: false,
+            reclaimed_bytes: 0,
+            duration_ms: 0,
+        };
+
+        if !report.integrity_ok() {
+            warn!(
+                "Database integrity check failed, skipping maintenance: {}",
+                report.integrity.join("; ")
+            );
+        } else if !options.dry_run {
+            let db = mm.db();
+            db.execute("ANALYZE", ()).await?;
+            report.analyzed = true;
+
+            if options.force_vacuum || before.free_ratio() >= options.vacuum_free_ratio {
+                db.execute("VACUUM", ()).await?;
+                if db.backend() == Backend::Sqlite {
+                    // Fold the rewritten pages back so the WAL file shrinks too
+                    let stmt = db.prepare("PRAGMA wal_checkpoint(TRUNCATE)").await?;
+                    stmt.query(()).await?;
+                }
+                report.vacuumed = true;
+            }
+
+            report.after = Self::stats(ctx, mm).await?;
+            report.reclaimed_bytes = (before.size_bytes() - report.after.size_bytes()).max(0);
+            info!(
+                vacuumed = report.vacuumed,
+                reclaimed_bytes = report.reclaimed_bytes,
+                "Database maintenance finished"
+            );
+        }
+        report.duration_ms = started.elapsed().as_millis() as u64;
+        Ok(report)
+    }
+}
+
+async fn pragma_i64(mm: &ModelManager, sql: &str) -> Result<i64> {
+    let stmt = mm.db().prepare(sql).await?;
+    let mut rows = stmt.query(()).await?;
+    match rows.next().await? {
+        Some(row) => Ok(row.get(0)?),
+        None => Ok(0),
+    }
+}
@@ -0,0 +1,511 @@
+//! Dead-letter store for sends that could not be delivered.
+//!
+//! A send fails outright when its project, sender or one of its recipients
+//! does not resolve: an agent was deleted, or a project was adopted into
+//! another and its agents moved with it. Rather than lose the message, the
+//! send paths keep its envelope and body in `dead_letters` with the error
+//! (see [`DeadLetterBmc::is_undeliverable`]) and tell the caller its id.
+//!
+//! Operators inspect the queue with [`DeadLetterBmc::list`] and reprocess an
+//! entry with [`DeadLetterBmc::requeue`], optionally pointing it at another
+//! project, sender or recipients. A requeue that fails again keeps the entry
+//! pending with the new error.
+//!
+//! Only the envelope and body are kept: custom fields, body format and reply
+//! deadlines of the original send are not replayed.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::capability_routing::{CapabilityResolution, CapabilityRoutingBmc};
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::model::message::{MessageBmc, MessageForCreate};
+use crate::model::project::ProjectBmc;
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::parse_timestamp;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Status of an entry still waiting to be delivered.
+pub const STATUS_PENDING: &str = "pending";
+
+/// Status of an entry delivered by a requeue.
+pub const STATUS_REQUEUED: &str = "requeued";
+
+/// Default number of entries listed.
+pub const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// A send that could not be delivered.
+///
+/// # Fields
+///
+/// - `to`, `cc`, `bcc` - Recipients as addressed: agent names, `broadcast`
+///   or `capability:<name>` addresses
+/// - `error` - Why the last delivery attempt failed
+/// - `attempts` - The original send plus every failed requeue
+/// - `status` - [`STATUS_PENDING`] or [`STATUS_REQUEUED`]
+/// - `message_id` - Message created by the requeue that delivered it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: i64,
+    pub project_slug: String,
+    pub sender_name: String,
+    pub on_behalf_of: Option<String>,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub body_md: String,
+    pub thread_id: Option<String>,
+    pub importance: Option<String>,
+    pub ack_required: bool,
+    pub error: String,
+    pub attempts: i64,
+    pub status: String,
+    pub message_id: Option<i64>,
+    pub created_ts: NaiveDateTime,
+    pub updated_ts: NaiveDateTime,
+}
+
+/// A failed send to keep as a dead letter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadLetterForCreate {
+    pub project_slug: String,
+    pub sender_name: String,
+    pub on_behalf_of: Option<String>,
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub cc: Vec<String>,
+    #[serde(default)]
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub body_md: String,
+    pub thread_id: Option<String>,
+    pub importance: Option<String>,
+    #[serde(default)]
+    pub ack_required: bool,
+    pub error: String,
+}
+
+/// Changes applied to a dead letter before it is requeued; `None` keeps
+/// the value it was sent with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadLetterRequeue {
+    pub project_slug: Option<String>,
+    pub sender_name: Option<String>,
+    pub to: Option<Vec<String>>,
+    pub cc: Option<Vec<String>>,
+    pub bcc: Option<Vec<String>>,
+}
+
+/// Which dead letters [`DeadLetterBmc::list`] returns.
+///
+/// # Fields
+///
+/// - `project_slug` - Only entries addressed to this project
+/// - `include_requeued` - Also list delivered entries
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadLetterFilter {
+    pub project_slug: Option<String>,
+    #[serde(default)]
+    pub include_requeued: bool,
+    pub limit: Option<i64>,
+}
+
+const SELECT_DEAD_LETTER: &str = r#"
+    SELECT id, project_slug, sender_name, on_behalf_of, to_names, cc_names, bcc_names,
+           subject, body_md, thread_id, importance, ack_required, error, attempts, status,
+           message_id, created_ts, updated_ts
+    FROM dead_letters
+"#;
+
+/// Backend Model Controller for undeliverable messages.
+pub struct DeadLetterBmc;
+
+impl DeadLetterBmc {
+    /// Whether a send that failed with `error` belongs in the dead-letter
+    /// store: its project or an agent it names no longer resolves.
+    pub fn is_undeliverable(error: &Error) -> bool {
+        matches!(
+            error,
+            Error::ProjectNotFound { .. } | Error::AgentNotFound { .. }
+        )
+    }
+
+    /// Keeps a failed send; returns the dead letter's id.
+    pub async fn record(ctx: &Ctx, mm: &ModelManager, dl_c: DeadLetterForCreate) -> Result<i64> {
+        let id = Self::insert(mm, &dl_c).await?;
+        warn!(
+            "Undeliverable message from '{}' in '{}' kept as dead letter {}: {}",
+            dl_c.sender_name, dl_c.project_slug, id, dl_c.error
+        );
+        let project_id = ProjectBmc::get_by_identifier(ctx, mm, &dl_c.project_slug)
+            .await
+            .ok()
+            .map(|p| p.id.get());
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                "message.dead_lettered",
+                project_id,
+                Some(&dl_c.sender_name),
+                serde_json::json!({
+                    "dead_letter_id": id,
+                    "project_slug": dl_c.project_slug,
+                    "subject": dl_c.subject,
+                    "error": dl_c.error,
+                }),
+            ),
+        )
+        .await;
+        Ok(id)
+    }
+
+    /// Gets a dead letter.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if there is no such entry.
+    pub async fn get(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<DeadLetter> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!("{} WHERE id = ?", SELECT_DEAD_LETTER))
+            .await?;
+        let mut rows = stmt.query([id]).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Lists dead letters, oldest first.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        filter: &DeadLetterFilter,
+    ) -> Result<Vec<DeadLetter>> {
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, 1000);
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE (?1 IS NULL OR project_slug = ?1) AND (?2 OR status = ?3) \
+                 ORDER BY id LIMIT ?4",
+                SELECT_DEAD_LETTER
+            ))
+            .await?;
+        let mut rows = stmt
+            .query(libsql::params![
+                filter.project_slug.clone(),
+                filter.include_requeued,
+                STATUS_PENDING,
+                limit,
+            ])
+            .await?;
+        let mut letters = Vec::new();
+        while let Some(row) = rows.next().await? {
+            letters.push(Self::from_row(&row)?);
+        }
+        Ok(letters)
+    }
+
+    /// Delivers a pending dead letter again, after applying `changes`.
+    ///
+    /// The project, sender and recipients are resolved as a new send would
+    /// resolve them. On success the entry is marked requeued with the new
+    /// message's id; on failure it stays pending with the new error.
+    ///
+    /// # Returns
+    /// The id of the delivered message.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if there is no such entry, `InvalidInput` if it
+    /// was already requeued, and the delivery error if it fails again.
+    pub async fn requeue(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        changes: DeadLetterRequeue,
+    ) -> Result<i64> {
+        let mut letter = Self::get(ctx, mm, id).await?;
+        if letter.status != STATUS_PENDING {
+            return Err(Error::InvalidInput(format!(
+                "Dead letter {} was already requeued as message {}",
+                id,
+                letter
+                    .message_id
+                    .map_or_else(|| "(deleted)".to_string(), |m| m.to_string())
+            )));
+        }
+        if let Some(project_slug) = changes.project_slug {
+            letter.project_slug = project_slug;
+        }
+        if let Some(sender_name) = changes.sender_name {
+            letter.sender_name = sender_name;
+        }
+        if let Some(to) = changes.to {
+            letter.to = to;
+        }
+        if let Some(cc) = changes.cc {
+            letter.cc = cc;
+        }
+        if let Some(bcc) = changes.bcc {
+            letter.bcc = bcc;
+        }
+
+        match Self::deliver(ctx, mm, &letter).await {
+            Ok(message_id) => {
+                Self::update(mm, &letter, STATUS_REQUEUED, Some(message_id), None).await?;
+                let project_id = ProjectBmc::get_by_identifier(ctx, mm, &letter.project_slug)
+                    .await
+                    .ok()
+                    .map(|p| p.id.get());
+                EventLogBmc::record(
+                    ctx,
+                    mm,
+                    EventForCreate::new(
+                        "message.requeued",
+                        project_id,
+                        Some(&letter.sender_name),
+                        serde_json::json!({
+                            "dead_letter_id": id,
+                            "message_id": message_id,
+                        }),
+                    ),
+                )
+                .await;
+                Ok(message_id)
+            }
+            Err(e) => {
+                let reason = e.to_string();
+                Self::update(mm, &letter, STATUS_PENDING, None, Some(&reason)).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Removes a dead letter without delivering it.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if there is no such entry.
+    pub async fn discard(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let db = mm.db();
+        let stmt = db.prepare("DELETE FROM dead_letters WHERE id = ?").await?;
+        if stmt.execute([id]).await? == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn insert(mm: &ModelManager, dl_c: &DeadLetterForCreate) -> Result<i64> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO dead_letters
+                    (project_slug, sender_name, on_behalf_of, to_names, cc_names, bcc_names,
+                     subject, body_md, thread_id, importance, ack_required, error)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query(libsql::params![
+                dl_c.project_slug.as_str(),
+                dl_c.sender_name.as_str(),
+                dl_c.on_behalf_of.clone(),
+                join_names(&dl_c.to),
+                join_names(&dl_c.cc),
+                join_names(&dl_c.bcc),
+                dl_c.subject.as_str(),
+                dl_c.body_md.as_str(),
+                dl_c.thread_id.clone(),
+                dl_c.importance.clone(),
+                dl_c.ack_required,
+                dl_c.error.as_str(),
+            ])
+            .await?;
+        let id: i64 = rows
+            .next()
+            .await?
+            .ok_or_else(|| Error::InvalidInput("Failed to record dead letter".into()))?
+            .get(0)?;
+        Ok(id)
+    }
+
+    async fn deliver(ctx: &Ctx, mm: &ModelManager, letter: &DeadLetter) -> Result<i64> {
+        let project = ProjectBmc::get_by_identifier(ctx, mm, &letter.project_slug).await?;
+        let sender = AgentBmc::get_by_name(ctx, mm, project.id, &letter.sender_name).await?;
+        let author = match letter.on_behalf_of.as_deref() {
+            Some(name) => AgentBmc::get_by_name(ctx, mm, project.id, name).await?,
+            None => sender.clone(),
+        };
+
+        let exclude = Some(author.id);
+        let (recipient_ids, mut routing) =
+            Self::resolve(ctx, mm, project.id, &letter.to, exclude).await?;
+        let (cc_ids, cc_routing) = Self::resolve(ctx, mm, project.id, &letter.cc, exclude).await?;
+        routing.extend(cc_routing);
+        let (bcc_ids, bcc_routing) =
+            Self::resolve(ctx, mm, project.id, &letter.bcc, exclude).await?;
+        routing.extend(bcc_routing);
+
+        let msg_c = MessageForCreate {
+            project_id: project.id,
+            sender_id: author.id,
+            recipient_ids,
+            cc_ids: (!cc_ids.is_empty()).then_some(cc_ids),
+            bcc_ids: (!bcc_ids.is_empty()).then_some(bcc_ids),
+            subject: letter.subject.clone(),
+            body_md: letter.body_md.clone(),
+            thread_id: letter.thread_id.clone(),
+            importance: letter.importance.clone(),
+            ack_required: letter.ack_required,
+        };
+        let message_id = MessageBmc::create_on_behalf(ctx, mm, sender.id, msg_c).await?;
+        CapabilityRoutingBmc::record(ctx, mm, message_id, &routing).await?;
+        Ok(message_id.get())
+    }
+
+    /// Resolves recipients like a send does, including `broadcast`.
+    async fn resolve(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        names: &[String],
+        exclude_agent_id: Option<AgentId>,
+    ) -> Result<(Vec<AgentId>, Vec<CapabilityResolution>)> {
+        if names.iter().any(|n| n.eq_ignore_ascii_case("broadcast")) {
+            let mut ids: Vec<AgentId> = AgentBmc::list_all_for_project(ctx, mm, project_id)
+                .await?
+                .iter()
+                .map(|a| a.id)
+                .collect();
+            let others: Vec<String> = names
+                .iter()
+                .filter(|n| !n.eq_ignore_ascii_case("broadcast"))
+                .cloned()
+                .collect();
+            let (more, routing) = CapabilityRoutingBmc::resolve_recipients(
+                ctx,
+                mm,
+                project_id,
+                &others,
+                exclude_agent_id,
+            )
+            .await?;
+            for id in more {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+            return Ok((ids, routing));
+        }
+        CapabilityRoutingBmc::resolve_recipients(ctx, mm, project_id, names, exclude_agent_id).await
+    }
+
+    async fn update(
+        mm: &ModelManager,
+        letter: &DeadLetter,
+        status: &str,
+        message_id: Option<i64>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE dead_letters
+                SET project_slug = ?, sender_name = ?, to_names = ?, cc_names = ?,
+                    bcc_names = ?, status = ?, message_id = ?, error = COALESCE(?, error),
+                    attempts = attempts + CASE WHEN ? IS NULL THEN 0 ELSE 1 END, updated_ts = CURRENT_TIMESTAMP
+                WHERE id = ?
+                "#,
+            )
+            .await?;
+        stmt.execute(libsql::params![
+            letter.project_slug.as_str(),
+            letter.sender_name.as_str(),
+            join_names(&letter.to),
+            join_names(&letter.cc),
+            join_names(&letter.bcc),
+            status,
+            message_id,
+            error,
+            error,
+            letter.id,
+        ])
+        .await?;
+        Ok(())
+    }
+
+    fn from_row(row: &Row) -> Result<DeadLetter> {
+        let to: String = row.get(4)?;
+        let cc: String = row.get(5)?;
+        let bcc: String = row.get(6)?;
+        let ack_required: i64 = row.get(11)?;
+        let created_ts: String = row.get(16)?;
+        let updated_ts: String = row.get(17)?;
+        Ok(DeadLetter {
+            id: row.get(0)?,
+            project_slug: row.get(1)?,
+            sender_name: row.get(2)?,
+            on_behalf_of: row.get(3)?,
+            to: split_names(&to),
+            cc: split_names(&cc),
+            bcc: split_names(&bcc),
+            subject: row.get(7)?,
+            body_md: row.get(8)?,
+            thread_id: row.get(9)?,
+            importance: row.get(10)?,
+            ack_required: ack_required != 0,
+            error: row.get(12)?,
+            attempts: row.get(13)?,
+            status: row.get(14)?,
+            message_id: row.get(15)?,
+            created_ts: parse_timestamp(&created_ts, "dead_letters.created_ts"),
+            updated_ts: parse_timestamp(&updated_ts, "dead_letters.updated_ts"),
+        })
+    }
+}
+
+/// Splits comma-separated recipients, as sends accept them.
+pub fn split_names(names: &str) -> Vec<String> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn join_names(names: &[String]) -> String {
+    names.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_names() {
+        assert_eq!(split_names(" alice, ,bob,"), vec!["alice", "bob"]);
+        assert!(split_names("").is_empty());
+    }
+
+    #[test]
+    fn test_is_undeliverable() {
+        assert!(DeadLetterBmc::is_undeliverable(&Error::agent_not_found(
+            "bob"
+        )));
+        assert!(DeadLetterBmc::is_undeliverable(&Error::project_not_found(
+            "gone"
+        )));
+        assert!(!DeadLetterBmc::is_undeliverable(&Error::InvalidInput(
+            "bad".into()
+        )));
+    }
+}
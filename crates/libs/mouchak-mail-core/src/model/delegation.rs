@@ -0,0 +1,260 @@
+//! Delegation grants: sending messages on behalf of another agent.
+//!
+//! A grantor can allow a delegate to send messages on its behalf, e.g. a
+//! supervisor agent drafting mail for its workers. Such messages keep the
+//! grantor as sender, so replies and threads behave as if the grantor had
+//! sent them, and record the delegate so readers see "delegate on behalf of
+//! grantor". [`crate::model::message::MessageBmc::create_on_behalf`]
+//! refuses to send without a grant.
+//!
+//! Grants, revocations and delegated sends are recorded in the event log as
+//! `delegation.granted`, `delegation.revoked` and `message.sent_on_behalf`.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Permission for `delegate` to send messages as `grantor`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delegation {
+    pub project_id: ProjectId,
+    pub grantor_id: AgentId,
+    pub grantor_name: String,
+    pub delegate_id: AgentId,
+    pub delegate_name: String,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Backend Model Controller for delegation grants.
+pub struct DelegationBmc;
+
+impl DelegationBmc {
+    /// Lets `delegate` send messages on behalf of `grantor`.
+    ///
+    /// # Returns
+    /// `false` if the grant already existed.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if the agents are the same or not both in
+    /// `project_id`.
+    pub async fn grant(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        grantor: AgentId,
+        delegate: AgentId,
+    ) -> Result<bool> {
+        if grantor == delegate {
+            return Err(Error::InvalidInput(
+                "an agent cannot delegate to itself".to_string(),
+            ));
+        }
+        let grantor_agent = AgentBmc::get(ctx, mm, grantor).await?;
+        let delegate_agent = AgentBmc::get(ctx, mm, delegate).await?;
+        if grantor_agent.project_id != project_id || delegate_agent.project_id != project_id {
+            return Err(Error::InvalidInput(format!(
+                "'{}' and '{}' must both belong to the project",
+                grantor_agent.name, delegate_agent.name
+            )));
+        }
+
+        let now = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT OR IGNORE INTO delegation_grants (project_id, grantor_id, delegate_id, created_ts)
+                VALUES (?, ?, ?, ?)
+                "#,
+            )
+            .await?;
+        let inserted = stmt
+            .execute((project_id.get(), grantor.get(), delegate.get(), now))
+            .await?;
+        if inserted > 0 {
+            Self::record_event(
+                ctx,
+                mm,
+                "delegation.granted",
+                project_id,
+                &grantor_agent.name,
+                &delegate_agent.name,
+            )
+            .await;
+        }
+        Ok(inserted > 0)
+    }
+
+    /// Withdraws a grant; messages already sent keep their delegate.
+    ///
+    /// # Returns
+    /// `false` if there was no such grant.
+    pub async fn revoke(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        grantor: AgentId,
+        delegate: AgentId,
+    ) -> Result<bool> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "DELETE FROM delegation_grants WHERE project_id = ? AND grantor_id = ? AND delegate_id = ?",
+            )
+            .await?;
+        let deleted = stmt
+            .execute((project_id.get(), grantor.get(), delegate.get()))
+            .await?;
+        if deleted > 0 {
+            let grantor_agent = AgentBmc::get(ctx, mm, grantor).await?;
+            let delegate_agent = AgentBmc::get(ctx, mm, delegate).await?;
+            Self::record_event(
+                ctx,
+                mm,
+                "delegation.revoked",
+                project_id,
+                &grantor_agent.name,
+                &delegate_agent.name,
+            )
+            .await;
+        }
+        Ok(deleted > 0)
+    }
+
+    /// Lists a project's grants, or those given or held by `agent`.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent: Option<AgentId>,
+    ) -> Result<Vec<Delegation>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT d.grantor_id, g.name, d.delegate_id, a.name, d.created_ts
+                FROM delegation_grants d
+                JOIN agents g ON g.id = d.grantor_id
+                JOIN agents a ON a.id = d.delegate_id
+                WHERE d.project_id = ?1
+                  AND (?2 IS NULL OR d.grantor_id = ?2 OR d.delegate_id = ?2)
+                ORDER BY g.name, a.name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((project_id.get(), agent.map(|a| a.get())))
+            .await?;
+        let mut grants = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(4)?;
+            grants.push(Delegation {
+                project_id,
+                grantor_id: AgentId::new(row.get(0)?),
+                grantor_name: row.get(1)?,
+                delegate_id: AgentId::new(row.get(2)?),
+                delegate_name: row.get(3)?,
+                created_ts: parse_timestamp(&created_ts, "delegation_grants.created_ts"),
+            });
+        }
+        Ok(grants)
+    }
+
+    /// Whether `delegate` may currently send on behalf of `grantor`.
+    pub async fn is_granted(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        grantor: AgentId,
+        delegate: AgentId,
+    ) -> Result<bool> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT 1 FROM delegation_grants WHERE grantor_id = ? AND delegate_id = ?")
+            .await?;
+        let mut rows = stmt.query((grantor.get(), delegate.get())).await?;
+        Ok(rows.next().await?.is_some())
+    }
+
+    /// The delegate names of those `message_ids` sent on someone's behalf.
+    pub async fn delegates_for_messages(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_ids: &[i64],
+    ) -> Result<HashMap<i64, String>> {
+        if message_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = vec!["?"; message_ids.len()].join(",");
+        let query = format!(
+            r#"
+            SELECT md.message_id, a.name
+            FROM message_delegations md
+            JOIN agents a ON a.id = md.delegate_id
+            WHERE md.message_id IN ({})
+            "#,
+            placeholders
+        );
+        let params: Vec<libsql::Value> = message_ids.iter().map(|&id| id.into()).collect();
+
+        let db = mm.db();
+        let stmt = db.prepare(&query).await?;
+        let mut rows = stmt
+            .query(libsql::params::Params::Positional(params))
+            .await?;
+        let mut delegates = HashMap::new();
+        while let Some(row) = rows.next().await? {
+            delegates.insert(row.get::<i64>(0)?, row.get::<String>(1)?);
+        }
+        Ok(delegates)
+    }
+
+    /// Records that `message_id` was sent by `delegate`.
+    pub(crate) async fn record_message(
+        mm: &ModelManager,
+        message_id: MessageId,
+        delegate: AgentId,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO message_delegations (message_id, delegate_id) VALUES (?, ?)
+                ON CONFLICT(message_id) DO UPDATE SET delegate_id = excluded.delegate_id
+                "#,
+            )
+            .await?;
+        stmt.execute((message_id.get(), delegate.get())).await?;
+        Ok(())
+    }
+
+    async fn record_event(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        kind: &str,
+        project_id: ProjectId,
+        grantor: &str,
+        delegate: &str,
+    ) {
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                kind,
+                Some(project_id.get()),
+                Some(grantor),
+                serde_json::json!({
+                    "grantor": grantor,
+                    "delegate": delegate,
+                }),
+            ),
+        )
+        .await;
+    }
+}
@@ -0,0 +1,394 @@
+//! Free space monitoring and the emergency read-only mode.
+//!
+//! SQLite fails writes with `SQLITE_FULL` once the volume is full, often in
+//! the middle of a transaction that also touched the Git archive. The
+//! [`DiskWatcher`] checks free space under the data directory and steps
+//! between three [`DiskLevel`]s:
+//!
+//! - `ok` - Nothing to do
+//! - `low` - Free space is below `warn_free_mb`; overseers are alerted
+//! - `critical` - Free space is below `read_only_free_mb`; overseers are
+//!   alerted and the [`ModelManager`] turns read-only
+//!
+//! While read-only, sending mail and uploading attachments fail with
+//! [`Error::ReadOnly`], MCP write tools are refused, and the database
+//! connection runs with `PRAGMA query_only` so any other write fails too;
+//! reads keep working. The server leaves read-only mode only once free
+//! space is back above `warn_free_mb`, so a volume hovering around the
+//! lower threshold does not flap.
+//!
+//! Free space is read with POSIX `df -Pk`, which keeps this module free of
+//! platform-specific system calls.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{AgentBmc, AgentForCreate};
+use crate::model::overseer_message::{OverseerMessageBmc, OverseerMessageForCreate};
+use crate::model::project::ProjectBmc;
+use crate::types::ProjectId;
+use crate::{Error, Result};
+use mouchak_mail_common::config::DiskWatchConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Name of the agent disk space alerts are sent from.
+pub const DISK_WATCH_AGENT_NAME: &str = "diskwatch";
+
+const MB: u64 = 1024 * 1024;
+
+/// How close the data volume is to full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskLevel {
+    Ok,
+    Low,
+    Critical,
+}
+
+impl DiskLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiskLevel::Ok => "ok",
+            DiskLevel::Low => "low",
+            DiskLevel::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for DiskLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Size and free space of a volume.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskSpace {
+    pub total_bytes: u64,
+    /// Space available to unprivileged writers
+    pub free_bytes: u64,
+}
+
+/// Free space thresholds in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskThresholds {
+    pub warn_free_bytes: u64,
+    pub read_only_free_bytes: u64,
+}
+
+impl DiskThresholds {
+    /// Thresholds of `config`; the warning threshold is never below the
+    /// read-only one.
+    pub fn from_config(config: &DiskWatchConfig) -> Self {
+        let read_only_free_bytes = config.read_only_free_mb.saturating_mul(MB);
+        Self {
+            warn_free_bytes: config
+                .warn_free_mb
+                .saturating_mul(MB)
+                .max(read_only_free_bytes),
+            read_only_free_bytes,
+        }
+    }
+
+    /// Level for `free_bytes`, given the level of the previous check.
+    ///
+    /// A `critical` volume stays critical until free space is back above
+    /// the warning threshold.
+    pub fn level(&self, free_bytes: u64, previous: DiskLevel) -> DiskLevel {
+        if free_bytes < self.read_only_free_bytes {
+            DiskLevel::Critical
+        } else if free_bytes < self.warn_free_bytes {
+            if previous == DiskLevel::Critical {
+                DiskLevel::Critical
+            } else {
+                DiskLevel::Low
+            }
+        } else {
+            DiskLevel::Ok
+        }
+    }
+}
+
+/// Outcome of one check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskStatus {
+    pub path: PathBuf,
+    pub space: DiskSpace,
+    pub level: DiskLevel,
+    pub previous: DiskLevel,
+    pub read_only: bool,
+}
+
+impl DiskStatus {
+    pub fn changed(&self) -> bool {
+        self.level != self.previous
+    }
+}
+
+/// Parses the output of `df -Pk <path>` into the volume's size and free
+/// space.
+pub fn parse_df(output: &str) -> Option<DiskSpace> {
+    // Filesystem 1024-blocks Used Available Capacity Mounted-on
+    let line = output.lines().nth(1)?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let total_kb: u64 = fields.get(1)?.parse().ok()?;
+    let free_kb: u64 = fields.get(3)?.parse().ok()?;
+    Some(DiskSpace {
+        total_bytes: total_kb.saturating_mul(1024),
+        free_bytes: free_kb.saturating_mul(1024),
+    })
+}
+
+/// Measures the volume holding `path`.
+///
+/// # Errors
+/// Returns `Io` if `df` cannot be run or its output is not understood.
+pub async fn probe(path: &Path) -> Result<DiskSpace> {
+    let output = tokio::process::Command::new("df")
+        .arg("-Pk")
+        .arg(path)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(Error::Io(std::io::Error::other(format!(
+            "df {} failed: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    parse_df(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        Error::Io(std::io::Error::other(format!(
+            "unexpected df output for {}",
+            path.display()
+        )))
+    })
+}
+
+/// Watches free space of one path and switches read-only mode.
+pub struct DiskWatcher {
+    path: PathBuf,
+    thresholds: DiskThresholds,
+    level: DiskLevel,
+}
+
+impl DiskWatcher {
+    pub fn new(path: PathBuf, thresholds: DiskThresholds) -> Self {
+        Self {
+            path,
+            thresholds,
+            level: DiskLevel::Ok,
+        }
+    }
+
+    /// Level of the last check.
+    pub fn level(&self) -> DiskLevel {
+        self.level
+    }
+
+    /// Measures free space and applies it.
+    pub async fn check(&mut self, ctx: &Ctx, mm: &ModelManager) -> Result<DiskStatus> {
+        let space = probe(&self.path).await?;
+        self.apply(ctx, mm, space).await
+    }
+
+    /// Moves to the level for `space`, alerting overseers and switching
+    /// read-only mode when the level changes.
+    ///
+    /// Alerts are written before writes are suspended and after they
+    /// resume, so they reach the database either way.
+    pub async fn apply(
+        &mut self,
+        ctx: &Ctx,
+        mm: &ModelManager,
+        space: DiskSpace,
+    ) -> Result<DiskStatus> {
+        let previous = self.level;
+        let level = self.thresholds.level(space.free_bytes, previous);
+        self.level = level;
+
+        if level != previous {
+            match level {
+                DiskLevel::Critical => {
+                    warn!(
+                        path = %self.path.display(),
+                        free_bytes = space.free_bytes,
+                        "Disk space critical, switching to read-only mode"
+                    );
+                    self.alert(ctx, mm, space, level).await;
+                    mm.set_read_only(true).await?;
+                }
+                DiskLevel::Low => {
+                    warn!(
+                        path = %self.path.display(),
+                        free_bytes = space.free_bytes,
+                        "Disk space low"
+                    );
+                    if previous == DiskLevel::Ok {
+                        self.alert(ctx, mm, space, level).await;
+                    }
+                }
+                DiskLevel::Ok => {
+                    info!(
+                        path = %self.path.display(),
+                        free_bytes = space.free_bytes,
+                        "Disk space recovered"
+                    );
+                    if previous == DiskLevel::Critical {
+                        mm.set_read_only(false).await?;
+                        self.alert(ctx, mm, space, level).await;
+                    }
+                }
+            }
+        }
+
+        Ok(DiskStatus {
+            path: self.path.clone(),
+            space,
+            level,
+            previous,
+            read_only: mm.is_read_only(),
+        })
+    }
+
+    /// Sends an overseer message about `level` to every project.
+    ///
+    /// Failures are logged; an alert must never keep the server from
+    /// switching modes.
+    async fn alert(&self, ctx: &Ctx, mm: &ModelManager, space: DiskSpace, level: DiskLevel) {
+        let free_mb = space.free_bytes / MB;
+        let (subject, body_md, importance) = match level {
+            DiskLevel::Critical => (
+                format!("Disk nearly full: mail is read-only ({free_mb} MB free)"),
+                format!(
+                    "Only {free_mb} MB is free under `{}`. Mouchak Mail has stopped \
+                     accepting writes so the database is not corrupted; reading mail \
+                     still works. Free up space and writes resume automatically once \
+                     more than {} MB is free.",
+                    self.path.display(),
+                    self.thresholds.warn_free_bytes / MB
+                ),
+                "critical",
+            ),
+            DiskLevel::Low => (
+                format!("Disk space low ({free_mb} MB free)"),
+                format!(
+                    "Only {free_mb} MB is free under `{}`. Below {} MB Mouchak Mail \
+                     turns read-only. Free up space or grow the volume.",
+                    self.path.display(),
+                    self.thresholds.read_only_free_bytes / MB
+                ),
+                "high",
+            ),
+            DiskLevel::Ok => (
+                format!("Disk space recovered: mail accepts writes again ({free_mb} MB free)"),
+                format!(
+                    "{free_mb} MB is free under `{}` and Mouchak Mail accepts writes again.",
+                    self.path.display()
+                ),
+                "high",
+            ),
+        };
+
+        let projects = match ProjectBmc::list_all(ctx, mm).await {
+            Ok(projects) => projects,
+            Err(e) => {
+                warn!(error = %e, "Could not list projects for disk space alert");
+                return;
+            }
+        };
+        for project in projects {
+            let sent = async {
+                let sender_id = disk_watch_agent(ctx, mm, project.id).await?;
+                OverseerMessageBmc::create(
+                    ctx,
+                    mm,
+                    OverseerMessageForCreate {
+                        project_id: project.id.get(),
+                        sender_id,
+                        subject: subject.clone(),
+                        body_md: body_md.clone(),
+                        importance: importance.to_string(),
+                    },
+                )
+                .await
+            }
+            .await;
+            if let Err(e) = sent {
+                warn!(project = %project.slug, error = %e, "Could not send disk space alert");
+            }
+        }
+    }
+}
+
+/// The project's `diskwatch` agent, registered on first use.
+async fn disk_watch_agent(ctx: &Ctx, mm: &ModelManager, project_id: ProjectId) -> Result<i64> {
+    match AgentBmc::get_by_name(ctx, mm, project_id, DISK_WATCH_AGENT_NAME).await {
+        Ok(agent) => Ok(agent.id.get()),
+        Err(Error::AgentNotFound { .. }) | Err(Error::NotFound) => {
+            let id = AgentBmc::create(
+                ctx,
+                mm,
+                AgentForCreate {
+                    project_id,
+                    name: DISK_WATCH_AGENT_NAME.to_string(),
+                    program: "mouchak-mail".to_string(),
+                    model: "none".to_string(),
+                    task_description: "Reports disk space of the mail server".to_string(),
+                },
+            )
+            .await?;
+            Ok(id.get())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn thresholds() -> DiskThresholds {
+        DiskThresholds::from_config(&DiskWatchConfig {
+            interval_seconds: 30,
+            warn_free_mb: 1024,
+            read_only_free_mb: 256,
+        })
+    }
+
+    #[test]
+    fn test_parse_df() {
+        let output = "Filesystem     1024-blocks     Used Available Capacity Mounted on\n\
+                      /dev/nvme0n1p2   490617784 81234560 384384224      18% /\n";
+        let space = parse_df(output).expect("parses");
+        assert_eq!(space.total_bytes, 490_617_784 * 1024);
+        assert_eq!(space.free_bytes, 384_384_224 * 1024);
+
+        assert_eq!(parse_df(""), None);
+        assert_eq!(parse_df("Filesystem 1024-blocks\n/dev/sda1 -\n"), None);
+    }
+
+    #[test]
+    fn test_levels_with_hysteresis() {
+        let t = thresholds();
+        assert_eq!(t.level(2048 * MB, DiskLevel::Ok), DiskLevel::Ok);
+        assert_eq!(t.level(512 * MB, DiskLevel::Ok), DiskLevel::Low);
+        assert_eq!(t.level(100 * MB, DiskLevel::Low), DiskLevel::Critical);
+        // Stays read-only until space is back above the warning threshold
+        assert_eq!(t.level(512 * MB, DiskLevel::Critical), DiskLevel::Critical);
+        assert_eq!(t.level(2048 * MB, DiskLevel::Critical), DiskLevel::Ok);
+    }
+
+    #[test]
+    fn test_warn_threshold_not_below_read_only() {
+        let t = DiskThresholds::from_config(&DiskWatchConfig {
+            interval_seconds: 30,
+            warn_free_mb: 100,
+            read_only_free_mb: 500,
+        });
+        assert_eq!(t.warn_free_bytes, 500 * MB);
+        assert_eq!(t.level(400 * MB, DiskLevel::Ok), DiskLevel::Critical);
+    }
+}
@@ -0,0 +1,410 @@
+//! Outbound email bridge for human supervisors.
+//!
+//! Projects opt in through their settings (`email_bridge`), optionally with
+//! their own recipient addresses. The server periodically collects messages
+//! of opted-in projects created since the last scan with
+//! [`EmailBridgeBmc::pending`], keeps those the `email_bridge` config
+//! forwards (by importance or subject keyword) and mails them over SMTP.
+//!
+//! Forwarded mail is thread-aware: every message of a thread carries the
+//! same `[project] subject`, replies are prefixed with `Re:`, and the
+//! `References` header names the thread, so mail clients group a thread's
+//! messages into one conversation.
+//!
+//! The cursor of the last message considered is stored in
+//! `email_bridge_state`. A fresh cursor starts at the newest existing
+//! message, so enabling the bridge never replays old mail. Messages held for
+//! approval or deferred by a focus window are parked (see
+//! [`crate::model::parked_message`]) and forwarded once they are delivered;
+//! rejected ones are never forwarded.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::message::MessageBmc;
+use crate::model::parked_message::{self, ParkedMessageBmc, ScanState};
+use crate::types::{MessageId, ProjectId};
+use crate::utils::{has_reply_prefix, normalize_subject, parse_timestamp};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// Name the bridge parks held and deferred messages under.
+const PARK_CONSUMER: &str = "email_bridge";
+
+/// Longest address accepted (RFC 5321 path limit).
+const MAX_ADDRESS_LEN: usize = 254;
+
+/// A message to forward by email.
+///
+/// # Fields
+///
+/// - `is_reply` - An earlier message exists in the same thread, or the
+///   subject starts with a reply prefix
+/// - `recipients` - The project's addresses; empty means the configured
+///   default recipients
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgedMessage {
+    pub message_id: i64,
+    pub project_id: i64,
+    pub project_slug: String,
+    pub sender_name: String,
+    pub subject: String,
+    pub body_md: String,
+    pub importance: String,
+    pub thread_id: Option<String>,
+    pub created_ts: NaiveDateTime,
+    pub is_reply: bool,
+    pub recipients: Vec<String>,
+}
+
+impl BridgedMessage {
+    /// Email subject shared by the whole thread: `[project] subject`, with
+    /// `Re: ` in front for replies.
+    pub fn email_subject(&self) -> String {
+        let subject = format!(
+            "[{}] {}",
+            self.project_slug,
+            normalize_subject(&self.subject)
+        );
+        if self.is_reply {
+            format!("Re: {}", subject)
+        } else {
+            subject
+        }
+    }
+
+    /// Stable key of the message's thread, safe to use in a `Message-ID`.
+    ///
+    /// Messages without a thread id form a thread of their own.
+    pub fn thread_key(&self) -> String {
+        match self.thread_id.as_deref().filter(|t| !t.trim().is_empty()) {
+            Some(thread_id) => message_id_part(thread_id.trim()),
+            None => format!("msg-{}", self.message_id),
+        }
+    }
+}
+
+/// Messages collected by one scan.
+///
+/// `scanned_to` is the ID to pass to [`EmailBridgeBmc::advance_cursor`]
+/// once every message has been handled; it also skips messages of projects
+/// that did not opt in.
+#[derive(Debug, Clone)]
+pub struct BridgeBatch {
+    pub messages: Vec<BridgedMessage>,
+    pub scanned_to: i64,
+}
+
+/// Parses a comma-separated list of email addresses.
+///
+/// Only bare addresses are accepted (`ops@example.com`, no display names),
+/// since they are written into SMTP commands and mail headers.
+///
+/// # Errors
+/// Returns `InvalidInput` for an address without a local part and domain,
+/// or containing whitespace, angle brackets or control characters.
+pub fn parse_addresses(list: &str) -> Result<Vec<String>> {
+    let mut addresses: Vec<String> = Vec::new();
+    for address in list.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        if !is_valid_address(address) {
+            return Err(Error::InvalidInput(format!(
+                "Invalid email address '{}'",
+                address
+            )));
+        }
+        if !addresses.iter().any(|a| a.eq_ignore_ascii_case(address)) {
+            addresses.push(address.to_string());
+        }
+    }
+    Ok(addresses)
+}
+
+/// `value` reduced to characters allowed in message IDs and tokens: every
+/// other character becomes `-`.
+pub fn message_id_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Backend Model Controller for the email bridge.
+pub struct EmailBridgeBmc;
+
+impl EmailBridgeBmc {
+    /// A project's recipient addresses, or `None` if it did not opt in.
+    pub async fn project_recipients(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Option<Vec<String>>> {
+        let stmt = mm
+            .db()
+            .prepare("SELECT recipients FROM email_bridge_projects WHERE project_id = ?")
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(split_recipients(&row.get::<String>(0)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Opts a project in, replacing its recipient addresses.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a malformed address.
+    pub async fn enable_project(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        recipients: &[String],
+    ) -> Result<()> {
+        let recipients = parse_addresses(&recipients.join(","))?;
+        let stmt = mm
+            .db()
+            .prepare(
+                r#"
+                INSERT INTO email_bridge_projects (project_id, recipients)
+                VALUES (?, ?)
+                ON CONFLICT(project_id) DO UPDATE SET
+                    recipients = excluded.recipients,
+                    updated_ts = CURRENT_TIMESTAMP
+                "#,
+            )
+            .await?;
+        stmt.execute((project_id.get(), recipients.join(",")))
+            .await?;
+        Ok(())
+    }
+
+    /// Opts a project out; its recipient addresses are forgotten.
+    pub async fn disable_project(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<()> {
+        let stmt = mm
+            .db()
+            .prepare("DELETE FROM email_bridge_projects WHERE project_id = ?")
+            .await?;
+        stmt.execute([project_id.get()]).await?;
+        Ok(())
+    }
+
+    /// Lists messages of opted-in projects created after the cursor, and
+    /// parked ones delivered since, oldest first, with their complete bodies.
+    /// Messages still held or deferred are parked; rejected ones are left out.
+    ///
+    /// # Returns
+    /// At most `limit` messages. Call [`Self::advance_cursor`] with
+    /// `scanned_to` once they have been handled.
+    pub async fn pending(ctx: &Ctx, mm: &ModelManager, limit: i64) -> Result<BridgeBatch> {
+        Self::ensure_state(mm).await?;
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT COALESCE(MAX(id), 0) FROM messages")
+            .await?;
+        let mut rows = stmt.query(()).await?;
+        let newest: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+
+        let sql = format!(
+            r#"
+            SELECT m.id, m.project_id, p.slug, ag.name, m.subject, m.body_md, m.importance,
+                   m.thread_id, m.created_ts, eb.recipients,
+                   EXISTS (
+                       SELECT 1 FROM messages AS prev
+                       WHERE prev.project_id = m.project_id
+                         AND prev.thread_id = m.thread_id
+                         AND prev.id < m.id
+                   ),
+                   {}
+            FROM messages AS m
+            JOIN email_bridge_projects AS eb ON eb.project_id = m.project_id
+            JOIN projects AS p ON m.project_id = p.id
+            JOIN agents AS ag ON m.sender_id = ag.id
+            WHERE (m.id > (SELECT last_message_id FROM email_bridge_state WHERE id = 1)
+                   AND m.id <= ?)
+               OR {}
+            ORDER BY m.id ASC
+            LIMIT ?
+            "#,
+            parked_message::state_columns(),
+            parked_message::released_sql()
+        );
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = stmt
+            .query((PARK_CONSUMER, newest, PARK_CONSUMER, limit))
+            .await?;
+
+        let mut scanned = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let subject: String = row.get(4)?;
+            let is_reply = row.get::<i64>(10)? != 0 || has_reply_prefix(&subject);
+            let state = ScanState {
+                parked: row.get::<i64>(11)? != 0,
+                waiting: row.get::<i64>(12)? != 0,
+                rejected: row.get::<i64>(13)? != 0,
+            };
+            let message = BridgedMessage {
+                message_id: row.get(0)?,
+                project_id: row.get(1)?,
+                project_slug: row.get(2)?,
+                sender_name: row.get(3)?,
+                subject,
+                body_md: row.get(5)?,
+                importance: row.get(6)?,
+                thread_id: row.get(7)?,
+                created_ts: parse_timestamp(&row.get::<String>(8)?, "created_ts"),
+                is_reply,
+                recipients: split_recipients(&row.get::<String>(9)?),
+            };
+            scanned.push((message, state));
+        }
+
+        // Held and deferred messages wait until they are delivered
+        let scanned_to = match scanned.last() {
+            Some((last, _)) if scanned.len() as i64 >= limit => last.message_id,
+            _ => newest,
+        };
+        let mut messages = Vec::new();
+        for (message, state) in scanned {
+            if ParkedMessageBmc::settle(mm, PARK_CONSUMER, message.message_id, state).await? {
+                messages.push(message);
+            }
+        }
+
+        for message in &mut messages {
+            if let Some(full) =
+                MessageBmc::get_offloaded_body(ctx, mm, MessageId::new(message.message_id)).await?
+            {
+                message.body_md = full.body_md;
+            }
+        }
+
+        Ok(BridgeBatch {
+            messages,
+            scanned_to,
+        })
+    }
+
+    /// Moves the bridge cursor past `last_message_id`.
+    pub async fn advance_cursor(_ctx: &Ctx, mm: &ModelManager, last_message_id: i64) -> Result<()> {
+        let stmt = mm
+            .db()
+            .prepare(
+                "UPDATE email_bridge_state SET last_message_id = MAX(last_message_id, ?) WHERE id = 1",
+            )
+            .await?;
+        stmt.execute([last_message_id]).await?;
+        ParkedMessageBmc::clear_released(mm, PARK_CONSUMER, last_message_id).await
+    }
+
+    /// Creates the bridge state row, starting at the newest message.
+    async fn ensure_state(mm: &ModelManager) -> Result<()> {
+        let stmt = mm
+            .db()
+            .prepare(
+                r#"
+                INSERT OR IGNORE INTO email_bridge_state (id, last_message_id)
+                VALUES (1, (SELECT COALESCE(MAX(id), 0) FROM messages))
+                "#,
+            )
+            .await?;
+        stmt.execute(()).await?;
+        Ok(())
+    }
+}
+
+fn is_valid_address(address: &str) -> bool {
+    let Some((local, domain)) = address.rsplit_once('@') else {
+        return false;
+    };
+    let domain_ok = domain == "localhost"
+        || (domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.'));
+    address.len() <= MAX_ADDRESS_LEN
+        && !local.is_empty()
+        && domain_ok
+        && !address.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ',' | ';' | '"')
+        })
+}
+
+fn split_recipients(csv: &str) -> Vec<String> {
+    csv.split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn bridged(subject: &str, thread_id: Option<&str>, is_reply: bool) -> BridgedMessage {
+        BridgedMessage {
+            message_id: 7,
+            project_id: 1,
+            project_slug: "backend".to_string(),
+            sender_name: "BlueLake".to_string(),
+            subject: subject.to_string(),
+            body_md: String::new(),
+            importance: "urgent".to_string(),
+            thread_id: thread_id.map(str::to_string),
+            created_ts: NaiveDateTime::default(),
+            is_reply,
+            recipients: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_addresses() {
+        assert_eq!(
+            parse_addresses(" ops@example.com, , lead@example.org,OPS@example.com").unwrap(),
+            vec!["ops@example.com", "lead@example.org"]
+        );
+        assert!(parse_addresses("").unwrap().is_empty());
+        assert_eq!(
+            parse_addresses("root@localhost").unwrap(),
+            vec!["root@localhost"]
+        );
+        for bad in [
+            "ops",
+            "@example.com",
+            "ops@example",
+            "Ops <ops@example.com>",
+            "ops@example.com\r\nRCPT TO:<x@y.z>",
+            "o ps@example.com",
+        ] {
+            assert!(parse_addresses(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_email_subject_is_thread_aware() {
+        let root = bridged("Deploy  plan", Some("T-1"), false);
+        assert_eq!(root.email_subject(), "[backend] Deploy plan");
+        let reply = bridged("Re: RE: Deploy plan", Some("T-1"), true);
+        assert_eq!(reply.email_subject(), "Re: [backend] Deploy plan");
+    }
+
+    #[test]
+    fn test_thread_key() {
+        assert_eq!(
+            bridged("x", Some("thread/42 <a>"), false).thread_key(),
+            "thread-42--a-"
+        );
+        assert_eq!(bridged("x", None, false).thread_key(), "msg-7");
+        assert_eq!(bridged("x", Some("  "), false).thread_key(), "msg-7");
+    }
+}
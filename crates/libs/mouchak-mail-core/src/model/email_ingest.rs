@@ -0,0 +1,892 @@
+//! Inbound email gateway.
+//!
+//! Raw mail posted to `/api/ingest/email` (by an MTA hook, or by another
+//! system) becomes an agent message. [`InboundEmail::parse`] reads the
+//! headers and the first `text/plain` part of the mail, keeping only the
+//! reply text above a quoted original or signature.
+//! [`EmailIngestBmc::ingest`] then finds where the message belongs:
+//!
+//! - Replies to bridged mail reference the bridge's `Message-ID`s (see
+//!   [`super::email_bridge`]), which name the project and thread; replies to
+//!   previously ingested mail reference that mail's `Message-ID`.
+//! - Other mail starts a thread in the project named by a `[project]` tag
+//!   at the start of its subject.
+//!
+//! The sender is the agent the `From` address maps to in
+//! `email_bridge.inbound_aliases`; mail from other addresses is refused.
+//! Recipients are the other participants of the thread, plus the agents
+//! whose addresses are among `To` and `Cc`.
+//!
+//! Auto-generated mail (`Auto-Submitted`, bulk `Precedence`) is skipped so
+//! vacation replies cannot loop, and a redelivered `Message-ID` posts
+//! nothing (migration 047).
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::email_bridge::{message_id_part, parse_addresses};
+use crate::model::message::{MessageBmc, MessageForCreate};
+use crate::model::project::ProjectBmc;
+use crate::types::{MessageId, ProjectId};
+use crate::utils::{normalize_subject, reply_subject};
+use crate::{Error, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Serialize;
+
+/// Deepest nesting of MIME parts searched for the text body.
+const MAX_MIME_DEPTH: usize = 5;
+
+/// A parsed inbound mail.
+///
+/// # Fields
+///
+/// - `message_id` - The mail's `Message-ID`, without angle brackets
+/// - `from` - Sender address, lowercase
+/// - `to` - `To` and `Cc` addresses, lowercase
+/// - `references` - `In-Reply-To`, then `References` newest first
+/// - `body` - Reply text, without quoted text and signature
+/// - `importance` - `high` when the mail is flagged as important
+/// - `auto_submitted` - Sent by a machine (auto-reply, bulk or list mail)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InboundEmail {
+    pub message_id: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub references: Vec<String>,
+    pub body: String,
+    pub importance: Option<String>,
+    pub auto_submitted: bool,
+}
+
+impl InboundEmail {
+    /// Parses a raw RFC 5322 mail.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` when the mail has no `From` address or no
+    /// `text/plain` body.
+    pub fn parse(raw: &[u8]) -> Result<Self> {
+        let (head, body) = split_head(raw);
+        let headers = parse_headers(&String::from_utf8_lossy(head));
+
+        let from = header(&headers, "from")
+            .and_then(|v| addresses(v).into_iter().next())
+            .ok_or_else(|| Error::InvalidInput("The mail has no From address".to_string()))?;
+        let mut to = Vec::new();
+        for name in ["to", "cc"] {
+            for value in headers.iter().filter(|(n, _)| n == name) {
+                to.extend(addresses(&value.1));
+            }
+        }
+
+        let mut references = header(&headers, "in-reply-to")
+            .map(angle_ids)
+            .unwrap_or_default();
+        let mut earlier = header(&headers, "references")
+            .map(angle_ids)
+            .unwrap_or_default();
+        earlier.reverse();
+        for id in earlier {
+            if !references.contains(&id) {
+                references.push(id);
+            }
+        }
+
+        let text = text_body(&headers, body, 0)
+            .ok_or_else(|| Error::InvalidInput("The mail has no text/plain body".to_string()))?;
+
+        let flagged = header(&headers, "importance")
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("high"))
+            || header(&headers, "x-priority")
+                .is_some_and(|v| matches!(v.trim().chars().next(), Some('1' | '2')));
+        let auto_submitted = header(&headers, "auto-submitted").is_some_and(|v| {
+            let token = header_token(v);
+            !token.is_empty() && !token.eq_ignore_ascii_case("no")
+        }) || header(&headers, "precedence").is_some_and(|v| {
+            matches!(
+                header_token(v).to_ascii_lowercase().as_str(),
+                "bulk" | "junk" | "list" | "auto_reply"
+            )
+        });
+
+        Ok(Self {
+            message_id: header(&headers, "message-id")
+                .and_then(|v| angle_ids(v).into_iter().next()),
+            from,
+            to,
+            subject: header(&headers, "subject")
+                .map(decode_words)
+                .unwrap_or_default(),
+            references,
+            body: reply_text(&text),
+            importance: flagged.then(|| "high".to_string()),
+            auto_submitted,
+        })
+    }
+}
+
+/// Where an ingested mail was posted.
+///
+/// `duplicate` marks a `Message-ID` that was ingested before; nothing new
+/// was posted and the fields describe the earlier message. `skipped` gives
+/// the reason a mail was not posted at all.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IngestedEmail {
+    pub message_id: Option<i64>,
+    pub project_slug: Option<String>,
+    pub thread_id: Option<String>,
+    pub duplicate: bool,
+    pub skipped: Option<String>,
+}
+
+/// Parses the `inbound_aliases` table: comma-separated `address=agent`
+/// pairs.
+///
+/// # Returns
+/// `(address, agent)` pairs with lowercase addresses.
+///
+/// # Errors
+/// Returns `InvalidInput` for a pair without `=`, a malformed address or a
+/// blank agent name.
+pub fn parse_aliases(list: &str) -> Result<Vec<(String, String)>> {
+    let mut aliases = Vec::new();
+    for pair in list.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let invalid = || {
+            Error::InvalidInput(format!(
+                "Invalid inbound alias '{}': expected address=agent",
+                pair
+            ))
+        };
+        let (address, agent) = pair.split_once('=').ok_or_else(invalid)?;
+        let agent = agent.trim();
+        match parse_addresses(address)?.as_slice() {
+            [address] if !agent.is_empty() => {
+                aliases.push((address.to_ascii_lowercase(), agent.to_string()));
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(aliases)
+}
+
+/// Project and thread an inbound mail is posted to; no thread starts one.
+struct Placement {
+    project_id: i64,
+    project_slug: String,
+    thread_id: Option<String>,
+}
+
+/// Backend Model Controller for inbound mail.
+pub struct EmailIngestBmc;
+
+impl EmailIngestBmc {
+    /// Posts an inbound mail as a message from the agent its sender maps to.
+    ///
+    /// # Errors
+    /// - `ContactDenied` when the sender has no inbound alias
+    /// - `InvalidInput` when the project cannot be told, or the mail has no
+    ///   reply text or no recipients
+    /// - `ProjectNotFound`/`AgentNotFound` for an unknown subject tag or an
+    ///   alias naming no agent of the project
+    pub async fn ingest(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        email: &InboundEmail,
+    ) -> Result<IngestedEmail> {
+        if email.auto_submitted {
+            return Ok(IngestedEmail {
+                skipped: Some("auto-submitted mail".to_string()),
+                ..Default::default()
+            });
+        }
+        // Checked first, so an unknown sender can't learn of ingested mail
+        let aliases = parse_aliases(&mm.app_config.email_bridge.inbound_aliases)?;
+        let sender_name = alias(&aliases, &email.from)
+            .ok_or_else(|| Error::ContactDenied(format!("No inbound alias for {}", email.from)))?;
+        if let Some(id) = &email.message_id
+            && let Some((placement, message_id)) = Self::ingested(mm, id).await?
+        {
+            return Ok(IngestedEmail {
+                message_id: Some(message_id),
+                project_slug: Some(placement.project_slug),
+                thread_id: placement.thread_id,
+                duplicate: true,
+                skipped: None,
+            });
+        }
+        if email.body.is_empty() {
+            return Err(Error::InvalidInput(
+                "The mail has no reply text".to_string(),
+            ));
+        }
+
+        let placement = Self::placement(ctx, mm, email).await?;
+        let project_id = ProjectId::new(placement.project_id);
+        let sender = AgentBmc::get_by_name(ctx, mm, project_id, sender_name).await?;
+
+        let mut recipient_ids = match &placement.thread_id {
+            Some(thread_id) => {
+                MessageBmc::thread_participants(
+                    ctx,
+                    mm,
+                    ProjectId::new(placement.project_id),
+                    thread_id,
+                )
+                .await?
+            }
+            None => Vec::new(),
+        };
+        for address in &email.to {
+            let Some(name) = alias(&aliases, address) else {
+                continue;
+            };
+            match AgentBmc::get_by_name(ctx, mm, project_id, name).await {
+                Ok(agent) => recipient_ids.push(agent.id),
+                Err(Error::AgentNotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        recipient_ids.retain(|id| *id != sender.id);
+        recipient_ids.sort_unstable();
+        recipient_ids.dedup();
+        if recipient_ids.is_empty() {
+            return Err(Error::InvalidInput(
+                "No recipients: reply to a thread, or address an agent's alias".to_string(),
+            ));
+        }
+
+        let subject = match subject_tag(&email.subject) {
+            Some((tag, rest)) if tag.eq_ignore_ascii_case(&placement.project_slug) => rest,
+            _ => normalize_subject(&email.subject),
+        };
+        let subject = match (&placement.thread_id, subject.is_empty()) {
+            (Some(_), _) => reply_subject(&subject),
+            (None, true) => "(no subject)".to_string(),
+            (None, false) => subject,
+        };
+
+        let msg_c = MessageForCreate {
+            project_id,
+            sender_id: sender.id,
+            recipient_ids,
+            cc_ids: None,
+            bcc_ids: None,
+            subject,
+            body_md: email.body.clone(),
+            thread_id: placement.thread_id,
+            importance: email.importance.clone(),
+            ack_required: false,
+        };
+        let message_id = MessageBmc::create(ctx, mm, msg_c).await?;
+        let thread_id = MessageBmc::get(ctx, mm, message_id).await?.thread_id;
+
+        if let Some(id) = &email.message_id {
+            let stmt = mm
+                .db()
+                .prepare(
+                    r#"
+                    INSERT INTO inbound_emails (email_message_id, project_id, message_id)
+                    VALUES (?, ?, ?)
+                    ON CONFLICT(email_message_id) DO UPDATE SET
+                        project_id = excluded.project_id,
+                        message_id = excluded.message_id
+                    "#,
+                )
+                .await?;
+            stmt.execute((id.as_str(), placement.project_id, message_id.get()))
+                .await?;
+        }
+
+        Ok(IngestedEmail {
+            message_id: Some(message_id.get()),
+            project_slug: Some(placement.project_slug),
+            thread_id,
+            duplicate: false,
+            skipped: None,
+        })
+    }
+
+    /// Finds the project and thread of a mail from the mail it replies to,
+    /// or else from the `[project]` tag of its subject.
+    async fn placement(ctx: &Ctx, mm: &ModelManager, email: &InboundEmail) -> Result<Placement> {
+        let domain = mm.app_config.email_bridge.message_id_domain();
+        for reference in &email.references {
+            if let Some(placement) = Self::bridged(ctx, mm, reference, domain).await? {
+                return Ok(placement);
+            }
+            if let Some((placement, _)) = Self::ingested(mm, reference).await? {
+                return Ok(placement);
+            }
+        }
+
+        let Some((slug, _)) = subject_tag(&email.subject) else {
+            return Err(Error::InvalidInput(
+                "Cannot tell the project: reply to a bridged mail, or start the subject with [project]"
+                    .to_string(),
+            ));
+        };
+        let project = ProjectBmc::get_by_slug(ctx, mm, &slug).await?;
+        Ok(Placement {
+            project_id: project.id.get(),
+            project_slug: project.slug,
+            thread_id: None,
+        })
+    }
+
+    /// Resolves a `Message-ID` of bridged mail: `thread-<key>.<slug>@domain`
+    /// for the first message of a thread, `msg-<id>.<slug>@domain` for later
+    /// ones.
+    async fn bridged(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        reference: &str,
+        domain: &str,
+    ) -> Result<Option<Placement>> {
+        let Some((local, ref_domain)) = reference.rsplit_once('@') else {
+            return Ok(None);
+        };
+        let Some((token, slug)) = local.rsplit_once('.') else {
+            return Ok(None);
+        };
+        if !ref_domain.eq_ignore_ascii_case(domain) {
+            return Ok(None);
+        }
+        let project = match ProjectBmc::get_by_slug(ctx, mm, slug).await {
+            Ok(project) => project,
+            Err(Error::ProjectNotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let thread_id = if let Some(id) = token.strip_prefix("msg-").and_then(|id| id.parse().ok())
+        {
+            match MessageBmc::get(ctx, mm, MessageId::new(id)).await {
+                Ok(message) if message.project_id == project.id.get() => message.thread_id,
+                Ok(_) | Err(Error::MessageNotFound(_)) => None,
+                Err(e) => return Err(e),
+            }
+        } else if let Some(key) = token.strip_prefix("thread-") {
+            Self::thread_for_key(mm, project.id.get(), key).await?
+        } else {
+            None
+        };
+        Ok(thread_id.map(|thread_id| Placement {
+            project_id: project.id.get(),
+            project_slug: project.slug,
+            thread_id: Some(thread_id),
+        }))
+    }
+
+    /// The thread whose [`message_id_part`] is `key`.
+    ///
+    /// Every replaced character of a key became `-`, which the `_` wildcard
+    /// of `LIKE` matches, so the candidates are checked exactly here.
+    async fn thread_for_key(
+        mm: &ModelManager,
+        project_id: i64,
+        key: &str,
+    ) -> Result<Option<String>> {
+        let stmt = mm
+            .db()
+            .prepare(
+                "SELECT DISTINCT thread_id FROM messages WHERE project_id = ? AND thread_id LIKE ?",
+            )
+            .await?;
+        let mut rows = stmt.query((project_id, key.replace('-', "_"))).await?;
+        while let Some(row) = rows.next().await? {
+            let thread_id: String = row.get(0)?;
+            if message_id_part(thread_id.trim()) == key {
+                return Ok(Some(thread_id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Where a previously ingested mail was posted, and its message.
+    async fn ingested(
+        mm: &ModelManager,
+        email_message_id: &str,
+    ) -> Result<Option<(Placement, i64)>> {
+        let stmt = mm
+            .db()
+            .prepare(
+                r#"
+                SELECT m.project_id, p.slug, m.thread_id, m.id
+                FROM inbound_emails AS ie
+                JOIN messages AS m ON m.id = ie.message_id
+                JOIN projects AS p ON p.id = m.project_id
+                WHERE ie.email_message_id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([email_message_id]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some((
+                Placement {
+                    project_id: row.get(0)?,
+                    project_slug: row.get(1)?,
+                    thread_id: row.get(2)?,
+                },
+                row.get(3)?,
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The agent an address maps to.
+fn alias<'a>(aliases: &'a [(String, String)], address: &str) -> Option<&'a str> {
+    aliases
+        .iter()
+        .find(|(a, _)| a.eq_ignore_ascii_case(address))
+        .map(|(_, agent)| agent.as_str())
+}
+
+/// The `[tag]` a subject starts with (after reply prefixes), and the rest
+/// of the subject.
+fn subject_tag(subject: &str) -> Option<(String, String)> {
+    let subject = normalize_subject(subject);
+    let (tag, rest) = subject.strip_prefix('[')?.split_once(']')?;
+    let tag = tag.trim();
+    (!tag.is_empty()).then(|| (tag.to_string(), normalize_subject(rest)))
+}
+
+/// Splits a mail at the blank line ending its headers.
+fn split_head(raw: &[u8]) -> (&[u8], &[u8]) {
+    let crlf = raw.windows(4).position(|w| w == b"\r\n\r\n");
+    let lf = raw.windows(2).position(|w| w == b"\n\n");
+    match (crlf, lf) {
+        (Some(c), Some(l)) if l < c => (&raw[..l], &raw[l + 2..]),
+        (Some(c), _) => (&raw[..c], &raw[c + 4..]),
+        (None, Some(l)) => (&raw[..l], &raw[l + 2..]),
+        (None, None) => (raw, &[]),
+    }
+}
+
+/// Unfolded headers as `(lowercase name, value)`, in order.
+fn parse_headers(head: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_str())
+}
+
+/// A header value up to its first parameter.
+fn header_token(value: &str) -> &str {
+    value.split(';').next().unwrap_or_default().trim()
+}
+
+/// The `<...>` identifiers of a header value.
+fn angle_ids(value: &str) -> Vec<String> {
+    value
+        .split('<')
+        .skip(1)
+        .filter_map(|part| part.split_once('>'))
+        .map(|(id, _)| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// The addresses of an address list header, lowercase.
+///
+/// Commas inside quoted display names and angle brackets do not separate
+/// addresses; groups without addresses are dropped.
+fn addresses(value: &str) -> Vec<String> {
+    let mut list = Vec::new();
+    let mut mailbox = String::new();
+    let mut quoted = false;
+    let mut angle = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' if !quoted && !angle => {
+                list.extend(address(&mailbox));
+                mailbox.clear();
+                continue;
+            }
+            _ => {}
+        }
+        mailbox.push(c);
+    }
+    list.extend(address(&mailbox));
+    list
+}
+
+fn address(mailbox: &str) -> Option<String> {
+    let address = match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox.split_whitespace().find(|w| w.contains('@'))?,
+    };
+    let address = address.trim();
+    address.contains('@').then(|| address.to_ascii_lowercase())
+}
+
+/// The `text/plain` body of a mail or MIME part, decoded; the first one
+/// found in multipart content.
+fn text_body(headers: &[(String, String)], body: &[u8], depth: usize) -> Option<String> {
+    let content_type = header(headers, "content-type").unwrap_or("text/plain");
+    let mime = header_token(content_type).to_ascii_lowercase();
+
+    if mime.starts_with("multipart/") {
+        let boundary = header_param(content_type, "boundary")?;
+        if depth >= MAX_MIME_DEPTH {
+            return None;
+        }
+        return mime_parts(body, &boundary).into_iter().find_map(|part| {
+            let (head, body) = split_head(part);
+            text_body(
+                &parse_headers(&String::from_utf8_lossy(head)),
+                body,
+                depth + 1,
+            )
+        });
+    }
+    if mime != "text/plain" {
+        return None;
+    }
+    let encoding = header(headers, "content-transfer-encoding")
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let bytes = match encoding.as_str() {
+        "base64" => {
+            let compact: Vec<u8> = body
+                .iter()
+                .copied()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect();
+            STANDARD.decode(compact).ok()?
+        }
+        "quoted-printable" => decode_quoted_printable(body, false),
+        _ => body.to_vec(),
+    };
+    let charset = header_param(content_type, "charset").unwrap_or_default();
+    Some(decode_charset(&bytes, &charset))
+}
+
+/// A parameter of a structured header value such as `Content-Type`.
+fn header_param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, val) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| val.trim().trim_matches('"').to_string())
+    })
+}
+
+/// The parts of a multipart body, between its `--boundary` lines.
+fn mime_parts<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive(|b| *b == b'\n') {
+        let text = line.trim_ascii_end();
+        if text.starts_with(delimiter.as_bytes()) {
+            if let Some(s) = start {
+                parts.push(&body[s..offset]);
+            }
+            if text == format!("{}--", delimiter).as_bytes() {
+                return parts;
+            }
+            start = Some(offset + line.len());
+        }
+        offset += line.len();
+    }
+    if let Some(s) = start {
+        parts.push(&body[s..]);
+    }
+    parts
+}
+
+/// Decodes quoted-printable content; `header` also reads `_` as a space
+/// (RFC 2047 `Q` encoding).
+fn decode_quoted_printable(input: &[u8], header: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'=' if input[i + 1..].starts_with(b"\r\n") => i += 3,
+            b'=' if input[i + 1..].starts_with(b"\n") => i += 2,
+            b'=' => {
+                let hex = input
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 3;
+                    }
+                    None => {
+                        out.push(b'=');
+                        i += 1;
+                    }
+                }
+            }
+            b'_' if header => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Text in a charset: Latin-1 and Windows-1252 byte for byte, everything
+/// else as UTF-8.
+fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    match charset.trim().to_ascii_lowercase().as_str() {
+        "iso-8859-1" | "latin1" | "windows-1252" => bytes.iter().map(|b| char::from(*b)).collect(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Decodes the RFC 2047 encoded words of a header value; whitespace between
+/// adjacent encoded words is dropped.
+fn decode_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, candidate) = rest.split_at(start);
+        match decode_word(candidate) {
+            Some((text, len)) => {
+                if !(after_word && before.trim().is_empty()) {
+                    out.push_str(before);
+                }
+                out.push_str(&text);
+                rest = &candidate[len..];
+                after_word = true;
+            }
+            None => {
+                out.push_str(before);
+                out.push_str("=?");
+                rest = &candidate[2..];
+                after_word = false;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Decodes the encoded word `s` starts with.
+///
+/// # Returns
+/// The text and the length of the encoded word.
+fn decode_word(s: &str) -> Option<(String, usize)> {
+    let inner = s.strip_prefix("=?")?;
+    let (charset, inner) = inner.split_once('?')?;
+    let (encoding, inner) = inner.split_once('?')?;
+    let end = inner.find("?=")?;
+    let text = &inner[..end];
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+    let bytes = match encoding {
+        "B" | "b" => STANDARD.decode(text).ok()?,
+        "Q" | "q" => decode_quoted_printable(text.as_bytes(), true),
+        _ => return None,
+    };
+    let len = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+    let charset = charset.split('*').next().unwrap_or_default();
+    Some((decode_charset(&bytes, charset), len))
+}
+
+/// The reply text of a mail body: lines up to an attribution line
+/// (`On ... wrote:`), an `-----Original Message-----` separator or a `-- `
+/// signature, without `>` quoted lines.
+fn reply_text(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut kept = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        let attribution = trimmed.starts_with("On ")
+            && (trimmed.ends_with("wrote:")
+                || lines
+                    .get(i + 1)
+                    .is_some_and(|next| next.trim().ends_with("wrote:")));
+        if *line == "-- " || trimmed == "-----Original Message-----" || attribution {
+            break;
+        }
+        if !trimmed.starts_with('>') {
+            kept.push(*line);
+        }
+    }
+    kept.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply_to_bridged_mail() {
+        let raw = concat!(
+            "Return-Path: <lead@example.com>\r\n",
+            "From: \"Lead, Team\" <Lead@Example.com>\r\n",
+            "To: Mouchak <bridge@example.com>, \"Ops, Eu\" <ops@example.com>\r\n",
+            "Cc: undisclosed-recipients:;\r\n",
+            "Subject: Re: [backend] =?UTF-8?B?U3TDtnJ1bmc=?=\r\n",
+            " =?UTF-8?Q?_im_RZ?=\r\n",
+            "Message-ID: <reply-1@mail.example.com>\r\n",
+            "In-Reply-To: <msg-42.backend@example.com>\r\n",
+            "References: <thread-T-1.backend@example.com>\r\n",
+            "\t<msg-42.backend@example.com>\r\n",
+            "Importance: High\r\n",
+            "\r\n",
+            "Restarting the database now.\r\n",
+            "\r\n",
+            "On Sat, 1 Mar 2025 at 12:00, BlueLake via Mouchak Mail\r\n",
+            "<bridge@example.com> wrote:\r\n",
+            "> Database is gone.\r\n",
+        );
+        let email = InboundEmail::parse(raw.as_bytes()).unwrap();
+        assert_eq!(email.from, "lead@example.com");
+        assert_eq!(email.to, vec!["bridge@example.com", "ops@example.com"]);
+        assert_eq!(email.subject, "Re: [backend] Störung im RZ");
+        assert_eq!(
+            email.message_id.as_deref(),
+            Some("reply-1@mail.example.com")
+        );
+        assert_eq!(
+            email.references,
+            vec![
+                "msg-42.backend@example.com",
+                "thread-T-1.backend@example.com"
+            ]
+        );
+        assert_eq!(email.body, "Restarting the database now.");
+        assert_eq!(email.importance.as_deref(), Some("high"));
+        assert!(!email.auto_submitted);
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let raw = concat!(
+            "From: lead@example.com\n",
+            "Subject: [backend] Deploy\n",
+            "Auto-Submitted: no\n",
+            "Content-Type: multipart/mixed; boundary=\"outer\"\n",
+            "\n",
+            "preamble\n",
+            "--outer\n",
+            "Content-Type: multipart/alternative; boundary=inner\n",
+            "\n",
+            "--inner\n",
+            "Content-Type: text/html\n",
+            "\n",
+            "<p>Ship it</p>\n",
+            "--inner\n",
+            "Content-Type: text/plain; charset=iso-8859-1\n",
+            "Content-Transfer-Encoding: quoted-printable\n",
+            "\n",
+            "Ship it, the tests are gr=FCn and this line is =\n",
+            "soft-wrapped.\n",
+            "> quoted\n",
+            "-- \n",
+            "Lead\n",
+            "--inner--\n",
+            "--outer\n",
+            "Content-Type: text/plain\n",
+            "Content-Disposition: attachment\n",
+            "\n",
+            "attached\n",
+            "--outer--\n",
+        );
+        let email = InboundEmail::parse(raw.as_bytes()).unwrap();
+        assert_eq!(
+            email.body,
+            "Ship it, the tests are grün and this line is soft-wrapped."
+        );
+        assert!(email.references.is_empty());
+        assert!(email.message_id.is_none());
+        assert!(email.importance.is_none());
+        assert!(!email.auto_submitted);
+    }
+
+    #[test]
+    fn test_parse_base64_and_auto_submitted() {
+        let body = STANDARD.encode("Out of office until Monday.\r\n");
+        let raw = format!(
+            "From: lead@example.com\r\nAuto-Submitted: auto-replied\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+            body
+        );
+        let email = InboundEmail::parse(raw.as_bytes()).unwrap();
+        assert_eq!(email.body, "Out of office until Monday.");
+        assert!(email.auto_submitted);
+
+        let email =
+            InboundEmail::parse(b"From: list@example.com\nPrecedence: bulk\n\nDigest").unwrap();
+        assert!(email.auto_submitted);
+
+        for bad in [
+            &b"Subject: no sender\n\nHello"[..],
+            b"From: lead@example.com\nContent-Type: text/html\n\n<p>Hi</p>",
+        ] {
+            assert!(matches!(
+                InboundEmail::parse(bad),
+                Err(Error::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_aliases() {
+        assert_eq!(
+            parse_aliases(" Lead@Example.com = BlueLake, ,ops@example.com=GreenCastle").unwrap(),
+            vec![
+                ("lead@example.com".to_string(), "BlueLake".to_string()),
+                ("ops@example.com".to_string(), "GreenCastle".to_string()),
+            ]
+        );
+        assert!(parse_aliases("").unwrap().is_empty());
+        for bad in ["lead@example.com", "lead=BlueLake", "lead@example.com= "] {
+            assert!(parse_aliases(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_subject_tag() {
+        assert_eq!(
+            subject_tag("Re: [backend]  Re: Deploy"),
+            Some(("backend".to_string(), "Deploy".to_string()))
+        );
+        assert_eq!(subject_tag("Deploy [backend]"), None);
+        assert_eq!(subject_tag("[] Deploy"), None);
+    }
+
+    #[test]
+    fn test_decode_words() {
+        assert_eq!(
+            decode_words("=?utf-8?q?caf=C3=A9_time?= now"),
+            "café time now"
+        );
+        assert_eq!(decode_words("a =?x?z?bad?= b"), "a =?x?z?bad?= b");
+        assert_eq!(decode_words("=?ISO-8859-1*de?B?Z3L8bg==?="), "grün");
+    }
+}
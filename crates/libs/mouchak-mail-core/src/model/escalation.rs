@@ -6,6 +6,17 @@
 //! - **Log mode**: Log a warning for human review
 //! - **File reservation mode**: Create a lock to draw attention
 //! - **Overseer mode**: Send an urgent message to human oversight
+//! - **Remind mode**: Send one reminder per message to the recipients that
+//!   have not acknowledged yet
+//!
+//! Recipients that agreed a reply deadline (see
+//! [`crate::model::reply_deadline`]) become overdue when it passes instead
+//! of after the threshold.
+//!
+//! Messages of projects with business hours are only escalated during them
+//! (see [`crate::model::project_settings`]); outside them they wait for the
+//! next run. Reminders and overseer messages are written in the project's
+//! locale (see [`crate::model::message_catalog`]).
 //!
 //! # Example
 //!
@@ -29,10 +40,19 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::model::broadcast_status::BroadcastStatusBmc;
 use crate::model::message::{MessageBmc, MessageForCreate, OverdueMessage};
+use crate::model::message_catalog::{Catalog, MessageCatalogBmc};
 use crate::model::overseer_message::{OverseerMessageBmc, OverseerMessageForCreate};
+use crate::model::project::ProjectBmc;
+use crate::model::project_settings::ProjectSettingsBmc;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::notifier::{self, Notification};
+use mouchak_mail_common::config::NotifierEvent;
 use serde::Serialize;
-use tracing::{info, warn};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info, warn};
 
 pub use mouchak_mail_common::config::EscalationMode;
 
@@ -87,20 +107,91 @@ impl EscalationBmc {
             "Processing overdue ACKs"
         );
 
+        // Remind mode covers all of a message's pending recipients at once
+        let mut reminded = HashSet::new();
+        let mut project_slugs = HashMap::new();
+        // Settings and message catalog of each project seen
+        let mut projects = HashMap::new();
+        let now = chrono::Utc::now().naive_utc();
         for msg in overdue {
+            if let Entry::Vacant(e) = projects.entry(msg.project_id) {
+                let settings =
+                    ProjectSettingsBmc::get(ctx, mm, ProjectId::new(msg.project_id)).await?;
+                let catalog = MessageCatalogBmc::for_locale(ctx, mm, &settings.locale).await?;
+                e.insert((settings, catalog));
+            }
+            let (settings, catalog) = &projects[&msg.project_id];
+            if !settings.is_business_time(now) {
+                debug!(
+                    message_id = msg.message_id,
+                    "Outside business hours, escalation deferred"
+                );
+                continue;
+            }
             let result = match mode {
                 EscalationMode::Log => Self::escalate_log(&msg, dry_run),
                 EscalationMode::FileReservation => {
                     Self::escalate_file_reservation(ctx, mm, &msg, dry_run).await
                 }
-                EscalationMode::Overseer => Self::escalate_overseer(ctx, mm, &msg, dry_run).await,
+                EscalationMode::Overseer => {
+                    Self::escalate_overseer(ctx, mm, &msg, catalog, dry_run).await
+                }
+                EscalationMode::Remind => {
+                    if !reminded.insert(msg.message_id) {
+                        continue;
+                    }
+                    Self::escalate_remind(ctx, mm, &msg, catalog, dry_run).await
+                }
             };
+            if result.success && !dry_run {
+                Self::notify_escalation(ctx, mm, &msg, &result, &mut project_slugs).await;
+            }
             results.push(result);
         }
 
         Ok(results)
     }
 
+    /// Runs the local notifier for an escalation that was carried out.
+    async fn notify_escalation(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        msg: &OverdueMessage,
+        result: &EscalationResult,
+        project_slugs: &mut HashMap<i64, String>,
+    ) {
+        let config = &mm.app_config.notifier;
+        if !config.notifies(NotifierEvent::Escalation) {
+            return;
+        }
+        let project = match project_slugs.entry(msg.project_id) {
+            Entry::Occupied(slug) => slug.get().clone(),
+            Entry::Vacant(slot) => {
+                let slug = ProjectBmc::get(ctx, mm, ProjectId::new(msg.project_id))
+                    .await
+                    .map(|p| p.slug)
+                    .unwrap_or_default();
+                slot.insert(slug).clone()
+            }
+        };
+        notifier::notify(
+            config,
+            &Notification {
+                event: NotifierEvent::Escalation,
+                title: format!("Escalation: {}", msg.subject),
+                body: format!(
+                    "Message {} from {} is unacknowledged by {} ({})",
+                    msg.message_id, msg.sender_name, msg.recipient_name, result.action_taken
+                ),
+                project,
+                sender: msg.sender_name.clone(),
+                subject: msg.subject.clone(),
+                message_id: Some(msg.message_id),
+                importance: "urgent".to_string(),
+            },
+        );
+    }
+
     fn escalate_log(msg: &OverdueMessage, dry_run: bool) -> EscalationResult {
         let action = if dry_run {
             "log_dry_run"
@@ -152,7 +243,7 @@ impl EscalationBmc {
             ctx,
             mm,
             crate::model::file_reservation::FileReservationForCreate {
-                project_id: crate::types::ProjectId::new(msg.project_id),
+                project_id: ProjectId::new(msg.project_id),
                 agent_id: crate::types::AgentId::new(msg.sender_id),
                 path_pattern: pattern.clone(),
                 exclusive: false,
@@ -184,6 +275,7 @@ impl EscalationBmc {
         ctx: &Ctx,
         mm: &ModelManager,
         msg: &OverdueMessage,
+        catalog: &Catalog,
         dry_run: bool,
     ) -> EscalationResult {
         if dry_run {
@@ -195,15 +287,15 @@ impl EscalationBmc {
             };
         }
 
-        let body = format!(
-            "**Overdue ACK Escalation**\n\n\
-             Message ID: {}\n\
-             Subject: {}\n\
-             From: {}\n\
-             To: {} (awaiting ACK)\n\
-             Created: {}\n\n\
-             This message has not been acknowledged within the configured TTL.",
-            msg.message_id, msg.subject, msg.sender_name, msg.recipient_name, msg.created_ts
+        let body = catalog.render(
+            "escalation.overseer.body",
+            &[
+                ("message_id", &msg.message_id.to_string()),
+                ("subject", &msg.subject),
+                ("sender", &msg.sender_name),
+                ("recipient", &msg.recipient_name),
+                ("created_ts", &msg.created_ts.to_string()),
+            ],
         );
 
         match OverseerMessageBmc::create(
@@ -212,7 +304,8 @@ impl EscalationBmc {
             OverseerMessageForCreate {
                 project_id: msg.project_id,
                 sender_id: msg.sender_id,
-                subject: format!("[OVERDUE ACK] {}", msg.subject),
+                subject: catalog
+                    .render("escalation.overseer.subject", &[("subject", &msg.subject)]),
                 body_md: body,
                 importance: "high".to_string(),
             },
@@ -234,10 +327,80 @@ impl EscalationBmc {
         }
     }
 
+    async fn escalate_remind(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        msg: &OverdueMessage,
+        catalog: &Catalog,
+        dry_run: bool,
+    ) -> EscalationResult {
+        let failed = |e: crate::Error| EscalationResult {
+            message_id: msg.message_id,
+            action_taken: "reminder_failed".to_string(),
+            success: false,
+            details: Some(format!("Error: {}", e)),
+        };
+
+        let status = match BroadcastStatusBmc::get(ctx, mm, MessageId::new(msg.message_id)).await {
+            Ok(status) => status,
+            Err(e) => return failed(e),
+        };
+        let names: Vec<&str> = status
+            .pending
+            .iter()
+            .map(|r| r.agent_name.as_str())
+            .collect();
+
+        if dry_run {
+            return EscalationResult {
+                message_id: msg.message_id,
+                action_taken: "reminder_dry_run".to_string(),
+                success: true,
+                details: Some(format!("Would remind: {}", names.join(", "))),
+            };
+        }
+
+        // The reminder itself does not require an ack: acknowledging the
+        // original message is what clears it from the next sweep.
+        let reminder = MessageForCreate {
+            project_id: ProjectId::new(msg.project_id),
+            sender_id: AgentId::new(msg.sender_id),
+            recipient_ids: status.pending.iter().map(|r| r.agent_id).collect(),
+            cc_ids: None,
+            bcc_ids: None,
+            subject: catalog.render("escalation.reminder.subject", &[("subject", &msg.subject)]),
+            body_md: catalog.render(
+                "escalation.reminder.body",
+                &[
+                    ("message_id", &msg.message_id.to_string()),
+                    ("acked", &status.acked.len().to_string()),
+                    ("total", &status.total_recipients.to_string()),
+                ],
+            ),
+            thread_id: status.thread_id.clone(),
+            importance: Some("high".to_string()),
+            ack_required: false,
+        };
+
+        match MessageBmc::create(ctx, mm, reminder).await {
+            Ok(id) => EscalationResult {
+                message_id: msg.message_id,
+                action_taken: "reminder_sent".to_string(),
+                success: true,
+                details: Some(format!(
+                    "Reminder message ID: {}, Reminded: {}",
+                    id,
+                    names.join(", ")
+                )),
+            },
+            Err(e) => failed(e),
+        }
+    }
+
     /// Sends a reminder message to the recipient of an overdue message.
     ///
-    /// Creates a new high-priority message with "REMINDER:" prefix that
-    /// references the original overdue message.
+    /// Creates a new high-priority message with a "REMINDER:" prefix that
+    /// references the original overdue message, in the project's locale.
     ///
     /// # Arguments
     ///
@@ -248,18 +411,23 @@ impl EscalationBmc {
     /// # Returns
     ///
     /// The ID of the newly created reminder message.
-    pub async fn send_reminder(ctx: &Ctx, mm: &ModelManager, msg: &OverdueMessage) -> Result<i64> {
+    pub async fn send_reminder(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        msg: &OverdueMessage,
+    ) -> Result<MessageId> {
+        let catalog =
+            MessageCatalogBmc::for_project(ctx, mm, ProjectId::new(msg.project_id)).await?;
         let reminder = MessageForCreate {
-            project_id: msg.project_id,
-            sender_id: msg.sender_id,
-            recipient_ids: vec![msg.recipient_id],
+            project_id: ProjectId::new(msg.project_id),
+            sender_id: AgentId::new(msg.sender_id),
+            recipient_ids: vec![AgentId::new(msg.recipient_id)],
             cc_ids: None,
             bcc_ids: None,
-            subject: format!("REMINDER: {}", msg.subject),
-            body_md: format!(
-                "[System Escalation] This message requires acknowledgment and is overdue.\n\n\
-                 Original message ID: {}",
-                msg.message_id
+            subject: catalog.render("escalation.reminder.subject", &[("subject", &msg.subject)]),
+            body_md: catalog.render(
+                "escalation.overdue.body",
+                &[("message_id", &msg.message_id.to_string())],
             ),
             thread_id: None,
             importance: Some("high".to_string()),
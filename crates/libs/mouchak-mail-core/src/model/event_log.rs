@@ -0,0 +1,334 @@
+//! Tamper-evident, append-only event log.
+//!
+//! Key actions (projects created, agents registered, messages sent and
+//! acknowledged, file reservations taken and released) are appended to the
+//! `event_log` table. Every record carries the SHA-256 hash of the previous
+//! record, so editing, inserting or deleting a historical record breaks the
+//! chain and is reported by [`verify_chain`].
+//!
+//! Like the Git archive, the log is written after the action itself and a
+//! failed append is logged rather than failing the action. Appended records
+//! are also published through the [event bridge](crate::utils::event_bridge)
+//! when one is configured, and broadcast on the in-process
+//! [event bus](crate::utils::event_bus).
+//!
+//! # Hashing
+//!
+//! A record's hash is the hex SHA-256 of the compact JSON array
+//! `[id, prev_hash, created_ts, kind, project_id, actor, payload]`, with
+//! `created_ts` formatted as `YYYY-MM-DD HH:MM:SS`. The first record's
+//! `prev_hash` is [`GENESIS_HASH`].
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::utils::parse_timestamp;
+use chrono::{NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// `prev_hash` of the first record in the chain.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Maximum number of records returned by one [`EventLogBmc::list`] call.
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+const TS_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Attempts to append before giving up when other writers keep winning.
+const APPEND_ATTEMPTS: usize = 5;
+
+/// One record of the event log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct EventRecord {
+    /// Position in the chain, starting at 1
+    pub id: i64,
+    pub created_ts: NaiveDateTime,
+    /// Event type, e.g. `message.sent`
+    pub kind: String,
+    pub project_id: Option<i64>,
+    /// Agent (or user) that caused the event, if known
+    pub actor: Option<String>,
+    #[schema(value_type = Object)]
+    pub payload: Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// An event to append.
+#[derive(Debug, Clone)]
+pub struct EventForCreate {
+    pub kind: String,
+    pub project_id: Option<i64>,
+    pub actor: Option<String>,
+    pub payload: Value,
+}
+
+impl EventForCreate {
+    pub fn new(kind: &str, project_id: Option<i64>, actor: Option<&str>, payload: Value) -> Self {
+        Self {
+            kind: kind.to_string(),
+            project_id,
+            actor: actor.map(str::to_string),
+            payload,
+        }
+    }
+}
+
+/// Result of verifying the hash chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ChainVerification {
+    pub valid: bool,
+    /// Number of records checked
+    pub records: i64,
+    /// Hash of the last valid record (anchor it elsewhere to detect truncation)
+    pub head_hash: String,
+    /// First record that fails verification
+    pub first_invalid_id: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// Computes the hash of a record from its contents.
+pub fn record_hash(
+    id: i64,
+    prev_hash: &str,
+    created_ts: &NaiveDateTime,
+    kind: &str,
+    project_id: Option<i64>,
+    actor: Option<&str>,
+    payload: &Value,
+) -> String {
+    let canonical = serde_json::json!([
+        id,
+        prev_hash,
+        created_ts.format(TS_FORMAT).to_string(),
+        kind,
+        project_id,
+        actor,
+        payload
+    ]);
+    hex::encode(Sha256::digest(canonical.to_string().as_bytes()))
+}
+
+/// Verifies a sequence of records in chain order.
+///
+/// Checks that ids are contiguous from 1, that each `prev_hash` matches the
+/// previous record's hash and that each stored hash matches the record's
+/// contents. Works on records read from the database or from an export.
+pub fn verify_chain<'a>(records: impl IntoIterator<Item = &'a EventRecord>) -> ChainVerification {
+    let mut head_hash = GENESIS_HASH.to_string();
+    let mut count = 0;
+
+    for record in records {
+        let expected_id = count + 1;
+        let error = if record.id != expected_id {
+            Some(format!(
+                "expected record {} but found {} (records missing or inserted)",
+                expected_id, record.id
+            ))
+        } else if record.prev_hash != head_hash {
+            Some(format!(
+                "record {} does not link to the previous record",
+                record.id
+            ))
+        } else if record.hash
+            != record_hash(
+                record.id,
+                &record.prev_hash,
+                &record.created_ts,
+                &record.kind,
+                record.project_id,
+                record.actor.as_deref(),
+                &record.payload,
+            )
+        {
+            Some(format!("record {} was modified", record.id))
+        } else {
+            None
+        };
+
+        if let Some(error) = error {
+            return ChainVerification {
+                valid: false,
+                records: count,
+                head_hash,
+                first_invalid_id: Some(record.id),
+                error: Some(error),
+            };
+        }
+        head_hash = record.hash.clone();
+        count += 1;
+    }
+
+    ChainVerification {
+        valid: true,
+        records: count,
+        head_hash,
+        first_invalid_id: None,
+        error: None,
+    }
+}
+
+/// Backend Model Controller for the event log.
+pub struct EventLogBmc;
+
+impl EventLogBmc {
+    /// Appends an event to the chain.
+    ///
+    /// Concurrent writers (e.g. the HTTP and MCP servers sharing a database)
+    /// race for the next id; the loser re-reads the head and retries.
+    ///
+    /// # Errors
+    /// Database errors, or `InvalidInput` if the append keeps losing races.
+    pub async fn append(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        event: EventForCreate,
+    ) -> Result<EventRecord> {
+        let db = mm.db();
+        let payload_json = serde_json::to_string(&event.payload)?;
+
+        for _ in 0..APPEND_ATTEMPTS {
+            let stmt = db
+                .prepare("SELECT id, hash FROM event_log ORDER BY id DESC LIMIT 1")
+                .await?;
+            let mut rows = stmt.query(()).await?;
+            let (prev_id, prev_hash) = match rows.next().await? {
+                Some(row) => (row.get::<i64>(0)?, row.get::<String>(1)?),
+                None => (0, GENESIS_HASH.to_string()),
+            };
+
+            let id = prev_id + 1;
+            // Stored with second precision, so hash the truncated timestamp
+            let now = chrono::Utc::now().naive_utc();
+            let created_ts = now.with_nanosecond(0).unwrap_or(now);
+            let hash = record_hash(
+                id,
+                &prev_hash,
+                &created_ts,
+                &event.kind,
+                event.project_id,
+                event.actor.as_deref(),
+                &event.payload,
+            );
+
+            let stmt = db
+                .prepare(
+                    r#"
+                INSERT OR IGNORE INTO event_log
+                    (id, created_ts, kind, project_id, actor, payload, prev_hash, hash)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                "#,
+                )
+                .await?;
+            let inserted = stmt
+                .execute((
+                    id,
+                    created_ts.format(TS_FORMAT).to_string(),
+                    event.kind.as_str(),
+                    event.project_id,
+                    event.actor.as_deref(),
+                    payload_json.as_str(),
+                    prev_hash.as_str(),
+                    hash.as_str(),
+                ))
+                .await?;
+
+            if inserted > 0 {
+                let record = EventRecord {
+                    id,
+                    created_ts,
+                    kind: event.kind,
+                    project_id: event.project_id,
+                    actor: event.actor,
+                    payload: event.payload,
+                    prev_hash,
+                    hash,
+                };
+                if let Some(bridge) = &mm.event_bridge {
+                    bridge.publish(&record);
+                }
+                mm.event_bus.publish(&record);
+                return Ok(record);
+            }
+        }
+
+        Err(crate::Error::InvalidInput(
+            "Event log append kept conflicting with concurrent writers".into(),
+        ))
+    }
+
+    /// Appends an event, logging instead of failing if the append fails.
+    pub async fn record(ctx: &Ctx, mm: &ModelManager, event: EventForCreate) {
+        let kind = event.kind.clone();
+        if let Err(e) = Self::append(ctx, mm, event).await {
+            warn!("Failed to append {} to event log: {}", kind, e);
+        }
+    }
+
+    /// Lists records in chain order, starting after `after_id`.
+    ///
+    /// `limit` is capped at [`MAX_PAGE_SIZE`].
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<EventRecord>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT id, created_ts, kind, project_id, actor, payload, prev_hash, hash
+            FROM event_log
+            WHERE id > ?
+            ORDER BY id ASC
+            LIMIT ?
+            "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((after_id, limit.clamp(1, MAX_PAGE_SIZE)))
+            .await?;
+
+        let mut records = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(1)?;
+            let payload: String = row.get(5)?;
+            records.push(EventRecord {
+                id: row.get(0)?,
+                created_ts: parse_timestamp(&created_ts, "event_log.created_ts"),
+                kind: row.get(2)?,
+                project_id: row.get(3)?,
+                actor: row.get(4)?,
+                // Unparseable payloads become null, which then fails verification
+                payload: serde_json::from_str(&payload).unwrap_or(Value::Null),
+                prev_hash: row.get(6)?,
+                hash: row.get(7)?,
+            });
+        }
+        Ok(records)
+    }
+
+    /// Returns the whole chain in order.
+    pub async fn export(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<EventRecord>> {
+        let mut records = Vec::new();
+        loop {
+            let after_id = records.last().map_or(0, |r: &EventRecord| r.id);
+            let page = Self::list(ctx, mm, after_id, MAX_PAGE_SIZE).await?;
+            let done = (page.len() as i64) < MAX_PAGE_SIZE;
+            records.extend(page);
+            if done {
+                return Ok(records);
+            }
+        }
+    }
+
+    /// Verifies the stored chain.
+    pub async fn verify(ctx: &Ctx, mm: &ModelManager) -> Result<ChainVerification> {
+        let records = Self::export(ctx, mm).await?;
+        Ok(verify_chain(&records))
+    }
+}
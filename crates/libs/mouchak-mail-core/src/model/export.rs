@@ -1,13 +1,31 @@
 //! Export functionality for mailbox data
 //!
-//! Supports exporting messages in HTML, JSON, and Markdown formats.
+//! Supports exporting messages in HTML, JSON, Markdown and CSV formats, and
+//! as RFC 5322 mail (an mbox mailbox, or one `.eml` file per message) for
+//! standard mail tooling and e-discovery systems.
 
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
-use crate::model::message::MessageBmc;
+use crate::model::agent::AgentBmc;
+use crate::model::message::{Message, MessageBmc};
 use crate::model::project::ProjectBmc;
+use crate::model::time_travel::parse_timestamp;
+use crate::types::ProjectId;
+use crate::utils::body_format::highlight_css;
+use crate::utils::diagram::markdown_to_html_with_diagrams;
+use crate::utils::identicon::identicon_data_uri;
+use crate::utils::rfc5322::{EmailAttachment, EmailMessage, media_type_for};
+use crate::utils::{TS_FORMAT, parse_timestamp as parse_db_timestamp};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Maximum number of messages in an export, newest first.
+const EXPORT_MESSAGE_LIMIT: i64 = 100;
+
+/// Edge length of sender avatars in HTML exports, in pixels.
+const EXPORT_AVATAR_SIZE: u32 = 32;
 
 /// Export format options
 /// Export format options.
@@ -23,18 +41,64 @@ pub enum ExportFormat {
     Markdown,
     /// Comma-separated values
     Csv,
+    /// `mboxrd` mailbox of RFC 5322 messages, oldest first
+    Mbox,
 }
 
 impl std::str::FromStr for ExportFormat {
     type Err = std::convert::Infallible;
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        Ok(match s.to_lowercase().as_str() {
-            "html" => Self::Html,
-            "md" | "markdown" => Self::Markdown,
-            "csv" => Self::Csv,
-            _ => Self::Json, // default
-        })
+        Ok(Self::from_name(s).unwrap_or(Self::Json))
+    }
+}
+
+impl ExportFormat {
+    /// Format of a name, case-insensitive, or `None` for an unknown name.
+    ///
+    /// `from_str` falls back to JSON for unknown names instead.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "html" => Some(Self::Html),
+            "json" => Some(Self::Json),
+            "md" | "markdown" => Some(Self::Markdown),
+            "csv" => Some(Self::Csv),
+            "mbox" => Some(Self::Mbox),
+            _ => None,
+        }
+    }
+
+    /// Name of the format, as accepted by `from_str`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Json => "json",
+            Self::Markdown => "markdown",
+            Self::Csv => "csv",
+            Self::Mbox => "mbox",
+        }
+    }
+
+    /// File extension of exports in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Json => "json",
+            Self::Markdown => "md",
+            Self::Csv => "csv",
+            Self::Mbox => "mbox",
+        }
+    }
+
+    /// Media type of exports in this format.
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Self::Html => "text/html",
+            Self::Json => "application/json",
+            Self::Markdown => "text/markdown",
+            Self::Csv => "text/csv",
+            Self::Mbox => "application/mbox",
+        }
     }
 }
 
@@ -55,6 +119,14 @@ pub struct ExportedMailbox {
 use lazy_static::lazy_static;
 use regex::Regex;
 
+/// One message rendered as a standalone `.eml` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmlFile {
+    /// `{message_id}.eml`
+    pub filename: String,
+    pub content: String,
+}
+
 /// Scrubbing mode for privacy protection
 /// Scrubbing mode for privacy protection.
 ///
@@ -74,11 +146,85 @@ impl std::str::FromStr for ScrubMode {
     type Err = std::convert::Infallible;
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        Ok(match s.to_lowercase().as_str() {
-            "standard" => Self::Standard,
-            "aggressive" | "strict" => Self::Aggressive,
-            _ => Self::None,
-        })
+        Ok(Self::from_name(s).unwrap_or_default())
+    }
+}
+
+impl ScrubMode {
+    /// Mode of a name, case-insensitive, or `None` for an unknown name.
+    ///
+    /// `from_str` falls back to no scrubbing for unknown names instead.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "standard" => Some(Self::Standard),
+            "aggressive" | "strict" => Some(Self::Aggressive),
+            _ => None,
+        }
+    }
+
+    /// Name of the mode, as accepted by `from_str`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Standard => "standard",
+            Self::Aggressive => "aggressive",
+        }
+    }
+}
+
+/// Which messages of a project an export includes.
+///
+/// Timestamps accept the formats of
+/// [`crate::model::time_travel::parse_timestamp`]; `until` is exclusive.
+/// The newest `limit` matching messages are exported.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    /// Sender agent name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub importance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+}
+
+impl ExportFilter {
+    /// Checks the filter and returns `since` and `until` in the database
+    /// timestamp format.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an unparsable timestamp, `since` not
+    /// before `until`, or a `limit` below 1.
+    pub fn resolve(&self) -> Result<(Option<String>, Option<String>)> {
+        let parse = |ts: &Option<String>| -> Result<Option<String>> {
+            ts.as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(|t| parse_timestamp(t).map(|dt| dt.naive_utc().format(TS_FORMAT).to_string()))
+                .transpose()
+        };
+        let since = parse(&self.since)?;
+        let until = parse(&self.until)?;
+        if let (Some(since), Some(until)) = (&since, &until)
+            && since >= until
+        {
+            return Err(crate::Error::InvalidInput(
+                "Export filter 'since' must be before 'until'".into(),
+            ));
+        }
+        if self.limit.is_some_and(|limit| limit < 1) {
+            return Err(crate::Error::InvalidInput(
+                "Export filter 'limit' must be at least 1".into(),
+            ));
+        }
+        Ok((since, until))
     }
 }
 
@@ -200,13 +346,44 @@ impl ExportBmc {
         project_slug: &str,
         format: ExportFormat,
         scrub_mode: ScrubMode,
-        _include_attachments: bool,
+        include_attachments: bool,
+    ) -> Result<ExportedMailbox> {
+        Self::export_filtered(
+            ctx,
+            mm,
+            project_slug,
+            format,
+            scrub_mode,
+            include_attachments,
+            &ExportFilter::default(),
+        )
+        .await
+    }
+
+    /// Exports the messages of a project's mailbox matching `filter`, newest
+    /// first, to the specified format.
+    ///
+    /// Without a `limit` the 100 newest matching messages are exported.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an invalid filter (see
+    /// [`ExportFilter::resolve`]).
+    pub async fn export_filtered(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_slug: &str,
+        format: ExportFormat,
+        scrub_mode: ScrubMode,
+        include_attachments: bool,
+        filter: &ExportFilter,
     ) -> Result<ExportedMailbox> {
+        let (since, until) = filter.resolve()?;
+
         // Get project
         let project = ProjectBmc::get_by_slug(ctx, mm, project_slug).await?;
 
-        // Get recent messages (limit to 100 for export)
-        let messages = MessageBmc::list_recent(ctx, mm, project.id, 100).await?;
+        // Get matching messages
+        let messages = Self::list_matching(mm, project.id, filter, since, until).await?;
 
         let exported_at = chrono::Utc::now()
             .format("%Y-%m-%d %H:%M:%S UTC")
@@ -216,17 +393,29 @@ impl ExportBmc {
         let scrubber = Scrubber::new(scrub_mode);
 
         let content = match format {
-            ExportFormat::Html => Self::render_html(&project.slug, &messages, &scrubber),
+            ExportFormat::Html => {
+                // Avatars are derived from agent names, so omit them when names are redacted
+                let mut avatars = HashMap::new();
+                if scrub_mode != ScrubMode::Aggressive {
+                    for agent in AgentBmc::list_all_for_project(ctx, mm, project.id).await? {
+                        avatars.insert(
+                            agent.id.get(),
+                            identicon_data_uri(&agent.name, &agent.program, EXPORT_AVATAR_SIZE),
+                        );
+                    }
+                }
+                Self::render_html(&project.slug, &messages, &scrubber, &avatars).await
+            }
             ExportFormat::Json => Self::render_json(&messages, &scrubber)?,
             ExportFormat::Markdown => Self::render_markdown(&project.slug, &messages, &scrubber),
             ExportFormat::Csv => Self::render_csv(&messages, &scrubber)?,
-        };
-
-        let format_str = match format {
-            ExportFormat::Html => "html",
-            ExportFormat::Json => "json",
-            ExportFormat::Markdown => "markdown",
-            ExportFormat::Csv => "csv",
+            ExportFormat::Mbox => {
+                Self::email_messages(mm, &project.slug, &messages, &scrubber, include_attachments)
+                    .await?
+                    .iter()
+                    .map(EmailMessage::render_mbox_entry)
+                    .collect()
+            }
         };
 
         Ok(ExportedMailbox {
@@ -235,14 +424,185 @@ impl ExportBmc {
             message_count,
             exported_at,
             content,
-            format: format_str.to_string(),
+            format: format.as_str().to_string(),
         })
     }
 
-    fn render_html(
+    /// Lists a project's messages matching `filter`, newest first.
+    async fn list_matching(
+        mm: &ModelManager,
+        project_id: ProjectId,
+        filter: &ExportFilter,
+        since: Option<String>,
+        until: Option<String>,
+    ) -> Result<Vec<Message>> {
+        let db = mm.db_read();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT
+                    m.id, m.project_id, m.sender_id, ag.name, m.thread_id, m.subject, m.body_md,
+                    m.importance, m.ack_required, m.created_ts, m.attachments,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
+                FROM messages AS m
+                JOIN agents AS ag ON m.sender_id = ag.id
+                WHERE m.project_id = ?1
+                  AND (?2 IS NULL OR m.created_ts >= ?2)
+                  AND (?3 IS NULL OR m.created_ts < ?3)
+                  AND (?4 IS NULL OR m.thread_id = ?4)
+                  AND (?5 IS NULL OR LOWER(ag.name) = LOWER(?5))
+                  AND (?6 IS NULL OR m.importance = ?6)
+                ORDER BY m.created_ts DESC, m.id DESC
+                LIMIT ?7
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query(libsql::params![
+                project_id.get(),
+                since,
+                until,
+                filter.thread_id.clone(),
+                filter.sender.clone(),
+                filter.importance.clone(),
+                filter.limit.unwrap_or(EXPORT_MESSAGE_LIMIT),
+            ])
+            .await?;
+
+        let mut messages = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let attachments: String = row.get(10)?;
+            messages.push(Message {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                sender_id: row.get(2)?,
+                sender_name: row.get(3)?,
+                thread_id: row.get(4)?,
+                subject: row.get(5)?,
+                body_md: row.get(6)?,
+                importance: row.get(7)?,
+                ack_required: row.get(8)?,
+                created_ts: parse_db_timestamp(&row.get::<String>(9)?, "messages.created_ts"),
+                attachments: serde_json::from_str(&attachments)?,
+                public_id: row.get(11)?,
+            });
+        }
+        Ok(messages)
+    }
+
+    /// Exports a project's recent messages as one RFC 5322 `.eml` file each,
+    /// oldest first.
+    ///
+    /// Attachments are included as MIME parts when `include_attachments` is
+    /// set and `scrub_mode` is `None`; attachment content is never scrubbed,
+    /// so scrubbed exports leave it out.
+    pub async fn export_eml(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_slug: &str,
+        scrub_mode: ScrubMode,
+        include_attachments: bool,
+    ) -> Result<Vec<EmlFile>> {
+        let project = ProjectBmc::get_by_slug(ctx, mm, project_slug).await?;
+        let messages = MessageBmc::list_recent(ctx, mm, project.id, EXPORT_MESSAGE_LIMIT).await?;
+        let scrubber = Scrubber::new(scrub_mode);
+
+        let mails =
+            Self::email_messages(mm, &project.slug, &messages, &scrubber, include_attachments)
+                .await?;
+        Ok(mails
+            .iter()
+            .map(|mail| EmlFile {
+                filename: format!("{}.eml", mail.id),
+                content: mail.render(),
+            })
+            .collect())
+    }
+
+    /// Builds RFC 5322 messages, oldest first, from messages listed newest first.
+    async fn email_messages(
+        mm: &ModelManager,
+        project_slug: &str,
+        messages: &[crate::model::message::Message],
+        scrubber: &Scrubber,
+        include_attachments: bool,
+    ) -> Result<Vec<EmailMessage>> {
+        // A redacted name is not a valid address local part
+        let name = |n: &str| {
+            let scrubbed = scrubber.scrub_name(n);
+            if scrubbed == n {
+                scrubbed
+            } else {
+                "redacted".to_string()
+            }
+        };
+        let with_attachments = include_attachments && scrubber.mode == ScrubMode::None;
+
+        let mut mails = Vec::with_capacity(messages.len());
+        for msg in messages.iter().rev() {
+            let (mut to, mut cc, mut bcc) = (Vec::new(), Vec::new(), Vec::new());
+            for (kind, recipient) in Self::recipients_by_type(mm, msg.id).await? {
+                let list = match kind.as_str() {
+                    "cc" => &mut cc,
+                    "bcc" => &mut bcc,
+                    _ => &mut to,
+                };
+                list.push(name(&recipient));
+            }
+            let attachments = if with_attachments {
+                read_message_attachments(&mm.repo_root, project_slug, msg.id)?
+            } else {
+                Vec::new()
+            };
+            mails.push(EmailMessage {
+                id: msg.id,
+                project_slug: project_slug.to_string(),
+                from: name(&msg.sender_name),
+                to,
+                cc,
+                bcc,
+                subject: scrubber.scrub(&msg.subject),
+                date: msg.created_ts,
+                thread_id: msg.thread_id.clone(),
+                importance: msg.importance.clone(),
+                ack_required: msg.ack_required,
+                body: scrubber.scrub_body(&msg.body_md),
+                attachments,
+            });
+        }
+        Ok(mails)
+    }
+
+    /// Recipients of a message as `(recipient_type, agent_name)` pairs.
+    async fn recipients_by_type(
+        mm: &ModelManager,
+        message_id: i64,
+    ) -> Result<Vec<(String, String)>> {
+        let db = mm.db_read();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT mr.recipient_type, a.name
+                FROM message_recipients AS mr
+                JOIN agents AS a ON a.id = mr.agent_id
+                WHERE mr.message_id = ?
+                ORDER BY a.name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([message_id]).await?;
+        let mut recipients = Vec::new();
+        while let Some(row) = rows.next().await? {
+            recipients.push((row.get(0)?, row.get(1)?));
+        }
+        Ok(recipients)
+    }
+
+    async fn render_html(
         project_slug: &str,
         messages: &[crate::model::message::Message],
         scrubber: &Scrubber,
+        avatars: &HashMap<i64, String>,
     ) -> String {
         let mut html = String::new();
         html.push_str("<!DOCTYPE html>\n<html>\n<head>\n");
@@ -256,9 +616,14 @@ body { font-family: system-ui, sans-serif; max-width: 800px; margin: 0 auto; pad
 .message { border: 1px solid #ddd; padding: 15px; margin: 10px 0; border-radius: 8px; }
 .subject { font-weight: bold; font-size: 1.1em; }
 .meta { color: #666; font-size: 0.9em; margin: 5px 0; }
-.body { margin-top: 10px; white-space: pre-wrap; }
-</style>\n</head>\n<body>\n",
+.body { margin-top: 10px; }
+.body pre { background: #f6f8fa; padding: 10px; border-radius: 6px; overflow-x: auto; }
+.diagram img { max-width: 100%; }
+.avatar { width: 20px; height: 20px; border-radius: 4px; vertical-align: middle; margin-right: 4px; }
+",
         );
+        html.push_str(&highlight_css());
+        html.push_str("</style>\n</head>\n<body>\n");
         html.push_str(&format!("<h1>Mailbox Export: {}</h1>\n", project_slug));
         html.push_str(&format!("<p>Total messages: {}</p>\n", messages.len()));
 
@@ -272,14 +637,19 @@ body { font-family: system-ui, sans-serif; max-width: 800px; margin: 0 auto; pad
                 "<div class=\"subject\">{}</div>\n",
                 html_escape(&scrubbed_subject)
             ));
+            let avatar = avatars
+                .get(&msg.sender_id)
+                .map(|uri| format!("<img class=\"avatar\" alt=\"\" src=\"{}\">", uri))
+                .unwrap_or_default();
             html.push_str(&format!(
-                "<div class=\"meta\">From: {} | {}</div>\n",
+                "<div class=\"meta\">{}From: {} | {}</div>\n",
+                avatar,
                 html_escape(&scrubbed_sender),
                 msg.created_ts.format("%Y-%m-%d %H:%M")
             ));
             html.push_str(&format!(
                 "<div class=\"body\">{}</div>\n",
-                html_escape(&scrubbed_body)
+                markdown_to_html_with_diagrams(&scrubbed_body).await
             ));
             html.push_str("</div>\n");
         }
@@ -428,6 +798,51 @@ impl ExportBmc {
     }
 }
 
+/// Reads the attachments stored for a message in the archive.
+///
+/// `add_attachment` stores each file at
+/// `projects/{slug}/attachments/att_{message_id}_{suffix}/{filename}`.
+fn read_message_attachments(
+    repo_root: &Path,
+    project_slug: &str,
+    message_id: i64,
+) -> Result<Vec<EmailAttachment>> {
+    let dir = repo_root
+        .join("projects")
+        .join(project_slug)
+        .join("attachments");
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let prefix = format!("att_{}_", message_id);
+    let mut attachment_dirs: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+        .map(|e| e.path())
+        .collect();
+    attachment_dirs.sort();
+
+    let mut attachments = Vec::new();
+    for attachment_dir in attachment_dirs {
+        let mut files: Vec<_> = std::fs::read_dir(&attachment_dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .collect();
+        files.sort_by_key(|e| e.file_name());
+        for file in files {
+            let filename = file.file_name().to_string_lossy().into_owned();
+            attachments.push(EmailAttachment {
+                media_type: media_type_for(&filename).to_string(),
+                content: std::fs::read(file.path())?,
+                filename,
+            });
+        }
+    }
+    Ok(attachments)
+}
+
 fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -978,6 +1393,7 @@ impl ExportBmc {
             ExportFormat::Json => "json",
             ExportFormat::Markdown => "markdown",
             ExportFormat::Csv => "csv",
+            ExportFormat::Mbox => "mbox",
         };
 
         let exported = ExportedMailbox {
@@ -1038,6 +1454,7 @@ impl ExportBmc {
             ExportFormat::Json => "json",
             ExportFormat::Markdown => "markdown",
             ExportFormat::Csv => "csv",
+            ExportFormat::Mbox => "mbox",
         };
 
         let exported = ExportedMailbox {
@@ -0,0 +1,431 @@
+//! Asynchronous bulk exports.
+//!
+//! [`ExportJobBmc::create`] queues an export of a project's mailbox (format,
+//! scrub mode and [`ExportFilter`]) and returns at once. The server's export
+//! worker runs queued jobs one at a time with [`ExportJobBmc::run_pending`],
+//! rendering them with [`ExportBmc::export_filtered`] into
+//! `<data>/exports/<id>.<ext>`. Callers poll the job until it is
+//! [`STATUS_COMPLETED`] and then download the file, so a large export never
+//! holds a request or tool call open, and a UI can pick up its jobs again
+//! after a reload.
+//!
+//! Jobs left running by a restart are queued again by
+//! [`ExportJobBmc::requeue_interrupted`]. Finished jobs and their files are
+//! removed once they are older than `exports.retention_hours`.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::export::{ExportBmc, ExportFilter, ExportFormat, ScrubMode};
+use crate::model::project::ProjectBmc;
+use crate::store::db_statement::Row;
+use crate::types::{ExportJobId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Status of a job waiting for the worker.
+pub const STATUS_QUEUED: &str = "queued";
+
+/// Status of a job being rendered.
+pub const STATUS_RUNNING: &str = "running";
+
+/// Status of a job whose file is ready for download.
+pub const STATUS_COMPLETED: &str = "completed";
+
+/// Status of a job that could not be rendered; `error` says why.
+pub const STATUS_FAILED: &str = "failed";
+
+/// Default number of jobs listed.
+pub const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// An export job.
+///
+/// # Fields
+///
+/// - `format`, `scrub` - Names of the [`ExportFormat`] and [`ScrubMode`]
+/// - `filters` - Messages included; `limit` is always set
+/// - `status` - [`STATUS_QUEUED`], [`STATUS_RUNNING`], [`STATUS_COMPLETED`]
+///   or [`STATUS_FAILED`]
+/// - `message_count`, `size_bytes` - Size of the finished export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportJob {
+    pub id: ExportJobId,
+    pub project_id: ProjectId,
+    pub project_slug: String,
+    pub format: String,
+    pub scrub: String,
+    pub include_attachments: bool,
+    pub filters: ExportFilter,
+    pub status: String,
+    pub message_count: Option<i64>,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub created_ts: NaiveDateTime,
+    pub started_ts: Option<NaiveDateTime>,
+    pub completed_ts: Option<NaiveDateTime>,
+}
+
+impl ExportJob {
+    pub fn export_format(&self) -> ExportFormat {
+        self.format.parse().unwrap_or(ExportFormat::Json)
+    }
+
+    pub fn scrub_mode(&self) -> ScrubMode {
+        self.scrub.parse().unwrap_or_default()
+    }
+
+    /// Name the file is downloaded as.
+    pub fn filename(&self) -> String {
+        format!(
+            "{}_mailbox_{}.{}",
+            self.project_slug,
+            self.id,
+            self.export_format().extension()
+        )
+    }
+}
+
+/// Input data to queue an export job.
+///
+/// Without `filters.limit` the job exports up to `exports.max_messages`
+/// messages; larger limits are lowered to it.
+#[derive(Debug, Clone)]
+pub struct ExportJobForCreate {
+    pub project_id: ProjectId,
+    pub format: ExportFormat,
+    pub scrub: ScrubMode,
+    pub include_attachments: bool,
+    pub filters: ExportFilter,
+}
+
+const SELECT_JOB: &str = r#"
+    SELECT j.id, j.project_id, p.slug, j.format, j.scrub, j.include_attachments, j.filters,
+           j.status, j.message_count, j.size_bytes, j.error, j.created_ts, j.started_ts,
+           j.completed_ts
+    FROM export_jobs AS j
+    JOIN projects AS p ON p.id = j.project_id
+"#;
+
+/// Backend Model Controller for export jobs.
+pub struct ExportJobBmc;
+
+impl ExportJobBmc {
+    /// Queues an export job.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an invalid filter and `ProjectNotFound` if
+    /// the project does not exist.
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        job_c: ExportJobForCreate,
+    ) -> Result<ExportJob> {
+        job_c.filters.resolve()?;
+        ProjectBmc::get(ctx, mm, job_c.project_id).await?;
+
+        let max_messages = mm.app_config.exports.max_messages.max(1);
+        let filters = ExportFilter {
+            limit: Some(
+                job_c
+                    .filters
+                    .limit
+                    .unwrap_or(max_messages)
+                    .min(max_messages),
+            ),
+            ..job_c.filters
+        };
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO export_jobs (project_id, format, scrub, include_attachments, filters)
+                VALUES (?, ?, ?, ?, ?)
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                job_c.project_id.get(),
+                job_c.format.as_str(),
+                job_c.scrub.as_str(),
+                i64::from(job_c.include_attachments),
+                serde_json::to_string(&filters)?,
+            ))
+            .await?;
+        let id: i64 = rows
+            .next()
+            .await?
+            .ok_or_else(|| Error::InvalidInput("Failed to create export job".into()))?
+            .get(0)?;
+        Self::get(ctx, mm, ExportJobId::new(id)).await
+    }
+
+    /// Gets an export job.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if there is no such job.
+    pub async fn get(_ctx: &Ctx, mm: &ModelManager, id: ExportJobId) -> Result<ExportJob> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!("{} WHERE j.id = ?", SELECT_JOB))
+            .await?;
+        let mut rows = stmt.query([id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Lists export jobs, of one project or of all projects, newest first.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: Option<ProjectId>,
+        limit: i64,
+    ) -> Result<Vec<ExportJob>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE (?1 IS NULL OR j.project_id = ?1) ORDER BY j.id DESC LIMIT ?2",
+                SELECT_JOB
+            ))
+            .await?;
+        let mut rows = stmt
+            .query(libsql::params![
+                project_id.map(|p| p.get()),
+                limit.clamp(1, 1000)
+            ])
+            .await?;
+        let mut jobs = Vec::new();
+        while let Some(row) = rows.next().await? {
+            jobs.push(Self::from_row(&row)?);
+        }
+        Ok(jobs)
+    }
+
+    /// Where the file of a completed job is stored.
+    pub fn file_path(mm: &ModelManager, job: &ExportJob) -> PathBuf {
+        Self::path_for(mm, job.id, &job.format)
+    }
+
+    /// Runs the oldest queued job, if any.
+    ///
+    /// A job that cannot be rendered or written is marked failed with the
+    /// reason in `error`; that is not an error of this call.
+    ///
+    /// # Returns
+    /// The finished job, or `None` if no job was queued.
+    pub async fn run_next(ctx: &Ctx, mm: &ModelManager) -> Result<Option<ExportJob>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE export_jobs SET status = ?1, started_ts = CURRENT_TIMESTAMP
+                WHERE id = (SELECT id FROM export_jobs WHERE status = ?2 ORDER BY id LIMIT 1)
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((STATUS_RUNNING, STATUS_QUEUED)).await?;
+        let id = match rows.next().await? {
+            Some(row) => ExportJobId::new(row.get(0)?),
+            None => return Ok(None),
+        };
+        let job = Self::get(ctx, mm, id).await?;
+
+        let now = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        match Self::render(ctx, mm, &job).await {
+            Ok((message_count, size_bytes)) => {
+                let stmt = db
+                    .prepare(
+                        r#"
+                        UPDATE export_jobs
+                        SET status = ?, message_count = ?, size_bytes = ?, completed_ts = ?
+                        WHERE id = ?
+                        "#,
+                    )
+                    .await?;
+                stmt.execute((STATUS_COMPLETED, message_count, size_bytes, now, id.get()))
+                    .await?;
+            }
+            Err(e) => {
+                let stmt = db
+                    .prepare(
+                        "UPDATE export_jobs SET status = ?, error = ?, completed_ts = ? WHERE id = ?",
+                    )
+                    .await?;
+                stmt.execute((STATUS_FAILED, e.to_string(), now, id.get()))
+                    .await?;
+            }
+        }
+
+        match Self::get(ctx, mm, id).await {
+            Ok(finished) => Ok(Some(finished)),
+            // The project was deleted while its export ran
+            Err(Error::NotFound) => {
+                remove_file(&Self::file_path(mm, &job))?;
+                Ok(Some(job))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Runs every queued job, then removes jobs finished more than
+    /// `exports.retention_hours` ago.
+    ///
+    /// # Returns
+    /// Number of jobs run.
+    pub async fn run_pending(ctx: &Ctx, mm: &ModelManager) -> Result<usize> {
+        let mut ran = 0;
+        while Self::run_next(ctx, mm).await?.is_some() {
+            ran += 1;
+        }
+        Self::purge_expired(ctx, mm, mm.app_config.exports.retention_hours).await?;
+        Ok(ran)
+    }
+
+    /// Queues jobs left running, e.g. by a crash, again.
+    ///
+    /// Only call this while no worker is running.
+    ///
+    /// # Returns
+    /// Number of jobs queued again.
+    pub async fn requeue_interrupted(_ctx: &Ctx, mm: &ModelManager) -> Result<usize> {
+        let stmt = mm
+            .db()
+            .prepare("UPDATE export_jobs SET status = ?, started_ts = NULL WHERE status = ?")
+            .await?;
+        let requeued = stmt.execute((STATUS_QUEUED, STATUS_RUNNING)).await?;
+        Ok(requeued as usize)
+    }
+
+    /// Removes completed and failed jobs finished at least `retention_hours`
+    /// ago, with their files.
+    ///
+    /// # Returns
+    /// Number of jobs removed.
+    pub async fn purge_expired(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        retention_hours: u64,
+    ) -> Result<usize> {
+        let now = chrono::Utc::now().naive_utc();
+        let hours = i64::try_from(retention_hours).unwrap_or(i64::MAX);
+        let cutoff = chrono::Duration::try_hours(hours)
+            .and_then(|d| now.checked_sub_signed(d))
+            .unwrap_or(NaiveDateTime::MIN)
+            .format(TS_FORMAT)
+            .to_string();
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "SELECT id, format FROM export_jobs WHERE status IN (?1, ?2) AND completed_ts <= ?3",
+            )
+            .await?;
+        let mut rows = stmt
+            .query((STATUS_COMPLETED, STATUS_FAILED, cutoff.as_str()))
+            .await?;
+        let mut expired = Vec::new();
+        while let Some(row) = rows.next().await? {
+            expired.push((ExportJobId::new(row.get(0)?), row.get::<String>(1)?));
+        }
+
+        for (id, format) in &expired {
+            remove_file(&Self::path_for(mm, *id, format))?;
+            let stmt = db.prepare("DELETE FROM export_jobs WHERE id = ?").await?;
+            stmt.execute([id.get()]).await?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Removes a project's jobs and their files.
+    pub async fn delete_for_project(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT id, format FROM export_jobs WHERE project_id = ?")
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        while let Some(row) = rows.next().await? {
+            remove_file(&Self::path_for(
+                mm,
+                ExportJobId::new(row.get(0)?),
+                &row.get::<String>(1)?,
+            ))?;
+        }
+        let stmt = db
+            .prepare("DELETE FROM export_jobs WHERE project_id = ?")
+            .await?;
+        stmt.execute([project_id.get()]).await?;
+        Ok(())
+    }
+
+    /// Renders a job and writes its file.
+    ///
+    /// The file is written under a temporary name and renamed once
+    /// complete, so a partial export is never downloaded.
+    ///
+    /// # Returns
+    /// The number of messages exported and the file size.
+    async fn render(ctx: &Ctx, mm: &ModelManager, job: &ExportJob) -> Result<(i64, i64)> {
+        let exported = ExportBmc::export_filtered(
+            ctx,
+            mm,
+            &job.project_slug,
+            job.export_format(),
+            job.scrub_mode(),
+            job.include_attachments,
+            &job.filters,
+        )
+        .await?;
+
+        std::fs::create_dir_all(mm.exports_dir())?;
+        let path = Self::file_path(mm, job);
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, exported.content.as_bytes())?;
+        std::fs::rename(&partial, &path)?;
+        Ok((exported.message_count as i64, exported.content.len() as i64))
+    }
+
+    fn path_for(mm: &ModelManager, id: ExportJobId, format: &str) -> PathBuf {
+        let format: ExportFormat = format.parse().unwrap_or(ExportFormat::Json);
+        mm.exports_dir()
+            .join(format!("{}.{}", id, format.extension()))
+    }
+
+    fn from_row(row: &Row) -> Result<ExportJob> {
+        let filters: String = row.get(6)?;
+        Ok(ExportJob {
+            id: ExportJobId::new(row.get(0)?),
+            project_id: ProjectId::new(row.get(1)?),
+            project_slug: row.get(2)?,
+            format: row.get(3)?,
+            scrub: row.get(4)?,
+            include_attachments: row.get::<i64>(5)? != 0,
+            filters: serde_json::from_str(&filters)?,
+            status: row.get(7)?,
+            message_count: row.get(8)?,
+            size_bytes: row.get(9)?,
+            error: row.get(10)?,
+            created_ts: parse_timestamp(&row.get::<String>(11)?, "export_jobs.created_ts"),
+            started_ts: parse_timestamp_opt(row.get(12)?, "export_jobs.started_ts"),
+            completed_ts: parse_timestamp_opt(row.get(13)?, "export_jobs.completed_ts"),
+        })
+    }
+}
+
+/// Removes a file, if it exists.
+fn remove_file(path: &std::path::Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
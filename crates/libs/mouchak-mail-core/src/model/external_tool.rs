@@ -0,0 +1,312 @@
+//! External HTTP tools proxied through MCP.
+//!
+//! Operators register HTTP endpoints that MCP clients see as extra tools
+//! next to the mail tools, so a team can expose project-specific tooling
+//! over the same connection. The MCP server forwards each call as a JSON
+//! `POST` and returns the response body, bounded by the tool's timeout and
+//! result size cap.
+//!
+//! The `auth_header` value is sent as the `Authorization` header. It is
+//! stored server-side only: [`ExternalTool`] never serializes it.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::store::db_statement::Row;
+use crate::utils::parse_timestamp;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Maximum length of a tool name.
+pub const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// Default time allowed for the endpoint to answer.
+pub const DEFAULT_TIMEOUT_MS: i64 = 10_000;
+
+/// Longest timeout a tool may be registered with.
+pub const MAX_TIMEOUT_MS: i64 = 120_000;
+
+/// Default cap on the response body returned to the caller.
+pub const DEFAULT_MAX_RESULT_BYTES: i64 = 64 * 1024;
+
+/// Largest result cap a tool may be registered with.
+pub const MAX_RESULT_BYTES: i64 = 1024 * 1024;
+
+/// A registered external tool.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalTool {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the tool's arguments
+    pub input_schema: Map<String, Value>,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub auth_header: Option<String>,
+    /// Whether an `Authorization` header is configured
+    pub has_auth: bool,
+    pub timeout_ms: i64,
+    pub max_result_bytes: i64,
+    pub enabled: bool,
+    pub created_ts: NaiveDateTime,
+    pub updated_ts: NaiveDateTime,
+}
+
+/// Input to register a tool, or replace the registration of one.
+#[derive(Debug, Clone)]
+pub struct ExternalToolForCreate {
+    pub name: String,
+    pub description: String,
+    /// Defaults to an object schema accepting any arguments
+    pub input_schema: Option<Map<String, Value>>,
+    pub url: String,
+    pub auth_header: Option<String>,
+    pub timeout_ms: Option<i64>,
+    pub max_result_bytes: Option<i64>,
+    pub enabled: bool,
+}
+
+/// Checks a tool name matches MCP tool naming used by the built-in tools.
+fn validate_tool_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_TOOL_NAME_LEN
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Invalid tool name '{}': use lowercase letters, digits and underscores, starting with a letter (max {} chars)",
+            name, MAX_TOOL_NAME_LEN
+        )))
+    }
+}
+
+fn validate_url(url: &str) -> Result<()> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"));
+    match rest {
+        Some(host) if !host.is_empty() && !host.starts_with('/') => Ok(()),
+        _ => Err(Error::InvalidInput(format!(
+            "Invalid tool URL '{}': must be an http:// or https:// URL",
+            url
+        ))),
+    }
+}
+
+fn validate_schema(schema: &Map<String, Value>) -> Result<()> {
+    match schema.get("type") {
+        Some(Value::String(t)) if t == "object" => Ok(()),
+        _ => Err(Error::InvalidInput(
+            "Tool input_schema must be a JSON schema with \"type\": \"object\"".into(),
+        )),
+    }
+}
+
+fn bounded(value: Option<i64>, default: i64, max: i64, what: &str) -> Result<i64> {
+    match value {
+        None => Ok(default),
+        Some(v) if (1..=max).contains(&v) => Ok(v),
+        Some(v) => Err(Error::InvalidInput(format!(
+            "{} must be between 1 and {}, got {}",
+            what, max, v
+        ))),
+    }
+}
+
+/// Backend Model Controller for external tool registrations.
+pub struct ExternalToolBmc;
+
+impl ExternalToolBmc {
+    /// Registers a tool, replacing any registration with the same name.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an invalid name, URL, schema, timeout or
+    /// result cap.
+    pub async fn register(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        tool_c: ExternalToolForCreate,
+    ) -> Result<i64> {
+        validate_tool_name(&tool_c.name)?;
+        validate_url(&tool_c.url)?;
+        let schema = tool_c.input_schema.unwrap_or_else(|| {
+            let mut schema = Map::new();
+            schema.insert("type".into(), Value::from("object"));
+            schema
+        });
+        validate_schema(&schema)?;
+        let timeout_ms = bounded(
+            tool_c.timeout_ms,
+            DEFAULT_TIMEOUT_MS,
+            MAX_TIMEOUT_MS,
+            "timeout_ms",
+        )?;
+        let max_result_bytes = bounded(
+            tool_c.max_result_bytes,
+            DEFAULT_MAX_RESULT_BYTES,
+            MAX_RESULT_BYTES,
+            "max_result_bytes",
+        )?;
+        let auth_header = tool_c
+            .auth_header
+            .as_deref()
+            .map(str::trim)
+            .filter(|h| !h.is_empty());
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO external_tools
+                    (name, description, input_schema, url, auth_header, timeout_ms, max_result_bytes, enabled)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(name) DO UPDATE SET
+                    description = excluded.description,
+                    input_schema = excluded.input_schema,
+                    url = excluded.url,
+                    auth_header = excluded.auth_header,
+                    timeout_ms = excluded.timeout_ms,
+                    max_result_bytes = excluded.max_result_bytes,
+                    enabled = excluded.enabled,
+                    updated_ts = CURRENT_TIMESTAMP
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                tool_c.name.as_str(),
+                tool_c.description.trim(),
+                Value::Object(schema).to_string(),
+                tool_c.url.as_str(),
+                auth_header,
+                timeout_ms,
+                max_result_bytes,
+                tool_c.enabled,
+            ))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::InvalidInput("Failed to register tool".into())),
+        }
+    }
+
+    /// Lists registered tools by name, including disabled ones.
+    pub async fn list(_ctx: &Ctx, mm: &ModelManager) -> Result<Vec<ExternalTool>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!("{} ORDER BY name", SELECT_TOOL))
+            .await?;
+        let mut rows = stmt.query(()).await?;
+        let mut tools = Vec::new();
+        while let Some(row) = rows.next().await? {
+            tools.push(Self::from_row(&row)?);
+        }
+        Ok(tools)
+    }
+
+    /// Lists the tools MCP clients can call.
+    pub async fn list_enabled(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<ExternalTool>> {
+        Ok(Self::list(ctx, mm)
+            .await?
+            .into_iter()
+            .filter(|t| t.enabled)
+            .collect())
+    }
+
+    /// Gets a tool by name.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if no tool has the name.
+    pub async fn get_by_name(_ctx: &Ctx, mm: &ModelManager, name: &str) -> Result<ExternalTool> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!("{} WHERE name = ?", SELECT_TOOL))
+            .await?;
+        let mut rows = stmt.query([name]).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Removes a tool.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if no tool has the name.
+    pub async fn delete(_ctx: &Ctx, mm: &ModelManager, name: &str) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM external_tools WHERE name = ?")
+            .await?;
+        if stmt.execute([name]).await? == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    fn from_row(row: &Row) -> Result<ExternalTool> {
+        let schema: String = row.get(3)?;
+        let input_schema = match serde_json::from_str(&schema)? {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        let auth_header: Option<String> = row.get(5)?;
+        let created_ts: String = row.get(9)?;
+        let updated_ts: String = row.get(10)?;
+        Ok(ExternalTool {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            input_schema,
+            url: row.get(4)?,
+            has_auth: auth_header.is_some(),
+            auth_header,
+            timeout_ms: row.get(6)?,
+            max_result_bytes: row.get(7)?,
+            enabled: row.get(8)?,
+            created_ts: parse_timestamp(&created_ts, "created_ts"),
+            updated_ts: parse_timestamp(&updated_ts, "updated_ts"),
+        })
+    }
+}
+
+const SELECT_TOOL: &str = r#"
+    SELECT id, name, description, input_schema, url, auth_header,
+           timeout_ms, max_result_bytes, enabled, created_ts, updated_ts
+    FROM external_tools
+"#;
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validation() {
+        assert!(validate_tool_name("jira_search").is_ok());
+        assert!(validate_tool_name("Jira").is_err());
+        assert!(validate_tool_name("jira-search").is_err());
+
+        assert!(validate_url("https://tools.internal/jira").is_ok());
+        assert!(validate_url("http://localhost:9000").is_ok());
+        assert!(validate_url("ftp://tools.internal").is_err());
+        assert!(validate_url("https://").is_err());
+
+        let Value::Object(schema) = json!({"type": "object", "properties": {}}) else {
+            unreachable!()
+        };
+        assert!(validate_schema(&schema).is_ok());
+        assert!(validate_schema(&Map::new()).is_err());
+
+        assert_eq!(bounded(None, 5, 10, "x").unwrap(), 5);
+        assert!(bounded(Some(0), 5, 10, "x").is_err());
+        assert!(bounded(Some(11), 5, 10, "x").is_err());
+    }
+}
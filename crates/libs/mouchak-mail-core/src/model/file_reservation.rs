@@ -1,10 +1,23 @@
 use crate::Result;
 use crate::model::ModelManager;
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::model::reservation_watcher::{ReleaseCause, ReservationWatcherBmc};
+use crate::store::db_statement::Row;
 use crate::store::git_store;
 use crate::types::{AgentId, ProjectId};
+use crate::utils::pathspec::PatternSet;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long `check_paths` reuses a project's loaded reservations.
+///
+/// Hooks call `check_paths` several times per commit/push; reservation
+/// changes made through this module invalidate the cache immediately.
+const PROJECT_RESERVATIONS_TTL: Duration = Duration::from_secs(2);
 
 /// A file reservation (lock) for coordinating agent work.
 ///
@@ -55,6 +68,70 @@ pub struct FileReservationForCreate {
     pub expires_ts: NaiveDateTime,
 }
 
+/// An active reservation matching a checked path.
+///
+/// # Fields
+///
+/// - `reservation_id` - Reservation database ID
+/// - `agent_id` / `agent_name` - Agent holding the reservation
+/// - `path_pattern` - Pattern that matched
+/// - `exclusive` - True = Write Lock, False = Read Lock
+/// - `expires_ts` - When the reservation auto-releases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathConflict {
+    pub reservation_id: i64,
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub path_pattern: String,
+    pub exclusive: bool,
+    pub expires_ts: NaiveDateTime,
+}
+
+/// Verdict for one path passed to [`FileReservationBmc::check_paths`].
+///
+/// `reserved` is true when any other agent holds an active reservation
+/// matching `path`; `conflicts` lists those reservations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathVerdict {
+    pub path: String,
+    pub reserved: bool,
+    pub conflicts: Vec<PathConflict>,
+}
+
+/// A project's unreleased reservations with their patterns compiled.
+struct ProjectReservations {
+    loaded: Instant,
+    reservations: Vec<(FileReservation, String)>,
+    patterns: PatternSet,
+}
+
+/// Short-lived per-project cache of [`ProjectReservations`], held by the
+/// [`ModelManager`].
+#[derive(Default)]
+pub(crate) struct ProjectReservationsCache {
+    sets: Mutex<HashMap<i64, Arc<ProjectReservations>>>,
+}
+
+impl ProjectReservationsCache {
+    fn get(&self, project_id: ProjectId) -> Option<Arc<ProjectReservations>> {
+        let sets = self.sets.lock().unwrap_or_else(|e| e.into_inner());
+        sets.get(&project_id.get())
+            .filter(|set| set.loaded.elapsed() < PROJECT_RESERVATIONS_TTL)
+            .cloned()
+    }
+
+    fn put(&self, project_id: ProjectId, set: Arc<ProjectReservations>) {
+        let mut sets = self.sets.lock().unwrap_or_else(|e| e.into_inner());
+        sets.retain(|_, set| set.loaded.elapsed() < PROJECT_RESERVATIONS_TTL);
+        sets.insert(project_id.get(), set);
+    }
+
+    /// Drops every cached set; called after any reservation change.
+    pub(crate) fn clear(&self) {
+        self.sets.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
 /// Backend Model Controller for File Reservation operations.
 ///
 /// Manages file-level locking and coordination between agents.
@@ -67,9 +144,10 @@ impl FileReservationBmc {
     /// This method:
     /// 1. Inserts reservation into database
     /// 2. Archives reservation to Git
+    /// 3. Appends a `file_reservation.created` event to the event log
     ///
     /// # Arguments
-    /// * `_ctx` - Request context
+    /// * `ctx` - Request context
     /// * `mm` - ModelManager
     /// * `fr_c` - Reservation data (path pattern, exclusive flag, TTL)
     ///
@@ -101,7 +179,7 @@ impl FileReservationBmc {
     /// # }
     /// ```
     pub async fn create(
-        _ctx: &crate::Ctx,
+        ctx: &crate::Ctx,
         mm: &ModelManager,
         fr_c: FileReservationForCreate,
     ) -> Result<i64> {
@@ -136,6 +214,21 @@ impl FileReservationBmc {
                 "Failed to create file reservation".into(),
             ));
         };
+        mm.reservation_cache.clear();
+
+        Self::archive_created(ctx, mm, id, &fr_c).await?;
+        Ok(id)
+    }
+
+    /// Archives a newly inserted reservation to Git and appends a
+    /// `file_reservation.created` event.
+    pub(crate) async fn archive_created(
+        ctx: &crate::Ctx,
+        mm: &ModelManager,
+        id: i64,
+        fr_c: &FileReservationForCreate,
+    ) -> Result<()> {
+        let db = mm.db();
 
         // Write to Git
         let stmt = db.prepare("SELECT slug FROM projects WHERE id = ?").await?;
@@ -197,7 +290,23 @@ impl FileReservationBmc {
             "mcp-bot@localhost",
         )?;
 
-        Ok(id)
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                "file_reservation.created",
+                Some(fr_c.project_id.get()),
+                Some(&agent_name),
+                serde_json::json!({
+                    "reservation_id": id,
+                    "path_pattern": fr_c.path_pattern,
+                    "exclusive": fr_c.exclusive,
+                }),
+            ),
+        )
+        .await;
+
+        Ok(())
     }
 
     pub async fn list_active_for_project(
@@ -295,7 +404,7 @@ impl FileReservationBmc {
     ///
     /// # Errors
     /// Returns an error if the reservation doesn't exist
-    pub async fn release(_ctx: &crate::Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+    pub async fn release(ctx: &crate::Ctx, mm: &ModelManager, id: i64) -> Result<()> {
         let db = mm.db();
         let now = chrono::Utc::now().naive_utc();
         let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
@@ -309,13 +418,16 @@ impl FileReservationBmc {
             .await?;
 
         stmt.execute((now_str, id)).await?;
+        mm.reservation_cache.clear();
+
+        Self::send_release_receipts(ctx, mm, id, ReleaseCause::Released).await;
         Ok(())
     }
 
     pub async fn list_all_for_project(
         _ctx: &crate::Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<Vec<FileReservation>> {
         let db = mm.db();
         let stmt = db.prepare(
@@ -326,7 +438,7 @@ impl FileReservationBmc {
             ORDER BY created_ts DESC
             "#
         ).await?;
-        let mut rows = stmt.query([project_id]).await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
 
         let mut reservations = Vec::new();
         while let Some(row) = rows.next().await? {
@@ -336,10 +448,10 @@ impl FileReservationBmc {
     }
 
     pub async fn release_by_path(
-        _ctx: &crate::Ctx,
+        ctx: &crate::Ctx,
         mm: &ModelManager,
-        project_id: i64,
-        agent_id: i64,
+        project_id: ProjectId,
+        agent_id: AgentId,
         path_pattern: &str,
     ) -> Result<Option<i64>> {
         let db = mm.db();
@@ -355,7 +467,9 @@ impl FileReservationBmc {
             "#,
             )
             .await?;
-        let mut rows = stmt.query((project_id, agent_id, path_pattern)).await?;
+        let mut rows = stmt
+            .query((project_id.get(), agent_id.get(), path_pattern))
+            .await?;
 
         if let Some(row) = rows.next().await? {
             let id: i64 = row.get(0)?;
@@ -369,7 +483,9 @@ impl FileReservationBmc {
                 )
                 .await?;
             stmt.execute((now_str, id)).await?;
+            mm.reservation_cache.clear();
 
+            Self::send_release_receipts(ctx, mm, id, ReleaseCause::Released).await;
             Ok(Some(id))
         } else {
             Ok(None)
@@ -378,7 +494,7 @@ impl FileReservationBmc {
 
     /// Force release a reservation by ID (any agent can call this for emergencies)
     pub async fn force_release(
-        _ctx: &crate::Ctx,
+        ctx: &crate::Ctx,
         mm: &ModelManager,
         reservation_id: i64,
     ) -> Result<()> {
@@ -393,7 +509,12 @@ impl FileReservationBmc {
             "#,
             )
             .await?;
-        stmt.execute((now_str, reservation_id)).await?;
+        let affected = stmt.execute((now_str, reservation_id)).await?;
+        mm.reservation_cache.clear();
+
+        if affected > 0 {
+            Self::send_release_receipts(ctx, mm, reservation_id, ReleaseCause::ForceReleased).await;
+        }
         Ok(())
     }
 
@@ -415,10 +536,214 @@ impl FileReservationBmc {
             )
             .await?;
         stmt.execute((expires_str, reservation_id)).await?;
+        mm.reservation_cache.clear();
         Ok(())
     }
 
-    fn from_row(row: libsql::Row) -> Result<FileReservation> {
+    /// Checks many paths against a project's active reservations at once.
+    ///
+    /// Returns one verdict per path, in input order. Reservations held by
+    /// `exclude_agent` are ignored, so an agent's own locks never block it.
+    /// Patterns match as globs and as directories (see [`PatternSet`]).
+    ///
+    /// The project's reservations are loaded and compiled once and reused
+    /// for a couple of seconds, so back-to-back hook invocations cost one
+    /// query; expiry is still checked against the current time.
+    pub async fn check_paths(
+        _ctx: &crate::Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        exclude_agent: Option<AgentId>,
+        paths: &[String],
+    ) -> Result<Vec<PathVerdict>> {
+        let set = match mm.reservation_cache.get(project_id) {
+            Some(set) => set,
+            None => {
+                let set = Arc::new(Self::load_project_reservations(mm, project_id).await?);
+                mm.reservation_cache.put(project_id, set.clone());
+                set
+            }
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        let verdicts = paths
+            .iter()
+            .map(|path| {
+                let conflicts: Vec<PathConflict> = set
+                    .patterns
+                    .matches(path)
+                    .into_iter()
+                    .map(|idx| &set.reservations[idx])
+                    .filter(|(res, _)| Some(res.agent_id) != exclude_agent && res.expires_ts > now)
+                    .map(|(res, agent_name)| PathConflict {
+                        reservation_id: res.id,
+                        agent_id: res.agent_id,
+                        agent_name: agent_name.clone(),
+                        path_pattern: res.path_pattern.clone(),
+                        exclusive: res.exclusive,
+                        expires_ts: res.expires_ts,
+                    })
+                    .collect();
+                PathVerdict {
+                    path: path.clone(),
+                    reserved: !conflicts.is_empty(),
+                    conflicts,
+                }
+            })
+            .collect();
+        Ok(verdicts)
+    }
+
+    async fn load_project_reservations(
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<ProjectReservations> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT fr.id, fr.project_id, fr.agent_id, fr.path_pattern, fr.exclusive, fr.reason,
+                   fr.created_ts, fr.expires_ts, fr.released_ts, a.name
+            FROM file_reservations AS fr
+            JOIN agents AS a ON a.id = fr.agent_id
+            WHERE fr.project_id = ? AND fr.released_ts IS NULL
+            ORDER BY fr.created_ts DESC
+            "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+
+        let mut reservations = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let agent_name: String = row.get(9)?;
+            reservations.push((Self::from_row(row)?, agent_name));
+        }
+        let patterns = PatternSet::new(reservations.iter().map(|(r, _)| r.path_pattern.as_str()));
+        Ok(ProjectReservations {
+            loaded: Instant::now(),
+            reservations,
+            patterns,
+        })
+    }
+
+    /// Records a `file_reservation.expired` event for every unreleased
+    /// reservation whose TTL ran out in `(since, until]`.
+    ///
+    /// Intended to run periodically, passing the previous run's `until` as
+    /// `since`, so event subscribers learn about expiries nobody released.
+    ///
+    /// # Returns
+    /// Number of expiries recorded.
+    pub async fn announce_expired(
+        ctx: &crate::Ctx,
+        mm: &ModelManager,
+        since: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> Result<usize> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT fr.id, fr.project_id, fr.path_pattern, fr.expires_ts, a.name
+            FROM file_reservations AS fr
+            JOIN agents AS a ON a.id = fr.agent_id
+            WHERE fr.released_ts IS NULL AND fr.expires_ts > ? AND fr.expires_ts <= ?
+            ORDER BY fr.expires_ts, fr.id
+            "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                since.format(crate::utils::TS_FORMAT).to_string(),
+                until.format(crate::utils::TS_FORMAT).to_string(),
+            ))
+            .await?;
+
+        let mut expired = Vec::new();
+        while let Some(row) = rows.next().await? {
+            expired.push((
+                row.get::<i64>(0)?,
+                row.get::<i64>(1)?,
+                row.get::<String>(2)?,
+                row.get::<String>(3)?,
+                row.get::<String>(4)?,
+            ));
+        }
+
+        for (reservation_id, project_id, path_pattern, expires_ts, agent_name) in &expired {
+            EventLogBmc::record(
+                ctx,
+                mm,
+                EventForCreate::new(
+                    "file_reservation.expired",
+                    Some(*project_id),
+                    Some(agent_name),
+                    serde_json::json!({
+                        "reservation_id": reservation_id,
+                        "path_pattern": path_pattern,
+                        "expires_ts": expires_ts,
+                    }),
+                ),
+            )
+            .await;
+        }
+        Ok(expired.len())
+    }
+
+    /// Returns the project and holder name of a reservation.
+    async fn owner(mm: &ModelManager, reservation_id: i64) -> Result<Option<(i64, String)>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT fr.project_id, a.name
+            FROM file_reservations AS fr
+            JOIN agents AS a ON a.id = fr.agent_id
+            WHERE fr.id = ?
+            "#,
+            )
+            .await?;
+        let mut rows = stmt.query([reservation_id]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some((row.get(0)?, row.get(1)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Logs the release and notifies watchers; neither failing fails the release itself.
+    pub(crate) async fn send_release_receipts(
+        ctx: &crate::Ctx,
+        mm: &ModelManager,
+        reservation_id: i64,
+        cause: ReleaseCause,
+    ) {
+        let (project_id, agent_name) = match Self::owner(mm, reservation_id).await {
+            Ok(Some((project_id, agent_name))) => (Some(project_id), Some(agent_name)),
+            _ => (None, None),
+        };
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                "file_reservation.released",
+                project_id,
+                agent_name.as_deref(),
+                serde_json::json!({ "reservation_id": reservation_id, "cause": cause }),
+            ),
+        )
+        .await;
+
+        if let Err(e) = ReservationWatcherBmc::notify_release(ctx, mm, reservation_id, cause).await
+        {
+            tracing::warn!(
+                "Failed to send release receipts for reservation {}: {}",
+                reservation_id,
+                e
+            );
+        }
+    }
+
+    fn from_row(row: Row) -> Result<FileReservation> {
         let created_ts_str: String = row.get(6).unwrap_or_default();
         let expires_ts_str: String = row.get(7).unwrap_or_default();
         let released_ts_str: Option<String> = row.get(8).unwrap_or_default();
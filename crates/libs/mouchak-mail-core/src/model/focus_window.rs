@@ -0,0 +1,405 @@
+//! Project focus windows.
+//!
+//! A focus window is a time-boxed quiet period for a project (e.g. during a
+//! critical release). While a window is active, messages whose importance is
+//! not in the window's allow-list are still stored and archived, but their
+//! recipient rows are held in `deferred_deliveries` instead of
+//! `message_recipients`, so they do not appear in inboxes.
+//!
+//! Deferred deliveries are released when the window is ended early with
+//! [`FocusWindowBmc::end`] or, once it elapses, by
+//! [`FocusWindowBmc::deliver_due`] (run periodically by the server). A
+//! deferred message is announced (`message.sent`, urgent notifications,
+//! `post_receive` plugins) when it is released, not when it is sent.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::message::MessageBmc;
+use crate::store::db_statement::Row;
+use crate::types::{MessageId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Importance levels accepted by messages, lowest first.
+pub const IMPORTANCE_LEVELS: &[&str] = &["low", "normal", "high", "urgent"];
+
+/// Importance levels delivered during a focus window when none are given.
+pub const DEFAULT_ALLOWED_IMPORTANCE: &[&str] = &["high", "urgent"];
+
+/// A time-boxed quiet period for a project.
+///
+/// # Fields
+///
+/// - `id` - Database primary key
+/// - `project_id` - Project the window applies to
+/// - `starts_ts` / `ends_ts` - Scheduled window bounds
+/// - `allowed_importance` - Importance levels delivered immediately
+/// - `reason` - Why the window was opened
+/// - `created_ts` - When the window was configured
+/// - `ended_ts` - When the window was ended early (if applicable)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusWindow {
+    pub id: i64,
+    pub project_id: ProjectId,
+    pub starts_ts: NaiveDateTime,
+    pub ends_ts: NaiveDateTime,
+    pub allowed_importance: Vec<String>,
+    pub reason: String,
+    pub created_ts: NaiveDateTime,
+    pub ended_ts: Option<NaiveDateTime>,
+}
+
+impl FocusWindow {
+    /// Returns `true` if a message of this importance is delivered immediately.
+    pub fn allows(&self, importance: &str) -> bool {
+        self.allowed_importance
+            .iter()
+            .any(|level| level.eq_ignore_ascii_case(importance))
+    }
+}
+
+/// Input data to open a focus window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusWindowForCreate {
+    pub project_id: ProjectId,
+    pub starts_ts: NaiveDateTime,
+    pub ends_ts: NaiveDateTime,
+    pub allowed_importance: Vec<String>,
+    pub reason: String,
+}
+
+/// Backend Model Controller for project focus windows.
+pub struct FocusWindowBmc;
+
+impl FocusWindowBmc {
+    /// Opens a focus window.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if the window ends before it starts, names an
+    /// unknown importance level, or overlaps another open window.
+    pub async fn create(_ctx: &Ctx, mm: &ModelManager, fw_c: FocusWindowForCreate) -> Result<i64> {
+        if fw_c.ends_ts <= fw_c.starts_ts {
+            return Err(crate::Error::InvalidInput(
+                "Focus window must end after it starts".into(),
+            ));
+        }
+
+        let mut allowed = Vec::new();
+        for level in &fw_c.allowed_importance {
+            let level = level.trim().to_lowercase();
+            if !IMPORTANCE_LEVELS.contains(&level.as_str()) {
+                return Err(crate::Error::InvalidInput(format!(
+                    "Unknown importance '{}' (expected one of: {})",
+                    level,
+                    IMPORTANCE_LEVELS.join(", ")
+                )));
+            }
+            if !allowed.contains(&level) {
+                allowed.push(level);
+            }
+        }
+
+        let starts_str = fw_c.starts_ts.format(TS_FORMAT).to_string();
+        let ends_str = fw_c.ends_ts.format(TS_FORMAT).to_string();
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id FROM project_focus_windows
+                WHERE project_id = ? AND ended_ts IS NULL AND starts_ts < ? AND ends_ts > ?
+                LIMIT 1
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                fw_c.project_id.get(),
+                ends_str.as_str(),
+                starts_str.as_str(),
+            ))
+            .await?;
+        if let Some(row) = rows.next().await? {
+            let existing: i64 = row.get(0)?;
+            return Err(crate::Error::InvalidInput(format!(
+                "Focus window overlaps existing window {}; end it first",
+                existing
+            )));
+        }
+
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO project_focus_windows (project_id, starts_ts, ends_ts, allowed_importance, reason)
+                VALUES (?, ?, ?, ?, ?)
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                fw_c.project_id.get(),
+                starts_str,
+                ends_str,
+                allowed.join(","),
+                fw_c.reason.as_str(),
+            ))
+            .await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(row.get::<i64>(0)?)
+        } else {
+            Err(crate::Error::InvalidInput(
+                "Failed to create focus window".into(),
+            ))
+        }
+    }
+
+    /// Retrieves a focus window by ID.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the window does not exist.
+    pub async fn get(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<FocusWindow> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, starts_ts, ends_ts, allowed_importance, reason, created_ts, ended_ts
+                FROM project_focus_windows
+                WHERE id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([id]).await?;
+
+        if let Some(row) = rows.next().await? {
+            Self::from_row(row)
+        } else {
+            Err(crate::Error::NotFound)
+        }
+    }
+
+    /// Returns the window currently in effect for a project, if any.
+    pub async fn get_active(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Option<FocusWindow>> {
+        let now_str = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, starts_ts, ends_ts, allowed_importance, reason, created_ts, ended_ts
+                FROM project_focus_windows
+                WHERE project_id = ? AND ended_ts IS NULL AND starts_ts <= ? AND ends_ts > ?
+                ORDER BY id DESC
+                LIMIT 1
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((project_id.get(), now_str.as_str(), now_str.as_str()))
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::from_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Holds a message's recipient rows until the window ends.
+    pub async fn defer_delivery(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        focus_window_id: i64,
+        message_id: MessageId,
+        recipients: &[(i64, &str)],
+    ) -> Result<()> {
+        let db = mm.db();
+        for (agent_id, recipient_type) in recipients {
+            let stmt = db
+                .prepare(
+                    r#"
+                    INSERT OR IGNORE INTO deferred_deliveries (focus_window_id, message_id, agent_id, recipient_type)
+                    VALUES (?, ?, ?, ?)
+                    "#,
+                )
+                .await?;
+            stmt.execute((
+                focus_window_id,
+                message_id.get(),
+                *agent_id,
+                *recipient_type,
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Counts deliveries still held by a window.
+    pub async fn count_deferred(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        focus_window_id: i64,
+    ) -> Result<i64> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT COUNT(*) FROM deferred_deliveries
+                WHERE focus_window_id = ? AND delivered_ts IS NULL
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([focus_window_id]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<i64>(0)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Counts deliveries still held by any focus window.
+    pub async fn count_all_deferred(_ctx: &Ctx, mm: &ModelManager) -> Result<i64> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT COUNT(*) FROM deferred_deliveries WHERE delivered_ts IS NULL")
+            .await?;
+        let mut rows = stmt.query(()).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<i64>(0)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Ends a window early and delivers everything it held.
+    ///
+    /// # Returns
+    /// Number of deliveries released.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the window does not exist or has already ended.
+    pub async fn end(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<usize> {
+        let now_str = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE project_focus_windows SET ended_ts = ?
+                WHERE id = ? AND ended_ts IS NULL
+                "#,
+            )
+            .await?;
+        let affected = stmt.execute((now_str, id)).await?;
+        if affected == 0 {
+            return Err(crate::Error::NotFound);
+        }
+
+        Self::deliver_window(ctx, mm, id).await
+    }
+
+    /// Delivers deferred mail for every window that has elapsed or ended.
+    ///
+    /// Intended to run periodically from a background task.
+    ///
+    /// # Returns
+    /// Number of deliveries released.
+    pub async fn deliver_due(ctx: &Ctx, mm: &ModelManager) -> Result<usize> {
+        let now_str = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT DISTINCT w.id
+                FROM project_focus_windows w
+                JOIN deferred_deliveries d ON d.focus_window_id = w.id
+                WHERE d.delivered_ts IS NULL AND (w.ended_ts IS NOT NULL OR w.ends_ts <= ?)
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([now_str]).await?;
+
+        let mut window_ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            window_ids.push(row.get::<i64>(0)?);
+        }
+
+        let mut delivered = 0;
+        for window_id in window_ids {
+            delivered += Self::deliver_window(ctx, mm, window_id).await?;
+        }
+        Ok(delivered)
+    }
+
+    /// Delivers what a window held and announces each released message.
+    async fn deliver_window(ctx: &Ctx, mm: &ModelManager, focus_window_id: i64) -> Result<usize> {
+        let now_str = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT DISTINCT message_id FROM deferred_deliveries
+                WHERE focus_window_id = ? AND delivered_ts IS NULL
+                ORDER BY message_id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([focus_window_id]).await?;
+        let mut message_ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            message_ids.push(MessageId::new(row.get::<i64>(0)?));
+        }
+
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT OR IGNORE INTO message_recipients (message_id, agent_id, recipient_type)
+                SELECT message_id, agent_id, recipient_type FROM deferred_deliveries
+                WHERE focus_window_id = ? AND delivered_ts IS NULL
+                "#,
+            )
+            .await?;
+        stmt.execute([focus_window_id]).await?;
+
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE deferred_deliveries SET delivered_ts = ?
+                WHERE focus_window_id = ? AND delivered_ts IS NULL
+                "#,
+            )
+            .await?;
+        let delivered = stmt.execute((now_str, focus_window_id)).await?;
+
+        for message_id in message_ids {
+            MessageBmc::announce_released(ctx, mm, message_id).await;
+        }
+        Ok(delivered)
+    }
+
+    fn from_row(row: Row) -> Result<FocusWindow> {
+        let starts_ts: String = row.get(2)?;
+        let ends_ts: String = row.get(3)?;
+        let allowed: String = row.get(4)?;
+        let created_ts: String = row.get(6)?;
+        let ended_ts: Option<String> = row.get(7)?;
+
+        Ok(FocusWindow {
+            id: row.get(0)?,
+            project_id: ProjectId::new(row.get(1)?),
+            starts_ts: parse_timestamp(&starts_ts, "project_focus_windows.starts_ts"),
+            ends_ts: parse_timestamp(&ends_ts, "project_focus_windows.ends_ts"),
+            allowed_importance: allowed
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+            reason: row.get(5)?,
+            created_ts: parse_timestamp(&created_ts, "project_focus_windows.created_ts"),
+            ended_ts: parse_timestamp_opt(ended_ts, "project_focus_windows.ended_ts"),
+        })
+    }
+}
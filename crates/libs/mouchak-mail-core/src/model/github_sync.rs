@@ -0,0 +1,498 @@
+//! GitHub issue and pull request cross-posting.
+//!
+//! A project maps to one GitHub repository. Mirroring a thread links it to
+//! an issue or PR in that repository: thread messages are posted as issue
+//! comments, and comments written on GitHub come back into the thread as
+//! messages from the project's `github` agent. The HTTP side lives in the
+//! server; this module keeps the mappings and the sync state.
+//!
+//! # Dedup
+//!
+//! Each link records the newest message posted (`last_message_id`) and the
+//! newest GitHub comment imported (`last_comment_id`), so repeated syncs
+//! never post or import the same item twice. Comments the server posted
+//! carry [`MIRROR_MARKER`] and are skipped on import, and messages from the
+//! `github` agent are never posted back.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{AgentBmc, AgentForCreate};
+use crate::model::message::{Message, MessageBmc, MessageForCreate};
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::{parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Agent that GitHub comments are sent from.
+pub const GITHUB_AGENT_NAME: &str = "github";
+
+/// Marks comments posted from a thread, so they are not imported back.
+pub const MIRROR_MARKER: &str = "<!-- mouchak-mail:mirror -->";
+
+/// A project's repository mapping.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectGithubRepo {
+    pub project_id: i64,
+    /// `owner/name`
+    pub repo: String,
+    pub created_ts: NaiveDateTime,
+    pub updated_ts: NaiveDateTime,
+}
+
+/// A thread mirrored to a GitHub issue or PR.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubThreadLink {
+    pub id: i64,
+    pub project_id: i64,
+    pub thread_id: String,
+    pub repo: String,
+    pub issue_number: i64,
+    pub issue_url: String,
+    /// Newest thread message posted to GitHub
+    pub last_message_id: i64,
+    /// Newest GitHub comment imported into the thread
+    pub last_comment_id: i64,
+    pub created_ts: NaiveDateTime,
+    pub synced_ts: Option<NaiveDateTime>,
+}
+
+/// Input to link a thread to an issue or PR.
+#[derive(Debug, Clone)]
+pub struct GithubThreadLinkForCreate {
+    pub project_id: ProjectId,
+    pub thread_id: String,
+    pub repo: String,
+    pub issue_number: i64,
+    pub issue_url: String,
+    /// Messages up to this id are treated as already posted
+    pub last_message_id: i64,
+}
+
+/// A comment read from a GitHub issue or PR.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubComment {
+    pub id: i64,
+    /// GitHub login of the author
+    pub author: String,
+    pub body: String,
+    pub html_url: String,
+}
+
+/// Checks `repo` is an `owner/name` pair.
+pub fn validate_repo(repo: &str) -> Result<()> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part.len() <= 100
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    match repo.split_once('/') {
+        Some((owner, name)) if valid_part(owner) && valid_part(name) => Ok(()),
+        _ => Err(Error::InvalidInput(format!(
+            "Invalid GitHub repository '{}': expected owner/name",
+            repo
+        ))),
+    }
+}
+
+/// Formats a thread message as a GitHub comment or issue body.
+pub fn mirror_body(message: &Message) -> String {
+    format!(
+        "**{}** ({} UTC):\n\n{}\n\n{}",
+        message.sender_name,
+        message.created_ts.format("%Y-%m-%d %H:%M"),
+        message.body_md,
+        MIRROR_MARKER
+    )
+}
+
+/// Backend Model Controller for GitHub cross-posting.
+pub struct GithubSyncBmc;
+
+impl GithubSyncBmc {
+    /// Maps a project to a repository, replacing any previous mapping.
+    ///
+    /// Existing thread links keep the repository they were created with.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if `repo` is not `owner/name`.
+    pub async fn set_repo(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        repo: &str,
+    ) -> Result<()> {
+        let repo = repo.trim();
+        validate_repo(repo)?;
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO project_github_repos (project_id, repo) VALUES (?, ?)
+                ON CONFLICT(project_id) DO UPDATE SET
+                    repo = excluded.repo,
+                    updated_ts = CURRENT_TIMESTAMP
+                "#,
+            )
+            .await?;
+        stmt.execute((project_id.get(), repo)).await?;
+        Ok(())
+    }
+
+    /// Gets a project's repository mapping, if any.
+    pub async fn get_repo(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Option<ProjectGithubRepo>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "SELECT project_id, repo, created_ts, updated_ts FROM project_github_repos WHERE project_id = ?",
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => {
+                let created_ts: String = row.get(2)?;
+                let updated_ts: String = row.get(3)?;
+                Ok(Some(ProjectGithubRepo {
+                    project_id: row.get(0)?,
+                    repo: row.get(1)?,
+                    created_ts: parse_timestamp(&created_ts, "created_ts"),
+                    updated_ts: parse_timestamp(&updated_ts, "updated_ts"),
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Removes a project's repository mapping. Linked threads keep syncing.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no mapping.
+    pub async fn clear_repo(_ctx: &Ctx, mm: &ModelManager, project_id: ProjectId) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM project_github_repos WHERE project_id = ?")
+            .await?;
+        if stmt.execute([project_id.get()]).await? == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Links a thread to an issue or PR.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if the thread or the issue is already linked.
+    pub async fn link_thread(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        link_c: GithubThreadLinkForCreate,
+    ) -> Result<GithubThreadLink> {
+        validate_repo(&link_c.repo)?;
+        if Self::get_link(ctx, mm, link_c.project_id, &link_c.thread_id)
+            .await?
+            .is_some()
+        {
+            return Err(Error::InvalidInput(format!(
+                "Thread '{}' is already mirrored to GitHub",
+                link_c.thread_id
+            )));
+        }
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT 1 FROM github_thread_links WHERE repo = ? AND issue_number = ?")
+            .await?;
+        let mut rows = stmt
+            .query((link_c.repo.as_str(), link_c.issue_number))
+            .await?;
+        if rows.next().await?.is_some() {
+            return Err(Error::InvalidInput(format!(
+                "{}#{} is already linked to a thread",
+                link_c.repo, link_c.issue_number
+            )));
+        }
+
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO github_thread_links
+                    (project_id, thread_id, repo, issue_number, issue_url, last_message_id)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            link_c.project_id.get(),
+            link_c.thread_id.as_str(),
+            link_c.repo.as_str(),
+            link_c.issue_number,
+            link_c.issue_url.as_str(),
+            link_c.last_message_id,
+        ))
+        .await?;
+        Self::get_link(ctx, mm, link_c.project_id, &link_c.thread_id)
+            .await?
+            .ok_or(Error::NotFound)
+    }
+
+    /// Gets the link of a thread, if it is mirrored.
+    pub async fn get_link(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Option<GithubThreadLink>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE project_id = ? AND thread_id = ?",
+                SELECT_LINK
+            ))
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::link_from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Lists mirrored threads of a project, or of all projects.
+    pub async fn list_links(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: Option<ProjectId>,
+    ) -> Result<Vec<GithubThreadLink>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE ?1 IS NULL OR project_id = ?1 ORDER BY id",
+                SELECT_LINK
+            ))
+            .await?;
+        let mut rows = stmt.query([project_id.map(|p| p.get())]).await?;
+        let mut links = Vec::new();
+        while let Some(row) = rows.next().await? {
+            links.push(Self::link_from_row(&row)?);
+        }
+        Ok(links)
+    }
+
+    /// Removes a thread's link. Nothing already posted or imported changes.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the thread is not mirrored.
+    pub async fn unlink_thread(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM github_thread_links WHERE project_id = ? AND thread_id = ?")
+            .await?;
+        if stmt.execute((project_id.get(), thread_id)).await? == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Thread messages not yet posted to GitHub, oldest first.
+    ///
+    /// Messages from the `github` agent are left out, so imported comments
+    /// are never posted back.
+    pub async fn unposted_messages(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        link: &GithubThreadLink,
+    ) -> Result<Vec<Message>> {
+        let messages = MessageBmc::list_by_thread_after(
+            ctx,
+            mm,
+            ProjectId::new(link.project_id),
+            &link.thread_id,
+            Some(MessageId::new(link.last_message_id)),
+        )
+        .await?;
+        Ok(messages
+            .into_iter()
+            .filter(|m| m.sender_name != GITHUB_AGENT_NAME)
+            .collect())
+    }
+
+    /// Records that messages up to `message_id` were posted.
+    pub async fn mark_posted(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        link_id: i64,
+        message_id: MessageId,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE github_thread_links
+                SET last_message_id = MAX(last_message_id, ?), synced_ts = CURRENT_TIMESTAMP
+                WHERE id = ?
+                "#,
+            )
+            .await?;
+        stmt.execute((message_id.get(), link_id)).await?;
+        Ok(())
+    }
+
+    /// Imports a GitHub comment into the linked thread.
+    ///
+    /// The comment is sent from the `github` agent to everyone who took part
+    /// in the thread. Comments at or below the link's `last_comment_id`, and
+    /// comments posted from the thread, are skipped.
+    ///
+    /// # Returns
+    /// The new message's id, or `None` if the comment was skipped.
+    pub async fn import_comment(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        link: &GithubThreadLink,
+        comment: &GithubComment,
+    ) -> Result<Option<MessageId>> {
+        if comment.id <= link.last_comment_id {
+            return Ok(None);
+        }
+
+        let mut message_id = None;
+        if !comment.body.contains(MIRROR_MARKER) {
+            let project_id = ProjectId::new(link.project_id);
+            let sender_id = Self::github_agent(ctx, mm, project_id).await?;
+            let mut recipient_ids = MessageBmc::thread_participants(
+                ctx,
+                mm,
+                ProjectId::new(link.project_id),
+                &link.thread_id,
+            )
+            .await?;
+            recipient_ids.retain(|id| *id != sender_id);
+            if !recipient_ids.is_empty() {
+                let subject = MessageBmc::list_by_thread(
+                    ctx,
+                    mm,
+                    ProjectId::new(link.project_id),
+                    &link.thread_id,
+                )
+                .await?
+                .first()
+                .map(|m| m.subject.clone())
+                .unwrap_or_else(|| format!("{}#{}", link.repo, link.issue_number));
+                let subject = if subject.starts_with("Re: ") {
+                    subject
+                } else {
+                    format!("Re: {}", subject)
+                };
+                let msg_c = MessageForCreate {
+                    project_id,
+                    sender_id,
+                    recipient_ids,
+                    cc_ids: None,
+                    bcc_ids: None,
+                    subject,
+                    body_md: format!(
+                        "**@{}** commented on [{}#{}]({}):\n\n{}",
+                        comment.author,
+                        link.repo,
+                        link.issue_number,
+                        comment.html_url,
+                        comment.body
+                    ),
+                    thread_id: Some(link.thread_id.clone()),
+                    importance: None,
+                    ack_required: false,
+                };
+                message_id = Some(MessageBmc::create(ctx, mm, msg_c).await?);
+            }
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE github_thread_links
+                SET last_comment_id = MAX(last_comment_id, ?), synced_ts = CURRENT_TIMESTAMP
+                WHERE id = ?
+                "#,
+            )
+            .await?;
+        stmt.execute((comment.id, link.id)).await?;
+        Ok(message_id)
+    }
+
+    /// The project's `github` agent, registered on first use.
+    pub async fn github_agent(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<AgentId> {
+        match AgentBmc::get_by_name(ctx, mm, project_id, GITHUB_AGENT_NAME).await {
+            Ok(agent) => Ok(agent.id),
+            Err(Error::AgentNotFound { .. }) | Err(Error::NotFound) => {
+                let id = AgentBmc::create(
+                    ctx,
+                    mm,
+                    AgentForCreate {
+                        project_id,
+                        name: GITHUB_AGENT_NAME.to_string(),
+                        program: "github".to_string(),
+                        model: "none".to_string(),
+                        task_description: "Relays comments from mirrored GitHub issues and PRs"
+                            .to_string(),
+                    },
+                )
+                .await?;
+                Ok(id)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn link_from_row(row: &Row) -> Result<GithubThreadLink> {
+        let created_ts: String = row.get(8)?;
+        let synced_ts: Option<String> = row.get(9)?;
+        Ok(GithubThreadLink {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            thread_id: row.get(2)?,
+            repo: row.get(3)?,
+            issue_number: row.get(4)?,
+            issue_url: row.get(5)?,
+            last_message_id: row.get(6)?,
+            last_comment_id: row.get(7)?,
+            created_ts: parse_timestamp(&created_ts, "created_ts"),
+            synced_ts: parse_timestamp_opt(synced_ts, "synced_ts"),
+        })
+    }
+}
+
+const SELECT_LINK: &str = r#"
+    SELECT id, project_id, thread_id, repo, issue_number, issue_url,
+           last_message_id, last_comment_id, created_ts, synced_ts
+    FROM github_thread_links
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_repo() {
+        assert!(validate_repo("Avyukth/mouchak-mail").is_ok());
+        assert!(validate_repo("org/repo.rs").is_ok());
+        assert!(validate_repo("mouchak-mail").is_err());
+        assert!(validate_repo("org/").is_err());
+        assert!(validate_repo("org/repo/extra").is_err());
+        assert!(validate_repo("org/re po").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Globally unique thread identifiers.
+//!
+//! Thread ids are strings scoped to a project, so two projects of a product
+//! may use the same id for unrelated threads, and one conversation carried
+//! into another project starts a new id there. Every project thread is
+//! therefore mapped to a global thread id, a ULID
+//! (see [`crate::utils::ulid`]), when its first message is created.
+//!
+//! A message created with a global thread id as its `thread_id` joins that
+//! global thread in its own project. Replies and forwards across projects
+//! do this, so product tools can follow one conversation through every
+//! project it touched without merging unrelated threads that happen to share
+//! a local id.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::types::ProjectId;
+use crate::utils::ulid::new_ulid;
+use crate::{Error, Result};
+use serde::Serialize;
+
+/// A project's part of a global thread.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThreadMember {
+    pub project_id: ProjectId,
+    pub thread_id: String,
+}
+
+/// A global thread and the project threads it spans.
+///
+/// # Fields
+///
+/// - `members` - One entry per project, ordered by project id
+#[derive(Debug, Clone, Serialize)]
+pub struct GlobalThread {
+    pub global_thread_id: String,
+    pub members: Vec<ThreadMember>,
+}
+
+/// Backend Model Controller for global thread ids.
+pub struct GlobalThreadBmc;
+
+impl GlobalThreadBmc {
+    /// Maps a project thread to its global id, assigning one if the thread
+    /// is new.
+    ///
+    /// A new thread whose id is itself a known global thread id joins that
+    /// global thread; any other new thread gets a fresh ULID.
+    pub(crate) async fn assign(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<String> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO global_threads (project_id, thread_id, global_thread_id)
+                VALUES (?1, ?2, COALESCE(
+                    (SELECT global_thread_id FROM global_threads WHERE global_thread_id = ?2 LIMIT 1),
+                    ?3
+                ))
+                ON CONFLICT(project_id, thread_id) DO NOTHING
+                "#,
+            )
+            .await?;
+        stmt.execute((project_id.get(), thread_id, new_ulid()))
+            .await?;
+
+        Self::get_id(ctx, mm, project_id, thread_id)
+            .await?
+            .ok_or(Error::NotFound)
+    }
+
+    /// The global id of a project thread, if it has any messages.
+    pub async fn get_id(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Option<String>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "SELECT global_thread_id FROM global_threads WHERE project_id = ? AND thread_id = ?",
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Resolves `id` to the global threads it names within `project_ids`.
+    ///
+    /// `id` may be a global thread id, which names at most one thread, or a
+    /// local thread id, which names the global thread of every listed project
+    /// using it. Distinct threads sharing a local id stay separate. Members
+    /// outside `project_ids` are left out.
+    pub async fn resolve(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_ids: &[ProjectId],
+        id: &str,
+    ) -> Result<Vec<GlobalThread>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT g.global_thread_id, g.project_id, g.thread_id
+                FROM global_threads AS g
+                WHERE g.global_thread_id IN (
+                    SELECT global_thread_id FROM global_threads
+                    WHERE global_thread_id = ?1 OR thread_id = ?1
+                )
+                ORDER BY g.global_thread_id, g.project_id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([id]).await?;
+
+        let mut threads: Vec<GlobalThread> = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let global_thread_id: String = row.get(0)?;
+            let member = ThreadMember {
+                project_id: ProjectId::new(row.get(1)?),
+                thread_id: row.get(2)?,
+            };
+            if !project_ids.contains(&member.project_id) {
+                continue;
+            }
+            match threads.last_mut() {
+                Some(thread) if thread.global_thread_id == global_thread_id => {
+                    thread.members.push(member);
+                }
+                _ => threads.push(GlobalThread {
+                    global_thread_id,
+                    members: vec![member],
+                }),
+            }
+        }
+
+        // A local id only names threads that use it in one of the projects
+        threads.retain(|t| t.global_thread_id == id || t.members.iter().any(|m| m.thread_id == id));
+        Ok(threads)
+    }
+}
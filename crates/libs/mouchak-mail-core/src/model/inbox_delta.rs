@@ -0,0 +1,267 @@
+//! Inbox snapshot diffs for polling agents.
+//!
+//! [`InboxDeltaBmc::check`] returns what changed in an agent's inbox since a
+//! token from an earlier call, plus a new token to pass next time:
+//!
+//! - **New messages**: inbox messages with an id above the token's high-water
+//!   mark, oldest first
+//! - **State changes**: earlier inbox messages whose read or acknowledgement
+//!   time is at or after the token's timestamp, e.g. read from the web UI
+//!
+//! Without a token the call returns a snapshot of the newest messages and a
+//! token for the current state. State changes at the token's second boundary
+//! may be reported twice, never missed.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::message::Message;
+use crate::model::triage::TriageItem;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Largest number of new messages returned by one check.
+pub const MAX_DELTA_LIMIT: i64 = 200;
+
+/// Position of a delta token: the newest inbox message seen and when the
+/// check ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InboxDeltaToken {
+    pub last_message_id: i64,
+    pub checked_at: NaiveDateTime,
+}
+
+impl InboxDeltaToken {
+    /// Encodes the token as an opaque string (`v1.<message id>.<unix seconds>`).
+    pub fn encode(&self) -> String {
+        format!(
+            "v1.{}.{}",
+            self.last_message_id,
+            self.checked_at.and_utc().timestamp()
+        )
+    }
+
+    /// Decodes a token produced by [`Self::encode`].
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a malformed token.
+    pub fn decode(token: &str) -> Result<Self> {
+        let invalid = || Error::InvalidInput(format!("Invalid inbox delta token '{}'", token));
+        let mut parts = token.trim().split('.');
+        if parts.next() != Some("v1") {
+            return Err(invalid());
+        }
+        let last_message_id: i64 = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let checked_at = parts
+            .next()
+            .and_then(|p| p.parse::<i64>().ok())
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .ok_or_else(invalid)?
+            .naive_utc();
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            last_message_id,
+            checked_at,
+        })
+    }
+}
+
+/// Read/ack state of an earlier inbox message that changed since the token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipientStateChange {
+    pub message_id: i64,
+    pub read_ts: Option<NaiveDateTime>,
+    pub ack_ts: Option<NaiveDateTime>,
+}
+
+/// Result of an inbox delta check.
+///
+/// # Fields
+///
+/// - `new_messages` - Messages since the token (or the snapshot), oldest first
+/// - `changed` - Earlier messages whose read/ack state changed
+/// - `token` - Token for the next check
+/// - `has_more` - More new messages are waiting; check again with `token`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboxDelta {
+    pub new_messages: Vec<TriageItem>,
+    pub changed: Vec<RecipientStateChange>,
+    pub token: String,
+    pub has_more: bool,
+}
+
+/// Backend Model Controller for inbox snapshot diffs.
+pub struct InboxDeltaBmc;
+
+impl InboxDeltaBmc {
+    /// Returns the changes in an agent's inbox since `since_token`.
+    ///
+    /// `limit` caps the new messages returned (at most [`MAX_DELTA_LIMIT`]).
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a malformed token.
+    pub async fn check(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        since_token: Option<&str>,
+        limit: i64,
+    ) -> Result<InboxDelta> {
+        let since = since_token
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(InboxDeltaToken::decode)
+            .transpose()?;
+        let limit = limit.clamp(1, MAX_DELTA_LIMIT);
+        // Taken before querying, so changes made during the check are
+        // reported again next time rather than lost
+        let checked_at = chrono::Utc::now().naive_utc();
+
+        let db = mm.db();
+        let select = r#"
+            SELECT
+                m.id, m.project_id, m.sender_id, ag.name, m.thread_id, m.subject, m.body_md,
+                m.importance, m.ack_required, m.created_ts, m.attachments, mr.read_ts, mr.ack_ts,
+                COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
+            FROM messages AS m
+            JOIN message_recipients AS mr ON m.id = mr.message_id
+            JOIN agents AS ag ON m.sender_id = ag.id
+            WHERE mr.agent_id = ?1 AND m.project_id = ?2
+            "#;
+        let sql = match since {
+            // Snapshot: newest messages, fetched newest first and reversed below
+            None => format!("{select} ORDER BY m.id DESC LIMIT ?3"),
+            Some(_) => format!("{select} AND m.id > ?4 ORDER BY m.id ASC LIMIT ?3 + 1"),
+        };
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = match since {
+            None => {
+                stmt.query((agent_id.get(), project_id.get(), limit))
+                    .await?
+            }
+            Some(token) => {
+                stmt.query((
+                    agent_id.get(),
+                    project_id.get(),
+                    limit,
+                    token.last_message_id,
+                ))
+                .await?
+            }
+        };
+
+        let mut new_messages = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(9)?;
+            let attachments: String = row.get(10)?;
+            let attachments: Vec<Value> = serde_json::from_str(&attachments)?;
+            new_messages.push(TriageItem {
+                message: Message {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    sender_id: row.get(2)?,
+                    sender_name: row.get(3)?,
+                    thread_id: row.get(4)?,
+                    subject: row.get(5)?,
+                    body_md: row.get(6)?,
+                    importance: row.get(7)?,
+                    ack_required: row.get(8)?,
+                    created_ts: parse_timestamp(&created_ts, "created_ts"),
+                    attachments,
+                    public_id: row.get(13)?,
+                },
+                read_ts: parse_timestamp_opt(row.get(11)?, "read_ts"),
+                ack_ts: parse_timestamp_opt(row.get(12)?, "ack_ts"),
+            });
+        }
+
+        let has_more = since.is_some() && new_messages.len() as i64 > limit;
+        match since {
+            None => new_messages.reverse(),
+            Some(_) => new_messages.truncate(limit as usize),
+        }
+
+        let changed = match since {
+            Some(token) => {
+                let stmt = db
+                    .prepare(
+                        r#"
+                        SELECT mr.message_id, mr.read_ts, mr.ack_ts
+                        FROM message_recipients AS mr
+                        JOIN messages AS m ON m.id = mr.message_id
+                        WHERE mr.agent_id = ?1 AND m.project_id = ?2 AND m.id <= ?3
+                          AND (mr.read_ts >= ?4 OR mr.ack_ts >= ?4)
+                        ORDER BY mr.message_id ASC
+                        "#,
+                    )
+                    .await?;
+                let mut rows = stmt
+                    .query((
+                        agent_id.get(),
+                        project_id.get(),
+                        token.last_message_id,
+                        token.checked_at.format(TS_FORMAT).to_string(),
+                    ))
+                    .await?;
+                let mut changed = Vec::new();
+                while let Some(row) = rows.next().await? {
+                    changed.push(RecipientStateChange {
+                        message_id: row.get(0)?,
+                        read_ts: parse_timestamp_opt(row.get(1)?, "read_ts"),
+                        ack_ts: parse_timestamp_opt(row.get(2)?, "ack_ts"),
+                    });
+                }
+                changed
+            }
+            None => Vec::new(),
+        };
+
+        let last_message_id = match (since, new_messages.last()) {
+            (_, Some(item)) => item.message.id,
+            (Some(token), None) => token.last_message_id,
+            (None, None) => 0,
+        };
+        Ok(InboxDelta {
+            new_messages,
+            changed,
+            token: InboxDeltaToken {
+                last_message_id,
+                checked_at,
+            }
+            .encode(),
+            has_more,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_round_trip() {
+        let token = InboxDeltaToken {
+            last_message_id: 42,
+            checked_at: NaiveDateTime::parse_from_str("2026-01-02 03:04:05", TS_FORMAT).unwrap(),
+        };
+        assert_eq!(token.encode(), "v1.42.1767323045");
+        assert_eq!(InboxDeltaToken::decode(&token.encode()).unwrap(), token);
+    }
+
+    #[test]
+    fn test_malformed_tokens_are_rejected() {
+        for token in ["", "v2.1.2", "v1.x.2", "v1.1", "v1.1.2.3", "42"] {
+            assert!(InboxDeltaToken::decode(token).is_err(), "{}", token);
+        }
+    }
+}
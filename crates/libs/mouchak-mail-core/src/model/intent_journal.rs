@@ -0,0 +1,291 @@
+//! Write-ahead journal of multi-step operations.
+//!
+//! Sending a message writes the message row and its recipients (or held
+//! deliveries) in one transaction, then commits it to the Git archive in
+//! the background; storing an attachment writes its content before its
+//! row. A crash between those steps used to leave orphans behind: content
+//! without a row, messages missing from the archive.
+//!
+//! Such operations record an intent in `operation_intents` and remove it
+//! once they finished. [`IntentJournalBmc::recover`] runs at server
+//! startup and settles the intents recorded before it:
+//!
+//! | Kind | Recovery |
+//! |------|----------|
+//! | `message.create` | Completed: the message is committed to the Git archive |
+//! | `attachment.store` | The fresh copy of the content is removed, and so is its blob if it was never recorded |
+//!
+//! A send's intent is written in its transaction, so a send that did not
+//! commit leaves nothing to recover. An intent that keeps failing to
+//! recover is dropped after [`MAX_RECOVERY_ATTEMPTS`].
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::attachment::AttachmentBmc;
+use crate::model::message::MessageBmc;
+use crate::types::{AgentId, MessageId};
+use crate::utils::{TS_FORMAT, parse_timestamp};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+/// Intent of [`MessageBmc::create`].
+pub const MESSAGE_CREATE: &str = "message.create";
+
+/// Intent of [`AttachmentBmc::store`](crate::model::attachment::AttachmentBmc::store).
+pub const ATTACHMENT_STORE: &str = "attachment.store";
+
+/// Stage of a newly recorded intent.
+pub const STAGE_STARTED: &str = "started";
+
+/// Failed recoveries of one intent before it is dropped.
+pub const MAX_RECOVERY_ATTEMPTS: i64 = 5;
+
+/// A recorded intent.
+#[derive(Debug, Clone, Serialize)]
+pub struct Intent {
+    pub id: i64,
+    /// [`MESSAGE_CREATE`] or [`ATTACHMENT_STORE`]
+    pub kind: String,
+    pub stage: String,
+    pub payload: Value,
+    pub created_ts: NaiveDateTime,
+    pub updated_ts: NaiveDateTime,
+}
+
+/// Payload of a [`MESSAGE_CREATE`] intent: a sent message whose archive
+/// commit may be missing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageIntent {
+    pub message_id: MessageId,
+    /// Direct recipients, named in the archive commit
+    pub to: Vec<AgentId>,
+}
+
+/// Payload of an [`ATTACHMENT_STORE`] intent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentIntent {
+    /// Where the content is written
+    pub location: String,
+    /// Blob the content is moved to, once its hash is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+/// What [`IntentJournalBmc::recover`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RecoveryReport {
+    /// Operations finished
+    pub completed: usize,
+    /// Operations undone
+    pub rolled_back: usize,
+    /// Intents kept for another attempt, or dropped after too many
+    pub failed: usize,
+}
+
+enum Outcome {
+    Completed,
+    RolledBack,
+}
+
+/// Backend Model Controller for the intent journal.
+pub struct IntentJournalBmc;
+
+impl IntentJournalBmc {
+    /// Records the intent to run an operation of `kind`, at [`STAGE_STARTED`].
+    pub async fn begin(mm: &ModelManager, kind: &str, payload: &Value) -> Result<i64> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "INSERT INTO operation_intents (kind, stage, payload) VALUES (?, ?, ?) RETURNING id",
+            )
+            .await?;
+        let mut rows = stmt
+            .query((kind, STAGE_STARTED, payload.to_string()))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::InvalidInput("Failed to record intent".into())),
+        }
+    }
+
+    /// Moves an intent to `stage`, merging `patch` into its payload.
+    pub async fn advance(mm: &ModelManager, id: i64, stage: &str, patch: &Value) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE operation_intents
+                SET stage = ?, payload = json_patch(payload, ?), updated_ts = CURRENT_TIMESTAMP
+                WHERE id = ?
+                "#,
+            )
+            .await?;
+        stmt.execute((stage, patch.to_string(), id)).await?;
+        Ok(())
+    }
+
+    /// Removes an intent once its operation finished or was undone.
+    pub async fn complete(mm: &ModelManager, id: i64) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM operation_intents WHERE id = ?")
+            .await?;
+        stmt.execute([id]).await?;
+        Ok(())
+    }
+
+    /// Intents last touched before `before`, oldest first.
+    pub async fn list_abandoned(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        before: NaiveDateTime,
+    ) -> Result<Vec<Intent>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, kind, stage, payload, created_ts, updated_ts
+                FROM operation_intents
+                WHERE updated_ts < ?
+                ORDER BY id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([before.format(TS_FORMAT).to_string()]).await?;
+        let mut intents = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let payload: String = row.get(3)?;
+            let created_ts: String = row.get(4)?;
+            let updated_ts: String = row.get(5)?;
+            intents.push(Intent {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                stage: row.get(2)?,
+                payload: serde_json::from_str(&payload)?,
+                created_ts: parse_timestamp(&created_ts, "created_ts"),
+                updated_ts: parse_timestamp(&updated_ts, "updated_ts"),
+            });
+        }
+        Ok(intents)
+    }
+
+    /// Completes or rolls back operations abandoned before `before`, the
+    /// time this process started.
+    ///
+    /// Does nothing while writes are suspended. Failures to settle a single
+    /// intent are counted in [`RecoveryReport::failed`] rather than returned.
+    pub async fn recover(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        before: NaiveDateTime,
+    ) -> Result<RecoveryReport> {
+        let mut report = RecoveryReport::default();
+        if mm.is_read_only() {
+            return Ok(report);
+        }
+        for intent in Self::list_abandoned(ctx, mm, before).await? {
+            let outcome = match intent.kind.as_str() {
+                MESSAGE_CREATE => recover_message(ctx, mm, &intent).await,
+                ATTACHMENT_STORE => recover_attachment(ctx, mm, &intent).await,
+                // Written by a newer version; left for it to recover
+                other => {
+                    warn!("Skipping intent {} of unknown kind '{}'", intent.id, other);
+                    continue;
+                }
+            };
+            match outcome {
+                Ok(outcome) => {
+                    Self::complete(mm, intent.id).await?;
+                    match outcome {
+                        Outcome::Completed => report.completed += 1,
+                        Outcome::RolledBack => report.rolled_back += 1,
+                    }
+                }
+                Err(e) => {
+                    report.failed += 1;
+                    Self::record_failure(mm, &intent, &e).await?;
+                }
+            }
+        }
+        if report != RecoveryReport::default() {
+            info!(
+                "Recovered interrupted operations: {} completed, {} rolled back, {} failed",
+                report.completed, report.rolled_back, report.failed
+            );
+        }
+        Ok(report)
+    }
+
+    /// Counts a failed recovery, dropping the intent after too many.
+    async fn record_failure(mm: &ModelManager, intent: &Intent, e: &Error) -> Result<()> {
+        let attempts = intent
+            .payload
+            .get("attempts")
+            .and_then(Value::as_i64)
+            .unwrap_or(0)
+            + 1;
+        if attempts >= MAX_RECOVERY_ATTEMPTS {
+            error!(
+                "Giving up on {} intent {} after {} attempts: {}",
+                intent.kind, intent.id, attempts, e
+            );
+            return Self::complete(mm, intent.id).await;
+        }
+        warn!(
+            "Failed to recover {} intent {} (attempt {}): {}",
+            intent.kind, intent.id, attempts, e
+        );
+        Self::advance(
+            mm,
+            intent.id,
+            &intent.stage,
+            &serde_json::json!({ "attempts": attempts }),
+        )
+        .await
+    }
+}
+
+async fn recover_message(ctx: &Ctx, mm: &ModelManager, intent: &Intent) -> Result<Outcome> {
+    let payload: MessageIntent = serde_json::from_value(intent.payload.clone())?;
+    match MessageBmc::archive(ctx, mm, payload.message_id, &payload.to).await {
+        // Deleted since; nothing left to archive
+        Ok(()) | Err(Error::MessageNotFound(_)) => Ok(Outcome::Completed),
+        Err(e) => Err(e),
+    }
+}
+
+async fn recover_attachment(_ctx: &Ctx, mm: &ModelManager, intent: &Intent) -> Result<Outcome> {
+    let payload: AttachmentIntent = serde_json::from_value(intent.payload.clone())?;
+    let db = mm.db();
+    // Uploads from before deduplication recorded their content in place
+    let stmt = db
+        .prepare("SELECT 1 FROM attachments WHERE stored_path = ? LIMIT 1")
+        .await?;
+    let mut rows = stmt.query([payload.location.as_str()]).await?;
+    if rows.next().await?.is_some() {
+        return Ok(Outcome::Completed);
+    }
+
+    AttachmentBmc::remove_content(mm, &payload.location).await?;
+    let Some(blob) = payload.blob else {
+        info!(
+            "Removed content of interrupted attachment upload {}",
+            payload.location
+        );
+        return Ok(Outcome::RolledBack);
+    };
+    // A recorded blob without references is left to gc-attachments
+    let stmt = db
+        .prepare("SELECT 1 FROM attachment_blobs WHERE location = ? LIMIT 1")
+        .await?;
+    let mut rows = stmt.query([blob.as_str()]).await?;
+    if rows.next().await?.is_some() {
+        return Ok(Outcome::Completed);
+    }
+    AttachmentBmc::remove_content(mm, &blob).await?;
+    info!("Removed unrecorded attachment blob {}", blob);
+    Ok(Outcome::RolledBack)
+}
@@ -0,0 +1,494 @@
+//! Message labels for triage.
+//!
+//! Labels are declared per project with a name, a color for the UI and an
+//! optional description, then applied to any number of messages of that
+//! project. Unlike importance, which the sender picks, labels are applied
+//! and removed by whoever triages the mail (`blocked`, `needs-review`,
+//! `ship-it`).
+//!
+//! Deleting a label removes it from every message. Application and removal
+//! are recorded in the event log as `label.applied` and `label.removed`.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::model::message::{Message, MessageBmc};
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::parse_timestamp;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Maximum length of a label name.
+pub const MAX_LABEL_NAME_LEN: usize = 50;
+
+/// Colors given to labels created without one, picked by name so a label
+/// keeps its color when recreated.
+const LABEL_PALETTE: &[&str] = &[
+    "#dc2626", "#ea580c", "#ca8a04", "#16a34a", "#0d9488", "#2563eb", "#7c3aed", "#db2777",
+];
+
+/// A project label.
+///
+/// # Fields
+///
+/// - `color` - `#rrggbb`
+/// - `message_count` - Messages carrying the label
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    pub id: i64,
+    pub project_id: ProjectId,
+    pub name: String,
+    pub color: String,
+    pub description: String,
+    pub created_ts: NaiveDateTime,
+    pub message_count: i64,
+}
+
+/// Input for [`LabelBmc::create`].
+#[derive(Debug, Clone)]
+pub struct LabelForCreate {
+    pub project_id: ProjectId,
+    pub name: String,
+    /// `#rrggbb`; picked from a palette when `None`
+    pub color: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Changes for [`LabelBmc::update`]; `None` keeps the current value.
+#[derive(Debug, Clone, Default)]
+pub struct LabelForUpdate {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Checks a label name: lowercase letters, digits, `-`, `_` and `/`,
+/// starting with a letter or digit.
+fn validate_label_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_LABEL_NAME_LEN
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '/'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Invalid label name '{}': use lowercase letters, digits, '-', '_' and '/' (max {} chars)",
+            name, MAX_LABEL_NAME_LEN
+        )))
+    }
+}
+
+/// Normalizes a `#rrggbb` color to lowercase.
+fn validate_color(color: &str) -> Result<String> {
+    let hex = color.trim().strip_prefix('#').unwrap_or_default();
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(format!("#{}", hex.to_ascii_lowercase()))
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Invalid label color '{}': use #rrggbb",
+            color
+        )))
+    }
+}
+
+/// The palette color for a label name.
+fn default_color(name: &str) -> &'static str {
+    let index = name
+        .bytes()
+        .fold(0usize, |acc, b| acc.wrapping_add(usize::from(b)))
+        % LABEL_PALETTE.len();
+    LABEL_PALETTE.get(index).copied().unwrap_or("#6b7280")
+}
+
+const SELECT_LABELS: &str = r#"
+    SELECT l.id, l.project_id, l.name, l.color, l.description, l.created_ts,
+           (SELECT COUNT(*) FROM message_labels ml WHERE ml.label_id = l.id)
+    FROM labels l
+"#;
+
+/// Backend Model Controller for message labels.
+pub struct LabelBmc;
+
+impl LabelBmc {
+    /// Creates a label. Returns its id.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an invalid name or color, or a name
+    /// already used in the project.
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, label_c: LabelForCreate) -> Result<i64> {
+        validate_label_name(&label_c.name)?;
+        let color = match &label_c.color {
+            Some(color) => validate_color(color)?,
+            None => default_color(&label_c.name).to_string(),
+        };
+        if Self::get_by_name(ctx, mm, label_c.project_id, &label_c.name)
+            .await
+            .is_ok()
+        {
+            return Err(Error::InvalidInput(format!(
+                "Label '{}' already exists",
+                label_c.name
+            )));
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO labels (project_id, name, color, description)
+                VALUES (?, ?, ?, ?)
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                label_c.project_id.get(),
+                label_c.name.as_str(),
+                color,
+                label_c.description.unwrap_or_default(),
+            ))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::InvalidInput("Failed to create label".into())),
+        }
+    }
+
+    /// A project's label by name.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no such label.
+    pub async fn get_by_name(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        name: &str,
+    ) -> Result<Label> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE l.project_id = ? AND l.name = ?",
+                SELECT_LABELS
+            ))
+            .await?;
+        let mut rows = stmt.query((project_id.get(), name)).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Lists a project's labels by name, with message counts.
+    pub async fn list(_ctx: &Ctx, mm: &ModelManager, project_id: ProjectId) -> Result<Vec<Label>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE l.project_id = ? ORDER BY l.name",
+                SELECT_LABELS
+            ))
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut labels = Vec::new();
+        while let Some(row) = rows.next().await? {
+            labels.push(Self::from_row(&row)?);
+        }
+        Ok(labels)
+    }
+
+    /// Renames, recolors or redescribes a label. Messages keep it.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no label `name`, and
+    /// `InvalidInput` for an invalid new name or color, or a new name
+    /// already in use.
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        name: &str,
+        label_u: LabelForUpdate,
+    ) -> Result<Label> {
+        let current = Self::get_by_name(ctx, mm, project_id, name).await?;
+        let new_name = label_u.name.unwrap_or_else(|| current.name.clone());
+        if new_name != current.name {
+            validate_label_name(&new_name)?;
+            if Self::get_by_name(ctx, mm, project_id, &new_name)
+                .await
+                .is_ok()
+            {
+                return Err(Error::InvalidInput(format!(
+                    "Label '{}' already exists",
+                    new_name
+                )));
+            }
+        }
+        let color = match &label_u.color {
+            Some(color) => validate_color(color)?,
+            None => current.color,
+        };
+        let description = label_u.description.unwrap_or(current.description);
+
+        let db = mm.db();
+        let stmt = db
+            .prepare("UPDATE labels SET name = ?, color = ?, description = ? WHERE id = ?")
+            .await?;
+        stmt.execute((new_name.as_str(), color, description, current.id))
+            .await?;
+        Self::get_by_name(ctx, mm, project_id, &new_name).await
+    }
+
+    /// Deletes a label and removes it from every message.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no such label.
+    pub async fn delete(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        name: &str,
+    ) -> Result<()> {
+        let label = Self::get_by_name(ctx, mm, project_id, name).await?;
+        let db = mm.db();
+        db.execute("DELETE FROM message_labels WHERE label_id = ?", [label.id])
+            .await?;
+        db.execute("DELETE FROM labels WHERE id = ?", [label.id])
+            .await?;
+        Ok(())
+    }
+
+    /// Applies a project label to a message. Returns `false` if the message
+    /// already had it.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the message's project has no such label, and
+    /// `InvalidInput` if `project_id` is not the message's project.
+    pub async fn apply(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        message_id: MessageId,
+        name: &str,
+        agent: Option<(AgentId, &str)>,
+    ) -> Result<bool> {
+        Self::check_message(ctx, mm, project_id, message_id.get()).await?;
+        let label = Self::get_by_name(ctx, mm, project_id, name).await?;
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO message_labels (message_id, label_id, applied_by)
+                VALUES (?, ?, ?)
+                ON CONFLICT(message_id, label_id) DO NOTHING
+                "#,
+            )
+            .await?;
+        let applied = stmt
+            .execute((message_id.get(), label.id, agent.map(|(id, _)| id.get())))
+            .await?
+            > 0;
+        if applied {
+            Self::record(
+                ctx,
+                mm,
+                "label.applied",
+                project_id,
+                message_id.get(),
+                name,
+                agent,
+            )
+            .await;
+        }
+        Ok(applied)
+    }
+
+    /// Removes a label from a message. Returns `false` if the message did
+    /// not have it.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no such label, and
+    /// `InvalidInput` if `project_id` is not the message's project.
+    pub async fn remove(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        message_id: MessageId,
+        name: &str,
+        agent: Option<(AgentId, &str)>,
+    ) -> Result<bool> {
+        Self::check_message(ctx, mm, project_id, message_id.get()).await?;
+        let label = Self::get_by_name(ctx, mm, project_id, name).await?;
+        let db = mm.db();
+        let removed = db
+            .execute(
+                "DELETE FROM message_labels WHERE message_id = ? AND label_id = ?",
+                (message_id.get(), label.id),
+            )
+            .await?
+            > 0;
+        if removed {
+            Self::record(
+                ctx,
+                mm,
+                "label.removed",
+                project_id,
+                message_id.get(),
+                name,
+                agent,
+            )
+            .await;
+        }
+        Ok(removed)
+    }
+
+    /// Names of the labels on a message, sorted.
+    pub async fn names_for_message(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<Vec<String>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT l.name FROM message_labels ml
+                JOIN labels l ON l.id = ml.label_id
+                WHERE ml.message_id = ?
+                ORDER BY l.name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+        let mut names = Vec::new();
+        while let Some(row) = rows.next().await? {
+            names.push(row.get(0)?);
+        }
+        Ok(names)
+    }
+
+    /// Lists the messages carrying a label, newest first.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no such label.
+    pub async fn list_messages(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        name: &str,
+        limit: i64,
+    ) -> Result<Vec<Message>> {
+        let label = Self::get_by_name(ctx, mm, project_id, name).await?;
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT
+                    m.id, m.project_id, m.sender_id, ag.name, m.thread_id, m.subject, m.body_md,
+                    m.importance, m.ack_required, m.created_ts, m.attachments,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
+                FROM message_labels AS ml
+                JOIN messages AS m ON m.id = ml.message_id
+                JOIN agents AS ag ON m.sender_id = ag.id
+                WHERE ml.label_id = ?
+                ORDER BY m.created_ts DESC, m.id DESC
+                LIMIT ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((label.id, limit)).await?;
+        let mut messages = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(9)?;
+            let attachments: String = row.get(10)?;
+            messages.push(Message {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                sender_id: row.get(2)?,
+                sender_name: row.get(3)?,
+                thread_id: row.get(4)?,
+                subject: row.get(5)?,
+                body_md: row.get(6)?,
+                importance: row.get(7)?,
+                ack_required: row.get(8)?,
+                created_ts: parse_timestamp(&created_ts, "messages.created_ts"),
+                attachments: serde_json::from_str::<Vec<Value>>(&attachments)?,
+                public_id: row.get(11)?,
+            });
+        }
+        Ok(messages)
+    }
+
+    async fn check_message(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        message_id: i64,
+    ) -> Result<()> {
+        let message = MessageBmc::get(ctx, mm, MessageId::new(message_id)).await?;
+        if message.project_id != project_id.get() {
+            return Err(Error::InvalidInput(format!(
+                "Message {} belongs to another project",
+                message_id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn record(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        kind: &str,
+        project_id: ProjectId,
+        message_id: i64,
+        name: &str,
+        agent: Option<(AgentId, &str)>,
+    ) {
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                kind,
+                Some(project_id.get()),
+                agent.map(|(_, agent_name)| agent_name),
+                serde_json::json!({ "message_id": message_id, "label": name }),
+            ),
+        )
+        .await;
+    }
+
+    fn from_row(row: &Row) -> Result<Label> {
+        let created_ts: String = row.get(5)?;
+        Ok(Label {
+            id: row.get(0)?,
+            project_id: ProjectId::new(row.get(1)?),
+            name: row.get(2)?,
+            color: row.get(3)?,
+            description: row.get(4)?,
+            created_ts: parse_timestamp(&created_ts, "labels.created_ts"),
+            message_count: row.get(6)?,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_names_and_colors() {
+        assert!(validate_label_name("needs-review").is_ok());
+        assert!(validate_label_name("team/db").is_ok());
+        assert!(validate_label_name("Needs Review").is_err());
+        assert!(validate_label_name("-blocked").is_err());
+        assert_eq!(validate_color("#A1B2C3").unwrap(), "#a1b2c3");
+        assert!(validate_color("red").is_err());
+        assert_eq!(default_color("blocked"), default_color("blocked"));
+    }
+}
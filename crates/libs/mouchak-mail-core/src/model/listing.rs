@@ -0,0 +1,394 @@
+//! Paging, filtering and sorting for agent and project listings.
+//!
+//! A [`ListQuery`] drives both [`AgentBmc::list_page`] and
+//! [`ProjectBmc::list_page`]. Filters match agents directly; a project
+//! matches when at least one of its agents matches all filters. A project's
+//! last activity is that of its most recently active agent.
+//!
+//! Cursors are opaque to clients: pass `next_cursor` back to get the next
+//! page. They encode a position in the sorted listing, so an entry created
+//! between requests may shift later pages by one.
+//!
+//! Mailbox and thread listings, where new entries keep arriving at the top,
+//! use [`Keyset`] cursors instead: they record the sort timestamp and key of
+//! the last entry returned, so paging stays deterministic while mail arrives.
+//!
+//! A [`ChangeStamp`] tells whether a listing changed since it was last
+//! served, so polling clients can be answered without building it again.
+//!
+//! [`AgentBmc::list_page`]: crate::model::agent::AgentBmc::list_page
+//! [`ProjectBmc::list_page`]: crate::model::project::ProjectBmc::list_page
+
+use crate::store::db_statement::Row;
+use crate::utils::{TS_FORMAT, parse_timestamp_opt};
+use crate::{Error, Result};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+/// Largest page a single request may ask for.
+pub const MAX_LIST_LIMIT: i64 = 500;
+
+/// Sort key for listings.
+///
+/// `name` sorts ascending; `created` and `last_active` sort newest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    Name,
+    Created,
+    LastActive,
+}
+
+impl std::str::FromStr for ListSort {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "name" => Ok(Self::Name),
+            "created" | "created_at" => Ok(Self::Created),
+            "last_active" | "active" => Ok(Self::LastActive),
+            other => Err(Error::InvalidInput(format!(
+                "Unknown sort key '{}' (expected name, created or last_active)",
+                other
+            ))),
+        }
+    }
+}
+
+/// A listing request.
+///
+/// # Fields
+///
+/// - `limit` - Page size, at most [`MAX_LIST_LIMIT`]; `None` returns all matches
+/// - `cursor` - `next_cursor` of the previous page
+/// - `sort` - Sort key; agents default to `name`, projects to `created`
+/// - `active_within` - Only entries active within this duration (`90s`, `15m`, `2h`, `7d`)
+/// - `program` / `model` - Only entries with an agent on this program / model
+///   (case-insensitive)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct ListQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<ListSort>,
+    pub active_within: Option<String>,
+    pub program: Option<String>,
+    pub model: Option<String>,
+}
+
+/// One page of a listing.
+///
+/// # Fields
+///
+/// - `total` - All matches, across pages
+/// - `next_cursor` - Cursor for the next page, `None` on the last page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListPage<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+/// Checks a page size, capping it at [`MAX_LIST_LIMIT`].
+pub(crate) fn page_limit(limit: i64) -> Result<i64> {
+    if limit < 1 {
+        return Err(Error::InvalidInput(format!(
+            "limit must be at least 1, got {}",
+            limit
+        )));
+    }
+    Ok(limit.min(MAX_LIST_LIMIT))
+}
+
+impl ListQuery {
+    /// `LIMIT` for the page query; without a limit it is `i64::MAX`, as
+    /// Postgres rejects the negative one SQLite reads as unbounded.
+    pub(crate) fn sql_limit(&self) -> Result<i64> {
+        self.limit.map_or(Ok(i64::MAX), page_limit)
+    }
+
+    /// `OFFSET` encoded in the cursor.
+    pub(crate) fn sql_offset(&self) -> Result<i64> {
+        match self.cursor.as_deref().map(str::trim) {
+            None | Some("") => Ok(0),
+            Some(cursor) => cursor
+                .parse::<i64>()
+                .ok()
+                .filter(|offset| *offset >= 0)
+                .ok_or_else(|| Error::InvalidInput(format!("Invalid cursor '{}'", cursor))),
+        }
+    }
+
+    /// Oldest `last_active_ts` that passes the `active_within` filter.
+    pub(crate) fn active_since(&self) -> Result<Option<String>> {
+        let Some(within) = self.active_within.as_deref() else {
+            return Ok(None);
+        };
+        let seconds = parse_duration_secs(within)?;
+        let cutoff = chrono::Duration::try_seconds(seconds)
+            .and_then(|d| chrono::Utc::now().naive_utc().checked_sub_signed(d))
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("active_within '{}' is too long", within))
+            })?;
+        Ok(Some(cutoff.format(TS_FORMAT).to_string()))
+    }
+
+    /// Builds the page from the rows at `offset` and the total match count.
+    pub(crate) fn page<T>(&self, items: Vec<T>, offset: i64, total: i64) -> ListPage<T> {
+        let end = offset + items.len() as i64;
+        let next_cursor = (!items.is_empty() && end < total).then(|| end.to_string());
+        ListPage {
+            items,
+            total,
+            next_cursor,
+        }
+    }
+}
+
+/// What a listing was built from: row count, newest id and latest
+/// timestamp of each table it reads.
+///
+/// Inserts and deletes change the count or id, updates move a timestamp.
+/// It takes one aggregate query per table, far less than the listing, so a
+/// poller's `If-None-Match` can be answered before building the listing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeStamp {
+    parts: Vec<i64>,
+    pub last_modified: Option<NaiveDateTime>,
+}
+
+impl ChangeStamp {
+    /// Reads a `COUNT(*), MAX(id), MAX(<timestamp>)` row.
+    pub(crate) fn from_row(row: &Row, field_name: &str) -> Result<Self> {
+        Ok(Self {
+            parts: vec![row.get(0)?, row.get::<Option<i64>>(1)?.unwrap_or(0)],
+            last_modified: parse_timestamp_opt(row.get(2)?, field_name),
+        })
+    }
+
+    /// Adds the stamp of another table the listing reads.
+    pub fn merge(mut self, other: Self) -> Self {
+        self.parts.extend(other.parts);
+        self.last_modified = self.last_modified.max(other.last_modified);
+        self
+    }
+
+    /// Hex digest of the stamp and `variant`, the request parameters
+    /// (page, filters, reader) that select what the listing shows.
+    pub fn digest(&self, variant: &str) -> String {
+        let mut hasher = Sha256::new();
+        for part in &self.parts {
+            hasher.update(part.to_le_bytes());
+        }
+        if let Some(ts) = self.last_modified {
+            hasher.update(ts.format(TS_FORMAT).to_string());
+        }
+        hasher.update([0]);
+        hasher.update(variant);
+        hex::encode(&hasher.finalize()[..16])
+    }
+}
+
+/// Position after the last entry of a newest-first page.
+///
+/// Entries sort by timestamp, then by `key` (a message id or thread id) to
+/// break ties; the next page holds the entries that sort after this one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Keyset {
+    pub ts: String,
+    pub key: String,
+}
+
+impl Keyset {
+    /// Encodes the position of an entry as an opaque cursor.
+    pub(crate) fn encode(ts: NaiveDateTime, key: &str) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}\n{}", ts.format(TS_FORMAT), key))
+    }
+
+    /// Decodes a cursor made by [`Self::encode`]; no cursor means the first
+    /// page.
+    pub(crate) fn decode(cursor: Option<&str>) -> Result<Option<Self>> {
+        let cursor = match cursor.map(str::trim) {
+            None | Some("") => return Ok(None),
+            Some(cursor) => cursor,
+        };
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|decoded| {
+                let (ts, key) = decoded.split_once('\n')?;
+                NaiveDateTime::parse_from_str(ts, TS_FORMAT).ok()?;
+                Some(Self {
+                    ts: ts.to_string(),
+                    key: key.to_string(),
+                })
+            })
+            .map(Some)
+            .ok_or_else(|| Error::InvalidInput(format!("Invalid cursor '{}'", cursor)))
+    }
+
+    /// The key as a row id.
+    pub(crate) fn id(&self) -> Result<i64> {
+        self.key
+            .parse()
+            .map_err(|_| Error::InvalidInput("Invalid cursor for this listing".into()))
+    }
+}
+
+/// Builds a newest-first page from up to `limit + 1` rows; the extra row
+/// only tells that another page follows.
+pub(crate) fn keyset_page<T>(
+    mut items: Vec<T>,
+    limit: i64,
+    total: i64,
+    position: impl Fn(&T) -> (NaiveDateTime, String),
+) -> ListPage<T> {
+    let limit = usize::try_from(limit).unwrap_or(0);
+    let next_cursor = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|last| {
+            let (ts, key) = position(last);
+            Keyset::encode(ts, &key)
+        })
+    } else {
+        None
+    };
+    ListPage {
+        items,
+        total,
+        next_cursor,
+    }
+}
+
+/// Parses a duration such as `90s`, `15m`, `2h` or `7d`; bare numbers are seconds.
+pub fn parse_duration_secs(s: &str) -> Result<i64> {
+    let s = s.trim();
+    let invalid = || {
+        Error::InvalidInput(format!(
+            "Invalid duration '{}' (expected e.g. 90s, 15m, 2h or 7d)",
+            s
+        ))
+    };
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => s.split_at(idx),
+        None => (s, "s"),
+    };
+    let value: i64 = number.parse().map_err(|_| invalid())?;
+    let multiplier = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(invalid()),
+    };
+    value.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_secs() {
+        assert_eq!(parse_duration_secs("90").unwrap(), 90);
+        assert_eq!(parse_duration_secs("90s").unwrap(), 90);
+        assert_eq!(parse_duration_secs("15m").unwrap(), 900);
+        assert_eq!(parse_duration_secs("2h").unwrap(), 7200);
+        assert_eq!(parse_duration_secs(" 7d ").unwrap(), 604_800);
+        assert!(parse_duration_secs("").is_err());
+        assert!(parse_duration_secs("h").is_err());
+        assert!(parse_duration_secs("3y").is_err());
+        assert!(parse_duration_secs("-5m").is_err());
+    }
+
+    #[test]
+    fn test_limit_and_cursor() {
+        let query = ListQuery::default();
+        assert_eq!(query.sql_limit().unwrap(), i64::MAX);
+        assert_eq!(query.sql_offset().unwrap(), 0);
+
+        let query = ListQuery {
+            limit: Some(10_000),
+            cursor: Some("40".into()),
+            ..Default::default()
+        };
+        assert_eq!(query.sql_limit().unwrap(), MAX_LIST_LIMIT);
+        assert_eq!(query.sql_offset().unwrap(), 40);
+
+        let query = ListQuery {
+            limit: Some(0),
+            cursor: Some("abc".into()),
+            ..Default::default()
+        };
+        assert!(query.sql_limit().is_err());
+        assert!(query.sql_offset().is_err());
+    }
+
+    #[test]
+    fn test_page_next_cursor() {
+        let query = ListQuery::default();
+        assert_eq!(
+            query.page(vec![1, 2], 0, 5).next_cursor.as_deref(),
+            Some("2")
+        );
+        assert_eq!(query.page(vec![3, 4, 5], 2, 5).next_cursor, None);
+        assert_eq!(query.page(Vec::<i32>::new(), 9, 5).next_cursor, None);
+    }
+
+    #[test]
+    fn test_keyset_cursor() {
+        let ts = NaiveDateTime::parse_from_str("2025-03-01 12:00:00", TS_FORMAT).unwrap();
+        let position = |n: &i64| (ts, n.to_string());
+
+        let page = keyset_page(vec![9, 8, 7], 2, 3, position);
+        assert_eq!(page.items, [9, 8]);
+        let keyset = Keyset::decode(page.next_cursor.as_deref())
+            .unwrap()
+            .unwrap();
+        assert_eq!(keyset.ts, "2025-03-01 12:00:00");
+        assert_eq!(keyset.id().unwrap(), 8);
+
+        assert_eq!(keyset_page(vec![7], 2, 3, position).next_cursor, None);
+        assert_eq!(Keyset::decode(None).unwrap(), None);
+        assert_eq!(Keyset::decode(Some(" ")).unwrap(), None);
+        assert!(Keyset::decode(Some("40")).is_err());
+        assert!(Keyset::decode(Some("not a cursor!")).is_err());
+    }
+
+    #[test]
+    fn test_change_stamp_digest() {
+        let ts = NaiveDateTime::parse_from_str("2025-03-01 12:00:00", TS_FORMAT).unwrap();
+        let stamp = |count: i64, max_id: i64| ChangeStamp {
+            parts: vec![count, max_id],
+            last_modified: Some(ts),
+        };
+
+        let digest = stamp(3, 9).digest("inbox");
+        assert_eq!(digest.len(), 32);
+        assert_eq!(digest, stamp(3, 9).digest("inbox"));
+        assert_ne!(digest, stamp(3, 9).digest("inbox?limit=5"));
+        // A message deleted and another added
+        assert_ne!(digest, stamp(3, 10).digest("inbox"));
+        assert_ne!(digest, ChangeStamp::default().digest("inbox"));
+
+        // Tables stay apart: a new row in the first is not hidden by a
+        // larger id in the second
+        let merged = stamp(3, 9).merge(stamp(1, 50));
+        assert_ne!(
+            merged.digest(""),
+            stamp(3, 10).merge(stamp(1, 50)).digest("")
+        );
+        let later = ts + chrono::Duration::seconds(1);
+        let merged = stamp(3, 9).merge(ChangeStamp {
+            parts: vec![1, 1],
+            last_modified: Some(later),
+        });
+        assert_eq!(merged.last_modified, Some(later));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::store::db_statement::Row;
+use crate::types::ProjectId;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -117,7 +119,7 @@ impl MacroDefBmc {
     pub async fn get_by_name(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
         name: &str,
     ) -> Result<MacroDef> {
         let db = mm.db();
@@ -131,7 +133,7 @@ impl MacroDefBmc {
             )
             .await?;
 
-        let mut rows = stmt.query((project_id, name)).await?;
+        let mut rows = stmt.query((project_id.get(), name)).await?;
 
         if let Some(row) = rows.next().await? {
             Ok(Self::from_row(row)?)
@@ -152,7 +154,11 @@ impl MacroDefBmc {
     ///
     /// # Returns
     /// Vector of all macro definitions (may be empty)
-    pub async fn list(_ctx: &Ctx, mm: &ModelManager, project_id: i64) -> Result<Vec<MacroDef>> {
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<MacroDef>> {
         let db = mm.db();
         let stmt = db
             .prepare(
@@ -165,7 +171,7 @@ impl MacroDefBmc {
             )
             .await?;
 
-        let mut rows = stmt.query([project_id]).await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
         let mut macros = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -177,7 +183,7 @@ impl MacroDefBmc {
     pub async fn delete(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
         name: &str,
     ) -> Result<bool> {
         let db = mm.db();
@@ -188,11 +194,11 @@ impl MacroDefBmc {
             "#,
             )
             .await?;
-        let affected = stmt.execute((project_id, name)).await?;
+        let affected = stmt.execute((project_id.get(), name)).await?;
         Ok(affected > 0)
     }
 
-    fn from_row(row: libsql::Row) -> Result<MacroDef> {
+    fn from_row(row: Row) -> Result<MacroDef> {
         let created_ts_str: String = row.get(5).unwrap_or_default();
         let updated_ts_str: String = row.get(6).unwrap_or_default();
         let steps_str: String = row.get(4).unwrap_or_else(|_| "[]".to_string());
@@ -218,7 +224,7 @@ impl MacroDefBmc {
     pub async fn ensure_builtin_macros(
         ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<Vec<String>> {
         let mut created = Vec::new();
 
@@ -230,7 +236,7 @@ impl MacroDefBmc {
 
             // Create the macro
             let macro_c = MacroDefForCreate {
-                project_id,
+                project_id: project_id.get(),
                 name: name.clone(),
                 description,
                 steps,
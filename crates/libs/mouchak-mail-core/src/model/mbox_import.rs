@@ -0,0 +1,360 @@
+//! Importing real email from an mbox file into a project.
+//!
+//! Seeds agent context from an existing email thread: every message in the
+//! mailbox becomes a Mouchak message between the agents its addresses map
+//! to, backdated to its `Date` header.
+//!
+//! # Address mapping
+//!
+//! An address (or display name) is resolved to an agent, in order:
+//!
+//! 1. An explicit `sender_map` entry, keyed by address or display name
+//! 2. For mail exported by Mouchak (`{agent}@{project}.mouchak-mail.invalid`),
+//!    the agent named by the local part, if it exists
+//! 3. With `create_agents`, a placeholder agent named after the local part
+//!
+//! Messages whose sender cannot be resolved are skipped; unresolved
+//! recipients are dropped from the message.
+//!
+//! # Threading
+//!
+//! A message joins the thread of the first message it references through
+//! `References` / `In-Reply-To`. Replies to messages outside the file share
+//! a thread with other replies to the same root, and mail exported by
+//! Mouchak keeps its original thread id from `X-Mouchak-Thread-Id`.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{AgentBmc, AgentForCreate};
+use crate::model::message::{MessageBmc, MessageForCreate};
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::rfc5322::{self, Mailbox, ParsedEmail};
+use crate::utils::validation::{sanitize_agent_name, validate_agent_name};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// `program` recorded for placeholder agents.
+pub const PLACEHOLDER_PROGRAM: &str = "mbox-import";
+
+/// Options for [`MboxImportBmc::import`].
+///
+/// # Fields
+///
+/// - `sender_map` - Address or display name to agent name
+/// - `create_agents` - Create placeholder agents for unmapped addresses
+#[derive(Debug, Clone, Default)]
+pub struct MboxImportOptions {
+    pub sender_map: HashMap<String, String>,
+    pub create_agents: bool,
+}
+
+/// Outcome of an import.
+///
+/// # Fields
+///
+/// - `skipped` - One reason per message that was not imported
+/// - `unmapped_addresses` - Recipient addresses dropped because no agent matched
+/// - `attachments_skipped` - Attachments are not imported, only counted
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MboxImportSummary {
+    pub imported: Vec<MessageId>,
+    pub threads: usize,
+    pub agents_created: Vec<String>,
+    pub skipped: Vec<String>,
+    pub unmapped_addresses: Vec<String>,
+    pub attachments_skipped: usize,
+}
+
+/// Backend Model Controller for mbox imports.
+pub struct MboxImportBmc;
+
+impl MboxImportBmc {
+    /// Imports every message of `mbox`, the raw bytes of an mbox file, into
+    /// the project, oldest first in file order.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if a `sender_map` target is not an existing
+    /// agent and `create_agents` is off, or is not a valid agent name.
+    pub async fn import(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        mbox: &[u8],
+        options: MboxImportOptions,
+    ) -> Result<MboxImportSummary> {
+        let mut summary = MboxImportSummary::default();
+        let mut resolver = AgentResolver::new(project_id, options);
+        // Message-ID (or thread root) -> Mouchak thread id
+        let mut threads: HashMap<String, String> = HashMap::new();
+
+        for (index, raw) in rfc5322::split_mbox(mbox).iter().enumerate() {
+            let email = ParsedEmail::parse(raw);
+            summary.attachments_skipped += email.attachment_count;
+            let label = email
+                .message_id()
+                .unwrap_or_else(|| format!("message #{}", index + 1));
+
+            let Some(from) = email.from() else {
+                summary.skipped.push(format!("{}: no From address", label));
+                continue;
+            };
+            let Some(sender_id) = resolver.resolve(ctx, mm, &from, &mut summary).await? else {
+                summary.skipped.push(format!(
+                    "{}: sender {} is not mapped to an agent",
+                    label, from.address
+                ));
+                continue;
+            };
+
+            let mut recipient_ids = Vec::new();
+            let mut cc_ids = Vec::new();
+            for (mailboxes, ids) in [(email.to(), &mut recipient_ids), (email.cc(), &mut cc_ids)] {
+                for mailbox in mailboxes {
+                    match resolver.resolve(ctx, mm, &mailbox, &mut summary).await? {
+                        Some(id) if !ids.contains(&id) => ids.push(id),
+                        Some(_) => {}
+                        None => {
+                            if !summary.unmapped_addresses.contains(&mailbox.address) {
+                                summary.unmapped_addresses.push(mailbox.address);
+                            }
+                        }
+                    }
+                }
+            }
+
+            let thread_id = Self::thread_for(&email, &mut threads);
+            let message_id = MessageBmc::create(
+                ctx,
+                mm,
+                MessageForCreate {
+                    project_id,
+                    sender_id,
+                    recipient_ids,
+                    cc_ids: (!cc_ids.is_empty()).then_some(cc_ids),
+                    bcc_ids: None,
+                    subject: email.subject(),
+                    body_md: email.body.clone(),
+                    thread_id: Some(thread_id),
+                    importance: Some(importance_of(&email).to_string()),
+                    ack_required: email
+                        .header("X-Mouchak-Ack-Required")
+                        .is_some_and(|v| v.eq_ignore_ascii_case("yes")),
+                },
+            )
+            .await?;
+
+            if let Some(date) = email.date() {
+                let db = mm.db();
+                let stmt = db
+                    .prepare("UPDATE messages SET created_ts = ? WHERE id = ?")
+                    .await?;
+                stmt.execute((
+                    date.format(crate::utils::TS_FORMAT).to_string(),
+                    message_id.get(),
+                ))
+                .await?;
+            }
+            summary.imported.push(message_id);
+        }
+
+        let mut distinct: Vec<&String> = threads.values().collect();
+        distinct.sort();
+        distinct.dedup();
+        summary.threads = distinct.len();
+        Ok(summary)
+    }
+
+    /// Picks the thread of `email` and records its ids for later replies.
+    fn thread_for(email: &ParsedEmail, threads: &mut HashMap<String, String>) -> String {
+        let references = email.references();
+        let thread_id = references
+            .iter()
+            .find_map(|id| threads.get(id).cloned())
+            .or_else(|| {
+                email
+                    .header("X-Mouchak-Thread-Id")
+                    .map(rfc5322::decode_header_text)
+            })
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        // The root lets replies to a message outside the file find each other
+        if let Some(root) = references.first() {
+            threads
+                .entry(root.clone())
+                .or_insert_with(|| thread_id.clone());
+        }
+        if let Some(id) = email.message_id() {
+            threads.insert(id, thread_id.clone());
+        }
+        thread_id
+    }
+}
+
+/// Mouchak importance of an email, from the Mouchak header if present,
+/// else `Importance` or `X-Priority`.
+fn importance_of(email: &ParsedEmail) -> &'static str {
+    let header = |name: &str| {
+        email
+            .header(name)
+            .map(|v| v.trim().to_ascii_lowercase())
+            .unwrap_or_default()
+    };
+    match header("X-Mouchak-Importance").as_str() {
+        "urgent" => return "urgent",
+        "high" => return "high",
+        "low" => return "low",
+        "normal" => return "normal",
+        _ => {}
+    }
+    match header("Importance").as_str() {
+        "high" => return "high",
+        "low" => return "low",
+        _ => {}
+    }
+    match header("X-Priority").chars().next() {
+        Some('1' | '2') => "high",
+        Some('4' | '5') => "low",
+        _ => "normal",
+    }
+}
+
+/// Resolves addresses to agents, caching hits by address.
+struct AgentResolver {
+    project_id: ProjectId,
+    sender_map: HashMap<String, String>,
+    create_agents: bool,
+    cache: HashMap<String, AgentId>,
+}
+
+impl AgentResolver {
+    fn new(project_id: ProjectId, options: MboxImportOptions) -> Self {
+        Self {
+            project_id,
+            sender_map: options
+                .sender_map
+                .into_iter()
+                .map(|(k, v)| (k.trim().to_lowercase(), v))
+                .collect(),
+            create_agents: options.create_agents,
+            cache: HashMap::new(),
+        }
+    }
+
+    async fn resolve(
+        &mut self,
+        ctx: &Ctx,
+        mm: &ModelManager,
+        mailbox: &Mailbox,
+        summary: &mut MboxImportSummary,
+    ) -> Result<Option<AgentId>> {
+        if let Some(id) = self.cache.get(&mailbox.address) {
+            return Ok(Some(*id));
+        }
+
+        let mapped = self.sender_map.get(&mailbox.address).or_else(|| {
+            mailbox
+                .name
+                .as_ref()
+                .and_then(|name| self.sender_map.get(&name.to_lowercase()))
+        });
+        let resolved = match mapped.cloned() {
+            Some(name) => {
+                if validate_agent_name(&name).is_err() {
+                    return Err(crate::Error::InvalidInput(format!(
+                        "--map target '{}' is not a valid agent name",
+                        name
+                    )));
+                }
+                match self.find(ctx, mm, &name).await? {
+                    Some(id) => Some(id),
+                    None if self.create_agents => {
+                        Some(self.create(ctx, mm, &name, mailbox, summary).await?)
+                    }
+                    None => {
+                        return Err(crate::Error::InvalidInput(format!(
+                            "--map target '{}' is not an agent in this project",
+                            name
+                        )));
+                    }
+                }
+            }
+            None => {
+                let exported_name = mailbox
+                    .address
+                    .ends_with(&format!(".{}", rfc5322::ADDRESS_DOMAIN))
+                    .then(|| mailbox.local_part().to_string());
+                let existing = match &exported_name {
+                    Some(name) => self.find(ctx, mm, name).await?,
+                    None => None,
+                };
+                match existing {
+                    Some(id) => Some(id),
+                    None if self.create_agents => {
+                        let name = exported_name.unwrap_or_else(|| placeholder_name(mailbox));
+                        match self.find(ctx, mm, &name).await? {
+                            Some(id) => Some(id),
+                            None => Some(self.create(ctx, mm, &name, mailbox, summary).await?),
+                        }
+                    }
+                    None => None,
+                }
+            }
+        };
+
+        // Misses are not cached: the same address may come with a mapped
+        // display name later
+        if let Some(id) = resolved {
+            self.cache.insert(mailbox.address.clone(), id);
+        }
+        Ok(resolved)
+    }
+
+    async fn find(&self, ctx: &Ctx, mm: &ModelManager, name: &str) -> Result<Option<AgentId>> {
+        match AgentBmc::get_by_name(ctx, mm, self.project_id, name).await {
+            Ok(agent) => Ok(Some(agent.id)),
+            Err(crate::Error::AgentNotFound { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn create(
+        &self,
+        ctx: &Ctx,
+        mm: &ModelManager,
+        name: &str,
+        mailbox: &Mailbox,
+        summary: &mut MboxImportSummary,
+    ) -> Result<AgentId> {
+        let id = AgentBmc::create(
+            ctx,
+            mm,
+            AgentForCreate {
+                project_id: self.project_id,
+                name: name.to_string(),
+                program: PLACEHOLDER_PROGRAM.to_string(),
+                model: "human".to_string(),
+                task_description: format!(
+                    "Placeholder for {} (imported from mbox)",
+                    mailbox.address
+                ),
+            },
+        )
+        .await?;
+        summary.agents_created.push(name.to_string());
+        Ok(id)
+    }
+}
+
+/// Agent name for an unmapped address: its local part, made valid.
+fn placeholder_name(mailbox: &Mailbox) -> String {
+    let name: String = sanitize_agent_name(mailbox.local_part())
+        .chars()
+        .filter(|c| c.is_ascii())
+        .collect();
+    if validate_agent_name(&name).is_ok() {
+        name
+    } else {
+        "mbox-sender".to_string()
+    }
+}
@@ -6,11 +6,15 @@
 //!
 //! # Features
 //!
-//! - **Threading**: Messages can be grouped into conversation threads
+//! - **Threading**: Messages can be grouped into conversation threads; replies
+//!   without a thread id are matched by normalized subject and participants
 //! - **Importance**: High/Normal priority levels for triage
 //! - **Recipients**: To/CC/BCC support with delivery tracking
 //! - **Full-text search**: FTS5-powered message search
 //! - **Git archival**: Automatic commit to audit log
+//! - **Size cap**: Bodies over `message_body.max_inline_bytes` are stored
+//!   separately and replaced by a preview; [`MessageBmc::get_full`] returns
+//!   the whole body
 //!
 //! # Example
 //!
@@ -18,6 +22,7 @@
 //! use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 //! use mouchak_mail_core::model::ModelManager;
 //! use mouchak_mail_core::ctx::Ctx;
+//! use mouchak_mail_core::types::{AgentId, ProjectId};
 //!
 //! # async fn example() -> mouchak_mail_core::Result<()> {
 //! let mm = ModelManager::new(std::sync::Arc::new(mouchak_mail_common::config::AppConfig::default())).await?;
@@ -25,9 +30,9 @@
 //!
 //! // Send a message
 //! let msg = MessageForCreate {
-//!     project_id: 1,
-//!     sender_id: 1,
-//!     recipient_ids: vec![2],
+//!     project_id: ProjectId::new(1),
+//!     sender_id: AgentId::new(1),
+//!     recipient_ids: vec![AgentId::new(2)],
 //!     cc_ids: None,
 //!     bcc_ids: None,
 //!     subject: "Code Review".to_string(),
@@ -44,17 +49,43 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
-use crate::store::git_store;
-use crate::types::ProjectId;
+use crate::model::agent::AgentBmc;
+use crate::model::approval::ApprovalBmc;
+use crate::model::attachment_text::{AttachmentTextBmc, AttachmentTextForCreate};
+use crate::model::custom_field::{CustomFieldBmc, CustomFieldFilter};
+use crate::model::delegation::DelegationBmc;
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::model::focus_window::FocusWindowBmc;
+use crate::model::global_thread::GlobalThreadBmc;
+use crate::model::intent_journal::{IntentJournalBmc, MESSAGE_CREATE, MessageIntent};
+use crate::model::listing::{ChangeStamp, Keyset, ListPage, keyset_page, page_limit};
+use crate::model::project::ProjectBmc;
+use crate::model::project_settings::ProjectSettingsBmc;
+use crate::model::thread_watcher::ThreadWatcherBmc;
+use crate::model::usage::UsageBmc;
+use crate::store::{Backend, git_store, postgres};
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::body_format::BodyFormat;
+use crate::utils::notifier::{self, Notification};
+use crate::utils::plugins::{MessageEvent, PluginHook};
+use crate::utils::search_highlight::{SNIPPET_RADIUS, Snippet, build_snippet, extract_terms};
+use crate::utils::{has_reply_prefix, normalize_subject, parse_timestamp};
 use chrono::NaiveDateTime;
+use mouchak_mail_common::config::NotifierEvent;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing::{info, warn};
 use uuid::Uuid;
 
+/// Number of recent project messages scanned when matching a reply by subject.
+const SUBJECT_MATCH_WINDOW: i64 = 500;
+
 /// Filter type for importance query - strong type, not primitive String
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImportanceFilter {
@@ -95,6 +126,7 @@ impl ImportanceFilter {
 /// - `created_ts` - Creation timestamp
 /// - `attachments` - Attached file metadata
 /// - `sender_name` - Denormalized sender name for UI query optimization
+/// - `public_id` - Stable ULID for referencing the message outside this database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub id: i64,
@@ -108,6 +140,8 @@ pub struct Message {
     pub created_ts: NaiveDateTime,
     pub attachments: Vec<Value>, // Use Vec<Value> for attachments
     pub sender_name: String,     // Added sender_name for inbox display
+    #[serde(default)]
+    pub public_id: String,
 }
 
 /// Unified inbox item with project slug for display.
@@ -145,11 +179,11 @@ pub struct UnifiedInboxItem {
 /// - `ack_required` - Request read receipt
 #[derive(Deserialize, Serialize)]
 pub struct MessageForCreate {
-    pub project_id: i64,
-    pub sender_id: i64,
-    pub recipient_ids: Vec<i64>,   // "to" recipients
-    pub cc_ids: Option<Vec<i64>>,  // "cc" recipients
-    pub bcc_ids: Option<Vec<i64>>, // "bcc" recipients
+    pub project_id: ProjectId,
+    pub sender_id: AgentId,
+    pub recipient_ids: Vec<AgentId>,   // "to" recipients
+    pub cc_ids: Option<Vec<AgentId>>,  // "cc" recipients
+    pub bcc_ids: Option<Vec<AgentId>>, // "bcc" recipients
     pub subject: String,
     pub body_md: String,
     pub thread_id: Option<String>,
@@ -201,8 +235,124 @@ pub struct OverdueMessage {
     pub created_ts: NaiveDateTime,
 }
 
+/// Acknowledgments still owed, across all projects.
+///
+/// # Fields
+///
+/// - `pending` - Recipient/message pairs awaiting an ack
+/// - `oldest_age_seconds` - Age of the oldest of them; 0 when none
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AckBacklog {
+    pub pending: i64,
+    pub oldest_age_seconds: i64,
+}
+
+/// A neighbouring message shown alongside a search hit.
+///
+/// # Fields
+///
+/// - `position` - `"before"` or `"after"` the hit within its thread
+/// - `excerpt` - First line of the body, truncated
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchContextMessage {
+    pub id: i64,
+    pub position: &'static str,
+    pub sender_name: String,
+    pub subject: String,
+    pub created_ts: NaiveDateTime,
+    pub excerpt: String,
+}
+
+/// A search result with a highlighted snippet and optional thread context.
+///
+/// # Fields
+///
+/// - `message` - The matching message
+/// - `field` - Where the snippet was taken from (`"body"`, `"subject"` or `"attachment"`)
+/// - `attachment` - Filename of the matching attachment when `field` is `"attachment"`
+/// - `snippet` - Highlighted excerpt, or `None` if the match could not be
+///   located (e.g. stemmed or sender-name matches)
+/// - `score` - Relevance (negated BM25, higher is better), or `None` when
+///   only an attachment matched
+/// - `context` - Surrounding messages in the same thread, oldest first
+/// - `custom_fields` - The message's custom field values, if any
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub message: Message,
+    pub field: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<String>,
+    pub snippet: Option<Snippet>,
+    pub score: Option<f64>,
+    pub context: Vec<SearchContextMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_fields: Option<Map<String, Value>>,
+}
+
+/// A body stored outside `messages.body_md` because it exceeded the inline
+/// size cap; the message itself holds a [`body_preview`].
+///
+/// # Fields
+///
+/// - `body_md` - The complete body
+/// - `size_bytes` - Size of the complete body
+/// - `sha256` - Hex SHA-256 of the complete body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OffloadedBody {
+    pub message_id: i64,
+    pub body_md: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+}
+
+/// The first `preview_bytes` of a body, cut at a line break where one is
+/// close, followed by a note that the body continues.
+pub fn body_preview(body_md: &str, preview_bytes: usize) -> String {
+    let mut end = preview_bytes.min(body_md.len());
+    while !body_md.is_char_boundary(end) {
+        end -= 1;
+    }
+    // Prefer not to cut a line in half unless that loses most of the preview
+    if let Some(newline) = body_md[..end].rfind('\n')
+        && newline >= end / 2
+    {
+        end = newline;
+    }
+    format!(
+        "{}\n\n[Body truncated: {} of {} bytes shown. \
+         Fetch the full body with get_message(full=true).]",
+        body_md[..end].trim_end(),
+        end,
+        body_md.len()
+    )
+}
+
+/// Maximum length of a context message excerpt, in bytes.
+const CONTEXT_EXCERPT_LEN: usize = 120;
+
+/// First non-empty line of a body, truncated to [`CONTEXT_EXCERPT_LEN`] bytes.
+pub(crate) fn context_excerpt(body_md: &str) -> String {
+    let line = body_md
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+    if line.len() <= CONTEXT_EXCERPT_LEN {
+        return line.to_string();
+    }
+    let mut end = CONTEXT_EXCERPT_LEN;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &line[..end])
+}
+
 impl MessageBmc {
     /// List messages that require acknowledgement but haven't received one within the threshold
+    ///
+    /// Recipients that agreed a reply deadline (see
+    /// [`crate::model::reply_deadline`]) are overdue once that deadline
+    /// passes instead.
     pub async fn list_overdue_acks(
         _ctx: &Ctx,
         mm: &ModelManager,
@@ -215,7 +365,8 @@ impl MessageBmc {
         // Query logic:
         // 1. Message must require ack
         // 2. Recipient hasn't acked (mr.ack_ts is NULL)
-        // 3. Message created before threshold
+        // 3. Recipient's agreed reply deadline passed, or, without one,
+        //    message created before threshold
         let stmt = db.prepare(
             r#"
             SELECT 
@@ -224,10 +375,15 @@ impl MessageBmc {
             JOIN message_recipients AS mr ON m.id = mr.message_id
             JOIN agents AS ag_sender ON m.sender_id = ag_sender.id
             JOIN agents AS ag_recipient ON mr.agent_id = ag_recipient.id
+            LEFT JOIN reply_deadlines AS rd
+                ON rd.message_id = m.id AND rd.agent_id = mr.agent_id AND rd.status != 'requested'
             WHERE 
                 m.ack_required = 1 
                 AND mr.ack_ts IS NULL
-                AND m.created_ts < datetime('now', ?)
+                AND CASE
+                    WHEN rd.deadline_ts IS NOT NULL THEN rd.deadline_ts < datetime('now')
+                    ELSE m.created_ts < datetime('now', ?)
+                END
             ORDER BY m.created_ts ASC
             "#
         ).await?;
@@ -261,12 +417,36 @@ impl MessageBmc {
         Ok(overdue)
     }
 
-    pub async fn get_inbox_count(_ctx: &Ctx, mm: &ModelManager, agent_id: i64) -> Result<i64> {
+    /// Counts acknowledgments still owed across all projects, with the age
+    /// of the oldest, for the ack backlog metrics.
+    pub async fn ack_backlog(_ctx: &Ctx, mm: &ModelManager) -> Result<AckBacklog> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT COUNT(*), CAST(strftime('%s', 'now') - strftime('%s', MIN(m.created_ts)) AS INTEGER)
+                FROM messages AS m
+                JOIN message_recipients AS mr ON m.id = mr.message_id
+                WHERE m.ack_required = 1 AND mr.ack_ts IS NULL
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query(()).await?;
+        match rows.next().await? {
+            Some(row) => Ok(AckBacklog {
+                pending: row.get(0)?,
+                oldest_age_seconds: row.get::<Option<i64>>(1)?.unwrap_or(0).max(0),
+            }),
+            None => Ok(AckBacklog::default()),
+        }
+    }
+
+    pub async fn get_inbox_count(_ctx: &Ctx, mm: &ModelManager, agent_id: AgentId) -> Result<i64> {
         let db = mm.db();
         let stmt = db
             .prepare("SELECT COUNT(*) FROM message_recipients WHERE agent_id = ?")
             .await?;
-        let mut rows = stmt.query([agent_id]).await?;
+        let mut rows = stmt.query([agent_id.get()]).await?;
         if let Some(row) = rows.next().await? {
             Ok(row.get(0)?)
         } else {
@@ -274,13 +454,17 @@ impl MessageBmc {
         }
     }
 
-    async fn check_inbox_quotas(mm: &ModelManager, agent_ids: &[i64], limit: i64) -> Result<()> {
+    async fn check_inbox_quotas(
+        mm: &ModelManager,
+        agent_ids: &[AgentId],
+        limit: i64,
+    ) -> Result<()> {
         let db = mm.db();
         if agent_ids.is_empty() {
             return Ok(());
         }
 
-        let mut unique_ids = agent_ids.to_vec();
+        let mut unique_ids: Vec<i64> = agent_ids.iter().map(|id| id.get()).collect();
         unique_ids.sort_unstable();
         unique_ids.dedup();
 
@@ -309,6 +493,70 @@ impl MessageBmc {
         Ok(())
     }
 
+    /// Finds an existing thread for a reply that arrived without a `thread_id`.
+    ///
+    /// Scans the most recent messages in the project for one whose normalized
+    /// subject matches (see [`normalize_subject`]) and whose participants
+    /// (sender plus all recipients) are the same set as the new message.
+    async fn find_thread_by_subject(
+        mm: &ModelManager,
+        msg_c: &MessageForCreate,
+    ) -> Result<Option<String>> {
+        let subject = normalize_subject(&msg_c.subject);
+        if subject.is_empty() {
+            return Ok(None);
+        }
+
+        let mut participants: Vec<i64> = vec![msg_c.sender_id.get()];
+        participants.extend(msg_c.recipient_ids.iter().map(|id| id.get()));
+        participants.extend(msg_c.cc_ids.iter().flatten().map(|id| id.get()));
+        participants.extend(msg_c.bcc_ids.iter().flatten().map(|id| id.get()));
+        participants.sort_unstable();
+        participants.dedup();
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT m.thread_id, m.subject, m.sender_id,
+                       (SELECT GROUP_CONCAT(mr.agent_id) FROM message_recipients mr WHERE mr.message_id = m.id)
+                FROM messages m
+                WHERE m.project_id = ? AND m.thread_id IS NOT NULL
+                ORDER BY m.created_ts DESC, m.id DESC
+                LIMIT ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((msg_c.project_id.get(), SUBJECT_MATCH_WINDOW))
+            .await?;
+
+        while let Some(row) = rows.next().await? {
+            let candidate_subject: String = row.get(1)?;
+            if normalize_subject(&candidate_subject) != subject {
+                continue;
+            }
+
+            let sender_id: i64 = row.get(2)?;
+            let recipients: Option<String> = row.get(3)?;
+            let mut candidate: Vec<i64> = recipients
+                .as_deref()
+                .unwrap_or("")
+                .split(',')
+                .filter_map(|s| s.trim().parse().ok())
+                .collect();
+            candidate.push(sender_id);
+            candidate.sort_unstable();
+            candidate.dedup();
+
+            if candidate == participants {
+                return Ok(Some(row.get(0)?));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Creates a new message and sends it to one or more recipients.
     ///
     /// This method:
@@ -332,12 +580,13 @@ impl MessageBmc {
     /// # use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
     /// # use mouchak_mail_core::model::ModelManager;
     /// # use mouchak_mail_core::ctx::Ctx;
+    /// # use mouchak_mail_core::types::{AgentId, ProjectId};
     /// # async fn example(mm: &ModelManager) {
     /// let ctx = Ctx::root_ctx();
     /// let msg = MessageForCreate {
-    ///     project_id: 1,
-    ///     sender_id: 1,
-    ///     recipient_ids: vec![2, 3],
+    ///     project_id: ProjectId::new(1),
+    ///     sender_id: AgentId::new(1),
+    ///     recipient_ids: vec![AgentId::new(2), AgentId::new(3)],
     ///     cc_ids: None,
     ///     bcc_ids: None,
     ///     subject: "Task Update".to_string(),
@@ -349,7 +598,24 @@ impl MessageBmc {
     /// let id = MessageBmc::create(&ctx, mm, msg).await.unwrap();
     /// # }
     /// ```
-    pub async fn create(_ctx: &Ctx, mm: &ModelManager, msg_c: MessageForCreate) -> Result<i64> {
+    pub async fn create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        msg_c: MessageForCreate,
+    ) -> Result<MessageId> {
+        Self::create_written_by(ctx, mm, msg_c, None).await
+    }
+
+    /// Sends `msg_c`, recording `delegate_id` as its writer in the same
+    /// transaction as the message when a delegate sends it.
+    async fn create_written_by(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        msg_c: MessageForCreate,
+        delegate_id: Option<AgentId>,
+    ) -> Result<MessageId> {
+        mm.ensure_writable()?;
+
         // Enforce Quota
         if mm.app_config.quota.enabled {
             let limit = mm.app_config.quota.inbox_limit_count as i64;
@@ -363,90 +629,179 @@ impl MessageBmc {
                 }
                 Self::check_inbox_quotas(mm, &targets, limit).await?;
             }
+            UsageBmc::check_message_quota(mm, msg_c.sender_id).await?;
         }
 
         let db = mm.db();
 
         // 1. Insert into DB
-        let thread_id = msg_c
-            .thread_id
-            .unwrap_or_else(|| Uuid::new_v4().to_string());
-        let importance = msg_c.importance.unwrap_or("normal".to_string());
+        // Replies without a thread_id are matched by normalized subject + participants
+        let thread_id = match msg_c.thread_id.clone() {
+            Some(tid) => tid,
+            None if has_reply_prefix(&msg_c.subject) => Self::find_thread_by_subject(mm, &msg_c)
+                .await?
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            None => Uuid::new_v4().to_string(),
+        };
+        let importance = msg_c.importance.clone().unwrap_or("normal".to_string());
+
+        // Operator plugins may refuse the message before anything is stored
+        if mm.plugins().handles(PluginHook::PreSend) {
+            let event = Self::plugin_event(mm, &msg_c, &thread_id, &importance).await?;
+            mm.plugins().pre_send(&event)?;
+        }
 
         // Helper to serialize attachments (empty for now)
         let attachments_json = "[]";
 
-        let stmt = db.prepare(
-            r#"
-            INSERT INTO messages (project_id, sender_id, thread_id, subject, body_md, importance, attachments, ack_required)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            RETURNING id
-            "#
-        ).await?;
-
-        let mut rows = stmt
-            .query((
-                msg_c.project_id,
-                msg_c.sender_id,
-                thread_id.as_str(),
-                msg_c.subject.as_str(),
-                msg_c.body_md.as_str(),
-                importance.as_str(),
-                attachments_json,
-                msg_c.ack_required,
-            ))
-            .await?;
-
-        let id = if let Some(row) = rows.next().await? {
-            row.get::<i64>(0)?
+        // Oversized bodies are stored separately and replaced by a preview
+        let limits = &mm.app_config.message_body;
+        let offload =
+            limits.max_inline_bytes > 0 && msg_c.body_md.len() as u64 > limits.max_inline_bytes;
+        let stored_body = if offload {
+            Cow::Owned(body_preview(&msg_c.body_md, limits.preview_bytes as usize))
         } else {
-            return Err(crate::Error::InvalidInput(
-                "Failed to create message".into(),
-            ));
+            Cow::Borrowed(msg_c.body_md.as_str())
         };
 
-        // 2. Insert Recipients with recipient_type (BATCHED)
+        // Recipients with recipient_type
         let mut recipient_tuples = Vec::new();
         for rid in &msg_c.recipient_ids {
-            recipient_tuples.push((*rid, "to"));
+            recipient_tuples.push((rid.get(), "to"));
         }
         if let Some(cc) = &msg_c.cc_ids {
             for rid in cc {
-                recipient_tuples.push((*rid, "cc"));
+                recipient_tuples.push((rid.get(), "cc"));
             }
         }
         if let Some(bcc) = &msg_c.bcc_ids {
             for rid in bcc {
-                recipient_tuples.push((*rid, "bcc"));
+                recipient_tuples.push((rid.get(), "bcc"));
             }
         }
 
-        if !recipient_tuples.is_empty() {
-            // Constuct batch insert query: ... VALUES (?, ?, ?), (?, ?, ?)
-            let mut query = String::from(
-                "INSERT INTO message_recipients (message_id, agent_id, recipient_type) VALUES ",
-            );
-            let mut params: Vec<libsql::Value> = Vec::with_capacity(recipient_tuples.len() * 3);
+        let addressed = recipient_tuples.len();
 
-            for (i, (rid, rtype)) in recipient_tuples.iter().enumerate() {
-                if i > 0 {
-                    query.push_str(", ");
+        // Thread watchers get a bcc copy unless they sent or are addressed
+        let project_id = msg_c.project_id;
+        let watcher_ids: Vec<AgentId> = ThreadWatcherBmc::watcher_ids(mm, project_id, &thread_id)
+            .await?
+            .into_iter()
+            .filter(|w| {
+                *w != msg_c.sender_id && !recipient_tuples.iter().any(|(r, _)| *r == w.get())
+            })
+            .collect();
+        recipient_tuples.extend(watcher_ids.iter().map(|w| (w.get(), "bcc")));
+
+        // Low-importance mail sent during a focus window is held until the window ends;
+        // outside business hours, quiet hours open one
+        let active_window = match FocusWindowBmc::get_active(ctx, mm, project_id).await? {
+            Some(window) => Some(window),
+            None => ProjectSettingsBmc::quiet_hours_window(ctx, mm, project_id).await?,
+        };
+        let deferring_window = active_window.filter(|window| !window.allows(&importance));
+        // High-impact mail matching an approval rule waits for a decision instead
+        let approval_rule = ApprovalBmc::matching_rule(
+            ctx,
+            mm,
+            project_id,
+            &importance,
+            addressed,
+            &msg_c.subject,
+            &msg_c.body_md,
+        )
+        .await?;
+
+        // The message and its deliveries are written in one transaction, so a
+        // crash leaves all of them or none; an error rolls it back
+        let (id, intent) = mm
+            .transaction(async |mm| {
+                let db = mm.db();
+                let stmt = db.prepare(
+                    r#"
+                    INSERT INTO messages (project_id, sender_id, thread_id, subject, body_md, importance, attachments, ack_required)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                    RETURNING id
+                    "#
+                ).await?;
+
+                let mut rows = stmt
+                    .query((
+                        msg_c.project_id.get(),
+                        msg_c.sender_id.get(),
+                        thread_id.as_str(),
+                        msg_c.subject.as_str(),
+                        stored_body.as_ref(),
+                        importance.as_str(),
+                        attachments_json,
+                        msg_c.ack_required,
+                    ))
+                    .await?;
+
+                let id = if let Some(row) = rows.next().await? {
+                    row.get::<i64>(0)?
+                } else {
+                    return Err(crate::Error::InvalidInput(
+                        "Failed to create message".into(),
+                    ));
+                };
+                if offload {
+                    Self::store_full_body(ctx, mm, msg_c.project_id.get(), id, &msg_c.body_md)
+                        .await?;
                 }
-                query.push_str("(?, ?, ?)");
-                params.push(id.into());
-                params.push((*rid).into());
-                params.push((*rtype).to_string().into());
-            }
+                GlobalThreadBmc::assign(ctx, mm, msg_c.project_id, &thread_id).await?;
+
+                if let Some(rule) = &approval_rule {
+                    ApprovalBmc::hold(ctx, mm, MessageId::new(id), rule, &recipient_tuples).await?;
+                } else if let Some(window) = &deferring_window {
+                    FocusWindowBmc::defer_delivery(ctx, mm, window.id, MessageId::new(id), &recipient_tuples)
+                        .await?;
+                } else if !recipient_tuples.is_empty() {
+                    // Constuct batch insert query: ... VALUES (?, ?, ?), (?, ?, ?)
+                    let mut query = String::from(
+                        "INSERT INTO message_recipients (message_id, agent_id, recipient_type) VALUES ",
+                    );
+                    let mut params: Vec<libsql::Value> =
+                        Vec::with_capacity(recipient_tuples.len() * 3);
+
+                    for (i, (rid, rtype)) in recipient_tuples.iter().enumerate() {
+                        if i > 0 {
+                            query.push_str(", ");
+                        }
+                        query.push_str("(?, ?, ?)");
+                        params.push(id.into());
+                        params.push((*rid).into());
+                        params.push((*rtype).to_string().into());
+                    }
 
-            let stmt = db.prepare(&query).await?;
-            stmt.execute(libsql::params::Params::Positional(params))
+                    let stmt = db.prepare(&query).await?;
+                    stmt.execute(libsql::params::Params::Positional(params))
+                        .await?;
+                }
+                if !watcher_ids.is_empty() {
+                    ThreadWatcherBmc::record_deliveries(mm, MessageId::new(id), &watcher_ids).await?;
+                }
+                if let Some(delegate_id) = delegate_id {
+                    DelegationBmc::record_message(mm, MessageId::new(id), delegate_id).await?;
+                }
+                // Removed by the archive commit below, or settled by startup recovery
+                let intent = IntentJournalBmc::begin(
+                    mm,
+                    MESSAGE_CREATE,
+                    &serde_json::to_value(MessageIntent {
+                        message_id: MessageId::new(id),
+                        to: msg_c.recipient_ids.clone(),
+                    })?,
+                )
                 .await?;
-        }
+                Ok((id, intent))
+            })
+            .await?;
 
         // 3. Git Operations - DEFERRED to background task for low latency
         // Collect data needed for background git commit
         let stmt = db.prepare("SELECT slug FROM projects WHERE id = ?").await?;
-        let mut rows = stmt.query([msg_c.project_id]).await?;
+        let mut rows = stmt.query([msg_c.project_id.get()]).await?;
         let project_slug: String = if let Some(row) = rows.next().await? {
             row.get(0)?
         } else {
@@ -457,8 +812,8 @@ impl MessageBmc {
         };
 
         // Batch fetch sender and recipient names
-        let mut needed_ids = vec![msg_c.sender_id];
-        needed_ids.extend_from_slice(&msg_c.recipient_ids);
+        let mut needed_ids = vec![msg_c.sender_id.get()];
+        needed_ids.extend(msg_c.recipient_ids.iter().map(|id| id.get()));
 
         let placeholders = needed_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
         let query = format!("SELECT id, name FROM agents WHERE id IN ({})", placeholders);
@@ -477,12 +832,12 @@ impl MessageBmc {
         }
 
         let sender_name = agent_map
-            .remove(&msg_c.sender_id)
+            .remove(&msg_c.sender_id.get())
             .ok_or_else(|| crate::Error::agent_not_found(format!("ID: {}", msg_c.sender_id)))?;
 
         let mut recipient_names = Vec::new();
         for recipient_id in &msg_c.recipient_ids {
-            if let Some(name) = agent_map.get(recipient_id) {
+            if let Some(name) = agent_map.get(&recipient_id.get()) {
                 recipient_names.push(name.clone());
             } else {
                 warn!("Recipient Name not found for ID: {}", recipient_id);
@@ -490,24 +845,32 @@ impl MessageBmc {
             }
         }
 
+        // Held and deferred mail is announced once it reaches the inboxes
+        if approval_rule.is_none() && deferring_window.is_none() {
+            Self::announce_delivery(ctx, mm, &msg_c, id, &thread_id, &importance).await;
+        }
+
         // Spawn background task for git operations (non-blocking)
         // Get cached repository before spawning to ensure it's in the cache
         let cached_repo = match mm.get_repo().await {
             Ok(repo) => repo,
             Err(e) => {
                 warn!("Failed to get cached repo for message {}: {}", id, e);
-                return Ok(id); // Return success since DB write succeeded
+                return Ok(MessageId::new(id)); // Return success since DB write succeeded
             }
         };
 
+        let mm_clone = mm.clone();
         let git_lock = mm.git_lock.clone();
         let subject = msg_c.subject.clone();
         let body_md = msg_c.body_md.clone();
         let thread_id_clone = thread_id.clone();
         let importance_clone = importance.clone();
 
+        let pending = git_store::PendingArchiveCommit::new();
         tokio::spawn(async move {
-            if let Err(e) = commit_message_to_git(
+            let _pending = pending;
+            let committed = commit_message_to_git(
                 git_lock,
                 cached_repo,
                 id,
@@ -519,28 +882,293 @@ impl MessageBmc {
                 &thread_id_clone,
                 &importance_clone,
             )
-            .await
-            {
-                warn!("Background git commit failed for message {}: {}", id, e);
+            .await;
+            // A failed commit keeps the intent, so recovery retries it later
+            match committed {
+                Ok(()) => {
+                    if let Err(e) = IntentJournalBmc::complete(&mm_clone, intent).await {
+                        warn!("Failed to complete send intent of message {}: {}", id, e);
+                    }
+                }
+                Err(e) => warn!("Background git commit failed for message {}: {}", id, e),
             }
         });
 
+        Ok(MessageId::new(id))
+    }
+
+    /// Sends `msg_c` with `delegate_id` writing on behalf of its sender.
+    ///
+    /// The message keeps `msg_c.sender_id` as sender and records the
+    /// delegate, so it reads "delegate on behalf of sender" (see
+    /// [`DelegationBmc::delegates_for_messages`]). A delegate sending as
+    /// itself is a plain [`Self::create`].
+    ///
+    /// # Errors
+    /// Returns `DelegationDenied` if the sender has not granted the delegate
+    /// permission to send on its behalf.
+    pub async fn create_on_behalf(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        delegate_id: AgentId,
+        msg_c: MessageForCreate,
+    ) -> Result<MessageId> {
+        let grantor_id = msg_c.sender_id;
+        if grantor_id == delegate_id {
+            return Self::create(ctx, mm, msg_c).await;
+        }
+        let project_id = msg_c.project_id;
+        let grantor = AgentBmc::get(ctx, mm, grantor_id).await?;
+        let delegate = AgentBmc::get(ctx, mm, delegate_id).await?;
+        if !DelegationBmc::is_granted(ctx, mm, grantor_id, delegate_id).await? {
+            return Err(crate::Error::DelegationDenied(format!(
+                "'{}' may not send on behalf of '{}'",
+                delegate.name, grantor.name
+            )));
+        }
+
+        let id = Self::create_written_by(ctx, mm, msg_c, Some(delegate_id)).await?;
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                "message.sent_on_behalf",
+                Some(project_id.get()),
+                Some(&delegate.name),
+                serde_json::json!({
+                    "message_id": id,
+                    "on_behalf_of": grantor.name,
+                }),
+            ),
+        )
+        .await;
         Ok(id)
     }
 
+    /// Describes a message for operator plugins, with agents by name.
+    async fn plugin_event(
+        mm: &ModelManager,
+        msg_c: &MessageForCreate,
+        thread_id: &str,
+        importance: &str,
+    ) -> Result<MessageEvent> {
+        let db = mm.db();
+        let stmt = db.prepare("SELECT slug FROM projects WHERE id = ?").await?;
+        let mut rows = stmt.query([msg_c.project_id.get()]).await?;
+        let project: String = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => {
+                return Err(crate::Error::project_not_found(format!(
+                    "ID: {}",
+                    msg_c.project_id
+                )));
+            }
+        };
+
+        let cc = msg_c.cc_ids.as_deref().unwrap_or_default();
+        let bcc = msg_c.bcc_ids.as_deref().unwrap_or_default();
+        let mut ids = vec![msg_c.sender_id.get()];
+        ids.extend(msg_c.recipient_ids.iter().map(|id| id.get()));
+        ids.extend(cc.iter().map(|id| id.get()));
+        ids.extend(bcc.iter().map(|id| id.get()));
+        let placeholders = vec!["?"; ids.len()].join(",");
+        let stmt = db
+            .prepare(&format!(
+                "SELECT id, name FROM agents WHERE id IN ({})",
+                placeholders
+            ))
+            .await?;
+        let params: Vec<libsql::Value> = ids.iter().map(|&id| id.into()).collect();
+        let mut rows = stmt
+            .query(libsql::params::Params::Positional(params))
+            .await?;
+        let mut names = HashMap::new();
+        while let Some(row) = rows.next().await? {
+            names.insert(row.get::<i64>(0)?, row.get::<String>(1)?);
+        }
+        let name = |id: &AgentId| {
+            names
+                .get(&id.get())
+                .cloned()
+                .unwrap_or_else(|| format!("Unknown-{}", id))
+        };
+
+        Ok(MessageEvent {
+            message_id: None,
+            project,
+            sender: name(&msg_c.sender_id),
+            to: msg_c.recipient_ids.iter().map(name).collect(),
+            cc: cc.iter().map(name).collect(),
+            bcc: bcc.iter().map(name).collect(),
+            subject: msg_c.subject.clone(),
+            body_md: msg_c.body_md.clone(),
+            importance: importance.to_string(),
+            thread_id: thread_id.to_string(),
+        })
+    }
+
+    /// Announces a message that reached its recipients' inboxes: logs
+    /// `message.sent`, notifies about urgent mail and runs `post_receive`
+    /// plugins. Like the Git archive, none of this fails the send.
+    async fn announce_delivery(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        msg_c: &MessageForCreate,
+        id: i64,
+        thread_id: &str,
+        importance: &str,
+    ) {
+        let event = match Self::plugin_event(mm, msg_c, thread_id, importance).await {
+            Ok(event) => MessageEvent {
+                message_id: Some(id),
+                ..event
+            },
+            Err(e) => {
+                warn!("Failed to announce message {}: {}", id, e);
+                return;
+            }
+        };
+
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                "message.sent",
+                Some(msg_c.project_id.get()),
+                Some(&event.sender),
+                serde_json::json!({
+                    "message_id": id,
+                    "thread_id": thread_id,
+                    "subject": msg_c.subject,
+                    "importance": importance,
+                    "recipients": event.to,
+                }),
+            ),
+        )
+        .await;
+
+        if importance == "urgent" {
+            notifier::notify(
+                &mm.app_config.notifier,
+                &Notification {
+                    event: NotifierEvent::UrgentMessage,
+                    title: format!("Urgent: {}", msg_c.subject),
+                    body: format!(
+                        "{} to {}: {}",
+                        event.sender,
+                        event.to.join(", "),
+                        msg_c.body_md
+                    ),
+                    project: event.project.clone(),
+                    sender: event.sender.clone(),
+                    subject: msg_c.subject.clone(),
+                    message_id: Some(id),
+                    importance: importance.to_string(),
+                },
+            );
+        }
+
+        if mm.plugins().handles(PluginHook::PostReceive) {
+            Self::run_post_receive(ctx, mm, msg_c.project_id, &event).await;
+        }
+    }
+
+    /// Announces a held or deferred message once its deliveries are
+    /// released (see [`Self::announce_delivery`]).
+    pub(crate) async fn announce_released(ctx: &Ctx, mm: &ModelManager, message_id: MessageId) {
+        let msg_c = match Self::released_as_create(ctx, mm, message_id).await {
+            Ok(msg_c) => msg_c,
+            Err(e) => {
+                warn!("Failed to announce message {}: {}", message_id, e);
+                return;
+            }
+        };
+        let thread_id = msg_c
+            .thread_id
+            .clone()
+            .unwrap_or_else(|| message_id.to_string());
+        let importance = msg_c.importance.clone().unwrap_or_default();
+        Self::announce_delivery(ctx, mm, &msg_c, message_id.get(), &thread_id, &importance).await;
+    }
+
+    /// Rebuilds a delivered message as it was sent, with its recipients by type.
+    async fn released_as_create(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<MessageForCreate> {
+        let message = Self::get_full(ctx, mm, message_id).await?;
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "SELECT agent_id, recipient_type FROM message_recipients WHERE message_id = ? ORDER BY agent_id",
+            )
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+        let (mut to, mut cc, mut bcc) = (Vec::new(), Vec::new(), Vec::new());
+        while let Some(row) = rows.next().await? {
+            let agent_id = AgentId::new(row.get::<i64>(0)?);
+            match row.get::<String>(1)?.as_str() {
+                "to" => to.push(agent_id),
+                "cc" => cc.push(agent_id),
+                _ => bcc.push(agent_id),
+            }
+        }
+        Ok(MessageForCreate {
+            project_id: ProjectId::new(message.project_id),
+            sender_id: AgentId::new(message.sender_id),
+            recipient_ids: to,
+            cc_ids: Some(cc),
+            bcc_ids: Some(bcc),
+            subject: message.subject,
+            body_md: message.body_md,
+            thread_id: message.thread_id,
+            importance: Some(message.importance),
+            ack_required: message.ack_required,
+        })
+    }
+
+    /// Runs `post_receive` plugins and stores the custom fields they add.
+    /// Fields the project has not declared are logged and dropped.
+    async fn run_post_receive(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        event: &MessageEvent,
+    ) {
+        let added = mm.plugins().post_receive(event);
+        if added.is_empty() {
+            return;
+        }
+        let Some(id) = event.message_id else {
+            return;
+        };
+        let stored = match CustomFieldBmc::validate(ctx, mm, project_id, &added, false).await {
+            Ok(fields) => Self::merge_custom_fields(ctx, mm, MessageId::new(id), &fields).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = stored {
+            warn!(
+                "Plugin custom fields for message {} were not stored: {}",
+                id, e
+            );
+        }
+    }
+
     pub async fn list_inbox_for_agent(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
-        agent_id: i64,
+        project_id: ProjectId,
+        agent_id: AgentId,
         limit: i64,
     ) -> Result<Vec<Message>> {
-        let db = mm.db();
+        let db = mm.db_read();
         let stmt = db.prepare(
             r#"
             SELECT
                 m.id, m.project_id, m.sender_id, ag.name as sender_name, m.thread_id, m.subject, m.body_md,
-                m.importance, m.ack_required, m.created_ts, m.attachments
+                m.importance, m.ack_required, m.created_ts, m.attachments,
+                COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
             FROM messages AS m
             JOIN message_recipients AS mr ON m.id = mr.message_id
             JOIN agents AS ag ON m.sender_id = ag.id
@@ -550,7 +1178,9 @@ impl MessageBmc {
             "#
         ).await?;
 
-        let mut rows = stmt.query((agent_id, project_id, limit)).await?;
+        let mut rows = stmt
+            .query((agent_id.get(), project_id.get(), limit))
+            .await?;
         let mut messages = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -569,6 +1199,7 @@ impl MessageBmc {
 
             let attachments_str: String = row.get(10)?;
             let attachments: Vec<Value> = serde_json::from_str(&attachments_str)?;
+            let public_id: String = row.get(11)?;
 
             messages.push(Message {
                 id,
@@ -582,6 +1213,7 @@ impl MessageBmc {
                 ack_required,
                 created_ts,
                 attachments,
+                public_id,
             });
         }
         Ok(messages)
@@ -591,16 +1223,17 @@ impl MessageBmc {
     pub async fn list_outbox_for_agent(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
-        agent_id: i64,
+        project_id: ProjectId,
+        agent_id: AgentId,
         limit: i64,
     ) -> Result<Vec<Message>> {
-        let db = mm.db();
+        let db = mm.db_read();
         let stmt = db.prepare(
             r#"
             SELECT
                 m.id, m.project_id, m.sender_id, ag.name as sender_name, m.thread_id, m.subject, m.body_md,
-                m.importance, m.ack_required, m.created_ts, m.attachments
+                m.importance, m.ack_required, m.created_ts, m.attachments,
+                COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
             FROM messages AS m
             JOIN agents AS ag ON m.sender_id = ag.id
             WHERE m.sender_id = ? AND m.project_id = ?
@@ -609,7 +1242,9 @@ impl MessageBmc {
             "#
         ).await?;
 
-        let mut rows = stmt.query((agent_id, project_id, limit)).await?;
+        let mut rows = stmt
+            .query((agent_id.get(), project_id.get(), limit))
+            .await?;
         let mut messages = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -628,6 +1263,7 @@ impl MessageBmc {
 
             let attachments_str: String = row.get(10)?;
             let attachments: Vec<Value> = serde_json::from_str(&attachments_str)?;
+            let public_id: String = row.get(11)?;
 
             messages.push(Message {
                 id,
@@ -641,25 +1277,174 @@ impl MessageBmc {
                 ack_required,
                 created_ts,
                 attachments,
+                public_id,
             });
         }
         Ok(messages)
     }
 
-    pub async fn get(_ctx: &Ctx, mm: &ModelManager, message_id: i64) -> Result<Message> {
+    /// Lists a page of an agent's inbox, newest first.
+    ///
+    /// Pass the page's `next_cursor` back as `cursor` to get the next page.
+    /// Messages arriving between requests do not shift later pages.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a limit below 1 or a bad cursor.
+    pub async fn list_inbox_page(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        limit: i64,
+        cursor: Option<&str>,
+    ) -> Result<ListPage<Message>> {
+        let filter = r#"
+            FROM messages AS m
+            JOIN message_recipients AS mr ON m.id = mr.message_id
+            JOIN agents AS ag ON m.sender_id = ag.id
+            WHERE mr.agent_id = ?1 AND m.project_id = ?2
+            "#;
+        Self::list_mailbox_page(mm, filter, project_id, agent_id, limit, cursor).await
+    }
+
+    /// Change stamp of an agent's inbox: a new or deleted message changes it.
+    pub async fn inbox_stamp(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+    ) -> Result<ChangeStamp> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT COUNT(*), MAX(m.id), MAX(m.created_ts)
+                FROM messages AS m
+                JOIN message_recipients AS mr ON m.id = mr.message_id
+                WHERE mr.agent_id = ? AND m.project_id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((agent_id.get(), project_id.get())).await?;
+        match rows.next().await? {
+            Some(row) => ChangeStamp::from_row(&row, "messages.created_ts"),
+            None => Ok(ChangeStamp::default()),
+        }
+    }
+
+    /// Lists a page of the messages an agent sent, newest first.
+    ///
+    /// Pages like [`Self::list_inbox_page`].
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a limit below 1 or a bad cursor.
+    pub async fn list_outbox_page(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        limit: i64,
+        cursor: Option<&str>,
+    ) -> Result<ListPage<Message>> {
+        let filter = r#"
+            FROM messages AS m
+            JOIN agents AS ag ON m.sender_id = ag.id
+            WHERE m.sender_id = ?1 AND m.project_id = ?2
+            "#;
+        Self::list_mailbox_page(mm, filter, project_id, agent_id, limit, cursor).await
+    }
+
+    /// Pages through the messages matching `filter`, which binds the agent
+    /// as `?1` and the project as `?2`.
+    async fn list_mailbox_page(
+        mm: &ModelManager,
+        filter: &str,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        limit: i64,
+        cursor: Option<&str>,
+    ) -> Result<ListPage<Message>> {
+        let limit = page_limit(limit)?;
+        let (after_ts, after_id) = match Keyset::decode(cursor)? {
+            Some(keyset) => {
+                let id = keyset.id()?;
+                (Some(keyset.ts), Some(id))
+            }
+            None => (None, None),
+        };
+
+        let db = mm.db_read();
+        let stmt = db
+            .prepare(&format!(
+                r#"
+                SELECT
+                    m.id, m.project_id, m.sender_id, ag.name as sender_name, m.thread_id, m.subject, m.body_md,
+                    m.importance, m.ack_required, m.created_ts, m.attachments,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
+                {filter}
+                  AND (?3 IS NULL OR m.created_ts < ?3 OR (m.created_ts = ?3 AND m.id < ?4))
+                ORDER BY m.created_ts DESC, m.id DESC
+                LIMIT ?5
+                "#
+            ))
+            .await?;
+        let mut rows = stmt
+            .query((
+                agent_id.get(),
+                project_id.get(),
+                after_ts,
+                after_id,
+                limit + 1,
+            ))
+            .await?;
+
+        let mut messages = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(9)?;
+            let attachments: String = row.get(10)?;
+            messages.push(Message {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                sender_id: row.get(2)?,
+                sender_name: row.get(3)?,
+                thread_id: row.get(4)?,
+                subject: row.get(5)?,
+                body_md: row.get(6)?,
+                importance: row.get(7)?,
+                ack_required: row.get(8)?,
+                created_ts: parse_timestamp(&created_ts, "messages.created_ts"),
+                attachments: serde_json::from_str(&attachments)?,
+                public_id: row.get(11)?,
+            });
+        }
+
+        let stmt = db.prepare(&format!("SELECT COUNT(*) {filter}")).await?;
+        let mut rows = stmt.query((agent_id.get(), project_id.get())).await?;
+        let total: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+
+        Ok(keyset_page(messages, limit, total, |m| {
+            (m.created_ts, m.id.to_string())
+        }))
+    }
+
+    pub async fn get(_ctx: &Ctx, mm: &ModelManager, message_id: MessageId) -> Result<Message> {
         let db = mm.db();
         let stmt = db.prepare(
             r#"
             SELECT
                 m.id, m.project_id, m.sender_id, ag.name as sender_name, m.thread_id, m.subject, m.body_md,
-                m.importance, m.ack_required, m.created_ts, m.attachments
+                m.importance, m.ack_required, m.created_ts, m.attachments,
+                COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
             FROM messages AS m
             JOIN agents AS ag ON m.sender_id = ag.id
             WHERE m.id = ?
             "#
         ).await?;
 
-        let mut rows = stmt.query([message_id]).await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
 
         if let Some(row) = rows.next().await? {
             let id: i64 = row.get(0)?;
@@ -677,6 +1462,7 @@ impl MessageBmc {
 
             let attachments_str: String = row.get(10)?;
             let attachments: Vec<Value> = serde_json::from_str(&attachments_str)?;
+            let public_id: String = row.get(11)?;
 
             Ok(Message {
                 id,
@@ -690,9 +1476,10 @@ impl MessageBmc {
                 ack_required,
                 created_ts,
                 attachments,
+                public_id,
             })
         } else {
-            Err(crate::Error::MessageNotFound(message_id))
+            Err(crate::Error::MessageNotFound(message_id.get()))
         }
     }
 
@@ -700,7 +1487,7 @@ impl MessageBmc {
     pub async fn get_recipients(
         _ctx: &Ctx,
         mm: &ModelManager,
-        message_id: i64,
+        message_id: MessageId,
     ) -> Result<Vec<String>> {
         let db = mm.db();
         let stmt = db
@@ -714,38 +1501,415 @@ impl MessageBmc {
             "#,
             )
             .await?;
-
-        let mut rows = stmt.query([message_id]).await?;
-        let mut recipients = Vec::new();
-
+
+        let mut rows = stmt.query([message_id.get()]).await?;
+        let mut recipients = Vec::new();
+
+        while let Some(row) = rows.next().await? {
+            let name: String = row.get(0)?;
+            recipients.push(name);
+        }
+
+        Ok(recipients)
+    }
+
+    /// Records the format a message body is stored in.
+    ///
+    /// Markdown is the default and is not stored explicitly.
+    pub async fn set_body_format(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        format: BodyFormat,
+    ) -> Result<()> {
+        let db = mm.db();
+        if format == BodyFormat::Markdown {
+            db.execute(
+                "DELETE FROM message_body_formats WHERE message_id = ?",
+                [message_id.get()],
+            )
+            .await?;
+        } else {
+            db.execute(
+                r#"
+                INSERT INTO message_body_formats (message_id, body_format) VALUES (?, ?)
+                ON CONFLICT(message_id) DO UPDATE SET body_format = excluded.body_format
+                "#,
+                (message_id.get(), format.as_str()),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the format a message body is stored in.
+    pub async fn get_body_format(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<BodyFormat> {
+        let formats = Self::get_body_formats(ctx, mm, &[message_id.get()]).await?;
+        Ok(formats.get(&message_id.get()).copied().unwrap_or_default())
+    }
+
+    /// Stores the complete body of a message whose inline body is a preview,
+    /// and indexes it like an attachment so search still finds its text.
+    async fn store_full_body(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: i64,
+        message_id: i64,
+        body_md: &str,
+    ) -> Result<()> {
+        let sha256 = hex::encode(Sha256::digest(body_md.as_bytes()));
+        mm.db()
+            .execute(
+                "INSERT INTO message_bodies (message_id, body_md, size_bytes, sha256) VALUES (?, ?, ?, ?)",
+                (message_id, body_md, body_md.len() as i64, sha256.as_str()),
+            )
+            .await?;
+        AttachmentTextBmc::index(
+            ctx,
+            mm,
+            AttachmentTextForCreate {
+                project_id,
+                message_id,
+                attachment_ref: format!("body_{}", message_id),
+                filename: "body.md".to_string(),
+                content: body_md.as_bytes().to_vec(),
+            },
+        )
+        .await?;
+        info!(
+            message_id,
+            size_bytes = body_md.len(),
+            "Message body over the inline cap, stored separately"
+        );
+        Ok(())
+    }
+
+    /// Returns the complete body of a message whose inline body is a
+    /// preview, or `None` if the inline body is complete.
+    pub async fn get_offloaded_body(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<Option<OffloadedBody>> {
+        let stmt = mm
+            .db()
+            .prepare("SELECT body_md, size_bytes, sha256 FROM message_bodies WHERE message_id = ?")
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(OffloadedBody {
+                message_id: message_id.get(),
+                body_md: row.get(0)?,
+                size_bytes: row.get(1)?,
+                sha256: row.get(2)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the complete body sizes of the messages among `message_ids`
+    /// whose inline body is a preview.
+    pub async fn get_offloaded_sizes(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_ids: &[i64],
+    ) -> Result<HashMap<i64, i64>> {
+        let mut sizes = HashMap::new();
+        if message_ids.is_empty() {
+            return Ok(sizes);
+        }
+
+        let placeholders = vec!["?"; message_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT message_id, size_bytes FROM message_bodies WHERE message_id IN ({})",
+            placeholders
+        );
+        let params: Vec<libsql::Value> = message_ids
+            .iter()
+            .map(|id| libsql::Value::Integer(*id))
+            .collect();
+        let stmt = mm.db().prepare(&sql).await?;
+        let mut rows = stmt
+            .query(libsql::params::Params::Positional(params))
+            .await?;
+        while let Some(row) = rows.next().await? {
+            sizes.insert(row.get::<i64>(0)?, row.get::<i64>(1)?);
+        }
+        Ok(sizes)
+    }
+
+    /// Like [`MessageBmc::get`], with the complete body even when it was
+    /// offloaded.
+    pub async fn get_full(ctx: &Ctx, mm: &ModelManager, id: MessageId) -> Result<Message> {
+        let mut message = Self::get(ctx, mm, id).await?;
+        if let Some(full) = Self::get_offloaded_body(ctx, mm, id).await? {
+            message.body_md = full.body_md;
+        }
+        Ok(message)
+    }
+
+    /// Commits a sent message to the Git archive, naming `to_ids` as its
+    /// direct recipients.
+    ///
+    /// Sending commits in the background; this finishes sends whose commit
+    /// was lost to a crash.
+    ///
+    /// # Errors
+    /// Returns `MessageNotFound` if the message no longer exists.
+    pub(in crate::model) async fn archive(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        to_ids: &[AgentId],
+    ) -> Result<()> {
+        let message = Self::get_full(ctx, mm, message_id).await?;
+        let project = ProjectBmc::get(ctx, mm, ProjectId::new(message.project_id)).await?;
+        let mut recipient_names = Vec::with_capacity(to_ids.len());
+        for recipient_id in to_ids {
+            match AgentBmc::get(ctx, mm, *recipient_id).await {
+                Ok(agent) => recipient_names.push(agent.name),
+                Err(_) => recipient_names.push(format!("Unknown-{}", recipient_id)),
+            }
+        }
+        let thread_id = message
+            .thread_id
+            .clone()
+            .unwrap_or_else(|| message_id.to_string());
+
+        let cached_repo = mm.get_repo().await?;
+        commit_message_to_git(
+            mm.git_lock.clone(),
+            cached_repo,
+            message_id.get(),
+            &project.slug,
+            &message.sender_name,
+            &recipient_names,
+            &message.subject,
+            &message.body_md,
+            &thread_id,
+            &message.importance,
+        )
+        .await
+    }
+
+    /// Returns the body formats of non-Markdown messages among `message_ids`.
+    ///
+    /// Messages missing from the map are Markdown.
+    pub async fn get_body_formats(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_ids: &[i64],
+    ) -> Result<HashMap<i64, BodyFormat>> {
+        let mut formats = HashMap::new();
+        if message_ids.is_empty() {
+            return Ok(formats);
+        }
+
+        let db = mm.db();
+        let placeholders = vec!["?"; message_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT message_id, body_format FROM message_body_formats WHERE message_id IN ({})",
+            placeholders
+        );
+        let params: Vec<libsql::Value> = message_ids
+            .iter()
+            .map(|id| libsql::Value::Integer(*id))
+            .collect();
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = stmt.query(params).await?;
+        while let Some(row) = rows.next().await? {
+            let message_id: i64 = row.get(0)?;
+            let format: String = row.get(1)?;
+            formats.insert(message_id, format.parse().unwrap_or_default());
+        }
+        Ok(formats)
+    }
+
+    /// Checks custom field values for a new message against the project's
+    /// declarations, including required fields.
+    ///
+    /// Returns the values converted to their declared types, ready for
+    /// [`Self::merge_custom_fields`].
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for undeclared fields, mistyped values or
+    /// missing required fields.
+    pub async fn validate_custom_fields(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        values: Option<&Map<String, Value>>,
+    ) -> Result<Map<String, Value>> {
+        let empty = Map::new();
+        CustomFieldBmc::validate(ctx, mm, project_id, values.unwrap_or(&empty), true).await
+    }
+
+    /// Stores a message's custom field values. An empty map removes them.
+    pub async fn set_custom_fields(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        fields: &Map<String, Value>,
+    ) -> Result<()> {
+        let db = mm.db();
+        if fields.is_empty() {
+            db.execute(
+                "DELETE FROM message_custom_fields WHERE message_id = ?",
+                [message_id.get()],
+            )
+            .await?;
+        } else {
+            db.execute(
+                r#"
+                INSERT INTO message_custom_fields (message_id, fields) VALUES (?, ?)
+                ON CONFLICT(message_id) DO UPDATE SET fields = excluded.fields
+                "#,
+                (message_id.get(), Value::Object(fields.clone()).to_string()),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Adds custom field values to a message, keeping other fields it has.
+    /// Values given here replace stored values of the same field.
+    pub async fn merge_custom_fields(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        fields: &Map<String, Value>,
+    ) -> Result<()> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+        mm.db()
+            .execute(
+                r#"
+                INSERT INTO message_custom_fields (message_id, fields) VALUES (?, ?)
+                ON CONFLICT(message_id) DO UPDATE SET fields = json_patch(message_custom_fields.fields, excluded.fields)
+                "#,
+                (message_id.get(), Value::Object(fields.clone()).to_string()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the custom field values of the messages among `message_ids`
+    /// that have any.
+    pub async fn get_custom_fields(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_ids: &[i64],
+    ) -> Result<HashMap<i64, Map<String, Value>>> {
+        let mut fields = HashMap::new();
+        if message_ids.is_empty() {
+            return Ok(fields);
+        }
+
+        let db = mm.db();
+        let placeholders = vec!["?"; message_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT message_id, fields FROM message_custom_fields WHERE message_id IN ({})",
+            placeholders
+        );
+        let params: Vec<libsql::Value> = message_ids
+            .iter()
+            .map(|id| libsql::Value::Integer(*id))
+            .collect();
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = stmt.query(params).await?;
+        while let Some(row) = rows.next().await? {
+            let message_id: i64 = row.get(0)?;
+            let json: String = row.get(1)?;
+            if let Value::Object(map) = serde_json::from_str(&json)? {
+                fields.insert(message_id, map);
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Copies a message's custom field values to a reply.
+    pub async fn inherit_custom_fields(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        parent_id: MessageId,
+        message_id: MessageId,
+    ) -> Result<()> {
+        let mut fields = Self::get_custom_fields(ctx, mm, &[parent_id.get()]).await?;
+        match fields.remove(&parent_id.get()) {
+            Some(parent) => Self::merge_custom_fields(ctx, mm, message_id, &parent).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Ids of every sender and recipient in a thread, ascending.
+    pub async fn thread_participants(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Vec<AgentId>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT sender_id FROM messages WHERE project_id = ?1 AND thread_id = ?2
+                UNION
+                SELECT mr.agent_id FROM message_recipients AS mr
+                JOIN messages AS m ON m.id = mr.message_id
+                WHERE m.project_id = ?1 AND m.thread_id = ?2
+                ORDER BY 1
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        let mut ids = Vec::new();
         while let Some(row) = rows.next().await? {
-            let name: String = row.get(0)?;
-            recipients.push(name);
+            ids.push(AgentId::new(row.get(0)?));
         }
-
-        Ok(recipients)
+        Ok(ids)
     }
 
     pub async fn list_by_thread(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Vec<Message>> {
+        Self::list_by_thread_after(ctx, mm, project_id, thread_id, None).await
+    }
+
+    /// Lists the messages of a thread with an id above `after`, oldest first.
+    ///
+    /// With `after` of `None` this is [`Self::list_by_thread`].
+    pub async fn list_by_thread_after(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
         thread_id: &str,
+        after: Option<MessageId>,
     ) -> Result<Vec<Message>> {
         let db = mm.db();
         let stmt = db.prepare(
             r#"
             SELECT
                 m.id, m.project_id, m.sender_id, ag.name as sender_name, m.thread_id, m.subject, m.body_md,
-                m.importance, m.ack_required, m.created_ts, m.attachments
+                m.importance, m.ack_required, m.created_ts, m.attachments,
+                COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
             FROM messages AS m
             JOIN agents AS ag ON m.sender_id = ag.id
-            WHERE m.project_id = ? AND m.thread_id = ?
+            WHERE m.project_id = ?1 AND m.thread_id = ?2 AND (?3 IS NULL OR m.id > ?3)
             ORDER BY m.created_ts ASC, m.id ASC
             "#
         ).await?;
 
-        let mut rows = stmt.query((project_id, thread_id)).await?;
+        let mut rows = stmt
+            .query((project_id.get(), thread_id, after.map(|id| id.get())))
+            .await?;
         let mut messages = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -764,6 +1928,7 @@ impl MessageBmc {
 
             let attachments_str: String = row.get(10)?;
             let attachments: Vec<Value> = serde_json::from_str(&attachments_str)?;
+            let public_id: String = row.get(11)?;
 
             messages.push(Message {
                 id,
@@ -777,83 +1942,172 @@ impl MessageBmc {
                 ack_required,
                 created_ts,
                 attachments,
+                public_id,
             });
         }
         Ok(messages)
     }
 
-    /// Full-text search messages using FTS5
-    pub async fn search(
+    /// Change stamp of a thread: its messages and their linked tickets,
+    /// whose status is refreshed from the tracker.
+    pub async fn thread_stamp(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<ChangeStamp> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT COUNT(*), MAX(id), MAX(created_ts)
+                FROM messages
+                WHERE project_id = ?1 AND thread_id = ?2
+                UNION ALL
+                SELECT COUNT(*), MAX(t.id), MAX(MAX(mt.created_ts, COALESCE(t.fetched_ts, t.created_ts)))
+                FROM message_tickets AS mt
+                JOIN tickets AS t ON t.id = mt.ticket_id
+                JOIN messages AS m ON m.id = mt.message_id
+                WHERE m.project_id = ?1 AND m.thread_id = ?2
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        let mut stamp = ChangeStamp::default();
+        while let Some(row) = rows.next().await? {
+            stamp = stamp.merge(ChangeStamp::from_row(&row, "messages.created_ts")?);
+        }
+        Ok(stamp)
+    }
+
+    /// Returns a thread's message count and newest message id.
+    ///
+    /// The pair changes whenever a message is added to or removed from the
+    /// thread, so watchers can compare it instead of refetching messages.
+    pub async fn thread_version(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<(i64, i64)> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "SELECT COUNT(*), COALESCE(MAX(id), 0) FROM messages WHERE project_id = ? AND thread_id = ?",
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        match rows.next().await? {
+            Some(row) => Ok((row.get(0)?, row.get(1)?)),
+            None => Ok((0, 0)),
+        }
+    }
+
+    /// Full-text search over message subjects and bodies using FTS5,
+    /// best matches first.
+    pub async fn search(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
         query: &str,
         limit: i64,
     ) -> Result<Vec<Message>> {
-        let db = mm.db();
+        Self::search_with_options(ctx, mm, project_id, query, limit, false, &[]).await
+    }
 
-        // FTS5 Unsearchable patterns (return empty to avoid errors or heavy meaningless queries)
-        // Python equivalent: _FTS5_UNSEARCHABLE_PATTERNS
-        let trimmed = query.trim();
-        if matches!(
-            trimmed,
-            "" | "*" | "**" | "***" | "." | ".." | "..." | "?" | "??" | "???"
-        ) {
-            info!("Search query '{}' is in blocklist, returning empty", query);
-            return Ok(Vec::new());
-        }
+    /// Full-text search, optionally also matching indexed attachment text.
+    ///
+    /// The query supports FTS5 syntax: prefixes (`deploy*`), phrases
+    /// (`"schema migration"`), `AND`/`OR`/`NOT` and column filters
+    /// (`subject:deploy`). Results are ranked by BM25 with subject matches
+    /// weighted double, newest first among equals.
+    ///
+    /// With `include_attachments`, a message matches if its subject, body or
+    /// the content/filename of any of its indexed attachments matches
+    /// (see [`crate::model::attachment_text`]); attachment-only matches rank
+    /// last. Matches are further limited to messages whose custom fields
+    /// equal every filter in `fields`
+    /// (see [`crate::model::custom_field::CustomFieldBmc::filters`]).
+    pub async fn search_with_options(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        query: &str,
+        limit: i64,
+        include_attachments: bool,
+        fields: &[CustomFieldFilter],
+    ) -> Result<Vec<Message>> {
+        let ranked = Self::search_ranked(
+            ctx,
+            mm,
+            project_id,
+            query,
+            limit,
+            include_attachments,
+            fields,
+        )
+        .await?;
+        Ok(ranked.into_iter().map(|(message, _)| message).collect())
+    }
 
-        // Logic for handling raw vs literal queries:
-        // 1. If query contains explicit FTS operators (AND, OR, NOT) or wildcards (*), pass raw
-        // 2. If query has balanced quotes (phrase search), pass raw
-        // 3. Otherwise, quote each word to prevent hyphens being treated as NOT operator
-        //    e.g., "full-text search" -> FTS5 interprets as "full AND NOT text AND search"
-        //    Fix: Quote words containing hyphens: "\"full-text\" search"
-        let quote_count = query.chars().filter(|c| *c == '"').count();
-        let has_fts_operators = query.contains(" AND ")
-            || query.contains(" OR ")
-            || query.contains(" NOT ")
-            || query.contains('*');
+    /// [`Self::search_with_options`] with each message's relevance score
+    /// (negated BM25, higher is better; `None` for attachment-only matches).
+    async fn search_ranked(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        query: &str,
+        limit: i64,
+        include_attachments: bool,
+        fields: &[CustomFieldFilter],
+    ) -> Result<Vec<(Message, Option<f64>)>> {
+        let db = mm.db_read();
 
-        let fts_query = if quote_count % 2 != 0 {
-            // Unbalanced quotes: Treat as literal string search
-            // This satisfies PORT-5.2 (Error Handling) for obviously malformed inputs
-            format!("\"{}\"", query.replace('"', "\"\""))
-        } else if has_fts_operators || query.starts_with('"') {
-            // Has explicit FTS operators or is a phrase search - pass raw
-            query.to_string()
-        } else {
-            // Simple search: quote words containing hyphens to prevent FTS5 misinterpretation
-            // "full-text search" -> "\"full-text\" search"
-            query
-                .split_whitespace()
-                .map(|word| {
-                    if word.contains('-') && !word.starts_with('"') {
-                        format!("\"{}\"", word)
-                    } else {
-                        word.to_string()
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(" ")
+        let Some(fts_query) = fts_match_query(query) else {
+            info!("Search query '{}' is in blocklist, returning empty", query);
+            return Ok(Vec::new());
+        };
+        let backend = mm.db_backend();
+        let (condition, condition_params) =
+            search_condition(backend, &fts_query, include_attachments, fields);
+        // Lower ranks first; subject terms count double, as on SQLite
+        let ranks = match backend {
+            Backend::Sqlite => {
+                r#"SELECT rowid AS id, bm25(message_search, 2.0, 1.0) AS rank
+                FROM message_search WHERE message_search MATCH ?"#
+            }
+            Backend::Postgres => {
+                r#"SELECT s.id, -ts_rank('{0, 0, 0.5, 1}', message_search_document(s.subject, s.body_md), q) AS rank
+                FROM messages AS s, to_tsquery('simple', ?) AS q
+                WHERE message_search_document(s.subject, s.body_md) @@ q"#
+            }
         };
 
-        let stmt = db.prepare(
+        let sql = format!(
             r#"
             SELECT
                 m.id, m.project_id, m.sender_id, ag.name as sender_name, m.thread_id, m.subject, m.body_md,
-                m.importance, m.ack_required, m.created_ts, m.attachments
+                m.importance, m.ack_required, m.created_ts, m.attachments, r.rank,
+                COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
             FROM messages AS m
             JOIN agents AS ag ON m.sender_id = ag.id
-            WHERE m.project_id = ? AND m.id IN (
-                SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?
-            )
-            ORDER BY m.created_ts DESC
+            LEFT JOIN (
+                {}
+            ) AS r ON r.id = m.id
+            WHERE m.project_id = ? AND {}
+            ORDER BY r.rank IS NULL, r.rank, m.created_ts DESC
             LIMIT ?
-            "#
-        ).await?;
+            "#,
+            ranks, condition
+        );
+        let stmt = db.prepare(&sql).await?;
 
-        let mut rows = match stmt.query((project_id, fts_query.as_str(), limit)).await {
+        let mut params: Vec<libsql::Value> =
+            vec![search_param(backend, &fts_query), project_id.get().into()];
+        params.extend(condition_params);
+        params.push(limit.into());
+
+        let mut rows = match stmt.query(params).await {
             Ok(rows) => rows,
             Err(e) => {
                 info!(
@@ -897,30 +2151,139 @@ impl MessageBmc {
 
             let attachments_str: String = row.get(10)?;
             let attachments: Vec<Value> = serde_json::from_str(&attachments_str)?;
+            let rank: Option<f64> = row.get(11)?;
+            let public_id: String = row.get(12)?;
+
+            messages.push((
+                Message {
+                    id,
+                    project_id,
+                    sender_id,
+                    sender_name,
+                    thread_id,
+                    subject,
+                    body_md,
+                    importance,
+                    ack_required,
+                    created_ts,
+                    attachments,
+                    public_id,
+                },
+                rank.map(|r| -r),
+            ));
+        }
+        Ok(messages)
+    }
 
-            messages.push(Message {
-                id,
-                project_id,
-                sender_id,
-                sender_name,
-                thread_id,
-                subject,
-                body_md,
-                importance,
-                ack_required,
-                created_ts,
-                attachments,
+    /// Full-text search returning highlighted snippets instead of bare rows.
+    ///
+    /// Hits come in relevance order as in [`Self::search_with_options`] and
+    /// carry their score. Each hit carries a snippet around the first match in the body (falling
+    /// back to the subject, then to indexed attachment text when
+    /// `include_attachments` is set). When `context` is non-zero, up to
+    /// `context` messages before and after the hit in the same thread are
+    /// attached. `fields` filters as in [`Self::search_with_options`], and
+    /// hits carry the message's custom field values.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_with_snippets(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        query: &str,
+        limit: i64,
+        context: usize,
+        include_attachments: bool,
+        fields: &[CustomFieldFilter],
+    ) -> Result<Vec<SearchHit>> {
+        let messages = Self::search_ranked(
+            ctx,
+            mm,
+            project_id,
+            query,
+            limit,
+            include_attachments,
+            fields,
+        )
+        .await?;
+        let ids: Vec<i64> = messages.iter().map(|(m, _)| m.id).collect();
+        let mut custom_fields = Self::get_custom_fields(ctx, mm, &ids).await?;
+        let terms = extract_terms(query);
+        let mut threads: HashMap<String, Vec<Message>> = HashMap::new();
+        let mut hits = Vec::with_capacity(messages.len());
+
+        for (message, score) in messages {
+            let mut attachment = None;
+            let (mut field, mut snippet) =
+                match build_snippet(&message.body_md, &terms, SNIPPET_RADIUS) {
+                    Some(snippet) => ("body", Some(snippet)),
+                    None => (
+                        "subject",
+                        build_snippet(&message.subject, &terms, SNIPPET_RADIUS),
+                    ),
+                };
+
+            if snippet.is_none() && include_attachments {
+                for text in
+                    AttachmentTextBmc::list_for_message(ctx, mm, MessageId::new(message.id)).await?
+                {
+                    if let Some(found) = build_snippet(&text.content, &terms, SNIPPET_RADIUS) {
+                        field = "attachment";
+                        snippet = Some(found);
+                        attachment = Some(text.filename);
+                        break;
+                    }
+                }
+            }
+
+            let mut neighbours = Vec::new();
+            if context > 0
+                && let Some(thread_id) = message.thread_id.as_deref()
+            {
+                if !threads.contains_key(thread_id) {
+                    let thread = Self::list_by_thread(ctx, mm, project_id, thread_id).await?;
+                    threads.insert(thread_id.to_string(), thread);
+                }
+                let thread = &threads[thread_id];
+                if let Some(pos) = thread.iter().position(|m| m.id == message.id) {
+                    let before = &thread[pos.saturating_sub(context)..pos];
+                    let after = &thread[pos + 1..(pos + 1 + context).min(thread.len())];
+                    let tagged = before
+                        .iter()
+                        .map(|m| ("before", m))
+                        .chain(after.iter().map(|m| ("after", m)));
+                    for (position, m) in tagged {
+                        neighbours.push(SearchContextMessage {
+                            id: m.id,
+                            position,
+                            sender_name: m.sender_name.clone(),
+                            subject: m.subject.clone(),
+                            created_ts: m.created_ts,
+                            excerpt: context_excerpt(&m.body_md),
+                        });
+                    }
+                }
+            }
+
+            hits.push(SearchHit {
+                custom_fields: custom_fields.remove(&message.id),
+                message,
+                field,
+                attachment,
+                snippet,
+                score,
+                context: neighbours,
             });
         }
-        Ok(messages)
+
+        Ok(hits)
     }
 
     /// Mark a message as read by a recipient
     pub async fn mark_read(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
-        message_id: i64,
-        agent_id: i64,
+        message_id: MessageId,
+        agent_id: AgentId,
     ) -> Result<()> {
         let db = mm.db();
         let now = chrono::Utc::now().naive_utc();
@@ -931,16 +2294,21 @@ impl MessageBmc {
             UPDATE message_recipients SET read_ts = ? WHERE message_id = ? AND agent_id = ? AND read_ts IS NULL
             "#
         ).await?;
-        stmt.execute((now_str, message_id, agent_id)).await?;
+        let read = stmt
+            .execute((now_str, message_id.get(), agent_id.get()))
+            .await?;
+        if read > 0 {
+            Self::record_recipient_event(ctx, mm, "message.read", message_id, agent_id).await?;
+        }
         Ok(())
     }
 
     /// Acknowledge a message by a recipient
     pub async fn acknowledge(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
-        message_id: i64,
-        agent_id: i64,
+        message_id: MessageId,
+        agent_id: AgentId,
     ) -> Result<()> {
         let db = mm.db();
         let now = chrono::Utc::now().naive_utc();
@@ -956,19 +2324,82 @@ impl MessageBmc {
             "#,
             )
             .await?;
-        stmt.execute((now_str.as_str(), now_str.as_str(), message_id, agent_id))
+        let acknowledged = stmt
+            .execute((
+                now_str.as_str(),
+                now_str.as_str(),
+                message_id.get(),
+                agent_id.get(),
+            ))
+            .await?;
+
+        if acknowledged > 0 {
+            Self::record_recipient_event(ctx, mm, "message.acknowledged", message_id, agent_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Records a recipient's read or acknowledgment, with the project and
+    /// agent names subscribers filter on.
+    async fn record_recipient_event(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        kind: &str,
+        message_id: MessageId,
+        agent_id: AgentId,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT m.project_id, a.name, s.name
+            FROM messages m
+            JOIN agents s ON s.id = m.sender_id
+            JOIN agents a ON a.id = ?
+            WHERE m.id = ?
+            "#,
+            )
             .await?;
+        let mut rows = stmt.query((agent_id.get(), message_id.get())).await?;
+        let (project_id, agent_name, sender_name) = match rows.next().await? {
+            Some(row) => (
+                row.get::<i64>(0).ok(),
+                row.get::<String>(1).ok(),
+                row.get::<String>(2).ok(),
+            ),
+            None => (None, None, None),
+        };
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                kind,
+                project_id,
+                agent_name.as_deref(),
+                serde_json::json!({
+                    "message_id": message_id,
+                    "agent_id": agent_id,
+                    "sender": sender_name,
+                }),
+            ),
+        )
+        .await;
         Ok(())
     }
 
-    /// List distinct threads for a project
+    /// List distinct threads for a project, most recently active first.
+    ///
+    /// Archived threads (see [`crate::model::thread_archive`]) are left out
+    /// unless `include_archived` is set.
     pub async fn list_threads(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
         limit: i64,
+        include_archived: bool,
     ) -> Result<Vec<ThreadSummary>> {
-        let db = mm.db();
+        let db = mm.db_read();
 
         let stmt = db
             .prepare(
@@ -977,17 +2408,23 @@ impl MessageBmc {
                 m.thread_id,
                 MIN(m.subject) as subject,
                 COUNT(*) as message_count,
-                MAX(m.created_ts) as last_message_ts
+                MAX(m.created_ts) as last_message_ts,
+                a.thread_id IS NOT NULL as archived
             FROM messages AS m
-            WHERE m.project_id = ? AND m.thread_id IS NOT NULL
-            GROUP BY m.thread_id
+            LEFT JOIN archived_threads AS a
+                ON a.project_id = m.project_id AND a.thread_id = m.thread_id
+            WHERE m.project_id = ?1 AND m.thread_id IS NOT NULL
+              AND (?3 OR a.thread_id IS NULL)
+            GROUP BY m.thread_id, a.thread_id
             ORDER BY last_message_ts DESC
-            LIMIT ?
+            LIMIT ?2
             "#,
             )
             .await?;
 
-        let mut rows = stmt.query((project_id, limit)).await?;
+        let mut rows = stmt
+            .query((project_id.get(), limit, include_archived))
+            .await?;
         let mut threads = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -998,17 +2435,120 @@ impl MessageBmc {
             let last_message_ts =
                 NaiveDateTime::parse_from_str(&last_message_ts_str, "%Y-%m-%d %H:%M:%S")
                     .unwrap_or_default();
+            let archived: bool = row.get(4)?;
 
             threads.push(ThreadSummary {
                 thread_id,
                 subject,
                 message_count: message_count as usize,
                 last_message_ts,
+                archived,
             });
         }
         Ok(threads)
     }
 
+    /// Lists a page of a project's threads, most recently active first.
+    ///
+    /// Pages like [`Self::list_inbox_page`]; archived threads are left out
+    /// unless `include_archived` is set.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a limit below 1 or a bad cursor.
+    pub async fn list_threads_page(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        limit: i64,
+        cursor: Option<&str>,
+        include_archived: bool,
+    ) -> Result<ListPage<ThreadSummary>> {
+        let limit = page_limit(limit)?;
+        let after = Keyset::decode(cursor)?;
+        let (after_ts, after_thread) = match after {
+            Some(keyset) => (Some(keyset.ts), Some(keyset.key)),
+            None => (None, None),
+        };
+
+        let db = mm.db_read();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT
+                m.thread_id,
+                MIN(m.subject) as subject,
+                COUNT(*) as message_count,
+                MAX(m.created_ts) as last_message_ts,
+                a.thread_id IS NOT NULL as archived
+            FROM messages AS m
+            LEFT JOIN archived_threads AS a
+                ON a.project_id = m.project_id AND a.thread_id = m.thread_id
+            WHERE m.project_id = ?1 AND m.thread_id IS NOT NULL
+              AND (?2 OR a.thread_id IS NULL)
+            GROUP BY m.thread_id, a.thread_id
+            HAVING ?3 IS NULL OR MAX(m.created_ts) < ?3
+                OR (MAX(m.created_ts) = ?3 AND m.thread_id < ?4)
+            ORDER BY last_message_ts DESC, m.thread_id DESC
+            LIMIT ?5
+            "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                project_id.get(),
+                include_archived,
+                after_ts,
+                after_thread,
+                limit + 1,
+            ))
+            .await?;
+
+        let mut threads = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let message_count: i64 = row.get(2)?;
+            let last_message_ts: String = row.get(3)?;
+            threads.push(ThreadSummary {
+                thread_id: row.get(0)?,
+                subject: row.get(1)?,
+                message_count: usize::try_from(message_count).unwrap_or_default(),
+                last_message_ts: parse_timestamp(&last_message_ts, "messages.created_ts"),
+                archived: row.get(4)?,
+            });
+        }
+
+        let total = Self::count_threads(mm, project_id, include_archived).await?;
+        Ok(keyset_page(threads, limit, total, |t| {
+            (t.last_message_ts, t.thread_id.clone())
+        }))
+    }
+
+    /// Counts a project's threads, leaving out archived ones unless
+    /// `include_archived` is set.
+    pub(crate) async fn count_threads(
+        mm: &ModelManager,
+        project_id: ProjectId,
+        include_archived: bool,
+    ) -> Result<i64> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT COUNT(DISTINCT m.thread_id)
+            FROM messages AS m
+            LEFT JOIN archived_threads AS a
+                ON a.project_id = m.project_id AND a.thread_id = m.thread_id
+            WHERE m.project_id = ?1 AND m.thread_id IS NOT NULL
+              AND (?2 OR a.thread_id IS NULL)
+            "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), include_archived)).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(0),
+        }
+    }
+
     /// List recent messages for a project
     pub async fn list_recent(
         _ctx: &Ctx,
@@ -1016,12 +2556,13 @@ impl MessageBmc {
         project_id: ProjectId,
         limit: i64,
     ) -> Result<Vec<Message>> {
-        let db = mm.db();
+        let db = mm.db_read();
         let stmt = db.prepare(
             r#"
             SELECT
                 m.id, m.project_id, m.sender_id, ag.name as sender_name, m.thread_id, m.subject, m.body_md,
-                m.importance, m.ack_required, m.created_ts, m.attachments
+                m.importance, m.ack_required, m.created_ts, m.attachments,
+                COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
             FROM messages AS m
             JOIN agents AS ag ON m.sender_id = ag.id
             WHERE m.project_id = ?
@@ -1049,6 +2590,7 @@ impl MessageBmc {
 
             let attachments_str: String = row.get(10)?;
             let attachments: Vec<Value> = serde_json::from_str(&attachments_str)?;
+            let public_id: String = row.get(11)?;
 
             messages.push(Message {
                 id,
@@ -1062,6 +2604,7 @@ impl MessageBmc {
                 ack_required,
                 created_ts,
                 attachments,
+                public_id,
             });
         }
         Ok(messages)
@@ -1085,15 +2628,22 @@ impl MessageBmc {
     pub async fn list_pending_reviews(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: Option<i64>,
-        sender_id: Option<i64>,
+        project_id: Option<ProjectId>,
+        sender_id: Option<AgentId>,
         limit: i64,
     ) -> Result<Vec<PendingReviewRow>> {
         let db = mm.db();
         let limit_clamped = limit.clamp(1, 50);
 
         // Build query with optional filters
-        let mut query = String::from(
+        let (recipients_open, recipients_close) = match db.backend() {
+            Backend::Sqlite => ("json_group_array(json_object(", "))"),
+            Backend::Postgres => (
+                "COALESCE(CAST(json_agg(json_build_object(",
+                ")) AS TEXT), '[]')",
+            ),
+        };
+        let mut query = format!(
             r#"
             SELECT
                 m.id as message_id,
@@ -1111,13 +2661,13 @@ impl MessageBmc {
                 (SELECT COUNT(*) FROM messages m2
                  WHERE m2.thread_id = m.thread_id AND m.thread_id IS NOT NULL) as thread_count,
                 (
-                    SELECT json_group_array(json_object(
+                    SELECT {recipients_open}
                         'agent_id', mr.agent_id,
                         'agent_name', a.name,
                         'recipient_type', mr.recipient_type,
                         'read_ts', mr.read_ts,
                         'ack_ts', mr.ack_ts
-                    ))
+                    {recipients_close}
                     FROM message_recipients mr
                     JOIN agents a ON mr.agent_id = a.id
                     WHERE mr.message_id = m.id
@@ -1126,7 +2676,7 @@ impl MessageBmc {
             JOIN agents sender ON m.sender_id = sender.id
             JOIN projects p ON m.project_id = p.id
             WHERE
-                m.ack_required = TRUE
+                m.ack_required = 1
                 AND EXISTS (
                     SELECT 1 FROM message_recipients mr2
                     WHERE mr2.message_id = m.id AND mr2.ack_ts IS NULL
@@ -1138,12 +2688,12 @@ impl MessageBmc {
 
         if let Some(pid) = project_id {
             query.push_str(" AND m.project_id = ?");
-            params.push(pid.into());
+            params.push(pid.get().into());
         }
 
         if let Some(sid) = sender_id {
             query.push_str(" AND m.sender_id = ?");
-            params.push(sid.into());
+            params.push(sid.get().into());
         }
 
         query.push_str(" ORDER BY m.created_ts DESC LIMIT ?");
@@ -1213,7 +2763,7 @@ impl MessageBmc {
         importance: ImportanceFilter,
         limit: i32,
     ) -> Result<Vec<UnifiedInboxItem>> {
-        let db = mm.db();
+        let db = mm.db_read();
 
         // Build query based on importance filter - joins with projects for slug
         let (query, params): (String, Vec<libsql::Value>) = match importance {
@@ -1309,12 +2859,161 @@ impl MessageBmc {
     }
 }
 
+/// Converts a user search string into an FTS5 `MATCH` expression.
+///
+/// Returns `None` for strings that match nothing useful (`*`, `...`) and
+/// would only produce errors or scan everything.
+pub(crate) fn fts_match_query(query: &str) -> Option<String> {
+    // FTS5 Unsearchable patterns (return empty to avoid errors or heavy meaningless queries)
+    // Python equivalent: _FTS5_UNSEARCHABLE_PATTERNS
+    let trimmed = query.trim();
+    if matches!(
+        trimmed,
+        "" | "*" | "**" | "***" | "." | ".." | "..." | "?" | "??" | "???"
+    ) {
+        return None;
+    }
+
+    // Logic for handling raw vs literal queries:
+    // 1. If query contains explicit FTS operators (AND, OR, NOT) or wildcards (*), pass raw
+    // 2. If query has balanced quotes (phrase search), pass raw
+    // 3. Otherwise, quote each word to prevent hyphens being treated as NOT operator
+    //    e.g., "full-text search" -> FTS5 interprets as "full AND NOT text AND search"
+    //    Fix: Quote words containing hyphens: "\"full-text\" search"
+    let quote_count = query.chars().filter(|c| *c == '"').count();
+    let has_fts_operators = query.contains(" AND ")
+        || query.contains(" OR ")
+        || query.contains(" NOT ")
+        || query.contains('*');
+
+    let fts_query = if quote_count % 2 != 0 {
+        // Unbalanced quotes: Treat as literal string search
+        // This satisfies PORT-5.2 (Error Handling) for obviously malformed inputs
+        format!("\"{}\"", query.replace('"', "\"\""))
+    } else if has_fts_operators || query.starts_with('"') {
+        // Has explicit FTS operators or is a phrase search - pass raw
+        query.to_string()
+    } else {
+        // Simple search: quote words containing hyphens to prevent FTS5 misinterpretation
+        // "full-text search" -> "\"full-text\" search"
+        query
+            .split_whitespace()
+            .map(|word| {
+                if word.contains('-') && !word.starts_with('"') {
+                    format!("\"{}\"", word)
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    Some(fts_query)
+}
+
+/// The parameter an FTS5 `query` is bound as on `backend`: as it is on
+/// SQLite, translated for `to_tsquery` on Postgres (see
+/// [`postgres::fts_to_tsquery`]).
+pub(crate) fn search_param(backend: Backend, fts_query: &str) -> libsql::Value {
+    match backend {
+        Backend::Sqlite => fts_query.into(),
+        // An empty tsquery matches nothing
+        Backend::Postgres => postgres::fts_to_tsquery(fts_query)
+            .unwrap_or_default()
+            .into(),
+    }
+}
+
+/// SQL condition on `messages AS m` selecting the messages whose subject or
+/// body match the [`search_param`] it is bound with.
+pub(crate) fn message_match(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Sqlite => {
+            "m.id IN (SELECT rowid FROM message_search WHERE message_search MATCH ?)"
+        }
+        Backend::Postgres => {
+            "message_search_document(m.subject, m.body_md) @@ to_tsquery('simple', ?)"
+        }
+    }
+}
+
+/// SQL condition on `messages AS m` selecting the messages a search matches,
+/// with its parameters in order.
+///
+/// Shared by search and its facets so both see the same result set.
+pub(crate) fn search_condition(
+    backend: Backend,
+    fts_query: &str,
+    include_attachments: bool,
+    fields: &[CustomFieldFilter],
+) -> (String, Vec<libsql::Value>) {
+    let attachment_clause = match (include_attachments, backend) {
+        (false, _) => "",
+        (true, Backend::Sqlite) => {
+            r#"
+            OR m.id IN (
+                SELECT t.message_id FROM attachment_texts AS t
+                JOIN attachment_texts_fts AS f ON f.rowid = t.id
+                WHERE attachment_texts_fts MATCH ?
+            )"#
+        }
+        (true, Backend::Postgres) => {
+            r#"
+            OR m.id IN (
+                SELECT t.message_id FROM attachment_texts AS t
+                WHERE attachment_text_document(t.filename, t.content) @@ to_tsquery('simple', ?)
+            )"#
+        }
+    };
+    let fields_clause = if fields.is_empty() {
+        String::new()
+    } else {
+        let condition = match backend {
+            Backend::Sqlite => "json_extract(fields, ?) = ?",
+            // Compared as JSON, so `1` doesn't match `"1"` (nor `true`)
+            Backend::Postgres => "CAST(fields AS JSONB) -> CAST(? AS TEXT) = CAST(? AS JSONB)",
+        };
+        let conditions = vec![condition; fields.len()].join(" AND ");
+        format!(
+            " AND m.id IN (SELECT message_id FROM message_custom_fields WHERE {})",
+            conditions
+        )
+    };
+    let condition = format!(
+        "({}{}){}",
+        message_match(backend),
+        attachment_clause,
+        fields_clause
+    );
+
+    let mut params: Vec<libsql::Value> = vec![search_param(backend, fts_query)];
+    if include_attachments {
+        params.push(search_param(backend, fts_query));
+    }
+    for filter in fields {
+        match backend {
+            Backend::Sqlite => {
+                params.push(filter.json_path().into());
+                params.push(filter.sql_value());
+            }
+            Backend::Postgres => {
+                params.push(filter.name.clone().into());
+                params.push(filter.value.to_string().into());
+            }
+        }
+    }
+    (condition, params)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreadSummary {
     pub thread_id: String,
     pub subject: String,
     pub message_count: usize,
     pub last_message_ts: NaiveDateTime,
+    /// Whether the thread is archived for inactivity
+    #[serde(default)]
+    pub archived: bool,
 }
 
 /// Paths for git archival of a message
@@ -1510,6 +3209,20 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_body_preview_cuts_at_line_or_char_boundary() {
+        let body = format!("{}\n{}", "a".repeat(60), "b".repeat(100));
+        let preview = body_preview(&body, 100);
+        assert!(preview.starts_with(&"a".repeat(60)));
+        assert!(!preview.split("\n\n").next().unwrap().contains('b'));
+        assert!(preview.ends_with("[Body truncated: 60 of 161 bytes shown. Fetch the full body with get_message(full=true).]"));
+
+        // No usable line break: cut inside the multi-byte character's start
+        let preview = body_preview(&"é".repeat(10), 5);
+        assert!(preview.starts_with("éé\n\n"));
+        assert!(preview.contains("4 of 20 bytes"));
+    }
+
     // ============================================================================
     // TDD Tests for build_message_paths
     // ============================================================================
@@ -0,0 +1,743 @@
+//! Localized templates for system-generated messages.
+//!
+//! Reminders, escalations, SLA reports, release receipts, quiet-hours notes
+//! and thread archival summaries are rendered from a message catalog instead
+//! of hard-coded English.
+//! Each template has a dotted key (e.g. `escalation.reminder.subject`) and
+//! `{name}` placeholders filled in when a message is generated.
+//!
+//! A project picks its locale with the `locale` setting (see
+//! [`crate::model::project_settings`]); projects without one use English.
+//! Templates are looked up along a fallback chain, for `pt-BR`:
+//!
+//! 1. `pt-BR` - custom template, then built-in
+//! 2. `pt` - custom template, then built-in
+//! 3. `en` - custom template, then built-in
+//!
+//! Built-in catalogs ship for [`BUILTIN_LOCALES`]; custom templates, stored
+//! per locale with [`MessageCatalogBmc::set_template`], override them or
+//! add locales that have no built-in catalog.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::types::ProjectId;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Locale used when a project has none, and the last fallback.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locales with a built-in catalog.
+pub const BUILTIN_LOCALES: &[&str] = &["en", "de", "es", "fr"];
+
+/// The English catalog; it defines the template keys and their placeholders.
+const EN: &[(&str, &str)] = &[
+    ("escalation.reminder.subject", "REMINDER: {subject}"),
+    (
+        "escalation.reminder.body",
+        "[System Escalation] Please acknowledge message {message_id}. \
+         {acked} of {total} recipients have acknowledged it.",
+    ),
+    (
+        "escalation.overdue.body",
+        "[System Escalation] This message requires acknowledgment and is overdue.\n\n\
+         Original message ID: {message_id}",
+    ),
+    ("escalation.overseer.subject", "[OVERDUE ACK] {subject}"),
+    (
+        "escalation.overseer.body",
+        "**Overdue ACK Escalation**\n\n\
+         Message ID: {message_id}\n\
+         Subject: {subject}\n\
+         From: {sender}\n\
+         To: {recipient} (awaiting ACK)\n\
+         Created: {created_ts}\n\n\
+         This message has not been acknowledged within the configured TTL.",
+    ),
+    ("sla.violation.subject", "[SLA] {subject}"),
+    (
+        "sla.violation.body",
+        "**SLA Violation**\n\n\
+         Message ID: {message_id}\n\
+         Subject: {subject}\n\
+         Importance: {importance} (ack within {ack_within})\n\
+         Sent: {created_ts}\n\n\
+         Recipients that missed the deadline:\n\
+         {recipients}",
+    ),
+    ("sla.recipient.acked", "- {agent} (acknowledged {ack_ts})"),
+    ("sla.recipient.pending", "- {agent} (not acknowledged)"),
+    ("reservation.released.subject", "[RELEASED] {path}"),
+    (
+        "reservation.released.body",
+        "Reservation {reservation_id} on `{path}` was {cause}. \
+         The path is no longer held by this agent.",
+    ),
+    ("reservation.cause.released", "released"),
+    ("reservation.cause.force_released", "force released"),
+    ("reservation.cause.expired", "expired"),
+    (
+        "quiet_hours.reason",
+        "Quiet hours: outside business hours ({timezone})",
+    ),
+    ("thread.archived.subject", "[ARCHIVED] {subject}"),
+    (
+        "thread.archived.body",
+        "**Thread archived** after {days} days without messages.\n\n\
+         Messages: {message_count}\n\
+         Participants: {participants}\n\
+         Started: {first_ts}\n\
+         Last message: {last_ts} from {last_sender}\n\n\
+         > {last_snippet}\n\n\
+         A reply to this thread reactivates it.",
+    ),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("escalation.reminder.subject", "ERINNERUNG: {subject}"),
+    (
+        "escalation.reminder.body",
+        "[System-Eskalation] Bitte bestätigen Sie Nachricht {message_id}. \
+         {acked} von {total} Empfängern haben sie bestätigt.",
+    ),
+    (
+        "escalation.overdue.body",
+        "[System-Eskalation] Diese Nachricht erfordert eine Bestätigung und ist überfällig.\n\n\
+         Ursprüngliche Nachrichten-ID: {message_id}",
+    ),
+    (
+        "escalation.overseer.subject",
+        "[BESTÄTIGUNG ÜBERFÄLLIG] {subject}",
+    ),
+    (
+        "escalation.overseer.body",
+        "**Eskalation: überfällige Bestätigung**\n\n\
+         Nachrichten-ID: {message_id}\n\
+         Betreff: {subject}\n\
+         Von: {sender}\n\
+         An: {recipient} (Bestätigung ausstehend)\n\
+         Erstellt: {created_ts}\n\n\
+         Diese Nachricht wurde nicht innerhalb der konfigurierten Frist bestätigt.",
+    ),
+    ("sla.violation.subject", "[SLA] {subject}"),
+    (
+        "sla.violation.body",
+        "**SLA-Verletzung**\n\n\
+         Nachrichten-ID: {message_id}\n\
+         Betreff: {subject}\n\
+         Wichtigkeit: {importance} (Bestätigung innerhalb von {ack_within})\n\
+         Gesendet: {created_ts}\n\n\
+         Empfänger, die die Frist verpasst haben:\n\
+         {recipients}",
+    ),
+    ("sla.recipient.acked", "- {agent} (bestätigt {ack_ts})"),
+    ("sla.recipient.pending", "- {agent} (nicht bestätigt)"),
+    ("reservation.released.subject", "[FREIGEGEBEN] {path}"),
+    (
+        "reservation.released.body",
+        "Reservierung {reservation_id} auf `{path}`: {cause}. \
+         Der Pfad wird von diesem Agenten nicht mehr gehalten.",
+    ),
+    ("reservation.cause.released", "freigegeben"),
+    (
+        "reservation.cause.force_released",
+        "zwangsweise freigegeben",
+    ),
+    ("reservation.cause.expired", "abgelaufen"),
+    (
+        "quiet_hours.reason",
+        "Ruhezeit: außerhalb der Geschäftszeiten ({timezone})",
+    ),
+    ("thread.archived.subject", "[ARCHIVIERT] {subject}"),
+    (
+        "thread.archived.body",
+        "**Thread archiviert** nach {days} Tagen ohne Nachrichten.\n\n\
+         Nachrichten: {message_count}\n\
+         Teilnehmer: {participants}\n\
+         Begonnen: {first_ts}\n\
+         Letzte Nachricht: {last_ts} von {last_sender}\n\n\
+         > {last_snippet}\n\n\
+         Eine Antwort in diesem Thread reaktiviert ihn.",
+    ),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("escalation.reminder.subject", "RECORDATORIO: {subject}"),
+    (
+        "escalation.reminder.body",
+        "[Escalado del sistema] Confirme el mensaje {message_id}. \
+         {acked} de {total} destinatarios lo han confirmado.",
+    ),
+    (
+        "escalation.overdue.body",
+        "[Escalado del sistema] Este mensaje requiere confirmación y está vencido.\n\n\
+         ID del mensaje original: {message_id}",
+    ),
+    (
+        "escalation.overseer.subject",
+        "[CONFIRMACIÓN VENCIDA] {subject}",
+    ),
+    (
+        "escalation.overseer.body",
+        "**Escalado por confirmación vencida**\n\n\
+         ID del mensaje: {message_id}\n\
+         Asunto: {subject}\n\
+         De: {sender}\n\
+         Para: {recipient} (confirmación pendiente)\n\
+         Creado: {created_ts}\n\n\
+         Este mensaje no se ha confirmado dentro del plazo configurado.",
+    ),
+    ("sla.violation.subject", "[SLA] {subject}"),
+    (
+        "sla.violation.body",
+        "**Incumplimiento de SLA**\n\n\
+         ID del mensaje: {message_id}\n\
+         Asunto: {subject}\n\
+         Importancia: {importance} (confirmar en {ack_within})\n\
+         Enviado: {created_ts}\n\n\
+         Destinatarios que no cumplieron el plazo:\n\
+         {recipients}",
+    ),
+    ("sla.recipient.acked", "- {agent} (confirmado {ack_ts})"),
+    ("sla.recipient.pending", "- {agent} (sin confirmar)"),
+    ("reservation.released.subject", "[LIBERADA] {path}"),
+    (
+        "reservation.released.body",
+        "Reserva {reservation_id} sobre `{path}`: {cause}. \
+         Este agente ya no retiene la ruta.",
+    ),
+    ("reservation.cause.released", "liberada"),
+    ("reservation.cause.force_released", "liberada a la fuerza"),
+    ("reservation.cause.expired", "caducada"),
+    (
+        "quiet_hours.reason",
+        "Horas de silencio: fuera del horario laboral ({timezone})",
+    ),
+    ("thread.archived.subject", "[ARCHIVADO] {subject}"),
+    (
+        "thread.archived.body",
+        "**Hilo archivado** tras {days} días sin mensajes.\n\n\
+         Mensajes: {message_count}\n\
+         Participantes: {participants}\n\
+         Iniciado: {first_ts}\n\
+         Último mensaje: {last_ts} de {last_sender}\n\n\
+         > {last_snippet}\n\n\
+         Una respuesta en este hilo lo reactiva.",
+    ),
+];
+
+const FR: &[(&str, &str)] = &[
+    ("escalation.reminder.subject", "RAPPEL : {subject}"),
+    (
+        "escalation.reminder.body",
+        "[Escalade système] Veuillez accuser réception du message {message_id}. \
+         {acked} destinataires sur {total} en ont accusé réception.",
+    ),
+    (
+        "escalation.overdue.body",
+        "[Escalade système] Ce message exige un accusé de réception et est en retard.\n\n\
+         ID du message d'origine : {message_id}",
+    ),
+    (
+        "escalation.overseer.subject",
+        "[ACCUSÉ EN RETARD] {subject}",
+    ),
+    (
+        "escalation.overseer.body",
+        "**Escalade : accusé de réception en retard**\n\n\
+         ID du message : {message_id}\n\
+         Objet : {subject}\n\
+         De : {sender}\n\
+         À : {recipient} (accusé attendu)\n\
+         Créé : {created_ts}\n\n\
+         Ce message n'a pas reçu d'accusé de réception dans le délai configuré.",
+    ),
+    ("sla.violation.subject", "[SLA] {subject}"),
+    (
+        "sla.violation.body",
+        "**Non-respect du SLA**\n\n\
+         ID du message : {message_id}\n\
+         Objet : {subject}\n\
+         Importance : {importance} (accusé sous {ack_within})\n\
+         Envoyé : {created_ts}\n\n\
+         Destinataires ayant manqué l'échéance :\n\
+         {recipients}",
+    ),
+    ("sla.recipient.acked", "- {agent} (accusé {ack_ts})"),
+    ("sla.recipient.pending", "- {agent} (sans accusé)"),
+    ("reservation.released.subject", "[LIBÉRÉE] {path}"),
+    (
+        "reservation.released.body",
+        "Réservation {reservation_id} sur `{path}` : {cause}. \
+         Cet agent ne détient plus ce chemin.",
+    ),
+    ("reservation.cause.released", "libérée"),
+    ("reservation.cause.force_released", "libérée de force"),
+    ("reservation.cause.expired", "expirée"),
+    (
+        "quiet_hours.reason",
+        "Heures calmes : en dehors des heures ouvrées ({timezone})",
+    ),
+    ("thread.archived.subject", "[ARCHIVÉ] {subject}"),
+    (
+        "thread.archived.body",
+        "**Fil archivé** après {days} jours sans message.\n\n\
+         Messages : {message_count}\n\
+         Participants : {participants}\n\
+         Commencé : {first_ts}\n\
+         Dernier message : {last_ts} de {last_sender}\n\n\
+         > {last_snippet}\n\n\
+         Une réponse dans ce fil le réactive.",
+    ),
+];
+
+fn builtin(locale: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    match locale {
+        "en" => Some(EN),
+        "de" => Some(DE),
+        "es" => Some(ES),
+        "fr" => Some(FR),
+        _ => None,
+    }
+}
+
+fn builtin_template(locale: &str, key: &str) -> Option<&'static str> {
+    builtin(locale)?
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, template)| *template)
+}
+
+/// Template keys, in catalog order.
+pub fn template_keys() -> impl Iterator<Item = &'static str> {
+    EN.iter().map(|(key, _)| *key)
+}
+
+/// Placeholder names a template uses, in order of appearance.
+pub fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        match rest.find('}') {
+            Some(close) if is_placeholder_name(&rest[..close]) => {
+                if !names.contains(&&rest[..close]) {
+                    names.push(&rest[..close]);
+                }
+                rest = &rest[close + 1..];
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+}
+
+/// Fills `{name}` placeholders from `args` in a single pass, so values that
+/// contain braces are inserted as they are. Unknown placeholders are kept.
+pub fn render(template: &str, args: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let value = rest.find('}').and_then(|close| {
+            let name = &rest[1..close];
+            args.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, value)| (close, *value))
+        });
+        match value {
+            Some((close, value)) => {
+                out.push_str(value);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Normalizes a BCP 47 style tag: `pt_br` becomes `pt-BR`, `DE` becomes `de`.
+///
+/// # Errors
+/// Returns `InvalidInput` when the tag is not a 2-3 letter language code
+/// followed by optional alphanumeric subtags.
+pub fn normalize_locale(locale: &str) -> Result<String> {
+    let invalid = || {
+        Error::InvalidInput(format!(
+            "Invalid locale '{}': expected a tag like 'de' or 'pt-BR'",
+            locale
+        ))
+    };
+    let mut parts = locale.trim().split(['-', '_']);
+    let language = parts.next().unwrap_or_default();
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(invalid());
+    }
+    let mut tag = language.to_ascii_lowercase();
+    for part in parts {
+        if !(2..=8).contains(&part.len()) || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+        tag.push('-');
+        match part.len() {
+            2 => tag.push_str(&part.to_ascii_uppercase()),
+            4 => {
+                // Script subtag, e.g. Hant
+                tag.push_str(&part[..1].to_ascii_uppercase());
+                tag.push_str(&part[1..].to_ascii_lowercase());
+            }
+            _ => tag.push_str(&part.to_ascii_lowercase()),
+        }
+    }
+    Ok(tag)
+}
+
+/// Locales to try for `locale`, most specific first and ending with
+/// [`DEFAULT_LOCALE`]: `zh-Hant-TW` gives `zh-Hant-TW`, `zh-Hant`, `zh`, `en`.
+pub fn fallback_chain(locale: &str) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut tag = locale;
+    loop {
+        chain.push(tag.to_string());
+        match tag.rfind('-') {
+            Some(i) => tag = &tag[..i],
+            None => break,
+        }
+    }
+    if !chain.iter().any(|l| l == DEFAULT_LOCALE) {
+        chain.push(DEFAULT_LOCALE.to_string());
+    }
+    chain
+}
+
+/// Templates resolved for one locale, ready to render.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    locale: String,
+    templates: HashMap<&'static str, String>,
+}
+
+impl Catalog {
+    /// The built-in templates of `locale`, without custom templates.
+    pub fn builtin(locale: &str) -> Self {
+        Self::resolve(locale, &HashMap::new())
+    }
+
+    /// Resolves every key along the fallback chain; `custom` maps
+    /// `(locale, key)` to custom templates.
+    fn resolve(locale: &str, custom: &HashMap<(String, String), String>) -> Self {
+        let chain = fallback_chain(locale);
+        let templates = template_keys()
+            .map(|key| {
+                let template = chain
+                    .iter()
+                    .find_map(|l| {
+                        custom
+                            .get(&(l.clone(), key.to_string()))
+                            .cloned()
+                            .or_else(|| builtin_template(l, key).map(str::to_string))
+                    })
+                    .unwrap_or_else(|| key.to_string());
+                (key, template)
+            })
+            .collect();
+        Self {
+            locale: locale.to_string(),
+            templates,
+        }
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Renders the template `key`; an unknown key renders as itself.
+    pub fn render(&self, key: &str, args: &[(&str, &str)]) -> String {
+        match self.templates.get(key) {
+            Some(template) => render(template, args),
+            None => key.to_string(),
+        }
+    }
+}
+
+/// A template in effect for a locale.
+///
+/// # Fields
+///
+/// - `source` - Locale the template was found in along the fallback chain
+/// - `custom` - `true` for a stored template, `false` for a built-in one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageTemplate {
+    pub key: String,
+    pub template: String,
+    pub placeholders: Vec<String>,
+    pub source: String,
+    pub custom: bool,
+}
+
+/// Backend Model Controller for the message catalog.
+pub struct MessageCatalogBmc;
+
+impl MessageCatalogBmc {
+    /// The locale of a project's system messages.
+    pub async fn project_locale(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<String> {
+        let stmt = mm
+            .db()
+            .prepare("SELECT locale FROM project_locales WHERE project_id = ?")
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(DEFAULT_LOCALE.to_string()),
+        }
+    }
+
+    /// Sets the locale of a project's system messages.
+    ///
+    /// # Returns
+    /// The normalized locale.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a malformed locale tag.
+    pub async fn set_project_locale(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        locale: &str,
+    ) -> Result<String> {
+        let locale = normalize_locale(locale)?;
+        let stmt = mm
+            .db()
+            .prepare(
+                r#"
+                INSERT INTO project_locales (project_id, locale, updated_ts)
+                VALUES (?, ?, CURRENT_TIMESTAMP)
+                ON CONFLICT(project_id) DO UPDATE SET
+                    locale = excluded.locale,
+                    updated_ts = excluded.updated_ts
+                "#,
+            )
+            .await?;
+        stmt.execute((project_id.get(), locale.as_str())).await?;
+        Ok(locale)
+    }
+
+    /// The catalog for `locale`, custom templates included.
+    pub async fn for_locale(_ctx: &Ctx, mm: &ModelManager, locale: &str) -> Result<Catalog> {
+        let custom = load_custom(mm, &fallback_chain(locale)).await?;
+        Ok(Catalog::resolve(locale, &custom))
+    }
+
+    /// The catalog for a project's locale.
+    pub async fn for_project(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Catalog> {
+        let locale = Self::project_locale(ctx, mm, project_id).await?;
+        Self::for_locale(ctx, mm, &locale).await
+    }
+
+    /// Stores a custom template for `locale`, replacing any previous one.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an unknown key, an empty template, or a
+    /// placeholder the key does not provide.
+    pub async fn set_template(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        locale: &str,
+        key: &str,
+        template: &str,
+    ) -> Result<()> {
+        let locale = normalize_locale(locale)?;
+        let Some(reference) = builtin_template(DEFAULT_LOCALE, key) else {
+            return Err(Error::InvalidInput(format!(
+                "Unknown template key '{}'. Known keys: {}",
+                key,
+                template_keys().collect::<Vec<_>>().join(", ")
+            )));
+        };
+        if template.trim().is_empty() {
+            return Err(Error::InvalidInput("Template must not be empty".into()));
+        }
+        let allowed = placeholders(reference);
+        if let Some(unknown) = placeholders(template)
+            .into_iter()
+            .find(|name| !allowed.contains(name))
+        {
+            return Err(Error::InvalidInput(format!(
+                "Template '{}' has no placeholder {{{}}}; available: {}",
+                key,
+                unknown,
+                allowed
+                    .iter()
+                    .map(|n| format!("{{{}}}", n))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+
+        let stmt = mm
+            .db()
+            .prepare(
+                r#"
+                INSERT INTO message_templates (locale, key, template, updated_ts)
+                VALUES (?, ?, ?, CURRENT_TIMESTAMP)
+                ON CONFLICT(locale, key) DO UPDATE SET
+                    template = excluded.template,
+                    updated_ts = excluded.updated_ts
+                "#,
+            )
+            .await?;
+        stmt.execute((locale.as_str(), key, template)).await?;
+        Ok(())
+    }
+
+    /// Removes a custom template, restoring the built-in one.
+    ///
+    /// # Returns
+    /// `true` if a custom template was removed.
+    pub async fn remove_template(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        locale: &str,
+        key: &str,
+    ) -> Result<bool> {
+        let locale = normalize_locale(locale)?;
+        let stmt = mm
+            .db()
+            .prepare("DELETE FROM message_templates WHERE locale = ? AND key = ?")
+            .await?;
+        Ok(stmt.execute((locale.as_str(), key)).await? > 0)
+    }
+
+    /// Every template in effect for `locale` and where it comes from.
+    pub async fn list_templates(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        locale: &str,
+    ) -> Result<Vec<MessageTemplate>> {
+        let locale = normalize_locale(locale)?;
+        let chain = fallback_chain(&locale);
+        let custom = load_custom(mm, &chain).await?;
+
+        Ok(template_keys()
+            .map(|key| {
+                let (template, source, custom) = chain
+                    .iter()
+                    .find_map(|l| match custom.get(&(l.clone(), key.to_string())) {
+                        Some(template) => Some((template.clone(), l.clone(), true)),
+                        None => builtin_template(l, key)
+                            .map(|template| (template.to_string(), l.clone(), false)),
+                    })
+                    .unwrap_or_else(|| (key.to_string(), DEFAULT_LOCALE.to_string(), false));
+                MessageTemplate {
+                    key: key.to_string(),
+                    placeholders: placeholders(&template)
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect(),
+                    template,
+                    source,
+                    custom,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Custom templates of `locales`, keyed by `(locale, key)`.
+async fn load_custom(
+    mm: &ModelManager,
+    locales: &[String],
+) -> Result<HashMap<(String, String), String>> {
+    let mut custom = HashMap::new();
+    for locale in locales {
+        let stmt = mm
+            .db()
+            .prepare("SELECT key, template FROM message_templates WHERE locale = ?")
+            .await?;
+        let mut rows = stmt.query([locale.as_str()]).await?;
+        while let Some(row) = rows.next().await? {
+            custom.insert((locale.clone(), row.get::<String>(0)?), row.get(1)?);
+        }
+    }
+    Ok(custom)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_single_pass() {
+        assert_eq!(
+            render(
+                "[OVERDUE ACK] {subject} from {sender}",
+                &[("subject", "Fix {sender} parsing"), ("sender", "BlueLake")]
+            ),
+            "[OVERDUE ACK] Fix {sender} parsing from BlueLake"
+        );
+        assert_eq!(render("{unknown} {", &[]), "{unknown} {");
+        assert_eq!(placeholders("{a} {b_c} {a} {Not} {}"), vec!["a", "b_c"]);
+    }
+
+    #[test]
+    fn test_normalize_and_fallback() {
+        assert_eq!(normalize_locale("pt_br").unwrap(), "pt-BR");
+        assert_eq!(normalize_locale("DE").unwrap(), "de");
+        assert_eq!(normalize_locale("zh-hant-tw").unwrap(), "zh-Hant-TW");
+        for bad in ["", "e", "english", "de-", "de-x!"] {
+            assert!(normalize_locale(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(fallback_chain("pt-BR"), ["pt-BR", "pt", "en"]);
+        assert_eq!(fallback_chain("en-GB"), ["en-GB", "en"]);
+        assert_eq!(fallback_chain("en"), ["en"]);
+    }
+
+    #[test]
+    fn test_builtin_catalogs_match_english_keys() {
+        for locale in BUILTIN_LOCALES {
+            for (key, template) in builtin(locale).unwrap() {
+                let reference = builtin_template(DEFAULT_LOCALE, key)
+                    .unwrap_or_else(|| panic!("{}: unknown key {}", locale, key));
+                let mut expected = placeholders(reference);
+                let mut actual = placeholders(template);
+                expected.sort_unstable();
+                actual.sort_unstable();
+                assert_eq!(actual, expected, "{}: {}", locale, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_catalog_falls_back() {
+        let de = Catalog::builtin("de-AT");
+        assert_eq!(
+            de.render("escalation.reminder.subject", &[("subject", "Deploy")]),
+            "ERINNERUNG: Deploy"
+        );
+        let pt = Catalog::builtin("pt-BR");
+        assert_eq!(
+            pt.render("escalation.reminder.subject", &[("subject", "Deploy")]),
+            "REMINDER: Deploy"
+        );
+        assert_eq!(pt.render("no.such.key", &[]), "no.such.key");
+    }
+}
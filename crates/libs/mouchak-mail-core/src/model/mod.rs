@@ -16,14 +16,65 @@
 //! |-----|-------------|
 //! | `agent::AgentBmc` | AI agent registration and profiles |
 //! | `message::MessageBmc` | Inter-agent messaging |
+//! | `approval::ApprovalBmc` | Approval rules that hold high-impact messages until decided |
+//! | `delegation::DelegationBmc` | Grants to send messages on another agent's behalf |
+//! | `dead_letter::DeadLetterBmc` | Sends that could not be delivered, kept for inspection and requeue |
+//! | `message_catalog::MessageCatalogBmc` | Localized templates for system messages |
 //! | `project::ProjectBmc` | Project management |
+//! | `project_contact_policy::ProjectContactPolicyBmc` | Which projects may contact a project |
+//! | `project_settings::ProjectSettingsBmc` | Project time zone and business hours |
+//! | `project_template::ProjectTemplateBmc` | New projects from template bundles |
 //! | `file_reservation::FileReservationBmc` | File locking coordination |
 //! | `build_slot::BuildSlotBmc` | CI/CD slot management |
 //! | `macro_def::MacroDefBmc` | Workflow macro definitions |
 //! | `attachment::AttachmentBmc` | File attachments |
+//! | `attachment_blob::AttachmentBlobBmc` | Content-addressed attachment blobs and their garbage collection |
+//! | `attachment_text::AttachmentTextBmc` | Searchable text of message attachments |
 //! | `activity::ActivityBmc` | Unified activity feed |
+//! | `archive_gc::ArchiveGcBmc` | Git archive compaction |
+//! | `db_maintenance::DbMaintenanceBmc` | SQLite integrity check, ANALYZE and VACUUM |
+//! | `disk_watch::DiskWatcher` | Free space checks that switch the server to read-only |
+//! | `intent_journal::IntentJournalBmc` | Write-ahead intents of multi-step operations and their crash recovery |
 //! | `tool_metric::ToolMetricBmc` | Tool usage analytics |
+//! | `external_tool::ExternalToolBmc` | External HTTP endpoints proxied as MCP tools |
+//! | `github_sync::GithubSyncBmc` | Threads mirrored to GitHub issues and PRs |
+//! | `ticket::TicketBmc` | Jira and Linear tickets linked to messages |
+//! | `ci_status::CiStatusBmc` | CI build events routed to threads via commit links |
 //! | `overseer_message::OverseerMessageBmc` | Human escalation messages |
+//! | `broadcast_status::BroadcastStatusBmc` | Acknowledgment tracking for broadcast messages |
+//! | `receipt::ReceiptBmc` | Per-recipient read and ack timestamps of a message |
+//! | `reply_chain::ReplyChainBmc` | Reply links between messages and thread reply trees |
+//! | `sla::SlaBmc` | Acknowledgment SLAs and compliance reports |
+//! | `reply_deadline::ReplyDeadlineBmc` | Response-by times requested by senders and agreed by recipients |
+//! | `custom_field::CustomFieldBmc` | Per-project typed message fields |
+//! | `mbox_import::MboxImportBmc` | Importing real email from mbox files |
+//! | `capability_routing::CapabilityRoutingBmc` | `capability:<name>` recipient resolution |
+//! | `focus_window::FocusWindowBmc` | Project quiet periods that defer low-priority mail |
+//! | `onboarding::OnboardingBmc` | Project onboarding bundles |
+//! | `context_pack::ContextPackBmc` | Size-bounded agent bootstrapping briefings |
+//! | `reservation_set::ReservationSetBmc` | All-or-nothing multi-path reservations |
+//! | `reservation_watcher::ReservationWatcherBmc` | File reservation release receipts |
+//! | `retention::RetentionBmc` | Per-project message retention and its audited purges |
+//! | `saved_search::SavedSearchBmc` | Named message queries shown as smart folders |
+//! | `search_facet::SearchFacetBmc` | Search result counts by sender, importance, month and field |
+//! | `seed::SeedBmc` | Deterministic demo/benchmark datasets |
+//! | `label::LabelBmc` | Colored per-project labels applied to messages |
+//! | `public_id::PublicIdBmc` | Public ULIDs of projects, agents and messages |
+//! | `outbox_review::OutboxReviewBmc` | Sent mail whose recipients have not read or acked it in time |
+//! | `api_token::ApiTokenBmc` | Hashed per-agent API tokens with scoped permissions |
+//! | `usage::UsageBmc` | Per-agent and per-project storage usage and quotas |
+//! | `inbox_delta::InboxDeltaBmc` | Inbox snapshot diffs for polling agents |
+//! | `team_graph::TeamGraphBmc` | Export and import of agents, capabilities and contacts |
+//! | `global_thread::GlobalThreadBmc` | Globally unique thread ids spanning a product's projects |
+//! | `thread_archive::ThreadArchiveBmc` | Archival of inactive threads and their reactivation |
+//! | `thread_read::ThreadReadBmc` | Per-agent read positions and unread counts in threads |
+//! | `thread_watcher::ThreadWatcherBmc` | Copies of thread mail for watching agents |
+//! | `workflow::WorkflowBmc` | Thread workflows with validated handoffs between agents |
+//! | `webhook::WebhookBmc` | HTTP callbacks for project events with signed, retried deliveries |
+//! | `email_bridge::EmailBridgeBmc` | Per-project opt-in and cursor of the outbound SMTP bridge |
+//! | `parked_message::ParkedMessageBmc` | Held or deferred messages the bridge and Web Push scans come back to |
+//! | `email_ingest::EmailIngestBmc` | Inbound mail posted into threads as messages from aliased agents |
+//! | `export_job::ExportJobBmc` | Queued bulk exports rendered in the background for download |
 //!
 //! ## ModelManager
 //!
@@ -36,35 +87,100 @@ pub mod activity;
 pub mod agent;
 pub mod agent_capabilities;
 pub mod agent_link;
+pub mod api_token;
+pub mod approval;
 pub mod archive_browser;
+pub mod archive_gc;
 pub mod attachment;
+pub mod attachment_blob;
+pub mod attachment_text;
+pub mod broadcast_status;
 pub mod build_slot;
+pub mod capability_routing;
+pub mod ci_status;
+pub mod context_pack;
+pub mod custom_field;
+pub mod db_maintenance;
+pub mod dead_letter;
+pub mod delegation;
+pub mod disk_watch;
+pub mod email_bridge;
+pub mod email_ingest;
 pub mod escalation;
+pub mod event_log;
 pub mod export;
+pub mod export_job;
+pub mod external_tool;
 pub mod file_reservation;
+pub mod focus_window;
+pub mod github_sync;
+pub mod global_thread;
 pub mod identity;
+pub mod inbox_delta;
+pub mod intent_journal;
+pub mod label;
+pub mod listing;
 pub mod macro_def;
+pub mod mbox_import;
 pub mod message;
+pub mod message_catalog;
 pub mod message_recipient;
+pub mod onboarding;
 pub mod orchestration;
+pub mod outbox_review;
 pub mod overseer_message;
+pub mod parked_message;
 pub mod precommit_guard;
 pub mod product;
 pub mod project;
+pub mod project_contact_policy;
+pub mod project_settings;
 pub mod project_sibling_suggestion;
+pub mod project_template;
+pub mod public_id;
+pub mod receipt;
+pub mod reply_chain;
+pub mod reply_deadline;
+pub mod reservation_set;
+pub mod reservation_watcher;
+pub mod retention;
+pub mod saved_search;
+pub mod search_facet;
+pub mod seed;
+pub mod sla;
+pub mod team_graph;
+pub mod thread_archive;
+pub mod thread_read;
+pub mod thread_watcher;
+pub mod ticket;
 pub mod time_travel;
 pub mod tool_metric;
+pub mod triage;
+pub mod ui_preference;
+pub mod usage;
+pub mod web_push;
+pub mod webhook;
+pub mod workflow;
 
 use crate::Result;
 use crate::store::archive_lock::{ArchiveLock, LockGuard};
+use crate::store::attachment_store::{self, AttachmentStore, FsAttachmentStore};
+use crate::store::db_metrics::DbCheckout;
+use crate::store::db_statement;
 use crate::store::repo_cache::RepoCache;
 use crate::store::{self, Db};
+use crate::utils::event_bridge::EventBridge;
+use crate::utils::event_bus::EventBus;
+use crate::utils::plugins::PluginHost;
+use crate::utils::tool_call_log::ToolCallLog;
 use git2::Repository;
 use mouchak_mail_common::config::AppConfig;
+use std::panic::Location;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::Mutex;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Default LRU cache capacity for git repositories.
 /// Each repo uses ~10-50 FDs, so 8 repos = ~400 FDs max.
@@ -88,16 +204,40 @@ pub struct ModelManager {
     /// Handles stale lock cleanup from crashed processes.
     /// NIST Control: AU-9 (Audit Log Protection)
     archive_lock: Arc<ArchiveLock>,
+    /// Recently loaded project reservations for `FileReservationBmc::check_paths`.
+    reservation_cache: Arc<file_reservation::ProjectReservationsCache>,
+    /// Serializes the conflict check and insert of `ReservationSetBmc::acquire`.
+    reservation_lock: Arc<Mutex<()>>,
+    /// Held while a statement runs on `db`, see [`db_statement`].
+    statement_lock: Arc<Mutex<()>>,
+    /// The connection transactions run on, one at a time; opened on first
+    /// use. Postgres transactions take a pooled connection, but still hold
+    /// the lock.
+    tx_db: Arc<Mutex<Option<libsql::Connection>>>,
+    /// Set on the handle a transaction runs its BMC calls with.
+    in_transaction: bool,
     /// Application configuration.
     pub app_config: Arc<AppConfig>,
+    /// Operator WASM plugins run on message events.
+    plugins: Arc<PluginHost>,
+    /// Publishes event log records to NATS or Redis, when configured.
+    event_bridge: Option<EventBridge>,
+    /// Broadcasts event log records to in-process subscribers.
+    event_bus: EventBus,
+    /// Archives tool calls into Git, when enabled.
+    tool_call_log: Option<ToolCallLog>,
+    /// Set while writes are suspended because the disk is nearly full.
+    read_only: Arc<AtomicBool>,
+    /// Where attachment content is written.
+    attachment_store: Arc<dyn AttachmentStore>,
 }
 
 impl ModelManager {
     /// Constructor
     pub async fn new(app_config: Arc<AppConfig>) -> Result<Self> {
-        let db = store::new_db_pool().await?;
-        // Default to "data/archive" for now, similar to Python's default or configurable
-        let repo_root = std::env::current_dir()?.join("data").join("archive");
+        let db = store::new_db_pool(&app_config.database).await?;
+        // "data/archive", or "<dir>/archive" in portable mode
+        let repo_root = mouchak_mail_common::paths::archive_root();
         std::fs::create_dir_all(&repo_root)?;
 
         // Auto-initialize git repository if not exists
@@ -113,30 +253,114 @@ impl ModelManager {
         let archive_lock = Arc::new(ArchiveLock::new(&repo_root));
         Self::cleanup_stale_locks(&archive_lock).await;
 
+        let attachment_store = attachment_store::from_config(
+            &app_config.attachments,
+            mouchak_mail_common::paths::attachments_dir(),
+        )?;
+        let plugins = Arc::new(PluginHost::load(&app_config.plugins));
+        let event_bridge = EventBridge::start(&app_config.event_bridge);
+        let event_bus = EventBus::new(app_config.websocket.event_buffer);
+        let git_lock = Arc::new(Mutex::new(()));
+        let repo_cache = Arc::new(RepoCache::new(cache_size));
+        let tool_call_log = ToolCallLog::start(
+            &app_config.tool_call_log,
+            repo_root.clone(),
+            repo_cache.clone(),
+            git_lock.clone(),
+        );
+
         Ok(ModelManager {
             db,
             repo_root,
-            git_lock: Arc::new(Mutex::new(())),
-            repo_cache: Arc::new(RepoCache::new(cache_size)),
+            git_lock,
+            repo_cache,
             archive_lock,
+            reservation_cache: Arc::default(),
+            reservation_lock: Arc::new(Mutex::new(())),
+            statement_lock: Arc::default(),
+            tx_db: Arc::default(),
+            in_transaction: false,
             app_config,
+            plugins,
+            event_bridge,
+            event_bus,
+            tool_call_log,
+            read_only: Arc::default(),
+            attachment_store,
         })
     }
 
     /// Constructor for testing with custom db connection and paths
     /// This is public so integration tests can use it
-    pub fn new_for_test(db: Db, repo_root: PathBuf, app_config: Arc<AppConfig>) -> Self {
+    ///
+    /// Attachments are always stored on disk, next to `repo_root`.
+    pub fn new_for_test(db: impl Into<Db>, repo_root: PathBuf, app_config: Arc<AppConfig>) -> Self {
+        let db = db.into();
+        let attachments_root = repo_root
+            .parent()
+            .map_or_else(|| repo_root.clone(), |p| p.to_path_buf())
+            .join("attachments");
         let archive_lock = Arc::new(ArchiveLock::new(&repo_root));
+        let event_bridge = EventBridge::start(&app_config.event_bridge);
+        let event_bus = EventBus::new(app_config.websocket.event_buffer);
+        let git_lock = Arc::new(Mutex::new(()));
+        let repo_cache = Arc::new(RepoCache::default());
+        let tool_call_log = ToolCallLog::start(
+            &app_config.tool_call_log,
+            repo_root.clone(),
+            repo_cache.clone(),
+            git_lock.clone(),
+        );
         ModelManager {
             db,
-            repo_root: repo_root.clone(),
-            git_lock: Arc::new(Mutex::new(())),
-            repo_cache: Arc::new(RepoCache::default()),
+            repo_root,
+            git_lock,
+            repo_cache,
             archive_lock,
+            reservation_cache: Arc::default(),
+            reservation_lock: Arc::new(Mutex::new(())),
+            statement_lock: Arc::default(),
+            tx_db: Arc::default(),
+            in_transaction: false,
             app_config,
+            plugins: Arc::new(PluginHost::empty()),
+            event_bridge,
+            event_bus,
+            tool_call_log,
+            read_only: Arc::default(),
+            attachment_store: Arc::new(FsAttachmentStore::new(attachments_root)),
         }
     }
 
+    /// Returns the loaded operator plugins.
+    pub fn plugins(&self) -> &PluginHost {
+        &self.plugins
+    }
+
+    /// Returns the bus event log records are broadcast on.
+    pub fn event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    /// Returns the store attachment content is written to.
+    pub fn attachment_store(&self) -> &Arc<dyn AttachmentStore> {
+        &self.attachment_store
+    }
+
+    /// Returns the tool-call log, if archiving tool calls is enabled.
+    pub fn tool_call_log(&self) -> Option<&ToolCallLog> {
+        self.tool_call_log.as_ref()
+    }
+
+    /// Directory finished export jobs are written to: `exports` next to the
+    /// Git archive, i.e. `<data>/exports`.
+    pub fn exports_dir(&self) -> PathBuf {
+        self.repo_root
+            .parent()
+            .map_or_else(|| self.repo_root.clone(), |p| p.to_path_buf())
+            .join("exports")
+    }
+
     /// Cleanup stale locks from crashed processes on startup.
     /// NIST Control: AU-9 (Audit Log Protection)
     async fn cleanup_stale_locks(archive_lock: &ArchiveLock) {
@@ -201,10 +425,96 @@ impl ModelManager {
         self.archive_lock.acquire(agent, timeout).await
     }
 
-    /// Returns the sqlx db pool reference.
-    /// (Only for the model layer)
-    pub(in crate::model) fn db(&self) -> &Db {
-        &self.db
+    /// Checks out the db connection for the calling BMC, timing the use.
+    /// (Only for the model layer; see [`crate::store::db_metrics`])
+    #[track_caller]
+    pub(in crate::model) fn db(&self) -> DbCheckout<'_> {
+        let slow_query_ms = self.app_config.database.slow_query_ms;
+        DbCheckout::new(
+            &self.db,
+            &self.statement_lock,
+            Location::caller(),
+            (slow_query_ms > 0).then(|| std::time::Duration::from_millis(slow_query_ms)),
+        )
+    }
+
+    /// [`Self::db`] for read-only BMC calls (searches, listings, exports),
+    /// whose statements may run on a Postgres read replica that has caught
+    /// up with this process's writes; see [`crate::store::postgres`].
+    #[track_caller]
+    pub(in crate::model) fn db_read(&self) -> DbCheckout<'_> {
+        self.db().for_reads()
+    }
+
+    /// Runs `f` in a transaction on a connection of its own: it is committed
+    /// if `f` succeeds and rolled back otherwise, also when the future
+    /// running it is dropped.
+    ///
+    /// `f` gets the handle to make its BMC calls with; calls made through
+    /// any other handle are not part of the transaction. Transactions run one
+    /// at a time and hold the write lock, so keep `f` to a short run of writes.
+    pub(in crate::model) async fn transaction<T>(
+        &self,
+        f: impl AsyncFnOnce(&ModelManager) -> Result<T>,
+    ) -> Result<T> {
+        if self.in_transaction {
+            return f(self).await;
+        }
+        self.ensure_writable()?;
+        let mut idle = self.tx_db.lock().await;
+        let shared = match &self.db {
+            Db::Sqlite(conn) => conn,
+            Db::Postgres(pg) => {
+                let pg_tx = pg.begin().await?;
+                // Dropping `pg_tx` while the transaction is open rolls it back
+                let tx = ModelManager {
+                    db: Db::Postgres(pg_tx.db()),
+                    statement_lock: Arc::default(),
+                    in_transaction: true,
+                    ..self.clone()
+                };
+                let result = f(&tx).await;
+                drop(tx);
+                return match pg_tx.end(result.is_ok()).await {
+                    Ok(()) => result,
+                    Err(e) => {
+                        warn!("Failed to end transaction: {}", e);
+                        result.and(Err(e))
+                    }
+                };
+            }
+        };
+        let db = match idle.take() {
+            Some(db) => db,
+            None => store::connect_again(shared).await?,
+        };
+        if let Err(e) = db_statement::retry(|| db.execute("BEGIN IMMEDIATE", ())).await {
+            *idle = Some(db);
+            return Err(e.into());
+        }
+        // Dropping the connection while the transaction is open rolls it back
+        let tx = ModelManager {
+            db: Db::Sqlite(db.clone()),
+            statement_lock: Arc::default(),
+            in_transaction: true,
+            ..self.clone()
+        };
+        let result = f(&tx).await;
+        drop(tx);
+        let ended = match &result {
+            Ok(_) => db.execute("COMMIT", ()).await,
+            Err(_) => db.execute("ROLLBACK", ()).await,
+        };
+        match ended {
+            Ok(_) => {
+                *idle = Some(db);
+                result
+            }
+            Err(e) => {
+                warn!("Failed to end transaction: {}", e);
+                result.and(Err(e.into()))
+            }
+        }
     }
 
     /// Returns the db connection for integration tests
@@ -213,10 +523,53 @@ impl ModelManager {
         &self.db
     }
 
+    /// Whether writes are suspended; see [`disk_watch`].
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// Fails with [`crate::Error::ReadOnly`] while writes are suspended.
+    pub fn ensure_writable(&self) -> Result<()> {
+        if self.is_read_only() {
+            return Err(crate::Error::ReadOnly(
+                "disk space is low; writes resume once space is freed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Suspends or resumes writes.
+    ///
+    /// The shared SQLite connection is switched with `PRAGMA query_only`, so
+    /// a write without an [`ensure_writable`](Self::ensure_writable) check
+    /// still fails before touching the database. On Postgres, whose data
+    /// need not be on the local disk, only the check applies.
+    pub async fn set_read_only(&self, read_only: bool) -> Result<()> {
+        if let Db::Sqlite(conn) = &self.db {
+            let pragma = if read_only {
+                "PRAGMA query_only = ON;"
+            } else {
+                "PRAGMA query_only = OFF;"
+            };
+            conn.execute(pragma, ()).await?;
+        }
+        self.read_only.store(read_only, Ordering::Relaxed);
+        Ok(())
+    }
+
     /// Health check - verify database connectivity
     pub async fn health_check(&self) -> Result<bool> {
-        let stmt = self.db.prepare("SELECT 1").await?;
-        let mut rows = stmt.query(()).await?;
+        let mut rows = self.db.query("SELECT 1", ()).await?;
         Ok(rows.next().await?.is_some())
     }
+
+    /// Database engine the store runs on.
+    pub fn db_backend(&self) -> store::Backend {
+        self.db.backend()
+    }
+
+    /// Most connections the store opens, reported as `mouchak_db_pool_size`.
+    pub fn db_pool_size(&self) -> usize {
+        self.db.pool_size()
+    }
 }
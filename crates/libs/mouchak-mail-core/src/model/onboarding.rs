@@ -0,0 +1,196 @@
+//! Project onboarding bundle generation.
+//!
+//! Emits a ready-to-commit directory that connects a repository to a
+//! Mouchak Mail project in one step:
+//!
+//! | File | Purpose |
+//! |------|---------|
+//! | `.mouchak-mail-project-id` | Project identity marker |
+//! | `.githooks/pre-commit` | Reservation guard (commit) |
+//! | `.githooks/pre-push` | Reservation guard (push) |
+//! | `AGENTS.md` | Server URL and example tool calls for agents |
+//! | `.mcp.json` | MCP client configuration snippet |
+//!
+//! # Example
+//!
+//! ```no_run
+//! use mouchak_mail_core::model::onboarding::OnboardingBmc;
+//! use mouchak_mail_core::model::ModelManager;
+//! use mouchak_mail_core::ctx::Ctx;
+//!
+//! # async fn example(mm: &ModelManager) -> mouchak_mail_core::Result<()> {
+//! let ctx = Ctx::root_ctx();
+//! let bundle = OnboardingBmc::write_bundle(
+//!     &ctx,
+//!     mm,
+//!     "my-project",
+//!     std::path::Path::new("./onboarding"),
+//!     "http://localhost:8765",
+//! )
+//! .await?;
+//! println!("Wrote {} files", bundle.files.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::precommit_guard::{render_precommit_script, render_prepush_script};
+use crate::model::project::ProjectBmc;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Name of the project identity marker file.
+pub const PROJECT_ID_MARKER: &str = ".mouchak-mail-project-id";
+
+/// Directory (relative to the bundle root) holding the guard hooks.
+pub const HOOKS_DIR: &str = ".githooks";
+
+/// Result of writing an onboarding bundle.
+///
+/// # Fields
+///
+/// - `project_slug` - Canonical slug of the project
+/// - `output_dir` - Directory the bundle was written to
+/// - `files` - Bundle-relative paths of every written file
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingBundle {
+    pub project_slug: String,
+    pub output_dir: PathBuf,
+    pub files: Vec<String>,
+}
+
+/// Backend Model Controller for project onboarding bundles.
+pub struct OnboardingBmc;
+
+impl OnboardingBmc {
+    /// Writes the onboarding bundle for a project into `output_dir`.
+    ///
+    /// Existing files with the same names are overwritten so the bundle can
+    /// be regenerated after the server URL changes.
+    ///
+    /// # Errors
+    /// Returns `ProjectNotFound` if the project does not exist, or an I/O
+    /// error if the directory cannot be written.
+    pub async fn write_bundle(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project: &str,
+        output_dir: &Path,
+        server_url: &str,
+    ) -> Result<OnboardingBundle> {
+        let project = ProjectBmc::get_by_identifier(ctx, mm, project).await?;
+        let server_url = server_url.trim_end_matches('/');
+
+        let hooks_dir = output_dir.join(HOOKS_DIR);
+        tokio::fs::create_dir_all(&hooks_dir).await?;
+
+        let mut files = Vec::new();
+
+        tokio::fs::write(output_dir.join(PROJECT_ID_MARKER), &project.slug).await?;
+        files.push(PROJECT_ID_MARKER.to_string());
+
+        let precommit_path = hooks_dir.join("pre-commit");
+        tokio::fs::write(&precommit_path, render_precommit_script()).await?;
+        make_executable(&precommit_path).await?;
+        files.push(format!("{}/pre-commit", HOOKS_DIR));
+
+        let prepush_path = hooks_dir.join("pre-push");
+        tokio::fs::write(&prepush_path, render_prepush_script(server_url)).await?;
+        make_executable(&prepush_path).await?;
+        files.push(format!("{}/pre-push", HOOKS_DIR));
+
+        tokio::fs::write(
+            output_dir.join("AGENTS.md"),
+            render_agents_md(&project.slug, &project.human_key, server_url),
+        )
+        .await?;
+        files.push("AGENTS.md".to_string());
+
+        tokio::fs::write(output_dir.join(".mcp.json"), render_mcp_config(server_url)).await?;
+        files.push(".mcp.json".to_string());
+
+        Ok(OnboardingBundle {
+            project_slug: project.slug,
+            output_dir: output_dir.to_path_buf(),
+            files,
+        })
+    }
+}
+
+/// Render the AGENTS.md onboarding guide for a project.
+pub fn render_agents_md(project_slug: &str, human_key: &str, server_url: &str) -> String {
+    format!(
+        r#"# Agent Coordination
+
+This repository coordinates agents through Mouchak Mail.
+
+- **Server**: `{server_url}`
+- **Project**: `{project_slug}` ({human_key})
+- **MCP endpoint**: `{server_url}/mcp`
+
+## Setup
+
+```sh
+git config core.hooksPath {hooks_dir}
+export MOUCHAK_MAIL_URL={server_url}
+export MOUCHAK_MAIL_PROJECT={project_slug}
+export AGENT_NAME=<your agent name>
+```
+
+The hooks in `{hooks_dir}/` block commits and pushes that touch files
+reserved by another agent. Set `MOUCHAK_MAIL_GUARD_MODE=warn` to only warn.
+
+## Example tool calls
+
+Register yourself before doing any work:
+
+```json
+{{"tool": "register_agent", "arguments": {{"project_slug": "{project_slug}", "name": "BlueLake", "program": "claude-code", "model": "opus", "task_description": "Implement feature X"}}}}
+```
+
+Reserve files before editing them:
+
+```json
+{{"tool": "file_reservation_paths", "arguments": {{"project_slug": "{project_slug}", "agent_name": "BlueLake", "paths": ["src/api/**"], "exclusive": true, "reason": "feature X", "ttl_seconds": 3600}}}}
+```
+
+Send a message and check your inbox:
+
+```json
+{{"tool": "send_message", "arguments": {{"project_slug": "{project_slug}", "sender_name": "BlueLake", "to": "GreenRiver", "subject": "[TASK] Review feature X", "body_md": "Ready for review."}}}}
+{{"tool": "list_inbox", "arguments": {{"project_slug": "{project_slug}", "agent_name": "BlueLake"}}}}
+```
+"#,
+        hooks_dir = HOOKS_DIR,
+    )
+}
+
+/// Render an MCP client configuration snippet pointing at the server.
+pub fn render_mcp_config(server_url: &str) -> String {
+    let config = serde_json::json!({
+        "mcpServers": {
+            "mouchak-mail": {
+                "type": "http",
+                "url": format!("{}/mcp", server_url),
+            }
+        }
+    });
+    // Serializing a json! literal cannot fail
+    serde_json::to_string_pretty(&config).unwrap_or_default() + "\n"
+}
+
+/// Make a file executable (Unix only).
+async fn make_executable(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = tokio::fs::metadata(path).await?.permissions();
+        perms.set_mode(0o755);
+        tokio::fs::set_permissions(path, perms).await?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
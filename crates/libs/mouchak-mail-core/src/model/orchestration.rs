@@ -711,6 +711,7 @@ pub fn parse_thread_state(messages: &[Message]) -> OrchestrationState {
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::types::ProjectId;
 use std::time::Duration;
 
 /// Information about an abandoned task
@@ -754,7 +755,7 @@ impl OrchestrationBmc {
     pub async fn find_abandoned_tasks(
         ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
         stale_threshold: Duration,
     ) -> Result<Vec<AbandonedTask>> {
         use crate::model::message::MessageBmc;
@@ -764,7 +765,7 @@ impl OrchestrationBmc {
             chrono::Utc::now() - chrono::Duration::from_std(stale_threshold).unwrap_or_default();
 
         // Get all threads for the project
-        let threads = MessageBmc::list_threads(ctx, mm, project_id, 100).await?;
+        let threads = MessageBmc::list_threads(ctx, mm, project_id, 100, true).await?;
 
         for thread in threads {
             let messages =
@@ -810,7 +811,7 @@ impl OrchestrationBmc {
     pub async fn find_abandoned_reviews(
         ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
         stale_threshold: Duration,
     ) -> Result<Vec<AbandonedReview>> {
         use crate::model::message::MessageBmc;
@@ -819,7 +820,7 @@ impl OrchestrationBmc {
         let cutoff =
             chrono::Utc::now() - chrono::Duration::from_std(stale_threshold).unwrap_or_default();
 
-        let threads = MessageBmc::list_threads(ctx, mm, project_id, 100).await?;
+        let threads = MessageBmc::list_threads(ctx, mm, project_id, 100, true).await?;
 
         for thread in threads {
             let messages =
@@ -900,6 +901,7 @@ mod tests {
                 .unwrap(),
             attachments: vec![],
             sender_name: "test-sender".to_string(),
+            public_id: String::new(),
         }
     }
 
@@ -0,0 +1,353 @@
+//! Outbox review: sent mail whose recipients have gone quiet.
+//!
+//! [`OutboxReviewBmc::needs_attention`] lists an agent's sent messages with
+//! the recipients that still owe something, so the sender can chase them
+//! before the global escalation sweep (see [`crate::model::escalation`])
+//! does. A recipient is pending when it has:
+//!
+//! - **Unread**: not read the message within the unread threshold
+//! - **Unacked**: read an ack-required message but not acknowledged it
+//!   within the unacked threshold
+//! - **Deadline missed**: not acknowledged by an agreed reply deadline (see
+//!   [`crate::model::reply_deadline`]); agreed deadlines replace both
+//!   thresholds
+//!
+//! Each message comes with suggested follow-ups: remind unread recipients,
+//! ask for a reply deadline from those that read but did not acknowledge,
+//! escalate missed deadlines, and reassign work from recipients that have
+//! not been active since the message was sent.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+
+/// Default time before an unread recipient needs chasing.
+pub const DEFAULT_UNREAD_AFTER_SECS: i64 = 4 * 3600;
+
+/// Default time before a read but unacknowledged recipient needs chasing.
+pub const DEFAULT_UNACKED_AFTER_SECS: i64 = 24 * 3600;
+
+/// How long recipients may stay quiet before a message needs attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReviewThresholds {
+    pub unread_after_secs: i64,
+    pub unacked_after_secs: i64,
+}
+
+impl Default for ReviewThresholds {
+    fn default() -> Self {
+        Self {
+            unread_after_secs: DEFAULT_UNREAD_AFTER_SECS,
+            unacked_after_secs: DEFAULT_UNACKED_AFTER_SECS,
+        }
+    }
+}
+
+/// What a pending recipient still owes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingState {
+    Unread,
+    Unacked,
+    DeadlineMissed,
+}
+
+impl PendingState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unread => "unread",
+            Self::Unacked => "unacked",
+            Self::DeadlineMissed => "deadline_missed",
+        }
+    }
+}
+
+/// A recipient that has not done what the message asked in time.
+///
+/// # Fields
+///
+/// - `deadline_ts` - Agreed reply deadline, if any
+/// - `last_active_ts` - When the recipient last did anything
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingRecipient {
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub state: PendingState,
+    pub read_ts: Option<NaiveDateTime>,
+    pub deadline_ts: Option<NaiveDateTime>,
+    pub last_active_ts: NaiveDateTime,
+}
+
+/// Kind of follow-up suggested to the sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowUpAction {
+    /// Reply in the thread to remind recipients that have not read it
+    Remind,
+    /// Ask recipients that read it for a reply deadline
+    RequestDeadline,
+    /// Raise importance or involve the overseer
+    Escalate,
+    /// Send the work to another agent or a `capability:` address
+    Reassign,
+}
+
+impl FollowUpAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Remind => "remind",
+            Self::RequestDeadline => "request_deadline",
+            Self::Escalate => "escalate",
+            Self::Reassign => "reassign",
+        }
+    }
+}
+
+/// A suggested follow-up and the recipients it concerns.
+#[derive(Debug, Clone, Serialize)]
+pub struct FollowUp {
+    pub action: FollowUpAction,
+    pub recipients: Vec<String>,
+    pub detail: String,
+}
+
+/// A sent message that needs the sender's attention.
+///
+/// # Fields
+///
+/// - `pending` - Recipients still owing a read or ack, by name
+/// - `follow_ups` - Suggested actions, most urgent first
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxReviewItem {
+    pub message_id: i64,
+    pub subject: String,
+    pub thread_id: Option<String>,
+    pub importance: String,
+    pub ack_required: bool,
+    pub created_ts: NaiveDateTime,
+    pub pending: Vec<PendingRecipient>,
+    pub follow_ups: Vec<FollowUp>,
+}
+
+/// Backend Model Controller for the outbox review queue.
+pub struct OutboxReviewBmc;
+
+impl OutboxReviewBmc {
+    /// Lists up to `limit` messages sent by `sender_id` that have pending
+    /// recipients, oldest first.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if a threshold is not positive.
+    pub async fn needs_attention(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        sender_id: AgentId,
+        thresholds: ReviewThresholds,
+        limit: usize,
+    ) -> Result<Vec<OutboxReviewItem>> {
+        if thresholds.unread_after_secs <= 0 || thresholds.unacked_after_secs <= 0 {
+            return Err(Error::InvalidInput(
+                "Outbox review thresholds must be positive".into(),
+            ));
+        }
+        let now = chrono::Utc::now().naive_utc();
+        let cutoff = |secs: i64| {
+            now.checked_sub_signed(Duration::seconds(secs))
+                .unwrap_or(NaiveDateTime::MIN)
+                .format(TS_FORMAT)
+                .to_string()
+        };
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT m.id, m.subject, m.thread_id, m.importance, m.ack_required, m.created_ts,
+                    mr.agent_id, ag.name, mr.read_ts, ag.last_active_ts,
+                    CASE WHEN rd.status != 'requested' THEN rd.deadline_ts END
+                FROM messages AS m
+                JOIN message_recipients AS mr ON mr.message_id = m.id
+                JOIN agents AS ag ON ag.id = mr.agent_id
+                LEFT JOIN reply_deadlines AS rd
+                    ON rd.message_id = m.id AND rd.agent_id = mr.agent_id
+                WHERE m.project_id = ?1 AND m.sender_id = ?2 AND mr.agent_id != m.sender_id
+                  AND mr.ack_ts IS NULL
+                  AND CASE
+                    WHEN rd.status IS NOT NULL AND rd.status != 'requested'
+                        THEN rd.deadline_ts < ?3
+                    WHEN mr.read_ts IS NULL THEN m.created_ts < ?4
+                    ELSE m.ack_required = 1 AND m.created_ts < ?5
+                  END
+                ORDER BY m.created_ts ASC, m.id ASC, ag.name ASC
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                project_id.get(),
+                sender_id.get(),
+                now.format(TS_FORMAT).to_string(),
+                cutoff(thresholds.unread_after_secs),
+                cutoff(thresholds.unacked_after_secs),
+            ))
+            .await?;
+
+        let mut items: Vec<OutboxReviewItem> = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let message_id: i64 = row.get(0)?;
+            let read_ts = parse_timestamp_opt(row.get(8)?, "message_recipients.read_ts");
+            let deadline_ts = parse_timestamp_opt(row.get(10)?, "reply_deadlines.deadline_ts");
+            let last_active_ts: String = row.get(9)?;
+            let pending = PendingRecipient {
+                agent_id: AgentId::new(row.get(6)?),
+                agent_name: row.get(7)?,
+                state: match (deadline_ts, read_ts) {
+                    (Some(_), _) => PendingState::DeadlineMissed,
+                    (None, None) => PendingState::Unread,
+                    (None, Some(_)) => PendingState::Unacked,
+                },
+                read_ts,
+                deadline_ts,
+                last_active_ts: parse_timestamp(&last_active_ts, "agents.last_active_ts"),
+            };
+
+            if items
+                .last()
+                .is_none_or(|item| item.message_id != message_id)
+            {
+                if items.len() >= limit {
+                    break;
+                }
+                let created_ts: String = row.get(5)?;
+                items.push(OutboxReviewItem {
+                    message_id,
+                    subject: row.get(1)?,
+                    thread_id: row.get(2)?,
+                    importance: row.get(3)?,
+                    ack_required: row.get(4)?,
+                    created_ts: parse_timestamp(&created_ts, "messages.created_ts"),
+                    pending: Vec::new(),
+                    follow_ups: Vec::new(),
+                });
+            }
+            if let Some(item) = items.last_mut() {
+                item.pending.push(pending);
+            }
+        }
+
+        for item in &mut items {
+            item.follow_ups = follow_ups(item);
+        }
+        Ok(items)
+    }
+}
+
+/// Suggests follow-ups for a message's pending recipients, most urgent
+/// first.
+fn follow_ups(item: &OutboxReviewItem) -> Vec<FollowUp> {
+    // Quiet since the message arrived: a reminder will likely go unseen too
+    let inactive = |p: &PendingRecipient| p.last_active_ts < item.created_ts;
+    let candidates = [
+        (
+            FollowUpAction::Escalate,
+            names(&item.pending, |p| p.state == PendingState::DeadlineMissed),
+            "missed the agreed reply deadline; raise the importance or bring in the overseer",
+        ),
+        (
+            FollowUpAction::Reassign,
+            names(&item.pending, |p| {
+                p.state == PendingState::Unread && inactive(p)
+            }),
+            "not active since the message was sent; send it to another agent or a capability: address",
+        ),
+        (
+            FollowUpAction::Remind,
+            names(&item.pending, |p| {
+                p.state == PendingState::Unread && !inactive(p)
+            }),
+            "active but has not read it; reply in the thread as a reminder",
+        ),
+        (
+            FollowUpAction::RequestDeadline,
+            names(&item.pending, |p| p.state == PendingState::Unacked),
+            "read it without acknowledging; ask for a reply deadline",
+        ),
+    ];
+    candidates
+        .into_iter()
+        .filter(|(_, recipients, _)| !recipients.is_empty())
+        .map(|(action, recipients, detail)| FollowUp {
+            action,
+            detail: format!("{}: {}", recipients.join(", "), detail),
+            recipients,
+        })
+        .collect()
+}
+
+/// Names of the pending recipients matching `pred`.
+fn names(pending: &[PendingRecipient], pred: impl Fn(&PendingRecipient) -> bool) -> Vec<String> {
+    pending
+        .iter()
+        .filter(|p| pred(p))
+        .map(|p| p.agent_name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, TS_FORMAT).unwrap()
+    }
+
+    fn recipient(name: &str, state: PendingState, last_active: &str) -> PendingRecipient {
+        PendingRecipient {
+            agent_id: AgentId::new(1),
+            agent_name: name.to_string(),
+            state,
+            read_ts: None,
+            deadline_ts: None,
+            last_active_ts: ts(last_active),
+        }
+    }
+
+    #[test]
+    fn test_follow_ups_by_state() {
+        let item = OutboxReviewItem {
+            message_id: 1,
+            subject: "Review the migration".to_string(),
+            thread_id: None,
+            importance: "normal".to_string(),
+            ack_required: true,
+            created_ts: ts("2025-03-01 12:00:00"),
+            pending: vec![
+                recipient("alice", PendingState::Unread, "2025-03-01 13:00:00"),
+                recipient("bob", PendingState::Unread, "2025-02-27 09:00:00"),
+                recipient("carol", PendingState::Unacked, "2025-03-01 13:00:00"),
+                recipient("dave", PendingState::DeadlineMissed, "2025-03-01 13:00:00"),
+            ],
+            follow_ups: Vec::new(),
+        };
+
+        let actions: Vec<(FollowUpAction, Vec<String>)> = follow_ups(&item)
+            .into_iter()
+            .map(|f| (f.action, f.recipients))
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                (FollowUpAction::Escalate, vec!["dave".to_string()]),
+                (FollowUpAction::Reassign, vec!["bob".to_string()]),
+                (FollowUpAction::Remind, vec!["alice".to_string()]),
+                (FollowUpAction::RequestDeadline, vec!["carol".to_string()]),
+            ]
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::store::db_statement::Row;
+use crate::types::ProjectId;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
@@ -119,7 +121,7 @@ impl OverseerMessageBmc {
     pub async fn list_unread(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<Vec<OverseerMessage>> {
         let db = mm.db();
         let stmt = db
@@ -133,7 +135,7 @@ impl OverseerMessageBmc {
             )
             .await?;
 
-        let mut rows = stmt.query([project_id]).await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
         let mut messages = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -142,7 +144,86 @@ impl OverseerMessageBmc {
         Ok(messages)
     }
 
-    fn from_row(row: libsql::Row) -> Result<OverseerMessage> {
+    /// Lists the overseer inbox for a project.
+    ///
+    /// The overseer inbox is the single place where escalations (overdue
+    /// acks), guard violations, and agent-raised issues land for a human.
+    ///
+    /// # Arguments
+    /// * `_ctx` - Request context
+    /// * `mm` - ModelManager
+    /// * `project_id` - Project database ID
+    /// * `include_read` - Include messages the overseer already read
+    /// * `limit` - Maximum number of messages to return
+    ///
+    /// # Returns
+    /// Vector of messages (newest first)
+    pub async fn list_for_project(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        include_read: bool,
+        limit: i64,
+    ) -> Result<Vec<OverseerMessage>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT id, project_id, sender_id, subject, body_md, importance, created_ts, read_ts
+            FROM overseer_messages
+            WHERE project_id = ? AND (? OR read_ts IS NULL)
+            ORDER BY created_ts DESC, id DESC
+            LIMIT ?
+            "#,
+            )
+            .await?;
+
+        let mut rows = stmt.query((project_id.get(), include_read, limit)).await?;
+        let mut messages = Vec::new();
+
+        while let Some(row) = rows.next().await? {
+            messages.push(Self::from_row(row)?);
+        }
+        Ok(messages)
+    }
+
+    /// Counts the unread overseer messages of a project.
+    pub async fn count_unread(_ctx: &Ctx, mm: &ModelManager, project_id: ProjectId) -> Result<i64> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "SELECT COUNT(*) FROM overseer_messages WHERE project_id = ? AND read_ts IS NULL",
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Marks an overseer message as read.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if no message with this ID exists.
+    pub async fn mark_read(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let db = mm.db();
+        let now_str = chrono::Utc::now()
+            .naive_utc()
+            .format(crate::utils::TS_FORMAT)
+            .to_string();
+
+        let stmt = db
+            .prepare("UPDATE overseer_messages SET read_ts = COALESCE(read_ts, ?) WHERE id = ?")
+            .await?;
+        let affected = stmt.execute((now_str, id)).await?;
+        if affected == 0 {
+            return Err(crate::Error::NotFound);
+        }
+        Ok(())
+    }
+
+    fn from_row(row: Row) -> Result<OverseerMessage> {
         let created_ts_str: String = row.get(6).unwrap_or_default();
         let read_ts_str: Option<String> = row.get(7).unwrap_or_default();
 
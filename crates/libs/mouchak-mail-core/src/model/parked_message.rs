@@ -0,0 +1,105 @@
+//! Messages a cursor scan has to come back to.
+//!
+//! The email bridge and Web Push scan new messages by id. A message held for
+//! approval or deferred by a focus window has reached nobody yet, so a scan
+//! parks it under the scan's name instead of passing it on and moves its
+//! cursor past it. Later scans pick the message up again once it has been
+//! delivered, and forget it if it was rejected.
+//!
+//! A scan selects its parked messages with [`released_sql`], reads
+//! [`state_columns`] for every message and lets [`ParkedMessageBmc::settle`]
+//! decide what happens to it.
+
+use crate::Result;
+use crate::model::ModelManager;
+
+/// SQL condition: message `m` waits for an approval decision or for the end
+/// of a focus window.
+const WAITING_SQL: &str = r#"(
+    EXISTS (
+        SELECT 1 FROM pending_approvals AS pa
+        WHERE pa.message_id = m.id AND pa.status = 'pending'
+    )
+    OR EXISTS (
+        SELECT 1 FROM deferred_deliveries AS dd
+        WHERE dd.message_id = m.id AND dd.delivered_ts IS NULL
+    )
+)"#;
+
+/// SQL condition: message `m` was rejected and will never be delivered.
+const REJECTED_SQL: &str = r#"EXISTS (
+    SELECT 1 FROM pending_approvals AS pr
+    WHERE pr.message_id = m.id AND pr.status = 'rejected'
+)"#;
+
+/// Columns of message `m` read by [`ParkedMessageBmc::settle`]: whether the
+/// scan named by the bound parameter parked it, whether it still waits, and
+/// whether it was rejected.
+pub(crate) fn state_columns() -> String {
+    format!(
+        "m.id IN (SELECT message_id FROM parked_messages WHERE consumer = ?), {}, {}",
+        WAITING_SQL, REJECTED_SQL
+    )
+}
+
+/// SQL condition: message `m` was parked by the scan named by the bound
+/// parameter and no longer waits.
+pub(crate) fn released_sql() -> String {
+    format!(
+        "(m.id IN (SELECT message_id FROM parked_messages WHERE consumer = ?) AND NOT {})",
+        WAITING_SQL
+    )
+}
+
+/// Where a scanned message stands, as read from [`state_columns`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScanState {
+    pub parked: bool,
+    pub waiting: bool,
+    pub rejected: bool,
+}
+
+pub struct ParkedMessageBmc;
+
+impl ParkedMessageBmc {
+    /// Parks, forgets or releases a message met by the `consumer` scan.
+    ///
+    /// # Returns
+    /// Whether the scan passes the message on now.
+    pub(crate) async fn settle(
+        mm: &ModelManager,
+        consumer: &str,
+        message_id: i64,
+        state: ScanState,
+    ) -> Result<bool> {
+        let sql = if state.waiting {
+            "INSERT OR IGNORE INTO parked_messages (consumer, message_id) VALUES (?, ?)"
+        } else if state.rejected && state.parked {
+            "DELETE FROM parked_messages WHERE consumer = ? AND message_id = ?"
+        } else if state.parked {
+            "UPDATE parked_messages SET released = 1 WHERE consumer = ? AND message_id = ?"
+        } else {
+            return Ok(!state.rejected);
+        };
+        let stmt = mm.db().prepare(sql).await?;
+        stmt.execute((consumer, message_id)).await?;
+        Ok(!state.waiting && !state.rejected)
+    }
+
+    /// Forgets released messages up to `last_message_id` once the
+    /// `consumer` scan has handled them.
+    pub(crate) async fn clear_released(
+        mm: &ModelManager,
+        consumer: &str,
+        last_message_id: i64,
+    ) -> Result<()> {
+        let stmt = mm
+            .db()
+            .prepare(
+                "DELETE FROM parked_messages WHERE consumer = ? AND released = 1 AND message_id <= ?",
+            )
+            .await?;
+        stmt.execute((consumer, last_message_id)).await?;
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::model::agent::AgentBmc;
 use crate::model::file_reservation::FileReservationBmc;
+use crate::model::overseer_message::{OverseerMessageBmc, OverseerMessageForCreate};
 use crate::model::project::ProjectBmc;
 use crate::{Result, ctx::Ctx, model::ModelManager};
 use chrono::Utc;
@@ -181,6 +182,179 @@ pub fn get_hooks_dir(repo_path: &Path) -> PathBuf {
 /// Pre-commit guard for file reservation checks
 pub struct PrecommitGuardBmc;
 
+/// Render the pre-commit hook script content.
+///
+/// The script:
+/// 1. Skips unless WORKTREES_ENABLED/GIT_IDENTITY_ENABLED is set, or when bypassed
+/// 2. Lists the project's file reservations from the Mouchak Mail server
+/// 3. Blocks (enforce) or warns (warn/advisory) when staged files conflict
+pub fn render_precommit_script() -> String {
+    r#"#!/bin/sh
+# Mouchak Mail - Pre-commit Guard
+# Checks file reservations before allowing commit
+
+# Gate: Check if worktrees are enabled
+check_gate() {
+    # Check WORKTREES_ENABLED or GIT_IDENTITY_ENABLED
+    case "${WORKTREES_ENABLED:-0}${GIT_IDENTITY_ENABLED:-0}" in
+        *1*|*true*|*TRUE*|*yes*|*YES*|*y*|*Y*|*t*|*T*)
+            return 0
+            ;;
+    esac
+    return 1
+}
+
+# Gate: Skip if not enabled
+if ! check_gate; then
+    exit 0
+fi
+
+# Bypass mode: Skip all checks
+case "${MOUCHAK_MAIL_BYPASS:-0}" in
+    1|true|TRUE|yes|YES|y|Y|t|T)
+        echo "[pre-commit] bypass enabled via MOUCHAK_MAIL_BYPASS=1" >&2
+        exit 0
+        ;;
+esac
+
+# Check if AGENT_NAME is set
+if [ -z "$AGENT_NAME" ]; then
+    echo "Warning: AGENT_NAME not set, skipping reservation check" >&2
+    exit 0
+fi
+
+# Get server URL
+SERVER_URL="${MOUCHAK_MAIL_URL:-${API_URL:-http://localhost:8765}}"
+
+# Get project slug from env or derive from git root
+if [ -z "$MOUCHAK_MAIL_PROJECT" ]; then
+    GIT_ROOT=$(git rev-parse --show-toplevel 2>/dev/null)
+    if [ -z "$GIT_ROOT" ]; then
+        echo "Warning: Not in a git repository, skipping reservation check" >&2
+        exit 0
+    fi
+    # URL-safe slug: replace / with - and remove leading -
+    PROJECT_SLUG=$(echo "$GIT_ROOT" | sed 's|^/||; s|/|-|g')
+else
+    PROJECT_SLUG="$MOUCHAK_MAIL_PROJECT"
+fi
+
+# Get guard mode (enforce, warn, or advisory)
+GUARD_MODE="${MOUCHAK_MAIL_GUARD_MODE:-enforce}"
+
+# Get staged files
+STAGED_FILES=$(git diff --cached --name-only 2>/dev/null)
+if [ -z "$STAGED_FILES" ]; then
+    exit 0  # No staged files, nothing to check
+fi
+
+# Check if curl is available
+if ! command -v curl >/dev/null 2>&1; then
+    echo "Warning: curl not found, skipping reservation check" >&2
+    exit 0
+fi
+
+# Call API to list file reservations
+RESPONSE=$(curl -s -X POST "${SERVER_URL}/api/file_reservations/list" \
+    -H "Content-Type: application/json" \
+    -d "{\"project_slug\": \"$PROJECT_SLUG\"}" 2>/dev/null)
+
+# Check if API call succeeded
+if [ $? -ne 0 ] || [ -z "$RESPONSE" ]; then
+    echo "Warning: Could not reach Mouchak Mail server at $SERVER_URL" >&2
+    # In warn mode, continue; in enforce mode, block on API failure
+    case "$GUARD_MODE" in
+        warn|advisory)
+            exit 0
+            ;;
+        *)
+            echo "Error: Cannot verify file reservations (server unreachable)" >&2
+            exit 1
+            ;;
+    esac
+fi
+
+# Check for API error response
+if echo "$RESPONSE" | grep -q '"error"'; then
+    # API returned an error - likely project not found, which is OK
+    exit 0
+fi
+
+# Parse reservations and check for conflicts
+# Extract path_pattern:agent_name pairs from JSON (avoiding jq dependency)
+RESERVATIONS=$(echo "$RESPONSE" | grep -oE '"path_pattern":"[^"]*"|"agent_name":"[^"]*"' | \
+    sed 's/"path_pattern":"//; s/"agent_name":"//; s/"$//' | \
+    paste -d: - - 2>/dev/null || echo "")
+
+# Check each staged file against reservations
+for FILE in $STAGED_FILES; do
+    echo "$RESERVATIONS" | while IFS=: read -r PATTERN OWNER; do
+        [ -z "$PATTERN" ] && continue
+        [ "$OWNER" = "$AGENT_NAME" ] && continue
+        
+        MATCH=0
+        # Exact match
+        [ "$FILE" = "$PATTERN" ] && MATCH=1
+        # Glob pattern match
+        case "$FILE" in $PATTERN) MATCH=1 ;; esac
+        # Directory wildcard (**) match
+        case "$PATTERN" in
+            *"/**")
+                DIR_PREFIX="${PATTERN%/**}"
+                case "$FILE" in "$DIR_PREFIX"/*) MATCH=1 ;; esac
+                ;;
+        esac
+        
+        [ "$MATCH" -eq 1 ] && echo "CONFLICT:$FILE:$OWNER:$PATTERN"
+    done
+done > /tmp/precommit_conflicts_$$
+
+# Read conflicts
+if [ -s /tmp/precommit_conflicts_$$ ]; then
+    CONFLICTS=$(cat /tmp/precommit_conflicts_$$)
+    rm -f /tmp/precommit_conflicts_$$
+else
+    rm -f /tmp/precommit_conflicts_$$
+    exit 0  # No conflicts
+fi
+
+# Handle conflicts based on mode
+if [ -n "$CONFLICTS" ]; then
+    echo "" >&2
+    echo "========================================" >&2
+    echo "FILE RESERVATION CONFLICTS DETECTED" >&2
+    echo "========================================" >&2
+    echo "" >&2
+    echo "Agent: $AGENT_NAME" >&2
+    echo "The following files are reserved by other agents:" >&2
+    echo "" >&2
+    echo "$CONFLICTS" | while IFS=: read -r _ FILE OWNER PATTERN; do
+        echo "  - $FILE (reserved by: $OWNER, pattern: $PATTERN)" >&2
+    done
+    echo "" >&2
+    
+    case "$GUARD_MODE" in
+        warn|advisory)
+            echo "Mode: $GUARD_MODE - Allowing commit despite conflicts" >&2
+            echo "========================================" >&2
+            exit 0
+            ;;
+        *)
+            echo "Mode: enforce - Blocking commit" >&2
+            echo "" >&2
+            echo "To bypass: export MOUCHAK_MAIL_BYPASS=1" >&2
+            echo "Or coordinate with the reserving agent" >&2
+            echo "========================================" >&2
+            exit 1
+            ;;
+    esac
+fi
+
+exit 0
+"#
+    .to_string()
+}
+
 /// Render the pre-push hook script content.
 ///
 /// The script:
@@ -436,6 +610,33 @@ impl PrecommitGuardBmc {
                     violations_count = violations.len(),
                     "File reservation conflicts detected (enforce mode)"
                 );
+
+                // Surface blocked commits in the overseer inbox
+                let body_md = format!(
+                    "Agent '{}' attempted to commit reserved files:\n\n{}",
+                    agent_name,
+                    violations
+                        .iter()
+                        .map(|v| format!("- {}", v))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                );
+                if let Err(e) = OverseerMessageBmc::create(
+                    ctx,
+                    mm,
+                    OverseerMessageForCreate {
+                        project_id: project.id.get(),
+                        sender_id: agent.id.get(),
+                        subject: format!("[GUARD VIOLATION] Commit blocked for {}", agent_name),
+                        body_md,
+                        importance: "high".to_string(),
+                    },
+                )
+                .await
+                {
+                    warn!(error = %e, "Failed to record guard violation in overseer inbox");
+                }
+
                 Ok(Some(violations))
             }
             GuardMode::Warn => {
@@ -474,169 +675,7 @@ impl PrecommitGuardBmc {
 
         // Install pre-commit hook
         let precommit_path = hooks_dir.join("pre-commit");
-        let precommit_script = r#"#!/bin/sh
-# Mouchak Mail - Pre-commit Guard
-# Checks file reservations before allowing commit
-
-# Gate: Check if worktrees are enabled
-check_gate() {
-    # Check WORKTREES_ENABLED or GIT_IDENTITY_ENABLED
-    case "${WORKTREES_ENABLED:-0}${GIT_IDENTITY_ENABLED:-0}" in
-        *1*|*true*|*TRUE*|*yes*|*YES*|*y*|*Y*|*t*|*T*)
-            return 0
-            ;;
-    esac
-    return 1
-}
-
-# Gate: Skip if not enabled
-if ! check_gate; then
-    exit 0
-fi
-
-# Bypass mode: Skip all checks
-case "${MOUCHAK_MAIL_BYPASS:-0}" in
-    1|true|TRUE|yes|YES|y|Y|t|T)
-        echo "[pre-commit] bypass enabled via MOUCHAK_MAIL_BYPASS=1" >&2
-        exit 0
-        ;;
-esac
-
-# Check if AGENT_NAME is set
-if [ -z "$AGENT_NAME" ]; then
-    echo "Warning: AGENT_NAME not set, skipping reservation check" >&2
-    exit 0
-fi
-
-# Get server URL
-SERVER_URL="${MOUCHAK_MAIL_URL:-${API_URL:-http://localhost:8765}}"
-
-# Get project slug from env or derive from git root
-if [ -z "$MOUCHAK_MAIL_PROJECT" ]; then
-    GIT_ROOT=$(git rev-parse --show-toplevel 2>/dev/null)
-    if [ -z "$GIT_ROOT" ]; then
-        echo "Warning: Not in a git repository, skipping reservation check" >&2
-        exit 0
-    fi
-    # URL-safe slug: replace / with - and remove leading -
-    PROJECT_SLUG=$(echo "$GIT_ROOT" | sed 's|^/||; s|/|-|g')
-else
-    PROJECT_SLUG="$MOUCHAK_MAIL_PROJECT"
-fi
-
-# Get guard mode (enforce, warn, or advisory)
-GUARD_MODE="${MOUCHAK_MAIL_GUARD_MODE:-enforce}"
-
-# Get staged files
-STAGED_FILES=$(git diff --cached --name-only 2>/dev/null)
-if [ -z "$STAGED_FILES" ]; then
-    exit 0  # No staged files, nothing to check
-fi
-
-# Check if curl is available
-if ! command -v curl >/dev/null 2>&1; then
-    echo "Warning: curl not found, skipping reservation check" >&2
-    exit 0
-fi
-
-# Call API to list file reservations
-RESPONSE=$(curl -s -X POST "${SERVER_URL}/api/file_reservations/list" \
-    -H "Content-Type: application/json" \
-    -d "{\"project_slug\": \"$PROJECT_SLUG\"}" 2>/dev/null)
-
-# Check if API call succeeded
-if [ $? -ne 0 ] || [ -z "$RESPONSE" ]; then
-    echo "Warning: Could not reach Mouchak Mail server at $SERVER_URL" >&2
-    # In warn mode, continue; in enforce mode, block on API failure
-    case "$GUARD_MODE" in
-        warn|advisory)
-            exit 0
-            ;;
-        *)
-            echo "Error: Cannot verify file reservations (server unreachable)" >&2
-            exit 1
-            ;;
-    esac
-fi
-
-# Check for API error response
-if echo "$RESPONSE" | grep -q '"error"'; then
-    # API returned an error - likely project not found, which is OK
-    exit 0
-fi
-
-# Parse reservations and check for conflicts
-# Extract path_pattern:agent_name pairs from JSON (avoiding jq dependency)
-RESERVATIONS=$(echo "$RESPONSE" | grep -oE '"path_pattern":"[^"]*"|"agent_name":"[^"]*"' | \
-    sed 's/"path_pattern":"//; s/"agent_name":"//; s/"$//' | \
-    paste -d: - - 2>/dev/null || echo "")
-
-# Check each staged file against reservations
-for FILE in $STAGED_FILES; do
-    echo "$RESERVATIONS" | while IFS=: read -r PATTERN OWNER; do
-        [ -z "$PATTERN" ] && continue
-        [ "$OWNER" = "$AGENT_NAME" ] && continue
-        
-        MATCH=0
-        # Exact match
-        [ "$FILE" = "$PATTERN" ] && MATCH=1
-        # Glob pattern match
-        case "$FILE" in $PATTERN) MATCH=1 ;; esac
-        # Directory wildcard (**) match
-        case "$PATTERN" in
-            *"/**")
-                DIR_PREFIX="${PATTERN%/**}"
-                case "$FILE" in "$DIR_PREFIX"/*) MATCH=1 ;; esac
-                ;;
-        esac
-        
-        [ "$MATCH" -eq 1 ] && echo "CONFLICT:$FILE:$OWNER:$PATTERN"
-    done
-done > /tmp/precommit_conflicts_$$
-
-# Read conflicts
-if [ -s /tmp/precommit_conflicts_$$ ]; then
-    CONFLICTS=$(cat /tmp/precommit_conflicts_$$)
-    rm -f /tmp/precommit_conflicts_$$
-else
-    rm -f /tmp/precommit_conflicts_$$
-    exit 0  # No conflicts
-fi
-
-# Handle conflicts based on mode
-if [ -n "$CONFLICTS" ]; then
-    echo "" >&2
-    echo "========================================" >&2
-    echo "FILE RESERVATION CONFLICTS DETECTED" >&2
-    echo "========================================" >&2
-    echo "" >&2
-    echo "Agent: $AGENT_NAME" >&2
-    echo "The following files are reserved by other agents:" >&2
-    echo "" >&2
-    echo "$CONFLICTS" | while IFS=: read -r _ FILE OWNER PATTERN; do
-        echo "  - $FILE (reserved by: $OWNER, pattern: $PATTERN)" >&2
-    done
-    echo "" >&2
-    
-    case "$GUARD_MODE" in
-        warn|advisory)
-            echo "Mode: $GUARD_MODE - Allowing commit despite conflicts" >&2
-            echo "========================================" >&2
-            exit 0
-            ;;
-        *)
-            echo "Mode: enforce - Blocking commit" >&2
-            echo "" >&2
-            echo "To bypass: export MOUCHAK_MAIL_BYPASS=1" >&2
-            echo "Or coordinate with the reserving agent" >&2
-            echo "========================================" >&2
-            exit 1
-            ;;
-    esac
-fi
-
-exit 0
-"#;
+        let precommit_script = render_precommit_script();
 
         tokio::fs::write(&precommit_path, precommit_script).await?;
         Self::make_executable(&precommit_path).await?;
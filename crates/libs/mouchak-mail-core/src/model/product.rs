@@ -1,6 +1,7 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::types::ProjectId;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
@@ -170,13 +171,13 @@ impl ProductBmc {
         _ctx: &Ctx,
         mm: &ModelManager,
         product_id: i64,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<i64> {
         let db = mm.db();
         let stmt = db.prepare(
             "INSERT OR IGNORE INTO product_project_links (product_id, project_id) VALUES (?, ?) RETURNING id"
         ).await?;
-        let mut rows = stmt.query((product_id, project_id)).await?;
+        let mut rows = stmt.query((product_id, project_id.get())).await?;
 
         let id =
             if let Some(row) = rows.next().await? {
@@ -186,7 +187,7 @@ impl ProductBmc {
                 let stmt = db.prepare(
                 "SELECT id FROM product_project_links WHERE product_id = ? AND project_id = ?"
             ).await?;
-                let mut rows = stmt.query((product_id, project_id)).await?;
+                let mut rows = stmt.query((product_id, project_id.get())).await?;
                 if let Some(row) = rows.next().await? {
                     row.get::<i64>(0)?
                 } else {
@@ -202,13 +203,13 @@ impl ProductBmc {
         _ctx: &Ctx,
         mm: &ModelManager,
         product_id: i64,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<bool> {
         let db = mm.db();
         let stmt = db
             .prepare("DELETE FROM product_project_links WHERE product_id = ? AND project_id = ?")
             .await?;
-        let result = stmt.execute((product_id, project_id)).await?;
+        let result = stmt.execute((product_id, project_id.get())).await?;
 
         Ok(result > 0)
     }
@@ -237,7 +238,7 @@ impl ProductBmc {
     pub async fn list_for_project(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<Vec<Product>> {
         let db = mm.db();
         // Join products and product_project_links
@@ -249,7 +250,7 @@ impl ProductBmc {
              WHERE l.project_id = ?",
             )
             .await?;
-        let mut rows = stmt.query([project_id]).await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
 
         let mut products = Vec::new();
         while let Some(row) = rows.next().await? {
@@ -39,6 +39,8 @@
 
 use crate::Result;
 use crate::model::ModelManager;
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::model::listing::{ChangeStamp, ListPage, ListQuery, ListSort};
 use crate::store::git_store;
 use crate::types::ProjectId;
 use crate::utils::mistake_detection::suggest_similar;
@@ -46,6 +48,28 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+/// Per-project tables, in deletion order; their rows go before the agents
+/// and the project they refer to.
+const PROJECT_TABLES: &[&str] = &[
+    "project_focus_windows",
+    "web_push_subscriptions",
+    "thread_reads",
+    "reservation_sets",
+    "project_contact_rules",
+    "project_slas",
+    "thread_workflows",
+    "workflow_transitions",
+    "workflows",
+    "project_custom_fields",
+    "archived_threads",
+    "thread_watchers",
+    "delegation_grants",
+    "approval_rules",
+    "global_threads",
+    "saved_searches",
+    "labels",
+];
+
 /// A project workspace for AI agents.
 ///
 /// Projects provide isolation and organization for multi-agent collaboration.
@@ -57,6 +81,7 @@ use std::path::Path;
 /// - `slug` - URL-safe identifier (e.g., "my-project")
 /// - `human_key` - Human-readable name (e.g., "My Project")
 /// - `created_at` - Timestamp of project creation
+/// - `public_id` - Stable ULID, safe to share with external systems
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     /// Database primary key (strongly typed).
@@ -67,6 +92,9 @@ pub struct Project {
     pub human_key: String,
     /// Project creation timestamp.
     pub created_at: NaiveDateTime,
+    /// Stable ULID for referencing the project outside this database.
+    #[serde(default)]
+    pub public_id: String,
 }
 
 /// Backend Model Controller for Project operations.
@@ -129,7 +157,20 @@ impl ProjectBmc {
         Self::ensure_archive(mm, slug).await?;
 
         // Register built-in macros for this project
-        let _ = super::macro_def::MacroDefBmc::ensure_builtin_macros(ctx, mm, id).await;
+        let _ =
+            super::macro_def::MacroDefBmc::ensure_builtin_macros(ctx, mm, ProjectId::new(id)).await;
+
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                "project.created",
+                Some(id),
+                None,
+                serde_json::json!({ "slug": slug, "human_key": human_key }),
+            ),
+        )
+        .await;
 
         Ok(ProjectId::new(id))
     }
@@ -146,7 +187,11 @@ impl ProjectBmc {
         let db = mm.db();
         let stmt = db
             .prepare(
-                "SELECT id, slug, human_key, created_at FROM projects ORDER BY created_at DESC",
+                r#"
+                SELECT id, slug, human_key, created_at,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'project' AND x.entity_id = projects.id), '')
+                FROM projects ORDER BY created_at DESC
+                "#,
             )
             .await?;
         let mut rows = stmt.query(()).await?;
@@ -161,12 +206,128 @@ impl ProjectBmc {
                 id: ProjectId::new(row.get(0)?),
                 slug: row.get(1)?,
                 human_key: row.get(2)?,
+                public_id: row.get(4)?,
                 created_at,
             });
         }
         Ok(projects)
     }
 
+    /// Lists a page of projects, filtered and sorted by `query`.
+    ///
+    /// Sorts newest first unless `query.sort` says otherwise. Filters match a
+    /// project when one of its agents matches them all.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a bad limit, cursor or `active_within`.
+    pub async fn list_page(
+        _ctx: &crate::Ctx,
+        mm: &ModelManager,
+        query: &ListQuery,
+    ) -> Result<ListPage<Project>> {
+        let limit = query.sql_limit()?;
+        let offset = query.sql_offset()?;
+        let active_since = query.active_since()?;
+        let order = match query.sort.unwrap_or(ListSort::Created) {
+            ListSort::Name => "p.slug ASC, p.id ASC",
+            ListSort::Created => "p.created_at DESC, p.id DESC",
+            // Projects without agents have no activity and sort last
+            ListSort::LastActive => "act.last_active_ts DESC NULLS LAST, p.id DESC",
+        };
+        let filter = r#"
+            FROM projects AS p
+            LEFT JOIN (
+                SELECT project_id, MAX(last_active_ts) AS last_active_ts
+                FROM agents GROUP BY project_id
+            ) AS act ON act.project_id = p.id
+            WHERE (?1 IS NULL AND ?2 IS NULL AND ?3 IS NULL)
+               OR EXISTS (
+                    SELECT 1 FROM agents AS a
+                    WHERE a.project_id = p.id
+                      AND (?1 IS NULL OR a.last_active_ts >= ?1)
+                      AND (?2 IS NULL OR LOWER(a.program) = LOWER(?2))
+                      AND (?3 IS NULL OR LOWER(a.model) = LOWER(?3))
+               )
+            "#;
+        let params = || {
+            (
+                active_since.clone(),
+                query.program.clone(),
+                query.model.clone(),
+            )
+        };
+
+        let db = mm.db_read();
+        let stmt = db
+            .prepare(&format!(
+                r#"
+                SELECT p.id, p.slug, p.human_key, p.created_at,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'project' AND x.entity_id = p.id), '')
+                {filter}
+                ORDER BY {order}
+                LIMIT {limit} OFFSET {offset}
+                "#
+            ))
+            .await?;
+        let mut rows = stmt.query(params()).await?;
+
+        let mut projects = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_at_str: String = row.get(3)?;
+            projects.push(Project {
+                id: ProjectId::new(row.get(0)?),
+                slug: row.get(1)?,
+                human_key: row.get(2)?,
+                public_id: row.get(4)?,
+                created_at: NaiveDateTime::parse_from_str(&created_at_str, "%Y-%m-%d %H:%M:%S")
+                    .unwrap_or_default(),
+            });
+        }
+
+        let stmt = db.prepare(&format!("SELECT COUNT(*) {filter}")).await?;
+        let mut rows = stmt.query(params()).await?;
+        let total: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+
+        Ok(query.page(projects, offset, total))
+    }
+
+    /// Change stamp of the project listing `query` selects.
+    ///
+    /// Agents count too when the listing sorts or filters by them. `None`
+    /// for `active_within`, whose result moves with the clock alone.
+    pub async fn list_stamp(
+        _ctx: &crate::Ctx,
+        mm: &ModelManager,
+        query: &ListQuery,
+    ) -> Result<Option<ChangeStamp>> {
+        if query.active_within.is_some() {
+            return Ok(None);
+        }
+        let by_agents = query.sort == Some(ListSort::LastActive)
+            || query.program.is_some()
+            || query.model.is_some();
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT COUNT(*), MAX(id), MAX(created_at) FROM projects
+                UNION ALL
+                SELECT COUNT(*), MAX(id), MAX(last_active_ts) FROM agents WHERE ?1
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([i64::from(by_agents)]).await?;
+        let mut stamp = ChangeStamp::default();
+        while let Some(row) = rows.next().await? {
+            stamp = stamp.merge(ChangeStamp::from_row(&row, "projects.created_at")?);
+        }
+        Ok(Some(stamp))
+    }
+
     /// Retrieves a project by its slug (URL-safe identifier).
     ///
     /// # Arguments
@@ -183,7 +344,13 @@ impl ProjectBmc {
         let db = mm.db();
         // Note: We are mapping manually because libsql doesn't have FromRow like sqlx yet
         let stmt = db
-            .prepare("SELECT id, slug, human_key, created_at FROM projects WHERE slug = ?")
+            .prepare(
+                r#"
+                SELECT id, slug, human_key, created_at,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'project' AND x.entity_id = projects.id), '')
+                FROM projects WHERE slug = ?
+                "#,
+            )
             .await?;
         let mut rows = stmt.query([slug]).await?;
 
@@ -198,6 +365,7 @@ impl ProjectBmc {
                 id: ProjectId::new(row.get(0)?),
                 slug: row.get(1)?,
                 human_key: row.get(2)?,
+                public_id: row.get(4)?,
                 created_at,
             })
         } else {
@@ -239,7 +407,13 @@ impl ProjectBmc {
     ) -> Result<Project> {
         let db = mm.db();
         let stmt = db
-            .prepare("SELECT id, slug, human_key, created_at FROM projects WHERE human_key = ?")
+            .prepare(
+                r#"
+                SELECT id, slug, human_key, created_at,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'project' AND x.entity_id = projects.id), '')
+                FROM projects WHERE human_key = ?
+                "#,
+            )
             .await?;
         let mut rows = stmt.query([human_key]).await?;
 
@@ -252,6 +426,7 @@ impl ProjectBmc {
                 id: ProjectId::new(row.get(0)?),
                 slug: row.get(1)?,
                 human_key: row.get(2)?,
+                public_id: row.get(4)?,
                 created_at,
             })
         } else {
@@ -415,7 +590,13 @@ impl ProjectBmc {
     pub async fn get(_ctx: &crate::Ctx, mm: &ModelManager, id: ProjectId) -> Result<Project> {
         let db = mm.db();
         let stmt = db
-            .prepare("SELECT id, slug, human_key, created_at FROM projects WHERE id = ?")
+            .prepare(
+                r#"
+                SELECT id, slug, human_key, created_at,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'project' AND x.entity_id = projects.id), '')
+                FROM projects WHERE id = ?
+                "#,
+            )
             .await?;
         let mut rows = stmt.query([id.get()]).await?;
 
@@ -428,6 +609,7 @@ impl ProjectBmc {
                 id: ProjectId::new(row.get(0)?),
                 slug: row.get(1)?,
                 human_key: row.get(2)?,
+                public_id: row.get(4)?,
                 created_at,
             })
         } else {
@@ -443,7 +625,7 @@ impl ProjectBmc {
     ) -> Result<Vec<Project>> {
         // 1. Get products for this project
         let products =
-            crate::model::product::ProductBmc::list_for_project(ctx, mm, project_id.get()).await?;
+            crate::model::product::ProductBmc::list_for_project(ctx, mm, project_id).await?;
 
         let mut sibling_ids = std::collections::HashSet::new();
 
@@ -541,15 +723,16 @@ impl ProjectBmc {
     /// foreign key constraints. SQLite does not enforce FK cascades by default,
     /// so we manually delete in dependency order:
     ///
-    /// 1. message_recipients (references messages and agents)
-    /// 2. messages_fts (FTS5 virtual table, synced with messages)
+    /// 1. message_recipients and other per-message rows (reference messages and agents)
+    /// 2. messages_fts, message_search (FTS5 virtual tables, synced with messages)
     /// 3. messages (references project and sender agent)
     /// 4. file_reservations, build_slots, macros, overseer_messages
     /// 5. agent_links (references agents)
     /// 6. project_sibling_suggestions
-    /// 7. agents (references project)
-    /// 8. product_project_links
-    /// 9. project itself
+    /// 7. other per-project rows (`PROJECT_TABLES`)
+    /// 8. agents (references project)
+    /// 9. product_project_links
+    /// 10. project itself
     ///
     /// Also removes the project directory from the Git archive.
     ///
@@ -589,25 +772,44 @@ impl ProjectBmc {
         let agents = super::agent::AgentBmc::list_all_for_project(ctx, mm, project_id).await?;
         let agent_ids: Vec<i64> = agents.iter().map(|a| a.id.get()).collect();
 
-        // 1. Delete message_recipients for messages in this project
-        // (message_recipients references both messages and agents)
+        // 1. Delete message_recipients and the other per-message rows for
+        // messages in this project (they reference messages and agents)
+        for table in super::retention::MESSAGE_TABLES {
+            let sql = format!(
+                "DELETE FROM {} WHERE message_id IN (SELECT id FROM messages WHERE project_id = ?)",
+                table
+            );
+            let stmt = db.prepare(&sql).await?;
+            stmt.execute([pid]).await?;
+        }
+
+        // 2. Delete messages (FTS5 triggers handle messages_fts and message_search automatically)
+        let stmt = db
+            .prepare("DELETE FROM messages WHERE project_id = ?")
+            .await?;
+        stmt.execute([pid]).await?;
+
+        // 3. Delete file_reservations, the watchers waiting on them and their
+        // reservation set memberships
         let stmt = db
             .prepare(
                 r#"
-                DELETE FROM message_recipients
-                WHERE message_id IN (SELECT id FROM messages WHERE project_id = ?)
+                DELETE FROM file_reservation_watchers
+                WHERE reservation_id IN (SELECT id FROM file_reservations WHERE project_id = ?)
                 "#,
             )
             .await?;
         stmt.execute([pid]).await?;
-
-        // 2. Delete messages (FTS5 trigger handles messages_fts automatically)
         let stmt = db
-            .prepare("DELETE FROM messages WHERE project_id = ?")
+            .prepare(
+                r#"
+                DELETE FROM reservation_set_members
+                WHERE set_id IN (SELECT id FROM reservation_sets WHERE project_id = ?1)
+                   OR reservation_id IN (SELECT id FROM file_reservations WHERE project_id = ?1)
+                "#,
+            )
             .await?;
         stmt.execute([pid]).await?;
-
-        // 3. Delete file_reservations
         let stmt = db
             .prepare("DELETE FROM file_reservations WHERE project_id = ?")
             .await?;
@@ -653,23 +855,66 @@ impl ProjectBmc {
             .await?;
         stmt.execute([pid, pid]).await?;
 
-        // 9. Delete agents
+        // 9. Delete the rest of the project's per-project rows
+        for table in PROJECT_TABLES {
+            let sql = format!("DELETE FROM {} WHERE project_id = ?", table);
+            let stmt = db.prepare(&sql).await?;
+            stmt.execute([pid]).await?;
+        }
+
+        // 10. Delete agents
         let stmt = db
             .prepare("DELETE FROM agents WHERE project_id = ?")
             .await?;
         stmt.execute([pid]).await?;
 
-        // 10. Delete product_project_links
+        // 11. Delete product_project_links
         let stmt = db
             .prepare("DELETE FROM product_project_links WHERE project_id = ?")
             .await?;
         stmt.execute([pid]).await?;
 
-        // 11. Delete the project itself
+        // 12. Delete attachments (their blobs are left to gc-attachments)
+        let stmt = db
+            .prepare("DELETE FROM attachments WHERE project_id = ?")
+            .await?;
+        stmt.execute([pid]).await?;
+
+        // 13. Delete webhooks and their delivery logs
+        let stmt = db
+            .prepare(
+                r#"
+                DELETE FROM webhook_deliveries
+                WHERE webhook_id IN (SELECT id FROM webhooks WHERE project_id = ?)
+                "#,
+            )
+            .await?;
+        stmt.execute([pid]).await?;
+        let stmt = db
+            .prepare("DELETE FROM webhooks WHERE project_id = ?")
+            .await?;
+        stmt.execute([pid]).await?;
+
+        // 14. Opt the project out of the email bridge
+        let stmt = db
+            .prepare("DELETE FROM email_bridge_projects WHERE project_id = ?")
+            .await?;
+        stmt.execute([pid]).await?;
+
+        // 15. Delete export jobs and their files
+        super::export_job::ExportJobBmc::delete_for_project(ctx, mm, project_id).await?;
+
+        // 16. Forget the mail ingested into the project
+        let stmt = db
+            .prepare("DELETE FROM inbound_emails WHERE project_id = ?")
+            .await?;
+        stmt.execute([pid]).await?;
+
+        // 17. Delete the project itself
         let stmt = db.prepare("DELETE FROM projects WHERE id = ?").await?;
         stmt.execute([pid]).await?;
 
-        // 12. Clean up Git archive
+        // 18. Clean up Git archive
         let project_dir = mm.repo_root.join("projects").join(&project_slug);
         if project_dir.exists() {
             std::fs::remove_dir_all(&project_dir)?;
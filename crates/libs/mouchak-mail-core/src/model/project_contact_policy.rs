@@ -0,0 +1,290 @@
+//! Project-level contact policies.
+//!
+//! A project's rules decide which other projects may initiate contact with
+//! it. Each rule allows or denies a source project, by slug, or every
+//! project of a product, by product uid. The project target `*` matches any
+//! project.
+//!
+//! [`ProjectContactPolicyBmc::check`] applies the target project's rules:
+//!
+//! - Contact within one project is always allowed
+//! - A matching deny rule wins over any allow rule
+//! - Without a matching allow rule, cross-project contact is denied
+//!
+//! `AgentLinkBmc::request_contact` enforces the policy for every contact
+//! request.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::product::ProductBmc;
+use crate::model::project::ProjectBmc;
+use crate::types::ProjectId;
+use crate::utils::parse_timestamp;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Project target matching every other project.
+pub const ANY_PROJECT: &str = "*";
+
+/// Whether a rule allows or denies contact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactRuleAction {
+    Allow,
+    Deny,
+}
+
+impl ContactRuleAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+impl FromStr for ContactRuleAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "deny" => Ok(Self::Deny),
+            other => Err(Error::InvalidInput(format!(
+                "Unknown contact rule action '{}', expected 'allow' or 'deny'",
+                other
+            ))),
+        }
+    }
+}
+
+/// What a rule's `target` names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactRuleTarget {
+    /// A project slug, or [`ANY_PROJECT`]
+    Project,
+    /// A product uid, matching all projects linked to the product
+    Product,
+}
+
+impl ContactRuleTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::Product => "product",
+        }
+    }
+}
+
+impl FromStr for ContactRuleTarget {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "project" => Ok(Self::Project),
+            "product" => Ok(Self::Product),
+            other => Err(Error::InvalidInput(format!(
+                "Unknown contact rule target kind '{}', expected 'project' or 'product'",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ContactRuleTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A contact rule of a project.
+///
+/// # Fields
+///
+/// - `project_id` - Project the rule protects
+/// - `target_kind` / `target` - Source projects the rule applies to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectContactRule {
+    pub id: i64,
+    pub project_id: ProjectId,
+    pub action: ContactRuleAction,
+    pub target_kind: ContactRuleTarget,
+    pub target: String,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Input to add or change a contact rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectContactRuleForCreate {
+    pub project_id: ProjectId,
+    pub action: ContactRuleAction,
+    pub target_kind: ContactRuleTarget,
+    pub target: String,
+}
+
+/// Backend Model Controller for project contact policies.
+pub struct ProjectContactPolicyBmc;
+
+impl ProjectContactPolicyBmc {
+    /// Lists a project's contact rules, oldest first.
+    pub async fn list_rules(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<ProjectContactRule>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, action, target_kind, target, created_ts
+                FROM project_contact_rules
+                WHERE project_id = ?
+                ORDER BY id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut rules = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let action: String = row.get(2)?;
+            let target_kind: String = row.get(3)?;
+            let created_ts: String = row.get(5)?;
+            rules.push(ProjectContactRule {
+                id: row.get(0)?,
+                project_id: ProjectId::new(row.get(1)?),
+                action: action.parse()?,
+                target_kind: target_kind.parse()?,
+                target: row.get(4)?,
+                created_ts: parse_timestamp(&created_ts, "created_ts"),
+            });
+        }
+        Ok(rules)
+    }
+
+    /// Adds a rule, or changes the action of the rule for the same target.
+    ///
+    /// # Returns
+    /// The rule id.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an empty target or a product wildcard.
+    pub async fn set_rule(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        rule_c: ProjectContactRuleForCreate,
+    ) -> Result<i64> {
+        let target = rule_c.target.trim();
+        if target.is_empty() {
+            return Err(Error::InvalidInput(
+                "Contact rule target must not be empty".into(),
+            ));
+        }
+        if target == ANY_PROJECT && rule_c.target_kind != ContactRuleTarget::Project {
+            return Err(Error::InvalidInput(format!(
+                "'{}' is only valid as a project target",
+                ANY_PROJECT
+            )));
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO project_contact_rules (project_id, action, target_kind, target)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT(project_id, target_kind, target) DO UPDATE SET action = excluded.action
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                rule_c.project_id.get(),
+                rule_c.action.as_str(),
+                rule_c.target_kind.as_str(),
+                target,
+            ))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::InvalidInput("Failed to save contact rule".into())),
+        }
+    }
+
+    /// Removes one of a project's rules.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no rule with this id.
+    pub async fn delete_rule(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        rule_id: i64,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM project_contact_rules WHERE id = ? AND project_id = ?")
+            .await?;
+        let deleted = stmt.execute((rule_id, project_id.get())).await?;
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Checks that `from` may initiate contact with `to`.
+    ///
+    /// # Errors
+    /// Returns `ContactDenied` if `to`'s rules do not allow `from`.
+    pub async fn check(ctx: &Ctx, mm: &ModelManager, from: ProjectId, to: ProjectId) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        let rules = Self::list_rules(ctx, mm, to).await?;
+        let from_project = ProjectBmc::get(ctx, mm, from).await?;
+        let to_project = ProjectBmc::get(ctx, mm, to).await?;
+        let from_products: Vec<String> = if rules
+            .iter()
+            .any(|r| r.target_kind == ContactRuleTarget::Product)
+        {
+            ProductBmc::list_for_project(ctx, mm, from)
+                .await?
+                .into_iter()
+                .map(|p| p.product_uid)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let matching: Vec<&ProjectContactRule> = rules
+            .iter()
+            .filter(|r| match r.target_kind {
+                ContactRuleTarget::Project => {
+                    r.target == ANY_PROJECT || r.target == from_project.slug
+                }
+                ContactRuleTarget::Product => from_products.contains(&r.target),
+            })
+            .collect();
+
+        if let Some(rule) = matching
+            .iter()
+            .find(|r| r.action == ContactRuleAction::Deny)
+        {
+            return Err(Error::ContactDenied(format!(
+                "project '{}' denies contact from '{}' (rule {}: {} '{}')",
+                to_project.slug, from_project.slug, rule.id, rule.target_kind, rule.target
+            )));
+        }
+        if matching.is_empty() {
+            return Err(Error::ContactDenied(format!(
+                "project '{}' does not allow contact from '{}'; an admin must add an allow rule",
+                to_project.slug, from_project.slug
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,579 @@
+//! Per-project time zone and business hours.
+//!
+//! Timestamps are stored in UTC. A project can name the time zone its agents
+//! and humans work in and a working-hours window, which the time-based
+//! features interpret locally:
+//!
+//! - **SLA timers** - [`ProjectSettings::business_deadline`] counts only
+//!   business time, so an hour-long SLA on a message sent Friday evening is
+//!   due Monday morning
+//! - **Escalation** - overdue acknowledgments are escalated only during
+//!   business hours
+//! - **Quiet hours** - with `quiet_hours` on, a message sent outside business
+//!   hours opens a focus window until the next working day starts, so
+//!   low-importance mail is held like in any other focus window
+//!
+//! A project without settings uses UTC and has no business hours: every
+//! moment counts as business time, which is the behavior before settings
+//! existed.
+//!
+//! The `locale` setting picks the language of system messages, see
+//! [`crate::model::message_catalog`]. `email_bridge` opts the project into
+//! the outbound email bridge, see [`crate::model::email_bridge`].
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::email_bridge::{EmailBridgeBmc, parse_addresses};
+use crate::model::focus_window::{
+    DEFAULT_ALLOWED_IMPORTANCE, FocusWindow, FocusWindowBmc, FocusWindowForCreate,
+};
+use crate::model::message_catalog::{DEFAULT_LOCALE, MessageCatalogBmc, normalize_locale};
+use crate::types::ProjectId;
+use crate::utils::parse_timestamp;
+use crate::{Error, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// Working days when none are given.
+pub const DEFAULT_BUSINESS_DAYS: &[Weekday] = &[
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+/// Local working-hours window of a project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BusinessHours {
+    /// Local start of the working day
+    pub start: NaiveTime,
+    /// Local end of the working day, after `start`
+    pub end: NaiveTime,
+    pub days: Vec<Weekday>,
+}
+
+impl BusinessHours {
+    fn is_working_day(&self, day: Weekday) -> bool {
+        self.days.contains(&day)
+    }
+}
+
+impl std::fmt::Display for BusinessHours {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let days: Vec<String> = self
+            .days
+            .iter()
+            .map(|d| d.to_string().to_lowercase())
+            .collect();
+        write!(
+            f,
+            "{}-{} {}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M"),
+            days.join(",")
+        )
+    }
+}
+
+/// Time settings of a project.
+///
+/// # Fields
+///
+/// - `timezone` - IANA time zone name, e.g. `Europe/Berlin`
+/// - `business_hours` - `None` when every moment counts as business time
+/// - `quiet_hours` - Hold low-importance mail outside business hours
+/// - `locale` - Language of system messages, e.g. `de` or `pt-BR`
+/// - `email_bridge` - Forward matching messages to human mailboxes
+/// - `email_recipients` - Addresses they are sent to; empty means the
+///   configured `email_bridge.to`
+/// - `updated_ts` - `None` while the project uses the defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    pub project_id: ProjectId,
+    pub timezone: String,
+    pub business_hours: Option<BusinessHours>,
+    pub quiet_hours: bool,
+    pub locale: String,
+    pub email_bridge: bool,
+    pub email_recipients: Vec<String>,
+    pub updated_ts: Option<NaiveDateTime>,
+}
+
+/// Changes to a project's settings; `None` keeps the current value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSettingsForUpdate {
+    /// IANA time zone name
+    pub timezone: Option<String>,
+    /// `HH:MM-HH:MM` in local time, or an empty string to remove business hours
+    pub business_hours: Option<String>,
+    /// Working days, e.g. `mon-fri` or `mon,wed,fri`
+    pub business_days: Option<String>,
+    pub quiet_hours: Option<bool>,
+    /// Locale of system messages
+    pub locale: Option<String>,
+    pub email_bridge: Option<bool>,
+    /// Comma-separated addresses, or an empty string for the configured
+    /// ones; only kept while `email_bridge` is on
+    pub email_recipients: Option<String>,
+}
+
+impl ProjectSettings {
+    /// Settings of a project that has none stored.
+    pub fn defaults(project_id: ProjectId) -> Self {
+        Self {
+            project_id,
+            timezone: "UTC".to_string(),
+            business_hours: None,
+            quiet_hours: false,
+            locale: DEFAULT_LOCALE.to_string(),
+            email_bridge: false,
+            email_recipients: Vec::new(),
+            updated_ts: None,
+        }
+    }
+
+    /// The project's time zone; UTC if the stored name is not recognized.
+    pub fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// A UTC timestamp in the project's local time.
+    pub fn local(&self, utc: NaiveDateTime) -> DateTime<Tz> {
+        self.tz().from_utc_datetime(&utc)
+    }
+
+    /// Whether `utc` falls within business hours. Always true without
+    /// business hours.
+    pub fn is_business_time(&self, utc: NaiveDateTime) -> bool {
+        let Some(hours) = &self.business_hours else {
+            return true;
+        };
+        let local = self.local(utc);
+        hours.is_working_day(local.weekday())
+            && local.time() >= hours.start
+            && local.time() < hours.end
+    }
+
+    /// `utc` if it is business time, otherwise the UTC start of the next
+    /// working day.
+    pub fn next_business_start(&self, utc: NaiveDateTime) -> NaiveDateTime {
+        let Some(hours) = &self.business_hours else {
+            return utc;
+        };
+        if self.is_business_time(utc) {
+            return utc;
+        }
+        let local = self.local(utc).naive_local();
+        for offset in 0..=7 {
+            let date = local.date() + Duration::days(offset);
+            if !hours.is_working_day(date.weekday()) {
+                continue;
+            }
+            let start = date.and_time(hours.start);
+            if start > local {
+                return self.to_utc(start);
+            }
+        }
+        utc
+    }
+
+    /// When `seconds` of business time have passed since `start`.
+    ///
+    /// Without business hours this is `start + seconds`.
+    pub fn business_deadline(&self, start: NaiveDateTime, seconds: i64) -> NaiveDateTime {
+        let Some(hours) = &self.business_hours else {
+            return start + Duration::seconds(seconds);
+        };
+        let mut remaining = Duration::seconds(seconds.max(0));
+        let mut at = start;
+        // One iteration per working day; a year of them covers any real SLA
+        for _ in 0..366 {
+            at = self.next_business_start(at);
+            let day_end = self.to_utc(self.local(at).date_naive().and_time(hours.end));
+            let available = day_end - at;
+            if remaining <= available {
+                return at + remaining;
+            }
+            remaining -= available;
+            at = day_end;
+        }
+        at + remaining
+    }
+
+    /// A local time as UTC. Times skipped by a DST change move an hour
+    /// forward; repeated ones take the earlier instant.
+    fn to_utc(&self, local: NaiveDateTime) -> NaiveDateTime {
+        let tz = self.tz();
+        tz.from_local_datetime(&local)
+            .earliest()
+            .or_else(|| {
+                tz.from_local_datetime(&(local + Duration::hours(1)))
+                    .earliest()
+            })
+            .map(|dt| dt.naive_utc())
+            .unwrap_or(local)
+    }
+}
+
+/// Parses an IANA time zone name.
+///
+/// # Errors
+/// Returns `InvalidInput` for an unknown name.
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.trim().parse().map_err(|_| {
+        Error::InvalidInput(format!(
+            "Unknown time zone '{}' (expected an IANA name like 'Europe/Berlin')",
+            name
+        ))
+    })
+}
+
+/// Parses `HH:MM-HH:MM`; an empty string means no business hours.
+///
+/// # Errors
+/// Returns `InvalidInput` for malformed times or a window that does not end
+/// after it starts.
+pub fn parse_hours(hours: &str) -> Result<Option<(NaiveTime, NaiveTime)>> {
+    let hours = hours.trim();
+    if hours.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || {
+        Error::InvalidInput(format!(
+            "Invalid business hours '{}' (expected HH:MM-HH:MM, e.g. 09:00-17:00)",
+            hours
+        ))
+    };
+    let (start, end) = hours.split_once('-').ok_or_else(invalid)?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+    let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| invalid())?;
+    if end <= start {
+        return Err(Error::InvalidInput(format!(
+            "Business hours must end after they start: '{}'",
+            hours
+        )));
+    }
+    Ok(Some((start, end)))
+}
+
+/// Parses working days: a comma-separated list of days or ranges, e.g.
+/// `mon-fri` or `mon,wed,fri`.
+///
+/// # Errors
+/// Returns `InvalidInput` for an unknown day or an empty list.
+pub fn parse_days(days: &str) -> Result<Vec<Weekday>> {
+    let day = |name: &str| -> Result<Weekday> {
+        name.trim()
+            .parse()
+            .map_err(|_| Error::InvalidInput(format!("Unknown day '{}'", name.trim())))
+    };
+    let mut parsed = Vec::new();
+    for part in days.split(',').filter(|p| !p.trim().is_empty()) {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (mut current, to) = (day(from)?, day(to)?);
+                loop {
+                    if !parsed.contains(&current) {
+                        parsed.push(current);
+                    }
+                    if current == to {
+                        break;
+                    }
+                    current = current.succ();
+                }
+            }
+            None => {
+                let d = day(part)?;
+                if !parsed.contains(&d) {
+                    parsed.push(d);
+                }
+            }
+        }
+    }
+    if parsed.is_empty() {
+        return Err(Error::InvalidInput(
+            "Business days must name at least one day".into(),
+        ));
+    }
+    parsed.sort_by_key(|d| d.num_days_from_monday());
+    Ok(parsed)
+}
+
+/// Backend Model Controller for project settings.
+pub struct ProjectSettingsBmc;
+
+impl ProjectSettingsBmc {
+    /// A project's settings, or the defaults when none are stored.
+    pub async fn get(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<ProjectSettings> {
+        let locale = MessageCatalogBmc::project_locale(ctx, mm, project_id).await?;
+        let email_recipients = EmailBridgeBmc::project_recipients(ctx, mm, project_id).await?;
+        let email_bridge = email_recipients.is_some();
+        let email_recipients = email_recipients.unwrap_or_default();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT timezone, business_start, business_end, business_days, quiet_hours, updated_ts
+                FROM project_settings
+                WHERE project_id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(ProjectSettings {
+                locale,
+                email_bridge,
+                email_recipients,
+                ..ProjectSettings::defaults(project_id)
+            });
+        };
+
+        let start: Option<String> = row.get(1)?;
+        let end: Option<String> = row.get(2)?;
+        let days: String = row.get(3)?;
+        let business_hours = match (start, end) {
+            (Some(start), Some(end)) => {
+                parse_hours(&format!("{}-{}", start, end))?.map(|(start, end)| BusinessHours {
+                    start,
+                    end,
+                    days: parse_days(&days).unwrap_or_else(|_| DEFAULT_BUSINESS_DAYS.to_vec()),
+                })
+            }
+            _ => None,
+        };
+        Ok(ProjectSettings {
+            project_id,
+            timezone: row.get(0)?,
+            business_hours,
+            quiet_hours: row.get::<i64>(4)? != 0,
+            locale,
+            email_bridge,
+            email_recipients,
+            updated_ts: Some(parse_timestamp(&row.get::<String>(5)?, "updated_ts")),
+        })
+    }
+
+    /// Changes a project's settings.
+    ///
+    /// # Returns
+    /// The settings after the change.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an unknown time zone or day, or malformed
+    /// business hours, locale or email address.
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        settings_u: ProjectSettingsForUpdate,
+    ) -> Result<ProjectSettings> {
+        let current = Self::get(ctx, mm, project_id).await?;
+        if let Some(locale) = settings_u.locale.as_deref() {
+            normalize_locale(locale)?;
+        }
+        let email_recipients = match settings_u.email_recipients.as_deref() {
+            Some(list) => parse_addresses(list)?,
+            None => current.email_recipients,
+        };
+
+        let timezone = match settings_u.timezone.as_deref() {
+            Some(name) => parse_timezone(name)?.name().to_string(),
+            None => current.timezone,
+        };
+        let days = match settings_u.business_days.as_deref() {
+            Some(days) => parse_days(days)?,
+            None => current
+                .business_hours
+                .as_ref()
+                .map(|h| h.days.clone())
+                .unwrap_or_else(|| DEFAULT_BUSINESS_DAYS.to_vec()),
+        };
+        let window = match settings_u.business_hours.as_deref() {
+            Some(hours) => parse_hours(hours)?,
+            None => current.business_hours.as_ref().map(|h| (h.start, h.end)),
+        };
+        let quiet_hours = settings_u.quiet_hours.unwrap_or(current.quiet_hours);
+        let days_csv: Vec<String> = days.iter().map(|d| d.to_string().to_lowercase()).collect();
+
+        let stmt = mm
+            .db()
+            .prepare(
+                r#"
+                INSERT INTO project_settings
+                    (project_id, timezone, business_start, business_end, business_days, quiet_hours, updated_ts)
+                VALUES (?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+                ON CONFLICT(project_id) DO UPDATE SET
+                    timezone = excluded.timezone,
+                    business_start = excluded.business_start,
+                    business_end = excluded.business_end,
+                    business_days = excluded.business_days,
+                    quiet_hours = excluded.quiet_hours,
+                    updated_ts = excluded.updated_ts
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            project_id.get(),
+            timezone,
+            window.map(|(start, _)| start.format("%H:%M").to_string()),
+            window.map(|(_, end)| end.format("%H:%M").to_string()),
+            days_csv.join(","),
+            quiet_hours as i64,
+        ))
+        .await?;
+        if let Some(locale) = settings_u.locale.as_deref() {
+            MessageCatalogBmc::set_project_locale(ctx, mm, project_id, locale).await?;
+        }
+        if settings_u.email_bridge.is_some() || settings_u.email_recipients.is_some() {
+            if settings_u.email_bridge.unwrap_or(current.email_bridge) {
+                EmailBridgeBmc::enable_project(ctx, mm, project_id, &email_recipients).await?;
+            } else {
+                EmailBridgeBmc::disable_project(ctx, mm, project_id).await?;
+            }
+        }
+
+        Self::get(ctx, mm, project_id).await
+    }
+
+    /// Opens a focus window until the next working day starts when the
+    /// project has quiet hours on and it is outside business hours now.
+    ///
+    /// # Returns
+    /// The window in effect, or `None` during business hours.
+    pub async fn quiet_hours_window(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Option<FocusWindow>> {
+        let settings = Self::get(ctx, mm, project_id).await?;
+        let now = Utc::now().naive_utc();
+        if !settings.quiet_hours || settings.is_business_time(now) {
+            return Ok(None);
+        }
+
+        let fw_c = FocusWindowForCreate {
+            project_id,
+            starts_ts: now,
+            ends_ts: settings.next_business_start(now),
+            allowed_importance: DEFAULT_ALLOWED_IMPORTANCE
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            reason: MessageCatalogBmc::for_locale(ctx, mm, &settings.locale)
+                .await?
+                .render("quiet_hours.reason", &[("timezone", &settings.timezone)]),
+        };
+        match FocusWindowBmc::create(ctx, mm, fw_c).await {
+            Ok(id) => FocusWindowBmc::get(ctx, mm, id).await.map(Some),
+            // A concurrent message opened it first
+            Err(Error::InvalidInput(_)) => FocusWindowBmc::get_active(ctx, mm, project_id).await,
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn berlin_office() -> ProjectSettings {
+        ProjectSettings {
+            timezone: "Europe/Berlin".to_string(),
+            business_hours: Some(BusinessHours {
+                start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+                days: DEFAULT_BUSINESS_DAYS.to_vec(),
+            }),
+            ..ProjectSettings::defaults(ProjectId::new(1))
+        }
+    }
+
+    #[test]
+    fn test_parse_days_and_hours() {
+        assert_eq!(parse_days("mon-fri").unwrap(), DEFAULT_BUSINESS_DAYS);
+        assert_eq!(
+            parse_days("fri, mon,wed").unwrap(),
+            [Weekday::Mon, Weekday::Wed, Weekday::Fri]
+        );
+        assert_eq!(
+            parse_days("sat-mon").unwrap(),
+            [Weekday::Mon, Weekday::Sat, Weekday::Sun]
+        );
+        assert!(parse_days("funday").is_err());
+        assert!(parse_days("").is_err());
+
+        assert_eq!(parse_hours("").unwrap(), None);
+        assert!(parse_hours("17:00-09:00").is_err());
+        assert!(parse_hours("9am-5pm").is_err());
+        assert!(parse_timezone("Mars/Olympus").is_err());
+        assert_eq!(parse_timezone("Asia/Kolkata").unwrap(), Tz::Asia__Kolkata);
+    }
+
+    #[test]
+    fn test_business_time_is_local() {
+        let settings = berlin_office();
+        // 08:30 UTC is 10:30 in Berlin summer time (Wednesday)
+        assert!(settings.is_business_time(ts("2026-07-01 08:30")));
+        // 16:30 UTC is 18:30 in Berlin
+        assert!(!settings.is_business_time(ts("2026-07-01 16:30")));
+        // Saturday
+        assert!(!settings.is_business_time(ts("2026-07-04 10:00")));
+        // No business hours: always business time
+        assert!(
+            ProjectSettings::defaults(ProjectId::new(1)).is_business_time(ts("2026-07-04 03:00"))
+        );
+    }
+
+    #[test]
+    fn test_next_business_start() {
+        let settings = berlin_office();
+        // Friday evening -> Monday 09:00 Berlin (07:00 UTC)
+        assert_eq!(
+            settings.next_business_start(ts("2026-07-03 18:00")),
+            ts("2026-07-06 07:00")
+        );
+        // Early Wednesday -> same day
+        assert_eq!(
+            settings.next_business_start(ts("2026-07-01 04:00")),
+            ts("2026-07-01 07:00")
+        );
+        // Winter: 09:00 Berlin is 08:00 UTC
+        assert_eq!(
+            settings.next_business_start(ts("2026-01-07 05:00")),
+            ts("2026-01-07 08:00")
+        );
+    }
+
+    #[test]
+    fn test_business_deadline_skips_nights_and_weekends() {
+        let settings = berlin_office();
+        // Friday 16:00 Berlin + 2h business time -> Monday 10:00 Berlin
+        assert_eq!(
+            settings.business_deadline(ts("2026-07-03 14:00"), 2 * 3600),
+            ts("2026-07-06 08:00")
+        );
+        // Within one day
+        assert_eq!(
+            settings.business_deadline(ts("2026-07-01 08:00"), 3600),
+            ts("2026-07-01 09:00")
+        );
+        // Without business hours it is plain addition
+        let utc = ProjectSettings::defaults(ProjectId::new(1));
+        assert_eq!(
+            utc.business_deadline(ts("2026-07-03 14:00"), 2 * 3600),
+            ts("2026-07-03 16:00")
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use crate::Result;
 use crate::ctx::Ctx;
 use crate::model::ModelManager;
+use crate::store::db_statement::Row;
+use crate::types::ProjectId;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
@@ -90,7 +92,7 @@ impl ProjectSiblingSuggestionBmc {
     pub async fn list(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: i64,
+        project_id: ProjectId,
     ) -> Result<Vec<ProjectSiblingSuggestion>> {
         let db = mm.db();
         let stmt = db.prepare(
@@ -103,7 +105,7 @@ impl ProjectSiblingSuggestionBmc {
             "#
         ).await?;
 
-        let mut rows = stmt.query((project_id, project_id)).await?;
+        let mut rows = stmt.query((project_id.get(), project_id.get())).await?;
         let mut suggestions = Vec::new();
 
         while let Some(row) = rows.next().await? {
@@ -141,7 +143,7 @@ impl ProjectSiblingSuggestionBmc {
         Ok(())
     }
 
-    fn from_row(row: libsql::Row) -> Result<ProjectSiblingSuggestion> {
+    fn from_row(row: Row) -> Result<ProjectSiblingSuggestion> {
         let created_ts_str: String = row.get(6).unwrap_or_default();
         let evaluated_ts_str: String = row.get(7).unwrap_or_default();
         let confirmed_ts_str: Option<String> = row.get(8).unwrap_or_default();
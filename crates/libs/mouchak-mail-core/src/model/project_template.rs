@@ -0,0 +1,418 @@
+//! Project templates: new projects with a predefined setup.
+//!
+//! Teams that spin up many similar projects describe the common setup once,
+//! as a JSON bundle in the templates directory of the data dir
+//! (`data/templates/<name>.json`, see
+//! [`mouchak_mail_common::paths::templates_dir`]):
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "description": "Planner and reviewer pair",
+//!   "agents": [
+//!     { "name": "planner", "program": "claude-code", "model": "opus",
+//!       "capabilities": ["send_message", "fetch_inbox"] },
+//!     { "name": "reviewer", "program": "codex", "model": "gpt-5" }
+//!   ],
+//!   "contacts": [{ "from": "planner", "to": "reviewer", "reason": "reviews plans" }],
+//!   "macros": [{ "name": "handoff", "description": "Hand off a task", "steps": [] }],
+//!   "labels": ["blocked", "needs-review"],
+//!   "settings": { "timezone": "Europe/Berlin", "business_hours": "09:00-17:00" },
+//!   "slas": { "urgent": 3600, "high": 14400 },
+//!   "welcome_messages": [
+//!     { "subject": "Welcome", "body_md": "Planner drives, reviewer checks." }
+//!   ]
+//! }
+//! ```
+//!
+//! Agents and contacts use the [team graph](crate::model::team_graph)
+//! format. `labels` are created as [message labels](crate::model::label)
+//! with palette colors. `slas` is the escalation policy: acknowledgment deadlines per
+//! importance, whose violations are escalated to the overseer (see
+//! [`crate::model::sla`]). Welcome messages are sent by the template's
+//! `welcome` agent to every template agent unless `from` and `to` name
+//! template agents.
+//!
+//! [`ProjectTemplateBmc::instantiate`] always creates a new project. The
+//! template is checked before anything is written; entries that fail to
+//! apply afterwards (e.g. a macro name clashing with a built-in macro) are
+//! reported in [`ProjectTemplateSummary::skipped`].
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{AgentBmc, AgentForCreate};
+use crate::model::focus_window::IMPORTANCE_LEVELS;
+use crate::model::label::{LabelBmc, LabelForCreate};
+use crate::model::macro_def::{MacroDefBmc, MacroDefForCreate};
+use crate::model::message::{MessageBmc, MessageForCreate};
+use crate::model::project::ProjectBmc;
+use crate::model::project_settings::{ProjectSettingsBmc, ProjectSettingsForUpdate};
+use crate::model::sla::{ProjectSlaForCreate, SlaBmc};
+use crate::model::team_graph::{
+    TEAM_GRAPH_VERSION, TeamAgent, TeamContact, TeamGraph, TeamGraphBmc, TeamGraphImportSummary,
+};
+use crate::types::{AgentId, ProjectId};
+use crate::utils::validation::validate_agent_name;
+use crate::{Error, Result};
+use mouchak_mail_common::paths;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Format version understood by [`ProjectTemplateBmc`].
+pub const PROJECT_TEMPLATE_VERSION: u32 = 1;
+
+/// Agent that sends welcome messages without a `from`.
+pub const WELCOME_AGENT_NAME: &str = "welcome";
+
+/// A macro defined by a template.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateMacro {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub steps: Vec<Value>,
+}
+
+/// A message sent when a project is created from a template.
+///
+/// # Fields
+///
+/// - `from` - Template agent sending it; the `welcome` agent if unset
+/// - `to` - Template agents receiving it; all of them if empty
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WelcomeMessage {
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Vec<String>,
+    pub subject: String,
+    #[serde(default)]
+    pub body_md: String,
+    #[serde(default)]
+    pub importance: Option<String>,
+}
+
+/// A project template bundle.
+///
+/// # Fields
+///
+/// - `version` - Format version, see [`PROJECT_TEMPLATE_VERSION`]
+/// - `settings` - Time zone, business hours and locale of new projects
+/// - `slas` - Seconds to acknowledge, by importance
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectTemplate {
+    pub version: u32,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub agents: Vec<TeamAgent>,
+    #[serde(default)]
+    pub contacts: Vec<TeamContact>,
+    #[serde(default)]
+    pub macros: Vec<TemplateMacro>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub settings: Option<ProjectSettingsForUpdate>,
+    #[serde(default)]
+    pub slas: BTreeMap<String, i64>,
+    #[serde(default)]
+    pub welcome_messages: Vec<WelcomeMessage>,
+}
+
+/// Outcome of creating a project from a template.
+///
+/// # Fields
+///
+/// - `team` - Agents, capabilities and contacts added
+/// - `macros_created` - Names of template macros registered
+/// - `labels_created` - Names of template labels created, with palette colors
+/// - `slas_set` - Importance levels given a deadline
+/// - `welcome_messages_sent` - Subjects of the messages sent
+/// - `skipped` - One reason per entry that was not applied
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectTemplateSummary {
+    pub project_id: ProjectId,
+    pub slug: String,
+    pub template: String,
+    pub team: TeamGraphImportSummary,
+    pub macros_created: Vec<String>,
+    pub labels_created: Vec<String>,
+    pub settings_applied: bool,
+    pub slas_set: Vec<String>,
+    pub welcome_messages_sent: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+/// Backend Model Controller for project templates.
+pub struct ProjectTemplateBmc;
+
+impl ProjectTemplateBmc {
+    /// Names of the templates in the data dir, sorted.
+    pub fn list() -> Result<Vec<String>> {
+        Self::list_in(&paths::templates_dir())
+    }
+
+    /// Names of the templates in `dir`, sorted; empty if `dir` is missing.
+    pub fn list_in(dir: &Path) -> Result<Vec<String>> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json")
+                && let Some(stem) = path.file_stem().and_then(|s| s.to_str())
+            {
+                names.push(stem.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Loads a template from the data dir.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if the name is not a plain file name or no
+    /// such template exists, and a JSON error if the bundle is malformed.
+    pub fn load(name: &str) -> Result<ProjectTemplate> {
+        Self::load_from(&paths::templates_dir(), name)
+    }
+
+    /// Loads the template `name` from `dir`.
+    pub fn load_from(dir: &Path, name: &str) -> Result<ProjectTemplate> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::InvalidInput(format!(
+                "Invalid template name '{}': use letters, digits, '-' and '_'",
+                name
+            )));
+        }
+        let path = dir.join(format!("{}.json", name));
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let available = Self::list_in(dir)?;
+                return Err(Error::InvalidInput(format!(
+                    "Unknown project template '{}' in {} (available: {})",
+                    name,
+                    dir.display(),
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                )));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Creates a project and applies `template` to it.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` or a validation error if the template is
+    /// inconsistent, and an error if the project cannot be created; nothing
+    /// is written in either case.
+    pub async fn instantiate(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        template_name: &str,
+        template: &ProjectTemplate,
+        slug: &str,
+        human_key: &str,
+    ) -> Result<ProjectTemplateSummary> {
+        Self::check(template)?;
+        let project_id = ProjectBmc::create(ctx, mm, slug, human_key).await?;
+
+        let graph = TeamGraph {
+            version: TEAM_GRAPH_VERSION,
+            project: template_name.to_string(),
+            exported_ts: None,
+            agents: template.agents.clone(),
+            contacts: template.contacts.clone(),
+        };
+        let team = TeamGraphBmc::import(ctx, mm, project_id, &graph).await?;
+        let mut summary = ProjectTemplateSummary {
+            project_id,
+            slug: slug.to_string(),
+            template: template_name.to_string(),
+            team,
+            macros_created: Vec::new(),
+            labels_created: Vec::new(),
+            settings_applied: false,
+            slas_set: Vec::new(),
+            welcome_messages_sent: Vec::new(),
+            skipped: Vec::new(),
+        };
+
+        for macro_t in &template.macros {
+            let macro_c = MacroDefForCreate {
+                project_id: project_id.get(),
+                name: macro_t.name.clone(),
+                description: macro_t.description.clone(),
+                steps: macro_t.steps.clone(),
+            };
+            match MacroDefBmc::create(ctx, mm, macro_c).await {
+                Ok(_) => summary.macros_created.push(macro_t.name.clone()),
+                Err(e) => summary
+                    .skipped
+                    .push(format!("macro '{}': {}", macro_t.name, e)),
+            }
+        }
+
+        for label in &template.labels {
+            let label_c = LabelForCreate {
+                project_id,
+                name: label.clone(),
+                color: None,
+                description: None,
+            };
+            match LabelBmc::create(ctx, mm, label_c).await {
+                Ok(_) => summary.labels_created.push(label.clone()),
+                Err(e) => summary.skipped.push(format!("label '{}': {}", label, e)),
+            }
+        }
+
+        if let Some(settings) = &template.settings {
+            match ProjectSettingsBmc::update(ctx, mm, project_id, settings.clone()).await {
+                Ok(_) => summary.settings_applied = true,
+                Err(e) => summary.skipped.push(format!("settings: {}", e)),
+            }
+        }
+
+        for (importance, ack_within_seconds) in &template.slas {
+            let sla_c = ProjectSlaForCreate {
+                project_id,
+                importance: importance.clone(),
+                ack_within_seconds: *ack_within_seconds,
+            };
+            match SlaBmc::set_sla(ctx, mm, sla_c).await {
+                Ok(_) => summary.slas_set.push(importance.clone()),
+                Err(e) => summary.skipped.push(format!("sla '{}': {}", importance, e)),
+            }
+        }
+
+        if !template.welcome_messages.is_empty() {
+            let mut agent_ids = HashMap::new();
+            for agent in AgentBmc::list_all_for_project(ctx, mm, project_id).await? {
+                agent_ids.insert(agent.name, agent.id.get());
+            }
+            let team: Vec<i64> = template
+                .agents
+                .iter()
+                .filter_map(|a| agent_ids.get(&a.name).copied())
+                .collect();
+            for welcome in &template.welcome_messages {
+                // `check` made sure every named agent is a template agent
+                let sender_id = match &welcome.from {
+                    Some(from) => agent_ids.get(from).copied().unwrap_or_default(),
+                    None => Self::welcome_agent(ctx, mm, project_id).await?,
+                };
+                let recipient_ids: Vec<i64> = if welcome.to.is_empty() {
+                    team.iter().copied().filter(|id| *id != sender_id).collect()
+                } else {
+                    welcome
+                        .to
+                        .iter()
+                        .filter_map(|name| agent_ids.get(name).copied())
+                        .collect()
+                };
+                let msg_c = MessageForCreate {
+                    project_id,
+                    sender_id: AgentId::new(sender_id),
+                    recipient_ids: recipient_ids.into_iter().map(AgentId::new).collect(),
+                    cc_ids: None,
+                    bcc_ids: None,
+                    subject: welcome.subject.clone(),
+                    body_md: welcome.body_md.clone(),
+                    thread_id: None,
+                    importance: welcome.importance.clone(),
+                    ack_required: false,
+                };
+                match MessageBmc::create(ctx, mm, msg_c).await {
+                    Ok(_) => summary.welcome_messages_sent.push(welcome.subject.clone()),
+                    Err(e) => summary
+                        .skipped
+                        .push(format!("welcome message '{}': {}", welcome.subject, e)),
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Rejects templates that could not be applied consistently.
+    fn check(template: &ProjectTemplate) -> Result<()> {
+        if template.version == 0 || template.version > PROJECT_TEMPLATE_VERSION {
+            return Err(Error::InvalidInput(format!(
+                "Unsupported project template version {} (expected {})",
+                template.version, PROJECT_TEMPLATE_VERSION
+            )));
+        }
+        for agent in &template.agents {
+            validate_agent_name(&agent.name)?;
+        }
+        for importance in template.slas.keys() {
+            if !IMPORTANCE_LEVELS.contains(&importance.as_str()) {
+                return Err(Error::InvalidInput(format!(
+                    "Unknown SLA importance '{}', expected one of: {}",
+                    importance,
+                    IMPORTANCE_LEVELS.join(", ")
+                )));
+            }
+        }
+
+        let is_agent = |name: &str| template.agents.iter().any(|a| a.name == name);
+        for welcome in &template.welcome_messages {
+            let mut named = welcome.from.iter().chain(welcome.to.iter());
+            if let Some(unknown) = named.find(|name| !is_agent(name)) {
+                return Err(Error::InvalidInput(format!(
+                    "Welcome message '{}' names '{}', which is not a template agent",
+                    welcome.subject, unknown
+                )));
+            }
+            if welcome.to.is_empty() && template.agents.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "Welcome message '{}' has no recipients: the template has no agents",
+                    welcome.subject
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the project's `welcome` agent, registering it on first use.
+    async fn welcome_agent(ctx: &Ctx, mm: &ModelManager, project_id: ProjectId) -> Result<i64> {
+        match AgentBmc::get_by_name(ctx, mm, project_id, WELCOME_AGENT_NAME).await {
+            Ok(agent) => Ok(agent.id.get()),
+            Err(Error::AgentNotFound { .. }) | Err(Error::NotFound) => {
+                let id = AgentBmc::create(
+                    ctx,
+                    mm,
+                    AgentForCreate {
+                        project_id,
+                        name: WELCOME_AGENT_NAME.to_string(),
+                        program: "template".to_string(),
+                        model: "none".to_string(),
+                        task_description: "Sends the welcome messages of project templates"
+                            .to_string(),
+                    },
+                )
+                .await?;
+                Ok(id.get())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
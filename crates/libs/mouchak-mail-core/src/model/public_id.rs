@@ -0,0 +1,129 @@
+//! Public identifiers for projects, agents and messages.
+//!
+//! Integer primary keys reveal how many records exist and in what order they
+//! were created, and they collide as soon as two databases are merged. Every
+//! project, agent and message therefore also has a public id, a ULID (see
+//! [`crate::utils::ulid`]) assigned by trigger when the row is inserted and
+//! never changed. Records carry it as `public_id`, and external systems
+//! should reference records by it.
+//!
+//! [`PublicIdBmc::resolve`] maps a public id back to the record it names.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::utils::ulid::is_ulid;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Kind of record a public id names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublicEntity {
+    Project,
+    Agent,
+    Message,
+}
+
+impl PublicEntity {
+    /// The `entity` value stored in `public_ids`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::Agent => "agent",
+            Self::Message => "message",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        match s {
+            "project" => Ok(Self::Project),
+            "agent" => Ok(Self::Agent),
+            "message" => Ok(Self::Message),
+            other => Err(Error::InvalidInput(format!(
+                "unknown public id entity '{other}'"
+            ))),
+        }
+    }
+}
+
+/// The record a public id names.
+///
+/// # Fields
+///
+/// - `id` - Integer primary key of the record
+/// - `project_id` - Project the record belongs to; a project's own id
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublicRef {
+    pub public_id: String,
+    pub entity: PublicEntity,
+    pub id: i64,
+    pub project_id: i64,
+}
+
+/// Backend Model Controller for public ids.
+pub struct PublicIdBmc;
+
+impl PublicIdBmc {
+    /// Returns the public id of a record.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if no such record exists.
+    pub async fn get(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        entity: PublicEntity,
+        id: i64,
+    ) -> Result<String> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT public_id FROM public_ids WHERE entity = ? AND entity_id = ?")
+            .await?;
+        let mut rows = stmt.query((entity.as_str().to_string(), id)).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Resolves a public id to the record it names. Lowercase ids are
+    /// accepted.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if `public_id` is not a ULID and `NotFound`
+    /// if no record has it.
+    pub async fn resolve(_ctx: &Ctx, mm: &ModelManager, public_id: &str) -> Result<PublicRef> {
+        let public_id = public_id.trim().to_ascii_uppercase();
+        if !is_ulid(&public_id) {
+            return Err(Error::InvalidInput(format!(
+                "'{public_id}' is not a public id (expected a 26-character ULID)"
+            )));
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT p.entity, p.entity_id,
+                    CASE p.entity
+                        WHEN 'project' THEN p.entity_id
+                        WHEN 'agent' THEN (SELECT project_id FROM agents WHERE id = p.entity_id)
+                        ELSE (SELECT project_id FROM messages WHERE id = p.entity_id)
+                    END
+                FROM public_ids AS p
+                WHERE p.public_id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([public_id.clone()]).await?;
+        let Some(row) = rows.next().await? else {
+            return Err(Error::NotFound);
+        };
+        let entity: String = row.get(0)?;
+        Ok(PublicRef {
+            public_id,
+            entity: PublicEntity::parse(&entity)?,
+            id: row.get(1)?,
+            project_id: row.get(2)?,
+        })
+    }
+}
@@ -0,0 +1,119 @@
+//! Per-recipient read receipts of a single message.
+//!
+//! [`ReceiptBmc::get`] lists when each recipient of a message read and
+//! acknowledged it, so a sender can see which agents have processed an
+//! ack-required message without fetching the whole thread. Unlike
+//! [`crate::model::broadcast_status::BroadcastStatusBmc`] it keeps the
+//! recipients in one list and reports read progress as well as acks.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::message::MessageBmc;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::parse_timestamp_opt;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// When one recipient read and acknowledged a message.
+///
+/// # Fields
+///
+/// - `recipient_type` - "to", "cc", or "bcc"
+/// - `read_ts` - `None` until the recipient marks the message read
+/// - `ack_ts` - `None` until the recipient acknowledges it
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageReceipt {
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub recipient_type: String,
+    pub read_ts: Option<NaiveDateTime>,
+    pub ack_ts: Option<NaiveDateTime>,
+}
+
+/// Read receipts of one message.
+///
+/// # Fields
+///
+/// - `read_count` - Recipients that read the message
+/// - `acked_count` - Recipients that acknowledged it
+/// - `receipts` - One entry per recipient, ordered by type then name
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageReceipts {
+    pub message_id: MessageId,
+    pub project_id: ProjectId,
+    pub sender_id: AgentId,
+    pub sender_name: String,
+    pub subject: String,
+    pub ack_required: bool,
+    pub created_ts: NaiveDateTime,
+    pub read_count: usize,
+    pub acked_count: usize,
+    pub receipts: Vec<MessageReceipt>,
+}
+
+impl MessageReceipts {
+    /// Recipients that have not acknowledged yet, by name.
+    pub fn unacked(&self) -> impl Iterator<Item = &str> {
+        self.receipts
+            .iter()
+            .filter(|r| r.ack_ts.is_none())
+            .map(|r| r.agent_name.as_str())
+    }
+}
+
+/// Backend Model Controller for message read receipts.
+pub struct ReceiptBmc;
+
+impl ReceiptBmc {
+    /// Lists each recipient's read and ack timestamps for a message.
+    ///
+    /// # Errors
+    /// Returns `MessageNotFound` if the message does not exist.
+    pub async fn get(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<MessageReceipts> {
+        let message = MessageBmc::get(ctx, mm, message_id).await?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT mr.agent_id, a.name, mr.recipient_type, mr.read_ts, mr.ack_ts
+                FROM message_recipients AS mr
+                JOIN agents AS a ON a.id = mr.agent_id
+                WHERE mr.message_id = ?
+                ORDER BY CASE mr.recipient_type WHEN 'to' THEN 0 WHEN 'cc' THEN 1 ELSE 2 END,
+                         a.name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+
+        let mut receipts = Vec::new();
+        while let Some(row) = rows.next().await? {
+            receipts.push(MessageReceipt {
+                agent_id: AgentId::new(row.get(0)?),
+                agent_name: row.get(1)?,
+                recipient_type: row.get(2)?,
+                read_ts: parse_timestamp_opt(row.get(3)?, "message_recipients.read_ts"),
+                ack_ts: parse_timestamp_opt(row.get(4)?, "message_recipients.ack_ts"),
+            });
+        }
+
+        Ok(MessageReceipts {
+            message_id,
+            project_id: ProjectId::new(message.project_id),
+            sender_id: AgentId::new(message.sender_id),
+            sender_name: message.sender_name,
+            subject: message.subject,
+            ack_required: message.ack_required,
+            created_ts: message.created_ts,
+            read_count: receipts.iter().filter(|r| r.read_ts.is_some()).count(),
+            acked_count: receipts.iter().filter(|r| r.ack_ts.is_some()).count(),
+            receipts,
+        })
+    }
+}
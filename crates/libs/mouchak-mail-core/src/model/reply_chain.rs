@@ -0,0 +1,232 @@
+//! Explicit reply chains within threads.
+//!
+//! A thread id groups messages but says nothing about which message
+//! answers which. When an agent replies to a message, [`ReplyChainBmc::link`]
+//! records the message it answers (`in_reply_to`), and
+//! [`ReplyChainBmc::thread_tree`] turns a thread into a tree of replies so
+//! UIs can render nested conversations.
+//!
+//! Messages sent into a thread without replying to a particular message are
+//! roots of the tree, as are replies whose original is in another thread
+//! or project.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::message::{Message, MessageBmc};
+use crate::types::{MessageId, ProjectId};
+use crate::{Error, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A message in a thread tree with the replies to it.
+///
+/// # Fields
+///
+/// - `in_reply_to` - The message this one answers, if recorded
+/// - `depth` - 0 for roots, 1 for direct replies to a root, ...
+/// - `replies` - Direct replies, oldest first
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadNode {
+    #[serde(flatten)]
+    pub message: Message,
+    pub in_reply_to: Option<i64>,
+    pub depth: usize,
+    pub replies: Vec<ThreadNode>,
+}
+
+/// The reply structure of a thread.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadTree {
+    pub project_id: ProjectId,
+    pub thread_id: String,
+    pub message_count: usize,
+    /// Messages that reply to nothing in this thread, oldest first
+    pub roots: Vec<ThreadNode>,
+}
+
+/// Backend Model Controller for reply chains.
+pub struct ReplyChainBmc;
+
+impl ReplyChainBmc {
+    /// Records that `message_id` replies to `in_reply_to`.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if a message would reply to itself.
+    pub async fn link(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        in_reply_to: i64,
+    ) -> Result<()> {
+        if message_id == MessageId::new(in_reply_to) {
+            return Err(Error::InvalidInput(
+                "A message cannot reply to itself".into(),
+            ));
+        }
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO message_replies (message_id, in_reply_to) VALUES (?, ?)
+                ON CONFLICT(message_id) DO UPDATE SET in_reply_to = excluded.in_reply_to
+                "#,
+            )
+            .await?;
+        stmt.execute((message_id.get(), in_reply_to)).await?;
+        Ok(())
+    }
+
+    /// The message `message_id` replies to, if recorded.
+    pub async fn in_reply_to(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<Option<i64>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT in_reply_to FROM message_replies WHERE message_id = ?")
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Builds the reply tree of a thread.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the thread has no messages.
+    pub async fn thread_tree(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<ThreadTree> {
+        let messages = MessageBmc::list_by_thread(ctx, mm, project_id, thread_id).await?;
+        if messages.is_empty() {
+            return Err(Error::NotFound);
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT r.message_id, r.in_reply_to
+                FROM message_replies AS r
+                JOIN messages AS m ON m.id = r.message_id
+                WHERE m.project_id = ?1 AND m.thread_id = ?2
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        let mut parents = HashMap::new();
+        while let Some(row) = rows.next().await? {
+            parents.insert(row.get::<i64>(0)?, row.get::<i64>(1)?);
+        }
+
+        Ok(ThreadTree {
+            project_id,
+            thread_id: thread_id.to_string(),
+            message_count: messages.len(),
+            roots: build_tree(messages, &parents),
+        })
+    }
+}
+
+/// Nests `messages` (in thread order) under the messages they reply to.
+///
+/// A reply is only nested under a message listed before it, which keeps
+/// the result a tree even if links form a cycle. Built without recursion
+/// so long reply chains cannot overflow the stack.
+fn build_tree(messages: Vec<Message>, parents: &HashMap<i64, i64>) -> Vec<ThreadNode> {
+    let mut position = HashMap::new();
+    let mut parent_of = Vec::with_capacity(messages.len());
+    let mut depth = Vec::with_capacity(messages.len());
+    for (i, message) in messages.iter().enumerate() {
+        let parent = parents
+            .get(&message.id)
+            .and_then(|p| position.get(p).copied());
+        let d = parent.map_or(0, |p: usize| depth[p] + 1);
+        parent_of.push(parent);
+        depth.push(d);
+        position.insert(message.id, i);
+    }
+
+    // Children come after their parent, so walking backwards finishes every
+    // subtree before its root is reached.
+    let mut replies: Vec<Vec<ThreadNode>> = vec![Vec::new(); messages.len()];
+    let mut roots = Vec::new();
+    for (i, message) in messages.into_iter().enumerate().rev() {
+        let mut own_replies = std::mem::take(&mut replies[i]);
+        own_replies.reverse();
+        let node = ThreadNode {
+            in_reply_to: parents.get(&message.id).copied(),
+            message,
+            depth: depth[i],
+            replies: own_replies,
+        };
+        match parent_of[i] {
+            Some(p) => replies[p].push(node),
+            None => roots.push(node),
+        }
+    }
+    roots.reverse();
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: i64) -> Message {
+        Message {
+            id,
+            project_id: 1,
+            sender_id: 1,
+            thread_id: Some("t".into()),
+            subject: format!("m{id}"),
+            body_md: String::new(),
+            importance: "normal".into(),
+            ack_required: false,
+            created_ts: Default::default(),
+            attachments: Vec::new(),
+            sender_name: "alice".into(),
+            public_id: String::new(),
+        }
+    }
+
+    fn shape(nodes: &[ThreadNode]) -> String {
+        nodes
+            .iter()
+            .map(|n| {
+                if n.replies.is_empty() {
+                    n.message.id.to_string()
+                } else {
+                    format!("{}({})", n.message.id, shape(&n.replies))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let messages = (1..=6).map(message).collect();
+        // 2 and 3 answer 1, 4 answers 2, 6 answers a message elsewhere
+        let parents = HashMap::from([(2, 1), (3, 1), (4, 2), (6, 99)]);
+        let roots = build_tree(messages, &parents);
+        assert_eq!(shape(&roots), "1(2(4) 3) 5 6");
+        assert_eq!(roots[0].replies[0].replies[0].depth, 2);
+        assert_eq!(roots[2].in_reply_to, Some(99));
+        assert_eq!(roots[2].depth, 0);
+    }
+
+    #[test]
+    fn test_build_tree_ignores_cycles() {
+        let messages = (1..=2).map(message).collect();
+        let parents = HashMap::from([(1, 2), (2, 1)]);
+        let roots = build_tree(messages, &parents);
+        assert_eq!(shape(&roots), "1(2)");
+    }
+}
@@ -0,0 +1,339 @@
+//! Reply deadlines negotiated between a sender and its recipients.
+//!
+//! A sender can ask the recipients of a message to respond by a given time
+//! ([`ReplyDeadlineBmc::request`]). Each recipient then accepts that time or
+//! proposes another one ([`ReplyDeadlineBmc::respond`]); either way the
+//! deadline becomes agreed and the recipient meets it by acknowledging the
+//! message.
+//!
+//! Agreed deadlines feed ack escalation: a recipient with an agreed deadline
+//! is escalated once that deadline passes rather than after the server's
+//! fixed threshold (see [`crate::model::message::MessageBmc::list_overdue_acks`]).
+//! Requested deadlines nobody answered keep the fixed threshold.
+//!
+//! Responses are recorded in the event log as `deadline.accepted` and
+//! `deadline.proposed`.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::event_log::{EventForCreate, EventLogBmc};
+use crate::model::listing::parse_duration_secs;
+use crate::model::message::MessageBmc;
+use crate::model::time_travel;
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, MessageId};
+use crate::utils::clock::{self, ClientTime};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// One recipient's deadline for responding to a message.
+///
+/// # Fields
+///
+/// - `requested_ts` - Deadline the sender asked for
+/// - `deadline_ts` - Deadline in force: the requested one, or the
+///   recipient's proposal
+/// - `status` - `requested` until the recipient responds, then `accepted`
+///   or `proposed`
+/// - `acked` - Whether the recipient has acknowledged the message, which
+///   meets the deadline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplyDeadline {
+    pub message_id: i64,
+    pub subject: String,
+    pub sender_name: String,
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub requested_ts: NaiveDateTime,
+    pub deadline_ts: NaiveDateTime,
+    pub status: String,
+    pub responded_ts: Option<NaiveDateTime>,
+    pub acked: bool,
+}
+
+impl ReplyDeadline {
+    /// Whether the recipient has agreed to `deadline_ts`.
+    pub fn is_agreed(&self) -> bool {
+        self.status != "requested"
+    }
+}
+
+const SELECT_DEADLINES: &str = r#"
+    SELECT rd.message_id, m.subject, s.name, rd.agent_id, a.name, rd.requested_ts,
+           rd.deadline_ts, rd.status, rd.responded_ts, mr.ack_ts IS NOT NULL
+    FROM reply_deadlines rd
+    JOIN messages m ON m.id = rd.message_id
+    JOIN agents s ON s.id = m.sender_id
+    JOIN agents a ON a.id = rd.agent_id
+    LEFT JOIN message_recipients mr ON mr.message_id = rd.message_id AND mr.agent_id = rd.agent_id
+"#;
+
+/// Parses a deadline given as a duration from `now` (`4h`, `2d`) or as a
+/// timestamp (RFC 3339, ISO 8601, date, or Unix epoch; UTC when no offset).
+///
+/// `now` is the server clock. A timestamp slightly in the past is taken as
+/// client clock skew and moved forward with a warning (see
+/// [`clock::future_client_time`]).
+///
+/// # Errors
+/// Returns `InvalidInput` if `s` is neither, or is not after `now`.
+pub fn parse_deadline(s: &str, now: NaiveDateTime) -> Result<ClientTime> {
+    let s = s.trim();
+    // Durations end in a unit; bare numbers are epoch timestamps
+    let relative = if s.ends_with(|c: char| c.is_ascii_alphabetic()) {
+        parse_duration_secs(s).ok()
+    } else {
+        None
+    };
+    match relative {
+        Some(seconds) => {
+            let deadline = Duration::try_seconds(seconds)
+                .and_then(|d| now.checked_add_signed(d))
+                .ok_or_else(|| Error::InvalidInput(format!("Deadline '{}' is too far away", s)))?;
+            if deadline <= now {
+                return Err(Error::InvalidInput(format!(
+                    "Deadline '{}' is not in the future",
+                    s
+                )));
+            }
+            Ok(ClientTime {
+                value: deadline,
+                warning: None,
+            })
+        }
+        None => clock::future_client_time(
+            "Deadline",
+            time_travel::parse_timestamp(s)?.naive_utc(),
+            now,
+        ),
+    }
+}
+
+/// Backend Model Controller for reply deadlines.
+pub struct ReplyDeadlineBmc;
+
+impl ReplyDeadlineBmc {
+    /// Asks `recipients` to respond to a message by `deadline_ts`.
+    ///
+    /// Asking again resets each recipient's deadline to the new request.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if `deadline_ts` is not in the future.
+    pub async fn request(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        recipients: &[AgentId],
+        deadline_ts: NaiveDateTime,
+    ) -> Result<()> {
+        if deadline_ts <= clock::server_now() {
+            return Err(Error::InvalidInput(
+                "Reply deadline must be in the future".into(),
+            ));
+        }
+        let deadline_str = deadline_ts.format(TS_FORMAT).to_string();
+        let db = mm.db();
+        for agent_id in recipients {
+            let stmt = db
+                .prepare(
+                    r#"
+                    INSERT INTO reply_deadlines (message_id, agent_id, requested_ts, deadline_ts)
+                    VALUES (?1, ?2, ?3, ?3)
+                    ON CONFLICT(message_id, agent_id) DO UPDATE SET
+                        requested_ts = excluded.requested_ts,
+                        deadline_ts = excluded.deadline_ts,
+                        status = 'requested',
+                        responded_ts = NULL
+                    "#,
+                )
+                .await?;
+            stmt.execute((message_id.get(), agent_id.get(), deadline_str.as_str()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Accepts the requested deadline, or proposes `proposed_ts` instead.
+    ///
+    /// Either way the recipient commits to the resulting deadline. A
+    /// recipient may respond again to change its answer.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if no deadline was requested from `agent_id`, and
+    /// `InvalidInput` if `proposed_ts` is not in the future.
+    pub async fn respond(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        agent_id: AgentId,
+        proposed_ts: Option<NaiveDateTime>,
+    ) -> Result<ReplyDeadline> {
+        let current = Self::get(ctx, mm, message_id, agent_id)
+            .await?
+            .ok_or(Error::NotFound)?;
+        let now = clock::server_now();
+        let (status, deadline_ts) = match proposed_ts {
+            Some(ts) if ts <= now => {
+                return Err(Error::InvalidInput(
+                    "Proposed deadline must be in the future".into(),
+                ));
+            }
+            Some(ts) => ("proposed", ts),
+            None => ("accepted", current.requested_ts),
+        };
+
+        let deadline_str = deadline_ts.format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE reply_deadlines SET status = ?, deadline_ts = ?, responded_ts = ?
+                WHERE message_id = ? AND agent_id = ?
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            status,
+            deadline_str.as_str(),
+            now.format(TS_FORMAT).to_string(),
+            message_id.get(),
+            agent_id.get(),
+        ))
+        .await?;
+
+        let message = MessageBmc::get(ctx, mm, message_id).await?;
+        EventLogBmc::record(
+            ctx,
+            mm,
+            EventForCreate::new(
+                &format!("deadline.{}", status),
+                Some(message.project_id),
+                Some(&current.agent_name),
+                serde_json::json!({
+                    "message_id": message_id,
+                    "sender": current.sender_name,
+                    "requested_ts": current.requested_ts.format(TS_FORMAT).to_string(),
+                    "deadline_ts": deadline_str,
+                }),
+            ),
+        )
+        .await;
+
+        Self::get(ctx, mm, message_id, agent_id)
+            .await?
+            .ok_or(Error::NotFound)
+    }
+
+    /// The deadline requested from one recipient of a message, if any.
+    pub async fn get(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        agent_id: AgentId,
+    ) -> Result<Option<ReplyDeadline>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE rd.message_id = ? AND rd.agent_id = ?",
+                SELECT_DEADLINES
+            ))
+            .await?;
+        let mut rows = stmt.query((message_id.get(), agent_id.get())).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Lists the deadlines requested for a message, by recipient name.
+    pub async fn list_for_message(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+    ) -> Result<Vec<ReplyDeadline>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE rd.message_id = ? ORDER BY a.name",
+                SELECT_DEADLINES
+            ))
+            .await?;
+        let mut rows = stmt.query([message_id.get()]).await?;
+        let mut deadlines = Vec::new();
+        while let Some(row) = rows.next().await? {
+            deadlines.push(Self::from_row(&row)?);
+        }
+        Ok(deadlines)
+    }
+
+    /// Lists the deadlines an agent still owes (not yet acknowledged),
+    /// soonest first.
+    pub async fn list_open_for_agent(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        agent_id: AgentId,
+    ) -> Result<Vec<ReplyDeadline>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE rd.agent_id = ? AND mr.ack_ts IS NULL ORDER BY rd.deadline_ts, rd.message_id",
+                SELECT_DEADLINES
+            ))
+            .await?;
+        let mut rows = stmt.query([agent_id.get()]).await?;
+        let mut deadlines = Vec::new();
+        while let Some(row) = rows.next().await? {
+            deadlines.push(Self::from_row(&row)?);
+        }
+        Ok(deadlines)
+    }
+
+    fn from_row(row: &Row) -> Result<ReplyDeadline> {
+        let requested_ts: String = row.get(5)?;
+        let deadline_ts: String = row.get(6)?;
+        Ok(ReplyDeadline {
+            message_id: row.get(0)?,
+            subject: row.get(1)?,
+            sender_name: row.get(2)?,
+            agent_id: AgentId::new(row.get(3)?),
+            agent_name: row.get(4)?,
+            requested_ts: parse_timestamp(&requested_ts, "reply_deadlines.requested_ts"),
+            deadline_ts: parse_timestamp(&deadline_ts, "reply_deadlines.deadline_ts"),
+            status: row.get(7)?,
+            responded_ts: parse_timestamp_opt(row.get(8)?, "reply_deadlines.responded_ts"),
+            acked: row.get::<i64>(9)? != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_deadline() {
+        let now = NaiveDateTime::parse_from_str("2025-03-01 12:00:00", TS_FORMAT).unwrap();
+        assert_eq!(
+            parse_deadline("4h", now).unwrap().value,
+            NaiveDateTime::parse_from_str("2025-03-01 16:00:00", TS_FORMAT).unwrap()
+        );
+        assert_eq!(
+            parse_deadline("2025-03-02T09:30:00Z", now).unwrap().value,
+            NaiveDateTime::parse_from_str("2025-03-02 09:30:00", TS_FORMAT).unwrap()
+        );
+        assert!(parse_deadline("2025-02-28", now).is_err());
+        assert!(parse_deadline("-4h", now).is_err());
+
+        // A client clock a minute behind
+        let skewed = parse_deadline("2025-03-01T11:59:00Z", now).unwrap();
+        assert_eq!(
+            skewed.value,
+            NaiveDateTime::parse_from_str("2025-03-01 12:05:00", TS_FORMAT).unwrap()
+        );
+        assert!(skewed.warning.is_some());
+        assert!(parse_deadline("soon", now).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Reservation sets: multi-path reservations acquired and released as a unit.
+//!
+//! [`ReservationSetBmc::acquire`] checks every requested path against other
+//! agents' active reservations before reserving anything. If any path
+//! clashes, nothing is reserved and the clashes are reported per path with
+//! the holding agent. Otherwise all paths are inserted in one statement and
+//! grouped under a reservation-set id, which [`ReservationSetBmc::release`]
+//! releases in one go.
+//!
+//! Members are ordinary `file_reservations` rows, so listing, renewing and
+//! single-reservation release keep working on them.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::file_reservation::{FileReservationBmc, FileReservationForCreate};
+use crate::model::reservation_watcher::ReleaseCause;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::TS_FORMAT;
+use crate::utils::pathspec::paths_conflict;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Input data to reserve several paths at once.
+///
+/// # Fields
+///
+/// - `paths` - Path patterns to reserve (glob); duplicates are reserved once
+/// - `exclusive` - True for write access on every path
+/// - `reason` - Justification, shared by all members
+/// - `expires_ts` - Expiration time, shared by all members
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReservationSetForCreate {
+    pub project_id: ProjectId,
+    pub agent_id: AgentId,
+    pub paths: Vec<String>,
+    pub exclusive: bool,
+    pub reason: String,
+    pub expires_ts: NaiveDateTime,
+}
+
+/// A requested path that overlaps another agent's active reservation.
+///
+/// # Fields
+///
+/// - `path` - Requested path pattern
+/// - `reservation_id` / `path_pattern` - The reservation it overlaps
+/// - `agent_id` / `agent_name` - Agent holding that reservation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReservationClash {
+    pub path: String,
+    pub reservation_id: i64,
+    pub path_pattern: String,
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub exclusive: bool,
+    pub expires_ts: NaiveDateTime,
+}
+
+/// Result of [`ReservationSetBmc::acquire`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ReservationSetOutcome {
+    /// Every path was reserved; `reservation_ids[i]` holds `paths[i]`, with
+    /// `paths` deduplicated in request order.
+    Granted {
+        set_id: i64,
+        paths: Vec<String>,
+        reservation_ids: Vec<i64>,
+    },
+    /// Nothing was reserved because of these clashes.
+    Conflicted { clashes: Vec<ReservationClash> },
+}
+
+/// Backend Model Controller for reservation sets.
+pub struct ReservationSetBmc;
+
+impl ReservationSetBmc {
+    /// Reserves all paths of `rs_c`, or none of them.
+    ///
+    /// A path clashes with another agent's unexpired reservation when the
+    /// patterns overlap and either side is exclusive. The caller's own
+    /// reservations never clash.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` when `paths` is empty.
+    pub async fn acquire(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        rs_c: ReservationSetForCreate,
+    ) -> Result<ReservationSetOutcome> {
+        let mut paths: Vec<String> = Vec::with_capacity(rs_c.paths.len());
+        for path in &rs_c.paths {
+            if !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        }
+        if paths.is_empty() {
+            return Err(Error::InvalidInput(
+                "A reservation set needs at least one path".into(),
+            ));
+        }
+
+        // Checked and inserted under one lock so two sets cannot both pass
+        // the check for overlapping paths
+        let _guard = mm.reservation_lock.lock().await;
+
+        let clashes = Self::find_clashes(ctx, mm, &rs_c, &paths).await?;
+        if !clashes.is_empty() {
+            return Ok(ReservationSetOutcome::Conflicted { clashes });
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "INSERT INTO reservation_sets (project_id, agent_id, reason) VALUES (?, ?, ?) RETURNING id",
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                rs_c.project_id.get(),
+                rs_c.agent_id.get(),
+                rs_c.reason.as_str(),
+            ))
+            .await?;
+        let set_id: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => {
+                return Err(Error::InvalidInput(
+                    "Failed to create reservation set".into(),
+                ));
+            }
+        };
+
+        // One statement, so the members are inserted all-or-nothing
+        let expires_ts = rs_c.expires_ts.format(TS_FORMAT).to_string();
+        let mut query = String::from(
+            "INSERT INTO file_reservations (project_id, agent_id, path_pattern, exclusive, reason, expires_ts) VALUES ",
+        );
+        let mut params: Vec<libsql::Value> = Vec::with_capacity(paths.len() * 6);
+        for (i, path) in paths.iter().enumerate() {
+            if i > 0 {
+                query.push_str(", ");
+            }
+            query.push_str("(?, ?, ?, ?, ?, ?)");
+            params.push(rs_c.project_id.get().into());
+            params.push(rs_c.agent_id.get().into());
+            params.push(path.clone().into());
+            params.push(i64::from(rs_c.exclusive).into());
+            params.push(rs_c.reason.clone().into());
+            params.push(expires_ts.clone().into());
+        }
+        query.push_str(" RETURNING id");
+        let stmt = db.prepare(&query).await?;
+        let mut rows = stmt
+            .query(libsql::params::Params::Positional(params))
+            .await?;
+        let mut reservation_ids = Vec::with_capacity(paths.len());
+        while let Some(row) = rows.next().await? {
+            reservation_ids.push(row.get::<i64>(0)?);
+        }
+        // RETURNING yields rows in insert order, but sort to be safe
+        reservation_ids.sort_unstable();
+        mm.reservation_cache.clear();
+
+        let mut query =
+            String::from("INSERT INTO reservation_set_members (set_id, reservation_id) VALUES ");
+        let mut params: Vec<libsql::Value> = Vec::with_capacity(reservation_ids.len() * 2);
+        for (i, id) in reservation_ids.iter().enumerate() {
+            if i > 0 {
+                query.push_str(", ");
+            }
+            query.push_str("(?, ?)");
+            params.push(set_id.into());
+            params.push((*id).into());
+        }
+        let linked = match db.prepare(&query).await {
+            Ok(stmt) => {
+                stmt.execute(libsql::params::Params::Positional(params))
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = linked {
+            // Undo the members rather than leave untracked reservations behind
+            Self::mark_released(mm, set_id, &reservation_ids).await?;
+            return Err(e);
+        }
+
+        for (path, id) in paths.iter().zip(&reservation_ids) {
+            let fr_c = FileReservationForCreate {
+                project_id: rs_c.project_id,
+                agent_id: rs_c.agent_id,
+                path_pattern: path.clone(),
+                exclusive: rs_c.exclusive,
+                reason: rs_c.reason.clone(),
+                expires_ts: rs_c.expires_ts,
+            };
+            FileReservationBmc::archive_created(ctx, mm, *id, &fr_c).await?;
+        }
+
+        Ok(ReservationSetOutcome::Granted {
+            set_id,
+            paths,
+            reservation_ids,
+        })
+    }
+
+    /// Lists the ids of a set's reservations that are still unreleased.
+    pub async fn list_active_reservation_ids(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        set_id: i64,
+    ) -> Result<Vec<i64>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT fr.id
+                FROM reservation_set_members AS m
+                JOIN file_reservations AS fr ON fr.id = m.reservation_id
+                WHERE m.set_id = ? AND fr.released_ts IS NULL
+                ORDER BY fr.id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([set_id]).await?;
+        let mut ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ids.push(row.get(0)?);
+        }
+        Ok(ids)
+    }
+
+    /// Releases every still-active reservation of a set.
+    ///
+    /// With `agent_id`, only a set held by that agent is released.
+    ///
+    /// # Returns
+    /// The number of reservations released.
+    ///
+    /// # Errors
+    /// Returns `FileReservationNotFound` if the set does not exist or is
+    /// held by another agent.
+    pub async fn release(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        set_id: i64,
+        agent_id: Option<AgentId>,
+    ) -> Result<usize> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT agent_id FROM reservation_sets WHERE id = ?")
+            .await?;
+        let mut rows = stmt.query([set_id]).await?;
+        let holder: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => {
+                return Err(Error::FileReservationNotFound(format!(
+                    "reservation set {}",
+                    set_id
+                )));
+            }
+        };
+        if agent_id.is_some_and(|a| a.get() != holder) {
+            return Err(Error::FileReservationNotFound(format!(
+                "reservation set {}",
+                set_id
+            )));
+        }
+
+        let ids = Self::list_active_reservation_ids(ctx, mm, set_id).await?;
+        Self::mark_released(mm, set_id, &ids).await?;
+        for id in &ids {
+            FileReservationBmc::send_release_receipts(ctx, mm, *id, ReleaseCause::Released).await;
+        }
+        Ok(ids.len())
+    }
+
+    /// Clashes of `paths` with other agents' unexpired reservations.
+    async fn find_clashes(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        rs_c: &ReservationSetForCreate,
+        paths: &[String],
+    ) -> Result<Vec<ReservationClash>> {
+        let active = FileReservationBmc::list_active_for_project(ctx, mm, rs_c.project_id).await?;
+        let now = chrono::Utc::now().naive_utc();
+        let mut agent_names: HashMap<AgentId, String> = HashMap::new();
+        let mut clashes = Vec::new();
+        for path in paths {
+            for res in &active {
+                if res.agent_id == rs_c.agent_id
+                    || res.expires_ts <= now
+                    || !(res.exclusive || rs_c.exclusive)
+                    || !paths_conflict(&res.path_pattern, path)
+                {
+                    continue;
+                }
+                let agent_name = match agent_names.get(&res.agent_id) {
+                    Some(name) => name.clone(),
+                    None => {
+                        let name = AgentBmc::get(ctx, mm, res.agent_id)
+                            .await
+                            .map(|a| a.name)
+                            .unwrap_or_else(|_| format!("agent#{}", res.agent_id));
+                        agent_names.insert(res.agent_id, name.clone());
+                        name
+                    }
+                };
+                clashes.push(ReservationClash {
+                    path: path.clone(),
+                    reservation_id: res.id,
+                    path_pattern: res.path_pattern.clone(),
+                    agent_id: res.agent_id,
+                    agent_name,
+                    exclusive: res.exclusive,
+                    expires_ts: res.expires_ts,
+                });
+            }
+        }
+        Ok(clashes)
+    }
+
+    /// Marks the given members and the set itself released in one statement each.
+    async fn mark_released(mm: &ModelManager, set_id: i64, ids: &[i64]) -> Result<()> {
+        let db = mm.db();
+        let now = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        if !ids.is_empty() {
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            let query = format!(
+                "UPDATE file_reservations SET released_ts = ? WHERE released_ts IS NULL AND id IN ({})",
+                placeholders
+            );
+            let mut params: Vec<libsql::Value> = Vec::with_capacity(ids.len() + 1);
+            params.push(now.clone().into());
+            params.extend(ids.iter().map(|&id| libsql::Value::from(id)));
+            let stmt = db.prepare(&query).await?;
+            stmt.execute(libsql::params::Params::Positional(params))
+                .await?;
+            mm.reservation_cache.clear();
+        }
+        let stmt = db
+            .prepare(
+                "UPDATE reservation_sets SET released_ts = ? WHERE id = ? AND released_ts IS NULL",
+            )
+            .await?;
+        stmt.execute((now, set_id)).await?;
+        Ok(())
+    }
+}
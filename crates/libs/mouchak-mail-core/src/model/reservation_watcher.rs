@@ -0,0 +1,305 @@
+//! Release receipts for file reservations.
+//!
+//! Agents blocked on a reserved path can register as watchers of the
+//! blocking reservation. When the reservation is released (explicitly,
+//! forcibly, or by TTL expiry) each watcher receives a single
+//! `[RELEASED] <path>` message from the holder, so blocked agents no longer
+//! need to poll `list_file_reservations`.
+//!
+//! | Trigger | Notified by |
+//! |---------|-------------|
+//! | `release` / `release_by_path` / `force_release` | [`FileReservationBmc`] |
+//! | TTL expiry | [`ReservationWatcherBmc::notify_expired`] (background sweep) |
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::file_reservation::{FileReservation, FileReservationBmc};
+use crate::model::message::{MessageBmc, MessageForCreate};
+use crate::model::message_catalog::MessageCatalogBmc;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::pathspec::paths_conflict;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Interval between availability checks in [`ReservationWatcherBmc::wait_for_path`].
+pub const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Why a reservation stopped being held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseCause {
+    Released,
+    ForceReleased,
+    Expired,
+}
+
+impl ReleaseCause {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Released => "released",
+            Self::ForceReleased => "force released",
+            Self::Expired => "expired",
+        }
+    }
+
+    /// Key of the cause's wording in the message catalog.
+    fn catalog_key(&self) -> &'static str {
+        match self {
+            Self::Released => "reservation.cause.released",
+            Self::ForceReleased => "reservation.cause.force_released",
+            Self::Expired => "reservation.cause.expired",
+        }
+    }
+}
+
+/// Outcome of waiting for a path to become available.
+///
+/// # Fields
+///
+/// - `path` - The path that was waited on
+/// - `available` - True if no other agent holds a conflicting reservation
+/// - `waited_ms` - How long the call blocked
+/// - `blocking` - Reservations still blocking the path (empty when available)
+/// - `watching` - Reservation IDs the agent is now watching for a release receipt
+#[derive(Debug, Clone, Serialize)]
+pub struct PathWaitOutcome {
+    pub path: String,
+    pub available: bool,
+    pub waited_ms: u64,
+    pub blocking: Vec<FileReservation>,
+    pub watching: Vec<i64>,
+}
+
+/// Backend Model Controller for reservation release receipts.
+pub struct ReservationWatcherBmc;
+
+impl ReservationWatcherBmc {
+    /// Registers agents to be notified when a reservation is released.
+    ///
+    /// Registering the same agent twice is a no-op. The holder itself is
+    /// never registered.
+    ///
+    /// # Errors
+    /// Returns `FileReservationNotFound` if the reservation does not exist.
+    pub async fn add_watchers(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        reservation_id: i64,
+        agent_ids: &[AgentId],
+    ) -> Result<usize> {
+        let reservation = FileReservationBmc::get(ctx, mm, reservation_id).await?;
+
+        let db = mm.db();
+        let mut added = 0;
+        for agent_id in agent_ids {
+            if *agent_id == reservation.agent_id {
+                continue;
+            }
+            let stmt = db
+                .prepare(
+                    r#"
+                    INSERT OR IGNORE INTO file_reservation_watchers (reservation_id, agent_id)
+                    VALUES (?, ?)
+                    "#,
+                )
+                .await?;
+            added += stmt.execute((reservation_id, agent_id.get())).await?;
+        }
+        Ok(added)
+    }
+
+    /// Lists agent IDs that have not yet received a receipt for a reservation.
+    pub async fn list_pending(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        reservation_id: i64,
+    ) -> Result<Vec<AgentId>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT agent_id FROM file_reservation_watchers
+                WHERE reservation_id = ? AND notified_ts IS NULL
+                ORDER BY id ASC
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([reservation_id]).await?;
+
+        let mut agent_ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            agent_ids.push(AgentId::new(row.get(0)?));
+        }
+        Ok(agent_ids)
+    }
+
+    /// Sends a release receipt to every pending watcher of a reservation.
+    ///
+    /// The receipt is a single message from the holder to all pending
+    /// watchers. Watchers are marked notified so each receives at most one
+    /// receipt per reservation.
+    ///
+    /// # Returns
+    /// The ID of the receipt message, or `None` if nobody was waiting.
+    pub async fn notify_release(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        reservation_id: i64,
+        cause: ReleaseCause,
+    ) -> Result<Option<MessageId>> {
+        let watchers = Self::list_pending(ctx, mm, reservation_id).await?;
+        if watchers.is_empty() {
+            return Ok(None);
+        }
+
+        let reservation = FileReservationBmc::get(ctx, mm, reservation_id).await?;
+        let catalog = MessageCatalogBmc::for_project(ctx, mm, reservation.project_id).await?;
+        let msg_c = MessageForCreate {
+            project_id: reservation.project_id,
+            sender_id: reservation.agent_id,
+            recipient_ids: watchers,
+            cc_ids: None,
+            bcc_ids: None,
+            subject: catalog.render(
+                "reservation.released.subject",
+                &[("path", &reservation.path_pattern)],
+            ),
+            body_md: catalog.render(
+                "reservation.released.body",
+                &[
+                    ("reservation_id", &reservation.id.to_string()),
+                    ("path", &reservation.path_pattern),
+                    ("cause", &catalog.render(cause.catalog_key(), &[])),
+                ],
+            ),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        };
+        let message_id = MessageBmc::create(ctx, mm, msg_c).await?;
+
+        let now_str = chrono::Utc::now()
+            .naive_utc()
+            .format(crate::utils::TS_FORMAT)
+            .to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE file_reservation_watchers SET notified_ts = ?
+                WHERE reservation_id = ? AND notified_ts IS NULL
+                "#,
+            )
+            .await?;
+        stmt.execute((now_str, reservation_id)).await?;
+
+        Ok(Some(message_id))
+    }
+
+    /// Sends receipts for reservations that expired while still watched.
+    ///
+    /// Intended to run periodically from a background task.
+    ///
+    /// # Returns
+    /// Number of receipts sent.
+    pub async fn notify_expired(ctx: &Ctx, mm: &ModelManager) -> Result<usize> {
+        let now_str = chrono::Utc::now()
+            .naive_utc()
+            .format(crate::utils::TS_FORMAT)
+            .to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT DISTINCT r.id
+                FROM file_reservations r
+                JOIN file_reservation_watchers w ON w.reservation_id = r.id
+                WHERE r.released_ts IS NULL AND r.expires_ts <= ? AND w.notified_ts IS NULL
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([now_str]).await?;
+
+        let mut expired = Vec::new();
+        while let Some(row) = rows.next().await? {
+            expired.push(row.get::<i64>(0)?);
+        }
+
+        let mut sent = 0;
+        for reservation_id in expired {
+            if Self::notify_release(ctx, mm, reservation_id, ReleaseCause::Expired)
+                .await?
+                .is_some()
+            {
+                sent += 1;
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Lists live reservations held by other agents that overlap `path`.
+    ///
+    /// A reservation is live if it is neither released nor past its TTL.
+    pub async fn find_blocking(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        path: &str,
+    ) -> Result<Vec<FileReservation>> {
+        let now = chrono::Utc::now().naive_utc();
+        let active = FileReservationBmc::list_active_for_project(ctx, mm, project_id).await?;
+        Ok(active
+            .into_iter()
+            .filter(|r| {
+                r.agent_id != agent_id
+                    && r.expires_ts > now
+                    && paths_conflict(&r.path_pattern, path)
+            })
+            .collect())
+    }
+
+    /// Blocks until `path` is free of other agents' reservations or `timeout`
+    /// elapses.
+    ///
+    /// When the path is still blocked at the deadline and `watch` is set, the
+    /// agent is registered as a watcher of every blocking reservation and will
+    /// receive a release receipt later.
+    pub async fn wait_for_path(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        path: &str,
+        timeout: Duration,
+        watch: bool,
+    ) -> Result<PathWaitOutcome> {
+        let started = Instant::now();
+        let deadline = started + timeout;
+
+        loop {
+            let blocking = Self::find_blocking(ctx, mm, project_id, agent_id, path).await?;
+            let now = Instant::now();
+
+            if blocking.is_empty() || now >= deadline {
+                let mut watching = Vec::new();
+                if watch {
+                    for reservation in &blocking {
+                        Self::add_watchers(ctx, mm, reservation.id, &[agent_id]).await?;
+                        watching.push(reservation.id);
+                    }
+                }
+                return Ok(PathWaitOutcome {
+                    path: path.to_string(),
+                    available: blocking.is_empty(),
+                    waited_ms: started.elapsed().as_millis() as u64,
+                    blocking,
+                    watching,
+                });
+            }
+
+            tokio::time::sleep(WAIT_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+}
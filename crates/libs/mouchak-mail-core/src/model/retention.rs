@@ -0,0 +1,548 @@
+//! Message retention.
+//!
+//! The `retention` config sets how long messages are kept (`max_age_days`)
+//! and how many a project keeps (`max_messages`). A project can override
+//! either limit, or the action, with [`RetentionBmc::set_policy`].
+//!
+//! [`RetentionBmc::run`], called by the server's retention job and the
+//! `retention run` CLI command, purges messages past the limits: first those
+//! older than the age limit, then the oldest beyond the cap. Messages still
+//! waiting for approval or held by a focus window are kept until delivered.
+//!
+//! Purged messages leave the database together with their deliveries,
+//! labels and other per-message rows. With the `archive` action their
+//! markdown copies stay in the git archive; `delete` removes those as well,
+//! though they remain in git history. Either way every purge is recorded in
+//! the project's archive as `retention/<timestamp>.json`, listing each
+//! message and why it went. The record is committed before any row is
+//! removed, so an interrupted purge is repeated rather than lost.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::project::ProjectBmc;
+use crate::store::git_store;
+use crate::types::ProjectId;
+use crate::utils::{TS_FORMAT, parse_timestamp};
+use crate::{Error, Result};
+use chrono::{Duration, NaiveDateTime, Utc};
+use mouchak_mail_common::config::RetentionAction;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Per-message tables without a delete trigger on `messages`; their rows
+/// must go before the messages they refer to.
+pub(super) const MESSAGE_TABLES: &[&str] = &[
+    "message_recipients",
+    "message_routing",
+    "deferred_deliveries",
+    "held_deliveries",
+    "pending_approvals",
+    "watcher_deliveries",
+    "sla_violations",
+    "reply_deadlines",
+    "message_delegations",
+    "message_tickets",
+    "message_labels",
+    "parked_messages",
+];
+
+/// Messages deleted per statement.
+const DELETE_CHUNK: usize = 500;
+
+/// Messages of a project that retention may purge, oldest first once a
+/// filter and order are appended. `?1` is the project id.
+const PURGEABLE_SQL: &str = r#"
+    SELECT m.id, m.thread_id, COALESCE(a.name, ''), m.subject, m.created_ts
+    FROM messages AS m
+    LEFT JOIN agents AS a ON a.id = m.sender_id
+    WHERE m.project_id = ?1
+      AND NOT EXISTS (
+          SELECT 1 FROM pending_approvals AS p
+          WHERE p.message_id = m.id AND p.status = 'pending'
+      )
+      AND NOT EXISTS (SELECT 1 FROM held_deliveries AS h WHERE h.message_id = m.id)
+      AND NOT EXISTS (
+          SELECT 1 FROM deferred_deliveries AS d
+          WHERE d.message_id = m.id AND d.delivered_ts IS NULL
+      )
+"#;
+
+/// Retention limits in effect for a project.
+///
+/// # Fields
+///
+/// - `max_age_days` - `None` keeps messages regardless of age
+/// - `max_messages` - `None` means no cap
+/// - `updated_ts` - `None` while the project follows the `retention` config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RetentionPolicy {
+    pub project_id: ProjectId,
+    pub max_age_days: Option<u64>,
+    pub max_messages: Option<u64>,
+    pub action: RetentionAction,
+    pub updated_ts: Option<NaiveDateTime>,
+}
+
+/// Changes to a project's retention policy; `None` keeps the current
+/// value, `Some(0)` turns a limit off for the project.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionPolicyForUpdate {
+    pub max_age_days: Option<u64>,
+    pub max_messages: Option<u64>,
+    pub action: Option<RetentionAction>,
+}
+
+/// Why a message was purged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PurgeReason {
+    /// Older than `max_age_days`
+    Age,
+    /// Among the oldest of a project over `max_messages`
+    Cap,
+}
+
+/// A message purged by retention, or that would be on a dry run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgedMessage {
+    pub id: i64,
+    pub thread_id: Option<String>,
+    pub sender_name: String,
+    pub subject: String,
+    pub created_ts: NaiveDateTime,
+    pub reason: PurgeReason,
+}
+
+/// What retention did to one project.
+///
+/// # Fields
+///
+/// - `audit_path` - Archive path of the purge record; `None` on a dry run
+/// - `archive_files_removed` - Message files removed from the git archive
+///   by the `delete` action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectPurge {
+    pub project_id: ProjectId,
+    pub project_slug: String,
+    pub action: RetentionAction,
+    pub messages: Vec<PurgedMessage>,
+    pub audit_path: Option<String>,
+    pub archive_files_removed: usize,
+}
+
+/// What [`RetentionBmc::run`] purged, per project with anything to purge.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RetentionReport {
+    pub projects: Vec<ProjectPurge>,
+    pub dry_run: bool,
+}
+
+impl RetentionReport {
+    /// Messages purged across all projects.
+    pub fn purged(&self) -> usize {
+        self.projects.iter().map(|p| p.messages.len()).sum()
+    }
+}
+
+/// Purge record committed to the git archive.
+#[derive(Serialize)]
+struct RetentionAudit<'a> {
+    purged_ts: String,
+    action: RetentionAction,
+    max_age_days: Option<u64>,
+    max_messages: Option<u64>,
+    messages: &'a [PurgedMessage],
+    removed_files: Vec<String>,
+}
+
+/// Backend Model Controller for message retention.
+pub struct RetentionBmc;
+
+impl RetentionBmc {
+    /// Returns the limits in effect for a project: its own policy where it
+    /// has one, the `retention` config otherwise.
+    pub async fn get_policy(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<RetentionPolicy> {
+        let config = &mm.app_config.retention;
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT max_age_days, max_messages, action, updated_ts
+                FROM retention_policies
+                WHERE project_id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(RetentionPolicy {
+                project_id,
+                max_age_days: limit(None, config.max_age_days),
+                max_messages: limit(None, config.max_messages),
+                action: config.action,
+                updated_ts: None,
+            });
+        };
+
+        let action = match row.get::<Option<String>>(2)? {
+            Some(action) => action.parse().map_err(Error::InvalidInput)?,
+            None => config.action,
+        };
+        Ok(RetentionPolicy {
+            project_id,
+            max_age_days: limit(row.get(0)?, config.max_age_days),
+            max_messages: limit(row.get(1)?, config.max_messages),
+            action,
+            updated_ts: Some(parse_timestamp(&row.get::<String>(3)?, "updated_ts")),
+        })
+    }
+
+    /// Sets a project's own retention limits or action.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if a limit does not fit the database.
+    pub async fn set_policy(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        policy_u: RetentionPolicyForUpdate,
+    ) -> Result<RetentionPolicy> {
+        ProjectBmc::get(ctx, mm, project_id).await?;
+        let max_age_days = stored("max_age_days", policy_u.max_age_days)?;
+        let max_messages = stored("max_messages", policy_u.max_messages)?;
+
+        let stmt = mm
+            .db()
+            .prepare(
+                r#"
+                INSERT INTO retention_policies (project_id, max_age_days, max_messages, action, updated_ts)
+                VALUES (?, ?, ?, ?, CURRENT_TIMESTAMP)
+                ON CONFLICT(project_id) DO UPDATE SET
+                    max_age_days = COALESCE(excluded.max_age_days, retention_policies.max_age_days),
+                    max_messages = COALESCE(excluded.max_messages, retention_policies.max_messages),
+                    action = COALESCE(excluded.action, retention_policies.action),
+                    updated_ts = excluded.updated_ts
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            project_id.get(),
+            max_age_days,
+            max_messages,
+            policy_u.action.map(|a| a.as_str()),
+        ))
+        .await?;
+
+        Self::get_policy(ctx, mm, project_id).await
+    }
+
+    /// Drops a project's own policy, returning it to the `retention` config.
+    ///
+    /// # Returns
+    /// Whether the project had a policy.
+    pub async fn clear_policy(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<bool> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM retention_policies WHERE project_id = ?")
+            .await?;
+        Ok(stmt.execute([project_id.get()]).await? > 0)
+    }
+
+    /// Messages of a project past its retention limits, oldest first.
+    pub async fn candidates(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<PurgedMessage>> {
+        let policy = Self::get_policy(ctx, mm, project_id).await?;
+        Self::select_candidates(ctx, mm, &policy).await
+    }
+
+    /// Purges every project's messages past its retention limits.
+    ///
+    /// With `dry_run` nothing is changed; the report lists what would be
+    /// purged.
+    pub async fn run(ctx: &Ctx, mm: &ModelManager, dry_run: bool) -> Result<RetentionReport> {
+        let mut report = RetentionReport {
+            projects: Vec::new(),
+            dry_run,
+        };
+        for project in ProjectBmc::list_all(ctx, mm).await? {
+            let policy = Self::get_policy(ctx, mm, project.id).await?;
+            let messages = Self::select_candidates(ctx, mm, &policy).await?;
+            if messages.is_empty() {
+                continue;
+            }
+            let mut purge = ProjectPurge {
+                project_id: project.id,
+                project_slug: project.slug,
+                action: policy.action,
+                messages,
+                audit_path: None,
+                archive_files_removed: 0,
+            };
+            if !dry_run {
+                Self::purge(mm, &policy, &mut purge).await?;
+            }
+            report.projects.push(purge);
+        }
+        if !dry_run && report.purged() > 0 {
+            info!(
+                "Retention purged {} message(s) from {} project(s)",
+                report.purged(),
+                report.projects.len()
+            );
+        }
+        Ok(report)
+    }
+
+    async fn select_candidates(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        policy: &RetentionPolicy,
+    ) -> Result<Vec<PurgedMessage>> {
+        let pid = policy.project_id.get();
+        let cutoff = policy.max_age_days.and_then(cutoff_ts);
+
+        let mut messages = match &cutoff {
+            Some(cutoff) => {
+                Self::select(
+                    mm,
+                    PurgeReason::Age,
+                    "AND m.created_ts < ?2 ORDER BY m.created_ts, m.id",
+                    vec![pid.into(), cutoff.as_str().into()],
+                )
+                .await?
+            }
+            None => Vec::new(),
+        };
+
+        if let Some(max_messages) = policy.max_messages {
+            let total = ProjectBmc::count_messages(ctx, mm, policy.project_id).await?;
+            let remaining = u64::try_from(total)
+                .unwrap_or(0)
+                .saturating_sub(messages.len() as u64);
+            let excess = remaining.saturating_sub(max_messages);
+            if excess > 0 {
+                // Messages past the age limit are already in the list; an
+                // empty cutoff matches every message
+                let newer_than = cutoff.unwrap_or_default();
+                let capped = Self::select(
+                    mm,
+                    PurgeReason::Cap,
+                    "AND m.created_ts >= ?2 ORDER BY m.created_ts, m.id LIMIT ?3",
+                    vec![
+                        pid.into(),
+                        newer_than.into(),
+                        i64::try_from(excess).unwrap_or(i64::MAX).into(),
+                    ],
+                )
+                .await?;
+                messages.extend(capped);
+            }
+        }
+        Ok(messages)
+    }
+
+    async fn select(
+        mm: &ModelManager,
+        reason: PurgeReason,
+        filter: &str,
+        params: Vec<libsql::Value>,
+    ) -> Result<Vec<PurgedMessage>> {
+        let db = mm.db();
+        let stmt = db.prepare(&format!("{} {}", PURGEABLE_SQL, filter)).await?;
+        let mut rows = stmt.query(params).await?;
+        let mut messages = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(4)?;
+            messages.push(PurgedMessage {
+                id: row.get(0)?,
+                thread_id: row.get(1)?,
+                sender_name: row.get(2)?,
+                subject: row.get(3)?,
+                created_ts: parse_timestamp(&created_ts, "created_ts"),
+                reason,
+            });
+        }
+        Ok(messages)
+    }
+
+    /// Records the purge in the git archive, removes the archived copies
+    /// for the `delete` action, then the rows.
+    async fn purge(
+        mm: &ModelManager,
+        policy: &RetentionPolicy,
+        purge: &mut ProjectPurge,
+    ) -> Result<()> {
+        let ids: HashSet<i64> = purge.messages.iter().map(|m| m.id).collect();
+        let project_dir = Path::new("projects").join(&purge.project_slug);
+
+        {
+            let _git_guard = mm.git_lock.lock().await;
+            let repo_arc = mm.get_repo().await?;
+            let repo = repo_arc.lock().await;
+            let workdir = repo
+                .workdir()
+                .ok_or_else(|| Error::InvalidInput("No workdir".into()))?
+                .to_path_buf();
+
+            let removed: Vec<PathBuf> = match purge.action {
+                RetentionAction::Archive => Vec::new(),
+                RetentionAction::Delete => {
+                    let prefix = format!("{}/", project_dir.to_string_lossy());
+                    repo.index()?
+                        .iter()
+                        .filter_map(|entry| String::from_utf8(entry.path).ok())
+                        .filter(|path| {
+                            path.starts_with(&prefix)
+                                && archived_message_id(path).is_some_and(|id| ids.contains(&id))
+                        })
+                        .map(PathBuf::from)
+                        .collect()
+                }
+            };
+            for path in &removed {
+                match std::fs::remove_file(workdir.join(path)) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+
+            let now = Utc::now();
+            let audit = RetentionAudit {
+                purged_ts: now.naive_utc().format(TS_FORMAT).to_string(),
+                action: purge.action,
+                max_age_days: policy.max_age_days,
+                max_messages: policy.max_messages,
+                messages: &purge.messages,
+                removed_files: removed
+                    .iter()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .collect(),
+            };
+            let audit_path = project_dir
+                .join("retention")
+                .join(format!("{}.json", now.format("%Y-%m-%dT%H-%M-%S%.3fZ")));
+            let full_path = workdir.join(&audit_path);
+            if let Some(parent) = full_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&full_path, serde_json::to_string_pretty(&audit)?)?;
+
+            git_store::commit_paths_and_removals(
+                &repo,
+                &[&audit_path],
+                &removed,
+                &format!(
+                    "retention: {} {} message(s) of {}",
+                    purge.action.as_str(),
+                    purge.messages.len(),
+                    purge.project_slug
+                ),
+                "mcp-bot",
+                "mcp-bot@localhost",
+            )?;
+            purge.audit_path = Some(audit_path.to_string_lossy().into_owned());
+            purge.archive_files_removed = removed.len();
+        }
+
+        let ids: Vec<i64> = purge.messages.iter().map(|m| m.id).collect();
+        let db = mm.db();
+        for chunk in ids.chunks(DELETE_CHUNK) {
+            let list = chunk
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+            for table in MESSAGE_TABLES {
+                db.execute(
+                    &format!("DELETE FROM {} WHERE message_id IN ({})", table, list),
+                    (),
+                )
+                .await?;
+            }
+            // Triggers clear bodies, formats, fields, replies and search rows
+            db.execute(&format!("DELETE FROM messages WHERE id IN ({})", list), ())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// A stored limit, or the configured one when the project sets none;
+/// 0 means no limit.
+fn limit(stored: Option<i64>, configured: u64) -> Option<u64> {
+    let value = stored.map_or(configured, |v| u64::try_from(v).unwrap_or(0));
+    (value > 0).then_some(value)
+}
+
+fn stored(field: &str, value: Option<u64>) -> Result<Option<i64>> {
+    value
+        .map(|v| {
+            i64::try_from(v)
+                .map_err(|_| Error::InvalidInput(format!("{} of {} is too large", field, v)))
+        })
+        .transpose()
+}
+
+/// Timestamp `days` ago, `None` if that is before any representable time.
+fn cutoff_ts(days: u64) -> Option<String> {
+    let age = i64::try_from(days).ok().and_then(Duration::try_days)?;
+    Utc::now()
+        .naive_utc()
+        .checked_sub_signed(age)
+        .map(|ts| ts.format(TS_FORMAT).to_string())
+}
+
+/// Message id of an archived message copy, named `<ts>__<subject>__<id>.md`
+/// under `messages/`, an `inbox/` or an `outbox/`.
+fn archived_message_id(path: &str) -> Option<i64> {
+    if !["/messages/", "/inbox/", "/outbox/"]
+        .iter()
+        .any(|dir| path.contains(dir))
+    {
+        return None;
+    }
+    path.strip_suffix(".md")?.rsplit_once("__")?.1.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archived_message_id() {
+        assert_eq!(
+            archived_message_id("projects/p/messages/2025/03/2025-03-01T12-00-00Z__hello__42.md"),
+            Some(42)
+        );
+        assert_eq!(
+            archived_message_id("projects/p/agents/bob/inbox/2025/03/x__re-hello__7.md"),
+            Some(7)
+        );
+        assert_eq!(
+            archived_message_id("projects/p/retention/2025-03-01T12-00-00.000Z.json"),
+            None
+        );
+        assert_eq!(archived_message_id("projects/p/notes/a__3.md"), None);
+    }
+
+    #[test]
+    fn test_limit() {
+        assert_eq!(limit(None, 30), Some(30));
+        assert_eq!(limit(None, 0), None);
+        assert_eq!(limit(Some(7), 30), Some(7));
+        assert_eq!(limit(Some(0), 30), None);
+    }
+}
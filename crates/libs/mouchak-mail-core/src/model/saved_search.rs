@@ -0,0 +1,475 @@
+//! Saved searches, shown as smart folders.
+//!
+//! A saved search stores named criteria ("urgent unread from reviewers")
+//! rather than results, and is evaluated on demand so it always reflects the
+//! current mailbox. Searches without an owner are shared by the project and
+//! match all of its messages; searches owned by an agent match that agent's
+//! inbox and may filter on its read and ack state.
+//!
+//! Senders in [`SavedSearchCriteria::from`] are agent names or
+//! `capability:<name>` addresses. Capabilities are resolved at evaluation,
+//! so a search for mail from `capability:reviewer` follows grants and
+//! expiries.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::capability_routing::CapabilityAddress;
+use crate::model::focus_window::IMPORTANCE_LEVELS;
+use crate::model::listing::parse_duration_secs;
+use crate::model::message::{Message, fts_match_query, message_match, search_param};
+use crate::store::Backend;
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp};
+use crate::{Error, Result};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Longest accepted saved search name.
+pub const MAX_NAME_LEN: usize = 100;
+
+/// What a saved search matches. Every set criterion must hold; empty
+/// criteria match every message in scope.
+///
+/// # Fields
+///
+/// - `query` - Full-text query, with the syntax of message search
+/// - `importance` - Accepted importance levels
+/// - `from` - Sender names or `capability:<name>` addresses
+/// - `unread` - Only messages the owner has not read
+/// - `unacked` - Only ack-required messages the owner has not acknowledged
+/// - `within` - Only messages sent within this duration (`24h`, `7d`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSearchCriteria {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub importance: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub from: Vec<String>,
+    pub unread: bool,
+    pub unacked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub within: Option<String>,
+}
+
+impl SavedSearchCriteria {
+    /// The full-text query, if one is set to something other than blanks.
+    fn query(&self) -> Option<&str> {
+        self.query
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+    }
+}
+
+/// A stored search.
+///
+/// # Fields
+///
+/// - `agent_id`/`agent_name` - Owning agent, or `None` for a search shared
+///   by the project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub project_id: ProjectId,
+    pub agent_id: Option<AgentId>,
+    pub agent_name: Option<String>,
+    pub name: String,
+    pub criteria: SavedSearchCriteria,
+    pub created_ts: NaiveDateTime,
+    pub updated_ts: NaiveDateTime,
+}
+
+/// Input for [`SavedSearchBmc::save`].
+#[derive(Debug, Clone)]
+pub struct SavedSearchForCreate {
+    pub project_id: ProjectId,
+    pub agent_id: Option<AgentId>,
+    pub name: String,
+    pub criteria: SavedSearchCriteria,
+}
+
+/// A saved search evaluated against the current mailbox.
+///
+/// # Fields
+///
+/// - `total` - All matching messages, for folder badges
+/// - `messages` - The newest matches, up to the requested limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearchResult {
+    pub search: SavedSearch,
+    pub total: i64,
+    pub messages: Vec<Message>,
+}
+
+/// A saved search with its current match count, as shown in a sidebar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearchFolder {
+    #[serde(flatten)]
+    pub search: SavedSearch,
+    pub count: i64,
+}
+
+const SELECT_SEARCHES: &str = r#"
+    SELECT s.id, s.project_id, s.agent_id, a.name, s.name, s.criteria, s.created_ts, s.updated_ts
+    FROM saved_searches s
+    LEFT JOIN agents a ON a.id = s.agent_id
+"#;
+
+/// Backend Model Controller for saved searches.
+pub struct SavedSearchBmc;
+
+impl SavedSearchBmc {
+    /// Saves a search under `name`, replacing the criteria of an existing
+    /// search with the same name and owner. Returns its id.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an empty or overlong name, an unknown
+    /// importance, sender or duration, a query that can never match, or
+    /// `unread`/`unacked` on a search without an owner.
+    pub async fn save(ctx: &Ctx, mm: &ModelManager, search_c: SavedSearchForCreate) -> Result<i64> {
+        let name = search_c.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(Error::InvalidInput(format!(
+                "Saved search name must be 1 to {} characters",
+                MAX_NAME_LEN
+            )));
+        }
+        Self::validate(ctx, mm, &search_c).await?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO saved_searches (project_id, agent_id, name, criteria)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT(project_id, COALESCE(agent_id, 0), name) DO UPDATE SET
+                    criteria = excluded.criteria,
+                    updated_ts = CURRENT_TIMESTAMP
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                search_c.project_id.get(),
+                search_c.agent_id.map(|a| a.get()),
+                name,
+                serde_json::to_string(&search_c.criteria)?,
+            ))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::InvalidInput("Failed to save search".into())),
+        }
+    }
+
+    async fn validate(ctx: &Ctx, mm: &ModelManager, search_c: &SavedSearchForCreate) -> Result<()> {
+        let criteria = &search_c.criteria;
+        if let Some(query) = &criteria.query
+            && !query.trim().is_empty()
+            && fts_match_query(query).is_none()
+        {
+            return Err(Error::InvalidInput(format!(
+                "Search query '{}' matches nothing",
+                query
+            )));
+        }
+        for importance in &criteria.importance {
+            if !IMPORTANCE_LEVELS.contains(&importance.as_str()) {
+                return Err(Error::InvalidInput(format!(
+                    "Unknown importance '{}', expected one of: {}",
+                    importance,
+                    IMPORTANCE_LEVELS.join(", ")
+                )));
+            }
+        }
+        for sender in &criteria.from {
+            if CapabilityAddress::is_capability_address(sender) {
+                CapabilityAddress::parse(sender)?;
+            } else {
+                AgentBmc::get_by_name(ctx, mm, search_c.project_id, sender)
+                    .await
+                    .map_err(|_| Error::InvalidInput(format!("Unknown sender '{}'", sender)))?;
+            }
+        }
+        if (criteria.unread || criteria.unacked) && search_c.agent_id.is_none() {
+            return Err(Error::InvalidInput(
+                "unread and unacked need a saved search owned by an agent".into(),
+            ));
+        }
+        if let Some(within) = &criteria.within
+            && parse_duration_secs(within)? <= 0
+        {
+            return Err(Error::InvalidInput(format!(
+                "Duration '{}' must be positive",
+                within
+            )));
+        }
+        Ok(())
+    }
+
+    /// A saved search of a project.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no search with that id.
+    pub async fn get(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        id: i64,
+    ) -> Result<SavedSearch> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE s.id = ? AND s.project_id = ?",
+                SELECT_SEARCHES
+            ))
+            .await?;
+        let mut rows = stmt.query((id, project_id.get())).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Lists the searches shared by a project, plus those owned by
+    /// `agent_id` when given, by name.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: Option<AgentId>,
+    ) -> Result<Vec<SavedSearch>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE s.project_id = ? AND (s.agent_id IS NULL OR s.agent_id = ?) ORDER BY s.name, s.id",
+                SELECT_SEARCHES
+            ))
+            .await?;
+        let mut rows = stmt
+            .query((project_id.get(), agent_id.map(|a| a.get())))
+            .await?;
+        let mut searches = Vec::new();
+        while let Some(row) = rows.next().await? {
+            searches.push(Self::from_row(&row)?);
+        }
+        Ok(searches)
+    }
+
+    /// [`Self::list`] with each search's current match count.
+    pub async fn folders(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: Option<AgentId>,
+    ) -> Result<Vec<SavedSearchFolder>> {
+        let mut folders = Vec::new();
+        for search in Self::list(ctx, mm, project_id, agent_id).await? {
+            let count = Self::count(ctx, mm, &search).await?;
+            folders.push(SavedSearchFolder { search, count });
+        }
+        Ok(folders)
+    }
+
+    /// Deletes a saved search of a project.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no search with that id.
+    pub async fn delete(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        id: i64,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM saved_searches WHERE id = ? AND project_id = ?")
+            .await?;
+        let deleted = stmt.execute((id, project_id.get())).await?;
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Counts the messages a saved search currently matches.
+    pub async fn count(_ctx: &Ctx, mm: &ModelManager, search: &SavedSearch) -> Result<i64> {
+        let Some((condition, params)) = Self::condition(mm.db_backend(), search) else {
+            return Ok(0);
+        };
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "SELECT COUNT(*) FROM messages AS m JOIN agents AS ag ON m.sender_id = ag.id WHERE {}",
+                condition
+            ))
+            .await?;
+        let mut rows = stmt.query(params).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Evaluates a saved search: its match count and newest `limit`
+    /// matching messages.
+    pub async fn evaluate(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        search: SavedSearch,
+        limit: i64,
+    ) -> Result<SavedSearchResult> {
+        let total = Self::count(ctx, mm, &search).await?;
+        let Some((condition, mut params)) = Self::condition(mm.db_backend(), &search) else {
+            return Ok(SavedSearchResult {
+                search,
+                total,
+                messages: Vec::new(),
+            });
+        };
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                r#"
+                SELECT
+                    m.id, m.project_id, m.sender_id, ag.name, m.thread_id, m.subject, m.body_md,
+                    m.importance, m.ack_required, m.created_ts, m.attachments,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
+                FROM messages AS m
+                JOIN agents AS ag ON m.sender_id = ag.id
+                WHERE {}
+                ORDER BY m.created_ts DESC, m.id DESC
+                LIMIT ?
+                "#,
+                condition
+            ))
+            .await?;
+        params.push(limit.into());
+        let mut rows = stmt.query(params).await?;
+        let mut messages = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(9)?;
+            let attachments: String = row.get(10)?;
+            messages.push(Message {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                sender_id: row.get(2)?,
+                sender_name: row.get(3)?,
+                thread_id: row.get(4)?,
+                subject: row.get(5)?,
+                body_md: row.get(6)?,
+                importance: row.get(7)?,
+                ack_required: row.get(8)?,
+                created_ts: parse_timestamp(&created_ts, "messages.created_ts"),
+                attachments: serde_json::from_str::<Vec<Value>>(&attachments)?,
+                public_id: row.get(11)?,
+            });
+        }
+        Ok(SavedSearchResult {
+            search,
+            total,
+            messages,
+        })
+    }
+
+    /// SQL condition on `messages AS m` joined with its sender `agents AS ag`
+    /// selecting a search's matches, with its parameters in order. `None`
+    /// when the search can match nothing.
+    fn condition(backend: Backend, search: &SavedSearch) -> Option<(String, Vec<libsql::Value>)> {
+        let criteria = &search.criteria;
+        let now = chrono::Utc::now().naive_utc();
+        let mut conditions = vec!["m.project_id = ?".to_string()];
+        let mut params: Vec<libsql::Value> = vec![search.project_id.get().into()];
+
+        if let Some(agent_id) = search.agent_id {
+            let mut recipient = "mr.agent_id = ?".to_string();
+            if criteria.unread {
+                recipient.push_str(" AND mr.read_ts IS NULL");
+            }
+            if criteria.unacked {
+                recipient.push_str(" AND mr.ack_ts IS NULL AND m.ack_required = 1");
+            }
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM message_recipients AS mr WHERE mr.message_id = m.id AND {})",
+                recipient
+            ));
+            params.push(agent_id.get().into());
+        }
+        if let Some(query) = criteria.query() {
+            conditions.push(message_match(backend).into());
+            params.push(search_param(backend, &fts_match_query(query)?));
+        }
+        if !criteria.importance.is_empty() {
+            conditions.push(format!(
+                "m.importance IN ({})",
+                vec!["?"; criteria.importance.len()].join(", ")
+            ));
+            params.extend(criteria.importance.iter().map(|i| i.clone().into()));
+        }
+        if !criteria.from.is_empty() {
+            let (capabilities, names): (Vec<&String>, Vec<&String>) = criteria
+                .from
+                .iter()
+                .partition(|s| CapabilityAddress::is_capability_address(s));
+            let capabilities: Vec<String> = capabilities
+                .into_iter()
+                .filter_map(|s| CapabilityAddress::parse(s).ok())
+                .map(|a| a.capability)
+                .collect();
+            let mut senders = Vec::new();
+            if !names.is_empty() {
+                senders.push(format!(
+                    "ag.name IN ({})",
+                    vec!["?"; names.len()].join(", ")
+                ));
+                params.extend(names.into_iter().map(|n| n.clone().into()));
+            }
+            if !capabilities.is_empty() {
+                senders.push(format!(
+                    r#"m.sender_id IN (
+                        SELECT agent_id FROM agent_capabilities
+                        WHERE capability IN ({}) AND (expires_at IS NULL OR expires_at > ?)
+                    )"#,
+                    vec!["?"; capabilities.len()].join(", ")
+                ));
+                params.extend(capabilities.into_iter().map(libsql::Value::from));
+                params.push(now.format(TS_FORMAT).to_string().into());
+            }
+            if senders.is_empty() {
+                return None;
+            }
+            conditions.push(format!("({})", senders.join(" OR ")));
+        }
+        if let Some(within) = &criteria.within {
+            let since = parse_duration_secs(within)
+                .ok()
+                .and_then(Duration::try_seconds)
+                .and_then(|d| now.checked_sub_signed(d))?;
+            conditions.push("m.created_ts >= ?".into());
+            params.push(since.format(TS_FORMAT).to_string().into());
+        }
+        Some((conditions.join(" AND "), params))
+    }
+
+    fn from_row(row: &Row) -> Result<SavedSearch> {
+        let criteria: String = row.get(5)?;
+        let created_ts: String = row.get(6)?;
+        let updated_ts: String = row.get(7)?;
+        Ok(SavedSearch {
+            id: row.get(0)?,
+            project_id: ProjectId::new(row.get(1)?),
+            agent_id: row.get::<Option<i64>>(2)?.map(AgentId::new),
+            agent_name: row.get(3)?,
+            name: row.get(4)?,
+            criteria: serde_json::from_str(&criteria)?,
+            created_ts: parse_timestamp(&created_ts, "saved_searches.created_ts"),
+            updated_ts: parse_timestamp(&updated_ts, "saved_searches.updated_ts"),
+        })
+    }
+}
@@ -0,0 +1,186 @@
+//! Facet counts for search results.
+//!
+//! A search can match thousands of messages. Instead of paging through
+//! them, callers ask for the result set grouped by sender, importance,
+//! month and custom field value, then narrow the query (e.g. with a field
+//! filter) before fetching messages.
+//!
+//! Facets are computed over the same matches as
+//! [`MessageBmc::search_with_options`](crate::model::message::MessageBmc::search_with_options),
+//! including attachment text and custom field filters, but are not limited
+//! by a result page. Each facet keeps its `limit` largest values.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::custom_field::CustomFieldFilter;
+use crate::model::message::{fts_match_query, search_condition};
+use crate::store::Backend;
+use crate::types::ProjectId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Values kept per facet when no limit is given.
+pub const DEFAULT_FACET_LIMIT: usize = 20;
+
+/// Largest accepted per-facet limit.
+pub const MAX_FACET_LIMIT: usize = 100;
+
+/// Matching messages sharing one facet value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Counts of a search's matches, grouped several ways.
+///
+/// # Fields
+///
+/// - `total` - All matching messages
+/// - `sender` - By sender name, largest first
+/// - `importance` - By importance, largest first
+/// - `month` - By `YYYY-MM` of sending, newest first
+/// - `fields` - By value, for each custom field set on matches
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchFacets {
+    pub total: i64,
+    pub sender: Vec<FacetCount>,
+    pub importance: Vec<FacetCount>,
+    pub month: Vec<FacetCount>,
+    pub fields: BTreeMap<String, Vec<FacetCount>>,
+}
+
+/// Backend Model Controller for search facets.
+pub struct SearchFacetBmc;
+
+impl SearchFacetBmc {
+    /// Counts the messages matching a search by facet.
+    ///
+    /// `query`, `include_attachments` and `fields` match as in a search;
+    /// `limit` is clamped to [`MAX_FACET_LIMIT`]. A query that cannot match
+    /// (blocklisted or invalid FTS syntax) yields empty facets.
+    pub async fn facets(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        query: &str,
+        include_attachments: bool,
+        fields: &[CustomFieldFilter],
+        limit: usize,
+    ) -> Result<SearchFacets> {
+        let Some(fts_query) = fts_match_query(query) else {
+            return Ok(SearchFacets::default());
+        };
+        let backend = mm.db_backend();
+        let (condition, condition_params) =
+            search_condition(backend, &fts_query, include_attachments, fields);
+        let limit = limit.clamp(1, MAX_FACET_LIMIT);
+        let counts = |select: &str, from: &str, group_order: &str| {
+            let sql = format!(
+                "SELECT {} FROM messages AS m{} WHERE m.project_id = ? AND {} {}",
+                select, from, condition, group_order
+            );
+            let mut params: Vec<libsql::Value> = vec![project_id.get().into()];
+            params.extend(condition_params.iter().cloned());
+            async move {
+                let db = mm.db_read();
+                let stmt = db.prepare(&sql).await?;
+                let mut rows = stmt.query(params).await?;
+                let mut counts = Vec::new();
+                while let Some(row) = rows.next().await? {
+                    let key: Option<String> = row.get(0)?;
+                    let value: Option<String> = row.get(1)?;
+                    counts.push((key.unwrap_or_default(), value, row.get::<i64>(2)?));
+                }
+                Result::Ok(counts)
+            }
+        };
+
+        // An FTS syntax error surfaces on the first query; treat it as no
+        // matches, as search does.
+        let total = match counts("'', NULL, COUNT(*)", "", "").await {
+            Ok(rows) => rows.first().map(|(_, _, count)| *count).unwrap_or(0),
+            Err(e) => {
+                info!(
+                    "Facet query failed for '{}' (likely syntax): {}. Returning empty.",
+                    query, e
+                );
+                return Ok(SearchFacets::default());
+            }
+        };
+        if total == 0 {
+            return Ok(SearchFacets::default());
+        }
+
+        let into_facet = |rows: Vec<(String, Option<String>, i64)>| -> Vec<FacetCount> {
+            rows.into_iter()
+                .take(limit)
+                .map(|(value, _, count)| FacetCount { value, count })
+                .collect()
+        };
+        let sender = counts(
+            "ag.name, NULL, COUNT(*)",
+            " JOIN agents AS ag ON ag.id = m.sender_id",
+            "GROUP BY ag.name ORDER BY 3 DESC, 1",
+        )
+        .await?;
+        let importance = counts(
+            "m.importance, NULL, COUNT(*)",
+            "",
+            "GROUP BY m.importance ORDER BY 3 DESC, 1",
+        )
+        .await?;
+        // Timestamps are stored as `YYYY-MM-DD HH:MM:SS`
+        let month = counts(
+            "SUBSTR(m.created_ts, 1, 7), NULL, COUNT(*)",
+            "",
+            "GROUP BY 1 ORDER BY 1 DESC",
+        )
+        .await?;
+        let field_values = match backend {
+            Backend::Sqlite => {
+                counts(
+                    r#"j.key,
+                        CASE j.type
+                            WHEN 'true' THEN 'true'
+                            WHEN 'false' THEN 'false'
+                            ELSE CAST(j.value AS TEXT)
+                        END,
+                        COUNT(*)"#,
+                    " JOIN message_custom_fields AS cf ON cf.message_id = m.id, json_each(cf.fields) AS j",
+                    "AND j.type NOT IN ('object', 'array', 'null') GROUP BY 1, 2 ORDER BY 1, 3 DESC, 2",
+                )
+                .await?
+            }
+            Backend::Postgres => {
+                counts(
+                    "j.key, j.value #>> '{}', COUNT(*)",
+                    " JOIN message_custom_fields AS cf ON cf.message_id = m.id, jsonb_each(CAST(cf.fields AS JSONB)) AS j",
+                    "AND jsonb_typeof(j.value) NOT IN ('object', 'array', 'null') GROUP BY 1, 2 ORDER BY 1, 3 DESC, 2",
+                )
+                .await?
+            }
+        };
+
+        let mut by_field: BTreeMap<String, Vec<FacetCount>> = BTreeMap::new();
+        for (name, value, count) in field_values {
+            let values = by_field.entry(name).or_default();
+            if values.len() < limit {
+                values.push(FacetCount {
+                    value: value.unwrap_or_default(),
+                    count,
+                });
+            }
+        }
+
+        Ok(SearchFacets {
+            total,
+            sender: into_facet(sender),
+            importance: into_facet(importance),
+            month: into_facet(month),
+            fields: by_field,
+        })
+    }
+}
@@ -0,0 +1,418 @@
+//! Deterministic seed data generation.
+//!
+//! Populates a realistic, reproducible dataset for demos, UI development,
+//! and benchmark baselines. Given the same [`SeedConfig`], the generator
+//! produces the same projects, agents, threads, subjects, bodies, acks,
+//! file reservations, and attachments on every run.
+//!
+//! Only wall-clock derived values (database IDs, `created_ts` anchors) vary
+//! between runs; message timestamps are backdated at fixed offsets from the
+//! time of seeding so the relative ordering is always identical.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use mouchak_mail_core::model::seed::{SeedBmc, SeedConfig};
+//! use mouchak_mail_core::model::ModelManager;
+//! use mouchak_mail_core::ctx::Ctx;
+//!
+//! # async fn example() -> mouchak_mail_core::Result<()> {
+//! let mm = ModelManager::new(std::sync::Arc::new(mouchak_mail_common::config::AppConfig::default())).await?;
+//! let ctx = Ctx::root_ctx();
+//!
+//! let config = SeedConfig { projects: 3, agents: 5, messages: 200, seed: 42 };
+//! let summary = SeedBmc::seed(&ctx, &mm, &config).await?;
+//! println!("Seeded {} messages", summary.messages);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{AgentBmc, AgentForCreate};
+use crate::model::attachment::{AttachmentBmc, AttachmentUpload};
+use crate::model::file_reservation::{FileReservationBmc, FileReservationForCreate};
+use crate::model::message::{MessageBmc, MessageForCreate};
+use crate::model::project::ProjectBmc;
+use crate::types::{AgentId, ProjectId};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+/// Adjectives used to build deterministic agent names (e.g. "BlueLake").
+const ADJECTIVES: &[&str] = &[
+    "Blue", "Green", "Red", "Golden", "Silver", "Swift", "Quiet", "Bright", "Amber", "Crimson",
+];
+
+/// Nouns used to build deterministic agent names.
+const NOUNS: &[&str] = &[
+    "Lake", "River", "Mountain", "Forest", "Falcon", "Harbor", "Meadow", "Canyon", "Ridge", "Grove",
+];
+
+/// Project themes; each seeded project picks one in order.
+const PROJECT_THEMES: &[&str] = &[
+    "billing-service",
+    "web-dashboard",
+    "data-pipeline",
+    "auth-gateway",
+    "mobile-app",
+    "search-indexer",
+];
+
+/// Work topics used to generate thread subjects and bodies.
+const TOPICS: &[&str] = &[
+    "database migration",
+    "flaky integration test",
+    "API rate limiting",
+    "release checklist",
+    "memory leak in worker",
+    "dependency upgrade",
+    "schema validation",
+    "cache invalidation",
+    "CI pipeline timeout",
+    "error handling cleanup",
+];
+
+/// Subject prefixes mirroring the review workflow conventions.
+const SUBJECT_PREFIXES: &[&str] = &["[TASK]", "[REVIEW]", "[QUESTION]", "[FYI]", "[BLOCKED]"];
+
+/// Paths used for file reservations and attachment names.
+const PATHS: &[&str] = &[
+    "src/lib.rs",
+    "src/api/**",
+    "migrations/*.sql",
+    "tests/integration/**",
+    "Cargo.toml",
+    "docs/ARCHITECTURE.md",
+];
+
+/// Share of messages that continue an existing thread instead of starting one.
+const REPLY_PROBABILITY: f64 = 0.45;
+
+/// Share of messages that require acknowledgment.
+const ACK_REQUIRED_PROBABILITY: f64 = 0.25;
+
+/// Share of ack-required deliveries that have been acknowledged.
+const ACKED_PROBABILITY: f64 = 0.6;
+
+/// Share of messages that carry an attachment.
+const ATTACHMENT_PROBABILITY: f64 = 0.08;
+
+/// Spacing between seeded messages when backdating `created_ts`.
+const MESSAGE_SPACING_MINUTES: i64 = 7;
+
+/// Parameters for the seed generator.
+///
+/// # Fields
+///
+/// - `projects` - Number of projects to create
+/// - `agents` - Agents per project (minimum 2 so messages have a recipient)
+/// - `messages` - Messages per project
+/// - `seed` - RNG seed; identical seeds produce identical datasets
+#[derive(Debug, Clone, Copy)]
+pub struct SeedConfig {
+    pub projects: usize,
+    pub agents: usize,
+    pub messages: usize,
+    pub seed: u64,
+}
+
+/// Counts of everything the generator created.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SeedSummary {
+    pub project_slugs: Vec<String>,
+    pub agents: usize,
+    pub messages: usize,
+    pub threads: usize,
+    pub acks: usize,
+    pub reservations: usize,
+    pub attachments: usize,
+}
+
+/// An open thread tracked while generating replies.
+struct SeedThread {
+    thread_id: String,
+    subject: String,
+    participants: Vec<usize>,
+}
+
+/// Backend Model Controller for seed data generation.
+pub struct SeedBmc;
+
+impl SeedBmc {
+    /// Populates the database with a deterministic dataset.
+    ///
+    /// Project slugs are derived from the seed (`seed-{seed}-{theme}`), so
+    /// seeding the same database twice with the same seed fails with
+    /// `InvalidInput` instead of duplicating data.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if counts are out of range or a seeded project
+    /// already exists.
+    pub async fn seed(ctx: &Ctx, mm: &ModelManager, config: &SeedConfig) -> Result<SeedSummary> {
+        if config.projects == 0 || config.projects > PROJECT_THEMES.len() {
+            return Err(crate::Error::InvalidInput(format!(
+                "projects must be between 1 and {}",
+                PROJECT_THEMES.len()
+            )));
+        }
+        if config.agents < 2 || config.agents > ADJECTIVES.len() * NOUNS.len() {
+            return Err(crate::Error::InvalidInput(format!(
+                "agents must be between 2 and {}",
+                ADJECTIVES.len() * NOUNS.len()
+            )));
+        }
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut summary = SeedSummary::default();
+
+        for theme in PROJECT_THEMES.iter().take(config.projects) {
+            let slug = format!("seed-{}-{}", config.seed, theme);
+            if ProjectBmc::get_by_slug(ctx, mm, &slug).await.is_ok() {
+                return Err(crate::Error::InvalidInput(format!(
+                    "Project '{}' already exists; use a different --seed or clear the database",
+                    slug
+                )));
+            }
+
+            let human_key = format!("/seed/{}/{}", config.seed, theme);
+            let project_id = ProjectBmc::create(ctx, mm, &slug, &human_key).await?;
+
+            let agent_ids =
+                Self::seed_agents(ctx, mm, &mut rng, project_id, config.agents, theme).await?;
+            summary.agents += agent_ids.len();
+
+            Self::seed_messages(
+                ctx,
+                mm,
+                &mut rng,
+                project_id,
+                &agent_ids,
+                config,
+                &mut summary,
+            )
+            .await?;
+            summary.reservations +=
+                Self::seed_reservations(ctx, mm, &mut rng, project_id, &agent_ids).await?;
+
+            summary.project_slugs.push(slug);
+        }
+
+        Ok(summary)
+    }
+
+    async fn seed_agents(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        rng: &mut StdRng,
+        project_id: ProjectId,
+        count: usize,
+        theme: &str,
+    ) -> Result<Vec<AgentId>> {
+        let mut names: Vec<String> = Vec::with_capacity(count);
+        while names.len() < count {
+            let name = format!(
+                "{}{}",
+                ADJECTIVES[rng.gen_range(0..ADJECTIVES.len())],
+                NOUNS[rng.gen_range(0..NOUNS.len())]
+            );
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        let mut ids = Vec::with_capacity(count);
+        for (i, name) in names.into_iter().enumerate() {
+            let id = AgentBmc::create(
+                ctx,
+                mm,
+                AgentForCreate {
+                    project_id,
+                    name,
+                    program: if i % 2 == 0 { "claude-code" } else { "codex" }.to_string(),
+                    model: "seed-model".to_string(),
+                    task_description: format!("Seeded agent working on {}", theme),
+                },
+            )
+            .await?;
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    async fn seed_messages(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        rng: &mut StdRng,
+        project_id: ProjectId,
+        agent_ids: &[AgentId],
+        config: &SeedConfig,
+        summary: &mut SeedSummary,
+    ) -> Result<()> {
+        let mut threads: Vec<SeedThread> = Vec::new();
+        let anchor = chrono::Utc::now().naive_utc();
+
+        for i in 0..config.messages {
+            let continue_thread = !threads.is_empty() && rng.gen_bool(REPLY_PROBABILITY);
+
+            let (thread_idx, sender, recipients) = if continue_thread {
+                let idx = rng.gen_range(0..threads.len());
+                let participants = &threads[idx].participants;
+                let sender = participants[rng.gen_range(0..participants.len())];
+                let recipients: Vec<usize> = participants
+                    .iter()
+                    .copied()
+                    .filter(|p| *p != sender)
+                    .collect();
+                (idx, sender, recipients)
+            } else {
+                let sender = rng.gen_range(0..agent_ids.len());
+                let mut recipient = rng.gen_range(0..agent_ids.len() - 1);
+                if recipient >= sender {
+                    recipient += 1;
+                }
+                let topic = TOPICS[rng.gen_range(0..TOPICS.len())];
+                let prefix = SUBJECT_PREFIXES[rng.gen_range(0..SUBJECT_PREFIXES.len())];
+                threads.push(SeedThread {
+                    thread_id: format!("seed-{}-p{}-t{}", config.seed, project_id, threads.len()),
+                    subject: format!("{} {}", prefix, topic),
+                    participants: vec![sender, recipient],
+                });
+                (threads.len() - 1, sender, vec![recipient])
+            };
+
+            let thread = &threads[thread_idx];
+            let subject = if continue_thread {
+                format!("Re: {}", thread.subject)
+            } else {
+                thread.subject.clone()
+            };
+            let ack_required = rng.gen_bool(ACK_REQUIRED_PROBABILITY);
+            let importance = match rng.gen_range(0..10) {
+                0 => "urgent",
+                1 | 2 => "high",
+                _ => "normal",
+            };
+            let body_md = format!(
+                "Update {} on `{}`.\n\n- Status: {}\n- Next step: {}\n",
+                i + 1,
+                PATHS[rng.gen_range(0..PATHS.len())],
+                ["in progress", "blocked", "done", "needs review"][rng.gen_range(0..4)],
+                ["run tests", "open PR", "pair on fix", "write docs"][rng.gen_range(0..4)],
+            );
+
+            let message_id = MessageBmc::create(
+                ctx,
+                mm,
+                MessageForCreate {
+                    project_id,
+                    sender_id: agent_ids[sender],
+                    recipient_ids: recipients.iter().map(|r| agent_ids[*r]).collect(),
+                    cc_ids: None,
+                    bcc_ids: None,
+                    subject,
+                    body_md,
+                    thread_id: Some(thread.thread_id.clone()),
+                    importance: Some(importance.to_string()),
+                    ack_required,
+                },
+            )
+            .await?;
+
+            // Backdate so the dataset spans a realistic window, oldest first
+            let offset = (config.messages - i) as i64 * MESSAGE_SPACING_MINUTES;
+            let created_ts = anchor - chrono::Duration::minutes(offset);
+            let stmt = mm
+                .db()
+                .prepare("UPDATE messages SET created_ts = ? WHERE id = ?")
+                .await?;
+            stmt.execute((
+                created_ts.format(crate::utils::TS_FORMAT).to_string(),
+                message_id.get(),
+            ))
+            .await?;
+
+            for r in &recipients {
+                if ack_required && rng.gen_bool(ACKED_PROBABILITY) {
+                    MessageBmc::acknowledge(ctx, mm, message_id, agent_ids[*r]).await?;
+                    summary.acks += 1;
+                } else if rng.gen_bool(0.5) {
+                    MessageBmc::mark_read(ctx, mm, message_id, agent_ids[*r]).await?;
+                }
+            }
+
+            if rng.gen_bool(ATTACHMENT_PROBABILITY) {
+                Self::seed_attachment(ctx, mm, project_id, agent_ids[sender], message_id.get())
+                    .await?;
+                summary.attachments += 1;
+            }
+
+            summary.messages += 1;
+        }
+
+        summary.threads += threads.len();
+        Ok(())
+    }
+
+    async fn seed_reservations(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        rng: &mut StdRng,
+        project_id: ProjectId,
+        agent_ids: &[AgentId],
+    ) -> Result<usize> {
+        let now = chrono::Utc::now().naive_utc();
+        let mut created = 0;
+
+        for (i, path) in PATHS.iter().enumerate() {
+            let agent_id = agent_ids[rng.gen_range(0..agent_ids.len())];
+            let ttl_minutes = rng.gen_range(30..240);
+            let id = FileReservationBmc::create(
+                ctx,
+                mm,
+                FileReservationForCreate {
+                    project_id,
+                    agent_id,
+                    path_pattern: path.to_string(),
+                    exclusive: i % 3 != 2,
+                    reason: format!("Seeded reservation for {}", path),
+                    expires_ts: now + chrono::Duration::minutes(ttl_minutes),
+                },
+            )
+            .await?;
+            // Release roughly a third so history and active views both have data
+            if rng.gen_bool(0.33) {
+                FileReservationBmc::release(ctx, mm, id).await?;
+            }
+            created += 1;
+        }
+
+        Ok(created)
+    }
+
+    async fn seed_attachment(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        message_id: i64,
+    ) -> Result<()> {
+        let filename = format!("seed-notes-{}.md", message_id);
+        let content = format!("# Notes for message {}\n\nGenerated by seed.\n", message_id);
+
+        AttachmentBmc::store(
+            ctx,
+            mm,
+            AttachmentUpload {
+                project_id: project_id.get(),
+                agent_id: Some(agent_id.get()),
+                filename,
+                media_type: "text/markdown".to_string(),
+            },
+            Box::new(std::io::Cursor::new(content.into_bytes())),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,538 @@
+//! Agent SLAs: acknowledgment deadlines per message importance.
+//!
+//! A project defines how quickly recipients must acknowledge messages of a
+//! given importance, e.g. `urgent` within one hour. An SLA covers the
+//! ack-required messages of its importance sent after it was defined, so a
+//! new SLA does not retroactively flag old mail.
+//!
+//! Each recipient of a covered message either:
+//!
+//! - **met** the SLA - acknowledged before the deadline
+//! - **violated** it - acknowledged late, or not at all and the deadline passed
+//! - is **pending** - not acknowledged, deadline still ahead
+//!
+//! Deadlines count business time when the project has business hours (see
+//! [`crate::model::project_settings`]), so a message sent after hours is not
+//! overdue before the next working day.
+//!
+//! [`SlaBmc::report`] summarizes compliance per project and per agent over a
+//! period. [`SlaBmc::report_violations`] posts each new violation to the
+//! overseer inbox once, in the project's locale; the server runs it
+//! periodically.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::focus_window::IMPORTANCE_LEVELS;
+use crate::model::message_catalog::{Catalog, MessageCatalogBmc};
+use crate::model::overseer_message::{OverseerMessageBmc, OverseerMessageForCreate};
+use crate::model::project_settings::{ProjectSettings, ProjectSettingsBmc};
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// An acknowledgment deadline for one importance level of a project.
+///
+/// # Fields
+///
+/// - `ack_within_seconds` - Time recipients have to acknowledge
+/// - `created_ts` - When the SLA was defined; earlier messages are not covered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSla {
+    pub id: i64,
+    pub project_id: ProjectId,
+    pub importance: String,
+    pub ack_within_seconds: i64,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Input to define or change an SLA.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSlaForCreate {
+    pub project_id: ProjectId,
+    pub importance: String,
+    pub ack_within_seconds: i64,
+}
+
+/// Compliance counts over a set of recipients.
+///
+/// # Fields
+///
+/// - `measured` - Recipients of covered messages
+/// - `compliance_percent` - `met` out of `met + violated`; `None` when nothing
+///   has been decided yet
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SlaTally {
+    pub measured: i64,
+    pub met: i64,
+    pub violated: i64,
+    pub pending: i64,
+    pub compliance_percent: Option<f64>,
+}
+
+impl SlaTally {
+    fn add(&mut self, outcome: SlaOutcome) {
+        self.measured += 1;
+        match outcome {
+            SlaOutcome::Met => self.met += 1,
+            SlaOutcome::Violated => self.violated += 1,
+            SlaOutcome::Pending => self.pending += 1,
+        }
+        let decided = self.met + self.violated;
+        self.compliance_percent =
+            (decided > 0).then(|| (self.met as f64 * 1000.0 / decided as f64).round() / 10.0);
+    }
+}
+
+/// Compliance of one agent as a recipient.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentSlaCompliance {
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub tally: SlaTally,
+}
+
+/// A recipient that missed an SLA.
+///
+/// # Fields
+///
+/// - `ack_ts` - When the late acknowledgment came; `None` if still unacknowledged
+#[derive(Debug, Clone, Serialize)]
+pub struct SlaViolation {
+    pub message_id: MessageId,
+    pub subject: String,
+    pub importance: String,
+    pub sender_name: String,
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub created_ts: NaiveDateTime,
+    pub deadline_ts: NaiveDateTime,
+    pub ack_ts: Option<NaiveDateTime>,
+}
+
+/// SLA compliance of a project over a period.
+///
+/// # Fields
+///
+/// - `since` - Start of the period; messages sent before it are not counted
+/// - `agents` - Per-recipient compliance, by agent name
+/// - `violations` - Oldest first
+#[derive(Debug, Clone, Serialize)]
+pub struct SlaReport {
+    pub project_id: ProjectId,
+    pub since: NaiveDateTime,
+    pub generated_ts: NaiveDateTime,
+    pub slas: Vec<ProjectSla>,
+    pub overall: SlaTally,
+    pub agents: Vec<AgentSlaCompliance>,
+    pub violations: Vec<SlaViolation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlaOutcome {
+    Met,
+    Violated,
+    Pending,
+}
+
+fn outcome(
+    deadline: NaiveDateTime,
+    ack_ts: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> SlaOutcome {
+    match ack_ts {
+        Some(ack) if ack <= deadline => SlaOutcome::Met,
+        Some(_) => SlaOutcome::Violated,
+        None if now > deadline => SlaOutcome::Violated,
+        None => SlaOutcome::Pending,
+    }
+}
+
+/// Backend Model Controller for agent SLAs.
+pub struct SlaBmc;
+
+impl SlaBmc {
+    /// Lists a project's SLAs, by importance.
+    pub async fn list_slas(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<ProjectSla>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, importance, ack_within_seconds, created_ts
+                FROM project_slas
+                WHERE project_id = ?
+                ORDER BY CASE importance
+                    WHEN 'urgent' THEN 0 WHEN 'high' THEN 1 WHEN 'normal' THEN 2 ELSE 3
+                END
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut slas = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(4)?;
+            slas.push(ProjectSla {
+                id: row.get(0)?,
+                project_id: ProjectId::new(row.get(1)?),
+                importance: row.get(2)?,
+                ack_within_seconds: row.get(3)?,
+                created_ts: parse_timestamp(&created_ts, "created_ts"),
+            });
+        }
+        Ok(slas)
+    }
+
+    /// Defines the SLA for an importance level, or changes its deadline.
+    ///
+    /// Changing a deadline keeps the SLA's `created_ts`, so coverage still
+    /// starts when it was first defined.
+    ///
+    /// # Returns
+    /// The SLA id.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an unknown importance or a deadline that is
+    /// not positive.
+    pub async fn set_sla(_ctx: &Ctx, mm: &ModelManager, sla_c: ProjectSlaForCreate) -> Result<i64> {
+        if !IMPORTANCE_LEVELS.contains(&sla_c.importance.as_str()) {
+            return Err(Error::InvalidInput(format!(
+                "Unknown importance '{}', expected one of: {}",
+                sla_c.importance,
+                IMPORTANCE_LEVELS.join(", ")
+            )));
+        }
+        if sla_c.ack_within_seconds <= 0 {
+            return Err(Error::InvalidInput(
+                "SLA deadline must be a positive number of seconds".into(),
+            ));
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO project_slas (project_id, importance, ack_within_seconds)
+                VALUES (?, ?, ?)
+                ON CONFLICT(project_id, importance)
+                    DO UPDATE SET ack_within_seconds = excluded.ack_within_seconds
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                sla_c.project_id.get(),
+                sla_c.importance.as_str(),
+                sla_c.ack_within_seconds,
+            ))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::InvalidInput("Failed to save SLA".into())),
+        }
+    }
+
+    /// Removes one of a project's SLAs.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no SLA with this id.
+    pub async fn delete_sla(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        sla_id: i64,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM project_slas WHERE id = ? AND project_id = ?")
+            .await?;
+        let deleted = stmt.execute((sla_id, project_id.get())).await?;
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Reports SLA compliance for messages sent in the last `period_seconds`.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if `period_seconds` is not positive or out of range.
+    pub async fn report(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        period_seconds: i64,
+    ) -> Result<SlaReport> {
+        let now = chrono::Utc::now().naive_utc();
+        let since = Duration::try_seconds(period_seconds)
+            .filter(|period| *period > Duration::zero())
+            .and_then(|period| now.checked_sub_signed(period))
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("Invalid SLA report period: {}s", period_seconds))
+            })?;
+        let slas = Self::list_slas(ctx, mm, project_id).await?;
+        let settings = ProjectSettingsBmc::get(ctx, mm, project_id).await?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT m.id, m.subject, m.importance, m.created_ts, s.ack_within_seconds,
+                       mr.agent_id, a.name, mr.ack_ts, sender.name
+                FROM messages AS m
+                JOIN project_slas AS s
+                    ON s.project_id = m.project_id AND s.importance = m.importance
+                JOIN message_recipients AS mr ON mr.message_id = m.id
+                JOIN agents AS a ON a.id = mr.agent_id
+                JOIN agents AS sender ON sender.id = m.sender_id
+                WHERE m.project_id = ?
+                    AND m.ack_required = 1
+                    AND m.created_ts >= s.created_ts
+                    AND m.created_ts >= ?
+                ORDER BY m.created_ts, m.id, a.name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((project_id.get(), since.format(TS_FORMAT).to_string()))
+            .await?;
+
+        let mut overall = SlaTally::default();
+        let mut agents: BTreeMap<String, AgentSlaCompliance> = BTreeMap::new();
+        let mut violations = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts = parse_timestamp(&row.get::<String>(3)?, "created_ts");
+            let deadline_ts = settings.business_deadline(created_ts, row.get(4)?);
+            let agent_id = AgentId::new(row.get(5)?);
+            let agent_name: String = row.get(6)?;
+            let ack_ts = parse_timestamp_opt(row.get(7)?, "ack_ts");
+
+            let result = outcome(deadline_ts, ack_ts, now);
+            overall.add(result);
+            agents
+                .entry(agent_name.clone())
+                .or_insert_with(|| AgentSlaCompliance {
+                    agent_id,
+                    agent_name: agent_name.clone(),
+                    tally: SlaTally::default(),
+                })
+                .tally
+                .add(result);
+            if result == SlaOutcome::Violated {
+                violations.push(SlaViolation {
+                    message_id: MessageId::new(row.get(0)?),
+                    subject: row.get(1)?,
+                    importance: row.get(2)?,
+                    sender_name: row.get(8)?,
+                    agent_id,
+                    agent_name,
+                    created_ts,
+                    deadline_ts,
+                    ack_ts,
+                });
+            }
+        }
+
+        Ok(SlaReport {
+            project_id,
+            since,
+            generated_ts: now,
+            slas,
+            overall,
+            agents: agents.into_values().collect(),
+            violations,
+        })
+    }
+
+    /// Posts SLA violations not reported yet to their project's overseer
+    /// inbox, one overseer message per message, and records them so each is
+    /// reported once.
+    ///
+    /// # Returns
+    /// The number of recipient violations reported.
+    pub async fn report_violations(ctx: &Ctx, mm: &ModelManager) -> Result<usize> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT m.id, m.project_id, m.sender_id, m.subject, m.importance, m.created_ts,
+                       s.ack_within_seconds, mr.agent_id, a.name, mr.ack_ts
+                FROM messages AS m
+                JOIN project_slas AS s
+                    ON s.project_id = m.project_id AND s.importance = m.importance
+                JOIN message_recipients AS mr ON mr.message_id = m.id
+                JOIN agents AS a ON a.id = mr.agent_id
+                LEFT JOIN sla_violations AS v
+                    ON v.message_id = m.id AND v.agent_id = mr.agent_id
+                WHERE m.ack_required = 1
+                    AND m.created_ts >= s.created_ts
+                    AND v.message_id IS NULL
+                    AND strftime('%s', COALESCE(mr.ack_ts, 'now')) - strftime('%s', m.created_ts)
+                        > s.ack_within_seconds
+                ORDER BY m.id, a.name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query(()).await?;
+
+        struct Missed {
+            project_id: i64,
+            sender_id: i64,
+            subject: String,
+            importance: String,
+            created_ts: String,
+            ack_within_seconds: i64,
+            agents: Vec<(i64, String, Option<String>)>,
+        }
+        let mut missed: BTreeMap<i64, Missed> = BTreeMap::new();
+        while let Some(row) = rows.next().await? {
+            let message_id: i64 = row.get(0)?;
+            let entry = match missed.entry(message_id) {
+                std::collections::btree_map::Entry::Occupied(e) => e.into_mut(),
+                std::collections::btree_map::Entry::Vacant(e) => e.insert(Missed {
+                    project_id: row.get(1)?,
+                    sender_id: row.get(2)?,
+                    subject: row.get(3)?,
+                    importance: row.get(4)?,
+                    created_ts: row.get(5)?,
+                    ack_within_seconds: row.get(6)?,
+                    agents: Vec::new(),
+                }),
+            };
+            entry.agents.push((row.get(7)?, row.get(8)?, row.get(9)?));
+        }
+
+        // The query compares wall-clock time; business hours can only move
+        // deadlines later, so recheck against the project's business deadline
+        let now = chrono::Utc::now().naive_utc();
+        let mut settings: BTreeMap<i64, ProjectSettings> = BTreeMap::new();
+        for msg in missed.values() {
+            if let std::collections::btree_map::Entry::Vacant(e) = settings.entry(msg.project_id) {
+                e.insert(ProjectSettingsBmc::get(ctx, mm, ProjectId::new(msg.project_id)).await?);
+            }
+        }
+        missed.retain(|_, msg| {
+            let deadline = settings[&msg.project_id].business_deadline(
+                parse_timestamp(&msg.created_ts, "created_ts"),
+                msg.ack_within_seconds,
+            );
+            msg.agents.retain(|(_, _, ack_ts)| {
+                ack_ts
+                    .as_deref()
+                    .map_or(now, |ts| parse_timestamp(ts, "ack_ts"))
+                    > deadline
+            });
+            !msg.agents.is_empty()
+        });
+
+        let mut catalogs: BTreeMap<i64, Catalog> = BTreeMap::new();
+        for msg in missed.values() {
+            if let std::collections::btree_map::Entry::Vacant(e) = catalogs.entry(msg.project_id) {
+                let locale = &settings[&msg.project_id].locale;
+                e.insert(MessageCatalogBmc::for_locale(ctx, mm, locale).await?);
+            }
+        }
+
+        let mut reported = 0;
+        for (message_id, msg) in missed {
+            let catalog = &catalogs[&msg.project_id];
+            let mut recipients = String::new();
+            for (_, name, ack_ts) in &msg.agents {
+                let line = match ack_ts {
+                    Some(ack_ts) => catalog.render(
+                        "sla.recipient.acked",
+                        &[("agent", name), ("ack_ts", ack_ts)],
+                    ),
+                    None => catalog.render("sla.recipient.pending", &[("agent", name)]),
+                };
+                recipients.push_str(&line);
+                recipients.push('\n');
+            }
+            let body = catalog.render(
+                "sla.violation.body",
+                &[
+                    ("message_id", &message_id.to_string()),
+                    ("subject", &msg.subject),
+                    ("importance", &msg.importance),
+                    ("ack_within", &format_seconds(msg.ack_within_seconds)),
+                    ("created_ts", &msg.created_ts),
+                    ("recipients", &recipients),
+                ],
+            );
+
+            OverseerMessageBmc::create(
+                ctx,
+                mm,
+                OverseerMessageForCreate {
+                    project_id: msg.project_id,
+                    sender_id: msg.sender_id,
+                    subject: catalog.render("sla.violation.subject", &[("subject", &msg.subject)]),
+                    body_md: body,
+                    importance: "high".to_string(),
+                },
+            )
+            .await?;
+
+            for (agent_id, _, _) in &msg.agents {
+                let stmt = db
+                    .prepare(
+                        "INSERT OR IGNORE INTO sla_violations (message_id, agent_id) VALUES (?, ?)",
+                    )
+                    .await?;
+                stmt.execute((message_id, *agent_id)).await?;
+            }
+            reported += msg.agents.len();
+        }
+        Ok(reported)
+    }
+}
+
+/// `3600` -> `1h`, `90` -> `90s`.
+fn format_seconds(seconds: i64) -> String {
+    match seconds {
+        s if s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_and_compliance() {
+        let deadline = parse_timestamp("2025-01-01 10:00:00", "deadline");
+        let before = parse_timestamp("2025-01-01 09:30:00", "before");
+        let after = parse_timestamp("2025-01-01 10:30:00", "after");
+
+        assert_eq!(outcome(deadline, Some(before), after), SlaOutcome::Met);
+        assert_eq!(outcome(deadline, Some(after), after), SlaOutcome::Violated);
+        assert_eq!(outcome(deadline, None, after), SlaOutcome::Violated);
+        assert_eq!(outcome(deadline, None, before), SlaOutcome::Pending);
+
+        let mut tally = SlaTally::default();
+        tally.add(SlaOutcome::Pending);
+        assert_eq!(tally.compliance_percent, None);
+        for result in [SlaOutcome::Met, SlaOutcome::Met, SlaOutcome::Violated] {
+            tally.add(result);
+        }
+        assert_eq!(tally.measured, 4);
+        assert_eq!(tally.compliance_percent, Some(66.7));
+    }
+
+    #[test]
+    fn test_format_seconds() {
+        assert_eq!(format_seconds(3600), "1h");
+        assert_eq!(format_seconds(172_800), "2d");
+        assert_eq!(format_seconds(900), "15m");
+        assert_eq!(format_seconds(90), "90s");
+    }
+}
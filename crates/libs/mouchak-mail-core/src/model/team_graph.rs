@@ -0,0 +1,404 @@
+//! Export and import of a project's team topology.
+//!
+//! A team graph captures who is on a project and how they are wired: each
+//! agent's profile and capabilities, and the accepted contact links between
+//! agents. Exported as JSON, it can be imported into another project (on
+//! the same or another instance) to stamp out a recurring setup such as
+//! planner / reviewer / infra without registering everyone by hand.
+//!
+//! Import is additive and idempotent:
+//!
+//! - Agents missing from the target project are created with the exported
+//!   profile; existing agents keep their profile.
+//! - Capabilities are granted where not already held. Expiry is not
+//!   exported, so imported grants are permanent.
+//! - Contacts are requested and accepted unless already linked. Links to
+//!   another project are kept when a project with that slug exists on the
+//!   importing instance and its contact policy allows them.
+//!
+//! Anything that cannot be applied is reported in
+//! [`TeamGraphImportSummary::skipped`] rather than failing the import.
+//! Messages, reservations and other state are not part of the graph.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{Agent, AgentBmc, AgentForCreate, AgentProfileUpdate};
+use crate::model::agent_capabilities::{AgentCapabilityBmc, AgentCapabilityForCreate};
+use crate::model::agent_link::{AgentLinkBmc, AgentLinkForCreate};
+use crate::model::project::ProjectBmc;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::validation::validate_agent_name;
+use crate::{Error, Result};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Format version written by [`TeamGraphBmc::export`].
+pub const TEAM_GRAPH_VERSION: u32 = 1;
+
+/// An agent in a team graph.
+///
+/// # Fields
+///
+/// - `capabilities` - Capabilities held at export time, sorted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamAgent {
+    pub name: String,
+    pub program: String,
+    pub model: String,
+    #[serde(default)]
+    pub task_description: String,
+    #[serde(default)]
+    pub attachments_policy: Option<String>,
+    #[serde(default)]
+    pub contact_policy: Option<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+/// An accepted contact link in a team graph.
+///
+/// # Fields
+///
+/// - `from` - Agent that requested the contact, in the exported project
+///   unless `from_project` is set
+/// - `to` - Agent that accepted it, in the exported project unless
+///   `to_project` is set
+/// - `from_project` / `to_project` - Slug of the other project for
+///   cross-project links
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamContact {
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_project: Option<String>,
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_project: Option<String>,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// A project's agents, capabilities and contacts.
+///
+/// # Fields
+///
+/// - `version` - Format version, see [`TEAM_GRAPH_VERSION`]
+/// - `project` - Slug of the exported project
+/// - `exported_ts` - When the graph was exported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamGraph {
+    pub version: u32,
+    #[serde(default)]
+    pub project: String,
+    pub exported_ts: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub agents: Vec<TeamAgent>,
+    #[serde(default)]
+    pub contacts: Vec<TeamContact>,
+}
+
+/// Outcome of a team graph import.
+///
+/// # Fields
+///
+/// - `agents_created` - Names of agents registered by the import
+/// - `capabilities_granted` - `agent:capability` grants added
+/// - `contacts_created` - `from -> to` links added
+/// - `skipped` - One reason per entry that was not applied
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TeamGraphImportSummary {
+    pub agents_created: Vec<String>,
+    pub capabilities_granted: Vec<String>,
+    pub contacts_created: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+/// Backend Model Controller for team graph export and import.
+pub struct TeamGraphBmc;
+
+impl TeamGraphBmc {
+    /// Exports a project's agents, their capabilities and the accepted
+    /// contact links involving them.
+    pub async fn export(ctx: &Ctx, mm: &ModelManager, project_id: ProjectId) -> Result<TeamGraph> {
+        let project = ProjectBmc::get(ctx, mm, project_id).await?;
+        let agents = AgentBmc::list_all_for_project(ctx, mm, project_id).await?;
+
+        let mut team = Vec::with_capacity(agents.len());
+        for agent in &agents {
+            let mut capabilities: Vec<String> =
+                AgentCapabilityBmc::list_for_agent(ctx, mm, agent.id)
+                    .await?
+                    .into_iter()
+                    .map(|c| c.capability)
+                    .collect();
+            capabilities.sort();
+            capabilities.dedup();
+            team.push(TeamAgent {
+                name: agent.name.clone(),
+                program: agent.program.clone(),
+                model: agent.model.clone(),
+                task_description: agent.task_description.clone(),
+                attachments_policy: Some(agent.attachments_policy.clone()),
+                contact_policy: Some(agent.contact_policy.clone()),
+                capabilities,
+            });
+        }
+
+        // Agent and project names of every link endpoint, resolved lazily
+        // for agents outside the project.
+        let mut names: HashMap<i64, (String, Option<String>)> = agents
+            .iter()
+            .map(|a| (a.id.get(), (a.name.clone(), None)))
+            .collect();
+        let mut seen = Vec::new();
+        let mut contacts = Vec::new();
+        for agent in &agents {
+            let links = AgentLinkBmc::list_contacts(ctx, mm, project_id, agent.id).await?;
+            for link in links {
+                if seen.contains(&link.id) {
+                    continue;
+                }
+                seen.push(link.id);
+                let Some((from, from_project)) =
+                    Self::endpoint(ctx, mm, &mut names, link.a_agent_id).await?
+                else {
+                    continue;
+                };
+                let Some((to, to_project)) =
+                    Self::endpoint(ctx, mm, &mut names, link.b_agent_id).await?
+                else {
+                    continue;
+                };
+                contacts.push(TeamContact {
+                    from,
+                    from_project,
+                    to,
+                    to_project,
+                    reason: link.reason,
+                });
+            }
+        }
+        contacts.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+
+        Ok(TeamGraph {
+            version: TEAM_GRAPH_VERSION,
+            project: project.slug,
+            exported_ts: Some(Utc::now().naive_utc()),
+            agents: team,
+            contacts,
+        })
+    }
+
+    /// Imports a team graph into a project.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an unsupported version, and a validation
+    /// error if an agent name is invalid; nothing is imported in either case.
+    pub async fn import(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        graph: &TeamGraph,
+    ) -> Result<TeamGraphImportSummary> {
+        if graph.version == 0 || graph.version > TEAM_GRAPH_VERSION {
+            return Err(Error::InvalidInput(format!(
+                "Unsupported team graph version {} (expected {})",
+                graph.version, TEAM_GRAPH_VERSION
+            )));
+        }
+        for agent in &graph.agents {
+            validate_agent_name(&agent.name)?;
+        }
+
+        let mut summary = TeamGraphImportSummary::default();
+        for team_agent in &graph.agents {
+            let agent = match Self::find_agent(ctx, mm, project_id, &team_agent.name).await? {
+                Some(agent) => agent,
+                None => {
+                    let id = Self::create_agent(ctx, mm, project_id, team_agent).await?;
+                    summary.agents_created.push(team_agent.name.clone());
+                    AgentBmc::get(ctx, mm, id).await?
+                }
+            };
+
+            let held: Vec<String> = AgentCapabilityBmc::list_for_agent(ctx, mm, agent.id)
+                .await?
+                .into_iter()
+                .map(|c| c.capability)
+                .collect();
+            for capability in &team_agent.capabilities {
+                if capability.trim().is_empty() || held.contains(capability) {
+                    continue;
+                }
+                AgentCapabilityBmc::create(
+                    ctx,
+                    mm,
+                    AgentCapabilityForCreate {
+                        agent_id: agent.id.get(),
+                        capability: capability.clone(),
+                        granted_by: None,
+                        expires_at: None,
+                    },
+                )
+                .await?;
+                summary
+                    .capabilities_granted
+                    .push(format!("{}:{}", agent.name, capability));
+            }
+        }
+
+        for contact in &graph.contacts {
+            let label = format!("{} -> {}", contact.from, contact.to);
+            let from = Self::resolve_endpoint(
+                ctx,
+                mm,
+                project_id,
+                &contact.from,
+                contact.from_project.as_deref(),
+            )
+            .await?;
+            let to = Self::resolve_endpoint(
+                ctx,
+                mm,
+                project_id,
+                &contact.to,
+                contact.to_project.as_deref(),
+            )
+            .await?;
+            let (from, to) = match (from, to) {
+                (Ok(from), Ok(to)) => (from, to),
+                (Err(reason), _) | (_, Err(reason)) => {
+                    summary
+                        .skipped
+                        .push(format!("contact {}: {}", label, reason));
+                    continue;
+                }
+            };
+
+            let linked = AgentLinkBmc::list_contacts(ctx, mm, from.project_id, from.id)
+                .await?
+                .iter()
+                .any(|l| l.a_agent_id == to.id.get() || l.b_agent_id == to.id.get());
+            if linked {
+                continue;
+            }
+
+            let link_c = AgentLinkForCreate {
+                a_project_id: from.project_id.get(),
+                a_agent_id: from.id.get(),
+                b_project_id: to.project_id.get(),
+                b_agent_id: to.id.get(),
+                reason: contact.reason.clone(),
+            };
+            match AgentLinkBmc::request_contact(ctx, mm, link_c).await {
+                Ok(link_id) => {
+                    AgentLinkBmc::respond_contact(ctx, mm, link_id, true).await?;
+                    summary.contacts_created.push(label);
+                }
+                Err(Error::ContactDenied(reason)) | Err(Error::InvalidInput(reason)) => {
+                    summary
+                        .skipped
+                        .push(format!("contact {}: {}", label, reason));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn create_agent(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        team_agent: &TeamAgent,
+    ) -> Result<AgentId> {
+        let id = AgentBmc::create(
+            ctx,
+            mm,
+            AgentForCreate {
+                project_id,
+                name: team_agent.name.clone(),
+                program: team_agent.program.clone(),
+                model: team_agent.model.clone(),
+                task_description: team_agent.task_description.clone(),
+            },
+        )
+        .await?;
+        if team_agent.attachments_policy.is_some() || team_agent.contact_policy.is_some() {
+            AgentBmc::update_profile(
+                ctx,
+                mm,
+                id,
+                AgentProfileUpdate {
+                    task_description: None,
+                    attachments_policy: team_agent.attachments_policy.clone(),
+                    contact_policy: team_agent.contact_policy.clone(),
+                },
+            )
+            .await?;
+        }
+        Ok(id)
+    }
+
+    async fn find_agent(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        name: &str,
+    ) -> Result<Option<Agent>> {
+        match AgentBmc::get_by_name(ctx, mm, project_id, name).await {
+            Ok(agent) => Ok(Some(agent)),
+            Err(Error::AgentNotFound { .. }) | Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Resolves a contact endpoint on the importing instance.
+    ///
+    /// # Returns
+    /// The agent, or the reason it cannot be linked.
+    async fn resolve_endpoint(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        name: &str,
+        project_slug: Option<&str>,
+    ) -> Result<std::result::Result<Agent, String>> {
+        let project_id = match project_slug {
+            Some(slug) => match ProjectBmc::get_by_identifier(ctx, mm, slug).await {
+                Ok(project) => project.id,
+                Err(_) => return Ok(Err(format!("project '{}' not found", slug))),
+            },
+            None => project_id,
+        };
+        Ok(Self::find_agent(ctx, mm, project_id, name)
+            .await?
+            .ok_or_else(|| match project_slug {
+                Some(slug) => format!("agent '{}' not found in '{}'", name, slug),
+                None => format!("agent '{}' not found", name),
+            }))
+    }
+
+    /// Returns an agent's name and, if outside the exported project, its
+    /// project slug. `None` if the agent no longer exists.
+    async fn endpoint(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        names: &mut HashMap<i64, (String, Option<String>)>,
+        agent_id: i64,
+    ) -> Result<Option<(String, Option<String>)>> {
+        if let Some(endpoint) = names.get(&agent_id) {
+            return Ok(Some(endpoint.clone()));
+        }
+        let agent = match AgentBmc::get(ctx, mm, AgentId::new(agent_id)).await {
+            Ok(agent) => agent,
+            Err(Error::AgentNotFound { .. }) | Err(Error::NotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let project = ProjectBmc::get(ctx, mm, agent.project_id).await?;
+        let endpoint = (agent.name, Some(project.slug));
+        names.insert(agent_id, endpoint.clone());
+        Ok(Some(endpoint))
+    }
+}
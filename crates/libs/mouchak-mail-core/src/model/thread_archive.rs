@@ -0,0 +1,336 @@
+//! Automatic archival of inactive threads.
+//!
+//! A thread with no messages for the configured number of days is archived:
+//! it is recorded in `archived_threads` and left out of `list_threads`
+//! unless archived threads are asked for, which keeps active views short on
+//! long-lived projects. Its messages are untouched and stay readable and
+//! searchable.
+//!
+//! When a closing summary is requested, the project's `archivist` agent
+//! posts a short digest of the thread (message count, participants, last
+//! message) into the thread, addressed to everyone in it, before it is
+//! archived. The summary is rendered from the project's message catalog.
+//!
+//! Any new message in an archived thread reactivates it; a database trigger
+//! removes the archive record on insert, so replies through every path
+//! (tools, imports, bridges) bring the thread back.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{AgentBmc, AgentForCreate};
+use crate::model::message::{Message, MessageBmc, MessageForCreate};
+use crate::model::message_catalog::MessageCatalogBmc;
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp};
+use crate::{Error, Result};
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Agent that closing summaries are sent from.
+pub const ARCHIVIST_AGENT_NAME: &str = "archivist";
+
+/// Characters of the last message quoted in a closing summary.
+const SUMMARY_SNIPPET_CHARS: usize = 200;
+
+/// An archived thread.
+///
+/// # Fields
+///
+/// - `last_message_ts` - Newest message when the thread was archived
+/// - `summary_message_id` - Closing summary posted on archival, if any
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedThread {
+    pub project_id: i64,
+    pub thread_id: String,
+    pub last_message_ts: NaiveDateTime,
+    pub summary_message_id: Option<i64>,
+    pub archived_ts: NaiveDateTime,
+}
+
+/// Backend Model Controller for thread archival.
+pub struct ThreadArchiveBmc;
+
+impl ThreadArchiveBmc {
+    /// Archives every thread without messages for `inactive_days`.
+    ///
+    /// # Returns
+    /// The threads archived by this run, oldest activity first.
+    pub async fn archive_inactive(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        inactive_days: u64,
+        closing_summary: bool,
+    ) -> Result<Vec<ArchivedThread>> {
+        if inactive_days == 0 {
+            return Err(Error::InvalidInput(
+                "inactive_days must be at least 1".to_string(),
+            ));
+        }
+        let cutoff = (Utc::now() - Duration::days(inactive_days as i64))
+            .naive_utc()
+            .format(TS_FORMAT)
+            .to_string();
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT m.project_id, m.thread_id, MAX(m.created_ts) AS last_message_ts
+                FROM messages AS m
+                LEFT JOIN archived_threads AS a
+                    ON a.project_id = m.project_id AND a.thread_id = m.thread_id
+                WHERE m.thread_id IS NOT NULL AND a.thread_id IS NULL
+                GROUP BY m.project_id, m.thread_id
+                HAVING MAX(m.created_ts) < ?
+                ORDER BY MAX(m.created_ts)
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([cutoff]).await?;
+        let mut inactive = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let project_id: i64 = row.get(0)?;
+            let thread_id: String = row.get(1)?;
+            inactive.push((ProjectId::new(project_id), thread_id));
+        }
+
+        let mut archived = Vec::new();
+        for (project_id, thread_id) in inactive {
+            let summary = closing_summary.then_some(inactive_days);
+            if let Some(thread) = Self::archive(ctx, mm, project_id, &thread_id, summary).await? {
+                archived.push(thread);
+            }
+        }
+        if !archived.is_empty() {
+            info!(
+                "Archived {} thread(s) inactive for {} day(s)",
+                archived.len(),
+                inactive_days
+            );
+        }
+        Ok(archived)
+    }
+
+    /// Archives one thread.
+    ///
+    /// With `summary_after_days` set, a closing summary mentioning that many
+    /// days of inactivity is posted first.
+    ///
+    /// # Returns
+    /// The archive record, or `None` if the thread has no messages.
+    pub async fn archive(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        summary_after_days: Option<u64>,
+    ) -> Result<Option<ArchivedThread>> {
+        let messages = MessageBmc::list_by_thread(ctx, mm, project_id, thread_id).await?;
+        let Some(last) = messages.last() else {
+            return Ok(None);
+        };
+        // Recorded before the summary is posted, so the summary does not
+        // count as activity.
+        let last_message_ts = last.created_ts;
+
+        let summary_message_id = match summary_after_days {
+            Some(days) => {
+                Self::post_summary(ctx, mm, project_id, thread_id, &messages, days).await?
+            }
+            None => None,
+        };
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO archived_threads (project_id, thread_id, last_message_ts, summary_message_id)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT(project_id, thread_id) DO UPDATE SET
+                    last_message_ts = excluded.last_message_ts,
+                    summary_message_id = COALESCE(excluded.summary_message_id, archived_threads.summary_message_id)
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            project_id.get(),
+            thread_id,
+            last_message_ts.format(TS_FORMAT).to_string(),
+            summary_message_id.map(MessageId::get),
+        ))
+        .await?;
+
+        Self::get(ctx, mm, project_id, thread_id).await
+    }
+
+    /// Returns a thread's archive record, `None` if it is active.
+    pub async fn get(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Option<ArchivedThread>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT project_id, thread_id, last_message_ts, summary_message_id, archived_ts
+                FROM archived_threads WHERE project_id = ? AND thread_id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Lists a project's archived threads, most recently archived first.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<ArchivedThread>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT project_id, thread_id, last_message_ts, summary_message_id, archived_ts
+                FROM archived_threads WHERE project_id = ?
+                ORDER BY archived_ts DESC, thread_id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut threads = Vec::new();
+        while let Some(row) = rows.next().await? {
+            threads.push(Self::from_row(&row)?);
+        }
+        Ok(threads)
+    }
+
+    /// Reactivates an archived thread without posting to it.
+    ///
+    /// # Returns
+    /// Whether the thread was archived.
+    pub async fn reactivate(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<bool> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM archived_threads WHERE project_id = ? AND thread_id = ?")
+            .await?;
+        Ok(stmt.execute((project_id.get(), thread_id)).await? > 0)
+    }
+
+    fn from_row(row: &Row) -> Result<ArchivedThread> {
+        let last_message_ts: String = row.get(2)?;
+        let archived_ts: String = row.get(4)?;
+        Ok(ArchivedThread {
+            project_id: row.get(0)?,
+            thread_id: row.get(1)?,
+            last_message_ts: parse_timestamp(&last_message_ts, "last_message_ts"),
+            summary_message_id: row.get(3)?,
+            archived_ts: parse_timestamp(&archived_ts, "archived_ts"),
+        })
+    }
+
+    /// Posts the closing summary of a thread to everyone in it.
+    ///
+    /// # Returns
+    /// The summary's message id, `None` if the thread has no one to address.
+    async fn post_summary(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        messages: &[Message],
+        inactive_days: u64,
+    ) -> Result<Option<MessageId>> {
+        let (Some(first), Some(last)) = (messages.first(), messages.last()) else {
+            return Ok(None);
+        };
+        let sender_id = Self::archivist_agent(ctx, mm, project_id).await?;
+        let mut recipient_ids =
+            MessageBmc::thread_participants(ctx, mm, project_id, thread_id).await?;
+        recipient_ids.retain(|id| *id != sender_id);
+        if recipient_ids.is_empty() {
+            return Ok(None);
+        }
+
+        let mut participants: Vec<&str> = messages
+            .iter()
+            .map(|m| m.sender_name.as_str())
+            .filter(|name| *name != ARCHIVIST_AGENT_NAME)
+            .collect();
+        participants.sort_unstable();
+        participants.dedup();
+        let snippet: String = last
+            .body_md
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(SUMMARY_SNIPPET_CHARS)
+            .collect();
+
+        let catalog = MessageCatalogBmc::for_project(ctx, mm, project_id).await?;
+        let msg_c = MessageForCreate {
+            project_id,
+            sender_id,
+            recipient_ids,
+            cc_ids: None,
+            bcc_ids: None,
+            subject: catalog.render("thread.archived.subject", &[("subject", &first.subject)]),
+            body_md: catalog.render(
+                "thread.archived.body",
+                &[
+                    ("days", &inactive_days.to_string()),
+                    ("message_count", &messages.len().to_string()),
+                    ("participants", &participants.join(", ")),
+                    ("first_ts", &first.created_ts.to_string()),
+                    ("last_ts", &last.created_ts.to_string()),
+                    ("last_sender", &last.sender_name),
+                    ("last_snippet", &snippet),
+                ],
+            ),
+            thread_id: Some(thread_id.to_string()),
+            importance: Some("low".to_string()),
+            ack_required: false,
+        };
+        Ok(Some(MessageBmc::create(ctx, mm, msg_c).await?))
+    }
+
+    /// Returns the project's `archivist` agent, registering it on first use.
+    async fn archivist_agent(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<AgentId> {
+        match AgentBmc::get_by_name(ctx, mm, project_id, ARCHIVIST_AGENT_NAME).await {
+            Ok(agent) => Ok(agent.id),
+            Err(Error::AgentNotFound { .. }) | Err(Error::NotFound) => {
+                let id = AgentBmc::create(
+                    ctx,
+                    mm,
+                    AgentForCreate {
+                        project_id,
+                        name: ARCHIVIST_AGENT_NAME.to_string(),
+                        program: "archivist".to_string(),
+                        model: "none".to_string(),
+                        task_description: "Posts closing summaries of archived threads".to_string(),
+                    },
+                )
+                .await?;
+                Ok(id)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
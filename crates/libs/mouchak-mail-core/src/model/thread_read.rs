@@ -0,0 +1,295 @@
+//! Per-agent read positions in threads.
+//!
+//! Each agent has a read position per thread: the id of the newest thread
+//! message it has seen. Messages with a higher id are unread for that agent,
+//! except those it sent itself. Positions only move forward, so re-reading an
+//! old message never marks newer ones unread.
+//!
+//! `list_threads` uses positions for per-thread unread counts, and
+//! `get_thread` can return only the messages since the last read, so agents
+//! revisiting long threads do not refetch what they have already seen.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::listing::{Keyset, ListPage, keyset_page, page_limit};
+use crate::model::message::{Message, MessageBmc, ThreadSummary};
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// A thread with one agent's unread state.
+///
+/// # Fields
+///
+/// - `unread_count` - Messages after the read position not sent by the agent
+/// - `last_read_message_id` - Read position, `None` if never read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadUnread {
+    #[serde(flatten)]
+    pub thread: ThreadSummary,
+    pub unread_count: usize,
+    pub last_read_message_id: Option<i64>,
+}
+
+/// Backend Model Controller for thread read positions.
+pub struct ThreadReadBmc;
+
+impl ThreadReadBmc {
+    /// Returns an agent's read position in a thread, `None` if never read.
+    pub async fn get_position(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        thread_id: &str,
+    ) -> Result<Option<MessageId>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "SELECT last_read_message_id FROM thread_reads WHERE project_id = ? AND agent_id = ? AND thread_id = ?",
+            )
+            .await?;
+        let mut rows = stmt
+            .query((project_id.get(), agent_id.get(), thread_id))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(MessageId::new(row.get(0)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Moves an agent's read position in a thread up to `message_id`.
+    ///
+    /// A position already past `message_id` is kept.
+    pub async fn mark_read(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        thread_id: &str,
+        message_id: MessageId,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO thread_reads (project_id, agent_id, thread_id, last_read_message_id, updated_ts)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(project_id, agent_id, thread_id) DO UPDATE SET
+                    last_read_message_id = MAX(thread_reads.last_read_message_id, excluded.last_read_message_id),
+                    updated_ts = excluded.updated_ts
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            project_id.get(),
+            agent_id.get(),
+            thread_id,
+            message_id.get(),
+            now,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Marks a whole thread read for an agent.
+    ///
+    /// # Returns
+    /// The new read position, or `None` if the thread has no messages.
+    pub async fn mark_all_read(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        thread_id: &str,
+    ) -> Result<Option<MessageId>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT MAX(id) FROM messages WHERE project_id = ? AND thread_id = ?")
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        let newest: Option<i64> = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => None,
+        };
+        let Some(newest) = newest.map(MessageId::new) else {
+            return Ok(None);
+        };
+        Self::mark_read(ctx, mm, project_id, agent_id, thread_id, newest).await?;
+        Ok(Some(newest))
+    }
+
+    /// Lists the thread messages an agent has not read yet, oldest first.
+    ///
+    /// Does not move the read position.
+    pub async fn list_unread(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        thread_id: &str,
+    ) -> Result<Vec<Message>> {
+        let position = Self::get_position(ctx, mm, project_id, agent_id, thread_id).await?;
+        let messages =
+            MessageBmc::list_by_thread_after(ctx, mm, project_id, thread_id, position).await?;
+        Ok(messages
+            .into_iter()
+            .filter(|m| m.sender_id != agent_id.get())
+            .collect())
+    }
+
+    /// Lists a project's threads, most recently active first, with an agent's
+    /// unread count for each.
+    ///
+    /// Archived threads are left out unless `include_archived` is set.
+    pub async fn list_threads(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        limit: i64,
+        include_archived: bool,
+    ) -> Result<Vec<ThreadUnread>> {
+        let db = mm.db_read();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT
+                m.thread_id,
+                MIN(m.subject) as subject,
+                COUNT(*) as message_count,
+                MAX(m.created_ts) as last_message_ts,
+                MAX(tr.last_read_message_id) as last_read_message_id,
+                SUM(CASE
+                    WHEN m.sender_id != ?2 AND m.id > COALESCE(tr.last_read_message_id, 0) THEN 1
+                    ELSE 0
+                END) as unread_count,
+                a.thread_id IS NOT NULL as archived
+            FROM messages AS m
+            LEFT JOIN thread_reads AS tr
+                ON tr.project_id = m.project_id AND tr.agent_id = ?2 AND tr.thread_id = m.thread_id
+            LEFT JOIN archived_threads AS a
+                ON a.project_id = m.project_id AND a.thread_id = m.thread_id
+            WHERE m.project_id = ?1 AND m.thread_id IS NOT NULL
+              AND (?4 OR a.thread_id IS NULL)
+            GROUP BY m.thread_id, a.thread_id
+            ORDER BY last_message_ts DESC
+            LIMIT ?3
+            "#,
+            )
+            .await?;
+
+        let mut rows = stmt
+            .query((project_id.get(), agent_id.get(), limit, include_archived))
+            .await?;
+        let mut threads = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let message_count: i64 = row.get(2)?;
+            let last_message_ts: String = row.get(3)?;
+            let unread_count: i64 = row.get(5)?;
+            threads.push(ThreadUnread {
+                thread: ThreadSummary {
+                    thread_id: row.get(0)?,
+                    subject: row.get(1)?,
+                    message_count: message_count as usize,
+                    last_message_ts: NaiveDateTime::parse_from_str(&last_message_ts, TS_FORMAT)
+                        .unwrap_or_default(),
+                    archived: row.get(6)?,
+                },
+                unread_count: unread_count as usize,
+                last_read_message_id: row.get(4)?,
+            });
+        }
+        Ok(threads)
+    }
+
+    /// Lists a page of a project's threads, most recently active first,
+    /// with an agent's unread count for each.
+    ///
+    /// Pages like [`MessageBmc::list_threads_page`].
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a limit below 1 or a bad cursor.
+    pub async fn list_threads_page(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        limit: i64,
+        cursor: Option<&str>,
+        include_archived: bool,
+    ) -> Result<ListPage<ThreadUnread>> {
+        let limit = page_limit(limit)?;
+        let (after_ts, after_thread) = match Keyset::decode(cursor)? {
+            Some(keyset) => (Some(keyset.ts), Some(keyset.key)),
+            None => (None, None),
+        };
+
+        let db = mm.db_read();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT
+                m.thread_id,
+                MIN(m.subject) as subject,
+                COUNT(*) as message_count,
+                MAX(m.created_ts) as last_message_ts,
+                MAX(tr.last_read_message_id) as last_read_message_id,
+                SUM(CASE
+                    WHEN m.sender_id != ?2 AND m.id > COALESCE(tr.last_read_message_id, 0) THEN 1
+                    ELSE 0
+                END) as unread_count,
+                a.thread_id IS NOT NULL as archived
+            FROM messages AS m
+            LEFT JOIN thread_reads AS tr
+                ON tr.project_id = m.project_id AND tr.agent_id = ?2 AND tr.thread_id = m.thread_id
+            LEFT JOIN archived_threads AS a
+                ON a.project_id = m.project_id AND a.thread_id = m.thread_id
+            WHERE m.project_id = ?1 AND m.thread_id IS NOT NULL
+              AND (?3 OR a.thread_id IS NULL)
+            GROUP BY m.thread_id, a.thread_id
+            HAVING ?4 IS NULL OR MAX(m.created_ts) < ?4
+                OR (MAX(m.created_ts) = ?4 AND m.thread_id < ?5)
+            ORDER BY last_message_ts DESC, m.thread_id DESC
+            LIMIT ?6
+            "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                project_id.get(),
+                agent_id.get(),
+                include_archived,
+                after_ts,
+                after_thread,
+                limit + 1,
+            ))
+            .await?;
+
+        let mut threads = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let message_count: i64 = row.get(2)?;
+            let last_message_ts: String = row.get(3)?;
+            let unread_count: i64 = row.get(5)?;
+            threads.push(ThreadUnread {
+                thread: ThreadSummary {
+                    thread_id: row.get(0)?,
+                    subject: row.get(1)?,
+                    message_count: usize::try_from(message_count).unwrap_or_default(),
+                    last_message_ts: parse_timestamp(&last_message_ts, "messages.created_ts"),
+                    archived: row.get(6)?,
+                },
+                unread_count: usize::try_from(unread_count).unwrap_or_default(),
+                last_read_message_id: row.get(4)?,
+            });
+        }
+
+        let total = MessageBmc::count_threads(mm, project_id, include_archived).await?;
+        Ok(keyset_page(threads, limit, total, |t| {
+            (t.thread.last_message_ts, t.thread.thread_id.clone())
+        }))
+    }
+}
@@ -0,0 +1,260 @@
+//! Thread watchers: copies of a thread's mail for agents not addressed.
+//!
+//! An overseer or QA agent can watch a thread it is not a recipient of.
+//! Every message sent to the thread afterwards is delivered to the watcher
+//! as a bcc copy, so it shows up in the watcher's inbox without the other
+//! participants seeing it addressed. Copies are recorded as watcher
+//! deliveries, which lets inbox listings flag them.
+//!
+//! Watching is subject to the contact policies of the thread's
+//! participants: a participant with policy `deny` refuses watchers, and one
+//! with `manual` only accepts watchers it has an accepted contact link with
+//! (see [`crate::model::agent_link`]). Messages sent by the watcher, or
+//! already addressed to it, are not copied.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::agent_link::AgentLinkBmc;
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// An agent watching a thread.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreadWatcher {
+    pub project_id: ProjectId,
+    pub thread_id: String,
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Backend Model Controller for thread watchers.
+pub struct ThreadWatcherBmc;
+
+impl ThreadWatcherBmc {
+    /// Starts copying new messages of a thread to `agent_id`.
+    ///
+    /// # Returns
+    /// `false` if the agent was already watching the thread.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the thread has no messages in the project, and
+    /// `ContactDenied` if a participant's contact policy refuses the agent.
+    pub async fn watch(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        agent_id: AgentId,
+    ) -> Result<bool> {
+        let participants = Self::participants(mm, project_id, thread_id).await?;
+        if participants.is_empty() {
+            return Err(Error::NotFound);
+        }
+
+        let contacts: HashSet<i64> = AgentLinkBmc::list_contacts(ctx, mm, project_id, agent_id)
+            .await?
+            .into_iter()
+            .map(|link| {
+                if link.a_agent_id == agent_id.get() {
+                    link.b_agent_id
+                } else {
+                    link.a_agent_id
+                }
+            })
+            .collect();
+        for participant_id in participants {
+            if participant_id == agent_id.get() {
+                continue;
+            }
+            let participant = AgentBmc::get(ctx, mm, AgentId::new(participant_id)).await?;
+            let allowed = match participant.contact_policy.as_str() {
+                "deny" => false,
+                "manual" => contacts.contains(&participant_id),
+                _ => true,
+            };
+            if !allowed {
+                return Err(Error::ContactDenied(format!(
+                    "'{}' does not accept watchers on thread '{}' (contact policy '{}')",
+                    participant.name, thread_id, participant.contact_policy
+                )));
+            }
+        }
+
+        let now = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT OR IGNORE INTO thread_watchers (project_id, thread_id, agent_id, created_ts)
+                VALUES (?, ?, ?, ?)
+                "#,
+            )
+            .await?;
+        let inserted = stmt
+            .execute((project_id.get(), thread_id, agent_id.get(), now))
+            .await?;
+        Ok(inserted > 0)
+    }
+
+    /// Stops copying a thread's messages to `agent_id`.
+    ///
+    /// # Returns
+    /// `false` if the agent was not watching the thread.
+    pub async fn unwatch(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        agent_id: AgentId,
+    ) -> Result<bool> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "DELETE FROM thread_watchers WHERE project_id = ? AND thread_id = ? AND agent_id = ?",
+            )
+            .await?;
+        let deleted = stmt
+            .execute((project_id.get(), thread_id, agent_id.get()))
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Lists the watchers of a thread, oldest first.
+    pub async fn list_watchers(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Vec<ThreadWatcher>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT w.agent_id, a.name, w.created_ts
+                FROM thread_watchers w
+                JOIN agents a ON a.id = w.agent_id
+                WHERE w.project_id = ? AND w.thread_id = ?
+                ORDER BY w.created_ts, a.name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        let mut watchers = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(2)?;
+            watchers.push(ThreadWatcher {
+                project_id,
+                thread_id: thread_id.to_string(),
+                agent_id: AgentId::new(row.get(0)?),
+                agent_name: row.get(1)?,
+                created_ts: parse_timestamp(&created_ts, "thread_watchers.created_ts"),
+            });
+        }
+        Ok(watchers)
+    }
+
+    /// Which of `message_ids` reached `agent_id` as watcher copies.
+    pub async fn watcher_deliveries(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        agent_id: AgentId,
+        message_ids: &[i64],
+    ) -> Result<HashSet<i64>> {
+        if message_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let placeholders = vec!["?"; message_ids.len()].join(",");
+        let query = format!(
+            "SELECT message_id FROM watcher_deliveries WHERE agent_id = ? AND message_id IN ({})",
+            placeholders
+        );
+        let mut params: Vec<libsql::Value> = vec![agent_id.get().into()];
+        params.extend(message_ids.iter().map(|&id| libsql::Value::from(id)));
+
+        let db = mm.db();
+        let stmt = db.prepare(&query).await?;
+        let mut rows = stmt
+            .query(libsql::params::Params::Positional(params))
+            .await?;
+        let mut delivered = HashSet::new();
+        while let Some(row) = rows.next().await? {
+            delivered.insert(row.get::<i64>(0)?);
+        }
+        Ok(delivered)
+    }
+
+    /// Watchers of a thread, by agent id.
+    pub(crate) async fn watcher_ids(
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Vec<AgentId>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT agent_id FROM thread_watchers WHERE project_id = ? AND thread_id = ?")
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        let mut ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ids.push(AgentId::new(row.get(0)?));
+        }
+        Ok(ids)
+    }
+
+    /// Records that `message_id` was copied to `watcher_ids`.
+    pub(crate) async fn record_deliveries(
+        mm: &ModelManager,
+        message_id: MessageId,
+        watcher_ids: &[AgentId],
+    ) -> Result<()> {
+        let db = mm.db();
+        for watcher_id in watcher_ids {
+            let stmt = db
+                .prepare(
+                    "INSERT OR IGNORE INTO watcher_deliveries (message_id, agent_id) VALUES (?, ?)",
+                )
+                .await?;
+            stmt.execute((message_id.get(), watcher_id.get())).await?;
+        }
+        Ok(())
+    }
+
+    /// Senders and addressed recipients of a thread's messages.
+    async fn participants(
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Vec<i64>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT m.sender_id FROM messages m
+                WHERE m.project_id = ? AND m.thread_id = ?
+                UNION
+                SELECT mr.agent_id FROM message_recipients mr
+                JOIN messages m ON m.id = mr.message_id
+                WHERE m.project_id = ? AND m.thread_id = ?
+                  AND NOT EXISTS (
+                      SELECT 1 FROM watcher_deliveries wd
+                      WHERE wd.message_id = mr.message_id AND wd.agent_id = mr.agent_id
+                  )
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((project_id.get(), thread_id, project_id.get(), thread_id))
+            .await?;
+        let mut ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ids.push(row.get::<i64>(0)?);
+        }
+        Ok(ids)
+    }
+}
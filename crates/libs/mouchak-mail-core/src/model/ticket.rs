@@ -0,0 +1,399 @@
+//! Jira and Linear tickets linked to messages.
+//!
+//! A ticket is stored once per provider and key and can be linked to any
+//! number of messages. Its title and status are cached here; fetching them
+//! from the provider happens in the MCP layer, which refreshes tickets whose
+//! cache is older than the configured interval. A failed fetch keeps the last
+//! known metadata and records the error.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::message::MessageBmc;
+use crate::store::db_statement::Row;
+use crate::types::MessageId;
+use crate::utils::{parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Issue tracker a ticket lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TicketProvider {
+    Jira,
+    Linear,
+}
+
+impl TicketProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Jira => "jira",
+            Self::Linear => "linear",
+        }
+    }
+}
+
+impl fmt::Display for TicketProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TicketProvider {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "jira" => Ok(Self::Jira),
+            "linear" => Ok(Self::Linear),
+            other => Err(Error::InvalidInput(format!(
+                "Unknown ticket provider '{}': expected jira or linear",
+                other
+            ))),
+        }
+    }
+}
+
+/// A cached ticket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ticket {
+    pub id: i64,
+    pub provider: TicketProvider,
+    /// Key such as `PROJ-123`, uppercased
+    pub ticket_key: String,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub url: Option<String>,
+    /// Why the last fetch failed
+    pub fetch_error: Option<String>,
+    /// When metadata was last fetched, successfully or not
+    pub fetched_ts: Option<NaiveDateTime>,
+    pub created_ts: NaiveDateTime,
+}
+
+impl Ticket {
+    /// One-line summary, e.g. `jira:PROJ-123 [In Progress] Fix the parser`.
+    pub fn summary(&self) -> String {
+        let mut line = format!("{}:{}", self.provider, self.ticket_key);
+        if let Some(status) = &self.status {
+            line.push_str(&format!(" [{}]", status));
+        }
+        if let Some(title) = &self.title {
+            line.push(' ');
+            line.push_str(title);
+        }
+        if self.fetched_ts.is_none() {
+            line.push_str(" (not fetched yet)");
+        } else if self.fetch_error.is_some() {
+            line.push_str(" (refresh failed)");
+        }
+        line
+    }
+}
+
+/// Ticket metadata as returned by a provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketMetadata {
+    pub title: String,
+    pub status: String,
+    pub url: Option<String>,
+}
+
+/// Checks a ticket key looks like `PROJ-123` and returns it uppercased.
+///
+/// Jira issue keys and Linear issue identifiers share this shape.
+pub fn normalize_ticket_key(key: &str) -> Result<String> {
+    let key = key.trim().to_ascii_uppercase();
+    let valid = match key.split_once('-') {
+        Some((prefix, number)) => {
+            prefix.len() <= 20
+                && prefix.starts_with(|c: char| c.is_ascii_alphabetic())
+                && prefix
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !number.is_empty()
+                && number.len() <= 10
+                && !number.starts_with('0')
+                && number.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    };
+    if valid {
+        Ok(key)
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Invalid ticket key '{}': expected a key like PROJ-123",
+            key
+        )))
+    }
+}
+
+/// Backend Model Controller for linked tickets.
+pub struct TicketBmc;
+
+impl TicketBmc {
+    /// Links a ticket to a message, creating the ticket if it is new.
+    ///
+    /// Linking the same ticket twice is a no-op.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a malformed key and `MessageNotFound` if
+    /// the message does not exist.
+    pub async fn link(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        message_id: MessageId,
+        provider: TicketProvider,
+        ticket_key: &str,
+    ) -> Result<Ticket> {
+        let ticket_key = normalize_ticket_key(ticket_key)?;
+        MessageBmc::get(ctx, mm, message_id).await?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO tickets (provider, ticket_key) VALUES (?, ?)
+                ON CONFLICT(provider, ticket_key) DO NOTHING
+                "#,
+            )
+            .await?;
+        stmt.execute((provider.as_str(), ticket_key.as_str()))
+            .await?;
+        let ticket = Self::get_by_key(ctx, mm, provider, &ticket_key).await?;
+
+        let stmt = db
+            .prepare("INSERT OR IGNORE INTO message_tickets (message_id, ticket_id) VALUES (?, ?)")
+            .await?;
+        stmt.execute((message_id.get(), ticket.id)).await?;
+        Ok(ticket)
+    }
+
+    /// Gets a ticket by provider and key.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the ticket was never linked.
+    pub async fn get_by_key(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        provider: TicketProvider,
+        ticket_key: &str,
+    ) -> Result<Ticket> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE provider = ? AND ticket_key = ?",
+                SELECT_TICKET
+            ))
+            .await?;
+        let mut rows = stmt
+            .query((provider.as_str(), ticket_key.to_ascii_uppercase()))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Tickets linked to each of `message_ids`, in link order.
+    ///
+    /// Messages without tickets have no entry.
+    pub async fn list_for_messages(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        message_ids: &[i64],
+    ) -> Result<HashMap<i64, Vec<Ticket>>> {
+        let mut tickets: HashMap<i64, Vec<Ticket>> = HashMap::new();
+        if message_ids.is_empty() {
+            return Ok(tickets);
+        }
+
+        let db = mm.db();
+        let placeholders = vec!["?"; message_ids.len()].join(", ");
+        let sql = format!(
+            r#"
+            SELECT mt.message_id, t.id, t.provider, t.ticket_key, t.title, t.status, t.url,
+                   t.fetch_error, t.fetched_ts, t.created_ts
+            FROM message_tickets AS mt
+            JOIN tickets AS t ON t.id = mt.ticket_id
+            WHERE mt.message_id IN ({})
+            ORDER BY mt.created_ts, t.id
+            "#,
+            placeholders
+        );
+        let params: Vec<libsql::Value> = message_ids
+            .iter()
+            .map(|id| libsql::Value::Integer(*id))
+            .collect();
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = stmt.query(params).await?;
+        while let Some(row) = rows.next().await? {
+            let message_id: i64 = row.get(0)?;
+            let ticket = Self::ticket_from_columns(&row, 1)?;
+            tickets.entry(message_id).or_default().push(ticket);
+        }
+        Ok(tickets)
+    }
+
+    /// Linked tickets of `providers` never fetched or last fetched over
+    /// `max_age_seconds` ago, least recently fetched first.
+    pub async fn list_stale(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        providers: &[TicketProvider],
+        max_age_seconds: u64,
+        limit: i64,
+    ) -> Result<Vec<Ticket>> {
+        if providers.is_empty() {
+            return Ok(Vec::new());
+        }
+        let db = mm.db();
+        let placeholders = vec!["?"; providers.len()].join(", ");
+        let sql = format!(
+            r#"{}
+            WHERE provider IN ({})
+              AND (fetched_ts IS NULL OR fetched_ts < datetime('now', ?))
+              AND EXISTS (SELECT 1 FROM message_tickets WHERE ticket_id = tickets.id)
+            ORDER BY fetched_ts IS NOT NULL, fetched_ts, id
+            LIMIT ?"#,
+            SELECT_TICKET, placeholders
+        );
+        let mut params: Vec<libsql::Value> = providers
+            .iter()
+            .map(|p| libsql::Value::Text(p.as_str().to_string()))
+            .collect();
+        params.push(libsql::Value::Text(format!("-{} seconds", max_age_seconds)));
+        params.push(libsql::Value::Integer(limit));
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = stmt.query(params).await?;
+        let mut tickets = Vec::new();
+        while let Some(row) = rows.next().await? {
+            tickets.push(Self::from_row(&row)?);
+        }
+        Ok(tickets)
+    }
+
+    /// Caches freshly fetched metadata and clears any previous fetch error.
+    pub async fn update_metadata(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        ticket_id: i64,
+        metadata: &TicketMetadata,
+    ) -> Result<Ticket> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE tickets
+                SET title = ?, status = ?, url = COALESCE(?, url),
+                    fetch_error = NULL, fetched_ts = CURRENT_TIMESTAMP
+                WHERE id = ?
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            metadata.title.as_str(),
+            metadata.status.as_str(),
+            metadata.url.as_deref(),
+            ticket_id,
+        ))
+        .await?;
+        Self::get(ctx, mm, ticket_id).await
+    }
+
+    /// Records a failed fetch, keeping the last known metadata.
+    ///
+    /// The ticket counts as fetched, so it is retried at the next refresh
+    /// interval rather than on every run.
+    pub async fn record_fetch_error(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        ticket_id: i64,
+        error: &str,
+    ) -> Result<Ticket> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "UPDATE tickets SET fetch_error = ?, fetched_ts = CURRENT_TIMESTAMP WHERE id = ?",
+            )
+            .await?;
+        stmt.execute((error, ticket_id)).await?;
+        Self::get(ctx, mm, ticket_id).await
+    }
+
+    /// Gets a ticket by id.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the ticket does not exist.
+    pub async fn get(_ctx: &Ctx, mm: &ModelManager, ticket_id: i64) -> Result<Ticket> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!("{} WHERE id = ?", SELECT_TICKET))
+            .await?;
+        let mut rows = stmt.query([ticket_id]).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn from_row(row: &Row) -> Result<Ticket> {
+        Self::ticket_from_columns(row, 0)
+    }
+
+    fn ticket_from_columns(row: &Row, first: i32) -> Result<Ticket> {
+        let provider: String = row.get(first + 1)?;
+        let fetched_ts: Option<String> = row.get(first + 7)?;
+        let created_ts: String = row.get(first + 8)?;
+        Ok(Ticket {
+            id: row.get(first)?,
+            provider: provider.parse()?,
+            ticket_key: row.get(first + 2)?,
+            title: row.get(first + 3)?,
+            status: row.get(first + 4)?,
+            url: row.get(first + 5)?,
+            fetch_error: row.get(first + 6)?,
+            fetched_ts: parse_timestamp_opt(fetched_ts, "fetched_ts"),
+            created_ts: parse_timestamp(&created_ts, "created_ts"),
+        })
+    }
+}
+
+const SELECT_TICKET: &str = r#"
+    SELECT id, provider, ticket_key, title, status, url, fetch_error, fetched_ts, created_ts
+    FROM tickets
+"#;
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_ticket_key() {
+        assert_eq!(normalize_ticket_key("proj-123").unwrap(), "PROJ-123");
+        assert_eq!(normalize_ticket_key(" ENG2-7 ").unwrap(), "ENG2-7");
+        assert!(normalize_ticket_key("PROJ").is_err());
+        assert!(normalize_ticket_key("PROJ-").is_err());
+        assert!(normalize_ticket_key("PROJ-012").is_err());
+        assert!(normalize_ticket_key("1PROJ-1").is_err());
+        assert!(normalize_ticket_key("PR OJ-1").is_err());
+        assert!(normalize_ticket_key("PROJ-1-2").is_err());
+    }
+
+    #[test]
+    fn test_provider_parse() {
+        assert_eq!(
+            "Jira".parse::<TicketProvider>().unwrap(),
+            TicketProvider::Jira
+        );
+        assert_eq!(
+            "linear".parse::<TicketProvider>().unwrap(),
+            TicketProvider::Linear
+        );
+        assert!("github".parse::<TicketProvider>().is_err());
+    }
+}
@@ -40,6 +40,8 @@
 use crate::Ctx;
 use crate::Result;
 use crate::model::ModelManager;
+use crate::store::db_statement::Row;
+use crate::types::ProjectId;
 use serde::{Deserialize, Serialize};
 
 /// A recorded MCP tool invocation metric.
@@ -163,7 +165,7 @@ impl ToolMetricBmc {
     pub async fn list_recent(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: Option<i64>,
+        project_id: Option<ProjectId>,
         limit: i64,
     ) -> Result<Vec<ToolMetric>> {
         let db = mm.db();
@@ -173,7 +175,7 @@ impl ToolMetricBmc {
         if let Some(pid) = project_id {
             let stmt = db.prepare("SELECT id, project_id, agent_id, tool_name, args_json, status, error_code, duration_ms, created_at FROM tool_metrics WHERE project_id = ? ORDER BY created_at DESC LIMIT ?").await?;
             let mut rows = stmt
-                .query(vec![pid.into(), limit.into()] as Vec<libsql::Value>)
+                .query(vec![pid.get().into(), limit.into()] as Vec<libsql::Value>)
                 .await?;
             while let Some(row) = rows.next().await? {
                 metrics.push(Self::row_to_metric(&row)?);
@@ -189,7 +191,7 @@ impl ToolMetricBmc {
         Ok(metrics)
     }
 
-    fn row_to_metric(row: &libsql::Row) -> Result<ToolMetric> {
+    fn row_to_metric(row: &Row) -> Result<ToolMetric> {
         Ok(ToolMetric {
             id: row.get(0)?,
             project_id: row.get(1)?,
@@ -217,7 +219,7 @@ impl ToolMetricBmc {
     pub async fn get_stats(
         _ctx: &Ctx,
         mm: &ModelManager,
-        project_id: Option<i64>,
+        project_id: Option<ProjectId>,
     ) -> Result<Vec<ToolStat>> {
         let db = mm.db();
 
@@ -235,7 +237,9 @@ impl ToolMetricBmc {
                 GROUP BY tool_name ORDER BY count DESC
             "#;
             let stmt = db.prepare(sql).await?;
-            let mut rows = stmt.query(vec![pid.into()] as Vec<libsql::Value>).await?;
+            let mut rows = stmt
+                .query(vec![pid.get().into()] as Vec<libsql::Value>)
+                .await?;
             while let Some(row) = rows.next().await? {
                 stats.push(Self::row_to_stat(&row)?);
             }
@@ -259,7 +263,7 @@ impl ToolMetricBmc {
         Ok(stats)
     }
 
-    fn row_to_stat(row: &libsql::Row) -> Result<ToolStat> {
+    fn row_to_stat(row: &Row) -> Result<ToolStat> {
         Ok(ToolStat {
             tool_name: row.get(0)?,
             count: row.get(1)?,
@@ -0,0 +1,222 @@
+//! Keyboard-driven inbox triage.
+//!
+//! A triage cursor walks an agent's inbox one message at a time, in inbox
+//! order (newest first), visiting only messages that still need attention.
+//! Given the message currently shown, [`TriageBmc::step`] returns the next
+//! or previous matching message, so clients can implement `j`/`k` triage
+//! without refetching and diffing inbox lists.
+//!
+//! The cursor is stateless: position is the current message's
+//! `(created_ts, id)`, so a message that stops matching (e.g. after being
+//! read) does not break navigation.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::message::{Message, MessageBmc};
+use crate::types::{AgentId, MessageId, ProjectId};
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Direction to move the cursor in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TriageDirection {
+    /// Towards older messages (down the inbox)
+    #[default]
+    Next,
+    /// Towards newer messages (up the inbox)
+    Previous,
+}
+
+/// Which messages the cursor stops at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TriageState {
+    /// Not yet read
+    Unread,
+    /// Requires an acknowledgement that has not been given
+    PendingAck,
+    /// Unread or pending acknowledgement
+    #[default]
+    Actionable,
+}
+
+impl TriageState {
+    fn sql_condition(&self) -> &'static str {
+        match self {
+            Self::Unread => "mr.read_ts IS NULL",
+            Self::PendingAck => "(m.ack_required = 1 AND mr.ack_ts IS NULL)",
+            Self::Actionable => {
+                "(mr.read_ts IS NULL OR (m.ack_required = 1 AND mr.ack_ts IS NULL))"
+            }
+        }
+    }
+}
+
+/// A triage cursor request for one agent's inbox.
+///
+/// # Fields
+///
+/// - `from_message_id` - Message currently shown; `None` starts at the newest
+///   (`Next`) or oldest (`Previous`) match
+/// - `importance` / `thread_id` - Optional filters
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct TriageQuery {
+    pub from_message_id: Option<i64>,
+    #[serde(default)]
+    pub direction: TriageDirection,
+    #[serde(default)]
+    pub state: TriageState,
+    pub importance: Option<String>,
+    pub thread_id: Option<String>,
+}
+
+/// An inbox message with the recipient's read/ack state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageItem {
+    #[serde(flatten)]
+    pub message: Message,
+    pub read_ts: Option<NaiveDateTime>,
+    pub ack_ts: Option<NaiveDateTime>,
+}
+
+/// Result of moving a triage cursor.
+///
+/// # Fields
+///
+/// - `item` - The message moved to, or `None` if there is none in that direction
+/// - `remaining` - Further matches beyond `item` in the same direction
+/// - `total` - All matches in the inbox, regardless of position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageStep {
+    pub item: Option<TriageItem>,
+    pub remaining: i64,
+    pub total: i64,
+}
+
+/// Backend Model Controller for inbox triage cursors.
+pub struct TriageBmc;
+
+impl TriageBmc {
+    /// Moves the triage cursor of an agent's inbox one message.
+    ///
+    /// # Errors
+    /// Returns `MessageNotFound` if `from_message_id` does not exist.
+    pub async fn step(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        agent_id: AgentId,
+        query: &TriageQuery,
+    ) -> Result<TriageStep> {
+        let (from_ts, from_id) = match query.from_message_id {
+            Some(id) => {
+                let current = MessageBmc::get(ctx, mm, MessageId::new(id)).await?;
+                (
+                    Some(current.created_ts.format(TS_FORMAT).to_string()),
+                    Some(id),
+                )
+            }
+            None => (None, None),
+        };
+
+        let (cmp, order) = match query.direction {
+            TriageDirection::Next => ("<", "DESC"),
+            TriageDirection::Previous => (">", "ASC"),
+        };
+        let filter = format!(
+            r#"
+            FROM messages AS m
+            JOIN message_recipients AS mr ON m.id = mr.message_id
+            JOIN agents AS ag ON m.sender_id = ag.id
+            WHERE mr.agent_id = ?1 AND m.project_id = ?2
+              AND {state}
+              AND (?3 IS NULL OR m.importance = ?3)
+              AND (?4 IS NULL OR m.thread_id = ?4)
+              AND (?5 IS NULL OR m.created_ts {cmp} ?5 OR (m.created_ts = ?5 AND m.id {cmp} ?6))
+            "#,
+            state = query.state.sql_condition(),
+        );
+        let params = |from_ts: Option<String>, from_id: Option<i64>| {
+            (
+                agent_id.get(),
+                project_id.get(),
+                query.importance.clone(),
+                query.thread_id.clone(),
+                from_ts,
+                from_id,
+            )
+        };
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                r#"
+                SELECT
+                    m.id, m.project_id, m.sender_id, ag.name, m.thread_id, m.subject, m.body_md,
+                    m.importance, m.ack_required, m.created_ts, m.attachments, mr.read_ts, mr.ack_ts,
+                    COALESCE((SELECT x.public_id FROM public_ids AS x WHERE x.entity = 'message' AND x.entity_id = m.id), '')
+                {filter}
+                ORDER BY m.created_ts {order}, m.id {order}
+                LIMIT 1
+                "#
+            ))
+            .await?;
+        let mut rows = stmt.query(params(from_ts.clone(), from_id)).await?;
+        let item = match rows.next().await? {
+            Some(row) => {
+                let created_ts: String = row.get(9)?;
+                let attachments: String = row.get(10)?;
+                let attachments: Vec<Value> = serde_json::from_str(&attachments)?;
+                Some(TriageItem {
+                    message: Message {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        sender_id: row.get(2)?,
+                        sender_name: row.get(3)?,
+                        thread_id: row.get(4)?,
+                        subject: row.get(5)?,
+                        body_md: row.get(6)?,
+                        importance: row.get(7)?,
+                        ack_required: row.get(8)?,
+                        created_ts: parse_timestamp(&created_ts, "created_ts"),
+                        attachments,
+                        public_id: row.get(13)?,
+                    },
+                    read_ts: parse_timestamp_opt(row.get(11)?, "read_ts"),
+                    ack_ts: parse_timestamp_opt(row.get(12)?, "ack_ts"),
+                })
+            }
+            None => None,
+        };
+
+        let count_sql = format!("SELECT COUNT(*) {filter}");
+        let stmt = db.prepare(&count_sql).await?;
+        let mut rows = stmt.query(params(from_ts, from_id)).await?;
+        let ahead: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+
+        let total = if query.from_message_id.is_some() {
+            let stmt = db.prepare(&count_sql).await?;
+            let mut rows = stmt.query(params(None, None)).await?;
+            match rows.next().await? {
+                Some(row) => row.get(0)?,
+                None => 0,
+            }
+        } else {
+            ahead
+        };
+
+        Ok(TriageStep {
+            remaining: if item.is_some() { ahead - 1 } else { 0 },
+            item,
+            total,
+        })
+    }
+}
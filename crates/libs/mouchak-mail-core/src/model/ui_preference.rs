@@ -0,0 +1,164 @@
+//! Server-side UI preferences.
+//!
+//! A small key-value store for UI state (theme, column layouts, default
+//! project) so it follows a user or agent across browsers and clients instead
+//! of living only in `localStorage`. Values are arbitrary JSON documents;
+//! keys are short identifiers such as `theme` or `inbox.columns`.
+//!
+//! Preferences belong to a [`PreferenceOwner`]: an authenticated user (the
+//! token subject, or `local` when auth is disabled) or an agent.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::types::AgentId;
+use serde_json::Value;
+
+/// Preferences of one owner, keyed by preference name.
+pub type Preferences = serde_json::Map<String, Value>;
+
+/// Maximum number of preferences stored per owner.
+pub const MAX_PREFERENCES: usize = 100;
+
+/// Maximum length of a preference key.
+pub const MAX_KEY_LEN: usize = 64;
+
+/// Maximum size of one serialized preference value in bytes.
+pub const MAX_VALUE_BYTES: usize = 16 * 1024;
+
+/// Owner of a set of preferences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreferenceOwner {
+    /// A human user, identified by auth subject
+    User(String),
+    /// An agent
+    Agent(AgentId),
+}
+
+impl PreferenceOwner {
+    /// Storage key of the owner (`user:<subject>` or `agent:<id>`).
+    pub fn key(&self) -> String {
+        match self {
+            Self::User(subject) => format!("user:{}", subject),
+            Self::Agent(id) => format!("agent:{}", id.get()),
+        }
+    }
+}
+
+/// Backend Model Controller for UI preferences.
+pub struct UiPreferenceBmc;
+
+impl UiPreferenceBmc {
+    /// Returns all preferences of an owner.
+    pub async fn get(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        owner: &PreferenceOwner,
+    ) -> Result<Preferences> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT key, value FROM ui_preferences WHERE owner = ? ORDER BY key")
+            .await?;
+        let mut rows = stmt.query([owner.key()]).await?;
+
+        let mut prefs = Preferences::new();
+        while let Some(row) = rows.next().await? {
+            let key: String = row.get(0)?;
+            let value: String = row.get(1)?;
+            prefs.insert(key, serde_json::from_str(&value).unwrap_or(Value::Null));
+        }
+        Ok(prefs)
+    }
+
+    /// Merges `changes` into an owner's preferences.
+    ///
+    /// Keys set to `null` are removed; other keys are inserted or replaced.
+    ///
+    /// # Returns
+    /// The owner's preferences after the update.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if a key is not a short identifier, a value is
+    /// larger than [`MAX_VALUE_BYTES`], or the owner would exceed
+    /// [`MAX_PREFERENCES`] keys. Nothing is written on error.
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        owner: &PreferenceOwner,
+        changes: Preferences,
+    ) -> Result<Preferences> {
+        let mut merged = Self::get(ctx, mm, owner).await?;
+        let mut writes = Vec::new();
+        for (key, value) in changes {
+            validate_key(&key)?;
+            if value.is_null() {
+                merged.remove(&key);
+                writes.push((key, None));
+                continue;
+            }
+            let encoded = value.to_string();
+            if encoded.len() > MAX_VALUE_BYTES {
+                return Err(crate::Error::InvalidInput(format!(
+                    "Preference '{}' is too large ({} bytes, max {})",
+                    key,
+                    encoded.len(),
+                    MAX_VALUE_BYTES
+                )));
+            }
+            merged.insert(key.clone(), value);
+            writes.push((key, Some(encoded)));
+        }
+        if merged.len() > MAX_PREFERENCES {
+            return Err(crate::Error::InvalidInput(format!(
+                "Too many preferences ({}, max {})",
+                merged.len(),
+                MAX_PREFERENCES
+            )));
+        }
+
+        let owner_key = owner.key();
+        let db = mm.db();
+        for (key, encoded) in writes {
+            match encoded {
+                Some(encoded) => {
+                    let stmt = db
+                        .prepare(
+                            r#"
+                            INSERT INTO ui_preferences (owner, key, value) VALUES (?, ?, ?)
+                            ON CONFLICT(owner, key) DO UPDATE SET
+                                value = excluded.value,
+                                updated_ts = CURRENT_TIMESTAMP
+                            "#,
+                        )
+                        .await?;
+                    stmt.execute((owner_key.as_str(), key.as_str(), encoded))
+                        .await?;
+                }
+                None => {
+                    let stmt = db
+                        .prepare("DELETE FROM ui_preferences WHERE owner = ? AND key = ?")
+                        .await?;
+                    stmt.execute((owner_key.as_str(), key.as_str())).await?;
+                }
+            }
+        }
+
+        Ok(merged)
+    }
+}
+
+fn validate_key(key: &str) -> Result<()> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(crate::Error::InvalidInput(format!(
+            "Invalid preference key '{}' (use up to {} letters, digits, '_', '-' or '.')",
+            key, MAX_KEY_LEN
+        )))
+    }
+}
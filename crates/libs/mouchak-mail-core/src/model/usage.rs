@@ -0,0 +1,234 @@
+//! Storage usage per agent and project, and the quotas that cap it.
+//!
+//! [`UsageBmc`] reports what each agent has put into storage: messages sent
+//! (count and body bytes), attachments uploaded and messages waiting in its
+//! inbox. Project reports add the totals and list agents by storage used,
+//! largest first, so a runaway agent stands out.
+//!
+//! When `quota.enabled` is set, sending and uploading check the per-agent
+//! limits (`quota.agent_messages_limit_count`,
+//! `quota.agent_attachments_limit_bytes`) next to the existing per-project
+//! attachment and per-recipient inbox limits. A limit of 0 is unlimited.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, ProjectId};
+use crate::{Error, Result};
+use serde::Serialize;
+
+/// Storage used by one agent.
+///
+/// # Fields
+///
+/// - `messages_sent` / `message_bytes` - Messages sent and their body size
+/// - `attachments` / `attachment_bytes` - Attachments uploaded
+/// - `inbox_count` - Messages addressed to the agent
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentUsage {
+    pub agent_id: AgentId,
+    pub agent_name: String,
+    pub messages_sent: i64,
+    pub message_bytes: i64,
+    pub attachments: i64,
+    pub attachment_bytes: i64,
+    pub inbox_count: i64,
+}
+
+impl AgentUsage {
+    /// Bytes of message bodies and attachments together.
+    pub fn storage_bytes(&self) -> i64 {
+        self.message_bytes + self.attachment_bytes
+    }
+}
+
+/// Quota limits in effect; `None` means unlimited.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaLimits {
+    pub enabled: bool,
+    pub project_attachments_bytes: Option<i64>,
+    pub inbox_count: Option<i64>,
+    pub agent_messages: Option<i64>,
+    pub agent_attachments_bytes: Option<i64>,
+}
+
+impl QuotaLimits {
+    fn from_config(mm: &ModelManager) -> Self {
+        let quota = &mm.app_config.quota;
+        let limit = |value: u64| {
+            i64::try_from(value)
+                .ok()
+                .filter(|v| quota.enabled && *v > 0)
+        };
+        Self {
+            enabled: quota.enabled,
+            project_attachments_bytes: limit(quota.attachments_limit_bytes),
+            inbox_count: limit(quota.inbox_limit_count),
+            agent_messages: limit(quota.agent_messages_limit_count),
+            agent_attachments_bytes: limit(quota.agent_attachments_limit_bytes),
+        }
+    }
+}
+
+/// Storage used by a project and each of its agents.
+///
+/// # Fields
+///
+/// - `attachment_bytes` - Includes attachments not uploaded by an agent
+/// - `agents` - Per-agent usage, most storage first
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectUsage {
+    pub project_id: ProjectId,
+    pub messages: i64,
+    pub message_bytes: i64,
+    pub attachments: i64,
+    pub attachment_bytes: i64,
+    pub agents: Vec<AgentUsage>,
+    pub limits: QuotaLimits,
+}
+
+/// Usage columns of an agent, selected from `agents AS ag`.
+const AGENT_USAGE_COLUMNS: &str = r#"
+    ag.id, ag.name,
+    (SELECT COUNT(*) FROM messages WHERE sender_id = ag.id),
+    (SELECT COALESCE(SUM(octet_length(body_md)), 0) FROM messages WHERE sender_id = ag.id),
+    (SELECT COUNT(*) FROM attachments WHERE agent_id = ag.id),
+    (SELECT COALESCE(SUM(size_bytes), 0) FROM attachments WHERE agent_id = ag.id),
+    (SELECT COUNT(*) FROM message_recipients WHERE agent_id = ag.id)
+"#;
+
+/// Backend Model Controller for storage usage and per-agent quotas.
+pub struct UsageBmc;
+
+impl UsageBmc {
+    /// Reports an agent's storage usage.
+    ///
+    /// # Errors
+    /// Returns `NotFound` for an unknown agent.
+    pub async fn for_agent(_ctx: &Ctx, mm: &ModelManager, agent_id: AgentId) -> Result<AgentUsage> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "SELECT {AGENT_USAGE_COLUMNS} FROM agents AS ag WHERE ag.id = ?"
+            ))
+            .await?;
+        let mut rows = stmt.query([agent_id.get()]).await?;
+        match rows.next().await? {
+            Some(row) => Self::agent_from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Reports a project's storage usage with a breakdown per agent.
+    pub async fn for_project(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<ProjectUsage> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT
+                    (SELECT COUNT(*) FROM messages WHERE project_id = ?1),
+                    (SELECT COALESCE(SUM(octet_length(body_md)), 0)
+                        FROM messages WHERE project_id = ?1),
+                    (SELECT COUNT(*) FROM attachments WHERE project_id = ?1),
+                    (SELECT COALESCE(SUM(size_bytes), 0) FROM attachments WHERE project_id = ?1)
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let (messages, message_bytes, attachments, attachment_bytes) = match rows.next().await? {
+            Some(row) => (row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?),
+            None => (0, 0, 0, 0),
+        };
+
+        let stmt = db
+            .prepare(&format!(
+                "SELECT {AGENT_USAGE_COLUMNS} FROM agents AS ag WHERE ag.project_id = ?"
+            ))
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut agents = Vec::new();
+        while let Some(row) = rows.next().await? {
+            agents.push(Self::agent_from_row(&row)?);
+        }
+        agents.sort_by(|a, b| {
+            b.storage_bytes()
+                .cmp(&a.storage_bytes())
+                .then(b.messages_sent.cmp(&a.messages_sent))
+                .then_with(|| a.agent_name.cmp(&b.agent_name))
+        });
+
+        Ok(ProjectUsage {
+            project_id,
+            messages,
+            message_bytes,
+            attachments,
+            attachment_bytes,
+            agents,
+            limits: QuotaLimits::from_config(mm),
+        })
+    }
+
+    /// Quota limits in effect.
+    pub fn limits(mm: &ModelManager) -> QuotaLimits {
+        QuotaLimits::from_config(mm)
+    }
+
+    /// Checks that `sender_id` may send one more message.
+    ///
+    /// # Errors
+    /// Returns `QuotaExceeded` once the agent has sent its quota.
+    pub(crate) async fn check_message_quota(mm: &ModelManager, sender_id: AgentId) -> Result<()> {
+        let Some(limit) = QuotaLimits::from_config(mm).agent_messages else {
+            return Ok(());
+        };
+        let usage = Self::for_agent(&Ctx::root_ctx(), mm, sender_id).await?;
+        if usage.messages_sent >= limit {
+            return Err(Error::QuotaExceeded(format!(
+                "Agent '{}' has sent {} messages, reaching its limit of {}. \
+                 Ask an operator to raise QUOTA_AGENT_MESSAGES_LIMIT_COUNT.",
+                usage.agent_name, usage.messages_sent, limit
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that `agent_id` may upload `size_bytes` more attachment bytes.
+    ///
+    /// # Errors
+    /// Returns `QuotaExceeded` if the upload would take the agent over its
+    /// quota.
+    pub(crate) async fn check_attachment_quota(
+        mm: &ModelManager,
+        agent_id: AgentId,
+        size_bytes: i64,
+    ) -> Result<()> {
+        let Some(limit) = QuotaLimits::from_config(mm).agent_attachments_bytes else {
+            return Ok(());
+        };
+        let usage = Self::for_agent(&Ctx::root_ctx(), mm, agent_id).await?;
+        if usage.attachment_bytes + size_bytes > limit {
+            return Err(Error::QuotaExceeded(format!(
+                "Agent '{}' has uploaded {} attachment bytes; {} more would exceed its limit of {}. \
+                 Ask an operator to raise QUOTA_AGENT_ATTACHMENTS_LIMIT_BYTES.",
+                usage.agent_name, usage.attachment_bytes, size_bytes, limit
+            )));
+        }
+        Ok(())
+    }
+
+    fn agent_from_row(row: &Row) -> Result<AgentUsage> {
+        Ok(AgentUsage {
+            agent_id: AgentId::new(row.get(0)?),
+            agent_name: row.get(1)?,
+            messages_sent: row.get(2)?,
+            message_bytes: row.get(3)?,
+            attachments: row.get(4)?,
+            attachment_bytes: row.get(5)?,
+            inbox_count: row.get(6)?,
+        })
+    }
+}
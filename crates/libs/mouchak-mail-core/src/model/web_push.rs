@@ -0,0 +1,455 @@
+//! Web Push notifications for human supervisors.
+//!
+//! Browsers register a push subscription (endpoint plus encryption keys) for
+//! one project or for all projects. The server periodically collects urgent
+//! messages created since the last scan with [`WebPushBmc::pending_notices`]
+//! and pushes a short notice to every matching subscription, so supervisors
+//! are told about escalations without keeping the UI open.
+//!
+//! The VAPID key pair identifying this server to push services is generated
+//! on first use and stored in `web_push_state`, together with the cursor of
+//! the last message considered for push. A fresh cursor starts at the newest
+//! existing message, so enabling push never replays old mail. Urgent
+//! messages held for approval or deferred by a focus window are parked (see
+//! [`crate::model::parked_message`]) and pushed once they are delivered;
+//! rejected ones are never pushed.
+
+use crate::Result;
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::parked_message::{self, ParkedMessageBmc, ScanState};
+use crate::types::ProjectId;
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::NaiveDateTime;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use serde::{Deserialize, Serialize};
+
+/// Importance levels that trigger a push.
+pub const PUSH_IMPORTANCE: &[&str] = &["urgent"];
+
+/// Name the push scan parks held and deferred messages under.
+const PARK_CONSUMER: &str = "web_push";
+
+/// Subscriptions are dropped after this many consecutive failed pushes.
+pub const MAX_PUSH_FAILURES: i64 = 5;
+
+/// A browser push subscription.
+///
+/// # Fields
+///
+/// - `endpoint` - Push service URL issued by the browser
+/// - `p256dh` / `auth` - Browser keys used to encrypt payloads (base64url)
+/// - `project_id` - Project to notify about, or `None` for all projects
+/// - `label` - Free-form description (e.g. "alice laptop")
+/// - `failure_count` - Consecutive failed pushes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushSubscription {
+    pub id: i64,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub project_id: Option<i64>,
+    pub label: String,
+    pub created_ts: NaiveDateTime,
+    pub last_push_ts: Option<NaiveDateTime>,
+    pub failure_count: i64,
+}
+
+/// Input data to register a push subscription.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushSubscriptionForCreate {
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub project_id: Option<i64>,
+    pub label: String,
+}
+
+/// VAPID key pair, base64url-encoded without padding.
+///
+/// `public_key` is the uncompressed P-256 point browsers pass as
+/// `applicationServerKey`; `private_key` is the raw 32-byte scalar.
+#[derive(Debug, Clone)]
+pub struct VapidKeys {
+    pub public_key: String,
+    pub private_key: String,
+}
+
+/// An urgent message to push to supervisors.
+#[derive(Debug, Clone, Serialize)]
+pub struct PushNotice {
+    pub message_id: i64,
+    pub project_id: i64,
+    pub project_slug: String,
+    pub sender_name: String,
+    pub subject: String,
+    pub importance: String,
+    pub thread_id: Option<String>,
+}
+
+/// Notices collected by one scan.
+///
+/// `scanned_to` is the ID to pass to [`WebPushBmc::advance_cursor`] once
+/// every notice has been pushed, or `None` if nothing was scanned; it also
+/// skips messages that are parked until they are delivered.
+#[derive(Debug, Clone)]
+pub struct PushBatch {
+    pub notices: Vec<PushNotice>,
+    pub scanned_to: Option<i64>,
+}
+
+impl PushNotice {
+    /// JSON payload delivered to the browser's service worker.
+    pub fn payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "title": format!("[{}] {}", self.importance, self.subject),
+            "body": format!("From {} in {}", self.sender_name, self.project_slug),
+            "url": format!("/inbox/{}?project={}", self.message_id, self.project_slug),
+            "tag": format!("mouchak-message-{}", self.message_id),
+            "message_id": self.message_id,
+            "project_slug": self.project_slug,
+        })
+    }
+}
+
+/// Backend Model Controller for Web Push state and subscriptions.
+pub struct WebPushBmc;
+
+impl WebPushBmc {
+    /// Registers a subscription, replacing any existing one for the endpoint.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` if the endpoint is not an `https` URL or a key
+    /// is missing.
+    pub async fn subscribe(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        sub_c: PushSubscriptionForCreate,
+    ) -> Result<i64> {
+        if !sub_c.endpoint.starts_with("https://") {
+            return Err(crate::Error::InvalidInput(
+                "Push endpoint must be an https URL".into(),
+            ));
+        }
+        if sub_c.p256dh.trim().is_empty() || sub_c.auth.trim().is_empty() {
+            return Err(crate::Error::InvalidInput(
+                "Push subscription requires p256dh and auth keys".into(),
+            ));
+        }
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO web_push_subscriptions (endpoint, p256dh, auth, project_id, label)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(endpoint) DO UPDATE SET
+                    p256dh = excluded.p256dh,
+                    auth = excluded.auth,
+                    project_id = excluded.project_id,
+                    label = excluded.label,
+                    failure_count = 0
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                sub_c.endpoint,
+                sub_c.p256dh,
+                sub_c.auth,
+                sub_c.project_id,
+                sub_c.label,
+            ))
+            .await?;
+
+        let row = rows.next().await?.ok_or(crate::Error::NotFound)?;
+        Ok(row.get::<i64>(0)?)
+    }
+
+    /// Removes the subscription for an endpoint.
+    ///
+    /// # Returns
+    /// `true` if a subscription was removed.
+    pub async fn unsubscribe(_ctx: &Ctx, mm: &ModelManager, endpoint: &str) -> Result<bool> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM web_push_subscriptions WHERE endpoint = ?")
+            .await?;
+        Ok(stmt.execute([endpoint]).await? > 0)
+    }
+
+    /// Lists subscriptions that should be notified about a project.
+    ///
+    /// Includes subscriptions for all projects. With `project_id = None`,
+    /// every subscription is returned.
+    pub async fn list_subscriptions(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: Option<ProjectId>,
+    ) -> Result<Vec<PushSubscription>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, endpoint, p256dh, auth, project_id, label, created_ts, last_push_ts, failure_count
+                FROM web_push_subscriptions
+                WHERE ?1 IS NULL OR project_id IS NULL OR project_id = ?1
+                ORDER BY id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.map(ProjectId::get)]).await?;
+
+        let mut subs = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(6)?;
+            let last_push_ts: Option<String> = row.get(7)?;
+            subs.push(PushSubscription {
+                id: row.get(0)?,
+                endpoint: row.get(1)?,
+                p256dh: row.get(2)?,
+                auth: row.get(3)?,
+                project_id: row.get(4)?,
+                label: row.get(5)?,
+                created_ts: parse_timestamp(&created_ts, "web_push_subscriptions.created_ts"),
+                last_push_ts: parse_timestamp_opt(
+                    last_push_ts,
+                    "web_push_subscriptions.last_push_ts",
+                ),
+                failure_count: row.get(8)?,
+            });
+        }
+        Ok(subs)
+    }
+
+    /// Records the outcome of a push to a subscription.
+    ///
+    /// A success resets the failure count. A subscription that reaches
+    /// [`MAX_PUSH_FAILURES`] consecutive failures is removed.
+    pub async fn record_delivery(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        subscription_id: i64,
+        ok: bool,
+    ) -> Result<()> {
+        let db = mm.db();
+        if ok {
+            let now_str = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+            let stmt = db
+                .prepare(
+                    "UPDATE web_push_subscriptions SET last_push_ts = ?, failure_count = 0 WHERE id = ?",
+                )
+                .await?;
+            stmt.execute((now_str, subscription_id)).await?;
+        } else {
+            let stmt = db
+                .prepare(
+                    "UPDATE web_push_subscriptions SET failure_count = failure_count + 1 WHERE id = ?",
+                )
+                .await?;
+            stmt.execute([subscription_id]).await?;
+            let stmt = db
+                .prepare("DELETE FROM web_push_subscriptions WHERE id = ? AND failure_count >= ?")
+                .await?;
+            stmt.execute((subscription_id, MAX_PUSH_FAILURES)).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes a subscription the push service reported as gone.
+    pub async fn remove(_ctx: &Ctx, mm: &ModelManager, subscription_id: i64) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM web_push_subscriptions WHERE id = ?")
+            .await?;
+        stmt.execute([subscription_id]).await?;
+        Ok(())
+    }
+
+    /// Returns the stored VAPID key pair, generating it on first use.
+    pub async fn vapid_keys(_ctx: &Ctx, mm: &ModelManager) -> Result<VapidKeys> {
+        Self::ensure_state(mm).await?;
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT vapid_public_key, vapid_private_key FROM web_push_state WHERE id = 1")
+            .await?;
+        let mut rows = stmt.query(()).await?;
+        let row = rows.next().await?.ok_or(crate::Error::NotFound)?;
+        Ok(VapidKeys {
+            public_key: row.get(0)?,
+            private_key: row.get(1)?,
+        })
+    }
+
+    /// Lists urgent messages created after the push cursor, and parked ones
+    /// delivered since, oldest first. Messages still held or deferred are
+    /// parked; rejected ones are left out.
+    ///
+    /// # Returns
+    /// At most `limit` notices. Call [`Self::advance_cursor`] with
+    /// `scanned_to` once they have been pushed.
+    pub async fn pending_notices(_ctx: &Ctx, mm: &ModelManager, limit: i64) -> Result<PushBatch> {
+        Self::ensure_state(mm).await?;
+        let placeholders = vec!["?"; PUSH_IMPORTANCE.len()].join(", ");
+        let sql = format!(
+            r#"
+            SELECT m.id, m.project_id, p.slug, ag.name, m.subject, m.importance, m.thread_id, {}
+            FROM messages AS m
+            JOIN projects AS p ON m.project_id = p.id
+            JOIN agents AS ag ON m.sender_id = ag.id
+            WHERE (m.id > (SELECT last_message_id FROM web_push_state WHERE id = 1) OR {})
+              AND m.importance IN ({})
+            ORDER BY m.id ASC
+            LIMIT ?
+            "#,
+            parked_message::state_columns(),
+            parked_message::released_sql(),
+            placeholders
+        );
+
+        let mut params: Vec<libsql::Value> = vec![PARK_CONSUMER.into(), PARK_CONSUMER.into()];
+        params.extend(PUSH_IMPORTANCE.iter().map(|i| (*i).into()));
+        params.push(limit.into());
+
+        let db = mm.db();
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = stmt.query(params).await?;
+
+        let mut scanned = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let state = ScanState {
+                parked: row.get::<i64>(7)? != 0,
+                waiting: row.get::<i64>(8)? != 0,
+                rejected: row.get::<i64>(9)? != 0,
+            };
+            let notice = PushNotice {
+                message_id: row.get(0)?,
+                project_id: row.get(1)?,
+                project_slug: row.get(2)?,
+                sender_name: row.get(3)?,
+                subject: row.get(4)?,
+                importance: row.get(5)?,
+                thread_id: row.get(6)?,
+            };
+            scanned.push((notice, state));
+        }
+
+        // Held and deferred messages are pushed once they are delivered
+        let scanned_to = scanned.last().map(|(last, _)| last.message_id);
+        let mut notices = Vec::new();
+        for (notice, state) in scanned {
+            if ParkedMessageBmc::settle(mm, PARK_CONSUMER, notice.message_id, state).await? {
+                notices.push(notice);
+            }
+        }
+        Ok(PushBatch {
+            notices,
+            scanned_to,
+        })
+    }
+
+    /// Moves the push cursor past `last_message_id`.
+    pub async fn advance_cursor(_ctx: &Ctx, mm: &ModelManager, last_message_id: i64) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "UPDATE web_push_state SET last_message_id = MAX(last_message_id, ?) WHERE id = 1",
+            )
+            .await?;
+        stmt.execute([last_message_id]).await?;
+        ParkedMessageBmc::clear_released(mm, PARK_CONSUMER, last_message_id).await
+    }
+
+    /// Counts urgent messages waiting behind the push cursor.
+    ///
+    /// Returns 0 before the push state exists, since a fresh cursor starts
+    /// at the newest message.
+    pub async fn count_pending(_ctx: &Ctx, mm: &ModelManager) -> Result<i64> {
+        let placeholders = vec!["?"; PUSH_IMPORTANCE.len()].join(", ");
+        let sql = format!(
+            r#"
+            SELECT COUNT(*) FROM messages
+            WHERE id > (SELECT COALESCE(MAX(last_message_id), (SELECT MAX(id) FROM messages))
+                        FROM web_push_state WHERE id = 1)
+              AND importance IN ({})
+            "#,
+            placeholders
+        );
+        let params: Vec<libsql::Value> = PUSH_IMPORTANCE.iter().map(|i| (*i).into()).collect();
+
+        let db = mm.db();
+        let stmt = db.prepare(&sql).await?;
+        let mut rows = stmt.query(params).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<i64>(0)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Counts subscriptions whose last push failed and will be retried.
+    pub async fn count_failing(_ctx: &Ctx, mm: &ModelManager) -> Result<i64> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT COUNT(*) FROM web_push_subscriptions WHERE failure_count > 0")
+            .await?;
+        let mut rows = stmt.query(()).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<i64>(0)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Creates the push state row with a new key pair if it does not exist.
+    async fn ensure_state(mm: &ModelManager) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("SELECT 1 FROM web_push_state WHERE id = 1")
+            .await?;
+        let mut rows = stmt.query(()).await?;
+        if rows.next().await?.is_some() {
+            return Ok(());
+        }
+
+        let keys = generate_vapid_keys();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT OR IGNORE INTO web_push_state (id, vapid_public_key, vapid_private_key, last_message_id)
+                VALUES (1, ?, ?, (SELECT COALESCE(MAX(id), 0) FROM messages))
+                "#,
+            )
+            .await?;
+        stmt.execute((keys.public_key, keys.private_key)).await?;
+        Ok(())
+    }
+}
+
+/// Generates a new P-256 VAPID key pair.
+pub fn generate_vapid_keys() -> VapidKeys {
+    let secret = p256::SecretKey::random(&mut rand_core::OsRng);
+    let public = secret.public_key().to_encoded_point(false);
+    VapidKeys {
+        public_key: URL_SAFE_NO_PAD.encode(public.as_bytes()),
+        private_key: URL_SAFE_NO_PAD.encode(secret.to_bytes()),
+    }
+}
+
+/// Rebuilds a VAPID key pair from a base64url-encoded private key.
+///
+/// # Errors
+/// Returns `InvalidInput` if the key is not a valid P-256 scalar.
+pub fn vapid_keys_from_private_key(private_key: &str) -> Result<VapidKeys> {
+    let invalid = || crate::Error::InvalidInput("Invalid VAPID private key".into());
+    let bytes = URL_SAFE_NO_PAD
+        .decode(private_key.trim().trim_end_matches('='))
+        .map_err(|_| invalid())?;
+    let secret = p256::SecretKey::from_slice(&bytes).map_err(|_| invalid())?;
+    let public = secret.public_key().to_encoded_point(false);
+    Ok(VapidKeys {
+        public_key: URL_SAFE_NO_PAD.encode(public.as_bytes()),
+        private_key: URL_SAFE_NO_PAD.encode(secret.to_bytes()),
+    })
+}
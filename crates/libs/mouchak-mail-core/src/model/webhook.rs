@@ -0,0 +1,711 @@
+//! Webhooks: HTTP callbacks for project events.
+//!
+//! A project registers callback URLs for some of the [`EVENTS`]. Webhooks
+//! follow the event log: [`WebhookBmc::enqueue`] turns every record after a
+//! webhook's `last_event_id` that maps to a subscribed event (see
+//! [`webhook_event`]) into a delivery, so a webhook created now is never sent
+//! history. The server POSTs due deliveries and reports each attempt with
+//! [`WebhookBmc::record_attempt`]; failed attempts are retried with
+//! exponential backoff until the delivery runs out of attempts, when the
+//! project's overseer is told about it.
+//!
+//! Every body is signed with the webhook's secret: the signature header
+//! holds `sha256=` and the hex HMAC-SHA256 of the exact body bytes (see
+//! [`sign`]), so receivers can reject payloads not sent by this server.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::{AgentBmc, AgentForCreate};
+use crate::model::overseer_message::{OverseerMessageBmc, OverseerMessageForCreate};
+use crate::model::project::ProjectBmc;
+use crate::store::db_statement::Row;
+use crate::types::ProjectId;
+use crate::utils::{TS_FORMAT, parse_timestamp, parse_timestamp_opt};
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac};
+use mouchak_mail_common::config::WebhooksConfig;
+use mouchak_mail_common::net;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use tracing::warn;
+
+/// Events a webhook can subscribe to.
+pub const EVENTS: &[&str] = &[
+    "message.created",
+    "message.acknowledged",
+    "reservation.expired",
+];
+
+/// Status of a delivery waiting for its next attempt.
+pub const STATUS_PENDING: &str = "pending";
+
+/// Status of a delivery the receiver accepted.
+pub const STATUS_DELIVERED: &str = "delivered";
+
+/// Status of a delivery that ran out of attempts.
+pub const STATUS_FAILED: &str = "failed";
+
+/// Header carrying the payload signature.
+pub const SIGNATURE_HEADER: &str = "X-Mouchak-Signature";
+
+/// Header carrying the webhook event name.
+pub const EVENT_HEADER: &str = "X-Mouchak-Event";
+
+/// Header carrying the delivery id, stable across retries.
+pub const DELIVERY_HEADER: &str = "X-Mouchak-Delivery";
+
+/// Name of the agent failed deliveries are reported to the overseer from.
+pub const WEBHOOK_AGENT_NAME: &str = "webhooks";
+
+/// Default number of deliveries listed.
+pub const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Prefix of generated secrets.
+const SECRET_PREFIX: &str = "whsec_";
+
+/// Shortest secret accepted from the caller.
+const MIN_SECRET_LEN: usize = 16;
+
+/// Event log records examined per webhook by one [`WebhookBmc::enqueue`].
+const EVENTS_PER_SCAN: i64 = 500;
+
+/// The webhook event an event log record is delivered as, if any.
+pub fn webhook_event(kind: &str) -> Option<&'static str> {
+    match kind {
+        "message.sent" => Some("message.created"),
+        "message.acknowledged" => Some("message.acknowledged"),
+        "file_reservation.expired" => Some("reservation.expired"),
+        _ => None,
+    }
+}
+
+/// Signature header value for `body`: `sha256=` and the hex HMAC-SHA256
+/// keyed with `secret`.
+#[allow(clippy::expect_used)] // HMAC takes keys of any length
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// A registered webhook. The secret is only returned when it is created.
+///
+/// # Fields
+///
+/// - `events` - Subscribed [`EVENTS`]
+/// - `last_event_id` - Last event log record turned into deliveries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: i64,
+    pub project_id: i64,
+    pub url: String,
+    pub events: Vec<String>,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub description: String,
+    pub active: bool,
+    pub last_event_id: i64,
+    pub created_ts: NaiveDateTime,
+    pub updated_ts: NaiveDateTime,
+}
+
+/// Input data to register a webhook.
+///
+/// A secret is generated when `secret` is `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhookForCreate {
+    pub project_id: i64,
+    pub url: String,
+    pub events: Vec<String>,
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// One event sent, or to be sent, to a webhook.
+///
+/// # Fields
+///
+/// - `event_id` - Event log record the delivery was made from
+/// - `payload` - JSON body POSTed to the webhook
+/// - `status` - [`STATUS_PENDING`], [`STATUS_DELIVERED`] or [`STATUS_FAILED`]
+/// - `response_status`, `error` - Outcome of the last attempt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event_id: i64,
+    pub event: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i64,
+    pub next_attempt_ts: NaiveDateTime,
+    pub response_status: Option<i64>,
+    pub error: Option<String>,
+    pub created_ts: NaiveDateTime,
+    pub delivered_ts: Option<NaiveDateTime>,
+}
+
+/// A delivery due for an attempt, with what is needed to send it.
+#[derive(Debug, Clone)]
+pub struct DueDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub url: String,
+    pub secret: String,
+    pub event: String,
+    /// Exact body to send and sign
+    pub body: String,
+    pub attempts: i64,
+}
+
+/// Outcome of one delivery attempt; it succeeded when `error` is `None`.
+#[derive(Debug, Clone, Default)]
+pub struct DeliveryAttempt {
+    pub response_status: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// Which deliveries [`WebhookBmc::list_deliveries`] returns.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeliveryFilter {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+const SELECT_WEBHOOK: &str = r#"
+    SELECT id, project_id, url, events, secret, description, active, last_event_id,
+           created_ts, updated_ts
+    FROM webhooks
+"#;
+
+const SELECT_DELIVERY: &str = r#"
+    SELECT id, webhook_id, event_id, event, payload, status, attempts, next_attempt_ts,
+           response_status, error, created_ts, delivered_ts
+    FROM webhook_deliveries
+"#;
+
+/// Backend Model Controller for webhooks and their deliveries.
+pub struct WebhookBmc;
+
+impl WebhookBmc {
+    /// Registers a webhook; it receives events recorded from now on.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for a URL that is not http(s) or, unless
+    /// `webhooks.allow_private_urls` is set, points at a loopback, private or
+    /// internal host, an unknown or missing event, or a secret shorter than 16 characters, and
+    /// `ProjectNotFound` if the project does not exist.
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, hook_c: WebhookForCreate) -> Result<Webhook> {
+        validate_url(&hook_c.url, &mm.app_config.webhooks)?;
+        let events = validate_events(&hook_c.events)?;
+        let secret = match hook_c.secret {
+            Some(secret) if secret.len() < MIN_SECRET_LEN => {
+                return Err(Error::InvalidInput(format!(
+                    "Webhook secret must be at least {} characters",
+                    MIN_SECRET_LEN
+                )));
+            }
+            Some(secret) => secret,
+            None => {
+                let mut bytes = [0u8; 24];
+                OsRng.fill_bytes(&mut bytes);
+                format!("{}{}", SECRET_PREFIX, hex::encode(bytes))
+            }
+        };
+        ProjectBmc::get(ctx, mm, ProjectId::new(hook_c.project_id)).await?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO webhooks (project_id, url, events, secret, description, last_event_id)
+                VALUES (?, ?, ?, ?, ?, (SELECT COALESCE(MAX(id), 0) FROM event_log))
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                hook_c.project_id,
+                hook_c.url.as_str(),
+                events.join(","),
+                secret.as_str(),
+                hook_c.description.as_str(),
+            ))
+            .await?;
+        let id: i64 = rows
+            .next()
+            .await?
+            .ok_or_else(|| Error::InvalidInput("Failed to create webhook".into()))?
+            .get(0)?;
+        Self::get(ctx, mm, id).await
+    }
+
+    /// Gets a webhook.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if there is no such webhook.
+    pub async fn get(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Webhook> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!("{} WHERE id = ?", SELECT_WEBHOOK))
+            .await?;
+        let mut rows = stmt.query([id]).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Lists webhooks, of one project or of all projects.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: Option<ProjectId>,
+    ) -> Result<Vec<Webhook>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE (?1 IS NULL OR project_id = ?1) ORDER BY id",
+                SELECT_WEBHOOK
+            ))
+            .await?;
+        let mut rows = stmt
+            .query(libsql::params![project_id.map(|p| p.get())])
+            .await?;
+        let mut hooks = Vec::new();
+        while let Some(row) = rows.next().await? {
+            hooks.push(Self::from_row(&row)?);
+        }
+        Ok(hooks)
+    }
+
+    /// Removes a webhook and its delivery log.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if there is no such webhook.
+    pub async fn delete(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare("DELETE FROM webhook_deliveries WHERE webhook_id = ?")
+            .await?;
+        stmt.execute([id]).await?;
+        let stmt = db.prepare("DELETE FROM webhooks WHERE id = ?").await?;
+        if stmt.execute([id]).await? == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Turns event log records recorded since the last scan into pending
+    /// deliveries for the active webhooks subscribed to them.
+    ///
+    /// # Returns
+    /// Number of deliveries created.
+    pub async fn enqueue(ctx: &Ctx, mm: &ModelManager) -> Result<usize> {
+        let db = mm.db();
+        let mut created = 0;
+        for hook in Self::list(ctx, mm, None).await? {
+            if !hook.active {
+                continue;
+            }
+            let stmt = db
+                .prepare(
+                    r#"
+                SELECT id, created_ts, kind, actor, payload
+                FROM event_log
+                WHERE project_id = ? AND id > ?
+                ORDER BY id
+                LIMIT ?
+                "#,
+                )
+                .await?;
+            let mut rows = stmt
+                .query((hook.project_id, hook.last_event_id, EVENTS_PER_SCAN))
+                .await?;
+
+            let mut last_event_id = hook.last_event_id;
+            let mut records = Vec::new();
+            while let Some(row) = rows.next().await? {
+                last_event_id = row.get::<i64>(0)?;
+                let kind: String = row.get(2)?;
+                if let Some(event) = webhook_event(&kind)
+                    && hook.events.iter().any(|e| e == event)
+                {
+                    records.push((
+                        last_event_id,
+                        event,
+                        row.get::<String>(1)?,
+                        row.get::<Option<String>>(3)?,
+                        row.get::<String>(4)?,
+                    ));
+                }
+            }
+
+            for (event_id, event, created_ts, actor, data) in records {
+                let payload = serde_json::json!({
+                    "event": event,
+                    "event_id": event_id,
+                    "webhook_id": hook.id,
+                    "project_id": hook.project_id,
+                    "actor": actor,
+                    "created_ts": created_ts,
+                    "data": serde_json::from_str::<Value>(&data).unwrap_or(Value::Null),
+                });
+                let stmt = db
+                    .prepare(
+                        r#"
+                    INSERT OR IGNORE INTO webhook_deliveries (webhook_id, event_id, event, payload)
+                    VALUES (?, ?, ?, ?)
+                    "#,
+                    )
+                    .await?;
+                created += stmt
+                    .execute((hook.id, event_id, event, payload.to_string()))
+                    .await? as usize;
+            }
+
+            if last_event_id > hook.last_event_id {
+                let stmt = db
+                    .prepare("UPDATE webhooks SET last_event_id = ? WHERE id = ?")
+                    .await?;
+                stmt.execute((last_event_id, hook.id)).await?;
+            }
+        }
+        Ok(created)
+    }
+
+    /// Lists pending deliveries of active webhooks whose next attempt is
+    /// due, oldest first.
+    pub async fn due(_ctx: &Ctx, mm: &ModelManager, limit: i64) -> Result<Vec<DueDelivery>> {
+        let now = chrono::Utc::now().naive_utc().format(TS_FORMAT).to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+            SELECT d.id, d.webhook_id, w.url, w.secret, d.event, d.payload, d.attempts
+            FROM webhook_deliveries AS d
+            JOIN webhooks AS w ON w.id = d.webhook_id
+            WHERE d.status = ? AND d.next_attempt_ts <= ? AND w.active = 1
+            ORDER BY d.next_attempt_ts, d.id
+            LIMIT ?
+            "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((STATUS_PENDING, now.as_str(), limit.clamp(1, 1000)))
+            .await?;
+        let mut due = Vec::new();
+        while let Some(row) = rows.next().await? {
+            due.push(DueDelivery {
+                id: row.get(0)?,
+                webhook_id: row.get(1)?,
+                url: row.get(2)?,
+                secret: row.get(3)?,
+                event: row.get(4)?,
+                body: row.get(5)?,
+                attempts: row.get(6)?,
+            });
+        }
+        Ok(due)
+    }
+
+    /// Records an attempt of a delivery.
+    ///
+    /// A successful attempt marks it delivered. A failed one schedules the
+    /// next attempt after [`WebhooksConfig::retry_delay_seconds`], or marks
+    /// the delivery failed once it made `config.max_attempts` attempts and
+    /// sends the project's overseer a message about it.
+    ///
+    /// # Returns
+    /// The delivery's new status.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if there is no such delivery.
+    pub async fn record_attempt(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        delivery_id: i64,
+        attempt: &DeliveryAttempt,
+        config: &WebhooksConfig,
+    ) -> Result<&'static str> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT d.attempts, d.webhook_id, d.event, w.project_id, w.url
+                FROM webhook_deliveries AS d
+                JOIN webhooks AS w ON w.id = d.webhook_id
+                WHERE d.id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([delivery_id]).await?;
+        let Some(row) = rows.next().await? else {
+            return Err(Error::NotFound);
+        };
+        let attempts = row.get::<i64>(0)? + 1;
+        let webhook_id: i64 = row.get(1)?;
+        let event: String = row.get(2)?;
+        let project_id = ProjectId::new(row.get(3)?);
+        let url: String = row.get(4)?;
+
+        let now = chrono::Utc::now().naive_utc();
+        let (status, next_attempt_ts, delivered_ts) = if attempt.error.is_none() {
+            (STATUS_DELIVERED, now, Some(now))
+        } else if attempts >= i64::from(config.max_attempts) {
+            (STATUS_FAILED, now, None)
+        } else {
+            let attempts = u32::try_from(attempts).unwrap_or(u32::MAX);
+            let delay = i64::try_from(config.retry_delay_seconds(attempts)).unwrap_or(i64::MAX);
+            let next = chrono::Duration::try_seconds(delay)
+                .and_then(|d| now.checked_add_signed(d))
+                .unwrap_or(NaiveDateTime::MAX);
+            (STATUS_PENDING, next, None)
+        };
+
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE webhook_deliveries
+                SET status = ?, attempts = ?, next_attempt_ts = ?, response_status = ?,
+                    error = ?, delivered_ts = ?
+                WHERE id = ?
+                "#,
+            )
+            .await?;
+        stmt.execute(libsql::params![
+            status,
+            attempts,
+            next_attempt_ts.format(TS_FORMAT).to_string(),
+            attempt.response_status,
+            attempt.error.clone(),
+            delivered_ts.map(|ts| ts.format(TS_FORMAT).to_string()),
+            delivery_id,
+        ])
+        .await?;
+
+        if status == STATUS_FAILED {
+            let body_md = format!(
+                "Delivery {} of `{}` to webhook {} ({}) failed after {} attempts and will not be retried.\n\nLast error: {}",
+                delivery_id,
+                event,
+                webhook_id,
+                url,
+                attempts,
+                attempt.error.as_deref().unwrap_or("unknown"),
+            );
+            let reported = async {
+                let sender_id = webhook_agent(ctx, mm, project_id).await?;
+                OverseerMessageBmc::create(
+                    ctx,
+                    mm,
+                    OverseerMessageForCreate {
+                        project_id: project_id.get(),
+                        sender_id,
+                        subject: format!("Webhook delivery failed: {}", event),
+                        body_md,
+                        importance: "high".to_string(),
+                    },
+                )
+                .await
+            }
+            .await;
+            if let Err(e) = reported {
+                warn!(delivery_id, error = %e, "Could not report failed webhook delivery");
+            }
+        }
+        Ok(status)
+    }
+
+    /// Lists a webhook's deliveries, newest first.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if there is no such webhook.
+    pub async fn list_deliveries(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        webhook_id: i64,
+        filter: &DeliveryFilter,
+    ) -> Result<Vec<WebhookDelivery>> {
+        Self::get(ctx, mm, webhook_id).await?;
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, 1000);
+        let db = mm.db();
+        let stmt = db
+            .prepare(&format!(
+                "{} WHERE webhook_id = ?1 AND (?2 IS NULL OR status = ?2) ORDER BY id DESC LIMIT ?3",
+                SELECT_DELIVERY
+            ))
+            .await?;
+        let mut rows = stmt
+            .query(libsql::params![webhook_id, filter.status.clone(), limit])
+            .await?;
+        let mut deliveries = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let payload: String = row.get(4)?;
+            deliveries.push(WebhookDelivery {
+                id: row.get(0)?,
+                webhook_id: row.get(1)?,
+                event_id: row.get(2)?,
+                event: row.get(3)?,
+                payload: serde_json::from_str(&payload).unwrap_or(Value::Null),
+                status: row.get(5)?,
+                attempts: row.get(6)?,
+                next_attempt_ts: parse_timestamp(
+                    &row.get::<String>(7)?,
+                    "webhook_deliveries.next_attempt_ts",
+                ),
+                response_status: row.get(8)?,
+                error: row.get(9)?,
+                created_ts: parse_timestamp(
+                    &row.get::<String>(10)?,
+                    "webhook_deliveries.created_ts",
+                ),
+                delivered_ts: parse_timestamp_opt(row.get(11)?, "webhook_deliveries.delivered_ts"),
+            });
+        }
+        Ok(deliveries)
+    }
+
+    fn from_row(row: &Row) -> Result<Webhook> {
+        let events: String = row.get(3)?;
+        Ok(Webhook {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            url: row.get(2)?,
+            events: events
+                .split(',')
+                .filter(|e| !e.is_empty())
+                .map(str::to_string)
+                .collect(),
+            secret: row.get(4)?,
+            description: row.get(5)?,
+            active: row.get::<i64>(6)? != 0,
+            last_event_id: row.get(7)?,
+            created_ts: parse_timestamp(&row.get::<String>(8)?, "webhooks.created_ts"),
+            updated_ts: parse_timestamp(&row.get::<String>(9)?, "webhooks.updated_ts"),
+        })
+    }
+}
+
+/// The project's agent failed deliveries are reported from, created on
+/// first use.
+async fn webhook_agent(ctx: &Ctx, mm: &ModelManager, project_id: ProjectId) -> Result<i64> {
+    match AgentBmc::get_by_name(ctx, mm, project_id, WEBHOOK_AGENT_NAME).await {
+        Ok(agent) => Ok(agent.id.get()),
+        Err(Error::AgentNotFound { .. }) | Err(Error::NotFound) => {
+            let id = AgentBmc::create(
+                ctx,
+                mm,
+                AgentForCreate {
+                    project_id,
+                    name: WEBHOOK_AGENT_NAME.to_string(),
+                    program: "mouchak-mail".to_string(),
+                    model: "none".to_string(),
+                    task_description: "Reports failed webhook deliveries".to_string(),
+                },
+            )
+            .await?;
+            Ok(id.get())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks that `url` is an http(s) URL whose host may be called back (see
+/// [`net::check_public_host`]); the delivery job checks the addresses the
+/// host resolves to again.
+fn validate_url(url: &str, config: &WebhooksConfig) -> Result<()> {
+    let invalid =
+        |reason: &str| Error::InvalidInput(format!("Invalid webhook URL '{}': {}", url, reason));
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err(invalid("must be an http:// or https:// URL"));
+    }
+    let host = net::url_host(url).unwrap_or_default();
+    if host.is_empty() {
+        return Err(invalid("missing host"));
+    }
+    if config.allow_private_urls {
+        return Ok(());
+    }
+    let internal_networks = net::networks_from_env(net::INTERNAL_NETWORKS_ENV);
+    net::check_public_host(&host, &internal_networks).map_err(invalid)
+}
+
+/// Checks the subscribed events, dropping duplicates.
+fn validate_events(events: &[String]) -> Result<Vec<&'static str>> {
+    let mut valid: Vec<&'static str> = Vec::new();
+    for event in events {
+        let Some(known) = EVENTS.iter().find(|e| **e == event.trim()) else {
+            return Err(Error::InvalidInput(format!(
+                "Unknown webhook event '{}': expected one of {}",
+                event,
+                EVENTS.join(", ")
+            )));
+        };
+        if !valid.contains(known) {
+            valid.push(known);
+        }
+    }
+    if valid.is_empty() {
+        return Err(Error::InvalidInput(
+            "A webhook must subscribe to at least one event".into(),
+        ));
+    }
+    Ok(valid)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_matches_rfc_4231() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_webhook_event() {
+        assert_eq!(webhook_event("message.sent"), Some("message.created"));
+        assert_eq!(
+            webhook_event("file_reservation.expired"),
+            Some("reservation.expired")
+        );
+        assert_eq!(webhook_event("message.read"), None);
+    }
+
+    #[test]
+    fn test_validate_events() {
+        let events = validate_events(&[
+            "message.created".to_string(),
+            " reservation.expired".to_string(),
+            "message.created".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(events, vec!["message.created", "reservation.expired"]);
+        assert!(validate_events(&[]).is_err());
+        assert!(validate_events(&["message.sent".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_cap() {
+        let config = WebhooksConfig {
+            backoff_base_seconds: 10,
+            backoff_max_seconds: 60,
+            ..Default::default()
+        };
+        let delays: Vec<u64> = (1..=5).map(|n| config.retry_delay_seconds(n)).collect();
+        assert_eq!(delays, vec![10, 20, 40, 60, 60]);
+        assert_eq!(config.retry_delay_seconds(200), 60);
+    }
+}
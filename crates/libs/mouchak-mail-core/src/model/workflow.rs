@@ -0,0 +1,802 @@
+//! Workflow state machines for inter-agent handoffs.
+//!
+//! A project defines named workflows: states, the transitions allowed
+//! between them, and who is responsible for each state. Attaching a workflow
+//! to a thread puts the thread in the first state; advancing it checks the
+//! transition is allowed and mails the agents responsible for the new state
+//! in the same thread, e.g. for spec → implement → review → merge.
+//!
+//! # Responsibility
+//!
+//! A state's `responsible` is an agent name or a capability address
+//! (`capability:reviewer`, `capability:reviewer:best`), resolved when the
+//! thread enters the state. The agent making the move is never notified.
+//! A state without a responsible party, or with no outgoing transitions,
+//! sends no handoff; the latter ends the workflow.
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::agent::AgentBmc;
+use crate::model::capability_routing::{CapabilityAddress, CapabilityRoutingBmc};
+use crate::model::message::{MessageBmc, MessageForCreate};
+use crate::store::db_statement::Row;
+use crate::types::{AgentId, ProjectId};
+use crate::utils::parse_timestamp;
+use crate::utils::validation::validate_agent_name;
+use crate::{Error, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// A state of a workflow.
+///
+/// # Fields
+///
+/// - `responsible` - Agent name or `capability:<name>` address notified when
+///   a thread enters the state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowState {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub responsible: Option<String>,
+}
+
+/// An allowed move between two states.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowTransition {
+    pub from: String,
+    pub to: String,
+}
+
+impl WorkflowTransition {
+    /// Parses `"from -> to"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mouchak_mail_core::model::workflow::WorkflowTransition;
+    ///
+    /// let t = WorkflowTransition::parse("review -> implement").unwrap();
+    /// assert_eq!((t.from.as_str(), t.to.as_str()), ("review", "implement"));
+    /// assert!(WorkflowTransition::parse("review").is_err());
+    /// ```
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.split_once("->") {
+            Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => Ok(Self {
+                from: from.trim().to_string(),
+                to: to.trim().to_string(),
+            }),
+            _ => Err(Error::InvalidInput(format!(
+                "Invalid transition '{}' (expected 'from -> to')",
+                s
+            ))),
+        }
+    }
+}
+
+/// States and transitions of a workflow. The first state is the initial one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowDefinition {
+    pub states: Vec<WorkflowState>,
+    pub transitions: Vec<WorkflowTransition>,
+}
+
+impl WorkflowDefinition {
+    /// Checks state names are unique and non-empty, every transition joins
+    /// two known states, and every `responsible` is a well-formed address.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        if self.states.is_empty() {
+            return Err(Error::InvalidInput(
+                "A workflow needs at least one state".into(),
+            ));
+        }
+        let mut names = HashSet::new();
+        for state in &self.states {
+            if state.name.trim().is_empty() || state.name.trim() != state.name {
+                return Err(Error::InvalidInput(format!(
+                    "Invalid state name '{}'",
+                    state.name
+                )));
+            }
+            if !names.insert(state.name.as_str()) {
+                return Err(Error::InvalidInput(format!(
+                    "Duplicate state '{}'",
+                    state.name
+                )));
+            }
+            if let Some(responsible) = &state.responsible {
+                if CapabilityAddress::is_capability_address(responsible) {
+                    CapabilityAddress::parse(responsible)?;
+                } else {
+                    validate_agent_name(responsible)?;
+                }
+            }
+        }
+        for transition in &self.transitions {
+            for state in [&transition.from, &transition.to] {
+                if !names.contains(state.as_str()) {
+                    return Err(Error::InvalidInput(format!(
+                        "Transition {} -> {} uses unknown state '{}'",
+                        transition.from, transition.to, state
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The state a thread starts in.
+    pub fn initial_state(&self) -> Option<&WorkflowState> {
+        self.states.first()
+    }
+
+    pub fn state(&self, name: &str) -> Option<&WorkflowState> {
+        self.states.iter().find(|s| s.name == name)
+    }
+
+    /// States reachable from `state` in one move.
+    pub fn next_states(&self, state: &str) -> Vec<String> {
+        self.transitions
+            .iter()
+            .filter(|t| t.from == state)
+            .map(|t| t.to.clone())
+            .collect()
+    }
+
+    pub fn allows(&self, from: &str, to: &str) -> bool {
+        self.transitions
+            .iter()
+            .any(|t| t.from == from && t.to == to)
+    }
+}
+
+/// A workflow defined in a project.
+#[derive(Debug, Clone, Serialize)]
+pub struct Workflow {
+    pub id: i64,
+    pub project_id: ProjectId,
+    pub name: String,
+    pub definition: WorkflowDefinition,
+    pub created_ts: NaiveDateTime,
+    pub updated_ts: NaiveDateTime,
+}
+
+/// Input to define or replace a workflow.
+#[derive(Debug, Clone)]
+pub struct WorkflowForCreate {
+    pub project_id: ProjectId,
+    pub name: String,
+    pub definition: WorkflowDefinition,
+}
+
+/// One recorded move of a thread's workflow.
+///
+/// # Fields
+///
+/// - `from_state` - `None` for the start of the workflow
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowEvent {
+    pub from_state: Option<String>,
+    pub to_state: String,
+    pub agent_name: String,
+    pub note: Option<String>,
+    pub created_ts: NaiveDateTime,
+}
+
+/// Where a thread is in its workflow.
+///
+/// # Fields
+///
+/// - `next_states` - Allowed moves from `state`; empty once finished
+/// - `history` - Oldest first
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadWorkflowStatus {
+    pub thread_id: String,
+    pub workflow: String,
+    pub state: String,
+    pub responsible: Option<String>,
+    pub next_states: Vec<String>,
+    pub finished: bool,
+    pub history: Vec<WorkflowEvent>,
+}
+
+/// Result of starting or advancing a workflow.
+///
+/// # Fields
+///
+/// - `notified` - Agents mailed about the handoff
+/// - `message_id` - The handoff message, if anyone was notified
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowHandoff {
+    pub status: ThreadWorkflowStatus,
+    pub notified: Vec<String>,
+    pub message_id: Option<i64>,
+}
+
+/// Backend Model Controller for thread workflows.
+pub struct WorkflowBmc;
+
+impl WorkflowBmc {
+    /// Defines a workflow, or replaces the definition of one with the same
+    /// name.
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for an invalid definition, or if a replacement
+    /// drops a state that a thread is currently in.
+    pub async fn define(ctx: &Ctx, mm: &ModelManager, wf_c: WorkflowForCreate) -> Result<i64> {
+        let name = wf_c.name.trim();
+        if name.is_empty() {
+            return Err(Error::InvalidInput(
+                "Workflow name must not be empty".into(),
+            ));
+        }
+        wf_c.definition.validate()?;
+
+        if let Ok(existing) = Self::get_by_name(ctx, mm, wf_c.project_id, name).await {
+            let db = mm.db();
+            let stmt = db
+                .prepare("SELECT DISTINCT state FROM thread_workflows WHERE workflow_id = ?")
+                .await?;
+            let mut rows = stmt.query([existing.id]).await?;
+            while let Some(row) = rows.next().await? {
+                let state: String = row.get(0)?;
+                if wf_c.definition.state(&state).is_none() {
+                    return Err(Error::InvalidInput(format!(
+                        "Cannot remove state '{}' from workflow '{}': threads are in it",
+                        state, name
+                    )));
+                }
+            }
+        }
+
+        let definition = serde_json::to_string(&wf_c.definition)?;
+        let now = chrono::Utc::now()
+            .naive_utc()
+            .format(crate::utils::TS_FORMAT)
+            .to_string();
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO workflows (project_id, name, definition, created_ts, updated_ts)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(project_id, name) DO UPDATE SET
+                    definition = excluded.definition,
+                    updated_ts = excluded.updated_ts
+                RETURNING id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt
+            .query((
+                wf_c.project_id.get(),
+                name,
+                definition,
+                now.as_str(),
+                now.as_str(),
+            ))
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::InvalidInput("Failed to save workflow".into())),
+        }
+    }
+
+    /// Lists a project's workflows by name.
+    pub async fn list(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+    ) -> Result<Vec<Workflow>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, name, definition, created_ts, updated_ts
+                FROM workflows
+                WHERE project_id = ?
+                ORDER BY name
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query([project_id.get()]).await?;
+        let mut workflows = Vec::new();
+        while let Some(row) = rows.next().await? {
+            workflows.push(Self::from_row(&row)?);
+        }
+        Ok(workflows)
+    }
+
+    /// Gets a workflow by name.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the project has no workflow of that name.
+    pub async fn get_by_name(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        name: &str,
+    ) -> Result<Workflow> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT id, project_id, name, definition, created_ts, updated_ts
+                FROM workflows
+                WHERE project_id = ? AND name = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), name)).await?;
+        match rows.next().await? {
+            Some(row) => Self::from_row(&row),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Attaches a workflow to a thread, putting it in the initial state and
+    /// notifying whoever is responsible for that state.
+    ///
+    /// # Errors
+    /// Returns `NotFound` for an unknown workflow, and `InvalidInput` if the
+    /// thread has no messages or already has a workflow, or the responsible
+    /// party cannot be resolved.
+    pub async fn start(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        workflow_name: &str,
+        agent_id: AgentId,
+    ) -> Result<WorkflowHandoff> {
+        let workflow = Self::get_by_name(ctx, mm, project_id, workflow_name).await?;
+        if MessageBmc::list_by_thread(ctx, mm, project_id, thread_id)
+            .await?
+            .is_empty()
+        {
+            return Err(Error::InvalidInput(format!(
+                "Thread '{}' has no messages",
+                thread_id
+            )));
+        }
+        if let Some(current) = Self::current(mm, project_id, thread_id).await? {
+            return Err(Error::InvalidInput(format!(
+                "Thread '{}' already follows workflow '{}' (state '{}')",
+                thread_id, current.0.name, current.1
+            )));
+        }
+        let initial = workflow
+            .definition
+            .initial_state()
+            .ok_or_else(|| Error::InvalidInput("Workflow has no states".into()))?
+            .clone();
+        let recipients = Self::resolve_responsible(ctx, mm, project_id, &initial, agent_id).await?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                "INSERT INTO thread_workflows (project_id, thread_id, workflow_id, state) VALUES (?, ?, ?, ?)",
+            )
+            .await?;
+        stmt.execute((
+            project_id.get(),
+            thread_id,
+            workflow.id,
+            initial.name.as_str(),
+        ))
+        .await?;
+        Self::record(
+            mm,
+            project_id,
+            thread_id,
+            None,
+            &initial.name,
+            agent_id,
+            None,
+        )
+        .await?;
+
+        Self::hand_off(
+            ctx, mm, project_id, thread_id, &workflow, None, &initial, agent_id, None, recipients,
+        )
+        .await
+    }
+
+    /// Moves a thread to `to_state` and notifies whoever is responsible for
+    /// it.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the thread has no workflow, and `InvalidInput`
+    /// if the workflow does not allow the move or the responsible party
+    /// cannot be resolved.
+    pub async fn advance(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        to_state: &str,
+        agent_id: AgentId,
+        note: Option<&str>,
+    ) -> Result<WorkflowHandoff> {
+        let (workflow, from) = Self::current(mm, project_id, thread_id)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if !workflow.definition.allows(&from, to_state) {
+            let next = workflow.definition.next_states(&from);
+            return Err(Error::InvalidInput(format!(
+                "Workflow '{}' does not allow {} -> {}. Allowed from '{}': {}",
+                workflow.name,
+                from,
+                to_state,
+                from,
+                if next.is_empty() {
+                    "none (finished)".to_string()
+                } else {
+                    next.join(", ")
+                }
+            )));
+        }
+        let target = workflow
+            .definition
+            .state(to_state)
+            .ok_or_else(|| Error::InvalidInput(format!("Unknown state '{}'", to_state)))?
+            .clone();
+        let recipients = Self::resolve_responsible(ctx, mm, project_id, &target, agent_id).await?;
+
+        let db = mm.db();
+        let now = chrono::Utc::now()
+            .naive_utc()
+            .format(crate::utils::TS_FORMAT)
+            .to_string();
+        // Guard on the state read above so concurrent moves cannot both apply
+        let stmt = db
+            .prepare(
+                r#"
+                UPDATE thread_workflows SET state = ?, updated_ts = ?
+                WHERE project_id = ? AND thread_id = ? AND state = ?
+                "#,
+            )
+            .await?;
+        let updated = stmt
+            .execute((to_state, now, project_id.get(), thread_id, from.as_str()))
+            .await?;
+        if updated == 0 {
+            return Err(Error::InvalidInput(format!(
+                "Thread '{}' moved on from '{}' concurrently; check its status and retry",
+                thread_id, from
+            )));
+        }
+        Self::record(
+            mm,
+            project_id,
+            thread_id,
+            Some(&from),
+            to_state,
+            agent_id,
+            note,
+        )
+        .await?;
+
+        Self::hand_off(
+            ctx,
+            mm,
+            project_id,
+            thread_id,
+            &workflow,
+            Some(&from),
+            &target,
+            agent_id,
+            note,
+            recipients,
+        )
+        .await
+    }
+
+    /// Where a thread is in its workflow, with its history.
+    ///
+    /// # Errors
+    /// Returns `NotFound` if the thread has no workflow.
+    pub async fn status(
+        _ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<ThreadWorkflowStatus> {
+        let (workflow, state) = Self::current(mm, project_id, thread_id)
+            .await?
+            .ok_or(Error::NotFound)?;
+
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT t.from_state, t.to_state, a.name, t.note, t.created_ts
+                FROM workflow_transitions AS t
+                JOIN agents AS a ON a.id = t.agent_id
+                WHERE t.project_id = ? AND t.thread_id = ?
+                ORDER BY t.id
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        let mut history = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let created_ts: String = row.get(4)?;
+            history.push(WorkflowEvent {
+                from_state: row.get(0)?,
+                to_state: row.get(1)?,
+                agent_name: row.get(2)?,
+                note: row.get(3)?,
+                created_ts: parse_timestamp(&created_ts, "created_ts"),
+            });
+        }
+
+        let next_states = workflow.definition.next_states(&state);
+        Ok(ThreadWorkflowStatus {
+            thread_id: thread_id.to_string(),
+            workflow: workflow.name.clone(),
+            responsible: workflow
+                .definition
+                .state(&state)
+                .and_then(|s| s.responsible.clone()),
+            finished: next_states.is_empty(),
+            next_states,
+            state,
+            history,
+        })
+    }
+
+    /// The workflow and state of a thread, if it has one.
+    async fn current(
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+    ) -> Result<Option<(Workflow, String)>> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                SELECT w.id, w.project_id, w.name, w.definition, w.created_ts, w.updated_ts, tw.state
+                FROM thread_workflows AS tw
+                JOIN workflows AS w ON w.id = tw.workflow_id
+                WHERE tw.project_id = ? AND tw.thread_id = ?
+                "#,
+            )
+            .await?;
+        let mut rows = stmt.query((project_id.get(), thread_id)).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some((Self::from_row(&row)?, row.get(6)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Agents responsible for `state`, other than `actor`.
+    async fn resolve_responsible(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        state: &WorkflowState,
+        actor: AgentId,
+    ) -> Result<Vec<AgentId>> {
+        let Some(responsible) = &state.responsible else {
+            return Ok(Vec::new());
+        };
+        let ids = if CapabilityAddress::is_capability_address(responsible) {
+            let address = CapabilityAddress::parse(responsible)?;
+            match CapabilityRoutingBmc::resolve(ctx, mm, project_id, &address, Some(actor)).await {
+                Ok(resolution) => resolution.agent_ids,
+                // Only the actor holds the capability: nobody else to notify
+                Err(e) => {
+                    if CapabilityRoutingBmc::resolve(ctx, mm, project_id, &address, None)
+                        .await
+                        .is_ok()
+                    {
+                        Vec::new()
+                    } else {
+                        return Err(e);
+                    }
+                }
+            }
+        } else {
+            vec![
+                AgentBmc::get_by_name(ctx, mm, project_id, responsible)
+                    .await?
+                    .id
+                    .get(),
+            ]
+        };
+        Ok(ids
+            .into_iter()
+            .filter(|id| *id != actor.get())
+            .map(AgentId::new)
+            .collect())
+    }
+
+    async fn record(
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        from_state: Option<&str>,
+        to_state: &str,
+        agent_id: AgentId,
+        note: Option<&str>,
+    ) -> Result<()> {
+        let db = mm.db();
+        let stmt = db
+            .prepare(
+                r#"
+                INSERT INTO workflow_transitions (project_id, thread_id, from_state, to_state, agent_id, note)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .await?;
+        stmt.execute((
+            project_id.get(),
+            thread_id,
+            from_state,
+            to_state,
+            agent_id.get(),
+            note,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Mails the handoff to `recipients` in the thread and returns the new
+    /// status.
+    #[allow(clippy::too_many_arguments)]
+    async fn hand_off(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: ProjectId,
+        thread_id: &str,
+        workflow: &Workflow,
+        from: Option<&str>,
+        to: &WorkflowState,
+        agent_id: AgentId,
+        note: Option<&str>,
+        recipients: Vec<AgentId>,
+    ) -> Result<WorkflowHandoff> {
+        let status = Self::status(ctx, mm, project_id, thread_id).await?;
+        if recipients.is_empty() {
+            return Ok(WorkflowHandoff {
+                status,
+                notified: Vec::new(),
+                message_id: None,
+            });
+        }
+
+        let mut notified = Vec::new();
+        for id in &recipients {
+            notified.push(AgentBmc::get(ctx, mm, *id).await?.name);
+        }
+        let subject = match from {
+            Some(from) => format!("[{}] {} -> {}", workflow.name, from, to.name),
+            None => format!("[{}] started: {}", workflow.name, to.name),
+        };
+        let mut body = format!(
+            "Thread `{}` is now in state **{}** of workflow `{}`, and it is your turn.\n",
+            thread_id, to.name, workflow.name
+        );
+        if let Some(note) = note {
+            body.push_str(&format!("\n> {}\n", note));
+        }
+        if status.next_states.is_empty() {
+            body.push_str("\nThis is a final state.\n");
+        } else {
+            body.push_str(&format!(
+                "\nNext: `advance_workflow` to {}.\n",
+                status.next_states.join(" or ")
+            ));
+        }
+
+        let message_id = MessageBmc::create(
+            ctx,
+            mm,
+            MessageForCreate {
+                project_id,
+                sender_id: agent_id,
+                recipient_ids: recipients,
+                cc_ids: None,
+                bcc_ids: None,
+                subject,
+                body_md: body,
+                thread_id: Some(thread_id.to_string()),
+                importance: Some("high".to_string()),
+                ack_required: false,
+            },
+        )
+        .await?;
+
+        Ok(WorkflowHandoff {
+            status,
+            notified,
+            message_id: Some(message_id.get()),
+        })
+    }
+
+    fn from_row(row: &Row) -> Result<Workflow> {
+        let definition: String = row.get(3)?;
+        let created_ts: String = row.get(4)?;
+        let updated_ts: String = row.get(5)?;
+        Ok(Workflow {
+            id: row.get(0)?,
+            project_id: ProjectId::new(row.get(1)?),
+            name: row.get(2)?,
+            definition: serde_json::from_str(&definition)?,
+            created_ts: parse_timestamp(&created_ts, "created_ts"),
+            updated_ts: parse_timestamp(&updated_ts, "updated_ts"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(
+        states: &[(&str, Option<&str>)],
+        transitions: &[(&str, &str)],
+    ) -> WorkflowDefinition {
+        WorkflowDefinition {
+            states: states
+                .iter()
+                .map(|(name, responsible)| WorkflowState {
+                    name: name.to_string(),
+                    responsible: responsible.map(str::to_string),
+                })
+                .collect(),
+            transitions: transitions
+                .iter()
+                .map(|(from, to)| WorkflowTransition {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_definition() {
+        let ok = definition(
+            &[
+                ("spec", Some("lead")),
+                ("build", Some("capability:dev:best")),
+                ("done", None),
+            ],
+            &[("spec", "build"), ("build", "done")],
+        );
+        assert!(ok.validate().is_ok());
+        assert_eq!(ok.initial_state().map(|s| s.name.as_str()), Some("spec"));
+        assert_eq!(ok.next_states("build"), ["done"]);
+        assert!(ok.next_states("done").is_empty());
+        assert!(ok.allows("spec", "build"));
+        assert!(!ok.allows("spec", "done"));
+
+        assert!(definition(&[], &[]).validate().is_err());
+        assert!(
+            definition(&[("a", None), ("a", None)], &[])
+                .validate()
+                .is_err()
+        );
+        assert!(definition(&[(" a", None)], &[]).validate().is_err());
+        assert!(
+            definition(&[("a", None)], &[("a", "b")])
+                .validate()
+                .is_err()
+        );
+        assert!(
+            definition(&[("a", Some("not a name!"))], &[])
+                .validate()
+                .is_err()
+        );
+        assert!(
+            definition(&[("a", Some("capability:x:nearest"))], &[])
+                .validate()
+                .is_err()
+        );
+    }
+}
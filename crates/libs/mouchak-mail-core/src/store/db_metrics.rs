@@ -0,0 +1,179 @@
+//! Database connection instrumentation.
+//!
+//! BMCs reach the connection through `ModelManager::db`, which hands out a
+//! [`DbCheckout`]. While it lives the connection counts as checked out; when
+//! it drops, the time it was held is reported to the [`Observer`] installed
+//! with [`set_observer`] (the server records it on `/metrics`), labelled
+//! with the BMC module that took it. Uses slower than `database.slow_query_ms`
+//! are logged with their call site.
+//!
+//! The SQLite store is one libsql connection shared by every task, plus the
+//! one transactions run on, so [`POOL_SIZE`] is 2 and [`checked_out`] may
+//! exceed it: it counts BMC calls using a connection at the same time, and a
+//! high value means they queue on SQLite. On Postgres the pool size is
+//! `database.pool_size`. Statements run through the checkout one
+//! at a time, read their rows up front and are retried while a transaction
+//! holds the write lock (see [`super::db_statement`]). Checkouts taken
+//! for reads with `ModelManager::db_read` prepare their statements on a
+//! Postgres read replica when one has caught up (see [`super::postgres`]).
+
+use super::Db;
+use super::db_statement::{self, Rows, Statement};
+use crate::Result;
+use libsql::params::IntoParams;
+use std::ops::Deref;
+use std::panic::Location;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Connections the SQLite store opens; see [`Db::pool_size`].
+pub const POOL_SIZE: usize = 2;
+
+static CHECKED_OUT: AtomicUsize = AtomicUsize::new(0);
+static OBSERVER: OnceLock<Observer> = OnceLock::new();
+
+/// One finished use of the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbUse {
+    /// BMC module that held the connection, e.g. `message`
+    pub bmc: &'static str,
+    /// How long it was held
+    pub elapsed: Duration,
+    /// Whether `elapsed` reached the slow query threshold
+    pub slow: bool,
+}
+
+/// Receives every finished [`DbUse`]; must be cheap.
+pub type Observer = fn(&DbUse);
+
+/// Installs the observer of finished uses.
+///
+/// Only the first call takes effect; returns whether it was this one.
+pub fn set_observer(observer: Observer) -> bool {
+    OBSERVER.set(observer).is_ok()
+}
+
+/// BMC calls currently holding the connection.
+pub fn checked_out() -> usize {
+    CHECKED_OUT.load(Ordering::Relaxed)
+}
+
+/// The database connection, checked out by a BMC call.
+///
+/// Dereferences to [`Db`], but statements are run with its own `prepare`,
+/// `execute` and `query` (see [`super::db_statement`]); the use ends when
+/// it is dropped.
+pub struct DbCheckout<'a> {
+    db: &'a Db,
+    statement_lock: &'a Arc<Mutex<()>>,
+    location: &'static Location<'static>,
+    slow_threshold: Option<Duration>,
+    started: Instant,
+    reads: bool,
+}
+
+impl<'a> DbCheckout<'a> {
+    /// Checks out `db`, whose statements run holding `statement_lock`, for
+    /// the code at `location`; a `slow_threshold` of `None` disables slow
+    /// query logging.
+    pub(crate) fn new(
+        db: &'a Db,
+        statement_lock: &'a Arc<Mutex<()>>,
+        location: &'static Location<'static>,
+        slow_threshold: Option<Duration>,
+    ) -> Self {
+        CHECKED_OUT.fetch_add(1, Ordering::Relaxed);
+        Self {
+            db,
+            statement_lock,
+            location,
+            slow_threshold,
+            started: Instant::now(),
+            reads: false,
+        }
+    }
+
+    /// Marks the checkout as only reading, so statements it prepares may
+    /// run on a read replica; `execute` still runs on the primary.
+    pub(crate) fn for_reads(mut self) -> Self {
+        self.reads = true;
+        self
+    }
+
+    /// Prepares a statement, see [`libsql::Connection::prepare`].
+    pub async fn prepare(&self, sql: &str) -> Result<Statement> {
+        match self.db {
+            Db::Sqlite(conn) => Ok(Statement::new(
+                conn.prepare(sql).await?,
+                Arc::clone(self.statement_lock),
+            )),
+            Db::Postgres(pg) if self.reads => Ok(Statement::postgres(pg.reader().await, sql)),
+            Db::Postgres(pg) => Ok(Statement::postgres(pg.clone(), sql)),
+        }
+    }
+
+    /// Runs a statement, see [`libsql::Connection::execute`].
+    pub async fn execute(&self, sql: &str, params: impl IntoParams) -> Result<u64> {
+        let params = params.into_params()?;
+        match self.db {
+            Db::Sqlite(conn) => Ok(db_statement::retry(|| async {
+                let _running = self.statement_lock.lock().await;
+                conn.execute(sql, params.clone()).await
+            })
+            .await?),
+            Db::Postgres(pg) => pg.execute(sql, params).await,
+        }
+    }
+
+    /// Runs a statement for its rows, see [`libsql::Connection::query`].
+    pub async fn query(&self, sql: &str, params: impl IntoParams) -> Result<Rows> {
+        self.prepare(sql).await?.query(params).await
+    }
+}
+
+impl Deref for DbCheckout<'_> {
+    type Target = Db;
+
+    fn deref(&self) -> &Db {
+        self.db
+    }
+}
+
+impl Drop for DbCheckout<'_> {
+    fn drop(&mut self) {
+        CHECKED_OUT.fetch_sub(1, Ordering::Relaxed);
+        let elapsed = self.started.elapsed();
+        let slow = self.slow_threshold.is_some_and(|t| elapsed >= t);
+        let bmc = bmc_name(self.location.file());
+        if slow {
+            tracing::warn!(
+                bmc,
+                location = %self.location,
+                elapsed_ms = %elapsed.as_millis(),
+                "Slow database query"
+            );
+        }
+        if let Some(observer) = OBSERVER.get() {
+            observer(&DbUse { bmc, elapsed, slow });
+        }
+    }
+}
+
+/// BMC label of a source file: its stem, e.g. `message` for `model/message.rs`.
+fn bmc_name(file: &'static str) -> &'static str {
+    let name = file.rsplit(['/', '\\']).next().unwrap_or(file);
+    name.strip_suffix(".rs").unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bmc_name() {
+        assert_eq!(bmc_name("crates/libs/core/src/model/message.rs"), "message");
+        assert_eq!(bmc_name("src\\model\\agent.rs"), "agent");
+    }
+}
@@ -0,0 +1,358 @@
+//! Statements run on the shared connection.
+//!
+//! Transactions run on a connection of their own (see
+//! `ModelManager::transaction`), and SQLite lets one connection write at a
+//! time. A connection with a read open can't wait for the lock: its write
+//! fails with `SQLITE_BUSY` while a transaction holds it, and with
+//! `SQLITE_BUSY_SNAPSHOT` if one committed since the read began. A write
+//! that ends while another statement on the connection is unfinished isn't
+//! committed until that one ends, and is rolled back with it if it fails.
+//! So statements run through a [`DbCheckout`](super::db_metrics::DbCheckout):
+//!
+//! - run one at a time, holding the connection's statement lock;
+//! - read all their rows when run: no read outlives the query, so a BMC
+//!   holding rows doesn't keep its own later writes from running;
+//! - are retried for up to [`RETRY_TIMEOUT`] when refused as busy, without
+//!   the lock, so the others run meanwhile.
+//!
+//! On the Postgres backend statements only read their rows up front: the
+//! pool serves them concurrently and the server makes writers wait for
+//! locks.
+
+use super::postgres::PgDb;
+use crate::Result;
+use libsql::Value;
+use libsql::ffi::SQLITE_BUSY;
+use libsql::params::IntoParams;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a refused statement is retried before its error is returned,
+/// the busy timeout the connection had before.
+pub const RETRY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const FIRST_DELAY: Duration = Duration::from_millis(2);
+const MAX_DELAY: Duration = Duration::from_millis(50);
+
+/// Whether SQLite refused `e` for a lock another connection holds.
+fn is_busy(e: &libsql::Error) -> bool {
+    matches!(e, libsql::Error::SqliteFailure(code, _) if code & 0xff == SQLITE_BUSY)
+}
+
+/// Runs `run` until it isn't refused as busy or [`RETRY_TIMEOUT`] passed.
+pub(crate) async fn retry<T, F>(mut run: impl FnMut() -> F) -> libsql::Result<T>
+where
+    F: Future<Output = libsql::Result<T>>,
+{
+    let deadline = Instant::now() + RETRY_TIMEOUT;
+    let mut delay = FIRST_DELAY;
+    loop {
+        match run().await {
+            Err(e) if is_busy(&e) && Instant::now() < deadline => {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A prepared statement; see the [module docs](self).
+pub struct Statement {
+    inner: Inner,
+}
+
+enum Inner {
+    Sqlite {
+        stmt: libsql::Statement,
+        /// Held while `stmt` runs.
+        lock: Arc<Mutex<()>>,
+    },
+    /// Postgres prepares (and caches) statements per pooled connection when
+    /// they run, and runs them concurrently.
+    Postgres { db: PgDb, sql: String },
+}
+
+impl Statement {
+    pub(crate) fn new(inner: libsql::Statement, lock: Arc<Mutex<()>>) -> Self {
+        Self {
+            inner: Inner::Sqlite { stmt: inner, lock },
+        }
+    }
+
+    pub(crate) fn postgres(db: PgDb, sql: &str) -> Self {
+        Self {
+            inner: Inner::Postgres {
+                db,
+                sql: sql.to_string(),
+            },
+        }
+    }
+
+    /// Runs the statement, see [`libsql::Statement::execute`].
+    pub async fn execute(&self, params: impl IntoParams) -> Result<usize> {
+        let params = params.into_params()?;
+        let (stmt, lock) = match &self.inner {
+            Inner::Sqlite { stmt, lock } => (stmt, lock),
+            Inner::Postgres { db, sql } => {
+                let changed = db.execute(sql, params).await?;
+                return Ok(usize::try_from(changed).unwrap_or(usize::MAX));
+            }
+        };
+        Ok(retry(|| async {
+            let _running = lock.lock().await;
+            let changed = stmt.execute(params.clone()).await;
+            // Until reset, a refused statement counts as unfinished
+            stmt.reset();
+            changed
+        })
+        .await?)
+    }
+
+    /// Runs the statement and reads its rows, see [`libsql::Statement::query`].
+    pub async fn query(&self, params: impl IntoParams) -> Result<Rows> {
+        let params = params.into_params()?;
+        let read = match &self.inner {
+            Inner::Sqlite { stmt, lock } => {
+                retry(|| async {
+                    let _running = lock.lock().await;
+                    let read = read(stmt, params.clone()).await;
+                    stmt.reset();
+                    read
+                })
+                .await?
+            }
+            Inner::Postgres { db, sql } => db.query(sql, params).await?,
+        };
+        Ok(Rows::new(read))
+    }
+}
+
+async fn read(
+    stmt: &libsql::Statement,
+    params: libsql::params::Params,
+) -> libsql::Result<Vec<Row>> {
+    let mut rows = stmt.query(params).await?;
+    let columns = rows.column_count();
+    let mut read = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let values = (0..columns)
+            .map(|i| row.get_value(i))
+            .collect::<libsql::Result<_>>()?;
+        read.push(Row { values });
+    }
+    Ok(read)
+}
+
+/// Rows read by [`Statement::query`].
+pub struct Rows {
+    rows: std::vec::IntoIter<Row>,
+}
+
+impl Rows {
+    pub(crate) fn new(rows: Vec<Row>) -> Self {
+        Self {
+            rows: rows.into_iter(),
+        }
+    }
+
+    /// The next row, see [`libsql::Rows::next`].
+    pub async fn next(&mut self) -> libsql::Result<Option<Row>> {
+        Ok(self.rows.next())
+    }
+}
+
+/// A row of [`Rows`].
+#[derive(Debug, Clone)]
+pub struct Row {
+    values: Vec<Value>,
+}
+
+impl Row {
+    pub(crate) fn new(values: Vec<Value>) -> Self {
+        Self { values }
+    }
+
+    /// The value of column `idx` as `T`, see [`libsql::Row::get`].
+    pub fn get<T: FromColumn>(&self, idx: i32) -> libsql::Result<T> {
+        let value = usize::try_from(idx)
+            .ok()
+            .and_then(|i| self.values.get(i))
+            .ok_or(libsql::Error::InvalidColumnIndex)?;
+        T::from_column(value.clone())
+    }
+}
+
+/// Types [`Row::get`] reads a column as, converted like [`libsql::Row::get`]
+/// does.
+pub trait FromColumn: Sized {
+    /// Converts `value`, failing on `NULL` unless `Self` is an `Option`.
+    fn from_column(value: Value) -> libsql::Result<Self>;
+}
+
+impl FromColumn for Value {
+    fn from_column(value: Value) -> libsql::Result<Self> {
+        Ok(value)
+    }
+}
+
+impl<T: FromColumn> FromColumn for Option<T> {
+    fn from_column(value: Value) -> libsql::Result<Self> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_column(value).map(Some),
+        }
+    }
+}
+
+macro_rules! from_column {
+    ($($ty:ty: $variant:ident($v:ident) => $convert:expr;)*) => {
+        $(impl FromColumn for $ty {
+            fn from_column(value: Value) -> libsql::Result<Self> {
+                match value {
+                    Value::Null => Err(libsql::Error::NullValue),
+                    Value::$variant($v) => $convert,
+                    _ => Err(libsql::Error::InvalidColumnType),
+                }
+            }
+        })*
+    };
+}
+
+from_column! {
+    i64: Integer(i) => Ok(i);
+    i32: Integer(i) => Ok(i as i32);
+    u32: Integer(i) => Ok(i as u32);
+    u64: Integer(i) => Ok(i as u64);
+    f64: Real(f) => Ok(f);
+    String: Text(s) => Ok(s);
+    Vec<u8>: Blob(b) => Ok(b);
+    bool: Integer(i) => match i {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(libsql::Error::InvalidColumnType),
+    };
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    async fn open(dir: &tempfile::TempDir) -> (libsql::Connection, libsql::Connection) {
+        let db = libsql::Builder::new_local(dir.path().join("statement.db"))
+            .build()
+            .await
+            .unwrap();
+        let shared = db.connect().unwrap();
+        shared
+            .execute_batch(
+                "PRAGMA journal_mode=WAL; CREATE TABLE t (v INTEGER); INSERT INTO t VALUES (1), (2);",
+            )
+            .await
+            .unwrap();
+        (shared, db.connect().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_rows_leave_no_read_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let (shared, other) = open(&dir).await;
+
+        let select = Statement::new(
+            shared.prepare("SELECT v FROM t").await.unwrap(),
+            Arc::default(),
+        );
+        let mut rows = select.query(()).await.unwrap();
+        assert_eq!(
+            rows.next().await.unwrap().unwrap().get::<i64>(0).unwrap(),
+            1
+        );
+
+        // A commit elsewhere while the rows are held doesn't refuse the write
+        other.execute("INSERT INTO t VALUES (3)", ()).await.unwrap();
+        let update = Statement::new(
+            shared.prepare("UPDATE t SET v = v + 1").await.unwrap(),
+            Arc::default(),
+        );
+        assert_eq!(update.execute(()).await.unwrap(), 3);
+        assert_eq!(
+            rows.next().await.unwrap().unwrap().get::<i64>(0).unwrap(),
+            2
+        );
+        assert!(rows.next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_write_waits_for_transaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let (shared, other) = open(&dir).await;
+
+        // An open read keeps the shared connection from waiting on the lock
+        let reading = shared.prepare("SELECT v FROM t").await.unwrap();
+        let mut read = reading.query(()).await.unwrap();
+        read.next().await.unwrap();
+        other.execute("BEGIN IMMEDIATE", ()).await.unwrap();
+        let refused = shared.execute("UPDATE t SET v = 3", ()).await.unwrap_err();
+        assert!(is_busy(&refused), "{refused}");
+
+        let update = Statement::new(
+            shared.prepare("UPDATE t SET v = 4").await.unwrap(),
+            Arc::default(),
+        );
+        let retried = tokio::spawn(async move { update.execute(()).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!retried.is_finished(), "the update waits for the lock");
+        other.execute("ROLLBACK", ()).await.unwrap();
+        assert_eq!(retried.await.unwrap().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refused_write_holds_back_no_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let (shared, other) = open(&dir).await;
+        let lock = Arc::default();
+        let statement = |sql: &str| {
+            let lock = Arc::clone(&lock);
+            let shared = shared.clone();
+            let sql = sql.to_string();
+            async move { Statement::new(shared.prepare(&sql).await.unwrap(), lock) }
+        };
+
+        other.execute("BEGIN IMMEDIATE", ()).await.unwrap();
+        let refused = statement("UPDATE t SET v = 5 WHERE v = 1").await;
+        let retried = tokio::spawn(async move { refused.execute(()).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        other.execute("ROLLBACK", ()).await.unwrap();
+
+        // Committed at once, not when the refused statement next runs
+        let insert = statement("INSERT INTO t VALUES (3)").await;
+        assert_eq!(insert.execute(()).await.unwrap(), 1);
+        let mut rows = other.query("SELECT COUNT(*) FROM t", ()).await.unwrap();
+        assert_eq!(
+            rows.next().await.unwrap().unwrap().get::<i64>(0).unwrap(),
+            3
+        );
+        assert_eq!(retried.await.unwrap().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_row_get_converts_like_libsql() {
+        let row = Row {
+            values: vec![Value::Integer(1), Value::Null, Value::Text("a".into())],
+        };
+        assert!(row.get::<bool>(0).unwrap());
+        assert_eq!(row.get::<Option<i64>>(1).unwrap(), None);
+        assert!(matches!(row.get::<i64>(1), Err(libsql::Error::NullValue)));
+        assert_eq!(row.get::<String>(2).unwrap(), "a");
+        assert!(matches!(
+            row.get::<i64>(2),
+            Err(libsql::Error::InvalidColumnType)
+        ));
+        assert!(matches!(
+            row.get::<i64>(3),
+            Err(libsql::Error::InvalidColumnIndex)
+        ));
+    }
+}
@@ -27,6 +27,33 @@
 use crate::Result;
 use git2::{Error as GitError, Oid, Repository, Signature, Tree};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Archive commits spawned in the background and not yet finished.
+static PENDING_ARCHIVE_COMMITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of background archive commits still in flight.
+///
+/// A growing value means the Git archive is falling behind the database.
+pub fn pending_archive_commits() -> usize {
+    PENDING_ARCHIVE_COMMITS.load(Ordering::Relaxed)
+}
+
+/// Counts a background archive commit as pending until dropped.
+pub(crate) struct PendingArchiveCommit;
+
+impl PendingArchiveCommit {
+    pub(crate) fn new() -> Self {
+        PENDING_ARCHIVE_COMMITS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for PendingArchiveCommit {
+    fn drop(&mut self) {
+        PENDING_ARCHIVE_COMMITS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
 
 /// Initializes or opens a Git repository at the given path.
 ///
@@ -194,6 +221,36 @@ pub fn commit_paths<P: AsRef<Path>>(
     create_commit(repo, &tree, &signature, message)
 }
 
+/// Commits written files and removed files in a single commit.
+///
+/// Like [`commit_paths`], the `paths` must exist on disk. The `removed`
+/// paths are dropped from the index; deleting them from the working tree is
+/// up to the caller.
+///
+/// # Returns
+///
+/// The OID of the created commit.
+pub fn commit_paths_and_removals<P: AsRef<Path>, R: AsRef<Path>>(
+    repo: &Repository,
+    paths: &[P],
+    removed: &[R],
+    message: &str,
+    author_name: &str,
+    author_email: &str,
+) -> Result<Oid> {
+    let mut index = repo.index()?;
+    for path in paths {
+        index.add_path(path.as_ref())?;
+    }
+    for path in removed {
+        index.remove_path(path.as_ref())?;
+    }
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = Signature::now(author_name, author_email)?;
+
+    create_commit(repo, &tree, &signature, message)
+}
+
 /// Finds the last commit in the repository, returns None if no commits exist.
 fn find_last_commit(repo: &Repository) -> Result<Option<git2::Commit<'_>>> {
     let head = repo.head();
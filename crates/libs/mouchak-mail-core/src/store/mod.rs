@@ -2,8 +2,10 @@
 //!
 //! This module provides the storage layer for lib-core, handling:
 //!
-//! - **Database connections**: SQLite via libsql with optimized settings
+//! - **Database connections**: SQLite via libsql with optimized settings, or
+//!   Postgres (via `postgres`) when `database.url` points at one
 //! - **Git storage**: Audit trail for entities via git2
+//! - **Attachment storage**: Attachment content on disk or in S3 (via `attachment_store`)
 //!
 //! # Architecture
 //!
@@ -35,32 +37,44 @@
 //! use mouchak_mail_core::store::new_db_pool;
 //!
 //! async fn setup() -> mouchak_mail_core::Result<()> {
-//!     let db = new_db_pool().await?;
+//!     let db = new_db_pool(&Default::default()).await?;
 //!     // Database is ready with migrations applied
 //!     Ok(())
 //! }
 //! ```
 
 use crate::Result;
+use libsql::params::IntoParams;
 use libsql::{Builder, Connection};
+use mouchak_mail_common::config::DatabaseConfig;
+use mouchak_mail_common::paths;
+use serde::Serialize;
 use std::path::PathBuf;
 
 /// Resolves the database path, ensuring consistency regardless of CWD.
 ///
 /// Resolution order:
-/// 1. `DATABASE_PATH` env var (absolute path)
-/// 2. `CARGO_WORKSPACE_DIR` env var + "data/mouchak_mail.db"
-/// 3. Walk up directories to find workspace root (contains Cargo.toml with [workspace])
-/// 4. Fall back to CWD + "data/mouchak_mail.db"
+/// 1. Portable directory + "mouchak_mail.db" (see [`mouchak_mail_common::paths`])
+/// 2. `DATABASE_PATH` env var (absolute path)
+/// 3. `CARGO_WORKSPACE_DIR` env var + "data/mouchak_mail.db"
+/// 4. Walk up directories to find workspace root (contains Cargo.toml with [workspace])
+/// 5. Fall back to CWD + "data/mouchak_mail.db"
 fn resolve_db_path() -> PathBuf {
-    // 1. Check for explicit DATABASE_PATH
+    // 1. Portable mode keeps the database with everything else
+    if let Some(dir) = paths::portable_dir() {
+        let p = dir.join(paths::DB_FILE);
+        tracing::info!("Using portable database: {}", p.display());
+        return p;
+    }
+
+    // 2. Check for explicit DATABASE_PATH
     if let Ok(path) = std::env::var("DATABASE_PATH") {
         let p = PathBuf::from(&path);
         tracing::info!("Using DATABASE_PATH: {}", p.display());
         return p;
     }
 
-    // 2. Check for CARGO_WORKSPACE_DIR (set by some cargo configurations)
+    // 3. Check for CARGO_WORKSPACE_DIR (set by some cargo configurations)
     if let Ok(workspace_dir) = std::env::var("CARGO_WORKSPACE_DIR") {
         let p = PathBuf::from(workspace_dir)
             .join("data")
@@ -69,7 +83,7 @@ fn resolve_db_path() -> PathBuf {
         return p;
     }
 
-    // 3. Walk up to find workspace root (Cargo.toml with [workspace])
+    // 4. Walk up to find workspace root (Cargo.toml with [workspace])
     if let Ok(cwd) = std::env::current_dir() {
         let mut dir = cwd.as_path();
         loop {
@@ -90,7 +104,7 @@ fn resolve_db_path() -> PathBuf {
             }
         }
 
-        // 4. Fall back to CWD
+        // 5. Fall back to CWD
         let p = cwd.join("data").join("mouchak_mail.db");
         tracing::warn!("No workspace root found, using CWD: {}", p.display());
         return p;
@@ -100,10 +114,87 @@ fn resolve_db_path() -> PathBuf {
     PathBuf::from("data/mouchak_mail.db")
 }
 
-/// Type alias for database connections.
+/// Database connection of the store.
 ///
-/// Uses libsql's [`Connection`] for SQLite access.
-pub type Db = Connection;
+/// BMCs run their statements through `ModelManager::db` (see
+/// [`db_metrics::DbCheckout`]) and write SQL for SQLite, which the Postgres
+/// backend translates (see [`postgres`]); where the dialects differ beyond
+/// that, they branch on [`Db::backend`].
+#[derive(Clone)]
+pub enum Db {
+    /// A libsql connection to the SQLite database file.
+    Sqlite(Connection),
+    /// A pool of Postgres connections.
+    Postgres(postgres::PgDb),
+}
+
+/// Database engine behind a [`Db`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl From<Connection> for Db {
+    fn from(conn: Connection) -> Self {
+        Db::Sqlite(conn)
+    }
+}
+
+impl Db {
+    /// The database engine.
+    pub fn backend(&self) -> Backend {
+        match self {
+            Db::Sqlite(_) => Backend::Sqlite,
+            Db::Postgres(_) => Backend::Postgres,
+        }
+    }
+
+    /// Most connections the store opens to the database.
+    pub fn pool_size(&self) -> usize {
+        match self {
+            Db::Sqlite(_) => db_metrics::POOL_SIZE,
+            Db::Postgres(pg) => pg.pool_size(),
+        }
+    }
+
+    /// Prepares a statement outside any BMC, see [`db_statement`].
+    pub async fn prepare(&self, sql: &str) -> Result<db_statement::Statement> {
+        match self {
+            Db::Sqlite(conn) => Ok(db_statement::Statement::new(
+                conn.prepare(sql).await?,
+                Default::default(),
+            )),
+            Db::Postgres(pg) => Ok(db_statement::Statement::postgres(pg.clone(), sql)),
+        }
+    }
+
+    /// Runs a statement outside any BMC, returning the rows it changed.
+    pub async fn execute(&self, sql: &str, params: impl IntoParams) -> Result<u64> {
+        match self {
+            Db::Sqlite(conn) => Ok(conn.execute(sql, params).await?),
+            Db::Postgres(pg) => pg.execute(sql, params.into_params()?).await,
+        }
+    }
+
+    /// Runs a statement outside any BMC for its rows.
+    pub async fn query(&self, sql: &str, params: impl IntoParams) -> Result<db_statement::Rows> {
+        self.prepare(sql).await?.query(params).await
+    }
+
+    /// Runs statements without parameters; on Postgres they are not
+    /// translated.
+    pub async fn execute_batch(&self, sql: &str) -> Result<()> {
+        match self {
+            Db::Sqlite(conn) => {
+                conn.execute_batch(sql).await?;
+                Ok(())
+            }
+            Db::Postgres(pg) => pg.execute_batch(sql).await,
+        }
+    }
+}
 
 /// Git storage operations for audit logging.
 pub mod git_store;
@@ -117,13 +208,30 @@ pub mod archive_lock;
 /// File handle safety patterns documentation (PORT-2.3).
 pub mod file_safety;
 
+/// Data format version stamping and upgrades.
+pub mod upgrade;
+
+/// Filesystem and S3-compatible storage for attachment content.
+pub mod attachment_store;
+
+/// Connection checkout metrics and slow query logging.
+pub mod db_metrics;
+
+/// Statements run on the shared connection, next to transactions.
+pub mod db_statement;
+
+/// The Postgres backend.
+pub mod postgres;
+
 /// Creates a new database connection pool with migrations applied.
 ///
-/// This function:
+/// A Postgres `database.url` connects a pool to it and runs the Postgres
+/// migrations (see [`postgres::migrate`]); otherwise this function:
 /// 1. Creates the `data/` directory if needed
 /// 2. Opens or creates the SQLite database
 /// 3. Applies concurrency optimizations (WAL, timeouts, cache)
-/// 4. Runs all migrations
+/// 4. Snapshots and upgrades older data formats (see [`upgrade`])
+/// 5. Runs all migrations
 ///
 /// # Returns
 ///
@@ -142,11 +250,15 @@ pub mod file_safety;
 /// use mouchak_mail_core::store::new_db_pool;
 ///
 /// # async fn example() -> mouchak_mail_core::Result<()> {
-/// let db = new_db_pool().await?;
+/// let db = new_db_pool(&Default::default()).await?;
 /// # Ok(())
 /// # }
 /// ```
-pub async fn new_db_pool() -> Result<Db> {
+pub async fn new_db_pool(config: &DatabaseConfig) -> Result<Db> {
+    if let Some(url) = postgres_url(config) {
+        return new_postgres_pool(url, config).await;
+    }
+
     // Resolve database path (handles CWD-independence)
     let db_path = resolve_db_path();
 
@@ -163,31 +275,184 @@ pub async fn new_db_pool() -> Result<Db> {
     // WAL mode: enables concurrent reads during writes
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
     // busy_timeout: wait up to 30 seconds when database is locked (instead of failing immediately)
-    // This is critical for 100+ concurrent agents writing simultaneously
+    // while the format is checked and migrated; lifted once that is done
     let _ = conn.execute("PRAGMA busy_timeout=30000;", ()).await;
     // synchronous=NORMAL: good balance of safety and performance with WAL
     let _ = conn.execute("PRAGMA synchronous=NORMAL;", ()).await;
     // cache_size: increase cache to reduce disk I/O (negative = KB, so -64000 = 64MB)
     let _ = conn.execute("PRAGMA cache_size=-64000;", ()).await;
 
-    // Apply all migrations in order
+    // Check the data format stamp before migrating
+    let plan = upgrade::plan(&conn, &db_path).await?;
+    if plan.is_newer() {
+        return Err(crate::Error::UpgradeRequired(plan.describe()));
+    }
+    if plan.needs_upgrade() && !plan.fresh {
+        if !upgrade::auto_upgrade_enabled() {
+            return Err(crate::Error::UpgradeRequired(format!(
+                "{} Run `mouchak-mail upgrade`, or remove MOUCHAK_AUTO_UPGRADE=false to upgrade on startup.",
+                plan.describe()
+            )));
+        }
+        let snapshot = upgrade::snapshot(&conn, &plan).await?;
+        tracing::warn!(
+            "Upgrading data format {} -> {} (snapshot: {})",
+            plan.current_version,
+            plan.target_version,
+            snapshot.display()
+        );
+    }
+
+    // Apply all migrations in order and stamp the format version
     // Note: SQLite's IF NOT EXISTS makes this idempotent for table creation
-    let migrations = [
-        include_str!("../../../../../migrations/001_initial_schema.sql"),
-        include_str!("../../../../../migrations/002_agent_capabilities.sql"),
-        include_str!("../../../../../migrations/003_tool_metrics.sql"),
-        include_str!("../../../../../migrations/004_attachments.sql"),
-        include_str!("../../../../../migrations/005_attachments_agent.sql"),
-        include_str!("../../../../../migrations/006_query_indexes.sql"),
-    ];
+    upgrade::migrate(&conn).await?;
+
+    // From here on a statement waiting for the lock would block its thread,
+    // and with it a transaction of this process holding the lock; BMC
+    // statements retry with [`db_statement::retry`] instead
+    let _ = conn.execute("PRAGMA busy_timeout=0;", ()).await;
 
-    for migration in &migrations {
-        conn.execute_batch(migration).await?;
+    Ok(Db::Sqlite(conn))
+}
+
+/// The configured URL if it selects the Postgres backend.
+fn postgres_url(config: &DatabaseConfig) -> Option<&str> {
+    config
+        .url
+        .as_deref()
+        .filter(|url| postgres::is_postgres_url(url))
+}
+
+/// [`new_db_pool`] for Postgres: checks the data format like SQLite does,
+/// minus the snapshot, and adds the configured read replicas.
+async fn new_postgres_pool(url: &str, config: &DatabaseConfig) -> Result<Db> {
+    tracing::info!("Connecting to Postgres at: {}", postgres::redact_url(url));
+    let replica_urls = config.replica_url_list();
+    for replica_url in &replica_urls {
+        tracing::info!(
+            "Reading from Postgres replica at: {}",
+            postgres::redact_url(replica_url)
+        );
+    }
+    let pg = postgres::PgDb::connect(url, config.pool_size)
+        .await?
+        .with_replicas(&replica_urls, config.pool_size)?;
+    let plan = postgres::plan(&pg, url).await?;
+    if plan.is_newer() {
+        return Err(crate::Error::UpgradeRequired(plan.describe()));
+    }
+    if plan.needs_upgrade() && !plan.fresh {
+        if !upgrade::auto_upgrade_enabled() {
+            return Err(crate::Error::UpgradeRequired(format!(
+                "{} Run `mouchak-mail upgrade`, or remove MOUCHAK_AUTO_UPGRADE=false to upgrade on startup.",
+                plan.describe()
+            )));
+        }
+        tracing::warn!(
+            "Upgrading data format {} -> {}",
+            plan.current_version,
+            plan.target_version
+        );
+    }
+    postgres::migrate(&pg).await?;
+    Ok(Db::Postgres(pg))
+}
+
+/// Opens another connection to the database file `db` is connected to,
+/// with the settings of [`new_db_pool`].
+pub(crate) async fn connect_again(db: &Connection) -> Result<Connection> {
+    let mut rows = db.query("PRAGMA database_list", ()).await?;
+    let mut db_path = None;
+    while let Some(row) = rows.next().await? {
+        if row.get::<String>(1)? == "main" {
+            db_path = Some(PathBuf::from(row.get::<String>(2)?));
+        }
     }
+    let db_path = db_path
+        .filter(|p| !p.as_os_str().is_empty())
+        .ok_or_else(|| crate::Error::InvalidInput("database has no file".into()))?;
 
+    let conn = Builder::new_local(&db_path).build().await?.connect()?;
+    let _ = conn.execute("PRAGMA synchronous=NORMAL;", ()).await;
+    let _ = conn.execute("PRAGMA cache_size=-64000;", ()).await;
+    // Outside WAL mode, a read open on one connection holds back commits on
+    // the other; tests open the database without it
+    db_statement::retry(|| async {
+        let mut rows = conn.query("PRAGMA journal_mode=WAL;", ()).await?;
+        rows.next().await?;
+        Ok(())
+    })
+    .await?;
     Ok(conn)
 }
 
+/// Reports what an upgrade of the data directory would change, without
+/// changing anything.
+///
+/// A missing database is reported as fresh.
+pub async fn check_data_format(config: &DatabaseConfig) -> Result<upgrade::UpgradePlan> {
+    if let Some(url) = postgres_url(config) {
+        let pg = postgres::PgDb::connect(url, 1).await?;
+        return postgres::plan(&pg, url).await;
+    }
+    let db_path = resolve_db_path();
+    if !db_path.exists() {
+        return Ok(upgrade::UpgradePlan {
+            db_path,
+            current_version: 0,
+            target_version: upgrade::DATA_FORMAT_VERSION,
+            fresh: true,
+            pending_migrations: Vec::new(),
+            backend: Backend::Sqlite,
+        });
+    }
+
+    let db = Builder::new_local(&db_path).build().await?;
+    let conn = db.connect()?;
+    upgrade::plan(&conn, &db_path).await
+}
+
+/// Upgrades the data directory to this build's format, regardless of
+/// `MOUCHAK_AUTO_UPGRADE`.
+///
+/// # Returns
+/// The plan that was carried out and the pre-upgrade snapshot, if one was taken.
+///
+/// # Errors
+/// [`crate::Error::UpgradeRequired`] if the data is newer than this build.
+pub async fn upgrade_data_format(
+    config: &DatabaseConfig,
+) -> Result<(upgrade::UpgradePlan, Option<PathBuf>)> {
+    if let Some(url) = postgres_url(config) {
+        let pg = postgres::PgDb::connect(url, 1).await?;
+        let plan = postgres::plan(&pg, url).await?;
+        if plan.is_newer() {
+            return Err(crate::Error::UpgradeRequired(plan.describe()));
+        }
+        postgres::migrate(&pg).await?;
+        return Ok((plan, None));
+    }
+    let db_path = resolve_db_path();
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let db = Builder::new_local(&db_path).build().await?;
+    let conn = db.connect()?;
+    let _ = conn.execute("PRAGMA busy_timeout=30000;", ()).await;
+
+    let plan = upgrade::plan(&conn, &db_path).await?;
+    if plan.is_newer() {
+        return Err(crate::Error::UpgradeRequired(plan.describe()));
+    }
+    let snapshot = if plan.needs_upgrade() && !plan.fresh {
+        Some(upgrade::snapshot(&conn, &plan).await?)
+    } else {
+        None
+    };
+    upgrade::migrate(&conn).await?;
+    Ok((plan, snapshot))
+}
+
 /// Gets a database connection for executing queries.
 ///
 /// This is a helper function for obtaining a connection to the local database.
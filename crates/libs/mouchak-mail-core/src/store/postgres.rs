@@ -0,0 +1,1319 @@
+//! Postgres backend of the store.
+//!
+//! Selected by a `postgres://` (or `postgresql://`) `database.url`; see
+//! [`super::new_db_pool`]. BMCs keep writing SQLite-flavoured SQL with
+//! `?` parameters and reading `libsql::Value`s, and this module bridges the
+//! difference:
+//!
+//! - [`translate`] rewrites the SQL: `?`/`?N` become `$N`, `LIKE` becomes
+//!   `ILIKE` (SQLite's `LIKE` ignores ASCII case), `IFNULL` becomes
+//!   `COALESCE`, two-argument `MAX`/`MIN` become `GREATEST`/`LEAST` and
+//!   `CURRENT_TIMESTAMP` becomes `now_text()`, which writes timestamps as
+//!   SQLite does (see `migrations/postgres/001_initial_schema.sql`), and
+//!   `INSERT OR IGNORE` becomes `INSERT .. ON CONFLICT DO NOTHING`
+//! - parameters are converted to the type Postgres infers for them, so an
+//!   integer bound to a `TEXT` column is sent as its text, as SQLite's
+//!   column affinity would store it
+//! - result columns are read back as the `libsql::Value` SQLite would
+//!   return: booleans and integers as `Integer`, `NUMERIC` sums as
+//!   `Integer` or `Real`, `JSON`/`JSONB` as `Text`
+//!
+//! The SQLite functions the BMCs use that Postgres lacks (`datetime`,
+//! `strftime('%s', ..)`, `json_valid`, `json_patch`, `group_concat`) are
+//! defined by that migration. SQL that has neither (full-text search, JSON
+//! aggregates) is written per backend by its BMC, on
+//! [`Db::backend`](super::Db::backend).
+//!
+//! The schema is created by `migrations/postgres`, which mirror the SQLite
+//! migrations one for one, in [`migrate`].
+//!
+//! # Read replicas
+//!
+//! With `database.replica_urls`, BMC reads taken with `ModelManager::db_read`
+//! (searches, listings, exports) run on a replica, picked round robin by
+//! [`PgDb::reader`]. So that a message just sent shows up in the inbox
+//! checked next, a replica is only used once it has replayed the primary's
+//! WAL up to where it was after this process's last write; until then, or
+//! when no replica can be reached, reads go to the primary. Writes of other
+//! processes sharing the database are not waited for.
+
+use super::db_statement::Row;
+use super::upgrade::{self, UpgradePlan};
+use crate::Result;
+use bytes::BytesMut;
+use deadpool_postgres::{Manager, ManagerConfig, Object, Pool, RecyclingMethod};
+use libsql::Value;
+use libsql::params::Params;
+use std::error::Error as StdError;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::{FromSql, IsNull, Kind, ToSql, Type, to_sql_checked};
+
+/// Key of the advisory lock taken by migrations and transactions, so
+/// several processes sharing a database migrate once and run their
+/// transactions one at a time, as SQLite's write lock has them.
+const WRITE_LOCK_KEY: i64 = 0x006d_6f75_6368_616b; // "mouchak"
+
+/// Table the data format version is stamped in; SQLite uses
+/// `PRAGMA user_version`.
+const FORMAT_TABLE: &str = "mouchak_data_format";
+
+type BoxError = Box<dyn StdError + Sync + Send>;
+
+/// Whether `url` selects the Postgres backend.
+pub fn is_postgres_url(url: &str) -> bool {
+    url.starts_with("postgres://") || url.starts_with("postgresql://")
+}
+
+/// `url` with its password masked, for logs and upgrade plans.
+pub fn redact_url(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    match authority.rsplit_once('@') {
+        Some((user_info, host)) => {
+            let user = user_info.split(':').next().unwrap_or_default();
+            format!("{scheme}://{user}:***@{host}{path}")
+        }
+        None => url.to_string(),
+    }
+}
+
+/// Primary's WAL position, as a byte offset.
+const PRIMARY_LSN_SQL: &str = "SELECT CAST(pg_current_wal_lsn() - '0/0' AS BIGINT)";
+
+/// WAL position a replica has replayed up to; a server that is not
+/// replaying (a replica URL pointing at a primary) is always caught up.
+const REPLAYED_LSN_SQL: &str =
+    "SELECT CAST(COALESCE(pg_last_wal_replay_lsn(), pg_current_wal_lsn()) - '0/0' AS BIGINT)";
+
+/// A Postgres connection pool, or one pooled connection a transaction or a
+/// replica read runs on.
+#[derive(Clone)]
+pub struct PgDb {
+    pool: Pool,
+    /// Set on the handle a transaction runs its statements with.
+    pinned: Option<Arc<Object>>,
+    /// Read replicas of the primary `pool` connects to, if any.
+    replicas: Option<Arc<Replicas>>,
+}
+
+impl PgDb {
+    /// Connects a pool of up to `pool_size` connections to `url`.
+    ///
+    /// TLS is used unless the URL sets `sslmode=disable`; server
+    /// certificates are verified against the Mozilla root store.
+    pub async fn connect(url: &str, pool_size: usize) -> Result<Self> {
+        let db = Self {
+            pool: build_pool(url, pool_size)?,
+            pinned: None,
+            replicas: None,
+        };
+        // Fail on a bad URL or credentials now rather than on first use
+        db.client().await?;
+        Ok(db)
+    }
+
+    /// Adds pools of up to `pool_size` connections to the read replicas at
+    /// `urls`, for [`Self::reader`].
+    ///
+    /// Replicas are connected to on first use, so one that is down does not
+    /// keep the store from starting; reads go to the primary meanwhile.
+    pub fn with_replicas(mut self, urls: &[&str], pool_size: usize) -> Result<Self> {
+        if urls.is_empty() {
+            return Ok(self);
+        }
+        let pools = urls
+            .iter()
+            .map(|url| build_pool(url, pool_size))
+            .collect::<Result<Vec<_>>>()?;
+        self.replicas = Some(Arc::new(Replicas {
+            replayed: pools.iter().map(|_| AtomicI64::new(0)).collect(),
+            pools,
+            next: AtomicUsize::new(0),
+            writes: AtomicU64::new(0),
+            covered: AtomicU64::new(0),
+            required_lsn: AtomicI64::new(0),
+        }));
+        Ok(self)
+    }
+
+    /// Most connections the pool opens.
+    pub fn pool_size(&self) -> usize {
+        self.pool.status().max_size
+    }
+
+    /// The handle to run a read with: a connection to a replica that has
+    /// replayed this process's writes, or this handle.
+    ///
+    /// A transaction's handle is always returned as is, so it reads its
+    /// own writes.
+    pub(crate) async fn reader(&self) -> PgDb {
+        let Some(replicas) = self.replicas.as_ref().filter(|_| self.pinned.is_none()) else {
+            return self.clone();
+        };
+        match replicas.pick(&self.pool).await {
+            Ok(Some(client)) => Self {
+                pool: self.pool.clone(),
+                pinned: Some(client),
+                replicas: None,
+            },
+            Ok(None) => self.clone(),
+            Err(e) => {
+                tracing::warn!("Failed to read the primary's WAL position: {}", e);
+                self.clone()
+            }
+        }
+    }
+
+    /// Counts a write on the primary, which replicas must replay before
+    /// serving reads again.
+    fn wrote(&self) {
+        if let Some(replicas) = &self.replicas {
+            replicas.writes.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    async fn client(&self) -> Result<Client> {
+        Ok(match &self.pinned {
+            Some(client) => Arc::clone(client),
+            None => Arc::new(self.pool.get().await?),
+        })
+    }
+
+    /// Runs `sql` with `params`, returning the rows it changed.
+    pub async fn execute(&self, sql: &str, params: Params) -> Result<u64> {
+        let client = self.client().await?;
+        let (statement, params) = prepare(&client, sql, params).await?;
+        let changed = client.execute(&statement, &param_refs(&params)).await?;
+        self.wrote();
+        Ok(changed)
+    }
+
+    /// Runs `sql` with `params`, reading all its rows.
+    pub async fn query(&self, sql: &str, params: Params) -> Result<Vec<Row>> {
+        let client = self.client().await?;
+        let (statement, params) = prepare(&client, sql, params).await?;
+        let rows = client.query(&statement, &param_refs(&params)).await?;
+        if !is_select(sql) {
+            self.wrote();
+        }
+        rows.iter()
+            .map(|row| {
+                let values = (0..row.len())
+                    .map(|i| row.try_get::<_, Column>(i).map(|c| c.0))
+                    .collect::<std::result::Result<_, _>>()?;
+                Ok(Row::new(values))
+            })
+            .collect()
+    }
+
+    /// Runs one or more statements without parameters, untranslated.
+    pub async fn execute_batch(&self, sql: &str) -> Result<()> {
+        self.client().await?.batch_execute(sql).await?;
+        self.wrote();
+        Ok(())
+    }
+
+    /// Starts a transaction on a connection of its own; see
+    /// [`PgTransaction`].
+    pub(crate) async fn begin(&self) -> Result<PgTransaction> {
+        let client = Arc::new(self.pool.get().await?);
+        client
+            .batch_execute(&format!(
+                "BEGIN; SELECT pg_advisory_xact_lock({WRITE_LOCK_KEY});"
+            ))
+            .await?;
+        Ok(PgTransaction {
+            db: Self {
+                pool: self.pool.clone(),
+                pinned: Some(client),
+                replicas: self.replicas.clone(),
+            },
+            open: true,
+        })
+    }
+}
+
+/// An open transaction; see `ModelManager::transaction`.
+///
+/// Statements run through [`Self::db`] are part of it. Transactions take
+/// an advisory lock, so like SQLite's `BEGIN IMMEDIATE` they run one at a
+/// time across every process using the database. Dropped while still open,
+/// it is rolled back.
+pub(crate) struct PgTransaction {
+    db: PgDb,
+    open: bool,
+}
+
+impl PgTransaction {
+    /// The handle to run the transaction's statements with.
+    pub(crate) fn db(&self) -> PgDb {
+        self.db.clone()
+    }
+
+    /// Commits if `commit`, rolls back otherwise.
+    pub(crate) async fn end(mut self, commit: bool) -> Result<()> {
+        self.open = false;
+        let sql = if commit { "COMMIT" } else { "ROLLBACK" };
+        self.db.execute_batch(sql).await
+    }
+}
+
+impl Drop for PgTransaction {
+    fn drop(&mut self) {
+        if !self.open {
+            return;
+        }
+        // The connection goes back to the pool once rolled back; without a
+        // runtime to roll back on, it is closed, which rolls back too
+        let Some(client) = self.db.pinned.take() else {
+            return;
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(async move {
+                    if let Err(e) = client.batch_execute("ROLLBACK").await {
+                        tracing::warn!("Failed to roll back dropped transaction: {}", e);
+                    }
+                });
+            }
+            Err(_) => {
+                if let Ok(client) = Arc::try_unwrap(client) {
+                    drop(Object::take(client));
+                }
+            }
+        }
+    }
+}
+
+/// A connection: from the pool, or the one a transaction runs on.
+type Client = Arc<Object>;
+
+/// Read replicas of a [`PgDb`], and how far they must have replayed the
+/// primary's WAL to serve reads after this process's writes.
+struct Replicas {
+    pools: Vec<Pool>,
+    /// WAL position each replica was last seen at; it only moves forward.
+    replayed: Vec<AtomicI64>,
+    /// Round robin position.
+    next: AtomicUsize,
+    /// Writes made on the primary so far.
+    writes: AtomicU64,
+    /// Writes `required_lsn` is known to include.
+    covered: AtomicU64,
+    /// Primary's WAL position after the `covered` writes.
+    required_lsn: AtomicI64,
+}
+
+impl Replicas {
+    /// A connection to the next replica that has caught up, or `None` if
+    /// none has or can be reached.
+    async fn pick(&self, primary: &Pool) -> Result<Option<Client>> {
+        let writes = self.writes.load(Ordering::Acquire);
+        if writes > self.covered.load(Ordering::Acquire) {
+            let lsn = wal_position(&primary.get().await?, PRIMARY_LSN_SQL).await?;
+            self.required_lsn.fetch_max(lsn, Ordering::AcqRel);
+            self.covered.fetch_max(writes, Ordering::AcqRel);
+        }
+        let required = self.required_lsn.load(Ordering::Acquire);
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for i in 0..self.pools.len() {
+            let n = (start + i) % self.pools.len();
+            let client = match self.pools[n].get().await {
+                Ok(client) => client,
+                Err(e) => {
+                    tracing::warn!("Postgres replica {} unavailable: {}", n, e);
+                    continue;
+                }
+            };
+            if self.replayed[n].load(Ordering::Acquire) < required {
+                match wal_position(&client, REPLAYED_LSN_SQL).await {
+                    Ok(lsn) => {
+                        self.replayed[n].fetch_max(lsn, Ordering::AcqRel);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Postgres replica {} unavailable: {}", n, e);
+                        continue;
+                    }
+                }
+                if self.replayed[n].load(Ordering::Acquire) < required {
+                    continue;
+                }
+            }
+            return Ok(Some(Arc::new(client)));
+        }
+        Ok(None)
+    }
+}
+
+/// Runs one of the WAL position queries.
+async fn wal_position(client: &Object, sql: &str) -> Result<i64> {
+    Ok(client.query_one(sql, &[]).await?.try_get(0)?)
+}
+
+/// Whether `sql` only reads; statements run with `query` that are not
+/// (`INSERT .. RETURNING`) count as writes.
+fn is_select(sql: &str) -> bool {
+    let sql = sql.trim_start();
+    ["SELECT", "WITH"].iter().any(|keyword| {
+        sql.get(..keyword.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(keyword))
+    })
+}
+
+/// A pool of up to `pool_size` connections to `url`, connecting on demand.
+fn build_pool(url: &str, pool_size: usize) -> Result<Pool> {
+    let pg_config: tokio_postgres::Config = url.parse()?;
+    let manager_config = ManagerConfig {
+        recycling_method: RecyclingMethod::Fast,
+    };
+    let manager = if pg_config.get_ssl_mode() == tokio_postgres::config::SslMode::Disable {
+        Manager::from_config(pg_config, tokio_postgres::NoTls, manager_config)
+    } else {
+        Manager::from_config(pg_config, tls_connector()?, manager_config)
+    };
+    Pool::builder(manager)
+        .max_size(pool_size.max(1))
+        .build()
+        .map_err(|e| crate::Error::InvalidInput(format!("Postgres pool: {e}")))
+}
+
+/// Prepares the translation of `sql`, pairing `params` with the types
+/// Postgres inferred for them.
+///
+/// A parameter whose type can't be inferred (`? IS NULL` on its own) fails
+/// the first attempt; the statement is then prepared again with every
+/// parameter typed after its value.
+async fn prepare(
+    client: &Client,
+    sql: &str,
+    params: Params,
+) -> Result<(tokio_postgres::Statement, Vec<Param>)> {
+    let values = match params {
+        Params::None => Vec::new(),
+        Params::Positional(values) => values,
+        Params::Named(_) => {
+            return Err(crate::Error::InvalidInput(
+                "named parameters are not supported on Postgres".into(),
+            ));
+        }
+    };
+    let sql = translate(sql);
+    // Parameters Postgres cannot infer a type for, such as `?1` in
+    // `?1 IS NULL OR a = ?1`, are declared one at a time from their value;
+    // the others stay unspecified (oid 0) and are inferred
+    let unspecified = Type::new("unspecified".into(), 0, Kind::Simple, "pg_catalog".into());
+    let mut types = vec![unspecified.clone(); values.len()];
+    let mut null_as_int = vec![false; values.len()];
+    let mut typed: Option<usize> = None;
+    let statement = loop {
+        let prepared = if typed.is_none() {
+            client.prepare_cached(&sql).await
+        } else {
+            client.prepare_typed_cached(&sql, &types).await
+        };
+        let e = match prepared {
+            Ok(statement) => break statement,
+            Err(e) => e,
+        };
+        let index = match e.code() {
+            Some(&SqlState::INDETERMINATE_DATATYPE | &SqlState::AMBIGUOUS_PARAMETER) => {
+                untyped_parameter(&e).filter(|&n| n < values.len() && types[n] == unspecified)
+            }
+            // A NULL declared as text compared with a number
+            Some(&SqlState::UNDEFINED_FUNCTION | &SqlState::DATATYPE_MISMATCH) => {
+                typed.filter(|&n| matches!(values[n], Value::Null) && !null_as_int[n])
+            }
+            _ => None,
+        };
+        let Some(index) = index else {
+            return Err(e.into());
+        };
+        types[index] = if types[index] == Type::TEXT {
+            null_as_int[index] = true;
+            Type::INT8
+        } else {
+            value_type(&values[index])
+        };
+        typed = Some(index);
+    };
+    Ok((statement, values.into_iter().map(Param).collect()))
+}
+
+/// Index of the parameter an "could not determine data type of parameter
+/// $N" error is about.
+fn untyped_parameter(e: &tokio_postgres::Error) -> Option<usize> {
+    let message = e.as_db_error()?.message();
+    let number: usize = message.rsplit_once('$')?.1.parse().ok()?;
+    number.checked_sub(1)
+}
+
+fn param_refs(params: &[Param]) -> Vec<&(dyn ToSql + Sync)> {
+    params.iter().map(|p| p as &(dyn ToSql + Sync)).collect()
+}
+
+/// The Postgres type a value is sent as when none could be inferred.
+fn value_type(value: &Value) -> Type {
+    match value {
+        Value::Integer(_) => Type::INT8,
+        Value::Real(_) => Type::FLOAT8,
+        Value::Blob(_) => Type::BYTEA,
+        Value::Null | Value::Text(_) => Type::TEXT,
+    }
+}
+
+/// A parameter, sent as whatever type Postgres expects.
+#[derive(Debug)]
+struct Param(Value);
+
+impl ToSql for Param {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> std::result::Result<IsNull, BoxError> {
+        let mismatch = || -> BoxError { format!("cannot send {:?} as {}", self.0, ty).into() };
+        match &self.0 {
+            Value::Null => Ok(IsNull::Yes),
+            Value::Integer(i) => match *ty {
+                Type::INT8 => i.to_sql(ty, out),
+                Type::INT4 => i32::try_from(*i)?.to_sql(ty, out),
+                Type::INT2 => i16::try_from(*i)?.to_sql(ty, out),
+                Type::BOOL => (*i != 0).to_sql(ty, out),
+                Type::FLOAT8 => (*i as f64).to_sql(ty, out),
+                Type::FLOAT4 => (*i as f32).to_sql(ty, out),
+                _ if is_text(ty) => i.to_string().as_str().to_sql(&Type::TEXT, out),
+                _ => Err(mismatch()),
+            },
+            Value::Real(f) => match *ty {
+                Type::FLOAT8 => f.to_sql(ty, out),
+                Type::FLOAT4 => (*f as f32).to_sql(ty, out),
+                _ if is_text(ty) => f.to_string().as_str().to_sql(&Type::TEXT, out),
+                _ => Err(mismatch()),
+            },
+            Value::Text(s) => match *ty {
+                Type::INT8 => s.trim().parse::<i64>()?.to_sql(ty, out),
+                Type::INT4 => s.trim().parse::<i32>()?.to_sql(ty, out),
+                Type::INT2 => s.trim().parse::<i16>()?.to_sql(ty, out),
+                Type::FLOAT8 => s.trim().parse::<f64>()?.to_sql(ty, out),
+                Type::BOOL => matches!(s.trim(), "1" | "true" | "t").to_sql(ty, out),
+                Type::JSONB => {
+                    out.extend_from_slice(&[1]);
+                    out.extend_from_slice(s.as_bytes());
+                    Ok(IsNull::No)
+                }
+                Type::BYTEA => s.as_bytes().to_sql(ty, out),
+                _ if is_text(ty) || *ty == Type::JSON => {
+                    out.extend_from_slice(s.as_bytes());
+                    Ok(IsNull::No)
+                }
+                _ => Err(mismatch()),
+            },
+            Value::Blob(b) => match *ty {
+                Type::BYTEA => b.as_slice().to_sql(ty, out),
+                _ if is_text(ty) => std::str::from_utf8(b)?.to_sql(&Type::TEXT, out),
+                _ => Err(mismatch()),
+            },
+        }
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+fn is_text(ty: &Type) -> bool {
+    matches!(
+        *ty,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN
+    )
+}
+
+/// A result column, read as the value SQLite would return.
+struct Column(Value);
+
+impl<'a> FromSql<'a> for Column {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> std::result::Result<Self, BoxError> {
+        let value = match *ty {
+            Type::BOOL => Value::Integer(i64::from(bool::from_sql(ty, raw)?)),
+            Type::INT2 => Value::Integer(i16::from_sql(ty, raw)?.into()),
+            Type::INT4 => Value::Integer(i32::from_sql(ty, raw)?.into()),
+            Type::INT8 => Value::Integer(i64::from_sql(ty, raw)?),
+            Type::OID => Value::Integer(u32::from_sql(ty, raw)?.into()),
+            Type::FLOAT4 => Value::Real(f32::from_sql(ty, raw)?.into()),
+            Type::FLOAT8 => Value::Real(f64::from_sql(ty, raw)?),
+            Type::NUMERIC => numeric(raw)?,
+            Type::BYTEA => Value::Blob(raw.to_vec()),
+            // Binary JSONB is a version byte and the JSON text
+            Type::JSONB => Value::Text(String::from_utf8(
+                raw.get(1..).unwrap_or_default().to_vec(),
+            )?),
+            Type::VOID => Value::Null,
+            _ if is_text(ty) || *ty == Type::JSON => Value::Text(String::from_utf8(raw.to_vec())?),
+            _ => return Err(format!("unsupported column type {ty}").into()),
+        };
+        Ok(Column(value))
+    }
+
+    fn from_sql_null(_: &Type) -> std::result::Result<Self, BoxError> {
+        Ok(Column(Value::Null))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Decodes a binary `NUMERIC`: an `Integer` when it has no fractional
+/// digits (as `SUM` of integers is in SQLite), a `Real` otherwise.
+fn numeric(raw: &[u8]) -> std::result::Result<Value, BoxError> {
+    const NEGATIVE: u16 = 0x4000;
+    const NAN: u16 = 0xC000;
+    let word = |i: usize| -> std::result::Result<u16, BoxError> {
+        raw.get(i * 2..i * 2 + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| "truncated NUMERIC".into())
+    };
+    let ndigits = usize::from(word(0)?);
+    let weight = word(1)? as i16;
+    let sign = word(2)?;
+    let dscale = word(3)?;
+    if sign == NAN {
+        return Ok(Value::Null);
+    }
+    let mut value = 0f64;
+    let mut int_value = 0i128;
+    for i in 0..ndigits {
+        let digit = word(4 + i)?;
+        let exponent = i32::from(weight) - i as i32;
+        value += f64::from(digit) * 10_000f64.powi(exponent);
+        if exponent >= 0 {
+            int_value += i128::from(digit) * 10_000i128.pow(exponent as u32);
+        }
+    }
+    let negative = sign == NEGATIVE;
+    if dscale == 0 {
+        let int_value = if negative { -int_value } else { int_value };
+        return Ok(Value::Integer(i64::try_from(int_value)?));
+    }
+    Ok(Value::Real(if negative { -value } else { value }))
+}
+
+fn tls_connector() -> Result<tokio_postgres_rustls::MakeRustlsConnect> {
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| crate::Error::InvalidInput(format!("Postgres TLS: {e}")))?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Ok(tokio_postgres_rustls::MakeRustlsConnect::new(config))
+}
+
+/// Rewrites SQLite-flavoured SQL for Postgres; see the [module docs](self).
+///
+/// String literals, quoted identifiers and comments are left as they are.
+pub fn translate(sql: &str) -> String {
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len() + 16);
+    let mut last_param = 0u32;
+    // Set after `INSERT OR IGNORE` until its `ON CONFLICT` clause is written
+    let mut ignore_conflicts = false;
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\'' | b'"' => {
+                let end = quoted_end(bytes, i);
+                out.push_str(&sql[i..end]);
+                i = end;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                let end = sql[i..].find('\n').map_or(sql.len(), |n| i + n);
+                out.push_str(&sql[i..end]);
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = sql[i + 2..].find("*/").map_or(sql.len(), |n| i + 2 + n + 2);
+                out.push_str(&sql[i..end]);
+                i = end;
+            }
+            b'?' => {
+                let digits = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+                let index = if digits > 0 {
+                    sql[i + 1..i + 1 + digits].parse().unwrap_or(0)
+                } else {
+                    last_param + 1
+                };
+                last_param = last_param.max(index);
+                out.push('$');
+                out.push_str(&index.to_string());
+                i += 1 + digits;
+            }
+            _ if c.is_ascii_alphabetic() || c == b'_' => {
+                let len = bytes[i..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                    .count();
+                let word = &sql[i..i + len];
+                // Qualified names (`t.max`) are columns, not functions
+                let qualified = out.ends_with('.');
+                let upper = word.to_ascii_uppercase();
+                if upper == "OR" && !qualified && ends_with_word(&out, "INSERT") {
+                    let rest = sql[i + len..].trim_start();
+                    let ignore = rest
+                        .get(..6)
+                        .is_some_and(|w| w.eq_ignore_ascii_case("IGNORE"))
+                        && !rest[6..]
+                            .bytes()
+                            .next()
+                            .is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_');
+                    if ignore {
+                        // `INSERT` keeps its trailing space
+                        i = sql.len() - rest.len() + 6;
+                        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                            i += 1;
+                        }
+                        ignore_conflicts = true;
+                        continue;
+                    }
+                }
+                if ignore_conflicts && depth == 0 && upper == "RETURNING" {
+                    out.push_str("ON CONFLICT DO NOTHING ");
+                    ignore_conflicts = false;
+                }
+                let replacement = match upper.as_str() {
+                    _ if qualified => None,
+                    "LIKE" => Some("ILIKE"),
+                    "IFNULL" => Some("COALESCE"),
+                    "CURRENT_TIMESTAMP" => Some("now_text()"),
+                    "MAX" if has_two_args(sql, i + len) => Some("GREATEST"),
+                    "MIN" if has_two_args(sql, i + len) => Some("LEAST"),
+                    _ => None,
+                };
+                out.push_str(replacement.unwrap_or(word));
+                i += len;
+            }
+            b'(' | b')' => {
+                depth += if c == b'(' { 1 } else { -1 };
+                out.push(c as char);
+                i += 1;
+            }
+            b';' if ignore_conflicts && depth == 0 => {
+                out.push_str("\nON CONFLICT DO NOTHING;");
+                ignore_conflicts = false;
+                i += 1;
+            }
+            _ => {
+                let len = sql[i..].chars().next().map_or(1, char::len_utf8);
+                out.push_str(&sql[i..i + len]);
+                i += len;
+            }
+        }
+    }
+    if ignore_conflicts {
+        out.push_str("\nON CONFLICT DO NOTHING");
+    }
+    out
+}
+
+/// Whether `out` ends with the keyword `word`, ignoring trailing whitespace.
+fn ends_with_word(out: &str, word: &str) -> bool {
+    let out = out.trim_end();
+    out.len() >= word.len()
+        && out[out.len() - word.len()..].eq_ignore_ascii_case(word)
+        && !out[..out.len() - word.len()]
+            .bytes()
+            .next_back()
+            .is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// End of the literal or quoted identifier opening at `start`; a doubled
+/// quote is an escaped one.
+fn quoted_end(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Whether the call whose name ends at `from` has more than one argument.
+fn has_two_args(sql: &str, from: usize) -> bool {
+    let bytes = sql.as_bytes();
+    let mut i = from;
+    while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+        i += 1;
+    }
+    if bytes.get(i) != Some(&b'(') {
+        return false;
+    }
+    let mut depth = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' => {
+                i = quoted_end(bytes, i);
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return false;
+                }
+            }
+            b',' if depth == 1 => return true,
+            _ => {}
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Converts an FTS5 `MATCH` query into a `to_tsquery` one.
+///
+/// Bare terms are ANDed, `"a b"` phrases become `a <-> b`, `term*` a
+/// prefix match, and `AND`/`OR`/`NOT` and parentheses carry over. Returns
+/// `None` for a query without terms.
+pub fn fts_to_tsquery(query: &str) -> Option<String> {
+    #[derive(PartialEq)]
+    enum Token {
+        Term(String),
+        And,
+        Or,
+        Not,
+        Open,
+        Close,
+    }
+    let lexeme = |word: &str| -> Option<String> {
+        let (word, prefix) = match word.strip_suffix('*') {
+            Some(word) => (word, true),
+            None => (word, false),
+        };
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_');
+        if word.is_empty() {
+            return None;
+        }
+        let quoted = format!("'{}'", word.replace('\'', "''"));
+        Some(if prefix {
+            format!("{quoted}:*")
+        } else {
+            quoted
+        })
+    };
+
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    phrase.push(c);
+                }
+                let prefix = chars.peek() == Some(&'*');
+                if prefix {
+                    chars.next();
+                }
+                let mut words: Vec<String> = phrase
+                    .split(|c: char| c.is_whitespace())
+                    .filter_map(lexeme)
+                    .collect();
+                if prefix && let Some(last) = words.last_mut() {
+                    last.push_str(":*");
+                }
+                if !words.is_empty() {
+                    tokens.push(Token::Term(format!("({})", words.join(" <-> "))));
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "AND" => tokens.push(Token::And),
+                    "OR" => tokens.push(Token::Or),
+                    "NOT" => tokens.push(Token::Not),
+                    _ => tokens.extend(lexeme(&word).map(Token::Term)),
+                }
+            }
+        }
+    }
+
+    // Operands in a row are ANDed; `a NOT b` is `a & !b`
+    let mut out = String::new();
+    let mut operand_before = false;
+    let mut terms = 0;
+    for token in tokens {
+        let starts_operand = matches!(token, Token::Term(_) | Token::Open | Token::Not);
+        if starts_operand && operand_before {
+            out.push_str(" & ");
+        }
+        match token {
+            Token::Term(term) => {
+                out.push_str(&term);
+                terms += 1;
+                operand_before = true;
+            }
+            Token::Open => {
+                out.push('(');
+                operand_before = false;
+            }
+            Token::Close => {
+                out.push(')');
+                operand_before = true;
+            }
+            Token::Not => {
+                out.push('!');
+                operand_before = false;
+            }
+            Token::And | Token::Or => {
+                if operand_before {
+                    out.push_str(if token == Token::And { " & " } else { " | " });
+                }
+                operand_before = false;
+            }
+        }
+    }
+    (terms > 0).then_some(out)
+}
+
+/// Runs the Postgres migrations not yet applied and stamps the data format
+/// version, in one transaction holding the migration lock.
+///
+/// Every migration is written to be re-run, like the SQLite ones, so all of
+/// them run each time.
+pub async fn migrate(db: &PgDb) -> Result<()> {
+    let client = db.pool.get().await?;
+    client
+        .batch_execute(&format!(
+            "BEGIN; SELECT pg_advisory_xact_lock({WRITE_LOCK_KEY}); \
+             CREATE TABLE IF NOT EXISTS {FORMAT_TABLE} (\
+                 id BIGINT PRIMARY KEY CHECK (id = 1), version BIGINT NOT NULL);"
+        ))
+        .await?;
+    let migrated = async {
+        for (name, sql) in upgrade::PG_MIGRATIONS {
+            client.batch_execute(sql).await.map_err(|e| {
+                crate::Error::InvalidInput(format!(
+                    "Postgres migration {name} failed: {}",
+                    postgres_message(&e)
+                ))
+            })?;
+        }
+        client
+            .execute(
+                &format!(
+                    "INSERT INTO {FORMAT_TABLE} (id, version) VALUES (1, $1) \
+                     ON CONFLICT (id) DO UPDATE SET version = excluded.version"
+                ),
+                &[&upgrade::DATA_FORMAT_VERSION],
+            )
+            .await?;
+        Ok::<_, crate::Error>(())
+    }
+    .await;
+    let end = if migrated.is_ok() {
+        "COMMIT"
+    } else {
+        "ROLLBACK"
+    };
+    client.batch_execute(end).await?;
+    migrated
+}
+
+/// Reads the data format stamp; 0 before the first migration.
+pub async fn read_format_version(db: &PgDb) -> Result<i64> {
+    let client = db.pool.get().await?;
+    let exists = client
+        .query_one("SELECT to_regclass($1) IS NOT NULL", &[&FORMAT_TABLE])
+        .await?
+        .get::<_, bool>(0);
+    if !exists {
+        return Ok(0);
+    }
+    let row = client
+        .query_opt(
+            &format!("SELECT version FROM {FORMAT_TABLE} WHERE id = 1"),
+            &[],
+        )
+        .await?;
+    Ok(row.map_or(0, |row| row.get(0)))
+}
+
+/// Works out what opening the database at `url` with this build would
+/// change; like [`upgrade::plan`].
+pub async fn plan(db: &PgDb, url: &str) -> Result<UpgradePlan> {
+    let current_version = read_format_version(db).await?;
+    let tables: i64 = db
+        .pool
+        .get()
+        .await?
+        .query_one(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema = current_schema()",
+            &[],
+        )
+        .await?
+        .get(0);
+    let applied = usize::try_from(current_version).unwrap_or(0);
+    Ok(UpgradePlan {
+        db_path: PathBuf::from(redact_url(url)),
+        current_version,
+        target_version: upgrade::DATA_FORMAT_VERSION,
+        fresh: tables == 0,
+        pending_migrations: upgrade::PG_MIGRATIONS
+            .iter()
+            .skip(applied)
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        backend: super::Backend::Postgres,
+    })
+}
+
+/// The server's message for `e`, or its kind when it didn't come from the
+/// server.
+pub(crate) fn postgres_message(e: &tokio_postgres::Error) -> String {
+    match e.as_db_error() {
+        Some(db) => format!("{} ({})", db.message(), db.code().code()),
+        None => e.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_mirror_sqlite() {
+        let names = |migrations: &[(&str, &str)]| -> Vec<String> {
+            migrations
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect()
+        };
+        assert_eq!(names(upgrade::PG_MIGRATIONS), names(upgrade::MIGRATIONS));
+    }
+
+    #[test]
+    fn test_translate_numbers_parameters() {
+        assert_eq!(
+            translate("SELECT * FROM t WHERE a = ? AND b = ?"),
+            "SELECT * FROM t WHERE a = $1 AND b = $2"
+        );
+        assert_eq!(
+            translate("WHERE ?1 IS NULL OR a = ?1 LIMIT ?"),
+            "WHERE $1 IS NULL OR a = $1 LIMIT $2"
+        );
+        assert_eq!(
+            translate("SELECT '?', \"?\" -- ?\n, ?"),
+            "SELECT '?', \"?\" -- ?\n, $1"
+        );
+    }
+
+    #[test]
+    fn test_translate_rewrites_functions() {
+        assert_eq!(
+            translate("SELECT MAX(a), max(b, ?), MIN (c, 1), t.max FROM t"),
+            "SELECT MAX(a), GREATEST(b, $1), LEAST (c, 1), t.max FROM t"
+        );
+        assert_eq!(
+            translate("WHERE name NOT LIKE 'x%' AND IFNULL(a, 0) < CURRENT_TIMESTAMP"),
+            "WHERE name NOT ILIKE 'x%' AND COALESCE(a, 0) < now_text()"
+        );
+        assert_eq!(
+            translate("SELECT MAX(CAST(x AS INTEGER)), 'it''s like'"),
+            "SELECT MAX(CAST(x AS INTEGER)), 'it''s like'"
+        );
+    }
+
+    #[test]
+    fn test_translate_insert_or_ignore() {
+        assert_eq!(
+            translate("INSERT OR IGNORE INTO t (a) VALUES (?)"),
+            "INSERT INTO t (a) VALUES ($1)\nON CONFLICT DO NOTHING"
+        );
+        assert_eq!(
+            translate("insert or ignore into t (a) VALUES (?) RETURNING id"),
+            "insert into t (a) VALUES ($1) ON CONFLICT DO NOTHING RETURNING id"
+        );
+        assert_eq!(
+            translate("INSERT OR IGNORE INTO t SELECT (a) FROM u; SELECT 1"),
+            "INSERT INTO t SELECT (a) FROM u\nON CONFLICT DO NOTHING; SELECT 1"
+        );
+        assert_eq!(translate("SELECT a OR b FROM t"), "SELECT a OR b FROM t");
+    }
+
+    /// SQL statements written as string literals in the BMCs under
+    /// `src/model`, with the file each is in. `format!` templates are left
+    /// out, as they are only SQL once filled in.
+    fn bmc_statements() -> Vec<(PathBuf, String)> {
+        let mut statements = Vec::new();
+        let mut dirs = vec![PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/model")];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let source = std::fs::read_to_string(&path).unwrap();
+                // Unit tests set up their fixtures with SQL of their own
+                let source = source.split("#[cfg(test)]").next().unwrap_or_default();
+                for literal in string_literals(source) {
+                    let keyword = literal
+                        .trim_start()
+                        .split(|c: char| !c.is_ascii_alphabetic())
+                        .next()
+                        .unwrap_or_default()
+                        .to_ascii_uppercase();
+                    if ["SELECT", "INSERT", "UPDATE", "DELETE", "WITH"].contains(&keyword.as_str())
+                        && !literal.contains('{')
+                    {
+                        statements.push((path.clone(), literal));
+                    }
+                }
+            }
+        }
+        statements
+    }
+
+    /// Contents of the string literals in Rust `source`.
+    fn string_literals(source: &str) -> Vec<String> {
+        let bytes = source.as_bytes();
+        let mut literals = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                    i = source[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                }
+                // A char literal, or a lifetime
+                b'\'' => {
+                    i += match (bytes.get(i + 1), bytes.get(i + 2)) {
+                        (Some(b'\\'), _) => source[i + 3..].find('\'').map_or(1, |n| n + 4),
+                        (_, Some(b'\'')) => 3,
+                        _ => 1,
+                    };
+                }
+                b'r' if matches!(bytes.get(i + 1), Some(b'"' | b'#')) => {
+                    let hashes = bytes[i + 1..].iter().take_while(|b| **b == b'#').count();
+                    // Not `r#ident`
+                    if bytes.get(i + 1 + hashes) != Some(&b'"') {
+                        i += 1;
+                        continue;
+                    }
+                    let start = i + 2 + hashes;
+                    let close = format!("\"{}", "#".repeat(hashes));
+                    let end = start + source[start..].find(&close).unwrap();
+                    literals.push(source[start..end].to_string());
+                    i = end + close.len();
+                }
+                b'"' => {
+                    let mut literal = String::new();
+                    i += 1;
+                    while bytes[i] != b'"' {
+                        if bytes[i] != b'\\' {
+                            let len = source[i..].chars().next().map_or(1, char::len_utf8);
+                            literal.push_str(&source[i..i + len]);
+                            i += len;
+                            continue;
+                        }
+                        match bytes[i + 1] {
+                            b'n' => literal.push('\n'),
+                            b't' => literal.push('\t'),
+                            // A line continuation skips the next line's indent
+                            b'\n' => {
+                                i += 2;
+                                while bytes[i].is_ascii_whitespace() {
+                                    i += 1;
+                                }
+                                continue;
+                            }
+                            c => literal.push(c as char),
+                        }
+                        i += 2;
+                    }
+                    literals.push(literal);
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+        literals
+    }
+
+    /// Words, parameters and punctuation of `sql` outside literals and
+    /// comments, with their offsets.
+    fn sql_tokens(sql: &str) -> Vec<(usize, &str)> {
+        let bytes = sql.as_bytes();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let c = bytes[i];
+            let len = match c {
+                b'\'' | b'"' => {
+                    i = quoted_end(bytes, i);
+                    continue;
+                }
+                b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                    i = sql[i..].find('\n').map_or(sql.len(), |n| i + n);
+                    continue;
+                }
+                _ if c.is_ascii_alphanumeric() || c == b'_' || c == b'$' => {
+                    1 + bytes[i + 1..]
+                        .iter()
+                        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                        .count()
+                }
+                _ if c.is_ascii_whitespace() => {
+                    i += 1;
+                    continue;
+                }
+                _ => sql[i..].chars().next().map_or(1, char::len_utf8),
+            };
+            tokens.push((i, &sql[i..i + len]));
+            i += len;
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_translate_every_bmc_statement() {
+        let statements = bmc_statements();
+        assert!(
+            statements.len() > 300,
+            "found {} statements",
+            statements.len()
+        );
+        for (path, sql) in statements {
+            // FTS5 search is written per backend by MessageBmc
+            if sql.contains(" MATCH ") {
+                continue;
+            }
+            let pg = translate(&sql);
+            let at = format!("{}:\n{pg}", path.display());
+            let tokens = sql_tokens(&pg);
+            let mut depth = 0;
+            let mut params = std::collections::BTreeSet::new();
+            for (n, &(offset, token)) in tokens.iter().enumerate() {
+                let previous = n.checked_sub(1).map(|p| tokens[p].1);
+                match token.to_ascii_uppercase().as_str() {
+                    "?" => panic!("untranslated parameter in {at}"),
+                    "(" => depth += 1,
+                    ")" => {
+                        depth -= 1;
+                        assert!(depth >= 0, "unbalanced parentheses in {at}");
+                    }
+                    word @ ("LIKE" | "IFNULL" | "CURRENT_TIMESTAMP" | "NOCASE" | "GLOB") => {
+                        panic!("SQLite's {word} left in {at}")
+                    }
+                    "OR" if previous.is_some_and(|p| p.eq_ignore_ascii_case("INSERT")) => {
+                        panic!("INSERT OR left in {at}")
+                    }
+                    "MAX" | "MIN" if previous != Some(".") => assert!(
+                        !has_two_args(&pg, offset + token.len()),
+                        "two-argument {token} left in {at}"
+                    ),
+                    _ => {}
+                }
+                if let Some(index) = token.strip_prefix('$') {
+                    params.insert(index.parse::<usize>().unwrap());
+                }
+            }
+            assert_eq!(depth, 0, "unbalanced parentheses in {at}");
+            assert!(
+                params.iter().copied().eq(1..=params.len()),
+                "parameters {params:?} are not numbered from $1 in {at}"
+            );
+            if sql.to_ascii_uppercase().contains("INSERT OR IGNORE") {
+                assert!(pg.contains("ON CONFLICT DO NOTHING"), "{at}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_select() {
+        assert!(is_select("\n    select id FROM t"));
+        assert!(is_select("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(!is_select("INSERT INTO t (a) VALUES (?) RETURNING id"));
+        assert!(!is_select("SEL"));
+    }
+
+    #[test]
+    fn test_fts_to_tsquery() {
+        assert_eq!(
+            fts_to_tsquery("hello world*").as_deref(),
+            Some("'hello' & 'world':*")
+        );
+        assert_eq!(
+            fts_to_tsquery("\"exact phrase\" OR other").as_deref(),
+            Some("('exact' <-> 'phrase') | 'other'")
+        );
+        assert_eq!(
+            fts_to_tsquery("a NOT (b AND c)").as_deref(),
+            Some("'a' & !('b' & 'c')")
+        );
+        assert_eq!(
+            fts_to_tsquery("\"full-text\" it's").as_deref(),
+            Some("('full-text') & 'it''s'")
+        );
+        assert_eq!(fts_to_tsquery("* ..."), None);
+    }
+
+    #[test]
+    fn test_numeric_decodes_like_sqlite() {
+        // 12345678 = 1234 * 10000 + 5678
+        let int = [0, 2, 0, 1, 0, 0, 0, 0, 0x04, 0xD2, 0x16, 0x2E];
+        assert!(matches!(numeric(&int).unwrap(), Value::Integer(12_345_678)));
+        // -2.5: digits 2 and 5000, weight 0, dscale 1
+        let real = [0, 2, 0, 0, 0x40, 0, 0, 1, 0, 2, 0x13, 0x88];
+        assert!(matches!(numeric(&real).unwrap(), Value::Real(r) if r == -2.5));
+    }
+
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url("postgres://mail:secret@db:5432/mouchak"),
+            "postgres://mail:***@db:5432/mouchak"
+        );
+        assert_eq!(redact_url("postgres://db/mouchak"), "postgres://db/mouchak");
+    }
+}
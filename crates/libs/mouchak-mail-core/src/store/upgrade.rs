@@ -0,0 +1,583 @@
+//! Data format versioning and upgrades.
+//!
+//! The database is stamped with its data format version in SQLite's
+//! `PRAGMA user_version`. The format version is the number of migrations the
+//! writing build knew about, so version `N` means migrations `001..=N` have
+//! been applied. Databases created before stamping report version 0.
+//!
+//! On startup [`super::new_db_pool`] compares the stamp with
+//! [`DATA_FORMAT_VERSION`]:
+//!
+//! - **Older**: the database is snapshotted next to itself with
+//!   `VACUUM INTO` and then migrated, unless `MOUCHAK_AUTO_UPGRADE=false`, in
+//!   which case startup fails with instructions to run `mouchak-mail upgrade`
+//! - **Newer**: startup fails, since this build does not know the schema
+//! - **Fresh** (no tables yet): migrated without a snapshot
+//!
+//! `mouchak-mail upgrade --check` reports the [`UpgradePlan`] without
+//! changing anything.
+//!
+//! On the Postgres backend the stamp is kept in a table, the migrations are
+//! those of `migrations/postgres` ([`PG_MIGRATIONS`]) and run in one
+//! transaction, so no snapshot is taken: a failed upgrade leaves the
+//! database as it was (see [`super::postgres::migrate`]).
+
+use super::Backend;
+use crate::Result;
+use libsql::Connection;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Migrations in order, by file name.
+pub(crate) const MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "001_initial_schema",
+        include_str!("../../../../../migrations/001_initial_schema.sql"),
+    ),
+    (
+        "002_agent_capabilities",
+        include_str!("../../../../../migrations/002_agent_capabilities.sql"),
+    ),
+    (
+        "003_tool_metrics",
+        include_str!("../../../../../migrations/003_tool_metrics.sql"),
+    ),
+    (
+        "004_attachments",
+        include_str!("../../../../../migrations/004_attachments.sql"),
+    ),
+    (
+        "005_attachments_agent",
+        include_str!("../../../../../migrations/005_attachments_agent.sql"),
+    ),
+    (
+        "006_query_indexes",
+        include_str!("../../../../../migrations/006_query_indexes.sql"),
+    ),
+    (
+        "007_message_routing",
+        include_str!("../../../../../migrations/007_message_routing.sql"),
+    ),
+    (
+        "008_reservation_watchers",
+        include_str!("../../../../../migrations/008_reservation_watchers.sql"),
+    ),
+    (
+        "009_focus_windows",
+        include_str!("../../../../../migrations/009_focus_windows.sql"),
+    ),
+    (
+        "010_attachment_search",
+        include_str!("../../../../../migrations/010_attachment_search.sql"),
+    ),
+    (
+        "011_message_body_format",
+        include_str!("../../../../../migrations/011_message_body_format.sql"),
+    ),
+    (
+        "012_web_push",
+        include_str!("../../../../../migrations/012_web_push.sql"),
+    ),
+    (
+        "013_ui_preferences",
+        include_str!("../../../../../migrations/013_ui_preferences.sql"),
+    ),
+    (
+        "014_event_log",
+        include_str!("../../../../../migrations/014_event_log.sql"),
+    ),
+    (
+        "015_thread_reads",
+        include_str!("../../../../../migrations/015_thread_reads.sql"),
+    ),
+    (
+        "016_reservation_sets",
+        include_str!("../../../../../migrations/016_reservation_sets.sql"),
+    ),
+    (
+        "017_project_contact_policies",
+        include_str!("../../../../../migrations/017_project_contact_policies.sql"),
+    ),
+    (
+        "018_agent_slas",
+        include_str!("../../../../../migrations/018_agent_slas.sql"),
+    ),
+    (
+        "019_thread_workflows",
+        include_str!("../../../../../migrations/019_thread_workflows.sql"),
+    ),
+    (
+        "020_message_custom_fields",
+        include_str!("../../../../../migrations/020_message_custom_fields.sql"),
+    ),
+    (
+        "021_external_tools",
+        include_str!("../../../../../migrations/021_external_tools.sql"),
+    ),
+    (
+        "022_github_sync",
+        include_str!("../../../../../migrations/022_github_sync.sql"),
+    ),
+    (
+        "023_ticket_links",
+        include_str!("../../../../../migrations/023_ticket_links.sql"),
+    ),
+    (
+        "024_ci_status",
+        include_str!("../../../../../migrations/024_ci_status.sql"),
+    ),
+    (
+        "025_project_settings",
+        include_str!("../../../../../migrations/025_project_settings.sql"),
+    ),
+    (
+        "026_message_catalog",
+        include_str!("../../../../../migrations/026_message_catalog.sql"),
+    ),
+    (
+        "027_message_bodies",
+        include_str!("../../../../../migrations/027_message_bodies.sql"),
+    ),
+    (
+        "028_thread_archival",
+        include_str!("../../../../../migrations/028_thread_archival.sql"),
+    ),
+    (
+        "029_thread_watchers",
+        include_str!("../../../../../migrations/029_thread_watchers.sql"),
+    ),
+    (
+        "030_delegations",
+        include_str!("../../../../../migrations/030_delegations.sql"),
+    ),
+    (
+        "031_message_approvals",
+        include_str!("../../../../../migrations/031_message_approvals.sql"),
+    ),
+    (
+        "032_reply_deadlines",
+        include_str!("../../../../../migrations/032_reply_deadlines.sql"),
+    ),
+    (
+        "033_message_search",
+        include_str!("../../../../../migrations/033_message_search.sql"),
+    ),
+    (
+        "034_global_threads",
+        include_str!("../../../../../migrations/034_global_threads.sql"),
+    ),
+    (
+        "035_saved_searches",
+        include_str!("../../../../../migrations/035_saved_searches.sql"),
+    ),
+    (
+        "036_labels",
+        include_str!("../../../../../migrations/036_labels.sql"),
+    ),
+    (
+        "037_public_ids",
+        include_str!("../../../../../migrations/037_public_ids.sql"),
+    ),
+    (
+        "038_api_tokens",
+        include_str!("../../../../../migrations/038_api_tokens.sql"),
+    ),
+    (
+        "039_message_replies",
+        include_str!("../../../../../migrations/039_message_replies.sql"),
+    ),
+    (
+        "040_operation_intents",
+        include_str!("../../../../../migrations/040_operation_intents.sql"),
+    ),
+    (
+        "041_attachment_blobs",
+        include_str!("../../../../../migrations/041_attachment_blobs.sql"),
+    ),
+    (
+        "042_retention_policies",
+        include_str!("../../../../../migrations/042_retention_policies.sql"),
+    ),
+    (
+        "043_dead_letters",
+        include_str!("../../../../../migrations/043_dead_letters.sql"),
+    ),
+    (
+        "044_webhooks",
+        include_str!("../../../../../migrations/044_webhooks.sql"),
+    ),
+    (
+        "045_email_bridge",
+        include_str!("../../../../../migrations/045_email_bridge.sql"),
+    ),
+    (
+        "046_export_jobs",
+        include_str!("../../../../../migrations/046_export_jobs.sql"),
+    ),
+    (
+        "047_inbound_emails",
+        include_str!("../../../../../migrations/047_inbound_emails.sql"),
+    ),
+    (
+        "048_parked_messages",
+        include_str!("../../../../../migrations/048_parked_messages.sql"),
+    ),
+];
+
+/// Migrations of the Postgres backend, in order; one per SQLite migration,
+/// under the same name, so the data format version means the same on both.
+pub(crate) const PG_MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "001_initial_schema",
+        include_str!("../../../../../migrations/postgres/001_initial_schema.sql"),
+    ),
+    (
+        "002_agent_capabilities",
+        include_str!("../../../../../migrations/postgres/002_agent_capabilities.sql"),
+    ),
+    (
+        "003_tool_metrics",
+        include_str!("../../../../../migrations/postgres/003_tool_metrics.sql"),
+    ),
+    (
+        "004_attachments",
+        include_str!("../../../../../migrations/postgres/004_attachments.sql"),
+    ),
+    (
+        "005_attachments_agent",
+        include_str!("../../../../../migrations/postgres/005_attachments_agent.sql"),
+    ),
+    (
+        "006_query_indexes",
+        include_str!("../../../../../migrations/postgres/006_query_indexes.sql"),
+    ),
+    (
+        "007_message_routing",
+        include_str!("../../../../../migrations/postgres/007_message_routing.sql"),
+    ),
+    (
+        "008_reservation_watchers",
+        include_str!("../../../../../migrations/postgres/008_reservation_watchers.sql"),
+    ),
+    (
+        "009_focus_windows",
+        include_str!("../../../../../migrations/postgres/009_focus_windows.sql"),
+    ),
+    (
+        "010_attachment_search",
+        include_str!("../../../../../migrations/postgres/010_attachment_search.sql"),
+    ),
+    (
+        "011_message_body_format",
+        include_str!("../../../../../migrations/postgres/011_message_body_format.sql"),
+    ),
+    (
+        "012_web_push",
+        include_str!("../../../../../migrations/postgres/012_web_push.sql"),
+    ),
+    (
+        "013_ui_preferences",
+        include_str!("../../../../../migrations/postgres/013_ui_preferences.sql"),
+    ),
+    (
+        "014_event_log",
+        include_str!("../../../../../migrations/postgres/014_event_log.sql"),
+    ),
+    (
+        "015_thread_reads",
+        include_str!("../../../../../migrations/postgres/015_thread_reads.sql"),
+    ),
+    (
+        "016_reservation_sets",
+        include_str!("../../../../../migrations/postgres/016_reservation_sets.sql"),
+    ),
+    (
+        "017_project_contact_policies",
+        include_str!("../../../../../migrations/postgres/017_project_contact_policies.sql"),
+    ),
+    (
+        "018_agent_slas",
+        include_str!("../../../../../migrations/postgres/018_agent_slas.sql"),
+    ),
+    (
+        "019_thread_workflows",
+        include_str!("../../../../../migrations/postgres/019_thread_workflows.sql"),
+    ),
+    (
+        "020_message_custom_fields",
+        include_str!("../../../../../migrations/postgres/020_message_custom_fields.sql"),
+    ),
+    (
+        "021_external_tools",
+        include_str!("../../../../../migrations/postgres/021_external_tools.sql"),
+    ),
+    (
+        "022_github_sync",
+        include_str!("../../../../../migrations/postgres/022_github_sync.sql"),
+    ),
+    (
+        "023_ticket_links",
+        include_str!("../../../../../migrations/postgres/023_ticket_links.sql"),
+    ),
+    (
+        "024_ci_status",
+        include_str!("../../../../../migrations/postgres/024_ci_status.sql"),
+    ),
+    (
+        "025_project_settings",
+        include_str!("../../../../../migrations/postgres/025_project_settings.sql"),
+    ),
+    (
+        "026_message_catalog",
+        include_str!("../../../../../migrations/postgres/026_message_catalog.sql"),
+    ),
+    (
+        "027_message_bodies",
+        include_str!("../../../../../migrations/postgres/027_message_bodies.sql"),
+    ),
+    (
+        "028_thread_archival",
+        include_str!("../../../../../migrations/postgres/028_thread_archival.sql"),
+    ),
+    (
+        "029_thread_watchers",
+        include_str!("../../../../../migrations/postgres/029_thread_watchers.sql"),
+    ),
+    (
+        "030_delegations",
+        include_str!("../../../../../migrations/postgres/030_delegations.sql"),
+    ),
+    (
+        "031_message_approvals",
+        include_str!("../../../../../migrations/postgres/031_message_approvals.sql"),
+    ),
+    (
+        "032_reply_deadlines",
+        include_str!("../../../../../migrations/postgres/032_reply_deadlines.sql"),
+    ),
+    (
+        "033_message_search",
+        include_str!("../../../../../migrations/postgres/033_message_search.sql"),
+    ),
+    (
+        "034_global_threads",
+        include_str!("../../../../../migrations/postgres/034_global_threads.sql"),
+    ),
+    (
+        "035_saved_searches",
+        include_str!("../../../../../migrations/postgres/035_saved_searches.sql"),
+    ),
+    (
+        "036_labels",
+        include_str!("../../../../../migrations/postgres/036_labels.sql"),
+    ),
+    (
+        "037_public_ids",
+        include_str!("../../../../../migrations/postgres/037_public_ids.sql"),
+    ),
+    (
+        "038_api_tokens",
+        include_str!("../../../../../migrations/postgres/038_api_tokens.sql"),
+    ),
+    (
+        "039_message_replies",
+        include_str!("../../../../../migrations/postgres/039_message_replies.sql"),
+    ),
+    (
+        "040_operation_intents",
+        include_str!("../../../../../migrations/postgres/040_operation_intents.sql"),
+    ),
+    (
+        "041_attachment_blobs",
+        include_str!("../../../../../migrations/postgres/041_attachment_blobs.sql"),
+    ),
+    (
+        "042_retention_policies",
+        include_str!("../../../../../migrations/postgres/042_retention_policies.sql"),
+    ),
+    (
+        "043_dead_letters",
+        include_str!("../../../../../migrations/postgres/043_dead_letters.sql"),
+    ),
+    (
+        "044_webhooks",
+        include_str!("../../../../../migrations/postgres/044_webhooks.sql"),
+    ),
+    (
+        "045_email_bridge",
+        include_str!("../../../../../migrations/postgres/045_email_bridge.sql"),
+    ),
+    (
+        "046_export_jobs",
+        include_str!("../../../../../migrations/postgres/046_export_jobs.sql"),
+    ),
+    (
+        "047_inbound_emails",
+        include_str!("../../../../../migrations/postgres/047_inbound_emails.sql"),
+    ),
+    (
+        "048_parked_messages",
+        include_str!("../../../../../migrations/postgres/048_parked_messages.sql"),
+    ),
+];
+
+/// Data format version written by this build.
+pub const DATA_FORMAT_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// What an upgrade of the data directory would do.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradePlan {
+    pub db_path: PathBuf,
+    /// Format version stamped in the database (0 if unstamped)
+    pub current_version: i64,
+    pub target_version: i64,
+    /// The database has no tables yet
+    pub fresh: bool,
+    /// Migrations that would run
+    pub pending_migrations: Vec<String>,
+    /// Database the plan is for; on Postgres `db_path` is its URL, without
+    /// the password
+    pub backend: Backend,
+}
+
+impl UpgradePlan {
+    /// The database must be migrated before use.
+    pub fn needs_upgrade(&self) -> bool {
+        self.current_version < self.target_version
+    }
+
+    /// The database was written by a newer build.
+    pub fn is_newer(&self) -> bool {
+        self.current_version > self.target_version
+    }
+
+    /// Human-readable summary, including instructions when action is needed.
+    pub fn describe(&self) -> String {
+        let db = self.db_path.display();
+        let postgres = self.backend == Backend::Postgres;
+        if self.is_newer() {
+            let go_back = if postgres {
+                "restore a backup"
+            } else {
+                "restore a pre-upgrade snapshot (mouchak_mail.db.pre-upgrade-*)"
+            };
+            format!(
+                "{} uses data format {}, but this build only supports up to {}. \
+                 Install a newer mouchak-mail, or {} to go back.",
+                db, self.current_version, self.target_version, go_back
+            )
+        } else if self.fresh {
+            format!(
+                "{} is new; it will be created at data format {}.",
+                db, self.target_version
+            )
+        } else if self.needs_upgrade() {
+            format!(
+                "{} uses data format {} and will be upgraded to {} ({} migration(s): {}). {}",
+                db,
+                self.current_version,
+                self.target_version,
+                self.pending_migrations.len(),
+                self.pending_migrations.join(", "),
+                if postgres {
+                    "The migrations run in one transaction."
+                } else {
+                    "A snapshot of the database is taken first."
+                }
+            )
+        } else {
+            format!(
+                "{} is up to date (data format {}).",
+                db, self.current_version
+            )
+        }
+    }
+}
+
+/// Whether startup may upgrade older data (`MOUCHAK_AUTO_UPGRADE`, default on).
+pub fn auto_upgrade_enabled() -> bool {
+    std::env::var("MOUCHAK_AUTO_UPGRADE")
+        .map(|v| !matches!(v.to_lowercase().as_str(), "0" | "false" | "no" | "off"))
+        .unwrap_or(true)
+}
+
+/// Reads the data format stamp of an open database.
+pub async fn read_format_version(conn: &Connection) -> Result<i64> {
+    let mut rows = conn.query("PRAGMA user_version", ()).await?;
+    match rows.next().await? {
+        Some(row) => Ok(row.get::<i64>(0)?),
+        None => Ok(0),
+    }
+}
+
+/// Works out what opening `conn` with this build would change.
+pub async fn plan(conn: &Connection, db_path: &Path) -> Result<UpgradePlan> {
+    let current_version = read_format_version(conn).await?;
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            (),
+        )
+        .await?;
+    let fresh = match rows.next().await? {
+        Some(row) => row.get::<i64>(0)? == 0,
+        None => true,
+    };
+
+    let applied = usize::try_from(current_version).unwrap_or(0);
+    let pending_migrations = MIGRATIONS
+        .iter()
+        .skip(applied)
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    Ok(UpgradePlan {
+        db_path: db_path.to_path_buf(),
+        current_version,
+        target_version: DATA_FORMAT_VERSION,
+        fresh,
+        pending_migrations,
+        backend: Backend::Sqlite,
+    })
+}
+
+/// Copies the database next to itself before an upgrade.
+///
+/// Uses `VACUUM INTO`, which is consistent even while other connections
+/// write in WAL mode.
+///
+/// # Returns
+/// Path of the snapshot, e.g. `mouchak_mail.db.pre-upgrade-v3-20260101T120000`.
+pub async fn snapshot(conn: &Connection, plan: &UpgradePlan) -> Result<PathBuf> {
+    let file_name = plan
+        .db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "mouchak_mail.db".to_string());
+    let snapshot_path = plan.db_path.with_file_name(format!(
+        "{}.pre-upgrade-v{}-{}",
+        file_name,
+        plan.current_version,
+        chrono::Utc::now().format("%Y%m%dT%H%M%S")
+    ));
+
+    let target = snapshot_path.to_string_lossy().replace('\'', "''");
+    conn.execute(&format!("VACUUM INTO '{}'", target), ())
+        .await?;
+    Ok(snapshot_path)
+}
+
+/// Applies all migrations and stamps the current format version.
+///
+/// Migrations are idempotent, so re-applying already-applied ones is safe.
+pub async fn migrate(conn: &Connection) -> Result<()> {
+    for (_, migration) in MIGRATIONS {
+        conn.execute_batch(migration).await?;
+    }
+    conn.execute(
+        &format!("PRAGMA user_version = {}", DATA_FORMAT_VERSION),
+        (),
+    )
+    .await?;
+    Ok(())
+}
@@ -17,7 +17,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Project identifier (database primary key).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct ProjectId(pub i64);
 
@@ -54,7 +54,7 @@ impl fmt::Display for ProjectId {
 }
 
 /// Agent identifier (database primary key).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct AgentId(pub i64);
 
@@ -91,7 +91,7 @@ impl fmt::Display for AgentId {
 }
 
 /// Message identifier (database primary key).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct MessageId(pub i64);
 
@@ -127,6 +127,80 @@ impl fmt::Display for MessageId {
     }
 }
 
+/// Export job identifier (database primary key).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ExportJobId(pub i64);
+
+impl ExportJobId {
+    /// Create a new ExportJobId.
+    #[inline]
+    pub const fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    /// Get the raw i64 value.
+    #[inline]
+    pub const fn get(self) -> i64 {
+        self.0
+    }
+}
+
+impl From<i64> for ExportJobId {
+    fn from(id: i64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<ExportJobId> for i64 {
+    fn from(id: ExportJobId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for ExportJobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Approval rule identifier (database primary key).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ApprovalRuleId(pub i64);
+
+impl ApprovalRuleId {
+    /// Create a new ApprovalRuleId.
+    #[inline]
+    pub const fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    /// Get the raw i64 value.
+    #[inline]
+    pub const fn get(self) -> i64 {
+        self.0
+    }
+}
+
+impl From<i64> for ApprovalRuleId {
+    fn from(id: i64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<ApprovalRuleId> for i64 {
+    fn from(id: ApprovalRuleId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for ApprovalRuleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Project slug (URL-safe identifier).
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -254,7 +328,7 @@ impl fmt::Display for ThreadId {
 // Kani Formal Verification Proofs
 // ============================================================================
 //
-// Run with: cargo kani --package lib-core
+// Run with: cargo kani --package mouchak-mail-core
 // These proofs mathematically verify type invariants and conversion safety.
 
 #[cfg(kani)]
@@ -1,11 +1,13 @@
 //! Utility functions and helpers.
 //!
-//! This module provides common utility functions used throughout lib-core.
+//! This module provides common utility functions used throughout mouchak-mail-core.
 //!
 //! # Functions
 //!
 //! - `slugify` - Convert text to URL-safe slugs
 //! - `parse_timestamp` - Parse timestamp with warning on failure
+//! - `normalize_subject` - Canonicalize subjects for thread matching
+//! - `reply_subject` - Build a single-prefixed "Re:" subject
 
 use chrono::NaiveDateTime;
 use slug;
@@ -89,10 +91,108 @@ pub fn slugify(text: &str) -> String {
     slug::slugify(text)
 }
 
+/// Reply/forward prefixes stripped by [`normalize_subject`] (lowercase).
+const SUBJECT_PREFIXES: &[&str] = &["re", "fwd", "fw"];
+
+/// Strips a single leading reply/forward prefix such as `Re:`, `FWD:`, or
+/// `Re[2]:`, returning the remainder if one was found.
+fn strip_subject_prefix(subject: &str) -> Option<&str> {
+    let colon = subject.find(':')?;
+    let head = subject[..colon].trim_end();
+    // Allow counters like "Re[2]" or "Re(3)"
+    let word = head
+        .split(['[', '('])
+        .next()
+        .unwrap_or(head)
+        .to_ascii_lowercase();
+    if SUBJECT_PREFIXES.contains(&word.as_str()) && head.len() <= word.len() + 4 {
+        Some(subject[colon + 1..].trim_start())
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if the subject starts with a reply or forward prefix.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::has_reply_prefix;
+///
+/// assert!(has_reply_prefix("Re: Build failing"));
+/// assert!(has_reply_prefix("fwd: Build failing"));
+/// assert!(!has_reply_prefix("Build failing: again"));
+/// ```
+pub fn has_reply_prefix(subject: &str) -> bool {
+    strip_subject_prefix(subject.trim_start()).is_some()
+}
+
+/// Canonicalizes a subject for thread matching.
+///
+/// Repeatedly strips leading `Re:`/`Fwd:`/`Fw:` prefixes (case-insensitive,
+/// including counters like `Re[2]:`) and collapses runs of whitespace, so
+/// replies from clients that rewrite subjects still land in one thread.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::normalize_subject;
+///
+/// assert_eq!(normalize_subject("Re: RE:  Fwd: Deploy   plan "), "Deploy plan");
+/// assert_eq!(normalize_subject("Re[2]: Deploy plan"), "Deploy plan");
+/// assert_eq!(normalize_subject("Deploy plan"), "Deploy plan");
+/// ```
+pub fn normalize_subject(subject: &str) -> String {
+    let mut rest = subject.trim();
+    while let Some(stripped) = strip_subject_prefix(rest) {
+        rest = stripped;
+    }
+    rest.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Builds a reply subject with exactly one `Re: ` prefix.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::reply_subject;
+///
+/// assert_eq!(reply_subject("Deploy plan"), "Re: Deploy plan");
+/// assert_eq!(reply_subject("Re: re: Deploy plan"), "Re: Deploy plan");
+/// ```
+pub fn reply_subject(subject: &str) -> String {
+    format!("Re: {}", normalize_subject(subject))
+}
+
+/// Builds a forward subject with exactly one `Fwd: ` prefix.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::forward_subject;
+///
+/// assert_eq!(forward_subject("Re: Deploy plan"), "Fwd: Deploy plan");
+/// ```
+pub fn forward_subject(subject: &str) -> String {
+    format!("Fwd: {}", normalize_subject(subject))
+}
+
+pub mod body_format;
+pub mod clock;
+pub mod diagram;
+pub mod event_bridge;
+pub mod event_bus;
+pub mod identicon;
 pub mod image_processing;
 pub mod mistake_detection;
+pub mod notifier;
 pub mod pathspec;
+pub mod plugins;
 pub mod project_identity;
+pub mod rfc5322;
+pub mod search_highlight;
+pub mod tool_call_log;
+pub mod ulid;
 pub mod validation;
 
 pub use project_identity::compute_project_slug;
@@ -0,0 +1,338 @@
+//! Message body formats and server-side rendering.
+//!
+//! Every message stores its body in one of the [`BodyFormat`]s (Markdown by
+//! default). Consumers pick a [`RenderFormat`] and [`render_body`] converts
+//! the stored body, so clients never have to guess how to display it.
+//!
+//! HTML output is safe to inject: raw HTML in Markdown is escaped rather than
+//! passed through, and the rendered document is passed through an [`ammonia`]
+//! allowlist that also drops URLs with a scheme other than `http`, `https` or
+//! `mailto`. Fenced code blocks with a known language are syntax highlighted
+//! with CSS classes prefixed [`HIGHLIGHT_CLASS_PREFIX`]; see [`highlight_css`].
+
+use lazy_static::lazy_static;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd, html};
+use serde::Serialize;
+use std::collections::HashSet;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{ClassStyle, ClassedHTMLGenerator, css_for_theme_with_class_style};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Prefix of the CSS classes emitted for highlighted code.
+pub const HIGHLIGHT_CLASS_PREFIX: &str = "hl-";
+
+const HIGHLIGHT_CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: HIGHLIGHT_CLASS_PREFIX,
+};
+
+/// Theme used by [`highlight_css`].
+const HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+lazy_static! {
+    static ref SYNTAXES: SyntaxSet = SyntaxSet::load_defaults_newlines();
+}
+
+/// Format a message body is stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyFormat {
+    /// CommonMark with tables, strikethrough and task lists
+    #[default]
+    Markdown,
+    /// Preformatted plain text
+    Plain,
+    /// A JSON document
+    Json,
+}
+
+impl BodyFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Plain => "plain",
+            Self::Json => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for BodyFormat {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "md" | "markdown" => Ok(Self::Markdown),
+            "plain" | "text" | "txt" => Ok(Self::Plain),
+            "json" => Ok(Self::Json),
+            other => Err(crate::Error::InvalidInput(format!(
+                "Unknown body format '{}' (expected markdown, plain or json)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Format a consumer wants a message body rendered as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    /// The body exactly as stored
+    #[default]
+    Raw,
+    /// Plain text with Markdown syntax removed
+    Plain,
+    /// Sanitized HTML
+    Html,
+}
+
+impl std::str::FromStr for RenderFormat {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "raw" | "md" | "markdown" => Ok(Self::Raw),
+            "plain" | "text" | "txt" => Ok(Self::Plain),
+            "html" => Ok(Self::Html),
+            other => Err(crate::Error::InvalidInput(format!(
+                "Unknown render format '{}' (expected raw, plain or html)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Renders a body stored as `from` in the requested format.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::body_format::{BodyFormat, RenderFormat, render_body};
+///
+/// let body = "# Status\n\nBuild is **green**.";
+/// assert_eq!(
+///     render_body(body, BodyFormat::Markdown, RenderFormat::Plain),
+///     "Status\n\nBuild is green."
+/// );
+/// assert_eq!(
+///     render_body("<b>hi</b>", BodyFormat::Plain, RenderFormat::Html),
+///     "<pre>&lt;b&gt;hi&lt;/b&gt;</pre>\n"
+/// );
+/// ```
+pub fn render_body(body: &str, from: BodyFormat, to: RenderFormat) -> String {
+    match (from, to) {
+        (_, RenderFormat::Raw) | (BodyFormat::Plain, RenderFormat::Plain) => body.to_string(),
+        (BodyFormat::Markdown, RenderFormat::Plain) => markdown_to_plain(body),
+        (BodyFormat::Markdown, RenderFormat::Html) => markdown_to_html(body),
+        (BodyFormat::Plain, RenderFormat::Html) => format!("<pre>{}</pre>\n", html_escape(body)),
+        (BodyFormat::Json, RenderFormat::Plain) => pretty_json(body),
+        (BodyFormat::Json, RenderFormat::Html) => highlight_code("json", &pretty_json(body)),
+    }
+}
+
+/// Strips Markdown syntax, keeping text, code and paragraph breaks.
+pub fn markdown_to_plain(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Text(text) | Event::Code(text) => out.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => out.push('\n'),
+            Event::Start(Tag::Item) => out.push_str("- "),
+            Event::TaskListMarker(done) => out.push_str(if done { "[x] " } else { "[ ] " }),
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock) => {
+                out.push_str("\n\n")
+            }
+            Event::End(TagEnd::Item | TagEnd::TableRow | TagEnd::TableHead) => out.push('\n'),
+            Event::End(TagEnd::TableCell) => out.push('\t'),
+            Event::Rule => out.push_str("---\n\n"),
+            _ => {}
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// Renders Markdown to sanitized HTML.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::body_format::markdown_to_html;
+///
+/// let html = markdown_to_html("Run <b>this</b>:\n\n```rust\nfn main() {}\n```");
+/// assert!(html.contains("&lt;b&gt;this&lt;/b&gt;"));
+/// assert!(html.contains("<code class=\"language-rust\">"));
+/// assert!(html.contains("hl-source hl-rust"));
+/// ```
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut events = Vec::new();
+    let mut fence: Option<(String, String)> = None;
+
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        if let Some((lang, code)) = fence.as_mut() {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => {
+                    events.push(Event::Html(highlight_code(lang, code).into()));
+                    fence = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                fence = Some((lang, String::new()));
+            }
+            // Raw HTML is shown as text, never interpreted
+            Event::Html(raw) | Event::InlineHtml(raw) => events.push(Event::Text(raw)),
+            other => events.push(other),
+        }
+    }
+
+    let mut out = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut out, events.into_iter());
+    sanitize_html(&out)
+}
+
+/// Cleans HTML against the allowlist used for rendered message bodies.
+pub fn sanitize_html(html: &str) -> String {
+    ammonia::Builder::default()
+        .url_schemes(HashSet::from(["http", "https", "mailto"]))
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("code", ["class"])
+        .add_tag_attributes("span", ["class"])
+        .clean(html)
+        .to_string()
+}
+
+/// Highlights a code block, falling back to escaped text for unknown languages.
+pub fn highlight_code(lang: &str, code: &str) -> String {
+    let lang = if lang
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+' | '#' | '.'))
+    {
+        lang
+    } else {
+        ""
+    };
+
+    let highlighted = (!lang.is_empty())
+        .then(|| SYNTAXES.find_syntax_by_token(lang))
+        .flatten()
+        .and_then(|syntax| {
+            let mut generator = ClassedHTMLGenerator::new_with_class_style(
+                syntax,
+                &SYNTAXES,
+                HIGHLIGHT_CLASS_STYLE,
+            );
+            for line in LinesWithEndings::from(code) {
+                generator
+                    .parse_html_for_line_which_includes_newline(line)
+                    .ok()?;
+            }
+            Some(generator.finalize())
+        });
+    let inner = highlighted.unwrap_or_else(|| html_escape(code));
+
+    if lang.is_empty() {
+        format!("<pre><code>{}</code></pre>\n", inner)
+    } else {
+        format!(
+            "<pre><code class=\"language-{}\">{}</code></pre>\n",
+            lang, inner
+        )
+    }
+}
+
+/// Stylesheet for highlighted code, for pages that embed rendered bodies.
+pub fn highlight_css() -> String {
+    let themes = ThemeSet::load_defaults();
+    themes
+        .themes
+        .get(HIGHLIGHT_THEME)
+        .and_then(|theme| css_for_theme_with_class_style(theme, HIGHLIGHT_CLASS_STYLE).ok())
+        .unwrap_or_default()
+}
+
+/// Escapes text for inclusion in HTML.
+pub fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn markdown_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+fn pretty_json(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .and_then(|v| serde_json::to_string_pretty(&v))
+        .unwrap_or_else(|_| body.to_string())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_to_html_escapes_raw_html() {
+        let html = markdown_to_html("Hello <script>alert(1)</script> **there**");
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("<strong>there</strong>"));
+    }
+
+    #[test]
+    fn test_markdown_to_html_neutralizes_unsafe_links() {
+        let html =
+            markdown_to_html("[a](javascript:alert(1)) [b](https://example.com) [c](docs/x.md)");
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("href=\"https://example.com\""));
+        assert!(html.contains("href=\"docs/x.md\""));
+    }
+
+    #[test]
+    fn test_markdown_to_html_keeps_task_list_checkboxes() {
+        let html = markdown_to_html("- [x] merged\n- [ ] released");
+        assert!(html.contains("<input disabled=\"\" type=\"checkbox\" checked=\"\">"));
+    }
+
+    #[test]
+    fn test_highlight_code_escapes_unknown_languages() {
+        let html = highlight_code("x\"><script>", "<x>");
+        assert_eq!(html, "<pre><code>&lt;x&gt;</code></pre>\n");
+    }
+
+    #[test]
+    fn test_markdown_to_plain_keeps_lists_and_code() {
+        let plain = markdown_to_plain("Steps:\n\n- run `cargo test`\n- [x] merge\n");
+        assert_eq!(plain, "Steps:\n\n- run cargo test\n- [x] merge");
+    }
+
+    #[test]
+    fn test_json_body_is_pretty_printed() {
+        let plain = render_body("{\"ok\":true}", BodyFormat::Json, RenderFormat::Plain);
+        assert_eq!(plain, "{\n  \"ok\": true\n}");
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        assert_eq!("TEXT".parse::<BodyFormat>().unwrap(), BodyFormat::Plain);
+        assert_eq!(
+            "markdown".parse::<RenderFormat>().unwrap(),
+            RenderFormat::Raw
+        );
+        assert!("yaml".parse::<BodyFormat>().is_err());
+    }
+}
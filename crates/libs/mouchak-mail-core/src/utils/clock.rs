@@ -0,0 +1,138 @@
+//! Server-authoritative time for TTL-based features.
+//!
+//! Reservations, build slots and reply deadlines expire by the server's
+//! clock, never the caller's: TTLs are given as durations and resolved with
+//! [`expires_in`], and responses carry [`server_time`] so agents can compare
+//! expiry timestamps against the clock that enforces them.
+//!
+//! Absolute timestamps a client does send go through [`future_client_time`].
+//! One slightly in the past is most likely a client clock running behind, and
+//! is moved forward with a warning; one further in the past is rejected with
+//! the server time in the message.
+
+use crate::{Error, Result};
+use chrono::{Duration, NaiveDateTime};
+
+/// How far behind the server a client clock may run before its timestamps
+/// are rejected rather than adjusted.
+pub const SKEW_TOLERANCE_SECONDS: i64 = 300;
+
+/// Format of timestamps in tool responses, as for `expires_ts`.
+pub const RESPONSE_TS_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Current server time, UTC.
+pub fn server_now() -> NaiveDateTime {
+    chrono::Utc::now().naive_utc()
+}
+
+/// Current server time formatted like the expiry timestamps of responses.
+pub fn server_time() -> String {
+    server_now().format(RESPONSE_TS_FORMAT).to_string()
+}
+
+/// Expiry `ttl_seconds` from now by the server clock.
+///
+/// # Errors
+/// Returns `InvalidInput` if `ttl_seconds` is not positive or too large.
+pub fn expires_in(ttl_seconds: i64) -> Result<NaiveDateTime> {
+    if ttl_seconds <= 0 {
+        return Err(Error::InvalidInput(format!(
+            "TTL must be positive, got {} seconds",
+            ttl_seconds
+        )));
+    }
+    Duration::try_seconds(ttl_seconds)
+        .and_then(|d| server_now().checked_add_signed(d))
+        .ok_or_else(|| Error::InvalidInput(format!("TTL of {} seconds is too long", ttl_seconds)))
+}
+
+/// A client timestamp after skew checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientTime {
+    /// Timestamp to use
+    pub value: NaiveDateTime,
+    /// Set when `value` was adjusted; explains why
+    pub warning: Option<String>,
+}
+
+/// Checks a client timestamp `ts` for `field` that must lie after `now`.
+///
+/// Within [`SKEW_TOLERANCE_SECONDS`] in the past, `ts` is moved to the end
+/// of the tolerance window with a warning.
+///
+/// # Errors
+/// Returns `InvalidInput`, naming the server time, if `ts` is further in the
+/// past.
+pub fn future_client_time(
+    field: &str,
+    ts: NaiveDateTime,
+    now: NaiveDateTime,
+) -> Result<ClientTime> {
+    if ts > now {
+        return Ok(ClientTime {
+            value: ts,
+            warning: None,
+        });
+    }
+    let behind = (now - ts).num_seconds();
+    if behind > SKEW_TOLERANCE_SECONDS {
+        return Err(Error::InvalidInput(format!(
+            "{} {} is {}s in the past; server time is {} UTC. Check the client clock, or give a duration such as '4h'",
+            field,
+            ts.format(RESPONSE_TS_FORMAT),
+            behind,
+            now.format(RESPONSE_TS_FORMAT)
+        )));
+    }
+    let value = now + Duration::seconds(SKEW_TOLERANCE_SECONDS);
+    Ok(ClientTime {
+        value,
+        warning: Some(format!(
+            "{} {} was {}s in the past by the server clock ({} UTC), likely clock skew; using {} instead",
+            field,
+            ts.format(RESPONSE_TS_FORMAT),
+            behind,
+            now.format(RESPONSE_TS_FORMAT),
+            value.format(RESPONSE_TS_FORMAT)
+        )),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::utils::TS_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_future_client_time() {
+        let now = ts("2025-03-01 12:00:00");
+
+        let ahead = future_client_time("deadline", ts("2025-03-01 13:00:00"), now).unwrap();
+        assert_eq!(ahead.value, ts("2025-03-01 13:00:00"));
+        assert!(ahead.warning.is_none());
+
+        let skewed = future_client_time("deadline", ts("2025-03-01 11:58:00"), now).unwrap();
+        assert_eq!(skewed.value, ts("2025-03-01 12:05:00"));
+        assert!(skewed.warning.unwrap().contains("120s in the past"));
+
+        let err = future_client_time("deadline", ts("2025-03-01 11:00:00"), now).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("server time is 2025-03-01T12:00:00")
+        );
+    }
+
+    #[test]
+    fn test_expires_in() {
+        let before = server_now();
+        let expires = expires_in(60).unwrap();
+        assert!(expires >= before + Duration::seconds(60));
+        assert!(expires_in(0).is_err());
+        assert!(expires_in(-30).is_err());
+        assert!(expires_in(i64::MAX).is_err());
+    }
+}
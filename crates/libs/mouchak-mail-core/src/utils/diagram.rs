@@ -0,0 +1,289 @@
+//! Diagram rendering for exported message bodies.
+//!
+//! Mermaid and PlantUML code fences in rendered HTML are replaced with SVG
+//! produced by the corresponding command-line tool, so architecture
+//! discussions stay readable in archives. The SVG is embedded as a
+//! `data:` image rather than inline markup: browsers never run scripts in
+//! images, so untrusted diagram output cannot inject into the page.
+//!
+//! Renderers are external programs: `mmdc` (mermaid-cli) and `plantuml`,
+//! overridable with the `MERMAID_CLI` and `PLANTUML_CLI` environment
+//! variables. When a renderer is missing, fails or exceeds
+//! [`RENDER_TIMEOUT`], the fence is left as a code block.
+
+use crate::utils::body_format::markdown_to_html;
+use base64::Engine;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Longest time a single diagram may take to render.
+pub const RENDER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Opening markup of a fenced code block in rendered HTML.
+const CODE_BLOCK_OPEN: &str = "<pre><code class=\"language-";
+
+const CODE_BLOCK_CLOSE: &str = "</code></pre>";
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Diagram languages recognized in code fences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagramKind {
+    Mermaid,
+    PlantUml,
+}
+
+impl DiagramKind {
+    /// Maps a code fence language to a diagram kind.
+    pub fn from_lang(lang: &str) -> Option<Self> {
+        match lang.to_lowercase().as_str() {
+            "mermaid" => Some(Self::Mermaid),
+            "plantuml" | "puml" => Some(Self::PlantUml),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mermaid => "mermaid",
+            Self::PlantUml => "plantuml",
+        }
+    }
+
+    fn program(&self) -> String {
+        let (var, default) = match self {
+            Self::Mermaid => ("MERMAID_CLI", "mmdc"),
+            Self::PlantUml => ("PLANTUML_CLI", "plantuml"),
+        };
+        std::env::var(var)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| default.to_string())
+    }
+}
+
+/// Renders Markdown to sanitized HTML with diagrams rendered to SVG.
+pub async fn markdown_to_html_with_diagrams(markdown: &str) -> String {
+    render_diagrams(&markdown_to_html(markdown)).await
+}
+
+/// Replaces diagram code blocks in sanitized HTML with rendered SVG.
+///
+/// Each distinct diagram is rendered once.
+pub async fn render_diagrams(html: &str) -> String {
+    let mut sources = Vec::new();
+    render_diagrams_with(html, |kind, source| {
+        sources.push((kind, source.to_string()));
+        None
+    });
+    if sources.is_empty() {
+        return html.to_string();
+    }
+
+    let mut rendered = HashMap::new();
+    for (kind, source) in sources {
+        if !rendered.contains_key(&(kind, source.clone())) {
+            let svg = render_diagram(kind, &source).await;
+            rendered.insert((kind, source), svg);
+        }
+    }
+    render_diagrams_with(html, |kind, source| {
+        rendered.get(&(kind, source.to_string())).cloned().flatten()
+    })
+}
+
+/// Like [`render_diagrams`], rendering each diagram with `render`.
+///
+/// Blocks for which `render` returns `None` are kept unchanged.
+pub fn render_diagrams_with<F>(html: &str, mut render: F) -> String
+where
+    F: FnMut(DiagramKind, &str) -> Option<String>,
+{
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(CODE_BLOCK_OPEN) {
+        out.push_str(&rest[..start]);
+        let after_open = &rest[start + CODE_BLOCK_OPEN.len()..];
+
+        let replaced = after_open.split_once("\">").and_then(|(lang, block)| {
+            let kind = DiagramKind::from_lang(lang)?;
+            let (escaped, tail) = block.split_once(CODE_BLOCK_CLOSE)?;
+            // Only unhighlighted blocks hold plain escaped source.
+            if escaped.contains('<') {
+                return None;
+            }
+            let svg = render(kind, &html_unescape(escaped))?;
+            Some((diagram_figure(kind, &svg, escaped), tail))
+        });
+
+        match replaced {
+            Some((figure, tail)) => {
+                out.push_str(&figure);
+                rest = tail;
+            }
+            None => {
+                out.push_str(CODE_BLOCK_OPEN);
+                rest = after_open;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Renders one diagram to SVG with its external renderer.
+///
+/// # Returns
+/// The SVG document, or `None` if the renderer is unavailable or fails.
+pub async fn render_diagram(kind: DiagramKind, source: &str) -> Option<String> {
+    let program = kind.program();
+    let output = match kind {
+        DiagramKind::PlantUml => {
+            let mut cmd = Command::new(&program);
+            cmd.args(["-tsvg", "-pipe"]);
+            run_with_timeout(cmd, Some(source)).await
+        }
+        DiagramKind::Mermaid => render_mermaid(&program, source).await,
+    };
+
+    let svg = output.and_then(|bytes| String::from_utf8(bytes).ok())?;
+    let trimmed = svg.trim_start();
+    if trimmed.starts_with("<svg") || trimmed.starts_with("<?xml") {
+        Some(svg)
+    } else {
+        tracing::debug!(kind = kind.as_str(), "Diagram renderer returned no SVG");
+        None
+    }
+}
+
+/// mermaid-cli only reads and writes files, so render through temp files.
+async fn render_mermaid(program: &str, source: &str) -> Option<Vec<u8>> {
+    let base = temp_path();
+    let input = base.with_extension("mmd");
+    let output = base.with_extension("svg");
+
+    let result = match tokio::fs::write(&input, source).await {
+        Ok(()) => {
+            let mut cmd = Command::new(program);
+            cmd.arg("-q").arg("-i").arg(&input).arg("-o").arg(&output);
+            match run_with_timeout(cmd, None).await {
+                Some(_) => tokio::fs::read(&output).await.ok(),
+                None => None,
+            }
+        }
+        Err(_) => None,
+    };
+
+    let _ = tokio::fs::remove_file(&input).await;
+    let _ = tokio::fs::remove_file(&output).await;
+    result
+}
+
+fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "mouchak-diagram-{}-{}",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Runs `cmd`, feeding it `input`, and returns its stdout on success.
+///
+/// The process is killed if it runs longer than [`RENDER_TIMEOUT`].
+async fn run_with_timeout(mut cmd: Command, input: Option<&str>) -> Option<Vec<u8>> {
+    let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+    let mut child = cmd
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| tracing::debug!(program = %program, "Diagram renderer unavailable: {}", e))
+        .ok()?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        let input = input.to_string();
+        // Written alongside reading stdout, so a full pipe cannot stall the renderer
+        tokio::spawn(async move {
+            let _ = stdin.write_all(input.as_bytes()).await;
+        });
+    }
+
+    // Dropping the child on timeout kills it
+    match tokio::time::timeout(RENDER_TIMEOUT, child.wait_with_output()).await {
+        Ok(Ok(output)) => output.status.success().then_some(output.stdout),
+        Ok(Err(e)) => {
+            tracing::debug!(program = %program, "Diagram renderer failed: {}", e);
+            None
+        }
+        Err(_) => {
+            tracing::warn!(program = %program, "Diagram renderer timed out");
+            None
+        }
+    }
+}
+
+fn diagram_figure(kind: DiagramKind, svg: &str, escaped_source: &str) -> String {
+    let data = base64::engine::general_purpose::STANDARD.encode(svg);
+    format!(
+        "<figure class=\"diagram diagram-{kind}\"><img alt=\"{kind} diagram\" \
+         src=\"data:image/svg+xml;base64,{data}\">\
+         <details><summary>Source</summary>\
+         <pre><code class=\"language-{kind}\">{escaped_source}</code></pre></details>\
+         </figure>\n",
+        kind = kind.as_str(),
+    )
+}
+
+fn html_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagram_fences_are_replaced_with_images() {
+        let html =
+            markdown_to_html("See:\n\n```mermaid\ngraph TD; A-->B\n```\n\n```rust\nfn x() {}\n```");
+        let mut seen = Vec::new();
+        let out = render_diagrams_with(&html, |kind, source| {
+            seen.push((kind, source.to_string()));
+            Some("<svg></svg>".to_string())
+        });
+
+        assert_eq!(
+            seen,
+            vec![(DiagramKind::Mermaid, "graph TD; A-->B\n".to_string())]
+        );
+        assert!(out.contains("src=\"data:image/svg+xml;base64,PHN2Zz48L3N2Zz4=\""));
+        assert!(out.contains("<code class=\"language-rust\">"));
+        assert!(out.contains("See:"));
+    }
+
+    #[test]
+    fn test_failed_render_keeps_code_block() {
+        let html = markdown_to_html("```puml\n@startuml\nA -> B\n@enduml\n```");
+        let out = render_diagrams_with(&html, |kind, _| {
+            assert_eq!(kind, DiagramKind::PlantUml);
+            None
+        });
+        assert_eq!(out, html);
+    }
+}
@@ -0,0 +1,482 @@
+//! Event bridge: publishes event log records to NATS or Redis Streams.
+//!
+//! Every record appended to the [event log](crate::model::event_log) is also
+//! handed to the bridge, so indexers, analytics and bots can follow messages,
+//! reservations and agents without polling the REST API. See
+//! [`EventBridgeConfig`] for the configuration.
+//!
+//! | URL scheme | Published as |
+//! |------------|--------------|
+//! | `nats://` | `PUB <prefix>.<kind>` with the record as JSON, e.g. `mouchak.message.sent` |
+//! | `redis://` | `XADD <prefix> MAXLEN ~ <max_len> * kind <kind> event <json>` |
+//!
+//! Publishing never blocks or fails the action that produced the event.
+//! Records are queued for a background task that keeps one connection open
+//! and reconnects with backoff; while the queue is full, new records are
+//! dropped and a warning is logged. The event log remains the durable
+//! source, so a consumer that missed records can catch up from
+//! `GET /api/events`.
+//!
+//! Both protocols are spoken directly over TCP. TLS is not supported; run a
+//! local NATS leaf node or Redis proxy to reach a remote broker securely.
+
+use crate::model::event_log::EventRecord;
+use crate::{Error, Result};
+use mouchak_mail_common::config::EventBridgeConfig;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Records waiting to be published before new ones are dropped.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Time allowed to connect and complete the handshake.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait between reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Where the bridge publishes, parsed from the configured URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeTarget {
+    Nats {
+        addr: String,
+        user: Option<String>,
+        pass: Option<String>,
+    },
+    Redis {
+        addr: String,
+        user: Option<String>,
+        pass: Option<String>,
+        db: Option<u32>,
+    },
+}
+
+impl BridgeTarget {
+    /// Parses a `nats://` or `redis://` URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mouchak_mail_core::utils::event_bridge::BridgeTarget;
+    ///
+    /// assert_eq!(
+    ///     BridgeTarget::parse("redis://:secret@cache:6380/2").unwrap(),
+    ///     BridgeTarget::Redis {
+    ///         addr: "cache:6380".to_string(),
+    ///         user: None,
+    ///         pass: Some("secret".to_string()),
+    ///         db: Some(2),
+    ///     }
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `InvalidInput` for other schemes, a missing host or a bad
+    /// Redis database number.
+    pub fn parse(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            Error::InvalidInput(format!("Invalid event bridge URL '{}': {}", url, reason))
+        };
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| invalid("expected nats:// or redis://"))?;
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (credentials, host) = match authority.rsplit_once('@') {
+            Some((credentials, host)) => (Some(credentials), host),
+            None => (None, authority),
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        let (user, pass) = match credentials {
+            Some(credentials) => {
+                let (user, pass) = credentials.split_once(':').unwrap_or((credentials, ""));
+                (non_empty(user), non_empty(pass))
+            }
+            None => (None, None),
+        };
+
+        match scheme {
+            "nats" => Ok(Self::Nats {
+                addr: with_default_port(host, 4222),
+                user,
+                pass,
+            }),
+            "redis" => {
+                let db = match path.trim_end_matches('/') {
+                    "" => None,
+                    db => Some(db.parse().map_err(|_| invalid("bad database number"))?),
+                };
+                Ok(Self::Redis {
+                    addr: with_default_port(host, 6379),
+                    user,
+                    pass,
+                    db,
+                })
+            }
+            _ => Err(invalid("expected nats:// or redis://")),
+        }
+    }
+
+    fn addr(&self) -> &str {
+        match self {
+            Self::Nats { addr, .. } | Self::Redis { addr, .. } => addr,
+        }
+    }
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+fn with_default_port(host: &str, port: u16) -> String {
+    let has_port = match host.strip_prefix('[') {
+        // IPv6 literal, e.g. [::1]:4222
+        Some(rest) => rest.contains("]:"),
+        None => host.contains(':'),
+    };
+    if has_port {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Encodes a NATS `PUB` of `record` to `<prefix>.<kind>`.
+pub fn nats_publish_frame(prefix: &str, record: &EventRecord) -> Result<Vec<u8>> {
+    let payload = serde_json::to_vec(record)?;
+    let mut frame = format!("PUB {}.{} {}\r\n", prefix, record.kind, payload.len()).into_bytes();
+    frame.extend_from_slice(&payload);
+    frame.extend_from_slice(b"\r\n");
+    Ok(frame)
+}
+
+/// Encodes a Redis `XADD` of `record` to the `prefix` stream.
+pub fn redis_xadd_frame(prefix: &str, max_len: u64, record: &EventRecord) -> Result<Vec<u8>> {
+    let payload = serde_json::to_string(record)?;
+    Ok(redis_command(&[
+        "XADD",
+        prefix,
+        "MAXLEN",
+        "~",
+        &max_len.to_string(),
+        "*",
+        "kind",
+        &record.kind,
+        "event",
+        &payload,
+    ]))
+}
+
+/// Encodes a Redis command as a RESP array of bulk strings.
+fn redis_command(args: &[&str]) -> Vec<u8> {
+    let mut frame = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        frame.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        frame.extend_from_slice(arg.as_bytes());
+        frame.extend_from_slice(b"\r\n");
+    }
+    frame
+}
+
+/// Handle to the background publisher.
+#[derive(Debug, Clone)]
+pub struct EventBridge {
+    target: Arc<BridgeTarget>,
+    prefix: Arc<str>,
+    max_len: u64,
+    queue: mpsc::Sender<Vec<u8>>,
+    /// Set while records are being dropped, so the warning is logged once
+    overflowing: Arc<AtomicBool>,
+}
+
+impl EventBridge {
+    /// Starts the publisher for `config`, or returns `None` when no URL is
+    /// configured or it cannot be parsed. Must be called within a Tokio
+    /// runtime when a URL is set.
+    pub fn start(config: &EventBridgeConfig) -> Option<Self> {
+        let url = config.url.trim();
+        if url.is_empty() {
+            return None;
+        }
+        let target = match BridgeTarget::parse(url) {
+            Ok(target) => target,
+            Err(e) => {
+                warn!("Event bridge disabled: {}", e);
+                return None;
+            }
+        };
+        info!("Publishing events to {}", target.addr());
+
+        let (queue, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let target = Arc::new(target);
+        tokio::spawn(run(target.clone(), rx));
+        Some(Self {
+            target,
+            prefix: Arc::from(config.prefix.as_str()),
+            max_len: config.max_len,
+            queue,
+            overflowing: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Queues `record` for publishing.
+    pub fn publish(&self, record: &EventRecord) {
+        let frame = match self.target.as_ref() {
+            BridgeTarget::Nats { .. } => nats_publish_frame(&self.prefix, record),
+            BridgeTarget::Redis { .. } => redis_xadd_frame(&self.prefix, self.max_len, record),
+        };
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!(
+                    "Failed to encode {} for the event bridge: {}",
+                    record.kind, e
+                );
+                return;
+            }
+        };
+        match self.queue.try_send(frame) {
+            Ok(()) => self.overflowing.store(false, Ordering::Relaxed),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                if !self.overflowing.swap(true, Ordering::Relaxed) {
+                    warn!("Event bridge queue is full; dropping events until it drains");
+                }
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                debug!("Event bridge stopped; dropping {}", record.kind)
+            }
+        }
+    }
+}
+
+type Connection = (Lines<BufReader<OwnedReadHalf>>, OwnedWriteHalf);
+
+/// Publishes queued frames until every [`EventBridge`] handle is dropped.
+async fn run(target: Arc<BridgeTarget>, mut rx: mpsc::Receiver<Vec<u8>>) {
+    let mut backoff = Duration::from_secs(1);
+    // Frame whose write failed, retried on the next connection
+    let mut pending: Option<Vec<u8>> = None;
+
+    loop {
+        let (mut lines, mut writer) =
+            match tokio::time::timeout(CONNECT_TIMEOUT, connect(&target)).await {
+                Ok(Ok(connection)) => {
+                    debug!("Event bridge connected to {}", target.addr());
+                    backoff = Duration::from_secs(1);
+                    connection
+                }
+                Ok(Err(e)) => {
+                    warn!("Event bridge cannot reach {}: {}", target.addr(), e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+                Err(_) => {
+                    warn!("Event bridge timed out connecting to {}", target.addr());
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            };
+
+        loop {
+            let frame = match pending.take() {
+                Some(frame) => frame,
+                None => tokio::select! {
+                    frame = rx.recv() => match frame {
+                        Some(frame) => frame,
+                        None => return,
+                    },
+                    line = lines.next_line() => {
+                        match line {
+                            Ok(Some(line)) => {
+                                if let Err(e) = handle_reply(&target, &line, &mut writer).await {
+                                    warn!("Event bridge connection lost: {}", e);
+                                    break;
+                                }
+                            }
+                            Ok(None) => {
+                                warn!("Event bridge connection closed by {}", target.addr());
+                                break;
+                            }
+                            Err(e) => {
+                                warn!("Event bridge connection lost: {}", e);
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+                },
+            };
+            if let Err(e) = writer.write_all(&frame).await {
+                warn!("Event bridge connection lost: {}", e);
+                pending = Some(frame);
+                break;
+            }
+        }
+    }
+}
+
+/// Connects and authenticates.
+async fn connect(target: &BridgeTarget) -> std::io::Result<Connection> {
+    let stream = TcpStream::connect(target.addr()).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    match target {
+        BridgeTarget::Nats { user, pass, .. } => {
+            // The server greets with INFO before accepting CONNECT
+            match lines.next_line().await? {
+                Some(line) if line.starts_with("INFO") => {}
+                other => {
+                    return Err(std::io::Error::other(format!(
+                        "expected NATS INFO, got {:?}",
+                        other
+                    )));
+                }
+            }
+            let mut options = serde_json::json!({
+                "verbose": false,
+                "pedantic": false,
+                "name": "mouchak-mail",
+                "lang": "rust",
+                "version": env!("CARGO_PKG_VERSION"),
+            });
+            match (user, pass) {
+                (Some(user), Some(pass)) => {
+                    options["user"] = user.as_str().into();
+                    options["pass"] = pass.as_str().into();
+                }
+                // nats://token@host
+                (Some(token), None) => options["auth_token"] = token.as_str().into(),
+                _ => {}
+            }
+            writer
+                .write_all(format!("CONNECT {}\r\n", options).as_bytes())
+                .await?;
+        }
+        BridgeTarget::Redis { user, pass, db, .. } => {
+            let mut commands = Vec::new();
+            match (user, pass) {
+                (Some(user), Some(pass)) => {
+                    commands.push(vec!["AUTH", user.as_str(), pass.as_str()])
+                }
+                (None, Some(pass)) => commands.push(vec!["AUTH", pass.as_str()]),
+                _ => {}
+            }
+            let db = db.map(|db| db.to_string());
+            if let Some(db) = &db {
+                commands.push(vec!["SELECT", db.as_str()]);
+            }
+            for command in commands {
+                writer.write_all(&redis_command(&command)).await?;
+                match lines.next_line().await? {
+                    Some(line) if line.starts_with('+') => {}
+                    other => {
+                        return Err(std::io::Error::other(format!(
+                            "Redis {} failed: {:?}",
+                            command[0], other
+                        )));
+                    }
+                }
+            }
+        }
+    }
+    Ok((lines, writer))
+}
+
+/// Handles a line sent by the broker: answers NATS keep-alives and logs
+/// errors. Other replies (`XADD` ids, `+OK`) are ignored.
+async fn handle_reply(
+    target: &BridgeTarget,
+    line: &str,
+    writer: &mut OwnedWriteHalf,
+) -> std::io::Result<()> {
+    if matches!(target, BridgeTarget::Nats { .. }) && line == "PING" {
+        writer.write_all(b"PONG\r\n").await?;
+    } else if line.starts_with('-') {
+        warn!("Event bridge error from {}: {}", target.addr(), line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use serde_json::json;
+
+    fn record() -> EventRecord {
+        EventRecord {
+            id: 1,
+            created_ts: NaiveDateTime::default(),
+            kind: "message.sent".to_string(),
+            project_id: Some(1),
+            actor: Some("BlueLake".to_string()),
+            payload: json!({"message_id": 7}),
+            prev_hash: "0".to_string(),
+            hash: "1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            BridgeTarget::parse("nats://localhost").unwrap(),
+            BridgeTarget::Nats {
+                addr: "localhost:4222".to_string(),
+                user: None,
+                pass: None,
+            }
+        );
+        assert_eq!(
+            BridgeTarget::parse("nats://bot:pw@[::1]:4333").unwrap(),
+            BridgeTarget::Nats {
+                addr: "[::1]:4333".to_string(),
+                user: Some("bot".to_string()),
+                pass: Some("pw".to_string()),
+            }
+        );
+        assert_eq!(
+            BridgeTarget::parse("redis://cache").unwrap(),
+            BridgeTarget::Redis {
+                addr: "cache:6379".to_string(),
+                user: None,
+                pass: None,
+                db: None,
+            }
+        );
+        assert!(BridgeTarget::parse("kafka://broker").is_err());
+        assert!(BridgeTarget::parse("nats://").is_err());
+        assert!(BridgeTarget::parse("redis://cache/x").is_err());
+    }
+
+    #[test]
+    fn test_frames() {
+        let payload = serde_json::to_string(&record()).unwrap();
+
+        let nats = nats_publish_frame("mouchak", &record()).unwrap();
+        assert_eq!(
+            String::from_utf8(nats).unwrap(),
+            format!(
+                "PUB mouchak.message.sent {}\r\n{}\r\n",
+                payload.len(),
+                payload
+            )
+        );
+
+        let redis = String::from_utf8(redis_xadd_frame("events", 500, &record()).unwrap()).unwrap();
+        assert!(redis.starts_with(
+            "*10\r\n$4\r\nXADD\r\n$6\r\nevents\r\n$6\r\nMAXLEN\r\n$1\r\n~\r\n$3\r\n500\r\n"
+        ));
+        assert!(redis.ends_with(&format!("${}\r\n{}\r\n", payload.len(), payload)));
+    }
+}
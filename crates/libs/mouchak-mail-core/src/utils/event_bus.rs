@@ -0,0 +1,49 @@
+//! In-process event bus for real-time subscribers.
+//!
+//! Every record appended to the [event log](crate::model::event_log) is
+//! broadcast on the [`ModelManager`](crate::model::ModelManager)'s bus, so
+//! servers can push messages, acknowledgments and reservation changes to
+//! connected clients (e.g. the `/api/ws` WebSocket) instead of having them
+//! poll. BMCs publish by recording events; nothing else needs to know about
+//! subscribers.
+//!
+//! Publishing never blocks: each subscriber reads from a shared ring buffer
+//! of the last `capacity` records, and one that falls further behind gets
+//! [`broadcast::error::RecvError::Lagged`] with the number of records it
+//! missed. The event log remains the durable source to catch up from.
+
+use crate::model::event_log::EventRecord;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Broadcasts event log records to in-process subscribers.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<EventRecord>>,
+}
+
+impl EventBus {
+    /// Creates a bus buffering up to `capacity` records per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Sends `record` to every current subscriber. Records published while
+    /// nobody is subscribed are dropped.
+    pub fn publish(&self, record: &EventRecord) {
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send(Arc::new(record.clone()));
+        }
+    }
+
+    /// Subscribes to records published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<EventRecord>> {
+        self.sender.subscribe()
+    }
+
+    /// Number of live subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
@@ -0,0 +1,103 @@
+//! Deterministic agent identicons.
+//!
+//! Generates a small symmetric 5x5 pixel pattern as SVG from a hash of the
+//! agent's name and program, so every agent gets a stable, distinct avatar
+//! without an external avatar service. The same inputs always produce the
+//! same image.
+
+use base64::Engine;
+use sha1::{Digest, Sha1};
+
+/// Default avatar edge length in pixels.
+pub const DEFAULT_SIZE: u32 = 64;
+
+/// Smallest allowed avatar edge length in pixels.
+pub const MIN_SIZE: u32 = 16;
+
+/// Largest allowed avatar edge length in pixels.
+pub const MAX_SIZE: u32 = 512;
+
+const GRID: usize = 5;
+
+/// Hex digest identifying the identicon of an agent (usable as an ETag).
+pub fn identicon_hash(name: &str, program: &str) -> String {
+    hex::encode(digest(name, program))
+}
+
+/// Renders the identicon of an agent as an SVG document.
+///
+/// `size` is clamped to [`MIN_SIZE`]..=[`MAX_SIZE`].
+pub fn identicon_svg(name: &str, program: &str, size: u32) -> String {
+    let hash = digest(name, program);
+    let size = size.clamp(MIN_SIZE, MAX_SIZE);
+
+    let hue = u16::from_be_bytes([hash[0], hash[1]]) % 360;
+    let saturation = 55 + hash[2] % 20;
+    let lightness = 40 + hash[3] % 15;
+    let fg = format!("hsl({hue},{saturation}%,{lightness}%)");
+    let bg = format!("hsl({hue},{saturation}%,93%)");
+
+    let mut cells = String::new();
+    for row in 0..GRID {
+        for col in 0..GRID.div_ceil(2) {
+            // One bit per cell of the left half, mirrored onto the right
+            let bit = row * 3 + col;
+            if hash[4 + bit / 8] & (1 << (bit % 8)) == 0 {
+                continue;
+            }
+            for x in [col, GRID - 1 - col] {
+                cells.push_str(&format!(
+                    r#"<rect x="{x}" y="{row}" width="1" height="1"/>"#
+                ));
+                if x == GRID - 1 - x {
+                    break;
+                }
+            }
+        }
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+         viewBox=\"-0.5 -0.5 6 6\" shape-rendering=\"crispEdges\">\
+         <rect x=\"-0.5\" y=\"-0.5\" width=\"6\" height=\"6\" fill=\"{bg}\"/>\
+         <g fill=\"{fg}\">{cells}</g></svg>"
+    )
+}
+
+/// Renders the identicon as a `data:` URI for embedding in HTML.
+pub fn identicon_data_uri(name: &str, program: &str, size: u32) -> String {
+    format!(
+        "data:image/svg+xml;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(identicon_svg(name, program, size))
+    )
+}
+
+fn digest(name: &str, program: &str) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update(name.as_bytes());
+    hasher.update([0]);
+    hasher.update(program.as_bytes());
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identicon_is_deterministic_and_distinct() {
+        let a = identicon_svg("BlueLake", "claude-code", 64);
+        assert_eq!(a, identicon_svg("BlueLake", "claude-code", 64));
+        assert_ne!(a, identicon_svg("GreenCastle", "claude-code", 64));
+        assert_ne!(a, identicon_svg("BlueLake", "codex", 64));
+        assert_eq!(identicon_hash("BlueLake", "claude-code").len(), 40);
+    }
+
+    #[test]
+    fn test_identicon_size_is_clamped() {
+        assert!(identicon_svg("a", "b", 1).contains(r#"width="16""#));
+        assert!(identicon_svg("a", "b", 10_000).contains(r#"width="512""#));
+        assert!(identicon_data_uri("a", "b", 32).starts_with("data:image/svg+xml;base64,"));
+    }
+}
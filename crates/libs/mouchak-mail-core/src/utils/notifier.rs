@@ -0,0 +1,240 @@
+//! Local notifier: runs a user-configured command on selected events.
+//!
+//! Gives lightweight desktop alerts (`notify-send`, `terminal-notifier`, a
+//! script) without a full integration. See
+//! [`NotifierConfig`](mouchak_mail_common::config::NotifierConfig) for the
+//! configuration. Like the Git archive and event log, notifying never fails
+//! the action that triggered it; a command that cannot be started is logged.
+
+use mouchak_mail_common::config::{NotifierConfig, NotifierEvent};
+use tracing::{debug, warn};
+
+/// Maximum characters of message text substituted into `{body}`.
+const MAX_BODY_CHARS: usize = 200;
+
+/// Values substituted into the command template.
+///
+/// # Fields
+///
+/// - `title` - Short headline, e.g. "Urgent: Deploy blocked"
+/// - `body` - One-paragraph detail, truncated to a notification-friendly size
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub event: NotifierEvent,
+    pub title: String,
+    pub body: String,
+    pub project: String,
+    pub sender: String,
+    pub subject: String,
+    pub message_id: Option<i64>,
+    pub importance: String,
+}
+
+impl Notification {
+    fn placeholder(&self, name: &str) -> Option<String> {
+        Some(match name {
+            "event" => self.event.as_str().to_string(),
+            "title" => self.title.clone(),
+            "body" => truncate(&self.body, MAX_BODY_CHARS),
+            "project" => self.project.clone(),
+            "sender" => self.sender.clone(),
+            "subject" => self.subject.clone(),
+            "message_id" => self.message_id.map(|id| id.to_string()).unwrap_or_default(),
+            "importance" => self.importance.clone(),
+            _ => return None,
+        })
+    }
+}
+
+/// Runs the configured command for `notification` if its event is enabled.
+///
+/// The command runs in the background; its exit status is only logged.
+pub fn notify(config: &NotifierConfig, notification: &Notification) {
+    if !config.notifies(notification.event) {
+        return;
+    }
+    let Some(template) = config.command.as_deref() else {
+        return;
+    };
+    let args = render_command(template, notification);
+    let Some((program, args)) = args.split_first() else {
+        return;
+    };
+
+    match std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .spawn()
+    {
+        Ok(mut child) => {
+            let event = notification.event.as_str();
+            // Reap the child so long-running servers do not collect zombies
+            std::thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => {
+                    debug!("Notifier command for {} exited with {}", event, status)
+                }
+                Ok(_) => {}
+                Err(e) => debug!("Notifier command for {} failed: {}", event, e),
+            });
+        }
+        Err(e) => warn!("Failed to run notifier command '{}': {}", program, e),
+    }
+}
+
+/// Splits `template` into arguments and substitutes placeholders in each.
+///
+/// Unknown `{names}` are left as they are.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_common::config::NotifierEvent;
+/// use mouchak_mail_core::utils::notifier::{Notification, render_command};
+///
+/// let n = Notification {
+///     event: NotifierEvent::UrgentMessage,
+///     title: "Urgent: CI down".to_string(),
+///     body: "$(rm -rf ~)".to_string(),
+///     project: "backend".to_string(),
+///     sender: "BlueLake".to_string(),
+///     subject: "CI down".to_string(),
+///     message_id: Some(7),
+///     importance: "urgent".to_string(),
+/// };
+/// assert_eq!(
+///     render_command(r#"notify-send -a mouchak "{title}" '{body}' {x}"#, &n),
+///     ["notify-send", "-a", "mouchak", "Urgent: CI down", "$(rm -rf ~)", "{x}"]
+/// );
+/// ```
+pub fn render_command(template: &str, notification: &Notification) -> Vec<String> {
+    split_command_line(template)
+        .iter()
+        .map(|arg| substitute(arg, notification))
+        .collect()
+}
+
+/// Splits a command line into words, honouring single quotes, double quotes
+/// and backslash escapes the way a POSIX shell does.
+fn split_command_line(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    current.push(c);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(next @ ('"' | '\\' | '$' | '`')) => current.push(next),
+                            Some(next) => {
+                                current.push('\\');
+                                current.push(next);
+                            }
+                            None => current.push('\\'),
+                        },
+                        _ => current.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+fn substitute(arg: &str, notification: &Notification) -> String {
+    let mut out = String::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let value = candidate.find('}').and_then(|end| {
+            notification
+                .placeholder(&candidate[1..end])
+                .map(|v| (v, end))
+        });
+        match value {
+            Some((value, end)) => {
+                out.push_str(&value);
+                rest = &candidate[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(max_chars) {
+        Some((i, _)) => format!("{}...", &text[..i]),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command_line() {
+        assert_eq!(
+            split_command_line(r#"a "b c" 'd "e"' f\ g "h\"i" """#),
+            ["a", "b c", "d \"e\"", "f g", "h\"i", ""]
+        );
+    }
+
+    #[test]
+    fn test_notifies_only_configured_events() {
+        let mut config = NotifierConfig::default();
+        assert!(!config.notifies(NotifierEvent::UrgentMessage));
+
+        config.command = Some("notify-send {title}".to_string());
+        assert!(config.notifies(NotifierEvent::UrgentMessage));
+        config.events = "escalation".to_string();
+        assert!(!config.notifies(NotifierEvent::UrgentMessage));
+        assert!(config.notifies(NotifierEvent::Escalation));
+    }
+
+    #[test]
+    fn test_body_is_flattened_and_truncated() {
+        let long = format!("line one\n\nline two {}", "x".repeat(300));
+        let body = truncate(&long, MAX_BODY_CHARS);
+        assert!(body.starts_with("line one line two x"));
+        assert_eq!(body.chars().count(), MAX_BODY_CHARS + 3);
+    }
+}
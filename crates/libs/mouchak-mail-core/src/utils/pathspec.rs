@@ -4,6 +4,7 @@
 //! match overlapping files, used for detecting reservation conflicts.
 
 use glob::Pattern;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 
 /// Check if two path patterns could match overlapping files.
 ///
@@ -101,6 +102,57 @@ fn patterns_have_common_prefix(a: &str, b: &str) -> bool {
     a_starts_wild || b_starts_wild
 }
 
+/// Reservation patterns compiled once for matching many paths.
+///
+/// A pattern matches a path as a glob, and also as a directory: `src/api`
+/// matches `src/api/auth.rs`. Invalid patterns never match, as in the
+/// pre-commit guard. Leading `./` is ignored on both sides.
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    set: GlobSet,
+    /// Index of the source pattern for each compiled glob
+    owners: Vec<usize>,
+}
+
+impl PatternSet {
+    /// Compiles `patterns`; [`Self::matches`] returns indices into this list.
+    pub fn new<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        let mut owners = Vec::new();
+        for (idx, pattern) in patterns.into_iter().enumerate() {
+            let pattern = normalize(pattern).trim_end_matches('/');
+            if pattern.is_empty() {
+                continue;
+            }
+            for glob in [pattern.to_string(), format!("{}/**", pattern)] {
+                if let Ok(glob) = Glob::new(&glob) {
+                    builder.add(glob);
+                    owners.push(idx);
+                }
+            }
+        }
+        let set = builder.build().unwrap_or_else(|_| GlobSet::empty());
+        Self { set, owners }
+    }
+
+    /// Indices of the patterns matching `path`, ascending and deduplicated.
+    pub fn matches(&self, path: &str) -> Vec<usize> {
+        let mut matched: Vec<usize> = self
+            .set
+            .matches(normalize(path))
+            .into_iter()
+            .map(|glob| self.owners[glob])
+            .collect();
+        matched.sort_unstable();
+        matched.dedup();
+        matched
+    }
+}
+
+fn normalize(path: &str) -> &str {
+    path.trim().trim_start_matches("./")
+}
+
 // ============================================================================
 // Kani Formal Verification Proofs
 // ============================================================================
@@ -224,4 +276,22 @@ mod tests {
         assert!(!paths_conflict("[invalid", "src/main.rs"));
         assert!(!paths_conflict("src/main.rs", "[invalid"));
     }
+
+    #[test]
+    fn test_pattern_set_matches_globs_and_directories() {
+        let set = PatternSet::new(["src/**/*.rs", "docs", "./README.md", "[", ""]);
+        assert_eq!(set.matches("src/api/auth.rs"), [0]);
+        assert_eq!(set.matches("docs/guide/intro.md"), [1]);
+        assert_eq!(set.matches("docs"), [1]);
+        assert_eq!(set.matches("./README.md"), [2]);
+        assert!(set.matches("documents/x.md").is_empty());
+        assert!(set.matches("src/api/auth.py").is_empty());
+    }
+
+    #[test]
+    fn test_pattern_set_reports_every_matching_pattern() {
+        let set = PatternSet::new(["*", "src/**", "src/main.rs"]);
+        assert_eq!(set.matches("src/main.rs"), [0, 1, 2]);
+        assert_eq!(set.matches("Cargo.toml"), [0]);
+    }
 }
@@ -0,0 +1,726 @@
+//! Operator plugins: WASM modules that run on message events.
+//!
+//! Plugins let operators add policies without forking the crate: reject
+//! messages before they are stored, enrich them after delivery, or offer
+//! extra MCP tools. Modules are loaded at startup from the paths in
+//! [`PluginsConfig`] and run in wasmtime when built with the `wasm-plugins`
+//! feature; other builds log the configured plugins and ignore them.
+//!
+//! # Module interface
+//!
+//! Data crosses the boundary as UTF-8 JSON in the module's memory. A module
+//! exports `memory` and `alloc(len: i32) -> i32`, plus any of these handlers,
+//! each `(ptr: i32, len: i32) -> i64` returning its reply as
+//! `(ptr << 32) | len`, or 0 for no reply:
+//!
+//! | Export | Input | Reply |
+//! |--------|-------|-------|
+//! | `pre_send` | [`MessageEvent`] | `{"reject": "reason"}` blocks the message |
+//! | `post_receive` | [`MessageEvent`] with `message_id` | `{"custom_fields": {...}}` is merged into the message's custom fields |
+//! | `tools` | none (`0, 0`) | `[{"name", "description", "input_schema"}]`, read once at load |
+//! | `call_tool` | `{"tool", "arguments"}` | `{"text": "..."}` or `{"error": "..."}` |
+//!
+//! # Host API
+//!
+//! The only import offered is `mouchak.log(level: i32, ptr: i32, len: i32)`
+//! (0 debug, 1 info, 2 warn, 3 error). There is no WASI: a plugin cannot
+//! reach files, the network, the clock or the database, and sees only the
+//! event it is handed. Each call runs in a fresh instance bounded by
+//! `fuel` and `memory_mb`.
+//!
+//! A `pre_send` handler that fails rejects the message, so a broken policy
+//! does not let mail through. Other failures are logged and skipped.
+
+use crate::{Error, Result};
+use mouchak_mail_common::config::PluginsConfig;
+use mouchak_mail_common::paths;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Message event hooks a plugin can handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginHook {
+    /// Before a message is stored; may reject it
+    PreSend,
+    /// After a message is stored and delivered; may add custom fields
+    PostReceive,
+}
+
+impl PluginHook {
+    /// Name of the module export that handles the hook.
+    pub fn export_name(&self) -> &'static str {
+        match self {
+            Self::PreSend => "pre_send",
+            Self::PostReceive => "post_receive",
+        }
+    }
+}
+
+/// A message as plugins see it. Agents are given by name.
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageEvent {
+    /// Set for `post_receive` only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<i64>,
+    pub project: String,
+    pub sender: String,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub body_md: String,
+    pub importance: String,
+    pub thread_id: String,
+}
+
+/// A tool offered by a plugin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginTool {
+    /// Plugin that handles calls to the tool
+    #[serde(skip_deserializing)]
+    pub plugin: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// JSON schema of the tool's arguments
+    #[serde(default = "default_input_schema")]
+    pub input_schema: Map<String, Value>,
+}
+
+fn default_input_schema() -> Map<String, Value> {
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), Value::from("object"));
+    schema
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PreSendReply {
+    #[serde(default)]
+    reject: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PostReceiveReply {
+    #[serde(default)]
+    custom_fields: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ToolReply {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Loaded plugins, shared through the
+/// [`ModelManager`](crate::model::ModelManager).
+pub struct PluginHost {
+    backend: Option<backend::Backend>,
+    tools: Vec<PluginTool>,
+}
+
+impl PluginHost {
+    /// A host without plugins.
+    pub fn empty() -> Self {
+        Self {
+            backend: None,
+            tools: Vec::new(),
+        }
+    }
+
+    /// Loads the configured plugins. Modules that fail to load are logged
+    /// and left out; startup does not fail.
+    pub fn load(config: &PluginsConfig) -> Self {
+        let files = plugin_files(config);
+        if files.is_empty() {
+            return Self::empty();
+        }
+        let mut host = Self {
+            backend: backend::Backend::load(config, &files),
+            tools: Vec::new(),
+        };
+        host.tools = host.load_tools();
+        if !host.plugin_names().is_empty() {
+            info!(
+                plugins = %host.plugin_names().join(", "),
+                tools = host.tools.len(),
+                "Loaded WASM plugins"
+            );
+        }
+        host
+    }
+
+    /// Names of the loaded plugins, in load order.
+    pub fn plugin_names(&self) -> Vec<&str> {
+        self.backend
+            .as_ref()
+            .map(|b| b.plugins_exporting(None))
+            .unwrap_or_default()
+    }
+
+    /// Whether any plugin handles `hook`.
+    pub fn handles(&self, hook: PluginHook) -> bool {
+        self.backend
+            .as_ref()
+            .is_some_and(|b| !b.plugins_exporting(Some(hook.export_name())).is_empty())
+    }
+
+    /// Runs the `pre_send` handlers in load order.
+    ///
+    /// # Errors
+    /// Returns `PluginRejected` naming the first plugin that rejects the
+    /// message or fails.
+    pub fn pre_send(&self, event: &MessageEvent) -> Result<()> {
+        let Some(backend) = &self.backend else {
+            return Ok(());
+        };
+        let input = serde_json::to_vec(event)?;
+        for plugin in backend.plugins_exporting(Some(PluginHook::PreSend.export_name())) {
+            let reply = backend
+                .call(plugin, PluginHook::PreSend.export_name(), &input)
+                .and_then(|reply| parse_reply::<PreSendReply>(reply.as_deref()))
+                .map_err(|e| {
+                    warn!(plugin, error = %e, "pre_send plugin failed");
+                    Error::PluginRejected(format!("'{}' failed: {}", plugin, e))
+                })?;
+            if let Some(reason) = reply.reject {
+                return Err(Error::PluginRejected(format!("'{}': {}", plugin, reason)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the `post_receive` handlers in load order and returns the
+    /// custom fields they set, later plugins winning on conflicts.
+    pub fn post_receive(&self, event: &MessageEvent) -> Map<String, Value> {
+        let mut fields = Map::new();
+        let Some(backend) = &self.backend else {
+            return fields;
+        };
+        let input = match serde_json::to_vec(event) {
+            Ok(input) => input,
+            Err(e) => {
+                warn!(error = %e, "Failed to encode post_receive event");
+                return fields;
+            }
+        };
+        for plugin in backend.plugins_exporting(Some(PluginHook::PostReceive.export_name())) {
+            match backend
+                .call(plugin, PluginHook::PostReceive.export_name(), &input)
+                .and_then(|reply| parse_reply::<PostReceiveReply>(reply.as_deref()))
+            {
+                Ok(reply) => fields.extend(reply.custom_fields),
+                Err(e) => warn!(plugin, error = %e, "post_receive plugin failed"),
+            }
+        }
+        fields
+    }
+
+    /// Tools offered by the loaded plugins.
+    pub fn tools(&self) -> &[PluginTool] {
+        &self.tools
+    }
+
+    /// Finds a plugin tool by name.
+    pub fn tool(&self, name: &str) -> Option<&PluginTool> {
+        self.tools.iter().find(|t| t.name == name)
+    }
+
+    /// Calls a plugin tool and returns its text reply.
+    ///
+    /// # Errors
+    /// Returns `NotFound` for an unknown tool and `InvalidInput` with the
+    /// plugin's error or failure.
+    pub fn call_tool(&self, name: &str, arguments: Value) -> Result<String> {
+        let (Some(tool), Some(backend)) = (self.tool(name), &self.backend) else {
+            return Err(Error::NotFound);
+        };
+        let input = serde_json::to_vec(&serde_json::json!({
+            "tool": name,
+            "arguments": arguments,
+        }))?;
+        let reply = backend
+            .call(&tool.plugin, "call_tool", &input)
+            .and_then(|reply| {
+                reply
+                    .ok_or_else(|| "no reply".to_string())
+                    .and_then(|r| parse_reply::<ToolReply>(Some(&r)))
+            })
+            .map_err(|e| Error::InvalidInput(format!("Plugin '{}' failed: {}", tool.plugin, e)))?;
+        match (reply.error, reply.text) {
+            (Some(error), _) => Err(Error::InvalidInput(error)),
+            (None, text) => Ok(text.unwrap_or_default()),
+        }
+    }
+
+    fn load_tools(&self) -> Vec<PluginTool> {
+        let Some(backend) = &self.backend else {
+            return Vec::new();
+        };
+        let mut tools: Vec<PluginTool> = Vec::new();
+        for plugin in backend.plugins_exporting(Some("tools")) {
+            let declared = backend
+                .call(plugin, "tools", &[])
+                .and_then(|reply| parse_reply::<Vec<PluginTool>>(reply.as_deref()));
+            let declared = match declared {
+                Ok(declared) => declared,
+                Err(e) => {
+                    warn!(plugin, error = %e, "Failed to read plugin tools");
+                    continue;
+                }
+            };
+            for mut tool in declared {
+                if tools.iter().any(|t| t.name == tool.name) {
+                    warn!(plugin, tool = %tool.name, "Duplicate plugin tool ignored");
+                    continue;
+                }
+                tool.plugin = plugin.to_string();
+                tools.push(tool);
+            }
+        }
+        tools
+    }
+}
+
+impl Default for PluginHost {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// Decodes a handler's reply; no reply decodes as the type's default.
+fn parse_reply<T: for<'de> Deserialize<'de> + Default>(
+    reply: Option<&[u8]>,
+) -> std::result::Result<T, String> {
+    match reply {
+        None => Ok(T::default()),
+        Some(bytes) => {
+            serde_json::from_slice(bytes).map_err(|e| format!("invalid reply JSON: {}", e))
+        }
+    }
+}
+
+/// Expands the configured paths into module files. Directories contribute
+/// their `.wasm` files in name order. Relative paths are resolved against
+/// the portable directory in portable mode.
+fn plugin_files(config: &PluginsConfig) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in config.path_list() {
+        let path = paths::resolve(path);
+        if !path.is_dir() {
+            files.push(path);
+            continue;
+        }
+        match std::fs::read_dir(&path) {
+            Ok(entries) => {
+                let mut found: Vec<PathBuf> = entries
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "wasm"))
+                    .collect();
+                found.sort();
+                files.extend(found);
+            }
+            Err(e) => warn!(path = %path.display(), error = %e, "Failed to read plugin directory"),
+        }
+    }
+    files
+}
+
+/// Plugin name for a module file: its file stem.
+fn plugin_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(feature = "wasm-plugins")]
+mod backend {
+    use super::plugin_name;
+    use mouchak_mail_common::config::PluginsConfig;
+    use std::path::PathBuf;
+    use tracing::{debug, error, info, warn};
+    use wasmtime::{
+        Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder,
+    };
+
+    /// Longest message accepted from `mouchak.log`, in bytes.
+    const MAX_LOG_BYTES: usize = 4096;
+
+    struct HostState {
+        plugin: String,
+        limits: StoreLimits,
+    }
+
+    struct Plugin {
+        name: String,
+        module: Module,
+        exports: Vec<String>,
+    }
+
+    pub(super) struct Backend {
+        engine: Engine,
+        linker: Linker<HostState>,
+        plugins: Vec<Plugin>,
+        fuel: u64,
+        memory_bytes: usize,
+    }
+
+    impl Backend {
+        pub(super) fn load(config: &PluginsConfig, files: &[PathBuf]) -> Option<Self> {
+            let mut wasm_config = Config::new();
+            wasm_config.consume_fuel(true);
+            let engine = match Engine::new(&wasm_config) {
+                Ok(engine) => engine,
+                Err(e) => {
+                    error!(error = %format!("{e:#}"), "Failed to start the WASM engine");
+                    return None;
+                }
+            };
+            let mut linker = Linker::new(&engine);
+            if let Err(e) = linker.func_wrap("mouchak", "log", host_log) {
+                error!(error = %format!("{e:#}"), "Failed to register the plugin host API");
+                return None;
+            }
+
+            let mut plugins: Vec<Plugin> = Vec::new();
+            for path in files {
+                let name = plugin_name(path);
+                if plugins.iter().any(|p| p.name == name) {
+                    warn!(plugin = %name, path = %path.display(), "Duplicate plugin name ignored");
+                    continue;
+                }
+                let module = match Module::from_file(&engine, path) {
+                    Ok(module) => module,
+                    Err(e) => {
+                        error!(plugin = %name, error = %format!("{e:#}"), "Failed to load plugin");
+                        continue;
+                    }
+                };
+                // Rejects modules importing anything beyond the host API
+                if let Err(e) = linker.instantiate_pre(&module) {
+                    error!(plugin = %name, error = %format!("{e:#}"), "Plugin imports are not available");
+                    continue;
+                }
+                let exports = module.exports().map(|e| e.name().to_string()).collect();
+                info!(plugin = %name, path = %path.display(), "Loaded plugin");
+                plugins.push(Plugin {
+                    name,
+                    module,
+                    exports,
+                });
+            }
+
+            let memory_mb = usize::try_from(config.memory_mb).unwrap_or(usize::MAX);
+            Some(Self {
+                engine,
+                linker,
+                plugins,
+                fuel: config.fuel,
+                memory_bytes: memory_mb.saturating_mul(1024 * 1024),
+            })
+        }
+
+        /// Plugins exporting `export`, or all plugins for `None`.
+        pub(super) fn plugins_exporting(&self, export: Option<&str>) -> Vec<&str> {
+            self.plugins
+                .iter()
+                .filter(|p| export.is_none_or(|e| p.exports.iter().any(|x| x == e)))
+                .map(|p| p.name.as_str())
+                .collect()
+        }
+
+        /// Calls `export` of `plugin` in a fresh instance with `input` in
+        /// its memory, returning the reply bytes.
+        pub(super) fn call(
+            &self,
+            plugin: &str,
+            export: &str,
+            input: &[u8],
+        ) -> Result<Option<Vec<u8>>, String> {
+            let plugin = self
+                .plugins
+                .iter()
+                .find(|p| p.name == plugin)
+                .ok_or_else(|| format!("no plugin '{}'", plugin))?;
+            let mut store = Store::new(
+                &self.engine,
+                HostState {
+                    plugin: plugin.name.clone(),
+                    limits: StoreLimitsBuilder::new()
+                        .memory_size(self.memory_bytes)
+                        .instances(1)
+                        .build(),
+                },
+            );
+            store.limiter(|state| &mut state.limits);
+            store.set_fuel(self.fuel).map_err(|e| format!("{e:#}"))?;
+
+            let instance = self
+                .linker
+                .instantiate(&mut store, &plugin.module)
+                .map_err(|e| format!("{e:#}"))?;
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or("module does not export 'memory'")?;
+            let handler = instance
+                .get_typed_func::<(i32, i32), i64>(&mut store, export)
+                .map_err(|e| format!("{e:#}"))?;
+
+            let (ptr, len) = if input.is_empty() {
+                (0, 0)
+            } else {
+                let alloc = instance
+                    .get_typed_func::<i32, i32>(&mut store, "alloc")
+                    .map_err(|e| format!("{e:#}"))?;
+                let len = i32::try_from(input.len()).map_err(|_| "input too large")?;
+                let ptr = alloc.call(&mut store, len).map_err(|e| format!("{e:#}"))?;
+                memory
+                    .write(&mut store, ptr as u32 as usize, input)
+                    .map_err(|e| format!("{e:#}"))?;
+                (ptr, len)
+            };
+
+            let packed = handler
+                .call(&mut store, (ptr, len))
+                .map_err(|e| format!("{e:#}"))?;
+            if packed == 0 {
+                return Ok(None);
+            }
+            let out_ptr = (packed >> 32) as u32 as usize;
+            let out_len = packed as u32 as usize;
+            let reply = memory
+                .data(&store)
+                .get(out_ptr..out_ptr.saturating_add(out_len))
+                .ok_or("reply is outside the module's memory")?;
+            Ok(Some(reply.to_vec()))
+        }
+    }
+
+    /// `mouchak.log(level, ptr, len)`: logs UTF-8 text from the module's memory.
+    fn host_log(mut caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32) {
+        let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+            return;
+        };
+        let start = ptr as u32 as usize;
+        let end = start.saturating_add((len as u32 as usize).min(MAX_LOG_BYTES));
+        let text = memory
+            .data(&caller)
+            .get(start..end)
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+            .unwrap_or_default();
+        let plugin = caller.data().plugin.as_str();
+        match level {
+            0 => debug!(plugin, "{}", text),
+            1 => info!(plugin, "{}", text),
+            2 => warn!(plugin, "{}", text),
+            _ => error!(plugin, "{}", text),
+        }
+    }
+}
+
+#[cfg(not(feature = "wasm-plugins"))]
+mod backend {
+    use super::plugin_name;
+    use mouchak_mail_common::config::PluginsConfig;
+    use std::path::PathBuf;
+    use tracing::warn;
+
+    /// Stand-in for builds without the `wasm-plugins` feature.
+    pub(super) struct Backend;
+
+    impl Backend {
+        pub(super) fn load(_config: &PluginsConfig, files: &[PathBuf]) -> Option<Self> {
+            let names: Vec<String> = files.iter().map(|p| plugin_name(p)).collect();
+            warn!(
+                plugins = %names.join(", "),
+                "WASM plugins are configured but this build lacks the wasm-plugins feature; ignoring them"
+            );
+            None
+        }
+
+        pub(super) fn plugins_exporting(&self, _export: Option<&str>) -> Vec<&str> {
+            Vec::new()
+        }
+
+        pub(super) fn call(
+            &self,
+            plugin: &str,
+            _export: &str,
+            _input: &[u8],
+        ) -> Result<Option<Vec<u8>>, String> {
+            Err(format!(
+                "plugin '{}' cannot run without wasm-plugins",
+                plugin
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replies_default_when_empty() {
+        let reply = parse_reply::<PreSendReply>(None).unwrap();
+        assert!(reply.reject.is_none());
+        let reply = parse_reply::<PostReceiveReply>(Some(br#"{"custom_fields":{"a":1}}"#)).unwrap();
+        assert_eq!(reply.custom_fields["a"], 1);
+        assert!(parse_reply::<PreSendReply>(Some(b"not json")).is_err());
+    }
+
+    #[test]
+    fn test_tool_declarations_default_schema() {
+        let tools: Vec<PluginTool> =
+            parse_reply(Some(br#"[{"name":"lint","description":"Lint a diff"}]"#)).unwrap();
+        assert_eq!(tools[0].name, "lint");
+        assert_eq!(tools[0].input_schema["type"], "object");
+    }
+
+    #[test]
+    fn test_plugin_files_from_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.wasm", "a.wasm", "notes.txt"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let config = PluginsConfig {
+            paths: format!("{}, /opt/extra.wasm", dir.path().display()),
+            ..PluginsConfig::default()
+        };
+        let names: Vec<String> = plugin_files(&config)
+            .iter()
+            .map(|p| plugin_name(p))
+            .collect();
+        assert_eq!(names, ["a", "b", "extra"]);
+        assert!(
+            PluginHost::load(&PluginsConfig::default())
+                .tools()
+                .is_empty()
+        );
+    }
+
+    /// Writes a WAT module whose exports each answer with a fixed reply.
+    #[cfg(feature = "wasm-plugins")]
+    fn reply_module(dir: &Path, name: &str, replies: &[(&str, &str)]) -> PathBuf {
+        let mut body = String::new();
+        for (i, (export, reply)) in replies.iter().enumerate() {
+            let offset = 1024 + i * 512;
+            let data = reply.replace('\\', "\\\\").replace('"', "\\\"");
+            body.push_str(&format!(
+                r#"(data (i32.const {offset}) "{data}")
+                   (func (export "{export}") (param i32 i32) (result i64)
+                     (i64.or (i64.shl (i64.const {offset}) (i64.const 32))
+                             (i64.const {len})))
+                "#,
+                len = reply.len()
+            ));
+        }
+        let wat = format!(
+            r#"(module
+                 (import "mouchak" "log" (func $log (param i32 i32 i32)))
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32)
+                   (call $log (i32.const 0) (i32.const 1024) (i32.const 4))
+                   (i32.const 8192))
+                 {body})"#
+        );
+        let path = dir.join(format!("{name}.wat"));
+        std::fs::write(&path, wat).unwrap();
+        path
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    fn event() -> MessageEvent {
+        MessageEvent {
+            message_id: None,
+            project: "backend".to_string(),
+            sender: "BlueLake".to_string(),
+            to: vec!["GreenCastle".to_string()],
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            subject: "Deploy".to_string(),
+            body_md: "Deploying now".to_string(),
+            importance: "normal".to_string(),
+            thread_id: "T-1".to_string(),
+        }
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    #[test]
+    fn test_wasm_hooks_and_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = reply_module(
+            dir.path(),
+            "policy",
+            &[("pre_send", r#"{"reject":"no deploys on Friday"}"#)],
+        );
+        let tagger = reply_module(
+            dir.path(),
+            "tagger",
+            &[(
+                "post_receive",
+                r#"{"custom_fields":{"component":"deploy"}}"#,
+            )],
+        );
+        let tools = reply_module(
+            dir.path(),
+            "tools",
+            &[
+                ("tools", r#"[{"name":"echo","description":"Echo"}]"#),
+                ("call_tool", r#"{"text":"echoed"}"#),
+            ],
+        );
+        let config = PluginsConfig {
+            paths: [policy, tagger, tools]
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+            ..PluginsConfig::default()
+        };
+
+        let host = PluginHost::load(&config);
+        assert_eq!(host.plugin_names(), ["policy", "tagger", "tools"]);
+        assert!(host.handles(PluginHook::PreSend));
+
+        let err = host.pre_send(&event()).unwrap_err();
+        assert!(matches!(err, Error::PluginRejected(ref m) if m.contains("no deploys on Friday")));
+        assert_eq!(host.post_receive(&event())["component"], "deploy");
+
+        assert_eq!(host.tools()[0].plugin, "tools");
+        assert_eq!(host.call_tool("echo", Value::Null).unwrap(), "echoed");
+        assert!(matches!(
+            host.call_tool("missing", Value::Null),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    #[test]
+    fn test_wasm_runaway_plugin_is_stopped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spin.wat");
+        std::fs::write(
+            &path,
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                 (func (export "pre_send") (param i32 i32) (result i64)
+                   (loop $forever (br $forever))
+                   (i64.const 0)))"#,
+        )
+        .unwrap();
+        let config = PluginsConfig {
+            paths: path.display().to_string(),
+            fuel: 10_000,
+            ..PluginsConfig::default()
+        };
+        let host = PluginHost::load(&config);
+        let err = host.pre_send(&event()).unwrap_err();
+        assert!(matches!(err, Error::PluginRejected(ref m) if m.contains("'spin' failed")));
+    }
+}
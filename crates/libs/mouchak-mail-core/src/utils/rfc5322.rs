@@ -0,0 +1,997 @@
+//! RFC 5322 / MIME rendering and parsing of messages.
+//!
+//! Renders messages as Internet Message Format text so archives can be read
+//! by standard mail tooling: a single `.eml` file per message, or an
+//! `mboxrd` mailbox holding many. Agents get addresses of the form
+//! `{agent}@{project}.mouchak-mail.invalid` (the reserved `.invalid` TLD
+//! keeps them from ever being routed), and messages of one thread share a
+//! synthetic `References` id so mail clients group them.
+//!
+//! Mouchak-specific fields that have no standard header are kept in
+//! `X-Mouchak-*` headers.
+//!
+//! The parsing half ([`split_mbox`], [`ParsedEmail`]) reads real mail back
+//! in for `import-mbox`. It is deliberately forgiving: it extracts the
+//! headers Mouchak needs, the first `text/plain` body (falling back to
+//! stripped `text/html`), and counts attachments without keeping them.
+//! Messages are read as bytes, so each text part is decoded by the charset
+//! it declares.
+
+use base64::Engine;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+
+/// Domain suffix of agent addresses and message ids.
+pub const ADDRESS_DOMAIN: &str = "mouchak-mail.invalid";
+
+/// Maximum line length of base64 content, per RFC 2045.
+const BASE64_LINE_LEN: usize = 76;
+
+/// Maximum line length (without CRLF) that may be sent as 7bit, per RFC 5322.
+const MAX_7BIT_LINE_LEN: usize = 998;
+
+/// A file attached to an [`EmailMessage`].
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+    pub filename: String,
+    pub media_type: String,
+    pub content: Vec<u8>,
+}
+
+/// A message ready to be rendered as RFC 5322 text.
+///
+/// # Fields
+///
+/// - `id` - Mouchak message id, used for `Message-ID`
+/// - `from` / `to` / `cc` / `bcc` - Agent names, turned into addresses
+/// - `body` - Message body (Markdown), sent as `text/plain`
+#[derive(Debug, Clone)]
+pub struct EmailMessage {
+    pub id: i64,
+    pub project_slug: String,
+    pub from: String,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub date: NaiveDateTime,
+    pub thread_id: Option<String>,
+    pub importance: String,
+    pub ack_required: bool,
+    pub body: String,
+    pub attachments: Vec<EmailAttachment>,
+}
+
+/// Address of an agent in a project.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::rfc5322::agent_address;
+///
+/// assert_eq!(agent_address("BlueLake", "backend"), "BlueLake@backend.mouchak-mail.invalid");
+/// ```
+pub fn agent_address(agent_name: &str, project_slug: &str) -> String {
+    format!("{}@{}.{}", agent_name, project_slug, ADDRESS_DOMAIN)
+}
+
+/// `Message-ID` of a Mouchak message, including angle brackets.
+pub fn message_id_header(message_id: i64, project_slug: &str) -> String {
+    format!("<{}.{}@{}>", message_id, project_slug, ADDRESS_DOMAIN)
+}
+
+/// Synthetic id shared by all messages of a thread, including angle brackets.
+pub fn thread_reference(thread_id: &str, project_slug: &str) -> String {
+    let safe: String = thread_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("<thread.{}.{}@{}>", safe, project_slug, ADDRESS_DOMAIN)
+}
+
+/// Guesses a media type from a file extension.
+pub fn media_type_for(filename: &str) -> &'static str {
+    let ext = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "txt" | "log" | "rs" | "py" | "js" | "ts" | "toml" | "yaml" | "yml" | "sh" => "text/plain",
+        "md" => "text/markdown",
+        "json" => "application/json",
+        "html" | "htm" => "text/html",
+        "csv" => "text/csv",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+impl EmailMessage {
+    /// Renders the message as RFC 5322 text with CRLF line endings.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let address = |name: &String| agent_address(name, &self.project_slug);
+        let mut header = |name: &str, value: &str| {
+            out.push_str(name);
+            out.push_str(": ");
+            out.push_str(value);
+            out.push_str("\r\n");
+        };
+
+        header("Date", &Utc.from_utc_datetime(&self.date).to_rfc2822());
+        header("From", &address(&self.from));
+        for (name, list) in [("To", &self.to), ("Cc", &self.cc), ("Bcc", &self.bcc)] {
+            if !list.is_empty() {
+                let addresses: Vec<String> = list.iter().map(address).collect();
+                header(name, &addresses.join(", "));
+            }
+        }
+        header("Subject", &encode_header_text(&self.subject));
+        header(
+            "Message-ID",
+            &message_id_header(self.id, &self.project_slug),
+        );
+        if let Some(thread_id) = &self.thread_id {
+            let reference = thread_reference(thread_id, &self.project_slug);
+            header("In-Reply-To", &reference);
+            header("References", &reference);
+            header("X-Mouchak-Thread-Id", &encode_header_text(thread_id));
+        }
+        let importance = match self.importance.as_str() {
+            "high" | "urgent" => "high",
+            "low" => "low",
+            _ => "normal",
+        };
+        header("Importance", importance);
+        header("X-Mouchak-Importance", &self.importance);
+        header("X-Mouchak-Project", &self.project_slug);
+        header("X-Mouchak-Message-Id", &self.id.to_string());
+        if self.ack_required {
+            header("X-Mouchak-Ack-Required", "yes");
+        }
+        header("MIME-Version", "1.0");
+
+        if self.attachments.is_empty() {
+            push_text_part(&mut out, &self.body);
+            return out;
+        }
+
+        let boundary = format!("=_mouchak_{}_{}", self.project_slug, self.id);
+        out.push_str(&format!(
+            "Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n",
+            boundary
+        ));
+        out.push_str("This is a multi-part message in MIME format.\r\n");
+        out.push_str(&format!("\r\n--{}\r\n", boundary));
+        push_text_part(&mut out, &self.body);
+        for attachment in &self.attachments {
+            let filename = encode_param(&attachment.filename);
+            out.push_str(&format!("\r\n--{}\r\n", boundary));
+            out.push_str(&format!(
+                "Content-Type: {}; name=\"{}\"\r\n",
+                attachment.media_type, filename
+            ));
+            out.push_str(&format!(
+                "Content-Disposition: attachment; filename=\"{}\"\r\n",
+                filename
+            ));
+            out.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+            out.push_str(&base64_lines(&attachment.content));
+        }
+        out.push_str(&format!("\r\n--{}--\r\n", boundary));
+        out
+    }
+
+    /// Renders the message as one `mboxrd` entry: a `From ` separator line,
+    /// the message with LF line endings and `From ` lines quoted, then a
+    /// blank line.
+    pub fn render_mbox_entry(&self) -> String {
+        let mut out = format!(
+            "From {} {}\n",
+            agent_address(&self.from, &self.project_slug),
+            self.date.format("%a %b %e %H:%M:%S %Y")
+        );
+        for line in self.render().split("\r\n") {
+            if line.trim_start_matches('>').starts_with("From ") {
+                out.push('>');
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        // `render` ends with CRLF, so the split leaves one empty line; that
+        // line is the blank separator before the next entry.
+        out
+    }
+}
+
+/// Appends the body headers and content of a `text/plain` part.
+fn push_text_part(out: &mut String, body: &str) {
+    out.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+    let seven_bit = body.is_ascii() && body.lines().all(|l| l.len() <= MAX_7BIT_LINE_LEN);
+    if seven_bit {
+        out.push_str("Content-Transfer-Encoding: 7bit\r\n\r\n");
+        for line in body.lines() {
+            out.push_str(line);
+            out.push_str("\r\n");
+        }
+    } else {
+        out.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+        out.push_str(&base64_lines(body.as_bytes()));
+    }
+}
+
+/// Base64 encodes `data` in CRLF-terminated lines of [`BASE64_LINE_LEN`].
+fn base64_lines(data: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    let mut out = String::with_capacity(encoded.len() + encoded.len() / BASE64_LINE_LEN * 2 + 2);
+    for chunk in encoded.as_bytes().chunks(BASE64_LINE_LEN) {
+        // Base64 output is ASCII, so every chunk is valid UTF-8
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Encodes unstructured header text, using an RFC 2047 encoded word when it
+/// is not plain printable ASCII.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::rfc5322::encode_header_text;
+///
+/// assert_eq!(encode_header_text("Build failed"), "Build failed");
+/// assert_eq!(encode_header_text("Café"), "=?UTF-8?B?Q2Fmw6k=?=");
+/// ```
+pub fn encode_header_text(text: &str) -> String {
+    if text.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        return text.to_string();
+    }
+    format!(
+        "=?UTF-8?B?{}?=",
+        base64::engine::general_purpose::STANDARD.encode(text.as_bytes())
+    )
+}
+
+/// Encodes a quoted MIME parameter value such as a filename.
+fn encode_param(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    encode_header_text(&escaped)
+}
+
+/// Maximum MIME nesting followed when looking for the body.
+const MAX_MIME_DEPTH: usize = 8;
+
+/// A mail address with its optional display name.
+///
+/// `address` is lowercased so it can be used as a lookup key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mailbox {
+    pub name: Option<String>,
+    pub address: String,
+}
+
+impl Mailbox {
+    /// Part of the address before the `@`.
+    pub fn local_part(&self) -> &str {
+        self.address
+            .split_once('@')
+            .map_or(self.address.as_str(), |(local, _)| local)
+    }
+}
+
+/// A message parsed from RFC 5322 text.
+///
+/// # Fields
+///
+/// - `headers` - Unfolded headers in order, values still encoded
+/// - `body` - Decoded text body
+/// - `attachment_count` - Non-text parts that were skipped
+#[derive(Debug, Clone, Default)]
+pub struct ParsedEmail {
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub attachment_count: usize,
+}
+
+/// Body parts collected while walking a MIME tree.
+#[derive(Default)]
+struct BodyParts {
+    text: Option<String>,
+    html: Option<String>,
+    attachments: usize,
+}
+
+impl ParsedEmail {
+    /// Parses one message. Never fails; missing pieces come back empty.
+    pub fn parse(raw: &[u8]) -> Self {
+        let raw = crlf_to_lf(raw);
+        let (head, body) = split_head_body(&raw);
+        let headers = parse_headers(head);
+        let mut parts = BodyParts::default();
+        collect_body_parts(&headers, body, 0, &mut parts);
+        let body = parts
+            .text
+            .or_else(|| parts.html.map(|html| html_to_text(&html)))
+            .unwrap_or_default();
+        Self {
+            headers,
+            body: body.trim_end().to_string(),
+            attachment_count: parts.attachments,
+        }
+    }
+
+    /// Raw value of the first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Decoded `Subject`, empty if missing.
+    pub fn subject(&self) -> String {
+        self.header("Subject")
+            .map(decode_header_text)
+            .unwrap_or_default()
+    }
+
+    /// First address of `From`.
+    pub fn from(&self) -> Option<Mailbox> {
+        self.header("From")
+            .and_then(|v| parse_address_list(v).into_iter().next())
+    }
+
+    /// Addresses of `To`.
+    pub fn to(&self) -> Vec<Mailbox> {
+        self.header("To")
+            .map(parse_address_list)
+            .unwrap_or_default()
+    }
+
+    /// Addresses of `Cc`.
+    pub fn cc(&self) -> Vec<Mailbox> {
+        self.header("Cc")
+            .map(parse_address_list)
+            .unwrap_or_default()
+    }
+
+    /// `Message-ID`, including angle brackets.
+    pub fn message_id(&self) -> Option<String> {
+        let value = self.header("Message-ID")?;
+        msg_ids(value).into_iter().next().or_else(|| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| format!("<{}>", value))
+        })
+    }
+
+    /// Ids this message replies to: `References` oldest first, then
+    /// `In-Reply-To`, without duplicates.
+    pub fn references(&self) -> Vec<String> {
+        let mut ids = self.header("References").map(msg_ids).unwrap_or_default();
+        for id in self.header("In-Reply-To").map(msg_ids).unwrap_or_default() {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// `Date` in UTC.
+    pub fn date(&self) -> Option<NaiveDateTime> {
+        let value = self.header("Date")?;
+        // Drop trailing comments such as "(UTC)"
+        let value = value.split(" (").next().unwrap_or(value).trim();
+        chrono::DateTime::parse_from_rfc2822(value)
+            .ok()
+            .map(|dt| dt.naive_utc())
+    }
+}
+
+/// Splits an mbox file into messages, dropping the `From ` separator lines
+/// and unquoting `>From ` lines (mboxrd).
+///
+/// A `From ` line only separates messages at the start of the file or after
+/// a blank line, so unquoted `From ` lines inside bodies (mboxo) survive
+/// when they follow text.
+pub fn split_mbox(mbox: &[u8]) -> Vec<Vec<u8>> {
+    let mbox = crlf_to_lf(mbox);
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<&[u8]>> = None;
+    let mut prev_blank = true;
+    for line in mbox.split(|&b| b == b'\n') {
+        if prev_blank && line.starts_with(b"From ") {
+            if let Some(lines) = current.take() {
+                messages.push(lines.join(&b'\n'));
+            }
+            current = Some(Vec::new());
+        } else if let Some(lines) = current.as_mut() {
+            let unquoted = line
+                .iter()
+                .position(|&b| b != b'>')
+                .is_some_and(|i| i > 0 && line[i..].starts_with(b"From "));
+            lines.push(if unquoted { &line[1..] } else { line });
+        }
+        prev_blank = line.is_empty();
+    }
+    if let Some(lines) = current {
+        messages.push(lines.join(&b'\n'));
+    }
+    messages
+}
+
+/// Replaces CRLF line endings with LF.
+fn crlf_to_lf(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len());
+    for (i, &byte) in raw.iter().enumerate() {
+        if !(byte == b'\r' && raw.get(i + 1) == Some(&b'\n')) {
+            out.push(byte);
+        }
+    }
+    out
+}
+
+/// Decodes RFC 2047 encoded words in header text. Whitespace between
+/// adjacent encoded words is dropped, as the RFC requires.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::rfc5322::decode_header_text;
+///
+/// assert_eq!(decode_header_text("=?UTF-8?B?Q2Fmw6k=?= open"), "Café open");
+/// assert_eq!(decode_header_text("=?iso-8859-1?Q?caf=E9_au?= lait"), "café au lait");
+/// ```
+pub fn decode_header_text(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, candidate) = rest.split_at(start);
+        match decode_encoded_word(candidate) {
+            Some((decoded, len)) => {
+                if !(after_word && before.trim().is_empty()) {
+                    out.push_str(before);
+                }
+                out.push_str(&decoded);
+                rest = &candidate[len..];
+                after_word = true;
+            }
+            None => {
+                out.push_str(before);
+                out.push_str("=?");
+                rest = &candidate[2..];
+                after_word = false;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Decodes one `=?charset?enc?text?=` word at the start of `s`, returning the
+/// text and the number of bytes consumed.
+fn decode_encoded_word(s: &str) -> Option<(String, usize)> {
+    let inner = s.strip_prefix("=?")?;
+    let (charset, rest) = inner.split_once('?')?;
+    let (encoding, rest) = rest.split_once('?')?;
+    let end = rest.find("?=")?;
+    let text = &rest[..end];
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+    let consumed = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+    let bytes = match encoding {
+        "B" | "b" => base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .ok()?,
+        "Q" | "q" => decode_quoted_printable(text.as_bytes(), true),
+        _ => return None,
+    };
+    // RFC 2231 allows a language suffix: "utf-8*en"
+    let charset = charset.split('*').next().unwrap_or_default();
+    Some((decode_charset(&bytes, charset), consumed))
+}
+
+/// Decodes quoted-printable text. In headers (`Q` encoding) `_` is a space.
+fn decode_quoted_printable(bytes: &[u8], header: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'=' if bytes.get(i + 1) == Some(&b'\n') => i += 2,
+            b'=' => match bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    out.push(byte);
+                    i += 3;
+                }
+                None => {
+                    out.push(b'=');
+                    i += 1;
+                }
+            },
+            b'_' if header => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Decodes text in `charset`, a MIME charset label such as `iso-8859-1` or
+/// `windows-1252`. Unknown labels are treated as UTF-8; invalid sequences
+/// become U+FFFD.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::rfc5322::decode_charset;
+///
+/// assert_eq!(decode_charset(b"caf\xe9", "iso-8859-1"), "café");
+/// assert_eq!(decode_charset(b"\x93quoted\x94", "cp1252"), "\u{201c}quoted\u{201d}");
+/// assert_eq!(decode_charset("café".as_bytes(), "x-unknown"), "café");
+/// ```
+pub fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    let encoding =
+        encoding_rs::Encoding::for_label(charset.trim().as_bytes()).unwrap_or(encoding_rs::UTF_8);
+    encoding.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+/// Splits a message or MIME part at the first blank line.
+fn split_head_body(raw: &[u8]) -> (&[u8], &[u8]) {
+    if let Some(body) = raw.strip_prefix(b"\n") {
+        return (&[], body);
+    }
+    match raw.windows(2).position(|w| w == b"\n\n") {
+        Some(i) => (&raw[..i], &raw[i + 2..]),
+        None => (raw, &[]),
+    }
+}
+
+/// Parses and unfolds a header block. Raw 8-bit header text is read as
+/// UTF-8 (RFC 6532).
+fn parse_headers(head: &[u8]) -> Vec<(String, String)> {
+    let head = String::from_utf8_lossy(head);
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Splits a structured header value such as `Content-Type` into its
+/// lowercased main value and its parameters.
+fn header_params(value: &str) -> (String, Vec<(String, String)>) {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current);
+
+    let main = fields[0].trim().to_ascii_lowercase();
+    let params = fields[1..]
+        .iter()
+        .filter_map(|field| field.split_once('='))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+    (main, params)
+}
+
+fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.as_str())
+}
+
+/// Walks a MIME tree, keeping the first text and HTML bodies and counting
+/// everything else as an attachment.
+fn collect_body_parts(
+    headers: &[(String, String)],
+    body: &[u8],
+    depth: usize,
+    parts: &mut BodyParts,
+) {
+    let (mime, params) =
+        header_params(find_header(headers, "Content-Type").unwrap_or("text/plain"));
+
+    if mime.starts_with("multipart/") {
+        let boundary = param(&params, "boundary").filter(|_| depth < MAX_MIME_DEPTH);
+        if let Some(boundary) = boundary {
+            for part in split_multipart(body, boundary) {
+                let (head, body) = split_head_body(&part);
+                collect_body_parts(&parse_headers(head), body, depth + 1, parts);
+            }
+        }
+        return;
+    }
+
+    let disposition = find_header(headers, "Content-Disposition").unwrap_or_default();
+    if disposition
+        .trim_start()
+        .to_ascii_lowercase()
+        .starts_with("attachment")
+        || !mime.starts_with("text/")
+    {
+        parts.attachments += 1;
+        return;
+    }
+
+    let encoding = find_header(headers, "Content-Transfer-Encoding")
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let bytes = match encoding.as_str() {
+        "base64" => {
+            let compact: Vec<u8> = body
+                .iter()
+                .copied()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect();
+            base64::engine::general_purpose::STANDARD
+                .decode(compact)
+                .unwrap_or_else(|_| body.to_vec())
+        }
+        "quoted-printable" => decode_quoted_printable(body, false),
+        _ => body.to_vec(),
+    };
+    let text =
+        decode_charset(&bytes, param(&params, "charset").unwrap_or("utf-8")).replace("\r\n", "\n");
+
+    match mime.as_str() {
+        "text/html" if parts.html.is_none() => parts.html = Some(text),
+        "text/html" => parts.attachments += 1,
+        _ if parts.text.is_none() => parts.text = Some(text),
+        _ => parts.attachments += 1,
+    }
+}
+
+/// Splits a multipart body into its parts, dropping preamble and epilogue.
+fn split_multipart(body: &[u8], boundary: &str) -> Vec<Vec<u8>> {
+    let delimiter = format!("--{}", boundary);
+    let close = format!("{}--", delimiter);
+    let mut parts = Vec::new();
+    let mut current: Option<Vec<&[u8]>> = None;
+    for line in body.split(|&b| b == b'\n') {
+        let trimmed = line.trim_ascii_end();
+        if trimmed == delimiter.as_bytes() || trimmed == close.as_bytes() {
+            if let Some(lines) = current.take() {
+                parts.push(lines.join(&b'\n'));
+            }
+            if trimmed == close.as_bytes() {
+                break;
+            }
+            current = Some(Vec::new());
+        } else if let Some(lines) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    if let Some(lines) = current {
+        parts.push(lines.join(&b'\n'));
+    }
+    parts
+}
+
+/// Crude HTML to text: drops tags, `<style>`/`<script>` contents, and
+/// decodes the common entities.
+fn html_to_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let tag_and_rest = &rest[start..];
+        let Some(end) = tag_and_rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = tag_and_rest[1..end].to_ascii_lowercase();
+        rest = &tag_and_rest[end + 1..];
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        match name {
+            "br" | "br/" | "/p" | "/div" | "/li" | "/tr" | "/h1" | "/h2" | "/h3" => out.push('\n'),
+            "style" | "script" => {
+                let closing = format!("</{}", name);
+                rest = rest
+                    .to_ascii_lowercase()
+                    .find(&closing)
+                    .map_or("", move |i| &rest[i..]);
+            }
+            _ => {}
+        }
+    }
+    out.push_str(rest);
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Parses an address list header such as `To`. Group syntax and entries
+/// without an `@` are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::rfc5322::parse_address_list;
+///
+/// let list = parse_address_list(r#""Lake, Blue" <Blue@Example.com>, ops@example.com (Ops)"#);
+/// assert_eq!(list[0].name.as_deref(), Some("Lake, Blue"));
+/// assert_eq!(list[0].address, "blue@example.com");
+/// assert_eq!(list[1].name.as_deref(), Some("Ops"));
+/// ```
+pub fn parse_address_list(value: &str) -> Vec<Mailbox> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let (mut quoted, mut angle, mut comment) = (false, false, 0usize);
+    for c in value.chars() {
+        match c {
+            '"' if comment == 0 => quoted = !quoted,
+            '<' if !quoted && comment == 0 => angle = true,
+            '>' if !quoted && comment == 0 => angle = false,
+            '(' if !quoted => comment += 1,
+            ')' if !quoted => comment = comment.saturating_sub(1),
+            ',' if !quoted && !angle && comment == 0 => {
+                entries.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    entries.push(current);
+
+    entries
+        .iter()
+        .filter_map(|entry| parse_mailbox(entry.trim()))
+        .collect()
+}
+
+fn parse_mailbox(entry: &str) -> Option<Mailbox> {
+    let (name, address) = match (entry.rfind('<'), entry.ends_with('>')) {
+        (Some(lt), true) => {
+            let name = entry[..lt].trim().trim_matches('"').replace("\\\"", "\"");
+            (name, entry[lt + 1..entry.len() - 1].trim())
+        }
+        _ => match entry.split_once('(') {
+            Some((address, comment)) => (comment.trim_end_matches(')').to_string(), address.trim()),
+            None => (String::new(), entry),
+        },
+    };
+    if !address.contains('@') || address.contains(char::is_whitespace) {
+        return None;
+    }
+    let name = decode_header_text(name.trim());
+    Some(Mailbox {
+        name: (!name.is_empty()).then_some(name),
+        address: address.to_ascii_lowercase(),
+    })
+}
+
+/// Extracts the `<...>` ids from a `Message-ID`, `In-Reply-To`, or
+/// `References` value.
+fn msg_ids(value: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        ids.push(rest[start..start + len + 1].to_string());
+        rest = &rest[start + len + 1..];
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> EmailMessage {
+        EmailMessage {
+            id: 7,
+            project_slug: "backend".to_string(),
+            from: "alice".to_string(),
+            to: vec!["bob".to_string()],
+            cc: vec![],
+            bcc: vec![],
+            subject: "Deploy plan".to_string(),
+            date: NaiveDateTime::parse_from_str("2025-01-02 03:04:05", "%Y-%m-%d %H:%M:%S")
+                .unwrap_or_default(),
+            thread_id: Some("TKT-1".to_string()),
+            importance: "urgent".to_string(),
+            ack_required: true,
+            body: "Step one\nFrom now on, deploy on Fridays.".to_string(),
+            attachments: vec![],
+        }
+    }
+
+    #[test]
+    fn test_render_headers() {
+        let eml = sample().render();
+        assert!(eml.contains("From: alice@backend.mouchak-mail.invalid\r\n"));
+        assert!(eml.contains("To: bob@backend.mouchak-mail.invalid\r\n"));
+        assert!(eml.contains("Date: Thu, 2 Jan 2025 03:04:05 +0000\r\n"));
+        assert!(eml.contains("Message-ID: <7.backend@mouchak-mail.invalid>\r\n"));
+        assert!(eml.contains("References: <thread.TKT-1.backend@mouchak-mail.invalid>\r\n"));
+        assert!(eml.contains("Importance: high\r\n"));
+        assert!(eml.contains("X-Mouchak-Ack-Required: yes\r\n"));
+        assert!(eml.contains("\r\n\r\nStep one\r\n"));
+    }
+
+    #[test]
+    fn test_attachments_are_mime_parts() {
+        let mut msg = sample();
+        msg.attachments.push(EmailAttachment {
+            filename: "build.log".to_string(),
+            media_type: media_type_for("build.log").to_string(),
+            content: b"ok".to_vec(),
+        });
+        let eml = msg.render();
+        assert!(eml.contains("Content-Type: multipart/mixed; boundary=\"=_mouchak_backend_7\""));
+        assert!(eml.contains("Content-Disposition: attachment; filename=\"build.log\""));
+        assert!(eml.contains("\r\n\r\nb2s=\r\n"));
+        assert!(eml.ends_with("--=_mouchak_backend_7--\r\n"));
+    }
+
+    #[test]
+    fn test_parse_round_trips_rendered_message() {
+        let mut msg = sample();
+        msg.subject = "Café plan".to_string();
+        msg.attachments.push(EmailAttachment {
+            filename: "build.log".to_string(),
+            media_type: media_type_for("build.log").to_string(),
+            content: b"ok".to_vec(),
+        });
+        let parsed = ParsedEmail::parse(msg.render().as_bytes());
+        assert_eq!(parsed.subject(), "Café plan");
+        assert_eq!(
+            parsed.from().map(|m| m.address),
+            Some(agent_address("alice", "backend"))
+        );
+        assert_eq!(parsed.to()[0].local_part(), "bob");
+        assert_eq!(parsed.message_id(), Some(message_id_header(7, "backend")));
+        assert_eq!(parsed.references(), [thread_reference("TKT-1", "backend")]);
+        assert_eq!(parsed.date(), Some(msg.date));
+        assert_eq!(parsed.body, msg.body);
+        assert_eq!(parsed.attachment_count, 1);
+    }
+
+    #[test]
+    fn test_parse_quoted_printable_alternative() {
+        let raw = "From: \"Blue Lake\" <Blue@Example.com>\r\n\
+                   Subject: =?utf-8?Q?R=C3=A9sum=C3=A9?=\r\n\
+                   \tfollow-up\r\n\
+                   In-Reply-To: <b@x>\r\n\
+                   References: <a@x>\r\n <b@x>\r\n\
+                   Content-Type: multipart/alternative; boundary=\"xyz\"\r\n\
+                   \r\n\
+                   preamble\r\n\
+                   --xyz\r\n\
+                   Content-Type: text/plain; charset=\"iso-8859-1\"\r\n\
+                   Content-Transfer-Encoding: quoted-printable\r\n\
+                   \r\n\
+                   caf=E9 is a long line that was so=\r\n\
+                   ft-wrapped\r\n\
+                   --xyz\r\n\
+                   Content-Type: text/html\r\n\
+                   \r\n\
+                   <p>ignored</p>\r\n\
+                   --xyz--\r\n";
+        let parsed = ParsedEmail::parse(raw.as_bytes());
+        let from = parsed.from().unwrap_or_else(|| panic!("from"));
+        assert_eq!(from.name.as_deref(), Some("Blue Lake"));
+        assert_eq!(from.address, "blue@example.com");
+        assert_eq!(parsed.subject(), "Résumé follow-up");
+        assert_eq!(parsed.references(), ["<a@x>", "<b@x>"]);
+        assert_eq!(parsed.body, "café is a long line that was soft-wrapped");
+        assert_eq!(parsed.attachment_count, 0);
+    }
+
+    #[test]
+    fn test_parse_windows_1252_body() {
+        let raw = "Subject: =?windows-1252?Q?=93Ship=94?=\n\
+                   Content-Type: text/plain; charset=cp1252\n\
+                   Content-Transfer-Encoding: quoted-printable\n\
+                   \n\
+                   It=92s done =80 =96 caf=E9";
+        let parsed = ParsedEmail::parse(raw.as_bytes());
+        assert_eq!(parsed.subject(), "\u{201c}Ship\u{201d}");
+        assert_eq!(parsed.body, "It\u{2019}s done \u{20ac} \u{2013} café");
+    }
+
+    #[test]
+    fn test_parse_html_only_body() {
+        let raw =
+            "Subject: hi\nContent-Type: text/html\n\n<p>One &amp; two</p><style>p{}</style>three";
+        assert_eq!(ParsedEmail::parse(raw.as_bytes()).body, "One & two\nthree");
+    }
+
+    #[test]
+    fn test_split_mbox_unquotes_from_lines() {
+        let mut other = sample();
+        other.id = 8;
+        let mbox = sample().render_mbox_entry() + &other.render_mbox_entry();
+        let messages = split_mbox(mbox.as_bytes());
+        assert_eq!(messages.len(), 2);
+        let first = ParsedEmail::parse(&messages[0]);
+        assert!(first.body.ends_with("\nFrom now on, deploy on Fridays."));
+        assert_eq!(
+            ParsedEmail::parse(&messages[1]).message_id(),
+            Some(message_id_header(8, "backend"))
+        );
+    }
+
+    #[test]
+    fn test_split_mbox_keeps_each_message_charset() {
+        let mut mbox = b"From a@x Thu Jan  2 03:04:05 2025\n\
+                         Subject: latin\n\
+                         Content-Type: text/plain; charset=iso-8859-1\n\
+                         Content-Transfer-Encoding: 8bit\n\
+                         \n\
+                         caf\xe9\n\
+                         \n"
+        .to_vec();
+        mbox.extend_from_slice(
+            "From b@x Thu Jan  2 03:04:05 2025\n\
+             Subject: utf-8\n\
+             Content-Type: text/plain; charset=utf-8\n\
+             \n\
+             café\n"
+                .as_bytes(),
+        );
+        let messages = split_mbox(&mbox);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(ParsedEmail::parse(&messages[0]).body, "café");
+        assert_eq!(ParsedEmail::parse(&messages[1]).body, "café");
+    }
+
+    #[test]
+    fn test_mbox_entry_quotes_from_lines() {
+        let entry = sample().render_mbox_entry();
+        assert!(
+            entry.starts_with("From alice@backend.mouchak-mail.invalid Thu Jan  2 03:04:05 2025\n")
+        );
+        assert!(entry.contains("\n>From now on"));
+        assert!(!entry.contains('\r'));
+        assert!(entry.ends_with("\n\n"));
+    }
+}
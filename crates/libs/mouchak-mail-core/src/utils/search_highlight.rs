@@ -0,0 +1,226 @@
+//! Search result highlighting.
+//!
+//! Turns an FTS5 query into plain search terms and locates them in message
+//! text so callers can show a short, highlighted snippet instead of the full
+//! body. Matching is ASCII case-insensitive and anchored at word starts,
+//! mirroring how the FTS5 `unicode61` tokenizer matches tokens and prefixes.
+
+use serde::Serialize;
+
+/// Bytes of context kept on each side of the first match.
+pub const SNIPPET_RADIUS: usize = 80;
+
+/// Marker inserted before and after each match in [`Snippet::highlighted`].
+pub const HIGHLIGHT_MARKER: &str = "**";
+
+const ELLIPSIS: &str = "…";
+
+/// A byte range `[start, end)` within [`Snippet::text`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MatchOffset {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A highlighted excerpt around the first match in a text.
+///
+/// # Fields
+///
+/// - `text` - Plain excerpt (with `…` where the source was truncated)
+/// - `highlighted` - Same excerpt with every match wrapped in `**`
+/// - `matches` - Byte offsets of every match within `text`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Snippet {
+    pub text: String,
+    pub highlighted: String,
+    pub matches: Vec<MatchOffset>,
+}
+
+/// Extracts plain search terms from an FTS5 query.
+///
+/// Boolean operators, quotes, grouping and trailing `*` are dropped; phrases
+/// contribute each of their words. Terms are lowercased and de-duplicated.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::search_highlight::extract_terms;
+///
+/// assert_eq!(
+///     extract_terms("\"build failure\" OR deploy* NOT flaky"),
+///     vec!["build", "failure", "deploy", "flaky"]
+/// );
+/// ```
+pub fn extract_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for raw in query.split(|c: char| c.is_whitespace() || c == '"' || c == '(' || c == ')') {
+        if matches!(raw, "AND" | "OR" | "NOT" | "NEAR") {
+            continue;
+        }
+        let term = raw
+            .trim_end_matches('*')
+            .trim_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+            .to_ascii_lowercase();
+        if !term.is_empty() && !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+/// Finds every word-start occurrence of any term in `text`.
+///
+/// Overlapping matches are merged; offsets are sorted and index into `text`.
+pub fn find_matches(text: &str, terms: &[String]) -> Vec<MatchOffset> {
+    let haystack = text.to_ascii_lowercase();
+    let mut matches = Vec::new();
+
+    for term in terms {
+        let mut from = 0;
+        while let Some(pos) = haystack[from..].find(term.as_str()) {
+            let start = from + pos;
+            let end = start + term.len();
+            let at_word_start = haystack[..start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !c.is_alphanumeric());
+            if at_word_start {
+                matches.push(MatchOffset { start, end });
+            }
+            from = end;
+        }
+    }
+
+    matches.sort_by_key(|m| (m.start, m.end));
+    let mut merged: Vec<MatchOffset> = Vec::with_capacity(matches.len());
+    for m in matches {
+        match merged.last_mut() {
+            Some(last) if m.start <= last.end => last.end = last.end.max(m.end),
+            _ => merged.push(m),
+        }
+    }
+    merged
+}
+
+/// Builds a snippet around the first match of any term.
+///
+/// Returns `None` if no term occurs in `text`.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::search_highlight::{build_snippet, extract_terms};
+///
+/// let snippet = build_snippet("The deploy failed twice", &extract_terms("deploy"), 80).unwrap();
+/// assert_eq!(snippet.highlighted, "The **deploy** failed twice");
+/// assert_eq!(&snippet.text[snippet.matches[0].start..snippet.matches[0].end], "deploy");
+/// ```
+pub fn build_snippet(text: &str, terms: &[String], radius: usize) -> Option<Snippet> {
+    let all = find_matches(text, terms);
+    let first = *all.first()?;
+
+    let mut start = floor_char_boundary(text, first.start.saturating_sub(radius));
+    let mut end = ceil_char_boundary(text, (first.end + radius).min(text.len()));
+
+    // Prefer cutting at whitespace so words are not split
+    if start > 0
+        && let Some((ws, c)) = text[start..first.start]
+            .char_indices()
+            .find(|(_, c)| c.is_whitespace())
+    {
+        start += ws + c.len_utf8();
+    }
+    if end < text.len()
+        && let Some(ws) = text[first.end..end].rfind(char::is_whitespace)
+    {
+        end = first.end + ws;
+    }
+
+    let prefix = if start > 0 { ELLIPSIS } else { "" };
+    let suffix = if end < text.len() { ELLIPSIS } else { "" };
+    let excerpt = &text[start..end];
+
+    let matches: Vec<MatchOffset> = all
+        .into_iter()
+        .filter(|m| m.start >= start && m.end <= end)
+        .map(|m| MatchOffset {
+            start: m.start - start + prefix.len(),
+            end: m.end - start + prefix.len(),
+        })
+        .collect();
+
+    let body = format!("{}{}{}", prefix, excerpt, suffix);
+    let mut highlighted = String::with_capacity(body.len() + matches.len() * 4);
+    let mut cursor = 0;
+    for m in &matches {
+        highlighted.push_str(&body[cursor..m.start]);
+        highlighted.push_str(HIGHLIGHT_MARKER);
+        highlighted.push_str(&body[m.start..m.end]);
+        highlighted.push_str(HIGHLIGHT_MARKER);
+        cursor = m.end;
+    }
+    highlighted.push_str(&body[cursor..]);
+
+    Some(Snippet {
+        text: body,
+        highlighted,
+        matches,
+    })
+}
+
+fn floor_char_boundary(text: &str, mut idx: usize) -> usize {
+    while !text.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+fn ceil_char_boundary(text: &str, mut idx: usize) -> usize {
+    while !text.is_char_boundary(idx) {
+        idx += 1;
+    }
+    idx
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches_is_case_insensitive_and_word_anchored() {
+        let terms = extract_terms("test");
+        let matches = find_matches("Test the retest of TESTS", &terms);
+        assert_eq!(
+            matches,
+            vec![
+                MatchOffset { start: 0, end: 4 },
+                MatchOffset { start: 19, end: 23 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_snippet_truncates_with_ellipsis() {
+        let text = format!("{} needle {}", "a ".repeat(100), "b ".repeat(100));
+        let snippet = build_snippet(&text, &extract_terms("needle"), 20).unwrap();
+        assert!(snippet.text.starts_with(ELLIPSIS));
+        assert!(snippet.text.ends_with(ELLIPSIS));
+        let m = snippet.matches[0];
+        assert_eq!(&snippet.text[m.start..m.end], "needle");
+        assert!(snippet.highlighted.contains("**needle**"));
+    }
+
+    #[test]
+    fn test_build_snippet_handles_multibyte_text() {
+        let text = format!("{}déploiement réussi", "é".repeat(60));
+        let snippet = build_snippet(&text, &extract_terms("réussi"), 10).unwrap();
+        let m = snippet.matches[0];
+        assert_eq!(&snippet.text[m.start..m.end], "réussi");
+    }
+
+    #[test]
+    fn test_build_snippet_no_match() {
+        assert!(build_snippet("nothing here", &extract_terms("absent"), 80).is_none());
+    }
+}
@@ -0,0 +1,281 @@
+//! Tool-call log: archives a record of each MCP tool call into Git.
+//!
+//! With [`ToolCallLogConfig::enabled`], every tool call is appended as one
+//! JSON line to a daily file next to the entities it touched:
+//!
+//! | Caller | File |
+//! |--------|------|
+//! | Known project | `projects/<slug>/tool_calls/<YYYY>/<MM>/<DD>.jsonl` |
+//! | No project | `tool_calls/<YYYY>/<MM>/<DD>.jsonl` |
+//!
+//! Records are redacted: they carry the tool name, the calling project and
+//! agent, the duration and the result status, never the arguments or the
+//! result. Together with the entity files this gives a timeline of agent
+//! activity from the archive alone.
+//!
+//! Records are queued for a background task that commits them in batches,
+//! once `batch_size` records are buffered or `flush_interval_seconds` after
+//! the oldest one, so the archive gets one commit per batch rather than one
+//! per call. While the queue is full, new records are dropped and a warning
+//! is logged.
+
+use crate::Result;
+use crate::store::git_store::{self, PendingArchiveCommit};
+use crate::store::repo_cache::RepoCache;
+use chrono::{DateTime, Utc};
+use mouchak_mail_common::config::ToolCallLogConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+/// Records waiting to be committed before new ones are dropped.
+const QUEUE_CAPACITY: usize = 4096;
+
+/// A redacted record of one tool call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    /// When the call finished
+    pub ts: DateTime<Utc>,
+    pub tool: String,
+    /// Project the call was made in, if it exists
+    pub project_slug: Option<String>,
+    /// Calling agent, if it is registered in the project
+    pub agent_name: Option<String>,
+    pub duration_ms: u64,
+    /// `success` or `error`
+    pub status: String,
+    /// MCP error code of a failed call
+    pub error_code: Option<String>,
+}
+
+impl ToolCallRecord {
+    /// Archive path of the daily file this record belongs to.
+    pub fn archive_path(&self) -> PathBuf {
+        let day = PathBuf::from(self.ts.format("%Y").to_string())
+            .join(self.ts.format("%m").to_string())
+            .join(format!("{}.jsonl", self.ts.format("%d")));
+        match &self.project_slug {
+            Some(slug) => PathBuf::from("projects")
+                .join(slug)
+                .join("tool_calls")
+                .join(day),
+            None => PathBuf::from("tool_calls").join(day),
+        }
+    }
+}
+
+enum Command {
+    Record(ToolCallRecord),
+    Flush(oneshot::Sender<Result<usize>>),
+}
+
+/// Where batches are committed.
+struct Archive {
+    repo_root: PathBuf,
+    repo_cache: Arc<RepoCache>,
+    git_lock: Arc<Mutex<()>>,
+}
+
+/// Handle to the background writer.
+#[derive(Debug, Clone)]
+pub struct ToolCallLog {
+    queue: mpsc::Sender<Command>,
+    /// Set while records are being dropped, so the warning is logged once
+    overflowing: Arc<AtomicBool>,
+}
+
+impl ToolCallLog {
+    /// Starts the writer for `config`, or returns `None` when the log is
+    /// disabled. Must be called within a Tokio runtime when enabled.
+    pub(crate) fn start(
+        config: &ToolCallLogConfig,
+        repo_root: PathBuf,
+        repo_cache: Arc<RepoCache>,
+        git_lock: Arc<Mutex<()>>,
+    ) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        info!(
+            "Archiving tool calls in batches of {} (at most {}s apart)",
+            config.batch_size, config.flush_interval_seconds
+        );
+
+        let (queue, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let archive = Archive {
+            repo_root,
+            repo_cache,
+            git_lock,
+        };
+        tokio::spawn(run(
+            archive,
+            rx,
+            config.batch_size.max(1),
+            Duration::from_secs(config.flush_interval_seconds.max(1)),
+        ));
+        Some(Self {
+            queue,
+            overflowing: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Queues `record` for the next batch.
+    pub fn record(&self, record: ToolCallRecord) {
+        match self.queue.try_send(Command::Record(record)) {
+            Ok(()) => self.overflowing.store(false, Ordering::Relaxed),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                if !self.overflowing.swap(true, Ordering::Relaxed) {
+                    warn!("Tool-call log queue is full; dropping records until it drains");
+                }
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                debug!("Tool-call log stopped; dropping record")
+            }
+        }
+    }
+
+    /// Commits the buffered records now.
+    ///
+    /// # Returns
+    /// Number of records committed.
+    pub async fn flush(&self) -> Result<usize> {
+        let (tx, rx) = oneshot::channel();
+        if self.queue.send(Command::Flush(tx)).await.is_err() {
+            return Ok(0);
+        }
+        rx.await.unwrap_or(Ok(0))
+    }
+}
+
+async fn run(
+    archive: Archive,
+    mut rx: mpsc::Receiver<Command>,
+    batch_size: usize,
+    flush_interval: Duration,
+) {
+    let mut buffer: Vec<ToolCallRecord> = Vec::new();
+    let mut deadline: Option<tokio::time::Instant> = None;
+    loop {
+        let command = match deadline {
+            Some(at) => match tokio::time::timeout_at(at, rx.recv()).await {
+                Ok(command) => command,
+                Err(_) => {
+                    commit_logged(&archive, &mut buffer).await;
+                    deadline = None;
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+        match command {
+            Some(Command::Record(record)) => {
+                buffer.push(record);
+                deadline.get_or_insert_with(|| tokio::time::Instant::now() + flush_interval);
+                if buffer.len() >= batch_size {
+                    commit_logged(&archive, &mut buffer).await;
+                    deadline = None;
+                }
+            }
+            Some(Command::Flush(reply)) => {
+                let _ = reply.send(commit(&archive, &mut buffer).await);
+                deadline = None;
+            }
+            None => {
+                commit_logged(&archive, &mut buffer).await;
+                return;
+            }
+        }
+    }
+}
+
+async fn commit_logged(archive: &Archive, buffer: &mut Vec<ToolCallRecord>) {
+    if let Err(e) = commit(archive, buffer).await {
+        warn!("Failed to archive tool calls: {}", e);
+    }
+}
+
+/// Appends the buffered records to their daily files in one commit.
+///
+/// The buffer is emptied even when the commit fails, so one bad batch
+/// cannot block the ones after it.
+async fn commit(archive: &Archive, buffer: &mut Vec<ToolCallRecord>) -> Result<usize> {
+    if buffer.is_empty() {
+        return Ok(0);
+    }
+    let records = std::mem::take(buffer);
+    let _pending = PendingArchiveCommit::new();
+
+    let mut files: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for record in &records {
+        let line = serde_json::to_string(record)?;
+        let lines = files.entry(record.archive_path()).or_default();
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+
+    let _git_guard = archive.git_lock.lock().await;
+    let repo_arc = archive.repo_cache.get(&archive.repo_root).await?;
+    let repo = repo_arc.lock().await;
+    let workdir = repo
+        .workdir()
+        .ok_or(crate::Error::InvalidInput("No workdir".into()))?;
+    for (path, lines) in &files {
+        let full_path = workdir.join(path);
+        if let Some(parent) = full_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&full_path)?;
+        file.write_all(lines.as_bytes())?;
+    }
+
+    let paths: Vec<&PathBuf> = files.keys().collect();
+    git_store::commit_paths(
+        &repo,
+        &paths,
+        &format!("tool-calls: batch of {}", records.len()),
+        "mcp-bot",
+        "mcp-bot@localhost",
+    )?;
+    debug!("Archived {} tool calls", records.len());
+    Ok(records.len())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(project_slug: Option<&str>) -> ToolCallRecord {
+        ToolCallRecord {
+            ts: Utc.with_ymd_and_hms(2026, 3, 7, 12, 0, 0).unwrap(),
+            tool: "send_message".to_string(),
+            project_slug: project_slug.map(str::to_string),
+            agent_name: None,
+            duration_ms: 12,
+            status: "success".to_string(),
+            error_code: None,
+        }
+    }
+
+    #[test]
+    fn test_archive_path() {
+        assert_eq!(
+            record(Some("backend")).archive_path(),
+            PathBuf::from("projects/backend/tool_calls/2026/03/07.jsonl")
+        );
+        assert_eq!(
+            record(None).archive_path(),
+            PathBuf::from("tool_calls/2026/03/07.jsonl")
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! ULID generation for globally unique, time-ordered identifiers.
+//!
+//! A ULID is 128 bits written as 26 Crockford base32 characters: a 48-bit
+//! millisecond timestamp followed by 80 random bits. ULIDs sort by creation
+//! time as plain strings and do not collide across projects or databases.
+
+use uuid::Uuid;
+
+/// Crockford base32 alphabet (no I, L, O or U).
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Length of an encoded ULID.
+pub const ULID_LEN: usize = 26;
+
+/// Generates a new ULID for the current time.
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::ulid::{is_ulid, new_ulid};
+///
+/// let id = new_ulid();
+/// assert!(is_ulid(&id));
+/// ```
+pub fn new_ulid() -> String {
+    let millis = u64::try_from(chrono::Utc::now().timestamp_millis()).unwrap_or(0);
+    encode(millis, Uuid::new_v4().as_u128())
+}
+
+/// Encodes a ULID from a millisecond timestamp and random bits; only the low
+/// 48 bits of `millis` and the low 80 bits of `random` are used.
+fn encode(millis: u64, random: u128) -> String {
+    let value = (u128::from(millis & 0xFFFF_FFFF_FFFF) << 80) | (random & ((1u128 << 80) - 1));
+    (0..ULID_LEN)
+        .rev()
+        .map(|i| {
+            let digit = (value >> (i * 5)) & 31;
+            char::from(ALPHABET.get(digit as usize).copied().unwrap_or(b'0'))
+        })
+        .collect()
+}
+
+/// Returns `true` if `s` is a well-formed ULID (26 uppercase Crockford
+/// base32 characters, first one at most `7`).
+///
+/// # Examples
+///
+/// ```
+/// use mouchak_mail_core::utils::ulid::is_ulid;
+///
+/// assert!(is_ulid("01HZX3T6Q2V8W4K9M5N7P0R1S2"));
+/// assert!(!is_ulid("T-CROSS-PROJECT"));
+/// ```
+pub fn is_ulid(s: &str) -> bool {
+    s.len() == ULID_LEN
+        && s.starts_with(|c: char| ('0'..='7').contains(&c))
+        && s.bytes().all(|b| ALPHABET.contains(&b))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_orders_by_time() {
+        let earlier = encode(1_700_000_000_000, u128::MAX);
+        let later = encode(1_700_000_000_001, 0);
+        assert!(is_ulid(&earlier));
+        assert!(is_ulid(&later));
+        assert!(earlier < later);
+        assert_eq!(encode(0, 0), "0".repeat(ULID_LEN));
+    }
+}
@@ -18,7 +18,7 @@ use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
 use mouchak_mail_core::model::tool_metric::{ToolMetricBmc, ToolMetricForCreate};
-use mouchak_mail_core::types::ProjectId;
+use mouchak_mail_core::types::{AgentId, ProjectId};
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to set up a project with agent
@@ -70,9 +70,9 @@ async fn test_list_activity_with_messages() {
 
     // Send a message
     let msg = MessageForCreate {
-        project_id,
-        sender_id: agent_id,
-        recipient_ids: vec![agent2_id],
+        project_id: project_id.into(),
+        sender_id: agent_id.into(),
+        recipient_ids: vec![AgentId::new(agent2_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Test Activity Message".to_string(),
@@ -87,7 +87,7 @@ async fn test_list_activity_with_messages() {
         .expect("Failed to create message");
 
     // List activity
-    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id, 10)
+    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, ProjectId::new(project_id), 10)
         .await
         .expect("Failed to list activity");
 
@@ -125,7 +125,7 @@ async fn test_list_activity_with_tool_metrics() {
     }
 
     // List activity
-    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id, 10)
+    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, ProjectId::new(project_id), 10)
         .await
         .expect("Failed to list activity");
 
@@ -163,7 +163,7 @@ async fn test_list_activity_with_agents() {
     }
 
     // List activity
-    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id.get(), 10)
+    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id, 10)
         .await
         .expect("Failed to list activity");
 
@@ -198,7 +198,7 @@ async fn test_list_activity_with_limit() {
     }
 
     // List with limit of 5
-    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id, 5)
+    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, ProjectId::new(project_id), 5)
         .await
         .expect("Failed to list activity");
 
@@ -228,7 +228,7 @@ async fn test_activity_item_structure() {
         .await
         .expect("Failed to create tool metric");
 
-    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id, 10)
+    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, ProjectId::new(project_id), 10)
         .await
         .expect("Failed to list activity");
 
@@ -265,7 +265,7 @@ async fn test_empty_activity() {
 
     // No agents, messages, or metrics created
 
-    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id.get(), 10)
+    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id, 10)
         .await
         .expect("Failed to list activity");
 
@@ -298,7 +298,7 @@ async fn test_activity_sorted_by_time() {
             .expect("Failed to create tool metric");
     }
 
-    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, project_id, 10)
+    let activity = ActivityBmc::list_recent(&tc.ctx, &tc.mm, ProjectId::new(project_id), 10)
         .await
         .expect("Failed to list activity");
 
@@ -16,7 +16,7 @@ use crate::common::TestContext;
 use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::agent_link::{AgentLinkBmc, AgentLinkForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
-use mouchak_mail_core::types::ProjectId;
+use mouchak_mail_core::types::{AgentId, ProjectId};
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to set up a project with two agents
@@ -105,9 +105,10 @@ async fn test_accept_contact() {
         .expect("Failed to accept contact");
 
     // Verify A can see B in contacts
-    let contacts = AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id.get(), agent_a_id)
-        .await
-        .expect("Failed to list contacts");
+    let contacts =
+        AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id, AgentId::new(agent_a_id))
+            .await
+            .expect("Failed to list contacts");
 
     assert_eq!(contacts.len(), 1, "Agent A should have 1 contact");
     assert_eq!(contacts[0].status, "accepted");
@@ -141,9 +142,10 @@ async fn test_reject_contact() {
         .expect("Failed to reject contact");
 
     // Verify A has no contacts (rejected links don't show in contacts)
-    let contacts = AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id.get(), agent_a_id)
-        .await
-        .expect("Failed to list contacts");
+    let contacts =
+        AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id, AgentId::new(agent_a_id))
+            .await
+            .expect("Failed to list contacts");
 
     assert!(
         contacts.is_empty(),
@@ -175,7 +177,7 @@ async fn test_list_pending_requests() {
 
     // Agent B checks pending requests
     let pending =
-        AgentLinkBmc::list_pending_requests(&tc.ctx, &tc.mm, project_id.get(), agent_b_id)
+        AgentLinkBmc::list_pending_requests(&tc.ctx, &tc.mm, project_id, AgentId::new(agent_b_id))
             .await
             .expect("Failed to list pending requests");
 
@@ -212,13 +214,15 @@ async fn test_bidirectional_contacts() {
         .expect("Failed to accept contact");
 
     // Both agents should see the contact
-    let a_contacts = AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id.get(), agent_a_id)
-        .await
-        .expect("Failed to list A's contacts");
+    let a_contacts =
+        AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id, AgentId::new(agent_a_id))
+            .await
+            .expect("Failed to list A's contacts");
 
-    let b_contacts = AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id.get(), agent_b_id)
-        .await
-        .expect("Failed to list B's contacts");
+    let b_contacts =
+        AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id, AgentId::new(agent_b_id))
+            .await
+            .expect("Failed to list B's contacts");
 
     assert_eq!(a_contacts.len(), 1, "Agent A should see the contact");
     assert_eq!(b_contacts.len(), 1, "Agent B should see the contact");
@@ -276,10 +280,9 @@ async fn test_multiple_pending_requests() {
     }
 
     // Target should have 3 pending requests
-    let pending =
-        AgentLinkBmc::list_pending_requests(&tc.ctx, &tc.mm, project_id.get(), target_id.into())
-            .await
-            .expect("Failed to list pending requests");
+    let pending = AgentLinkBmc::list_pending_requests(&tc.ctx, &tc.mm, project_id, target_id)
+        .await
+        .expect("Failed to list pending requests");
 
     assert_eq!(pending.len(), 3, "Target should have 3 pending requests");
 }
@@ -293,9 +296,10 @@ async fn test_empty_contacts() {
 
     let (project_id, agent_a_id, _) = setup_project_with_agents(&tc, "empty").await;
 
-    let contacts = AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id.get(), agent_a_id)
-        .await
-        .expect("Failed to list contacts");
+    let contacts =
+        AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, project_id, AgentId::new(agent_a_id))
+            .await
+            .expect("Failed to list contacts");
 
     assert!(contacts.is_empty(), "New agent should have no contacts");
 }
@@ -14,9 +14,14 @@ mod common;
 
 use crate::common::TestContext;
 use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::label::{LabelBmc, LabelForCreate};
+use mouchak_mail_core::model::listing::{ListQuery, ListSort};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::thread_read::ThreadReadBmc;
+use mouchak_mail_core::model::thread_watcher::ThreadWatcherBmc;
 use mouchak_mail_core::utils::slugify;
-use mouchak_mail_core::{AgentId, ProjectId};
+use mouchak_mail_core::{AgentId, MessageId, ProjectId};
 
 /// Helper to create a test project
 async fn create_test_project(tc: &TestContext, name: &str) -> ProjectId {
@@ -139,6 +144,112 @@ async fn test_list_agents_for_project() {
     assert_eq!(agents.len(), 3, "Should have 3 agents");
 }
 
+/// Test paging, filtering and sorting agents
+#[tokio::test]
+async fn test_list_agents_page() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let project_id = create_test_project(&tc, "page-agents").await;
+
+    for (name, program, model) in [
+        ("AmberFalcon", "claude-code", "opus"),
+        ("BlueHarbor", "codex", "gpt-5"),
+        ("CoralRiver", "claude-code", "sonnet"),
+        ("DarkStone", "claude-code", "opus"),
+    ] {
+        let agent_c = AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: program.to_string(),
+            model: model.to_string(),
+            task_description: "Paging test".to_string(),
+        };
+        AgentBmc::create(&tc.ctx, &tc.mm, agent_c).await.unwrap();
+    }
+    // Only CoralRiver has been active recently
+    let db = tc.mm.db_for_test();
+    db.execute(
+        "UPDATE agents SET last_active_ts = datetime('now', '-3 days') WHERE project_id = ?",
+        [project_id.get()],
+    )
+    .await
+    .unwrap();
+    db.execute(
+        "UPDATE agents SET last_active_ts = datetime('now', '-5 minutes') WHERE name = 'CoralRiver'",
+        (),
+    )
+    .await
+    .unwrap();
+
+    // Pages follow the name order and hand over with the cursor
+    let query = ListQuery {
+        limit: Some(3),
+        ..Default::default()
+    };
+    let first = AgentBmc::list_page(&tc.ctx, &tc.mm, project_id, &query)
+        .await
+        .unwrap();
+    assert_eq!(first.total, 4);
+    let names: Vec<_> = first.items.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["AmberFalcon", "BlueHarbor", "CoralRiver"]);
+
+    let query = ListQuery {
+        cursor: first.next_cursor,
+        ..query
+    };
+    let second = AgentBmc::list_page(&tc.ctx, &tc.mm, project_id, &query)
+        .await
+        .unwrap();
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.items[0].name, "DarkStone");
+    assert!(second.next_cursor.is_none());
+
+    // Filters combine and match case-insensitively
+    let query = ListQuery {
+        program: Some("Claude-Code".to_string()),
+        model: Some("opus".to_string()),
+        ..Default::default()
+    };
+    let page = AgentBmc::list_page(&tc.ctx, &tc.mm, project_id, &query)
+        .await
+        .unwrap();
+    let names: Vec<_> = page.items.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["AmberFalcon", "DarkStone"]);
+
+    let query = ListQuery {
+        active_within: Some("1h".to_string()),
+        ..Default::default()
+    };
+    let page = AgentBmc::list_page(&tc.ctx, &tc.mm, project_id, &query)
+        .await
+        .unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0].name, "CoralRiver");
+
+    let query = ListQuery {
+        sort: Some(ListSort::LastActive),
+        limit: Some(1),
+        ..Default::default()
+    };
+    let page = AgentBmc::list_page(&tc.ctx, &tc.mm, project_id, &query)
+        .await
+        .unwrap();
+    assert_eq!(page.items[0].name, "CoralRiver");
+    assert_eq!(page.next_cursor.as_deref(), Some("1"));
+
+    let query = ListQuery {
+        cursor: Some("not-a-cursor".to_string()),
+        ..Default::default()
+    };
+    assert!(
+        AgentBmc::list_page(&tc.ctx, &tc.mm, project_id, &query)
+            .await
+            .is_err()
+    );
+}
+
 /// Test agent not found error
 #[tokio::test]
 async fn test_agent_not_found() {
@@ -171,9 +282,9 @@ async fn test_delete_agent_cascade() {
         .expect("Failed to create agent");
 
     let msg = mouchak_mail_core::model::message::MessageForCreate {
-        project_id: project_id.get(),
-        sender_id: agent_id.get(),
-        recipient_ids: vec![agent_id.get()],
+        project_id,
+        sender_id: agent_id,
+        recipient_ids: vec![agent_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "From doomed agent".into(),
@@ -200,6 +311,89 @@ async fn test_delete_agent_cascade() {
     );
 }
 
+/// Deleting an agent clears what it kept in threads and keeps the labels it
+/// applied on other agents' messages
+#[tokio::test]
+async fn test_delete_agent_with_thread_state() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let project_id = create_test_project(&tc, "delete-thread-state").await;
+    let mut agents = Vec::new();
+    for name in ["Sender", "Reader"] {
+        let agent = AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: String::new(),
+        };
+        agents.push(AgentBmc::create(&tc.ctx, &tc.mm, agent).await.unwrap());
+    }
+    let (sender, reader) = (agents[0], agents[1]);
+
+    let msg = MessageForCreate {
+        project_id,
+        sender_id: sender,
+        recipient_ids: vec![reader],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Deploy plan".into(),
+        body_md: "Friday".into(),
+        thread_id: Some("T-deploy".into()),
+        importance: None,
+        ack_required: false,
+    };
+    let message_id = MessageBmc::create(&tc.ctx, &tc.mm, msg).await.unwrap();
+    ThreadReadBmc::mark_read(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        reader,
+        "T-deploy",
+        MessageId(message_id.get()),
+    )
+    .await
+    .unwrap();
+    ThreadWatcherBmc::watch(&tc.ctx, &tc.mm, project_id, "T-deploy", reader)
+        .await
+        .unwrap();
+    LabelBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        LabelForCreate {
+            project_id,
+            name: "release".into(),
+            color: None,
+            description: None,
+        },
+    )
+    .await
+    .unwrap();
+    LabelBmc::apply(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        message_id,
+        "release",
+        Some((reader, "Reader")),
+    )
+    .await
+    .unwrap();
+
+    AgentBmc::delete(&tc.ctx, &tc.mm, reader)
+        .await
+        .expect("Failed to delete agent");
+
+    assert!(AgentBmc::get(&tc.ctx, &tc.mm, reader).await.is_err());
+    assert_eq!(
+        LabelBmc::names_for_message(&tc.ctx, &tc.mm, message_id)
+            .await
+            .unwrap(),
+        vec!["release"]
+    );
+}
+
 #[tokio::test]
 async fn test_delete_nonexistent_agent() {
     let tc = TestContext::new()
@@ -305,9 +499,9 @@ async fn test_count_messages_sent() {
     assert_eq!(count_before, 0);
 
     let msg = mouchak_mail_core::model::message::MessageForCreate {
-        project_id: project_id.get(),
-        sender_id: agent_id.get(),
-        recipient_ids: vec![agent_id.get()],
+        project_id,
+        sender_id: agent_id,
+        recipient_ids: vec![agent_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Test message".into(),
@@ -358,9 +552,9 @@ async fn test_count_messages_received() {
     assert_eq!(count_before, 0);
 
     let msg = mouchak_mail_core::model::message::MessageForCreate {
-        project_id: project_id.get(),
-        sender_id: sender_id.get(),
-        recipient_ids: vec![receiver_id.get()],
+        project_id,
+        sender_id,
+        recipient_ids: vec![receiver_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "For receiver".into(),
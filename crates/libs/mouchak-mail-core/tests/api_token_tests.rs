@@ -0,0 +1,209 @@
+//! API token tests
+//!
+//! Tests that per-agent API tokens are stored hashed, authenticate to their
+//! agent and scopes, keep working while the server is read-only, and stop
+//! working once revoked or expired.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::{Duration, Utc};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::api_token::{ApiTokenBmc, ApiTokenForCreate, TOKEN_PREFIX};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::AgentId;
+use mouchak_mail_core::utils::TS_FORMAT;
+
+fn token_for(agent_id: AgentId, scopes: &[&str]) -> ApiTokenForCreate {
+    ApiTokenForCreate {
+        agent_id,
+        name: "ci".to_string(),
+        scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        expires_ts: None,
+    }
+}
+
+#[tokio::test]
+async fn test_token_authenticates_until_revoked() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "tokens", "/tokens")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+
+    let issued = ApiTokenBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        token_for(alice, &["write:messages", "Read:Inbox", "read:inbox"]),
+    )
+    .await
+    .unwrap();
+    assert!(issued.secret.starts_with(TOKEN_PREFIX));
+    assert!(issued.secret.starts_with(&issued.token.token_prefix));
+    assert_eq!(issued.token.scopes, ["read:inbox", "write:messages"]);
+    assert_eq!(issued.token.agent_name, "alice");
+    assert_eq!(issued.token.project_slug, "tokens");
+
+    // Only the hash is stored
+    let db = tc.mm.db_for_test();
+    let mut rows = db
+        .query(
+            "SELECT token_hash FROM api_tokens WHERE id = ?",
+            [issued.token.id],
+        )
+        .await
+        .unwrap();
+    let hash: String = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_ne!(hash, issued.secret);
+
+    let token = ApiTokenBmc::authenticate(&tc.ctx, &tc.mm, &issued.secret)
+        .await
+        .unwrap()
+        .expect("token authenticates");
+    assert_eq!(token.agent_id, alice);
+    assert!(token.last_used_ts.is_some());
+    assert!(token.allows("read:inbox"));
+
+    // Use is recorded to the minute, not on every request
+    for (age, rewritten) in [(Duration::seconds(20), false), (Duration::minutes(5), true)] {
+        let used = (Utc::now() - age).naive_utc().format(TS_FORMAT).to_string();
+        db.execute(
+            "UPDATE api_tokens SET last_used_ts = ? WHERE id = ?",
+            (used.as_str(), issued.token.id),
+        )
+        .await
+        .unwrap();
+        let token = ApiTokenBmc::authenticate(&tc.ctx, &tc.mm, &issued.secret)
+            .await
+            .unwrap()
+            .expect("token authenticates");
+        let stored = token.last_used_ts.unwrap().format(TS_FORMAT).to_string();
+        assert_eq!(stored != used, rewritten);
+    }
+    assert!(token.allows("read:messages"));
+    assert!(!token.allows("admin:reservations"));
+
+    let unknown = format!("{}{}", TOKEN_PREFIX, "0".repeat(64));
+    assert!(
+        ApiTokenBmc::authenticate(&tc.ctx, &tc.mm, &unknown)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let revoked = ApiTokenBmc::revoke(&tc.ctx, &tc.mm, issued.token.id)
+        .await
+        .unwrap();
+    assert!(revoked.revoked_ts.is_some());
+    assert!(
+        ApiTokenBmc::authenticate(&tc.ctx, &tc.mm, &issued.secret)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(matches!(
+        ApiTokenBmc::revoke(&tc.ctx, &tc.mm, issued.token.id + 100).await,
+        Err(Error::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn test_token_authenticates_while_read_only() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "tokens", "/tokens")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let issued = ApiTokenBmc::create(&tc.ctx, &tc.mm, token_for(alice, &["read:inbox"]))
+        .await
+        .unwrap();
+
+    tc.mm.set_read_only(true).await.unwrap();
+    let token = ApiTokenBmc::authenticate(&tc.ctx, &tc.mm, &issued.secret)
+        .await
+        .unwrap()
+        .expect("token authenticates while read-only");
+    assert_eq!(token.agent_id, alice);
+    // Its use is not recorded
+    assert!(token.last_used_ts.is_none());
+
+    tc.mm.set_read_only(false).await.unwrap();
+    let token = ApiTokenBmc::authenticate(&tc.ctx, &tc.mm, &issued.secret)
+        .await
+        .unwrap()
+        .expect("token authenticates");
+    assert!(token.last_used_ts.is_some());
+}
+
+#[tokio::test]
+async fn test_token_expiry_and_listing() {
+    let tc = TestContext::new().await.unwrap();
+    let first = ProjectBmc::create(&tc.ctx, &tc.mm, "first", "/first")
+        .await
+        .unwrap();
+    let second = ProjectBmc::create(&tc.ctx, &tc.mm, "second", "/second")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, first, "alice").await;
+    let bob = create_agent(&tc, second, "bob").await;
+
+    let expiring = ApiTokenBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        ApiTokenForCreate {
+            expires_ts: Some(Utc::now().naive_utc() + Duration::hours(1)),
+            ..token_for(alice, &["admin"])
+        },
+    )
+    .await
+    .unwrap();
+    ApiTokenBmc::create(&tc.ctx, &tc.mm, token_for(bob, &["read:outbox"]))
+        .await
+        .unwrap();
+
+    tc.mm
+        .db_for_test()
+        .execute(
+            "UPDATE api_tokens SET expires_ts = datetime('now', '-1 minute') WHERE id = ?",
+            [expiring.token.id],
+        )
+        .await
+        .unwrap();
+    assert!(
+        ApiTokenBmc::authenticate(&tc.ctx, &tc.mm, &expiring.secret)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let all = ApiTokenBmc::list(&tc.ctx, &tc.mm, None).await.unwrap();
+    assert_eq!(all.len(), 2);
+    let in_first = ApiTokenBmc::list(&tc.ctx, &tc.mm, Some(first))
+        .await
+        .unwrap();
+    assert_eq!(in_first.len(), 1);
+    assert_eq!(in_first[0].agent_name, "alice");
+
+    // Invalid requests
+    let no_scopes = ApiTokenBmc::create(&tc.ctx, &tc.mm, token_for(alice, &[])).await;
+    assert!(matches!(no_scopes, Err(Error::InvalidInput(_))));
+    let bad_scope = ApiTokenBmc::create(&tc.ctx, &tc.mm, token_for(alice, &["read:files"])).await;
+    assert!(matches!(bad_scope, Err(Error::InvalidInput(_))));
+    let past = ApiTokenBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        ApiTokenForCreate {
+            expires_ts: Some(Utc::now().naive_utc() - Duration::hours(1)),
+            ..token_for(alice, &["admin"])
+        },
+    )
+    .await;
+    assert!(matches!(past, Err(Error::InvalidInput(_))));
+    let unknown_agent =
+        ApiTokenBmc::create(&tc.ctx, &tc.mm, token_for(AgentId::new(9999), &["admin"])).await;
+    assert!(matches!(unknown_agent, Err(Error::NotFound)));
+}
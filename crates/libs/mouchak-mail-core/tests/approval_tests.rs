@@ -0,0 +1,292 @@
+//! Approval tests
+//!
+//! Tests that messages matching a project's approval rules reach no inbox
+//! until an allowed approver decides, and that undecided ones time out.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::approval::{
+    ApprovalBmc, ApprovalRuleForCreate, HUMAN_DECIDER, TIMEOUT_DECIDER,
+};
+use mouchak_mail_core::model::event_log::EventLogBmc;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: &[AgentId],
+    subject: &str,
+    importance: Option<&str>,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: to.to_vec(),
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.to_string(),
+            body_md: "See the runbook".to_string(),
+            thread_id: None,
+            importance: importance.map(str::to_string),
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn inbox_ids(tc: &TestContext, project_id: ProjectId, agent: AgentId) -> Vec<i64> {
+    MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, agent, 10)
+        .await
+        .unwrap()
+        .iter()
+        .map(|m| m.id)
+        .collect()
+}
+
+/// Messages announced with a `message.sent` event.
+async fn announced(tc: &TestContext) -> Vec<MessageId> {
+    EventLogBmc::list(&tc.ctx, &tc.mm, 0, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|e| e.kind == "message.sent")
+        .map(|e| MessageId::new(e.payload["message_id"].as_i64().unwrap()))
+        .collect()
+}
+
+fn keyword_rule(project_id: ProjectId, approvers: &[&str]) -> ApprovalRuleForCreate {
+    ApprovalRuleForCreate {
+        project_id,
+        min_importance: None,
+        min_recipients: None,
+        keyword: Some("deploy".to_string()),
+        approvers: approvers.iter().map(|a| a.to_string()).collect(),
+        timeout_seconds: 3600,
+    }
+}
+
+#[tokio::test]
+async fn test_held_message_delivered_on_approval() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "approve", "/approve")
+        .await
+        .unwrap();
+    let worker = create_agent(&tc, project_id, "worker").await;
+    let ops = create_agent(&tc, project_id, "ops").await;
+    let lead = create_agent(&tc, project_id, "lead").await;
+    let other = create_agent(&tc, project_id, "other").await;
+    ApprovalBmc::create_rule(&tc.ctx, &tc.mm, keyword_rule(project_id, &["lead"]))
+        .await
+        .unwrap();
+
+    let plain = send(&tc, project_id, worker, &[ops], "Status", None).await;
+    let held = send(&tc, project_id, worker, &[ops], "Deploy v2 tonight", None).await;
+    assert_eq!(inbox_ids(&tc, project_id, ops).await, [plain.get()]);
+    let pending = ApprovalBmc::list_pending(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].message_id, held);
+    assert_eq!(pending[0].sender_name, "worker");
+    assert_eq!(pending[0].approvers, ["lead"]);
+
+    // Neither the sender nor agents outside the approver list may decide
+    let own = ApprovalBmc::decide(&tc.ctx, &tc.mm, held, Some(worker), true, None).await;
+    assert!(matches!(own, Err(Error::ApprovalDenied(_))));
+    let outsider = ApprovalBmc::decide(&tc.ctx, &tc.mm, held, Some(other), true, None).await;
+    assert!(matches!(outsider, Err(Error::ApprovalDenied(_))));
+
+    let decided = ApprovalBmc::decide(&tc.ctx, &tc.mm, held, Some(lead), true, Some("Planned"))
+        .await
+        .unwrap();
+    assert_eq!(decided.status, "approved");
+    assert_eq!(decided.decided_by.as_deref(), Some("lead"));
+    assert_eq!(decided.reason.as_deref(), Some("Planned"));
+    let mut ids = inbox_ids(&tc, project_id, ops).await;
+    ids.sort_unstable();
+    assert_eq!(ids, [plain.get(), held.get()]);
+    assert!(
+        ApprovalBmc::list_pending(&tc.ctx, &tc.mm, project_id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let again = ApprovalBmc::decide(&tc.ctx, &tc.mm, held, Some(lead), false, None).await;
+    assert!(matches!(again, Err(Error::InvalidInput(_))));
+    let never_held = ApprovalBmc::decide(&tc.ctx, &tc.mm, plain, Some(lead), true, None).await;
+    assert!(matches!(never_held, Err(Error::NotFound)));
+
+    let kinds: Vec<String> = EventLogBmc::list(&tc.ctx, &tc.mm, 0, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.kind)
+        .collect();
+    assert!(kinds.iter().any(|k| k == "message.held"));
+    assert!(kinds.iter().any(|k| k == "message.approved"));
+}
+
+#[tokio::test]
+async fn test_held_message_announced_only_when_delivered() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "announce", "/announce")
+        .await
+        .unwrap();
+    let worker = create_agent(&tc, project_id, "worker").await;
+    let ops = create_agent(&tc, project_id, "ops").await;
+    ApprovalBmc::create_rule(&tc.ctx, &tc.mm, keyword_rule(project_id, &[]))
+        .await
+        .unwrap();
+
+    let plain = send(&tc, project_id, worker, &[ops], "Status", None).await;
+    let approved = send(&tc, project_id, worker, &[ops], "Deploy v2", Some("urgent")).await;
+    let rejected = send(&tc, project_id, worker, &[ops], "Deploy v3", None).await;
+    assert_eq!(announced(&tc).await, [plain]);
+
+    ApprovalBmc::decide(&tc.ctx, &tc.mm, rejected, Some(ops), false, None)
+        .await
+        .unwrap();
+    ApprovalBmc::decide(&tc.ctx, &tc.mm, approved, Some(ops), true, None)
+        .await
+        .unwrap();
+    assert_eq!(announced(&tc).await, [plain, approved]);
+
+    let event = EventLogBmc::list(&tc.ctx, &tc.mm, 0, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .rfind(|e| e.kind == "message.sent")
+        .unwrap();
+    assert_eq!(event.actor.as_deref(), Some("worker"));
+    assert_eq!(event.payload["recipients"], serde_json::json!(["ops"]));
+    assert_eq!(event.payload["importance"], "urgent");
+}
+
+#[tokio::test]
+async fn test_rejected_and_timed_out_messages_are_dropped() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "reject", "/reject")
+        .await
+        .unwrap();
+    let worker = create_agent(&tc, project_id, "worker").await;
+    let ops = create_agent(&tc, project_id, "ops").await;
+    let qa = create_agent(&tc, project_id, "qa").await;
+    ApprovalBmc::create_rule(
+        &tc.ctx,
+        &tc.mm,
+        ApprovalRuleForCreate {
+            project_id,
+            min_importance: Some("urgent".to_string()),
+            min_recipients: Some(2),
+            keyword: None,
+            approvers: Vec::new(),
+            timeout_seconds: 1,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Both conditions must hold
+    let one = send(&tc, project_id, worker, &[ops], "Outage", Some("urgent")).await;
+    let high = send(&tc, project_id, worker, &[ops, qa], "Outage", Some("high")).await;
+    let rejected = send(
+        &tc,
+        project_id,
+        worker,
+        &[ops, qa],
+        "Outage",
+        Some("urgent"),
+    )
+    .await;
+    let expired = send(
+        &tc,
+        project_id,
+        worker,
+        &[ops, qa],
+        "Outage",
+        Some("urgent"),
+    )
+    .await;
+    assert_eq!(inbox_ids(&tc, project_id, qa).await, [high.get()]);
+    assert_eq!(
+        ApprovalBmc::list_pending(&tc.ctx, &tc.mm, project_id)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Without named approvers a human operator may decide too
+    let decided = ApprovalBmc::decide(&tc.ctx, &tc.mm, rejected, None, false, None)
+        .await
+        .unwrap();
+    assert_eq!(decided.status, "rejected");
+    assert_eq!(decided.decided_by.as_deref(), Some(HUMAN_DECIDER));
+
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    assert_eq!(ApprovalBmc::expire_due(&tc.ctx, &tc.mm).await.unwrap(), 1);
+    assert_eq!(ApprovalBmc::expire_due(&tc.ctx, &tc.mm).await.unwrap(), 0);
+    let timed_out = ApprovalBmc::get(&tc.ctx, &tc.mm, expired)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(timed_out.status, "rejected");
+    assert_eq!(timed_out.decided_by.as_deref(), Some(TIMEOUT_DECIDER));
+
+    let mut ids = inbox_ids(&tc, project_id, ops).await;
+    ids.sort_unstable();
+    assert_eq!(ids, [one.get(), high.get()]);
+}
+
+#[tokio::test]
+async fn test_rule_validation() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "rules", "/rules")
+        .await
+        .unwrap();
+    create_agent(&tc, project_id, "lead").await;
+
+    let mut no_condition = keyword_rule(project_id, &[]);
+    no_condition.keyword = Some("  ".to_string());
+    let result = ApprovalBmc::create_rule(&tc.ctx, &tc.mm, no_condition).await;
+    assert!(matches!(result, Err(Error::InvalidInput(_))));
+
+    let result =
+        ApprovalBmc::create_rule(&tc.ctx, &tc.mm, keyword_rule(project_id, &["nobody"])).await;
+    assert!(matches!(result, Err(Error::InvalidInput(_))));
+
+    let mut bad_importance = keyword_rule(project_id, &[]);
+    bad_importance.min_importance = Some("critical".to_string());
+    let result = ApprovalBmc::create_rule(&tc.ctx, &tc.mm, bad_importance).await;
+    assert!(matches!(result, Err(Error::InvalidInput(_))));
+
+    let rule_id = ApprovalBmc::create_rule(&tc.ctx, &tc.mm, keyword_rule(project_id, &["lead"]))
+        .await
+        .unwrap();
+    let rules = ApprovalBmc::list_rules(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap();
+    assert_eq!(rules.len(), 1);
+    assert!(rules[0].matches("normal", 1, "Ready to DEPLOY?", ""));
+    assert!(!rules[0].matches("urgent", 9, "Status", "All green"));
+
+    ApprovalBmc::delete_rule(&tc.ctx, &tc.mm, project_id, rule_id)
+        .await
+        .unwrap();
+    let missing = ApprovalBmc::delete_rule(&tc.ctx, &tc.mm, project_id, rule_id).await;
+    assert!(matches!(missing, Err(Error::NotFound)));
+}
@@ -0,0 +1,100 @@
+//! Archive compaction tests
+//!
+//! Tests that `ArchiveGcBmc::gc` packs loose objects, honours its threshold
+//! and dry-run options, and leaves the archive readable and writable.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::archive_gc::{ArchiveGcBmc, GcOptions};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::store::git_store;
+use mouchak_mail_core::types::ProjectId;
+
+async fn register(tc: &TestContext, project_id: ProjectId, name: &str) {
+    AgentBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: String::new(),
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_gc_packs_loose_objects() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "archive", "/archive")
+        .await
+        .unwrap();
+    for name in ["BlueLake", "GreenCastle", "RedStone"] {
+        register(&tc, project_id, name).await;
+    }
+    let before = ArchiveGcBmc::stats(&tc.ctx, &tc.mm).await.unwrap();
+    assert!(before.loose_objects > 0);
+
+    // Below the threshold nothing runs
+    let report = ArchiveGcBmc::gc(
+        &tc.ctx,
+        &tc.mm,
+        &GcOptions {
+            min_loose_objects: before.loose_objects + 1,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!report.compacted);
+    assert!(report.skipped.unwrap().contains("below the threshold"));
+
+    let report = ArchiveGcBmc::gc(
+        &tc.ctx,
+        &tc.mm,
+        &GcOptions {
+            dry_run: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!report.compacted);
+    assert_eq!(report.after, before);
+
+    let report = ArchiveGcBmc::gc(&tc.ctx, &tc.mm, &GcOptions::default())
+        .await
+        .unwrap();
+    assert!(report.compacted);
+    assert_eq!(report.before, before);
+    assert!(report.after.loose_objects < before.loose_objects);
+    assert!(report.after.packs >= 1);
+    assert_eq!(
+        report.reclaimed_bytes,
+        before
+            .total_bytes()
+            .saturating_sub(report.after.total_bytes())
+    );
+
+    // The archive stays readable and accepts new commits
+    let repo = git_store::open_repo(tc.repo_root()).unwrap();
+    let profile =
+        git_store::read_file_content(&repo, "projects/archive/agents/BlueLake/profile.json")
+            .unwrap();
+    assert!(profile.contains("BlueLake"));
+    register(&tc, project_id, "PurpleHill").await;
+    let repo = git_store::open_repo(tc.repo_root()).unwrap();
+    assert!(
+        git_store::read_file_content(&repo, "projects/archive/agents/PurpleHill/profile.json")
+            .is_ok()
+    );
+}
@@ -13,10 +13,13 @@
 mod common;
 
 use crate::common::TestContext;
-use mouchak_mail_core::model::attachment::{AttachmentBmc, AttachmentForCreate};
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::model::attachment::{AttachmentBmc, AttachmentForCreate, AttachmentUpload};
+use mouchak_mail_core::model::attachment_blob::AttachmentBlobBmc;
 use mouchak_mail_core::model::project::ProjectBmc;
 use mouchak_mail_core::types::ProjectId;
 use mouchak_mail_core::utils::slugify;
+use tokio::io::AsyncReadExt;
 
 /// Helper to set up a project for attachment tests
 async fn setup_project(tc: &TestContext) -> ProjectId {
@@ -117,7 +120,7 @@ async fn test_list_by_project() {
             .expect("Failed to create attachment");
     }
 
-    let attachments = AttachmentBmc::list_by_project(&tc.ctx, &tc.mm, project_id.get())
+    let attachments = AttachmentBmc::list_by_project(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list attachments");
 
@@ -154,7 +157,7 @@ async fn test_empty_attachment_list() {
 
     let project_id = setup_project(&tc).await;
 
-    let attachments = AttachmentBmc::list_by_project(&tc.ctx, &tc.mm, project_id.get())
+    let attachments = AttachmentBmc::list_by_project(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list attachments");
 
@@ -201,3 +204,231 @@ async fn test_various_media_types() {
         assert_eq!(attachment.media_type, media_type);
     }
 }
+
+/// Where `new_for_test` stores attachment files
+fn attachments_root(tc: &TestContext) -> std::path::PathBuf {
+    tc.repo_root().parent().unwrap().join("attachments")
+}
+
+fn upload(project_id: ProjectId, filename: &str) -> AttachmentUpload {
+    AttachmentUpload {
+        project_id: project_id.get(),
+        agent_id: None,
+        filename: filename.to_string(),
+        media_type: "text/plain".to_string(),
+    }
+}
+
+/// Test streaming an attachment into the store and reading it back
+#[tokio::test]
+async fn test_store_and_open_attachment() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let project_id = setup_project(&tc).await;
+
+    let content = b"line one\nline two\n".to_vec();
+    let id = AttachmentBmc::store(
+        &tc.ctx,
+        &tc.mm,
+        upload(project_id, "notes.txt"),
+        Box::new(std::io::Cursor::new(content.clone())),
+    )
+    .await
+    .expect("Failed to store attachment");
+
+    let attachment = AttachmentBmc::get(&tc.ctx, &tc.mm, id).await.unwrap();
+    assert_eq!(attachment.filename, "notes.txt");
+    assert_eq!(attachment.size_bytes, content.len() as i64);
+    let hash = blake3::hash(&content).to_hex().to_string();
+    assert_eq!(attachment.content_hash.as_deref(), Some(hash.as_str()));
+    assert!(
+        attachment
+            .stored_path
+            .ends_with(&format!("blobs/{}/{}", &hash[..2], hash))
+    );
+    assert!(std::path::Path::new(&attachment.stored_path).starts_with(attachments_root(&tc)));
+
+    let mut reader = AttachmentBmc::open(&tc.ctx, &tc.mm, &attachment)
+        .await
+        .unwrap();
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read).await.unwrap();
+    assert_eq!(read, content);
+}
+
+/// Files under `dir`, recursively
+fn files_under(dir: &std::path::Path) -> Vec<String> {
+    let mut found = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return found;
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            found.extend(files_under(&path));
+        } else {
+            found.push(path.file_name().unwrap().to_string_lossy().into_owned());
+        }
+    }
+    found
+}
+
+/// Test that rejected uploads leave nothing behind but blobs for gc
+#[tokio::test]
+async fn test_store_attachment_rejections() {
+    let mut config = AppConfig::default();
+    config.attachments.max_bytes = 16;
+    config.quota.enabled = true;
+    config.quota.attachments_limit_bytes = 20;
+    let tc = TestContext::new_with_config(config)
+        .await
+        .expect("Failed to create test context");
+    let project_id = setup_project(&tc).await;
+    let store = |filename: &str, content: &[u8]| {
+        AttachmentBmc::store(
+            &tc.ctx,
+            &tc.mm,
+            upload(project_id, filename),
+            Box::new(std::io::Cursor::new(content.to_vec())),
+        )
+    };
+
+    let res = store("big.txt", &[b'x'; 17]).await;
+    assert!(
+        matches!(res, Err(mouchak_mail_core::Error::InvalidInput(_))),
+        "Expected InvalidInput, got {:?}",
+        res
+    );
+    for filename in ["../escape.txt", "dir/file.txt", ".hidden", ""] {
+        assert!(store(filename, b"x").await.is_err(), "{}", filename);
+    }
+
+    store("first.txt", &[b'x'; 16]).await.unwrap();
+    // Fits the size limit but not the project quota
+    let res = store("second.txt", &[b'x'; 8]).await;
+    assert!(
+        matches!(res, Err(mouchak_mail_core::Error::QuotaExceeded(_))),
+        "Expected QuotaExceeded, got {:?}",
+        res
+    );
+
+    let root = attachments_root(&tc);
+    assert!(files_under(&root.join("incoming")).is_empty());
+    // The rejected content waits for gc as an unreferenced blob
+    let files = files_under(&root.join("blobs"));
+    assert_eq!(files.len(), 2, "{:?}", files);
+    let report = AttachmentBlobBmc::gc(&tc.ctx, &tc.mm, 0, false)
+        .await
+        .unwrap();
+    assert_eq!((report.blobs, report.bytes), (1, 8));
+    assert_eq!(
+        files_under(&root.join("blobs")),
+        vec![blake3::hash(&[b'x'; 16]).to_hex().to_string()]
+    );
+}
+
+/// Test that identical content is stored once and reclaimed once unused
+#[tokio::test]
+async fn test_store_deduplicates_content() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let project_id = setup_project(&tc).await;
+    let mut attachments = Vec::new();
+    for (filename, content) in [("a.txt", "shared"), ("b.txt", "shared"), ("c.txt", "other")] {
+        let id = AttachmentBmc::store(
+            &tc.ctx,
+            &tc.mm,
+            upload(project_id, filename),
+            Box::new(std::io::Cursor::new(content.as_bytes().to_vec())),
+        )
+        .await
+        .unwrap();
+        attachments.push(AttachmentBmc::get(&tc.ctx, &tc.mm, id).await.unwrap());
+    }
+
+    assert_eq!(attachments[0].stored_path, attachments[1].stored_path);
+    assert_ne!(attachments[0].stored_path, attachments[2].stored_path);
+    assert_eq!(files_under(&attachments_root(&tc)).len(), 2);
+    let shared = blake3::hash(b"shared").to_hex().to_string();
+    let blob = AttachmentBlobBmc::get_by_hash(&tc.ctx, &tc.mm, &shared)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(blob.ref_count, 2);
+
+    // Still referenced by b.txt
+    AttachmentBmc::delete(&tc.ctx, &tc.mm, attachments[0].id)
+        .await
+        .unwrap();
+    let report = AttachmentBlobBmc::gc(&tc.ctx, &tc.mm, 0, false)
+        .await
+        .unwrap();
+    assert_eq!(report.blobs, 0);
+
+    AttachmentBmc::delete(&tc.ctx, &tc.mm, attachments[1].id)
+        .await
+        .unwrap();
+    // Within the grace period, and a dry run removes nothing
+    let report = AttachmentBlobBmc::gc(&tc.ctx, &tc.mm, 3600, false)
+        .await
+        .unwrap();
+    assert_eq!(report.blobs, 0);
+    let report = AttachmentBlobBmc::gc(&tc.ctx, &tc.mm, 0, true)
+        .await
+        .unwrap();
+    assert_eq!((report.blobs, report.bytes), (1, 6));
+    assert!(std::path::Path::new(&attachments[1].stored_path).exists());
+
+    let report = AttachmentBlobBmc::gc(&tc.ctx, &tc.mm, 0, false)
+        .await
+        .unwrap();
+    assert_eq!((report.blobs, report.bytes), (1, 6));
+    assert!(!std::path::Path::new(&attachments[1].stored_path).exists());
+    assert!(
+        AttachmentBlobBmc::get_by_hash(&tc.ctx, &tc.mm, &shared)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    // The other content is untouched
+    let mut reader = AttachmentBmc::open(&tc.ctx, &tc.mm, &attachments[2])
+        .await
+        .unwrap();
+    let mut read = String::new();
+    reader.read_to_string(&mut read).await.unwrap();
+    assert_eq!(read, "other");
+}
+
+/// Test that a missing file is reported as not found
+#[tokio::test]
+async fn test_open_missing_attachment() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let project_id = setup_project(&tc).await;
+
+    let id = AttachmentBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AttachmentForCreate {
+            project_id: project_id.get(),
+            agent_id: None,
+            filename: "gone.txt".to_string(),
+            stored_path: tc
+                .repo_root()
+                .join("gone.txt")
+                .to_string_lossy()
+                .into_owned(),
+            media_type: "text/plain".to_string(),
+            size_bytes: 4,
+        },
+    )
+    .await
+    .unwrap();
+    let attachment = AttachmentBmc::get(&tc.ctx, &tc.mm, id).await.unwrap();
+
+    let res = AttachmentBmc::open(&tc.ctx, &tc.mm, &attachment).await;
+    assert!(matches!(res, Err(mouchak_mail_core::Error::NotFound)));
+}
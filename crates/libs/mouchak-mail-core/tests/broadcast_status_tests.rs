@@ -0,0 +1,188 @@
+//! Broadcast status tests
+//!
+//! Tests for acknowledgment tracking of broadcast messages, per-recipient
+//! read receipts, and reminders to the recipients that have not acknowledged.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::broadcast_status::BroadcastStatusBmc;
+use mouchak_mail_core::model::escalation::{EscalationBmc, EscalationMode};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::receipt::ReceiptBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+struct Announcement {
+    tc: TestContext,
+    project_id: ProjectId,
+    /// lead, bob, carol, dave
+    agents: Vec<AgentId>,
+    message_id: MessageId,
+}
+
+/// Sends an ack-required announcement from `lead` to three agents, sent 25
+/// hours ago, and has `bob` acknowledge it.
+async fn setup() -> Announcement {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "announce", "/announce")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["lead", "bob", "carol", "dave"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id);
+    }
+
+    let message_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: agents[0],
+            recipient_ids: agents[1..].to_vec(),
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Freeze main".to_string(),
+            body_md: "No merges until the release is cut.".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: true,
+        },
+    )
+    .await
+    .unwrap();
+    tc.mm
+        .db_for_test()
+        .execute(
+            "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
+            [message_id.get()],
+        )
+        .await
+        .unwrap();
+    MessageBmc::acknowledge(&tc.ctx, &tc.mm, message_id, agents[1])
+        .await
+        .unwrap();
+
+    Announcement {
+        tc,
+        project_id,
+        agents,
+        message_id,
+    }
+}
+
+#[tokio::test]
+async fn test_status_splits_acked_and_pending() {
+    let a = setup().await;
+    let status = BroadcastStatusBmc::get(&a.tc.ctx, &a.tc.mm, a.message_id)
+        .await
+        .unwrap();
+
+    assert_eq!(status.total_recipients, 3);
+    assert!(!status.is_complete());
+    let acked: Vec<&str> = status.acked.iter().map(|r| r.agent_name.as_str()).collect();
+    let pending: Vec<&str> = status
+        .pending
+        .iter()
+        .map(|r| r.agent_name.as_str())
+        .collect();
+    assert_eq!(acked, ["bob"]);
+    assert_eq!(pending, ["carol", "dave"]);
+
+    let stats = status.time_to_ack.expect("bob acked");
+    assert!(stats.min_seconds >= 25 * 3600);
+    assert_eq!(stats.min_seconds, stats.max_seconds);
+
+    let summaries = BroadcastStatusBmc::list_for_project(&a.tc.ctx, &a.tc.mm, a.project_id, 10)
+        .await
+        .unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].message_id, a.message_id);
+    assert_eq!(summaries[0].total_recipients, 3);
+    assert_eq!(summaries[0].acked_count, 1);
+}
+
+#[tokio::test]
+async fn test_receipts_list_read_and_ack_per_recipient() {
+    let a = setup().await;
+    MessageBmc::mark_read(&a.tc.ctx, &a.tc.mm, a.message_id, a.agents[2])
+        .await
+        .unwrap();
+
+    let receipts = ReceiptBmc::get(&a.tc.ctx, &a.tc.mm, a.message_id)
+        .await
+        .unwrap();
+    assert_eq!(receipts.sender_name, "lead");
+    assert!(receipts.ack_required);
+    assert_eq!(receipts.read_count, 2);
+    assert_eq!(receipts.acked_count, 1);
+    let states: Vec<(&str, bool, bool)> = receipts
+        .receipts
+        .iter()
+        .map(|r| {
+            (
+                r.agent_name.as_str(),
+                r.read_ts.is_some(),
+                r.ack_ts.is_some(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        states,
+        [
+            ("bob", true, true),
+            ("carol", true, false),
+            ("dave", false, false)
+        ]
+    );
+    assert_eq!(receipts.unacked().collect::<Vec<_>>(), ["carol", "dave"]);
+
+    let missing = ReceiptBmc::get(&a.tc.ctx, &a.tc.mm, MessageId::new(999_999)).await;
+    assert!(missing.is_err());
+}
+
+#[tokio::test]
+async fn test_remind_mode_nags_only_pending_recipients() {
+    let a = setup().await;
+    let results =
+        EscalationBmc::escalate_overdue(&a.tc.ctx, &a.tc.mm, 24, EscalationMode::Remind, false)
+            .await
+            .unwrap();
+
+    // One reminder for the message, not one per overdue recipient
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].action_taken, "reminder_sent");
+    assert!(results[0].success);
+
+    for (agent, expect_reminder) in [
+        (a.agents[1], false),
+        (a.agents[2], true),
+        (a.agents[3], true),
+    ] {
+        let inbox = MessageBmc::list_inbox_for_agent(&a.tc.ctx, &a.tc.mm, a.project_id, agent, 10)
+            .await
+            .unwrap();
+        let reminded = inbox
+            .iter()
+            .any(|m| m.subject == "REMINDER: Freeze main" && !m.ack_required);
+        assert_eq!(reminded, expect_reminder);
+    }
+}
@@ -16,6 +16,7 @@ use crate::common::TestContext;
 use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::build_slot::{BuildSlotBmc, BuildSlotForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::ProjectId;
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to set up a project and agent for build slot tests
@@ -170,6 +171,38 @@ async fn test_renew_build_slot() {
     );
 }
 
+/// Test that TTLs must be positive, whatever the caller's clock
+#[tokio::test]
+async fn test_non_positive_ttl_rejected() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let (project_id, agent_id) = setup_project_and_agent(&tc, "ttl").await;
+
+    let slot_c = BuildSlotForCreate {
+        project_id,
+        agent_id,
+        slot_name: "ttl-test".to_string(),
+        ttl_seconds: -60,
+    };
+    let result = BuildSlotBmc::acquire(&tc.ctx, &tc.mm, slot_c.clone()).await;
+    assert!(result.is_err(), "Negative TTL should be rejected");
+
+    let slot_id = BuildSlotBmc::acquire(
+        &tc.ctx,
+        &tc.mm,
+        BuildSlotForCreate {
+            ttl_seconds: 60,
+            ..slot_c
+        },
+    )
+    .await
+    .expect("Failed to acquire slot");
+    let result = BuildSlotBmc::renew(&tc.ctx, &tc.mm, slot_id, 0).await;
+    assert!(result.is_err(), "Zero TTL should be rejected");
+}
+
 /// Test listing active build slots
 #[tokio::test]
 async fn test_list_active_slots() {
@@ -192,7 +225,7 @@ async fn test_list_active_slots() {
             .expect("Failed to acquire slot");
     }
 
-    let active_slots = BuildSlotBmc::list_active(&tc.ctx, &tc.mm, project_id)
+    let active_slots = BuildSlotBmc::list_active(&tc.ctx, &tc.mm, ProjectId::new(project_id))
         .await
         .expect("Failed to list active slots");
 
@@ -241,7 +274,7 @@ async fn test_released_slots_not_in_active() {
         .expect("Failed to acquire second slot");
 
     // List active should only show 1
-    let active_slots = BuildSlotBmc::list_active(&tc.ctx, &tc.mm, project_id)
+    let active_slots = BuildSlotBmc::list_active(&tc.ctx, &tc.mm, ProjectId::new(project_id))
         .await
         .expect("Failed to list active slots");
 
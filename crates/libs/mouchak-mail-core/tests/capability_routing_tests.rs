@@ -0,0 +1,152 @@
+//! Capability routing tests
+//!
+//! Tests for resolving `capability:<name>` recipient addresses.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::model::agent_capabilities::{AgentCapabilityBmc, AgentCapabilityForCreate};
+use mouchak_mail_core::model::capability_routing::{
+    CapabilityAddress, CapabilityRoutingBmc, RoutingMode,
+};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::AgentId;
+
+async fn grant(tc: &TestContext, agent_id: AgentId, capability: &str) {
+    AgentCapabilityBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentCapabilityForCreate {
+            agent_id: agent_id.get(),
+            capability: capability.to_string(),
+            granted_by: None,
+            expires_at: None,
+        },
+    )
+    .await
+    .expect("Failed to grant capability");
+}
+
+/// Test parsing of address modes
+#[test]
+fn test_parse_capability_address() {
+    let all = CapabilityAddress::parse("capability:db-migrations").unwrap();
+    assert_eq!(all.capability, "db-migrations");
+    assert_eq!(all.mode, RoutingMode::All);
+
+    let best = CapabilityAddress::parse("Capability:db-migrations:best").unwrap();
+    assert_eq!(best.mode, RoutingMode::Best);
+
+    assert!(CapabilityAddress::parse("capability:").is_err());
+    assert!(CapabilityAddress::parse("capability:db:nearest").is_err());
+}
+
+/// Test all/best resolution, sender exclusion, and recorded routing
+#[tokio::test]
+async fn test_resolve_and_record() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "routing-proj", "/routing")
+        .await
+        .unwrap();
+
+    let sender = create_agent(&tc, project_id, "Sender").await;
+    let dba1 = create_agent(&tc, project_id, "DbaOne").await;
+    let dba2 = create_agent(&tc, project_id, "DbaTwo").await;
+    grant(&tc, sender, "db-migrations").await;
+    grant(&tc, dba1, "db-migrations").await;
+    grant(&tc, dba2, "db-migrations").await;
+
+    let (ids, routing) = CapabilityRoutingBmc::resolve_recipients(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        &["capability:db-migrations".to_string()],
+        Some(sender),
+    )
+    .await
+    .unwrap();
+    assert_eq!(ids.len(), 2);
+    assert!(!ids.contains(&sender), "sender must be excluded");
+    assert_eq!(routing.len(), 1);
+
+    let best = CapabilityRoutingBmc::resolve(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        &CapabilityAddress::parse("capability:db-migrations:best").unwrap(),
+        Some(sender),
+    )
+    .await
+    .unwrap();
+    assert_eq!(best.agent_ids.len(), 1);
+
+    let message_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: sender,
+            recipient_ids: ids.clone(),
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Schema change".to_string(),
+            body_md: "Please review".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    CapabilityRoutingBmc::record(&tc.ctx, &tc.mm, message_id, &routing)
+        .await
+        .unwrap();
+
+    let records = CapabilityRoutingBmc::list_for_message(&tc.ctx, &tc.mm, message_id)
+        .await
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].capability, "db-migrations");
+    assert_eq!(records[0].mode, "all");
+    assert_eq!(
+        records[0].resolved_agent_ids,
+        ids.iter().map(|id| id.get()).collect::<Vec<_>>()
+    );
+}
+
+/// Test that an unknown capability produces a helpful error
+#[tokio::test]
+async fn test_resolve_unknown_capability() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "routing-miss", "/routing-miss")
+        .await
+        .unwrap();
+    let agent = create_agent(&tc, project_id, "Frontend").await;
+    grant(&tc, agent, "ui-review").await;
+
+    let err = CapabilityRoutingBmc::resolve(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        &CapabilityAddress::parse("capability:db-migrations").unwrap(),
+        None,
+    )
+    .await
+    .unwrap_err();
+
+    let msg = err.to_string();
+    assert!(msg.contains("db-migrations"));
+    assert!(
+        msg.contains("ui-review"),
+        "error should list known capabilities"
+    );
+}
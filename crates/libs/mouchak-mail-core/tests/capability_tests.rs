@@ -17,6 +17,7 @@ use mouchak_mail_core::model::agent_capabilities::{
     CAP_FILE_RESERVATION, CAP_SEND_MESSAGE, DEFAULT_CAPABILITIES,
 };
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::AgentId;
 use mouchak_mail_core::utils::slugify;
 
 #[tokio::test]
@@ -44,9 +45,10 @@ async fn test_agent_capability_lifecycle() {
     let agent_id_raw: i64 = agent_id.into();
 
     // 1. Check capability (should be false)
-    let has_cap = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, "send_message")
-        .await
-        .unwrap();
+    let has_cap =
+        AgentCapabilityBmc::check(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw), "send_message")
+            .await
+            .unwrap();
     assert!(!has_cap, "Agent should not have capability yet");
 
     // 2. Grant capability
@@ -62,13 +64,14 @@ async fn test_agent_capability_lifecycle() {
     assert!(cap_id > 0);
 
     // 3. Check capability again (should be true)
-    let has_cap = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, "send_message")
-        .await
-        .unwrap();
+    let has_cap =
+        AgentCapabilityBmc::check(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw), "send_message")
+            .await
+            .unwrap();
     assert!(has_cap, "Agent should have capability now");
 
     // 4. List capabilities
-    let caps = AgentCapabilityBmc::list_for_agent(&tc.ctx, &tc.mm, agent_id_raw)
+    let caps = AgentCapabilityBmc::list_for_agent(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw))
         .await
         .unwrap();
     assert_eq!(caps.len(), 1);
@@ -80,9 +83,10 @@ async fn test_agent_capability_lifecycle() {
         .unwrap();
 
     // 6. Check capability (should be false)
-    let has_cap = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, "send_message")
-        .await
-        .unwrap();
+    let has_cap =
+        AgentCapabilityBmc::check(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw), "send_message")
+            .await
+            .unwrap();
     assert!(!has_cap, "Agent should not have capability after revoke");
 }
 
@@ -111,9 +115,10 @@ async fn test_grant_defaults_new_agent() {
     let agent_id_raw: i64 = agent_id.into();
 
     // Verify agent has no capabilities initially
-    let caps_before = AgentCapabilityBmc::list_for_agent(&tc.ctx, &tc.mm, agent_id_raw)
-        .await
-        .unwrap();
+    let caps_before =
+        AgentCapabilityBmc::list_for_agent(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw))
+            .await
+            .unwrap();
     assert_eq!(
         caps_before.len(),
         0,
@@ -121,39 +126,56 @@ async fn test_grant_defaults_new_agent() {
     );
 
     // Grant default capabilities
-    let granted = AgentCapabilityBmc::grant_defaults(&tc.ctx, &tc.mm, agent_id_raw)
+    let granted = AgentCapabilityBmc::grant_defaults(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw))
         .await
         .unwrap();
     assert_eq!(granted, 4, "Should grant exactly 4 default capabilities");
 
     // Verify all default capabilities were granted
-    let caps_after = AgentCapabilityBmc::list_for_agent(&tc.ctx, &tc.mm, agent_id_raw)
-        .await
-        .unwrap();
+    let caps_after =
+        AgentCapabilityBmc::list_for_agent(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw))
+            .await
+            .unwrap();
     assert_eq!(caps_after.len(), 4, "Agent should have 4 capabilities");
 
     // Verify each specific capability using constants
-    let has_send = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, CAP_SEND_MESSAGE)
-        .await
-        .unwrap();
+    let has_send = AgentCapabilityBmc::check(
+        &tc.ctx,
+        &tc.mm,
+        AgentId::new(agent_id_raw),
+        CAP_SEND_MESSAGE,
+    )
+    .await
+    .unwrap();
     assert!(has_send, "Agent should have send_message capability");
 
-    let has_fetch = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, CAP_FETCH_INBOX)
-        .await
-        .unwrap();
+    let has_fetch =
+        AgentCapabilityBmc::check(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw), CAP_FETCH_INBOX)
+            .await
+            .unwrap();
     assert!(has_fetch, "Agent should have fetch_inbox capability");
 
-    let has_file = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, CAP_FILE_RESERVATION)
-        .await
-        .unwrap();
+    let has_file = AgentCapabilityBmc::check(
+        &tc.ctx,
+        &tc.mm,
+        AgentId::new(agent_id_raw),
+        CAP_FILE_RESERVATION,
+    )
+    .await
+    .unwrap();
     assert!(
         has_file,
         "Agent should have file_reservation_paths capability"
     );
 
-    let has_ack = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, CAP_ACKNOWLEDGE_MESSAGE)
-        .await
-        .unwrap();
+    let has_ack = AgentCapabilityBmc::check(
+        &tc.ctx,
+        &tc.mm,
+        AgentId::new(agent_id_raw),
+        CAP_ACKNOWLEDGE_MESSAGE,
+    )
+    .await
+    .unwrap();
     assert!(has_ack, "Agent should have acknowledge_message capability");
 }
 
@@ -212,9 +234,10 @@ async fn test_expired_capability_rejected() {
         .unwrap();
 
     // Expired capability should NOT pass check
-    let has_cap = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, "send_message")
-        .await
-        .unwrap();
+    let has_cap =
+        AgentCapabilityBmc::check(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw), "send_message")
+            .await
+            .unwrap();
     assert!(!has_cap, "Expired capability should be rejected");
 
     // Grant capability that expires in the future
@@ -230,9 +253,10 @@ async fn test_expired_capability_rejected() {
         .unwrap();
 
     // Non-expired capability should pass check
-    let has_cap2 = AgentCapabilityBmc::check(&tc.ctx, &tc.mm, agent_id_raw, "fetch_inbox")
-        .await
-        .unwrap();
+    let has_cap2 =
+        AgentCapabilityBmc::check(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw), "fetch_inbox")
+            .await
+            .unwrap();
     assert!(has_cap2, "Non-expired capability should pass");
 }
 
@@ -261,13 +285,14 @@ async fn test_duplicate_grant_defaults_fails() {
     let agent_id_raw: i64 = agent_id.into();
 
     // First grant should succeed
-    let granted = AgentCapabilityBmc::grant_defaults(&tc.ctx, &tc.mm, agent_id_raw)
+    let granted = AgentCapabilityBmc::grant_defaults(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw))
         .await
         .unwrap();
     assert_eq!(granted, 4);
 
     // Second grant should fail (UNIQUE constraint)
-    let result = AgentCapabilityBmc::grant_defaults(&tc.ctx, &tc.mm, agent_id_raw).await;
+    let result =
+        AgentCapabilityBmc::grant_defaults(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw)).await;
     assert!(
         result.is_err(),
         "Duplicate grant_defaults should fail due to UNIQUE constraint"
@@ -336,7 +361,7 @@ async fn test_list_for_agent_filters_expired() {
         .unwrap();
 
     // list_for_agent should only return non-expired capabilities
-    let caps = AgentCapabilityBmc::list_for_agent(&tc.ctx, &tc.mm, agent_id_raw)
+    let caps = AgentCapabilityBmc::list_for_agent(&tc.ctx, &tc.mm, AgentId::new(agent_id_raw))
         .await
         .unwrap();
 
@@ -16,7 +16,7 @@ use crate::common::TestContext;
 use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
-use mouchak_mail_core::types::ProjectId;
+use mouchak_mail_core::types::{AgentId, ProjectId};
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to set up project with sender and multiple recipients
@@ -115,10 +115,10 @@ async fn test_send_message_with_cc() {
         setup_cc_bcc_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
-        cc_ids: Some(vec![cc_recipient_id]),
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
+        cc_ids: Some(vec![AgentId::new(cc_recipient_id)]),
         bcc_ids: None,
         subject: "Test with CC".to_string(),
         body_md: "This message has a CC recipient.".to_string(),
@@ -132,10 +132,10 @@ async fn test_send_message_with_cc() {
         .expect("Failed to send message with CC");
 
     // Verify message was created
-    assert!(msg_id > 0, "Message should have valid ID");
+    assert!(msg_id.get() > 0, "Message should have valid ID");
 
     // Verify recipient types in database
-    let recipient_types = get_recipient_types(&tc, msg_id).await;
+    let recipient_types = get_recipient_types(&tc, msg_id.get()).await;
     assert_eq!(recipient_types.len(), 2, "Should have 2 recipients");
 
     // Find the TO recipient
@@ -171,11 +171,11 @@ async fn test_send_message_with_bcc() {
         setup_cc_bcc_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
         cc_ids: None,
-        bcc_ids: Some(vec![bcc_recipient_id]),
+        bcc_ids: Some(vec![AgentId::new(bcc_recipient_id)]),
         subject: "Test with BCC".to_string(),
         body_md: "This message has a BCC recipient.".to_string(),
         thread_id: None,
@@ -188,10 +188,10 @@ async fn test_send_message_with_bcc() {
         .expect("Failed to send message with BCC");
 
     // Verify message was created
-    assert!(msg_id > 0, "Message should have valid ID");
+    assert!(msg_id.get() > 0, "Message should have valid ID");
 
     // Verify recipient types in database
-    let recipient_types = get_recipient_types(&tc, msg_id).await;
+    let recipient_types = get_recipient_types(&tc, msg_id.get()).await;
     assert_eq!(recipient_types.len(), 2, "Should have 2 recipients");
 
     // Find the TO recipient
@@ -227,11 +227,11 @@ async fn test_send_message_with_cc_and_bcc() {
         setup_cc_bcc_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
-        cc_ids: Some(vec![cc_recipient_id]),
-        bcc_ids: Some(vec![bcc_recipient_id]),
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
+        cc_ids: Some(vec![AgentId::new(cc_recipient_id)]),
+        bcc_ids: Some(vec![AgentId::new(bcc_recipient_id)]),
         subject: "Test with CC and BCC".to_string(),
         body_md: "This message has both CC and BCC recipients.".to_string(),
         thread_id: None,
@@ -244,10 +244,10 @@ async fn test_send_message_with_cc_and_bcc() {
         .expect("Failed to send message with CC and BCC");
 
     // Verify message was created
-    assert!(msg_id > 0, "Message should have valid ID");
+    assert!(msg_id.get() > 0, "Message should have valid ID");
 
     // Verify recipient types in database
-    let recipient_types = get_recipient_types(&tc, msg_id).await;
+    let recipient_types = get_recipient_types(&tc, msg_id.get()).await;
     assert_eq!(recipient_types.len(), 3, "Should have 3 recipients");
 
     // Verify TO recipient
@@ -294,10 +294,10 @@ async fn test_cc_recipient_can_see_message() {
         setup_cc_bcc_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
-        cc_ids: Some(vec![cc_recipient_id]),
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
+        cc_ids: Some(vec![AgentId::new(cc_recipient_id)]),
         bcc_ids: None,
         subject: "CC Visibility Test".to_string(),
         body_md: "CC recipient should see this.".to_string(),
@@ -309,24 +309,36 @@ async fn test_cc_recipient_can_see_message() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // Check TO recipient's inbox
-    let to_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, to_recipient_id, 10)
-            .await
-            .expect("Failed to get TO recipient inbox");
+    let to_inbox = MessageBmc::list_inbox_for_agent(
+        &tc.ctx,
+        &tc.mm,
+        project_id.into(),
+        to_recipient_id.into(),
+        10,
+    )
+    .await
+    .expect("Failed to get TO recipient inbox");
     assert_eq!(to_inbox.len(), 1, "TO recipient should have 1 message");
     assert_eq!(
-        to_inbox[0].id, msg_id,
+        to_inbox[0].id,
+        msg_id.get(),
         "TO recipient should see the message"
     );
 
     // Check CC recipient's inbox
-    let cc_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, cc_recipient_id, 10)
-            .await
-            .expect("Failed to get CC recipient inbox");
+    let cc_inbox = MessageBmc::list_inbox_for_agent(
+        &tc.ctx,
+        &tc.mm,
+        project_id.into(),
+        cc_recipient_id.into(),
+        10,
+    )
+    .await
+    .expect("Failed to get CC recipient inbox");
     assert_eq!(cc_inbox.len(), 1, "CC recipient should have 1 message");
     assert_eq!(
-        cc_inbox[0].id, msg_id,
+        cc_inbox[0].id,
+        msg_id.get(),
         "CC recipient should see the message"
     );
 }
@@ -341,11 +353,11 @@ async fn test_bcc_recipient_can_see_message() {
         setup_cc_bcc_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
         cc_ids: None,
-        bcc_ids: Some(vec![bcc_recipient_id]),
+        bcc_ids: Some(vec![AgentId::new(bcc_recipient_id)]),
         subject: "BCC Visibility Test".to_string(),
         body_md: "BCC recipient should see this but others shouldn't know.".to_string(),
         thread_id: None,
@@ -356,29 +368,41 @@ async fn test_bcc_recipient_can_see_message() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // Check TO recipient's inbox
-    let to_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, to_recipient_id, 10)
-            .await
-            .expect("Failed to get TO recipient inbox");
+    let to_inbox = MessageBmc::list_inbox_for_agent(
+        &tc.ctx,
+        &tc.mm,
+        project_id.into(),
+        to_recipient_id.into(),
+        10,
+    )
+    .await
+    .expect("Failed to get TO recipient inbox");
     assert_eq!(to_inbox.len(), 1, "TO recipient should have 1 message");
     assert_eq!(
-        to_inbox[0].id, msg_id,
+        to_inbox[0].id,
+        msg_id.get(),
         "TO recipient should see the message"
     );
 
     // Check BCC recipient's inbox
-    let bcc_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, bcc_recipient_id, 10)
-            .await
-            .expect("Failed to get BCC recipient inbox");
+    let bcc_inbox = MessageBmc::list_inbox_for_agent(
+        &tc.ctx,
+        &tc.mm,
+        project_id.into(),
+        bcc_recipient_id.into(),
+        10,
+    )
+    .await
+    .expect("Failed to get BCC recipient inbox");
     assert_eq!(bcc_inbox.len(), 1, "BCC recipient should have 1 message");
     assert_eq!(
-        bcc_inbox[0].id, msg_id,
+        bcc_inbox[0].id,
+        msg_id.get(),
         "BCC recipient should see the message"
     );
 
     // Verify BCC recipient is stored with correct type (would not be visible in a real UI)
-    let recipient_types = get_recipient_types(&tc, msg_id).await;
+    let recipient_types = get_recipient_types(&tc, msg_id.get()).await;
     let bcc_entry = recipient_types
         .iter()
         .find(|(id, _)| *id == bcc_recipient_id);
@@ -415,10 +439,10 @@ async fn test_multiple_cc_recipients() {
         .unwrap();
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
-        cc_ids: Some(vec![cc_recipient_id, cc_recipient2_id.into()]),
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
+        cc_ids: Some(vec![AgentId::new(cc_recipient_id), cc_recipient2_id]),
         bcc_ids: None,
         subject: "Multiple CC Test".to_string(),
         body_md: "This has multiple CC recipients.".to_string(),
@@ -430,7 +454,7 @@ async fn test_multiple_cc_recipients() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // Verify recipient types in database
-    let recipient_types = get_recipient_types(&tc, msg_id).await;
+    let recipient_types = get_recipient_types(&tc, msg_id.get()).await;
     assert_eq!(recipient_types.len(), 3, "Should have 3 recipients total");
 
     // Count CC recipients
@@ -441,10 +465,15 @@ async fn test_multiple_cc_recipients() {
     assert_eq!(cc_count, 2, "Should have 2 CC recipients");
 
     // Both CC recipients should see the message
-    let cc1_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, cc_recipient_id, 10)
-            .await
-            .unwrap();
+    let cc1_inbox = MessageBmc::list_inbox_for_agent(
+        &tc.ctx,
+        &tc.mm,
+        project_id.into(),
+        cc_recipient_id.into(),
+        10,
+    )
+    .await
+    .unwrap();
     assert_eq!(
         cc1_inbox.len(),
         1,
@@ -452,7 +481,7 @@ async fn test_multiple_cc_recipients() {
     );
 
     let cc2_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, cc_recipient2_id.into(), 10)
+        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id.into(), cc_recipient2_id, 10)
             .await
             .unwrap();
     assert_eq!(
@@ -484,11 +513,11 @@ async fn test_multiple_bcc_recipients() {
         .unwrap();
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
         cc_ids: None,
-        bcc_ids: Some(vec![bcc_recipient_id, bcc_recipient2_id.into()]),
+        bcc_ids: Some(vec![AgentId::new(bcc_recipient_id), bcc_recipient2_id]),
         subject: "Multiple BCC Test".to_string(),
         body_md: "This has multiple BCC recipients.".to_string(),
         thread_id: None,
@@ -499,7 +528,7 @@ async fn test_multiple_bcc_recipients() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // Verify recipient types in database
-    let recipient_types = get_recipient_types(&tc, msg_id).await;
+    let recipient_types = get_recipient_types(&tc, msg_id.get()).await;
     assert_eq!(recipient_types.len(), 3, "Should have 3 recipients total");
 
     // Count BCC recipients
@@ -510,10 +539,15 @@ async fn test_multiple_bcc_recipients() {
     assert_eq!(bcc_count, 2, "Should have 2 BCC recipients");
 
     // Both BCC recipients should see the message
-    let bcc1_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, bcc_recipient_id, 10)
-            .await
-            .unwrap();
+    let bcc1_inbox = MessageBmc::list_inbox_for_agent(
+        &tc.ctx,
+        &tc.mm,
+        project_id.into(),
+        bcc_recipient_id.into(),
+        10,
+    )
+    .await
+    .unwrap();
     assert_eq!(
         bcc1_inbox.len(),
         1,
@@ -521,7 +555,7 @@ async fn test_multiple_bcc_recipients() {
     );
 
     let bcc2_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, bcc_recipient2_id.into(), 10)
+        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id.into(), bcc_recipient2_id, 10)
             .await
             .unwrap();
     assert_eq!(
@@ -541,10 +575,10 @@ async fn test_cc_recipient_mark_as_read() {
         setup_cc_bcc_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
-        cc_ids: Some(vec![cc_recipient_id]),
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
+        cc_ids: Some(vec![AgentId::new(cc_recipient_id)]),
         bcc_ids: None,
         subject: "CC Read Test".to_string(),
         body_md: "CC recipient can mark as read.".to_string(),
@@ -556,7 +590,7 @@ async fn test_cc_recipient_mark_as_read() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // CC recipient marks as read
-    let result = MessageBmc::mark_read(&tc.ctx, &tc.mm, msg_id, cc_recipient_id).await;
+    let result = MessageBmc::mark_read(&tc.ctx, &tc.mm, msg_id, cc_recipient_id.into()).await;
     assert!(
         result.is_ok(),
         "CC recipient should be able to mark message as read"
@@ -573,11 +607,11 @@ async fn test_bcc_recipient_acknowledge() {
         setup_cc_bcc_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![to_recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(to_recipient_id)],
         cc_ids: None,
-        bcc_ids: Some(vec![bcc_recipient_id]),
+        bcc_ids: Some(vec![AgentId::new(bcc_recipient_id)]),
         subject: "BCC Acknowledge Test".to_string(),
         body_md: "BCC recipient can acknowledge.".to_string(),
         thread_id: None,
@@ -588,7 +622,7 @@ async fn test_bcc_recipient_acknowledge() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // BCC recipient acknowledges
-    let result = MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, bcc_recipient_id).await;
+    let result = MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, bcc_recipient_id.into()).await;
     assert!(
         result.is_ok(),
         "BCC recipient should be able to acknowledge message"
@@ -0,0 +1,182 @@
+//! CI status ingestion tests
+//!
+//! Tests commit links, posting build events into linked threads, redelivery
+//! dedup and the overseer fallback for unlinked failing builds.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::ci_status::{CiEvent, CiStatus, CiStatusBmc};
+use mouchak_mail_core::model::github_sync::GithubSyncBmc;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::overseer_message::OverseerMessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+const SHA: &str = "0123456789abcdef0123456789abcdef01234567";
+
+/// Thread `T-1` between two agents in project `builds`.
+async fn setup() -> (TestContext, ProjectId) {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "builds", "/builds")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["BlueLake", "GreenCastle"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id.get());
+    }
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: (agents[0]).into(),
+            recipient_ids: vec![AgentId::new(agents[1])],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Parser fix".to_string(),
+            body_md: "Pushed the fix".to_string(),
+            thread_id: Some("T-1".to_string()),
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    (tc, project_id)
+}
+
+fn event(status: CiStatus) -> CiEvent {
+    CiEvent {
+        provider: "github-actions".to_string(),
+        repo: "acme/widgets".to_string(),
+        sha: SHA.to_string(),
+        status,
+        url: Some("https://ci.example.com/runs/42".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_build_event_posts_to_linked_thread() {
+    let (tc, project_id) = setup().await;
+
+    let link = CiStatusBmc::link_commit(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        "T-1",
+        "0123456789ABCDEF",
+        Some("acme/widgets"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(link.sha, "0123456789abcdef");
+    assert!(matches!(
+        CiStatusBmc::link_commit(&tc.ctx, &tc.mm, project_id, "T-404", SHA, None).await,
+        Err(Error::NotFound)
+    ));
+    assert!(matches!(
+        CiStatusBmc::link_commit(&tc.ctx, &tc.mm, project_id, "T-1", "xyz", None).await,
+        Err(Error::InvalidInput(_))
+    ));
+
+    let delivery = CiStatusBmc::ingest(&tc.ctx, &tc.mm, &event(CiStatus::Failure), None)
+        .await
+        .unwrap();
+    assert!(!delivery.duplicate);
+    assert_eq!(delivery.thread_messages.len(), 1);
+    assert!(delivery.overseer_messages.is_empty());
+
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, "T-1")
+        .await
+        .unwrap();
+    let posted = messages.last().unwrap();
+    assert_eq!(posted.sender_name, "ci");
+    assert_eq!(
+        posted.subject,
+        "[CI] github-actions failure: acme/widgets@0123456"
+    );
+    assert_eq!(posted.importance, "high");
+    assert!(posted.body_md.contains("https://ci.example.com/runs/42"));
+
+    // A redelivered webhook posts nothing
+    let again = CiStatusBmc::ingest(&tc.ctx, &tc.mm, &event(CiStatus::Failure), None)
+        .await
+        .unwrap();
+    assert!(again.duplicate);
+    assert_eq!(again.event_id, delivery.event_id);
+    assert!(again.thread_messages.is_empty());
+
+    // The link is scoped to its repository
+    let mut other = event(CiStatus::Success);
+    other.repo = "acme/gadgets".to_string();
+    assert!(matches!(
+        CiStatusBmc::ingest(&tc.ctx, &tc.mm, &other, None).await,
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_unlinked_failure_goes_to_overseer() {
+    let (tc, project_id) = setup().await;
+
+    // No link and no repository mapping
+    assert!(matches!(
+        CiStatusBmc::ingest(&tc.ctx, &tc.mm, &event(CiStatus::Failure), None).await,
+        Err(Error::InvalidInput(_))
+    ));
+
+    GithubSyncBmc::set_repo(&tc.ctx, &tc.mm, project_id, "acme/widgets")
+        .await
+        .unwrap();
+    // Green builds are only recorded
+    let delivery = CiStatusBmc::ingest(&tc.ctx, &tc.mm, &event(CiStatus::Success), None)
+        .await
+        .unwrap();
+    assert!(delivery.thread_messages.is_empty());
+    assert!(delivery.overseer_messages.is_empty());
+
+    let delivery = CiStatusBmc::ingest(&tc.ctx, &tc.mm, &event(CiStatus::Error), None)
+        .await
+        .unwrap();
+    assert_eq!(delivery.overseer_messages.len(), 1);
+    let inbox = OverseerMessageBmc::list_for_project(&tc.ctx, &tc.mm, project_id, true, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].importance, "high");
+    assert!(inbox[0].body_md.contains("link_commit"));
+
+    assert!(matches!(
+        CiStatusBmc::ingest(
+            &tc.ctx,
+            &tc.mm,
+            &CiEvent {
+                url: Some("ftp://ci.example.com".to_string()),
+                ..event(CiStatus::Failure)
+            },
+            None,
+        )
+        .await,
+        Err(Error::InvalidInput(_))
+    ));
+}
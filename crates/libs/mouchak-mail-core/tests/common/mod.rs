@@ -10,6 +10,8 @@
 #![allow(dead_code, clippy::unwrap_used, clippy::expect_used)]
 
 use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::types::{AgentId, ProjectId};
 use mouchak_mail_core::{Ctx, ModelManager, Result};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -80,6 +82,23 @@ impl TestContext {
     }
 }
 
+/// Register a test agent named `name` in `project_id`
+pub(crate) async fn create_agent(tc: &TestContext, project_id: ProjectId, name: &str) -> AgentId {
+    AgentBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: String::new(),
+        },
+    )
+    .await
+    .unwrap()
+}
+
 /// Create an isolated database for testing
 async fn create_test_db(db_path: &std::path::Path) -> Result<mouchak_mail_core::store::Db> {
     use libsql::Builder;
@@ -96,26 +115,10 @@ async fn create_test_db(db_path: &std::path::Path) -> Result<mouchak_mail_core::
     // Apply migrations
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    // Read schema from migrations directory (same path as store/mod.rs uses)
-    let schema = include_str!("../../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema).await?;
-    let schema002 = include_str!("../../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema002).await?;
-    let schema003 = include_str!("../../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema003).await?;
-    let schema004 = include_str!("../../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema004).await?;
-    let schema005 = include_str!("../../../../../migrations/005_attachments_agent.sql");
-    conn.execute_batch(schema005).await?;
-    let schema006 = include_str!("../../../../../migrations/006_query_indexes.sql");
-    conn.execute_batch(schema006).await?;
+    mouchak_mail_core::store::upgrade::migrate(&conn).await?;
 
     // Verify idempotency: running migrations again should not fail
-    conn.execute_batch(schema).await?;
-    conn.execute_batch(schema002).await?;
-    conn.execute_batch(schema003).await?;
-    conn.execute_batch(schema004).await?;
-    conn.execute_batch(schema006).await?;
+    mouchak_mail_core::store::upgrade::migrate(&conn).await?;
 
-    Ok(conn)
+    Ok(conn.into())
 }
@@ -20,7 +20,7 @@ use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::file_reservation::{FileReservationBmc, FileReservationForCreate};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
-use mouchak_mail_core::types::{AgentId, ProjectId};
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
 use std::sync::Arc;
 use tempfile::TempDir;
 
@@ -31,16 +31,9 @@ async fn create_test_mm() -> (ModelManager, TempDir) {
     let db = Builder::new_local(&db_path).build().await.unwrap();
     let conn = db.connect().unwrap();
 
-    // Run migrations inline (same as store/mod.rs)
-    let migrations = [
-        include_str!("../../../../migrations/001_initial_schema.sql"),
-        include_str!("../../../../migrations/002_agent_capabilities.sql"),
-        include_str!("../../../../migrations/003_tool_metrics.sql"),
-        include_str!("../../../../migrations/004_attachments.sql"),
-    ];
-    for migration in &migrations {
-        conn.execute_batch(migration).await.expect("run migration");
-    }
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .expect("run migration");
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, temp_dir.path().to_path_buf(), app_config);
@@ -83,9 +76,9 @@ fn make_message(
     thread_id: Option<String>,
 ) -> MessageForCreate {
     MessageForCreate {
-        project_id: project_id.get(),
-        sender_id,
-        recipient_ids,
+        project_id,
+        sender_id: sender_id.into(),
+        recipient_ids: recipient_ids.into_iter().map(AgentId::new).collect(),
         cc_ids: None,
         bcc_ids: None,
         subject,
@@ -362,7 +355,7 @@ async fn test_concurrent_inbox_fetches() {
 
             tokio::spawn(async move {
                 let ctx = Ctx::root_ctx();
-                MessageBmc::list_inbox_for_agent(&ctx, &mm, pid.get(), agent_id, 100)
+                MessageBmc::list_inbox_for_agent(&ctx, &mm, pid, agent_id.into(), 100)
                     .await
                     .is_ok()
             })
@@ -423,7 +416,7 @@ async fn test_concurrent_inbox_fetch_during_message_send() {
 
         handles.push(tokio::spawn(async move {
             let ctx = Ctx::root_ctx();
-            MessageBmc::list_inbox_for_agent(&ctx, &mm, pid.get(), agent_id, 100)
+            MessageBmc::list_inbox_for_agent(&ctx, &mm, pid, agent_id.into(), 100)
                 .await
                 .is_ok()
         }));
@@ -564,7 +557,7 @@ async fn test_concurrent_message_read_write() {
         handles.push(tokio::spawn(async move {
             let ctx = Ctx::root_ctx();
             // May or may not find the message
-            let _ = MessageBmc::get(&ctx, &mm, i).await;
+            let _ = MessageBmc::get(&ctx, &mm, MessageId::new(i)).await;
             true // Just checking no panic
         }));
     }
@@ -673,3 +666,89 @@ async fn test_concurrent_message_bundle_writes() {
         assert!(count >= 1, "Agent {} bundle should have some successes", i);
     }
 }
+
+// ============================================================================
+// TEST 13: Failed Send During Concurrent Acks
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_failed_send_keeps_concurrent_acks() {
+    let (mm, _temp) = create_test_mm().await;
+    let mm = Arc::new(mm);
+    let ctx = Ctx::root_ctx();
+
+    let (project_id, agent_ids) = setup_test_project(&mm).await;
+    let (sender_id, reader_id) = (agent_ids[0], agent_ids[1]);
+
+    let mut to_ack = Vec::new();
+    for i in 0..10 {
+        let msg = make_message(
+            project_id,
+            sender_id,
+            vec![reader_id],
+            format!("Ack me {}", i),
+            "Please ack".to_string(),
+            None,
+        );
+        to_ack.push(MessageBmc::create(&ctx, &mm, msg).await.unwrap());
+    }
+
+    // Sends now fail at their last write, inside the transaction
+    let db = mm.db_for_test();
+    db.execute_batch(
+        r#"
+        CREATE TRIGGER refuse_intents BEFORE INSERT ON operation_intents
+        BEGIN SELECT RAISE(ABORT, 'intent refused'); END;
+        "#,
+    )
+    .await
+    .unwrap();
+
+    let mut handles = Vec::new();
+
+    // A failing send next to each ack
+    for (i, &message_id) in to_ack.iter().enumerate() {
+        let send_mm = Arc::clone(&mm);
+        handles.push(tokio::spawn(async move {
+            let ctx = Ctx::root_ctx();
+            let msg = make_message(
+                project_id,
+                sender_id,
+                vec![reader_id],
+                format!("Refused {}", i),
+                "Never stored".to_string(),
+                None,
+            );
+            assert!(MessageBmc::create(&ctx, &send_mm, msg).await.is_err());
+        }));
+
+        let ack_mm = Arc::clone(&mm);
+        handles.push(tokio::spawn(async move {
+            let ctx = Ctx::root_ctx();
+            MessageBmc::acknowledge(&ctx, &ack_mm, message_id, AgentId::new(reader_id))
+                .await
+                .unwrap();
+        }));
+    }
+    for result in join_all(handles).await {
+        result.unwrap();
+    }
+
+    let stmt = db
+        .prepare(
+            "SELECT COUNT(*) FROM message_recipients WHERE agent_id = ? AND ack_ts IS NOT NULL",
+        )
+        .await
+        .unwrap();
+    let mut rows = stmt.query([reader_id]).await.unwrap();
+    let acked: i64 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_eq!(acked, 10, "Every ack should survive the failed sends");
+
+    let stmt = db
+        .prepare("SELECT COUNT(*) FROM messages WHERE subject LIKE 'Refused%'")
+        .await
+        .unwrap();
+    let mut rows = stmt.query(()).await.unwrap();
+    let refused: i64 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_eq!(refused, 0, "Failed sends should leave no message behind");
+}
@@ -0,0 +1,119 @@
+//! Context pack tests
+//!
+//! Tests for size-bounded agent bootstrapping briefings.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::{Duration, Utc};
+use mouchak_mail_core::model::context_pack::{BYTES_PER_TOKEN, ContextPackBmc, MIN_BUDGET_TOKENS};
+use mouchak_mail_core::model::file_reservation::{FileReservationBmc, FileReservationForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    subject: &str,
+    importance: &str,
+    ack_required: bool,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.to_string(),
+            body_md: format!("Details for {}", subject),
+            thread_id: None,
+            importance: Some(importance.to_string()),
+            ack_required,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_context_pack_collects_sections() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "pack-sections", "/pack/sections")
+        .await
+        .unwrap();
+    let lead = create_agent(&tc, project_id, "lead").await;
+    let worker = create_agent(&tc, project_id, "worker").await;
+
+    let urgent = send(&tc, project_id, lead, worker, "Hotfix now", "urgent", false).await;
+    let read = send(&tc, project_id, lead, worker, "Already seen", "high", false).await;
+    send(&tc, project_id, lead, worker, "FYI", "normal", false).await;
+    send(&tc, project_id, lead, worker, "Sign off", "normal", true).await;
+    MessageBmc::mark_read(&tc.ctx, &tc.mm, read, worker)
+        .await
+        .unwrap();
+
+    FileReservationBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        FileReservationForCreate {
+            project_id,
+            agent_id: lead,
+            path_pattern: "src/api/**".to_string(),
+            exclusive: true,
+            reason: "refactor".to_string(),
+            expires_ts: Utc::now().naive_utc() + Duration::hours(1),
+        },
+    )
+    .await
+    .unwrap();
+
+    let pack = ContextPackBmc::build(&tc.ctx, &tc.mm, project_id, worker, 4000)
+        .await
+        .unwrap();
+
+    assert_eq!(pack.agent_name, "worker");
+    assert_eq!(pack.urgent_unread.len(), 1);
+    assert_eq!(pack.urgent_unread[0].id, urgent.get());
+    assert_eq!(pack.pending_acks.len(), 1);
+    assert_eq!(pack.pending_acks[0].subject, "Sign off");
+    assert_eq!(pack.reservations.len(), 1);
+    assert!(!pack.reservations[0].mine);
+    assert_eq!(pack.reservations[0].agent_name, "lead");
+    assert_eq!(pack.omitted, 0);
+    assert!(pack.render().contains("src/api/** by lead"));
+}
+
+#[tokio::test]
+async fn test_context_pack_respects_budget() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "pack-budget", "/pack/budget")
+        .await
+        .unwrap();
+    let lead = create_agent(&tc, project_id, "lead").await;
+    let worker = create_agent(&tc, project_id, "worker").await;
+
+    for i in 0..15 {
+        let subject = format!("Urgent item {} {}", i, "x".repeat(80));
+        send(&tc, project_id, lead, worker, &subject, "urgent", true).await;
+    }
+
+    let pack = ContextPackBmc::build(&tc.ctx, &tc.mm, project_id, worker, 0)
+        .await
+        .unwrap();
+
+    assert_eq!(pack.budget_tokens, MIN_BUDGET_TOKENS);
+    assert!(pack.omitted > 0);
+    assert!(pack.threads.is_empty(), "threads are dropped first");
+    assert!(pack.render().len() <= MIN_BUDGET_TOKENS * BYTES_PER_TOKEN);
+    assert!(pack.render().contains("omitted to fit the budget"));
+}
@@ -0,0 +1,264 @@
+//! Custom message field tests
+//!
+//! Tests for declaring per-project fields, validating values on messages,
+//! and filtering searches by field values.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::custom_field::{
+    CustomFieldBmc, CustomFieldForCreate, CustomFieldType,
+};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+use serde_json::{Map, Value, json};
+
+struct Setup {
+    tc: TestContext,
+    project_id: ProjectId,
+    agent: AgentId,
+}
+
+/// A project with a required `component` and an optional numeric `severity`.
+async fn setup() -> Setup {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "fields", "/fields")
+        .await
+        .unwrap();
+    let agent = AgentBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentForCreate {
+            project_id,
+            name: "triager".to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: String::new(),
+        },
+    )
+    .await
+    .unwrap();
+
+    for (name, field_type, required) in [
+        ("component", CustomFieldType::String, true),
+        ("severity", CustomFieldType::Number, false),
+    ] {
+        CustomFieldBmc::define(
+            &tc.ctx,
+            &tc.mm,
+            CustomFieldForCreate {
+                project_id,
+                name: name.to_string(),
+                field_type,
+                required,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    Setup {
+        tc,
+        project_id,
+        agent,
+    }
+}
+
+fn fields(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(map) => map,
+        _ => panic!("expected an object"),
+    }
+}
+
+impl Setup {
+    /// Sends a message about a crash with the given field values.
+    async fn send(&self, values: Value) -> MessageId {
+        let validated = MessageBmc::validate_custom_fields(
+            &self.tc.ctx,
+            &self.tc.mm,
+            self.project_id,
+            Some(&fields(values)),
+        )
+        .await
+        .unwrap();
+        let id = MessageBmc::create(
+            &self.tc.ctx,
+            &self.tc.mm,
+            MessageForCreate {
+                project_id: self.project_id,
+                sender_id: self.agent,
+                recipient_ids: vec![self.agent],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: "Crash report".to_string(),
+                body_md: "The service crashed".to_string(),
+                thread_id: None,
+                importance: None,
+                ack_required: false,
+            },
+        )
+        .await
+        .unwrap();
+        MessageBmc::set_custom_fields(&self.tc.ctx, &self.tc.mm, id, &validated)
+            .await
+            .unwrap();
+        id
+    }
+}
+
+#[tokio::test]
+async fn test_validate_enforces_schema() {
+    let s = setup().await;
+    let validate = |values: Value| {
+        let values = fields(values);
+        let tc = &s.tc;
+        let project_id = s.project_id;
+        async move {
+            MessageBmc::validate_custom_fields(&tc.ctx, &tc.mm, project_id, Some(&values)).await
+        }
+    };
+
+    // Numbers given as strings are converted
+    let ok = validate(json!({"component": "db", "severity": "2"}))
+        .await
+        .unwrap();
+    assert_eq!(ok["severity"], json!(2));
+
+    let missing = validate(json!({"severity": 1})).await.unwrap_err();
+    assert!(matches!(missing, Error::InvalidInput(ref m) if m.contains("component")));
+
+    let mistyped = validate(json!({"component": "db", "severity": "high"}))
+        .await
+        .unwrap_err();
+    assert!(matches!(mistyped, Error::InvalidInput(ref m) if m.contains("must be a number")));
+
+    let unknown = validate(json!({"component": "db", "owner": "me"}))
+        .await
+        .unwrap_err();
+    assert!(matches!(unknown, Error::InvalidInput(ref m) if m.contains("component, severity")));
+
+    let none = MessageBmc::validate_custom_fields(&s.tc.ctx, &s.tc.mm, s.project_id, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(none, Error::InvalidInput(_)));
+}
+
+#[tokio::test]
+async fn test_search_filters_by_fields() {
+    let s = setup().await;
+    let db_high = s.send(json!({"component": "db", "severity": 3})).await;
+    let db_low = s.send(json!({"component": "db", "severity": 1})).await;
+    let api = s.send(json!({"component": "api", "severity": 3})).await;
+
+    let search = |values: Value| {
+        let values = fields(values);
+        let tc = &s.tc;
+        let project_id = s.project_id;
+        async move {
+            let filters = CustomFieldBmc::filters(&tc.ctx, &tc.mm, project_id, &values)
+                .await
+                .unwrap();
+            let mut ids: Vec<i64> = MessageBmc::search_with_snippets(
+                &tc.ctx, &tc.mm, project_id, "crashed", 10, 0, false, &filters,
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.message.id)
+            .collect();
+            ids.sort();
+            ids
+        }
+    };
+
+    assert_eq!(
+        search(json!({})).await,
+        [db_high.get(), db_low.get(), api.get()]
+    );
+    assert_eq!(
+        search(json!({"component": "db"})).await,
+        [db_high.get(), db_low.get()]
+    );
+    assert_eq!(
+        search(json!({"severity": "3"})).await,
+        [db_high.get(), api.get()]
+    );
+    assert_eq!(
+        search(json!({"component": "db", "severity": 3})).await,
+        [db_high.get()]
+    );
+
+    let hits = MessageBmc::search_with_snippets(
+        &s.tc.ctx,
+        &s.tc.mm,
+        s.project_id,
+        "crashed",
+        1,
+        0,
+        false,
+        &[],
+    )
+    .await
+    .unwrap();
+    assert!(
+        hits[0]
+            .custom_fields
+            .as_ref()
+            .unwrap()
+            .contains_key("component")
+    );
+}
+
+#[tokio::test]
+async fn test_replies_inherit_fields_and_delete() {
+    let s = setup().await;
+    let parent = s.send(json!({"component": "db"})).await;
+    let reply = MessageBmc::create(
+        &s.tc.ctx,
+        &s.tc.mm,
+        MessageForCreate {
+            project_id: s.project_id,
+            sender_id: s.agent,
+            recipient_ids: vec![s.agent],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Re: Crash report".to_string(),
+            body_md: "Looking".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    MessageBmc::inherit_custom_fields(&s.tc.ctx, &s.tc.mm, parent, reply)
+        .await
+        .unwrap();
+    let stored = MessageBmc::get_custom_fields(&s.tc.ctx, &s.tc.mm, &[parent.get(), reply.get()])
+        .await
+        .unwrap();
+    assert_eq!(stored[&reply.get()], stored[&parent.get()]);
+
+    CustomFieldBmc::delete(&s.tc.ctx, &s.tc.mm, s.project_id, "severity")
+        .await
+        .unwrap();
+    let err = CustomFieldBmc::delete(&s.tc.ctx, &s.tc.mm, s.project_id, "severity")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::NotFound));
+    let names: Vec<String> = CustomFieldBmc::list(&s.tc.ctx, &s.tc.mm, s.project_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|f| f.name)
+        .collect();
+    assert_eq!(names, ["component"]);
+}
@@ -0,0 +1,84 @@
+//! Database maintenance tests
+//!
+//! Tests that `DbMaintenanceBmc::maintain` checks integrity, honours its
+//! free page threshold and dry-run option, and reclaims free pages.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::db_maintenance::{DbMaintenanceBmc, MaintainOptions};
+
+/// Fills a scratch table and drops it, leaving its pages on the freelist.
+async fn make_free_pages(tc: &TestContext) {
+    let db = tc.mm.db_for_test();
+    db.execute("CREATE TABLE scratch (data BLOB)", ())
+        .await
+        .unwrap();
+    for _ in 0..200 {
+        db.execute("INSERT INTO scratch (data) VALUES (zeroblob(4096))", ())
+            .await
+            .unwrap();
+    }
+    db.execute("DROP TABLE scratch", ()).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_maintain_vacuums_free_pages() {
+    let tc = TestContext::new().await.unwrap();
+    make_free_pages(&tc).await;
+    let before = DbMaintenanceBmc::stats(&tc.ctx, &tc.mm).await.unwrap();
+    assert!(before.free_pages >= 200);
+
+    let report = DbMaintenanceBmc::maintain(
+        &tc.ctx,
+        &tc.mm,
+        &MaintainOptions {
+            dry_run: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(report.integrity_ok());
+    assert!(!report.analyzed);
+    assert!(!report.vacuumed);
+    assert_eq!(report.after, before);
+
+    // Below the threshold only ANALYZE runs
+    let report = DbMaintenanceBmc::maintain(
+        &tc.ctx,
+        &tc.mm,
+        &MaintainOptions {
+            vacuum_free_ratio: 1.0,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(report.analyzed);
+    assert!(!report.vacuumed);
+    // ANALYZE may take a few free pages for its statistics tables
+    assert!(report.after.free_pages >= 200);
+
+    let report = DbMaintenanceBmc::maintain(
+        &tc.ctx,
+        &tc.mm,
+        &MaintainOptions {
+            force_vacuum: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(report.vacuumed);
+    assert_eq!(report.after.free_pages, 0);
+    assert!(report.after.page_count < before.page_count);
+    assert_eq!(
+        report.reclaimed_bytes,
+        before.size_bytes() - report.after.size_bytes()
+    );
+}
@@ -0,0 +1,162 @@
+//! Dead-letter store tests
+//!
+//! Tests keeping undeliverable sends, listing them, and requeueing them
+//! after the project they were addressed to was adopted into another.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::dead_letter::{
+    DeadLetterBmc, DeadLetterFilter, DeadLetterForCreate, DeadLetterRequeue, STATUS_PENDING,
+    STATUS_REQUEUED,
+};
+use mouchak_mail_core::model::message::MessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::MessageId;
+
+fn letter(project_slug: &str, to: &[&str]) -> DeadLetterForCreate {
+    DeadLetterForCreate {
+        project_slug: project_slug.into(),
+        sender_name: "alice".into(),
+        to: to.iter().map(|s| s.to_string()).collect(),
+        subject: "Status".into(),
+        body_md: "All green".into(),
+        ack_required: true,
+        error: "Agent not found: alice".into(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_requeue_into_adopting_project() {
+    let tc = TestContext::new().await.unwrap();
+    let old = ProjectBmc::create(&tc.ctx, &tc.mm, "old-repo", "old-repo")
+        .await
+        .unwrap();
+    let new = ProjectBmc::create(&tc.ctx, &tc.mm, "new-repo", "new-repo")
+        .await
+        .unwrap();
+    create_agent(&tc, old, "alice").await;
+    create_agent(&tc, old, "bob").await;
+    ProjectBmc::adopt(&tc.ctx, &tc.mm, old, new).await.unwrap();
+
+    let id = DeadLetterBmc::record(&tc.ctx, &tc.mm, letter("old-repo", &["bob"]))
+        .await
+        .unwrap();
+    let pending = DeadLetterBmc::list(&tc.ctx, &tc.mm, &DeadLetterFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].to, vec!["bob"]);
+    assert_eq!(pending[0].attempts, 1);
+
+    // The agents moved with the adoption, so the old project still fails
+    let err = DeadLetterBmc::requeue(&tc.ctx, &tc.mm, id, DeadLetterRequeue::default())
+        .await
+        .unwrap_err();
+    assert!(DeadLetterBmc::is_undeliverable(&err));
+    let letter = DeadLetterBmc::get(&tc.ctx, &tc.mm, id).await.unwrap();
+    assert_eq!(letter.status, STATUS_PENDING);
+    assert_eq!(letter.attempts, 2);
+
+    let message_id = DeadLetterBmc::requeue(
+        &tc.ctx,
+        &tc.mm,
+        id,
+        DeadLetterRequeue {
+            project_slug: Some("new-repo".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let message = MessageBmc::get(&tc.ctx, &tc.mm, message_id.into())
+        .await
+        .unwrap();
+    assert_eq!(message.project_id, new.get());
+    assert_eq!(message.sender_name, "alice");
+    assert!(message.ack_required);
+
+    let letter = DeadLetterBmc::get(&tc.ctx, &tc.mm, id).await.unwrap();
+    assert_eq!(letter.status, STATUS_REQUEUED);
+    assert_eq!(letter.project_slug, "new-repo");
+    assert_eq!(letter.message_id, Some(message_id));
+
+    // Requeued entries are listed on request only, and never delivered twice
+    let filter = DeadLetterFilter::default();
+    assert!(
+        DeadLetterBmc::list(&tc.ctx, &tc.mm, &filter)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let filter = DeadLetterFilter {
+        include_requeued: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        DeadLetterBmc::list(&tc.ctx, &tc.mm, &filter)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(matches!(
+        DeadLetterBmc::requeue(&tc.ctx, &tc.mm, id, DeadLetterRequeue::default()).await,
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_list_filter_broadcast_and_discard() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "team", "team")
+        .await
+        .unwrap();
+    for name in ["alice", "bob", "carol"] {
+        create_agent(&tc, project, name).await;
+    }
+
+    let broadcast = DeadLetterBmc::record(&tc.ctx, &tc.mm, letter("team", &["broadcast"]))
+        .await
+        .unwrap();
+    let elsewhere = DeadLetterBmc::record(&tc.ctx, &tc.mm, letter("gone", &["bob"]))
+        .await
+        .unwrap();
+
+    let filter = DeadLetterFilter {
+        project_slug: Some("gone".into()),
+        ..Default::default()
+    };
+    let listed = DeadLetterBmc::list(&tc.ctx, &tc.mm, &filter).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, elsewhere);
+
+    let message_id =
+        DeadLetterBmc::requeue(&tc.ctx, &tc.mm, broadcast, DeadLetterRequeue::default())
+            .await
+            .unwrap();
+    let recipients = MessageBmc::get_recipients(&tc.ctx, &tc.mm, MessageId::new(message_id))
+        .await
+        .unwrap();
+    assert!(recipients.contains(&"bob".to_string()));
+    assert!(recipients.contains(&"carol".to_string()));
+
+    DeadLetterBmc::discard(&tc.ctx, &tc.mm, elsewhere)
+        .await
+        .unwrap();
+    assert!(matches!(
+        DeadLetterBmc::get(&tc.ctx, &tc.mm, elsewhere).await,
+        Err(Error::NotFound)
+    ));
+    assert!(
+        DeadLetterBmc::discard(&tc.ctx, &tc.mm, elsewhere)
+            .await
+            .is_err()
+    );
+}
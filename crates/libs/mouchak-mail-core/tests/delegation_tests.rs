@@ -0,0 +1,196 @@
+//! Delegation tests
+//!
+//! Tests that delegates can send on behalf of their grantor only while a
+//! grant exists, and that such messages record who wrote them.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::delegation::DelegationBmc;
+use mouchak_mail_core::model::event_log::EventLogBmc;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+fn message(project_id: ProjectId, from: AgentId, to: AgentId) -> MessageForCreate {
+    MessageForCreate {
+        project_id,
+        sender_id: from,
+        recipient_ids: vec![to],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Status".to_string(),
+        body_md: "Drafted by the supervisor".to_string(),
+        thread_id: None,
+        importance: None,
+        ack_required: false,
+    }
+}
+
+#[tokio::test]
+async fn test_send_on_behalf_requires_grant() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "deleg", "/deleg")
+        .await
+        .unwrap();
+    let worker = create_agent(&tc, project_id, "worker").await;
+    let supervisor = create_agent(&tc, project_id, "supervisor").await;
+    let lead = create_agent(&tc, project_id, "lead").await;
+
+    let denied = MessageBmc::create_on_behalf(
+        &tc.ctx,
+        &tc.mm,
+        supervisor,
+        message(project_id, worker, lead),
+    )
+    .await;
+    assert!(matches!(denied, Err(Error::DelegationDenied(_))));
+
+    assert!(
+        DelegationBmc::grant(&tc.ctx, &tc.mm, project_id, worker, supervisor)
+            .await
+            .unwrap()
+    );
+    assert!(
+        !DelegationBmc::grant(&tc.ctx, &tc.mm, project_id, worker, supervisor)
+            .await
+            .unwrap()
+    );
+    let id = MessageBmc::create_on_behalf(
+        &tc.ctx,
+        &tc.mm,
+        supervisor,
+        message(project_id, worker, lead),
+    )
+    .await
+    .unwrap();
+
+    let sent = MessageBmc::get(&tc.ctx, &tc.mm, id).await.unwrap();
+    assert_eq!(sent.sender_name, "worker");
+    let delegates = DelegationBmc::delegates_for_messages(&tc.ctx, &tc.mm, &[id.get()])
+        .await
+        .unwrap();
+    assert_eq!(
+        delegates.get(&id.get()).map(String::as_str),
+        Some("supervisor")
+    );
+
+    let kinds: Vec<String> = EventLogBmc::list(&tc.ctx, &tc.mm, 0, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.kind)
+        .collect();
+    assert!(kinds.iter().any(|k| k == "delegation.granted"));
+    assert!(kinds.iter().any(|k| k == "message.sent_on_behalf"));
+
+    // Revoking stops further delegated sends but keeps past ones attributed
+    assert!(
+        DelegationBmc::revoke(&tc.ctx, &tc.mm, project_id, worker, supervisor)
+            .await
+            .unwrap()
+    );
+    let denied = MessageBmc::create_on_behalf(
+        &tc.ctx,
+        &tc.mm,
+        supervisor,
+        message(project_id, worker, lead),
+    )
+    .await;
+    assert!(matches!(denied, Err(Error::DelegationDenied(_))));
+    let delegates = DelegationBmc::delegates_for_messages(&tc.ctx, &tc.mm, &[id.get()])
+        .await
+        .unwrap();
+    assert_eq!(delegates.len(), 1);
+}
+
+#[tokio::test]
+async fn test_delegated_send_is_atomic() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "atomic", "/atomic")
+        .await
+        .unwrap();
+    let worker = create_agent(&tc, project_id, "worker").await;
+    let supervisor = create_agent(&tc, project_id, "supervisor").await;
+    let lead = create_agent(&tc, project_id, "lead").await;
+    DelegationBmc::grant(&tc.ctx, &tc.mm, project_id, worker, supervisor)
+        .await
+        .unwrap();
+
+    // Without its delegation record the message is not sent either
+    tc.mm
+        .db_for_test()
+        .execute("DROP TABLE message_delegations", ())
+        .await
+        .unwrap();
+    let failed = MessageBmc::create_on_behalf(
+        &tc.ctx,
+        &tc.mm,
+        supervisor,
+        message(project_id, worker, lead),
+    )
+    .await;
+    assert!(failed.is_err());
+    assert!(
+        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, lead, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_grant_validation_and_listing() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "deleg-list", "/deleg-list")
+        .await
+        .unwrap();
+    let other_project = ProjectBmc::create(&tc.ctx, &tc.mm, "deleg-other", "/deleg-other")
+        .await
+        .unwrap();
+    let worker = create_agent(&tc, project_id, "worker").await;
+    let supervisor = create_agent(&tc, project_id, "supervisor").await;
+    let reviewer = create_agent(&tc, project_id, "reviewer").await;
+    let outsider = create_agent(&tc, other_project, "outsider").await;
+
+    let to_self = DelegationBmc::grant(&tc.ctx, &tc.mm, project_id, worker, worker).await;
+    assert!(matches!(to_self, Err(Error::InvalidInput(_))));
+    let cross = DelegationBmc::grant(&tc.ctx, &tc.mm, project_id, worker, outsider).await;
+    assert!(matches!(cross, Err(Error::InvalidInput(_))));
+
+    DelegationBmc::grant(&tc.ctx, &tc.mm, project_id, worker, supervisor)
+        .await
+        .unwrap();
+    DelegationBmc::grant(&tc.ctx, &tc.mm, project_id, reviewer, supervisor)
+        .await
+        .unwrap();
+
+    let all = DelegationBmc::list(&tc.ctx, &tc.mm, project_id, None)
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 2);
+    let for_worker = DelegationBmc::list(&tc.ctx, &tc.mm, project_id, Some(worker))
+        .await
+        .unwrap();
+    assert_eq!(for_worker.len(), 1);
+    assert_eq!(for_worker[0].delegate_name, "supervisor");
+
+    // Sending as oneself needs no grant
+    let id = MessageBmc::create_on_behalf(
+        &tc.ctx,
+        &tc.mm,
+        worker,
+        message(project_id, worker, reviewer),
+    )
+    .await
+    .unwrap();
+    let delegates = DelegationBmc::delegates_for_messages(&tc.ctx, &tc.mm, &[id.get()])
+        .await
+        .unwrap();
+    assert!(delegates.is_empty());
+}
@@ -0,0 +1,165 @@
+//! Disk watch tests
+//!
+//! Tests for overseer alerts on low disk space and the read-only mode
+//! entered before the disk fills up.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::disk_watch::{
+    DISK_WATCH_AGENT_NAME, DiskLevel, DiskSpace, DiskThresholds, DiskWatcher,
+};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::overseer_message::OverseerMessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+const MB: u64 = 1024 * 1024;
+
+fn space(free_mb: u64) -> DiskSpace {
+    DiskSpace {
+        total_bytes: 10_000 * MB,
+        free_bytes: free_mb * MB,
+    }
+}
+
+fn message(project_id: ProjectId, sender_id: i64, recipient_id: i64) -> MessageForCreate {
+    MessageForCreate {
+        project_id,
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Status".into(),
+        body_md: "Still going".into(),
+        thread_id: None,
+        importance: None,
+        ack_required: false,
+    }
+}
+
+#[tokio::test]
+async fn test_disk_watch_alerts_and_read_only() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "disk-watch", "Disk Watch")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["alice", "bob"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id.get());
+    }
+    let (alice, bob) = (agents[0], agents[1]);
+
+    let mut watcher = DiskWatcher::new(
+        tc.repo_root(),
+        DiskThresholds {
+            warn_free_bytes: 1024 * MB,
+            read_only_free_bytes: 256 * MB,
+        },
+    );
+    let overseer = |tc: &TestContext| {
+        let ctx = tc.ctx.clone();
+        let mm = tc.mm.clone();
+        async move {
+            OverseerMessageBmc::list_for_project(&ctx, &mm, project_id, true, 100)
+                .await
+                .unwrap()
+        }
+    };
+
+    // Plenty of space: nothing happens
+    let status = watcher.apply(&tc.ctx, &tc.mm, space(5000)).await.unwrap();
+    assert_eq!(status.level, DiskLevel::Ok);
+    assert!(!status.changed());
+    assert!(overseer(&tc).await.is_empty());
+
+    // Low: overseers are warned, writes still work
+    let status = watcher.apply(&tc.ctx, &tc.mm, space(800)).await.unwrap();
+    assert_eq!(status.level, DiskLevel::Low);
+    assert!(!status.read_only);
+    let alerts = overseer(&tc).await;
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].importance, "high");
+    assert!(alerts[0].subject.contains("800 MB free"));
+    let sender = AgentBmc::get_by_name(&tc.ctx, &tc.mm, project_id, DISK_WATCH_AGENT_NAME)
+        .await
+        .unwrap();
+    assert_eq!(alerts[0].sender_id, sender.id.get());
+    MessageBmc::create(&tc.ctx, &tc.mm, message(project_id, alice, bob))
+        .await
+        .unwrap();
+
+    // Critical: read-only, reads still work
+    let status = watcher.apply(&tc.ctx, &tc.mm, space(100)).await.unwrap();
+    assert_eq!(status.level, DiskLevel::Critical);
+    assert!(status.read_only);
+    assert!(tc.mm.is_read_only());
+    let alerts = overseer(&tc).await;
+    assert_eq!(alerts.len(), 2);
+    assert!(alerts.iter().any(|a| a.importance == "critical"));
+    let res = MessageBmc::create(&tc.ctx, &tc.mm, message(project_id, alice, bob)).await;
+    assert!(
+        matches!(res, Err(mouchak_mail_core::Error::ReadOnly(_))),
+        "Expected ReadOnly error, got {:?}",
+        res
+    );
+    // Writes without their own check are refused by SQLite
+    assert!(
+        AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: "carol".into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .is_err()
+    );
+    let inbox =
+        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, AgentId::new(bob), 10)
+            .await
+            .unwrap();
+    assert_eq!(inbox.len(), 1);
+
+    // Freed a little: stays read-only until above the warning threshold
+    let status = watcher.apply(&tc.ctx, &tc.mm, space(600)).await.unwrap();
+    assert_eq!(status.level, DiskLevel::Critical);
+    assert!(tc.mm.is_read_only());
+
+    // Recovered: writes resume and overseers are told
+    let status = watcher.apply(&tc.ctx, &tc.mm, space(2000)).await.unwrap();
+    assert_eq!(status.level, DiskLevel::Ok);
+    assert_eq!(status.previous, DiskLevel::Critical);
+    assert!(!tc.mm.is_read_only());
+    let alerts = overseer(&tc).await;
+    assert_eq!(alerts.len(), 3);
+    assert!(alerts.iter().any(|a| a.subject.contains("recovered")));
+    MessageBmc::create(&tc.ctx, &tc.mm, message(project_id, alice, bob))
+        .await
+        .unwrap();
+}
@@ -0,0 +1,252 @@
+//! Email bridge tests
+//!
+//! Tests the per-project opt-in through project settings and the collection
+//! of messages to forward behind the bridge cursor, including held messages
+//! that are forwarded once approved.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::approval::{ApprovalBmc, ApprovalRuleForCreate};
+use mouchak_mail_core::model::email_bridge::EmailBridgeBmc;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::project_settings::{ProjectSettingsBmc, ProjectSettingsForUpdate};
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    subject: &str,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.into(),
+            body_md: "Database is unreachable".into(),
+            thread_id: None,
+            importance: Some("urgent".into()),
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn opt_in(recipients: &str) -> ProjectSettingsForUpdate {
+    ProjectSettingsForUpdate {
+        email_bridge: Some(true),
+        email_recipients: Some(recipients.to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_opt_in_through_settings() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "ops", "/ops")
+        .await
+        .unwrap();
+
+    let settings = ProjectSettingsBmc::get(&tc.ctx, &tc.mm, project)
+        .await
+        .unwrap();
+    assert!(!settings.email_bridge);
+
+    let settings = ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project,
+        opt_in("oncall@example.com, lead@example.com"),
+    )
+    .await
+    .unwrap();
+    assert!(settings.email_bridge);
+    assert_eq!(
+        settings.email_recipients,
+        vec!["oncall@example.com", "lead@example.com"]
+    );
+
+    // Other settings leave the opt-in alone
+    let settings = ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project,
+        ProjectSettingsForUpdate {
+            timezone: Some("Europe/Berlin".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(settings.email_bridge);
+    assert_eq!(settings.email_recipients.len(), 2);
+
+    let err = ProjectSettingsBmc::update(&tc.ctx, &tc.mm, project, opt_in("oncall"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+
+    let settings = ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project,
+        ProjectSettingsForUpdate {
+            email_bridge: Some(false),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!settings.email_bridge);
+    assert!(settings.email_recipients.is_empty());
+    assert!(
+        EmailBridgeBmc::project_recipients(&tc.ctx, &tc.mm, project)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_pending_follows_opted_in_projects() {
+    let tc = TestContext::new().await.unwrap();
+    let ops = ProjectBmc::create(&tc.ctx, &tc.mm, "ops", "/ops")
+        .await
+        .unwrap();
+    let other = ProjectBmc::create(&tc.ctx, &tc.mm, "other", "/other")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, ops, "alice").await;
+    let bob = create_agent(&tc, ops, "bob").await;
+    let carol = create_agent(&tc, other, "carol").await;
+    let dave = create_agent(&tc, other, "dave").await;
+
+    // History before the bridge first ran is not forwarded
+    send(&tc, ops, alice, bob, "Old news").await;
+    ProjectSettingsBmc::update(&tc.ctx, &tc.mm, ops, opt_in(""))
+        .await
+        .unwrap();
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    assert!(batch.messages.is_empty());
+    EmailBridgeBmc::advance_cursor(&tc.ctx, &tc.mm, batch.scanned_to)
+        .await
+        .unwrap();
+
+    let root = send(&tc, ops, alice, bob, "Prod down").await;
+    send(&tc, other, carol, dave, "Prod down").await;
+    let reply = send(&tc, ops, bob, alice, "Re: Prod down").await;
+
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    let ids: Vec<i64> = batch.messages.iter().map(|m| m.message_id).collect();
+    assert_eq!(ids, vec![root.get(), reply.get()]);
+    assert_eq!(batch.scanned_to, reply.get());
+    let first = &batch.messages[0];
+    assert_eq!(first.project_slug, "ops");
+    assert_eq!(first.sender_name, "alice");
+    assert_eq!(first.body_md, "Database is unreachable");
+    assert!(first.recipients.is_empty());
+    assert!(!first.is_reply);
+    assert_eq!(first.email_subject(), "[ops] Prod down");
+    assert!(batch.messages[1].is_reply);
+    assert_eq!(batch.messages[1].email_subject(), "Re: [ops] Prod down");
+
+    // A full batch only scans up to its last message
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 1).await.unwrap();
+    assert_eq!(batch.messages.len(), 1);
+    assert_eq!(batch.scanned_to, root.get());
+
+    EmailBridgeBmc::advance_cursor(&tc.ctx, &tc.mm, reply.get())
+        .await
+        .unwrap();
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    assert!(batch.messages.is_empty());
+
+    // Deleting the project drops its opt-in
+    ProjectBmc::delete(&tc.ctx, &tc.mm, ops).await.unwrap();
+    assert!(
+        EmailBridgeBmc::project_recipients(&tc.ctx, &tc.mm, ops)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_pending_waits_for_approval() {
+    let tc = TestContext::new().await.unwrap();
+    let ops = ProjectBmc::create(&tc.ctx, &tc.mm, "ops", "/ops")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, ops, "alice").await;
+    let bob = create_agent(&tc, ops, "bob").await;
+    ProjectSettingsBmc::update(&tc.ctx, &tc.mm, ops, opt_in(""))
+        .await
+        .unwrap();
+    ApprovalBmc::create_rule(
+        &tc.ctx,
+        &tc.mm,
+        ApprovalRuleForCreate {
+            project_id: ops,
+            min_importance: None,
+            min_recipients: None,
+            keyword: Some("deploy".to_string()),
+            approvers: Vec::new(),
+            timeout_seconds: 3600,
+        },
+    )
+    .await
+    .unwrap();
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    EmailBridgeBmc::advance_cursor(&tc.ctx, &tc.mm, batch.scanned_to)
+        .await
+        .unwrap();
+
+    let approved = send(&tc, ops, alice, bob, "Deploy v2").await;
+    let rejected = send(&tc, ops, alice, bob, "Deploy v3").await;
+    let plain = send(&tc, ops, alice, bob, "Prod down").await;
+
+    // Held mail is not forwarded, but does not hold up later mail either
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    let ids: Vec<i64> = batch.messages.iter().map(|m| m.message_id).collect();
+    assert_eq!(ids, vec![plain.get()]);
+    assert_eq!(batch.scanned_to, plain.get());
+    EmailBridgeBmc::advance_cursor(&tc.ctx, &tc.mm, batch.scanned_to)
+        .await
+        .unwrap();
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    assert!(batch.messages.is_empty());
+
+    ApprovalBmc::decide(&tc.ctx, &tc.mm, approved, Some(bob), true, None)
+        .await
+        .unwrap();
+    ApprovalBmc::decide(&tc.ctx, &tc.mm, rejected, Some(bob), false, None)
+        .await
+        .unwrap();
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    let ids: Vec<i64> = batch.messages.iter().map(|m| m.message_id).collect();
+    assert_eq!(ids, vec![approved.get()]);
+    assert_eq!(batch.messages[0].subject, "Deploy v2");
+
+    // Until handled, the approved message is offered again
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    assert_eq!(batch.messages.len(), 1);
+    EmailBridgeBmc::advance_cursor(&tc.ctx, &tc.mm, batch.scanned_to)
+        .await
+        .unwrap();
+    let batch = EmailBridgeBmc::pending(&tc.ctx, &tc.mm, 10).await.unwrap();
+    assert!(batch.messages.is_empty());
+}
@@ -0,0 +1,283 @@
+//! Inbound email gateway tests
+//!
+//! Tests posting parsed mail into bridged threads, starting threads from
+//! tagged subjects, redeliveries and refused mail.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::email_ingest::{EmailIngestBmc, InboundEmail};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn setup() -> TestContext {
+    let mut config = AppConfig::default();
+    config.email_bridge.from = "bridge@example.com".into();
+    config.email_bridge.inbound_aliases =
+        "lead@example.com=Lead, ops@example.com=Ops, ghost@example.com=Ghost".into();
+    TestContext::new_with_config(config).await.unwrap()
+}
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    subject: &str,
+    thread_id: &str,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.into(),
+            body_md: "Database is unreachable".into(),
+            thread_id: Some(thread_id.into()),
+            importance: Some("urgent".into()),
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn mail(
+    from: &str,
+    to: &str,
+    subject: &str,
+    message_id: &str,
+    references: &[&str],
+) -> InboundEmail {
+    InboundEmail {
+        message_id: Some(message_id.into()),
+        from: from.into(),
+        to: vec![to.into()],
+        subject: subject.into(),
+        references: references.iter().map(|r| r.to_string()).collect(),
+        body: "Restarting the database now.".into(),
+        ..Default::default()
+    }
+}
+
+async fn refused(tc: &TestContext, email: &InboundEmail) -> Error {
+    EmailIngestBmc::ingest(&tc.ctx, &tc.mm, email)
+        .await
+        .unwrap_err()
+}
+
+#[tokio::test]
+async fn test_replies_land_in_bridged_threads() {
+    let tc = setup().await;
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "backend", "/backend")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project, "alice").await;
+    let bob = create_agent(&tc, project, "bob").await;
+    create_agent(&tc, project, "Lead").await;
+    create_agent(&tc, project, "Ops").await;
+    send(&tc, project, alice, bob, "Prod down", "T-1").await;
+    let reply = send(&tc, project, bob, alice, "Re: Prod down", "T-1").await;
+
+    // A reply to a later message of the thread
+    let reply_ref = format!("msg-{}.backend@example.com", reply);
+    let email = mail(
+        "lead@example.com",
+        "bridge@example.com",
+        "Re: [backend] Prod down",
+        "reply-1@mail.example.com",
+        &[&reply_ref, "thread-T-1.backend@example.com"],
+    );
+    let ingested = EmailIngestBmc::ingest(&tc.ctx, &tc.mm, &email)
+        .await
+        .unwrap();
+    assert!(!ingested.duplicate);
+    assert_eq!(ingested.project_slug.as_deref(), Some("backend"));
+    assert_eq!(ingested.thread_id.as_deref(), Some("T-1"));
+    let message_id = ingested.message_id.unwrap();
+    let message = MessageBmc::get(&tc.ctx, &tc.mm, message_id.into())
+        .await
+        .unwrap();
+    assert_eq!(message.sender_name, "Lead");
+    assert_eq!(message.subject, "Re: Prod down");
+    assert_eq!(message.body_md, "Restarting the database now.");
+    assert_eq!(message.importance, "normal");
+    assert_eq!(
+        MessageBmc::get_recipients(&tc.ctx, &tc.mm, MessageId::new(message_id))
+            .await
+            .unwrap(),
+        vec!["alice", "bob"]
+    );
+
+    // A redelivery posts nothing
+    let again = EmailIngestBmc::ingest(&tc.ctx, &tc.mm, &email)
+        .await
+        .unwrap();
+    assert!(again.duplicate);
+    assert_eq!(again.message_id, Some(message_id));
+
+    // A reply to the ingested mail joins the same thread
+    let ingested = EmailIngestBmc::ingest(
+        &tc.ctx,
+        &tc.mm,
+        &mail(
+            "OPS@example.com",
+            "lead@example.com",
+            "Re: Re: [backend] Prod down",
+            "reply-2@mail.example.com",
+            &["reply-1@mail.example.com"],
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(ingested.thread_id.as_deref(), Some("T-1"));
+    let message_id = ingested.message_id.unwrap();
+    assert_eq!(
+        MessageBmc::get_recipients(&tc.ctx, &tc.mm, MessageId::new(message_id))
+            .await
+            .unwrap(),
+        vec!["Lead", "alice", "bob"]
+    );
+
+    // Thread keys stand for thread ids with other characters
+    send(&tc, project, alice, bob, "Incident", "INC 7/a").await;
+    let ingested = EmailIngestBmc::ingest(
+        &tc.ctx,
+        &tc.mm,
+        &mail(
+            "lead@example.com",
+            "bridge@example.com",
+            "Re: [backend] Incident",
+            "reply-3@mail.example.com",
+            &["thread-INC-7-a.backend@example.com"],
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(ingested.thread_id.as_deref(), Some("INC 7/a"));
+}
+
+#[tokio::test]
+async fn test_tagged_subjects_start_threads() {
+    let tc = setup().await;
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "backend", "/backend")
+        .await
+        .unwrap();
+    create_agent(&tc, project, "Lead").await;
+    create_agent(&tc, project, "Ops").await;
+
+    // References of other domains are not bridged mail
+    let mut email = mail(
+        "ops@example.com",
+        "Lead@Example.com",
+        "[backend] Disk full",
+        "alert-1@monitor.example.com",
+        &["msg-1.backend@elsewhere.example.com"],
+    );
+    email.importance = Some("high".into());
+    let ingested = EmailIngestBmc::ingest(&tc.ctx, &tc.mm, &email)
+        .await
+        .unwrap();
+    let message = MessageBmc::get(&tc.ctx, &tc.mm, (ingested.message_id.unwrap()).into())
+        .await
+        .unwrap();
+    assert_eq!(message.subject, "Disk full");
+    assert_eq!(message.sender_name, "Ops");
+    assert_eq!(message.importance, "high");
+    assert_eq!(message.thread_id, ingested.thread_id);
+
+    // Machine-sent mail is skipped
+    let mut auto = mail(
+        "lead@example.com",
+        "ops@example.com",
+        "[backend] Out of office",
+        "auto-1@mail.example.com",
+        &[],
+    );
+    auto.auto_submitted = true;
+    let skipped = EmailIngestBmc::ingest(&tc.ctx, &tc.mm, &auto)
+        .await
+        .unwrap();
+    assert!(skipped.message_id.is_none());
+    assert!(skipped.skipped.is_some());
+
+    let unknown = mail(
+        "mallory@example.com",
+        "lead@example.com",
+        "[backend] Hi",
+        "x-1@example.com",
+        &[],
+    );
+    let untagged = mail(
+        "ops@example.com",
+        "lead@example.com",
+        "Disk full",
+        "x-2@example.com",
+        &[],
+    );
+    let no_project = mail(
+        "ops@example.com",
+        "lead@example.com",
+        "[frontend] Disk full",
+        "x-3@example.com",
+        &[],
+    );
+    let no_recipients = mail(
+        "ops@example.com",
+        "someone@example.com",
+        "[backend] Disk full",
+        "x-4@example.com",
+        &[],
+    );
+    let no_agent = mail(
+        "ghost@example.com",
+        "lead@example.com",
+        "[backend] Boo",
+        "x-5@example.com",
+        &[],
+    );
+    assert!(matches!(
+        refused(&tc, &unknown).await,
+        Error::ContactDenied(_)
+    ));
+    assert!(matches!(
+        refused(&tc, &untagged).await,
+        Error::InvalidInput(_)
+    ));
+    assert!(matches!(
+        refused(&tc, &no_project).await,
+        Error::ProjectNotFound { .. }
+    ));
+    assert!(matches!(
+        refused(&tc, &no_recipients).await,
+        Error::InvalidInput(_)
+    ));
+    assert!(matches!(
+        refused(&tc, &no_agent).await,
+        Error::AgentNotFound { .. }
+    ));
+
+    // Deleting the project forgets its ingested mail, so it is not a
+    // redelivery afterwards
+    ProjectBmc::delete(&tc.ctx, &tc.mm, project).await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "backend", "/backend")
+        .await
+        .unwrap();
+    create_agent(&tc, project, "Lead").await;
+    create_agent(&tc, project, "Ops").await;
+    let ingested = EmailIngestBmc::ingest(&tc.ctx, &tc.mm, &email)
+        .await
+        .unwrap();
+    assert!(!ingested.duplicate);
+}
@@ -6,6 +6,7 @@ use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::escalation::{EscalationBmc, EscalationMode};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::AgentId;
 use uuid::Uuid;
 
 #[tokio::test]
@@ -48,9 +49,9 @@ async fn test_list_overdue_acks() -> mouchak_mail_core::Result<()> {
 
     // 2. Create Overdue Message (Older than 24h)
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Overdue Subject".to_string(),
@@ -65,15 +66,15 @@ async fn test_list_overdue_acks() -> mouchak_mail_core::Result<()> {
     let db = mm.db_for_test();
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [overdue_msg_id],
+        [overdue_msg_id.get()],
     )
     .await?;
 
     // 3. Create Recent Message (Not Overdue)
     let msg_recent = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Recent Subject".to_string(),
@@ -86,9 +87,9 @@ async fn test_list_overdue_acks() -> mouchak_mail_core::Result<()> {
 
     // 4. Create Acked Message (Old but Acked)
     let msg_acked = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Acked Subject".to_string(),
@@ -101,17 +102,17 @@ async fn test_list_overdue_acks() -> mouchak_mail_core::Result<()> {
     // Backdate
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [acked_msg_id],
+        [acked_msg_id.get()],
     )
     .await?;
     // Ack it
-    MessageBmc::acknowledge(ctx, mm, acked_msg_id, recipient).await?;
+    MessageBmc::acknowledge(ctx, mm, acked_msg_id, recipient.into()).await?;
 
     // 5. Create Non-Ack-Required Message (Old but no ack required)
     let msg_no_ack = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "No Ack Subject".to_string(),
@@ -123,7 +124,7 @@ async fn test_list_overdue_acks() -> mouchak_mail_core::Result<()> {
     let no_ack_msg_id = MessageBmc::create(ctx, mm, msg_no_ack).await?;
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [no_ack_msg_id],
+        [no_ack_msg_id.get()],
     )
     .await?;
 
@@ -137,7 +138,7 @@ async fn test_list_overdue_acks() -> mouchak_mail_core::Result<()> {
         "Should find exactly 1 overdue message, found {}",
         overdue_list.len()
     );
-    assert_eq!(overdue_list[0].message_id, overdue_msg_id);
+    assert_eq!(overdue_list[0].message_id, overdue_msg_id.get());
     assert_eq!(overdue_list[0].subject, "Overdue Subject");
 
     Ok(())
@@ -181,9 +182,9 @@ async fn test_escalate_overdue_log_mode_dry_run() -> mouchak_mail_core::Result<(
     let recipient: i64 = recipient.into();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Test Escalation".to_string(),
@@ -197,14 +198,14 @@ async fn test_escalate_overdue_log_mode_dry_run() -> mouchak_mail_core::Result<(
     let db = mm.db_for_test();
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [msg_id],
+        [msg_id.get()],
     )
     .await?;
 
     let results = EscalationBmc::escalate_overdue(ctx, mm, 24, EscalationMode::Log, true).await?;
 
     assert_eq!(results.len(), 1);
-    assert_eq!(results[0].message_id, msg_id);
+    assert_eq!(results[0].message_id, msg_id.get());
     assert_eq!(results[0].action_taken, "log_dry_run");
     assert!(results[0].success);
 
@@ -249,9 +250,9 @@ async fn test_escalate_overdue_log_mode_real() -> mouchak_mail_core::Result<()>
     let recipient: i64 = recipient.into();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Log Real Test".to_string(),
@@ -265,14 +266,14 @@ async fn test_escalate_overdue_log_mode_real() -> mouchak_mail_core::Result<()>
     let db = mm.db_for_test();
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [msg_id],
+        [msg_id.get()],
     )
     .await?;
 
     let results = EscalationBmc::escalate_overdue(ctx, mm, 24, EscalationMode::Log, false).await?;
 
     assert_eq!(results.len(), 1);
-    assert_eq!(results[0].message_id, msg_id);
+    assert_eq!(results[0].message_id, msg_id.get());
     assert_eq!(results[0].action_taken, "logged");
     assert!(results[0].success);
     assert!(
@@ -324,9 +325,9 @@ async fn test_escalate_overdue_no_overdue_messages() -> mouchak_mail_core::Resul
     let recipient: i64 = recipient.into();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Recent Message".to_string(),
@@ -382,9 +383,9 @@ async fn test_escalate_overdue_overseer_mode_dry_run() -> mouchak_mail_core::Res
     let recipient: i64 = recipient.into();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Overseer Dry Run".to_string(),
@@ -398,7 +399,7 @@ async fn test_escalate_overdue_overseer_mode_dry_run() -> mouchak_mail_core::Res
     let db = mm.db_for_test();
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [msg_id],
+        [msg_id.get()],
     )
     .await?;
 
@@ -450,9 +451,9 @@ async fn test_escalate_overdue_overseer_mode_real() -> mouchak_mail_core::Result
     let recipient: i64 = recipient.into();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Overseer Real".to_string(),
@@ -466,7 +467,7 @@ async fn test_escalate_overdue_overseer_mode_real() -> mouchak_mail_core::Result
     let db = mm.db_for_test();
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [msg_id],
+        [msg_id.get()],
     )
     .await?;
 
@@ -525,9 +526,9 @@ async fn test_escalate_overdue_file_reservation_dry_run() -> mouchak_mail_core::
     let recipient: i64 = recipient.into();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "FileRes Dry".to_string(),
@@ -541,7 +542,7 @@ async fn test_escalate_overdue_file_reservation_dry_run() -> mouchak_mail_core::
     let db = mm.db_for_test();
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [msg_id],
+        [msg_id.get()],
     )
     .await?;
 
@@ -600,9 +601,9 @@ async fn test_escalate_overdue_file_reservation_real() -> mouchak_mail_core::Res
     let recipient: i64 = recipient.into();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "FileRes Real".to_string(),
@@ -616,7 +617,7 @@ async fn test_escalate_overdue_file_reservation_real() -> mouchak_mail_core::Res
     let db = mm.db_for_test();
     db.execute(
         "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
-        [msg_id],
+        [msg_id.get()],
     )
     .await?;
 
@@ -689,7 +690,7 @@ async fn test_send_reminder() -> mouchak_mail_core::Result<()> {
     };
 
     let reminder_id = EscalationBmc::send_reminder(ctx, mm, &overdue).await?;
-    assert!(reminder_id > 0);
+    assert!(reminder_id.get() > 0);
 
     // Verify the reminder message was created
     let reminder = MessageBmc::get(ctx, mm, reminder_id).await?;
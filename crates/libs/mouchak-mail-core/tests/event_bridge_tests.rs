@@ -0,0 +1,120 @@
+//! Event bridge tests
+//!
+//! Tests that event log records reach NATS and Redis, using minimal fake
+//! brokers that record what the bridge sends.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::TcpListener;
+use tokio::net::tcp::OwnedReadHalf;
+
+async fn next_line(lines: &mut Lines<BufReader<OwnedReadHalf>>) -> String {
+    tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+        .await
+        .expect("timed out waiting for the bridge")
+        .unwrap()
+        .expect("bridge closed the connection")
+}
+
+async fn context_for(url: String) -> TestContext {
+    let mut config = AppConfig::default();
+    config.event_bridge.url = url;
+    TestContext::new_with_config(config).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_nats_bridge_publishes_events() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let tc = context_for(format!("nats://bot:pw@{}", listener.local_addr().unwrap())).await;
+
+    let (stream, _) = listener.accept().await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    writer.write_all(b"INFO {}\r\n").await.unwrap();
+
+    let connect = next_line(&mut lines).await;
+    let options: Value = serde_json::from_str(connect.strip_prefix("CONNECT ").unwrap()).unwrap();
+    assert_eq!(options["user"], "bot");
+    assert_eq!(options["pass"], "pw");
+
+    // Keep-alives are answered
+    writer.write_all(b"PING\r\n").await.unwrap();
+    assert_eq!(next_line(&mut lines).await, "PONG");
+
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "bridged", "/bridged")
+        .await
+        .unwrap();
+
+    let publish = next_line(&mut lines).await;
+    let payload = next_line(&mut lines).await;
+    assert_eq!(
+        publish,
+        format!("PUB mouchak.project.created {}", payload.len())
+    );
+    let record: Value = serde_json::from_str(&payload).unwrap();
+    assert_eq!(record["kind"], "project.created");
+    assert_eq!(record["project_id"], project_id.get());
+    assert_eq!(record["id"], 1);
+}
+
+#[tokio::test]
+async fn test_redis_bridge_appends_to_stream() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut config = AppConfig::default();
+    config.event_bridge.url = format!("redis://:secret@{}/3", listener.local_addr().unwrap());
+    config.event_bridge.prefix = "mail-events".to_string();
+    config.event_bridge.max_len = 50;
+    let tc = TestContext::new_with_config(config).await.unwrap();
+
+    let (stream, _) = listener.accept().await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    // Reads one RESP array of bulk strings
+    async fn command(lines: &mut Lines<BufReader<OwnedReadHalf>>) -> Vec<String> {
+        let count: usize = next_line(lines).await[1..].parse().unwrap();
+        let mut args = Vec::new();
+        for _ in 0..count {
+            next_line(lines).await;
+            args.push(next_line(lines).await);
+        }
+        args
+    }
+
+    assert_eq!(command(&mut lines).await, ["AUTH", "secret"]);
+    writer.write_all(b"+OK\r\n").await.unwrap();
+    assert_eq!(command(&mut lines).await, ["SELECT", "3"]);
+    writer.write_all(b"+OK\r\n").await.unwrap();
+
+    ProjectBmc::create(&tc.ctx, &tc.mm, "streamed", "/streamed")
+        .await
+        .unwrap();
+
+    let xadd = command(&mut lines).await;
+    assert_eq!(
+        xadd[..8],
+        [
+            "XADD",
+            "mail-events",
+            "MAXLEN",
+            "~",
+            "50",
+            "*",
+            "kind",
+            "project.created"
+        ]
+    );
+    assert_eq!(xadd[8], "event");
+    let record: Value = serde_json::from_str(&xadd[9]).unwrap();
+    assert_eq!(record["kind"], "project.created");
+}
@@ -0,0 +1,125 @@
+//! Event bus tests
+//!
+//! Tests that mutations recorded in the event log reach bus subscribers,
+//! with the project and agent names subscribers filter on, and that
+//! expired reservations are announced once.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::{Duration, Utc};
+use mouchak_mail_core::model::file_reservation::{FileReservationBmc, FileReservationForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+
+#[tokio::test]
+async fn test_message_events_reach_subscribers() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "bus", "/bus")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+
+    let mut events = tc.mm.event_bus().subscribe();
+    let message_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: alice,
+            recipient_ids: vec![bob],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Ready for review".to_string(),
+            body_md: "PR is up".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: true,
+        },
+    )
+    .await
+    .unwrap();
+    // Only the first read is an event
+    for _ in 0..2 {
+        MessageBmc::mark_read(&tc.ctx, &tc.mm, message_id, bob)
+            .await
+            .unwrap();
+    }
+    MessageBmc::acknowledge(&tc.ctx, &tc.mm, message_id, bob)
+        .await
+        .unwrap();
+
+    let sent = events.recv().await.unwrap();
+    assert_eq!(sent.kind, "message.sent");
+    assert_eq!(sent.project_id, Some(project_id.get()));
+    assert_eq!(sent.actor.as_deref(), Some("alice"));
+    assert_eq!(sent.payload["recipients"][0], "bob");
+
+    let read = events.recv().await.unwrap();
+    assert_eq!(read.kind, "message.read");
+    assert_eq!(read.project_id, Some(project_id.get()));
+    assert_eq!(read.actor.as_deref(), Some("bob"));
+    assert_eq!(read.payload["sender"], "alice");
+
+    let acked = events.recv().await.unwrap();
+    assert_eq!(acked.kind, "message.acknowledged");
+    assert_eq!(acked.project_id, Some(project_id.get()));
+    assert_eq!(acked.actor.as_deref(), Some("bob"));
+    assert_eq!(acked.payload["sender"], "alice");
+    assert!(acked.id > read.id && read.id > sent.id);
+}
+
+#[tokio::test]
+async fn test_expired_reservations_announced_once() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "expiry", "/expiry")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let now = Utc::now().naive_utc();
+    for (pattern, ttl) in [
+        ("src/lapsed.rs", -Duration::minutes(5)),
+        ("src/live.rs", Duration::hours(1)),
+    ] {
+        FileReservationBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            FileReservationForCreate {
+                project_id,
+                agent_id: alice,
+                path_pattern: pattern.to_string(),
+                exclusive: true,
+                reason: "test".to_string(),
+                expires_ts: now + ttl,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let mut events = tc.mm.event_bus().subscribe();
+    let since = now - Duration::minutes(10);
+    let announced = FileReservationBmc::announce_expired(&tc.ctx, &tc.mm, since, now)
+        .await
+        .unwrap();
+    assert_eq!(announced, 1);
+    let expired = events.recv().await.unwrap();
+    assert_eq!(expired.kind, "file_reservation.expired");
+    assert_eq!(expired.project_id, Some(project_id.get()));
+    assert_eq!(expired.actor.as_deref(), Some("alice"));
+    assert_eq!(expired.payload["path_pattern"], "src/lapsed.rs");
+
+    // The next window starts where this one ended
+    let later = now + Duration::minutes(1);
+    assert_eq!(
+        FileReservationBmc::announce_expired(&tc.ctx, &tc.mm, now, later)
+            .await
+            .unwrap(),
+        0
+    );
+}
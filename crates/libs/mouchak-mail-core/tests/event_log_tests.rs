@@ -0,0 +1,131 @@
+//! Event log tests
+//!
+//! Tests for the hash-chained, append-only event log and tamper detection.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::event_log::{
+    EventForCreate, EventLogBmc, EventRecord, GENESIS_HASH, verify_chain,
+};
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde_json::json;
+
+async fn append(tc: &TestContext, kind: &str) {
+    EventLogBmc::append(
+        &tc.ctx,
+        &tc.mm,
+        EventForCreate::new(kind, None, Some("tester"), json!({ "kind": kind })),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_actions_are_appended_to_a_valid_chain() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "audit", "/audit")
+        .await
+        .unwrap();
+    AgentBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentForCreate {
+            project_id,
+            name: "BlueLake".to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: String::new(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let records = EventLogBmc::export(&tc.ctx, &tc.mm).await.unwrap();
+    let kinds: Vec<_> = records.iter().map(|r| r.kind.as_str()).collect();
+    assert_eq!(kinds, ["project.created", "agent.registered"]);
+    assert_eq!(records[0].prev_hash, GENESIS_HASH);
+    assert_eq!(records[1].prev_hash, records[0].hash);
+    assert_eq!(records[1].actor.as_deref(), Some("BlueLake"));
+    assert_eq!(records[1].project_id, Some(project_id.get()));
+
+    let verification = EventLogBmc::verify(&tc.ctx, &tc.mm).await.unwrap();
+    assert!(verification.valid);
+    assert_eq!(verification.records, 2);
+    assert_eq!(verification.head_hash, records[1].hash);
+}
+
+#[tokio::test]
+async fn test_event_log_rejects_updates_and_deletes() {
+    let tc = TestContext::new().await.unwrap();
+    append(&tc, "first").await;
+
+    let db = tc.mm.db_for_test();
+    assert!(
+        db.execute("UPDATE event_log SET kind = 'edited'", ())
+            .await
+            .is_err()
+    );
+    assert!(db.execute("DELETE FROM event_log", ()).await.is_err());
+    assert!(EventLogBmc::verify(&tc.ctx, &tc.mm).await.unwrap().valid);
+}
+
+#[tokio::test]
+async fn test_verify_detects_tampering() {
+    let tc = TestContext::new().await.unwrap();
+    for kind in ["first", "second", "third"] {
+        append(&tc, kind).await;
+    }
+
+    // Bypass the append-only triggers, as an attacker with file access could
+    let db = tc.mm.db_for_test();
+    db.execute("DROP TRIGGER event_log_no_update", ())
+        .await
+        .unwrap();
+    db.execute("DROP TRIGGER event_log_no_delete", ())
+        .await
+        .unwrap();
+
+    db.execute(
+        "UPDATE event_log SET payload = '{\"kind\":\"forged\"}' WHERE id = 2",
+        (),
+    )
+    .await
+    .unwrap();
+    let verification = EventLogBmc::verify(&tc.ctx, &tc.mm).await.unwrap();
+    assert!(!verification.valid);
+    assert_eq!(verification.first_invalid_id, Some(2));
+    assert_eq!(verification.records, 1);
+
+    db.execute("DELETE FROM event_log WHERE id = 2", ())
+        .await
+        .unwrap();
+    let verification = EventLogBmc::verify(&tc.ctx, &tc.mm).await.unwrap();
+    assert!(!verification.valid);
+    assert_eq!(verification.first_invalid_id, Some(3));
+}
+
+#[tokio::test]
+async fn test_verify_exported_records() {
+    let tc = TestContext::new().await.unwrap();
+    append(&tc, "first").await;
+    append(&tc, "second").await;
+
+    let mut records = EventLogBmc::export(&tc.ctx, &tc.mm).await.unwrap();
+    assert!(verify_chain(&records).valid);
+
+    // A round trip through JSON (the export format) keeps the chain valid
+    let exported = serde_json::to_string(&records).unwrap();
+    let imported: Vec<EventRecord> = serde_json::from_str(&exported).unwrap();
+    assert!(verify_chain(&imported).valid);
+
+    records[0].actor = Some("someone-else".to_string());
+    let verification = verify_chain(&records);
+    assert_eq!(verification.first_invalid_id, Some(1));
+    assert_eq!(verification.head_hash, GENESIS_HASH);
+}
@@ -0,0 +1,281 @@
+//! Export job tests
+//!
+//! Tests queueing bulk exports, running them into files, filtering the
+//! exported messages and cleaning jobs up.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::export::{ExportFilter, ExportFormat, ScrubMode};
+use mouchak_mail_core::model::export_job::{
+    ExportJobBmc, ExportJobForCreate, STATUS_COMPLETED, STATUS_FAILED, STATUS_QUEUED,
+};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    subject: &str,
+    importance: &str,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.into(),
+            body_md: "Contact ops@example.com".into(),
+            thread_id: None,
+            importance: Some(importance.into()),
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn export_of(
+    project_id: ProjectId,
+    format: ExportFormat,
+    filters: ExportFilter,
+) -> ExportJobForCreate {
+    ExportJobForCreate {
+        project_id,
+        format,
+        scrub: ScrubMode::Standard,
+        include_attachments: false,
+        filters,
+    }
+}
+
+#[tokio::test]
+async fn test_job_runs_into_a_file() {
+    let mut config = AppConfig::default();
+    config.exports.max_messages = 2;
+    let tc = TestContext::new_with_config(config).await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "bulk", "/bulk")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project, "alice").await;
+    let bob = create_agent(&tc, project, "bob").await;
+    send(&tc, project, alice, bob, "First", "normal").await;
+    send(&tc, project, bob, alice, "Second", "urgent").await;
+    send(&tc, project, alice, bob, "Third", "normal").await;
+    send(&tc, project, alice, bob, "Fourth", "normal").await;
+
+    // Limits above exports.max_messages are lowered to it
+    let job = ExportJobBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        export_of(
+            project,
+            ExportFormat::Csv,
+            ExportFilter {
+                sender: Some("ALICE".into()),
+                limit: Some(500),
+                ..Default::default()
+            },
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(job.status, STATUS_QUEUED);
+    assert_eq!(job.project_slug, "bulk");
+    assert_eq!(job.format, "csv");
+    assert_eq!(job.scrub, "standard");
+    assert_eq!(job.filters.limit, Some(2));
+    assert_eq!(job.filename(), format!("bulk_mailbox_{}.csv", job.id));
+
+    let finished = ExportJobBmc::run_next(&tc.ctx, &tc.mm)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(finished.id, job.id);
+    assert_eq!(finished.status, STATUS_COMPLETED);
+    assert_eq!(finished.message_count, Some(2));
+    assert!(finished.started_ts.is_some());
+    assert!(finished.completed_ts.is_some());
+
+    let content = std::fs::read_to_string(ExportJobBmc::file_path(&tc.mm, &finished)).unwrap();
+    assert_eq!(finished.size_bytes, Some(content.len() as i64));
+    assert!(content.contains("Fourth"));
+    assert!(content.contains("Third"));
+    assert!(!content.contains("Second"));
+    assert!(!content.contains("First"));
+    assert!(content.contains("[EMAIL]"));
+
+    assert!(
+        ExportJobBmc::run_next(&tc.ctx, &tc.mm)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Importance filter, newest first in the listing
+    let job = ExportJobBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        export_of(
+            project,
+            ExportFormat::Json,
+            ExportFilter {
+                importance: Some("urgent".into()),
+                ..Default::default()
+            },
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(ExportJobBmc::run_pending(&tc.ctx, &tc.mm).await.unwrap(), 1);
+    let job = ExportJobBmc::get(&tc.ctx, &tc.mm, job.id).await.unwrap();
+    assert_eq!(job.message_count, Some(1));
+    let jobs = ExportJobBmc::list(&tc.ctx, &tc.mm, Some(project), 10)
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 2);
+    assert_eq!(jobs[0].id, job.id);
+}
+
+#[tokio::test]
+async fn test_invalid_filters_are_rejected() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "bulk", "/bulk")
+        .await
+        .unwrap();
+
+    for filters in [
+        ExportFilter {
+            since: Some("last tuesday".into()),
+            ..Default::default()
+        },
+        ExportFilter {
+            since: Some("2025-02-01".into()),
+            until: Some("2025-01-01T00:00:00Z".into()),
+            ..Default::default()
+        },
+        ExportFilter {
+            limit: Some(0),
+            ..Default::default()
+        },
+    ] {
+        let err = ExportJobBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            export_of(project, ExportFormat::Json, filters),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{:?}", err);
+    }
+
+    let err = ExportJobBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        export_of(
+            ProjectId::new(999),
+            ExportFormat::Json,
+            ExportFilter::default(),
+        ),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, Error::ProjectNotFound { .. }), "{:?}", err);
+}
+
+#[tokio::test]
+async fn test_failed_requeued_and_expired_jobs() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "bulk", "/bulk")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project, "alice").await;
+    let bob = create_agent(&tc, project, "bob").await;
+    send(&tc, project, alice, bob, "Hello", "normal").await;
+    let create = || {
+        ExportJobBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            export_of(project, ExportFormat::Markdown, ExportFilter::default()),
+        )
+    };
+
+    // The exports directory cannot be created
+    let exports_dir = tc.mm.exports_dir();
+    std::fs::write(&exports_dir, "not a directory").unwrap();
+    let job = create().await.unwrap();
+    let failed = ExportJobBmc::run_next(&tc.ctx, &tc.mm)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(failed.id, job.id);
+    assert_eq!(failed.status, STATUS_FAILED);
+    assert!(failed.error.is_some());
+    std::fs::remove_file(&exports_dir).unwrap();
+
+    // Nothing is left running; queued jobs stay queued
+    let queued = create().await.unwrap();
+    assert_eq!(
+        ExportJobBmc::requeue_interrupted(&tc.ctx, &tc.mm)
+            .await
+            .unwrap(),
+        0
+    );
+    let done = ExportJobBmc::run_next(&tc.ctx, &tc.mm)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(done.id, queued.id);
+    let path = ExportJobBmc::file_path(&tc.mm, &done);
+    assert!(path.exists());
+
+    // Finished jobs older than the retention are removed with their files
+    assert_eq!(
+        ExportJobBmc::purge_expired(&tc.ctx, &tc.mm, 24)
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        ExportJobBmc::purge_expired(&tc.ctx, &tc.mm, 0)
+            .await
+            .unwrap(),
+        2
+    );
+    assert!(!path.exists());
+    assert!(matches!(
+        ExportJobBmc::get(&tc.ctx, &tc.mm, done.id).await,
+        Err(Error::NotFound)
+    ));
+
+    // Deleting the project removes its jobs and files
+    let job = create().await.unwrap();
+    let done = ExportJobBmc::run_next(&tc.ctx, &tc.mm)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(done.id, job.id);
+    let path = ExportJobBmc::file_path(&tc.mm, &done);
+    assert!(path.exists());
+    ProjectBmc::delete(&tc.ctx, &tc.mm, project).await.unwrap();
+    assert!(!path.exists());
+    assert!(
+        ExportJobBmc::list(&tc.ctx, &tc.mm, None, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
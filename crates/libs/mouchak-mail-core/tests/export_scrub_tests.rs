@@ -1,19 +1,21 @@
 #![allow(clippy::unwrap_used, clippy::expect_used)]
 
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
 use mouchak_mail_core::ctx::Ctx;
-use mouchak_mail_core::model::ModelManager;
 use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::export::{ExportBmc, ExportFormat, ScrubMode};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::AgentId;
 use uuid::Uuid;
 
 #[tokio::test]
 async fn test_export_scrubbing() -> mouchak_mail_core::Result<()> {
-    let mm = ModelManager::new(std::sync::Arc::new(
-        mouchak_mail_common::config::AppConfig::default(),
-    ))
-    .await?;
+    let tc = TestContext::new().await?;
+    let mm = tc.mm.clone();
     let ctx = Ctx::root_ctx();
 
     // 1. Setup
@@ -53,9 +55,9 @@ async fn test_export_scrubbing() -> mouchak_mail_core::Result<()> {
     let body = "Please process credit card 4111-1111-1111-1111 and SSN 123-45-6789. Contact support@company.com.";
 
     let msg = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender,
-        recipient_ids: vec![recipient],
+        project_id,
+        sender_id: sender.into(),
+        recipient_ids: vec![AgentId::new(recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: subject.to_string(),
@@ -18,6 +18,7 @@ use mouchak_mail_core::model::export::{ExportBmc, ExportFormat, ScrubMode};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
 use mouchak_mail_core::types::ProjectId;
+use mouchak_mail_core::utils::identicon::identicon_data_uri;
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to set up a project with messages for export tests
@@ -56,9 +57,9 @@ async fn setup_project_with_messages(tc: &TestContext, suffix: &str) -> (Project
     // Create some messages
     for i in 1..=3 {
         let msg = MessageForCreate {
-            project_id: project_id.get(),
-            sender_id: sender_id.into(),
-            recipient_ids: vec![recipient_id.into()],
+            project_id,
+            sender_id,
+            recipient_ids: vec![recipient_id],
             cc_ids: None,
             bcc_ids: None,
             subject: format!("Test Message {}", i),
@@ -129,6 +130,23 @@ async fn test_export_html() {
     assert!(exported.content.contains("<!DOCTYPE html>"));
     assert!(exported.content.contains("<title>"));
     assert!(exported.content.contains("Test Message"));
+
+    // Sender identicon is embedded
+    let avatar = identicon_data_uri("sender-agent", "claude-code", 32);
+    assert!(exported.content.contains(&avatar));
+
+    // ...but not when names are redacted
+    let redacted = ExportBmc::export_mailbox(
+        &tc.ctx,
+        &tc.mm,
+        &slug,
+        ExportFormat::Html,
+        ScrubMode::Aggressive,
+        false,
+    )
+    .await
+    .expect("Failed to export mailbox");
+    assert!(!redacted.content.contains("class=\"avatar\""));
 }
 
 /// Test exporting mailbox in Markdown format
@@ -186,6 +204,89 @@ async fn test_export_csv() {
     assert!(exported.content.contains("Test Message"));
 }
 
+/// Test exporting mailbox as an mbox of RFC 5322 messages
+#[tokio::test]
+async fn test_export_mbox() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let (_, slug) = setup_project_with_messages(&tc, "mbox").await;
+
+    let exported = ExportBmc::export_mailbox(
+        &tc.ctx,
+        &tc.mm,
+        &slug,
+        ExportFormat::Mbox,
+        ScrubMode::None,
+        true,
+    )
+    .await
+    .expect("Failed to export mailbox");
+
+    assert_eq!(exported.format, "mbox");
+    let sender = format!("sender-agent@{}.mouchak-mail.invalid", slug);
+    assert!(exported.content.starts_with(&format!("From {} ", sender)));
+    assert_eq!(exported.content.matches("\nMessage-ID: <").count(), 3);
+    assert!(exported.content.contains(&format!("\nFrom: {}\n", sender)));
+    assert!(exported.content.contains(&format!(
+        "\nTo: recipient-agent@{}.mouchak-mail.invalid\n",
+        slug
+    )));
+    assert!(exported.content.contains("\nSubject: Test Message 2\n"));
+    assert!(!exported.content.contains('\r'));
+}
+
+/// Test per-message EML export with attachments as MIME parts
+#[tokio::test]
+async fn test_export_eml_with_attachment() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let (project_id, slug) = setup_project_with_messages(&tc, "eml").await;
+    let messages = MessageBmc::list_recent(&tc.ctx, &tc.mm, project_id, 10)
+        .await
+        .unwrap();
+    let message_id = messages[0].id;
+
+    // Stored the way add_attachment stores files in the archive
+    let attachment_dir = tc
+        .repo_root()
+        .join("projects")
+        .join(&slug)
+        .join("attachments")
+        .join(format!("att_{}_0a1b2c3d", message_id));
+    std::fs::create_dir_all(&attachment_dir).unwrap();
+    std::fs::write(attachment_dir.join("build.log"), "linker failed").unwrap();
+
+    let files = ExportBmc::export_eml(&tc.ctx, &tc.mm, &slug, ScrubMode::None, true)
+        .await
+        .expect("Failed to export eml");
+    assert_eq!(files.len(), 3);
+    let eml = files
+        .iter()
+        .find(|f| f.filename == format!("{}.eml", message_id))
+        .expect("eml for the message");
+    assert!(eml.content.contains("Content-Type: multipart/mixed"));
+    assert!(
+        eml.content
+            .contains("Content-Disposition: attachment; filename=\"build.log\"")
+    );
+    // base64 of "linker failed"
+    assert!(eml.content.contains("bGlua2VyIGZhaWxlZA=="));
+
+    // Attachment content is not scrubbed, so scrubbed exports leave it out
+    let scrubbed = ExportBmc::export_eml(&tc.ctx, &tc.mm, &slug, ScrubMode::Standard, true)
+        .await
+        .unwrap();
+    assert!(
+        scrubbed
+            .iter()
+            .all(|f| !f.content.contains("multipart/mixed"))
+    );
+}
+
 /// Test exporting empty mailbox
 #[tokio::test]
 async fn test_export_empty_mailbox() {
@@ -232,11 +333,14 @@ async fn test_export_format_parsing() {
         ExportFormat::Markdown
     );
     assert_eq!(ExportFormat::from_str("csv").unwrap(), ExportFormat::Csv);
+    assert_eq!(ExportFormat::from_str("mbox").unwrap(), ExportFormat::Mbox);
     // Unknown defaults to JSON
     assert_eq!(
         ExportFormat::from_str("unknown").unwrap(),
         ExportFormat::Json
     );
+    assert_eq!(ExportFormat::from_name("MD"), Some(ExportFormat::Markdown));
+    assert_eq!(ExportFormat::from_name("unknown"), None);
 }
 
 /// Test export for nonexistent project
@@ -790,6 +894,8 @@ async fn test_scrub_mode_parsing() {
     );
     // Unknown defaults to None
     assert_eq!(ScrubMode::from_str("unknown").unwrap(), ScrubMode::None);
+    assert_eq!(ScrubMode::from_name("strict"), Some(ScrubMode::Aggressive));
+    assert_eq!(ScrubMode::from_name("unknown"), None);
 }
 
 /// Test encrypted export with identity roundtrip
@@ -996,8 +1102,8 @@ async fn test_export_html_xss_prevention() {
         &tc.ctx,
         &tc.mm,
         MessageForCreate {
-            project_id: project_id.get(),
-            sender_id: sender_id.into(),
+            project_id,
+            sender_id,
             recipient_ids: vec![],
             cc_ids: None,
             bcc_ids: None,
@@ -1069,8 +1175,8 @@ async fn test_export_unicode_content() {
         &tc.ctx,
         &tc.mm,
         MessageForCreate {
-            project_id: project_id.get(),
-            sender_id: sender_id.into(),
+            project_id,
+            sender_id,
             recipient_ids: vec![],
             cc_ids: None,
             bcc_ids: None,
@@ -216,8 +216,8 @@ async fn test_release_by_path() {
     let released_id = FileReservationBmc::release_by_path(
         &tc.ctx,
         &tc.mm,
-        project_id.get(),
-        agent_id,
+        project_id,
+        AgentId::new(agent_id),
         path_pattern,
     )
     .await
@@ -351,7 +351,7 @@ async fn test_list_all_for_project() {
         .expect("Failed to create reservation");
 
     // list_all_for_project should return both
-    let all = FileReservationBmc::list_all_for_project(&tc.ctx, &tc.mm, project_id.get())
+    let all = FileReservationBmc::list_all_for_project(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list all reservations");
 
@@ -672,3 +672,66 @@ async fn test_guard_ignores_expired_reservations() {
     )
     .await;
 }
+
+/// Test batch path checks return per-path verdicts and see changes immediately
+#[tokio::test]
+async fn test_check_paths_verdicts() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let (project_id, agent_id) = setup_project_and_agent(&tc).await;
+    let other_id = create_second_agent(&tc, project_id).await;
+
+    let reservation_id = FileReservationBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        FileReservationForCreate {
+            project_id,
+            agent_id: AgentId(agent_id),
+            path_pattern: "src/api".to_string(),
+            exclusive: true,
+            reason: "API work".to_string(),
+            expires_ts: Utc::now().naive_utc() + Duration::hours(1),
+        },
+    )
+    .await
+    .expect("Failed to create file reservation");
+
+    let paths = vec!["src/api/auth.rs".to_string(), "README.md".to_string()];
+    let verdicts = FileReservationBmc::check_paths(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        Some(AgentId(other_id)),
+        &paths,
+    )
+    .await
+    .expect("Failed to check paths");
+    assert_eq!(verdicts.len(), 2);
+    assert!(verdicts[0].reserved);
+    assert_eq!(verdicts[0].conflicts[0].agent_name, "test-agent");
+    assert_eq!(verdicts[0].conflicts[0].reservation_id, reservation_id);
+    assert!(!verdicts[1].reserved);
+
+    // The holder is never blocked by its own reservation
+    let own = FileReservationBmc::check_paths(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        Some(AgentId(agent_id)),
+        &paths,
+    )
+    .await
+    .expect("Failed to check paths");
+    assert!(own.iter().all(|v| !v.reserved));
+
+    // A release is visible right away despite the cached reservation set
+    FileReservationBmc::release(&tc.ctx, &tc.mm, reservation_id)
+        .await
+        .expect("Failed to release");
+    let verdicts = FileReservationBmc::check_paths(&tc.ctx, &tc.mm, project_id, None, &paths)
+        .await
+        .expect("Failed to check paths");
+    assert!(verdicts.iter().all(|v| !v.reserved));
+}
@@ -0,0 +1,203 @@
+//! Focus window tests
+//!
+//! Tests for project quiet periods that defer low-priority deliveries.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::{Duration, Utc};
+use mouchak_mail_core::model::event_log::EventLogBmc;
+use mouchak_mail_core::model::focus_window::{FocusWindowBmc, FocusWindowForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+fn message(
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    importance: &str,
+) -> MessageForCreate {
+    MessageForCreate {
+        project_id,
+        sender_id: from,
+        recipient_ids: vec![to],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: format!("{} update", importance),
+        body_md: "body".to_string(),
+        thread_id: None,
+        importance: Some(importance.to_string()),
+        ack_required: false,
+    }
+}
+
+fn window(project_id: ProjectId, minutes: i64) -> FocusWindowForCreate {
+    let starts_ts = Utc::now().naive_utc() - Duration::seconds(1);
+    FocusWindowForCreate {
+        project_id,
+        starts_ts,
+        ends_ts: starts_ts + Duration::minutes(minutes),
+        allowed_importance: vec!["urgent".to_string()],
+        reason: "release".to_string(),
+    }
+}
+
+/// Number of messages announced with a `message.sent` event.
+async fn announced(tc: &TestContext) -> usize {
+    EventLogBmc::list(&tc.ctx, &tc.mm, 0, 100)
+        .await
+        .unwrap()
+        .iter()
+        .filter(|e| e.kind == "message.sent")
+        .count()
+}
+
+#[tokio::test]
+async fn test_focus_window_defers_until_ended() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "focus-defer", "/focus/defer")
+        .await
+        .unwrap();
+    let sender = create_agent(&tc, project_id, "sender").await;
+    let reader = create_agent(&tc, project_id, "reader").await;
+
+    let window_id = FocusWindowBmc::create(&tc.ctx, &tc.mm, window(project_id, 30))
+        .await
+        .unwrap();
+    let active = FocusWindowBmc::get_active(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap()
+        .expect("window should be active");
+    assert_eq!(active.id, window_id);
+
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        message(project_id, sender, reader, "normal"),
+    )
+    .await
+    .unwrap();
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        message(project_id, sender, reader, "urgent"),
+    )
+    .await
+    .unwrap();
+
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, reader, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].importance, "urgent");
+    // The deferred message is announced once it is delivered
+    assert_eq!(announced(&tc).await, 1);
+    assert_eq!(
+        FocusWindowBmc::count_deferred(&tc.ctx, &tc.mm, window_id)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        FocusWindowBmc::count_all_deferred(&tc.ctx, &tc.mm)
+            .await
+            .unwrap(),
+        1
+    );
+
+    let delivered = FocusWindowBmc::end(&tc.ctx, &tc.mm, window_id)
+        .await
+        .unwrap();
+    assert_eq!(delivered, 1);
+    assert_eq!(
+        FocusWindowBmc::count_all_deferred(&tc.ctx, &tc.mm)
+            .await
+            .unwrap(),
+        0
+    );
+
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, reader, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 2);
+    assert_eq!(announced(&tc).await, 2);
+    assert!(
+        FocusWindowBmc::get_active(&tc.ctx, &tc.mm, project_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_deliver_due_releases_elapsed_windows() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "focus-due", "/focus/due")
+        .await
+        .unwrap();
+    let sender = create_agent(&tc, project_id, "sender").await;
+    let reader = create_agent(&tc, project_id, "reader").await;
+
+    let window_id = FocusWindowBmc::create(&tc.ctx, &tc.mm, window(project_id, 30))
+        .await
+        .unwrap();
+    MessageBmc::create(&tc.ctx, &tc.mm, message(project_id, sender, reader, "low"))
+        .await
+        .unwrap();
+
+    // Still active: nothing is due
+    assert_eq!(
+        FocusWindowBmc::deliver_due(&tc.ctx, &tc.mm).await.unwrap(),
+        0
+    );
+
+    // Simulate the window elapsing
+    let db = tc.mm.db_for_test();
+    db.execute(
+        "UPDATE project_focus_windows SET ends_ts = datetime('now', '-1 minute') WHERE id = ?",
+        [window_id],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        FocusWindowBmc::deliver_due(&tc.ctx, &tc.mm).await.unwrap(),
+        1
+    );
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, reader, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+}
+
+#[tokio::test]
+async fn test_focus_window_rejects_overlap_and_bad_importance() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "focus-overlap", "/focus/overlap")
+        .await
+        .unwrap();
+
+    FocusWindowBmc::create(&tc.ctx, &tc.mm, window(project_id, 30))
+        .await
+        .unwrap();
+    let overlap = FocusWindowBmc::create(&tc.ctx, &tc.mm, window(project_id, 10)).await;
+    assert!(matches!(
+        overlap,
+        Err(mouchak_mail_core::Error::InvalidInput(_))
+    ));
+
+    let mut bad = window(project_id, 10);
+    bad.starts_ts += Duration::hours(2);
+    bad.ends_ts += Duration::hours(2);
+    bad.allowed_importance = vec!["critical".to_string()];
+    let result = FocusWindowBmc::create(&tc.ctx, &tc.mm, bad).await;
+    assert!(matches!(
+        result,
+        Err(mouchak_mail_core::Error::InvalidInput(_))
+    ));
+}
@@ -5,6 +5,10 @@
     clippy::inefficient_to_string
 )]
 
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
 use mouchak_mail_core::Result;
 use mouchak_mail_core::ctx::Ctx;
 use mouchak_mail_core::model::ModelManager;
@@ -14,17 +18,6 @@ use mouchak_mail_core::model::project::ProjectBmc;
 use mouchak_mail_core::types::ProjectId;
 use serial_test::serial;
 
-// --- Test Setup Helper ---
-async fn create_test_mm() -> ModelManager {
-    // In-memory DB with migrations
-    // FTS tables created automatically by migrations in ModelManager::new()
-    ModelManager::new(std::sync::Arc::new(
-        mouchak_mail_common::config::AppConfig::default(),
-    ))
-    .await
-    .unwrap()
-}
-
 async fn setup_project_and_agent(ctx: &Ctx, mm: &ModelManager, suffix: &str) -> (ProjectId, i64) {
     // Start with "ftsproj-" so it is slug-safe (lowercase, etc)
     // Avoid underscores in slug usually, but uuid has hyphens.
@@ -55,7 +48,8 @@ async fn setup_project_and_agent(ctx: &Ctx, mm: &ModelManager, suffix: &str) ->
 #[tokio::test]
 #[serial]
 async fn test_fts_wildcard_search() -> Result<()> {
-    let mm = create_test_mm().await;
+    let tc = TestContext::new().await.unwrap();
+    let mm = tc.mm.clone();
     let ctx = Ctx::root_ctx();
     let (p_id, a_id) = setup_project_and_agent(&ctx, &mm, "wild").await;
 
@@ -64,8 +58,8 @@ async fn test_fts_wildcard_search() -> Result<()> {
         &ctx,
         &mm,
         MessageForCreate {
-            project_id: p_id.into(),
-            sender_id: a_id,
+            project_id: p_id,
+            sender_id: a_id.into(),
             recipient_ids: vec![],
             cc_ids: None,
             bcc_ids: None,
@@ -81,16 +75,16 @@ async fn test_fts_wildcard_search() -> Result<()> {
     // 2. Search with prefix wildcard (standard FTS5)
     // "quick*" should match "quick" if we allow wildcards
     // Currently fails because we quote it as "quick*"
-    let res = MessageBmc::search(&ctx, &mm, p_id.into(), "quick*", 10).await?;
+    let res = MessageBmc::search(&ctx, &mm, p_id, "quick*", 10).await?;
     assert_eq!(res.len(), 1, "Should match 'quick*' (prefix)");
 
     // 3. Search with phrase to ensure we don't break normal phrases
-    let res2 = MessageBmc::search(&ctx, &mm, p_id.into(), "\"brown fox\"", 10).await?;
+    let res2 = MessageBmc::search(&ctx, &mm, p_id, "\"brown fox\"", 10).await?;
     assert_eq!(res2.len(), 1, "Should match phrase \"brown fox\"");
 
     // 4. Leading wildcard (FTS5 syntax error typically)
     // We want to return empty (graceful) instead of error
-    let res3 = MessageBmc::search(&ctx, &mm, p_id.into(), "*dog", 10).await;
+    let res3 = MessageBmc::search(&ctx, &mm, p_id, "*dog", 10).await;
     // Assertion: Should be Ok(empty) or Ok(results) if supported, but NOT Err
     assert!(res3.is_ok(), "Should handle '*dog' gracefully (no crash)");
 
@@ -100,13 +94,14 @@ async fn test_fts_wildcard_search() -> Result<()> {
 #[tokio::test]
 #[serial]
 async fn test_fts_malformed_query_graceful() -> Result<()> {
-    let mm = create_test_mm().await;
+    let tc = TestContext::new().await.unwrap();
+    let mm = tc.mm.clone();
     let ctx = Ctx::root_ctx();
     let (p_id, _) = setup_project_and_agent(&ctx, &mm, "err").await;
 
     // Unclosed quote - FTS5 throws error if passed raw
     // We want graceful empty result
-    let res = MessageBmc::search(&ctx, &mm, p_id.into(), "\"unclosed phrase", 10).await;
+    let res = MessageBmc::search(&ctx, &mm, p_id, "\"unclosed phrase", 10).await;
 
     // We expect OK (handled) and empty
     assert!(res.is_ok(), "Should return Ok for malformed FTS query");
@@ -118,3 +113,213 @@ async fn test_fts_malformed_query_graceful() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn test_search_with_snippets_highlights_and_adds_context() -> Result<()> {
+    let tc = TestContext::new().await.unwrap();
+    let mm = tc.mm.clone();
+    let ctx = Ctx::root_ctx();
+    let (p_id, a_id) = setup_project_and_agent(&ctx, &mm, "snip").await;
+
+    let long_tail = "filler ".repeat(60);
+    for body in [
+        "Opening the investigation.".to_string(),
+        format!(
+            "Root cause: the Ledger cache evicted hot keys. {}",
+            long_tail
+        ),
+        "Patched and verified.".to_string(),
+    ] {
+        MessageBmc::create(
+            &ctx,
+            &mm,
+            MessageForCreate {
+                project_id: p_id,
+                sender_id: a_id.into(),
+                recipient_ids: vec![],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: "Incident".to_string(),
+                body_md: body,
+                thread_id: Some("incident-1".to_string()),
+                importance: None,
+                ack_required: false,
+            },
+        )
+        .await?;
+    }
+
+    let hits =
+        MessageBmc::search_with_snippets(&ctx, &mm, p_id, "ledger", 10, 1, false, &[]).await?;
+    assert_eq!(hits.len(), 1);
+
+    let hit = &hits[0];
+    assert_eq!(hit.field, "body");
+    let snippet = hit.snippet.as_ref().expect("body snippet");
+    assert!(snippet.highlighted.contains("**Ledger**"));
+    assert!(snippet.text.ends_with('…'), "long body should be truncated");
+    let m = snippet.matches[0];
+    assert_eq!(&snippet.text[m.start..m.end], "Ledger");
+
+    let positions: Vec<_> = hit.context.iter().map(|c| c.position).collect();
+    assert_eq!(positions, vec!["before", "after"]);
+    assert_eq!(hit.context[0].excerpt, "Opening the investigation.");
+
+    // Without context, no neighbours are loaded
+    let hits =
+        MessageBmc::search_with_snippets(&ctx, &mm, p_id, "ledger", 10, 0, false, &[]).await?;
+    assert!(hits[0].context.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_search_includes_attachment_text_when_requested() -> Result<()> {
+    use mouchak_mail_core::model::attachment_text::{AttachmentTextBmc, AttachmentTextForCreate};
+
+    let tc = TestContext::new().await.unwrap();
+    let mm = tc.mm.clone();
+    let ctx = Ctx::root_ctx();
+    let (p_id, a_id) = setup_project_and_agent(&ctx, &mm, "attach").await;
+
+    let message_id = MessageBmc::create(
+        &ctx,
+        &mm,
+        MessageForCreate {
+            project_id: p_id,
+            sender_id: a_id.into(),
+            recipient_ids: vec![],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Nightly logs".to_string(),
+            body_md: "See attached.".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await?;
+
+    let indexed = AttachmentTextBmc::index(
+        &ctx,
+        &mm,
+        AttachmentTextForCreate {
+            project_id: p_id.into(),
+            message_id: message_id.get(),
+            attachment_ref: "att_log".to_string(),
+            filename: "nightly.log".to_string(),
+            content: b"step 4: segfault_quokka in worker pool".to_vec(),
+        },
+    )
+    .await?;
+    assert!(indexed.is_some());
+
+    // Binary content is skipped
+    let skipped = AttachmentTextBmc::index(
+        &ctx,
+        &mm,
+        AttachmentTextForCreate {
+            project_id: p_id.into(),
+            message_id: message_id.get(),
+            attachment_ref: "att_core".to_string(),
+            filename: "core.dump".to_string(),
+            content: b"segfault_quokka\x00\x00\x01".to_vec(),
+        },
+    )
+    .await?;
+    assert!(skipped.is_none());
+
+    let res = MessageBmc::search(&ctx, &mm, p_id, "segfault_quokka", 10).await?;
+    assert!(res.is_empty(), "Attachments are opt-in");
+
+    let hits =
+        MessageBmc::search_with_snippets(&ctx, &mm, p_id, "segfault_quokka", 10, 0, true, &[])
+            .await?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].message.id, message_id.get());
+    assert_eq!(hits[0].field, "attachment");
+    assert_eq!(hits[0].attachment.as_deref(), Some("nightly.log"));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_search_ranks_subject_and_body_matches() -> Result<()> {
+    let tc = TestContext::new().await.unwrap();
+    let mm = tc.mm.clone();
+    let ctx = Ctx::root_ctx();
+    let (p_id, a_id) = setup_project_and_agent(&ctx, &mm, "rank").await;
+
+    let mut ids = Vec::new();
+    for (subject, body) in [
+        (
+            "Rollout plan",
+            "Steps for the billing rollout, then a second rollout for search.",
+        ),
+        (
+            "Standup notes",
+            "Discussed lunch, the offsite, hiring, the rollout, and the quarterly planning cycle in detail.",
+        ),
+        ("Rollout", "See the attached checklist."),
+        ("Lunch", "Pizza on Friday."),
+        ("Hiring", "Two interviews booked."),
+        ("Offsite", "Venue confirmed."),
+        ("Budget", "Numbers are in."),
+    ] {
+        let id = MessageBmc::create(
+            &ctx,
+            &mm,
+            MessageForCreate {
+                project_id: p_id,
+                sender_id: a_id.into(),
+                recipient_ids: vec![],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.to_string(),
+                body_md: body.to_string(),
+                thread_id: None,
+                importance: None,
+                ack_required: false,
+            },
+        )
+        .await?;
+        ids.push(id);
+    }
+
+    // Subject matches outrank a single mention in a long body, whatever
+    // the creation order
+    let hits =
+        MessageBmc::search_with_snippets(&ctx, &mm, p_id, "rollout", 10, 0, false, &[]).await?;
+    let found: Vec<i64> = hits.iter().map(|h| h.message.id).collect();
+    assert_eq!(found.len(), 3);
+    assert_eq!(found[2], ids[1].get());
+    let scores: Vec<f64> = hits.iter().map(|h| h.score.expect("ranked")).collect();
+    assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+
+    // A subject-only match is found and highlighted in the subject
+    let subject_hit = hits.iter().find(|h| h.message.id == ids[2].get()).unwrap();
+    assert_eq!(subject_hit.field, "subject");
+    assert!(
+        subject_hit
+            .snippet
+            .as_ref()
+            .expect("subject snippet")
+            .highlighted
+            .contains("**Rollout**")
+    );
+
+    let res = MessageBmc::search(&ctx, &mm, p_id, "roll*", 10).await?;
+    assert_eq!(res.len(), 3, "prefix matches subjects and bodies");
+    let res = MessageBmc::search(&ctx, &mm, p_id, "\"second rollout\"", 10).await?;
+    let found: Vec<i64> = res.iter().map(|m| m.id).collect();
+    assert_eq!(found, vec![ids[0].get()]);
+    let res = MessageBmc::search(&ctx, &mm, p_id, "subject:rollout", 10).await?;
+    let mut found: Vec<i64> = res.iter().map(|m| m.id).collect();
+    found.sort_unstable();
+    assert_eq!(found, vec![ids[0].get(), ids[2].get()]);
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! GitHub cross-posting tests
+//!
+//! Tests repository mappings, thread link dedup and importing issue comments
+//! as messages from the `github` agent.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::github_sync::{
+    GITHUB_AGENT_NAME, GithubComment, GithubSyncBmc, GithubThreadLinkForCreate, MIRROR_MARKER,
+};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+/// A project mapped to acme/widgets with a two-message thread `T-1`.
+async fn setup() -> (TestContext, ProjectId, Vec<i64>) {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "widgets", "/widgets")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["BlueLake", "GreenCastle"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id.get());
+    }
+    for (sender, recipient) in [(agents[0], agents[1]), (agents[1], agents[0])] {
+        MessageBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            MessageForCreate {
+                project_id,
+                sender_id: sender.into(),
+                recipient_ids: vec![AgentId::new(recipient)],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: "Parser bug".to_string(),
+                body_md: "details".to_string(),
+                thread_id: Some("T-1".to_string()),
+                importance: None,
+                ack_required: false,
+            },
+        )
+        .await
+        .unwrap();
+    }
+    GithubSyncBmc::set_repo(&tc.ctx, &tc.mm, project_id, "acme/widgets")
+        .await
+        .unwrap();
+    (tc, project_id, agents)
+}
+
+fn link_c(project_id: ProjectId, thread_id: &str, issue_number: i64) -> GithubThreadLinkForCreate {
+    GithubThreadLinkForCreate {
+        project_id,
+        thread_id: thread_id.to_string(),
+        repo: "acme/widgets".to_string(),
+        issue_number,
+        issue_url: format!("https://github.com/acme/widgets/issues/{}", issue_number),
+        last_message_id: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_repo_mapping_and_link_dedup() {
+    let (tc, project_id, _) = setup().await;
+
+    assert!(
+        GithubSyncBmc::set_repo(&tc.ctx, &tc.mm, project_id, "widgets")
+            .await
+            .is_err()
+    );
+    let repo = GithubSyncBmc::get_repo(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(repo.repo, "acme/widgets");
+
+    GithubSyncBmc::link_thread(&tc.ctx, &tc.mm, link_c(project_id, "T-1", 7))
+        .await
+        .unwrap();
+    // Neither the thread nor the issue can be linked twice
+    assert!(
+        GithubSyncBmc::link_thread(&tc.ctx, &tc.mm, link_c(project_id, "T-1", 8))
+            .await
+            .is_err()
+    );
+    assert!(
+        GithubSyncBmc::link_thread(&tc.ctx, &tc.mm, link_c(project_id, "T-2", 7))
+            .await
+            .is_err()
+    );
+
+    let link = GithubSyncBmc::get_link(&tc.ctx, &tc.mm, project_id, "T-1")
+        .await
+        .unwrap()
+        .unwrap();
+    let unposted = GithubSyncBmc::unposted_messages(&tc.ctx, &tc.mm, &link)
+        .await
+        .unwrap();
+    assert_eq!(unposted.len(), 2);
+    GithubSyncBmc::mark_posted(&tc.ctx, &tc.mm, link.id, MessageId::new(unposted[1].id))
+        .await
+        .unwrap();
+    let link = GithubSyncBmc::get_link(&tc.ctx, &tc.mm, project_id, "T-1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(
+        GithubSyncBmc::unposted_messages(&tc.ctx, &tc.mm, &link)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_import_comment_dedups_and_skips_mirrored_comments() {
+    let (tc, project_id, agents) = setup().await;
+    let link = GithubSyncBmc::link_thread(&tc.ctx, &tc.mm, link_c(project_id, "T-1", 7))
+        .await
+        .unwrap();
+    let comment = |id: i64, body: &str| GithubComment {
+        id,
+        author: "octocat".to_string(),
+        body: body.to_string(),
+        html_url: format!(
+            "https://github.com/acme/widgets/issues/7#issuecomment-{}",
+            id
+        ),
+    };
+
+    let mirrored = format!("**BlueLake**: details\n\n{}", MIRROR_MARKER);
+    assert_eq!(
+        GithubSyncBmc::import_comment(&tc.ctx, &tc.mm, &link, &comment(10, &mirrored))
+            .await
+            .unwrap(),
+        None
+    );
+    let message_id = GithubSyncBmc::import_comment(&tc.ctx, &tc.mm, &link, &comment(11, "LGTM"))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let message = MessageBmc::get(&tc.ctx, &tc.mm, message_id).await.unwrap();
+    assert_eq!(message.sender_name, GITHUB_AGENT_NAME);
+    assert_eq!(message.thread_id.as_deref(), Some("T-1"));
+    assert_eq!(message.subject, "Re: Parser bug");
+    assert!(message.body_md.contains("LGTM"));
+    let github = AgentBmc::get_by_name(&tc.ctx, &tc.mm, project_id, GITHUB_AGENT_NAME)
+        .await
+        .unwrap();
+    assert_eq!(message.sender_id, github.id.get());
+    for agent in &agents {
+        let inbox =
+            MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, AgentId::new(*agent), 10)
+                .await
+                .unwrap();
+        assert!(inbox.iter().any(|m| m.id == message_id.get()));
+    }
+
+    // Imported comments are not imported again and never posted back
+    let link = GithubSyncBmc::get_link(&tc.ctx, &tc.mm, project_id, "T-1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(link.last_comment_id, 11);
+    assert_eq!(
+        GithubSyncBmc::import_comment(&tc.ctx, &tc.mm, &link, &comment(11, "LGTM"))
+            .await
+            .unwrap(),
+        None
+    );
+    let unposted = GithubSyncBmc::unposted_messages(&tc.ctx, &tc.mm, &link)
+        .await
+        .unwrap();
+    assert!(unposted.iter().all(|m| m.sender_name != GITHUB_AGENT_NAME));
+}
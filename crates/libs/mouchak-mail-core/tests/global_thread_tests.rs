@@ -0,0 +1,106 @@
+//! Global thread id tests
+//!
+//! Tests that project threads map to ULIDs, that equal local ids in two
+//! projects stay apart, and that a global id used as a thread id joins it.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::model::global_thread::GlobalThreadBmc;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+use mouchak_mail_core::utils::ulid::is_ulid;
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    thread_id: &str,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![from],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Schema rollout".to_string(),
+            body_md: "Status update".to_string(),
+            thread_id: Some(thread_id.to_string()),
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_local_ids_stay_apart_until_joined() {
+    let tc = TestContext::new().await.unwrap();
+    let api = ProjectBmc::create(&tc.ctx, &tc.mm, "api", "/api")
+        .await
+        .unwrap();
+    let web = ProjectBmc::create(&tc.ctx, &tc.mm, "web", "/web")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, api, "alice").await;
+    let bob = create_agent(&tc, web, "bob").await;
+
+    send(&tc, api, alice, "rollout").await;
+    send(&tc, api, alice, "rollout").await;
+    send(&tc, web, bob, "rollout").await;
+
+    let api_global = GlobalThreadBmc::get_id(&tc.ctx, &tc.mm, api, "rollout")
+        .await
+        .unwrap()
+        .unwrap();
+    let web_global = GlobalThreadBmc::get_id(&tc.ctx, &tc.mm, web, "rollout")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(is_ulid(&api_global));
+    assert_ne!(api_global, web_global);
+
+    let both = [api, web];
+    let threads = GlobalThreadBmc::resolve(&tc.ctx, &tc.mm, &both, "rollout")
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 2);
+
+    // Continuing with the global id joins the api thread from web
+    send(&tc, web, bob, &api_global).await;
+    assert_eq!(
+        GlobalThreadBmc::get_id(&tc.ctx, &tc.mm, web, &api_global)
+            .await
+            .unwrap(),
+        Some(api_global.clone())
+    );
+    let threads = GlobalThreadBmc::resolve(&tc.ctx, &tc.mm, &both, &api_global)
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 1);
+    let members: Vec<(ProjectId, &str)> = threads[0]
+        .members
+        .iter()
+        .map(|m| (m.project_id, m.thread_id.as_str()))
+        .collect();
+    assert_eq!(members, [(api, "rollout"), (web, api_global.as_str())]);
+
+    // Projects outside the list are left out
+    let threads = GlobalThreadBmc::resolve(&tc.ctx, &tc.mm, &[web], &api_global)
+        .await
+        .unwrap();
+    assert_eq!(threads[0].members.len(), 1);
+    let unknown = GlobalThreadBmc::resolve(&tc.ctx, &tc.mm, &both, "no-such-thread")
+        .await
+        .unwrap();
+    assert!(unknown.is_empty());
+}
@@ -0,0 +1,172 @@
+//! Inbox delta tests
+//!
+//! Tests for snapshot/delta tokens over an agent's inbox.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::inbox_delta::{InboxDelta, InboxDeltaBmc};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+struct Inbox {
+    tc: TestContext,
+    project_id: ProjectId,
+    sender: AgentId,
+    reader: AgentId,
+}
+
+async fn setup() -> Inbox {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "delta", "/delta")
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for name in ["sender", "reader"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        ids.push(id);
+    }
+    Inbox {
+        tc,
+        project_id,
+        sender: ids[0],
+        reader: ids[1],
+    }
+}
+
+impl Inbox {
+    async fn send(&self, subject: &str) -> MessageId {
+        MessageBmc::create(
+            &self.tc.ctx,
+            &self.tc.mm,
+            MessageForCreate {
+                project_id: self.project_id,
+                sender_id: self.sender,
+                recipient_ids: vec![self.reader],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.to_string(),
+                body_md: "body".to_string(),
+                thread_id: None,
+                importance: None,
+                ack_required: false,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn check(&self, token: Option<&str>, limit: i64) -> InboxDelta {
+        InboxDeltaBmc::check(
+            &self.tc.ctx,
+            &self.tc.mm,
+            self.project_id,
+            self.reader,
+            token,
+            limit,
+        )
+        .await
+        .unwrap()
+    }
+}
+
+fn ids(delta: &InboxDelta) -> Vec<MessageId> {
+    delta
+        .new_messages
+        .iter()
+        .map(|i| MessageId::new(i.message.id))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_snapshot_then_only_new_messages() {
+    let inbox = setup().await;
+    let first = inbox.send("one").await;
+    let second = inbox.send("two").await;
+
+    let snapshot = inbox.check(None, 50).await;
+    assert_eq!(ids(&snapshot), [first, second]);
+    assert!(snapshot.changed.is_empty());
+    assert!(!snapshot.has_more);
+
+    // Nothing changed since the snapshot
+    let delta = inbox.check(Some(&snapshot.token), 50).await;
+    assert!(delta.new_messages.is_empty());
+    assert!(delta.changed.is_empty());
+
+    let third = inbox.send("three").await;
+    let delta = inbox.check(Some(&delta.token), 50).await;
+    assert_eq!(ids(&delta), [third]);
+}
+
+#[tokio::test]
+async fn test_read_and_ack_are_reported_as_changes() {
+    let inbox = setup().await;
+    let first = inbox.send("one").await;
+    let snapshot = inbox.check(None, 50).await;
+
+    MessageBmc::mark_read(&inbox.tc.ctx, &inbox.tc.mm, first, inbox.reader)
+        .await
+        .unwrap();
+
+    let delta = inbox.check(Some(&snapshot.token), 50).await;
+    assert!(delta.new_messages.is_empty());
+    assert_eq!(delta.changed.len(), 1);
+    assert_eq!(delta.changed[0].message_id, first.get());
+    assert!(delta.changed[0].read_ts.is_some());
+    assert!(delta.changed[0].ack_ts.is_none());
+}
+
+#[tokio::test]
+async fn test_has_more_pages_through_new_messages() {
+    let inbox = setup().await;
+    let snapshot = inbox.check(None, 50).await;
+    assert!(snapshot.new_messages.is_empty());
+
+    let mut sent = Vec::new();
+    for i in 0..5 {
+        sent.push(inbox.send(&format!("msg {}", i)).await);
+    }
+
+    let page = inbox.check(Some(&snapshot.token), 3).await;
+    assert_eq!(ids(&page), sent[..3]);
+    assert!(page.has_more);
+
+    let page = inbox.check(Some(&page.token), 3).await;
+    assert_eq!(ids(&page), sent[3..]);
+    assert!(!page.has_more);
+}
+
+#[tokio::test]
+async fn test_malformed_token_is_invalid_input() {
+    let inbox = setup().await;
+    let err = InboxDeltaBmc::check(
+        &inbox.tc.ctx,
+        &inbox.tc.mm,
+        inbox.project_id,
+        inbox.reader,
+        Some("not-a-token"),
+        10,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, mouchak_mail_core::Error::InvalidInput(_)));
+}
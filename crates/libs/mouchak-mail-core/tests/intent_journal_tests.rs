@@ -0,0 +1,319 @@
+//! Intent journal tests
+//!
+//! Tests for sends written in one transaction and startup recovery of
+//! archive commits and attachment uploads interrupted by a crash.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use chrono::{Duration, NaiveDateTime, Utc};
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::attachment::{AttachmentBmc, AttachmentUpload};
+use mouchak_mail_core::model::intent_journal::{
+    ATTACHMENT_STORE, AttachmentIntent, IntentJournalBmc, MESSAGE_CREATE, MessageIntent,
+    RecoveryReport,
+};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::store::git_store;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+struct Setup {
+    project_id: ProjectId,
+    alice: AgentId,
+    bob: AgentId,
+}
+
+async fn setup(tc: &TestContext) -> Setup {
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "intents", "Intents")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["alice", "bob"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id);
+    }
+    Setup {
+        project_id,
+        alice: agents[0],
+        bob: agents[1],
+    }
+}
+
+/// Inserts a message row the way a send does, without archiving it.
+async fn insert_message(tc: &TestContext, s: &Setup, thread_id: &str, subject: &str) -> MessageId {
+    let db = tc.mm.db_for_test();
+    let mut rows = db
+        .query(
+            r#"
+            INSERT INTO messages (project_id, sender_id, thread_id, subject, body_md, importance, attachments, ack_required)
+            VALUES (?, ?, ?, ?, 'Body', 'normal', '[]', 0)
+            RETURNING id
+            "#,
+            (s.project_id.get(), s.alice.get(), thread_id, subject),
+        )
+        .await
+        .unwrap();
+    MessageId::new(rows.next().await.unwrap().unwrap().get(0).unwrap())
+}
+
+async fn begin_send(tc: &TestContext, s: &Setup, message_id: MessageId) -> i64 {
+    let payload = MessageIntent {
+        message_id,
+        to: vec![s.bob],
+    };
+    IntentJournalBmc::begin(
+        &tc.mm,
+        MESSAGE_CREATE,
+        &serde_json::to_value(payload).unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+/// A time after every intent recorded so far, like a later restart.
+fn restart() -> NaiveDateTime {
+    Utc::now().naive_utc() + Duration::minutes(1)
+}
+
+async fn count_messages(tc: &TestContext, subject: &str) -> i64 {
+    let mut rows = tc
+        .mm
+        .db_for_test()
+        .query("SELECT COUNT(*) FROM messages WHERE subject = ?", [subject])
+        .await
+        .unwrap();
+    rows.next().await.unwrap().unwrap().get(0).unwrap()
+}
+
+fn message(s: &Setup, subject: &str) -> MessageForCreate {
+    MessageForCreate {
+        project_id: s.project_id,
+        sender_id: s.alice,
+        recipient_ids: vec![s.bob],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: subject.into(),
+        body_md: "World".into(),
+        thread_id: None,
+        importance: None,
+        ack_required: false,
+    }
+}
+
+async fn wait_for_archive_commits() {
+    while git_store::pending_archive_commits() > 0 {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_completed_operations_leave_no_intent() {
+    let tc = TestContext::new().await.unwrap();
+    let s = setup(&tc).await;
+
+    MessageBmc::create(&tc.ctx, &tc.mm, message(&s, "Hello"))
+        .await
+        .unwrap();
+    AttachmentBmc::store(
+        &tc.ctx,
+        &tc.mm,
+        AttachmentUpload {
+            project_id: s.project_id.get(),
+            agent_id: None,
+            filename: "notes.txt".into(),
+            media_type: "text/plain".into(),
+        },
+        Box::new(std::io::Cursor::new(b"notes".to_vec())),
+    )
+    .await
+    .unwrap();
+    wait_for_archive_commits().await;
+
+    let intents = IntentJournalBmc::list_abandoned(&tc.ctx, &tc.mm, restart())
+        .await
+        .unwrap();
+    assert!(intents.is_empty(), "Leftover intents: {:?}", intents);
+}
+
+#[tokio::test]
+async fn test_failed_sends_write_nothing() {
+    let tc = TestContext::new().await.unwrap();
+    let s = setup(&tc).await;
+
+    // Recording recipients fails after the message row was written
+    tc.mm
+        .db_for_test()
+        .execute(
+            r#"
+            CREATE TRIGGER fail_recipients BEFORE INSERT ON message_recipients
+            WHEN (SELECT subject FROM messages WHERE id = NEW.message_id) = 'Half sent'
+            BEGIN SELECT RAISE(ABORT, 'disk I/O error'); END
+            "#,
+            (),
+        )
+        .await
+        .unwrap();
+    assert!(
+        MessageBmc::create(&tc.ctx, &tc.mm, message(&s, "Half sent"))
+            .await
+            .is_err()
+    );
+
+    // The next send waits for the rollback, then lands whole
+    let id = MessageBmc::create(&tc.ctx, &tc.mm, message(&s, "Sent"))
+        .await
+        .unwrap();
+    wait_for_archive_commits().await;
+    assert_eq!(count_messages(&tc, "Half sent").await, 0);
+    assert_eq!(count_messages(&tc, "Sent").await, 1);
+    assert_eq!(
+        MessageBmc::get_recipients(&tc.ctx, &tc.mm, id)
+            .await
+            .unwrap(),
+        vec!["bob"]
+    );
+    assert!(
+        IntentJournalBmc::list_abandoned(&tc.ctx, &tc.mm, restart())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_recover_archives_delivered_sends() {
+    let tc = TestContext::new().await.unwrap();
+    let s = setup(&tc).await;
+
+    let message_id = insert_message(&tc, &s, "t-3", "Delivered").await;
+    begin_send(&tc, &s, message_id).await;
+
+    // Recorded after this start: a send of this process, left alone
+    let started = Utc::now().naive_utc() - Duration::minutes(1);
+    let report = IntentJournalBmc::recover(&tc.ctx, &tc.mm, started)
+        .await
+        .unwrap();
+    assert_eq!(report, RecoveryReport::default());
+
+    let report = IntentJournalBmc::recover(&tc.ctx, &tc.mm, restart())
+        .await
+        .unwrap();
+    assert_eq!(report.completed, 1);
+    MessageBmc::get(&tc.ctx, &tc.mm, message_id).await.unwrap();
+
+    let archived = walkdir(&tc.repo_root().join("projects/intents/messages"));
+    assert!(
+        archived
+            .iter()
+            .any(|p| p.ends_with(&format!("__delivered__{}.md", message_id))),
+        "Message not archived: {:?}",
+        archived
+    );
+}
+
+#[tokio::test]
+async fn test_recover_removes_unrecorded_attachment_content() {
+    let tc = TestContext::new().await.unwrap();
+    let s = setup(&tc).await;
+    let root = tc.repo_root().parent().unwrap().join("attachments");
+
+    // Content written, row never created
+    let orphan = root.join(format!("{}/orphan_notes.txt", s.project_id));
+    std::fs::create_dir_all(orphan.parent().unwrap()).unwrap();
+    std::fs::write(&orphan, b"orphan").unwrap();
+    std::fs::write(
+        root.join(format!("{}/orphan_notes.txt.partial", s.project_id)),
+        b"or",
+    )
+    .unwrap();
+
+    // Content moved to its blob, blob never recorded
+    let blob = root.join("blobs/ab/abandoned");
+    std::fs::create_dir_all(blob.parent().unwrap()).unwrap();
+    std::fs::write(&blob, b"abandoned").unwrap();
+
+    // Row created, intent not yet removed
+    let id = AttachmentBmc::store(
+        &tc.ctx,
+        &tc.mm,
+        AttachmentUpload {
+            project_id: s.project_id.get(),
+            agent_id: None,
+            filename: "kept.txt".into(),
+            media_type: "text/plain".into(),
+        },
+        Box::new(std::io::Cursor::new(b"kept".to_vec())),
+    )
+    .await
+    .unwrap();
+    let kept = AttachmentBmc::get(&tc.ctx, &tc.mm, id).await.unwrap();
+
+    let moved = root.join("incoming/moved").to_string_lossy().into_owned();
+    for (location, blob) in [
+        (orphan.to_string_lossy().into_owned(), None),
+        (moved, Some(blob.to_string_lossy().into_owned())),
+        (
+            root.join("incoming/kept").to_string_lossy().into_owned(),
+            Some(kept.stored_path.clone()),
+        ),
+    ] {
+        IntentJournalBmc::begin(
+            &tc.mm,
+            ATTACHMENT_STORE,
+            &serde_json::to_value(AttachmentIntent { location, blob }).unwrap(),
+        )
+        .await
+        .unwrap();
+    }
+
+    let report = IntentJournalBmc::recover(&tc.ctx, &tc.mm, restart())
+        .await
+        .unwrap();
+    assert_eq!(report.rolled_back, 2);
+    assert_eq!(report.completed, 1);
+    assert!(!orphan.exists());
+    assert!(!blob.exists());
+    assert!(
+        std::fs::read_dir(orphan.parent().unwrap())
+            .unwrap()
+            .all(|e| !e
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with("orphan"))
+    );
+    assert!(std::path::Path::new(&kept.stored_path).exists());
+}
+
+fn walkdir(dir: &std::path::Path) -> Vec<String> {
+    let mut found = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return found;
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            found.extend(walkdir(&path));
+        } else {
+            found.push(path.to_string_lossy().into_owned());
+        }
+    }
+    found
+}
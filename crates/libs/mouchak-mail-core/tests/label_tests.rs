@@ -0,0 +1,187 @@
+//! Label tests
+//!
+//! Tests for creating, updating and deleting project labels, and for
+//! applying them to messages and listing messages by label.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::label::{LabelBmc, LabelForCreate, LabelForUpdate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(tc: &TestContext, project_id: ProjectId, from: AgentId, subject: &str) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![from],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.to_string(),
+            body_md: "Details inside".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn label(project_id: ProjectId, name: &str, color: Option<&str>) -> LabelForCreate {
+    LabelForCreate {
+        project_id,
+        name: name.to_string(),
+        color: color.map(str::to_string),
+        description: None,
+    }
+}
+
+#[tokio::test]
+async fn test_label_crud() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "labels", "/labels")
+        .await
+        .unwrap();
+
+    LabelBmc::create(&tc.ctx, &tc.mm, label(project_id, "blocked", None))
+        .await
+        .unwrap();
+    LabelBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        label(project_id, "needs-review", Some("#2563EB")),
+    )
+    .await
+    .unwrap();
+
+    for invalid in [
+        label(project_id, "blocked", None),
+        label(project_id, "Needs Review", None),
+        label(project_id, "urgent", Some("red")),
+    ] {
+        let err = LabelBmc::create(&tc.ctx, &tc.mm, invalid).await;
+        assert!(matches!(err, Err(Error::InvalidInput(_))));
+    }
+
+    let labels = LabelBmc::list(&tc.ctx, &tc.mm, project_id).await.unwrap();
+    let names: Vec<&str> = labels.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, ["blocked", "needs-review"]);
+    assert!(labels[0].color.starts_with('#'));
+    assert_eq!(labels[1].color, "#2563eb");
+
+    let updated = LabelBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        "needs-review",
+        LabelForUpdate {
+            name: Some("review".to_string()),
+            description: Some("Waiting on a reviewer".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.name, "review");
+    assert_eq!(updated.color, "#2563eb");
+    assert_eq!(updated.description, "Waiting on a reviewer");
+    let taken = LabelBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        "review",
+        LabelForUpdate {
+            name: Some("blocked".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(matches!(taken, Err(Error::InvalidInput(_))));
+
+    LabelBmc::delete(&tc.ctx, &tc.mm, project_id, "review")
+        .await
+        .unwrap();
+    let gone = LabelBmc::get_by_name(&tc.ctx, &tc.mm, project_id, "review").await;
+    assert!(matches!(gone, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+async fn test_apply_and_list_by_label() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "triage", "/triage")
+        .await
+        .unwrap();
+    let other = ProjectBmc::create(&tc.ctx, &tc.mm, "other", "/other")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, other, "bob").await;
+    let first = send(&tc, project_id, alice, "Migration stuck").await;
+    let second = send(&tc, project_id, alice, "Deploy waits on migration").await;
+    let foreign = send(&tc, other, bob, "Unrelated").await;
+    LabelBmc::create(&tc.ctx, &tc.mm, label(project_id, "blocked", None))
+        .await
+        .unwrap();
+
+    let by = Some((alice, "alice"));
+    for id in [first, second] {
+        assert!(
+            LabelBmc::apply(&tc.ctx, &tc.mm, project_id, id, "blocked", by)
+                .await
+                .unwrap()
+        );
+    }
+    // Applying twice is a no-op
+    assert!(
+        !LabelBmc::apply(&tc.ctx, &tc.mm, project_id, first, "blocked", by)
+            .await
+            .unwrap()
+    );
+    let unknown = LabelBmc::apply(&tc.ctx, &tc.mm, project_id, first, "nope", by).await;
+    assert!(matches!(unknown, Err(Error::NotFound)));
+    let cross = LabelBmc::apply(&tc.ctx, &tc.mm, project_id, foreign, "blocked", by).await;
+    assert!(matches!(cross, Err(Error::InvalidInput(_))));
+
+    let messages = LabelBmc::list_messages(&tc.ctx, &tc.mm, project_id, "blocked", 10)
+        .await
+        .unwrap();
+    let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+    assert_eq!(ids, [second.get(), first.get()]);
+    assert_eq!(
+        LabelBmc::names_for_message(&tc.ctx, &tc.mm, first)
+            .await
+            .unwrap(),
+        ["blocked"]
+    );
+
+    assert!(
+        LabelBmc::remove(&tc.ctx, &tc.mm, project_id, first, "blocked", by)
+            .await
+            .unwrap()
+    );
+    let label = LabelBmc::get_by_name(&tc.ctx, &tc.mm, project_id, "blocked")
+        .await
+        .unwrap();
+    assert_eq!(label.message_count, 1);
+
+    // Deleting the label takes it off every message
+    LabelBmc::delete(&tc.ctx, &tc.mm, project_id, "blocked")
+        .await
+        .unwrap();
+    assert!(
+        LabelBmc::names_for_message(&tc.ctx, &tc.mm, second)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
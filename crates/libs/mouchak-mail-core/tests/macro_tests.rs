@@ -25,7 +25,7 @@ async fn test_builtin_macros_registration() {
         .unwrap();
 
     // Verify automatic registration via ProjectBmc::create
-    let listed = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let listed = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     assert_eq!(
         listed.len(),
         5,
@@ -41,7 +41,7 @@ async fn test_builtin_macros_registration() {
     assert!(names.contains(&"broadcast_message".to_string()));
 
     // Verify idempotency
-    let created_again = MacroDefBmc::ensure_builtin_macros(c, mm, project_id.get())
+    let created_again = MacroDefBmc::ensure_builtin_macros(c, mm, project_id)
         .await
         .unwrap();
     assert_eq!(
@@ -62,7 +62,7 @@ async fn test_builtin_macro_contents() {
         .unwrap();
 
     // Test start_session macro structure
-    let start_session = MacroDefBmc::get_by_name(c, mm, project_id.get(), "start_session")
+    let start_session = MacroDefBmc::get_by_name(c, mm, project_id, "start_session")
         .await
         .unwrap();
     assert_eq!(start_session.name, "start_session");
@@ -72,7 +72,7 @@ async fn test_builtin_macro_contents() {
     assert_eq!(start_session.steps[1]["tool"], "check_inbox");
 
     // Test prepare_thread macro structure
-    let prepare_thread = MacroDefBmc::get_by_name(c, mm, project_id.get(), "prepare_thread")
+    let prepare_thread = MacroDefBmc::get_by_name(c, mm, project_id, "prepare_thread")
         .await
         .unwrap();
     assert_eq!(prepare_thread.name, "prepare_thread");
@@ -82,7 +82,7 @@ async fn test_builtin_macro_contents() {
     assert_eq!(prepare_thread.steps[1]["tool"], "reserve_file");
 
     // Test file_reservation_cycle macro structure
-    let file_res = MacroDefBmc::get_by_name(c, mm, project_id.get(), "file_reservation_cycle")
+    let file_res = MacroDefBmc::get_by_name(c, mm, project_id, "file_reservation_cycle")
         .await
         .unwrap();
     assert_eq!(file_res.name, "file_reservation_cycle");
@@ -92,7 +92,7 @@ async fn test_builtin_macro_contents() {
     assert_eq!(file_res.steps[2]["tool"], "release_reservation");
 
     // Test contact_handshake macro structure
-    let contact = MacroDefBmc::get_by_name(c, mm, project_id.get(), "contact_handshake")
+    let contact = MacroDefBmc::get_by_name(c, mm, project_id, "contact_handshake")
         .await
         .unwrap();
     assert_eq!(contact.name, "contact_handshake");
@@ -101,7 +101,7 @@ async fn test_builtin_macro_contents() {
     assert_eq!(contact.steps[1]["tool"], "respond_contact");
 
     // Test broadcast_message macro structure
-    let broadcast = MacroDefBmc::get_by_name(c, mm, project_id.get(), "broadcast_message")
+    let broadcast = MacroDefBmc::get_by_name(c, mm, project_id, "broadcast_message")
         .await
         .unwrap();
     assert_eq!(broadcast.name, "broadcast_message");
@@ -125,8 +125,8 @@ async fn test_builtin_macros_per_project_isolation() {
         .unwrap();
 
     // Both should have 5 built-in macros
-    let p1_macros = MacroDefBmc::list(c, mm, project1_id.get()).await.unwrap();
-    let p2_macros = MacroDefBmc::list(c, mm, project2_id.get()).await.unwrap();
+    let p1_macros = MacroDefBmc::list(c, mm, project1_id).await.unwrap();
+    let p2_macros = MacroDefBmc::list(c, mm, project2_id).await.unwrap();
 
     assert_eq!(p1_macros.len(), 5);
     assert_eq!(p2_macros.len(), 5);
@@ -164,7 +164,7 @@ async fn test_builtin_macros_have_timestamps() {
     let project_id = ProjectBmc::create(c, mm, "timestamp-test", "/path/ts")
         .await
         .unwrap();
-    let macros = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let macros = MacroDefBmc::list(c, mm, project_id).await.unwrap();
 
     for macro_def in macros {
         // Verify timestamps are not default/zero
@@ -192,7 +192,7 @@ async fn test_macro_registration_order() {
         .unwrap();
 
     // List returns macros ordered by name ASC
-    let macros = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let macros = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     let names: Vec<String> = macros.iter().map(|m| m.name.clone()).collect();
 
     let mut sorted_names = names.clone();
@@ -224,24 +224,24 @@ async fn test_macro_crud() {
     let mid = MacroDefBmc::create(c, mm, macro_c).await.unwrap();
 
     // Get
-    let m = MacroDefBmc::get_by_name(c, mm, project_id.get(), "custom_macro")
+    let m = MacroDefBmc::get_by_name(c, mm, project_id, "custom_macro")
         .await
         .unwrap();
     assert_eq!(m.id, mid);
     assert_eq!(m.description, "A custom test macro");
 
     // List
-    let list = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let list = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     assert!(list.iter().any(|x| x.name == "custom_macro"));
 
     // Delete
-    let deleted = MacroDefBmc::delete(c, mm, project_id.get(), "custom_macro")
+    let deleted = MacroDefBmc::delete(c, mm, project_id, "custom_macro")
         .await
         .unwrap();
     assert!(deleted);
 
     // Verify gone
-    let list_after = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let list_after = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     assert!(!list_after.iter().any(|x| x.name == "custom_macro"));
 }
 
@@ -256,7 +256,7 @@ async fn test_register_custom_macro() {
         .unwrap();
 
     // Initially has 5 built-in macros
-    let initial = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let initial = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     assert_eq!(initial.len(), 5);
 
     // Register a custom macro with complex steps
@@ -287,11 +287,11 @@ async fn test_register_custom_macro() {
     assert!(custom_id > 0);
 
     // Verify it appears in the list (now 6 total)
-    let after = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let after = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     assert_eq!(after.len(), 6);
 
     // Verify we can retrieve it
-    let retrieved = MacroDefBmc::get_by_name(c, mm, project_id.get(), "deploy_workflow")
+    let retrieved = MacroDefBmc::get_by_name(c, mm, project_id, "deploy_workflow")
         .await
         .unwrap();
     assert_eq!(retrieved.id, custom_id);
@@ -309,7 +309,7 @@ async fn test_list_registered_macros() {
         .unwrap();
 
     // List all macros
-    let all_macros = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let all_macros = MacroDefBmc::list(c, mm, project_id).await.unwrap();
 
     // Should have exactly 5 built-in macros
     assert_eq!(all_macros.len(), 5);
@@ -345,7 +345,7 @@ async fn test_list_registered_macros() {
     }
 
     // Should now have 8 macros
-    let updated = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let updated = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     assert_eq!(updated.len(), 8);
 }
 
@@ -369,21 +369,21 @@ async fn test_unregister_macro() {
     MacroDefBmc::create(c, mm, custom).await.unwrap();
 
     // Verify it exists
-    let before = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let before = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     assert_eq!(before.len(), 6); // 5 built-in + 1 custom
 
     // Delete it
-    let deleted = MacroDefBmc::delete(c, mm, project_id.get(), "temp_macro")
+    let deleted = MacroDefBmc::delete(c, mm, project_id, "temp_macro")
         .await
         .unwrap();
     assert!(deleted, "Delete should return true");
 
     // Verify it's gone
-    let after = MacroDefBmc::list(c, mm, project_id.get()).await.unwrap();
+    let after = MacroDefBmc::list(c, mm, project_id).await.unwrap();
     assert_eq!(after.len(), 5); // back to just built-in
 
     // Deleting again should return false
-    let deleted_again = MacroDefBmc::delete(c, mm, project_id.get(), "temp_macro")
+    let deleted_again = MacroDefBmc::delete(c, mm, project_id, "temp_macro")
         .await
         .unwrap();
     assert!(
@@ -403,7 +403,7 @@ async fn test_macro_get_nonexistent() {
         .unwrap();
 
     // Try to get a macro that doesn't exist
-    let result = MacroDefBmc::get_by_name(c, mm, project_id.get(), "does_not_exist").await;
+    let result = MacroDefBmc::get_by_name(c, mm, project_id, "does_not_exist").await;
     assert!(
         result.is_err(),
         "Getting non-existent macro should return error"
@@ -421,7 +421,7 @@ async fn test_builtin_macros_parameter_structure() {
         .unwrap();
 
     // Test that each built-in macro has proper parameter structure
-    let start_session = MacroDefBmc::get_by_name(c, mm, project_id.get(), "start_session")
+    let start_session = MacroDefBmc::get_by_name(c, mm, project_id, "start_session")
         .await
         .unwrap();
     assert!(
@@ -431,7 +431,7 @@ async fn test_builtin_macros_parameter_structure() {
     let params = start_session.steps[0]["params"].as_array().unwrap();
     assert!(!params.is_empty(), "Params should not be empty");
 
-    let prepare = MacroDefBmc::get_by_name(c, mm, project_id.get(), "prepare_thread")
+    let prepare = MacroDefBmc::get_by_name(c, mm, project_id, "prepare_thread")
         .await
         .unwrap();
     for step in &prepare.steps {
@@ -476,7 +476,7 @@ async fn test_macro_steps_serialization() {
     MacroDefBmc::create(c, mm, custom).await.unwrap();
 
     // Retrieve and verify structure is preserved
-    let retrieved = MacroDefBmc::get_by_name(c, mm, project_id.get(), "complex_macro")
+    let retrieved = MacroDefBmc::get_by_name(c, mm, project_id, "complex_macro")
         .await
         .unwrap();
     assert_eq!(retrieved.steps.len(), 1);
@@ -0,0 +1,240 @@
+//! Mbox import tests
+//!
+//! Tests for importing real email into a project: address mapping,
+//! placeholder agents, threading, and backdating.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::export::{ExportBmc, ExportFormat, ScrubMode};
+use mouchak_mail_core::model::mbox_import::{MboxImportBmc, MboxImportOptions};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use std::collections::HashMap;
+
+const MBOX: &str = "\
+From alice@example.com Mon Jan  6 09:00:00 2025
+From: Alice Smith <alice@example.com>
+To: Bob <bob@example.com>, carol@example.com
+Subject: Release checklist
+Date: Mon, 6 Jan 2025 09:00:00 +0000
+Message-ID: <1@example.com>
+
+Please review the checklist.
+>From here on, no merges.
+
+From bob@example.com Mon Jan  6 10:30:00 2025
+From: Bob <bob@example.com>
+To: alice@example.com
+Subject: Re: Release checklist
+Date: Mon, 6 Jan 2025 10:30:00 +0000
+Message-ID: <2@example.com>
+In-Reply-To: <1@example.com>
+References: <1@example.com>
+
+Looks good.
+
+From mallory@elsewhere.com Mon Jan  6 11:00:00 2025
+From: mallory@elsewhere.com
+To: alice@example.com
+Subject: Unrelated
+Date: Mon, 6 Jan 2025 11:00:00 +0000
+Message-ID: <3@elsewhere.com>
+
+Buy now.
+";
+
+fn sender_map(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_import_maps_senders_and_threads_replies() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "release", "/release")
+        .await
+        .unwrap();
+    let lead = create_agent(&tc, project_id, "lead").await;
+    let reviewer = create_agent(&tc, project_id, "reviewer").await;
+
+    let summary = MboxImportBmc::import(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        MBOX.as_bytes(),
+        MboxImportOptions {
+            // By address and by display name
+            sender_map: sender_map(&[("alice@example.com", "lead"), ("Bob", "reviewer")]),
+            create_agents: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(summary.imported.len(), 2);
+    assert_eq!(summary.threads, 1);
+    assert!(summary.agents_created.is_empty());
+    assert_eq!(summary.unmapped_addresses, ["carol@example.com"]);
+    assert_eq!(summary.skipped.len(), 1);
+    assert!(summary.skipped[0].contains("mallory@elsewhere.com"));
+
+    let first = MessageBmc::get(&tc.ctx, &tc.mm, summary.imported[0])
+        .await
+        .unwrap();
+    let reply = MessageBmc::get(&tc.ctx, &tc.mm, summary.imported[1])
+        .await
+        .unwrap();
+    assert_eq!(first.sender_id, lead.get());
+    assert_eq!(
+        first.body_md,
+        "Please review the checklist.\nFrom here on, no merges."
+    );
+    assert_eq!(reply.sender_id, reviewer.get());
+    assert_eq!(reply.subject, "Re: Release checklist");
+    assert_eq!(reply.thread_id, first.thread_id);
+    assert_eq!(
+        reply.created_ts.format("%Y-%m-%d %H:%M:%S").to_string(),
+        "2025-01-06 10:30:00"
+    );
+
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, reviewer, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].subject, "Release checklist");
+}
+
+#[tokio::test]
+async fn test_import_creates_placeholder_agents() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "seeded", "/seeded")
+        .await
+        .unwrap();
+
+    let summary = MboxImportBmc::import(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        MBOX.as_bytes(),
+        MboxImportOptions {
+            sender_map: HashMap::new(),
+            create_agents: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(summary.imported.len(), 3);
+    assert_eq!(summary.threads, 2);
+    assert_eq!(summary.agents_created, ["alice", "bob", "carol", "mallory"]);
+    let bob = AgentBmc::get_by_name(&tc.ctx, &tc.mm, project_id, "bob")
+        .await
+        .unwrap();
+    assert_eq!(bob.program, "mbox-import");
+}
+
+#[tokio::test]
+async fn test_import_rejects_unknown_map_target() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "strict", "/strict")
+        .await
+        .unwrap();
+
+    let err = MboxImportBmc::import(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        MBOX.as_bytes(),
+        MboxImportOptions {
+            sender_map: sender_map(&[("alice@example.com", "ghost")]),
+            create_agents: false,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+}
+
+/// An mbox export of one project imports into another with the same agents
+/// and thread ids.
+#[tokio::test]
+async fn test_import_round_trips_mbox_export() {
+    let tc = TestContext::new().await.unwrap();
+    let source = ProjectBmc::create(&tc.ctx, &tc.mm, "source", "/source")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, source, "alice").await;
+    let bob = create_agent(&tc, source, "bob").await;
+    for (sender, recipient, subject) in [
+        (alice, bob, "Schema change"),
+        (bob, alice, "Re: Schema change"),
+    ] {
+        MessageBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            MessageForCreate {
+                project_id: source,
+                sender_id: sender,
+                recipient_ids: vec![recipient],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.to_string(),
+                body_md: "Adding a `labels` column.".to_string(),
+                thread_id: Some("TKT-9".to_string()),
+                importance: Some("high".to_string()),
+                ack_required: true,
+            },
+        )
+        .await
+        .unwrap();
+    }
+    let exported = ExportBmc::export_mailbox(
+        &tc.ctx,
+        &tc.mm,
+        "source",
+        ExportFormat::Mbox,
+        ScrubMode::None,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let dest = ProjectBmc::create(&tc.ctx, &tc.mm, "dest", "/dest")
+        .await
+        .unwrap();
+    let summary = MboxImportBmc::import(
+        &tc.ctx,
+        &tc.mm,
+        dest,
+        exported.content.as_bytes(),
+        MboxImportOptions {
+            sender_map: HashMap::new(),
+            create_agents: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(summary.imported.len(), 2);
+    assert_eq!(summary.agents_created, ["alice", "bob"]);
+    let thread = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, dest, "TKT-9")
+        .await
+        .unwrap();
+    let mut senders: Vec<&str> = thread.iter().map(|m| m.sender_name.as_str()).collect();
+    senders.sort_unstable();
+    assert_eq!(senders, ["alice", "bob"]);
+    for message in &thread {
+        assert_eq!(message.body_md, "Adding a `labels` column.");
+        assert_eq!(message.importance, "high");
+        assert!(message.ack_required);
+    }
+}
@@ -0,0 +1,146 @@
+//! Message body size cap tests
+//!
+//! Tests that oversized bodies are stored separately behind a preview and
+//! stay retrievable and searchable.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_common::config::{AppConfig, MessageBodyConfig};
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+fn config(max_inline_bytes: u64) -> AppConfig {
+    AppConfig {
+        message_body: MessageBodyConfig {
+            max_inline_bytes,
+            preview_bytes: 200,
+        },
+        ..Default::default()
+    }
+}
+
+async fn send(tc: &TestContext, project_id: ProjectId, body_md: String) -> MessageId {
+    let mut ids = Vec::new();
+    for name in ["sender", "reader"] {
+        let id = match AgentBmc::get_by_name(&tc.ctx, &tc.mm, project_id, name).await {
+            Ok(agent) => agent.id,
+            Err(_) => AgentBmc::create(
+                &tc.ctx,
+                &tc.mm,
+                AgentForCreate {
+                    project_id,
+                    name: name.to_string(),
+                    program: "test".to_string(),
+                    model: "test".to_string(),
+                    task_description: String::new(),
+                },
+            )
+            .await
+            .unwrap(),
+        };
+        ids.push(id.get());
+    }
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: (ids[0]).into(),
+            recipient_ids: vec![AgentId::new(ids[1])],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Build log".to_string(),
+            body_md,
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn large_body() -> String {
+    let mut body = "compiling crate\n".repeat(300);
+    body.push_str("error: zanzibar linker failed\n");
+    body
+}
+
+#[tokio::test]
+async fn test_large_body_is_offloaded() {
+    let tc = TestContext::new_with_config(config(1024)).await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "bodies", "/bodies")
+        .await
+        .unwrap();
+
+    let small = send(&tc, project_id, "short".to_string()).await;
+    assert!(
+        MessageBmc::get_offloaded_body(&tc.ctx, &tc.mm, small)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let body = large_body();
+    let id = send(&tc, project_id, body.clone()).await;
+    let message = MessageBmc::get(&tc.ctx, &tc.mm, id).await.unwrap();
+    assert!(message.body_md.len() < 1024);
+    assert!(message.body_md.starts_with("compiling crate\n"));
+    assert!(message.body_md.contains("get_message(full=true)"));
+    assert!(!message.body_md.contains("zanzibar"));
+
+    let full = MessageBmc::get_offloaded_body(&tc.ctx, &tc.mm, id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(full.size_bytes, body.len() as i64);
+    assert_eq!(full.sha256.len(), 64);
+    assert_eq!(
+        MessageBmc::get_full(&tc.ctx, &tc.mm, id)
+            .await
+            .unwrap()
+            .body_md,
+        body
+    );
+    let sizes = MessageBmc::get_offloaded_sizes(&tc.ctx, &tc.mm, &[small.get(), id.get()])
+        .await
+        .unwrap();
+    assert_eq!(sizes.len(), 1);
+    assert_eq!(sizes[&id.get()], body.len() as i64);
+
+    // The offloaded text is still found by search
+    let hits =
+        MessageBmc::search_with_snippets(&tc.ctx, &tc.mm, project_id, "zanzibar", 10, 0, true, &[])
+            .await
+            .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].message.id, id.get());
+}
+
+#[tokio::test]
+async fn test_zero_limit_keeps_bodies_inline() {
+    let tc = TestContext::new_with_config(config(0)).await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "inline", "/inline")
+        .await
+        .unwrap();
+
+    let body = large_body();
+    let id = send(&tc, project_id, body.clone()).await;
+    assert_eq!(
+        MessageBmc::get(&tc.ctx, &tc.mm, id).await.unwrap().body_md,
+        body
+    );
+    assert!(
+        MessageBmc::get_offloaded_body(&tc.ctx, &tc.mm, id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
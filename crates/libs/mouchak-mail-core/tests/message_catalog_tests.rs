@@ -0,0 +1,164 @@
+//! Message catalog tests
+//!
+//! Tests for project locales, custom templates and their fallback, and
+//! localized system messages.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::escalation::EscalationBmc;
+use mouchak_mail_core::model::message::{MessageBmc, OverdueMessage};
+use mouchak_mail_core::model::message_catalog::MessageCatalogBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::project_settings::{ProjectSettingsBmc, ProjectSettingsForUpdate};
+use mouchak_mail_core::types::ProjectId;
+
+async fn set_locale(tc: &TestContext, project_id: ProjectId, locale: &str) -> String {
+    ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        ProjectSettingsForUpdate {
+            locale: Some(locale.to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .map(|settings| settings.locale)
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_custom_templates_and_fallback() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "i18n", "/i18n")
+        .await
+        .unwrap();
+    let subject = |catalog: &mouchak_mail_core::model::message_catalog::Catalog| {
+        catalog.render("escalation.reminder.subject", &[("subject", "Deploy")])
+    };
+
+    let catalog = MessageCatalogBmc::for_project(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap();
+    assert_eq!(catalog.locale(), "en");
+    assert_eq!(subject(&catalog), "REMINDER: Deploy");
+
+    assert_eq!(set_locale(&tc, project_id, "de_at").await, "de-AT");
+    let catalog = MessageCatalogBmc::for_project(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap();
+    assert_eq!(subject(&catalog), "ERINNERUNG: Deploy");
+
+    // A custom template of the language applies to its regional variants
+    MessageCatalogBmc::set_template(
+        &tc.ctx,
+        &tc.mm,
+        "de",
+        "escalation.reminder.subject",
+        "Bitte bestätigen: {subject}",
+    )
+    .await
+    .unwrap();
+    let catalog = MessageCatalogBmc::for_project(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap();
+    assert_eq!(subject(&catalog), "Bitte bestätigen: Deploy");
+
+    let templates = MessageCatalogBmc::list_templates(&tc.ctx, &tc.mm, "de-AT")
+        .await
+        .unwrap();
+    let reminder = templates
+        .iter()
+        .find(|t| t.key == "escalation.reminder.subject")
+        .unwrap();
+    assert!(reminder.custom);
+    assert_eq!(reminder.source, "de");
+    assert_eq!(reminder.placeholders, ["subject"]);
+    assert!(
+        templates
+            .iter()
+            .filter(|t| t.key != reminder.key)
+            .all(|t| !t.custom)
+    );
+
+    // Locales without a built-in catalog fall back to English
+    let templates = MessageCatalogBmc::list_templates(&tc.ctx, &tc.mm, "pt-BR")
+        .await
+        .unwrap();
+    assert!(templates.iter().all(|t| t.source == "en"));
+
+    for (key, template) in [
+        ("no.such.key", "text"),
+        ("escalation.reminder.subject", "  "),
+        ("escalation.reminder.subject", "{subject} for {recipient}"),
+    ] {
+        let err = MessageCatalogBmc::set_template(&tc.ctx, &tc.mm, "de", key, template)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{}", key);
+    }
+    let err = ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        ProjectSettingsForUpdate {
+            locale: Some("german".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+
+    assert!(
+        MessageCatalogBmc::remove_template(&tc.ctx, &tc.mm, "de", "escalation.reminder.subject")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !MessageCatalogBmc::remove_template(&tc.ctx, &tc.mm, "de", "escalation.reminder.subject")
+            .await
+            .unwrap()
+    );
+    let catalog = MessageCatalogBmc::for_project(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap();
+    assert_eq!(subject(&catalog), "ERINNERUNG: Deploy");
+}
+
+#[tokio::test]
+async fn test_reminder_uses_project_locale() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "recordatorio", "/recordatorio")
+        .await
+        .unwrap();
+    let sender = create_agent(&tc, project_id, "sender").await;
+    let recipient = create_agent(&tc, project_id, "recipient").await;
+    set_locale(&tc, project_id, "es").await;
+
+    let overdue = OverdueMessage {
+        message_id: 42,
+        project_id: project_id.get(),
+        sender_id: sender.get(),
+        subject: "Revisar {subject}".to_string(),
+        sender_name: "sender".to_string(),
+        recipient_id: recipient.get(),
+        recipient_name: "recipient".to_string(),
+        created_ts: chrono::NaiveDateTime::default(),
+    };
+    let reminder_id = EscalationBmc::send_reminder(&tc.ctx, &tc.mm, &overdue)
+        .await
+        .unwrap();
+
+    let reminder = MessageBmc::get(&tc.ctx, &tc.mm, reminder_id).await.unwrap();
+    // Placeholders inside values are not expanded
+    assert_eq!(reminder.subject, "RECORDATORIO: Revisar {subject}");
+    assert!(reminder.body_md.starts_with("[Escalado del sistema]"));
+    assert!(reminder.body_md.contains("ID del mensaje original: 42"));
+}
@@ -16,6 +16,7 @@ use crate::common::TestContext;
 use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, ProjectId};
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to set up project and agents for message tests
@@ -64,9 +65,9 @@ async fn test_send_message() {
     let (project_id, sender_id, recipient_id) = setup_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Test Subject".to_string(),
@@ -80,7 +81,7 @@ async fn test_send_message() {
         .await
         .expect("Failed to send message");
 
-    assert!(msg_id > 0, "Message should have valid ID");
+    assert!(msg_id.get() > 0, "Message should have valid ID");
 }
 
 /// Test getting a specific message
@@ -92,9 +93,9 @@ async fn test_get_message() {
     let (project_id, sender_id, recipient_id) = setup_messaging(&tc).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Important Message".to_string(),
@@ -124,9 +125,9 @@ async fn test_message_threading() {
 
     // Send initial message
     let initial_msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Thread Start".to_string(),
@@ -144,9 +145,9 @@ async fn test_message_threading() {
 
     // Send reply in same thread
     let reply_msg_c = MessageForCreate {
-        project_id,
-        sender_id: recipient_id, // Reply from recipient
-        recipient_ids: vec![sender_id],
+        project_id: project_id.into(),
+        sender_id: recipient_id.into(), // Reply from recipient
+        recipient_ids: vec![AgentId::new(sender_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Re: Thread Start".to_string(),
@@ -167,6 +168,130 @@ async fn test_message_threading() {
     );
 }
 
+/// Replies without a thread_id join the thread matched by subject + participants
+#[tokio::test]
+async fn test_reply_threading_by_normalized_subject() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let (project_id, sender_id, recipient_id) = setup_messaging(&tc).await;
+
+    let initial_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(recipient_id)],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Deploy  plan".to_string(),
+            body_md: "Starting a thread".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    let initial = MessageBmc::get(&tc.ctx, &tc.mm, initial_id).await.unwrap();
+
+    // Client dropped the thread_id and rewrote the prefix
+    let reply_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id: project_id.into(),
+            sender_id: recipient_id.into(),
+            recipient_ids: vec![AgentId::new(sender_id)],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "RE: Fwd: Deploy plan".to_string(),
+            body_md: "This is a reply".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    let reply = MessageBmc::get(&tc.ctx, &tc.mm, reply_id).await.unwrap();
+    assert_eq!(initial.thread_id, reply.thread_id);
+
+    // A non-reply subject always starts a fresh thread
+    let fresh_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(recipient_id)],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Deploy plan".to_string(),
+            body_md: "Unrelated".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    let fresh = MessageBmc::get(&tc.ctx, &tc.mm, fresh_id).await.unwrap();
+    assert_ne!(initial.thread_id, fresh.thread_id);
+}
+
+/// Replies with different participants do not join an unrelated thread
+#[tokio::test]
+async fn test_reply_threading_requires_same_participants() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let (project_id, sender_id, recipient_id) = setup_messaging(&tc).await;
+
+    let initial_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(recipient_id)],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Schema change".to_string(),
+            body_md: "Starting a thread".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    let initial = MessageBmc::get(&tc.ctx, &tc.mm, initial_id).await.unwrap();
+
+    // Self-addressed reply has a different participant set
+    let other_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(sender_id)],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Re: Schema change".to_string(),
+            body_md: "Note to self".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    let other = MessageBmc::get(&tc.ctx, &tc.mm, other_id).await.unwrap();
+    assert_ne!(initial.thread_id, other.thread_id);
+}
+
 /// Test full-text search using FTS5
 #[tokio::test]
 async fn test_search_messages() {
@@ -177,9 +302,9 @@ async fn test_search_messages() {
 
     // Send a few messages with different content
     let msg1_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Database Migration".to_string(),
@@ -191,9 +316,9 @@ async fn test_search_messages() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg1_c).await.unwrap();
 
     let msg2_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "API Design".to_string(),
@@ -205,9 +330,9 @@ async fn test_search_messages() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg2_c).await.unwrap();
 
     let msg3_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Performance".to_string(),
@@ -219,9 +344,15 @@ async fn test_search_messages() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg3_c).await.unwrap();
 
     // Search for "full-text search" - should match msg1 and msg3
-    let results = MessageBmc::search(&tc.ctx, &tc.mm, project_id, "full-text search", 10)
-        .await
-        .expect("Search should succeed");
+    let results = MessageBmc::search(
+        &tc.ctx,
+        &tc.mm,
+        ProjectId::new(project_id),
+        "full-text search",
+        10,
+    )
+    .await
+    .expect("Search should succeed");
 
     // Verify search finds relevant messages
     assert!(
@@ -246,9 +377,9 @@ async fn test_mark_message_read() {
 
     // Send a message
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Unread Message".to_string(),
@@ -260,11 +391,11 @@ async fn test_mark_message_read() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // Mark as read by recipient
-    let result = MessageBmc::mark_read(&tc.ctx, &tc.mm, msg_id, recipient_id).await;
+    let result = MessageBmc::mark_read(&tc.ctx, &tc.mm, msg_id, recipient_id.into()).await;
     assert!(result.is_ok(), "mark_read should succeed");
 
     // Calling mark_read again should be idempotent (no error)
-    let result2 = MessageBmc::mark_read(&tc.ctx, &tc.mm, msg_id, recipient_id).await;
+    let result2 = MessageBmc::mark_read(&tc.ctx, &tc.mm, msg_id, recipient_id.into()).await;
     assert!(result2.is_ok(), "mark_read should be idempotent");
 }
 
@@ -278,9 +409,9 @@ async fn test_acknowledge_message() {
 
     // Send a message with ack_required
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Important - ACK Required".to_string(),
@@ -292,11 +423,11 @@ async fn test_acknowledge_message() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // Acknowledge by recipient (also marks as read)
-    let result = MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, recipient_id).await;
+    let result = MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, recipient_id.into()).await;
     assert!(result.is_ok(), "acknowledge should succeed");
 
     // Calling acknowledge again should be idempotent
-    let result2 = MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, recipient_id).await;
+    let result2 = MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, recipient_id.into()).await;
     assert!(result2.is_ok(), "acknowledge should be idempotent");
 }
 
@@ -310,9 +441,9 @@ async fn test_list_threads() {
 
     // Send messages in thread 1
     let msg1_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Thread A - Start".to_string(),
@@ -326,9 +457,9 @@ async fn test_list_threads() {
 
     // Reply in thread 1
     let reply_c = MessageForCreate {
-        project_id,
-        sender_id: recipient_id,
-        recipient_ids: vec![sender_id],
+        project_id: project_id.into(),
+        sender_id: recipient_id.into(),
+        recipient_ids: vec![AgentId::new(sender_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Re: Thread A - Start".to_string(),
@@ -341,9 +472,9 @@ async fn test_list_threads() {
 
     // Send message in thread 2
     let msg2_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Thread B - Different topic".to_string(),
@@ -355,7 +486,7 @@ async fn test_list_threads() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg2_c).await.unwrap();
 
     // List threads
-    let threads = MessageBmc::list_threads(&tc.ctx, &tc.mm, project_id, 10)
+    let threads = MessageBmc::list_threads(&tc.ctx, &tc.mm, ProjectId::new(project_id), 10, false)
         .await
         .expect("Should list threads");
 
@@ -378,9 +509,9 @@ async fn test_list_outbox() {
 
     // Send multiple messages from sender
     let msg1_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Outbox Test 1".to_string(),
@@ -392,9 +523,9 @@ async fn test_list_outbox() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg1_c).await.unwrap();
 
     let msg2_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Outbox Test 2".to_string(),
@@ -406,9 +537,10 @@ async fn test_list_outbox() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg2_c).await.unwrap();
 
     // List outbox for sender
-    let outbox = MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project_id, sender_id, 10)
-        .await
-        .expect("Should list outbox messages");
+    let outbox =
+        MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project_id.into(), sender_id.into(), 10)
+            .await
+            .expect("Should list outbox messages");
 
     // Verify we got both messages
     assert_eq!(outbox.len(), 2, "Should have 2 messages in outbox");
@@ -493,9 +625,9 @@ async fn test_outbox_project_filtering() {
 
     // Send message in project 1
     let msg1_c = MessageForCreate {
-        project_id: project1.id.into(),
-        sender_id: sender1_id.into(),
-        recipient_ids: vec![recipient1_id.into()],
+        project_id: project1.id,
+        sender_id: sender1_id,
+        recipient_ids: vec![recipient1_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Project 1 Message".to_string(),
@@ -508,9 +640,9 @@ async fn test_outbox_project_filtering() {
 
     // Send message in project 2
     let msg2_c = MessageForCreate {
-        project_id: project2.id.into(),
-        sender_id: sender2_id.into(),
-        recipient_ids: vec![recipient2_id.into()],
+        project_id: project2.id,
+        sender_id: sender2_id,
+        recipient_ids: vec![recipient2_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Project 2 Message".to_string(),
@@ -522,26 +654,14 @@ async fn test_outbox_project_filtering() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg2_c).await.unwrap();
 
     // List outbox for sender1 in project1
-    let outbox1 = MessageBmc::list_outbox_for_agent(
-        &tc.ctx,
-        &tc.mm,
-        project1.id.into(),
-        sender1_id.into(),
-        10,
-    )
-    .await
-    .expect("Should list outbox for project 1");
+    let outbox1 = MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project1.id, sender1_id, 10)
+        .await
+        .expect("Should list outbox for project 1");
 
     // List outbox for sender2 in project2
-    let outbox2 = MessageBmc::list_outbox_for_agent(
-        &tc.ctx,
-        &tc.mm,
-        project2.id.into(),
-        sender2_id.into(),
-        10,
-    )
-    .await
-    .expect("Should list outbox for project 2");
+    let outbox2 = MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project2.id, sender2_id, 10)
+        .await
+        .expect("Should list outbox for project 2");
 
     // Verify correct filtering
     assert_eq!(
@@ -569,9 +689,9 @@ async fn test_outbox_pagination() {
     // Send 5 messages
     for i in 1..=5 {
         let msg_c = MessageForCreate {
-            project_id,
-            sender_id,
-            recipient_ids: vec![recipient_id],
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(recipient_id)],
             cc_ids: None,
             bcc_ids: None,
             subject: format!("Message {}", i),
@@ -585,7 +705,7 @@ async fn test_outbox_pagination() {
 
     // List with limit of 3
     let outbox_limited =
-        MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project_id, sender_id, 3)
+        MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project_id.into(), sender_id.into(), 3)
             .await
             .expect("Should list outbox with limit");
 
@@ -593,9 +713,10 @@ async fn test_outbox_pagination() {
     assert_eq!(outbox_limited.len(), 3, "Should return exactly 3 messages");
 
     // List with limit of 10 (should return all 5)
-    let outbox_all = MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project_id, sender_id, 10)
-        .await
-        .expect("Should list all outbox messages");
+    let outbox_all =
+        MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project_id.into(), sender_id.into(), 10)
+            .await
+            .expect("Should list all outbox messages");
 
     assert_eq!(outbox_all.len(), 5, "Should return all 5 messages");
 }
@@ -659,11 +780,11 @@ async fn test_outbox_with_multiple_recipients() {
 
     // Send message with multiple recipients (to, cc, bcc)
     let msg_c = MessageForCreate {
-        project_id: project.id.into(),
-        sender_id: sender_id.into(),
-        recipient_ids: vec![recipient1_id.into()],
-        cc_ids: Some(vec![recipient2_id.into()]),
-        bcc_ids: Some(vec![recipient3_id.into()]),
+        project_id: project.id,
+        sender_id,
+        recipient_ids: vec![recipient1_id],
+        cc_ids: Some(vec![recipient2_id]),
+        bcc_ids: Some(vec![recipient3_id]),
         subject: "Multi-recipient Message".to_string(),
         body_md: "This message has multiple recipients".to_string(),
         thread_id: None,
@@ -673,51 +794,32 @@ async fn test_outbox_with_multiple_recipients() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // Check outbox for sender
-    let outbox =
-        MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project.id.into(), sender_id.into(), 10)
-            .await
-            .expect("Should list outbox");
+    let outbox = MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project.id, sender_id, 10)
+        .await
+        .expect("Should list outbox");
 
     // Should have exactly 1 message
     assert_eq!(outbox.len(), 1, "Should have 1 message in outbox");
     assert_eq!(outbox[0].subject, "Multi-recipient Message");
 
     // Verify each recipient got the message in their inbox
-    let inbox1 = MessageBmc::list_inbox_for_agent(
-        &tc.ctx,
-        &tc.mm,
-        project.id.into(),
-        recipient1_id.into(),
-        10,
-    )
-    .await
-    .expect("Should list inbox for recipient1");
+    let inbox1 = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id, recipient1_id, 10)
+        .await
+        .expect("Should list inbox for recipient1");
     assert_eq!(inbox1.len(), 1, "Recipient1 should have message in inbox");
 
-    let inbox2 = MessageBmc::list_inbox_for_agent(
-        &tc.ctx,
-        &tc.mm,
-        project.id.into(),
-        recipient2_id.into(),
-        10,
-    )
-    .await
-    .expect("Should list inbox for recipient2");
+    let inbox2 = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id, recipient2_id, 10)
+        .await
+        .expect("Should list inbox for recipient2");
     assert_eq!(
         inbox2.len(),
         1,
         "Recipient2 (CC) should have message in inbox"
     );
 
-    let inbox3 = MessageBmc::list_inbox_for_agent(
-        &tc.ctx,
-        &tc.mm,
-        project.id.into(),
-        recipient3_id.into(),
-        10,
-    )
-    .await
-    .expect("Should list inbox for recipient3");
+    let inbox3 = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id, recipient3_id, 10)
+        .await
+        .expect("Should list inbox for recipient3");
     assert_eq!(
         inbox3.len(),
         1,
@@ -734,9 +836,10 @@ async fn test_empty_outbox() {
     let (project_id, sender_id, _recipient_id) = setup_messaging(&tc).await;
 
     // List outbox without sending any messages
-    let outbox = MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project_id, sender_id, 10)
-        .await
-        .expect("Should list empty outbox");
+    let outbox =
+        MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project_id.into(), sender_id.into(), 10)
+            .await
+            .expect("Should list empty outbox");
 
     assert_eq!(outbox.len(), 0, "Outbox should be empty");
 }
@@ -786,11 +889,10 @@ async fn test_multiple_to_recipients() {
     }
 
     // Send message with multiple TO recipients (no CC/BCC)
-    let recipient_ids_i64: Vec<i64> = recipient_ids.iter().map(|id| (*id).into()).collect();
     let msg_c = MessageForCreate {
-        project_id: project.id.into(),
-        sender_id: sender_id.into(),
-        recipient_ids: recipient_ids_i64,
+        project_id: project.id,
+        sender_id,
+        recipient_ids: recipient_ids.clone(),
         cc_ids: None,
         bcc_ids: None,
         subject: "Batch TO Recipients Test".to_string(),
@@ -809,10 +911,9 @@ async fn test_multiple_to_recipients() {
 
     // Verify ALL 3 recipients received the message in their inbox
     for (i, rid) in recipient_ids.iter().enumerate() {
-        let inbox =
-            MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id.into(), (*rid).into(), 10)
-                .await
-                .expect("Should list inbox");
+        let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id, *rid, 10)
+            .await
+            .expect("Should list inbox");
         assert_eq!(
             inbox.len(),
             1,
@@ -823,10 +924,9 @@ async fn test_multiple_to_recipients() {
     }
 
     // Verify sender's outbox has exactly 1 message
-    let outbox =
-        MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project.id.into(), sender_id.into(), 10)
-            .await
-            .expect("Should list outbox");
+    let outbox = MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project.id, sender_id, 10)
+        .await
+        .expect("Should list outbox");
     assert_eq!(outbox.len(), 1, "Sender should have 1 message in outbox");
 }
 
@@ -844,9 +944,9 @@ async fn test_ack_required_persisted() {
 
     // Create message with ack_required = true
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Review Required".to_string(),
@@ -878,9 +978,9 @@ async fn test_ack_required_defaults_false() {
 
     // Create message without specifying ack_required (should default to false)
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Normal Message".to_string(),
@@ -911,9 +1011,9 @@ async fn test_list_pending_reviews_returns_ack_required_messages() {
 
     // Create message WITH ack_required
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "[COMPLETION] Task Done".to_string(),
@@ -927,13 +1027,19 @@ async fn test_list_pending_reviews_returns_ack_required_messages() {
         .expect("Should create message");
 
     // List pending reviews
-    let pending = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project_id), None, 10)
-        .await
-        .expect("Should list pending reviews");
+    let pending = MessageBmc::list_pending_reviews(
+        &tc.ctx,
+        &tc.mm,
+        Some(ProjectId::new(project_id)),
+        None,
+        10,
+    )
+    .await
+    .expect("Should list pending reviews");
 
     // Should find our message
     assert_eq!(pending.len(), 1, "Should have 1 pending review");
-    assert_eq!(pending[0].message_id, msg_id);
+    assert_eq!(pending[0].message_id, msg_id.get());
     assert_eq!(pending[0].subject, "[COMPLETION] Task Done");
 }
 
@@ -947,9 +1053,9 @@ async fn test_list_pending_reviews_excludes_non_ack_required() {
 
     // Create message WITHOUT ack_required
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Regular Update".to_string(),
@@ -963,9 +1069,15 @@ async fn test_list_pending_reviews_excludes_non_ack_required() {
         .expect("Should create message");
 
     // List pending reviews
-    let pending = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project_id), None, 10)
-        .await
-        .expect("Should list pending reviews");
+    let pending = MessageBmc::list_pending_reviews(
+        &tc.ctx,
+        &tc.mm,
+        Some(ProjectId::new(project_id)),
+        None,
+        10,
+    )
+    .await
+    .expect("Should list pending reviews");
 
     // Should NOT find our message (ack_required = false)
     assert_eq!(pending.len(), 0, "Should have no pending reviews");
@@ -981,9 +1093,9 @@ async fn test_list_pending_reviews_excludes_acknowledged() {
 
     // Create message with ack_required
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Review Request".to_string(),
@@ -997,22 +1109,32 @@ async fn test_list_pending_reviews_excludes_acknowledged() {
         .expect("Should create message");
 
     // Before acknowledge: should appear in pending
-    let pending_before =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project_id), None, 10)
-            .await
-            .unwrap();
+    let pending_before = MessageBmc::list_pending_reviews(
+        &tc.ctx,
+        &tc.mm,
+        Some(ProjectId::new(project_id)),
+        None,
+        10,
+    )
+    .await
+    .unwrap();
     assert_eq!(pending_before.len(), 1, "Should have 1 pending before ack");
 
     // Acknowledge the message
-    MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, recipient_id)
+    MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, recipient_id.into())
         .await
         .expect("Should acknowledge");
 
     // After acknowledge: should NOT appear in pending
-    let pending_after =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project_id), None, 10)
-            .await
-            .unwrap();
+    let pending_after = MessageBmc::list_pending_reviews(
+        &tc.ctx,
+        &tc.mm,
+        Some(ProjectId::new(project_id)),
+        None,
+        10,
+    )
+    .await
+    .unwrap();
     assert_eq!(
         pending_after.len(),
         0,
@@ -1064,9 +1186,9 @@ async fn test_list_pending_reviews_partial_ack() {
 
     // Create message requiring ack from both reviewers
     let msg_c = MessageForCreate {
-        project_id: project.id.into(),
-        sender_id: sender_id.into(),
-        recipient_ids: vec![r1_id.into(), r2_id.into()],
+        project_id: project.id,
+        sender_id,
+        recipient_ids: vec![r1_id, r2_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Dual Review Required".to_string(),
@@ -1080,34 +1202,31 @@ async fn test_list_pending_reviews_partial_ack() {
         .expect("Should create message");
 
     // Before any ack: pending count = 1
-    let pending =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, 10)
-            .await
-            .unwrap();
+    let pending = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, 10)
+        .await
+        .unwrap();
     assert_eq!(pending.len(), 1);
 
     // First reviewer acknowledges
-    MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, r1_id.into())
+    MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, r1_id)
         .await
         .expect("R1 should ack");
 
     // Still pending (r2 hasn't acked)
-    let pending =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, 10)
-            .await
-            .unwrap();
+    let pending = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, 10)
+        .await
+        .unwrap();
     assert_eq!(pending.len(), 1, "Should still be pending - partial ack");
 
     // Second reviewer acknowledges
-    MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, r2_id.into())
+    MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, r2_id)
         .await
         .expect("R2 should ack");
 
     // Now fully acked - should be removed from pending
-    let pending =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, 10)
-            .await
-            .unwrap();
+    let pending = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, 10)
+        .await
+        .unwrap();
     assert_eq!(pending.len(), 0, "Should be empty after all acked");
 }
 
@@ -1126,9 +1245,9 @@ async fn test_list_pending_reviews_empty_when_all_acked() {
     // Create 3 messages all requiring acknowledgment
     for i in 1..=3 {
         let msg_c = MessageForCreate {
-            project_id,
-            sender_id,
-            recipient_ids: vec![recipient_id],
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(recipient_id)],
             cc_ids: None,
             bcc_ids: None,
             subject: format!("Review Request {}", i),
@@ -1140,15 +1259,21 @@ async fn test_list_pending_reviews_empty_when_all_acked() {
         let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
         // Acknowledge each message immediately
-        MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, recipient_id)
+        MessageBmc::acknowledge(&tc.ctx, &tc.mm, msg_id, recipient_id.into())
             .await
             .unwrap();
     }
 
     // After all acknowledged, pending reviews should be empty
-    let pending = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project_id), None, 10)
-        .await
-        .expect("Should list pending reviews");
+    let pending = MessageBmc::list_pending_reviews(
+        &tc.ctx,
+        &tc.mm,
+        Some(ProjectId::new(project_id)),
+        None,
+        10,
+    )
+    .await
+    .expect("Should list pending reviews");
 
     assert_eq!(
         pending.len(),
@@ -1195,9 +1320,9 @@ async fn test_list_pending_reviews_full_context_present() {
 
     // Create message with ack_required
     let msg_c = MessageForCreate {
-        project_id: project.id.into(),
-        sender_id: sender_id.into(),
-        recipient_ids: vec![recipient_id.into()],
+        project_id: project.id,
+        sender_id,
+        recipient_ids: vec![recipient_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "[COMPLETION] Full Context Test".to_string(),
@@ -1209,17 +1334,16 @@ async fn test_list_pending_reviews_full_context_present() {
     let msg_id = MessageBmc::create(&tc.ctx, &tc.mm, msg_c).await.unwrap();
 
     // Retrieve pending reviews
-    let pending =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, 10)
-            .await
-            .expect("Should list pending reviews");
+    let pending = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, 10)
+        .await
+        .expect("Should list pending reviews");
 
     assert_eq!(pending.len(), 1, "Should have 1 pending review");
 
     let row = &pending[0];
 
     // Verify message fields
-    assert_eq!(row.message_id, msg_id);
+    assert_eq!(row.message_id, msg_id.get());
     assert_eq!(row.subject, "[COMPLETION] Full Context Test");
     assert_eq!(row.body_md, "This message tests full context retrieval.");
     assert_eq!(row.importance, "high");
@@ -1306,9 +1430,9 @@ async fn test_list_pending_reviews_filter_by_project() {
 
     // Create ack_required message in project 1
     let msg1_c = MessageForCreate {
-        project_id: project1.id.into(),
-        sender_id: sender1_id.into(),
-        recipient_ids: vec![r1_id.into()],
+        project_id: project1.id,
+        sender_id: sender1_id,
+        recipient_ids: vec![r1_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Project 1 Review".to_string(),
@@ -1321,9 +1445,9 @@ async fn test_list_pending_reviews_filter_by_project() {
 
     // Create ack_required message in project 2
     let msg2_c = MessageForCreate {
-        project_id: project2.id.into(),
-        sender_id: sender2_id.into(),
-        recipient_ids: vec![r2_id.into()],
+        project_id: project2.id,
+        sender_id: sender2_id,
+        recipient_ids: vec![r2_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Project 2 Review".to_string(),
@@ -1335,20 +1459,18 @@ async fn test_list_pending_reviews_filter_by_project() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg2_c).await.unwrap();
 
     // Filter by project 1 only
-    let pending1 =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project1.id.into()), None, 10)
-            .await
-            .expect("Should filter by project 1");
+    let pending1 = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project1.id), None, 10)
+        .await
+        .expect("Should filter by project 1");
 
     assert_eq!(pending1.len(), 1, "Should have 1 pending in project 1");
     assert_eq!(pending1[0].subject, "Project 1 Review");
     assert_eq!(pending1[0].project_id, Into::<i64>::into(project1.id));
 
     // Filter by project 2 only
-    let pending2 =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project2.id.into()), None, 10)
-            .await
-            .expect("Should filter by project 2");
+    let pending2 = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project2.id), None, 10)
+        .await
+        .expect("Should filter by project 2");
 
     assert_eq!(pending2.len(), 1, "Should have 1 pending in project 2");
     assert_eq!(pending2[0].subject, "Project 2 Review");
@@ -1412,9 +1534,9 @@ async fn test_list_pending_reviews_filter_by_sender() {
 
     // Message from sender 1
     let msg1_c = MessageForCreate {
-        project_id: project.id.into(),
-        sender_id: sender1_id.into(),
-        recipient_ids: vec![recipient_id.into()],
+        project_id: project.id,
+        sender_id: sender1_id,
+        recipient_ids: vec![recipient_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "From Sender1".to_string(),
@@ -1427,9 +1549,9 @@ async fn test_list_pending_reviews_filter_by_sender() {
 
     // Message from sender 2
     let msg2_c = MessageForCreate {
-        project_id: project.id.into(),
-        sender_id: sender2_id.into(),
-        recipient_ids: vec![recipient_id.into()],
+        project_id: project.id,
+        sender_id: sender2_id,
+        recipient_ids: vec![recipient_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "From Sender2".to_string(),
@@ -1441,40 +1563,29 @@ async fn test_list_pending_reviews_filter_by_sender() {
     MessageBmc::create(&tc.ctx, &tc.mm, msg2_c).await.unwrap();
 
     // Filter by sender 1 only
-    let pending1 = MessageBmc::list_pending_reviews(
-        &tc.ctx,
-        &tc.mm,
-        Some(project.id.into()),
-        Some(sender1_id.into()),
-        10,
-    )
-    .await
-    .expect("Should filter by sender 1");
+    let pending1 =
+        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), Some(sender1_id), 10)
+            .await
+            .expect("Should filter by sender 1");
 
     assert_eq!(pending1.len(), 1, "Should have 1 message from sender 1");
     assert_eq!(pending1[0].subject, "From Sender1");
     assert_eq!(pending1[0].sender_id, Into::<i64>::into(sender1_id));
 
     // Filter by sender 2 only
-    let pending2 = MessageBmc::list_pending_reviews(
-        &tc.ctx,
-        &tc.mm,
-        Some(project.id.into()),
-        Some(sender2_id.into()),
-        10,
-    )
-    .await
-    .expect("Should filter by sender 2");
+    let pending2 =
+        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), Some(sender2_id), 10)
+            .await
+            .expect("Should filter by sender 2");
 
     assert_eq!(pending2.len(), 1, "Should have 1 message from sender 2");
     assert_eq!(pending2[0].subject, "From Sender2");
     assert_eq!(pending2[0].sender_id, Into::<i64>::into(sender2_id));
 
     // No sender filter - should return both
-    let pending_all =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, 10)
-            .await
-            .expect("Should list all without sender filter");
+    let pending_all = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, 10)
+        .await
+        .expect("Should list all without sender filter");
 
     assert_eq!(
         pending_all.len(),
@@ -1522,9 +1633,9 @@ async fn test_list_pending_reviews_limit_clamped() {
     // Create 5 messages with ack_required
     for i in 1..=5 {
         let msg_c = MessageForCreate {
-            project_id: project.id.into(),
-            sender_id: sender_id.into(),
-            recipient_ids: vec![recipient_id.into()],
+            project_id: project.id,
+            sender_id,
+            recipient_ids: vec![recipient_id],
             cc_ids: None,
             bcc_ids: None,
             subject: format!("Review {}", i),
@@ -1537,24 +1648,21 @@ async fn test_list_pending_reviews_limit_clamped() {
     }
 
     // Test limit=3 returns exactly 3
-    let pending3 =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, 3)
-            .await
-            .expect("Should respect limit=3");
+    let pending3 = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, 3)
+        .await
+        .expect("Should respect limit=3");
     assert_eq!(pending3.len(), 3, "Should return exactly 3 with limit=3");
 
     // Test limit=0 is clamped to 1 (minimum)
-    let pending0 =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, 0)
-            .await
-            .expect("Should clamp limit=0 to 1");
+    let pending0 = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, 0)
+        .await
+        .expect("Should clamp limit=0 to 1");
     assert_eq!(pending0.len(), 1, "limit=0 should be clamped to 1");
 
     // Test negative limit is clamped to 1
-    let pending_neg =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, -5)
-            .await
-            .expect("Should clamp negative limit to 1");
+    let pending_neg = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, -5)
+        .await
+        .expect("Should clamp negative limit to 1");
     assert_eq!(
         pending_neg.len(),
         1,
@@ -1563,10 +1671,9 @@ async fn test_list_pending_reviews_limit_clamped() {
 
     // Test limit=100 is clamped to 50 (maximum)
     // We only have 5 messages so we can't directly verify 50, but we verify it doesn't error
-    let pending100 =
-        MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id.into()), None, 100)
-            .await
-            .expect("Should clamp limit=100 to 50 (max)");
+    let pending100 = MessageBmc::list_pending_reviews(&tc.ctx, &tc.mm, Some(project.id), None, 100)
+        .await
+        .expect("Should clamp limit=100 to 50 (max)");
     assert!(
         pending100.len() <= 50,
         "Large limit should be clamped to max 50"
@@ -1644,15 +1751,12 @@ async fn test_batch_mixed_recipient_types() {
     }
 
     // Send message with 2 TO, 2 CC, 2 BCC (6 total recipients)
-    let to_ids_i64: Vec<i64> = to_ids.iter().map(|id| (*id).into()).collect();
-    let cc_ids_i64: Vec<i64> = cc_ids.iter().map(|id| (*id).into()).collect();
-    let bcc_ids_i64: Vec<i64> = bcc_ids.iter().map(|id| (*id).into()).collect();
     let msg_c = MessageForCreate {
-        project_id: project.id.into(),
-        sender_id: sender_id.into(),
-        recipient_ids: to_ids_i64,
-        cc_ids: Some(cc_ids_i64),
-        bcc_ids: Some(bcc_ids_i64),
+        project_id: project.id,
+        sender_id,
+        recipient_ids: to_ids.clone(),
+        cc_ids: Some(cc_ids.clone()),
+        bcc_ids: Some(bcc_ids.clone()),
         subject: "Mixed Batch Test".to_string(),
         body_md: "Testing batch insert with 6 total recipients".to_string(),
         thread_id: None,
@@ -1669,35 +1773,123 @@ async fn test_batch_mixed_recipient_types() {
 
     // Verify all TO recipients received it
     for rid in &to_ids {
-        let inbox =
-            MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id.into(), (*rid).into(), 10)
-                .await
-                .unwrap();
+        let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id, *rid, 10)
+            .await
+            .unwrap();
         assert_eq!(inbox.len(), 1, "TO recipient should have message");
     }
 
     // Verify all CC recipients received it
     for rid in &cc_ids {
-        let inbox =
-            MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id.into(), (*rid).into(), 10)
-                .await
-                .unwrap();
+        let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id, *rid, 10)
+            .await
+            .unwrap();
         assert_eq!(inbox.len(), 1, "CC recipient should have message");
     }
 
     // Verify all BCC recipients received it
     for rid in &bcc_ids {
-        let inbox =
-            MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id.into(), (*rid).into(), 10)
-                .await
-                .unwrap();
+        let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id, *rid, 10)
+            .await
+            .unwrap();
         assert_eq!(inbox.len(), 1, "BCC recipient should have message");
     }
 
     // Verify sender has exactly 1 message in outbox
+    let outbox = MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project.id, sender_id, 10)
+        .await
+        .unwrap();
+    assert_eq!(outbox.len(), 1, "Sender should have 1 outbox message");
+}
+
+/// Test paging through an inbox and threads with cursors
+#[tokio::test]
+async fn test_inbox_and_thread_pages() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    let (project_id, sender_id, recipient_id) = setup_messaging(&tc).await;
+    let send = |i: i64| MessageForCreate {
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: format!("Message {}", i),
+        body_md: "Body".to_string(),
+        thread_id: Some(format!("T-{}", i % 3)),
+        importance: None,
+        ack_required: false,
+    };
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        ids.push(MessageBmc::create(&tc.ctx, &tc.mm, send(i)).await.unwrap());
+    }
+    let (pid, rid) = (ProjectId::new(project_id), AgentId::new(recipient_id));
+
+    // Messages created in the same second are ordered by id
+    let first = MessageBmc::list_inbox_page(&tc.ctx, &tc.mm, pid, rid, 2, None)
+        .await
+        .unwrap();
+    assert_eq!(first.total, 5);
+    let page_ids: Vec<i64> = first.items.iter().map(|m| m.id).collect();
+    assert_eq!(page_ids, [ids[4].get(), ids[3].get()]);
+
+    // New mail does not shift later pages
+    MessageBmc::create(&tc.ctx, &tc.mm, send(5)).await.unwrap();
+    let second =
+        MessageBmc::list_inbox_page(&tc.ctx, &tc.mm, pid, rid, 2, first.next_cursor.as_deref())
+            .await
+            .unwrap();
+    let page_ids: Vec<i64> = second.items.iter().map(|m| m.id).collect();
+    assert_eq!(page_ids, [ids[2].get(), ids[1].get()]);
+    let third =
+        MessageBmc::list_inbox_page(&tc.ctx, &tc.mm, pid, rid, 2, second.next_cursor.as_deref())
+            .await
+            .unwrap();
+    assert_eq!(third.items.len(), 1);
+    assert_eq!(third.items[0].id, ids[0].get());
+    assert!(third.next_cursor.is_none());
+
     let outbox =
-        MessageBmc::list_outbox_for_agent(&tc.ctx, &tc.mm, project.id.into(), sender_id.into(), 10)
+        MessageBmc::list_outbox_page(&tc.ctx, &tc.mm, pid, AgentId::new(sender_id), 10, None)
             .await
             .unwrap();
-    assert_eq!(outbox.len(), 1, "Sender should have 1 outbox message");
+    assert_eq!(outbox.total, 6);
+    assert!(outbox.next_cursor.is_none());
+
+    let threads = MessageBmc::list_threads_page(&tc.ctx, &tc.mm, pid, 2, None, false)
+        .await
+        .unwrap();
+    assert_eq!(threads.total, 3);
+    assert_eq!(threads.items.len(), 2);
+    let rest = MessageBmc::list_threads_page(
+        &tc.ctx,
+        &tc.mm,
+        pid,
+        2,
+        threads.next_cursor.as_deref(),
+        false,
+    )
+    .await
+    .unwrap();
+    assert_eq!(rest.items.len(), 1);
+    assert!(rest.next_cursor.is_none());
+    assert!(
+        threads
+            .items
+            .iter()
+            .all(|t| t.thread_id != rest.items[0].thread_id)
+    );
+
+    assert!(
+        MessageBmc::list_inbox_page(&tc.ctx, &tc.mm, pid, rid, 2, Some("bogus"))
+            .await
+            .is_err()
+    );
+    assert!(
+        MessageBmc::list_inbox_page(&tc.ctx, &tc.mm, pid, rid, 0, None)
+            .await
+            .is_err()
+    );
 }
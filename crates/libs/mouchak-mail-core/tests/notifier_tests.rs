@@ -0,0 +1,145 @@
+//! Local notifier tests
+//!
+//! Tests that the configured notifier command runs for urgent messages and
+//! escalations, using `sh` to append the substituted arguments to a file.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+#![cfg(unix)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::escalation::{EscalationBmc, EscalationMode};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+struct Setup {
+    tc: TestContext,
+    project_id: ProjectId,
+    sender: AgentId,
+    recipient: AgentId,
+    log: PathBuf,
+    _log_dir: tempfile::TempDir,
+}
+
+/// A project with two agents and a notifier that appends
+/// "{event} {message_id} {project}" lines to a log file.
+async fn setup(events: &str) -> Setup {
+    let log_dir = tempfile::tempdir().unwrap();
+    let log = log_dir.path().join("notifications.log");
+    let mut config = AppConfig::default();
+    config.notifier.command = Some(format!(
+        r#"sh -c 'echo "$0 $1 $2" >> "$3"' {{event}} {{message_id}} {{project}} "{}""#,
+        log.display()
+    ));
+    config.notifier.events = events.to_string();
+
+    let tc = TestContext::new_with_config(config).await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "alerts", "/alerts")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["sender", "recipient"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id);
+    }
+    Setup {
+        tc,
+        project_id,
+        sender: agents[0],
+        recipient: agents[1],
+        log,
+        _log_dir: log_dir,
+    }
+}
+
+impl Setup {
+    async fn send(&self, importance: &str, ack_required: bool) -> MessageId {
+        MessageBmc::create(
+            &self.tc.ctx,
+            &self.tc.mm,
+            MessageForCreate {
+                project_id: self.project_id,
+                sender_id: self.sender,
+                recipient_ids: vec![self.recipient],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: format!("{} update", importance),
+                body_md: "Details".to_string(),
+                thread_id: None,
+                importance: Some(importance.to_string()),
+                ack_required,
+            },
+        )
+        .await
+        .unwrap()
+    }
+}
+
+/// Waits for the notifier command to write `lines` lines.
+async fn read_log(log: &Path, lines: usize) -> Vec<String> {
+    for _ in 0..50 {
+        if let Ok(content) = std::fs::read_to_string(log) {
+            let found: Vec<String> = content.lines().map(str::to_string).collect();
+            if found.len() >= lines {
+                return found;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Vec::new()
+}
+
+#[tokio::test]
+async fn test_urgent_message_runs_command() {
+    let s = setup("urgent_message").await;
+    s.send("normal", false).await;
+    let urgent_id = s.send("urgent", false).await;
+
+    let lines = read_log(&s.log, 1).await;
+    assert_eq!(lines, [format!("urgent_message {} alerts", urgent_id)]);
+}
+
+#[tokio::test]
+async fn test_escalation_runs_command() {
+    let s = setup("escalation").await;
+    let message_id = s.send("normal", true).await;
+    s.tc.mm
+        .db_for_test()
+        .execute(
+            "UPDATE messages SET created_ts = datetime('now', '-25 hours') WHERE id = ?",
+            [message_id.get()],
+        )
+        .await
+        .unwrap();
+
+    // A dry run takes no action, so nothing is notified
+    EscalationBmc::escalate_overdue(&s.tc.ctx, &s.tc.mm, 24, EscalationMode::Log, true)
+        .await
+        .unwrap();
+    EscalationBmc::escalate_overdue(&s.tc.ctx, &s.tc.mm, 24, EscalationMode::Log, false)
+        .await
+        .unwrap();
+
+    let lines = read_log(&s.log, 1).await;
+    assert_eq!(lines, [format!("escalation {} alerts", message_id)]);
+}
@@ -0,0 +1,87 @@
+//! Onboarding bundle tests
+//!
+//! Tests for `projects init-bundle` output.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::onboarding::OnboardingBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+
+/// Test that the bundle contains every expected file with project details
+#[tokio::test]
+async fn test_write_bundle() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+    ProjectBmc::create(&tc.ctx, &tc.mm, "bundle-proj", "/work/bundle-proj")
+        .await
+        .expect("Failed to create project");
+
+    let out = tempfile::tempdir().unwrap();
+    let bundle = OnboardingBmc::write_bundle(
+        &tc.ctx,
+        &tc.mm,
+        "bundle-proj",
+        out.path(),
+        "http://mail.example:8765/",
+    )
+    .await
+    .expect("Failed to write bundle");
+
+    assert_eq!(bundle.project_slug, "bundle-proj");
+    assert_eq!(bundle.files.len(), 5);
+    for file in &bundle.files {
+        assert!(out.path().join(file).exists(), "missing {}", file);
+    }
+
+    let marker = std::fs::read_to_string(out.path().join(".mouchak-mail-project-id")).unwrap();
+    assert_eq!(marker, "bundle-proj");
+
+    let agents_md = std::fs::read_to_string(out.path().join("AGENTS.md")).unwrap();
+    assert!(agents_md.contains("http://mail.example:8765/mcp"));
+    assert!(agents_md.contains("\"project_slug\": \"bundle-proj\""));
+
+    let mcp: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(out.path().join(".mcp.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        mcp["mcpServers"]["mouchak-mail"]["url"],
+        "http://mail.example:8765/mcp"
+    );
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(out.path().join(".githooks/pre-commit"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert!(mode & 0o111 != 0, "pre-commit hook should be executable");
+    }
+}
+
+/// Test that an unknown project is rejected without writing files
+#[tokio::test]
+async fn test_write_bundle_unknown_project() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let out = tempfile::tempdir().unwrap();
+    let result = OnboardingBmc::write_bundle(
+        &tc.ctx,
+        &tc.mm,
+        "missing-proj",
+        out.path(),
+        "http://localhost:8765",
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert!(!out.path().join("AGENTS.md").exists());
+}
@@ -14,7 +14,7 @@ use mouchak_mail_core::model::orchestration::{
     OrchestrationBmc, OrchestrationState, parse_thread_state,
 };
 use mouchak_mail_core::model::project::ProjectBmc;
-use mouchak_mail_core::types::ProjectId;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
 
 async fn setup_project(tc: &TestContext, name: &str) -> (ProjectId, String) {
     let slug = format!("test-project-{}", name);
@@ -49,12 +49,12 @@ async fn send_message(
     body: &str,
     thread_id: Option<String>,
     ack_required: bool,
-) -> i64 {
+) -> MessageId {
     let msg = MessageForCreate {
-        project_id: project_id.get(),
-        sender_id,
-        recipient_ids,
-        cc_ids,
+        project_id,
+        sender_id: sender_id.into(),
+        recipient_ids: recipient_ids.into_iter().map(AgentId::new).collect(),
+        cc_ids: cc_ids.map(|ids| ids.into_iter().map(AgentId::new).collect()),
         bcc_ids: None,
         subject: subject.to_string(),
         body_md: body.to_string(),
@@ -94,7 +94,7 @@ async fn test_happy_path_worker_reviewer_approved() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .expect("Failed to list thread messages");
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Completed);
@@ -112,7 +112,7 @@ async fn test_happy_path_worker_reviewer_approved() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .expect("Failed to list thread messages");
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Reviewing);
@@ -130,7 +130,7 @@ async fn test_happy_path_worker_reviewer_approved() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .expect("Failed to list thread messages");
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Approved);
@@ -196,7 +196,7 @@ async fn test_fix_flow_reviewer_applies_fixes() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .expect("Failed to list thread messages");
 
@@ -235,14 +235,14 @@ async fn test_single_agent_fallback_no_reviewer() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .expect("Failed to list thread messages");
 
     assert_eq!(messages.len(), 1);
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Completed);
 
-    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id.get(), human_id, 10)
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, human_id.into(), 10)
         .await
         .expect("Failed to list human inbox");
     assert_eq!(
@@ -292,7 +292,7 @@ async fn test_review_claim_prevents_duplicates() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .expect("Failed to list thread messages");
 
@@ -333,7 +333,7 @@ async fn test_abandoned_task_detection() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .expect("Failed to list thread messages");
 
@@ -342,7 +342,7 @@ async fn test_abandoned_task_detection() {
     let abandoned = OrchestrationBmc::find_abandoned_tasks(
         &tc.ctx,
         &tc.mm,
-        project_id.get(),
+        project_id,
         std::time::Duration::from_secs(0),
     )
     .await
@@ -381,7 +381,7 @@ async fn test_full_state_machine_transitions() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .unwrap();
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Started);
@@ -399,7 +399,7 @@ async fn test_full_state_machine_transitions() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .unwrap();
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Completed);
@@ -417,7 +417,7 @@ async fn test_full_state_machine_transitions() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .unwrap();
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Reviewing);
@@ -435,7 +435,7 @@ async fn test_full_state_machine_transitions() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .unwrap();
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Rejected);
@@ -453,7 +453,7 @@ async fn test_full_state_machine_transitions() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .unwrap();
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Fixed);
@@ -471,7 +471,7 @@ async fn test_full_state_machine_transitions() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .unwrap();
     assert_eq!(parse_thread_state(&messages), OrchestrationState::Approved);
@@ -489,7 +489,7 @@ async fn test_full_state_machine_transitions() {
     )
     .await;
 
-    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, &thread_id)
         .await
         .unwrap();
     assert_eq!(
@@ -528,7 +528,7 @@ async fn test_cc_audit_trail_preserved() {
     .await;
 
     let human_inbox =
-        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id.get(), human_id, 10)
+        MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, human_id.into(), 10)
             .await
             .expect("Failed to list human inbox");
 
@@ -0,0 +1,172 @@
+//! Outbox review tests
+//!
+//! Tests that sent messages with recipients that have not read or
+//! acknowledged them in time are listed with suggested follow-ups.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::{Duration, Utc};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::outbox_review::{
+    FollowUpAction, OutboxReviewBmc, PendingState, ReviewThresholds,
+};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::reply_deadline::ReplyDeadlineBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: &[AgentId],
+    subject: &str,
+) -> i64 {
+    (MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: to.to_vec(),
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.to_string(),
+            body_md: "Please take a look".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: true,
+        },
+    )
+    .await
+    .unwrap())
+    .get()
+}
+
+#[tokio::test]
+async fn test_needs_attention_lists_pending_recipients() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "outbox", "/outbox")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+    let carol = create_agent(&tc, project_id, "carol").await;
+    let dave = create_agent(&tc, project_id, "dave").await;
+    let erin = create_agent(&tc, project_id, "erin").await;
+
+    let stale = send(&tc, project_id, alice, &[bob, carol, dave, erin], "Review").await;
+    let fresh = send(&tc, project_id, alice, &[bob, carol], "Just sent").await;
+    let db = tc.mm.db_for_test();
+    db.execute(
+        "UPDATE messages SET created_ts = datetime('now', '-2 days') WHERE id = ?",
+        [stale],
+    )
+    .await
+    .unwrap();
+    db.execute(
+        "UPDATE agents SET last_active_ts = datetime('now', '-3 days') WHERE id = ?",
+        [dave.get()],
+    )
+    .await
+    .unwrap();
+    MessageBmc::mark_read(&tc.ctx, &tc.mm, MessageId::new(stale), bob)
+        .await
+        .unwrap();
+    MessageBmc::acknowledge(&tc.ctx, &tc.mm, MessageId::new(stale), erin)
+        .await
+        .unwrap();
+
+    // bob agreed a deadline on the fresh message that has since passed
+    ReplyDeadlineBmc::request(
+        &tc.ctx,
+        &tc.mm,
+        MessageId::new(fresh),
+        &[bob],
+        Utc::now().naive_utc() + Duration::hours(1),
+    )
+    .await
+    .unwrap();
+    ReplyDeadlineBmc::respond(&tc.ctx, &tc.mm, MessageId::new(fresh), bob, None)
+        .await
+        .unwrap();
+    db.execute(
+        "UPDATE reply_deadlines SET deadline_ts = datetime('now', '-1 hour') WHERE message_id = ?",
+        [fresh],
+    )
+    .await
+    .unwrap();
+
+    let items = OutboxReviewBmc::needs_attention(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        alice,
+        ReviewThresholds::default(),
+        10,
+    )
+    .await
+    .unwrap();
+    let ids: Vec<i64> = items.iter().map(|i| i.message_id).collect();
+    assert_eq!(ids, [stale, fresh]);
+
+    let pending: Vec<(&str, PendingState)> = items[0]
+        .pending
+        .iter()
+        .map(|p| (p.agent_name.as_str(), p.state))
+        .collect();
+    assert_eq!(
+        pending,
+        [
+            ("bob", PendingState::Unacked),
+            ("carol", PendingState::Unread),
+            ("dave", PendingState::Unread),
+        ]
+    );
+    let actions: Vec<FollowUpAction> = items[0].follow_ups.iter().map(|f| f.action).collect();
+    assert_eq!(
+        actions,
+        [
+            FollowUpAction::Reassign,
+            FollowUpAction::Remind,
+            FollowUpAction::RequestDeadline,
+        ]
+    );
+
+    // carol is still within the unread threshold on the fresh message
+    assert_eq!(items[1].pending.len(), 1);
+    assert_eq!(items[1].pending[0].state, PendingState::DeadlineMissed);
+    assert_eq!(items[1].follow_ups[0].action, FollowUpAction::Escalate);
+
+    let first = OutboxReviewBmc::needs_attention(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        alice,
+        ReviewThresholds::default(),
+        1,
+    )
+    .await
+    .unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].pending.len(), 3);
+
+    let invalid = OutboxReviewBmc::needs_attention(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        alice,
+        ReviewThresholds {
+            unread_after_secs: 0,
+            unacked_after_secs: 3600,
+        },
+        10,
+    )
+    .await;
+    assert!(matches!(invalid, Err(Error::InvalidInput(_))));
+}
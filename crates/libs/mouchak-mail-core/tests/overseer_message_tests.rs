@@ -90,7 +90,7 @@ async fn test_list_unread_messages() {
             .expect("Failed to create message");
     }
 
-    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id.into())
+    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list unread messages");
 
@@ -121,7 +121,7 @@ async fn test_message_importance_levels() {
             .expect("Failed to create message");
     }
 
-    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id.into())
+    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list messages");
 
@@ -147,7 +147,7 @@ async fn test_empty_unread_list() {
 
     let (project_id, _) = setup_project_and_agent(&tc, "empty").await;
 
-    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id.into())
+    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list unread messages");
 
@@ -180,7 +180,7 @@ async fn test_message_ordering() {
             .expect("Failed to create message");
     }
 
-    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id.into())
+    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list messages");
 
@@ -215,7 +215,7 @@ async fn test_message_structure() {
         .await
         .expect("Failed to create message");
 
-    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id.into())
+    let unread = OverseerMessageBmc::list_unread(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list messages");
 
@@ -228,3 +228,62 @@ async fn test_message_structure() {
     assert_eq!(msg.importance, "high");
     assert!(msg.read_ts.is_none(), "New message should be unread");
 }
+
+/// Test the overseer inbox listing and mark-read flow
+#[tokio::test]
+async fn test_overseer_inbox_mark_read() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let (project_id, sender_id) = setup_project_and_agent(&tc, "inbox").await;
+
+    let mut ids = Vec::new();
+    for i in 1..=2 {
+        let msg_c = OverseerMessageForCreate {
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            subject: format!("[OVERDUE ACK] Item {}", i),
+            body_md: "Needs a human".to_string(),
+            importance: "high".to_string(),
+        };
+        ids.push(
+            OverseerMessageBmc::create(&tc.ctx, &tc.mm, msg_c)
+                .await
+                .expect("Failed to create message"),
+        );
+    }
+
+    // The unread count covers more than a page cut short by the limit
+    let page = OverseerMessageBmc::list_for_project(&tc.ctx, &tc.mm, project_id, false, 1)
+        .await
+        .expect("Failed to list inbox");
+    assert_eq!(page.len(), 1);
+    let count = OverseerMessageBmc::count_unread(&tc.ctx, &tc.mm, project_id)
+        .await
+        .expect("Failed to count unread");
+    assert_eq!(count, 2);
+
+    OverseerMessageBmc::mark_read(&tc.ctx, &tc.mm, ids[0])
+        .await
+        .expect("Failed to mark read");
+    let count = OverseerMessageBmc::count_unread(&tc.ctx, &tc.mm, project_id)
+        .await
+        .expect("Failed to count unread");
+    assert_eq!(count, 1);
+
+    let unread = OverseerMessageBmc::list_for_project(&tc.ctx, &tc.mm, project_id, false, 50)
+        .await
+        .expect("Failed to list inbox");
+    assert_eq!(unread.len(), 1);
+    assert_eq!(unread[0].id, ids[1]);
+
+    let all = OverseerMessageBmc::list_for_project(&tc.ctx, &tc.mm, project_id, true, 50)
+        .await
+        .expect("Failed to list inbox");
+    assert_eq!(all.len(), 2);
+    assert!(all.iter().any(|m| m.id == ids[0] && m.read_ts.is_some()));
+
+    let missing = OverseerMessageBmc::mark_read(&tc.ctx, &tc.mm, 999_999).await;
+    assert!(missing.is_err(), "Unknown message should not be marked");
+}
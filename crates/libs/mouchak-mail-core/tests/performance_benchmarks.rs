@@ -29,6 +29,7 @@ use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::export::{ExportBmc, ExportFormat, ScrubMode};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::AgentId;
 use std::sync::Arc;
 use std::time::Instant;
 use tempfile::TempDir;
@@ -48,16 +49,9 @@ async fn create_test_mm() -> (ModelManager, TempDir) {
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
     let _ = conn.execute("PRAGMA synchronous=NORMAL;", ()).await;
 
-    // Run migrations inline (same as store/mod.rs)
-    let migrations = [
-        include_str!("../../../../migrations/001_initial_schema.sql"),
-        include_str!("../../../../migrations/002_agent_capabilities.sql"),
-        include_str!("../../../../migrations/003_tool_metrics.sql"),
-        include_str!("../../../../migrations/004_attachments.sql"),
-    ];
-    for migration in &migrations {
-        conn.execute_batch(migration).await.expect("run migration");
-    }
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .expect("run migration");
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, temp_dir.path().to_path_buf(), app_config);
@@ -111,9 +105,9 @@ fn make_message(
     body_md: String,
 ) -> MessageForCreate {
     MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids,
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: recipient_ids.into_iter().map(AgentId::new).collect(),
         cc_ids: None,
         bcc_ids: None,
         subject,
@@ -0,0 +1,269 @@
+//! Postgres backend tests
+//!
+//! Run the BMCs against the Postgres database `MOUCHAK_TEST_DATABASE_URL`
+//! points at, so they are ignored by default:
+//!
+//! ```bash
+//! MOUCHAK_TEST_DATABASE_URL='postgres://postgres@localhost/mouchak_test?sslmode=disable' \
+//!     cargo test -p mouchak-mail-core --test postgres_tests -- --ignored
+//! ```
+//!
+//! Each test works in a schema of its own.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use mouchak_mail_common::config::{AppConfig, DatabaseConfig};
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::thread_read::ThreadReadBmc;
+use mouchak_mail_core::store::{self, Backend, postgres::PgDb};
+use mouchak_mail_core::types::{AgentId, ProjectId};
+use mouchak_mail_core::{Ctx, ModelManager};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tempfile::TempDir;
+
+static SCHEMA_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+struct PgContext {
+    mm: ModelManager,
+    ctx: Ctx,
+    config: DatabaseConfig,
+    _temp_dir: TempDir,
+}
+
+/// A model manager on a fresh schema of the test database.
+async fn setup() -> PgContext {
+    setup_with_replicas(|_| Vec::new()).await
+}
+
+/// [`setup`] with the replica URLs `replicas` returns for the test
+/// database's URL.
+async fn setup_with_replicas(replicas: impl Fn(&str) -> Vec<String>) -> PgContext {
+    let url = std::env::var("MOUCHAK_TEST_DATABASE_URL")
+        .expect("MOUCHAK_TEST_DATABASE_URL must point at a Postgres database");
+    let schema = format!(
+        "mouchak_test_{}_{}",
+        std::process::id(),
+        SCHEMA_COUNTER.fetch_add(1, Ordering::SeqCst)
+    );
+    let admin = PgDb::connect(&url, 1).await.unwrap();
+    admin
+        .execute_batch(&format!(
+            "DROP SCHEMA IF EXISTS {schema} CASCADE; CREATE SCHEMA {schema};"
+        ))
+        .await
+        .unwrap();
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let url = format!("{url}{separator}options=-c%20search_path%3D{schema}");
+    let config = DatabaseConfig {
+        replica_urls: replicas(&url).join(","),
+        url: Some(url),
+        pool_size: 4,
+        ..Default::default()
+    };
+    let db = store::new_db_pool(&config).await.unwrap();
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_root = temp_dir.path().join("archive");
+    std::fs::create_dir_all(&archive_root).unwrap();
+    let mm = ModelManager::new_for_test(db, archive_root, Arc::new(AppConfig::default()));
+    PgContext {
+        mm,
+        ctx: Ctx::root_ctx(),
+        config,
+        _temp_dir: temp_dir,
+    }
+}
+
+async fn create_agent(pc: &PgContext, project_id: ProjectId, name: &str) -> AgentId {
+    AgentBmc::create(
+        &pc.ctx,
+        &pc.mm,
+        AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: String::new(),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn message(project_id: ProjectId, from: AgentId, to: AgentId, subject: &str) -> MessageForCreate {
+    MessageForCreate {
+        project_id,
+        sender_id: from,
+        recipient_ids: vec![to],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: subject.to_string(),
+        body_md: format!("Body of {subject}"),
+        thread_id: Some("t-1".to_string()),
+        importance: None,
+        ack_required: true,
+    }
+}
+
+#[tokio::test]
+#[ignore = "Requires a Postgres database at MOUCHAK_TEST_DATABASE_URL"]
+async fn test_migrated_schema_is_current() {
+    let pc = setup().await;
+    assert_eq!(pc.mm.db_backend(), Backend::Postgres);
+    assert_eq!(pc.mm.db_pool_size(), 4);
+    assert!(pc.mm.health_check().await.unwrap());
+
+    let plan = store::check_data_format(&pc.config).await.unwrap();
+    assert_eq!(plan.backend, Backend::Postgres);
+    assert!(!plan.needs_upgrade(), "{}", plan.describe());
+    assert!(plan.pending_migrations.is_empty());
+}
+
+#[tokio::test]
+#[ignore = "Requires a Postgres database at MOUCHAK_TEST_DATABASE_URL"]
+async fn test_send_read_and_search() {
+    let pc = setup().await;
+    let project_id = ProjectBmc::create(&pc.ctx, &pc.mm, "pg", "/pg")
+        .await
+        .unwrap();
+    let alice = create_agent(&pc, project_id, "alice").await;
+    let bob = create_agent(&pc, project_id, "bob").await;
+
+    let first = MessageBmc::create(
+        &pc.ctx,
+        &pc.mm,
+        message(project_id, alice, bob, "Deploy plan"),
+    )
+    .await
+    .unwrap();
+    MessageBmc::create(
+        &pc.ctx,
+        &pc.mm,
+        message(project_id, alice, bob, "Rollback steps"),
+    )
+    .await
+    .unwrap();
+
+    let inbox = MessageBmc::list_inbox_for_agent(&pc.ctx, &pc.mm, project_id, bob, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 2);
+    assert!(
+        inbox
+            .iter()
+            .all(|m| m.ack_required && m.sender_name == "alice")
+    );
+
+    let found = MessageBmc::search(&pc.ctx, &pc.mm, project_id, "deploy", 10)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, first.get());
+    let found = MessageBmc::search(&pc.ctx, &pc.mm, project_id, "roll*", 10)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+
+    MessageBmc::mark_read(&pc.ctx, &pc.mm, first, bob)
+        .await
+        .unwrap();
+    MessageBmc::acknowledge(&pc.ctx, &pc.mm, first, bob)
+        .await
+        .unwrap();
+    let backlog = MessageBmc::ack_backlog(&pc.ctx, &pc.mm).await.unwrap();
+    assert_eq!(backlog.pending, 1);
+
+    let threads = MessageBmc::list_threads(&pc.ctx, &pc.mm, project_id, 10, false)
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0].message_count, 2);
+    assert!(!threads[0].archived);
+}
+
+#[tokio::test]
+#[ignore = "Requires a Postgres database at MOUCHAK_TEST_DATABASE_URL"]
+async fn test_upserts_keep_the_latest_read_position() {
+    let pc = setup().await;
+    let project_id = ProjectBmc::create(&pc.ctx, &pc.mm, "pg-reads", "/pg-reads")
+        .await
+        .unwrap();
+    let alice = create_agent(&pc, project_id, "alice").await;
+    let bob = create_agent(&pc, project_id, "bob").await;
+    let first = MessageBmc::create(&pc.ctx, &pc.mm, message(project_id, alice, bob, "One"))
+        .await
+        .unwrap();
+    let second = MessageBmc::create(&pc.ctx, &pc.mm, message(project_id, alice, bob, "Two"))
+        .await
+        .unwrap();
+
+    // Reading an older message does not move the position back
+    for id in [second, first] {
+        ThreadReadBmc::mark_read(&pc.ctx, &pc.mm, project_id, bob, "t-1", id)
+            .await
+            .unwrap();
+    }
+    let threads = ThreadReadBmc::list_threads(&pc.ctx, &pc.mm, project_id, bob, 10, false)
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0].unread_count, 0);
+    assert_eq!(threads[0].last_read_message_id, Some(second.get()));
+}
+
+#[tokio::test]
+#[ignore = "Requires a Postgres database at MOUCHAK_TEST_DATABASE_URL"]
+async fn test_replica_reads_see_writes_just_made() {
+    // The primary standing in for its own replica, always caught up
+    let pc = setup_with_replicas(|url| vec![url.to_string()]).await;
+    let project_id = ProjectBmc::create(&pc.ctx, &pc.mm, "pg-replica", "/pg-replica")
+        .await
+        .unwrap();
+    let alice = create_agent(&pc, project_id, "alice").await;
+    let bob = create_agent(&pc, project_id, "bob").await;
+
+    for (sent, subject) in ["First", "Second"].into_iter().enumerate() {
+        MessageBmc::create(&pc.ctx, &pc.mm, message(project_id, alice, bob, subject))
+            .await
+            .unwrap();
+        let inbox = MessageBmc::list_inbox_for_agent(&pc.ctx, &pc.mm, project_id, bob, 10)
+            .await
+            .unwrap();
+        assert_eq!(inbox.len(), sent + 1);
+        assert!(inbox.iter().any(|m| m.subject == subject));
+    }
+    let found = MessageBmc::search(&pc.ctx, &pc.mm, project_id, "second", 10)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "Requires a Postgres database at MOUCHAK_TEST_DATABASE_URL"]
+async fn test_unreachable_replica_falls_back_to_the_primary() {
+    let pc = setup_with_replicas(|_| {
+        vec!["postgres://mouchak@127.0.0.1:1/mouchak?sslmode=disable".to_string()]
+    })
+    .await;
+    let project_id = ProjectBmc::create(&pc.ctx, &pc.mm, "pg-down", "/pg-down")
+        .await
+        .unwrap();
+    let alice = create_agent(&pc, project_id, "alice").await;
+    let bob = create_agent(&pc, project_id, "bob").await;
+    MessageBmc::create(&pc.ctx, &pc.mm, message(project_id, alice, bob, "Hello"))
+        .await
+        .unwrap();
+
+    let inbox = MessageBmc::list_inbox_for_agent(&pc.ctx, &pc.mm, project_id, bob, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+    let threads = MessageBmc::list_threads(&pc.ctx, &pc.mm, project_id, 10, false)
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 1);
+}
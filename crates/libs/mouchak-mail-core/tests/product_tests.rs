@@ -15,6 +15,7 @@ mod common;
 use crate::common::TestContext;
 use mouchak_mail_core::model::product::ProductBmc;
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::ProjectId;
 
 /// Test creating a new product via ensure
 #[tokio::test]
@@ -138,7 +139,7 @@ async fn test_link_unlink_project() {
         .unwrap();
 
     // Link project to product
-    let link_id = ProductBmc::link_project(&tc.ctx, &tc.mm, product.id, project_id.get())
+    let link_id = ProductBmc::link_project(&tc.ctx, &tc.mm, product.id, project_id)
         .await
         .expect("Failed to link project");
     assert!(link_id > 0);
@@ -150,7 +151,7 @@ async fn test_link_unlink_project() {
     assert!(linked_projects.contains(&project_id.get()));
 
     // Unlink project
-    let unlinked = ProductBmc::unlink_project(&tc.ctx, &tc.mm, product.id, project_id.get())
+    let unlinked = ProductBmc::unlink_project(&tc.ctx, &tc.mm, product.id, project_id)
         .await
         .expect("Failed to unlink project");
     assert!(unlinked, "Should return true for successful unlink");
@@ -174,7 +175,7 @@ async fn test_unlink_nonexistent_returns_false() {
         .unwrap();
 
     // Try to unlink a project that was never linked
-    let result = ProductBmc::unlink_project(&tc.ctx, &tc.mm, product.id, 99999)
+    let result = ProductBmc::unlink_project(&tc.ctx, &tc.mm, product.id, ProjectId::new(99999))
         .await
         .expect("Unlink should not error");
 
@@ -202,15 +203,15 @@ async fn test_list_for_project() {
         .await
         .unwrap();
 
-    ProductBmc::link_project(&tc.ctx, &tc.mm, product1.id, project_id.get())
+    ProductBmc::link_project(&tc.ctx, &tc.mm, product1.id, project_id)
         .await
         .unwrap();
-    ProductBmc::link_project(&tc.ctx, &tc.mm, product2.id, project_id.get())
+    ProductBmc::link_project(&tc.ctx, &tc.mm, product2.id, project_id)
         .await
         .unwrap();
 
     // List products for this project
-    let products = ProductBmc::list_for_project(&tc.ctx, &tc.mm, project_id.get())
+    let products = ProductBmc::list_for_project(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list products for project");
 
@@ -230,7 +231,7 @@ async fn test_list_for_project_empty() {
         .await
         .unwrap();
 
-    let products = ProductBmc::list_for_project(&tc.ctx, &tc.mm, project_id.get())
+    let products = ProductBmc::list_for_project(&tc.ctx, &tc.mm, project_id)
         .await
         .expect("Failed to list products for project");
 
@@ -252,10 +253,10 @@ async fn test_link_project_idempotent() {
         .unwrap();
 
     // Link twice
-    let link1 = ProductBmc::link_project(&tc.ctx, &tc.mm, product.id, project_id.get())
+    let link1 = ProductBmc::link_project(&tc.ctx, &tc.mm, product.id, project_id)
         .await
         .unwrap();
-    let link2 = ProductBmc::link_project(&tc.ctx, &tc.mm, product.id, project_id.get())
+    let link2 = ProductBmc::link_project(&tc.ctx, &tc.mm, product.id, project_id)
         .await
         .unwrap();
 
@@ -295,10 +296,10 @@ async fn test_sibling_logic() {
         .unwrap();
 
     // 3. Link A and B to P
-    ProductBmc::link_project(&tc.ctx, &tc.mm, product_p.id, id_a.get())
+    ProductBmc::link_project(&tc.ctx, &tc.mm, product_p.id, id_a)
         .await
         .unwrap();
-    ProductBmc::link_project(&tc.ctx, &tc.mm, product_p.id, id_b.get())
+    ProductBmc::link_project(&tc.ctx, &tc.mm, product_p.id, id_b)
         .await
         .unwrap();
 
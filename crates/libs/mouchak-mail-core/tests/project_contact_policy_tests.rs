@@ -0,0 +1,245 @@
+//! Project contact policy tests
+//!
+//! Tests for project-level allow/deny rules on cross-project contact.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::agent_link::{AgentLinkBmc, AgentLinkForCreate};
+use mouchak_mail_core::model::product::ProductBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::project_contact_policy::{
+    ANY_PROJECT, ContactRuleAction, ContactRuleTarget, ProjectContactPolicyBmc,
+    ProjectContactRuleForCreate,
+};
+use mouchak_mail_core::types::ProjectId;
+
+struct Projects {
+    tc: TestContext,
+    frontend: ProjectId,
+    backend: ProjectId,
+}
+
+async fn setup() -> Projects {
+    let tc = TestContext::new().await.unwrap();
+    let frontend = ProjectBmc::create(&tc.ctx, &tc.mm, "frontend", "/frontend")
+        .await
+        .unwrap();
+    let backend = ProjectBmc::create(&tc.ctx, &tc.mm, "backend", "/backend")
+        .await
+        .unwrap();
+    Projects {
+        tc,
+        frontend,
+        backend,
+    }
+}
+
+impl Projects {
+    async fn rule(
+        &self,
+        project_id: ProjectId,
+        action: ContactRuleAction,
+        target_kind: ContactRuleTarget,
+        target: &str,
+    ) -> i64 {
+        ProjectContactPolicyBmc::set_rule(
+            &self.tc.ctx,
+            &self.tc.mm,
+            ProjectContactRuleForCreate {
+                project_id,
+                action,
+                target_kind,
+                target: target.to_string(),
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn frontend_may_contact_backend(&self) -> bool {
+        match ProjectContactPolicyBmc::check(&self.tc.ctx, &self.tc.mm, self.frontend, self.backend)
+            .await
+        {
+            Ok(()) => true,
+            Err(Error::ContactDenied(_)) => false,
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_cross_project_contact_is_denied_by_default() {
+    let projects = setup().await;
+    assert!(!projects.frontend_may_contact_backend().await);
+
+    // Contact within a project needs no rule
+    ProjectContactPolicyBmc::check(
+        &projects.tc.ctx,
+        &projects.tc.mm,
+        projects.frontend,
+        projects.frontend,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_allow_by_slug_and_product() {
+    let projects = setup().await;
+
+    let rule_id = projects
+        .rule(
+            projects.backend,
+            ContactRuleAction::Allow,
+            ContactRuleTarget::Project,
+            "frontend",
+        )
+        .await;
+    assert!(projects.frontend_may_contact_backend().await);
+    ProjectContactPolicyBmc::delete_rule(
+        &projects.tc.ctx,
+        &projects.tc.mm,
+        projects.backend,
+        rule_id,
+    )
+    .await
+    .unwrap();
+    assert!(!projects.frontend_may_contact_backend().await);
+
+    let product = ProductBmc::ensure(&projects.tc.ctx, &projects.tc.mm, "shop", "Shop")
+        .await
+        .unwrap();
+    ProductBmc::link_project(
+        &projects.tc.ctx,
+        &projects.tc.mm,
+        product.id,
+        projects.frontend,
+    )
+    .await
+    .unwrap();
+    projects
+        .rule(
+            projects.backend,
+            ContactRuleAction::Allow,
+            ContactRuleTarget::Product,
+            "shop",
+        )
+        .await;
+    assert!(projects.frontend_may_contact_backend().await);
+
+    let rules =
+        ProjectContactPolicyBmc::list_rules(&projects.tc.ctx, &projects.tc.mm, projects.backend)
+            .await
+            .unwrap();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].target_kind, ContactRuleTarget::Product);
+}
+
+#[tokio::test]
+async fn test_deny_wins_over_allow() {
+    let projects = setup().await;
+    projects
+        .rule(
+            projects.backend,
+            ContactRuleAction::Allow,
+            ContactRuleTarget::Project,
+            ANY_PROJECT,
+        )
+        .await;
+    assert!(projects.frontend_may_contact_backend().await);
+
+    let rule_id = projects
+        .rule(
+            projects.backend,
+            ContactRuleAction::Deny,
+            ContactRuleTarget::Project,
+            "frontend",
+        )
+        .await;
+    assert!(!projects.frontend_may_contact_backend().await);
+
+    // Setting the same target again changes the rule in place
+    let same_id = projects
+        .rule(
+            projects.backend,
+            ContactRuleAction::Allow,
+            ContactRuleTarget::Project,
+            "frontend",
+        )
+        .await;
+    assert_eq!(same_id, rule_id);
+    assert!(projects.frontend_may_contact_backend().await);
+}
+
+#[tokio::test]
+async fn test_request_contact_enforces_policy() {
+    let projects = setup().await;
+    let ctx = &projects.tc.ctx;
+    let mm = &projects.tc.mm;
+    let mut agents = Vec::new();
+    for (project_id, name) in [(projects.frontend, "ui"), (projects.backend, "api")] {
+        let id = AgentBmc::create(
+            ctx,
+            mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id);
+    }
+    let link_c = AgentLinkForCreate {
+        a_project_id: projects.frontend.get(),
+        a_agent_id: agents[0].get(),
+        b_project_id: projects.backend.get(),
+        b_agent_id: agents[1].get(),
+        reason: "API questions".to_string(),
+    };
+
+    let err = AgentLinkBmc::request_contact(ctx, mm, link_c.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::ContactDenied(_)));
+
+    projects
+        .rule(
+            projects.backend,
+            ContactRuleAction::Allow,
+            ContactRuleTarget::Project,
+            "frontend",
+        )
+        .await;
+    AgentLinkBmc::request_contact(ctx, mm, link_c)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_product_wildcard_is_rejected() {
+    let projects = setup().await;
+    let err = ProjectContactPolicyBmc::set_rule(
+        &projects.tc.ctx,
+        &projects.tc.mm,
+        ProjectContactRuleForCreate {
+            project_id: projects.backend,
+            action: ContactRuleAction::Allow,
+            target_kind: ContactRuleTarget::Product,
+            target: ANY_PROJECT.to_string(),
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+}
@@ -0,0 +1,263 @@
+//! Project settings tests
+//!
+//! Tests for per-project time zones and business hours, and their effect on
+//! quiet hours and escalation.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::{Datelike, Duration, NaiveTime, Utc, Weekday};
+use mouchak_mail_core::model::escalation::{EscalationBmc, EscalationMode};
+use mouchak_mail_core::model::focus_window::FocusWindowBmc;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::project_settings::{
+    DEFAULT_BUSINESS_DAYS, ProjectSettingsBmc, ProjectSettingsForUpdate,
+};
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+fn message(
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    importance: &str,
+) -> MessageForCreate {
+    MessageForCreate {
+        project_id,
+        sender_id: from,
+        recipient_ids: vec![to],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: format!("{} update", importance),
+        body_md: "body".to_string(),
+        thread_id: None,
+        importance: Some(importance.to_string()),
+        ack_required: true,
+    }
+}
+
+/// Business hours in UTC on tomorrow's weekday only, so now is after hours.
+fn closed_today() -> (ProjectSettingsForUpdate, Weekday) {
+    let tomorrow = (Utc::now() + Duration::days(1)).weekday();
+    let settings_u = ProjectSettingsForUpdate {
+        timezone: Some("UTC".to_string()),
+        business_hours: Some("09:00-17:00".to_string()),
+        business_days: Some(tomorrow.to_string()),
+        quiet_hours: Some(true),
+        locale: None,
+        email_bridge: None,
+        email_recipients: None,
+    };
+    (settings_u, tomorrow)
+}
+
+#[tokio::test]
+async fn test_settings_default_and_update() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "tz", "/tz")
+        .await
+        .unwrap();
+
+    let settings = ProjectSettingsBmc::get(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap();
+    assert_eq!(settings.timezone, "UTC");
+    assert!(settings.business_hours.is_none());
+    assert!(settings.updated_ts.is_none());
+
+    let settings = ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        ProjectSettingsForUpdate {
+            timezone: Some("Asia/Kolkata".to_string()),
+            business_hours: Some("09:30-18:00".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(settings.timezone, "Asia/Kolkata");
+    let hours = settings.business_hours.clone().unwrap();
+    assert_eq!(hours.start, NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+    assert_eq!(hours.end, NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+    assert_eq!(hours.days, DEFAULT_BUSINESS_DAYS);
+    assert!(settings.updated_ts.is_some());
+
+    // Partial updates keep the other fields
+    let settings = ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        ProjectSettingsForUpdate {
+            business_days: Some("sun-thu".to_string()),
+            quiet_hours: Some(true),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(settings.timezone, "Asia/Kolkata");
+    assert!(settings.quiet_hours);
+    let hours = settings.business_hours.clone().unwrap();
+    assert_eq!(hours.start, NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+    assert_eq!(hours.days.len(), 5);
+    assert!(hours.days.contains(&Weekday::Sun));
+
+    // Invalid input is rejected and changes nothing
+    for settings_u in [
+        ProjectSettingsForUpdate {
+            timezone: Some("Mars/Olympus".to_string()),
+            ..Default::default()
+        },
+        ProjectSettingsForUpdate {
+            business_hours: Some("18:00-09:00".to_string()),
+            ..Default::default()
+        },
+    ] {
+        let err = ProjectSettingsBmc::update(&tc.ctx, &tc.mm, project_id, settings_u)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, mouchak_mail_core::Error::InvalidInput(_)));
+    }
+    assert_eq!(
+        ProjectSettingsBmc::get(&tc.ctx, &tc.mm, project_id)
+            .await
+            .unwrap(),
+        settings
+    );
+
+    // An empty string removes business hours
+    let settings = ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        ProjectSettingsForUpdate {
+            business_hours: Some(String::new()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(settings.business_hours.is_none());
+    assert!(settings.is_business_time(Utc::now().naive_utc()));
+}
+
+#[tokio::test]
+async fn test_quiet_hours_hold_mail_until_next_working_day() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "quiet", "/quiet")
+        .await
+        .unwrap();
+    let sender = create_agent(&tc, project_id, "sender").await;
+    let reader = create_agent(&tc, project_id, "reader").await;
+
+    // Without settings mail is delivered at any hour
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        message(project_id, sender, reader, "normal"),
+    )
+    .await
+    .unwrap();
+    assert!(
+        FocusWindowBmc::get_active(&tc.ctx, &tc.mm, project_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let (settings_u, tomorrow) = closed_today();
+    ProjectSettingsBmc::update(&tc.ctx, &tc.mm, project_id, settings_u)
+        .await
+        .unwrap();
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        message(project_id, sender, reader, "normal"),
+    )
+    .await
+    .unwrap();
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        message(project_id, sender, reader, "urgent"),
+    )
+    .await
+    .unwrap();
+
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, reader, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 2);
+    assert!(inbox.iter().any(|m| m.importance == "urgent"));
+
+    // One window holds after-hours mail until 09:00 on the next working day
+    let window = FocusWindowBmc::get_active(&tc.ctx, &tc.mm, project_id)
+        .await
+        .unwrap()
+        .expect("quiet hours window");
+    assert!(window.reason.starts_with("Quiet hours"));
+    assert_eq!(window.ends_ts.weekday(), tomorrow);
+    assert_eq!(
+        window.ends_ts.time(),
+        NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+    );
+    assert_eq!(
+        FocusWindowBmc::count_deferred(&tc.ctx, &tc.mm, window.id)
+            .await
+            .unwrap(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_escalation_waits_for_business_hours() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "escalate", "/escalate")
+        .await
+        .unwrap();
+    let sender = create_agent(&tc, project_id, "sender").await;
+    let reader = create_agent(&tc, project_id, "reader").await;
+    MessageBmc::create(&tc.ctx, &tc.mm, message(project_id, sender, reader, "high"))
+        .await
+        .unwrap();
+    tc.mm
+        .db_for_test()
+        .execute(
+            "UPDATE messages SET created_ts = datetime('now', '-2 hours')",
+            (),
+        )
+        .await
+        .unwrap();
+
+    let (mut settings_u, _) = closed_today();
+    settings_u.quiet_hours = Some(false);
+    ProjectSettingsBmc::update(&tc.ctx, &tc.mm, project_id, settings_u)
+        .await
+        .unwrap();
+    let results = EscalationBmc::escalate_overdue(&tc.ctx, &tc.mm, 1, EscalationMode::Log, true)
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+
+    ProjectSettingsBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        ProjectSettingsForUpdate {
+            business_hours: Some(String::new()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let results = EscalationBmc::escalate_overdue(&tc.ctx, &tc.mm, 1, EscalationMode::Log, true)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+}
@@ -17,6 +17,7 @@ use mouchak_mail_core::model::project::ProjectBmc;
 use mouchak_mail_core::model::project_sibling_suggestion::{
     ProjectSiblingSuggestionBmc, ProjectSiblingSuggestionForCreate,
 };
+use mouchak_mail_core::types::ProjectId;
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to create two projects for suggestion tests
@@ -85,9 +86,10 @@ async fn test_list_sibling_suggestions() {
         .expect("Failed to create suggestion");
 
     // List from project A's perspective
-    let suggestions = ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, project_a_id)
-        .await
-        .expect("Failed to list suggestions");
+    let suggestions =
+        ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, ProjectId::new(project_a_id))
+            .await
+            .expect("Failed to list suggestions");
 
     assert_eq!(suggestions.len(), 1, "Should have 1 suggestion");
     assert_eq!(suggestions[0].project_a_id, project_a_id);
@@ -95,9 +97,10 @@ async fn test_list_sibling_suggestions() {
     assert_eq!(suggestions[0].status, "pending");
 
     // List from project B's perspective (should find same suggestion)
-    let suggestions_b = ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, project_b_id)
-        .await
-        .expect("Failed to list suggestions for B");
+    let suggestions_b =
+        ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, ProjectId::new(project_b_id))
+            .await
+            .expect("Failed to list suggestions for B");
 
     assert_eq!(
         suggestions_b.len(),
@@ -132,9 +135,10 @@ async fn test_accept_sibling_suggestion() {
         .expect("Failed to accept suggestion");
 
     // Verify it's no longer in pending list
-    let suggestions = ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, project_a_id)
-        .await
-        .expect("Failed to list suggestions");
+    let suggestions =
+        ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, ProjectId::new(project_a_id))
+            .await
+            .expect("Failed to list suggestions");
 
     assert!(
         suggestions.is_empty(),
@@ -168,9 +172,10 @@ async fn test_dismiss_sibling_suggestion() {
         .expect("Failed to dismiss suggestion");
 
     // Verify it's no longer in pending list
-    let suggestions = ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, project_a_id)
-        .await
-        .expect("Failed to list suggestions");
+    let suggestions =
+        ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, ProjectId::new(project_a_id))
+            .await
+            .expect("Failed to list suggestions");
 
     assert!(
         suggestions.is_empty(),
@@ -193,9 +198,10 @@ async fn test_empty_suggestions() {
         .expect("Failed to create project")
         .get();
 
-    let suggestions = ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, project_id)
-        .await
-        .expect("Failed to list suggestions");
+    let suggestions =
+        ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, ProjectId::new(project_id))
+            .await
+            .expect("Failed to list suggestions");
 
     assert!(
         suggestions.is_empty(),
@@ -254,9 +260,10 @@ async fn test_suggestions_sorted_by_score() {
         .expect("Failed to create high suggestion");
 
     // List and verify sorting (highest score first)
-    let suggestions = ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, project_a_id)
-        .await
-        .expect("Failed to list suggestions");
+    let suggestions =
+        ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, ProjectId::new(project_a_id))
+            .await
+            .expect("Failed to list suggestions");
 
     assert_eq!(suggestions.len(), 2, "Should have 2 suggestions");
     assert!(
@@ -287,9 +294,10 @@ async fn test_suggestion_structure() {
         .await
         .expect("Failed to create suggestion");
 
-    let suggestions = ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, project_a_id)
-        .await
-        .expect("Failed to list suggestions");
+    let suggestions =
+        ProjectSiblingSuggestionBmc::list(&tc.ctx, &tc.mm, ProjectId::new(project_a_id))
+            .await
+            .expect("Failed to list suggestions");
 
     let suggestion = &suggestions[0];
     assert_eq!(suggestion.id, suggestion_id);
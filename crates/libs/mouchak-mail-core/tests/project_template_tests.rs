@@ -0,0 +1,130 @@
+//! Project template tests
+//!
+//! Tests for loading template bundles from a directory and creating
+//! projects with their agents, macros, labels, settings, SLAs and
+//! welcome messages.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::macro_def::MacroDefBmc;
+use mouchak_mail_core::model::message::MessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::project_settings::ProjectSettingsBmc;
+use mouchak_mail_core::model::project_template::{ProjectTemplate, ProjectTemplateBmc};
+use mouchak_mail_core::model::sla::SlaBmc;
+use tempfile::TempDir;
+
+const TEMPLATE: &str = r#"{
+    "version": 1,
+    "description": "Planner and reviewer pair",
+    "agents": [
+        { "name": "planner", "program": "claude-code", "model": "opus",
+          "capabilities": ["code-review"] },
+        { "name": "reviewer", "program": "codex", "model": "gpt-5" }
+    ],
+    "contacts": [{ "from": "planner", "to": "reviewer", "reason": "reviews plans" }],
+    "macros": [{ "name": "handoff", "description": "Hand off a task", "steps": [] }],
+    "labels": ["blocked"],
+    "settings": { "timezone": "Europe/Berlin" },
+    "slas": { "urgent": 3600 },
+    "welcome_messages": [
+        { "subject": "Welcome", "body_md": "Planner drives, reviewer checks." },
+        { "from": "planner", "to": ["reviewer"], "subject": "First plan is up" }
+    ]
+}"#;
+
+fn template_dir() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("pair.json"), TEMPLATE).unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "not a template").unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn test_project_from_template() {
+    let tc = TestContext::new().await.unwrap();
+    let dir = template_dir();
+    assert_eq!(ProjectTemplateBmc::list_in(dir.path()).unwrap(), ["pair"]);
+    let template = ProjectTemplateBmc::load_from(dir.path(), "pair").unwrap();
+
+    let summary =
+        ProjectTemplateBmc::instantiate(&tc.ctx, &tc.mm, "pair", &template, "tpl", "/tpl")
+            .await
+            .unwrap();
+    assert_eq!(summary.team.agents_created, ["planner", "reviewer"]);
+    assert_eq!(summary.team.contacts_created, ["planner -> reviewer"]);
+    assert_eq!(summary.macros_created, ["handoff"]);
+    assert!(summary.settings_applied);
+    assert_eq!(summary.slas_set, ["urgent"]);
+    assert_eq!(summary.welcome_messages_sent.len(), 2);
+    assert_eq!(summary.labels_created, ["blocked"]);
+    assert!(summary.skipped.is_empty());
+
+    let project = ProjectBmc::get_by_slug(&tc.ctx, &tc.mm, "tpl")
+        .await
+        .unwrap();
+    assert_eq!(project.id, summary.project_id);
+    assert!(
+        MacroDefBmc::get_by_name(&tc.ctx, &tc.mm, project.id, "handoff")
+            .await
+            .is_ok()
+    );
+    let settings = ProjectSettingsBmc::get(&tc.ctx, &tc.mm, project.id)
+        .await
+        .unwrap();
+    assert_eq!(settings.timezone, "Europe/Berlin");
+    let slas = SlaBmc::list_slas(&tc.ctx, &tc.mm, project.id)
+        .await
+        .unwrap();
+    assert_eq!(slas.len(), 1);
+    assert_eq!(slas[0].ack_within_seconds, 3600);
+
+    let reviewer = AgentBmc::get_by_name(&tc.ctx, &tc.mm, project.id, "reviewer")
+        .await
+        .unwrap();
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project.id, reviewer.id, 10)
+        .await
+        .unwrap();
+    let mut senders: Vec<&str> = inbox.iter().map(|m| m.sender_name.as_str()).collect();
+    senders.sort();
+    assert_eq!(senders, ["planner", "welcome"]);
+
+    // Templates always create a new project
+    let again =
+        ProjectTemplateBmc::instantiate(&tc.ctx, &tc.mm, "pair", &template, "tpl", "/tpl").await;
+    assert!(again.is_err());
+}
+
+#[tokio::test]
+async fn test_bad_templates_create_nothing() {
+    let tc = TestContext::new().await.unwrap();
+    let dir = template_dir();
+
+    for name in ["../pair", ""] {
+        let result = ProjectTemplateBmc::load_from(dir.path(), name);
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+    }
+    match ProjectTemplateBmc::load_from(dir.path(), "missing") {
+        Err(Error::InvalidInput(msg)) => assert!(msg.contains("available: pair")),
+        other => panic!("expected InvalidInput, got {:?}", other.map(|t| t.version)),
+    }
+
+    let mut template: ProjectTemplate = serde_json::from_str(TEMPLATE).unwrap();
+    template.welcome_messages[1].to = vec!["nobody".to_string()];
+    let result =
+        ProjectTemplateBmc::instantiate(&tc.ctx, &tc.mm, "pair", &template, "tpl-bad", "/tpl-bad")
+            .await;
+    assert!(matches!(result, Err(Error::InvalidInput(_))));
+    assert!(
+        ProjectBmc::get_by_slug(&tc.ctx, &tc.mm, "tpl-bad")
+            .await
+            .is_err()
+    );
+}
@@ -14,6 +14,7 @@ mod common;
 
 use crate::common::TestContext;
 use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::listing::{ListQuery, ListSort};
 use mouchak_mail_core::model::project::ProjectBmc;
 use mouchak_mail_core::utils::slugify;
 
@@ -100,6 +101,93 @@ async fn test_list_all_projects() {
     assert_eq!(projects.len(), 3, "Should have 3 projects");
 }
 
+/// Test paging, filtering and sorting projects
+#[tokio::test]
+async fn test_list_projects_page() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let mut ids = Vec::new();
+    for name in &["alpha", "beta", "gamma"] {
+        let human_key = format!("/page/{}", name);
+        let slug = slugify(&human_key);
+        ids.push(
+            ProjectBmc::create(&tc.ctx, &tc.mm, &slug, &human_key)
+                .await
+                .unwrap(),
+        );
+    }
+    // Only beta has an agent, running on codex
+    AgentBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentForCreate {
+            project_id: ids[1],
+            name: "BlueHarbor".to_string(),
+            program: "codex".to_string(),
+            model: "gpt-5".to_string(),
+            task_description: "Paging test".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let query = ListQuery {
+        sort: Some(ListSort::Name),
+        limit: Some(2),
+        ..Default::default()
+    };
+    let first = ProjectBmc::list_page(&tc.ctx, &tc.mm, &query)
+        .await
+        .unwrap();
+    assert_eq!(first.total, 3);
+    let slugs: Vec<_> = first.items.iter().map(|p| p.slug.as_str()).collect();
+    assert_eq!(slugs, ["page-alpha", "page-beta"]);
+
+    let query = ListQuery {
+        cursor: first.next_cursor,
+        ..query
+    };
+    let second = ProjectBmc::list_page(&tc.ctx, &tc.mm, &query)
+        .await
+        .unwrap();
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.items[0].slug, "page-gamma");
+    assert!(second.next_cursor.is_none());
+
+    // Projects with activity sort before those without agents
+    let query = ListQuery {
+        sort: Some(ListSort::LastActive),
+        ..Default::default()
+    };
+    let page = ProjectBmc::list_page(&tc.ctx, &tc.mm, &query)
+        .await
+        .unwrap();
+    assert_eq!(page.items[0].id, ids[1]);
+
+    let query = ListQuery {
+        program: Some("codex".to_string()),
+        active_within: Some("1h".to_string()),
+        ..Default::default()
+    };
+    let page = ProjectBmc::list_page(&tc.ctx, &tc.mm, &query)
+        .await
+        .unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0].id, ids[1]);
+
+    let query = ListQuery {
+        model: Some("opus".to_string()),
+        ..Default::default()
+    };
+    let page = ProjectBmc::list_page(&tc.ctx, &tc.mm, &query)
+        .await
+        .unwrap();
+    assert_eq!(page.total, 0);
+    assert!(page.items.is_empty());
+}
+
 /// Test project not found error
 #[tokio::test]
 async fn test_project_not_found() {
@@ -141,9 +229,9 @@ async fn test_adopt_project() {
 
     // 3. Add a Message to Source
     let msg_c = mouchak_mail_core::model::message::MessageForCreate {
-        project_id: src_id.get(),
-        sender_id: agent_id.into(),
-        recipient_ids: vec![agent_id.into()],
+        project_id: src_id,
+        sender_id: agent_id,
+        recipient_ids: vec![agent_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Test adopt".into(),
@@ -211,9 +299,9 @@ async fn test_delete_project_cascade() {
         .expect("Failed to create agent");
 
     let msg = mouchak_mail_core::model::message::MessageForCreate {
-        project_id: project_id.get(),
-        sender_id: agent_id.into(),
-        recipient_ids: vec![agent_id.into()],
+        project_id,
+        sender_id: agent_id,
+        recipient_ids: vec![agent_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Doomed message".into(),
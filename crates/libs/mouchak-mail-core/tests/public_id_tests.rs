@@ -0,0 +1,103 @@
+//! Public id tests
+//!
+//! Tests that projects, agents and messages get distinct ULID public ids on
+//! insert and that public ids resolve back to their records.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::public_id::{PublicEntity, PublicIdBmc};
+use mouchak_mail_core::utils::ulid::is_ulid;
+
+#[tokio::test]
+async fn test_records_carry_public_ids() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "public", "/public")
+        .await
+        .unwrap();
+    let agent_id = AgentBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentForCreate {
+            project_id,
+            name: "alice".to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: String::new(),
+        },
+    )
+    .await
+    .unwrap();
+    let message_id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: agent_id,
+            recipient_ids: vec![agent_id],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Hello".to_string(),
+            body_md: "Body".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let project = ProjectBmc::get(&tc.ctx, &tc.mm, project_id).await.unwrap();
+    let agent = AgentBmc::get(&tc.ctx, &tc.mm, agent_id).await.unwrap();
+    let message = MessageBmc::get(&tc.ctx, &tc.mm, message_id).await.unwrap();
+    for public_id in [&project.public_id, &agent.public_id, &message.public_id] {
+        assert!(is_ulid(public_id), "not a ULID: {public_id}");
+    }
+    assert_ne!(project.public_id, agent.public_id);
+    assert_ne!(agent.public_id, message.public_id);
+
+    // List queries carry the same ids
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, agent_id, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox[0].public_id, message.public_id);
+    assert_eq!(
+        PublicIdBmc::get(&tc.ctx, &tc.mm, PublicEntity::Agent, agent_id.get())
+            .await
+            .unwrap(),
+        agent.public_id
+    );
+
+    let resolved = PublicIdBmc::resolve(&tc.ctx, &tc.mm, &message.public_id)
+        .await
+        .unwrap();
+    assert_eq!(resolved.entity, PublicEntity::Message);
+    assert_eq!(resolved.id, message_id.get());
+    assert_eq!(resolved.project_id, project_id.get());
+
+    // Lowercase ids are accepted
+    let resolved = PublicIdBmc::resolve(&tc.ctx, &tc.mm, &agent.public_id.to_lowercase())
+        .await
+        .unwrap();
+    assert_eq!(resolved.entity, PublicEntity::Agent);
+    assert_eq!(resolved.id, agent_id.get());
+}
+
+#[tokio::test]
+async fn test_resolve_rejects_unknown_ids() {
+    let tc = TestContext::new().await.unwrap();
+
+    let invalid = PublicIdBmc::resolve(&tc.ctx, &tc.mm, "42").await;
+    assert!(matches!(invalid, Err(Error::InvalidInput(_))));
+
+    let unknown = PublicIdBmc::resolve(&tc.ctx, &tc.mm, "01HZX3T6Q2V8W4K9M5N7P0R1S2").await;
+    assert!(matches!(unknown, Err(Error::NotFound)));
+}
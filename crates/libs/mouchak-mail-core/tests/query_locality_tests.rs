@@ -49,8 +49,8 @@ async fn setup_data(tc: &TestContext) -> (ProjectId, i64) {
             &tc.ctx,
             &tc.mm,
             MessageForCreate {
-                project_id: p_id.into(),
-                sender_id: a_id_i64,
+                project_id: p_id,
+                sender_id: a_id_i64.into(),
                 recipient_ids: vec![],
                 cc_ids: None,
                 bcc_ids: None,
@@ -1,6 +1,7 @@
 //! Quota enforcement tests
 //!
-//! Tests for quota limits on attachments and inbox messages.
+//! Tests for quota limits on attachments, inbox and sent messages, and for
+//! storage usage reports.
 
 // Tests are allowed to use unwrap()/expect() for clearer failure messages
 #![allow(
@@ -18,7 +19,8 @@ use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::attachment::{AttachmentBmc, AttachmentForCreate};
 use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
-use mouchak_mail_core::types::ProjectId;
+use mouchak_mail_core::model::usage::UsageBmc;
+use mouchak_mail_core::types::{AgentId, ProjectId};
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to set up a project
@@ -112,9 +114,9 @@ async fn test_quota_inbox_limit_exceeded() {
 
     // 1. Send first message
     let msg1 = MessageForCreate {
-        project_id: pid.into(),
-        sender_id: aid_sender,
-        recipient_ids: vec![aid_recipient],
+        project_id: pid,
+        sender_id: aid_sender.into(),
+        recipient_ids: vec![AgentId::new(aid_recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Msg 1".into(),
@@ -129,9 +131,9 @@ async fn test_quota_inbox_limit_exceeded() {
 
     // 2. Send second message (Limit is 2, current count is 1. 1 >= 2 is False. Succeeds)
     let msg2 = MessageForCreate {
-        project_id: pid.into(),
-        sender_id: aid_sender,
-        recipient_ids: vec![aid_recipient],
+        project_id: pid,
+        sender_id: aid_sender.into(),
+        recipient_ids: vec![AgentId::new(aid_recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Msg 2".into(),
@@ -146,9 +148,9 @@ async fn test_quota_inbox_limit_exceeded() {
 
     // 3. Send third message (Current count is 2. 2 >= 2 is True. Fails)
     let msg3 = MessageForCreate {
-        project_id: pid.into(),
-        sender_id: aid_sender,
-        recipient_ids: vec![aid_recipient],
+        project_id: pid,
+        sender_id: aid_sender.into(),
+        recipient_ids: vec![AgentId::new(aid_recipient)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Msg 3".into(),
@@ -166,3 +168,137 @@ async fn test_quota_inbox_limit_exceeded() {
         _ => panic!("Expected QuotaExceeded error, got {:?}", res),
     }
 }
+
+fn message(
+    project_id: ProjectId,
+    sender_id: i64,
+    recipient_id: i64,
+    body: &str,
+) -> MessageForCreate {
+    MessageForCreate {
+        project_id,
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Status".into(),
+        body_md: body.into(),
+        thread_id: None,
+        importance: None,
+        ack_required: false,
+    }
+}
+
+fn attachment(
+    project_id: ProjectId,
+    agent_id: Option<i64>,
+    size_bytes: i64,
+) -> AttachmentForCreate {
+    AttachmentForCreate {
+        project_id: project_id.into(),
+        agent_id,
+        filename: "log.txt".into(),
+        stored_path: "/tmp/log.txt".into(),
+        media_type: "text/plain".into(),
+        size_bytes,
+    }
+}
+
+#[tokio::test]
+async fn test_quota_agent_limits_exceeded() {
+    let mut config = AppConfig::default();
+    config.quota.enabled = true;
+    config.quota.agent_messages_limit_count = 2;
+    config.quota.agent_attachments_limit_bytes = 1000;
+
+    let tc = TestContext::new_with_config(config)
+        .await
+        .expect("Failed to create test context");
+
+    let (pid, p_slug) = setup_project(&tc).await;
+    let (runaway, _) = setup_agent(&tc, &p_slug, "runaway").await;
+    let (other, _) = setup_agent(&tc, &p_slug, "other").await;
+
+    for body in ["one", "two"] {
+        MessageBmc::create(&tc.ctx, &tc.mm, message(pid, runaway, other, body))
+            .await
+            .expect("Should succeed");
+    }
+    let res = MessageBmc::create(&tc.ctx, &tc.mm, message(pid, runaway, other, "three")).await;
+    match res {
+        Err(mouchak_mail_core::Error::QuotaExceeded(msg)) => {
+            assert!(msg.contains("'runaway' has sent 2 messages"), "{msg}");
+        }
+        _ => panic!("Expected QuotaExceeded error, got {:?}", res),
+    }
+    // The limit is per agent
+    MessageBmc::create(&tc.ctx, &tc.mm, message(pid, other, runaway, "reply"))
+        .await
+        .expect("Should succeed");
+
+    AttachmentBmc::create(&tc.ctx, &tc.mm, attachment(pid, Some(runaway), 800))
+        .await
+        .expect("Should succeed");
+    let res = AttachmentBmc::create(&tc.ctx, &tc.mm, attachment(pid, Some(runaway), 300)).await;
+    assert!(
+        matches!(res, Err(mouchak_mail_core::Error::QuotaExceeded(_))),
+        "Expected QuotaExceeded error, got {:?}",
+        res
+    );
+    AttachmentBmc::create(&tc.ctx, &tc.mm, attachment(pid, Some(other), 300))
+        .await
+        .expect("Should succeed");
+}
+
+#[tokio::test]
+async fn test_usage_report() {
+    let tc = TestContext::new()
+        .await
+        .expect("Failed to create test context");
+
+    let (pid, p_slug) = setup_project(&tc).await;
+    let (alice, _) = setup_agent(&tc, &p_slug, "alice").await;
+    let (bob, _) = setup_agent(&tc, &p_slug, "bob").await;
+
+    MessageBmc::create(&tc.ctx, &tc.mm, message(pid, alice, bob, "héllo"))
+        .await
+        .unwrap();
+    MessageBmc::create(&tc.ctx, &tc.mm, message(pid, bob, alice, "hi"))
+        .await
+        .unwrap();
+    MessageBmc::create(&tc.ctx, &tc.mm, message(pid, bob, alice, "again"))
+        .await
+        .unwrap();
+    AttachmentBmc::create(&tc.ctx, &tc.mm, attachment(pid, Some(alice), 4096))
+        .await
+        .unwrap();
+    AttachmentBmc::create(&tc.ctx, &tc.mm, attachment(pid, None, 100))
+        .await
+        .unwrap();
+
+    let usage = UsageBmc::for_agent(&tc.ctx, &tc.mm, AgentId::new(alice))
+        .await
+        .unwrap();
+    assert_eq!(usage.messages_sent, 1);
+    assert_eq!(usage.message_bytes, 6); // "héllo" is 6 bytes in UTF-8
+    assert_eq!(usage.attachments, 1);
+    assert_eq!(usage.attachment_bytes, 4096);
+    assert_eq!(usage.inbox_count, 2);
+
+    let project = UsageBmc::for_project(&tc.ctx, &tc.mm, pid).await.unwrap();
+    assert_eq!(project.messages, 3);
+    assert_eq!(project.message_bytes, 6 + 2 + 5);
+    assert_eq!(project.attachments, 2);
+    assert_eq!(project.attachment_bytes, 4196);
+    let names: Vec<&str> = project
+        .agents
+        .iter()
+        .map(|a| a.agent_name.as_str())
+        .collect();
+    assert_eq!(names, ["alice", "bob"]);
+    assert!(!project.limits.enabled);
+    assert_eq!(project.limits.agent_messages, None);
+
+    let missing = UsageBmc::for_agent(&tc.ctx, &tc.mm, AgentId::new(9999)).await;
+    assert!(matches!(missing, Err(mouchak_mail_core::Error::NotFound)));
+}
@@ -0,0 +1,164 @@
+//! Reply deadline tests
+//!
+//! Tests that recipients can accept or counter a requested reply deadline,
+//! and that agreed deadlines replace the fixed ack escalation threshold.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::{Duration, Utc};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::reply_deadline::ReplyDeadlineBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(tc: &TestContext, project_id: ProjectId, from: AgentId, to: &[AgentId]) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: to.to_vec(),
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Review the schema".to_string(),
+            body_md: "Need sign-off before the migration".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: true,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_accept_and_propose_deadlines() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "deadlines", "/deadlines")
+        .await
+        .unwrap();
+    let lead = create_agent(&tc, project_id, "lead").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+    let carol = create_agent(&tc, project_id, "carol").await;
+    let id = send(&tc, project_id, lead, &[bob, carol]).await;
+
+    let requested = Utc::now().naive_utc() + Duration::hours(4);
+    let past = ReplyDeadlineBmc::request(
+        &tc.ctx,
+        &tc.mm,
+        id,
+        &[bob],
+        Utc::now().naive_utc() - Duration::hours(1),
+    )
+    .await;
+    assert!(matches!(past, Err(Error::InvalidInput(_))));
+    ReplyDeadlineBmc::request(&tc.ctx, &tc.mm, id, &[bob, carol], requested)
+        .await
+        .unwrap();
+
+    let accepted = ReplyDeadlineBmc::respond(&tc.ctx, &tc.mm, id, bob, None)
+        .await
+        .unwrap();
+    assert_eq!(accepted.status, "accepted");
+    assert_eq!(accepted.deadline_ts, accepted.requested_ts);
+
+    let later = Utc::now().naive_utc() + Duration::hours(8);
+    let proposed = ReplyDeadlineBmc::respond(&tc.ctx, &tc.mm, id, carol, Some(later))
+        .await
+        .unwrap();
+    assert_eq!(proposed.status, "proposed");
+    assert!(proposed.deadline_ts > proposed.requested_ts);
+
+    // Only recipients asked for a deadline can respond
+    let not_asked = ReplyDeadlineBmc::respond(&tc.ctx, &tc.mm, id, lead, None).await;
+    assert!(matches!(not_asked, Err(Error::NotFound)));
+
+    let all = ReplyDeadlineBmc::list_for_message(&tc.ctx, &tc.mm, id)
+        .await
+        .unwrap();
+    let names: Vec<&str> = all.iter().map(|d| d.agent_name.as_str()).collect();
+    assert_eq!(names, ["bob", "carol"]);
+
+    MessageBmc::acknowledge(&tc.ctx, &tc.mm, id, bob)
+        .await
+        .unwrap();
+    let open = ReplyDeadlineBmc::list_open_for_agent(&tc.ctx, &tc.mm, bob)
+        .await
+        .unwrap();
+    assert!(open.is_empty());
+    let open = ReplyDeadlineBmc::list_open_for_agent(&tc.ctx, &tc.mm, carol)
+        .await
+        .unwrap();
+    assert_eq!(open.len(), 1);
+    assert!(!open[0].acked);
+}
+
+#[tokio::test]
+async fn test_agreed_deadlines_drive_overdue_acks() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "deadline-esc", "/deadline-esc")
+        .await
+        .unwrap();
+    let lead = create_agent(&tc, project_id, "lead").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+    let carol = create_agent(&tc, project_id, "carol").await;
+    let dave = create_agent(&tc, project_id, "dave").await;
+    let id = send(&tc, project_id, lead, &[bob, carol, dave]).await;
+
+    let requested = Utc::now().naive_utc() + Duration::hours(1);
+    ReplyDeadlineBmc::request(&tc.ctx, &tc.mm, id, &[bob, carol, dave], requested)
+        .await
+        .unwrap();
+    ReplyDeadlineBmc::respond(&tc.ctx, &tc.mm, id, bob, None)
+        .await
+        .unwrap();
+    ReplyDeadlineBmc::respond(
+        &tc.ctx,
+        &tc.mm,
+        id,
+        carol,
+        Some(Utc::now().naive_utc() + Duration::days(3)),
+    )
+    .await
+    .unwrap();
+
+    // Two days old and bob's agreed deadline has passed; carol's has not,
+    // and dave never answered so the fixed threshold applies to him
+    let db = tc.mm.db_for_test();
+    db.execute(
+        "UPDATE messages SET created_ts = datetime('now', '-2 days') WHERE id = ?",
+        [id.get()],
+    )
+    .await
+    .unwrap();
+    db.execute(
+        "UPDATE reply_deadlines SET deadline_ts = datetime('now', '-1 hours') WHERE message_id = ? AND agent_id = ?",
+        (id.get(), bob.get()),
+    )
+    .await
+    .unwrap();
+
+    let overdue = MessageBmc::list_overdue_acks(&tc.ctx, &tc.mm, 24)
+        .await
+        .unwrap();
+    let mut names: Vec<&str> = overdue
+        .iter()
+        .filter(|o| o.message_id == id.get())
+        .map(|o| o.recipient_name.as_str())
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, ["bob", "dave"]);
+
+    let overdue = MessageBmc::list_overdue_acks(&tc.ctx, &tc.mm, 72)
+        .await
+        .unwrap();
+    let names: Vec<&str> = overdue.iter().map(|o| o.recipient_name.as_str()).collect();
+    assert_eq!(names, ["bob"]);
+}
@@ -0,0 +1,170 @@
+//! Reservation set tests
+//!
+//! Tests for all-or-nothing multi-path reservations and set release.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use chrono::{Duration, Utc};
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::file_reservation::FileReservationBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::reservation_set::{
+    ReservationSetBmc, ReservationSetForCreate, ReservationSetOutcome,
+};
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+struct Sets {
+    tc: TestContext,
+    project_id: ProjectId,
+    alice: AgentId,
+    bob: AgentId,
+}
+
+async fn setup() -> Sets {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "sets", "/sets")
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for name in ["alice", "bob"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        ids.push(id);
+    }
+    Sets {
+        tc,
+        project_id,
+        alice: ids[0],
+        bob: ids[1],
+    }
+}
+
+impl Sets {
+    async fn acquire(&self, agent_id: AgentId, paths: &[&str]) -> ReservationSetOutcome {
+        ReservationSetBmc::acquire(
+            &self.tc.ctx,
+            &self.tc.mm,
+            ReservationSetForCreate {
+                project_id: self.project_id,
+                agent_id,
+                paths: paths.iter().map(|p| p.to_string()).collect(),
+                exclusive: true,
+                reason: "test".to_string(),
+                expires_ts: Utc::now().naive_utc() + Duration::hours(1),
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn active_patterns(&self, agent_id: AgentId) -> Vec<String> {
+        let mut patterns: Vec<String> =
+            FileReservationBmc::list_active_for_project(&self.tc.ctx, &self.tc.mm, self.project_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|r| r.agent_id == agent_id)
+                .map(|r| r.path_pattern)
+                .collect();
+        patterns.sort();
+        patterns
+    }
+}
+
+#[tokio::test]
+async fn test_acquire_grants_every_path_under_one_set() {
+    let sets = setup().await;
+    let outcome = sets
+        .acquire(sets.alice, &["src/lib.rs", "docs/**", "src/lib.rs"])
+        .await;
+    let ReservationSetOutcome::Granted {
+        set_id,
+        paths,
+        reservation_ids,
+    } = outcome
+    else {
+        panic!("expected a granted set");
+    };
+    assert_eq!(paths, ["src/lib.rs", "docs/**"]);
+    assert_eq!(reservation_ids.len(), 2);
+
+    let members = ReservationSetBmc::list_active_reservation_ids(&sets.tc.ctx, &sets.tc.mm, set_id)
+        .await
+        .unwrap();
+    assert_eq!(members, reservation_ids);
+    assert_eq!(
+        sets.active_patterns(sets.alice).await,
+        ["docs/**", "src/lib.rs"]
+    );
+}
+
+#[tokio::test]
+async fn test_conflict_reserves_nothing_and_names_holder() {
+    let sets = setup().await;
+    sets.acquire(sets.alice, &["src/**"]).await;
+
+    let outcome = sets.acquire(sets.bob, &["README.md", "src/main.rs"]).await;
+    let ReservationSetOutcome::Conflicted { clashes } = outcome else {
+        panic!("expected a conflict");
+    };
+    assert_eq!(clashes.len(), 1);
+    assert_eq!(clashes[0].path, "src/main.rs");
+    assert_eq!(clashes[0].path_pattern, "src/**");
+    assert_eq!(clashes[0].agent_name, "alice");
+
+    // README.md did not clash but must not be reserved either
+    assert!(sets.active_patterns(sets.bob).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_release_frees_every_member() {
+    let sets = setup().await;
+    let ReservationSetOutcome::Granted { set_id, .. } =
+        sets.acquire(sets.alice, &["a.rs", "b.rs"]).await
+    else {
+        panic!("expected a granted set");
+    };
+
+    // Only the holder may release the set
+    let err = ReservationSetBmc::release(&sets.tc.ctx, &sets.tc.mm, set_id, Some(sets.bob))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        mouchak_mail_core::Error::FileReservationNotFound(_)
+    ));
+
+    let released = ReservationSetBmc::release(&sets.tc.ctx, &sets.tc.mm, set_id, Some(sets.alice))
+        .await
+        .unwrap();
+    assert_eq!(released, 2);
+    assert!(sets.active_patterns(sets.alice).await.is_empty());
+
+    // The paths are free for others now
+    assert!(matches!(
+        sets.acquire(sets.bob, &["a.rs"]).await,
+        ReservationSetOutcome::Granted { .. }
+    ));
+
+    // Releasing again is a no-op
+    let released = ReservationSetBmc::release(&sets.tc.ctx, &sets.tc.mm, set_id, None)
+        .await
+        .unwrap();
+    assert_eq!(released, 0);
+}
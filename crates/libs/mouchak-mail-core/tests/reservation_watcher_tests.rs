@@ -0,0 +1,165 @@
+//! Reservation release receipt tests
+//!
+//! Tests for notifying waiting agents when a file reservation is released.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::{Duration, Utc};
+use mouchak_mail_core::model::file_reservation::{FileReservationBmc, FileReservationForCreate};
+use mouchak_mail_core::model::message::MessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::reservation_watcher::ReservationWatcherBmc;
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+async fn reserve(
+    tc: &TestContext,
+    project_id: ProjectId,
+    agent_id: AgentId,
+    path: &str,
+    ttl: Duration,
+) -> i64 {
+    FileReservationBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        FileReservationForCreate {
+            project_id,
+            agent_id,
+            path_pattern: path.to_string(),
+            exclusive: true,
+            reason: "test".to_string(),
+            expires_ts: Utc::now().naive_utc() + ttl,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_release_sends_receipt_once() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "watch-release", "/watch/release")
+        .await
+        .unwrap();
+    let holder = create_agent(&tc, project_id, "holder").await;
+    let waiter = create_agent(&tc, project_id, "waiter").await;
+
+    let res_id = reserve(&tc, project_id, holder, "src/lib.rs", Duration::hours(1)).await;
+    let added = ReservationWatcherBmc::add_watchers(&tc.ctx, &tc.mm, res_id, &[waiter, holder])
+        .await
+        .unwrap();
+    assert_eq!(added, 1, "holder must not watch its own reservation");
+
+    FileReservationBmc::release(&tc.ctx, &tc.mm, res_id)
+        .await
+        .unwrap();
+
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, waiter, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].subject, "[RELEASED] src/lib.rs");
+    assert_eq!(inbox[0].sender_id, holder.get());
+
+    // A second release must not re-notify
+    FileReservationBmc::force_release(&tc.ctx, &tc.mm, res_id)
+        .await
+        .unwrap();
+    let pending = ReservationWatcherBmc::list_pending(&tc.ctx, &tc.mm, res_id)
+        .await
+        .unwrap();
+    assert!(pending.is_empty());
+}
+
+#[tokio::test]
+async fn test_notify_expired_sends_receipt() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "watch-expiry", "/watch/expiry")
+        .await
+        .unwrap();
+    let holder = create_agent(&tc, project_id, "holder").await;
+    let waiter = create_agent(&tc, project_id, "waiter").await;
+
+    let res_id = reserve(&tc, project_id, holder, "docs/**", Duration::seconds(-5)).await;
+    ReservationWatcherBmc::add_watchers(&tc.ctx, &tc.mm, res_id, &[waiter])
+        .await
+        .unwrap();
+
+    let sent = ReservationWatcherBmc::notify_expired(&tc.ctx, &tc.mm)
+        .await
+        .unwrap();
+    assert_eq!(sent, 1);
+
+    let again = ReservationWatcherBmc::notify_expired(&tc.ctx, &tc.mm)
+        .await
+        .unwrap();
+    assert_eq!(again, 0);
+
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, waiter, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+    assert!(inbox[0].body_md.contains("expired"));
+}
+
+#[tokio::test]
+async fn test_wait_for_path_returns_when_released() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "watch-wait", "/watch/wait")
+        .await
+        .unwrap();
+    let holder = create_agent(&tc, project_id, "holder").await;
+    let waiter = create_agent(&tc, project_id, "waiter").await;
+
+    let res_id = reserve(&tc, project_id, holder, "src/**", Duration::hours(1)).await;
+
+    let outcome = ReservationWatcherBmc::wait_for_path(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        waiter,
+        "src/main.rs",
+        std::time::Duration::ZERO,
+        true,
+    )
+    .await
+    .unwrap();
+    assert!(!outcome.available);
+    assert_eq!(outcome.watching, vec![res_id]);
+
+    let ctx = tc.ctx.clone();
+    let mm = tc.mm.clone();
+    let releaser = tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        FileReservationBmc::release(&ctx, &mm, res_id)
+            .await
+            .unwrap();
+    });
+
+    let outcome = ReservationWatcherBmc::wait_for_path(
+        &tc.ctx,
+        &tc.mm,
+        project_id,
+        waiter,
+        "src/main.rs",
+        std::time::Duration::from_secs(5),
+        true,
+    )
+    .await
+    .unwrap();
+    releaser.await.unwrap();
+
+    assert!(outcome.available);
+    assert!(outcome.blocking.is_empty());
+    assert!(outcome.watching.is_empty());
+
+    // The watcher registered by the first call got a receipt
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, waiter, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+}
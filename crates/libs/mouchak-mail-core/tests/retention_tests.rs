@@ -0,0 +1,279 @@
+//! Message retention tests
+//!
+//! Tests per-project retention policies, dry runs, purges by age and by
+//! mailbox cap, and the audit records and archive cleanup they leave.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_common::config::{AppConfig, RetentionAction};
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::retention::{PurgeReason, RetentionBmc, RetentionPolicyForUpdate};
+use mouchak_mail_core::store::git_store;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+struct Setup {
+    project_id: ProjectId,
+    alice: AgentId,
+    bob: AgentId,
+}
+
+async fn setup(tc: &TestContext, slug: &str) -> Setup {
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, slug, slug)
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["alice", "bob"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id);
+    }
+    Setup {
+        project_id,
+        alice: agents[0],
+        bob: agents[1],
+    }
+}
+
+async fn send(tc: &TestContext, s: &Setup, subject: &str, days_old: i64) -> MessageId {
+    let id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id: s.project_id,
+            sender_id: s.alice,
+            recipient_ids: vec![s.bob],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.into(),
+            body_md: "Body".into(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    tc.mm
+        .db_for_test()
+        .execute(
+            "UPDATE messages SET created_ts = datetime('now', ?) WHERE id = ?",
+            (format!("-{} days", days_old), id.get()),
+        )
+        .await
+        .unwrap();
+    id
+}
+
+async fn wait_for_archive_commits() {
+    while git_store::pending_archive_commits() > 0 {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+}
+
+fn archived_copies(dir: &std::path::Path, id: i64) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .map(|e| e.unwrap().path())
+        .map(|path| {
+            if path.is_dir() {
+                archived_copies(&path, id)
+            } else {
+                usize::from(path.to_string_lossy().ends_with(&format!("__{}.md", id)))
+            }
+        })
+        .sum()
+}
+
+#[tokio::test]
+async fn test_policy_overrides_config() {
+    let mut config = AppConfig::default();
+    config.retention.max_age_days = 90;
+    let tc = TestContext::new_with_config(config).await.unwrap();
+    let s = setup(&tc, "policy").await;
+
+    let policy = RetentionBmc::get_policy(&tc.ctx, &tc.mm, s.project_id)
+        .await
+        .unwrap();
+    assert_eq!(policy.max_age_days, Some(90));
+    assert_eq!(policy.max_messages, None);
+    assert_eq!(policy.action, RetentionAction::Archive);
+    assert!(policy.updated_ts.is_none());
+
+    let policy = RetentionBmc::set_policy(
+        &tc.ctx,
+        &tc.mm,
+        s.project_id,
+        RetentionPolicyForUpdate {
+            max_messages: Some(100),
+            action: Some(RetentionAction::Delete),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(policy.max_age_days, Some(90));
+    assert_eq!(policy.max_messages, Some(100));
+    assert_eq!(policy.action, RetentionAction::Delete);
+    assert!(policy.updated_ts.is_some());
+
+    // 0 turns the inherited age limit off; the cap set before is kept
+    let policy = RetentionBmc::set_policy(
+        &tc.ctx,
+        &tc.mm,
+        s.project_id,
+        RetentionPolicyForUpdate {
+            max_age_days: Some(0),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(policy.max_age_days, None);
+    assert_eq!(policy.max_messages, Some(100));
+
+    assert!(
+        RetentionBmc::clear_policy(&tc.ctx, &tc.mm, s.project_id)
+            .await
+            .unwrap()
+    );
+    let policy = RetentionBmc::get_policy(&tc.ctx, &tc.mm, s.project_id)
+        .await
+        .unwrap();
+    assert_eq!(policy.max_age_days, Some(90));
+    assert_eq!(policy.action, RetentionAction::Archive);
+}
+
+#[tokio::test]
+async fn test_run_purges_by_age_and_cap() {
+    let mut config = AppConfig::default();
+    config.retention.max_age_days = 30;
+    let tc = TestContext::new_with_config(config).await.unwrap();
+    let s = setup(&tc, "retained").await;
+    let old = send(&tc, &s, "Old news", 45).await;
+    let older_kept = send(&tc, &s, "Last month", 20).await;
+    let recent = send(&tc, &s, "Recent", 10).await;
+    let newest = send(&tc, &s, "Today", 0).await;
+    RetentionBmc::set_policy(
+        &tc.ctx,
+        &tc.mm,
+        s.project_id,
+        RetentionPolicyForUpdate {
+            max_messages: Some(2),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    wait_for_archive_commits().await;
+
+    let report = RetentionBmc::run(&tc.ctx, &tc.mm, true).await.unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.projects.len(), 1);
+    let purge = &report.projects[0];
+    let planned: Vec<(i64, PurgeReason)> =
+        purge.messages.iter().map(|m| (m.id, m.reason)).collect();
+    assert_eq!(
+        planned,
+        vec![
+            (old.get(), PurgeReason::Age),
+            (older_kept.get(), PurgeReason::Cap)
+        ]
+    );
+    assert!(purge.audit_path.is_none());
+    MessageBmc::get(&tc.ctx, &tc.mm, old).await.unwrap();
+
+    let report = RetentionBmc::run(&tc.ctx, &tc.mm, false).await.unwrap();
+    assert_eq!(report.purged(), 2);
+    for id in [old, older_kept] {
+        assert!(MessageBmc::get(&tc.ctx, &tc.mm, id).await.is_err());
+    }
+    for id in [recent, newest] {
+        MessageBmc::get(&tc.ctx, &tc.mm, id).await.unwrap();
+    }
+    assert!(
+        MessageBmc::get_recipients(&tc.ctx, &tc.mm, old)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // Archive action: the markdown stays, the purge is on record
+    let project_dir = tc.repo_root().join("projects/retained");
+    assert!(archived_copies(&project_dir, old.get()) > 0);
+    let audit_path = report.projects[0].audit_path.clone().unwrap();
+    let audit: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(tc.repo_root().join(&audit_path)).unwrap())
+            .unwrap();
+    assert_eq!(audit["action"], "archive");
+    assert_eq!(audit["messages"][0]["id"], old.get());
+    assert_eq!(audit["messages"][1]["reason"], "cap");
+
+    // Nothing left past the limits
+    let report = RetentionBmc::run(&tc.ctx, &tc.mm, false).await.unwrap();
+    assert_eq!(report.purged(), 0);
+}
+
+#[tokio::test]
+async fn test_delete_action_removes_archived_copies() {
+    let tc = TestContext::new().await.unwrap();
+    let s = setup(&tc, "forgetful").await;
+    let other = setup(&tc, "untouched").await;
+    let old = send(&tc, &s, "Secret", 400).await;
+    let kept = send(&tc, &s, "Fresh", 1).await;
+    let elsewhere = send(&tc, &other, "Old but unlimited", 400).await;
+    RetentionBmc::set_policy(
+        &tc.ctx,
+        &tc.mm,
+        s.project_id,
+        RetentionPolicyForUpdate {
+            max_age_days: Some(365),
+            action: Some(RetentionAction::Delete),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    wait_for_archive_commits().await;
+
+    let project_dir = tc.repo_root().join("projects/forgetful");
+    // Canonical copy, sender outbox and recipient inbox
+    assert_eq!(archived_copies(&project_dir, old.get()), 3);
+
+    let report = RetentionBmc::run(&tc.ctx, &tc.mm, false).await.unwrap();
+    assert_eq!(report.projects.len(), 1);
+    assert_eq!(report.projects[0].archive_files_removed, 3);
+    assert_eq!(archived_copies(&project_dir, old.get()), 0);
+    assert_eq!(archived_copies(&project_dir, kept.get()), 3);
+    MessageBmc::get(&tc.ctx, &tc.mm, kept).await.unwrap();
+    MessageBmc::get(&tc.ctx, &tc.mm, elsewhere).await.unwrap();
+
+    // The removal is committed along with the audit record
+    let repo = git_store::open_repo(tc.repo_root()).unwrap();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(
+        head.message().unwrap(),
+        "retention: delete 1 message(s) of forgetful"
+    );
+    let audit_path = report.projects[0].audit_path.clone().unwrap();
+    git_store::read_file_content(&repo, &audit_path).unwrap();
+}
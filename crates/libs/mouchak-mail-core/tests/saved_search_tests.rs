@@ -0,0 +1,257 @@
+//! Saved search tests
+//!
+//! Tests that saved searches are validated, scoped to their owner's inbox,
+//! and evaluated against the current mailbox each time.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent_capabilities::{AgentCapabilityBmc, AgentCapabilityForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::saved_search::{
+    SavedSearchBmc, SavedSearchCriteria, SavedSearchForCreate,
+};
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    subject: &str,
+    importance: &str,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.to_string(),
+            body_md: "Details inside".to_string(),
+            thread_id: None,
+            importance: Some(importance.to_string()),
+            ack_required: true,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_smart_folder_tracks_inbox_state() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "folders", "/folders")
+        .await
+        .unwrap();
+    let reviewer = create_agent(&tc, project_id, "reviewer").await;
+    let carol = create_agent(&tc, project_id, "carol").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+    AgentCapabilityBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentCapabilityForCreate {
+            agent_id: reviewer.get(),
+            capability: "code-review".to_string(),
+            granted_by: None,
+            expires_at: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let wanted = send(&tc, project_id, reviewer, bob, "Schema review", "urgent").await;
+    send(&tc, project_id, carol, bob, "Lunch order", "urgent").await;
+    send(&tc, project_id, reviewer, bob, "Style nits", "normal").await;
+    send(&tc, project_id, reviewer, carol, "Schema review", "urgent").await;
+
+    let criteria = SavedSearchCriteria {
+        importance: vec!["urgent".to_string()],
+        from: vec!["capability:code-review".to_string()],
+        unread: true,
+        ..Default::default()
+    };
+    let id = SavedSearchBmc::save(
+        &tc.ctx,
+        &tc.mm,
+        SavedSearchForCreate {
+            project_id,
+            agent_id: Some(bob),
+            name: "Urgent from reviewers".to_string(),
+            criteria: criteria.clone(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let search = SavedSearchBmc::get(&tc.ctx, &tc.mm, project_id, id)
+        .await
+        .unwrap();
+    assert_eq!(search.agent_name.as_deref(), Some("bob"));
+    assert_eq!(search.criteria, criteria);
+    let result = SavedSearchBmc::evaluate(&tc.ctx, &tc.mm, search.clone(), 10)
+        .await
+        .unwrap();
+    assert_eq!(result.total, 1);
+    let ids: Vec<i64> = result.messages.iter().map(|m| m.id).collect();
+    assert_eq!(ids, [wanted.get()]);
+
+    // Reading the message takes it out of the folder
+    MessageBmc::mark_read(&tc.ctx, &tc.mm, wanted, bob)
+        .await
+        .unwrap();
+    assert_eq!(
+        SavedSearchBmc::count(&tc.ctx, &tc.mm, &search)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // Saving under the same name replaces the criteria
+    let same = SavedSearchBmc::save(
+        &tc.ctx,
+        &tc.mm,
+        SavedSearchForCreate {
+            project_id,
+            agent_id: Some(bob),
+            name: "Urgent from reviewers".to_string(),
+            criteria: SavedSearchCriteria {
+                unread: false,
+                ..criteria
+            },
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(same, id);
+    let search = SavedSearchBmc::get(&tc.ctx, &tc.mm, project_id, id)
+        .await
+        .unwrap();
+    assert_eq!(
+        SavedSearchBmc::count(&tc.ctx, &tc.mm, &search)
+            .await
+            .unwrap(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_shared_searches_and_validation() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "shared", "/shared")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+    send(&tc, project_id, alice, bob, "Schema review", "high").await;
+    send(
+        &tc,
+        project_id,
+        bob,
+        alice,
+        "Schema migration done",
+        "normal",
+    )
+    .await;
+    send(&tc, project_id, bob, alice, "Release notes", "normal").await;
+
+    let save = |agent_id: Option<AgentId>, name: &str, criteria: SavedSearchCriteria| {
+        SavedSearchBmc::save(
+            &tc.ctx,
+            &tc.mm,
+            SavedSearchForCreate {
+                project_id,
+                agent_id,
+                name: name.to_string(),
+                criteria,
+            },
+        )
+    };
+
+    let shared = save(
+        None,
+        "Schema",
+        SavedSearchCriteria {
+            query: Some("schema".to_string()),
+            within: Some("1d".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    save(Some(alice), "Mine", SavedSearchCriteria::default())
+        .await
+        .unwrap();
+
+    let search = SavedSearchBmc::get(&tc.ctx, &tc.mm, project_id, shared)
+        .await
+        .unwrap();
+    let result = SavedSearchBmc::evaluate(&tc.ctx, &tc.mm, search, 10)
+        .await
+        .unwrap();
+    assert_eq!(result.total, 2);
+
+    let invalid = [
+        (
+            None,
+            "Unread",
+            SavedSearchCriteria {
+                unread: true,
+                ..Default::default()
+            },
+        ),
+        (
+            Some(alice),
+            "Bad level",
+            SavedSearchCriteria {
+                importance: vec!["critical".to_string()],
+                ..Default::default()
+            },
+        ),
+        (
+            Some(alice),
+            "Ghost",
+            SavedSearchCriteria {
+                from: vec!["nobody".to_string()],
+                ..Default::default()
+            },
+        ),
+        (Some(alice), "  ", SavedSearchCriteria::default()),
+    ];
+    for (agent_id, name, criteria) in invalid {
+        let err = save(agent_id, name, criteria).await;
+        assert!(matches!(err, Err(Error::InvalidInput(_))), "{}", name);
+    }
+
+    // Agents see shared searches and their own
+    let alice_list = SavedSearchBmc::list(&tc.ctx, &tc.mm, project_id, Some(alice))
+        .await
+        .unwrap();
+    let names: Vec<&str> = alice_list.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Mine", "Schema"]);
+    let bob_list = SavedSearchBmc::list(&tc.ctx, &tc.mm, project_id, Some(bob))
+        .await
+        .unwrap();
+    let names: Vec<&str> = bob_list.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Schema"]);
+    let folders = SavedSearchBmc::folders(&tc.ctx, &tc.mm, project_id, Some(alice))
+        .await
+        .unwrap();
+    let counts: Vec<i64> = folders.iter().map(|f| f.count).collect();
+    assert_eq!(counts, [2, 2]);
+
+    SavedSearchBmc::delete(&tc.ctx, &tc.mm, project_id, shared)
+        .await
+        .unwrap();
+    let gone = SavedSearchBmc::get(&tc.ctx, &tc.mm, project_id, shared).await;
+    assert!(matches!(gone, Err(Error::NotFound)));
+}
@@ -0,0 +1,173 @@
+//! Search facet tests
+//!
+//! Tests for counting search matches by sender, importance, month and
+//! custom field value.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use chrono::Utc;
+use mouchak_mail_core::model::custom_field::{
+    CustomFieldBmc, CustomFieldForCreate, CustomFieldType,
+};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::search_facet::{FacetCount, SearchFacetBmc};
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+use serde_json::{Value, json};
+
+async fn post(
+    tc: &TestContext,
+    project_id: ProjectId,
+    (from, to): (AgentId, AgentId),
+    subject: &str,
+    importance: &str,
+    fields: Value,
+) -> MessageId {
+    let id = MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: subject.to_string(),
+            body_md: subject.to_string(),
+            thread_id: None,
+            importance: Some(importance.to_string()),
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+    if let Value::Object(fields) = fields {
+        MessageBmc::set_custom_fields(&tc.ctx, &tc.mm, id, &fields)
+            .await
+            .unwrap();
+    }
+    id
+}
+
+fn counts(pairs: &[(&str, i64)]) -> Vec<FacetCount> {
+    pairs
+        .iter()
+        .map(|(value, count)| FacetCount {
+            value: value.to_string(),
+            count: *count,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_facets_group_all_matches() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "facets", "/facets")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+    for (name, field_type) in [
+        ("component", CustomFieldType::String),
+        ("blocking", CustomFieldType::Boolean),
+    ] {
+        CustomFieldBmc::define(
+            &tc.ctx,
+            &tc.mm,
+            CustomFieldForCreate {
+                project_id,
+                name: name.to_string(),
+                field_type,
+                required: false,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let old = post(
+        &tc,
+        project_id,
+        (alice, bob),
+        "Deploy failed on db",
+        "high",
+        json!({"component": "db", "blocking": true}),
+    )
+    .await;
+    post(
+        &tc,
+        project_id,
+        (alice, bob),
+        "Deploy done",
+        "normal",
+        json!({"component": "api"}),
+    )
+    .await;
+    post(
+        &tc,
+        project_id,
+        (bob, alice),
+        "Deploy rolled back",
+        "high",
+        json!({"component": "db"}),
+    )
+    .await;
+    post(
+        &tc,
+        project_id,
+        (bob, alice),
+        "Lunch",
+        "normal",
+        Value::Null,
+    )
+    .await;
+    tc.mm
+        .db_for_test()
+        .execute(
+            "UPDATE messages SET created_ts = '2025-01-15 10:00:00' WHERE id = ?",
+            [old.get()],
+        )
+        .await
+        .unwrap();
+
+    let facets = SearchFacetBmc::facets(&tc.ctx, &tc.mm, project_id, "deploy", false, &[], 20)
+        .await
+        .unwrap();
+    assert_eq!(facets.total, 3);
+    assert_eq!(facets.sender, counts(&[("alice", 2), ("bob", 1)]));
+    assert_eq!(facets.importance, counts(&[("high", 2), ("normal", 1)]));
+    let this_month = Utc::now().format("%Y-%m").to_string();
+    assert_eq!(
+        facets.month,
+        counts(&[(this_month.as_str(), 2), ("2025-01", 1)])
+    );
+    assert_eq!(facets.fields["component"], counts(&[("db", 2), ("api", 1)]));
+    assert_eq!(facets.fields["blocking"], counts(&[("true", 1)]));
+
+    // Field filters narrow the counted set; limit trims each facet
+    let mut values = serde_json::Map::new();
+    values.insert("component".to_string(), json!("db"));
+    let filters = CustomFieldBmc::filters(&tc.ctx, &tc.mm, project_id, &values)
+        .await
+        .unwrap();
+    let facets = SearchFacetBmc::facets(&tc.ctx, &tc.mm, project_id, "deploy", false, &filters, 1)
+        .await
+        .unwrap();
+    assert_eq!(facets.total, 2);
+    assert_eq!(facets.sender, counts(&[("alice", 1)]));
+    assert_eq!(facets.importance, counts(&[("high", 2)]));
+
+    // Queries that cannot match give empty facets
+    for query in ["*", "nothing-like-this", "\"unbalanced"] {
+        let facets = SearchFacetBmc::facets(&tc.ctx, &tc.mm, project_id, query, false, &[], 20)
+            .await
+            .unwrap();
+        assert_eq!(facets.total, 0, "{}", query);
+        assert!(facets.sender.is_empty());
+    }
+}
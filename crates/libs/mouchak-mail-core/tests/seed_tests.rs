@@ -0,0 +1,84 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+mod common;
+
+use common::TestContext;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::file_reservation::FileReservationBmc;
+use mouchak_mail_core::model::message::MessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::seed::{SeedBmc, SeedConfig};
+
+fn config(seed: u64) -> SeedConfig {
+    SeedConfig {
+        projects: 2,
+        agents: 4,
+        messages: 30,
+        seed,
+    }
+}
+
+#[tokio::test]
+async fn test_seed_populates_counts() -> mouchak_mail_core::Result<()> {
+    let tc = TestContext::new().await?;
+    let summary = SeedBmc::seed(&tc.ctx, &tc.mm, &config(42)).await?;
+
+    assert_eq!(summary.project_slugs.len(), 2);
+    assert_eq!(summary.agents, 8);
+    assert_eq!(summary.messages, 60);
+    assert!(summary.threads > 0 && summary.threads <= 60);
+    assert!(summary.reservations > 0);
+
+    let project = ProjectBmc::get_by_slug(&tc.ctx, &tc.mm, &summary.project_slugs[0]).await?;
+    let agents = AgentBmc::list_all_for_project(&tc.ctx, &tc.mm, project.id).await?;
+    assert_eq!(agents.len(), 4);
+
+    let messages = MessageBmc::list_recent(&tc.ctx, &tc.mm, project.id, 100).await?;
+    assert_eq!(messages.len(), 30);
+    assert!(messages.iter().any(|m| m.subject.starts_with("Re: ")));
+
+    let reservations =
+        FileReservationBmc::list_all_for_project(&tc.ctx, &tc.mm, project.id).await?;
+    assert!(!reservations.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seed_is_reproducible() -> mouchak_mail_core::Result<()> {
+    let first = TestContext::new().await?;
+    let second = TestContext::new().await?;
+
+    let a = SeedBmc::seed(&first.ctx, &first.mm, &config(7)).await?;
+    let b = SeedBmc::seed(&second.ctx, &second.mm, &config(7)).await?;
+
+    assert_eq!(a.project_slugs, b.project_slugs);
+    assert_eq!(a.threads, b.threads);
+    assert_eq!(a.acks, b.acks);
+    assert_eq!(a.attachments, b.attachments);
+
+    for slug in &a.project_slugs {
+        let pa = ProjectBmc::get_by_slug(&first.ctx, &first.mm, slug).await?;
+        let pb = ProjectBmc::get_by_slug(&second.ctx, &second.mm, slug).await?;
+        let ma = MessageBmc::list_recent(&first.ctx, &first.mm, pa.id, 100).await?;
+        let mb = MessageBmc::list_recent(&second.ctx, &second.mm, pb.id, 100).await?;
+        let sa: Vec<_> = ma.iter().map(|m| (&m.subject, &m.body_md)).collect();
+        let sb: Vec<_> = mb.iter().map(|m| (&m.subject, &m.body_md)).collect();
+        assert_eq!(sa, sb);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seed_rejects_existing_dataset() -> mouchak_mail_core::Result<()> {
+    let tc = TestContext::new().await?;
+    SeedBmc::seed(&tc.ctx, &tc.mm, &config(1)).await?;
+
+    let again = SeedBmc::seed(&tc.ctx, &tc.mm, &config(1)).await;
+    assert!(matches!(
+        again,
+        Err(mouchak_mail_core::Error::InvalidInput(_))
+    ));
+
+    Ok(())
+}
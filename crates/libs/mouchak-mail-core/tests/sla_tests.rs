@@ -0,0 +1,272 @@
+//! Agent SLA tests
+//!
+//! Tests for SLA definitions, compliance reports, and reporting violations
+//! to the overseer inbox.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::overseer_message::OverseerMessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::sla::{ProjectSlaForCreate, SlaBmc};
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+struct Setup {
+    tc: TestContext,
+    project_id: ProjectId,
+    lead: AgentId,
+    fast: AgentId,
+    slow: AgentId,
+}
+
+/// A project with an hour-long SLA for urgent messages, defined a day ago.
+async fn setup() -> Setup {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "sla", "/sla")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["lead", "fast", "slow"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id);
+    }
+
+    SlaBmc::set_sla(
+        &tc.ctx,
+        &tc.mm,
+        ProjectSlaForCreate {
+            project_id,
+            importance: "urgent".to_string(),
+            ack_within_seconds: 3600,
+        },
+    )
+    .await
+    .unwrap();
+    tc.mm
+        .db_for_test()
+        .execute(
+            "UPDATE project_slas SET created_ts = datetime('now', '-1 day')",
+            (),
+        )
+        .await
+        .unwrap();
+
+    Setup {
+        tc,
+        project_id,
+        lead: agents[0],
+        fast: agents[1],
+        slow: agents[2],
+    }
+}
+
+impl Setup {
+    /// Sends a message to `fast` and `slow`, `hours_ago` hours in the past.
+    async fn send(&self, importance: &str, hours_ago: i64) -> MessageId {
+        let id = MessageBmc::create(
+            &self.tc.ctx,
+            &self.tc.mm,
+            MessageForCreate {
+                project_id: self.project_id,
+                sender_id: self.lead,
+                recipient_ids: vec![self.fast, self.slow],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: format!("{} request", importance),
+                body_md: "Please confirm".to_string(),
+                thread_id: None,
+                importance: Some(importance.to_string()),
+                ack_required: true,
+            },
+        )
+        .await
+        .unwrap();
+        self.tc
+            .mm
+            .db_for_test()
+            .execute(
+                "UPDATE messages SET created_ts = datetime('now', ?) WHERE id = ?",
+                (format!("-{} hours", hours_ago), id.get()),
+            )
+            .await
+            .unwrap();
+        id
+    }
+
+    /// Records an ack `minutes_after` minutes after the message was sent.
+    async fn ack(&self, message_id: i64, agent: AgentId, minutes_after: i64) {
+        self.tc
+            .mm
+            .db_for_test()
+            .execute(
+                "UPDATE message_recipients
+                 SET ack_ts = (SELECT datetime(created_ts, ?) FROM messages WHERE id = ?)
+                 WHERE message_id = ? AND agent_id = ?",
+                (
+                    format!("+{} minutes", minutes_after),
+                    message_id,
+                    message_id,
+                    agent.get(),
+                ),
+            )
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_report_counts_met_violated_and_pending() {
+    let s = setup().await;
+    // fast acks in 10 minutes, slow in 2 hours
+    let acked = s.send("urgent", 5).await;
+    s.ack(acked.get(), s.fast, 10).await;
+    s.ack(acked.get(), s.slow, 120).await;
+    // Nobody acks; the deadline has passed
+    let overdue = s.send("urgent", 3).await;
+    // Sent just now; still within the deadline
+    s.send("urgent", 0).await;
+    // No SLA for normal messages
+    s.send("normal", 5).await;
+
+    let report = SlaBmc::report(&s.tc.ctx, &s.tc.mm, s.project_id, 86_400)
+        .await
+        .unwrap();
+
+    assert_eq!(report.slas.len(), 1);
+    assert_eq!(report.overall.measured, 6);
+    assert_eq!(report.overall.met, 1);
+    assert_eq!(report.overall.violated, 3);
+    assert_eq!(report.overall.pending, 2);
+    assert_eq!(report.overall.compliance_percent, Some(25.0));
+
+    let names: Vec<&str> = report
+        .agents
+        .iter()
+        .map(|a| a.agent_name.as_str())
+        .collect();
+    assert_eq!(names, ["fast", "slow"]);
+    assert_eq!(report.agents[0].tally.compliance_percent, Some(50.0));
+    assert_eq!(report.agents[1].tally.compliance_percent, Some(0.0));
+
+    let violations: Vec<(MessageId, &str, bool)> = report
+        .violations
+        .iter()
+        .map(|v| (v.message_id, v.agent_name.as_str(), v.ack_ts.is_some()))
+        .collect();
+    assert_eq!(
+        violations,
+        [
+            (acked, "slow", true),
+            (overdue, "fast", false),
+            (overdue, "slow", false),
+        ]
+    );
+
+    // A shorter period leaves out the older messages
+    let recent = SlaBmc::report(&s.tc.ctx, &s.tc.mm, s.project_id, 4 * 3600)
+        .await
+        .unwrap();
+    assert_eq!(recent.overall.measured, 4);
+}
+
+#[tokio::test]
+async fn test_messages_before_sla_are_not_covered() {
+    let s = setup().await;
+    s.send("urgent", 30).await;
+
+    let report = SlaBmc::report(&s.tc.ctx, &s.tc.mm, s.project_id, 7 * 86_400)
+        .await
+        .unwrap();
+    assert_eq!(report.overall.measured, 0);
+    assert_eq!(report.overall.compliance_percent, None);
+}
+
+#[tokio::test]
+async fn test_violations_reach_overseer_inbox_once() {
+    let s = setup().await;
+    let overdue = s.send("urgent", 3).await;
+    s.ack(overdue.get(), s.fast, 30).await;
+    s.send("urgent", 0).await;
+
+    let reported = SlaBmc::report_violations(&s.tc.ctx, &s.tc.mm)
+        .await
+        .unwrap();
+    assert_eq!(reported, 1);
+    let again = SlaBmc::report_violations(&s.tc.ctx, &s.tc.mm)
+        .await
+        .unwrap();
+    assert_eq!(again, 0);
+
+    let inbox = OverseerMessageBmc::list_for_project(&s.tc.ctx, &s.tc.mm, s.project_id, false, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].subject, "[SLA] urgent request");
+    assert!(inbox[0].body_md.contains("- slow (not acknowledged)"));
+    assert!(!inbox[0].body_md.contains("fast"));
+}
+
+#[tokio::test]
+async fn test_set_sla_validates_and_updates() {
+    let s = setup().await;
+    let err = SlaBmc::set_sla(
+        &s.tc.ctx,
+        &s.tc.mm,
+        ProjectSlaForCreate {
+            project_id: s.project_id,
+            importance: "critical".to_string(),
+            ack_within_seconds: 60,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+
+    let slas = SlaBmc::list_slas(&s.tc.ctx, &s.tc.mm, s.project_id)
+        .await
+        .unwrap();
+    let id = SlaBmc::set_sla(
+        &s.tc.ctx,
+        &s.tc.mm,
+        ProjectSlaForCreate {
+            project_id: s.project_id,
+            importance: "urgent".to_string(),
+            ack_within_seconds: 1800,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(id, slas[0].id);
+    let updated = SlaBmc::list_slas(&s.tc.ctx, &s.tc.mm, s.project_id)
+        .await
+        .unwrap();
+    assert_eq!(updated[0].ack_within_seconds, 1800);
+    assert_eq!(updated[0].created_ts, slas[0].created_ts);
+
+    SlaBmc::delete_sla(&s.tc.ctx, &s.tc.mm, s.project_id, id)
+        .await
+        .unwrap();
+    let err = SlaBmc::delete_sla(&s.tc.ctx, &s.tc.mm, s.project_id, id)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::NotFound));
+}
@@ -0,0 +1,163 @@
+//! Team graph tests
+//!
+//! Tests for exporting a project's agents, capabilities and contacts and
+//! importing them into another project.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::agent_capabilities::{
+    AgentCapabilityBmc, AgentCapabilityForCreate, DEFAULT_CAPABILITIES,
+};
+use mouchak_mail_core::model::agent_link::{AgentLinkBmc, AgentLinkForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::team_graph::{
+    TEAM_GRAPH_VERSION, TeamContact, TeamGraph, TeamGraphBmc,
+};
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+async fn create_agent(tc: &TestContext, project_id: ProjectId, name: &str) -> AgentId {
+    AgentBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: "claude-code".to_string(),
+            model: "test".to_string(),
+            task_description: format!("{} duties", name),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_team_graph_round_trip() {
+    let tc = TestContext::new().await.unwrap();
+    let source = ProjectBmc::create(&tc.ctx, &tc.mm, "team-src", "/team-src")
+        .await
+        .unwrap();
+    let target = ProjectBmc::create(&tc.ctx, &tc.mm, "team-dst", "/team-dst")
+        .await
+        .unwrap();
+
+    let planner = create_agent(&tc, source, "planner").await;
+    let reviewer = create_agent(&tc, source, "reviewer").await;
+    AgentCapabilityBmc::grant_defaults(&tc.ctx, &tc.mm, planner)
+        .await
+        .unwrap();
+    AgentCapabilityBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentCapabilityForCreate {
+            agent_id: reviewer.get(),
+            capability: "code-review".to_string(),
+            granted_by: None,
+            expires_at: None,
+        },
+    )
+    .await
+    .unwrap();
+    let link_id = AgentLinkBmc::request_contact(
+        &tc.ctx,
+        &tc.mm,
+        AgentLinkForCreate {
+            a_project_id: source.get(),
+            a_agent_id: planner.get(),
+            b_project_id: source.get(),
+            b_agent_id: reviewer.get(),
+            reason: "reviews plans".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    AgentLinkBmc::respond_contact(&tc.ctx, &tc.mm, link_id, true)
+        .await
+        .unwrap();
+
+    let graph = TeamGraphBmc::export(&tc.ctx, &tc.mm, source).await.unwrap();
+    assert_eq!(graph.version, TEAM_GRAPH_VERSION);
+    assert_eq!(graph.project, "team-src");
+    let names: Vec<&str> = graph.agents.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names.len(), 2);
+    assert!(names.contains(&"planner") && names.contains(&"reviewer"));
+    let reviewer_entry = graph.agents.iter().find(|a| a.name == "reviewer").unwrap();
+    assert_eq!(reviewer_entry.capabilities, ["code-review"]);
+    assert_eq!(
+        graph.contacts,
+        [TeamContact {
+            from: "planner".to_string(),
+            from_project: None,
+            to: "reviewer".to_string(),
+            to_project: None,
+            reason: "reviews plans".to_string(),
+        }]
+    );
+
+    // Round trip through JSON, as the tools do
+    let json = serde_json::to_string(&graph).unwrap();
+    let mut graph: TeamGraph = serde_json::from_str(&json).unwrap();
+    graph.contacts.push(TeamContact {
+        from: "planner".to_string(),
+        from_project: None,
+        to: "infra".to_string(),
+        to_project: Some("elsewhere".to_string()),
+        reason: String::new(),
+    });
+
+    let summary = TeamGraphBmc::import(&tc.ctx, &tc.mm, target, &graph)
+        .await
+        .unwrap();
+    assert_eq!(summary.agents_created.len(), 2);
+    assert_eq!(
+        summary.capabilities_granted.len(),
+        DEFAULT_CAPABILITIES.len() + 1
+    );
+    assert_eq!(summary.contacts_created, ["planner -> reviewer"]);
+    assert_eq!(summary.skipped.len(), 1);
+    assert!(summary.skipped[0].contains("project 'elsewhere' not found"));
+
+    let imported = AgentBmc::get_by_name(&tc.ctx, &tc.mm, target, "reviewer")
+        .await
+        .unwrap();
+    assert_eq!(imported.task_description, "reviewer duties");
+    assert!(
+        AgentCapabilityBmc::check(&tc.ctx, &tc.mm, imported.id, "code-review")
+            .await
+            .unwrap()
+    );
+    let contacts = AgentLinkBmc::list_contacts(&tc.ctx, &tc.mm, target, imported.id)
+        .await
+        .unwrap();
+    assert_eq!(contacts.len(), 1);
+
+    // Importing again changes nothing
+    let again = TeamGraphBmc::import(&tc.ctx, &tc.mm, target, &graph)
+        .await
+        .unwrap();
+    assert!(again.agents_created.is_empty());
+    assert!(again.capabilities_granted.is_empty());
+    assert!(again.contacts_created.is_empty());
+}
+
+#[tokio::test]
+async fn test_team_graph_import_rejects_bad_input() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "team-bad", "/team-bad")
+        .await
+        .unwrap();
+    let mut graph = TeamGraphBmc::export(&tc.ctx, &tc.mm, project)
+        .await
+        .unwrap();
+
+    graph.version = TEAM_GRAPH_VERSION + 1;
+    let result = TeamGraphBmc::import(&tc.ctx, &tc.mm, project, &graph).await;
+    assert!(matches!(result, Err(Error::InvalidInput(_))));
+}
@@ -0,0 +1,175 @@
+//! Thread archival tests
+//!
+//! Tests that inactive threads are archived and hidden from thread listings,
+//! get a closing summary when asked, and come back on a new reply.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::thread_archive::{ARCHIVIST_AGENT_NAME, ThreadArchiveBmc};
+use mouchak_mail_core::model::thread_read::ThreadReadBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn post(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    thread_id: &str,
+    body_md: &str,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: format!("About {}", thread_id),
+            body_md: body_md.to_string(),
+            thread_id: Some(thread_id.to_string()),
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn age_thread(tc: &TestContext, thread_id: &str, days: i64) {
+    tc.mm
+        .db_for_test()
+        .execute(
+            "UPDATE messages SET created_ts = datetime('now', ?) WHERE thread_id = ?",
+            (format!("-{} days", days), thread_id),
+        )
+        .await
+        .unwrap();
+}
+
+async fn listed(tc: &TestContext, project_id: ProjectId, include_archived: bool) -> Vec<String> {
+    let mut ids: Vec<String> =
+        MessageBmc::list_threads(&tc.ctx, &tc.mm, project_id, 50, include_archived)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.thread_id)
+            .collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn test_inactive_threads_archived_and_reactivated() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "archival", "/archival")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+
+    post(&tc, project_id, alice, bob, "old", "Kickoff").await;
+    post(&tc, project_id, bob, alice, "old", "Done").await;
+    post(&tc, project_id, alice, bob, "fresh", "Still going").await;
+    age_thread(&tc, "old", 40).await;
+    age_thread(&tc, "fresh", 2).await;
+
+    let archived = ThreadArchiveBmc::archive_inactive(&tc.ctx, &tc.mm, 30, false)
+        .await
+        .unwrap();
+    assert_eq!(archived.len(), 1);
+    assert_eq!(archived[0].thread_id, "old");
+    assert!(archived[0].summary_message_id.is_none());
+
+    // A second run finds nothing new
+    assert!(
+        ThreadArchiveBmc::archive_inactive(&tc.ctx, &tc.mm, 30, false)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    assert_eq!(listed(&tc, project_id, false).await, ["fresh"]);
+    assert_eq!(listed(&tc, project_id, true).await, ["fresh", "old"]);
+    let threads = MessageBmc::list_threads(&tc.ctx, &tc.mm, project_id, 50, true)
+        .await
+        .unwrap();
+    assert!(threads.iter().any(|t| t.thread_id == "old" && t.archived));
+    let unread = ThreadReadBmc::list_threads(&tc.ctx, &tc.mm, project_id, bob, 50, false)
+        .await
+        .unwrap();
+    assert_eq!(unread.len(), 1);
+    assert_eq!(unread[0].thread.thread_id, "fresh");
+
+    // Messages of an archived thread stay readable
+    assert_eq!(
+        MessageBmc::list_by_thread(&tc.ctx, &tc.mm, project_id, "old")
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // A new reply reactivates the thread
+    post(&tc, project_id, bob, alice, "old", "One more thing").await;
+    assert!(
+        ThreadArchiveBmc::get(&tc.ctx, &tc.mm, project_id, "old")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(listed(&tc, project_id, false).await, ["fresh", "old"]);
+}
+
+#[tokio::test]
+async fn test_closing_summary_posted_to_participants() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "summary", "/summary")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+
+    post(&tc, project_id, alice, bob, "release", "Cut the branch?").await;
+    post(&tc, project_id, bob, alice, "release", "Branch cut at v2.1").await;
+    age_thread(&tc, "release", 10).await;
+
+    let archived = ThreadArchiveBmc::archive_inactive(&tc.ctx, &tc.mm, 7, true)
+        .await
+        .unwrap();
+    assert_eq!(archived.len(), 1);
+    let summary_id = archived[0].summary_message_id.expect("closing summary");
+
+    // Posting the summary does not reactivate the thread
+    assert_eq!(
+        ThreadArchiveBmc::list(&tc.ctx, &tc.mm, project_id)
+            .await
+            .unwrap(),
+        archived
+    );
+
+    let summary = MessageBmc::get(&tc.ctx, &tc.mm, summary_id.into())
+        .await
+        .unwrap();
+    assert_eq!(summary.sender_name, ARCHIVIST_AGENT_NAME);
+    assert_eq!(summary.thread_id.as_deref(), Some("release"));
+    assert_eq!(summary.subject, "[ARCHIVED] About release");
+    assert!(summary.body_md.contains("after 7 days"));
+    assert!(summary.body_md.contains("Messages: 2"));
+    assert!(summary.body_md.contains("Participants: alice, bob"));
+    assert!(summary.body_md.contains("> Branch cut at v2.1"));
+
+    let mut recipients = MessageBmc::get_recipients(&tc.ctx, &tc.mm, MessageId::new(summary_id))
+        .await
+        .unwrap();
+    recipients.sort();
+    assert_eq!(recipients, ["alice", "bob"]);
+}
@@ -0,0 +1,181 @@
+//! Thread read position tests
+//!
+//! Tests for per-agent last-read tracking and unread counts per thread.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::thread_read::ThreadReadBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+struct Threads {
+    tc: TestContext,
+    project_id: ProjectId,
+    alice: AgentId,
+    bob: AgentId,
+}
+
+async fn setup() -> Threads {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "threads", "/threads")
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for name in ["alice", "bob"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        ids.push(id);
+    }
+    Threads {
+        tc,
+        project_id,
+        alice: ids[0],
+        bob: ids[1],
+    }
+}
+
+impl Threads {
+    async fn send(&self, from: AgentId, to: AgentId, thread_id: &str, subject: &str) -> MessageId {
+        MessageBmc::create(
+            &self.tc.ctx,
+            &self.tc.mm,
+            MessageForCreate {
+                project_id: self.project_id,
+                sender_id: from,
+                recipient_ids: vec![to],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.to_string(),
+                body_md: "body".to_string(),
+                thread_id: Some(thread_id.to_string()),
+                importance: None,
+                ack_required: false,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn unread_counts(&self, agent: AgentId) -> Vec<(String, usize)> {
+        ThreadReadBmc::list_threads(&self.tc.ctx, &self.tc.mm, self.project_id, agent, 50, false)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.thread.thread_id, t.unread_count))
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn test_unread_counts_follow_read_position() {
+    let threads = setup().await;
+    threads
+        .send(threads.alice, threads.bob, "T-1", "design")
+        .await;
+    threads
+        .send(threads.bob, threads.alice, "T-1", "Re: design")
+        .await;
+    threads
+        .send(threads.alice, threads.bob, "T-2", "build")
+        .await;
+
+    // Own messages never count as unread
+    let mut counts = threads.unread_counts(threads.bob).await;
+    counts.sort();
+    assert_eq!(counts, [("T-1".to_string(), 1), ("T-2".to_string(), 1)]);
+
+    let position = ThreadReadBmc::mark_all_read(
+        &threads.tc.ctx,
+        &threads.tc.mm,
+        threads.project_id,
+        threads.bob,
+        "T-1",
+    )
+    .await
+    .unwrap();
+    assert!(position.is_some());
+
+    let mut counts = threads.unread_counts(threads.bob).await;
+    counts.sort();
+    assert_eq!(counts, [("T-1".to_string(), 0), ("T-2".to_string(), 1)]);
+
+    // A new message in a read thread is unread again
+    threads
+        .send(threads.alice, threads.bob, "T-1", "Re: design")
+        .await;
+    let counts = threads.unread_counts(threads.bob).await;
+    assert!(counts.contains(&("T-1".to_string(), 1)));
+}
+
+#[tokio::test]
+async fn test_list_unread_returns_messages_since_last_read() {
+    let threads = setup().await;
+    let first = threads
+        .send(threads.alice, threads.bob, "T-1", "design")
+        .await;
+    let second = threads
+        .send(threads.alice, threads.bob, "T-1", "Re: design")
+        .await;
+
+    let ctx = &threads.tc.ctx;
+    let mm = &threads.tc.mm;
+    let unread = ThreadReadBmc::list_unread(ctx, mm, threads.project_id, threads.bob, "T-1")
+        .await
+        .unwrap();
+    assert_eq!(unread.len(), 2);
+
+    ThreadReadBmc::mark_read(ctx, mm, threads.project_id, threads.bob, "T-1", first)
+        .await
+        .unwrap();
+    let unread = ThreadReadBmc::list_unread(ctx, mm, threads.project_id, threads.bob, "T-1")
+        .await
+        .unwrap();
+    assert_eq!(unread.len(), 1);
+    assert_eq!(unread[0].id, second.get());
+
+    // Positions only move forward
+    ThreadReadBmc::mark_read(ctx, mm, threads.project_id, threads.bob, "T-1", second)
+        .await
+        .unwrap();
+    ThreadReadBmc::mark_read(ctx, mm, threads.project_id, threads.bob, "T-1", first)
+        .await
+        .unwrap();
+    assert_eq!(
+        ThreadReadBmc::get_position(ctx, mm, threads.project_id, threads.bob, "T-1")
+            .await
+            .unwrap(),
+        Some(second)
+    );
+    assert!(
+        ThreadReadBmc::list_unread(ctx, mm, threads.project_id, threads.bob, "T-1")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // Read positions are per agent
+    assert_eq!(
+        ThreadReadBmc::get_position(ctx, mm, threads.project_id, threads.alice, "T-1")
+            .await
+            .unwrap(),
+        None
+    );
+}
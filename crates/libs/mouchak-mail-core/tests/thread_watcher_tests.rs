@@ -0,0 +1,163 @@
+//! Thread watcher tests
+//!
+//! Tests that watchers receive flagged copies of new thread messages and
+//! that participants' contact policies can refuse them.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentProfileUpdate};
+use mouchak_mail_core::model::agent_link::{AgentLinkBmc, AgentLinkForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::thread_watcher::ThreadWatcherBmc;
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    to: AgentId,
+    thread_id: &str,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Migration plan".to_string(),
+            body_md: "Step one".to_string(),
+            thread_id: Some(thread_id.to_string()),
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn set_contact_policy(tc: &TestContext, agent_id: AgentId, policy: &str) {
+    AgentBmc::update_profile(
+        &tc.ctx,
+        &tc.mm,
+        agent_id,
+        AgentProfileUpdate {
+            task_description: None,
+            attachments_policy: None,
+            contact_policy: Some(policy.to_string()),
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_watcher_receives_flagged_copies() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "watch", "/watch")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+    let qa = create_agent(&tc, project_id, "qa").await;
+
+    let before = send(&tc, project_id, alice, bob, "T-1").await;
+    assert!(
+        ThreadWatcherBmc::watch(&tc.ctx, &tc.mm, project_id, "T-1", qa)
+            .await
+            .unwrap()
+    );
+    assert!(
+        !ThreadWatcherBmc::watch(&tc.ctx, &tc.mm, project_id, "T-1", qa)
+            .await
+            .unwrap()
+    );
+    let watchers = ThreadWatcherBmc::list_watchers(&tc.ctx, &tc.mm, project_id, "T-1")
+        .await
+        .unwrap();
+    assert_eq!(watchers.len(), 1);
+    assert_eq!(watchers[0].agent_name, "qa");
+
+    let after = send(&tc, project_id, bob, alice, "T-1").await;
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, qa, 10)
+        .await
+        .unwrap();
+    let ids: Vec<i64> = inbox.iter().map(|m| m.id).collect();
+    assert_eq!(ids, [after.get()]);
+    let flagged =
+        ThreadWatcherBmc::watcher_deliveries(&tc.ctx, &tc.mm, qa, &[before.get(), after.get()])
+            .await
+            .unwrap();
+    assert!(flagged.contains(&after.get()) && !flagged.contains(&before.get()));
+    // Addressed recipients do not get a second copy
+    let alice_flagged =
+        ThreadWatcherBmc::watcher_deliveries(&tc.ctx, &tc.mm, alice, &[before.get(), after.get()])
+            .await
+            .unwrap();
+    assert!(alice_flagged.is_empty());
+
+    assert!(
+        ThreadWatcherBmc::unwatch(&tc.ctx, &tc.mm, project_id, "T-1", qa)
+            .await
+            .unwrap()
+    );
+    send(&tc, project_id, alice, bob, "T-1").await;
+    let inbox = MessageBmc::list_inbox_for_agent(&tc.ctx, &tc.mm, project_id, qa, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+}
+
+#[tokio::test]
+async fn test_contact_policy_refuses_watchers() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "watch-policy", "/watch-policy")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project_id, "alice").await;
+    let bob = create_agent(&tc, project_id, "bob").await;
+    let qa = create_agent(&tc, project_id, "qa").await;
+    send(&tc, project_id, alice, bob, "T-2").await;
+
+    let missing = ThreadWatcherBmc::watch(&tc.ctx, &tc.mm, project_id, "nope", qa).await;
+    assert!(matches!(missing, Err(Error::NotFound)));
+
+    set_contact_policy(&tc, bob, "deny").await;
+    let denied = ThreadWatcherBmc::watch(&tc.ctx, &tc.mm, project_id, "T-2", qa).await;
+    assert!(matches!(denied, Err(Error::ContactDenied(_))));
+
+    // `manual` accepts watchers it has an accepted contact with
+    set_contact_policy(&tc, bob, "manual").await;
+    let denied = ThreadWatcherBmc::watch(&tc.ctx, &tc.mm, project_id, "T-2", qa).await;
+    assert!(matches!(denied, Err(Error::ContactDenied(_))));
+    let link_id = AgentLinkBmc::request_contact(
+        &tc.ctx,
+        &tc.mm,
+        AgentLinkForCreate {
+            a_project_id: project_id.get(),
+            a_agent_id: qa.get(),
+            b_project_id: project_id.get(),
+            b_agent_id: bob.get(),
+            reason: "QA".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    AgentLinkBmc::respond_contact(&tc.ctx, &tc.mm, link_id, true)
+        .await
+        .unwrap();
+    assert!(
+        ThreadWatcherBmc::watch(&tc.ctx, &tc.mm, project_id, "T-2", qa)
+            .await
+            .unwrap()
+    );
+}
@@ -0,0 +1,211 @@
+//! Ticket linking tests
+//!
+//! Tests linking Jira/Linear tickets to messages, the metadata cache and
+//! which tickets count as stale.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::ticket::{TicketBmc, TicketMetadata, TicketProvider};
+use mouchak_mail_core::types::{AgentId, MessageId};
+
+/// Two messages in project `tickets`.
+async fn setup() -> (TestContext, Vec<MessageId>) {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "tickets", "/tickets")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["BlueLake", "GreenCastle"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id.get());
+    }
+    let mut messages = Vec::new();
+    for subject in ["First", "Second"] {
+        let id = MessageBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            MessageForCreate {
+                project_id,
+                sender_id: (agents[0]).into(),
+                recipient_ids: vec![AgentId::new(agents[1])],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.to_string(),
+                body_md: "body".to_string(),
+                thread_id: None,
+                importance: None,
+                ack_required: false,
+            },
+        )
+        .await
+        .unwrap();
+        messages.push(id);
+    }
+    (tc, messages)
+}
+
+#[tokio::test]
+async fn test_link_shares_one_ticket_across_messages() {
+    let (tc, messages) = setup().await;
+
+    let first = TicketBmc::link(
+        &tc.ctx,
+        &tc.mm,
+        MessageId::new((messages[0]).get()),
+        TicketProvider::Jira,
+        "proj-1",
+    )
+    .await
+    .unwrap();
+    assert_eq!(first.ticket_key, "PROJ-1");
+    assert!(first.fetched_ts.is_none());
+    let again = TicketBmc::link(
+        &tc.ctx,
+        &tc.mm,
+        MessageId::new((messages[0]).get()),
+        TicketProvider::Jira,
+        "PROJ-1",
+    )
+    .await
+    .unwrap();
+    let shared = TicketBmc::link(
+        &tc.ctx,
+        &tc.mm,
+        MessageId::new((messages[1]).get()),
+        TicketProvider::Jira,
+        "PROJ-1",
+    )
+    .await
+    .unwrap();
+    assert_eq!(again.id, first.id);
+    assert_eq!(shared.id, first.id);
+    // Same key, other provider: a different ticket
+    TicketBmc::link(
+        &tc.ctx,
+        &tc.mm,
+        MessageId::new((messages[0]).get()),
+        TicketProvider::Linear,
+        "PROJ-1",
+    )
+    .await
+    .unwrap();
+
+    let ids: Vec<i64> = messages.iter().map(|id| id.get()).collect();
+    let linked = TicketBmc::list_for_messages(&tc.ctx, &tc.mm, &ids)
+        .await
+        .unwrap();
+    assert_eq!(linked[&ids[0]].len(), 2);
+    assert_eq!(linked[&ids[1]].len(), 1);
+
+    assert!(matches!(
+        TicketBmc::link(
+            &tc.ctx,
+            &tc.mm,
+            MessageId::new((messages[0]).get()),
+            TicketProvider::Jira,
+            "nope"
+        )
+        .await,
+        Err(Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        TicketBmc::link(
+            &tc.ctx,
+            &tc.mm,
+            MessageId::new(999_999),
+            TicketProvider::Jira,
+            "PROJ-1"
+        )
+        .await,
+        Err(Error::MessageNotFound(999_999))
+    ));
+}
+
+#[tokio::test]
+async fn test_metadata_cache_and_staleness() {
+    let (tc, messages) = setup().await;
+    let jira = TicketBmc::link(
+        &tc.ctx,
+        &tc.mm,
+        MessageId::new((messages[0]).get()),
+        TicketProvider::Jira,
+        "PROJ-1",
+    )
+    .await
+    .unwrap();
+    TicketBmc::link(
+        &tc.ctx,
+        &tc.mm,
+        MessageId::new((messages[0]).get()),
+        TicketProvider::Linear,
+        "ENG-2",
+    )
+    .await
+    .unwrap();
+
+    // Only configured providers are listed
+    let stale = TicketBmc::list_stale(&tc.ctx, &tc.mm, &[TicketProvider::Jira], 300, 10)
+        .await
+        .unwrap();
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0].id, jira.id);
+    assert!(
+        TicketBmc::list_stale(&tc.ctx, &tc.mm, &[], 300, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let metadata = TicketMetadata {
+        title: "Fix the parser".to_string(),
+        status: "In Progress".to_string(),
+        url: Some("https://acme.atlassian.net/browse/PROJ-1".to_string()),
+    };
+    let ticket = TicketBmc::update_metadata(&tc.ctx, &tc.mm, jira.id, &metadata)
+        .await
+        .unwrap();
+    assert_eq!(ticket.summary(), "jira:PROJ-1 [In Progress] Fix the parser");
+    assert!(
+        TicketBmc::list_stale(&tc.ctx, &tc.mm, &[TicketProvider::Jira], 300, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // A failed refresh keeps the last known metadata
+    let ticket = TicketBmc::record_fetch_error(&tc.ctx, &tc.mm, jira.id, "Jira returned 503")
+        .await
+        .unwrap();
+    assert_eq!(ticket.status.as_deref(), Some("In Progress"));
+    assert_eq!(ticket.fetch_error.as_deref(), Some("Jira returned 503"));
+    assert_eq!(
+        ticket.summary(),
+        "jira:PROJ-1 [In Progress] Fix the parser (refresh failed)"
+    );
+
+    let ticket = TicketBmc::update_metadata(&tc.ctx, &tc.mm, jira.id, &metadata)
+        .await
+        .unwrap();
+    assert!(ticket.fetch_error.is_none());
+}
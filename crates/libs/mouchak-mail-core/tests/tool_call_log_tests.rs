@@ -0,0 +1,86 @@
+//! Tool-call log tests
+//!
+//! Tests that tool-call records are committed to the Git archive in
+//! batches, one JSON line per call.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use chrono::Utc;
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::store::git_store;
+use mouchak_mail_core::utils::tool_call_log::ToolCallRecord;
+
+fn record(tool: &str, project_slug: Option<&str>) -> ToolCallRecord {
+    ToolCallRecord {
+        ts: Utc::now(),
+        tool: tool.to_string(),
+        project_slug: project_slug.map(str::to_string),
+        agent_name: Some("BlueLake".to_string()),
+        duration_ms: 42,
+        status: "success".to_string(),
+        error_code: None,
+    }
+}
+
+fn head_message(tc: &TestContext) -> String {
+    let repo = git_store::open_repo(tc.repo_root()).unwrap();
+    let commit = repo.head().unwrap().peel_to_commit().unwrap();
+    commit.message().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_disabled_by_default() {
+    let tc = TestContext::new().await.unwrap();
+    assert!(tc.mm.tool_call_log().is_none());
+}
+
+#[tokio::test]
+async fn test_records_are_committed_in_batches() {
+    let mut config = AppConfig::default();
+    config.tool_call_log.enabled = true;
+    config.tool_call_log.batch_size = 2;
+    config.tool_call_log.flush_interval_seconds = 3600;
+    let tc = TestContext::new_with_config(config).await.unwrap();
+    ProjectBmc::create(&tc.ctx, &tc.mm, "backend", "/backend")
+        .await
+        .unwrap();
+    let log = tc.mm.tool_call_log().unwrap();
+
+    // One project call and one global call fill a batch
+    let project_call = record("send_message", Some("backend"));
+    let global_call = record("health_check", None);
+    log.record(project_call.clone());
+    log.record(global_call.clone());
+    // The batch was committed before the flush was handled
+    assert_eq!(log.flush().await.unwrap(), 0);
+    assert_eq!(head_message(&tc), "tool-calls: batch of 2");
+
+    let repo = git_store::open_repo(tc.repo_root()).unwrap();
+    let content = git_store::read_file_content(&repo, project_call.archive_path()).unwrap();
+    let archived: ToolCallRecord = serde_json::from_str(content.trim()).unwrap();
+    assert_eq!(archived, project_call);
+    assert!(
+        !git_store::read_file_content(&repo, global_call.archive_path())
+            .unwrap()
+            .contains("backend")
+    );
+
+    // A partial batch waits for a flush and appends to the daily file
+    log.record(record("fetch_inbox", Some("backend")));
+    assert_eq!(log.flush().await.unwrap(), 1);
+    assert_eq!(head_message(&tc), "tool-calls: batch of 1");
+    let content = git_store::read_file_content(&repo, project_call.archive_path()).unwrap();
+    let tools: Vec<String> = content
+        .lines()
+        .map(|line| serde_json::from_str::<ToolCallRecord>(line).unwrap().tool)
+        .collect();
+    assert_eq!(tools, ["send_message", "fetch_inbox"]);
+
+    assert_eq!(log.flush().await.unwrap(), 0);
+}
@@ -106,7 +106,7 @@ async fn test_list_recent_for_project() {
             .expect("Failed to create metric");
     }
 
-    let metrics = ToolMetricBmc::list_recent(&tc.ctx, &tc.mm, Some(project_id.get()), 10)
+    let metrics = ToolMetricBmc::list_recent(&tc.ctx, &tc.mm, Some(project_id), 10)
         .await
         .expect("Failed to list metrics");
 
@@ -139,7 +139,7 @@ async fn test_list_recent_with_limit() {
     }
 
     // Limit to 3
-    let metrics = ToolMetricBmc::list_recent(&tc.ctx, &tc.mm, Some(project_id.get()), 3)
+    let metrics = ToolMetricBmc::list_recent(&tc.ctx, &tc.mm, Some(project_id), 3)
         .await
         .expect("Failed to list metrics");
 
@@ -235,7 +235,7 @@ async fn test_get_stats_for_project() {
         .await
         .expect("Failed to create metric");
 
-    let stats = ToolMetricBmc::get_stats(&tc.ctx, &tc.mm, Some(project_id.get()))
+    let stats = ToolMetricBmc::get_stats(&tc.ctx, &tc.mm, Some(project_id))
         .await
         .expect("Failed to get stats");
 
@@ -322,7 +322,7 @@ async fn test_tool_metric_with_agent() {
         .await
         .expect("Failed to create metric");
 
-    let metrics = ToolMetricBmc::list_recent(&tc.ctx, &tc.mm, Some(project_id.get()), 1)
+    let metrics = ToolMetricBmc::list_recent(&tc.ctx, &tc.mm, Some(project_id), 1)
         .await
         .expect("Failed to list metrics");
 
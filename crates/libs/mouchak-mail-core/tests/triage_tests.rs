@@ -0,0 +1,189 @@
+//! Inbox triage cursor tests
+//!
+//! Tests for next/previous navigation over unread and pending-ack messages.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::triage::{TriageBmc, TriageDirection, TriageQuery, TriageState};
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+struct Inbox {
+    tc: TestContext,
+    project_id: ProjectId,
+    sender: AgentId,
+    reader: AgentId,
+}
+
+async fn setup() -> Inbox {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "triage", "/triage")
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for name in ["sender", "reader"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        ids.push(id);
+    }
+    Inbox {
+        tc,
+        project_id,
+        sender: ids[0],
+        reader: ids[1],
+    }
+}
+
+impl Inbox {
+    async fn send(&self, subject: &str, importance: &str, ack_required: bool) -> MessageId {
+        MessageBmc::create(
+            &self.tc.ctx,
+            &self.tc.mm,
+            MessageForCreate {
+                project_id: self.project_id,
+                sender_id: self.sender,
+                recipient_ids: vec![self.reader],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.to_string(),
+                body_md: "body".to_string(),
+                thread_id: None,
+                importance: Some(importance.to_string()),
+                ack_required,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn step(&self, query: TriageQuery) -> (Option<i64>, i64, i64) {
+        let step = TriageBmc::step(
+            &self.tc.ctx,
+            &self.tc.mm,
+            self.project_id,
+            self.reader,
+            &query,
+        )
+        .await
+        .unwrap();
+        (step.item.map(|i| i.message.id), step.remaining, step.total)
+    }
+}
+
+#[tokio::test]
+async fn test_triage_walks_unread_messages_in_inbox_order() {
+    let inbox = setup().await;
+    let first = inbox.send("first", "normal", false).await;
+    let second = inbox.send("second", "normal", false).await;
+    let third = inbox.send("third", "normal", false).await;
+    MessageBmc::mark_read(&inbox.tc.ctx, &inbox.tc.mm, second, inbox.reader)
+        .await
+        .unwrap();
+
+    let unread = |from_message_id, direction| TriageQuery {
+        from_message_id,
+        direction,
+        state: TriageState::Unread,
+        ..Default::default()
+    };
+
+    // Newest first; the read message is skipped
+    assert_eq!(
+        inbox.step(unread(None, TriageDirection::Next)).await,
+        (Some(third.get()), 1, 2)
+    );
+    assert_eq!(
+        inbox
+            .step(unread(Some(third.get()), TriageDirection::Next))
+            .await,
+        (Some(first.get()), 0, 2)
+    );
+    assert_eq!(
+        inbox
+            .step(unread(Some(first.get()), TriageDirection::Next))
+            .await,
+        (None, 0, 2)
+    );
+
+    // Moving back from a message that no longer matches still works
+    assert_eq!(
+        inbox
+            .step(unread(Some(second.get()), TriageDirection::Previous))
+            .await,
+        (Some(third.get()), 0, 2)
+    );
+    assert_eq!(
+        inbox.step(unread(None, TriageDirection::Previous)).await,
+        (Some(first.get()), 1, 2)
+    );
+}
+
+#[tokio::test]
+async fn test_triage_pending_ack_and_filters() {
+    let inbox = setup().await;
+    let needs_ack = inbox.send("please ack", "high", true).await;
+    let plain = inbox.send("fyi", "normal", false).await;
+    MessageBmc::mark_read(&inbox.tc.ctx, &inbox.tc.mm, needs_ack, inbox.reader)
+        .await
+        .unwrap();
+
+    // Read but unacknowledged messages still need triage
+    let pending = TriageQuery {
+        state: TriageState::PendingAck,
+        ..Default::default()
+    };
+    assert_eq!(inbox.step(pending).await, (Some(needs_ack.get()), 0, 1));
+    assert_eq!(
+        inbox.step(TriageQuery::default()).await,
+        (Some(plain.get()), 1, 2)
+    );
+
+    let high_only = TriageQuery {
+        importance: Some("high".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(inbox.step(high_only).await, (Some(needs_ack.get()), 0, 1));
+
+    MessageBmc::acknowledge(&inbox.tc.ctx, &inbox.tc.mm, needs_ack, inbox.reader)
+        .await
+        .unwrap();
+    assert_eq!(
+        inbox.step(TriageQuery::default()).await,
+        (Some(plain.get()), 0, 1)
+    );
+}
+
+#[tokio::test]
+async fn test_triage_unknown_message_is_an_error() {
+    let inbox = setup().await;
+    let result = TriageBmc::step(
+        &inbox.tc.ctx,
+        &inbox.tc.mm,
+        inbox.project_id,
+        inbox.reader,
+        &TriageQuery {
+            from_message_id: Some(9999),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(result.is_err());
+}
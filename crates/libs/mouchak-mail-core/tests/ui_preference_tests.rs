@@ -0,0 +1,113 @@
+//! UI preference tests
+//!
+//! Tests for the per-user/per-agent preference store.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::model::ui_preference::{
+    MAX_PREFERENCES, MAX_VALUE_BYTES, PreferenceOwner, Preferences, UiPreferenceBmc,
+};
+use mouchak_mail_core::types::AgentId;
+use serde_json::json;
+
+fn prefs(value: serde_json::Value) -> Preferences {
+    value.as_object().unwrap().clone()
+}
+
+#[tokio::test]
+async fn test_preferences_merge_and_delete() {
+    let tc = TestContext::new().await.unwrap();
+    let owner = PreferenceOwner::User("alice".to_string());
+
+    assert!(
+        UiPreferenceBmc::get(&tc.ctx, &tc.mm, &owner)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    UiPreferenceBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        &owner,
+        prefs(json!({"theme": "dark", "inbox.columns": ["from", "subject"]})),
+    )
+    .await
+    .unwrap();
+
+    let updated = UiPreferenceBmc::update(
+        &tc.ctx,
+        &tc.mm,
+        &owner,
+        prefs(json!({"theme": null, "default_project": "backend"})),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        updated,
+        prefs(json!({"inbox.columns": ["from", "subject"], "default_project": "backend"}))
+    );
+
+    let stored = UiPreferenceBmc::get(&tc.ctx, &tc.mm, &owner).await.unwrap();
+    assert_eq!(stored, updated);
+}
+
+#[tokio::test]
+async fn test_preferences_are_isolated_per_owner() {
+    let tc = TestContext::new().await.unwrap();
+    let user = PreferenceOwner::User("alice".to_string());
+    let agent = PreferenceOwner::Agent(AgentId::new(7));
+
+    UiPreferenceBmc::update(&tc.ctx, &tc.mm, &user, prefs(json!({"theme": "dark"})))
+        .await
+        .unwrap();
+    UiPreferenceBmc::update(&tc.ctx, &tc.mm, &agent, prefs(json!({"theme": "light"})))
+        .await
+        .unwrap();
+
+    let user_prefs = UiPreferenceBmc::get(&tc.ctx, &tc.mm, &user).await.unwrap();
+    let agent_prefs = UiPreferenceBmc::get(&tc.ctx, &tc.mm, &agent).await.unwrap();
+    assert_eq!(user_prefs["theme"], "dark");
+    assert_eq!(agent_prefs["theme"], "light");
+    assert_eq!(agent.key(), "agent:7");
+}
+
+#[tokio::test]
+async fn test_invalid_preferences_are_rejected() {
+    let tc = TestContext::new().await.unwrap();
+    let owner = PreferenceOwner::User("local".to_string());
+
+    for changes in [
+        json!({"bad key": 1}),
+        json!({"": 1}),
+        json!({"theme": "x".repeat(MAX_VALUE_BYTES)}),
+    ] {
+        assert!(
+            UiPreferenceBmc::update(&tc.ctx, &tc.mm, &owner, prefs(changes))
+                .await
+                .is_err()
+        );
+    }
+
+    let too_many: Preferences = (0..=MAX_PREFERENCES)
+        .map(|i| (format!("k{}", i), json!(i)))
+        .collect();
+    assert!(
+        UiPreferenceBmc::update(&tc.ctx, &tc.mm, &owner, too_many)
+            .await
+            .is_err()
+    );
+
+    // Rejected updates write nothing
+    assert!(
+        UiPreferenceBmc::get(&tc.ctx, &tc.mm, &owner)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
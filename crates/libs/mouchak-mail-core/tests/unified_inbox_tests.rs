@@ -17,6 +17,7 @@ use crate::common::TestContext;
 use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
 use mouchak_mail_core::model::message::{ImportanceFilter, MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::AgentId;
 use mouchak_mail_core::utils::slugify;
 
 /// Helper to set up a project with agents for testing
@@ -73,9 +74,9 @@ async fn test_list_unified_inbox_returns_all_projects() {
 
     // Send message in project 1
     let msg1_c = MessageForCreate {
-        project_id: project1_id,
-        sender_id: sender1_id,
-        recipient_ids: vec![recipient1_id],
+        project_id: project1_id.into(),
+        sender_id: sender1_id.into(),
+        recipient_ids: vec![AgentId::new(recipient1_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Project 1 Message".to_string(),
@@ -88,9 +89,9 @@ async fn test_list_unified_inbox_returns_all_projects() {
 
     // Send message in project 2
     let msg2_c = MessageForCreate {
-        project_id: project2_id,
-        sender_id: sender2_id,
-        recipient_ids: vec![recipient2_id],
+        project_id: project2_id.into(),
+        sender_id: sender2_id.into(),
+        recipient_ids: vec![AgentId::new(recipient2_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Project 2 Message".to_string(),
@@ -129,9 +130,9 @@ async fn test_list_unified_inbox_filters_by_high_importance() {
 
     // Send high importance message
     let high_msg = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "URGENT Task".to_string(),
@@ -144,9 +145,9 @@ async fn test_list_unified_inbox_filters_by_high_importance() {
 
     // Send normal importance message
     let normal_msg = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![recipient_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(recipient_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Regular Update".to_string(),
@@ -185,9 +186,9 @@ async fn test_list_unified_inbox_respects_limit() {
     // Send 5 messages
     for i in 1..=5 {
         let msg = MessageForCreate {
-            project_id,
-            sender_id,
-            recipient_ids: vec![recipient_id],
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(recipient_id)],
             cc_ids: None,
             bcc_ids: None,
             subject: format!("Message {}", i),
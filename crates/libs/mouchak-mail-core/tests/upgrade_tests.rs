@@ -0,0 +1,100 @@
+//! Data format upgrade tests
+//!
+//! Tests for format version stamping, upgrade planning and pre-upgrade snapshots.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use libsql::{Builder, Connection};
+use mouchak_mail_core::store::upgrade::{
+    DATA_FORMAT_VERSION, migrate, plan, read_format_version, snapshot,
+};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+async fn open(path: &Path) -> Connection {
+    Builder::new_local(path)
+        .build()
+        .await
+        .unwrap()
+        .connect()
+        .unwrap()
+}
+
+fn db_path(dir: &TempDir) -> PathBuf {
+    dir.path().join("mouchak_mail.db")
+}
+
+#[tokio::test]
+async fn test_fresh_database_is_stamped() {
+    let dir = TempDir::new().unwrap();
+    let conn = open(&db_path(&dir)).await;
+
+    let before = plan(&conn, &db_path(&dir)).await.unwrap();
+    assert!(before.fresh);
+    assert!(before.needs_upgrade());
+    assert_eq!(before.pending_migrations.len() as i64, DATA_FORMAT_VERSION);
+
+    migrate(&conn).await.unwrap();
+    assert_eq!(
+        read_format_version(&conn).await.unwrap(),
+        DATA_FORMAT_VERSION
+    );
+
+    let after = plan(&conn, &db_path(&dir)).await.unwrap();
+    assert!(!after.fresh);
+    assert!(!after.needs_upgrade());
+    assert!(after.pending_migrations.is_empty());
+    assert!(after.describe().contains("up to date"));
+}
+
+#[tokio::test]
+async fn test_older_format_lists_pending_migrations_and_snapshots() {
+    let dir = TempDir::new().unwrap();
+    let path = db_path(&dir);
+    let conn = open(&path).await;
+    migrate(&conn).await.unwrap();
+    conn.execute("PRAGMA user_version = 3", ()).await.unwrap();
+
+    let older = plan(&conn, &path).await.unwrap();
+    assert!(!older.fresh);
+    assert!(older.needs_upgrade());
+    assert_eq!(older.current_version, 3);
+    assert_eq!(older.pending_migrations[0], "004_attachments");
+    assert_eq!(
+        older.pending_migrations.len() as i64,
+        DATA_FORMAT_VERSION - 3
+    );
+
+    let snapshot_path = snapshot(&conn, &older).await.unwrap();
+    assert!(snapshot_path.exists());
+    assert!(
+        snapshot_path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("mouchak_mail.db.pre-upgrade-v3-")
+    );
+    // The snapshot keeps the old stamp, so it can be restored as-is
+    let snapshot_conn = open(&snapshot_path).await;
+    assert_eq!(read_format_version(&snapshot_conn).await.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn test_newer_format_is_detected() {
+    let dir = TempDir::new().unwrap();
+    let path = db_path(&dir);
+    let conn = open(&path).await;
+    migrate(&conn).await.unwrap();
+    conn.execute(
+        &format!("PRAGMA user_version = {}", DATA_FORMAT_VERSION + 1),
+        (),
+    )
+    .await
+    .unwrap();
+
+    let newer = plan(&conn, &path).await.unwrap();
+    assert!(newer.is_newer());
+    assert!(!newer.needs_upgrade());
+    assert!(newer.describe().contains("newer mouchak-mail"));
+}
@@ -0,0 +1,285 @@
+//! Web Push tests
+//!
+//! Tests for push subscriptions, VAPID keys and the urgent-message cursor,
+//! including held messages that are pushed once approved.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_core::model::approval::{ApprovalBmc, ApprovalRuleForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::web_push::{
+    MAX_PUSH_FAILURES, PushSubscriptionForCreate, WebPushBmc, vapid_keys_from_private_key,
+};
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(
+    tc: &TestContext,
+    project_id: ProjectId,
+    from: AgentId,
+    importance: &str,
+) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![from],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: format!("{} update", importance),
+            body_md: "body".to_string(),
+            thread_id: None,
+            importance: Some(importance.to_string()),
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn subscription(endpoint: &str, project_id: Option<i64>) -> PushSubscriptionForCreate {
+    PushSubscriptionForCreate {
+        endpoint: endpoint.to_string(),
+        p256dh: "BPkey".to_string(),
+        auth: "authsecret".to_string(),
+        project_id,
+        label: "laptop".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_pending_notices_skip_existing_mail_and_non_urgent() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "push-cursor", "/push/cursor")
+        .await
+        .unwrap();
+    let sender = create_agent(&tc, project_id, "sender").await;
+
+    // Sent before push state exists: never replayed
+    send(&tc, project_id, sender, "urgent").await;
+    assert_eq!(WebPushBmc::count_pending(&tc.ctx, &tc.mm).await.unwrap(), 0);
+    assert!(
+        WebPushBmc::pending_notices(&tc.ctx, &tc.mm, 10)
+            .await
+            .unwrap()
+            .notices
+            .is_empty()
+    );
+
+    send(&tc, project_id, sender, "normal").await;
+    let urgent_id = send(&tc, project_id, sender, "urgent").await;
+    assert_eq!(WebPushBmc::count_pending(&tc.ctx, &tc.mm).await.unwrap(), 1);
+
+    let notices = WebPushBmc::pending_notices(&tc.ctx, &tc.mm, 10)
+        .await
+        .unwrap()
+        .notices;
+    assert_eq!(notices.len(), 1);
+    assert_eq!(notices[0].message_id, urgent_id.get());
+    assert_eq!(notices[0].project_slug, "push-cursor");
+    assert_eq!(notices[0].payload()["title"], "[urgent] urgent update");
+
+    WebPushBmc::advance_cursor(&tc.ctx, &tc.mm, urgent_id.get())
+        .await
+        .unwrap();
+    assert!(
+        WebPushBmc::pending_notices(&tc.ctx, &tc.mm, 10)
+            .await
+            .unwrap()
+            .notices
+            .is_empty()
+    );
+    assert_eq!(WebPushBmc::count_pending(&tc.ctx, &tc.mm).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_pending_notices_wait_for_approval() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "push-held", "/push/held")
+        .await
+        .unwrap();
+    let sender = create_agent(&tc, project_id, "sender").await;
+    let lead = create_agent(&tc, project_id, "lead").await;
+    ApprovalBmc::create_rule(
+        &tc.ctx,
+        &tc.mm,
+        ApprovalRuleForCreate {
+            project_id,
+            min_importance: Some("urgent".to_string()),
+            min_recipients: None,
+            keyword: None,
+            approvers: vec!["lead".to_string()],
+            timeout_seconds: 3600,
+        },
+    )
+    .await
+    .unwrap();
+    WebPushBmc::pending_notices(&tc.ctx, &tc.mm, 10)
+        .await
+        .unwrap();
+
+    let approved = send(&tc, project_id, sender, "urgent").await;
+    let rejected = send(&tc, project_id, sender, "urgent").await;
+
+    // Held messages are not pushed, but the cursor moves past them
+    let batch = WebPushBmc::pending_notices(&tc.ctx, &tc.mm, 10)
+        .await
+        .unwrap();
+    assert!(batch.notices.is_empty());
+    assert_eq!(batch.scanned_to, Some(rejected.get()));
+    WebPushBmc::advance_cursor(&tc.ctx, &tc.mm, rejected.get())
+        .await
+        .unwrap();
+
+    ApprovalBmc::decide(&tc.ctx, &tc.mm, approved, Some(lead), true, None)
+        .await
+        .unwrap();
+    ApprovalBmc::decide(&tc.ctx, &tc.mm, rejected, Some(lead), false, None)
+        .await
+        .unwrap();
+    let batch = WebPushBmc::pending_notices(&tc.ctx, &tc.mm, 10)
+        .await
+        .unwrap();
+    let ids: Vec<i64> = batch.notices.iter().map(|n| n.message_id).collect();
+    assert_eq!(ids, [approved.get()]);
+
+    WebPushBmc::advance_cursor(&tc.ctx, &tc.mm, batch.scanned_to.unwrap())
+        .await
+        .unwrap();
+    assert!(
+        WebPushBmc::pending_notices(&tc.ctx, &tc.mm, 10)
+            .await
+            .unwrap()
+            .notices
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_subscriptions_are_scoped_and_upserted() {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "push-subs", "/push/subs")
+        .await
+        .unwrap()
+        .get();
+    let other_id = ProjectBmc::create(&tc.ctx, &tc.mm, "push-other", "/push/other")
+        .await
+        .unwrap()
+        .get();
+
+    let global = WebPushBmc::subscribe(
+        &tc.ctx,
+        &tc.mm,
+        subscription("https://push.example/a", None),
+    )
+    .await
+    .unwrap();
+    WebPushBmc::subscribe(
+        &tc.ctx,
+        &tc.mm,
+        subscription("https://push.example/b", Some(project_id)),
+    )
+    .await
+    .unwrap();
+    WebPushBmc::subscribe(
+        &tc.ctx,
+        &tc.mm,
+        subscription("https://push.example/c", Some(other_id)),
+    )
+    .await
+    .unwrap();
+
+    let subs = WebPushBmc::list_subscriptions(&tc.ctx, &tc.mm, Some(ProjectId::new(project_id)))
+        .await
+        .unwrap();
+    let endpoints: Vec<_> = subs.iter().map(|s| s.endpoint.as_str()).collect();
+    assert_eq!(
+        endpoints,
+        ["https://push.example/a", "https://push.example/b"]
+    );
+
+    // Re-subscribing the same endpoint updates it in place
+    let again = WebPushBmc::subscribe(
+        &tc.ctx,
+        &tc.mm,
+        subscription("https://push.example/a", Some(project_id)),
+    )
+    .await
+    .unwrap();
+    assert_eq!(again, global);
+
+    assert!(
+        WebPushBmc::subscribe(&tc.ctx, &tc.mm, subscription("http://insecure", None))
+            .await
+            .is_err()
+    );
+
+    assert!(
+        WebPushBmc::unsubscribe(&tc.ctx, &tc.mm, "https://push.example/b")
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        WebPushBmc::list_subscriptions(&tc.ctx, &tc.mm, None)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_failing_subscription_is_dropped() {
+    let tc = TestContext::new().await.unwrap();
+    let id = WebPushBmc::subscribe(
+        &tc.ctx,
+        &tc.mm,
+        subscription("https://push.example/x", None),
+    )
+    .await
+    .unwrap();
+
+    for _ in 0..MAX_PUSH_FAILURES - 1 {
+        WebPushBmc::record_delivery(&tc.ctx, &tc.mm, id, false)
+            .await
+            .unwrap();
+    }
+    let subs = WebPushBmc::list_subscriptions(&tc.ctx, &tc.mm, None)
+        .await
+        .unwrap();
+    assert_eq!(subs[0].failure_count, MAX_PUSH_FAILURES - 1);
+    assert_eq!(WebPushBmc::count_failing(&tc.ctx, &tc.mm).await.unwrap(), 1);
+
+    WebPushBmc::record_delivery(&tc.ctx, &tc.mm, id, false)
+        .await
+        .unwrap();
+    assert!(
+        WebPushBmc::list_subscriptions(&tc.ctx, &tc.mm, None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_vapid_keys_are_stable() {
+    let tc = TestContext::new().await.unwrap();
+    let first = WebPushBmc::vapid_keys(&tc.ctx, &tc.mm).await.unwrap();
+    let second = WebPushBmc::vapid_keys(&tc.ctx, &tc.mm).await.unwrap();
+
+    assert_eq!(first.public_key, second.public_key);
+    // Uncompressed P-256 point (65 bytes) and scalar (32 bytes), base64url
+    assert_eq!(first.public_key.len(), 87);
+    assert_eq!(first.private_key.len(), 43);
+
+    let rebuilt = vapid_keys_from_private_key(&first.private_key).unwrap();
+    assert_eq!(rebuilt.public_key, first.public_key);
+    assert!(vapid_keys_from_private_key("not-a-key").is_err());
+}
@@ -0,0 +1,359 @@
+//! Webhook tests
+//!
+//! Tests fanning event log records out to subscribed webhooks, the retry
+//! schedule of failed deliveries, overseer reports of deliveries that gave
+//! up, and the delivery log.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::{TestContext, create_agent};
+use mouchak_mail_common::config::{AppConfig, WebhooksConfig};
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::overseer_message::OverseerMessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::webhook::{
+    DeliveryAttempt, DeliveryFilter, STATUS_DELIVERED, STATUS_FAILED, STATUS_PENDING, WebhookBmc,
+    WebhookForCreate,
+};
+use mouchak_mail_core::types::{AgentId, MessageId, ProjectId};
+
+async fn send(tc: &TestContext, project_id: ProjectId, from: AgentId, to: AgentId) -> MessageId {
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: from,
+            recipient_ids: vec![to],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Build".into(),
+            body_md: "Green".into(),
+            thread_id: None,
+            importance: None,
+            ack_required: true,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn hook(project_id: ProjectId, events: &[&str]) -> WebhookForCreate {
+    WebhookForCreate {
+        project_id: project_id.get(),
+        url: "https://hooks.example.com/mail".into(),
+        events: events.iter().map(|e| e.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_enqueue_subscribed_events() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "team", "team")
+        .await
+        .unwrap();
+    let other = ProjectBmc::create(&tc.ctx, &tc.mm, "other", "other")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project, "alice").await;
+    let bob = create_agent(&tc, project, "bob").await;
+    let carol = create_agent(&tc, other, "carol").await;
+    let dave = create_agent(&tc, other, "dave").await;
+
+    // History before the webhook existed is not delivered
+    send(&tc, project, alice, bob).await;
+
+    let created = WebhookBmc::create(&tc.ctx, &tc.mm, hook(project, &["message.created"]))
+        .await
+        .unwrap();
+    assert!(created.secret.starts_with("whsec_"));
+    let acks = WebhookBmc::create(&tc.ctx, &tc.mm, hook(project, &["message.acknowledged"]))
+        .await
+        .unwrap();
+
+    let message_id = send(&tc, project, alice, bob).await;
+    MessageBmc::acknowledge(&tc.ctx, &tc.mm, message_id, bob)
+        .await
+        .unwrap();
+    send(&tc, other, carol, dave).await;
+
+    assert_eq!(WebhookBmc::enqueue(&tc.ctx, &tc.mm).await.unwrap(), 2);
+    // Records already scanned are not enqueued again
+    assert_eq!(WebhookBmc::enqueue(&tc.ctx, &tc.mm).await.unwrap(), 0);
+
+    let filter = DeliveryFilter::default();
+    let deliveries = WebhookBmc::list_deliveries(&tc.ctx, &tc.mm, created.id, &filter)
+        .await
+        .unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].event, "message.created");
+    assert_eq!(deliveries[0].status, STATUS_PENDING);
+    assert_eq!(deliveries[0].payload["project_id"], project.get());
+    assert_eq!(deliveries[0].payload["actor"], "alice");
+    assert_eq!(
+        deliveries[0].payload["data"]["message_id"],
+        message_id.get()
+    );
+
+    let deliveries = WebhookBmc::list_deliveries(&tc.ctx, &tc.mm, acks.id, &filter)
+        .await
+        .unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].event, "message.acknowledged");
+
+    let due = WebhookBmc::due(&tc.ctx, &tc.mm, 10).await.unwrap();
+    assert_eq!(due.len(), 2);
+    assert_eq!(due[0].secret, created.secret);
+    assert!(due[0].body.contains("\"message.created\""));
+}
+
+#[tokio::test]
+async fn test_retry_backoff_and_failure() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "team", "team")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project, "alice").await;
+    let bob = create_agent(&tc, project, "bob").await;
+    let webhook = WebhookBmc::create(&tc.ctx, &tc.mm, hook(project, &["message.created"]))
+        .await
+        .unwrap();
+    send(&tc, project, alice, bob).await;
+    send(&tc, project, alice, bob).await;
+    WebhookBmc::enqueue(&tc.ctx, &tc.mm).await.unwrap();
+
+    let config = WebhooksConfig {
+        max_attempts: 3,
+        ..Default::default()
+    };
+    let due = WebhookBmc::due(&tc.ctx, &tc.mm, 10).await.unwrap();
+    assert_eq!(due.len(), 2);
+    let (first, second) = (due[0].id, due[1].id);
+
+    let refused = DeliveryAttempt {
+        response_status: Some(503),
+        error: Some("HTTP 503".into()),
+    };
+    let status = WebhookBmc::record_attempt(&tc.ctx, &tc.mm, first, &refused, &config)
+        .await
+        .unwrap();
+    assert_eq!(status, STATUS_PENDING);
+    // Backing off: no longer due
+    let due = WebhookBmc::due(&tc.ctx, &tc.mm, 10).await.unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].id, second);
+
+    for expected in [STATUS_PENDING, STATUS_FAILED] {
+        let status = WebhookBmc::record_attempt(&tc.ctx, &tc.mm, first, &refused, &config)
+            .await
+            .unwrap();
+        assert_eq!(status, expected);
+    }
+    let accepted = DeliveryAttempt {
+        response_status: Some(204),
+        error: None,
+    };
+    let status = WebhookBmc::record_attempt(&tc.ctx, &tc.mm, second, &accepted, &config)
+        .await
+        .unwrap();
+    assert_eq!(status, STATUS_DELIVERED);
+
+    let filter = DeliveryFilter {
+        status: Some(STATUS_FAILED.into()),
+        ..Default::default()
+    };
+    let failed = WebhookBmc::list_deliveries(&tc.ctx, &tc.mm, webhook.id, &filter)
+        .await
+        .unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].attempts, 3);
+    assert_eq!(failed[0].response_status, Some(503));
+    assert_eq!(failed[0].error.as_deref(), Some("HTTP 503"));
+
+    let all = WebhookBmc::list_deliveries(&tc.ctx, &tc.mm, webhook.id, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].id, second);
+    assert!(all[0].delivered_ts.is_some());
+    assert!(
+        WebhookBmc::due(&tc.ctx, &tc.mm, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_failed_delivery_reaches_overseer() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "team", "team")
+        .await
+        .unwrap();
+    let alice = create_agent(&tc, project, "alice").await;
+    let bob = create_agent(&tc, project, "bob").await;
+    let webhook = WebhookBmc::create(&tc.ctx, &tc.mm, hook(project, &["message.created"]))
+        .await
+        .unwrap();
+    send(&tc, project, alice, bob).await;
+    WebhookBmc::enqueue(&tc.ctx, &tc.mm).await.unwrap();
+    let delivery = WebhookBmc::due(&tc.ctx, &tc.mm, 10).await.unwrap()[0].id;
+
+    let config = WebhooksConfig {
+        max_attempts: 2,
+        ..Default::default()
+    };
+    let refused = DeliveryAttempt {
+        response_status: None,
+        error: Some("connection refused".into()),
+    };
+    WebhookBmc::record_attempt(&tc.ctx, &tc.mm, delivery, &refused, &config)
+        .await
+        .unwrap();
+    // Retries are not reported
+    let inbox = OverseerMessageBmc::list_for_project(&tc.ctx, &tc.mm, project, true, 10)
+        .await
+        .unwrap();
+    assert!(inbox.is_empty());
+
+    let status = WebhookBmc::record_attempt(&tc.ctx, &tc.mm, delivery, &refused, &config)
+        .await
+        .unwrap();
+    assert_eq!(status, STATUS_FAILED);
+    let inbox = OverseerMessageBmc::list_for_project(&tc.ctx, &tc.mm, project, true, 10)
+        .await
+        .unwrap();
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].subject, "Webhook delivery failed: message.created");
+    assert_eq!(inbox[0].importance, "high");
+    assert!(inbox[0].body_md.contains(&webhook.url));
+    assert!(inbox[0].body_md.contains("connection refused"));
+    let sender = AgentBmc::get(&tc.ctx, &tc.mm, AgentId::new(inbox[0].sender_id))
+        .await
+        .unwrap();
+    assert_eq!(sender.name, "webhooks");
+}
+
+#[tokio::test]
+async fn test_create_refuses_internal_urls() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "team", "team")
+        .await
+        .unwrap();
+    for url in [
+        "http://127.0.0.1:8765/api/admin",
+        "http://localhost/hook",
+        "http://169.254.169.254/latest/meta-data/",
+        "https://[::1]/hook",
+        "https://10.0.0.5/hook",
+    ] {
+        let result = WebhookBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            WebhookForCreate {
+                url: url.into(),
+                ..hook(project, &["message.created"])
+            },
+        )
+        .await;
+        assert!(
+            matches!(&result, Err(Error::InvalidInput(e)) if e.contains("loopback") || e.contains("private")),
+            "{}: {:?}",
+            url,
+            result.map(|w| w.url)
+        );
+    }
+
+    // Operators can let receivers on their own network in
+    let mut config = AppConfig::default();
+    config.webhooks.allow_private_urls = true;
+    let tc = TestContext::new_with_config(config).await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "team", "team")
+        .await
+        .unwrap();
+    let webhook = WebhookBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        WebhookForCreate {
+            url: "http://127.0.0.1:8765/hook".into(),
+            ..hook(project, &["message.created"])
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(webhook.url, "http://127.0.0.1:8765/hook");
+}
+
+#[tokio::test]
+async fn test_create_validation_and_delete() {
+    let tc = TestContext::new().await.unwrap();
+    let project = ProjectBmc::create(&tc.ctx, &tc.mm, "team", "team")
+        .await
+        .unwrap();
+
+    for bad in [
+        WebhookForCreate {
+            url: "ftp://hooks.example.com".into(),
+            ..hook(project, &["message.created"])
+        },
+        hook(project, &[]),
+        hook(project, &["message.sent"]),
+        WebhookForCreate {
+            secret: Some("short".into()),
+            ..hook(project, &["message.created"])
+        },
+    ] {
+        assert!(matches!(
+            WebhookBmc::create(&tc.ctx, &tc.mm, bad).await,
+            Err(Error::InvalidInput(_))
+        ));
+    }
+    assert!(
+        WebhookBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            hook(ProjectId::new(999), &["message.created"])
+        )
+        .await
+        .is_err()
+    );
+
+    let webhook = WebhookBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        WebhookForCreate {
+            secret: Some("0123456789abcdef".into()),
+            ..hook(project, &["reservation.expired", "message.created"])
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(webhook.secret, "0123456789abcdef");
+    assert_eq!(
+        webhook.events,
+        vec!["reservation.expired", "message.created"]
+    );
+    let listed = WebhookBmc::list(&tc.ctx, &tc.mm, Some(project))
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1);
+
+    WebhookBmc::delete(&tc.ctx, &tc.mm, webhook.id)
+        .await
+        .unwrap();
+    assert!(matches!(
+        WebhookBmc::get(&tc.ctx, &tc.mm, webhook.id).await,
+        Err(Error::NotFound)
+    ));
+    assert!(matches!(
+        WebhookBmc::list_deliveries(&tc.ctx, &tc.mm, webhook.id, &Default::default()).await,
+        Err(Error::NotFound)
+    ));
+}
@@ -0,0 +1,339 @@
+//! Thread workflow tests
+//!
+//! Tests for defining workflows, attaching them to threads, and advancing
+//! them with handoff notifications.
+
+// Tests are allowed to use unwrap()/expect() for clearer failure messages
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+#[path = "common/mod.rs"]
+mod common;
+
+use crate::common::TestContext;
+use mouchak_mail_core::Error;
+use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+use mouchak_mail_core::model::agent_capabilities::{AgentCapabilityBmc, AgentCapabilityForCreate};
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::workflow::{
+    WorkflowBmc, WorkflowDefinition, WorkflowForCreate, WorkflowState, WorkflowTransition,
+};
+use mouchak_mail_core::types::{AgentId, ProjectId};
+
+const THREAD: &str = "FEAT-1";
+
+struct Setup {
+    tc: TestContext,
+    project_id: ProjectId,
+    lead: AgentId,
+    coder: AgentId,
+    reviewer: AgentId,
+}
+
+fn state(name: &str, responsible: Option<&str>) -> WorkflowState {
+    WorkflowState {
+        name: name.to_string(),
+        responsible: responsible.map(str::to_string),
+    }
+}
+
+fn transitions(pairs: &[&str]) -> Vec<WorkflowTransition> {
+    pairs
+        .iter()
+        .map(|p| WorkflowTransition::parse(p).unwrap())
+        .collect()
+}
+
+/// spec → implement → review → merge, with review able to send work back.
+fn review_flow() -> WorkflowDefinition {
+    WorkflowDefinition {
+        states: vec![
+            state("spec", Some("lead")),
+            state("implement", Some("capability:implementer")),
+            state("review", Some("reviewer")),
+            state("merge", None),
+        ],
+        transitions: transitions(&[
+            "spec -> implement",
+            "implement -> review",
+            "review -> implement",
+            "review -> merge",
+        ]),
+    }
+}
+
+/// A project with a `review` workflow and a thread to attach it to.
+async fn setup() -> Setup {
+    let tc = TestContext::new().await.unwrap();
+    let project_id = ProjectBmc::create(&tc.ctx, &tc.mm, "wf", "/wf")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["lead", "coder", "reviewer"] {
+        let id = AgentBmc::create(
+            &tc.ctx,
+            &tc.mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id);
+    }
+    AgentCapabilityBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        AgentCapabilityForCreate {
+            agent_id: agents[1].get(),
+            capability: "implementer".to_string(),
+            granted_by: None,
+            expires_at: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    WorkflowBmc::define(
+        &tc.ctx,
+        &tc.mm,
+        WorkflowForCreate {
+            project_id,
+            name: "review".to_string(),
+            definition: review_flow(),
+        },
+    )
+    .await
+    .unwrap();
+
+    MessageBmc::create(
+        &tc.ctx,
+        &tc.mm,
+        MessageForCreate {
+            project_id,
+            sender_id: agents[0],
+            recipient_ids: vec![AgentId::new(agents[1].get())],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Feature 1".to_string(),
+            body_md: "Let's build it".to_string(),
+            thread_id: Some(THREAD.to_string()),
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    Setup {
+        tc,
+        project_id,
+        lead: agents[0],
+        coder: agents[1],
+        reviewer: agents[2],
+    }
+}
+
+impl Setup {
+    /// Replaces the definition of the `review` workflow.
+    async fn redefine(&self, definition: WorkflowDefinition) -> Result<i64, Error> {
+        WorkflowBmc::define(
+            &self.tc.ctx,
+            &self.tc.mm,
+            WorkflowForCreate {
+                project_id: self.project_id,
+                name: "review".to_string(),
+                definition,
+            },
+        )
+        .await
+    }
+}
+
+#[tokio::test]
+async fn test_advance_through_workflow_notifies_responsible() {
+    let s = setup().await;
+
+    // The lead starts the workflow in a state it owns, so nobody is mailed
+    let started = WorkflowBmc::start(&s.tc.ctx, &s.tc.mm, s.project_id, THREAD, "review", s.lead)
+        .await
+        .unwrap();
+    assert_eq!(started.status.state, "spec");
+    assert_eq!(started.status.next_states, ["implement"]);
+    assert!(started.notified.is_empty());
+    assert!(started.message_id.is_none());
+
+    let handoff = WorkflowBmc::advance(
+        &s.tc.ctx,
+        &s.tc.mm,
+        s.project_id,
+        THREAD,
+        "implement",
+        s.lead,
+        Some("Spec is in the first message"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(handoff.notified, ["coder"]);
+    let inbox = MessageBmc::list_inbox_for_agent(&s.tc.ctx, &s.tc.mm, s.project_id, s.coder, 10)
+        .await
+        .unwrap();
+    let message = inbox
+        .iter()
+        .find(|m| Some(m.id) == handoff.message_id)
+        .unwrap();
+    assert_eq!(message.thread_id.as_deref(), Some(THREAD));
+    assert_eq!(message.subject, "[review] spec -> implement");
+    assert!(message.body_md.contains("Spec is in the first message"));
+    assert!(message.body_md.contains("review"));
+
+    WorkflowBmc::advance(
+        &s.tc.ctx,
+        &s.tc.mm,
+        s.project_id,
+        THREAD,
+        "review",
+        s.coder,
+        None,
+    )
+    .await
+    .unwrap();
+    let reviewer_inbox =
+        MessageBmc::list_inbox_for_agent(&s.tc.ctx, &s.tc.mm, s.project_id, s.reviewer, 10)
+            .await
+            .unwrap();
+    assert_eq!(reviewer_inbox.len(), 1);
+
+    let done = WorkflowBmc::advance(
+        &s.tc.ctx,
+        &s.tc.mm,
+        s.project_id,
+        THREAD,
+        "merge",
+        s.reviewer,
+        None,
+    )
+    .await
+    .unwrap();
+    assert!(done.status.finished);
+    assert!(done.notified.is_empty());
+
+    let status = WorkflowBmc::status(&s.tc.ctx, &s.tc.mm, s.project_id, THREAD)
+        .await
+        .unwrap();
+    let history: Vec<(Option<&str>, &str, &str)> = status
+        .history
+        .iter()
+        .map(|e| {
+            (
+                e.from_state.as_deref(),
+                e.to_state.as_str(),
+                e.agent_name.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        history,
+        [
+            (None, "spec", "lead"),
+            (Some("spec"), "implement", "lead"),
+            (Some("implement"), "review", "coder"),
+            (Some("review"), "merge", "reviewer"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_disallowed_transition_is_rejected() {
+    let s = setup().await;
+    WorkflowBmc::start(&s.tc.ctx, &s.tc.mm, s.project_id, THREAD, "review", s.lead)
+        .await
+        .unwrap();
+
+    let err = WorkflowBmc::advance(
+        &s.tc.ctx,
+        &s.tc.mm,
+        s.project_id,
+        THREAD,
+        "merge",
+        s.lead,
+        None,
+    )
+    .await
+    .unwrap_err();
+    match err {
+        Error::InvalidInput(msg) => assert!(msg.contains("Allowed from 'spec': implement")),
+        other => panic!("unexpected error: {other:?}"),
+    }
+    let status = WorkflowBmc::status(&s.tc.ctx, &s.tc.mm, s.project_id, THREAD)
+        .await
+        .unwrap();
+    assert_eq!(status.state, "spec");
+    assert_eq!(status.history.len(), 1);
+
+    // A thread only follows one workflow at a time
+    let err = WorkflowBmc::start(&s.tc.ctx, &s.tc.mm, s.project_id, THREAD, "review", s.lead)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+
+    // Threads without a workflow cannot be advanced
+    let err = WorkflowBmc::advance(
+        &s.tc.ctx,
+        &s.tc.mm,
+        s.project_id,
+        "OTHER",
+        "implement",
+        s.lead,
+        None,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, Error::NotFound));
+}
+
+#[tokio::test]
+async fn test_define_validates_and_protects_occupied_states() {
+    let s = setup().await;
+    let mut unknown_state = review_flow();
+    unknown_state.transitions = transitions(&["spec -> deploy"]);
+    assert!(matches!(
+        s.redefine(unknown_state).await.unwrap_err(),
+        Error::InvalidInput(_)
+    ));
+
+    let mut bad_responsible = review_flow();
+    bad_responsible.states[0].responsible = Some("capability:".to_string());
+    assert!(s.redefine(bad_responsible).await.is_err());
+
+    WorkflowBmc::start(&s.tc.ctx, &s.tc.mm, s.project_id, THREAD, "review", s.lead)
+        .await
+        .unwrap();
+    let mut without_spec = review_flow();
+    without_spec.states.remove(0);
+    without_spec.transitions.remove(0);
+    assert!(matches!(
+        s.redefine(without_spec).await.unwrap_err(),
+        Error::InvalidInput(_)
+    ));
+
+    // Adding a transition keeps the same workflow
+    let mut extended = review_flow();
+    extended
+        .transitions
+        .push(WorkflowTransition::parse("spec -> review").unwrap());
+    let id = s.redefine(extended).await.unwrap();
+    let workflows = WorkflowBmc::list(&s.tc.ctx, &s.tc.mm, s.project_id)
+        .await
+        .unwrap();
+    assert_eq!(workflows.len(), 1);
+    assert_eq!(workflows[0].id, id);
+    let status = WorkflowBmc::status(&s.tc.ctx, &s.tc.mm, s.project_id, THREAD)
+        .await
+        .unwrap();
+    assert_eq!(status.next_states, ["implement", "review"]);
+}
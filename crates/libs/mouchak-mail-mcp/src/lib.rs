@@ -24,6 +24,8 @@ pub async fn run_stdio(config: AppConfig) -> Result<()> {
 
     // Initialize the service with worktrees config
     let service = MouchakMailService::new_with_config(config).await?;
+    let journal = service.session_journal();
+    let tool_call_log = service.tool_call_log();
 
     // Run over stdio
     let transport = (stdin(), stdout());
@@ -35,10 +37,19 @@ pub async fn run_stdio(config: AppConfig) -> Result<()> {
     let quit_reason = server.waiting().await?;
     tracing::info!("Server shutting down: {:?}", quit_reason);
 
+    if let Some(journal) = journal {
+        let posted = journal.flush().await;
+        tracing::info!("Posted {} session journal message(s)", posted.len());
+    }
+    if let Some(log) = tool_call_log {
+        let archived = log.flush().await?;
+        tracing::info!("Archived {} pending tool call(s)", archived);
+    }
+
     Ok(())
 }
 
-pub async fn run_sse(config: AppConfig) -> Result<()> {
+pub async fn run_sse(mut config: AppConfig) -> Result<()> {
     use rmcp::transport::streamable_http_server::{
         session::local::LocalSessionManager,
         tower::{StreamableHttpServerConfig, StreamableHttpService},
@@ -59,6 +70,12 @@ pub async fn run_sse(config: AppConfig) -> Result<()> {
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false);
 
+    // Stateless mode creates a service per request, so there is no session to journal
+    if config.mcp.session_journal_enabled && !stateful_mode {
+        tracing::warn!("Session journaling requires MOUCHAK_MCP_STATEFUL=1; disabling it");
+        config.mcp.session_journal_enabled = false;
+    }
+
     let server_config = StreamableHttpServerConfig {
         stateful_mode,
         ..Default::default()
@@ -8,6 +8,7 @@ use mouchak_mail_core::{
         ModelManager,
         agent::{AgentBmc, AgentForCreate, AgentProfileUpdate},
         agent_capabilities::AgentCapabilityBmc,
+        context_pack::{ContextPackBmc, DEFAULT_BUDGET_TOKENS},
         file_reservation::FileReservationBmc,
     },
     utils::mistake_detection::detect_unix_username_as_agent,
@@ -18,8 +19,8 @@ use std::sync::Arc;
 
 use super::helpers;
 use super::{
-    CreateAgentIdentityParams, GetAgentProfileParams, ListAgentsParams, RegisterAgentParams,
-    UpdateAgentProfileParams, WhoisParams,
+    CreateAgentIdentityParams, GetAgentProfileParams, GetContextPackParams, ListAgentsParams,
+    RegisterAgentParams, UpdateAgentProfileParams, WhoisParams,
 };
 
 /// Register an agent in a project.
@@ -68,7 +69,7 @@ pub async fn register_agent_impl(
                 .await
                 .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
-            AgentCapabilityBmc::grant_defaults(ctx, mm, id.get())
+            AgentCapabilityBmc::grant_defaults(ctx, mm, id)
                 .await
                 .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -100,9 +101,10 @@ pub async fn whois_impl(
         .map_err(|e| McpError::invalid_params(format!("Agent not found: {}", e), None))?;
 
     let output = format!(
-        "Agent: {}\nID: {}\nProgram: {}\nModel: {}\nTask: {}\nContact Policy: {}\nAttachments Policy: {}",
+        "Agent: {}\nID: {}\nPublic ID: {}\nProgram: {}\nModel: {}\nTask: {}\nContact Policy: {}\nAttachments Policy: {}",
         agent.name,
         agent.id,
+        agent.public_id,
         agent.program,
         agent.model,
         agent.task_description,
@@ -167,9 +169,10 @@ pub async fn get_agent_profile_impl(
         .count();
 
     let output = format!(
-        "Agent: {}\nID: {}\nProgram: {}\nModel: {}\nTask: {}\nContact Policy: {}\nAttachments Policy: {}\nMessages Sent: {}\nMessages Received: {}\nActive Reservations: {}\nInception: {}\nLast Active: {}",
+        "Agent: {}\nID: {}\nPublic ID: {}\nProgram: {}\nModel: {}\nTask: {}\nContact Policy: {}\nAttachments Policy: {}\nMessages Sent: {}\nMessages Received: {}\nActive Reservations: {}\nInception: {}\nLast Active: {}",
         agent.name,
         agent.id,
+        agent.public_id,
         agent.program,
         agent.model,
         agent.task_description,
@@ -184,7 +187,7 @@ pub async fn get_agent_profile_impl(
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-/// List all agents registered in a project.
+/// List agents registered in a project, optionally paged, filtered and sorted.
 pub async fn list_agents_impl(
     ctx: &Ctx,
     mm: &Arc<ModelManager>,
@@ -192,21 +195,33 @@ pub async fn list_agents_impl(
 ) -> Result<CallToolResult, McpError> {
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let agents = AgentBmc::list_all_for_project(ctx, mm, project.id)
+    let query = helpers::list_query(
+        params.limit,
+        params.cursor,
+        params.sort.as_deref(),
+        params.active_within,
+        params.program,
+        params.model,
+    )?;
+    let page = AgentBmc::list_page(ctx, mm, project.id, &query)
         .await
-        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        .map_err(helpers::list_error)?;
 
-    let mut output = format!(
-        "Agents in '{}' ({}):\n\n",
-        params.project_slug,
-        agents.len()
-    );
-    for a in &agents {
+    let count = if page.items.len() as i64 == page.total {
+        page.total.to_string()
+    } else {
+        format!("{} of {}", page.items.len(), page.total)
+    };
+    let mut output = format!("Agents in '{}' ({}):\n\n", params.project_slug, count);
+    for a in &page.items {
         output.push_str(&format!(
-            "- {} (program: {}, model: {})\n  Task: {}\n",
-            a.name, a.program, a.model, a.task_description
+            "- {} (program: {}, model: {}, last active: {})\n  Task: {}\n",
+            a.name, a.program, a.model, a.last_active_ts, a.task_description
         ));
     }
+    if let Some(cursor) = &page.next_cursor {
+        output.push_str(&format!("\nMore results: pass cursor \"{}\"\n", cursor));
+    }
 
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
@@ -310,3 +325,26 @@ pub async fn create_agent_identity_impl(
 
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
+
+/// Build a size-bounded briefing so a fresh agent can catch up in one call.
+pub async fn get_context_pack_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: GetContextPackParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let pack = ContextPackBmc::build(
+        ctx,
+        mm,
+        project.id,
+        agent.id,
+        params.budget_tokens.unwrap_or(DEFAULT_BUDGET_TOKENS),
+    )
+    .await
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    Ok(CallToolResult::success(vec![Content::text(pack.render())]))
+}
@@ -2,7 +2,8 @@
 //!
 //! Handles adding and retrieving message attachments via Git storage.
 
-use mouchak_mail_core::{ctx::Ctx, model::ModelManager, store::git_store};
+use mouchak_mail_core::model::attachment_text::{AttachmentTextBmc, AttachmentTextForCreate};
+use mouchak_mail_core::{ctx::Ctx, model::ModelManager, store::git_store, types::MessageId};
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
 
@@ -20,7 +21,7 @@ pub async fn add_attachment_impl(
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
     // Verify message exists
-    let _message = MessageBmc::get(ctx, mm, params.message_id)
+    let _message = MessageBmc::get(ctx, mm, MessageId::new(params.message_id))
         .await
         .map_err(|e| McpError::invalid_params(format!("Message not found: {}", e), None))?;
 
@@ -64,10 +65,34 @@ pub async fn add_attachment_impl(
     )
     .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
-    let msg = format!(
+    // Index text-like content for search; failure must not fail the upload
+    let indexed = match AttachmentTextBmc::index(
+        ctx,
+        mm,
+        AttachmentTextForCreate {
+            project_id: project.id.get(),
+            message_id: params.message_id,
+            attachment_ref: attachment_id.clone(),
+            filename: params.filename.clone(),
+            content,
+        },
+    )
+    .await
+    {
+        Ok(id) => id.is_some(),
+        Err(e) => {
+            tracing::warn!("Failed to index attachment {}: {}", attachment_id, e);
+            false
+        }
+    };
+
+    let mut msg = format!(
         "Attachment '{}' added with ID {}",
         params.filename, attachment_id
     );
+    if indexed {
+        msg.push_str(" (indexed for search)");
+    }
     Ok(CallToolResult::success(vec![Content::text(msg)]))
 }
 
@@ -105,8 +130,11 @@ pub async fn get_attachment_impl(
             };
 
             let output = format!(
-                "Attachment: {}\nMIME Type: {}\n\nContent (base64):\n{}",
-                params.filename, mime_type, content_base64
+                "Attachment: {}\nMIME Type: {}\nBLAKE3: {}\n\nContent (base64):\n{}",
+                params.filename,
+                mime_type,
+                blake3::hash(content.as_bytes()).to_hex(),
+                content_base64
             );
             Ok(CallToolResult::success(vec![Content::text(output)]))
         }
@@ -0,0 +1,152 @@
+//! Query budget annotations for tool responses.
+//!
+//! Every successful tool call reports how many bytes it returned in the
+//! result's `_meta` under [`META_KEY`]. List-style tools additionally attach a
+//! [`ResultBudget`] with the number of items returned versus the limit and a
+//! few narrower filters to try. When a result was likely cut off by its limit
+//! or exceeds [`LARGE_RESPONSE_BYTES`], a one-line hint is appended to the text
+//! output so agents that ignore `_meta` still see it.
+
+use rmcp::model::{CallToolResult, Content, Meta, RawContent};
+use serde::Serialize;
+
+/// Key under which the budget is stored in a result's `_meta`.
+pub const META_KEY: &str = "mouchak/budget";
+
+/// Responses larger than this get a hint to narrow the query.
+pub const LARGE_RESPONSE_BYTES: usize = 16 * 1024;
+
+/// Size and completeness of a tool result.
+///
+/// # Fields
+///
+/// - `returned` - Items included in the response
+/// - `limit` - Limit applied to the query
+/// - `truncated` - `true` if the limit was reached, so more items may exist
+/// - `bytes` - Bytes of text content returned
+/// - `suggestions` - Narrower filters the caller can apply
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResultBudget {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub returned: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    pub truncated: bool,
+    pub bytes: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+impl ResultBudget {
+    /// Budget for a list of `returned` items fetched with `limit`.
+    pub fn list(returned: usize, limit: i64) -> Self {
+        Self {
+            returned: Some(returned),
+            limit: Some(limit),
+            truncated: limit > 0 && returned as i64 >= limit,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a narrower filter to suggest when the result is large.
+    pub fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestions.push(suggestion.into());
+        self
+    }
+
+    fn over_budget(&self) -> bool {
+        self.truncated || self.bytes > LARGE_RESPONSE_BYTES
+    }
+}
+
+/// Attaches `budget` to a successful result.
+///
+/// Suggestions are dropped unless the result is truncated or large.
+pub fn annotate(mut result: CallToolResult, mut budget: ResultBudget) -> CallToolResult {
+    budget.bytes = text_bytes(&result);
+
+    if budget.over_budget() {
+        let mut hint = match (budget.returned, budget.limit) {
+            (Some(returned), Some(limit)) if budget.truncated => format!(
+                "[budget] Returned {} of limit {} ({} bytes); more results may exist.",
+                returned, limit, budget.bytes
+            ),
+            _ => format!("[budget] Large response ({} bytes).", budget.bytes),
+        };
+        if !budget.suggestions.is_empty() {
+            hint.push_str(&format!(" Narrow with: {}.", budget.suggestions.join("; ")));
+        }
+        result.content.push(Content::text(hint));
+    } else {
+        budget.suggestions.clear();
+    }
+
+    set_meta(&mut result, &budget);
+    result
+}
+
+/// Records the response size for results that carry no budget yet.
+pub fn ensure_bytes(result: &mut CallToolResult) {
+    if result
+        .meta
+        .as_ref()
+        .is_some_and(|m| m.0.contains_key(META_KEY))
+    {
+        return;
+    }
+    let budget = ResultBudget {
+        bytes: text_bytes(result),
+        ..Default::default()
+    };
+    set_meta(result, &budget);
+}
+
+fn text_bytes(result: &CallToolResult) -> usize {
+    result
+        .content
+        .iter()
+        .map(|c| match &c.raw {
+            RawContent::Text(t) => t.text.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn set_meta(result: &mut CallToolResult, budget: &ResultBudget) {
+    if let Ok(value) = serde_json::to_value(budget) {
+        result
+            .meta
+            .get_or_insert_with(Meta::new)
+            .0
+            .insert(META_KEY.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_flags_truncated_lists() {
+        let result = CallToolResult::success(vec![Content::text("- [1] a\n- [2] b\n")]);
+        let budget = ResultBudget::list(2, 2).suggest("since_ts");
+        let result = annotate(result, budget);
+
+        let meta = &result.meta.as_ref().unwrap().0[META_KEY];
+        assert_eq!(meta["truncated"], true);
+        assert_eq!(meta["suggestions"][0], "since_ts");
+        assert_eq!(result.content.len(), 2);
+    }
+
+    #[test]
+    fn test_annotate_small_result_has_no_hint() {
+        let result = CallToolResult::success(vec![Content::text("- [1] a\n")]);
+        let result = annotate(result, ResultBudget::list(1, 50).suggest("since_ts"));
+
+        let meta = &result.meta.as_ref().unwrap().0[META_KEY];
+        assert_eq!(meta["truncated"], false);
+        assert!(meta.get("suggestions").is_none());
+        assert_eq!(result.content.len(), 1);
+    }
+}
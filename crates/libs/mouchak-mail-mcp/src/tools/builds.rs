@@ -9,6 +9,7 @@ use mouchak_mail_core::{
         agent::AgentBmc,
         build_slot::{BuildSlotBmc, BuildSlotForCreate},
     },
+    utils::clock,
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
@@ -40,7 +41,8 @@ pub async fn acquire_build_slot_impl(
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
-    let expires = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(ttl);
+    let expires =
+        clock::expires_in(ttl).map_err(|e| McpError::invalid_params(e.to_string(), None))?;
     let msg = format!(
         "Acquired build slot '{}' (id: {}, expires: {})",
         params.slot_name, slot_id, expires
@@ -0,0 +1,48 @@
+//! CI status tool implementations
+//!
+//! Links commits to threads so build events ingested by the server are
+//! posted where the work is discussed.
+
+use mouchak_mail_core::{
+    ctx::Ctx,
+    model::{ModelManager, ci_status::CiStatusBmc},
+};
+use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
+use std::sync::Arc;
+
+use super::LinkCommitParams;
+use super::helpers;
+
+/// Link a commit to a thread for CI build notifications.
+pub async fn link_commit_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: LinkCommitParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let link = CiStatusBmc::link_commit(
+        ctx,
+        mm,
+        project.id,
+        &params.thread_id,
+        &params.sha,
+        params.repo.as_deref(),
+    )
+    .await
+    .map_err(|e| match e {
+        mouchak_mail_core::Error::InvalidInput(msg) => McpError::invalid_params(msg, None),
+        mouchak_mail_core::Error::NotFound => McpError::invalid_params(
+            format!("Thread '{}' has no messages", params.thread_id),
+            None,
+        ),
+        e => McpError::internal_error(e.to_string(), None),
+    })?;
+
+    let msg = format!(
+        "Linked commit {} to thread '{}'{}; its CI builds will be posted there.",
+        link.sha,
+        link.thread_id,
+        link.repo.map(|r| format!(" for {}", r)).unwrap_or_default()
+    );
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
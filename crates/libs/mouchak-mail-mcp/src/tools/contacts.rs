@@ -1,6 +1,6 @@
 //! Contact management tool implementations
 //!
-//! Handles agent-to-agent contact requests and policies.
+//! Handles agent-to-agent contact requests, policies and delegation grants.
 
 use mouchak_mail_core::{
     ctx::Ctx,
@@ -8,7 +8,9 @@ use mouchak_mail_core::{
         ModelManager,
         agent::{AgentBmc, AgentProfileUpdate},
         agent_link::{AgentLinkBmc, AgentLinkForCreate},
+        delegation::DelegationBmc,
         project::ProjectBmc,
+        team_graph::{TeamGraph, TeamGraphBmc},
     },
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
@@ -16,7 +18,8 @@ use std::sync::Arc;
 
 use super::helpers;
 use super::{
-    ListContactsParams, RequestContactParams, RespondContactByNameParams, RespondContactParams,
+    DelegationParams, ExportTeamGraphParams, ImportTeamGraphParams, ListContactsParams,
+    ListDelegationsParams, RequestContactParams, RespondContactByNameParams, RespondContactParams,
     SetContactPolicyParams,
 };
 
@@ -50,7 +53,10 @@ pub async fn request_contact_impl(
 
     let link_id = AgentLinkBmc::request_contact(ctx, mm, link_c)
         .await
-        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::ContactDenied(msg) => McpError::invalid_request(msg, None),
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
 
     let msg = format!(
         "Contact request sent (link_id: {}, status: pending)",
@@ -92,7 +98,7 @@ pub async fn respond_contact_by_name_impl(
         .await
         .map_err(|e| McpError::invalid_params(format!("From agent not found: {}", e), None))?;
 
-    let pending = AgentLinkBmc::list_pending_requests(ctx, mm, project.id.get(), to_agent.id.get())
+    let pending = AgentLinkBmc::list_pending_requests(ctx, mm, project.id, to_agent.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -145,7 +151,7 @@ pub async fn list_contacts_impl(
         .await
         .map_err(|e| McpError::invalid_params(format!("Agent not found: {}", e), None))?;
 
-    let links = AgentLinkBmc::list_contacts(ctx, mm, project.id.get(), agent.id.get())
+    let links = AgentLinkBmc::list_contacts(ctx, mm, project.id, agent.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -196,3 +202,161 @@ pub async fn set_contact_policy_impl(
     );
     Ok(CallToolResult::success(vec![Content::text(msg)]))
 }
+
+/// Export a project's agents, capabilities and contacts as JSON.
+pub async fn export_team_graph_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ExportTeamGraphParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    let graph = TeamGraphBmc::export(ctx, mm, project.id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let json = serde_json::to_string_pretty(&graph)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+/// Import agents, capabilities and contacts from a team graph.
+pub async fn import_team_graph_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ImportTeamGraphParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    // Accept the graph as an object or as the JSON text export returns
+    let graph: TeamGraph = match params.graph {
+        serde_json::Value::String(text) => serde_json::from_str(&text),
+        value => serde_json::from_value(value),
+    }
+    .map_err(|e| McpError::invalid_params(format!("Invalid team graph: {}", e), None))?;
+
+    let summary = TeamGraphBmc::import(ctx, mm, project.id, &graph)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::InvalidInput(_) | mouchak_mail_core::Error::Validation(_) => {
+                McpError::invalid_params(e.to_string(), None)
+            }
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
+
+    let mut output = format!(
+        "Imported team graph into '{}': {} agent(s) created, {} capability grant(s), {} contact(s) linked\n",
+        project.slug,
+        summary.agents_created.len(),
+        summary.capabilities_granted.len(),
+        summary.contacts_created.len()
+    );
+    for (label, items) in [
+        ("Agents created", &summary.agents_created),
+        ("Capabilities granted", &summary.capabilities_granted),
+        ("Contacts linked", &summary.contacts_created),
+    ] {
+        if !items.is_empty() {
+            output.push_str(&format!("{}: {}\n", label, items.join(", ")));
+        }
+    }
+    if !summary.skipped.is_empty() {
+        output.push_str("\nSkipped:\n");
+        for reason in &summary.skipped {
+            output.push_str(&format!("- {}\n", reason));
+        }
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Let one agent send messages on behalf of another.
+pub async fn grant_delegation_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: DelegationParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, grantor) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+    let delegate = helpers::resolve_agent(ctx, mm, project.id.get(), &params.delegate_name).await?;
+
+    let added = DelegationBmc::grant(ctx, mm, project.id, grantor.id, delegate.id)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::InvalidInput(_) => {
+                McpError::invalid_params(e.to_string(), None)
+            }
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
+
+    let msg = if added {
+        format!(
+            "'{}' may now send on behalf of '{}'",
+            delegate.name, grantor.name
+        )
+    } else {
+        format!(
+            "'{}' could already send on behalf of '{}'",
+            delegate.name, grantor.name
+        )
+    };
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
+/// Withdraw a delegation grant.
+pub async fn revoke_delegation_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: DelegationParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, grantor) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+    let delegate = helpers::resolve_agent(ctx, mm, project.id.get(), &params.delegate_name).await?;
+
+    let removed = DelegationBmc::revoke(ctx, mm, project.id, grantor.id, delegate.id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let msg = if removed {
+        format!(
+            "'{}' may no longer send on behalf of '{}'",
+            delegate.name, grantor.name
+        )
+    } else {
+        format!(
+            "'{}' had no grant to send on behalf of '{}'",
+            delegate.name, grantor.name
+        )
+    };
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
+/// List delegation grants in a project.
+pub async fn list_delegations_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ListDelegationsParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let agent = match params.agent_name.as_deref() {
+        Some(name) => Some(helpers::resolve_agent(ctx, mm, project.id.get(), name).await?),
+        None => None,
+    };
+
+    let grants = DelegationBmc::list(ctx, mm, project.id, agent.as_ref().map(|a| a.id))
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let mut output = format!(
+        "Delegations in '{}' ({} grants):\n\n",
+        project.slug,
+        grants.len()
+    );
+    for g in &grants {
+        output.push_str(&format!(
+            "- {} may send on behalf of {} (since {})\n",
+            g.delegate_name, g.grantor_name, g.created_ts
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
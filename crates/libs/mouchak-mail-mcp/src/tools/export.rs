@@ -2,7 +2,14 @@
 
 use mouchak_mail_core::{
     ctx::Ctx,
-    model::{ModelManager, agent::AgentBmc, message::MessageBmc},
+    model::{
+        ModelManager,
+        agent::AgentBmc,
+        export::{ExportBmc, ExportFormat, ScrubMode},
+        message::MessageBmc,
+    },
+    utils::body_format::{highlight_css, html_escape},
+    utils::diagram::markdown_to_html_with_diagrams,
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
@@ -19,6 +26,22 @@ pub async fn export_mailbox_impl(
 
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
+    if format == "mbox" {
+        let exported = ExportBmc::export_mailbox(
+            ctx,
+            mm,
+            &project.slug,
+            ExportFormat::Mbox,
+            ScrubMode::None,
+            params.include_attachments.unwrap_or(true),
+        )
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        return Ok(CallToolResult::success(vec![Content::text(
+            exported.content,
+        )]));
+    }
+
     let agents = AgentBmc::list_all_for_project(ctx, mm, project.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -27,7 +50,7 @@ pub async fn export_mailbox_impl(
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
-    let threads = MessageBmc::list_threads(ctx, mm, project.id.get(), 100)
+    let threads = MessageBmc::list_threads(ctx, mm, project.id, 100, true)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -83,7 +106,10 @@ pub async fn export_mailbox_impl(
         .message {{ background: #f5f5f5; border-radius: 8px; padding: 1rem; margin: 1rem 0; }}
         .message-header {{ font-weight: bold; color: #1976d2; }}
         .message-meta {{ color: #666; font-size: 0.9rem; }}
-        .message-body {{ margin-top: 0.5rem; white-space: pre-wrap; }}
+        .message-body {{ margin-top: 0.5rem; }}
+        .message-body pre {{ background: #fff; padding: 0.5rem; border-radius: 4px; overflow-x: auto; }}
+        .diagram img {{ max-width: 100%; }}
+        {}
         .agent {{ display: inline-block; background: #e3f2fd; padding: 0.25rem 0.5rem; border-radius: 4px; margin: 0.25rem; }}
         .thread {{ background: #fff3e0; border-left: 4px solid #ff9800; padding: 0.5rem 1rem; margin: 0.5rem 0; }}
     </style>
@@ -93,6 +119,7 @@ pub async fn export_mailbox_impl(
     <p>Project: {} | Exported: {}</p>
 "#,
                 project.human_key,
+                highlight_css(),
                 project.human_key,
                 project.slug,
                 chrono::Utc::now().format("%Y-%m-%d %H:%M:%S")
@@ -123,7 +150,11 @@ pub async fn export_mailbox_impl(
     <div class="message-meta">From: {} | {} | {}</div>
     <div class="message-body">{}</div>
 </div>"#,
-                    m.subject, m.sender_name, m.importance, m.created_ts, m.body_md
+                    html_escape(&m.subject),
+                    html_escape(&m.sender_name),
+                    m.importance,
+                    m.created_ts,
+                    markdown_to_html_with_diagrams(&m.body_md).await
                 ));
             }
 
@@ -0,0 +1,117 @@
+//! External HTTP tools proxied through MCP
+//!
+//! Endpoints registered with `ExternalToolBmc` are listed after the built-in
+//! and plugin tools, which they cannot replace. A call is forwarded as a
+//! JSON `POST` of `{"tool", "arguments"}` and the response body becomes the
+//! tool result, cut at the tool's size cap. Redirects are not followed, so
+//! the `Authorization` header only ever reaches the registered URL.
+
+use mouchak_mail_core::{
+    Error,
+    ctx::Ctx,
+    model::{
+        ModelManager,
+        external_tool::{DEFAULT_TIMEOUT_MS, ExternalTool, ExternalToolBmc},
+    },
+};
+use rmcp::{
+    ErrorData as McpError,
+    model::{CallToolResult, Content, Tool},
+};
+use serde_json::{Map, Value};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap_or_default()
+});
+
+/// Enabled external tools whose names are not `taken`.
+pub async fn external_tools(mm: &ModelManager, taken: impl Fn(&str) -> bool) -> Vec<Tool> {
+    match ExternalToolBmc::list_enabled(&Ctx::root_ctx(), mm).await {
+        Ok(tools) => tools
+            .into_iter()
+            .filter(|t| !taken(&t.name))
+            .map(|t| Tool::new(t.name, t.description, Arc::new(t.input_schema)))
+            .collect(),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to list external tools");
+            Vec::new()
+        }
+    }
+}
+
+/// The enabled external tool called `name`, if any.
+pub async fn find_external_tool(mm: &ModelManager, name: &str) -> Option<ExternalTool> {
+    match ExternalToolBmc::get_by_name(&Ctx::root_ctx(), mm, name).await {
+        Ok(tool) if tool.enabled => Some(tool),
+        Ok(_) | Err(Error::NotFound) => None,
+        Err(e) => {
+            tracing::warn!(tool = %name, error = %e, "Failed to look up external tool");
+            None
+        }
+    }
+}
+
+/// Forwards a call to an external tool's endpoint.
+pub async fn call_external_tool_impl(
+    tool: &ExternalTool,
+    arguments: Option<Value>,
+) -> Result<CallToolResult, McpError> {
+    let timeout_ms = u64::try_from(tool.timeout_ms).unwrap_or(DEFAULT_TIMEOUT_MS as u64);
+    let mut request = CLIENT
+        .post(&tool.url)
+        .timeout(Duration::from_millis(timeout_ms))
+        .json(&serde_json::json!({
+            "tool": tool.name,
+            "arguments": arguments.unwrap_or_else(|| Value::Object(Map::new())),
+        }));
+    if let Some(auth) = &tool.auth_header {
+        request = request.header(reqwest::header::AUTHORIZATION, auth);
+    }
+
+    let failed = |e: reqwest::Error| {
+        let reason = if e.is_timeout() {
+            format!("timed out after {} ms", timeout_ms)
+        } else {
+            e.to_string()
+        };
+        McpError::internal_error(
+            format!("External tool '{}' failed: {}", tool.name, reason),
+            None,
+        )
+    };
+    let mut response = request.send().await.map_err(failed)?;
+    let status = response.status();
+
+    let cap = usize::try_from(tool.max_result_bytes).unwrap_or(usize::MAX);
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response.chunk().await.map_err(failed)? {
+        let room = cap.saturating_sub(body.len());
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            truncated = true;
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let mut text = String::from_utf8_lossy(&body).into_owned();
+    if truncated {
+        text.push_str(&format!("\n[truncated at {} bytes]", cap));
+    }
+
+    if !status.is_success() {
+        return Err(McpError::internal_error(
+            format!(
+                "External tool '{}' returned {}: {}",
+                tool.name, status, text
+            ),
+            None,
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(text)]))
+}
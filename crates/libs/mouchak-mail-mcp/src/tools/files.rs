@@ -9,9 +9,14 @@ use mouchak_mail_core::{
         agent::AgentBmc,
         agent_capabilities::AgentCapabilityBmc,
         file_reservation::{FileReservationBmc, FileReservationForCreate},
+        reservation_set::{ReservationSetBmc, ReservationSetForCreate, ReservationSetOutcome},
+        reservation_watcher::ReservationWatcherBmc,
     },
-    utils::validation::{
-        validate_agent_name, validate_project_key, validate_reservation_path, validate_ttl,
+    utils::{
+        clock,
+        validation::{
+            validate_agent_name, validate_project_key, validate_reservation_path, validate_ttl,
+        },
     },
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
@@ -19,11 +24,16 @@ use std::sync::Arc;
 
 use super::helpers;
 use super::{
-    FileReservationParams, FileReservationPathsParams, ForceReleaseReservationParams,
-    ListReservationsParams, ReleaseFileReservationsByAgentParams, ReleaseReservationParams,
-    RenewFileReservationParams, RenewFileReservationsByAgentParams,
+    CheckPathsParams, FileReservationParams, FileReservationPathsParams,
+    ForceReleaseReservationParams, ListReservationsParams, ReleaseFileReservationsByAgentParams,
+    ReleaseReservationParams, ReleaseReservationSetParams, RenewFileReservationParams,
+    RenewFileReservationsByAgentParams, WaitForPathParams,
 };
 
+/// Default and maximum blocking time for `wait_for_path`.
+const WAIT_FOR_PATH_DEFAULT_SECS: u64 = 30;
+const WAIT_FOR_PATH_MAX_SECS: u64 = 300;
+
 /// Reserve a file path pattern to prevent conflicts between agents.
 pub async fn reserve_file_impl(
     ctx: &Ctx,
@@ -67,7 +77,7 @@ pub async fn reserve_file_impl(
         .await
         .map_err(|e| McpError::invalid_params(format!("Agent not found: {}", e), None))?;
 
-    if !AgentCapabilityBmc::check(ctx, mm, agent.id.get(), "file_reservation_paths")
+    if !AgentCapabilityBmc::check(ctx, mm, agent.id, "file_reservation_paths")
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?
     {
@@ -80,8 +90,17 @@ pub async fn reserve_file_impl(
         ));
     }
 
+    let watcher_ids = helpers::resolve_optional_agent_names(
+        ctx,
+        mm,
+        project.id.get(),
+        params.notify_on_release.as_deref(),
+    )
+    .await?;
+
     let ttl = params.ttl_seconds.unwrap_or(3600);
-    let expires_ts = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(ttl);
+    let expires_ts =
+        clock::expires_in(ttl).map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
     let res_c = FileReservationForCreate {
         project_id: project.id,
@@ -98,13 +117,79 @@ pub async fn reserve_file_impl(
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
-    let msg = format!(
+    let mut msg = format!(
         "Reserved '{}' for agent '{}' (reservation id: {}, expires: {})",
         params.path_pattern, params.agent_name, id, expires_ts
     );
+
+    if let Some(watcher_ids) = watcher_ids {
+        let added = ReservationWatcherBmc::add_watchers(ctx, mm, id, &watcher_ids)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        msg.push_str(&format!("\n{} agent(s) will be notified on release", added));
+    }
+
     Ok(CallToolResult::success(vec![Content::text(msg)]))
 }
 
+/// Block until a path is free of other agents' reservations.
+pub async fn wait_for_path_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: WaitForPathParams,
+) -> Result<CallToolResult, McpError> {
+    validate_reservation_path(&params.path).map_err(|e| {
+        McpError::invalid_params(
+            format!("{}", e),
+            Some(serde_json::json!({ "details": e.context() })),
+        )
+    })?;
+
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let timeout_secs = params
+        .timeout_seconds
+        .unwrap_or(WAIT_FOR_PATH_DEFAULT_SECS)
+        .min(WAIT_FOR_PATH_MAX_SECS);
+
+    let outcome = ReservationWatcherBmc::wait_for_path(
+        ctx,
+        mm,
+        project.id,
+        agent.id,
+        &params.path,
+        std::time::Duration::from_secs(timeout_secs),
+        params.watch.unwrap_or(true),
+    )
+    .await
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let mut output = if outcome.available {
+        format!(
+            "Path '{}' is available (waited {} ms)",
+            outcome.path, outcome.waited_ms
+        )
+    } else {
+        format!(
+            "Path '{}' is still reserved after {} ms:\n",
+            outcome.path, outcome.waited_ms
+        )
+    };
+    for r in &outcome.blocking {
+        output.push_str(&format!(
+            "- [{}] {} (agent_id: {}, expires: {})\n",
+            r.id, r.path_pattern, r.agent_id, r.expires_ts
+        ));
+    }
+    if !outcome.watching.is_empty() {
+        output.push_str("You will receive a [RELEASED] message when the path is released.");
+    }
+
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
 /// List all active file reservations in a project.
 pub async fn list_reservations_impl(
     ctx: &Ctx,
@@ -132,6 +217,49 @@ pub async fn list_reservations_impl(
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
+/// Check many paths against active reservations at once.
+pub async fn check_paths_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: CheckPathsParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let exclude_agent = match &params.agent_name {
+        Some(name) => Some(
+            helpers::resolve_agent(ctx, mm, project.id.get(), name)
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+
+    let verdicts =
+        FileReservationBmc::check_paths(ctx, mm, project.id, exclude_agent, &params.paths)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let reserved = verdicts.iter().filter(|v| v.reserved).count();
+    let mut output = format!(
+        "Checked {} path(s) in '{}': {} reserved\n\n",
+        verdicts.len(),
+        params.project_slug,
+        reserved
+    );
+    for v in &verdicts {
+        if v.conflicts.is_empty() {
+            output.push_str(&format!("- {}: free\n", v.path));
+        }
+        for c in &v.conflicts {
+            output.push_str(&format!(
+                "- {}: reserved by '{}' (pattern: {}, exclusive: {}, expires: {})\n",
+                v.path, c.agent_name, c.path_pattern, c.exclusive, c.expires_ts
+            ));
+        }
+    }
+
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
 /// Release a file reservation by ID.
 pub async fn release_reservation_impl(
     ctx: &Ctx,
@@ -167,7 +295,8 @@ pub async fn renew_file_reservation_impl(
     params: RenewFileReservationParams,
 ) -> Result<CallToolResult, McpError> {
     let ttl = params.ttl_seconds.unwrap_or(3600);
-    let new_expires = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(ttl);
+    let new_expires =
+        clock::expires_in(ttl).map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
     FileReservationBmc::renew(ctx, mm, params.reservation_id, new_expires)
         .await
@@ -226,64 +355,94 @@ pub async fn file_reservation_paths_impl(
         .await
         .map_err(|e| McpError::invalid_params(format!("Agent not found: {}", e), None))?;
 
-    let active_reservations = FileReservationBmc::list_active_for_project(ctx, mm, project.id)
-        .await
-        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-
     let ttl = params.ttl_seconds.unwrap_or(3600);
-    let now = chrono::Utc::now().naive_utc();
-    let expires_ts = now + chrono::Duration::seconds(ttl);
-
-    let mut granted = Vec::new();
-    let mut conflicts = Vec::new();
-
-    for path in params.paths {
-        // Check conflicts using glob pattern matching
-        for res in &active_reservations {
-            if res.agent_id != agent.id
-                && (res.exclusive || params.exclusive)
-                && mouchak_mail_core::utils::pathspec::paths_conflict(&res.path_pattern, &path)
-            {
-                conflicts.push(format!(
-                    "Conflict: {} overlaps with {} (held by agent ID {}, expires: {})",
-                    path, res.path_pattern, res.agent_id, res.expires_ts
-                ));
-            }
-        }
-
-        // Always grant (advisory model)
-        let fr_c = FileReservationForCreate {
+    let expires_ts =
+        clock::expires_in(ttl).map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+    // All-or-nothing: any clash reserves none of the paths
+    let outcome = ReservationSetBmc::acquire(
+        ctx,
+        mm,
+        ReservationSetForCreate {
             project_id: project.id,
             agent_id: agent.id,
-            path_pattern: path.clone(),
+            paths: params.paths,
             exclusive: params.exclusive,
-            reason: params.reason.clone().unwrap_or_default(),
+            reason: params.reason.unwrap_or_default(),
             expires_ts,
-        };
-
-        let id = FileReservationBmc::create(ctx, mm, fr_c)
-            .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        },
+    )
+    .await
+    .map_err(helpers::list_error)?;
+
+    let output = match outcome {
+        ReservationSetOutcome::Granted {
+            set_id,
+            paths,
+            reservation_ids,
+        } => {
+            let mut output = format!(
+                "Granted {} reservations (reservation set {})\n\n",
+                reservation_ids.len(),
+                set_id
+            );
+            for (path, id) in paths.iter().zip(&reservation_ids) {
+                output.push_str(&format!(
+                    "  Granted: {} (id: {}, expires: {})\n",
+                    path, id, expires_ts
+                ));
+            }
+            output.push_str(&format!(
+                "\nRelease them together with release_reservation_set (set_id: {})\n",
+                set_id
+            ));
+            output
+        }
+        ReservationSetOutcome::Conflicted { clashes } => {
+            let mut output = format!(
+                "Reserved nothing: {} conflict(s) with other agents' reservations:\n",
+                clashes.len()
+            );
+            for c in &clashes {
+                output.push_str(&format!(
+                    "  Conflict: {} overlaps {} held by '{}' (reservation {}, exclusive: {}, expires: {})\n",
+                    c.path, c.path_pattern, c.agent_name, c.reservation_id, c.exclusive, c.expires_ts
+                ));
+            }
+            output
+        }
+    };
 
-        granted.push(format!(
-            "Granted: {} (id: {}, expires: {})",
-            path, id, expires_ts
-        ));
-    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
 
-    let mut output = format!("Granted {} reservations\n\n", granted.len());
-    for g in granted {
-        output.push_str(&format!("  {}\n", g));
-    }
+/// Release every reservation of a reservation set at once.
+pub async fn release_reservation_set_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ReleaseReservationSetParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let agent = helpers::resolve_agent(ctx, mm, project.id.get(), &params.agent_name).await?;
 
-    if !conflicts.is_empty() {
-        output.push_str(&format!("\n⚠️ {} conflicts detected:\n", conflicts.len()));
-        for c in conflicts {
-            output.push_str(&format!("  {}\n", c));
-        }
-    }
+    let released = ReservationSetBmc::release(ctx, mm, params.set_id, Some(agent.id))
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::FileReservationNotFound(_) => McpError::invalid_params(
+                format!(
+                    "Reservation set {} not found for agent '{}'",
+                    params.set_id, params.agent_name
+                ),
+                None,
+            ),
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
 
-    Ok(CallToolResult::success(vec![Content::text(output)]))
+    let msg = format!(
+        "Released {} reservation(s) in reservation set {}",
+        released, params.set_id
+    );
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
 }
 
 pub async fn release_file_reservations_by_path_impl(
@@ -316,7 +475,7 @@ pub async fn release_file_reservations_by_path_impl(
     if let Some(paths) = &params.paths {
         for path in paths {
             if let Some(id) =
-                FileReservationBmc::release_by_path(ctx, mm, project.id.get(), agent.id.get(), path)
+                FileReservationBmc::release_by_path(ctx, mm, project.id, agent.id, path)
                     .await
                     .map_err(|e| McpError::internal_error(e.to_string(), None))?
             {
@@ -392,7 +551,8 @@ pub async fn renew_file_reservations_by_agent_impl(
     };
 
     let ttl = params.extend_seconds.unwrap_or(3600);
-    let new_expires = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(ttl);
+    let new_expires =
+        clock::expires_in(ttl).map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
     let mut renewed_ids = Vec::new();
     for res in &reservations_to_renew {
@@ -7,8 +7,11 @@ use mouchak_mail_core::{
     model::{
         ModelManager,
         agent::{Agent, AgentBmc},
+        capability_routing::{CapabilityAddress, CapabilityResolution, CapabilityRoutingBmc},
+        listing::{ListQuery, ListSort},
         project::{Project, ProjectBmc},
     },
+    types::AgentId,
     utils::validation::{validate_agent_name, validate_project_key},
 };
 use rmcp::ErrorData as McpError;
@@ -16,6 +19,39 @@ use std::sync::Arc;
 
 use crate::tools::errors::{ErrorCode, mcp_err};
 
+/// Build a listing query from list_projects/list_agents parameters.
+///
+/// Returns an invalid-params error for an unknown sort key.
+pub fn list_query(
+    limit: Option<i64>,
+    cursor: Option<String>,
+    sort: Option<&str>,
+    active_within: Option<String>,
+    program: Option<String>,
+    model: Option<String>,
+) -> Result<ListQuery, McpError> {
+    let sort = sort
+        .map(str::parse::<ListSort>)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    Ok(ListQuery {
+        limit,
+        cursor,
+        sort,
+        active_within,
+        program,
+        model,
+    })
+}
+
+/// Map a listing error, reporting bad paging or filter input as invalid params.
+pub fn list_error(e: mouchak_mail_core::Error) -> McpError {
+    match e {
+        mouchak_mail_core::Error::InvalidInput(msg) => McpError::invalid_params(msg, None),
+        e => McpError::internal_error(e.to_string(), None),
+    }
+}
+
 /// Resolve a project by slug or human_key.
 ///
 /// Validates input format before querying database.
@@ -110,15 +146,33 @@ pub async fn resolve_project_and_agent(
 
 /// Parse comma-separated agent names and resolve them to IDs.
 ///
-/// Supports special keyword "broadcast" to resolve to all agents in the project.
+/// Supports special keyword "broadcast" to resolve to all agents in the project,
+/// and `capability:<name>[:all|:best]` addresses resolved from the capability registry.
 /// Returns Vec of agent IDs or error if any agent not found.
 pub async fn resolve_agent_names(
     ctx: &Ctx,
     mm: &Arc<ModelManager>,
     project_id: i64,
     names_csv: &str,
-) -> Result<Vec<i64>, McpError> {
+) -> Result<Vec<AgentId>, McpError> {
+    let (ids, _) = resolve_recipients(ctx, mm, project_id, names_csv, None).await?;
+    Ok(ids)
+}
+
+/// Parse comma-separated recipients and resolve them to IDs, keeping routing details.
+///
+/// Like [`resolve_agent_names`], but also returns how each capability address
+/// was resolved so callers can record it with the message. `exclude_agent_id`
+/// (usually the sender) is never resolved from a capability address.
+pub async fn resolve_recipients(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    project_id: i64,
+    names_csv: &str,
+    exclude_agent_id: Option<AgentId>,
+) -> Result<(Vec<AgentId>, Vec<CapabilityResolution>), McpError> {
     let mut ids = Vec::new();
+    let mut resolutions = Vec::new();
     for name in names_csv
         .split(',')
         .map(|s| s.trim())
@@ -139,18 +193,67 @@ pub async fn resolve_agent_names(
                 )
             })?;
             for agent in all_agents {
-                if !ids.contains(&agent.id.get()) {
-                    ids.push(agent.id.get());
+                if !ids.contains(&agent.id) {
+                    ids.push(agent.id);
                 }
             }
+        } else if CapabilityAddress::is_capability_address(name) {
+            let resolution =
+                resolve_capability_address(ctx, mm, project_id, name, exclude_agent_id).await?;
+            for id in resolution.agent_ids.iter().copied().map(AgentId::new) {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+            resolutions.push(resolution);
         } else {
             let agent = resolve_agent(ctx, mm, project_id, name).await?;
-            if !ids.contains(&agent.id.get()) {
-                ids.push(agent.id.get());
+            if !ids.contains(&agent.id) {
+                ids.push(agent.id);
             }
         }
     }
-    Ok(ids)
+    Ok((ids, resolutions))
+}
+
+/// Resolve a single `capability:<name>` address to the agents holding it.
+async fn resolve_capability_address(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    project_id: i64,
+    address: &str,
+    exclude_agent_id: Option<AgentId>,
+) -> Result<CapabilityResolution, McpError> {
+    let parsed = CapabilityAddress::parse(address).map_err(|e| {
+        let message = e.to_string();
+        mcp_err!(
+            ErrorCode::InvalidRecipient,
+            &message,
+            { "address": address, "suggestion": "Use capability:<name>, capability:<name>:all, or capability:<name>:best" }
+        )
+    })?;
+
+    CapabilityRoutingBmc::resolve(
+        ctx,
+        mm,
+        mouchak_mail_core::types::ProjectId::new(project_id),
+        &parsed,
+        exclude_agent_id,
+    )
+    .await
+    .map_err(|e| {
+        let message = e.to_string();
+        mcp_err!(
+            ErrorCode::InvalidRecipient,
+            &message,
+            {
+                "address": address,
+                "capability": parsed.capability,
+                "project_id": project_id,
+                "suggestion": "Grant the capability to an agent or address recipients by name"
+            }
+        )
+    })
 }
 
 /// Parse optional comma-separated agent names and resolve them to IDs.
@@ -161,7 +264,7 @@ pub async fn resolve_optional_agent_names(
     mm: &Arc<ModelManager>,
     project_id: i64,
     names_csv: Option<&str>,
-) -> Result<Option<Vec<i64>>, McpError> {
+) -> Result<Option<Vec<AgentId>>, McpError> {
     match names_csv {
         Some(names) if !names.trim().is_empty() => {
             Ok(Some(resolve_agent_names(ctx, mm, project_id, names).await?))
@@ -0,0 +1,307 @@
+//! Session journaling.
+//!
+//! When `mcp.session_journal_enabled` is set, each MCP session records its
+//! significant (state-changing) tool calls. At session end the calls are
+//! summarized into one message per agent, posted to the configured journal
+//! thread in that agent's project, so teams keep a durable log of what each
+//! agent instance actually did.
+//!
+//! Read-only tools (listing, fetching, searching, summarizing) are never
+//! recorded. Calls that name no project and agent cannot be attributed and
+//! are skipped.
+
+use chrono::{NaiveDateTime, Utc};
+use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::ModelManager;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::MessageId;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Tool name prefixes that never change state.
+const READ_ONLY_PREFIXES: &[&str] = &["list_", "get_", "search_", "fetch_", "check_", "summarize_"];
+
+/// Read-only tools that do not match [`READ_ONLY_PREFIXES`].
+const READ_ONLY_TOOLS: &[&str] = &[
+    "whois",
+    "product_inbox",
+    "export_mailbox",
+    "wait_for_path",
+    "mark_message_read",
+];
+
+/// Arguments worth quoting in a journal line, in display order.
+const SUMMARY_KEYS: &[&str] = &[
+    "to",
+    "subject",
+    "thread_id",
+    "message_id",
+    "path_pattern",
+    "paths",
+    "slot_name",
+    "name",
+];
+
+/// Longest argument value quoted in a journal line.
+const MAX_VALUE_CHARS: usize = 60;
+
+/// Returns `true` if calls to `tool_name` belong in a session journal.
+pub fn is_significant(tool_name: &str) -> bool {
+    !READ_ONLY_TOOLS.contains(&tool_name)
+        && !READ_ONLY_PREFIXES.iter().any(|p| tool_name.starts_with(p))
+}
+
+/// A recorded tool call.
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub project_slug: String,
+    pub agent_name: String,
+    pub tool_name: String,
+    pub summary: String,
+    pub ok: bool,
+    pub ts: NaiveDateTime,
+}
+
+/// Buffer of a session's significant tool calls.
+///
+/// Entries still buffered when the last handle is dropped are posted from a
+/// background task, so sessions that end without an explicit
+/// [`flush`](Self::flush) (e.g. closed HTTP sessions) are journaled too.
+pub struct SessionJournal {
+    mm: Arc<ModelManager>,
+    thread_id: String,
+    entries: Mutex<Vec<JournalEntry>>,
+}
+
+impl SessionJournal {
+    pub fn new(mm: Arc<ModelManager>, thread_id: impl Into<String>) -> Self {
+        Self {
+            mm,
+            thread_id: thread_id.into(),
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Thread that journals are posted to.
+    pub fn thread_id(&self) -> &str {
+        &self.thread_id
+    }
+
+    /// Records a tool call if it is significant and attributable.
+    pub fn record(
+        &self,
+        tool_name: &str,
+        project_slug: Option<String>,
+        agent_name: Option<String>,
+        args: &Option<serde_json::Value>,
+        ok: bool,
+    ) {
+        if !is_significant(tool_name) {
+            return;
+        }
+        let (Some(project_slug), Some(agent_name)) = (project_slug, agent_name) else {
+            tracing::debug!(tool = %tool_name, "Skipping unattributed call in session journal");
+            return;
+        };
+
+        let entry = JournalEntry {
+            project_slug,
+            agent_name,
+            tool_name: tool_name.to_string(),
+            summary: summarize_args(args),
+            ok,
+            ts: Utc::now().naive_utc(),
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.push(entry);
+        }
+    }
+
+    /// Number of buffered entries.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Posts buffered entries as journal messages and clears the buffer.
+    ///
+    /// # Returns
+    /// IDs of the posted messages, one per project and agent.
+    pub async fn flush(&self) -> Vec<MessageId> {
+        let entries = self.take();
+        post_journals(&self.mm, &self.thread_id, entries).await
+    }
+
+    fn take(&self) -> Vec<JournalEntry> {
+        self.entries
+            .lock()
+            .map(|mut e| std::mem::take(&mut *e))
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for SessionJournal {
+    fn drop(&mut self) {
+        let entries = self.take();
+        if entries.is_empty() {
+            return;
+        }
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                let mm = self.mm.clone();
+                let thread_id = std::mem::take(&mut self.thread_id);
+                handle.spawn(async move {
+                    post_journals(&mm, &thread_id, entries).await;
+                });
+            }
+            Err(_) => tracing::warn!(
+                count = entries.len(),
+                "Dropping session journal entries: no runtime to post them"
+            ),
+        }
+    }
+}
+
+/// Renders the journal message body for one agent's entries.
+pub fn render_journal(agent_name: &str, entries: &[JournalEntry]) -> String {
+    let mut body = format!(
+        "Session journal for **{}**: {} significant tool call(s).\n\n",
+        agent_name,
+        entries.len()
+    );
+    for e in entries {
+        body.push_str(&format!(
+            "- {} `{}`{}{}\n",
+            e.ts.format("%H:%M:%S"),
+            e.tool_name,
+            if e.summary.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", e.summary)
+            },
+            if e.ok { "" } else { " (failed)" }
+        ));
+    }
+    body
+}
+
+async fn post_journals(
+    mm: &ModelManager,
+    thread_id: &str,
+    entries: Vec<JournalEntry>,
+) -> Vec<MessageId> {
+    let mut groups: BTreeMap<(String, String), Vec<JournalEntry>> = BTreeMap::new();
+    for e in entries {
+        groups
+            .entry((e.project_slug.clone(), e.agent_name.clone()))
+            .or_default()
+            .push(e);
+    }
+
+    let ctx = Ctx::root_ctx();
+    let mut ids = Vec::new();
+    for ((project_slug, agent_name), entries) in groups {
+        match post_journal(&ctx, mm, thread_id, &project_slug, &agent_name, &entries).await {
+            Ok(id) => ids.push(id),
+            Err(e) => tracing::warn!(
+                project = %project_slug,
+                agent = %agent_name,
+                "Failed to post session journal: {}",
+                e
+            ),
+        }
+    }
+    ids
+}
+
+async fn post_journal(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    thread_id: &str,
+    project_slug: &str,
+    agent_name: &str,
+    entries: &[JournalEntry],
+) -> mouchak_mail_core::Result<MessageId> {
+    let project = ProjectBmc::get_by_slug(ctx, mm, project_slug).await?;
+    let agent = AgentBmc::get_by_name(ctx, mm, project.id, agent_name).await?;
+
+    let started = entries.first().map(|e| e.ts).unwrap_or_default();
+    let msg_c = MessageForCreate {
+        project_id: project.id,
+        sender_id: agent.id,
+        recipient_ids: vec![agent.id],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: format!(
+            "Session journal: {} ({})",
+            agent_name,
+            started.format("%Y-%m-%d %H:%M")
+        ),
+        body_md: render_journal(agent_name, entries),
+        thread_id: Some(thread_id.to_string()),
+        importance: Some("low".to_string()),
+        ack_required: false,
+    };
+    MessageBmc::create(ctx, mm, msg_c).await
+}
+
+fn summarize_args(args: &Option<serde_json::Value>) -> String {
+    let Some(obj) = args.as_ref().and_then(|v| v.as_object()) else {
+        return String::new();
+    };
+    SUMMARY_KEYS
+        .iter()
+        .filter_map(|key| {
+            let value = match obj.get(*key)? {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => return None,
+                other => other.to_string(),
+            };
+            let value: String = if value.chars().count() > MAX_VALUE_CHARS {
+                format!(
+                    "{}…",
+                    value.chars().take(MAX_VALUE_CHARS).collect::<String>()
+                )
+            } else {
+                value
+            };
+            Some(format!("{}={}", key, value))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_significant_skips_read_only_tools() {
+        assert!(is_significant("send_message"));
+        assert!(is_significant("reserve_file"));
+        assert!(!is_significant("list_inbox"));
+        assert!(!is_significant("get_context_pack"));
+        assert!(!is_significant("whois"));
+        assert!(!is_significant("mark_message_read"));
+    }
+
+    #[test]
+    fn test_summarize_args_quotes_known_keys() {
+        let args = Some(serde_json::json!({
+            "project_slug": "p",
+            "to": "bob",
+            "subject": "x".repeat(80),
+            "body_md": "not quoted",
+        }));
+        let summary = summarize_args(&args);
+        assert!(summary.starts_with("to=bob subject=xxx"));
+        assert!(summary.ends_with('…'));
+        assert!(!summary.contains("body_md"));
+    }
+}
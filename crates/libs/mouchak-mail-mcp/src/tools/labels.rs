@@ -0,0 +1,183 @@
+//! Message label tools
+//!
+//! `apply_label` and `remove_label` tag messages for triage, creating
+//! labels on first use; `list_by_label` lists a project's labels or the
+//! messages carrying one.
+
+use mouchak_mail_core::{
+    ctx::Ctx,
+    model::{
+        ModelManager,
+        label::{LabelBmc, LabelForCreate},
+    },
+    types::MessageId,
+};
+use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
+use std::sync::Arc;
+
+use super::helpers;
+use super::{ApplyLabelParams, ListByLabelParams, RemoveLabelParams};
+
+/// Maps label errors: bad input and unknown labels or messages are the
+/// caller's to fix.
+fn label_err(e: mouchak_mail_core::Error) -> McpError {
+    match e {
+        mouchak_mail_core::Error::InvalidInput(_)
+        | mouchak_mail_core::Error::NotFound
+        | mouchak_mail_core::Error::MessageNotFound(_) => {
+            McpError::invalid_params(e.to_string(), None)
+        }
+        e => McpError::internal_error(e.to_string(), None),
+    }
+}
+
+/// The `Labels:` line of a message, empty when it has none.
+pub fn labels_line(names: &[String]) -> String {
+    if names.is_empty() {
+        String::new()
+    } else {
+        format!("Labels: {}\n", names.join(", "))
+    }
+}
+
+/// Apply a label to a message, creating the label if the project lacks it.
+pub async fn apply_label_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ApplyLabelParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let created = match LabelBmc::get_by_name(ctx, mm, project.id, &params.label).await {
+        Ok(_) => false,
+        Err(mouchak_mail_core::Error::NotFound) => {
+            let label_c = LabelForCreate {
+                project_id: project.id,
+                name: params.label.clone(),
+                color: params.color.clone(),
+                description: None,
+            };
+            LabelBmc::create(ctx, mm, label_c)
+                .await
+                .map_err(label_err)?;
+            true
+        }
+        Err(e) => return Err(label_err(e)),
+    };
+    let applied = LabelBmc::apply(
+        ctx,
+        mm,
+        project.id,
+        MessageId::new(params.message_id),
+        &params.label,
+        Some((agent.id, agent.name.as_str())),
+    )
+    .await
+    .map_err(label_err)?;
+
+    let text = match (applied, created) {
+        (false, _) => format!(
+            "Message {} already has label '{}'.",
+            params.message_id, params.label
+        ),
+        (true, true) => format!(
+            "Created label '{}' and applied it to message {}.",
+            params.label, params.message_id
+        ),
+        (true, false) => format!(
+            "Applied label '{}' to message {}.",
+            params.label, params.message_id
+        ),
+    };
+    Ok(CallToolResult::success(vec![Content::text(text)]))
+}
+
+/// Remove a label from a message.
+pub async fn remove_label_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: RemoveLabelParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let removed = LabelBmc::remove(
+        ctx,
+        mm,
+        project.id,
+        MessageId::new(params.message_id),
+        &params.label,
+        Some((agent.id, agent.name.as_str())),
+    )
+    .await
+    .map_err(label_err)?;
+
+    let text = if removed {
+        format!(
+            "Removed label '{}' from message {}.",
+            params.label, params.message_id
+        )
+    } else {
+        format!(
+            "Message {} does not have label '{}'.",
+            params.message_id, params.label
+        )
+    };
+    Ok(CallToolResult::success(vec![Content::text(text)]))
+}
+
+/// List the messages carrying a label, or the project's labels.
+pub async fn list_by_label_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ListByLabelParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    let Some(label) = params.label else {
+        let labels = LabelBmc::list(ctx, mm, project.id)
+            .await
+            .map_err(label_err)?;
+        if labels.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "Project '{}' has no labels.",
+                params.project_slug
+            ))]));
+        }
+        let mut output = format!("Labels in '{}':\n", params.project_slug);
+        for label in &labels {
+            output.push_str(&format!(
+                "- {} ({}): {} message(s){}\n",
+                label.name,
+                label.color,
+                label.message_count,
+                if label.description.is_empty() {
+                    String::new()
+                } else {
+                    format!(" - {}", label.description)
+                }
+            ));
+        }
+        return Ok(CallToolResult::success(vec![Content::text(output)]));
+    };
+
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+    let messages = LabelBmc::list_messages(ctx, mm, project.id, &label, limit)
+        .await
+        .map_err(label_err)?;
+    let mut output = format!("Messages labeled '{}' ({}):\n\n", label, messages.len());
+    for msg in &messages {
+        output.push_str(&format!(
+            "- [{}] {} (from: {}, importance: {}, {})\n",
+            msg.id,
+            msg.subject,
+            msg.sender_name,
+            msg.importance,
+            msg.created_ts.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
@@ -13,6 +13,8 @@ use mouchak_mail_core::{
         message::{MessageBmc, MessageForCreate},
         project::ProjectBmc,
     },
+    types::AgentId,
+    utils::clock,
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
@@ -33,7 +35,7 @@ pub async fn list_macros_impl(
 ) -> Result<CallToolResult, McpError> {
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let macros = MacroDefBmc::list(ctx, mm, project.id.get())
+    let macros = MacroDefBmc::list(ctx, mm, project.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -84,7 +86,7 @@ pub async fn unregister_macro_impl(
 ) -> Result<CallToolResult, McpError> {
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let deleted = MacroDefBmc::delete(ctx, mm, project.id.get(), &params.name)
+    let deleted = MacroDefBmc::delete(ctx, mm, project.id, &params.name)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -104,7 +106,7 @@ pub async fn invoke_macro_impl(
 ) -> Result<CallToolResult, McpError> {
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let macro_def = MacroDefBmc::get_by_name(ctx, mm, project.id.get(), &params.name)
+    let macro_def = MacroDefBmc::get_by_name(ctx, mm, project.id, &params.name)
         .await
         .map_err(|e| McpError::invalid_params(format!("Macro not found: {}", e), None))?;
 
@@ -156,15 +158,15 @@ pub async fn quick_standup_workflow_impl(
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
-    let recipient_ids: Vec<i64> = agents.iter().map(|a| a.id.get()).collect();
+    let recipient_ids: Vec<AgentId> = agents.iter().map(|a| a.id).collect();
 
     let question = params
         .standup_question
         .unwrap_or_else(|| "What are you working on today?".to_string());
 
     let msg_c = MessageForCreate {
-        project_id: project.id.get(),
-        sender_id: sender.id.get(),
+        project_id: project.id,
+        sender_id: sender.id,
         recipient_ids,
         cc_ids: None,
         bcc_ids: None,
@@ -206,9 +208,9 @@ pub async fn quick_handoff_workflow_impl(
     };
 
     let msg_c = MessageForCreate {
-        project_id: project.id.get(),
-        sender_id: from_agent.id.get(),
-        recipient_ids: vec![to_agent.id.get()],
+        project_id: project.id,
+        sender_id: from_agent.id,
+        recipient_ids: vec![to_agent.id],
         cc_ids: None,
         bcc_ids: None,
         subject: format!("Task Handoff: {}", params.task_description),
@@ -261,9 +263,9 @@ pub async fn quick_review_workflow_impl(
     }
 
     let msg_c = MessageForCreate {
-        project_id: project.id.get(),
-        sender_id: requester.id.get(),
-        recipient_ids: vec![reviewer.id.get()],
+        project_id: project.id,
+        sender_id: requester.id,
+        recipient_ids: vec![reviewer.id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Code Review Request".to_string(),
@@ -341,8 +343,8 @@ pub async fn macro_start_session_impl(
     let mut granted_reservations = Vec::new();
     let mut reservation_conflicts = Vec::new();
     if let Some(paths) = params.file_reservation_paths {
-        let now = chrono::Utc::now().naive_utc();
-        let expires_ts = now + chrono::Duration::seconds(params.file_reservation_ttl_seconds);
+        let expires_ts = clock::expires_in(params.file_reservation_ttl_seconds)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
         let active_reservations = FileReservationBmc::list_active_for_project(ctx, mm, project.id)
             .await
@@ -382,15 +384,10 @@ pub async fn macro_start_session_impl(
     }
 
     // Fetch inbox
-    let inbox_messages = MessageBmc::list_inbox_for_agent(
-        ctx,
-        mm,
-        project.id.get(),
-        agent.id.get(),
-        params.inbox_limit,
-    )
-    .await
-    .unwrap_or_default();
+    let inbox_messages =
+        MessageBmc::list_inbox_for_agent(ctx, mm, project.id, agent.id, params.inbox_limit)
+            .await
+            .unwrap_or_default();
 
     let inbox_items: Vec<serde_json::Value> = inbox_messages
         .iter()
@@ -470,7 +467,7 @@ pub async fn macro_prepare_thread_impl(
     };
 
     // Get thread messages
-    let thread_messages = MessageBmc::list_by_thread(ctx, mm, project.id.get(), &params.thread_id)
+    let thread_messages = MessageBmc::list_by_thread(ctx, mm, project.id, &params.thread_id)
         .await
         .unwrap_or_default();
 
@@ -501,15 +498,10 @@ pub async fn macro_prepare_thread_impl(
     };
 
     // Fetch inbox
-    let inbox_messages = MessageBmc::list_inbox_for_agent(
-        ctx,
-        mm,
-        project.id.get(),
-        agent.id.get(),
-        params.inbox_limit,
-    )
-    .await
-    .unwrap_or_default();
+    let inbox_messages =
+        MessageBmc::list_inbox_for_agent(ctx, mm, project.id, agent.id, params.inbox_limit)
+            .await
+            .unwrap_or_default();
 
     let inbox_items: Vec<serde_json::Value> = inbox_messages
         .iter()
@@ -571,8 +563,8 @@ pub async fn macro_file_reservation_cycle_impl(
         .await
         .map_err(|e| McpError::invalid_params(format!("Agent not found: {}", e), None))?;
 
-    let now = chrono::Utc::now().naive_utc();
-    let expires_ts = now + chrono::Duration::seconds(params.ttl_seconds);
+    let expires_ts = clock::expires_in(params.ttl_seconds)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
     let active_reservations = FileReservationBmc::list_active_for_project(ctx, mm, project.id)
         .await
@@ -741,9 +733,9 @@ pub async fn macro_contact_handshake_impl(
     let welcome_result =
         if let (Some(subject), Some(body)) = (params.welcome_subject, params.welcome_body) {
             let msg_c = MessageForCreate {
-                project_id: project.id.get(),
-                sender_id: requester.id.get(),
-                recipient_ids: vec![target.id.get()],
+                project_id: project.id,
+                sender_id: requester.id,
+                recipient_ids: vec![target.id],
                 cc_ids: None,
                 bcc_ids: None,
                 subject,
@@ -7,30 +7,134 @@ use mouchak_mail_core::{
     model::{
         ModelManager,
         agent_capabilities::AgentCapabilityBmc,
-        message::{MessageBmc, MessageForCreate},
+        approval::ApprovalBmc,
+        broadcast_status::BroadcastStatusBmc,
+        capability_routing::CapabilityRoutingBmc,
+        custom_field::CustomFieldBmc,
+        dead_letter::{
+            DeadLetter, DeadLetterBmc, DeadLetterFilter, DeadLetterForCreate, DeadLetterRequeue,
+            split_names,
+        },
+        delegation::DelegationBmc,
+        global_thread::GlobalThreadBmc,
+        inbox_delta::InboxDeltaBmc,
+        label::LabelBmc,
+        message::{Message, MessageBmc, MessageForCreate},
+        product::ProductBmc,
+        project::ProjectBmc,
+        receipt::ReceiptBmc,
+        reply_chain::ReplyChainBmc,
+        reply_deadline::{ReplyDeadlineBmc, parse_deadline},
+        saved_search::{SavedSearchBmc, SavedSearchCriteria, SavedSearchForCreate},
+        search_facet::{DEFAULT_FACET_LIMIT, FacetCount, SearchFacetBmc},
+        thread_read::ThreadReadBmc,
+        thread_watcher::ThreadWatcherBmc,
+        ticket::TicketBmc,
+    },
+    types::{AgentId, MessageId, ProjectId},
+    utils::{
+        body_format::{BodyFormat, RenderFormat, render_body},
+        clock,
     },
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
 
+use super::budget::{self, ResultBudget};
+use super::errors::ErrorCode;
 use super::helpers;
+use super::labels;
+use super::tickets;
 use super::{
-    AcknowledgeMessageParams, GetMessageParams, GetThreadParams, ListInboxParams,
-    ListThreadsParams, MarkMessageReadParams, ReplyMessageParams, SearchMessagesParams,
-    SendMessageParams,
+    AcknowledgeMessageParams, ApprovePendingMessageParams, CheckInboxDeltaParams,
+    DeleteSavedSearchParams, ForwardMessageParams, GetBroadcastStatusParams, GetMessageParams,
+    GetMessageReceiptsParams, GetThreadParams, GetThreadTreeParams, ListCustomFieldsParams,
+    ListDeadLettersParams, ListDeadlinesParams, ListInboxParams, ListPendingMessagesParams,
+    ListSavedSearchesParams, ListThreadsParams, MarkMessageReadParams, ReplyMessageParams,
+    RequeueDeadLetterParams, RespondDeadlineParams, RunSavedSearchParams, SaveSearchParams,
+    SearchFacetsParams, SearchMessagesParams, SendMessageParams, WatchThreadParams,
 };
 
 /// Send a message from one agent to others.
+///
+/// A send whose project or agents no longer resolve is kept as a dead letter.
 pub async fn send_message_impl(
     ctx: &Ctx,
     mm: &Arc<ModelManager>,
     params: SendMessageParams,
+) -> Result<CallToolResult, McpError> {
+    let envelope = DeadLetterForCreate {
+        project_slug: params.project_slug.clone(),
+        sender_name: params.sender_name.clone(),
+        on_behalf_of: params
+            .on_behalf_of
+            .clone()
+            .filter(|name| !name.trim().is_empty()),
+        to: split_names(&params.to),
+        cc: split_names(params.cc.as_deref().unwrap_or_default()),
+        bcc: split_names(params.bcc.as_deref().unwrap_or_default()),
+        subject: params.subject.clone(),
+        body_md: params.body_md.clone(),
+        thread_id: params.thread_id.clone(),
+        importance: params.importance.clone(),
+        ack_required: params.ack_required.unwrap_or(false),
+        error: String::new(),
+    };
+    match deliver_message(ctx, mm, params).await {
+        Err(e) if is_undeliverable(&e) => Err(dead_letter(ctx, mm, envelope, e).await),
+        result => result,
+    }
+}
+
+/// Whether a send failed because its project or an agent did not resolve.
+fn is_undeliverable(e: &McpError) -> bool {
+    let code = e
+        .data
+        .as_ref()
+        .and_then(|data| data.get("error_code"))
+        .and_then(|code| serde_json::from_value::<ErrorCode>(code.clone()).ok());
+    matches!(
+        code,
+        Some(ErrorCode::ProjectNotFound | ErrorCode::AgentNotFound)
+    )
+}
+
+/// Keeps an undeliverable send as a dead letter and names it in the error.
+async fn dead_letter(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    mut envelope: DeadLetterForCreate,
+    mut e: McpError,
+) -> McpError {
+    envelope.error = e.message.to_string();
+    match DeadLetterBmc::record(ctx, mm, envelope).await {
+        Ok(id) => {
+            e.message = format!(
+                "{} (kept as dead letter {}; requeue it with requeue_dead_letter)",
+                e.message, id
+            )
+            .into();
+            if let Some(data) = e.data.as_mut().and_then(|d| d.as_object_mut()) {
+                data.insert("dead_letter_id".to_string(), id.into());
+            }
+        }
+        Err(record_err) => {
+            tracing::warn!("Failed to keep undeliverable message: {}", record_err);
+        }
+    }
+    e
+}
+
+async fn deliver_message(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: SendMessageParams,
 ) -> Result<CallToolResult, McpError> {
     let (project, sender) =
         helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.sender_name)
             .await?;
 
-    if !AgentCapabilityBmc::check(ctx, mm, sender.id.get(), "send_message")
+    if !AgentCapabilityBmc::check(ctx, mm, sender.id, "send_message")
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?
     {
@@ -43,19 +147,71 @@ pub async fn send_message_impl(
         ));
     }
 
-    let recipient_ids = helpers::resolve_agent_names(ctx, mm, project.id.get(), &params.to).await?;
+    let body_format = parse_format::<BodyFormat>(params.body_format.as_deref())?;
+    if body_format == BodyFormat::Json
+        && serde_json::from_str::<serde_json::Value>(&params.body_md).is_err()
+    {
+        return Err(McpError::invalid_params(
+            "body_md is not valid JSON but body_format is 'json'".to_string(),
+            None,
+        ));
+    }
+
+    let custom_fields =
+        MessageBmc::validate_custom_fields(ctx, mm, project.id, params.custom_fields.as_ref())
+            .await
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
-    let cc_ids =
-        helpers::resolve_optional_agent_names(ctx, mm, project.id.get(), params.cc.as_deref())
-            .await?;
+    // A delegate writes as the agent it acts for, which stays the sender
+    let author = match params.on_behalf_of.as_deref() {
+        Some(name) if !name.trim().is_empty() => {
+            helpers::resolve_agent(ctx, mm, project.id.get(), name).await?
+        }
+        _ => sender.clone(),
+    };
 
-    let bcc_ids =
-        helpers::resolve_optional_agent_names(ctx, mm, project.id.get(), params.bcc.as_deref())
-            .await?;
+    // Capability addresses never resolve back to the sender
+    let sender_id = Some(author.id);
+    let (recipient_ids, mut routing) =
+        helpers::resolve_recipients(ctx, mm, project.id.get(), &params.to, sender_id).await?;
+
+    let cc_ids = match params.cc.as_deref() {
+        Some(cc) if !cc.trim().is_empty() => {
+            let (ids, cc_routing) =
+                helpers::resolve_recipients(ctx, mm, project.id.get(), cc, sender_id).await?;
+            routing.extend(cc_routing);
+            Some(ids)
+        }
+        _ => None,
+    };
+
+    let bcc_ids = match params.bcc.as_deref() {
+        Some(bcc) if !bcc.trim().is_empty() => {
+            let (ids, bcc_routing) =
+                helpers::resolve_recipients(ctx, mm, project.id.get(), bcc, sender_id).await?;
+            routing.extend(bcc_routing);
+            Some(ids)
+        }
+        _ => None,
+    };
+
+    // Addressed recipients owe the reply; bcc recipients stay silent
+    let deadline = match params.respond_by.as_deref() {
+        Some(respond_by) if !respond_by.trim().is_empty() => Some(
+            parse_deadline(respond_by, clock::server_now())
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?,
+        ),
+        _ => None,
+    };
+    let deadline_recipients: Vec<AgentId> = recipient_ids
+        .iter()
+        .chain(cc_ids.iter().flatten())
+        .copied()
+        .collect();
 
     let msg_c = MessageForCreate {
-        project_id: project.id.get(),
-        sender_id: sender.id.get(),
+        project_id: project.id,
+        sender_id: author.id,
         recipient_ids,
         cc_ids,
         bcc_ids,
@@ -63,17 +219,73 @@ pub async fn send_message_impl(
         body_md: params.body_md,
         thread_id: params.thread_id,
         importance: params.importance,
-        ack_required: params.ack_required.unwrap_or(false),
+        ack_required: params.ack_required.unwrap_or(false) || deadline.is_some(),
     };
 
-    let msg_id = MessageBmc::create(ctx, mm, msg_c)
+    let msg_id = MessageBmc::create_on_behalf(ctx, mm, sender.id, msg_c)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::DelegationDenied(_) => {
+                McpError::invalid_params(e.to_string(), None)
+            }
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
+
+    CapabilityRoutingBmc::record(ctx, mm, msg_id, &routing)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if let Some(deadline) = &deadline {
+        ReplyDeadlineBmc::request(ctx, mm, msg_id, &deadline_recipients, deadline.value)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    }
 
-    let msg = format!(
-        "Message sent (id: {}) from '{}' to '{}' with subject '{}'",
-        msg_id, params.sender_name, params.to, params.subject
+    if body_format != BodyFormat::Markdown {
+        MessageBmc::set_body_format(ctx, mm, msg_id, body_format)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    }
+    // Sender values win over fields added by post_receive plugins
+    MessageBmc::merge_custom_fields(ctx, mm, msg_id, &custom_fields)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let from = if author.id == sender.id {
+        format!("'{}'", sender.name)
+    } else {
+        format!("'{}' on behalf of '{}'", sender.name, author.name)
+    };
+    let mut msg = format!(
+        "Message sent (id: {}) from {} to '{}' with subject '{}'",
+        msg_id, from, params.to, params.subject
     );
+    if let Some(deadline) = deadline {
+        msg.push_str(&format!(
+            "\nReply requested by {} UTC",
+            deadline.value.format("%Y-%m-%d %H:%M")
+        ));
+        if let Some(warning) = deadline.warning {
+            msg.push_str(&format!("\nWarning: {}", warning));
+        }
+    }
+    let held = ApprovalBmc::get(ctx, mm, msg_id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if let Some(pending) = held {
+        msg.push_str(&format!(
+            "\nHeld for approval until {} (approvers: {})",
+            pending.expires_ts.format("%Y-%m-%d %H:%M UTC"),
+            approvers_label(&pending.approvers)
+        ));
+    }
+    for r in &routing {
+        msg.push_str(&format!(
+            "\nRouted '{}' ({}) to: {}",
+            r.address,
+            r.mode.as_str(),
+            r.agent_names.join(", ")
+        ));
+    }
     Ok(CallToolResult::success(vec![Content::text(msg)]))
 }
 
@@ -87,7 +299,7 @@ pub async fn list_inbox_impl(
         helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
             .await?;
 
-    if !AgentCapabilityBmc::check(ctx, mm, agent.id.get(), "fetch_inbox")
+    if !AgentCapabilityBmc::check(ctx, mm, agent.id, "fetch_inbox")
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?
     {
@@ -100,28 +312,145 @@ pub async fn list_inbox_impl(
         ));
     }
 
-    let messages = MessageBmc::list_inbox_for_agent(
+    let render = parse_format::<RenderFormat>(params.format.as_deref())?;
+    let include_bodies = params.include_bodies.unwrap_or(false) || params.format.is_some();
+
+    let limit = params.limit.unwrap_or(50);
+    let page = MessageBmc::list_inbox_page(
         ctx,
         mm,
-        project.id.get(),
-        agent.id.get(),
-        params.limit.unwrap_or(50),
+        project.id,
+        agent.id,
+        limit,
+        params.cursor.as_deref(),
     )
     .await
-    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    .map_err(helpers::list_error)?;
+    let messages = &page.items;
+    let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+    let formats = if include_bodies && render != RenderFormat::Raw {
+        MessageBmc::get_body_formats(ctx, mm, &ids)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?
+    } else {
+        Default::default()
+    };
+    let watched = ThreadWatcherBmc::watcher_deliveries(ctx, mm, agent.id, &ids)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let delegates = DelegationBmc::delegates_for_messages(ctx, mm, &ids)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
+    let count = if messages.len() as i64 == page.total {
+        page.total.to_string()
+    } else {
+        format!("{} of {}", messages.len(), page.total)
+    };
     let mut output = format!(
         "Inbox for '{}' ({} messages):\n\n",
+        params.agent_name, count
+    );
+    for m in messages {
+        output.push_str(&format!(
+            "- [{}] {} (from: {}, thread: {:?}, {}){}\n",
+            m.id,
+            m.subject,
+            sender_label(&m.sender_name, delegates.get(&m.id)),
+            m.thread_id,
+            m.importance,
+            if watched.contains(&m.id) {
+                " [watching]"
+            } else {
+                ""
+            }
+        ));
+        if include_bodies {
+            let from = formats.get(&m.id).copied().unwrap_or_default();
+            for line in render_body(&m.body_md, from, render).lines() {
+                output.push_str(&format!("    {}\n", line));
+            }
+        }
+    }
+    if let Some(cursor) = &page.next_cursor {
+        output.push_str(&format!("\nMore results: pass cursor \"{}\"\n", cursor));
+    }
+
+    let budget = ResultBudget::list(messages.len(), limit)
+        .suggest("search_messages with specific terms")
+        .suggest("get_thread for a single conversation")
+        .suggest("a smaller limit");
+    Ok(budget::annotate(
+        CallToolResult::success(vec![Content::text(output)]),
+        budget,
+    ))
+}
+
+/// Return new inbox messages and read/ack changes since a delta token.
+pub async fn check_inbox_delta_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: CheckInboxDeltaParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    if !AgentCapabilityBmc::check(ctx, mm, agent.id, "fetch_inbox")
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+    {
+        return Err(McpError::invalid_params(
+            format!(
+                "Agent '{}' does not have 'fetch_inbox' capability",
+                params.agent_name
+            ),
+            None,
+        ));
+    }
+
+    let delta = InboxDeltaBmc::check(
+        ctx,
+        mm,
+        project.id,
+        agent.id,
+        params.since_token.as_deref(),
+        params.limit.unwrap_or(50),
+    )
+    .await
+    .map_err(helpers::list_error)?;
+
+    let mut output = format!(
+        "Inbox delta for '{}' ({} new, {} changed):\n\n",
         params.agent_name,
-        messages.len()
+        delta.new_messages.len(),
+        delta.changed.len()
     );
-    for m in &messages {
+    for item in &delta.new_messages {
+        let m = &item.message;
         output.push_str(&format!(
             "- [{}] {} (from: {}, thread: {:?}, {})\n",
             m.id, m.subject, m.sender_name, m.thread_id, m.importance
         ));
     }
-
+    for change in &delta.changed {
+        let read = change
+            .read_ts
+            .map(|ts| ts.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let ack = change
+            .ack_ts
+            .map(|ts| ts.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        output.push_str(&format!(
+            "- [{}] changed (read: {}, acked: {})\n",
+            change.message_id, read, ack
+        ));
+    }
+    output.push_str(&format!("\nNext token: {}\n", delta.token));
+    if delta.has_more {
+        output.push_str("More new messages are waiting; check again with this token.\n");
+    }
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
@@ -131,24 +460,90 @@ pub async fn get_message_impl(
     mm: &Arc<ModelManager>,
     params: GetMessageParams,
 ) -> Result<CallToolResult, McpError> {
-    let message = MessageBmc::get(ctx, mm, params.message_id)
+    let render = parse_format::<RenderFormat>(params.format.as_deref())?;
+    let mut message = MessageBmc::get(ctx, mm, MessageId::new(params.message_id))
         .await
         .map_err(|e| McpError::invalid_params(format!("Message not found: {}", e), None))?;
+    let offloaded = MessageBmc::get_offloaded_body(ctx, mm, MessageId::new(message.id))
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let size_line = match offloaded {
+        Some(full) if params.full.unwrap_or(false) => {
+            message.body_md = full.body_md;
+            String::new()
+        }
+        Some(full) => format!(
+            "Body: preview of {} bytes (full=true for the whole body)\n",
+            full.size_bytes
+        ),
+        None => String::new(),
+    };
+
+    let body = if render == RenderFormat::Raw {
+        message.body_md
+    } else {
+        let from = MessageBmc::get_body_format(ctx, mm, MessageId::new(message.id))
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        render_body(&message.body_md, from, render)
+    };
+
+    let fields = MessageBmc::get_custom_fields(ctx, mm, &[message.id])
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let fields_line = fields
+        .get(&message.id)
+        .map(|f| format!("Fields: {}\n", format_custom_fields(f)))
+        .unwrap_or_default();
+    let linked = TicketBmc::list_for_messages(ctx, mm, &[message.id])
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let delegates = DelegationBmc::delegates_for_messages(ctx, mm, &[message.id])
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let label_names = LabelBmc::names_for_message(ctx, mm, MessageId::new(message.id))
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
     let output = format!(
-        "Message ID: {}\nFrom: {}\nSubject: {}\nThread: {:?}\nImportance: {}\nCreated: {}\n\n---\n{}",
+        "Message ID: {}\nPublic ID: {}\nFrom: {}\nSubject: {}\nThread: {:?}\nImportance: {}\nCreated: {}\n{}{}{}{}\n---\n{}",
         message.id,
-        message.sender_name,
+        message.public_id,
+        sender_label(&message.sender_name, delegates.get(&message.id)),
         message.subject,
         message.thread_id,
         message.importance,
         message.created_ts,
-        message.body_md
+        fields_line,
+        labels::labels_line(&label_names),
+        tickets::tickets_line(linked.get(&message.id)),
+        size_line,
+        body
     );
 
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
+/// Names the sender, and the delegate who wrote on its behalf if any.
+fn sender_label(sender: &str, delegate: Option<&String>) -> String {
+    match delegate {
+        Some(delegate) => format!("{} on behalf of {}", delegate, sender),
+        None => sender.to_string(),
+    }
+}
+
+/// Formats custom field values as `name=value` pairs.
+fn format_custom_fields(fields: &serde_json::Map<String, serde_json::Value>) -> String {
+    fields
+        .iter()
+        .map(|(name, value)| match value {
+            serde_json::Value::String(s) => format!("{}={}", name, s),
+            other => format!("{}={}", name, other),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Search messages using full-text search.
 pub async fn search_messages_impl(
     ctx: &Ctx,
@@ -157,12 +552,23 @@ pub async fn search_messages_impl(
 ) -> Result<CallToolResult, McpError> {
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let messages = MessageBmc::search(
+    let filters = match &params.fields {
+        Some(fields) => CustomFieldBmc::filters(ctx, mm, project.id, fields)
+            .await
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?,
+        None => Vec::new(),
+    };
+
+    let limit = params.limit.unwrap_or(20);
+    let hits = MessageBmc::search_with_snippets(
         ctx,
         mm,
-        project.id.get(),
+        project.id,
         &params.query,
-        params.limit.unwrap_or(20),
+        limit,
+        params.context.unwrap_or(0),
+        params.include_attachments.unwrap_or(false),
+        &filters,
     )
     .await
     .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -170,43 +576,374 @@ pub async fn search_messages_impl(
     let mut output = format!(
         "Search results for '{}' ({} matches):\n\n",
         params.query,
-        messages.len()
+        hits.len()
     );
-    for m in &messages {
+    for hit in &hits {
+        let m = &hit.message;
         output.push_str(&format!(
-            "- [{}] {} (from: {}, thread: {:?})\n",
+            "- [{}] {} (from: {}, thread: {:?}",
             m.id, m.subject, m.sender_name, m.thread_id
         ));
+        if let Some(score) = hit.score {
+            output.push_str(&format!(", score: {:.2}", score));
+        }
+        output.push_str(")\n");
+        if let Some(fields) = &hit.custom_fields {
+            output.push_str(&format!("    fields: {}\n", format_custom_fields(fields)));
+        }
+        if let Some(snippet) = &hit.snippet {
+            let offsets: Vec<String> = snippet
+                .matches
+                .iter()
+                .map(|o| format!("{}..{}", o.start, o.end))
+                .collect();
+            let field = match &hit.attachment {
+                Some(filename) => format!("{} {}", hit.field, filename),
+                None => hit.field.to_string(),
+            };
+            output.push_str(&format!(
+                "    {}: {}\n    matches: [{}]\n",
+                field,
+                snippet.highlighted.replace('\n', " "),
+                offsets.join(", ")
+            ));
+        }
+        for c in &hit.context {
+            output.push_str(&format!(
+                "    {} [{}] {}: {}\n",
+                c.position, c.id, c.sender_name, c.excerpt
+            ));
+        }
+    }
+
+    let mut budget = ResultBudget::list(hits.len(), limit)
+        .suggest("more specific terms or a quoted phrase")
+        .suggest("a smaller limit");
+    if params.context.unwrap_or(0) > 0 {
+        budget = budget.suggest("context=0");
     }
+    Ok(budget::annotate(
+        CallToolResult::success(vec![Content::text(output)]),
+        budget,
+    ))
+}
+
+/// Count the messages matching a search by facet.
+pub async fn search_facets_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: SearchFacetsParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    let filters = match &params.fields {
+        Some(fields) => CustomFieldBmc::filters(ctx, mm, project.id, fields)
+            .await
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?,
+        None => Vec::new(),
+    };
+    let facets = SearchFacetBmc::facets(
+        ctx,
+        mm,
+        project.id,
+        &params.query,
+        params.include_attachments.unwrap_or(false),
+        &filters,
+        params.limit.unwrap_or(DEFAULT_FACET_LIMIT),
+    )
+    .await
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
+    let format_counts = |counts: &[FacetCount]| {
+        counts
+            .iter()
+            .map(|c| format!("{} ({})", c.value, c.count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut output = format!(
+        "Facets for '{}' ({} matches):\n",
+        params.query, facets.total
+    );
+    if facets.total > 0 {
+        output.push_str(&format!("- sender: {}\n", format_counts(&facets.sender)));
+        output.push_str(&format!(
+            "- importance: {}\n",
+            format_counts(&facets.importance)
+        ));
+        output.push_str(&format!("- month: {}\n", format_counts(&facets.month)));
+        for (name, counts) in &facets.fields {
+            output.push_str(&format!("- field {}: {}\n", name, format_counts(counts)));
+        }
+    }
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-/// Get all messages in a thread.
-pub async fn get_thread_impl(
+/// List a project's custom message fields.
+pub async fn list_custom_fields_impl(
     ctx: &Ctx,
     mm: &Arc<ModelManager>,
-    params: GetThreadParams,
+    params: ListCustomFieldsParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let fields = CustomFieldBmc::list(ctx, mm, project.id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    if fields.is_empty() {
+        return Ok(CallToolResult::success(vec![Content::text(format!(
+            "Project '{}' defines no custom fields.",
+            params.project_slug
+        ))]));
+    }
+    let mut output = format!("Custom fields in '{}':\n", params.project_slug);
+    for field in &fields {
+        output.push_str(&format!(
+            "- {} ({}{})\n",
+            field.name,
+            field.field_type.as_str(),
+            if field.required { ", required" } else { "" }
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Splits a comma-separated list, dropping blanks.
+fn split_csv(csv: Option<&str>) -> Vec<String> {
+    csv.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Maps a saved search lookup error, naming the search when it is missing.
+fn saved_search_err(e: mouchak_mail_core::Error, search_id: i64, project_slug: &str) -> McpError {
+    match e {
+        mouchak_mail_core::Error::NotFound => McpError::invalid_params(
+            format!(
+                "Saved search {} not found in project '{}'",
+                search_id, project_slug
+            ),
+            None,
+        ),
+        e => McpError::internal_error(e.to_string(), None),
+    }
+}
+
+/// Save named search criteria as a smart folder.
+pub async fn save_search_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: SaveSearchParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let agent_id = match &params.agent_name {
+        Some(name) => Some(
+            helpers::resolve_agent(ctx, mm, project.id.get(), name)
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+
+    let id = SavedSearchBmc::save(
+        ctx,
+        mm,
+        SavedSearchForCreate {
+            project_id: project.id,
+            agent_id,
+            name: params.name.clone(),
+            criteria: SavedSearchCriteria {
+                query: params.query,
+                importance: split_csv(params.importance.as_deref()),
+                from: split_csv(params.from.as_deref()),
+                unread: params.unread,
+                unacked: params.unacked,
+                within: params.within,
+            },
+        },
+    )
+    .await
+    .map_err(|e| match e {
+        mouchak_mail_core::Error::InvalidInput(_) => McpError::invalid_params(e.to_string(), None),
+        e => McpError::internal_error(e.to_string(), None),
+    })?;
+    let search = SavedSearchBmc::get(ctx, mm, project.id, id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let count = SavedSearchBmc::count(ctx, mm, &search)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    Ok(CallToolResult::success(vec![Content::text(format!(
+        "Saved search '{}' (id: {}) matches {} message(s).",
+        search.name, id, count
+    ))]))
+}
+
+/// List saved searches with their current match counts.
+pub async fn list_saved_searches_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ListSavedSearchesParams,
 ) -> Result<CallToolResult, McpError> {
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let agent_id = match &params.agent_name {
+        Some(name) => Some(
+            helpers::resolve_agent(ctx, mm, project.id.get(), name)
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+    let folders = SavedSearchBmc::folders(ctx, mm, project.id, agent_id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    if folders.is_empty() {
+        return Ok(CallToolResult::success(vec![Content::text(format!(
+            "No saved searches in '{}'.",
+            params.project_slug
+        ))]));
+    }
+    let mut output = format!("Saved searches in '{}':\n", params.project_slug);
+    for folder in &folders {
+        let search = &folder.search;
+        output.push_str(&format!(
+            "- [{}] {} ({}): {} message(s), {}\n",
+            search.id,
+            search.name,
+            search.agent_name.as_deref().unwrap_or("shared"),
+            folder.count,
+            serde_json::to_string(&search.criteria).unwrap_or_default()
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
 
-    let messages = MessageBmc::list_by_thread(ctx, mm, project.id.get(), &params.thread_id)
+/// Evaluate a saved search against the current mailbox.
+pub async fn run_saved_search_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: RunSavedSearchParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let search = SavedSearchBmc::get(ctx, mm, project.id, params.search_id)
+        .await
+        .map_err(|e| saved_search_err(e, params.search_id, &params.project_slug))?;
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+    let result = SavedSearchBmc::evaluate(ctx, mm, search, limit)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
     let mut output = format!(
-        "Thread '{}' ({} messages):\n\n",
-        params.thread_id,
-        messages.len()
+        "Saved search '{}' ({} matches):\n\n",
+        result.search.name, result.total
     );
+    for msg in &result.messages {
+        output.push_str(&format!(
+            "- [{}] {} (from: {}, importance: {}, {})\n",
+            msg.id,
+            msg.subject,
+            msg.sender_name,
+            msg.importance,
+            msg.created_ts.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Delete a saved search.
+pub async fn delete_saved_search_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: DeleteSavedSearchParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    SavedSearchBmc::delete(ctx, mm, project.id, params.search_id)
+        .await
+        .map_err(|e| saved_search_err(e, params.search_id, &params.project_slug))?;
+    Ok(CallToolResult::success(vec![Content::text(format!(
+        "Deleted saved search {}.",
+        params.search_id
+    ))]))
+}
+
+/// Get the messages in a thread, optionally only those since the agent's last read.
+pub async fn get_thread_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: GetThreadParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let reader = match &params.agent_name {
+        Some(name) => Some(helpers::resolve_agent(ctx, mm, project.id.get(), name).await?),
+        None if params.unread_only => {
+            return Err(McpError::invalid_params(
+                "unread_only requires agent_name".to_string(),
+                None,
+            ));
+        }
+        None => None,
+    };
+
+    let messages = match &reader {
+        Some(agent) if params.unread_only => {
+            ThreadReadBmc::list_unread(ctx, mm, project.id, agent.id, &params.thread_id).await
+        }
+        _ => MessageBmc::list_by_thread(ctx, mm, project.id, &params.thread_id).await,
+    }
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if let Some(agent) = &reader {
+        ThreadReadBmc::mark_all_read(ctx, mm, project.id, agent.id, &params.thread_id)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    }
+
+    let mut output = if params.unread_only {
+        format!(
+            "Thread '{}' ({} new messages since last read):\n\n",
+            params.thread_id,
+            messages.len()
+        )
+    } else {
+        format!(
+            "Thread '{}' ({} messages):\n\n",
+            params.thread_id,
+            messages.len()
+        )
+    };
+    let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+    let linked = TicketBmc::list_for_messages(ctx, mm, &ids)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let delegates = DelegationBmc::delegates_for_messages(ctx, mm, &ids)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
     for m in &messages {
         output.push_str(&format!(
-            "---\n[{}] From: {} | {}\nSubject: {}\n\n{}\n\n",
-            m.id, m.sender_name, m.created_ts, m.subject, m.body_md
+            "---\n[{}] From: {} | {}\nSubject: {}\n{}\n{}\n\n",
+            m.id,
+            sender_label(&m.sender_name, delegates.get(&m.id)),
+            m.created_ts,
+            m.subject,
+            tickets::tickets_line(linked.get(&m.id)),
+            m.body_md
         ));
     }
 
-    Ok(CallToolResult::success(vec![Content::text(output)]))
+    let budget = ResultBudget {
+        returned: Some(messages.len()),
+        ..Default::default()
+    }
+    .suggest("summarize_thread for a digest")
+    .suggest("search_messages with context to read only relevant messages");
+    Ok(budget::annotate(
+        CallToolResult::success(vec![Content::text(output)]),
+        budget,
+    ))
 }
 
 /// Reply to an existing message.
@@ -219,7 +956,7 @@ pub async fn reply_message_impl(
         helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.sender_name)
             .await?;
 
-    if !AgentCapabilityBmc::check(ctx, mm, sender.id.get(), "send_message")
+    if !AgentCapabilityBmc::check(ctx, mm, sender.id, "send_message")
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?
     {
@@ -232,25 +969,22 @@ pub async fn reply_message_impl(
         ));
     }
 
-    let original_msg = MessageBmc::get(ctx, mm, params.message_id)
+    let original_msg = MessageBmc::get(ctx, mm, MessageId::new(params.message_id))
         .await
         .map_err(|e| McpError::invalid_params(format!("Message not found: {}", e), None))?;
 
-    let subject = if original_msg.subject.starts_with("Re: ") {
-        original_msg.subject.clone()
-    } else {
-        format!("Re: {}", original_msg.subject)
-    };
+    let subject = mouchak_mail_core::utils::reply_subject(&original_msg.subject);
+    let thread_id = continued_thread_id(ctx, mm, &original_msg, project.id).await?;
 
     let msg_c = MessageForCreate {
-        project_id: project.id.get(),
-        sender_id: sender.id.get(),
-        recipient_ids: vec![original_msg.sender_id],
+        project_id: project.id,
+        sender_id: sender.id,
+        recipient_ids: vec![AgentId::new(original_msg.sender_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: subject.clone(),
         body_md: params.body_md,
-        thread_id: original_msg.thread_id.clone(),
+        thread_id,
         importance: params.importance,
         ack_required: false,
     };
@@ -258,11 +992,158 @@ pub async fn reply_message_impl(
     let msg_id = MessageBmc::create(ctx, mm, msg_c)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    MessageBmc::inherit_custom_fields(ctx, mm, MessageId::new(original_msg.id), msg_id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    ReplyChainBmc::link(ctx, mm, msg_id, original_msg.id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
     let msg = format!("Reply sent (id: {}) with subject '{}'", msg_id, subject);
     Ok(CallToolResult::success(vec![Content::text(msg)]))
 }
 
+/// Get a thread as a tree of replies.
+pub async fn get_thread_tree_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: GetThreadTreeParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let tree = ReplyChainBmc::thread_tree(ctx, mm, project.id, &params.thread_id)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::NotFound => McpError::invalid_params(
+                format!("Thread '{}' has no messages", params.thread_id),
+                None,
+            ),
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
+    let json_str = serde_json::to_string_pretty(&tree)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// Thread id for a message continuing `original` in `project_id`.
+///
+/// Within the original's project that is its thread id; elsewhere it is the
+/// original's global thread id, so the new message joins the same global
+/// thread instead of starting one that merely shares a local id.
+async fn continued_thread_id(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    original: &Message,
+    project_id: ProjectId,
+) -> Result<Option<String>, McpError> {
+    let Some(thread_id) = original.thread_id.as_deref() else {
+        return Ok(None);
+    };
+    if original.project_id == project_id.get() {
+        return Ok(Some(thread_id.to_string()));
+    }
+    let global = GlobalThreadBmc::get_id(ctx, mm, ProjectId::new(original.project_id), thread_id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(global.or_else(|| Some(thread_id.to_string())))
+}
+
+/// Forward a message into a project, across a product's projects if needed.
+pub async fn forward_message_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ForwardMessageParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, sender) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.sender_name)
+            .await?;
+
+    if !AgentCapabilityBmc::check(ctx, mm, sender.id, "send_message")
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+    {
+        return Err(McpError::invalid_params(
+            format!(
+                "Agent '{}' does not have 'send_message' capability",
+                params.sender_name
+            ),
+            None,
+        ));
+    }
+
+    let original = MessageBmc::get(ctx, mm, MessageId::new(params.message_id))
+        .await
+        .map_err(|e| McpError::invalid_params(format!("Message not found: {}", e), None))?;
+    let source = ProjectBmc::get(ctx, mm, ProjectId::new(original.project_id))
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if source.id != project.id {
+        let products = ProductBmc::list_for_project(ctx, mm, project.id)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let source_products = ProductBmc::list_for_project(ctx, mm, source.id)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        if !products
+            .iter()
+            .any(|p| source_products.iter().any(|s| s.id == p.id))
+        {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Project '{}' shares no product with '{}'; link both to a product to forward between them",
+                    source.slug, project.slug
+                ),
+                None,
+            ));
+        }
+    }
+
+    let (recipient_ids, routing) =
+        helpers::resolve_recipients(ctx, mm, project.id.get(), &params.to, Some(sender.id)).await?;
+    let thread_id = continued_thread_id(ctx, mm, &original, project.id).await?;
+    let subject = mouchak_mail_core::utils::forward_subject(&original.subject);
+    let mut body_md = match params.note.as_deref() {
+        Some(note) if !note.trim().is_empty() => format!("{}\n\n", note.trim_end()),
+        _ => String::new(),
+    };
+    body_md.push_str(&format!(
+        "---------- Forwarded message ----------\nFrom: {} ({})\nDate: {}\nSubject: {}\n\n{}",
+        original.sender_name, source.slug, original.created_ts, original.subject, original.body_md
+    ));
+
+    let msg_c = MessageForCreate {
+        project_id: project.id,
+        sender_id: sender.id,
+        recipient_ids,
+        cc_ids: None,
+        bcc_ids: None,
+        subject: subject.clone(),
+        body_md,
+        thread_id: thread_id.clone(),
+        importance: Some(original.importance.clone()),
+        ack_required: false,
+    };
+    let msg_id = MessageBmc::create(ctx, mm, msg_c)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    CapabilityRoutingBmc::record(ctx, mm, msg_id, &routing)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let mut msg = format!(
+        "Forwarded message {} from '{}' as {} to '{}' with subject '{}'",
+        original.id, source.slug, msg_id, params.to, subject
+    );
+    if let Some(thread_id) = thread_id {
+        let global = GlobalThreadBmc::get_id(ctx, mm, project.id, &thread_id)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        if let Some(global) = global {
+            msg.push_str(&format!("\nGlobal thread: {}", global));
+        }
+    }
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
 /// Mark a message as read.
 pub async fn mark_message_read_impl(
     ctx: &Ctx,
@@ -273,7 +1154,7 @@ pub async fn mark_message_read_impl(
         helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
             .await?;
 
-    MessageBmc::mark_read(ctx, mm, params.message_id, agent.id.get())
+    MessageBmc::mark_read(ctx, mm, MessageId::new(params.message_id), agent.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -284,6 +1165,74 @@ pub async fn mark_message_read_impl(
     Ok(CallToolResult::success(vec![Content::text(msg)]))
 }
 
+/// Start copying a thread's new messages to an agent.
+pub async fn watch_thread_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: WatchThreadParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let added = ThreadWatcherBmc::watch(ctx, mm, project.id, &params.thread_id, agent.id)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::NotFound => McpError::invalid_params(
+                format!(
+                    "Thread '{}' not found in project '{}'",
+                    params.thread_id, project.slug
+                ),
+                None,
+            ),
+            mouchak_mail_core::Error::ContactDenied(_) => {
+                McpError::invalid_params(e.to_string(), None)
+            }
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
+
+    let msg = if added {
+        format!(
+            "'{}' is now watching thread '{}'",
+            params.agent_name, params.thread_id
+        )
+    } else {
+        format!(
+            "'{}' was already watching thread '{}'",
+            params.agent_name, params.thread_id
+        )
+    };
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
+/// Stop copying a thread's new messages to an agent.
+pub async fn unwatch_thread_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: WatchThreadParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let removed = ThreadWatcherBmc::unwatch(ctx, mm, project.id, &params.thread_id, agent.id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let msg = if removed {
+        format!(
+            "'{}' stopped watching thread '{}'",
+            params.agent_name, params.thread_id
+        )
+    } else {
+        format!(
+            "'{}' was not watching thread '{}'",
+            params.agent_name, params.thread_id
+        )
+    };
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
 /// Acknowledge a message requiring acknowledgment.
 pub async fn acknowledge_message_impl(
     ctx: &Ctx,
@@ -294,7 +1243,7 @@ pub async fn acknowledge_message_impl(
         helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
             .await?;
 
-    if !AgentCapabilityBmc::check(ctx, mm, agent.id.get(), "acknowledge_message")
+    if !AgentCapabilityBmc::check(ctx, mm, agent.id, "acknowledge_message")
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?
     {
@@ -307,7 +1256,7 @@ pub async fn acknowledge_message_impl(
         ));
     }
 
-    MessageBmc::acknowledge(ctx, mm, params.message_id, agent.id.get())
+    MessageBmc::acknowledge(ctx, mm, MessageId::new(params.message_id), agent.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -318,28 +1267,504 @@ pub async fn acknowledge_message_impl(
     Ok(CallToolResult::success(vec![Content::text(msg)]))
 }
 
-/// List all conversation threads in a project.
-pub async fn list_threads_impl(
+/// Accept a requested reply deadline or propose another one.
+pub async fn respond_deadline_impl(
     ctx: &Ctx,
     mm: &Arc<ModelManager>,
-    params: ListThreadsParams,
+    params: RespondDeadlineParams,
+) -> Result<CallToolResult, McpError> {
+    let (_project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let proposed = match params.propose.as_deref() {
+        Some(propose) if !propose.trim().is_empty() => Some(
+            parse_deadline(propose, clock::server_now())
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?,
+        ),
+        _ => None,
+    };
+    let deadline = ReplyDeadlineBmc::respond(
+        ctx,
+        mm,
+        MessageId::new(params.message_id),
+        agent.id,
+        proposed.as_ref().map(|p| p.value),
+    )
+    .await
+    .map_err(|e| match e {
+        mouchak_mail_core::Error::NotFound => McpError::invalid_params(
+            format!(
+                "No reply deadline was requested from '{}' on message {}",
+                params.agent_name, params.message_id
+            ),
+            None,
+        ),
+        mouchak_mail_core::Error::InvalidInput(_) => McpError::invalid_params(e.to_string(), None),
+        e => McpError::internal_error(e.to_string(), None),
+    })?;
+
+    let verb = if deadline.status == "proposed" {
+        "proposed"
+    } else {
+        "accepted"
+    };
+    let mut msg = format!(
+        "'{}' {} replying to message {} from '{}' by {} UTC",
+        params.agent_name,
+        verb,
+        params.message_id,
+        deadline.sender_name,
+        deadline.deadline_ts.format("%Y-%m-%d %H:%M")
+    );
+    if let Some(warning) = proposed.and_then(|p| p.warning) {
+        msg.push_str(&format!("\nWarning: {}", warning));
+    }
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
+/// List the reply deadlines an agent owes, or those of one message.
+pub async fn list_deadlines_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ListDeadlinesParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let (deadlines, heading) = match params.message_id {
+        Some(message_id) => {
+            let message = MessageBmc::get(ctx, mm, MessageId::new(message_id))
+                .await
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+            if message.project_id != project.id.get() {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Message {} does not belong to project '{}'",
+                        message_id, params.project_slug
+                    ),
+                    None,
+                ));
+            }
+            (
+                ReplyDeadlineBmc::list_for_message(ctx, mm, MessageId::new(message_id)).await,
+                format!("Reply deadlines for message {}", message_id),
+            )
+        }
+        None => (
+            ReplyDeadlineBmc::list_open_for_agent(ctx, mm, agent.id).await,
+            format!("Reply deadlines owed by '{}'", params.agent_name),
+        ),
+    };
+    let deadlines = deadlines.map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if deadlines.is_empty() {
+        return Ok(CallToolResult::success(vec![Content::text(format!(
+            "{}: none",
+            heading
+        ))]));
+    }
+
+    let mut output = format!("{} ({}):\n\n", heading, deadlines.len());
+    for d in &deadlines {
+        let state = match (d.acked, d.is_agreed()) {
+            (true, _) => "met".to_string(),
+            (false, true) => d.status.clone(),
+            (false, false) => "awaiting response".to_string(),
+        };
+        output.push_str(&format!(
+            "- [{}] {} from '{}' to '{}': by {} UTC ({})\n",
+            d.message_id,
+            d.subject,
+            d.sender_name,
+            d.agent_name,
+            d.deadline_ts.format("%Y-%m-%d %H:%M"),
+            state
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Approve or reject a message held for approval.
+pub async fn approve_pending_message_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ApprovePendingMessageParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let not_held = || {
+        McpError::invalid_params(
+            format!(
+                "Message {} is not held for approval in project '{}'",
+                params.message_id, project.slug
+            ),
+            None,
+        )
+    };
+    let pending = ApprovalBmc::get(ctx, mm, MessageId::new(params.message_id))
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .ok_or_else(not_held)?;
+    if pending.project_id != project.id {
+        return Err(not_held());
+    }
+
+    let approve = params.approve.unwrap_or(true);
+    let decided = ApprovalBmc::decide(
+        ctx,
+        mm,
+        MessageId::new(params.message_id),
+        Some(agent.id),
+        approve,
+        params.reason.as_deref(),
+    )
+    .await
+    .map_err(|e| match e {
+        mouchak_mail_core::Error::ApprovalDenied(_) | mouchak_mail_core::Error::InvalidInput(_) => {
+            McpError::invalid_params(e.to_string(), None)
+        }
+        mouchak_mail_core::Error::NotFound => not_held(),
+        e => McpError::internal_error(e.to_string(), None),
+    })?;
+
+    let msg = if approve {
+        format!(
+            "Message {} from '{}' approved by '{}' and delivered",
+            decided.message_id, decided.sender_name, params.agent_name
+        )
+    } else {
+        format!(
+            "Message {} from '{}' rejected by '{}'",
+            decided.message_id, decided.sender_name, params.agent_name
+        )
+    };
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
+/// List a project's messages waiting for approval.
+pub async fn list_pending_messages_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ListPendingMessagesParams,
 ) -> Result<CallToolResult, McpError> {
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let threads = MessageBmc::list_threads(ctx, mm, project.id.get(), params.limit.unwrap_or(50))
+    let pending = ApprovalBmc::list_pending(ctx, mm, project.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if pending.is_empty() {
+        return Ok(CallToolResult::success(vec![Content::text(format!(
+            "No messages held for approval in '{}'",
+            project.slug
+        ))]));
+    }
+
+    let mut output = format!(
+        "Messages held for approval in '{}' ({}):\n\n",
+        project.slug,
+        pending.len()
+    );
+    for p in &pending {
+        output.push_str(&format!(
+            "- [{}] {} from '{}' (approvers: {}; expires {})\n",
+            p.message_id,
+            p.subject,
+            p.sender_name,
+            approvers_label(&p.approvers),
+            p.expires_ts.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Who may decide on a held message, for display.
+fn approvers_label(approvers: &[String]) -> String {
+    if approvers.is_empty() {
+        "any agent but the sender".to_string()
+    } else {
+        approvers.join(", ")
+    }
+}
+
+/// List sends that could not be delivered.
+pub async fn list_dead_letters_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ListDeadLettersParams,
+) -> Result<CallToolResult, McpError> {
+    let filter = DeadLetterFilter {
+        project_slug: params.project_slug.filter(|slug| !slug.trim().is_empty()),
+        include_requeued: params.include_requeued.unwrap_or(false),
+        limit: params.limit,
+    };
+    let letters = DeadLetterBmc::list(ctx, mm, &filter)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if letters.is_empty() {
+        return Ok(CallToolResult::success(vec![Content::text(
+            "No dead letters".to_string(),
+        )]));
+    }
+
+    let mut output = format!("Dead letters ({}):\n\n", letters.len());
+    for letter in &letters {
+        output.push_str(&dead_letter_line(letter));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Deliver a dead letter again, optionally to another project or recipients.
+pub async fn requeue_dead_letter_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: RequeueDeadLetterParams,
+) -> Result<CallToolResult, McpError> {
+    let names = |csv: Option<String>| csv.map(|csv| split_names(&csv));
+    let changes = DeadLetterRequeue {
+        project_slug: params.project_slug,
+        sender_name: params.sender_name,
+        to: names(params.to),
+        cc: names(params.cc),
+        bcc: names(params.bcc),
+    };
+    let message_id = DeadLetterBmc::requeue(ctx, mm, params.dead_letter_id, changes)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::Libsql(_)
+            | mouchak_mail_core::Error::Postgres(_)
+            | mouchak_mail_core::Error::PostgresPool(_)
+            | mouchak_mail_core::Error::Git2(_)
+            | mouchak_mail_core::Error::Io(_) => McpError::internal_error(e.to_string(), None),
+            e => McpError::invalid_params(
+                format!("Dead letter {} not delivered: {}", params.dead_letter_id, e),
+                None,
+            ),
+        })?;
+    Ok(CallToolResult::success(vec![Content::text(format!(
+        "Dead letter {} delivered as message {}",
+        params.dead_letter_id, message_id
+    ))]))
+}
+
+/// One dead letter, for display.
+fn dead_letter_line(letter: &DeadLetter) -> String {
+    let delivered = match letter.message_id {
+        Some(id) => format!(", delivered as message {}", id),
+        None => String::new(),
+    };
+    format!(
+        "- [{}] {} from '{}' in '{}' to {} ({} attempt(s){})\n    {}\n",
+        letter.id,
+        letter.subject,
+        letter.sender_name,
+        letter.project_slug,
+        letter.to.join(", "),
+        letter.attempts,
+        delivered,
+        letter.error
+    )
+}
+
+/// Report which recipients of a broadcast message have acknowledged it.
+pub async fn get_broadcast_status_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: GetBroadcastStatusParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    let status = BroadcastStatusBmc::get(ctx, mm, MessageId::new(params.message_id))
+        .await
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    if status.project_id != project.id {
+        return Err(McpError::invalid_params(
+            format!(
+                "Message {} does not belong to project '{}'",
+                params.message_id, params.project_slug
+            ),
+            None,
+        ));
+    }
 
     let mut output = format!(
-        "Threads in '{}' ({}):\n\n",
-        params.project_slug,
-        threads.len()
+        "Broadcast {} '{}' from {}: {}/{} acknowledged{}\n",
+        params.message_id,
+        status.subject,
+        status.sender_name,
+        status.acked.len(),
+        status.total_recipients,
+        if status.ack_required {
+            ""
+        } else {
+            " (ack not required)"
+        }
     );
-    for t in &threads {
+    if let Some(stats) = &status.time_to_ack {
+        output.push_str(&format!(
+            "Time to ack: min {}s, median {}s, mean {:.0}s, max {}s\n",
+            stats.min_seconds, stats.median_seconds, stats.mean_seconds, stats.max_seconds
+        ));
+    }
+    output.push_str("\nAcknowledged:\n");
+    for r in &status.acked {
         output.push_str(&format!(
-            "- {} | {} ({} msgs, last: {})\n",
-            t.thread_id, t.subject, t.message_count, t.last_message_ts
+            "- {} ({}s)\n",
+            r.agent_name,
+            r.seconds_to_ack.unwrap_or_default()
         ));
     }
+    output.push_str("\nPending:\n");
+    for r in &status.pending {
+        let read = if r.read_ts.is_some() {
+            "read"
+        } else {
+            "unread"
+        };
+        output.push_str(&format!("- {} ({})\n", r.agent_name, read));
+    }
+
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
+
+/// Report when each recipient of a message read and acknowledged it.
+pub async fn get_message_receipts_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: GetMessageReceiptsParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    let receipts = ReceiptBmc::get(ctx, mm, MessageId::new(params.message_id))
+        .await
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    if receipts.project_id != project.id {
+        return Err(McpError::invalid_params(
+            format!(
+                "Message {} does not belong to project '{}'",
+                params.message_id, params.project_slug
+            ),
+            None,
+        ));
+    }
+
+    let mut output = format!(
+        "Receipts for message {} '{}' from {}: {}/{} read, {}/{} acknowledged{}\n\n",
+        params.message_id,
+        receipts.subject,
+        receipts.sender_name,
+        receipts.read_count,
+        receipts.receipts.len(),
+        receipts.acked_count,
+        receipts.receipts.len(),
+        if receipts.ack_required {
+            ""
+        } else {
+            " (ack not required)"
+        }
+    );
+    let ts = |ts: Option<chrono::NaiveDateTime>| {
+        ts.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    for r in &receipts.receipts {
+        output.push_str(&format!(
+            "- {} ({}): read {}, acked {}\n",
+            r.agent_name,
+            r.recipient_type,
+            ts(r.read_ts),
+            ts(r.ack_ts)
+        ));
+    }
+
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// List all conversation threads in a project.
+pub async fn list_threads_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ListThreadsParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    let limit = params.limit.unwrap_or(50);
+    let include_archived = params.include_archived.unwrap_or(false);
+    let cursor = params.cursor.as_deref();
+    let (threads, total, next_cursor) = match &params.agent_name {
+        Some(name) => {
+            let agent = helpers::resolve_agent(ctx, mm, project.id.get(), name).await?;
+            let page = ThreadReadBmc::list_threads_page(
+                ctx,
+                mm,
+                project.id,
+                agent.id,
+                limit,
+                cursor,
+                include_archived,
+            )
+            .await
+            .map_err(helpers::list_error)?;
+            let threads = page
+                .items
+                .into_iter()
+                .map(|t| (t.thread, Some(t.unread_count)))
+                .collect::<Vec<_>>();
+            (threads, page.total, page.next_cursor)
+        }
+        None => {
+            let page =
+                MessageBmc::list_threads_page(ctx, mm, project.id, limit, cursor, include_archived)
+                    .await
+                    .map_err(helpers::list_error)?;
+            let threads = page
+                .items
+                .into_iter()
+                .map(|t| (t, None))
+                .collect::<Vec<_>>();
+            (threads, page.total, page.next_cursor)
+        }
+    };
+
+    let count = if threads.len() as i64 == total {
+        total.to_string()
+    } else {
+        format!("{} of {}", threads.len(), total)
+    };
+    let mut output = format!("Threads in '{}' ({}):\n\n", params.project_slug, count);
+    for (t, unread) in &threads {
+        let unread = unread
+            .map(|n| format!(", {} unread", n))
+            .unwrap_or_default();
+        let archived = if t.archived { ", archived" } else { "" };
+        output.push_str(&format!(
+            "- {} | {} ({} msgs{}{}, last: {})\n",
+            t.thread_id, t.subject, t.message_count, unread, archived, t.last_message_ts
+        ));
+    }
+    if let Some(cursor) = &next_cursor {
+        output.push_str(&format!("\nMore results: pass cursor \"{}\"\n", cursor));
+    }
+
+    let budget = ResultBudget::list(threads.len(), limit)
+        .suggest("search_messages to find a specific thread")
+        .suggest("a smaller limit");
+    Ok(budget::annotate(
+        CallToolResult::success(vec![Content::text(output)]),
+        budget,
+    ))
+}
+
+/// Parses an optional format name, defaulting when absent.
+fn parse_format<F>(format: Option<&str>) -> Result<F, McpError>
+where
+    F: std::str::FromStr<Err = mouchak_mail_core::Error> + Default,
+{
+    format
+        .map(str::parse::<F>)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))
+        .map(Option::unwrap_or_default)
+}
@@ -1,6 +1,6 @@
 //! MCP Tool implementations for Mouchak Mail
 //!
-//! This module defines all MCP tools that wrap the lib-core functionality.
+//! This module defines all MCP tools that wrap the mouchak-mail-core functionality.
 
 use anyhow::Result;
 use mouchak_mail_common::config::AppConfig;
@@ -10,36 +10,48 @@ use rmcp::{
     model::{
         CallToolRequestParam, CallToolResult, Content, Implementation, ListResourcesResult,
         ListToolsResult, PaginatedRequestParam, ReadResourceRequestParam, ReadResourceResult,
-        ResourcesCapability, ServerCapabilities, ServerInfo, ToolsCapability,
+        ResourceUpdatedNotificationParam, ResourcesCapability, ServerCapabilities, ServerInfo,
+        SubscribeRequestParam, ToolsCapability, UnsubscribeRequestParam,
     },
     service::{RequestContext, RoleServer},
     tool, tool_router,
 };
 use serde::Serialize;
 use std::sync::Arc;
+use tracing::Instrument;
 
 use mouchak_mail_core::{ctx::Ctx, model::ModelManager};
 
 pub mod agent;
 pub mod archive;
 pub mod attachments;
+pub mod budget;
 pub mod builds;
+pub mod ci;
 pub mod contacts;
 pub mod errors;
 pub mod export;
+pub mod external;
 pub mod files;
 pub mod helpers;
+pub mod journal;
+pub mod labels;
 pub mod macros;
 pub mod messaging;
 pub mod observability;
 pub mod outbox;
 mod params;
+pub mod plugins;
 pub mod precommit;
 pub mod products;
 pub mod project;
 pub mod resources;
 pub mod reviews;
 mod schema;
+pub mod server_time;
+pub mod subscriptions;
+pub mod tickets;
+pub mod workflows;
 
 pub use params::*;
 pub use schema::schema_from_params;
@@ -70,6 +82,64 @@ const BUILD_SLOT_TOOLS: &[&str] = &[
     "renew_build_slot",
 ];
 
+/// Built-in tools that never write. Every other tool, including tools
+/// added later and plugin or external tools, counts as a write tool.
+const READ_TOOLS: &[&str] = &[
+    "check_inbox_delta",
+    "check_paths",
+    "export_team_graph",
+    "get_agent_profile",
+    "get_attachment",
+    "get_broadcast_status",
+    "get_context_pack",
+    "get_message",
+    "get_message_receipts",
+    "get_project_info",
+    "get_review_state",
+    "get_sla_report",
+    "get_thread_tree",
+    "get_tool_stats",
+    "get_usage",
+    "get_workflow_status",
+    "list_activity",
+    "list_agents",
+    "list_builtin_workflows",
+    "list_by_label",
+    "list_contacts",
+    "list_custom_fields",
+    "list_dead_letters",
+    "list_deadlines",
+    "list_delegations",
+    "list_inbox",
+    "list_macros",
+    "list_message_templates",
+    "list_outbox",
+    "list_pending_messages",
+    "list_pending_reviews",
+    "list_products",
+    "list_project_siblings",
+    "list_projects",
+    "list_reservations",
+    "list_saved_searches",
+    "list_threads",
+    "list_tool_metrics",
+    "product_inbox",
+    "run_saved_search",
+    "search_facets",
+    "search_messages",
+    "search_messages_product",
+    "summarize_thread",
+    "summarize_thread_product",
+    "wait_for_path",
+    "whois",
+];
+
+/// Returns `true` if `tool_name` may write, so it is refused while the
+/// server is read-only.
+pub fn is_write_tool(tool_name: &str) -> bool {
+    !READ_TOOLS.contains(&tool_name)
+}
+
 /// Get schema information for all tools
 ///
 /// When `worktrees_enabled` is false, build slot tools are excluded from the list.
@@ -89,11 +159,31 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
             "ensure_project",
             "Ensure a project exists, creating it if necessary.",
         ),
+        schema_from_params::<CreateProjectFromTemplateParams>(
+            "create_project_from_template",
+            "Create a project with the agents, macros and policies of a template.",
+        ),
         schema_from_params::<ListProjectsParams>("list_projects", "List all projects."),
         schema_from_params::<GetProjectInfoParams>(
             "get_project_info",
             "Get detailed project information.",
         ),
+        schema_from_params::<SetFocusWindowParams>(
+            "set_focus_window",
+            "Start or end a project focus window that defers low-priority mail.",
+        ),
+        schema_from_params::<SetProjectSettingsParams>(
+            "set_project_settings",
+            "Set a project's time zone, business hours, quiet hours and locale.",
+        ),
+        schema_from_params::<SetMessageTemplateParams>(
+            "set_message_template",
+            "Override a system message template for a locale.",
+        ),
+        schema_from_params::<ListMessageTemplatesParams>(
+            "list_message_templates",
+            "List the system message templates in effect for a locale.",
+        ),
         schema_from_params::<ListProjectSiblingsParams>(
             "list_project_siblings",
             "List sibling projects (related repositories).",
@@ -121,6 +211,10 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
             "update_agent_profile",
             "Update agent profile settings.",
         ),
+        schema_from_params::<GetContextPackParams>(
+            "get_context_pack",
+            "Get a size-bounded briefing of urgent mail, pending acks, reservations and recent threads.",
+        ),
         schema_from_params::<CreateAgentIdentityParams>(
             "create_agent_identity",
             "Create a unique agent identity with auto-generated name.",
@@ -132,7 +226,7 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
         ),
         schema_from_params::<ListInboxParams>(
             "check_inbox",
-            "Check an agent's inbox for new messages.",
+            "Check an agent's inbox for new messages, newest first; pass next cursor back as cursor for older pages.",
         ),
         schema_from_params::<ListInboxParams>(
             "fetch_inbox",
@@ -142,27 +236,122 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
             "list_inbox",
             "List an agent's inbox messages. (Alias for check_inbox)",
         ),
+        schema_from_params::<CheckInboxDeltaParams>(
+            "check_inbox_delta",
+            "Return only inbox messages and read/ack changes since a token, plus a new token.",
+        ),
         schema_from_params::<ReplyMessageParams>(
             "reply_message",
             "Reply to an existing message in a thread.",
         ),
-        schema_from_params::<GetMessageParams>("get_message", "Get a specific message by ID."),
-        schema_from_params::<ListOutboxParams>("list_outbox", "List messages sent by an agent."),
+        schema_from_params::<ForwardMessageParams>(
+            "forward_message",
+            "Forward a message to agents of this project or of another project in the same product, continuing its global thread.",
+        ),
+        schema_from_params::<GetMessageParams>(
+            "get_message",
+            "Get a specific message by ID; full=true returns the whole body of an oversized message.",
+        ),
+        schema_from_params::<ListOutboxParams>(
+            "list_outbox",
+            "List messages sent by an agent; needs_attention=true lists those recipients have not read or acked in time, with suggested follow-ups.",
+        ),
         schema_from_params::<MarkMessageReadParams>("mark_message_read", "Mark a message as read."),
         schema_from_params::<AcknowledgeMessageParams>(
             "acknowledge_message",
             "Acknowledge receipt of a message.",
         ),
+        schema_from_params::<RespondDeadlineParams>(
+            "respond_deadline",
+            "Accept a requested reply deadline or propose another one.",
+        ),
+        schema_from_params::<ListDeadlinesParams>(
+            "list_deadlines",
+            "List reply deadlines an agent owes, or those of one message.",
+        ),
+        schema_from_params::<ApprovePendingMessageParams>(
+            "approve_pending_message",
+            "Approve or reject a message held by a project approval rule.",
+        ),
+        schema_from_params::<ListPendingMessagesParams>(
+            "list_pending_messages",
+            "List messages waiting for approval in a project.",
+        ),
+        schema_from_params::<ListDeadLettersParams>(
+            "list_dead_letters",
+            "List sends that could not be delivered because their project or an agent no longer resolved.",
+        ),
+        schema_from_params::<RequeueDeadLetterParams>(
+            "requeue_dead_letter",
+            "Deliver a dead letter again, optionally to another project, sender or recipients.",
+        ),
+        schema_from_params::<GetBroadcastStatusParams>(
+            "get_broadcast_status",
+            "Show which recipients of a broadcast message have acknowledged it, with time-to-ack stats.",
+        ),
+        schema_from_params::<GetMessageReceiptsParams>(
+            "get_message_receipts",
+            "Show when each recipient of a message read and acknowledged it.",
+        ),
         schema_from_params::<SearchMessagesParams>(
             "search_messages",
-            "Search messages using full-text search.",
+            "Search message subjects and bodies using full-text search, best matches first, with highlighted snippets and optional thread context.",
+        ),
+        schema_from_params::<SearchFacetsParams>(
+            "search_facets",
+            "Count the messages matching a search by sender, importance, month and custom field value.",
+        ),
+        schema_from_params::<ApplyLabelParams>(
+            "apply_label",
+            "Apply a colored project label to a message, creating the label on first use.",
+        ),
+        schema_from_params::<RemoveLabelParams>("remove_label", "Remove a label from a message."),
+        schema_from_params::<ListByLabelParams>(
+            "list_by_label",
+            "List the messages carrying a label, or a project's labels with message counts.",
+        ),
+        schema_from_params::<SaveSearchParams>(
+            "save_search",
+            "Save a named search (query, importance, senders, unread/unacked, age) as a smart folder.",
+        ),
+        schema_from_params::<ListSavedSearchesParams>(
+            "list_saved_searches",
+            "List a project's saved searches and an agent's own, with current match counts.",
+        ),
+        schema_from_params::<RunSavedSearchParams>(
+            "run_saved_search",
+            "Evaluate a saved search and return its newest matching messages.",
+        ),
+        schema_from_params::<DeleteSavedSearchParams>(
+            "delete_saved_search",
+            "Delete a saved search.",
+        ),
+        schema_from_params::<ListCustomFieldsParams>(
+            "list_custom_fields",
+            "List a project's custom message fields.",
+        ),
+        schema_from_params::<LinkTicketParams>(
+            "link_ticket",
+            "Link a Jira or Linear ticket to a message, showing its title and status.",
         ),
         // Threads
         schema_from_params::<ListThreadsParams>(
             "list_threads",
-            "List message threads in a project.",
+            "List message threads in a project, paged with limit/cursor. Threads archived for inactivity are left out unless include_archived is set.",
         ),
         schema_from_params::<GetThreadParams>("get_thread", "Get all messages in a thread."),
+        schema_from_params::<GetThreadTreeParams>(
+            "get_thread_tree",
+            "Get a thread as a tree of replies for nested rendering.",
+        ),
+        schema_from_params::<WatchThreadParams>(
+            "watch_thread",
+            "Receive copies of new messages in a thread the agent is not addressed in.",
+        ),
+        schema_from_params::<WatchThreadParams>(
+            "unwatch_thread",
+            "Stop receiving copies of a watched thread's messages.",
+        ),
         schema_from_params::<SummarizeThreadParams>(
             "summarize_thread",
             "Summarize one or more threads.",
@@ -199,16 +388,44 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
             "set_contact_policy",
             "Set agent contact policy.",
         ),
+        schema_from_params::<DelegationParams>(
+            "grant_delegation",
+            "Let another agent send messages on this agent's behalf.",
+        ),
+        schema_from_params::<DelegationParams>(
+            "revoke_delegation",
+            "Withdraw permission to send on this agent's behalf.",
+        ),
+        schema_from_params::<ListDelegationsParams>(
+            "list_delegations",
+            "List who may send messages on whose behalf.",
+        ),
+        schema_from_params::<ExportTeamGraphParams>(
+            "export_team_graph",
+            "Export a project's agents, capabilities and contacts as JSON.",
+        ),
+        schema_from_params::<ImportTeamGraphParams>(
+            "import_team_graph",
+            "Import agents, capabilities and contacts from a team graph.",
+        ),
         // File Reservations
         schema_from_params::<FileReservationParams>("reserve_file", "Reserve a file path pattern."),
         schema_from_params::<FileReservationPathsParams>(
             "file_reservation_paths",
-            "Reserve multiple file paths at once.",
+            "Reserve multiple file paths at once, all or nothing.",
         ),
         schema_from_params::<ListReservationsParams>(
             "list_file_reservations",
             "List active file reservations.",
         ),
+        schema_from_params::<ReleaseReservationSetParams>(
+            "release_reservation_set",
+            "Release every reservation of a reservation set at once.",
+        ),
+        schema_from_params::<CheckPathsParams>(
+            "check_paths",
+            "Check paths against active file reservations, one verdict per path.",
+        ),
         schema_from_params::<ReleaseReservationParams>(
             "release_reservation",
             "Release a file reservation by ID.",
@@ -217,6 +434,10 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
             "force_release_reservation",
             "Force release a reservation (for emergencies).",
         ),
+        schema_from_params::<WaitForPathParams>(
+            "wait_for_path",
+            "Block until a path is free of other agents' reservations.",
+        ),
         schema_from_params::<RenewFileReservationParams>(
             "renew_file_reservation",
             "Extend a file reservation's TTL by ID.",
@@ -248,6 +469,10 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
         ),
         schema_from_params::<RenewBuildSlotParams>("renew_build_slot", "Renew a build slot's TTL."),
         schema_from_params::<ReleaseBuildSlotParams>("release_build_slot", "Release a build slot."),
+        schema_from_params::<LinkCommitParams>(
+            "link_commit",
+            "Link a commit to a thread so its CI build results are posted there.",
+        ),
         // Macros
         schema_from_params::<ListMacrosParams>("list_macros", "List registered macros."),
         schema_from_params::<RegisterMacroParams>("register_macro", "Register a new macro."),
@@ -290,6 +515,22 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
             "quick_review_workflow",
             "Request a code review from another agent.",
         ),
+        schema_from_params::<DefineWorkflowParams>(
+            "define_workflow",
+            "Define a thread workflow: states, responsible agents and allowed transitions.",
+        ),
+        schema_from_params::<StartWorkflowParams>(
+            "start_workflow",
+            "Attach a workflow to a thread, starting in its first state.",
+        ),
+        schema_from_params::<AdvanceWorkflowParams>(
+            "advance_workflow",
+            "Move a thread to the next workflow state and notify who is responsible.",
+        ),
+        schema_from_params::<GetWorkflowStatusParams>(
+            "get_workflow_status",
+            "Get a thread's workflow state, allowed next states and history.",
+        ),
         // Products (Multi-Project)
         schema_from_params::<EnsureProductParams>(
             "ensure_product",
@@ -340,6 +581,14 @@ fn get_all_tool_schemas() -> Vec<ToolSchema> {
             "list_activity",
             "List recent activity in a project.",
         ),
+        schema_from_params::<GetSlaReportParams>(
+            "get_sla_report",
+            "Report acknowledgment SLA compliance per project and agent over a period.",
+        ),
+        schema_from_params::<GetUsageParams>(
+            "get_usage",
+            "Report storage used per project and agent, with quota limits.",
+        ),
         // Overseer
         schema_from_params::<SendOverseerMessageParams>(
             "send_overseer_message",
@@ -361,6 +610,10 @@ pub struct MouchakMailService {
     tool_router: ToolRouter<Self>,
     /// Whether worktrees/build slot tools are enabled
     worktrees_enabled: bool,
+    /// Journal of significant tool calls, when session journaling is enabled
+    journal: Option<Arc<journal::SessionJournal>>,
+    /// This session's thread resource subscriptions
+    subscriptions: Arc<subscriptions::ResourceSubscriptions>,
 }
 
 impl MouchakMailService {
@@ -381,8 +634,14 @@ impl MouchakMailService {
     pub async fn new_with_config(config: AppConfig) -> Result<Self> {
         let worktrees_enabled = config.mcp.worktrees_active();
         let app_config = Arc::new(config);
+        let journal_thread = app_config
+            .mcp
+            .session_journal_enabled
+            .then(|| app_config.mcp.session_journal_thread.clone());
         let mm = Arc::new(ModelManager::new(app_config).await?);
         let tool_router = Self::tool_router();
+        let journal =
+            journal_thread.map(|thread| Arc::new(journal::SessionJournal::new(mm.clone(), thread)));
 
         if worktrees_enabled {
             tracing::info!("MCP service starting with worktrees/build-slots ENABLED");
@@ -390,10 +649,17 @@ impl MouchakMailService {
             tracing::info!("MCP service starting with worktrees/build-slots DISABLED");
         }
 
+        let subscriptions = Arc::new(subscriptions::ResourceSubscriptions::new(
+            mm.clone(),
+            subscriptions::THREAD_POLL_INTERVAL,
+        ));
+
         Ok(Self {
             mm,
             tool_router,
             worktrees_enabled,
+            journal,
+            subscriptions,
         })
     }
 
@@ -407,13 +673,44 @@ impl MouchakMailService {
             tracing::info!("MCP service starting with worktrees/build-slots DISABLED");
         }
 
+        let subscriptions = Arc::new(subscriptions::ResourceSubscriptions::new(
+            mm.clone(),
+            subscriptions::THREAD_POLL_INTERVAL,
+        ));
+
         Self {
             mm,
             tool_router,
             worktrees_enabled,
+            journal: None,
+            subscriptions,
         }
     }
 
+    /// Enables session journaling, posting to `thread_id` at session end.
+    pub fn with_session_journal(mut self, thread_id: impl Into<String>) -> Self {
+        self.journal = Some(Arc::new(journal::SessionJournal::new(
+            self.mm.clone(),
+            thread_id,
+        )));
+        self
+    }
+
+    /// Returns the session journal, if journaling is enabled
+    pub fn session_journal(&self) -> Option<Arc<journal::SessionJournal>> {
+        self.journal.clone()
+    }
+
+    /// Returns the tool-call log, if archiving tool calls is enabled
+    pub fn tool_call_log(&self) -> Option<mouchak_mail_core::utils::tool_call_log::ToolCallLog> {
+        self.mm.tool_call_log().cloned()
+    }
+
+    /// Returns this session's resource subscriptions
+    pub fn subscriptions(&self) -> Arc<subscriptions::ResourceSubscriptions> {
+        self.subscriptions.clone()
+    }
+
     /// Returns whether worktrees/build-slot tools are enabled
     pub fn worktrees_enabled(&self) -> bool {
         self.worktrees_enabled
@@ -439,6 +736,7 @@ impl MouchakMailService {
         use mouchak_mail_core::model::agent::AgentBmc;
         use mouchak_mail_core::model::project::ProjectBmc;
         use mouchak_mail_core::model::tool_metric::{ToolMetricBmc, ToolMetricForCreate};
+        use mouchak_mail_core::utils::tool_call_log::ToolCallRecord;
 
         let (status, error_code) = match result {
             Ok(_) => ("success".to_string(), None),
@@ -452,18 +750,39 @@ impl MouchakMailService {
         let ctx = self.ctx();
         let mut project_id = None;
         let mut agent_id = None;
+        // Only names that resolve are archived, never raw arguments
+        let mut caller_project = None;
+        let mut caller_agent = None;
 
         if let Some(slug) = project_slug
             && let Ok(p) = ProjectBmc::get_by_slug(&ctx, &self.mm, &slug).await
         {
             project_id = Some(p.id);
+            caller_project = Some(p.slug);
             if let Some(name) = agent_name
                 && let Ok(a) = AgentBmc::get_by_name(&ctx, &self.mm, p.id, &name).await
             {
                 agent_id = Some(a.id);
+                caller_agent = Some(a.name);
             }
         }
 
+        if let Some(log) = self.mm.tool_call_log() {
+            log.record(ToolCallRecord {
+                ts: chrono::Utc::now(),
+                tool: tool_name.to_string(),
+                project_slug: caller_project,
+                agent_name: caller_agent,
+                duration_ms: duration.as_millis() as u64,
+                status: status.clone(),
+                error_code: error_code.clone(),
+            });
+        }
+
+        // The metrics table is not written while the server is read-only
+        if self.mm.is_read_only() {
+            return;
+        }
         let metric = ToolMetricForCreate {
             project_id: project_id.map(|id| id.get()),
             agent_id: agent_id.map(|id| id.get()),
@@ -479,6 +798,41 @@ impl MouchakMailService {
         }
     }
 
+    /// Creates the span for a tool call, or a disabled span if the trace is
+    /// not sampled.
+    ///
+    /// The span carries the project and agent from the arguments so traces
+    /// can be filtered by project.
+    fn tool_call_span(&self, tool_name: &str, args: &Option<serde_json::Value>) -> tracing::Span {
+        let parent = tracing::Span::current();
+        let parent_sampled = (!parent.is_none()).then(|| !parent.is_disabled());
+        let trace_id = uuid::Uuid::new_v4().as_u128();
+        if !self
+            .mm
+            .app_config
+            .tracing
+            .should_sample(parent_sampled, trace_id)
+        {
+            return tracing::Span::none();
+        }
+
+        let span = tracing::info_span!(
+            "tool_call",
+            tool = %tool_name,
+            trace_id = %format_args!("{:032x}", trace_id),
+            project_slug = tracing::field::Empty,
+            agent_name = tracing::field::Empty,
+        );
+        let (project_slug, agent_name) = self.extract_context(args);
+        if let Some(project_slug) = project_slug {
+            span.record("project_slug", project_slug.as_str());
+        }
+        if let Some(agent_name) = agent_name {
+            span.record("agent_name", agent_name.as_str());
+        }
+        span
+    }
+
     pub fn extract_context(
         &self,
         args: &Option<serde_json::Value>,
@@ -558,14 +912,17 @@ impl MouchakMailService {
     /// Public for testing ServerHandler::list_tools filtering logic.
     pub fn list_tools_filtered(&self) -> Vec<rmcp::model::Tool> {
         let all_tools = self.tool_router.list_all();
-        if self.worktrees_enabled {
+        let plugin_tools = plugins::plugin_tools(&self.mm, &all_tools);
+        let mut tools: Vec<rmcp::model::Tool> = if self.worktrees_enabled {
             all_tools
         } else {
             all_tools
                 .into_iter()
                 .filter(|tool| !BUILD_SLOT_TOOLS.contains(&&*tool.name))
                 .collect()
-        }
+        };
+        tools.extend(plugin_tools);
+        tools
     }
 }
 
@@ -576,7 +933,10 @@ impl ServerHandler for MouchakMailService {
             protocol_version: Default::default(),
             capabilities: ServerCapabilities {
                 tools: Some(ToolsCapability::default()),
-                resources: Some(ResourcesCapability::default()),
+                resources: Some(ResourcesCapability {
+                    subscribe: Some(true),
+                    ..Default::default()
+                }),
                 ..Default::default()
             },
             server_info: Implementation {
@@ -597,8 +957,14 @@ impl ServerHandler for MouchakMailService {
         _context: RequestContext<RoleServer>,
     ) -> impl std::future::Future<Output = Result<ListToolsResult, McpError>> + Send + '_ {
         async move {
+            let mut tools = self.list_tools_filtered();
+            let external = external::external_tools(&self.mm, |name| {
+                self.tool_router.has_route(name) || tools.iter().any(|t| t.name == name)
+            })
+            .await;
+            tools.extend(external);
             Ok(ListToolsResult {
-                tools: self.list_tools_filtered(),
+                tools,
                 next_cursor: None,
                 meta: None,
             })
@@ -647,18 +1013,73 @@ impl ServerHandler for MouchakMailService {
                 ));
             }
 
-            let tool_context =
-                rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
-            let result = self.tool_router.call(tool_context).await;
+            if self.mm.is_read_only() && is_write_tool(&tool_name) {
+                tracing::warn!(tool = %tool_name, "Rejected write while the server is read-only");
+                return Err(McpError::invalid_request(
+                    format!(
+                        "Tool '{}' is not available: the server is read-only because disk space is low. Reads still work; writes resume once space is freed.",
+                        tool_name
+                    ),
+                    None,
+                ));
+            }
+
+            let args_val = args.map(serde_json::Value::Object);
+            let span = self.tool_call_span(&tool_name, &args_val);
+
+            // Built-in tools win over plugin tools, which win over external ones
+            let is_builtin = self.tool_router.has_route(&tool_name);
+            let is_plugin_tool = !is_builtin && self.mm.plugins().tool(&tool_name).is_some();
+            let external_tool = if is_builtin || is_plugin_tool {
+                None
+            } else {
+                external::find_external_tool(&self.mm, &tool_name).await
+            };
+            let mut result = if is_plugin_tool {
+                plugins::call_plugin_tool_impl(&self.mm, &tool_name, args_val.clone())
+                    .instrument(span.clone())
+                    .await
+            } else if let Some(tool) = &external_tool {
+                external::call_external_tool_impl(tool, args_val.clone())
+                    .instrument(span.clone())
+                    .await
+            } else {
+                let tool_context =
+                    rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+                self.tool_router
+                    .call(tool_context)
+                    .instrument(span.clone())
+                    .await
+            };
+            if let Ok(ok) = result.as_mut() {
+                budget::ensure_bytes(ok);
+                server_time::stamp(ok);
+            }
 
             let duration = start.elapsed();
+            span.in_scope(|| {
+                tracing::debug!(
+                    duration_ms = duration.as_millis() as u64,
+                    ok = result.is_ok(),
+                    "Tool call finished"
+                )
+            });
 
             // Fire and forget metric recording (spawn generic task or just await since we are async)
             // Awaiting is safer to ensure it's recorded before response?
             // Better to spawn to avoid latency, but for now await is fine as DB write is fast.
-            let args_val = args.map(serde_json::Value::Object);
             self.record_tool_metric(&tool_name, &args_val, duration, &result)
                 .await;
+            if let Some(journal) = &self.journal {
+                let (project_slug, agent_name) = self.extract_context(&args_val);
+                journal.record(
+                    &tool_name,
+                    project_slug,
+                    agent_name,
+                    &args_val,
+                    result.as_ref().is_ok_and(|r| r.is_error != Some(true)),
+                );
+            }
 
             result
         }
@@ -679,6 +1100,37 @@ impl ServerHandler for MouchakMailService {
     ) -> impl std::future::Future<Output = Result<ReadResourceResult, McpError>> + Send + '_ {
         self.read_resource_impl(request)
     }
+
+    fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<(), McpError>> + Send + '_ {
+        async move {
+            let peer = context.peer;
+            self.subscriptions
+                .subscribe(&request.uri, move |uri| {
+                    let peer = peer.clone();
+                    async move {
+                        peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                            .await
+                            .is_ok()
+                    }
+                })
+                .await
+        }
+    }
+
+    fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> impl std::future::Future<Output = Result<(), McpError>> + Send + '_ {
+        async move {
+            self.subscriptions.unsubscribe(&request.uri);
+            Ok(())
+        }
+    }
 }
 
 // ============================================================================
@@ -699,6 +1151,17 @@ impl MouchakMailService {
         project::ensure_project_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Create a project from a template bundle
+    #[tool(
+        description = "Create a new project from a template bundle in the server's data dir (templates/<name>.json): registers the template's agents with their capabilities and contacts, adds its macros, applies its time zone, business hours and SLA escalation policy, and sends its welcome messages. Fails if the project already exists."
+    )]
+    async fn create_project_from_template(
+        &self,
+        params: Parameters<CreateProjectFromTemplateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        project::create_project_from_template_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Register a new agent in a project
     #[tool(description = "Register an agent in a project. Agents can send and receive messages.")]
     async fn register_agent(
@@ -720,7 +1183,9 @@ impl MouchakMailService {
     }
 
     /// List messages in an agent's inbox
-    #[tool(description = "Get messages for an agent's inbox.")]
+    #[tool(
+        description = "Get messages for an agent's inbox, newest first. Page with limit/cursor."
+    )]
     async fn list_inbox(
         &self,
         params: Parameters<ListInboxParams>,
@@ -728,8 +1193,21 @@ impl MouchakMailService {
         messaging::list_inbox_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Check what changed in an agent's inbox since a token
+    #[tool(
+        description = "Return only the inbox messages and read/ack changes since since_token, plus a new token for the next call. Omit since_token for an initial snapshot; call again right away when has_more is set."
+    )]
+    async fn check_inbox_delta(
+        &self,
+        params: Parameters<CheckInboxDeltaParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::check_inbox_delta_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Get a specific message by ID
-    #[tool(description = "Retrieve a message by its ID, including full body content.")]
+    #[tool(
+        description = "Retrieve a message by its ID. Bodies over the size cap are stored with a preview; pass full=true for the whole body."
+    )]
     async fn get_message(
         &self,
         params: Parameters<GetMessageParams>,
@@ -746,7 +1224,9 @@ impl MouchakMailService {
     }
 
     /// Search messages using full-text search
-    #[tool(description = "Search messages by content using full-text search.")]
+    #[tool(
+        description = "Search message subjects and bodies using full-text search, ranked by relevance (BM25). Supports prefixes (deploy*), quoted phrases (\"schema migration\"), AND/OR/NOT and subject:term filters. Returns highlighted snippets with match offsets and optional surrounding thread context. Set include_attachments=true to also match text attachment contents, and fields={\"component\": \"db\"} to only match messages with those custom field values."
+    )]
     async fn search_messages(
         &self,
         params: Parameters<SearchMessagesParams>,
@@ -754,8 +1234,116 @@ impl MouchakMailService {
         messaging::search_messages_impl(&self.ctx(), &self.mm, params.0).await
     }
 
-    /// Get all messages in a thread
-    #[tool(description = "Retrieve all messages in a conversation thread.")]
+    /// Count search matches by facet
+    #[tool(
+        description = "Count the messages matching a full-text search, grouped by sender, importance, month (YYYY-MM) and custom field value. Use it on large result sets to pick a narrower query or fields filter before calling search_messages."
+    )]
+    async fn search_facets(
+        &self,
+        params: Parameters<SearchFacetsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::search_facets_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Apply a label to a message
+    #[tool(
+        description = "Label a message for triage beyond importance, e.g. label=\"blocked\" or \"needs-review\". Labels belong to the project and are shared by its agents; a label that does not exist yet is created, with color (#rrggbb) or one picked by name. get_message shows a message's labels."
+    )]
+    async fn apply_label(
+        &self,
+        params: Parameters<ApplyLabelParams>,
+    ) -> Result<CallToolResult, McpError> {
+        labels::apply_label_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Remove a label from a message
+    #[tool(description = "Remove a label from a message. The label itself stays in the project.")]
+    async fn remove_label(
+        &self,
+        params: Parameters<RemoveLabelParams>,
+    ) -> Result<CallToolResult, McpError> {
+        labels::remove_label_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// List messages by label
+    #[tool(
+        description = "List the newest messages carrying a label. Without label, list the project's labels with their colors and message counts."
+    )]
+    async fn list_by_label(
+        &self,
+        params: Parameters<ListByLabelParams>,
+    ) -> Result<CallToolResult, McpError> {
+        labels::list_by_label_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Save a search as a smart folder
+    #[tool(
+        description = "Save named search criteria as a smart folder, e.g. urgent unread mail from reviewers: importance=\"urgent\", from=\"capability:code-review\", unread=true. All given criteria must match. With agent_name the search covers that agent's inbox and may use unread/unacked; without it the search is shared and covers the whole project. Senders given as capability:<name> are resolved each time the search runs. Saving an existing name replaces its criteria."
+    )]
+    async fn save_search(
+        &self,
+        params: Parameters<SaveSearchParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::save_search_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// List saved searches
+    #[tool(
+        description = "List a project's shared saved searches, plus those agent_name owns, with how many messages each matches right now."
+    )]
+    async fn list_saved_searches(
+        &self,
+        params: Parameters<ListSavedSearchesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::list_saved_searches_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Evaluate a saved search
+    #[tool(
+        description = "Run a saved search against the current mailbox: its match count and newest matching messages."
+    )]
+    async fn run_saved_search(
+        &self,
+        params: Parameters<RunSavedSearchParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::run_saved_search_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Delete a saved search
+    #[tool(description = "Delete a saved search by ID.")]
+    async fn delete_saved_search(
+        &self,
+        params: Parameters<DeleteSavedSearchParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::delete_saved_search_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// List a project's custom message fields
+    #[tool(
+        description = "List the custom fields a project defines for messages (name, type, required). Set them with send_message custom_fields; required ones must be present. Filter searches with search_messages fields."
+    )]
+    async fn list_custom_fields(
+        &self,
+        params: Parameters<ListCustomFieldsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::list_custom_fields_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Link a Jira or Linear ticket to a message
+    #[tool(
+        description = "Link a Jira or Linear ticket (e.g. PROJ-123) to a message. The ticket's title and status are fetched, cached and refreshed periodically, and shown with the message in get_message and get_thread."
+    )]
+    async fn link_ticket(
+        &self,
+        params: Parameters<LinkTicketParams>,
+    ) -> Result<CallToolResult, McpError> {
+        tickets::link_ticket_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Get the messages in a thread
+    #[tool(
+        description = "Retrieve the messages in a conversation thread. Pass agent_name to mark the thread read for that agent, and unread_only to get only messages since its last read."
+    )]
     async fn get_thread(
         &self,
         params: Parameters<GetThreadParams>,
@@ -763,6 +1351,37 @@ impl MouchakMailService {
         messaging::get_thread_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Get the reply tree of a thread
+    #[tool(
+        description = "Retrieve a thread as a tree: each message with the replies made to it (via reply_message), nested by depth. Messages that reply to nothing in the thread are roots. Returns JSON for rendering nested conversations."
+    )]
+    async fn get_thread_tree(
+        &self,
+        params: Parameters<GetThreadTreeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::get_thread_tree_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Watch a thread
+    #[tool(
+        description = "Watch a thread the agent is not a recipient of: every new message in it is copied to the agent's inbox as a bcc, flagged [watching] in fetch_inbox. Refused when a participant's contact policy is 'deny', or 'manual' without an accepted contact. Useful for overseer or QA agents monitoring work."
+    )]
+    async fn watch_thread(
+        &self,
+        params: Parameters<WatchThreadParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::watch_thread_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Stop watching a thread
+    #[tool(description = "Stop copying a watched thread's new messages to the agent.")]
+    async fn unwatch_thread(
+        &self,
+        params: Parameters<WatchThreadParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::unwatch_thread_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Get review state of a task thread
     #[tool(
         description = "Get the current review state of a task thread based on message prefixes."
@@ -785,14 +1404,21 @@ impl MouchakMailService {
         reviews::claim_review_impl(&self.ctx(), &self.mm, params.0).await
     }
 
-    /// List all projects
-    #[tool(description = "List all available projects in the system.")]
-    async fn list_projects(&self) -> Result<CallToolResult, McpError> {
-        project::list_projects_impl(&self.ctx(), &self.mm).await
+    /// List projects
+    #[tool(
+        description = "List projects in the system. Optional: limit/cursor paging, sort (created, name, last_active), and filters active_within, program, model."
+    )]
+    async fn list_projects(
+        &self,
+        params: Parameters<ListProjectsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        project::list_projects_impl(&self.ctx(), &self.mm, params.0).await
     }
 
-    /// List all agents in a project
-    #[tool(description = "List all agents registered in a project.")]
+    /// List agents in a project
+    #[tool(
+        description = "List agents registered in a project. Optional: limit/cursor paging, sort (name, created, last_active), and filters active_within, program, model."
+    )]
     async fn list_agents(
         &self,
         params: Parameters<ListAgentsParams>,
@@ -818,6 +1444,17 @@ impl MouchakMailService {
         files::list_reservations_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Check paths against active reservations
+    #[tool(
+        description = "Check paths against a project's active file reservations in one call. Returns a verdict per path with the reservations held by other agents that match it; pass agent_name to ignore that agent's own reservations."
+    )]
+    async fn check_paths(
+        &self,
+        params: Parameters<CheckPathsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        files::check_paths_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Release a file reservation
     #[tool(description = "Release a file reservation by ID.")]
     async fn release_reservation(
@@ -838,6 +1475,17 @@ impl MouchakMailService {
         files::force_release_reservation_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Wait for a reserved path to become available
+    #[tool(
+        description = "Block until no other agent holds a reservation overlapping the path, or the timeout elapses. If still blocked, registers for a release receipt message."
+    )]
+    async fn wait_for_path(
+        &self,
+        params: Parameters<WaitForPathParams>,
+    ) -> Result<CallToolResult, McpError> {
+        files::wait_for_path_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Renew a file reservation TTL
     #[tool(
         description = "Extend the TTL of a file reservation. Keeps the lock active for more work."
@@ -878,6 +1526,17 @@ impl MouchakMailService {
         messaging::reply_message_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Forward a message, across projects of a product if needed
+    #[tool(
+        description = "Forward a message to agents of this project. The message may come from another project linked to the same product; the forward continues the original's global thread, so summarize_thread_product sees one conversation."
+    )]
+    async fn forward_message(
+        &self,
+        params: Parameters<ForwardMessageParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::forward_message_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Mark a message as read
     #[tool(description = "Mark a message as read by a specific agent.")]
     async fn mark_message_read(
@@ -896,6 +1555,105 @@ impl MouchakMailService {
         messaging::acknowledge_message_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Accept or counter a reply deadline
+    #[tool(
+        description = "Respond to a reply deadline requested with send_message's respond_by: omit propose to accept it, or propose another time (\"6h\", \"2025-03-02T09:30:00Z\"). Either way the agent commits to the resulting deadline, met by acknowledging the message; once it passes the message is escalated like an overdue ack. Responding again changes the answer."
+    )]
+    async fn respond_deadline(
+        &self,
+        params: Parameters<RespondDeadlineParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::respond_deadline_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// List reply deadlines
+    #[tool(
+        description = "List the reply deadlines agent_name still owes, soonest first, or with message_id every recipient's deadline and response for that message."
+    )]
+    async fn list_deadlines(
+        &self,
+        params: Parameters<ListDeadlinesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::list_deadlines_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Approve or reject a held message
+    #[tool(
+        description = "Approve (default) or reject a message held by a project approval rule, e.g. urgent mail to many recipients or mail mentioning 'deploy'. Approval delivers it at once; rejection drops it. The sender cannot approve its own message, and only the rule's approvers may decide when it names any. Undecided messages are rejected when the rule's timeout passes."
+    )]
+    async fn approve_pending_message(
+        &self,
+        params: Parameters<ApprovePendingMessageParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::approve_pending_message_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// List held messages
+    #[tool(
+        description = "List a project's messages held for approval, with sender, subject, approvers and when each times out."
+    )]
+    async fn list_pending_messages(
+        &self,
+        params: Parameters<ListPendingMessagesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::list_pending_messages_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// List undeliverable sends
+    #[tool(
+        description = "List sends that failed because their project, sender or a recipient no longer resolved (e.g. a deleted agent or a project adopted into another), oldest first. send_message keeps these as dead letters instead of losing them; filter by project_slug, include_requeued=true to see delivered ones too."
+    )]
+    async fn list_dead_letters(
+        &self,
+        params: Parameters<ListDeadLettersParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::list_dead_letters_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Deliver a dead letter again
+    #[tool(
+        description = "Deliver a dead letter again. Pass project_slug, sender_name, to, cc or bcc to replace what it was sent with, e.g. the project it was adopted into or a recipient that replaced a deleted agent. A requeue that fails again keeps the dead letter with the new error."
+    )]
+    async fn requeue_dead_letter(
+        &self,
+        params: Parameters<RequeueDeadLetterParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::requeue_dead_letter_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Show acknowledgment progress of a broadcast message
+    #[tool(
+        description = "Show which recipients of a broadcast message have acknowledged it and which have not, with time-to-ack stats (min/median/mean/max)."
+    )]
+    async fn get_broadcast_status(
+        &self,
+        params: Parameters<GetBroadcastStatusParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::get_broadcast_status_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Show per-recipient read receipts of a message
+    #[tool(
+        description = "Show when each recipient of a message read and acknowledged it, with read/ack counts. Lets a sender check which agents have processed an ack-required message without fetching the whole thread."
+    )]
+    async fn get_message_receipts(
+        &self,
+        params: Parameters<GetMessageReceiptsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        messaging::get_message_receipts_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Get a compact bootstrapping briefing for an agent
+    #[tool(
+        description = "Get a size-bounded context pack for an agent: unread urgent messages, messages awaiting acknowledgement, active file reservations and recent thread summaries, trimmed to budget_tokens. Use when starting or resuming work."
+    )]
+    async fn get_context_pack(
+        &self,
+        params: Parameters<GetContextPackParams>,
+    ) -> Result<CallToolResult, McpError> {
+        agent::get_context_pack_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Generate agent identity names
     #[tool(description = "Generate memorable agent names with collision detection.")]
     async fn create_agent_identity(
@@ -923,6 +1681,50 @@ impl MouchakMailService {
         project::get_project_info_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Start or end a project focus window
+    #[tool(
+        description = "Start a time-boxed focus window during which messages below the allowed importance are deferred until it ends. Pass duration_minutes=0 to end the active window and deliver held mail."
+    )]
+    async fn set_focus_window(
+        &self,
+        params: Parameters<SetFocusWindowParams>,
+    ) -> Result<CallToolResult, McpError> {
+        project::set_focus_window_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Set project time zone and business hours
+    #[tool(
+        description = "Set a project's time zone, business hours and locale. SLA deadlines count business time only, overdue acks are escalated only during business hours, and with quiet_hours=true low-importance mail sent after hours is held until the next working day. System messages (reminders, escalations, SLA reports) are written in the project's locale. With email_bridge=true, messages the server's email bridge forwards (urgent ones by default) are also mailed to email_recipients."
+    )]
+    async fn set_project_settings(
+        &self,
+        params: Parameters<SetProjectSettingsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        project::set_project_settings_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Override a system message template
+    #[tool(
+        description = "Override the template of a system message (reminder, escalation, SLA report, release receipt) for a locale. Use the {placeholders} listed by list_message_templates; an empty template restores the built-in one."
+    )]
+    async fn set_message_template(
+        &self,
+        params: Parameters<SetMessageTemplateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        project::set_message_template_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// List system message templates
+    #[tool(
+        description = "List the system message templates in effect for a locale, with their placeholders and whether each is built in, custom or inherited from a fallback locale."
+    )]
+    async fn list_message_templates(
+        &self,
+        params: Parameters<ListMessageTemplatesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        project::list_message_templates_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Get agent profile
     #[tool(description = "Get detailed profile information for an agent.")]
     async fn get_agent_profile(
@@ -933,7 +1735,9 @@ impl MouchakMailService {
     }
 
     /// List threads
-    #[tool(description = "List all conversation threads in a project.")]
+    #[tool(
+        description = "List conversation threads in a project. Pass agent_name to include that agent's unread count per thread; page with limit/cursor. Threads archived for inactivity are left out unless include_archived is set; a new reply reactivates them."
+    )]
     async fn list_threads(
         &self,
         params: Parameters<ListThreadsParams>,
@@ -984,6 +1788,72 @@ impl MouchakMailService {
         contacts::set_contact_policy_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Grant delegation
+    #[tool(
+        description = "Let delegate_name send messages on behalf of agent_name, e.g. a supervisor agent drafting for a worker. Delegated messages keep agent_name as sender and show as 'delegate on behalf of agent'. Use send_message with on_behalf_of to send them."
+    )]
+    async fn grant_delegation(
+        &self,
+        params: Parameters<DelegationParams>,
+    ) -> Result<CallToolResult, McpError> {
+        contacts::grant_delegation_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Revoke delegation
+    #[tool(
+        description = "Withdraw delegate_name's permission to send on behalf of agent_name. Messages already sent keep their delegate."
+    )]
+    async fn revoke_delegation(
+        &self,
+        params: Parameters<DelegationParams>,
+    ) -> Result<CallToolResult, McpError> {
+        contacts::revoke_delegation_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// List delegations
+    #[tool(
+        description = "List delegation grants in a project, optionally only those given or held by agent_name."
+    )]
+    async fn list_delegations(
+        &self,
+        params: Parameters<ListDelegationsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        contacts::list_delegations_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Export the team graph
+    #[tool(
+        description = "Export a project's team graph as JSON: every agent's profile and capabilities, and the accepted contact links between agents (cross-project links name the other project). Import it elsewhere with import_team_graph to reuse a team topology."
+    )]
+    async fn export_team_graph(
+        &self,
+        params: Parameters<ExportTeamGraphParams>,
+    ) -> Result<CallToolResult, McpError> {
+        contacts::export_team_graph_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Import a team graph
+    #[tool(
+        description = "Import a team graph from export_team_graph into a project. Additive: missing agents are registered, missing capabilities granted and missing contacts linked; existing agents keep their profile. Entries that cannot be applied (e.g. a cross-project contact to an unknown project) are listed as skipped."
+    )]
+    async fn import_team_graph(
+        &self,
+        params: Parameters<ImportTeamGraphParams>,
+    ) -> Result<CallToolResult, McpError> {
+        contacts::import_team_graph_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Link a commit to a thread for CI notifications
+    #[tool(
+        description = "Link a commit (full SHA or a prefix of at least 7 characters) to a thread. Build events CI reports for that commit are then posted into the thread by the project's ci agent, so red builds show up without polling CI."
+    )]
+    async fn link_commit(
+        &self,
+        params: Parameters<LinkCommitParams>,
+    ) -> Result<CallToolResult, McpError> {
+        ci::link_commit_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// Acquire build slot
     #[tool(description = "Acquire an exclusive build slot for CI/CD isolation.")]
     async fn acquire_build_slot(
@@ -1119,6 +1989,54 @@ impl MouchakMailService {
         macros::quick_review_workflow_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    // ========================================================================
+    // Thread Workflows
+    // ========================================================================
+
+    /// Define a thread workflow
+    #[tool(
+        description = "Define (or replace) a named workflow for threads: ordered states, each optionally owned by an agent name or capability:<name> address, and the allowed transitions as \"from -> to\". Threads start in the first state."
+    )]
+    async fn define_workflow(
+        &self,
+        params: Parameters<DefineWorkflowParams>,
+    ) -> Result<CallToolResult, McpError> {
+        workflows::define_workflow_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Attach a workflow to a thread
+    #[tool(
+        description = "Attach a workflow to an existing thread. The thread enters the workflow's first state and its responsible agent is notified in the thread."
+    )]
+    async fn start_workflow(
+        &self,
+        params: Parameters<StartWorkflowParams>,
+    ) -> Result<CallToolResult, McpError> {
+        workflows::start_workflow_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Advance a thread's workflow
+    #[tool(
+        description = "Move a thread to another workflow state. Rejects transitions the workflow does not allow (listing the allowed ones) and sends a handoff message in the thread to the agents responsible for the new state."
+    )]
+    async fn advance_workflow(
+        &self,
+        params: Parameters<AdvanceWorkflowParams>,
+    ) -> Result<CallToolResult, McpError> {
+        workflows::advance_workflow_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Get a thread's workflow status
+    #[tool(
+        description = "Get where a thread is in its workflow: current state, who is responsible, allowed next states and the history of moves."
+    )]
+    async fn get_workflow_status(
+        &self,
+        params: Parameters<GetWorkflowStatusParams>,
+    ) -> Result<CallToolResult, McpError> {
+        workflows::get_workflow_status_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     // ========================================================================
     // Macro Convenience Tools (Session/Workflow Helpers)
     // ========================================================================
@@ -1174,6 +2092,7 @@ impl MouchakMailService {
         &self,
         params: Parameters<SummarizeThreadParams>,
     ) -> Result<CallToolResult, McpError> {
+        use mouchak_mail_core::model::global_thread::GlobalThreadBmc;
         use mouchak_mail_core::model::message::MessageBmc;
 
         let ctx = self.ctx();
@@ -1186,7 +2105,7 @@ impl MouchakMailService {
         let mut errors = Vec::new();
 
         for thread_id in thread_ids {
-            match MessageBmc::list_by_thread(&ctx, &self.mm, project.id.get(), &thread_id).await {
+            match MessageBmc::list_by_thread(&ctx, &self.mm, project.id, &thread_id).await {
                 Ok(messages) if !messages.is_empty() => {
                     let mut participants: Vec<String> =
                         messages.iter().map(|m| m.sender_name.clone()).collect();
@@ -1202,8 +2121,13 @@ impl MouchakMailService {
                         .map(|m| m.body_md.chars().take(100).collect::<String>())
                         .unwrap_or_default();
 
+                    let global_thread_id =
+                        GlobalThreadBmc::get_id(&ctx, &self.mm, project.id, &thread_id)
+                            .await
+                            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
                     summaries.push(ThreadSummaryItem {
                         thread_id,
+                        global_thread_id,
                         subject,
                         message_count: messages.len(),
                         participants,
@@ -1348,7 +2272,9 @@ impl MouchakMailService {
     }
 
     /// List messages in an agent's outbox
-    #[tool(description = "Get messages from an agent's outbox (sent messages).")]
+    #[tool(
+        description = "Get messages from an agent's outbox (sent messages). With needs_attention=true, lists only messages whose recipients have not read them within unread_after or acknowledged them within unacked_after (or missed an agreed reply deadline), with suggested follow-ups: remind, request a deadline, escalate or reassign."
+    )]
     async fn list_outbox(
         &self,
         params: Parameters<ListOutboxParams>,
@@ -1357,7 +2283,7 @@ impl MouchakMailService {
     }
 
     #[tool(
-        description = "Reserve multiple file paths for exclusive editing with conflict detection."
+        description = "Reserve multiple file paths at once, all or nothing. If any path overlaps another agent's reservation, nothing is reserved and each clashing path is listed with its holder. On success the reservations share a set_id for release_reservation_set."
     )]
     async fn file_reservation_paths(
         &self,
@@ -1366,6 +2292,14 @@ impl MouchakMailService {
         files::file_reservation_paths_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    #[tool(description = "Release every reservation of a reservation set at once.")]
+    async fn release_reservation_set(
+        &self,
+        params: Parameters<ReleaseReservationSetParams>,
+    ) -> Result<CallToolResult, McpError> {
+        files::release_reservation_set_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     #[tool(description = "Install pre-commit guard for file reservation conflict detection.")]
     async fn install_precommit_guard(
         &self,
@@ -1427,6 +2361,28 @@ impl MouchakMailService {
         observability::list_activity_impl(&self.ctx(), &self.mm, params.0).await
     }
 
+    /// Report SLA compliance for a project
+    #[tool(
+        description = "Report acknowledgment SLA compliance for a project over a period (e.g. \"24h\", \"7d\"): met, violated and pending acks overall and per agent, plus each violation. SLAs are defined per importance by project admins."
+    )]
+    async fn get_sla_report(
+        &self,
+        params: Parameters<GetSlaReportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        observability::get_sla_report_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
+    /// Report storage usage per project and agent
+    #[tool(
+        description = "Report storage usage for a project: messages sent (count and body bytes), attachments uploaded and inbox size, in total and per agent with the largest first, plus the quota limits in effect. Pass agent_name to report one agent. Sends and uploads fail with a quota error once an agent reaches its limit."
+    )]
+    async fn get_usage(
+        &self,
+        params: Parameters<GetUsageParams>,
+    ) -> Result<CallToolResult, McpError> {
+        observability::get_usage_impl(&self.ctx(), &self.mm, params.0).await
+    }
+
     /// List messages requiring acknowledgment that haven't been fully acknowledged
     #[tool(
         description = "List messages requiring acknowledgment that haven't been fully acknowledged. Returns complete message details, sender info, project context, and per-recipient status in a single call."
@@ -1463,14 +2419,9 @@ mod tests {
         let conn = db.connect().unwrap();
         let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-        let schema1 = include_str!("../../../../../migrations/001_initial_schema.sql");
-        conn.execute_batch(schema1).await.unwrap();
-        let schema2 = include_str!("../../../../../migrations/002_agent_capabilities.sql");
-        conn.execute_batch(schema2).await.unwrap();
-        let schema3 = include_str!("../../../../../migrations/003_tool_metrics.sql");
-        conn.execute_batch(schema3).await.unwrap();
-        let schema4 = include_str!("../../../../../migrations/004_attachments.sql");
-        conn.execute_batch(schema4).await.unwrap();
+        mouchak_mail_core::store::upgrade::migrate(&conn)
+            .await
+            .unwrap();
 
         let app_config = Arc::new(AppConfig::default());
         let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -1507,9 +2458,13 @@ mod tests {
             bcc: None,
             subject: "Test".into(),
             body_md: "Body".into(),
+            body_format: None,
             importance: None,
             thread_id: None,
             ack_required: None,
+            custom_fields: None,
+            on_behalf_of: None,
+            respond_by: None,
         };
 
         // We invoke the handler directly
@@ -1541,9 +2496,13 @@ mod tests {
             bcc: None,
             subject: "Test".into(),
             body_md: "Body".into(),
+            body_format: None,
             importance: None,
             thread_id: None,
             ack_required: None,
+            custom_fields: None,
+            on_behalf_of: None,
+            respond_by: None,
         };
         let result = service.send_message(Parameters(params2)).await;
         assert!(result.is_ok());
@@ -1569,10 +2528,10 @@ mod tests {
         let product = ProductBmc::ensure(&ctx, &mm, "prod-p", "Product P")
             .await
             .unwrap();
-        ProductBmc::link_project(&ctx, &mm, product.id, id_a.into())
+        ProductBmc::link_project(&ctx, &mm, product.id, id_a)
             .await
             .unwrap();
-        ProductBmc::link_project(&ctx, &mm, product.id, id_b.into())
+        ProductBmc::link_project(&ctx, &mm, product.id, id_b)
             .await
             .unwrap();
 
@@ -1661,9 +2620,13 @@ mod tests {
             bcc: Some("BCCAgent".into()),
             subject: "CC Test".into(),
             body_md: "Body".into(),
+            body_format: None,
             importance: None,
             thread_id: None,
             ack_required: None,
+            custom_fields: None,
+            on_behalf_of: None,
+            respond_by: None,
         };
 
         // Invoke
@@ -1684,19 +2647,18 @@ mod tests {
         // We can't access mm.db().
         // BUT we can use `list_inbox` for each agent to verify delivery!
 
-        let inbox_recv =
-            MessageBmc::list_inbox_for_agent(&ctx, &mm, pid.into(), recv_id.into(), 10)
-                .await
-                .unwrap();
+        let inbox_recv = MessageBmc::list_inbox_for_agent(&ctx, &mm, pid, recv_id, 10)
+            .await
+            .unwrap();
         assert_eq!(inbox_recv.len(), 1);
 
         // Correct verification of CC/BCC delivery:
-        let inbox_cc = MessageBmc::list_inbox_for_agent(&ctx, &mm, pid.into(), cc_id.into(), 10)
+        let inbox_cc = MessageBmc::list_inbox_for_agent(&ctx, &mm, pid, cc_id, 10)
             .await
             .unwrap();
         assert_eq!(inbox_cc.len(), 1, "CC agent should have message in inbox");
 
-        let inbox_bcc = MessageBmc::list_inbox_for_agent(&ctx, &mm, pid.into(), bcc_id.into(), 10)
+        let inbox_bcc = MessageBmc::list_inbox_for_agent(&ctx, &mm, pid, bcc_id, 10)
             .await
             .unwrap();
         assert_eq!(inbox_bcc.len(), 1, "BCC agent should have message in inbox");
@@ -1737,9 +2699,9 @@ mod tests {
 
         // Send a message
         let msg_c = MessageForCreate {
-            project_id: pid.into(),
-            sender_id: sender_id.into(),
-            recipient_ids: vec![recv_id.into()],
+            project_id: pid,
+            sender_id,
+            recipient_ids: vec![recv_id],
             cc_ids: None,
             bcc_ids: None,
             subject: "Outbox Check".into(),
@@ -1814,7 +2776,7 @@ mod tests {
             .await;
 
         // 4. Verify DB
-        let metrics = ToolMetricBmc::list_recent(&ctx, &mm, Some(project_id.into()), 10)
+        let metrics = ToolMetricBmc::list_recent(&ctx, &mm, Some(project_id), 10)
             .await
             .unwrap();
         assert_eq!(metrics.len(), 1);
@@ -1902,6 +2864,23 @@ mod tests {
         assert!(BUILD_SLOT_TOOLS.contains(&"renew_build_slot"));
     }
 
+    #[test]
+    fn test_is_write_tool() {
+        // Read tools name real tools
+        let router = MouchakMailService::tool_router();
+        for tool in READ_TOOLS {
+            assert!(router.has_route(tool), "{} is not a tool", tool);
+        }
+        assert!(!is_write_tool("list_inbox"));
+        assert!(!is_write_tool("search_messages"));
+        assert!(is_write_tool("send_message"));
+        // Marking read and reading a thread as an agent write receipts
+        assert!(is_write_tool("mark_message_read"));
+        assert!(is_write_tool("get_thread"));
+        // Unknown tools are treated as writes
+        assert!(is_write_tool("plugin_tool"));
+    }
+
     #[tokio::test]
     async fn test_service_worktrees_enabled_flag() {
         let (mm, _temp) = create_test_mm().await;
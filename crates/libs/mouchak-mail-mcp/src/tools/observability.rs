@@ -1,18 +1,28 @@
 //! Observability tool implementations
 //!
-//! Handles tool metrics, activity tracking, and pending reviews listing.
+//! Handles tool metrics, activity tracking, pending reviews listing, SLA
+//! compliance reports, and storage usage.
 
 use mouchak_mail_core::{
     ctx::Ctx,
     model::{
-        ModelManager, activity::ActivityBmc, agent::AgentBmc, message::MessageBmc,
-        project::ProjectBmc, tool_metric::ToolMetricBmc,
+        ModelManager, activity::ActivityBmc, agent::AgentBmc, listing::parse_duration_secs,
+        message::MessageBmc, project::ProjectBmc, sla::SlaBmc, tool_metric::ToolMetricBmc,
+        usage::UsageBmc,
     },
+    types::ProjectId,
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
 
-use super::{ListActivityParams, ListPendingReviewsParams, ListToolMetricsParams};
+use super::helpers;
+use super::{
+    GetSlaReportParams, GetUsageParams, ListActivityParams, ListPendingReviewsParams,
+    ListToolMetricsParams,
+};
+
+/// Period of an SLA report when none is given.
+const DEFAULT_SLA_PERIOD: &str = "7d";
 
 /// List recent tool usage metrics for observability.
 pub async fn list_tool_metrics_impl(
@@ -21,7 +31,7 @@ pub async fn list_tool_metrics_impl(
     params: ListToolMetricsParams,
 ) -> Result<CallToolResult, McpError> {
     let limit = params.limit.unwrap_or(50);
-    let metrics = ToolMetricBmc::list_recent(ctx, mm, params.project_id, limit)
+    let metrics = ToolMetricBmc::list_recent(ctx, mm, params.project_id.map(ProjectId::new), limit)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -37,7 +47,7 @@ pub async fn get_tool_stats_impl(
     mm: &Arc<ModelManager>,
     params: ListToolMetricsParams,
 ) -> Result<CallToolResult, McpError> {
-    let stats = ToolMetricBmc::get_stats(ctx, mm, params.project_id)
+    let stats = ToolMetricBmc::get_stats(ctx, mm, params.project_id.map(ProjectId::new))
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -54,7 +64,7 @@ pub async fn list_activity_impl(
     params: ListActivityParams,
 ) -> Result<CallToolResult, McpError> {
     let limit = params.limit.unwrap_or(50);
-    let items = ActivityBmc::list_recent(ctx, mm, params.project_id, limit)
+    let items = ActivityBmc::list_recent(ctx, mm, ProjectId::new(params.project_id), limit)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -95,18 +105,63 @@ pub async fn list_pending_reviews_impl(
     };
 
     let limit = params.limit.unwrap_or(5);
-    let rows = MessageBmc::list_pending_reviews(
-        ctx,
-        mm,
-        project_id.map(|p| p.get()),
-        sender_id.map(|s| s.get()),
-        limit,
-    )
-    .await
-    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let rows = MessageBmc::list_pending_reviews(ctx, mm, project_id, sender_id, limit)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
     let json_str = serde_json::to_string_pretty(&rows)
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
     Ok(CallToolResult::success(vec![Content::text(json_str)]))
 }
+
+/// Report SLA compliance per project and per agent over a period.
+pub async fn get_sla_report_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: GetSlaReportParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let period = parse_duration_secs(params.period.as_deref().unwrap_or(DEFAULT_SLA_PERIOD))
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+    let report = SlaBmc::report(ctx, mm, project.id, period)
+        .await
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+    let json_str = serde_json::to_string_pretty(&report)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// Report storage usage of a project and its agents, or of one agent.
+pub async fn get_usage_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: GetUsageParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    let json_str = match &params.agent_name {
+        Some(agent_name) => {
+            let agent = helpers::resolve_agent(ctx, mm, project.id.get(), agent_name).await?;
+            let usage = UsageBmc::for_agent(ctx, mm, agent.id)
+                .await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            serde_json::to_string_pretty(&serde_json::json!({
+                "usage": usage,
+                "limits": UsageBmc::limits(mm),
+            }))
+        }
+        None => {
+            let usage = UsageBmc::for_project(ctx, mm, project.id)
+                .await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            serde_json::to_string_pretty(&usage)
+        }
+    }
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
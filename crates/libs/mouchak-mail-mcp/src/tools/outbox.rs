@@ -1,10 +1,17 @@
 //! Outbox tool implementations
 //!
-//! Handles listing sent messages for agents.
+//! Handles listing sent messages for agents, and the needs-attention review
+//! of sent messages whose recipients have gone quiet.
 
 use mouchak_mail_core::{
     ctx::Ctx,
-    model::{ModelManager, agent::AgentBmc, message::MessageBmc},
+    model::{
+        ModelManager,
+        agent::AgentBmc,
+        listing::parse_duration_secs,
+        message::MessageBmc,
+        outbox_review::{OutboxReviewBmc, ReviewThresholds},
+    },
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
@@ -24,27 +31,93 @@ pub async fn list_outbox_impl(
         .await
         .map_err(|e| McpError::invalid_params(format!("Agent not found: {}", e), None))?;
 
-    let messages = MessageBmc::list_outbox_for_agent(
+    if params.needs_attention.unwrap_or(false) {
+        let mut thresholds = ReviewThresholds::default();
+        if let Some(d) = params.unread_after.as_deref() {
+            thresholds.unread_after_secs = parse_duration_secs(d)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        }
+        if let Some(d) = params.unacked_after.as_deref() {
+            thresholds.unacked_after_secs = parse_duration_secs(d)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        }
+        let limit = usize::try_from(params.limit.unwrap_or(50)).unwrap_or(0);
+        let items =
+            OutboxReviewBmc::needs_attention(ctx, mm, project.id, agent.id, thresholds, limit)
+                .await
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+        if items.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No sent messages need attention for '{}'.",
+                params.agent_name
+            ))]));
+        }
+        let mut output = format!(
+            "Sent messages needing attention for '{}' ({}):\n\n",
+            params.agent_name,
+            items.len()
+        );
+        for item in &items {
+            output.push_str(&format!(
+                "- [{}] {} (sent {}, thread: {:?}, {}{})\n",
+                item.message_id,
+                item.subject,
+                item.created_ts,
+                item.thread_id,
+                item.importance,
+                if item.ack_required {
+                    ", ack required"
+                } else {
+                    ""
+                }
+            ));
+            let pending: Vec<String> = item
+                .pending
+                .iter()
+                .map(|p| format!("{} ({})", p.agent_name, p.state.as_str()))
+                .collect();
+            output.push_str(&format!("    Pending: {}\n", pending.join(", ")));
+            for f in &item.follow_ups {
+                output.push_str(&format!(
+                    "    Suggest {}: {}\n",
+                    f.action.as_str(),
+                    f.detail
+                ));
+            }
+        }
+        return Ok(CallToolResult::success(vec![Content::text(output)]));
+    }
+
+    let page = MessageBmc::list_outbox_page(
         ctx,
         mm,
-        project.id.get(),
-        agent.id.get(),
+        project.id,
+        agent.id,
         params.limit.unwrap_or(50),
+        params.cursor.as_deref(),
     )
     .await
-    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    .map_err(helpers::list_error)?;
 
+    let count = if page.items.len() as i64 == page.total {
+        page.total.to_string()
+    } else {
+        format!("{} of {}", page.items.len(), page.total)
+    };
     let mut output = format!(
         "Outbox for '{}' ({} messages):\n\n",
-        params.agent_name,
-        messages.len()
+        params.agent_name, count
     );
-    for m in &messages {
+    for m in &page.items {
         output.push_str(&format!(
             "- [{}] {} (to: {:?}, thread: {:?}, {})\n",
             m.id, m.subject, m.sender_name, m.thread_id, m.importance
         ));
     }
+    if let Some(cursor) = &page.next_cursor {
+        output.push_str(&format!("\nMore results: pass cursor \"{}\"\n", cursor));
+    }
 
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
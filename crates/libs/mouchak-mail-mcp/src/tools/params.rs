@@ -46,9 +46,40 @@ impl EnsureProjectParams {
     }
 }
 
-/// Parameters for list_projects tool (no parameters required)
+/// Parameters for create_project_from_template tool
 #[derive(Debug, Deserialize, JsonSchema)]
-pub struct ListProjectsParams {}
+pub struct CreateProjectFromTemplateParams {
+    /// Slug of the new project
+    pub slug: String,
+    /// Human-readable project name/key (default: the slug)
+    #[serde(default)]
+    pub human_key: Option<String>,
+    /// Template name: a bundle `templates/<name>.json` in the server's data dir
+    pub template: String,
+}
+
+/// Parameters for list_projects tool (all optional)
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ListProjectsParams {
+    /// Maximum projects to return (default: all, max 500)
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// Cursor from a previous page's "next cursor"
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Sort key: "created" (default, newest first), "name", or "last_active"
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// Only projects with an agent active within this duration (e.g. "15m", "2h", "7d")
+    #[serde(default)]
+    pub active_within: Option<String>,
+    /// Only projects with an agent on this program (e.g. "claude-code")
+    #[serde(default)]
+    pub program: Option<String>,
+    /// Only projects with an agent on this model
+    #[serde(default)]
+    pub model: Option<String>,
+}
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct RegisterAgentParams {
@@ -73,7 +104,8 @@ pub struct SendMessageParams {
     pub project_slug: String,
     /// Sender agent name
     pub sender_name: String,
-    /// Recipient agent names (comma-separated for multiple)
+    /// Recipient agent names (comma-separated for multiple). Use
+    /// `capability:<name>` (all holders) or `capability:<name>:best` to route by capability
     pub to: String,
     /// CC recipient agent names (comma-separated for multiple)
     pub cc: Option<String>,
@@ -83,6 +115,8 @@ pub struct SendMessageParams {
     pub subject: String,
     /// Message body in markdown
     pub body_md: String,
+    /// Format of `body_md`: markdown (default), plain or json
+    pub body_format: Option<String>,
     /// Message importance (low, normal, high, urgent)
     pub importance: Option<String>,
     /// Thread ID to continue existing conversation
@@ -90,6 +124,18 @@ pub struct SendMessageParams {
     /// Whether recipients must acknowledge this message (default: false)
     #[serde(default)]
     pub ack_required: Option<bool>,
+    /// Custom field values, e.g. {"component": "db", "severity": 2} (see list_custom_fields)
+    #[serde(default)]
+    pub custom_fields: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Send as this agent instead of sender_name, which must hold a
+    /// delegation grant from it (see grant_delegation)
+    #[serde(default)]
+    pub on_behalf_of: Option<String>,
+    /// Ask recipients to respond by this time, as a duration ("4h", "2d")
+    /// or timestamp ("2025-03-02T09:30:00Z"); implies ack_required. They
+    /// accept or counter it with respond_deadline
+    #[serde(default)]
+    pub respond_by: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -101,18 +147,51 @@ pub struct ListInboxParams {
     pub agent_name: String,
     /// Maximum number of messages to return
     pub limit: Option<i64>,
+    /// Cursor from a previous page's "next cursor"
+    #[serde(default)]
+    pub cursor: Option<String>,
     /// Filter to only urgent/high-priority messages
     pub urgent_only: Option<bool>,
     /// Filter messages since this timestamp (ISO 8601 format)
     pub since_ts: Option<String>,
     /// Include full message bodies in response (default: false for token efficiency)
     pub include_bodies: Option<bool>,
+    /// Render bodies as raw (default), plain or html; implies include_bodies
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckInboxDeltaParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Agent name whose inbox to check
+    pub agent_name: String,
+    /// Token returned by the previous check; omit for an initial snapshot
+    #[serde(default)]
+    pub since_token: Option<String>,
+    /// Maximum new messages to return (default 50, max 200)
+    pub limit: Option<i64>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetMessageParams {
     /// Message ID to retrieve
     pub message_id: i64,
+    /// Render the body as raw (default), plain or html
+    pub format: Option<String>,
+    /// Return the complete body of a message stored with a preview because it was too large
+    pub full: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LinkTicketParams {
+    /// Message to link the ticket to
+    pub message_id: i64,
+    /// Issue tracker: "jira" or "linear"
+    pub provider: String,
+    /// Ticket key, e.g. "PROJ-123"
+    pub ticket_key: String,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -147,19 +226,204 @@ pub struct SearchMessagesParams {
     /// Project slug
     #[serde(alias = "project_key")]
     pub project_slug: String,
-    /// Search query (full-text search)
+    /// Search query: words, prefixes (deploy*), quoted phrases, AND/OR/NOT, subject:term
     pub query: String,
     /// Maximum results
     pub limit: Option<i64>,
+    /// Number of surrounding thread messages to include before and after each hit (default: 0)
+    #[serde(default)]
+    pub context: Option<usize>,
+    /// Also match the content of text-like attachments (default: false)
+    #[serde(default)]
+    pub include_attachments: Option<bool>,
+    /// Only match messages whose custom fields equal these values, e.g. {"component": "db"}
+    #[serde(default)]
+    pub fields: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchFacetsParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Search query (full-text search)
+    pub query: String,
+    /// Also match the content of text-like attachments (default: false)
+    #[serde(default)]
+    pub include_attachments: Option<bool>,
+    /// Only count messages whose custom fields equal these values, e.g. {"component": "db"}
+    #[serde(default)]
+    pub fields: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Values shown per facet (default: 20, max: 100)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Parameters for apply_label tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ApplyLabelParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Agent applying the label
+    pub agent_name: String,
+    /// Message to label
+    pub message_id: i64,
+    /// Label name: lowercase letters, digits, '-', '_' and '/'
+    pub label: String,
+    /// Color as #rrggbb when the label is new (default: picked by name)
+    pub color: Option<String>,
+}
+
+/// Parameters for remove_label tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RemoveLabelParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Agent removing the label
+    pub agent_name: String,
+    /// Labeled message
+    pub message_id: i64,
+    /// Label name
+    pub label: String,
+}
+
+/// Parameters for list_by_label tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListByLabelParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Label whose messages to list; omit to list the project's labels
+    pub label: Option<String>,
+    /// Newest messages returned (default: 20, max: 100)
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+/// Parameters for save_search tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SaveSearchParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Folder name; saving an existing name replaces its criteria
+    pub name: String,
+    /// Owning agent, whose inbox the search covers; omit to share the
+    /// search with the whole project
+    pub agent_name: Option<String>,
+    /// Full-text query, with the syntax of search_messages
+    pub query: Option<String>,
+    /// Comma-separated importance levels to match, e.g. "urgent,high"
+    pub importance: Option<String>,
+    /// Comma-separated sender names or capability:<name> addresses
+    pub from: Option<String>,
+    /// Only messages the owner has not read (needs agent_name)
+    #[serde(default)]
+    pub unread: bool,
+    /// Only ack-required messages the owner has not acknowledged (needs agent_name)
+    #[serde(default)]
+    pub unacked: bool,
+    /// Only messages sent within this long, e.g. "24h", "7d"
+    pub within: Option<String>,
+}
+
+/// Parameters for list_saved_searches tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListSavedSearchesParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Also list the searches this agent owns
+    pub agent_name: Option<String>,
+}
+
+/// Parameters for run_saved_search tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RunSavedSearchParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Saved search ID, from list_saved_searches
+    pub search_id: i64,
+    /// Newest matches returned (default: 20, max: 100)
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+/// Parameters for delete_saved_search tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeleteSavedSearchParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Saved search ID, from list_saved_searches
+    pub search_id: i64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListCustomFieldsParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct GetThreadParams {
     /// Project slug
     #[serde(alias = "project_key")]
     pub project_slug: String,
     /// Thread ID
     pub thread_id: String,
+    /// Reading agent. When given, the thread is marked read for this agent.
+    #[serde(default)]
+    pub agent_name: Option<String>,
+    /// Only return messages since the agent's last read (requires agent_name)
+    #[serde(default)]
+    pub unread_only: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetThreadTreeParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Thread ID
+    pub thread_id: String,
+}
+
+/// Parameters for watch_thread and unwatch_thread tools
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WatchThreadParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Watching agent
+    pub agent_name: String,
+    /// Thread ID
+    pub thread_id: String,
+}
+
+/// Parameters for grant_delegation and revoke_delegation tools
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DelegationParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Agent granting permission to send on its behalf
+    pub agent_name: String,
+    /// Agent allowed to send on behalf of agent_name
+    pub delegate_name: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListDelegationsParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Only grants given or held by this agent
+    pub agent_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -182,11 +446,29 @@ pub struct ClaimReviewParams {
     pub reviewer_name: String,
 }
 
-#[derive(Debug, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct ListAgentsParams {
     /// Project slug
     #[serde(alias = "project_key")]
     pub project_slug: String,
+    /// Maximum agents to return (default: all, max 500)
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// Cursor from a previous page's "next cursor"
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Sort key: "name" (default), "created", or "last_active" (newest first)
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// Only agents active within this duration (e.g. "15m", "2h", "7d")
+    #[serde(default)]
+    pub active_within: Option<String>,
+    /// Only agents on this program (e.g. "claude-code")
+    #[serde(default)]
+    pub program: Option<String>,
+    /// Only agents on this model
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -204,6 +486,24 @@ pub struct FileReservationParams {
     pub reason: Option<String>,
     /// TTL in seconds (default 3600)
     pub ttl_seconds: Option<i64>,
+    /// Comma-separated agent names to notify when this reservation is released or expires
+    #[serde(default)]
+    pub notify_on_release: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WaitForPathParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Agent name waiting for the path
+    pub agent_name: String,
+    /// File path (or glob pattern) to wait for
+    pub path: String,
+    /// Maximum seconds to block (default 30, max 300)
+    pub timeout_seconds: Option<u64>,
+    /// Register for a release receipt if still blocked at timeout (default: true)
+    pub watch: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -217,6 +517,18 @@ pub struct ListReservationsParams {
     pub all_agents: Option<bool>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckPathsParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Paths to check, relative to the project root
+    pub paths: Vec<String>,
+    /// Agent whose own reservations are ignored (optional)
+    #[serde(default)]
+    pub agent_name: Option<String>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ReleaseReservationParams {
     /// Reservation ID to release
@@ -286,6 +598,21 @@ pub struct ReplyMessageParams {
     pub subject_prefix: Option<String>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ForwardMessageParams {
+    /// Project slug to forward into
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Forwarding agent, registered in the project forwarded into
+    pub sender_name: String,
+    /// Message ID to forward (from this project or one sharing a product with it)
+    pub message_id: i64,
+    /// Recipients in the project forwarded into (comma-separated)
+    pub to: String,
+    /// Note placed above the forwarded message (optional)
+    pub note: Option<String>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct MarkMessageReadParams {
     /// Project slug
@@ -308,6 +635,118 @@ pub struct AcknowledgeMessageParams {
     pub message_id: i64,
 }
 
+/// Parameters for respond_deadline tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RespondDeadlineParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Recipient responding to the deadline
+    pub agent_name: String,
+    /// Message carrying the requested deadline
+    pub message_id: i64,
+    /// Counter-proposal as a duration ("6h") or timestamp; omit to accept
+    /// the requested deadline
+    pub propose: Option<String>,
+}
+
+/// Parameters for list_deadlines tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListDeadlinesParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Agent whose open deadlines to list
+    pub agent_name: String,
+    /// List every recipient's deadline for this message instead
+    pub message_id: Option<i64>,
+}
+
+/// Parameters for approve_pending_message tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ApprovePendingMessageParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Approving agent; must not be the sender
+    pub agent_name: String,
+    /// ID of the held message
+    pub message_id: i64,
+    /// `true` (default) delivers the message, `false` rejects it
+    pub approve: Option<bool>,
+    /// Why, kept in the audit log
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListPendingMessagesParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListDeadLettersParams {
+    /// Only sends addressed to this project
+    #[serde(default, alias = "project_key")]
+    pub project_slug: Option<String>,
+    /// Also list dead letters already delivered by a requeue (default: false)
+    #[serde(default)]
+    pub include_requeued: Option<bool>,
+    /// Maximum number of dead letters to return (default: 50)
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RequeueDeadLetterParams {
+    /// ID of the dead letter
+    pub dead_letter_id: i64,
+    /// Deliver in this project instead, e.g. the one it was adopted into
+    #[serde(default, alias = "project_key")]
+    pub project_slug: Option<String>,
+    /// Send as this agent instead
+    #[serde(default)]
+    pub sender_name: Option<String>,
+    /// Replacement recipient agent names (comma-separated)
+    #[serde(default)]
+    pub to: Option<String>,
+    /// Replacement CC recipient agent names (comma-separated)
+    #[serde(default)]
+    pub cc: Option<String>,
+    /// Replacement BCC recipient agent names (comma-separated)
+    #[serde(default)]
+    pub bcc: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetBroadcastStatusParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// ID of the broadcast message
+    pub message_id: i64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetMessageReceiptsParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Message ID
+    pub message_id: i64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetContextPackParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Agent to brief
+    pub agent_name: String,
+    /// Approximate size limit of the briefing in tokens (default: 2000, range 200-16000)
+    pub budget_tokens: Option<usize>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateAgentIdentityParams {
     /// Project slug
@@ -346,6 +785,59 @@ pub struct GetProjectInfoParams {
     pub project_slug: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetFocusWindowParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Window length in minutes starting now (default 60). Use 0 to end the active window.
+    pub duration_minutes: Option<i64>,
+    /// Comma-separated importance levels still delivered immediately (default: "high,urgent")
+    pub allowed_importance: Option<String>,
+    /// Reason for the quiet period
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetProjectSettingsParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// IANA time zone, e.g. "Europe/Berlin" (default: "UTC")
+    pub timezone: Option<String>,
+    /// Local working hours as "HH:MM-HH:MM", e.g. "09:00-17:00". Empty string removes them.
+    pub business_hours: Option<String>,
+    /// Working days, e.g. "mon-fri" or "mon,wed,fri" (default: "mon-fri")
+    pub business_days: Option<String>,
+    /// Hold low-importance mail outside business hours until the next working day
+    pub quiet_hours: Option<bool>,
+    /// Language of system messages (reminders, escalations), e.g. "de" or "pt-BR" (default: "en")
+    pub locale: Option<String>,
+    /// Forward matching messages (e.g. urgent ones) to human mailboxes over SMTP
+    pub email_bridge: Option<bool>,
+    /// Comma-separated email addresses to forward to; empty string uses the server default
+    pub email_recipients: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetMessageTemplateParams {
+    /// Locale the template is for, e.g. "de" or "pt-BR"
+    pub locale: String,
+    /// Template key, e.g. "escalation.reminder.subject" (see list_message_templates)
+    pub key: String,
+    /// Template text with {placeholder} names of the key. Empty string restores the built-in template.
+    pub template: String,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ListMessageTemplatesParams {
+    /// Locale to list (default: the project's locale, or "en")
+    pub locale: Option<String>,
+    /// Project whose locale to list
+    #[serde(alias = "project_key")]
+    pub project_slug: Option<String>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetAgentProfileParams {
     /// Project slug
@@ -355,13 +847,22 @@ pub struct GetAgentProfileParams {
     pub agent_name: String,
 }
 
-#[derive(Debug, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct ListThreadsParams {
     /// Project slug
     #[serde(alias = "project_key")]
     pub project_slug: String,
     /// Maximum threads to return
     pub limit: Option<i64>,
+    /// Cursor from a previous page's "next cursor"
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Agent whose unread count to show per thread
+    #[serde(default)]
+    pub agent_name: Option<String>,
+    /// Also list threads archived for inactivity (default: false)
+    #[serde(default)]
+    pub include_archived: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -421,6 +922,35 @@ pub struct SetContactPolicyParams {
     pub contact_policy: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExportTeamGraphParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ImportTeamGraphParams {
+    /// Project slug to import into
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Team graph JSON as returned by export_team_graph
+    pub graph: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LinkCommitParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Thread to post the commit's build results to
+    pub thread_id: String,
+    /// Commit SHA, or a prefix of at least 7 characters
+    pub sha: String,
+    /// Only match builds of this repository ("owner/name")
+    pub repo: Option<String>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct AcquireBuildSlotParams {
     /// Project slug
@@ -547,6 +1077,68 @@ pub struct QuickReviewWorkflowParams {
     pub description: String,
 }
 
+// ============================================================================
+// Thread Workflows
+// ============================================================================
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WorkflowStateParam {
+    /// State name, e.g. "review"
+    pub name: String,
+    /// Agent name or `capability:<name>` address notified when a thread enters the state
+    pub responsible: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DefineWorkflowParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Workflow name (redefining an existing name replaces it)
+    pub name: String,
+    /// States in order; threads start in the first one
+    pub states: Vec<WorkflowStateParam>,
+    /// Allowed moves, e.g. ["spec -> implement", "review -> implement"]
+    pub transitions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StartWorkflowParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Thread to attach the workflow to
+    pub thread_id: String,
+    /// Workflow name
+    pub workflow: String,
+    /// Agent starting the workflow
+    pub agent_name: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AdvanceWorkflowParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Thread following the workflow
+    pub thread_id: String,
+    /// State to move to
+    pub to_state: String,
+    /// Agent making the move
+    pub agent_name: String,
+    /// Note for the next responsible agent
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetWorkflowStatusParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Thread following the workflow
+    pub thread_id: String,
+}
+
 // ============================================================================
 // Macro Convenience Tools (Session/Workflow Helpers)
 // ============================================================================
@@ -772,10 +1364,9 @@ pub struct ExportMailboxParams {
     /// Project slug to export
     #[serde(alias = "project_key")]
     pub project_slug: String,
-    /// Export format: html, json, or markdown
+    /// Export format: html, json, markdown, or mbox
     pub format: Option<String>,
-    /// Include attachments in export
-    #[allow(dead_code)]
+    /// Include attachments as MIME parts (mbox only, default true)
     pub include_attachments: Option<bool>,
 }
 
@@ -788,6 +1379,18 @@ pub struct ListOutboxParams {
     pub agent_name: String,
     /// Maximum number of messages to return
     pub limit: Option<i64>,
+    /// Cursor from a previous page's "next cursor"
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Only sent messages whose recipients have not read or acknowledged
+    /// them in time, with suggested follow-ups
+    pub needs_attention: Option<bool>,
+    /// With needs_attention: how long a recipient may leave a message unread
+    /// (e.g. "4h"; default 4h)
+    pub unread_after: Option<String>,
+    /// With needs_attention: how long a recipient may leave an ack-required
+    /// message unacknowledged (e.g. "1d"; default 24h)
+    pub unacked_after: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -807,6 +1410,17 @@ pub struct FileReservationPathsParams {
     pub ttl_seconds: Option<i64>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReleaseReservationSetParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Agent holding the reservation set
+    pub agent_name: String,
+    /// Reservation set ID returned by file_reservation_paths
+    pub set_id: i64,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct InstallPrecommitGuardParams {
     /// Project slug
@@ -862,6 +1476,24 @@ pub struct ListActivityParams {
     pub limit: Option<i64>,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetSlaReportParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Period to report on, e.g. "24h", "7d", "4w" (default "7d")
+    pub period: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetUsageParams {
+    /// Project slug
+    #[serde(alias = "project_key")]
+    pub project_slug: String,
+    /// Report only this agent's usage (optional)
+    pub agent_name: Option<String>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ListPendingReviewsParams {
     /// Filter by project slug (optional)
@@ -895,6 +1527,9 @@ pub struct ClaimResult {
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ThreadSummaryItem {
     pub thread_id: String,
+    /// Global thread id, spanning every project the thread continued in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_thread_id: Option<String>,
     pub subject: String,
     pub message_count: usize,
     pub participants: Vec<String>,
@@ -0,0 +1,54 @@
+//! Tools offered by operator WASM plugins
+//!
+//! Plugin tools are listed next to the built-in tools and dispatched to the
+//! plugin that declared them. A plugin cannot replace a built-in tool.
+
+use mouchak_mail_core::{Error, model::ModelManager};
+use rmcp::{
+    ErrorData as McpError,
+    model::{CallToolResult, Content, Tool},
+};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Plugin tools whose names do not clash with `builtin`.
+pub fn plugin_tools(mm: &ModelManager, builtin: &[Tool]) -> Vec<Tool> {
+    mm.plugins()
+        .tools()
+        .iter()
+        .filter(|t| !builtin.iter().any(|b| b.name == t.name))
+        .map(|t| {
+            Tool::new(
+                t.name.clone(),
+                format!("{} (plugin: {})", t.description, t.plugin),
+                Arc::new(t.input_schema.clone()),
+            )
+        })
+        .collect()
+}
+
+/// Calls a plugin tool. Plugins run on a blocking thread since a call may
+/// use its whole fuel budget.
+pub async fn call_plugin_tool_impl(
+    mm: &Arc<ModelManager>,
+    name: &str,
+    arguments: Option<Value>,
+) -> Result<CallToolResult, McpError> {
+    let mm = mm.clone();
+    let tool = name.to_string();
+    let result = tokio::task::spawn_blocking(move || {
+        mm.plugins()
+            .call_tool(&tool, arguments.unwrap_or(Value::Null))
+    })
+    .await
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    match result {
+        Ok(text) => Ok(CallToolResult::success(vec![Content::text(text)])),
+        Err(Error::NotFound) => Err(McpError::invalid_params(
+            format!("Unknown plugin tool '{}'", name),
+            None,
+        )),
+        Err(e) => Err(McpError::invalid_params(e.to_string(), None)),
+    }
+}
@@ -5,7 +5,10 @@
 use mouchak_mail_core::{
     ProjectId,
     ctx::Ctx,
-    model::{ModelManager, message::MessageBmc, product::ProductBmc, project::ProjectBmc},
+    model::{
+        ModelManager, global_thread::GlobalThreadBmc, message::MessageBmc, product::ProductBmc,
+        project::ProjectBmc,
+    },
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
@@ -46,7 +49,7 @@ pub async fn link_project_to_product_impl(
 
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let link_id = ProductBmc::link_project(ctx, mm, product.id, project.id.get())
+    let link_id = ProductBmc::link_project(ctx, mm, product.id, project.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -69,7 +72,7 @@ pub async fn unlink_project_from_product_impl(
 
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let unlinked = ProductBmc::unlink_project(ctx, mm, product.id, project.id.get())
+    let unlinked = ProductBmc::unlink_project(ctx, mm, product.id, project.id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -184,7 +187,7 @@ pub async fn search_messages_product_impl(
             .await
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
-        let messages = MessageBmc::search(ctx, mm, pid.get(), &params.query, limit)
+        let messages = MessageBmc::search(ctx, mm, pid, &params.query, limit)
             .await
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -224,43 +227,62 @@ pub async fn summarize_thread_product_impl(
         .await
         .map_err(|e| McpError::invalid_params(format!("Product not found: {}", e), None))?;
 
-    let project_ids = ProductBmc::get_linked_projects(ctx, mm, product.id)
+    let project_ids: Vec<ProjectId> = ProductBmc::get_linked_projects(ctx, mm, product.id)
         .await
-        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .into_iter()
+        .map(ProjectId::new)
+        .collect();
 
     let thread_ids: Vec<String> = params.thread_id.into();
     let mut summaries = Vec::new();
     let mut errors = Vec::new();
 
     for thread_id in &thread_ids {
-        let mut aggregated_messages = Vec::new();
-        let mut project_sources = Vec::new();
-
-        // Collect messages from all projects
-        for &project_id in &project_ids {
-            let pid = ProjectId::new(project_id);
-            let project = ProjectBmc::get(ctx, mm, pid)
-                .await
-                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-
-            match MessageBmc::list_by_thread(ctx, mm, pid.get(), thread_id).await {
-                Ok(messages) if !messages.is_empty() => {
-                    project_sources.push(project.slug.clone());
-                    aggregated_messages.extend(messages);
-                }
-                Ok(_) => {} // Empty, skip
-                Err(e) => {
-                    errors.push(ThreadSummaryError {
-                        thread_id: thread_id.clone(),
-                        error: format!("Error in project {}: {}", project.slug, e),
-                    });
+        // Global ids name one conversation across projects; a local id may
+        // name unrelated threads in several projects, summarized separately
+        let threads = GlobalThreadBmc::resolve(ctx, mm, &project_ids, thread_id)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        if threads.is_empty() {
+            errors.push(ThreadSummaryError {
+                thread_id: thread_id.clone(),
+                error: "Thread not found in any linked project".to_string(),
+            });
+        }
+
+        for thread in threads {
+            let mut aggregated_messages = Vec::new();
+            let mut project_sources = Vec::new();
+
+            // Collect messages from every project the thread spans
+            for member in &thread.members {
+                let project = ProjectBmc::get(ctx, mm, member.project_id)
+                    .await
+                    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+                match MessageBmc::list_by_thread(ctx, mm, member.project_id, &member.thread_id)
+                    .await
+                {
+                    Ok(messages) if !messages.is_empty() => {
+                        project_sources.push(project.slug.clone());
+                        aggregated_messages.extend(messages);
+                    }
+                    Ok(_) => {} // Empty, skip
+                    Err(e) => {
+                        errors.push(ThreadSummaryError {
+                            thread_id: thread_id.clone(),
+                            error: format!("Error in project {}: {}", project.slug, e),
+                        });
+                    }
                 }
             }
-        }
+            if aggregated_messages.is_empty() {
+                continue;
+            }
 
-        if !aggregated_messages.is_empty() {
             // Sort by created_ts
-            aggregated_messages.sort_by(|a, b| a.created_ts.cmp(&b.created_ts));
+            aggregated_messages.sort_by_key(|m| m.created_ts);
 
             let mut participants: Vec<String> = aggregated_messages
                 .iter()
@@ -280,16 +302,12 @@ pub async fn summarize_thread_product_impl(
 
             summaries.push(ThreadSummaryItem {
                 thread_id: thread_id.clone(),
+                global_thread_id: Some(thread.global_thread_id),
                 subject: format!("{} (from: {})", subject, project_sources.join(", ")),
                 message_count: aggregated_messages.len(),
                 participants,
                 last_snippet,
             });
-        } else if errors.iter().all(|e| e.thread_id != *thread_id) {
-            errors.push(ThreadSummaryError {
-                thread_id: thread_id.clone(),
-                error: "Thread not found in any linked project".to_string(),
-            });
         }
     }
 
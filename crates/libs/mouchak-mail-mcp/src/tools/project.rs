@@ -4,14 +4,26 @@
 
 use mouchak_mail_core::{
     ctx::Ctx,
-    model::{ModelManager, agent::AgentBmc, project::ProjectBmc},
+    model::{
+        ModelManager,
+        agent::AgentBmc,
+        focus_window::{DEFAULT_ALLOWED_IMPORTANCE, FocusWindowBmc, FocusWindowForCreate},
+        message_catalog::{DEFAULT_LOCALE, MessageCatalogBmc},
+        project::ProjectBmc,
+        project_settings::{ProjectSettings, ProjectSettingsBmc, ProjectSettingsForUpdate},
+        project_template::ProjectTemplateBmc,
+    },
     utils::validation::validate_project_key,
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
 
 use super::helpers;
-use super::{EnsureProjectParams, GetProjectInfoParams};
+use super::{
+    CreateProjectFromTemplateParams, EnsureProjectParams, GetProjectInfoParams,
+    ListMessageTemplatesParams, ListProjectsParams, SetFocusWindowParams, SetMessageTemplateParams,
+    SetProjectSettingsParams,
+};
 
 /// Ensure a project exists (create if not).
 pub async fn ensure_project_impl(
@@ -31,8 +43,8 @@ pub async fn ensure_project_impl(
     match ProjectBmc::get_by_identifier(ctx, mm, &slug).await {
         Ok(project) => {
             let msg = format!(
-                "Project exists: {} (id: {}, created: {})",
-                project.slug, project.id, project.created_at
+                "Project exists: {} (id: {}, public id: {}, created: {})",
+                project.slug, project.id, project.public_id, project.created_at
             );
             Ok(CallToolResult::success(vec![Content::text(msg)]))
         }
@@ -48,22 +60,114 @@ pub async fn ensure_project_impl(
     }
 }
 
-/// List all available projects.
+/// Create a new project from a template bundle in the data dir.
+pub async fn create_project_from_template_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: CreateProjectFromTemplateParams,
+) -> Result<CallToolResult, McpError> {
+    validate_project_key(&params.slug).map_err(|e| {
+        McpError::invalid_params(
+            format!("{}", e),
+            Some(serde_json::json!({ "details": e.context() })),
+        )
+    })?;
+    if ProjectBmc::get_by_identifier(ctx, mm, &params.slug)
+        .await
+        .is_ok()
+    {
+        return Err(McpError::invalid_params(
+            format!("Project '{}' already exists", params.slug),
+            None,
+        ));
+    }
+
+    let invalid = |e: mouchak_mail_core::Error| match e {
+        mouchak_mail_core::Error::InvalidInput(_)
+        | mouchak_mail_core::Error::Validation(_)
+        | mouchak_mail_core::Error::SerdeJson(_) => McpError::invalid_params(e.to_string(), None),
+        e => McpError::internal_error(e.to_string(), None),
+    };
+    let template = ProjectTemplateBmc::load(&params.template).map_err(invalid)?;
+    let human_key = params.human_key.as_deref().unwrap_or(&params.slug);
+    let summary = ProjectTemplateBmc::instantiate(
+        ctx,
+        mm,
+        &params.template,
+        &template,
+        &params.slug,
+        human_key,
+    )
+    .await
+    .map_err(invalid)?;
+
+    let mut output = format!(
+        "Created project '{}' with id {} from template '{}'\n",
+        summary.slug, summary.project_id, summary.template
+    );
+    for (label, items) in [
+        ("Agents created", &summary.team.agents_created),
+        ("Capabilities granted", &summary.team.capabilities_granted),
+        ("Contacts linked", &summary.team.contacts_created),
+        ("Macros added", &summary.macros_created),
+        ("Labels created", &summary.labels_created),
+        ("SLAs set", &summary.slas_set),
+        ("Welcome messages sent", &summary.welcome_messages_sent),
+    ] {
+        if !items.is_empty() {
+            output.push_str(&format!("{}: {}\n", label, items.join(", ")));
+        }
+    }
+    if summary.settings_applied {
+        output.push_str("Project settings applied\n");
+    }
+    let skipped: Vec<&String> = summary
+        .team
+        .skipped
+        .iter()
+        .chain(&summary.skipped)
+        .collect();
+    if !skipped.is_empty() {
+        output.push_str("\nSkipped:\n");
+        for reason in skipped {
+            output.push_str(&format!("- {}\n", reason));
+        }
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// List available projects, optionally paged, filtered and sorted.
 pub async fn list_projects_impl(
     ctx: &Ctx,
     mm: &Arc<ModelManager>,
+    params: ListProjectsParams,
 ) -> Result<CallToolResult, McpError> {
-    let projects = ProjectBmc::list_all(ctx, mm)
+    let query = helpers::list_query(
+        params.limit,
+        params.cursor,
+        params.sort.as_deref(),
+        params.active_within,
+        params.program,
+        params.model,
+    )?;
+    let page = ProjectBmc::list_page(ctx, mm, &query)
         .await
-        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        .map_err(helpers::list_error)?;
 
-    let mut output = format!("Projects ({}):\n\n", projects.len());
-    for p in &projects {
+    let mut output = if page.items.len() as i64 == page.total {
+        format!("Projects ({}):\n\n", page.total)
+    } else {
+        format!("Projects ({} of {}):\n\n", page.items.len(), page.total)
+    };
+    for p in &page.items {
         output.push_str(&format!(
             "- {} (slug: {}, created: {})\n",
             p.human_key, p.slug, p.created_at
         ));
     }
+    if let Some(cursor) = &page.next_cursor {
+        output.push_str(&format!("\nMore results: pass cursor \"{}\"\n", cursor));
+    }
 
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
@@ -84,14 +188,253 @@ pub async fn get_project_info_impl(
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
-    let output = format!(
-        "Project: {} ({})\nID: {}\nAgents: {}\nMessages: {}\nCreated: {}",
+    let focus_window = FocusWindowBmc::get_active(ctx, mm, project.id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let settings = ProjectSettingsBmc::get(ctx, mm, project.id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let mut output = format!(
+        "Project: {} ({})\nID: {}\nAgents: {}\nMessages: {}\nCreated: {}\n{}",
         project.human_key,
         project.slug,
         project.id,
         agents.len(),
         message_count,
-        project.created_at
+        project.created_at,
+        format_settings(&settings)
+    );
+    if let Some(window) = focus_window {
+        let deferred = FocusWindowBmc::count_deferred(ctx, mm, window.id)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        output.push_str(&format!(
+            "\nFocus window: active until {} (allows: {}, deferred: {}, reason: {})",
+            window.ends_ts,
+            window.allowed_importance.join(", "),
+            deferred,
+            window.reason
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Start or end a project focus window.
+pub async fn set_focus_window_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: SetFocusWindowParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let duration_minutes = params.duration_minutes.unwrap_or(60);
+
+    if duration_minutes < 0 {
+        return Err(McpError::invalid_params(
+            "duration_minutes must not be negative".to_string(),
+            None,
+        ));
+    }
+
+    if duration_minutes == 0 {
+        let Some(window) = FocusWindowBmc::get_active(ctx, mm, project.id)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        else {
+            let msg = format!("No active focus window in '{}'", project.slug);
+            return Ok(CallToolResult::success(vec![Content::text(msg)]));
+        };
+
+        let delivered = FocusWindowBmc::end(ctx, mm, window.id)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let msg = format!(
+            "Ended focus window {} in '{}'; delivered {} deferred message(s)",
+            window.id, project.slug, delivered
+        );
+        return Ok(CallToolResult::success(vec![Content::text(msg)]));
+    }
+
+    let allowed_importance = match params.allowed_importance.as_deref() {
+        Some(csv) => csv
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        None => DEFAULT_ALLOWED_IMPORTANCE
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+    };
+
+    let starts_ts = chrono::Utc::now().naive_utc();
+    let ends_ts = starts_ts + chrono::Duration::minutes(duration_minutes);
+    let fw_c = FocusWindowForCreate {
+        project_id: project.id,
+        starts_ts,
+        ends_ts,
+        allowed_importance,
+        reason: params.reason.unwrap_or_default(),
+    };
+
+    let id = FocusWindowBmc::create(ctx, mm, fw_c)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::InvalidInput(msg) => McpError::invalid_params(msg, None),
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
+    let window = FocusWindowBmc::get(ctx, mm, id)
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let msg = format!(
+        "Started focus window {} in '{}' until {} (allows: {})",
+        window.id,
+        project.slug,
+        window.ends_ts,
+        window.allowed_importance.join(", ")
     );
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
+/// Set a project's time zone, business hours, quiet hours, locale and email bridge.
+pub async fn set_project_settings_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: SetProjectSettingsParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+
+    let settings_u = ProjectSettingsForUpdate {
+        timezone: params.timezone,
+        business_hours: params.business_hours,
+        business_days: params.business_days,
+        quiet_hours: params.quiet_hours,
+        locale: params.locale,
+        email_bridge: params.email_bridge,
+        email_recipients: params.email_recipients,
+    };
+    let settings = ProjectSettingsBmc::update(ctx, mm, project.id, settings_u)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::InvalidInput(msg) => McpError::invalid_params(msg, None),
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
+
+    let msg = format!(
+        "Updated settings of '{}'\n{}",
+        project.slug,
+        format_settings(&settings)
+    );
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
+/// Override or restore a system message template.
+pub async fn set_message_template_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: SetMessageTemplateParams,
+) -> Result<CallToolResult, McpError> {
+    let to_mcp = |e: mouchak_mail_core::Error| match e {
+        mouchak_mail_core::Error::InvalidInput(msg) => McpError::invalid_params(msg, None),
+        e => McpError::internal_error(e.to_string(), None),
+    };
+
+    let msg = if params.template.trim().is_empty() {
+        let removed = MessageCatalogBmc::remove_template(ctx, mm, &params.locale, &params.key)
+            .await
+            .map_err(to_mcp)?;
+        if removed {
+            format!(
+                "Restored the built-in '{}' template for '{}'",
+                params.key, params.locale
+            )
+        } else {
+            format!(
+                "No custom '{}' template for '{}'",
+                params.key, params.locale
+            )
+        }
+    } else {
+        MessageCatalogBmc::set_template(ctx, mm, &params.locale, &params.key, &params.template)
+            .await
+            .map_err(to_mcp)?;
+        format!("Set the '{}' template for '{}'", params.key, params.locale)
+    };
+    Ok(CallToolResult::success(vec![Content::text(msg)]))
+}
+
+/// List the system message templates in effect for a locale.
+pub async fn list_message_templates_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: ListMessageTemplatesParams,
+) -> Result<CallToolResult, McpError> {
+    let locale = match (params.locale, params.project_slug) {
+        (Some(locale), _) => locale,
+        (None, Some(slug)) => {
+            let project = helpers::resolve_project(ctx, mm, &slug).await?;
+            MessageCatalogBmc::project_locale(ctx, mm, project.id)
+                .await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        }
+        (None, None) => DEFAULT_LOCALE.to_string(),
+    };
+    let templates = MessageCatalogBmc::list_templates(ctx, mm, &locale)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::InvalidInput(msg) => McpError::invalid_params(msg, None),
+            e => McpError::internal_error(e.to_string(), None),
+        })?;
+
+    let mut output = format!("Message templates for '{}':\n", locale);
+    for t in &templates {
+        let origin = if t.custom { "custom" } else { "built-in" };
+        output.push_str(&format!(
+            "\n{} ({}, {})\n  {}\n",
+            t.key,
+            t.source,
+            origin,
+            t.template.replace('\n', "\n  ")
+        ));
+    }
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
+
+/// Time zone, local time and business hours, one per line.
+fn format_settings(settings: &ProjectSettings) -> String {
+    let now = chrono::Utc::now().naive_utc();
+    let mut output = format!(
+        "Time zone: {} (local time {})",
+        settings.timezone,
+        settings.local(now).format("%Y-%m-%d %H:%M")
+    );
+    match &settings.business_hours {
+        Some(hours) => {
+            output.push_str(&format!(
+                "\nBusiness hours: {} ({})",
+                hours,
+                if settings.is_business_time(now) {
+                    "open now"
+                } else {
+                    "closed now"
+                }
+            ));
+            if settings.quiet_hours {
+                output.push_str("\nQuiet hours: low-importance mail is held after hours");
+            }
+        }
+        None => output.push_str("\nBusiness hours: none (around the clock)"),
+    }
+    output.push_str(&format!("\nLocale: {}", settings.locale));
+    if settings.email_bridge {
+        let recipients = if settings.email_recipients.is_empty() {
+            "server default".to_string()
+        } else {
+            settings.email_recipients.join(", ")
+        };
+        output.push_str(&format!("\nEmail bridge: on ({})", recipients));
+    }
+    output
+}
@@ -17,6 +17,9 @@ use rmcp::{
 };
 use std::sync::Arc;
 
+/// Most recently active threads listed as resources per project.
+const LISTED_THREADS: i64 = 50;
+
 #[derive(serde::Serialize)]
 struct ResourceMessage<'a> {
     id: i64,
@@ -95,6 +98,32 @@ fn parse_resource_uri(
     ))
 }
 
+/// Returns the project slug and thread id named by a thread resource URI.
+///
+/// Accepts `mouchak-mail://{project}/thread/{thread_id}` and
+/// `resource://thread/{thread_id}?project={project}`.
+pub fn parse_thread_uri(uri_str: &str) -> Result<(String, String), McpError> {
+    let uri = url::Url::parse(uri_str)
+        .map_err(|e| McpError::invalid_params(format!("Invalid URI: {}", e), None))?;
+    if uri.scheme() != "mouchak-mail" && uri.scheme() != "resource" {
+        return Err(McpError::invalid_params(
+            "URI scheme must be 'mouchak-mail' or 'resource'".to_string(),
+            None,
+        ));
+    }
+    let query: std::collections::HashMap<_, _> = uri.query_pairs().into_owned().collect();
+    let (project_slug, resource_type, resource_id, _, _) = parse_resource_uri(&uri, &query)?;
+    match resource_id {
+        Some(thread_id) if resource_type == "thread" && !project_slug.is_empty() => {
+            Ok((project_slug, thread_id))
+        }
+        _ => Err(McpError::invalid_params(
+            format!("Not a thread resource: {}", uri_str),
+            None,
+        )),
+    }
+}
+
 /// Handle identity resource type
 fn handle_identity_resource(uri: &url::Url, uri_str: &str) -> Result<ReadResourceResult, McpError> {
     let path = uri.path();
@@ -178,9 +207,9 @@ async fn handle_mailbox_resource(
         .map_err(|e| McpError::invalid_params(format!("Agent not found: {}", e), None))?;
 
     let messages = if is_inbox {
-        MessageBmc::list_inbox_for_agent(ctx, mm, project_id.get(), agent.id.get(), limit).await
+        MessageBmc::list_inbox_for_agent(ctx, mm, project_id, agent.id, limit).await
     } else {
-        MessageBmc::list_outbox_for_agent(ctx, mm, project_id.get(), agent.id.get(), limit).await
+        MessageBmc::list_outbox_for_agent(ctx, mm, project_id, agent.id, limit).await
     }
     .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -213,7 +242,7 @@ async fn handle_thread_resource(
     thread_id: &str,
     include_bodies: bool,
 ) -> Result<String, McpError> {
-    let messages = MessageBmc::list_by_thread(ctx, mm, project_id.get(), thread_id)
+    let messages = MessageBmc::list_by_thread(ctx, mm, project_id, thread_id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -321,7 +350,7 @@ pub async fn read_resource_impl(
             handle_thread_resource(ctx, mm, project_id, thread_id_str, include_bodies).await?
         }
         "threads" => {
-            let threads = MessageBmc::list_threads(ctx, mm, project_id.get(), limit)
+            let threads = MessageBmc::list_threads(ctx, mm, project_id, limit, false)
                 .await
                 .map_err(|e| McpError::internal_error(e.to_string(), None))?;
             serde_json::to_string_pretty(&threads)
@@ -461,6 +490,28 @@ pub async fn list_resources_impl(
             annotations: None,
         });
 
+        let threads = MessageBmc::list_threads(ctx, mm, project.id, LISTED_THREADS, false)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        for thread in threads {
+            resources.push(Resource {
+                raw: RawResource {
+                    uri: format!("mouchak-mail://{}/thread/{}", slug, thread.thread_id),
+                    name: format!("Thread: {} ({})", thread.subject, slug),
+                    description: Some(format!(
+                        "Messages of thread '{}'; subscribe to be notified of new messages",
+                        thread.thread_id
+                    )),
+                    mime_type: Some("application/json".to_string()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                    title: None,
+                },
+                annotations: None,
+            });
+        }
+
         resources.push(Resource {
             raw: RawResource {
                 uri: format!("mouchak-mail://{}/file_reservations", slug),
@@ -10,6 +10,7 @@ use mouchak_mail_core::{
         message::{MessageBmc, MessageForCreate},
         orchestration::{OrchestrationState, parse_thread_state},
     },
+    types::{AgentId, MessageId},
 };
 use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
 use std::sync::Arc;
@@ -25,7 +26,7 @@ pub async fn get_review_state_impl(
 ) -> Result<CallToolResult, McpError> {
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
-    let messages = MessageBmc::list_by_thread(ctx, mm, project.id.get(), &params.thread_id)
+    let messages = MessageBmc::list_by_thread(ctx, mm, project.id, &params.thread_id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -73,7 +74,7 @@ pub async fn claim_review_impl(
     let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
 
     // Get the completion message
-    let message = MessageBmc::get(ctx, mm, params.message_id)
+    let message = MessageBmc::get(ctx, mm, MessageId::new(params.message_id))
         .await
         .map_err(|e| McpError::invalid_params(format!("Message not found: {}", e), None))?;
 
@@ -83,7 +84,7 @@ pub async fn claim_review_impl(
         .unwrap_or_else(|| format!("TASK-{}", params.message_id));
 
     // Get thread messages to check state
-    let messages = MessageBmc::list_by_thread(ctx, mm, project.id.get(), &thread_id)
+    let messages = MessageBmc::list_by_thread(ctx, mm, project.id, &thread_id)
         .await
         .map_err(|e| McpError::internal_error(e.to_string(), None))?;
 
@@ -115,9 +116,9 @@ pub async fn claim_review_impl(
 
     // Send [REVIEWING] message
     let msg = MessageForCreate {
-        project_id: project.id.get(),
-        sender_id: reviewer.id.get(),
-        recipient_ids: vec![message.sender_id],
+        project_id: project.id,
+        sender_id: reviewer.id,
+        recipient_ids: vec![AgentId::new(message.sender_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: format!(
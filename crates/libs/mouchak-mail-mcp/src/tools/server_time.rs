@@ -0,0 +1,45 @@
+//! Server clock annotation for tool responses.
+//!
+//! Every successful tool call carries the server's current time in the
+//! result's `_meta` under [`META_KEY`], in the format of the `expires_ts`
+//! values tools return. Agents compare the two to see how long a
+//! reservation or build slot really has left, whatever their own clock says.
+
+use mouchak_mail_core::utils::clock;
+use rmcp::model::{CallToolResult, Meta};
+
+/// Key under which the server time is stored in a result's `_meta`.
+pub const META_KEY: &str = "mouchak/server_time";
+
+/// Records the current server time on `result`.
+pub fn stamp(result: &mut CallToolResult) {
+    result
+        .meta
+        .get_or_insert_with(Meta::new)
+        .0
+        .insert(META_KEY.to_string(), clock::server_time().into());
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::tools::budget;
+    use rmcp::model::Content;
+
+    #[test]
+    fn test_stamp_keeps_other_meta() {
+        let mut result = CallToolResult::success(vec![Content::text("ok")]);
+        budget::ensure_bytes(&mut result);
+        stamp(&mut result);
+
+        let meta = &result.meta.as_ref().unwrap().0;
+        assert!(meta.contains_key(budget::META_KEY));
+        let stamped = meta[META_KEY].as_str().unwrap();
+        assert!(
+            chrono::NaiveDateTime::parse_from_str(stamped, clock::RESPONSE_TS_FORMAT).is_ok(),
+            "Unexpected server time {}",
+            stamped
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Resource subscriptions for thread resources.
+//!
+//! MCP clients call `resources/subscribe` with a thread resource URI
+//! (`mouchak-mail://{project}/thread/{thread_id}`) and receive a
+//! `notifications/resources/updated` whenever a message is added to or
+//! removed from the thread, then re-read the resource.
+//!
+//! Each subscription runs a background task that polls the thread's
+//! [`MessageBmc::thread_version`]. Polling the database, rather than hooking
+//! message creation, also catches messages sent by other server processes
+//! and the web UI. Subscriptions belong to one MCP session and end when the
+//! session's service is dropped.
+
+use super::resources::parse_thread_uri;
+use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::ModelManager;
+use mouchak_mail_core::model::message::MessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::ProjectId;
+use rmcp::ErrorData as McpError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Default interval between checks of a subscribed thread.
+pub const THREAD_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A session's active resource subscriptions, keyed by URI.
+pub struct ResourceSubscriptions {
+    mm: Arc<ModelManager>,
+    interval: Duration,
+    watchers: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl ResourceSubscriptions {
+    pub fn new(mm: Arc<ModelManager>, interval: Duration) -> Self {
+        Self {
+            mm,
+            interval,
+            watchers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Subscribes to a thread resource.
+    ///
+    /// `notify` is called with the URI each time the thread changes; the
+    /// subscription ends when it returns `false` (e.g. the client is gone).
+    /// Subscribing again to the same URI replaces the earlier subscription.
+    ///
+    /// # Errors
+    /// Returns `invalid_params` if `uri` is not a thread resource of an
+    /// existing project.
+    pub async fn subscribe<F, Fut>(&self, uri: &str, notify: F) -> Result<(), McpError>
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send,
+    {
+        let ctx = Ctx::root_ctx();
+        let (project_slug, thread_id) = parse_thread_uri(uri)?;
+        let project = ProjectBmc::get_by_slug(&ctx, &self.mm, &project_slug)
+            .await
+            .map_err(|e| McpError::invalid_params(format!("Project not found: {}", e), None))?;
+        let project_id = project.id.get();
+        let mut last =
+            MessageBmc::thread_version(&ctx, &self.mm, ProjectId::new(project_id), &thread_id)
+                .await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let mm = self.mm.clone();
+        let interval = self.interval;
+        let watched_uri = uri.to_string();
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match MessageBmc::thread_version(&ctx, &mm, ProjectId::new(project_id), &thread_id)
+                    .await
+                {
+                    Ok(version) if version != last => {
+                        last = version;
+                        if !notify(watched_uri.clone()).await {
+                            tracing::debug!(uri = %watched_uri, "Subscriber gone, ending subscription");
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!(uri = %watched_uri, error = %e, "Failed to check subscribed thread");
+                    }
+                }
+            }
+        });
+
+        if let Some(old) = self.lock().insert(uri.to_string(), handle) {
+            old.abort();
+        }
+        Ok(())
+    }
+
+    /// Ends the subscription to `uri`. Unknown URIs are ignored.
+    pub fn unsubscribe(&self, uri: &str) {
+        if let Some(handle) = self.lock().remove(uri) {
+            handle.abort();
+        }
+    }
+
+    /// URIs with an active subscription, sorted.
+    pub fn subscribed_uris(&self) -> Vec<String> {
+        let mut uris: Vec<String> = self
+            .lock()
+            .iter()
+            .filter(|(_, handle)| !handle.is_finished())
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        uris.sort();
+        uris
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, JoinHandle<()>>> {
+        self.watchers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Drop for ResourceSubscriptions {
+    fn drop(&mut self) {
+        for (_, handle) in self.lock().drain() {
+            handle.abort();
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Jira and Linear ticket linking
+//!
+//! `link_ticket` attaches a ticket to a message and fetches its title and
+//! status right away when the provider is configured. Cached metadata is
+//! refreshed by [`refresh_stale_tickets`], which the server runs
+//! periodically. Fetch failures never fail the link; they are recorded on
+//! the ticket and shown next to the last known metadata.
+
+use chrono::Utc;
+use mouchak_mail_common::config::TicketsConfig;
+use mouchak_mail_core::{
+    ctx::Ctx,
+    model::{
+        ModelManager,
+        ticket::{Ticket, TicketBmc, TicketMetadata, TicketProvider},
+    },
+    types::MessageId,
+};
+use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use super::LinkTicketParams;
+
+/// Tickets refreshed per run, so one run stays short.
+const REFRESH_BATCH: i64 = 50;
+
+/// Time allowed for one provider request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+#[derive(Deserialize)]
+struct JiraIssue {
+    fields: JiraFields,
+}
+
+#[derive(Deserialize)]
+struct JiraFields {
+    summary: String,
+    status: JiraStatus,
+}
+
+#[derive(Deserialize)]
+struct JiraStatus {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct LinearResponse {
+    data: Option<LinearData>,
+    #[serde(default)]
+    errors: Vec<LinearError>,
+}
+
+#[derive(Deserialize)]
+struct LinearData {
+    issue: Option<LinearIssue>,
+}
+
+#[derive(Deserialize)]
+struct LinearIssue {
+    title: String,
+    url: String,
+    state: LinearState,
+}
+
+#[derive(Deserialize)]
+struct LinearState {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct LinearError {
+    message: String,
+}
+
+/// Providers metadata can be fetched from.
+pub fn configured_providers(config: &TicketsConfig) -> Vec<TicketProvider> {
+    let mut providers = Vec::new();
+    if config.jira().is_some() {
+        providers.push(TicketProvider::Jira);
+    }
+    if config.linear_api_key().is_some() {
+        providers.push(TicketProvider::Linear);
+    }
+    providers
+}
+
+/// Fetches a ticket's title and status.
+///
+/// Returns `None` when the provider has no credentials configured.
+pub async fn fetch_metadata(
+    config: &TicketsConfig,
+    provider: TicketProvider,
+    ticket_key: &str,
+) -> Option<Result<TicketMetadata, String>> {
+    match provider {
+        TicketProvider::Jira => {
+            let (site, email, token) = config.jira()?;
+            Some(fetch_jira(site, email, token, ticket_key).await)
+        }
+        TicketProvider::Linear => {
+            let api_key = config.linear_api_key()?;
+            Some(fetch_linear(&config.linear_api_url, api_key, ticket_key).await)
+        }
+    }
+}
+
+async fn fetch_jira(
+    site: &str,
+    email: &str,
+    token: &str,
+    ticket_key: &str,
+) -> Result<TicketMetadata, String> {
+    let response = CLIENT
+        .get(format!("{}/rest/api/3/issue/{}", site, ticket_key))
+        .query(&[("fields", "summary,status")])
+        .basic_auth(email, Some(token))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Jira request failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Jira returned {} for {}", status, ticket_key));
+    }
+    let issue: JiraIssue = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Jira response: {}", e))?;
+    Ok(TicketMetadata {
+        title: issue.fields.summary,
+        status: issue.fields.status.name,
+        url: Some(format!("{}/browse/{}", site, ticket_key)),
+    })
+}
+
+async fn fetch_linear(
+    api_url: &str,
+    api_key: &str,
+    ticket_key: &str,
+) -> Result<TicketMetadata, String> {
+    let response = CLIENT
+        .post(api_url)
+        // Personal API keys are sent as-is, without a scheme
+        .header(reqwest::header::AUTHORIZATION, api_key)
+        .timeout(REQUEST_TIMEOUT)
+        .json(&json!({
+            "query": "query Issue($id: String!) { issue(id: $id) { title url state { name } } }",
+            "variables": { "id": ticket_key },
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Linear request failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Linear returned {} for {}", status, ticket_key));
+    }
+    let body: LinearResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Linear response: {}", e))?;
+    if let Some(error) = body.errors.first() {
+        return Err(format!("Linear: {}", error.message));
+    }
+    let issue = body
+        .data
+        .and_then(|d| d.issue)
+        .ok_or_else(|| format!("Linear issue {} not found", ticket_key))?;
+    Ok(TicketMetadata {
+        title: issue.title,
+        status: issue.state.name,
+        url: Some(issue.url),
+    })
+}
+
+/// Fetches a ticket's metadata and caches the outcome.
+///
+/// Returns the ticket unchanged when its provider is not configured.
+pub async fn refresh_ticket(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    ticket: Ticket,
+) -> mouchak_mail_core::Result<Ticket> {
+    let config = &mm.app_config.tickets;
+    match fetch_metadata(config, ticket.provider, &ticket.ticket_key).await {
+        Some(Ok(metadata)) => TicketBmc::update_metadata(ctx, mm, ticket.id, &metadata).await,
+        Some(Err(error)) => {
+            tracing::warn!(ticket = %ticket.ticket_key, "Ticket refresh failed: {}", error);
+            TicketBmc::record_fetch_error(ctx, mm, ticket.id, &error).await
+        }
+        None => Ok(ticket),
+    }
+}
+
+/// Refreshes linked tickets whose cached metadata is older than the
+/// configured interval.
+///
+/// # Returns
+/// Number of tickets fetched, successfully or not.
+pub async fn refresh_stale_tickets(mm: &ModelManager) -> mouchak_mail_core::Result<usize> {
+    let ctx = Ctx::root_ctx();
+    let config = &mm.app_config.tickets;
+    let stale = TicketBmc::list_stale(
+        &ctx,
+        mm,
+        &configured_providers(config),
+        config.refresh_interval_seconds,
+        REFRESH_BATCH,
+    )
+    .await?;
+    let count = stale.len();
+    for ticket in stale {
+        refresh_ticket(&ctx, mm, ticket).await?;
+    }
+    Ok(count)
+}
+
+/// Link a Jira or Linear ticket to a message.
+pub async fn link_ticket_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: LinkTicketParams,
+) -> Result<CallToolResult, McpError> {
+    let provider: TicketProvider = params
+        .provider
+        .parse()
+        .map_err(|e: mouchak_mail_core::Error| McpError::invalid_params(e.to_string(), None))?;
+    let ticket = TicketBmc::link(
+        ctx,
+        mm,
+        MessageId::new(params.message_id),
+        provider,
+        &params.ticket_key,
+    )
+    .await
+    .map_err(|e| match e {
+        mouchak_mail_core::Error::InvalidInput(_)
+        | mouchak_mail_core::Error::MessageNotFound(_) => {
+            McpError::invalid_params(e.to_string(), None)
+        }
+        _ => McpError::internal_error(e.to_string(), None),
+    })?;
+
+    // Fresh cache entries are shared with other messages linking the ticket
+    let interval =
+        i64::try_from(mm.app_config.tickets.refresh_interval_seconds).unwrap_or(i64::MAX);
+    let fresh = ticket
+        .fetched_ts
+        .is_some_and(|ts| (Utc::now().naive_utc() - ts).num_seconds() < interval);
+    let ticket = if fresh {
+        ticket
+    } else {
+        refresh_ticket(ctx, mm, ticket)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?
+    };
+
+    let mut output = format!(
+        "Linked {} to message {}",
+        ticket.summary(),
+        params.message_id
+    );
+    if let Some(url) = &ticket.url {
+        output.push_str(&format!("\nURL: {}", url));
+    }
+    if let Some(error) = &ticket.fetch_error {
+        output.push_str(&format!("\nMetadata fetch failed: {}", error));
+    } else if ticket.fetched_ts.is_none() {
+        output.push_str(&format!(
+            "\nNo {} credentials are configured, so title and status are not fetched",
+            ticket.provider
+        ));
+    }
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Formats linked tickets as a `Tickets:` line, or nothing when there are none.
+pub fn tickets_line(tickets: Option<&Vec<Ticket>>) -> String {
+    match tickets {
+        Some(tickets) if !tickets.is_empty() => format!(
+            "Tickets: {}\n",
+            tickets
+                .iter()
+                .map(Ticket::summary)
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,171 @@
+//! Thread workflow tool implementations
+//!
+//! Handles defining workflows, attaching them to threads, and advancing
+//! threads between states with handoff notifications.
+
+use mouchak_mail_core::{
+    Error,
+    ctx::Ctx,
+    model::{
+        ModelManager,
+        workflow::{
+            WorkflowBmc, WorkflowDefinition, WorkflowForCreate, WorkflowState, WorkflowTransition,
+        },
+    },
+    types::ProjectId,
+};
+use rmcp::{ErrorData as McpError, model::CallToolResult, model::Content};
+use serde::Serialize;
+use std::sync::Arc;
+
+use super::helpers;
+use super::{
+    AdvanceWorkflowParams, DefineWorkflowParams, GetWorkflowStatusParams, StartWorkflowParams,
+};
+
+fn to_json<T: Serialize>(value: &T) -> Result<CallToolResult, McpError> {
+    let json_str = serde_json::to_string_pretty(value)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// Maps a missing thread workflow to a hint instead of a bare "not found".
+fn workflow_error(e: Error, thread_id: &str) -> McpError {
+    match e {
+        Error::NotFound => McpError::invalid_params(
+            format!(
+                "Thread '{}' has no workflow. Attach one with start_workflow.",
+                thread_id
+            ),
+            None,
+        ),
+        e => McpError::invalid_params(e.to_string(), None),
+    }
+}
+
+/// Define or replace a workflow.
+pub async fn define_workflow_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: DefineWorkflowParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let transitions = params
+        .transitions
+        .iter()
+        .map(|t| WorkflowTransition::parse(t))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    let definition = WorkflowDefinition {
+        states: params
+            .states
+            .into_iter()
+            .map(|s| WorkflowState {
+                name: s.name,
+                responsible: s.responsible,
+            })
+            .collect(),
+        transitions,
+    };
+
+    WorkflowBmc::define(
+        ctx,
+        mm,
+        WorkflowForCreate {
+            project_id: project.id,
+            name: params.name.clone(),
+            definition,
+        },
+    )
+    .await
+    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+    let workflow = WorkflowBmc::get_by_name(ctx, mm, project.id, params.name.trim())
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    to_json(&workflow)
+}
+
+/// Attach a workflow to a thread.
+pub async fn start_workflow_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: StartWorkflowParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let handoff = match WorkflowBmc::start(
+        ctx,
+        mm,
+        project.id,
+        &params.thread_id,
+        &params.workflow,
+        agent.id,
+    )
+    .await
+    {
+        Ok(handoff) => handoff,
+        Err(Error::NotFound) => {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Workflow '{}' not found. Defined workflows: {}",
+                    params.workflow,
+                    defined_workflows(ctx, mm, project.id).await
+                ),
+                None,
+            ));
+        }
+        Err(e) => return Err(McpError::invalid_params(e.to_string(), None)),
+    };
+    to_json(&handoff)
+}
+
+/// Move a thread to another workflow state.
+pub async fn advance_workflow_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: AdvanceWorkflowParams,
+) -> Result<CallToolResult, McpError> {
+    let (project, agent) =
+        helpers::resolve_project_and_agent(ctx, mm, &params.project_slug, &params.agent_name)
+            .await?;
+
+    let handoff = WorkflowBmc::advance(
+        ctx,
+        mm,
+        project.id,
+        &params.thread_id,
+        &params.to_state,
+        agent.id,
+        params.note.as_deref(),
+    )
+    .await
+    .map_err(|e| workflow_error(e, &params.thread_id))?;
+    to_json(&handoff)
+}
+
+/// Get a thread's workflow state and history.
+pub async fn get_workflow_status_impl(
+    ctx: &Ctx,
+    mm: &Arc<ModelManager>,
+    params: GetWorkflowStatusParams,
+) -> Result<CallToolResult, McpError> {
+    let project = helpers::resolve_project(ctx, mm, &params.project_slug).await?;
+    let status = WorkflowBmc::status(ctx, mm, project.id, &params.thread_id)
+        .await
+        .map_err(|e| workflow_error(e, &params.thread_id))?;
+    to_json(&status)
+}
+
+async fn defined_workflows(ctx: &Ctx, mm: &Arc<ModelManager>, project_id: ProjectId) -> String {
+    match WorkflowBmc::list(ctx, mm, project_id).await {
+        Ok(workflows) if !workflows.is_empty() => workflows
+            .iter()
+            .map(|w| w.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => "none (use define_workflow)".to_string(),
+    }
+}
@@ -3,11 +3,12 @@
 use libsql::Builder;
 use mouchak_mail_common::config::AppConfig;
 use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 use mouchak_mail_core::model::{ModelManager, agent::AgentBmc, project::ProjectBmc};
 use mouchak_mail_mcp::tools::agent;
 use mouchak_mail_mcp::tools::{
-    CreateAgentIdentityParams, GetAgentProfileParams, ListAgentsParams, RegisterAgentParams,
-    UpdateAgentProfileParams, WhoisParams,
+    CreateAgentIdentityParams, GetAgentProfileParams, GetContextPackParams, ListAgentsParams,
+    RegisterAgentParams, UpdateAgentProfileParams, WhoisParams,
 };
 use std::sync::Arc;
 use tempfile::TempDir;
@@ -30,14 +31,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -320,6 +316,7 @@ async fn test_list_agents_impl_empty() {
 
     let params = ListAgentsParams {
         project_slug: project_slug.clone(),
+        ..Default::default()
     };
 
     let result = agent::list_agents_impl(&ctx, &mm, params).await;
@@ -350,6 +347,7 @@ async fn test_list_agents_impl_with_agents() {
 
     let params = ListAgentsParams {
         project_slug: project_slug.clone(),
+        ..Default::default()
     };
 
     let result = agent::list_agents_impl(&ctx, &mm, params).await;
@@ -508,6 +506,57 @@ async fn test_create_agent_identity_impl_avoids_existing() {
     assert!(!output.contains("BlueMountain") || output.contains("Alternatives"));
 }
 
+#[tokio::test]
+async fn test_list_agents_impl_paged_and_filtered() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    let project_slug = setup_project(&mm, "list_paged").await;
+
+    for (name, program) in [
+        ("AgentAlpha", "claude_code"),
+        ("AgentBeta", "codex"),
+        ("AgentGamma", "claude_code"),
+    ] {
+        let params = RegisterAgentParams {
+            project_slug: project_slug.clone(),
+            name: name.to_string(),
+            program: program.to_string(),
+            model: "opus".to_string(),
+            task_description: format!("Task for {}", name),
+        };
+        agent::register_agent_impl(&ctx, &mm, params).await.unwrap();
+    }
+
+    let params = ListAgentsParams {
+        project_slug: project_slug.clone(),
+        limit: Some(2),
+        ..Default::default()
+    };
+    let output = extract_text(&agent::list_agents_impl(&ctx, &mm, params).await.unwrap());
+    assert!(output.contains("(2 of 3)"));
+    assert!(output.contains("AgentBeta"));
+    assert!(!output.contains("AgentGamma"));
+    // The debug-formatted text escapes its quotes
+    assert!(output.contains(r#"pass cursor \"2\""#));
+
+    let params = ListAgentsParams {
+        project_slug: project_slug.clone(),
+        program: Some("codex".to_string()),
+        ..Default::default()
+    };
+    let output = extract_text(&agent::list_agents_impl(&ctx, &mm, params).await.unwrap());
+    assert!(output.contains("(1)"));
+    assert!(output.contains("AgentBeta"));
+
+    let params = ListAgentsParams {
+        project_slug,
+        sort: Some("loudest".to_string()),
+        ..Default::default()
+    };
+    assert!(agent::list_agents_impl(&ctx, &mm, params).await.is_err());
+}
+
 #[tokio::test]
 async fn test_list_agents_impl_invalid_project() {
     let (mm, _temp) = create_test_mm().await;
@@ -515,6 +564,7 @@ async fn test_list_agents_impl_invalid_project() {
 
     let params = ListAgentsParams {
         project_slug: "nonexistent_project".to_string(),
+        ..Default::default()
     };
 
     let result = agent::list_agents_impl(&ctx, &mm, params).await;
@@ -600,3 +650,65 @@ async fn test_register_agent_impl_invalid_project_slug() {
     let result = agent::register_agent_impl(&ctx, &mm, params).await;
     assert!(result.is_err());
 }
+
+#[tokio::test]
+async fn test_get_context_pack_impl_lists_urgent_unread() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    let project_slug = setup_project(&mm, "pack").await;
+    for name in ["PackLead", "PackWorker"] {
+        agent::register_agent_impl(
+            &ctx,
+            &mm,
+            RegisterAgentParams {
+                project_slug: project_slug.clone(),
+                name: name.to_string(),
+                program: "claude_code".to_string(),
+                model: "opus".to_string(),
+                task_description: "Context pack test".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let project = ProjectBmc::get_by_identifier(&ctx, &mm, &project_slug)
+        .await
+        .unwrap();
+    let lead = AgentBmc::get_by_name(&ctx, &mm, project.id, "PackLead")
+        .await
+        .unwrap();
+    let worker = AgentBmc::get_by_name(&ctx, &mm, project.id, "PackWorker")
+        .await
+        .unwrap();
+    MessageBmc::create(
+        &ctx,
+        &mm,
+        MessageForCreate {
+            project_id: project.id,
+            sender_id: lead.id,
+            recipient_ids: vec![worker.id],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Stop the deploy".to_string(),
+            body_md: "Rollback in progress.".to_string(),
+            thread_id: None,
+            importance: Some("urgent".to_string()),
+            ack_required: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    let params = GetContextPackParams {
+        project_slug,
+        agent_name: "PackWorker".to_string(),
+        budget_tokens: None,
+    };
+    let result = agent::get_context_pack_impl(&ctx, &mm, params).await;
+    let output = extract_text(&result.unwrap());
+    assert!(output.contains("Unread urgent (1)"));
+    assert!(output.contains("Awaiting your ack (1)"));
+    assert!(output.contains("Stop the deploy"));
+}
@@ -15,8 +15,8 @@ use mouchak_mail_core::model::{
     project::ProjectBmc,
 };
 use mouchak_mail_core::store::git_store;
-use mouchak_mail_mcp::tools::attachments;
-use mouchak_mail_mcp::tools::{AddAttachmentParams, GetAttachmentParams};
+use mouchak_mail_mcp::tools::{AddAttachmentParams, GetAttachmentParams, SearchMessagesParams};
+use mouchak_mail_mcp::tools::{attachments, messaging};
 use std::sync::Arc;
 use tempfile::TempDir;
 
@@ -32,14 +32,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -72,9 +67,9 @@ async fn setup_project_and_message(mm: &Arc<ModelManager>) -> (i64, i64, String)
     let receiver_id = AgentBmc::create(&ctx, mm, agent_c2).await.unwrap();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.get(),
-        sender_id: sender_id.get(),
-        recipient_ids: vec![receiver_id.get()],
+        project_id,
+        sender_id,
+        recipient_ids: vec![receiver_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Test message".to_string(),
@@ -85,7 +80,7 @@ async fn setup_project_and_message(mm: &Arc<ModelManager>) -> (i64, i64, String)
     };
     let message_id = MessageBmc::create(&ctx, mm, msg_c).await.unwrap();
 
-    (project_id.into(), message_id, project_slug)
+    (project_id.into(), message_id.get(), project_slug)
 }
 
 #[tokio::test]
@@ -112,6 +107,42 @@ async fn test_add_attachment_impl_success() {
     assert!(text.contains("test.txt"));
 }
 
+#[tokio::test]
+async fn test_add_attachment_indexes_text_for_search() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (_, message_id, project_slug) = setup_project_and_message(&mm).await;
+
+    let content = "thread 'main' panicked at wobbly_invariant_check";
+    let params = AddAttachmentParams {
+        project_slug: project_slug.clone(),
+        message_id,
+        filename: "panic.log".to_string(),
+        content_base64: base64::engine::general_purpose::STANDARD.encode(content),
+    };
+    let result = attachments::add_attachment_impl(&ctx, &mm, params).await;
+    let text = format!("{:?}", result.unwrap());
+    assert!(text.contains("indexed for search"));
+
+    let search = |include_attachments| SearchMessagesParams {
+        project_slug: project_slug.clone(),
+        query: "wobbly_invariant_check".to_string(),
+        limit: None,
+        context: None,
+        include_attachments,
+        fields: None,
+    };
+
+    let result = messaging::search_messages_impl(&ctx, &mm, search(None)).await;
+    assert!(format!("{:?}", result.unwrap()).contains("0 matches"));
+
+    let result = messaging::search_messages_impl(&ctx, &mm, search(Some(true))).await;
+    let text = format!("{:?}", result.unwrap());
+    assert!(text.contains("1 matches"));
+    assert!(text.contains("attachment panic.log"));
+    assert!(text.contains("**wobbly_invariant_check**"));
+}
+
 #[tokio::test]
 async fn test_add_attachment_impl_invalid_project() {
     let (mm, _temp) = create_test_mm().await;
@@ -193,6 +224,50 @@ async fn test_add_attachment_impl_json_file() {
     assert!(result.is_ok());
 }
 
+#[tokio::test]
+async fn test_get_attachment_impl_reports_content_hash() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (_, message_id, project_slug) = setup_project_and_message(&mm).await;
+
+    let params = AddAttachmentParams {
+        project_slug: project_slug.clone(),
+        message_id,
+        filename: "abc.txt".to_string(),
+        content_base64: base64::engine::general_purpose::STANDARD.encode("abc"),
+    };
+    let added = format!(
+        "{:?}",
+        attachments::add_attachment_impl(&ctx, &mm, params)
+            .await
+            .unwrap()
+    );
+    let attachment_id: String = added
+        .split("added with ID ")
+        .nth(1)
+        .unwrap()
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+
+    let params = GetAttachmentParams {
+        project_slug,
+        attachment_id,
+        filename: "abc.txt".to_string(),
+    };
+    let text = format!(
+        "{:?}",
+        attachments::get_attachment_impl(&ctx, &mm, params)
+            .await
+            .unwrap()
+    );
+    assert!(
+        text.contains("BLAKE3: 6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"),
+        "Missing hash in {}",
+        text
+    );
+}
+
 #[tokio::test]
 async fn test_get_attachment_impl_not_found() {
     let (mm, _temp) = create_test_mm().await;
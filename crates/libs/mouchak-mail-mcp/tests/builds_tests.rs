@@ -34,14 +34,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -7,6 +7,9 @@ use mouchak_mail_core::model::{
     ModelManager,
     agent::{AgentBmc, AgentForCreate},
     project::ProjectBmc,
+    project_contact_policy::{
+        ContactRuleAction, ContactRuleTarget, ProjectContactPolicyBmc, ProjectContactRuleForCreate,
+    },
 };
 use mouchak_mail_mcp::tools::contacts;
 use mouchak_mail_mcp::tools::{
@@ -34,14 +37,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -82,6 +80,20 @@ async fn setup_two_projects_with_agents(
     };
     AgentBmc::create(&ctx, mm, agent2_c).await.unwrap();
 
+    // Cross-project contact is denied unless the target project allows it
+    ProjectContactPolicyBmc::set_rule(
+        &ctx,
+        mm,
+        ProjectContactRuleForCreate {
+            project_id: project2_id,
+            action: ContactRuleAction::Allow,
+            target_kind: ContactRuleTarget::Project,
+            target: project1_slug.clone(),
+        },
+    )
+    .await
+    .unwrap();
+
     (
         project1_slug,
         "alice".to_string(),
@@ -114,6 +126,29 @@ async fn test_request_contact_impl_success() {
     assert!(output.contains("pending"));
 }
 
+#[tokio::test]
+async fn test_request_contact_impl_denied_without_allow_rule() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    let (proj1_slug, agent1_name, proj2_slug, agent2_name) =
+        setup_two_projects_with_agents(&mm, "denied").await;
+
+    // Project 1 has no rule allowing project 2, so the reverse direction is denied
+    let params = RequestContactParams {
+        from_project_slug: proj2_slug,
+        from_agent_name: agent2_name,
+        to_project_slug: proj1_slug,
+        to_agent_name: agent1_name,
+        reason: "Unsolicited".to_string(),
+    };
+
+    let err = contacts::request_contact_impl(&ctx, &mm, params)
+        .await
+        .unwrap_err();
+    assert!(err.message.contains("does not allow contact"));
+}
+
 #[tokio::test]
 async fn test_request_contact_impl_project_not_found() {
     let (mm, _temp) = create_test_mm().await;
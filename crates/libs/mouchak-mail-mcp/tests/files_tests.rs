@@ -13,7 +13,7 @@ use mouchak_mail_mcp::tools::files;
 use mouchak_mail_mcp::tools::{
     FileReservationParams, FileReservationPathsParams, ForceReleaseReservationParams,
     ListReservationsParams, ReleaseFileReservationsByAgentParams, ReleaseReservationParams,
-    RenewFileReservationParams, RenewFileReservationsByAgentParams,
+    RenewFileReservationParams, RenewFileReservationsByAgentParams, WaitForPathParams,
 };
 use std::sync::Arc;
 use tempfile::TempDir;
@@ -36,14 +36,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -97,6 +92,7 @@ async fn test_reserve_file_impl_success() {
         exclusive: Some(true),
         reason: Some("Working on source files".to_string()),
         ttl_seconds: Some(3600),
+        notify_on_release: None,
     };
 
     let result = files::reserve_file_impl(&ctx, &mm, params).await;
@@ -122,6 +118,7 @@ async fn test_reserve_file_impl_invalid_path() {
         exclusive: Some(true),
         reason: None,
         ttl_seconds: None,
+        notify_on_release: None,
     };
 
     let result = files::reserve_file_impl(&ctx, &mm, params).await;
@@ -160,6 +157,7 @@ async fn test_reserve_file_impl_agent_without_capability() {
         exclusive: Some(true),
         reason: None,
         ttl_seconds: None,
+        notify_on_release: None,
     };
 
     let result = files::reserve_file_impl(&ctx, &mm, params).await;
@@ -206,6 +204,7 @@ async fn test_list_reservations_impl_with_reservations() {
         exclusive: Some(true),
         reason: Some("Editing cargo manifest".to_string()),
         ttl_seconds: Some(3600),
+        notify_on_release: None,
     };
     files::reserve_file_impl(&ctx, &mm, reserve_params)
         .await
@@ -239,6 +238,7 @@ async fn test_release_reservation_impl_success() {
         exclusive: Some(true),
         reason: None,
         ttl_seconds: Some(3600),
+        notify_on_release: None,
     };
     let reserve_result = files::reserve_file_impl(&ctx, &mm, reserve_params)
         .await
@@ -261,6 +261,73 @@ async fn test_release_reservation_impl_success() {
     assert!(output.contains("Released reservation"));
 }
 
+#[tokio::test]
+async fn test_reserve_file_notify_on_release_and_wait_for_path() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    let (project_slug, agent_name) = setup_project_with_agent(&mm, "watch").await;
+    let project = ProjectBmc::get_by_slug(&ctx, &mm, &project_slug)
+        .await
+        .unwrap();
+    AgentBmc::create(
+        &ctx,
+        &mm,
+        AgentForCreate {
+            project_id: project.id,
+            name: "waiting_agent".to_string(),
+            program: "claude".to_string(),
+            model: "opus".to_string(),
+            task_description: "Blocked on a path".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let reserve_params = FileReservationParams {
+        project_slug: project_slug.clone(),
+        agent_name,
+        path_pattern: "src/api/**".to_string(),
+        exclusive: Some(true),
+        reason: None,
+        ttl_seconds: Some(3600),
+        notify_on_release: Some("waiting_agent".to_string()),
+    };
+    let reserve_result = files::reserve_file_impl(&ctx, &mm, reserve_params)
+        .await
+        .unwrap();
+    let output = extract_text(&reserve_result);
+    assert!(output.contains("1 agent(s) will be notified on release"));
+    let reservation_id: i64 = output
+        .split("reservation id:")
+        .nth(1)
+        .and_then(|s| s.split(',').next())
+        .and_then(|s| s.trim().parse().ok())
+        .expect("Should extract reservation id");
+
+    let wait_params = || WaitForPathParams {
+        project_slug: project_slug.clone(),
+        agent_name: "waiting_agent".to_string(),
+        path: "src/api/routes.rs".to_string(),
+        timeout_seconds: Some(0),
+        watch: Some(false),
+    };
+
+    let blocked = files::wait_for_path_impl(&ctx, &mm, wait_params())
+        .await
+        .unwrap();
+    assert!(extract_text(&blocked).contains("still reserved"));
+
+    files::release_reservation_impl(&ctx, &mm, ReleaseReservationParams { reservation_id })
+        .await
+        .unwrap();
+
+    let free = files::wait_for_path_impl(&ctx, &mm, wait_params())
+        .await
+        .unwrap();
+    assert!(extract_text(&free).contains("is available"));
+}
+
 #[tokio::test]
 async fn test_force_release_reservation_impl_success() {
     let (mm, _temp) = create_test_mm().await;
@@ -275,6 +342,7 @@ async fn test_force_release_reservation_impl_success() {
         exclusive: Some(true),
         reason: Some("This will be force released".to_string()),
         ttl_seconds: Some(7200),
+        notify_on_release: None,
     };
     let reserve_result = files::reserve_file_impl(&ctx, &mm, reserve_params)
         .await
@@ -311,6 +379,7 @@ async fn test_renew_file_reservation_impl_success() {
         exclusive: Some(true),
         reason: None,
         ttl_seconds: Some(1800),
+        notify_on_release: None,
     };
     let reserve_result = files::reserve_file_impl(&ctx, &mm, reserve_params)
         .await
@@ -1,3 +1,7 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use libsql::Builder;
+use mouchak_mail_common::config::AppConfig;
 use mouchak_mail_core::model::{
     ModelManager,
     agent::{AgentBmc, AgentForCreate},
@@ -8,17 +12,30 @@ use mouchak_mail_core::model::{
 use mouchak_mail_mcp::tools::MouchakMailService;
 use rmcp::model::ReadResourceRequestParam;
 use std::sync::Arc;
+use tempfile::TempDir;
 use uuid::Uuid;
 
+async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let db_path = temp_dir.path().join("test.db");
+    let archive_root = temp_dir.path().join("archive");
+    std::fs::create_dir_all(&archive_root).unwrap();
+
+    let db = Builder::new_local(&db_path).build().await.unwrap();
+    let conn = db.connect().unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
+
+    let app_config = Arc::new(AppConfig::default());
+    let mm = ModelManager::new_for_test(conn, archive_root, app_config);
+    (Arc::new(mm), temp_dir)
+}
+
 #[tokio::test]
 async fn test_mcp_resource_api_schemes() -> anyhow::Result<()> {
     // 1. Setup Data
-    let mm = Arc::new(
-        ModelManager::new(std::sync::Arc::new(
-            mouchak_mail_common::config::AppConfig::default(),
-        ))
-        .await?,
-    );
+    let (mm, _temp_dir) = create_test_mm().await;
     let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
 
     // Create Project
@@ -45,9 +62,9 @@ async fn test_mcp_resource_api_schemes() -> anyhow::Result<()> {
         &ctx,
         &mm,
         MessageForCreate {
-            project_id: project_id.into(),
-            sender_id: agent_id.into(),
-            recipient_ids: vec![agent_id.into()],
+            project_id,
+            sender_id: agent_id,
+            recipient_ids: vec![agent_id],
             cc_ids: None,
             bcc_ids: None,
             subject: "Lazy Load Subject".to_string(),
@@ -18,12 +18,14 @@ use mouchak_mail_core::model::{
     message::{MessageBmc, MessageForCreate},
     project::ProjectBmc,
 };
-use mouchak_mail_mcp::tools::messaging;
+use mouchak_mail_core::types::{AgentId, MessageId};
 use mouchak_mail_mcp::tools::{
-    AcknowledgeMessageParams, GetMessageParams, GetThreadParams, ListInboxParams,
-    ListThreadsParams, MarkMessageReadParams, ReplyMessageParams, SearchMessagesParams,
-    SendMessageParams,
+    AcknowledgeMessageParams, GetBroadcastStatusParams, GetMessageParams, GetThreadParams,
+    GetThreadTreeParams, ListDeadLettersParams, ListInboxParams, ListThreadsParams,
+    MarkMessageReadParams, ReplyMessageParams, RequeueDeadLetterParams, SearchMessagesParams,
+    SendMessageParams, SetFocusWindowParams,
 };
+use mouchak_mail_mcp::tools::{budget, messaging, project};
 use std::sync::Arc;
 use tempfile::TempDir;
 
@@ -37,14 +39,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -136,9 +133,13 @@ async fn test_send_message_impl_success() {
         bcc: None,
         subject: "Test Subject".to_string(),
         body_md: "This is a test message body.".to_string(),
+        body_format: None,
         thread_id: Some("THREAD-001".to_string()),
         importance: Some("high".to_string()),
         ack_required: Some(true),
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
     };
 
     let result = messaging::send_message_impl(&ctx, &mm, params).await;
@@ -150,6 +151,94 @@ async fn test_send_message_impl_success() {
     assert!(text.contains("Test Subject"));
 }
 
+#[tokio::test]
+async fn test_send_message_impl_respond_by_clock_skew() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (_, _, _, project_slug) = setup_project_and_agents(&mm).await;
+
+    let send = |respond_by: chrono::DateTime<chrono::Utc>| SendMessageParams {
+        project_slug: project_slug.clone(),
+        sender_name: "sender_agent".to_string(),
+        to: "receiver_agent".to_string(),
+        cc: None,
+        bcc: None,
+        subject: "Deadline".to_string(),
+        body_md: "Reply soon.".to_string(),
+        body_format: None,
+        thread_id: None,
+        importance: None,
+        ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: Some(respond_by.to_rfc3339()),
+    };
+
+    // A sender clock a minute behind: accepted, with a warning
+    let now = chrono::Utc::now();
+    let result = messaging::send_message_impl(&ctx, &mm, send(now - chrono::Duration::seconds(60)))
+        .await
+        .expect("slightly skewed deadline should be adjusted");
+    let text = format!("{:?}", result);
+    assert!(text.contains("Reply requested by"));
+    assert!(text.contains("likely clock skew"));
+
+    // An hour in the past is no skew; the error names the server time
+    let err = messaging::send_message_impl(&ctx, &mm, send(now - chrono::Duration::hours(1)))
+        .await
+        .expect_err("past deadline should be rejected");
+    assert!(err.message.contains("server time is"));
+}
+
+#[tokio::test]
+async fn test_send_message_impl_capability_address() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (_, _, _, project_slug) = setup_project_and_agents(&mm).await;
+
+    let params = SendMessageParams {
+        project_slug: project_slug.clone(),
+        sender_name: "sender_agent".to_string(),
+        to: "capability:fetch_inbox".to_string(),
+        cc: None,
+        bcc: None,
+        subject: "Routed by capability".to_string(),
+        body_md: "Whoever reads inboxes.".to_string(),
+        body_format: None,
+        thread_id: None,
+        importance: None,
+        ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
+    };
+
+    let result = messaging::send_message_impl(&ctx, &mm, params).await;
+    let text = format!("{:?}", result.expect("capability send should succeed"));
+    assert!(text.contains("Routed 'capability:fetch_inbox' (all) to: receiver_agent"));
+
+    let params = SendMessageParams {
+        project_slug,
+        sender_name: "sender_agent".to_string(),
+        to: "capability:db-migrations".to_string(),
+        cc: None,
+        bcc: None,
+        subject: "Nobody".to_string(),
+        body_md: "No capable agent.".to_string(),
+        body_format: None,
+        thread_id: None,
+        importance: None,
+        ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
+    };
+    let err = messaging::send_message_impl(&ctx, &mm, params)
+        .await
+        .expect_err("unknown capability should fail");
+    assert!(err.message.contains("db-migrations"));
+}
+
 #[tokio::test]
 async fn test_send_message_impl_with_cc_bcc() {
     let (mm, _temp) = create_test_mm().await;
@@ -182,9 +271,13 @@ async fn test_send_message_impl_with_cc_bcc() {
         bcc: Some("bcc_agent".to_string()),
         subject: "CC/BCC Test".to_string(),
         body_md: "Testing CC and BCC.".to_string(),
+        body_format: None,
         thread_id: None,
         importance: None,
         ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
     };
 
     let result = messaging::send_message_impl(&ctx, &mm, params).await;
@@ -214,9 +307,13 @@ async fn test_send_message_impl_without_capability() {
         bcc: None,
         subject: "Should Fail".to_string(),
         body_md: "This should fail.".to_string(),
+        body_format: None,
         thread_id: None,
         importance: None,
         ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
     };
 
     let result = messaging::send_message_impl(&ctx, &mm, params).await;
@@ -241,15 +338,109 @@ async fn test_send_message_impl_invalid_project() {
         bcc: None,
         subject: "Test".to_string(),
         body_md: "Test".to_string(),
+        body_format: None,
         thread_id: None,
         importance: None,
         ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
     };
 
     let result = messaging::send_message_impl(&ctx, &mm, params).await;
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn test_send_to_deleted_agent_is_dead_lettered() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (project_id, _, _, project_slug) = setup_project_and_agents(&mm).await;
+    let retired = AgentBmc::create(
+        &ctx,
+        &mm,
+        AgentForCreate {
+            project_id: project_id.into(),
+            name: "retired_agent".to_string(),
+            program: "claude".to_string(),
+            model: "opus".to_string(),
+            task_description: "Leaves".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    AgentBmc::delete(&ctx, &mm, retired).await.unwrap();
+
+    let params = SendMessageParams {
+        project_slug: project_slug.clone(),
+        sender_name: "sender_agent".to_string(),
+        to: "retired_agent".to_string(),
+        cc: None,
+        bcc: None,
+        subject: "Hand-off".to_string(),
+        body_md: "Notes for whoever takes over".to_string(),
+        body_format: None,
+        thread_id: None,
+        importance: None,
+        ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
+    };
+    let err = messaging::send_message_impl(&ctx, &mm, params)
+        .await
+        .unwrap_err();
+    assert!(err.message.contains("dead letter"));
+    let dead_letter_id = err.data.unwrap()["dead_letter_id"].as_i64().unwrap();
+
+    let listed = messaging::list_dead_letters_impl(
+        &ctx,
+        &mm,
+        ListDeadLettersParams {
+            project_slug: Some(project_slug.clone()),
+            include_requeued: None,
+            limit: None,
+        },
+    )
+    .await
+    .unwrap();
+    let text = format!("{:?}", listed);
+    assert!(text.contains("Hand-off"));
+    assert!(text.contains("retired_agent"));
+
+    let requeued = messaging::requeue_dead_letter_impl(
+        &ctx,
+        &mm,
+        RequeueDeadLetterParams {
+            dead_letter_id,
+            project_slug: None,
+            sender_name: None,
+            to: Some("receiver_agent".to_string()),
+            cc: None,
+            bcc: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(format!("{:?}", requeued).contains("delivered as message"));
+
+    // Delivered once only
+    let again = messaging::requeue_dead_letter_impl(
+        &ctx,
+        &mm,
+        RequeueDeadLetterParams {
+            dead_letter_id,
+            project_slug: None,
+            sender_name: None,
+            to: None,
+            cc: None,
+            bcc: None,
+        },
+    )
+    .await;
+    assert!(again.is_err());
+}
+
 #[tokio::test]
 async fn test_list_inbox_impl_success() {
     let (mm, _temp) = create_test_mm().await;
@@ -257,9 +448,9 @@ async fn test_list_inbox_impl_success() {
     let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Inbox Test".to_string(),
@@ -274,9 +465,11 @@ async fn test_list_inbox_impl_success() {
         project_slug: project_slug.clone(),
         agent_name: "receiver_agent".to_string(),
         limit: Some(10),
+        cursor: None,
         urgent_only: None,
         since_ts: None,
         include_bodies: None,
+        format: None,
     };
 
     let result = messaging::list_inbox_impl(&ctx, &mm, params).await;
@@ -287,6 +480,147 @@ async fn test_list_inbox_impl_success() {
     assert!(text.contains("Inbox Test"));
 }
 
+#[tokio::test]
+async fn test_list_inbox_impl_annotates_truncated_results() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
+
+    for i in 0..3 {
+        let msg_c = MessageForCreate {
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(receiver_id)],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: format!("Budget {}", i),
+            body_md: "body".to_string(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        };
+        MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+    }
+
+    let params = ListInboxParams {
+        project_slug: project_slug.clone(),
+        agent_name: "receiver_agent".to_string(),
+        limit: Some(2),
+        cursor: None,
+        urgent_only: None,
+        since_ts: None,
+        include_bodies: None,
+        format: None,
+    };
+
+    let result = messaging::list_inbox_impl(&ctx, &mm, params).await.unwrap();
+    let meta = &result.meta.as_ref().expect("budget meta").0[budget::META_KEY];
+    assert_eq!(meta["returned"], 2);
+    assert_eq!(meta["limit"], 2);
+    assert_eq!(meta["truncated"], true);
+    assert!(meta["bytes"].as_u64().unwrap() > 0);
+
+    let text = format!("{:?}", result);
+    assert!(text.contains("[budget] Returned 2 of limit 2"));
+    assert!(text.contains("search_messages with specific terms"));
+}
+
+#[tokio::test]
+async fn test_focus_window_defers_low_priority_mail() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (_, _, _, project_slug) = setup_project_and_agents(&mm).await;
+
+    let focus = project::set_focus_window_impl(
+        &ctx,
+        &mm,
+        SetFocusWindowParams {
+            project_slug: project_slug.clone(),
+            duration_minutes: Some(30),
+            allowed_importance: None,
+            reason: Some("release".to_string()),
+        },
+    )
+    .await
+    .unwrap();
+    assert!(format!("{:?}", focus).contains("Started focus window"));
+
+    let send = |subject: &str, importance: &str| SendMessageParams {
+        project_slug: project_slug.clone(),
+        sender_name: "sender_agent".to_string(),
+        to: "receiver_agent".to_string(),
+        cc: None,
+        bcc: None,
+        subject: subject.to_string(),
+        body_md: "body".to_string(),
+        body_format: None,
+        thread_id: None,
+        importance: Some(importance.to_string()),
+        ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
+    };
+    messaging::send_message_impl(&ctx, &mm, send("Routine note", "normal"))
+        .await
+        .unwrap();
+    messaging::send_message_impl(&ctx, &mm, send("Build broken", "urgent"))
+        .await
+        .unwrap();
+
+    let inbox = || ListInboxParams {
+        project_slug: project_slug.clone(),
+        agent_name: "receiver_agent".to_string(),
+        limit: Some(10),
+        cursor: None,
+        urgent_only: None,
+        since_ts: None,
+        include_bodies: None,
+        format: None,
+    };
+    let text = format!(
+        "{:?}",
+        messaging::list_inbox_impl(&ctx, &mm, inbox())
+            .await
+            .unwrap()
+    );
+    assert!(text.contains("Build broken"));
+    assert!(!text.contains("Routine note"));
+
+    let info = project::get_project_info_impl(
+        &ctx,
+        &mm,
+        mouchak_mail_mcp::tools::GetProjectInfoParams {
+            project_slug: project_slug.clone(),
+        },
+    )
+    .await
+    .unwrap();
+    assert!(format!("{:?}", info).contains("deferred: 1"));
+
+    let ended = project::set_focus_window_impl(
+        &ctx,
+        &mm,
+        SetFocusWindowParams {
+            project_slug: project_slug.clone(),
+            duration_minutes: Some(0),
+            allowed_importance: None,
+            reason: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(format!("{:?}", ended).contains("delivered 1"));
+
+    let text = format!(
+        "{:?}",
+        messaging::list_inbox_impl(&ctx, &mm, inbox())
+            .await
+            .unwrap()
+    );
+    assert!(text.contains("Routine note"));
+}
+
 #[tokio::test]
 async fn test_list_inbox_impl_empty() {
     let (mm, _temp) = create_test_mm().await;
@@ -297,9 +631,11 @@ async fn test_list_inbox_impl_empty() {
         project_slug: project_slug.clone(),
         agent_name: "receiver_agent".to_string(),
         limit: Some(10),
+        cursor: None,
         urgent_only: None,
         since_ts: None,
         include_bodies: None,
+        format: None,
     };
 
     let result = messaging::list_inbox_impl(&ctx, &mm, params).await;
@@ -319,9 +655,11 @@ async fn test_list_inbox_impl_without_capability() {
         project_slug: project_slug.clone(),
         agent_name: "sender_agent".to_string(),
         limit: None,
+        cursor: None,
         urgent_only: None,
         since_ts: None,
         include_bodies: None,
+        format: None,
     };
 
     let result = messaging::list_inbox_impl(&ctx, &mm, params).await;
@@ -340,9 +678,9 @@ async fn test_get_message_impl_success() {
     let (project_id, sender_id, receiver_id, _) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Get Message Test".to_string(),
@@ -353,7 +691,11 @@ async fn test_get_message_impl_success() {
     };
     let msg_id = MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
 
-    let params = GetMessageParams { message_id: msg_id };
+    let params = GetMessageParams {
+        message_id: msg_id.get(),
+        format: None,
+        full: None,
+    };
 
     let result = messaging::get_message_impl(&ctx, &mm, params).await;
     assert!(result.is_ok());
@@ -369,7 +711,11 @@ async fn test_get_message_impl_not_found() {
     let (mm, _temp) = create_test_mm().await;
     let ctx = Ctx::root_ctx();
 
-    let params = GetMessageParams { message_id: 999999 };
+    let params = GetMessageParams {
+        message_id: 999999,
+        format: None,
+        full: None,
+    };
 
     let result = messaging::get_message_impl(&ctx, &mm, params).await;
     assert!(result.is_err());
@@ -377,6 +723,96 @@ async fn test_get_message_impl_not_found() {
     assert!(err.message.contains("Message not found"));
 }
 
+#[tokio::test]
+async fn test_body_format_is_stored_and_rendered() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (project_id, _, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
+
+    let send = |subject: &str, body: &str, body_format: Option<&str>| SendMessageParams {
+        project_slug: project_slug.clone(),
+        sender_name: "sender_agent".to_string(),
+        to: "receiver_agent".to_string(),
+        cc: None,
+        bcc: None,
+        subject: subject.to_string(),
+        body_md: body.to_string(),
+        body_format: body_format.map(String::from),
+        thread_id: None,
+        importance: None,
+        ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
+    };
+    messaging::send_message_impl(&ctx, &mm, send("Status", "Build is **green**", None))
+        .await
+        .unwrap();
+    messaging::send_message_impl(&ctx, &mm, send("Report", "{\"ok\":true}", Some("json")))
+        .await
+        .unwrap();
+
+    let err = messaging::send_message_impl(&ctx, &mm, send("Bad", "{oops", Some("json")))
+        .await
+        .expect_err("invalid JSON body should be rejected");
+    assert!(err.message.contains("not valid JSON"));
+
+    let inbox =
+        MessageBmc::list_inbox_for_agent(&ctx, &mm, project_id.into(), receiver_id.into(), 10)
+            .await
+            .unwrap();
+    let report = inbox.iter().find(|m| m.subject == "Report").unwrap();
+    let status = inbox.iter().find(|m| m.subject == "Status").unwrap();
+    assert_eq!(
+        MessageBmc::get_body_format(&ctx, &mm, MessageId::new(report.id))
+            .await
+            .unwrap(),
+        mouchak_mail_core::utils::body_format::BodyFormat::Json
+    );
+
+    let params = GetMessageParams {
+        message_id: report.id,
+        format: Some("html".to_string()),
+        full: None,
+    };
+    let text = format!(
+        "{:?}",
+        messaging::get_message_impl(&ctx, &mm, params)
+            .await
+            .unwrap()
+    );
+    assert!(text.contains("language-json"));
+
+    let params = ListInboxParams {
+        project_slug: project_slug.clone(),
+        agent_name: "receiver_agent".to_string(),
+        limit: Some(10),
+        cursor: None,
+        urgent_only: None,
+        since_ts: None,
+        include_bodies: None,
+        format: Some("plain".to_string()),
+    };
+    let text = format!(
+        "{:?}",
+        messaging::list_inbox_impl(&ctx, &mm, params).await.unwrap()
+    );
+    assert!(text.contains(&format!("[{}] Status", status.id)));
+    assert!(text.contains("    Build is green"));
+    assert!(!text.contains("**green**"));
+
+    let params = GetMessageParams {
+        message_id: status.id,
+        format: Some("pdf".to_string()),
+        full: None,
+    };
+    assert!(
+        messaging::get_message_impl(&ctx, &mm, params)
+            .await
+            .is_err()
+    );
+}
+
 #[tokio::test]
 async fn test_search_messages_impl_success() {
     let (mm, _temp) = create_test_mm().await;
@@ -384,9 +820,9 @@ async fn test_search_messages_impl_success() {
     let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Searchable Subject".to_string(),
@@ -401,6 +837,9 @@ async fn test_search_messages_impl_success() {
         project_slug: project_slug.clone(),
         query: "unique_keyword_xyz".to_string(),
         limit: Some(10),
+        context: None,
+        include_attachments: None,
+        fields: None,
     };
 
     let result = messaging::search_messages_impl(&ctx, &mm, params).await;
@@ -420,6 +859,9 @@ async fn test_search_messages_impl_no_results() {
         project_slug: project_slug.clone(),
         query: "nonexistent_term_abcxyz".to_string(),
         limit: None,
+        context: None,
+        include_attachments: None,
+        fields: None,
     };
 
     let result = messaging::search_messages_impl(&ctx, &mm, params).await;
@@ -429,6 +871,51 @@ async fn test_search_messages_impl_no_results() {
     assert!(text.contains("0 matches"));
 }
 
+#[tokio::test]
+async fn test_search_messages_impl_snippets_and_context() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
+
+    let bodies = [
+        "Kicking off the rollout plan.",
+        "Staging looks fine but the migration_zeta step timed out twice.",
+        "Retrying with a larger timeout.",
+    ];
+    for body in bodies {
+        let msg_c = MessageForCreate {
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(receiver_id)],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Rollout".to_string(),
+            body_md: body.to_string(),
+            thread_id: Some("rollout-thread".to_string()),
+            importance: None,
+            ack_required: false,
+        };
+        MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+    }
+
+    let params = SearchMessagesParams {
+        project_slug,
+        query: "migration_zeta".to_string(),
+        limit: None,
+        context: Some(1),
+        include_attachments: None,
+        fields: None,
+    };
+
+    let result = messaging::search_messages_impl(&ctx, &mm, params).await;
+    let text = format!("{:?}", result.unwrap());
+    assert!(text.contains("**migration_zeta**"));
+    assert!(text.contains("matches: [27..41]"));
+    assert!(text.contains("before"));
+    assert!(text.contains("Kicking off the rollout plan."));
+    assert!(text.contains("Retrying with a larger timeout."));
+}
+
 #[tokio::test]
 async fn test_get_thread_impl_success() {
     let (mm, _temp) = create_test_mm().await;
@@ -437,9 +924,9 @@ async fn test_get_thread_impl_success() {
 
     for i in 1..=3 {
         let msg_c = MessageForCreate {
-            project_id,
-            sender_id,
-            recipient_ids: vec![receiver_id],
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(receiver_id)],
             cc_ids: None,
             bcc_ids: None,
             subject: format!("Thread Message {}", i),
@@ -454,6 +941,7 @@ async fn test_get_thread_impl_success() {
     let params = GetThreadParams {
         project_slug: project_slug.clone(),
         thread_id: "THREAD-TEST".to_string(),
+        ..Default::default()
     };
 
     let result = messaging::get_thread_impl(&ctx, &mm, params).await;
@@ -464,6 +952,87 @@ async fn test_get_thread_impl_success() {
     assert!(text.contains("3 messages"));
 }
 
+#[tokio::test]
+async fn test_thread_unread_tracking() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
+
+    let send = |i: i32| MessageForCreate {
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: format!("Unread Message {}", i),
+        body_md: format!("Body number {}.", i),
+        thread_id: Some("THREAD-UNREAD".to_string()),
+        importance: None,
+        ack_required: false,
+    };
+    for i in 1..=2 {
+        MessageBmc::create(&ctx, &mm, send(i)).await.unwrap();
+    }
+
+    let list = |agent_name: &str| ListThreadsParams {
+        project_slug: project_slug.clone(),
+        agent_name: Some(agent_name.to_string()),
+        ..Default::default()
+    };
+    let text = format!(
+        "{:?}",
+        messaging::list_threads_impl(&ctx, &mm, list("receiver_agent"))
+            .await
+            .unwrap()
+    );
+    assert!(text.contains("2 unread"));
+
+    // Reading the thread as an agent marks it read
+    let params = GetThreadParams {
+        project_slug: project_slug.clone(),
+        thread_id: "THREAD-UNREAD".to_string(),
+        agent_name: Some("receiver_agent".to_string()),
+        unread_only: true,
+    };
+    let text = format!(
+        "{:?}",
+        messaging::get_thread_impl(&ctx, &mm, params).await.unwrap()
+    );
+    assert!(text.contains("2 new messages since last read"));
+    let text = format!(
+        "{:?}",
+        messaging::list_threads_impl(&ctx, &mm, list("receiver_agent"))
+            .await
+            .unwrap()
+    );
+    assert!(text.contains("0 unread"));
+
+    // Only the new message comes back next time
+    MessageBmc::create(&ctx, &mm, send(3)).await.unwrap();
+    let params = GetThreadParams {
+        project_slug: project_slug.clone(),
+        thread_id: "THREAD-UNREAD".to_string(),
+        agent_name: Some("receiver_agent".to_string()),
+        unread_only: true,
+    };
+    let text = format!(
+        "{:?}",
+        messaging::get_thread_impl(&ctx, &mm, params).await.unwrap()
+    );
+    assert!(text.contains("1 new messages since last read"));
+    assert!(text.contains("Body number 3."));
+    assert!(!text.contains("Body number 1."));
+
+    // unread_only needs an agent
+    let params = GetThreadParams {
+        project_slug,
+        thread_id: "THREAD-UNREAD".to_string(),
+        unread_only: true,
+        ..Default::default()
+    };
+    assert!(messaging::get_thread_impl(&ctx, &mm, params).await.is_err());
+}
+
 #[tokio::test]
 async fn test_get_thread_impl_empty() {
     let (mm, _temp) = create_test_mm().await;
@@ -473,6 +1042,7 @@ async fn test_get_thread_impl_empty() {
     let params = GetThreadParams {
         project_slug: project_slug.clone(),
         thread_id: "NONEXISTENT-THREAD".to_string(),
+        ..Default::default()
     };
 
     let result = messaging::get_thread_impl(&ctx, &mm, params).await;
@@ -489,9 +1059,9 @@ async fn test_reply_message_impl_success() {
     let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Original Message".to_string(),
@@ -505,7 +1075,7 @@ async fn test_reply_message_impl_success() {
     let params = ReplyMessageParams {
         project_slug: project_slug.clone(),
         sender_name: "receiver_agent".to_string(),
-        message_id: original_msg_id,
+        message_id: original_msg_id.get(),
         body_md: "This is my reply.".to_string(),
         importance: Some("normal".to_string()),
         to: None,
@@ -529,9 +1099,9 @@ async fn test_reply_message_impl_no_thread_id() {
     let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "No Thread Message".to_string(),
@@ -545,7 +1115,7 @@ async fn test_reply_message_impl_no_thread_id() {
     let params = ReplyMessageParams {
         project_slug: project_slug.clone(),
         sender_name: "receiver_agent".to_string(),
-        message_id: original_msg_id,
+        message_id: original_msg_id.get(),
         body_md: "Replying to message with no thread.".to_string(),
         importance: None,
         to: None,
@@ -569,9 +1139,9 @@ async fn test_reply_message_impl_already_has_re_prefix() {
     let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Re: Already Replied".to_string(),
@@ -585,7 +1155,7 @@ async fn test_reply_message_impl_already_has_re_prefix() {
     let params = ReplyMessageParams {
         project_slug: project_slug.clone(),
         sender_name: "receiver_agent".to_string(),
-        message_id: msg_id,
+        message_id: msg_id.get(),
         body_md: "Another reply.".to_string(),
         importance: None,
         to: None,
@@ -626,6 +1196,63 @@ async fn test_reply_message_impl_message_not_found() {
     assert!(err.message.contains("Message not found"));
 }
 
+#[tokio::test]
+async fn test_get_thread_tree_impl_nests_replies() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
+
+    let msg_c = MessageForCreate {
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Tree Root".to_string(),
+        body_md: "Start of the tree.".to_string(),
+        thread_id: Some("TREE-THREAD".to_string()),
+        importance: None,
+        ack_required: false,
+    };
+    let original_msg_id = MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+
+    let params = ReplyMessageParams {
+        project_slug: project_slug.clone(),
+        sender_name: "receiver_agent".to_string(),
+        message_id: original_msg_id.get(),
+        body_md: "A nested reply.".to_string(),
+        importance: None,
+        to: None,
+        cc: None,
+        bcc: None,
+        subject_prefix: None,
+    };
+    messaging::reply_message_impl(&ctx, &mm, params)
+        .await
+        .unwrap();
+
+    let params = GetThreadTreeParams {
+        project_slug: project_slug.clone(),
+        thread_id: "TREE-THREAD".to_string(),
+    };
+    let result = messaging::get_thread_tree_impl(&ctx, &mm, params).await;
+    assert!(result.is_ok());
+
+    let text = format!("{:?}", result.unwrap());
+    assert!(text.contains("Tree Root"));
+    assert!(text.contains("Re: Tree Root"));
+    assert!(text.contains(&format!("\\\"in_reply_to\\\": {}", original_msg_id)));
+    assert!(text.contains("\\\"depth\\\": 1"));
+
+    let params = GetThreadTreeParams {
+        project_slug,
+        thread_id: "NO-SUCH-THREAD".to_string(),
+    };
+    let result = messaging::get_thread_tree_impl(&ctx, &mm, params).await;
+    assert!(result.is_err());
+    assert!(result.unwrap_err().message.contains("has no messages"));
+}
+
 #[tokio::test]
 async fn test_mark_message_read_impl_success() {
     let (mm, _temp) = create_test_mm().await;
@@ -633,9 +1260,9 @@ async fn test_mark_message_read_impl_success() {
     let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Read Test".to_string(),
@@ -649,7 +1276,7 @@ async fn test_mark_message_read_impl_success() {
     let params = MarkMessageReadParams {
         project_slug: project_slug.clone(),
         agent_name: "receiver_agent".to_string(),
-        message_id: msg_id,
+        message_id: msg_id.get(),
     };
 
     let result = messaging::mark_message_read_impl(&ctx, &mm, params).await;
@@ -667,9 +1294,9 @@ async fn test_acknowledge_message_impl_success() {
     let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Ack Required".to_string(),
@@ -683,7 +1310,7 @@ async fn test_acknowledge_message_impl_success() {
     let params = AcknowledgeMessageParams {
         project_slug: project_slug.clone(),
         agent_name: "receiver_agent".to_string(),
-        message_id: msg_id,
+        message_id: msg_id.get(),
     };
 
     let result = messaging::acknowledge_message_impl(&ctx, &mm, params).await;
@@ -694,6 +1321,57 @@ async fn test_acknowledge_message_impl_success() {
     assert!(text.contains("receiver_agent"));
 }
 
+#[tokio::test]
+async fn test_get_broadcast_status_impl() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+    let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
+
+    let msg_c = MessageForCreate {
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Release freeze".to_string(),
+        body_md: "Please acknowledge.".to_string(),
+        thread_id: None,
+        importance: None,
+        ack_required: true,
+    };
+    let msg_id = MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+    let status = |message_id| GetBroadcastStatusParams {
+        project_slug: project_slug.clone(),
+        message_id,
+    };
+
+    let result = messaging::get_broadcast_status_impl(&ctx, &mm, status(msg_id.get()))
+        .await
+        .unwrap();
+    let text = format!("{:?}", result);
+    assert!(text.contains("0/1 acknowledged"));
+    assert!(text.contains("receiver_agent (unread)"));
+
+    let ack = AcknowledgeMessageParams {
+        project_slug: project_slug.clone(),
+        agent_name: "receiver_agent".to_string(),
+        message_id: msg_id.get(),
+    };
+    messaging::acknowledge_message_impl(&ctx, &mm, ack)
+        .await
+        .unwrap();
+
+    let result = messaging::get_broadcast_status_impl(&ctx, &mm, status(msg_id.get()))
+        .await
+        .unwrap();
+    let text = format!("{:?}", result);
+    assert!(text.contains("1/1 acknowledged"));
+    assert!(text.contains("Time to ack"));
+
+    let missing = messaging::get_broadcast_status_impl(&ctx, &mm, status(9999)).await;
+    assert!(missing.is_err());
+}
+
 #[tokio::test]
 async fn test_acknowledge_message_impl_without_capability() {
     let (mm, _temp) = create_test_mm().await;
@@ -701,9 +1379,9 @@ async fn test_acknowledge_message_impl_without_capability() {
     let (project_id, sender_id, receiver_id, project_slug) = setup_project_and_agents(&mm).await;
 
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Ack Test".to_string(),
@@ -717,7 +1395,7 @@ async fn test_acknowledge_message_impl_without_capability() {
     let params = AcknowledgeMessageParams {
         project_slug: project_slug.clone(),
         agent_name: "sender_agent".to_string(),
-        message_id: msg_id,
+        message_id: msg_id.get(),
     };
 
     let result = messaging::acknowledge_message_impl(&ctx, &mm, params).await;
@@ -737,9 +1415,9 @@ async fn test_list_threads_impl_success() {
 
     for i in 1..=3 {
         let msg_c = MessageForCreate {
-            project_id,
-            sender_id,
-            recipient_ids: vec![receiver_id],
+            project_id: project_id.into(),
+            sender_id: sender_id.into(),
+            recipient_ids: vec![AgentId::new(receiver_id)],
             cc_ids: None,
             bcc_ids: None,
             subject: format!("Thread {} Subject", i),
@@ -754,6 +1432,7 @@ async fn test_list_threads_impl_success() {
     let params = ListThreadsParams {
         project_slug: project_slug.clone(),
         limit: Some(50),
+        ..Default::default()
     };
 
     let result = messaging::list_threads_impl(&ctx, &mm, params).await;
@@ -774,6 +1453,7 @@ async fn test_list_threads_impl_empty() {
     let params = ListThreadsParams {
         project_slug: project_slug.clone(),
         limit: None,
+        ..Default::default()
     };
 
     let result = messaging::list_threads_impl(&ctx, &mm, params).await;
@@ -806,9 +1486,13 @@ async fn test_send_message_impl_multiple_recipients() {
         bcc: None,
         subject: "Multi-recipient".to_string(),
         body_md: "Sent to multiple.".to_string(),
+        body_format: None,
         thread_id: None,
         importance: None,
         ack_required: None,
+        custom_fields: None,
+        on_behalf_of: None,
+        respond_by: None,
     };
 
     let result = messaging::send_message_impl(&ctx, &mm, params).await;
@@ -833,9 +1517,9 @@ async fn test_reply_message_impl_without_capability() {
 
     // Create a message to reply to
     let msg_c = MessageForCreate {
-        project_id,
-        sender_id,
-        recipient_ids: vec![receiver_id],
+        project_id: project_id.into(),
+        sender_id: sender_id.into(),
+        recipient_ids: vec![AgentId::new(receiver_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "Test Message".to_string(),
@@ -850,7 +1534,7 @@ async fn test_reply_message_impl_without_capability() {
     let params = ReplyMessageParams {
         project_slug: project_slug.clone(),
         sender_name: "no_reply_cap_agent".to_string(),
-        message_id: msg_id,
+        message_id: msg_id.get(),
         body_md: "Attempted reply.".to_string(),
         importance: None,
         to: None,
@@ -877,9 +1561,11 @@ async fn test_list_inbox_impl_invalid_project() {
         project_slug: "nonexistent_project".to_string(),
         agent_name: "some_agent".to_string(),
         limit: None,
+        cursor: None,
         urgent_only: None,
         since_ts: None,
         include_bodies: None,
+        format: None,
     };
 
     let result = messaging::list_inbox_impl(&ctx, &mm, params).await;
@@ -895,6 +1581,9 @@ async fn test_search_messages_impl_invalid_project() {
         project_slug: "nonexistent_project".to_string(),
         query: "test".to_string(),
         limit: None,
+        context: None,
+        include_attachments: None,
+        fields: None,
     };
 
     let result = messaging::search_messages_impl(&ctx, &mm, params).await;
@@ -909,6 +1598,7 @@ async fn test_get_thread_impl_invalid_project() {
     let params = GetThreadParams {
         project_slug: "nonexistent_project".to_string(),
         thread_id: "THREAD-123".to_string(),
+        ..Default::default()
     };
 
     let result = messaging::get_thread_impl(&ctx, &mm, params).await;
@@ -923,6 +1613,7 @@ async fn test_list_threads_impl_invalid_project() {
     let params = ListThreadsParams {
         project_slug: "nonexistent_project".to_string(),
         limit: None,
+        ..Default::default()
     };
 
     let result = messaging::list_threads_impl(&ctx, &mm, params).await;
@@ -9,11 +9,15 @@ use mouchak_mail_core::model::{
     agent_capabilities::{AgentCapabilityBmc, AgentCapabilityForCreate},
     message::{MessageBmc, MessageForCreate},
     project::ProjectBmc,
+    sla::{ProjectSlaForCreate, SlaBmc},
     tool_metric::{ToolMetricBmc, ToolMetricForCreate},
 };
+use mouchak_mail_core::types::AgentId;
+use mouchak_mail_mcp::tools::journal::SessionJournal;
 use mouchak_mail_mcp::tools::observability;
 use mouchak_mail_mcp::tools::{
-    ListActivityParams, ListPendingReviewsParams, ListToolMetricsParams,
+    GetSlaReportParams, GetUsageParams, ListActivityParams, ListPendingReviewsParams,
+    ListToolMetricsParams,
 };
 use std::sync::Arc;
 use tempfile::TempDir;
@@ -36,14 +40,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -275,9 +274,9 @@ async fn test_list_pending_reviews_impl_with_ack_required_message() {
     let receiver_id = AgentBmc::create(&ctx, &mm, receiver_c).await.unwrap();
 
     let msg = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender_id.into(),
-        recipient_ids: vec![receiver_id.into()],
+        project_id,
+        sender_id,
+        recipient_ids: vec![receiver_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Review needed".to_string(),
@@ -342,9 +341,9 @@ async fn test_list_pending_reviews_impl_with_sender_filter() {
     let receiver_id = AgentBmc::create(&ctx, &mm, receiver_c).await.unwrap();
 
     let msg = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: sender_id.into(),
-        recipient_ids: vec![receiver_id.into()],
+        project_id,
+        sender_id,
+        recipient_ids: vec![receiver_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Filtered review".to_string(),
@@ -364,3 +363,175 @@ async fn test_list_pending_reviews_impl_with_sender_filter() {
     let result = observability::list_pending_reviews_impl(&ctx, &mm, params).await;
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+async fn test_session_journal_posts_significant_calls() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    let project_id = ProjectBmc::create(&ctx, &mm, "journal-project", "Journal Test Project")
+        .await
+        .unwrap();
+    let agent_c = AgentForCreate {
+        project_id,
+        name: "journal_agent".to_string(),
+        program: "claude".to_string(),
+        model: "opus".to_string(),
+        task_description: "Agent for journal test".to_string(),
+    };
+    AgentBmc::create(&ctx, &mm, agent_c).await.unwrap();
+
+    let journal = SessionJournal::new(mm.clone(), "session-journal");
+    let slug = Some("journal-project".to_string());
+    let agent = Some("journal_agent".to_string());
+    let args = Some(serde_json::json!({"to": "reviewer", "subject": "Ready for review"}));
+
+    journal.record("send_message", slug.clone(), agent.clone(), &args, true);
+    journal.record("list_inbox", slug.clone(), agent.clone(), &None, true);
+    journal.record("reserve_file", slug.clone(), agent.clone(), &None, false);
+    journal.record("send_message", None, None, &args, true);
+    assert_eq!(
+        journal.len(),
+        2,
+        "Only significant, attributed calls are kept"
+    );
+
+    let posted = journal.flush().await;
+    assert_eq!(posted.len(), 1);
+    assert!(journal.is_empty());
+
+    let thread = MessageBmc::list_by_thread(&ctx, &mm, project_id, "session-journal")
+        .await
+        .unwrap();
+    assert_eq!(thread.len(), 1);
+    assert_eq!(thread[0].importance, "low");
+    assert!(
+        thread[0]
+            .body_md
+            .contains("`send_message` to=reviewer subject=Ready for review")
+    );
+    assert!(thread[0].body_md.contains("`reserve_file` (failed)"));
+    assert!(!thread[0].body_md.contains("list_inbox"));
+}
+
+#[tokio::test]
+async fn test_get_sla_report_impl() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    let project_id = ProjectBmc::create(&ctx, &mm, "sla-project", "SLA Test Project")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["sla_sender", "sla_recipient"] {
+        let agent_c = AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: "claude".to_string(),
+            model: "opus".to_string(),
+            task_description: "Agent for SLA test".to_string(),
+        };
+        agents.push(AgentBmc::create(&ctx, &mm, agent_c).await.unwrap());
+    }
+    SlaBmc::set_sla(
+        &ctx,
+        &mm,
+        ProjectSlaForCreate {
+            project_id,
+            importance: "urgent".to_string(),
+            ack_within_seconds: 3600,
+        },
+    )
+    .await
+    .unwrap();
+    let msg_c = MessageForCreate {
+        project_id,
+        sender_id: agents[0],
+        recipient_ids: vec![AgentId::new(agents[1].get())],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Prod is down".to_string(),
+        body_md: "Please ack".to_string(),
+        thread_id: None,
+        importance: Some("urgent".to_string()),
+        ack_required: true,
+    };
+    MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+
+    let params = |period: &str| GetSlaReportParams {
+        project_slug: "sla-project".to_string(),
+        period: Some(period.to_string()),
+    };
+    let result = observability::get_sla_report_impl(&ctx, &mm, params("24h"))
+        .await
+        .unwrap();
+    let output = extract_text(&result);
+    assert!(
+        output.contains("sla_recipient"),
+        "Per-agent compliance: {}",
+        output
+    );
+    assert!(output.contains("pending"));
+
+    let invalid = observability::get_sla_report_impl(&ctx, &mm, params("soon")).await;
+    assert!(invalid.is_err());
+}
+
+#[tokio::test]
+async fn test_get_usage_impl() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    let project_id = ProjectBmc::create(&ctx, &mm, "usage-project", "Usage Test Project")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["usage_sender", "usage_recipient"] {
+        let agent_c = AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: "claude".to_string(),
+            model: "opus".to_string(),
+            task_description: "Agent for usage test".to_string(),
+        };
+        agents.push(AgentBmc::create(&ctx, &mm, agent_c).await.unwrap());
+    }
+    let msg_c = MessageForCreate {
+        project_id,
+        sender_id: agents[0],
+        recipient_ids: vec![AgentId::new(agents[1].get())],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Build log".to_string(),
+        body_md: "All green".to_string(),
+        thread_id: None,
+        importance: None,
+        ack_required: false,
+    };
+    MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+
+    let params = |agent_name: Option<&str>| GetUsageParams {
+        project_slug: "usage-project".to_string(),
+        agent_name: agent_name.map(str::to_string),
+    };
+    let result = observability::get_usage_impl(&ctx, &mm, params(None))
+        .await
+        .unwrap();
+    let output = extract_text(&result);
+    assert!(
+        output.contains("usage_recipient"),
+        "Per-agent usage: {}",
+        output
+    );
+    assert!(output.contains(r#"\"message_bytes\": 9"#), "{}", output);
+
+    let result = observability::get_usage_impl(&ctx, &mm, params(Some("usage_sender")))
+        .await
+        .unwrap();
+    let output = extract_text(&result);
+    assert!(output.contains(r#"\"messages_sent\": 1"#), "{}", output);
+    assert!(output.contains(r#"\"limits\""#));
+
+    let unknown = observability::get_usage_impl(&ctx, &mm, params(Some("nobody"))).await;
+    assert!(unknown.is_err());
+}
@@ -11,14 +11,16 @@ use mouchak_mail_core::ctx::Ctx;
 use mouchak_mail_core::model::{
     ModelManager,
     agent::{AgentBmc, AgentForCreate},
+    agent_capabilities::AgentCapabilityBmc,
+    global_thread::GlobalThreadBmc,
     message::{MessageBmc, MessageForCreate},
     product::ProductBmc,
     project::ProjectBmc,
 };
 use mouchak_mail_mcp::tools::{
-    EnsureProductParams, LinkProjectToProductParams, MouchakMailService, ProductInboxParams,
-    SearchMessagesProductParams, SummarizeThreadProductParams, ThreadIdInput,
-    UnlinkProjectFromProductParams, products,
+    EnsureProductParams, ForwardMessageParams, LinkProjectToProductParams, MouchakMailService,
+    ProductInboxParams, SearchMessagesProductParams, SummarizeThreadProductParams, ThreadIdInput,
+    UnlinkProjectFromProductParams, messaging, products,
 };
 use rmcp::handler::server::wrapper::Parameters;
 use std::sync::Arc;
@@ -43,14 +45,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -171,7 +168,7 @@ async fn test_unlink_project_from_product_impl_success() {
         .await
         .unwrap();
 
-    ProductBmc::link_project(&ctx, &mm, product.id, project_id.into())
+    ProductBmc::link_project(&ctx, &mm, product.id, project_id)
         .await
         .unwrap();
 
@@ -262,7 +259,7 @@ async fn test_product_inbox_impl_success() {
         .await
         .unwrap();
 
-    ProductBmc::link_project(&ctx, &mm, product.id, project_id.into())
+    ProductBmc::link_project(&ctx, &mm, product.id, project_id)
         .await
         .unwrap();
 
@@ -284,9 +281,9 @@ async fn test_product_inbox_impl_success() {
         &ctx,
         &mm,
         MessageForCreate {
-            project_id: project_id.into(),
-            sender_id: agent_id.into(),
-            recipient_ids: vec![agent_id.into()],
+            project_id,
+            sender_id: agent_id,
+            recipient_ids: vec![agent_id],
             cc_ids: None,
             bcc_ids: None,
             subject: "Product Inbox Test Message".to_string(),
@@ -330,12 +327,7 @@ async fn test_product_inbox_impl_product_not_found() {
 
 #[tokio::test]
 async fn test_search_messages_product() -> anyhow::Result<()> {
-    let mm = Arc::new(
-        ModelManager::new(std::sync::Arc::new(
-            mouchak_mail_common::config::AppConfig::default(),
-        ))
-        .await?,
-    );
+    let (mm, _temp_dir) = create_test_mm().await;
     let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
 
     // Create product (name must be unique)
@@ -349,8 +341,8 @@ async fn test_search_messages_product() -> anyhow::Result<()> {
     let project1_id = ProjectBmc::create(&ctx, &mm, &project1_slug, "Search Project 1").await?;
     let project2_id = ProjectBmc::create(&ctx, &mm, &project2_slug, "Search Project 2").await?;
 
-    ProductBmc::link_project(&ctx, &mm, product.id, project1_id.into()).await?;
-    ProductBmc::link_project(&ctx, &mm, product.id, project2_id.into()).await?;
+    ProductBmc::link_project(&ctx, &mm, product.id, project1_id).await?;
+    ProductBmc::link_project(&ctx, &mm, product.id, project2_id).await?;
 
     // Create agents in each project
     let agent1_id = AgentBmc::create(
@@ -384,9 +376,9 @@ async fn test_search_messages_product() -> anyhow::Result<()> {
         &ctx,
         &mm,
         MessageForCreate {
-            project_id: project1_id.into(),
-            sender_id: agent1_id.into(),
-            recipient_ids: vec![agent1_id.into()],
+            project_id: project1_id,
+            sender_id: agent1_id,
+            recipient_ids: vec![agent1_id],
             cc_ids: None,
             bcc_ids: None,
             subject: "Message in project one".to_string(),
@@ -402,9 +394,9 @@ async fn test_search_messages_product() -> anyhow::Result<()> {
         &ctx,
         &mm,
         MessageForCreate {
-            project_id: project2_id.into(),
-            sender_id: agent2_id.into(),
-            recipient_ids: vec![agent2_id.into()],
+            project_id: project2_id,
+            sender_id: agent2_id,
+            recipient_ids: vec![agent2_id],
             cc_ids: None,
             bcc_ids: None,
             subject: "Message in project two".to_string(),
@@ -450,12 +442,7 @@ async fn test_search_messages_product() -> anyhow::Result<()> {
 
 #[tokio::test]
 async fn test_summarize_thread_product() -> anyhow::Result<()> {
-    let mm = Arc::new(
-        ModelManager::new(std::sync::Arc::new(
-            mouchak_mail_common::config::AppConfig::default(),
-        ))
-        .await?,
-    );
+    let (mm, _temp_dir) = create_test_mm().await;
     let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
 
     // Create product (name must be unique)
@@ -469,8 +456,8 @@ async fn test_summarize_thread_product() -> anyhow::Result<()> {
     let project1_id = ProjectBmc::create(&ctx, &mm, &project1_slug, "Summary Project 1").await?;
     let project2_id = ProjectBmc::create(&ctx, &mm, &project2_slug, "Summary Project 2").await?;
 
-    ProductBmc::link_project(&ctx, &mm, product.id, project1_id.into()).await?;
-    ProductBmc::link_project(&ctx, &mm, product.id, project2_id.into()).await?;
+    ProductBmc::link_project(&ctx, &mm, product.id, project1_id).await?;
+    ProductBmc::link_project(&ctx, &mm, product.id, project2_id).await?;
 
     // Create agents
     let agent1_id = AgentBmc::create(
@@ -499,16 +486,16 @@ async fn test_summarize_thread_product() -> anyhow::Result<()> {
     )
     .await?;
 
-    // Create messages in the SAME thread across BOTH projects
+    // Both projects happen to use the same local thread id
     let shared_thread_id = "T-CROSS-PROJECT".to_string();
 
-    MessageBmc::create(
+    let first_id = MessageBmc::create(
         &ctx,
         &mm,
         MessageForCreate {
-            project_id: project1_id.into(),
-            sender_id: agent1_id.into(),
-            recipient_ids: vec![agent1_id.into()],
+            project_id: project1_id,
+            sender_id: agent1_id,
+            recipient_ids: vec![agent1_id],
             cc_ids: None,
             bcc_ids: None,
             subject: "Cross-Project Thread Message".to_string(),
@@ -524,9 +511,9 @@ async fn test_summarize_thread_product() -> anyhow::Result<()> {
         &ctx,
         &mm,
         MessageForCreate {
-            project_id: project2_id.into(),
-            sender_id: agent2_id.into(),
-            recipient_ids: vec![agent2_id.into()],
+            project_id: project2_id,
+            sender_id: agent2_id,
+            recipient_ids: vec![agent2_id],
             cc_ids: None,
             bcc_ids: None,
             subject: "Cross-Project Thread Reply".to_string(),
@@ -549,35 +536,100 @@ async fn test_summarize_thread_product() -> anyhow::Result<()> {
 
     let output = extract_text(&result);
 
-    // Should aggregate messages from both projects
+    // Unrelated threads sharing a local id are summarized separately
     assert!(
         output.contains(&shared_thread_id),
         "Should contain thread ID: {}",
         output
     );
     assert!(
-        output.contains("charlie") || output.contains("diana"),
+        output.contains("charlie") && output.contains("diana"),
         "Should list participants: {}",
         output
     );
-    // The subject should indicate it came from multiple projects
+    assert_eq!(
+        output.matches("global_thread_id").count(),
+        2,
+        "Should keep the two threads apart: {}",
+        output
+    );
+
+    // Forwarding into project 2 continues project 1's global thread
+    AgentCapabilityBmc::grant_defaults(&ctx, &mm, agent2_id).await?;
+    let forwarded = messaging::forward_message_impl(
+        &ctx,
+        &mm,
+        ForwardMessageParams {
+            project_slug: project2_slug.clone(),
+            sender_name: "diana".to_string(),
+            message_id: first_id.get(),
+            to: "diana".to_string(),
+            note: Some("FYI".to_string()),
+        },
+    )
+    .await?;
+    let forwarded = extract_text(&forwarded);
     assert!(
-        output.contains("message_count") || output.contains("2"),
-        "Should aggregate message count: {}",
+        forwarded.contains("Fwd: Cross-Project Thread Message"),
+        "{}",
+        forwarded
+    );
+    let global_thread_id = GlobalThreadBmc::get_id(&ctx, &mm, project1_id, &shared_thread_id)
+        .await?
+        .expect("global thread id");
+    assert!(forwarded.contains(&global_thread_id), "{}", forwarded);
+
+    let result = service
+        .summarize_thread_product_impl(Parameters(SummarizeThreadProductParams {
+            product_uid: product_uid.clone(),
+            thread_id: ThreadIdInput::Single(global_thread_id.clone()),
+        }))
+        .await?;
+    let output = extract_text(&result);
+    assert_eq!(output.matches("global_thread_id").count(), 1, "{}", output);
+    assert!(
+        output.contains(&project1_slug) && output.contains(&project2_slug),
+        "{}",
         output
     );
+    assert!(output.contains("message_count\\\": 2"), "{}", output);
+
+    // Forwarding from a project outside the product is refused
+    let outsider_slug = format!("sum-outsider-{}", Uuid::new_v4());
+    let outsider_project = ProjectBmc::create(&ctx, &mm, &outsider_slug, "Outsider").await?;
+    let outsider = AgentBmc::create(
+        &ctx,
+        &mm,
+        AgentForCreate {
+            project_id: outsider_project,
+            name: "eve".to_string(),
+            program: "test".to_string(),
+            model: "gpt-4".to_string(),
+            task_description: "test".to_string(),
+        },
+    )
+    .await?;
+    AgentCapabilityBmc::grant_defaults(&ctx, &mm, outsider).await?;
+    let refused = messaging::forward_message_impl(
+        &ctx,
+        &mm,
+        ForwardMessageParams {
+            project_slug: outsider_slug,
+            sender_name: "eve".to_string(),
+            message_id: first_id.get(),
+            to: "eve".to_string(),
+            note: None,
+        },
+    )
+    .await;
+    assert!(refused.is_err());
 
     Ok(())
 }
 
 #[tokio::test]
 async fn test_search_messages_product_no_matches() -> anyhow::Result<()> {
-    let mm = Arc::new(
-        ModelManager::new(std::sync::Arc::new(
-            mouchak_mail_common::config::AppConfig::default(),
-        ))
-        .await?,
-    );
+    let (mm, _temp_dir) = create_test_mm().await;
     let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
 
     // Create product with a project but no matching messages (name must be unique)
@@ -587,7 +639,7 @@ async fn test_search_messages_product_no_matches() -> anyhow::Result<()> {
 
     let project_slug = format!("nomatch-{}", Uuid::new_v4());
     let project_id = ProjectBmc::create(&ctx, &mm, &project_slug, "No Match Project").await?;
-    ProductBmc::link_project(&ctx, &mm, product.id, project_id.into()).await?;
+    ProductBmc::link_project(&ctx, &mm, product.id, project_id).await?;
 
     let service = MouchakMailService::new_with_mm(mm.clone(), false);
     let result = service
@@ -611,12 +663,7 @@ async fn test_search_messages_product_no_matches() -> anyhow::Result<()> {
 
 #[tokio::test]
 async fn test_summarize_thread_product_not_found() -> anyhow::Result<()> {
-    let mm = Arc::new(
-        ModelManager::new(std::sync::Arc::new(
-            mouchak_mail_common::config::AppConfig::default(),
-        ))
-        .await?,
-    );
+    let (mm, _temp_dir) = create_test_mm().await;
     let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
 
     // Create product with a project but no matching thread (name must be unique)
@@ -626,7 +673,7 @@ async fn test_summarize_thread_product_not_found() -> anyhow::Result<()> {
 
     let project_slug = format!("nothread-{}", Uuid::new_v4());
     let project_id = ProjectBmc::create(&ctx, &mm, &project_slug, "No Thread Project").await?;
-    ProductBmc::link_project(&ctx, &mm, product.id, project_id.into()).await?;
+    ProductBmc::link_project(&ctx, &mm, product.id, project_id).await?;
 
     let service = MouchakMailService::new_with_mm(mm.clone(), false);
     let result = service
@@ -1,3 +1,7 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use libsql::Builder;
+use mouchak_mail_common::config::AppConfig;
 use mouchak_mail_core::model::{
     ModelManager,
     agent::{AgentBmc, AgentForCreate},
@@ -5,19 +9,33 @@ use mouchak_mail_core::model::{
     project::ProjectBmc,
 };
 use mouchak_mail_mcp::tools::MouchakMailService;
+use mouchak_mail_mcp::tools::subscriptions::ResourceSubscriptions;
 use rmcp::model::ReadResourceRequestParam;
 use std::sync::Arc;
+use tempfile::TempDir;
 use uuid::Uuid;
 
+async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let db_path = temp_dir.path().join("test.db");
+    let archive_root = temp_dir.path().join("archive");
+    std::fs::create_dir_all(&archive_root).unwrap();
+
+    let db = Builder::new_local(&db_path).build().await.unwrap();
+    let conn = db.connect().unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
+
+    let app_config = Arc::new(AppConfig::default());
+    let mm = ModelManager::new_for_test(conn, archive_root, app_config);
+    (Arc::new(mm), temp_dir)
+}
+
 #[tokio::test]
 async fn test_resources_gap_features() -> anyhow::Result<()> {
     // 1. Setup Data
-    let mm = Arc::new(
-        ModelManager::new(std::sync::Arc::new(
-            mouchak_mail_common::config::AppConfig::default(),
-        ))
-        .await?,
-    );
+    let (mm, _temp_dir) = create_test_mm().await;
     let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
 
     // Create Project
@@ -45,9 +63,9 @@ async fn test_resources_gap_features() -> anyhow::Result<()> {
         &ctx,
         &mm,
         MessageForCreate {
-            project_id: project_id.into(),
-            sender_id: agent_id.into(),
-            recipient_ids: vec![agent_id.into()], // Self message
+            project_id,
+            sender_id: agent_id,
+            recipient_ids: vec![agent_id], // Self message
             cc_ids: None,
             bcc_ids: None,
             subject: "Test Subject".to_string(),
@@ -78,6 +96,12 @@ async fn test_resources_gap_features() -> anyhow::Result<()> {
     assert!(has_outbox, "List resources should contain outbox");
     assert!(has_threads, "List resources should contain threads");
 
+    let thread_uri = format!("mouchak-mail://{}/thread/{}", project_slug, thread_id);
+    assert!(
+        res.resources.iter().any(|r| r.raw.uri == thread_uri),
+        "List resources should contain each thread"
+    );
+
     // 4. Test read_resource (Inbox) - with include_bodies=true to get message content
     let inbox_with_bodies_uri = format!("{}?include_bodies=true", inbox_uri);
     let inbox_req = ReadResourceRequestParam {
@@ -115,3 +139,78 @@ async fn test_resources_gap_features() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_thread_subscription_notifies_on_new_message() -> anyhow::Result<()> {
+    let (mm, _temp_dir) = create_test_mm().await;
+    let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+    let project_slug = format!("test-sub-{}", Uuid::new_v4());
+    let project_id = ProjectBmc::create(&ctx, &mm, &project_slug, "Sub Project").await?;
+    let agent_id = AgentBmc::create(
+        &ctx,
+        &mm,
+        AgentForCreate {
+            project_id,
+            name: "watcher".to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: "test".to_string(),
+        },
+    )
+    .await?;
+    let send = |thread_id: &'static str| {
+        let (ctx, mm) = (ctx.clone(), mm.clone());
+        async move {
+            MessageBmc::create(
+                &ctx,
+                &mm,
+                MessageForCreate {
+                    project_id,
+                    sender_id: agent_id,
+                    recipient_ids: vec![agent_id],
+                    cc_ids: None,
+                    bcc_ids: None,
+                    subject: "Update".to_string(),
+                    body_md: "body".to_string(),
+                    thread_id: Some(thread_id.to_string()),
+                    importance: None,
+                    ack_required: false,
+                },
+            )
+            .await
+        }
+    };
+    send("SUB-1").await?;
+
+    let subs = ResourceSubscriptions::new(mm.clone(), std::time::Duration::from_millis(20));
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let uri = format!("mouchak-mail://{}/thread/SUB-1", project_slug);
+    subs.subscribe(&uri, move |uri| {
+        let tx = tx.clone();
+        async move { tx.send(uri).is_ok() }
+    })
+    .await?;
+    assert_eq!(subs.subscribed_uris(), std::slice::from_ref(&uri));
+
+    // Messages in other threads do not notify
+    send("SUB-2").await?;
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(rx.try_recv().is_err());
+
+    send("SUB-1").await?;
+    let notified = tokio::time::timeout(std::time::Duration::from_secs(2), rx.recv()).await?;
+    assert_eq!(notified, Some(uri.clone()));
+
+    subs.unsubscribe(&uri);
+    assert!(subs.subscribed_uris().is_empty());
+
+    // Only thread resources can be subscribed to
+    let threads_uri = format!("mouchak-mail://{}/threads", project_slug);
+    assert!(
+        subs.subscribe(&threads_uri, |_| async { true })
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
@@ -15,6 +15,7 @@ use mouchak_mail_core::model::{
     agent::{AgentBmc, AgentForCreate},
     project::ProjectBmc,
 };
+use mouchak_mail_core::types::AgentId;
 use std::sync::Arc;
 use tempfile::TempDir;
 
@@ -29,15 +30,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    // Run migrations
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -62,9 +57,7 @@ async fn test_list_builtin_workflows_returns_5_workflows() {
     // RED: This should list the 5 built-in workflow names
     // We're testing the CONTENT, not the tool (tool will call this logic)
     use mouchak_mail_core::model::macro_def::MacroDefBmc;
-    let macros = MacroDefBmc::list(&ctx, &mm, project_id.into())
-        .await
-        .unwrap();
+    let macros = MacroDefBmc::list(&ctx, &mm, project_id).await.unwrap();
 
     // Should have exactly 5 built-ins
     assert_eq!(macros.len(), 5, "Should have 5 built-in workflows");
@@ -112,10 +105,10 @@ async fn test_quick_standup_sends_to_all_agents() {
 
     // Test we can send a message to all of them
     use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
-    let agent_ids: Vec<i64> = agents.iter().map(|a| a.id.into()).collect();
+    let agent_ids: Vec<AgentId> = agents.iter().map(|a| a.id).collect();
 
     let msg_c = MessageForCreate {
-        project_id: project_id.into(),
+        project_id,
         sender_id: agent_ids[0],          // First agent sends
         recipient_ids: agent_ids.clone(), // To all agents
         cc_ids: None,
@@ -128,7 +121,7 @@ async fn test_quick_standup_sends_to_all_agents() {
     };
 
     let msg_id = MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
-    assert!(msg_id > 0, "Should create standup message");
+    assert!(msg_id.get() > 0, "Should create standup message");
 }
 
 // ==============================================================================
@@ -168,9 +161,9 @@ async fn test_quick_handoff_sends_message() {
     use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 
     let handoff_msg = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: agent1_id.into(),
-        recipient_ids: vec![agent2_id.into()],
+        project_id,
+        sender_id: agent1_id,
+        recipient_ids: vec![agent2_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Task Handoff: Feature X".to_string(),
@@ -181,7 +174,7 @@ async fn test_quick_handoff_sends_message() {
     };
 
     let msg_id = MessageBmc::create(&ctx, &mm, handoff_msg).await.unwrap();
-    assert!(msg_id > 0, "Should create handoff message");
+    assert!(msg_id.get() > 0, "Should create handoff message");
 }
 
 // ==============================================================================
@@ -238,9 +231,9 @@ async fn test_quick_review_reserves_files_and_sends_message() {
     use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
 
     let review_msg = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: requester_id.into(),
-        recipient_ids: vec![reviewer_id.into()],
+        project_id,
+        sender_id: requester_id,
+        recipient_ids: vec![reviewer_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Code Review Request".to_string(),
@@ -251,5 +244,5 @@ async fn test_quick_review_reserves_files_and_sends_message() {
     };
 
     let msg_id = MessageBmc::create(&ctx, &mm, review_msg).await.unwrap();
-    assert!(msg_id > 0, "Should create review request message");
+    assert!(msg_id.get() > 0, "Should create review request message");
 }
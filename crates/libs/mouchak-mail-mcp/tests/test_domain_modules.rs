@@ -8,11 +8,17 @@ use mouchak_mail_core::ctx::Ctx;
 use mouchak_mail_core::model::{
     ModelManager,
     agent::{AgentBmc, AgentForCreate},
+    external_tool::{ExternalToolBmc, ExternalToolForCreate},
     message::{MessageBmc, MessageForCreate},
     project::ProjectBmc,
+    project_contact_policy::{
+        ContactRuleAction, ContactRuleTarget, ProjectContactPolicyBmc, ProjectContactRuleForCreate,
+    },
 };
 use mouchak_mail_mcp::tools::{
     // Params
+    AdvanceWorkflowParams,
+    DefineWorkflowParams,
     EnsureProjectParams,
     GetProjectInfoParams,
     GetReviewStateParams,
@@ -20,20 +26,25 @@ use mouchak_mail_mcp::tools::{
     ListContactsParams,
     ListOutboxParams,
     ListPendingReviewsParams,
+    ListProjectsParams,
     ListReservationsParams,
     ListToolMetricsParams,
     RegisterAgentParams,
     RequestContactParams,
     SetContactPolicyParams,
+    StartWorkflowParams,
     WhoisParams,
+    WorkflowStateParam,
     // Domain impl functions
     agent,
     contacts,
+    external,
     files,
     observability,
     outbox,
     project,
     reviews,
+    workflows,
 };
 use std::sync::Arc;
 use tempfile::TempDir;
@@ -49,15 +60,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    // Run migrations
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -124,7 +129,7 @@ async fn test_list_projects_returns_all() {
             .unwrap();
     }
 
-    let result = project::list_projects_impl(&ctx, &mm).await;
+    let result = project::list_projects_impl(&ctx, &mm, ListProjectsParams::default()).await;
     assert!(result.is_ok());
 
     let content = format!("{:?}", result);
@@ -292,6 +297,19 @@ async fn test_request_contact() {
     };
     AgentBmc::create(&ctx, &mm, agent2_c).await.unwrap();
 
+    ProjectContactPolicyBmc::set_rule(
+        &ctx,
+        &mm,
+        ProjectContactRuleForCreate {
+            project_id: proj2_id,
+            action: ContactRuleAction::Allow,
+            target_kind: ContactRuleTarget::Project,
+            target: "proj-a".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
     let params = RequestContactParams {
         from_project_slug: "proj-a".to_string(),
         from_agent_name: "agent-a".to_string(),
@@ -445,9 +463,9 @@ async fn test_get_review_state_with_completion() {
 
     // Create a [COMPLETION] message
     let msg = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: agent_id.into(),
-        recipient_ids: vec![agent_id.into()],
+        project_id,
+        sender_id: agent_id,
+        recipient_ids: vec![agent_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "[COMPLETION] Task done".to_string(),
@@ -498,6 +516,10 @@ async fn test_list_outbox_empty() {
         project_slug: "outbox-test".to_string(),
         agent_name: "sender".to_string(),
         limit: Some(10),
+        cursor: None,
+        needs_attention: None,
+        unread_after: None,
+        unacked_after: None,
     };
 
     let result = outbox::list_outbox_impl(&ctx, &mm, params).await;
@@ -535,9 +557,9 @@ async fn test_list_outbox_with_messages() {
     // Create messages
     for i in 1..=3 {
         let msg = MessageForCreate {
-            project_id: project_id.into(),
-            sender_id: sender_id.into(),
-            recipient_ids: vec![recipient_id.into()],
+            project_id,
+            sender_id,
+            recipient_ids: vec![recipient_id],
             cc_ids: None,
             bcc_ids: None,
             subject: format!("Test Message {}", i),
@@ -553,6 +575,10 @@ async fn test_list_outbox_with_messages() {
         project_slug: "outbox-full".to_string(),
         agent_name: "sender".to_string(),
         limit: Some(10),
+        cursor: None,
+        needs_attention: None,
+        unread_after: None,
+        unacked_after: None,
     };
 
     let result = outbox::list_outbox_impl(&ctx, &mm, params).await;
@@ -560,6 +586,31 @@ async fn test_list_outbox_with_messages() {
 
     let content = format!("{:?}", result);
     assert!(content.contains("Test Message"));
+
+    // Just sent, so nothing is overdue yet
+    let params = ListOutboxParams {
+        project_slug: "outbox-full".to_string(),
+        agent_name: "sender".to_string(),
+        limit: Some(10),
+        cursor: None,
+        needs_attention: Some(true),
+        unread_after: Some("1h".to_string()),
+        unacked_after: None,
+    };
+    let result = outbox::list_outbox_impl(&ctx, &mm, params).await;
+    let content = format!("{:?}", result);
+    assert!(content.contains("No sent messages need attention"));
+
+    let params = ListOutboxParams {
+        project_slug: "outbox-full".to_string(),
+        agent_name: "sender".to_string(),
+        limit: Some(10),
+        cursor: None,
+        needs_attention: Some(true),
+        unread_after: Some("soon".to_string()),
+        unacked_after: None,
+    };
+    assert!(outbox::list_outbox_impl(&ctx, &mm, params).await.is_err());
 }
 
 // ==============================================================================
@@ -630,3 +681,176 @@ async fn test_list_pending_reviews() {
     let result = observability::list_pending_reviews_impl(&ctx, &mm, params).await;
     assert!(result.is_ok());
 }
+
+// ==============================================================================
+// Thread Workflow Module Tests
+// ==============================================================================
+
+#[tokio::test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+async fn test_advance_workflow_validates_and_hands_off() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    let project_id = ProjectBmc::create(&ctx, &mm, "workflow-test", "/workflow")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["author", "reviewer"] {
+        let agent_c = AgentForCreate {
+            project_id,
+            name: name.to_string(),
+            program: "test".to_string(),
+            model: "test".to_string(),
+            task_description: String::new(),
+        };
+        agents.push(AgentBmc::create(&ctx, &mm, agent_c).await.unwrap());
+    }
+    let msg = MessageForCreate {
+        project_id,
+        sender_id: agents[0],
+        recipient_ids: vec![agents[1]],
+        cc_ids: None,
+        bcc_ids: None,
+        subject: "Feature".to_string(),
+        body_md: "Spec attached".to_string(),
+        thread_id: Some("FEAT-7".to_string()),
+        importance: None,
+        ack_required: false,
+    };
+    MessageBmc::create(&ctx, &mm, msg).await.unwrap();
+
+    let params = DefineWorkflowParams {
+        project_slug: "workflow-test".to_string(),
+        name: "review".to_string(),
+        states: vec![
+            WorkflowStateParam {
+                name: "implement".to_string(),
+                responsible: Some("author".to_string()),
+            },
+            WorkflowStateParam {
+                name: "review".to_string(),
+                responsible: Some("reviewer".to_string()),
+            },
+        ],
+        transitions: vec!["implement -> review".to_string()],
+    };
+    workflows::define_workflow_impl(&ctx, &mm, params)
+        .await
+        .unwrap();
+
+    let params = StartWorkflowParams {
+        project_slug: "workflow-test".to_string(),
+        thread_id: "FEAT-7".to_string(),
+        workflow: "review".to_string(),
+        agent_name: "author".to_string(),
+    };
+    workflows::start_workflow_impl(&ctx, &mm, params)
+        .await
+        .unwrap();
+
+    // Moves the workflow does not define are rejected
+    let params = AdvanceWorkflowParams {
+        project_slug: "workflow-test".to_string(),
+        thread_id: "FEAT-7".to_string(),
+        to_state: "done".to_string(),
+        agent_name: "author".to_string(),
+        note: None,
+    };
+    let err = workflows::advance_workflow_impl(&ctx, &mm, params)
+        .await
+        .unwrap_err();
+    assert!(err.message.contains("Allowed from 'implement': review"));
+
+    let params = AdvanceWorkflowParams {
+        project_slug: "workflow-test".to_string(),
+        thread_id: "FEAT-7".to_string(),
+        to_state: "review".to_string(),
+        agent_name: "author".to_string(),
+        note: Some("Ready".to_string()),
+    };
+    let result = workflows::advance_workflow_impl(&ctx, &mm, params)
+        .await
+        .unwrap();
+    let content = format!("{:?}", result);
+    assert!(content.contains("reviewer"));
+    assert!(content.contains("finished"));
+}
+
+#[tokio::test]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+async fn test_external_tool_proxies_with_auth_and_size_cap() {
+    let (mm, _temp) = create_test_mm().await;
+    let ctx = Ctx::root_ctx();
+
+    // Echoes the Authorization header and the forwarded call
+    let app = axum::Router::new().route(
+        "/tool",
+        axum::routing::post(|headers: axum::http::HeaderMap, body: String| async move {
+            let auth = headers
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("-")
+                .to_string();
+            format!("{} {}", auth, body)
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let register = |name: &str, path: &str, max_result_bytes: Option<i64>, enabled: bool| {
+        ExternalToolForCreate {
+            name: name.to_string(),
+            description: "Echo".to_string(),
+            input_schema: None,
+            url: format!("http://{}{}", addr, path),
+            auth_header: Some("Bearer secret".to_string()),
+            timeout_ms: None,
+            max_result_bytes,
+            enabled,
+        }
+    };
+    for tool_c in [
+        register("echo", "/tool", None, true),
+        register("echo_short", "/tool", Some(13), true),
+        register("broken", "/missing", None, true),
+        register("disabled", "/tool", None, false),
+    ] {
+        ExternalToolBmc::register(&ctx, &mm, tool_c).await.unwrap();
+    }
+
+    let echo = external::find_external_tool(&mm, "echo").await.unwrap();
+    let result = external::call_external_tool_impl(&echo, Some(serde_json::json!({"q": "rust"})))
+        .await
+        .unwrap();
+    let content = format!("{:?}", result);
+    assert!(content.contains("Bearer secret"));
+    assert!(content.contains("rust"));
+
+    let short = external::find_external_tool(&mm, "echo_short")
+        .await
+        .unwrap();
+    let result = external::call_external_tool_impl(&short, None)
+        .await
+        .unwrap();
+    let content = format!("{:?}", result);
+    assert!(content.contains("Bearer secret"));
+    assert!(content.contains("[truncated at 13 bytes]"));
+    assert!(!content.contains("arguments"));
+
+    let broken = external::find_external_tool(&mm, "broken").await.unwrap();
+    let err = external::call_external_tool_impl(&broken, None)
+        .await
+        .unwrap_err();
+    assert!(err.message.contains("404"));
+
+    assert!(
+        external::find_external_tool(&mm, "disabled")
+            .await
+            .is_none()
+    );
+    let listed = external::external_tools(&mm, |name| name == "echo").await;
+    let names: Vec<&str> = listed.iter().map(|t| &*t.name).collect();
+    assert_eq!(names, ["broken", "echo_short"]);
+}
@@ -16,6 +16,7 @@ use mouchak_mail_core::model::{
     message::{MessageBmc, MessageForCreate},
     project::ProjectBmc,
 };
+use mouchak_mail_core::types::AgentId;
 use mouchak_mail_mcp::tools::{ClaimReviewParams, ExportMailboxParams, GetReviewStateParams};
 use mouchak_mail_mcp::tools::{export, reviews};
 use std::sync::Arc;
@@ -31,14 +32,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -72,9 +68,9 @@ async fn setup_project_with_messages(mm: &Arc<ModelManager>) -> (i64, i64, i64,
 
     // Create some test messages
     let msg = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: agent1_id.into(),
-        recipient_ids: vec![agent2_id.into()],
+        project_id,
+        sender_id: agent1_id,
+        recipient_ids: vec![agent2_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Test Message".to_string(),
@@ -227,9 +223,9 @@ async fn test_get_review_state_impl_with_completion_message() {
 
     // Create a [COMPLETION] message
     let msg = MessageForCreate {
-        project_id,
-        sender_id: agent1_id,
-        recipient_ids: vec![agent2_id],
+        project_id: project_id.into(),
+        sender_id: agent1_id.into(),
+        recipient_ids: vec![AgentId::new(agent2_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "[COMPLETION] Task finished".to_string(),
@@ -278,9 +274,9 @@ async fn test_claim_review_impl_success() {
 
     // Create a [COMPLETION] message that can be claimed
     let msg = MessageForCreate {
-        project_id,
-        sender_id: agent1_id,
-        recipient_ids: vec![agent2_id],
+        project_id: project_id.into(),
+        sender_id: agent1_id.into(),
+        recipient_ids: vec![AgentId::new(agent2_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "[COMPLETION] Feature implementation".to_string(),
@@ -293,7 +289,7 @@ async fn test_claim_review_impl_success() {
 
     let params = ClaimReviewParams {
         project_slug,
-        message_id: msg_id,
+        message_id: msg_id.get(),
         reviewer_name: "receiver_agent".to_string(),
     };
 
@@ -313,9 +309,9 @@ async fn test_claim_review_impl_already_claimed() {
 
     // Create a [COMPLETION] message
     let msg = MessageForCreate {
-        project_id,
-        sender_id: agent1_id,
-        recipient_ids: vec![agent2_id],
+        project_id: project_id.into(),
+        sender_id: agent1_id.into(),
+        recipient_ids: vec![AgentId::new(agent2_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "[COMPLETION] Work done".to_string(),
@@ -329,7 +325,7 @@ async fn test_claim_review_impl_already_claimed() {
     // First claim
     let params1 = ClaimReviewParams {
         project_slug: project_slug.clone(),
-        message_id: msg_id,
+        message_id: msg_id.get(),
         reviewer_name: "receiver_agent".to_string(),
     };
     reviews::claim_review_impl(&ctx, &mm, params1)
@@ -339,7 +335,7 @@ async fn test_claim_review_impl_already_claimed() {
     // Try to claim again
     let params2 = ClaimReviewParams {
         project_slug,
-        message_id: msg_id,
+        message_id: msg_id.get(),
         reviewer_name: "receiver_agent".to_string(),
     };
 
@@ -374,9 +370,9 @@ async fn test_claim_review_impl_reviewer_not_found() {
     let (project_id, agent1_id, agent2_id, project_slug) = setup_project_with_messages(&mm).await;
 
     let msg = MessageForCreate {
-        project_id,
-        sender_id: agent1_id,
-        recipient_ids: vec![agent2_id],
+        project_id: project_id.into(),
+        sender_id: agent1_id.into(),
+        recipient_ids: vec![AgentId::new(agent2_id)],
         cc_ids: None,
         bcc_ids: None,
         subject: "[COMPLETION] Task".to_string(),
@@ -389,7 +385,7 @@ async fn test_claim_review_impl_reviewer_not_found() {
 
     let params = ClaimReviewParams {
         project_slug,
-        message_id: msg_id,
+        message_id: msg_id.get(),
         reviewer_name: "nonexistent_reviewer".to_string(),
     };
 
@@ -23,15 +23,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    // Run migrations
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -62,21 +56,17 @@ async fn test_unregister_macro_removes_from_database() {
     let _macro_id = MacroDefBmc::create(&ctx, &mm, macro_c).await.unwrap();
 
     // Verify macro exists (along with 5 built-in macros = 6 total)
-    let macros = MacroDefBmc::list(&ctx, &mm, project_id.into())
-        .await
-        .unwrap();
+    let macros = MacroDefBmc::list(&ctx, &mm, project_id).await.unwrap();
     assert_eq!(macros.len(), 6, "Should have 5 built-in + 1 test macro");
 
     // GREEN: Call MacroDefBmc::delete directly
-    let deleted = MacroDefBmc::delete(&ctx, &mm, project_id.into(), "test-workflow")
+    let deleted = MacroDefBmc::delete(&ctx, &mm, project_id, "test-workflow")
         .await
         .unwrap();
     assert!(deleted, "delete should return true");
 
     // Verify custom macro is gone (5 built-in macros remain)
-    let macros = MacroDefBmc::list(&ctx, &mm, project_id.into())
-        .await
-        .unwrap();
+    let macros = MacroDefBmc::list(&ctx, &mm, project_id).await.unwrap();
     assert_eq!(
         macros.len(),
         5,
@@ -95,7 +85,7 @@ async fn test_unregister_nonexistent_macro_returns_false() {
         .unwrap();
 
     // Try to delete a macro that doesn't exist
-    let deleted = MacroDefBmc::delete(&ctx, &mm, project_id.into(), "nonexistent-macro")
+    let deleted = MacroDefBmc::delete(&ctx, &mm, project_id, "nonexistent-macro")
         .await
         .unwrap();
     assert!(!deleted, "Should return false for nonexistent macro");
@@ -129,9 +119,7 @@ async fn test_list_returns_correct_macros() {
     MacroDefBmc::create(&ctx, &mm, macro2).await.unwrap();
 
     // Use lib-core list method directly (5 built-in + 2 custom = 7)
-    let macros = MacroDefBmc::list(&ctx, &mm, project_id.into())
-        .await
-        .unwrap();
+    let macros = MacroDefBmc::list(&ctx, &mm, project_id).await.unwrap();
 
     assert_eq!(macros.len(), 7, "Should have 5 built-in + 2 custom macros");
     assert!(macros.iter().any(|m| m.name == "workflow-1"));
@@ -159,7 +147,7 @@ async fn test_create_and_get_macro_by_name() {
     assert!(macro_id > 0);
 
     // Verify it exists via get_by_name
-    let retrieved = MacroDefBmc::get_by_name(&ctx, &mm, project_id.into(), "new-workflow")
+    let retrieved = MacroDefBmc::get_by_name(&ctx, &mm, project_id, "new-workflow")
         .await
         .unwrap();
     assert_eq!(retrieved.name, "new-workflow");
@@ -188,7 +176,7 @@ async fn test_get_macro_returns_correct_steps() {
     };
     MacroDefBmc::create(&ctx, &mm, macro_c).await.unwrap();
 
-    let retrieved = MacroDefBmc::get_by_name(&ctx, &mm, project_id.into(), "deploy-workflow")
+    let retrieved = MacroDefBmc::get_by_name(&ctx, &mm, project_id, "deploy-workflow")
         .await
         .unwrap();
 
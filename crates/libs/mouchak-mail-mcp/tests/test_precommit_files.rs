@@ -37,14 +37,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -245,6 +240,7 @@ async fn test_reserve_file_impl_success() {
         ttl_seconds: Some(3600),
         exclusive: Some(true),
         reason: Some("Development work".to_string()),
+        notify_on_release: None,
     };
 
     let result = files::reserve_file_impl(&ctx, &mm, params).await;
@@ -267,6 +263,7 @@ async fn test_reserve_file_impl_default_values() {
         ttl_seconds: None, // Uses default
         exclusive: None,   // Uses default
         reason: None,      // Uses default
+        notify_on_release: None,
     };
 
     let result = files::reserve_file_impl(&ctx, &mm, params).await;
@@ -285,6 +282,7 @@ async fn test_reserve_file_impl_invalid_project() {
         ttl_seconds: None,
         exclusive: None,
         reason: None,
+        notify_on_release: None,
     };
 
     let result = files::reserve_file_impl(&ctx, &mm, params).await;
@@ -304,6 +302,7 @@ async fn test_reserve_file_impl_invalid_agent() {
         ttl_seconds: None,
         exclusive: None,
         reason: None,
+        notify_on_release: None,
     };
 
     let result = files::reserve_file_impl(&ctx, &mm, params).await;
@@ -337,6 +336,7 @@ async fn test_reserve_file_impl_no_capability() {
         ttl_seconds: None,
         exclusive: None,
         reason: None,
+        notify_on_release: None,
     };
 
     let result = files::reserve_file_impl(&ctx, &mm, params).await;
@@ -375,6 +375,7 @@ async fn test_list_reservations_impl_with_reservations() {
         ttl_seconds: Some(3600),
         exclusive: Some(true),
         reason: Some("Testing".to_string()),
+        notify_on_release: None,
     };
     files::reserve_file_impl(&ctx, &mm, reserve_params)
         .await
@@ -408,6 +409,7 @@ async fn test_release_reservation_impl_success() {
         ttl_seconds: Some(3600),
         exclusive: Some(true),
         reason: Some("To be released".to_string()),
+        notify_on_release: None,
     };
     files::reserve_file_impl(&ctx, &mm, reserve_params)
         .await
@@ -442,6 +444,7 @@ async fn test_renew_file_reservation_impl_success() {
         ttl_seconds: Some(100), // Short TTL
         exclusive: Some(true),
         reason: Some("To be renewed".to_string()),
+        notify_on_release: None,
     };
     files::reserve_file_impl(&ctx, &mm, reserve_params)
         .await
@@ -477,6 +480,7 @@ async fn test_force_release_reservation_impl_success() {
         ttl_seconds: Some(3600),
         exclusive: Some(true),
         reason: Some("To be force released".to_string()),
+        notify_on_release: None,
     };
     files::reserve_file_impl(&ctx, &mm, reserve_params)
         .await
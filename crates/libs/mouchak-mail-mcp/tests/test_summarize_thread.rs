@@ -16,6 +16,7 @@ use mouchak_mail_core::model::{
     message::{MessageBmc, MessageForCreate},
     project::ProjectBmc,
 };
+use mouchak_mail_core::types::ProjectId;
 use std::sync::Arc;
 use tempfile::TempDir;
 
@@ -30,14 +31,9 @@ async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
     let conn = db.connect().unwrap();
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
 
-    let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema1).await.unwrap();
-    let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema2).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
 
     let app_config = Arc::new(AppConfig::default());
     let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -71,9 +67,9 @@ async fn setup_test_data(mm: &ModelManager) -> (i64, i64, i64) {
     let agent2_id = AgentBmc::create(&ctx, mm, agent2_c).await.unwrap();
 
     let msg1 = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: agent1_id.into(),
-        recipient_ids: vec![agent2_id.into()],
+        project_id,
+        sender_id: agent1_id,
+        recipient_ids: vec![agent2_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Thread 1 Subject".to_string(),
@@ -85,9 +81,9 @@ async fn setup_test_data(mm: &ModelManager) -> (i64, i64, i64) {
     MessageBmc::create(&ctx, mm, msg1).await.unwrap();
 
     let msg2 = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: agent2_id.into(),
-        recipient_ids: vec![agent1_id.into()],
+        project_id,
+        sender_id: agent2_id,
+        recipient_ids: vec![agent1_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Re: Thread 1 Subject".to_string(),
@@ -99,9 +95,9 @@ async fn setup_test_data(mm: &ModelManager) -> (i64, i64, i64) {
     MessageBmc::create(&ctx, mm, msg2).await.unwrap();
 
     let msg3 = MessageForCreate {
-        project_id: project_id.into(),
-        sender_id: agent1_id.into(),
-        recipient_ids: vec![agent2_id.into()],
+        project_id,
+        sender_id: agent1_id,
+        recipient_ids: vec![agent2_id],
         cc_ids: None,
         bcc_ids: None,
         subject: "Thread 2 Subject".to_string(),
@@ -156,6 +152,7 @@ fn test_summarize_result_structure() {
     let result = SummarizeResult {
         summaries: vec![ThreadSummaryItem {
             thread_id: "THREAD-001".to_string(),
+            global_thread_id: None,
             subject: "Test Subject".to_string(),
             message_count: 5,
             participants: vec!["alice".to_string(), "bob".to_string()],
@@ -181,7 +178,7 @@ async fn test_summarize_single_thread() {
     let (project_id, _, _) = setup_test_data(&mm).await;
     let ctx = Ctx::root_ctx();
 
-    let messages = MessageBmc::list_by_thread(&ctx, &mm, project_id, "THREAD-001")
+    let messages = MessageBmc::list_by_thread(&ctx, &mm, ProjectId::new(project_id), "THREAD-001")
         .await
         .unwrap();
 
@@ -206,7 +203,7 @@ async fn test_summarize_multiple_threads() {
     let mut summaries = Vec::new();
 
     for thread_id in &thread_ids {
-        let messages = MessageBmc::list_by_thread(&ctx, &mm, project_id, thread_id)
+        let messages = MessageBmc::list_by_thread(&ctx, &mm, ProjectId::new(project_id), thread_id)
             .await
             .unwrap();
         summaries.push(((*thread_id).to_string(), messages.len()));
@@ -229,7 +226,8 @@ async fn test_summarize_partial_failure() {
     let mut errors = Vec::new();
 
     for thread_id in &thread_ids {
-        let messages = MessageBmc::list_by_thread(&ctx, &mm, project_id, thread_id).await;
+        let messages =
+            MessageBmc::list_by_thread(&ctx, &mm, ProjectId::new(project_id), thread_id).await;
         match messages {
             Ok(msgs) if !msgs.is_empty() => {
                 successes.push((*thread_id).to_string());
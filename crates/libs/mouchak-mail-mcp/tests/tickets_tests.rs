@@ -0,0 +1,278 @@
+//! Tests for Jira/Linear ticket linking
+//!
+//! Uses a fake server answering both the Jira REST and the Linear GraphQL
+//! endpoints.
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use axum::Json;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use libsql::Builder;
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::{
+    ModelManager,
+    agent::{AgentBmc, AgentForCreate},
+    message::{MessageBmc, MessageForCreate},
+    project::ProjectBmc,
+};
+use mouchak_mail_core::types::AgentId;
+use mouchak_mail_mcp::tools::{GetMessageParams, GetThreadParams, LinkTicketParams};
+use mouchak_mail_mcp::tools::{messaging, tickets};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+async fn create_test_mm(app_config: AppConfig) -> (Arc<ModelManager>, TempDir) {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let db_path = temp_dir.path().join("test_tickets.db");
+    let archive_root = temp_dir.path().join("archive");
+    std::fs::create_dir_all(&archive_root).unwrap();
+
+    let db = Builder::new_local(&db_path).build().await.unwrap();
+    let conn = db.connect().unwrap();
+    let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
+
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
+
+    let mm = ModelManager::new_for_test(conn, archive_root, Arc::new(app_config));
+    (Arc::new(mm), temp_dir)
+}
+
+/// Serves Jira issue `PROJ-7` and Linear issue `ENG-3`, both with the
+/// shared `status`; anything else is not found.
+async fn spawn_fake_trackers(status: Arc<Mutex<String>>) -> String {
+    let jira_status = status.clone();
+    let app = axum::Router::new()
+        .route(
+            "/rest/api/3/issue/{key}",
+            get(
+                move |axum::extract::Path(key): axum::extract::Path<String>,
+                      headers: HeaderMap| {
+                    let status = jira_status.lock().unwrap().clone();
+                    async move {
+                        // Basic auth of "bot@acme.test:jira-token"
+                        let auth = headers.get("authorization").and_then(|v| v.to_str().ok());
+                        if auth != Some("Basic Ym90QGFjbWUudGVzdDpqaXJhLXRva2Vu") {
+                            return (StatusCode::UNAUTHORIZED, Json(json!({})));
+                        }
+                        if key != "PROJ-7" {
+                            return (StatusCode::NOT_FOUND, Json(json!({})));
+                        }
+                        (
+                            StatusCode::OK,
+                            Json(json!({
+                                "key": key,
+                                "fields": { "summary": "Fix the parser", "status": { "name": status } }
+                            })),
+                        )
+                    }
+                },
+            ),
+        )
+        .route(
+            "/graphql",
+            post(move |headers: HeaderMap, Json(body): Json<Value>| {
+                let status = status.lock().unwrap().clone();
+                async move {
+                    assert_eq!(
+                        headers.get("authorization").unwrap().to_str().unwrap(),
+                        "lin_api_key"
+                    );
+                    if body["variables"]["id"] != "ENG-3" {
+                        return Json(json!({ "data": { "issue": null } }));
+                    }
+                    Json(json!({
+                        "data": { "issue": {
+                            "title": "Ship the importer",
+                            "url": "https://linear.app/acme/issue/ENG-3",
+                            "state": { "name": status }
+                        } }
+                    }))
+                }
+            }),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+async fn create_message(mm: &Arc<ModelManager>) -> i64 {
+    let ctx = Ctx::root_ctx();
+    let project_id = ProjectBmc::create(&ctx, mm, "tickets", "/tickets")
+        .await
+        .unwrap();
+    let mut agents = Vec::new();
+    for name in ["BlueLake", "GreenCastle"] {
+        let id = AgentBmc::create(
+            &ctx,
+            mm,
+            AgentForCreate {
+                project_id,
+                name: name.to_string(),
+                program: "test".to_string(),
+                model: "test".to_string(),
+                task_description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        agents.push(id.get());
+    }
+    MessageBmc::create(
+        &ctx,
+        mm,
+        MessageForCreate {
+            project_id,
+            sender_id: (agents[0]).into(),
+            recipient_ids: vec![AgentId::new(agents[1])],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Parser work".to_string(),
+            body_md: "Tracking both tickets".to_string(),
+            thread_id: Some("T-9".to_string()),
+            importance: None,
+            ack_required: false,
+        },
+    )
+    .await
+    .unwrap()
+    .get()
+}
+
+fn link(message_id: i64, provider: &str, ticket_key: &str) -> LinkTicketParams {
+    LinkTicketParams {
+        message_id,
+        provider: provider.to_string(),
+        ticket_key: ticket_key.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_link_ticket_fetches_and_shows_metadata() {
+    let status = Arc::new(Mutex::new("In Progress".to_string()));
+    let base = spawn_fake_trackers(status.clone()).await;
+    let mut config = AppConfig::default();
+    config.tickets.jira_url = Some(format!("{}/", base));
+    config.tickets.jira_email = Some("bot@acme.test".to_string());
+    config.tickets.jira_token = Some("jira-token".to_string());
+    config.tickets.linear_api_key = Some("lin_api_key".to_string());
+    config.tickets.linear_api_url = format!("{}/graphql", base);
+    let (mm, _temp) = create_test_mm(config).await;
+    let ctx = Ctx::root_ctx();
+    let message_id = create_message(&mm).await;
+
+    let result = tickets::link_ticket_impl(&ctx, &mm, link(message_id, "jira", "proj-7"))
+        .await
+        .unwrap();
+    let text = format!("{:?}", result);
+    assert!(text.contains("jira:PROJ-7 [In Progress] Fix the parser"));
+    assert!(text.contains(&format!("{}/browse/PROJ-7", base)));
+    tickets::link_ticket_impl(&ctx, &mm, link(message_id, "Linear", "ENG-3"))
+        .await
+        .unwrap();
+
+    // Fetch failures are recorded instead of failing the link
+    let result = tickets::link_ticket_impl(&ctx, &mm, link(message_id, "jira", "PROJ-404"))
+        .await
+        .unwrap();
+    let text = format!("{:?}", result);
+    assert!(text.contains("Metadata fetch failed: Jira returned 404"));
+
+    assert!(
+        tickets::link_ticket_impl(&ctx, &mm, link(message_id, "github", "PROJ-7"))
+            .await
+            .is_err()
+    );
+    assert!(
+        tickets::link_ticket_impl(&ctx, &mm, link(message_id, "jira", "PROJ"))
+            .await
+            .is_err()
+    );
+    assert!(
+        tickets::link_ticket_impl(&ctx, &mm, link(999_999, "jira", "PROJ-7"))
+            .await
+            .is_err()
+    );
+
+    let result = messaging::get_message_impl(
+        &ctx,
+        &mm,
+        GetMessageParams {
+            message_id,
+            format: None,
+            full: None,
+        },
+    )
+    .await
+    .unwrap();
+    let text = format!("{:?}", result);
+    assert!(text.contains(
+        "Tickets: jira:PROJ-7 [In Progress] Fix the parser; linear:ENG-3 [In Progress] Ship the importer; jira:PROJ-404 (refresh failed)"
+    ));
+
+    // Stale tickets pick up status changes
+    *status.lock().unwrap() = "Done".to_string();
+    mm.db_for_test()
+        .execute(
+            "UPDATE tickets SET fetched_ts = datetime('now', '-1 hour')",
+            (),
+        )
+        .await
+        .unwrap();
+    assert_eq!(tickets::refresh_stale_tickets(&mm).await.unwrap(), 3);
+    assert_eq!(tickets::refresh_stale_tickets(&mm).await.unwrap(), 0);
+
+    let result = messaging::get_thread_impl(
+        &ctx,
+        &mm,
+        GetThreadParams {
+            project_slug: "tickets".to_string(),
+            thread_id: "T-9".to_string(),
+            agent_name: None,
+            unread_only: false,
+        },
+    )
+    .await
+    .unwrap();
+    let text = format!("{:?}", result);
+    assert!(text.contains("jira:PROJ-7 [Done] Fix the parser"));
+    assert!(text.contains("linear:ENG-3 [Done] Ship the importer"));
+}
+
+#[tokio::test]
+async fn test_link_ticket_without_credentials_skips_fetch() {
+    let (mm, _temp) = create_test_mm(AppConfig::default()).await;
+    let ctx = Ctx::root_ctx();
+    let message_id = create_message(&mm).await;
+
+    let result = tickets::link_ticket_impl(&ctx, &mm, link(message_id, "linear", "ENG-3"))
+        .await
+        .unwrap();
+    let text = format!("{:?}", result);
+    assert!(text.contains("linear:ENG-3 (not fetched yet)"));
+    assert!(text.contains("No linear credentials are configured"));
+
+    // Linking twice keeps one link
+    tickets::link_ticket_impl(&ctx, &mm, link(message_id, "linear", "eng-3"))
+        .await
+        .unwrap();
+    let result = messaging::get_message_impl(
+        &ctx,
+        &mm,
+        GetMessageParams {
+            message_id,
+            format: None,
+            full: None,
+        },
+    )
+    .await
+    .unwrap();
+    let text = format!("{:?}", result);
+    assert_eq!(text.matches("linear:ENG-3").count(), 1);
+    assert_eq!(tickets::refresh_stale_tickets(&mm).await.unwrap(), 0);
+}
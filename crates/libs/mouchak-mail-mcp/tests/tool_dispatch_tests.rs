@@ -5,10 +5,12 @@
 
 #![allow(clippy::unwrap_used, clippy::expect_used)]
 
+use libsql::Builder;
 use mouchak_mail_common::config::AppConfig;
 use mouchak_mail_core::model::ModelManager;
 use mouchak_mail_mcp::tools::{MouchakMailService, get_tool_schemas};
 use std::sync::Arc;
+use tempfile::TempDir;
 
 const BUILD_SLOT_TOOLS: &[&str] = &[
     "acquire_build_slot",
@@ -16,6 +18,26 @@ const BUILD_SLOT_TOOLS: &[&str] = &[
     "renew_build_slot",
 ];
 
+/// ModelManager over a database and archive in the returned directory
+async fn create_test_mm() -> (Arc<ModelManager>, TempDir) {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let archive_root = temp_dir.path().join("archive");
+    std::fs::create_dir_all(&archive_root).unwrap();
+
+    let db = Builder::new_local(temp_dir.path().join("test.db"))
+        .build()
+        .await
+        .unwrap();
+    let conn = db.connect().unwrap();
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
+
+    let app_config = Arc::new(AppConfig::default());
+    let mm = ModelManager::new_for_test(conn, archive_root, app_config);
+    (Arc::new(mm), temp_dir)
+}
+
 mod get_tool_schemas_filtering {
     use super::*;
 
@@ -98,11 +120,7 @@ mod service_worktrees_config {
 
     #[tokio::test]
     async fn service_respects_worktrees_enabled_false() {
-        let mm = Arc::new(
-            ModelManager::new(Arc::new(AppConfig::default()))
-                .await
-                .expect("Failed to create ModelManager"),
-        );
+        let (mm, _temp_dir) = create_test_mm().await;
         let service = MouchakMailService::new_with_mm(mm, false);
 
         assert!(
@@ -113,11 +131,7 @@ mod service_worktrees_config {
 
     #[tokio::test]
     async fn service_respects_worktrees_enabled_true() {
-        let mm = Arc::new(
-            ModelManager::new(Arc::new(AppConfig::default()))
-                .await
-                .expect("Failed to create ModelManager"),
-        );
+        let (mm, _temp_dir) = create_test_mm().await;
         let service = MouchakMailService::new_with_mm(mm, true);
 
         assert!(
@@ -181,11 +195,7 @@ mod server_handler_list_tools {
 
     #[tokio::test]
     async fn excludes_build_slot_tools_when_worktrees_disabled() {
-        let mm = Arc::new(
-            ModelManager::new(Arc::new(AppConfig::default()))
-                .await
-                .expect("Failed to create ModelManager"),
-        );
+        let (mm, _temp_dir) = create_test_mm().await;
         let service = MouchakMailService::new_with_mm(mm, false);
 
         let tools = service.list_tools_filtered();
@@ -203,11 +213,7 @@ mod server_handler_list_tools {
 
     #[tokio::test]
     async fn includes_build_slot_tools_when_worktrees_enabled() {
-        let mm = Arc::new(
-            ModelManager::new(Arc::new(AppConfig::default()))
-                .await
-                .expect("Failed to create ModelManager"),
-        );
+        let (mm, _temp_dir) = create_test_mm().await;
         let service = MouchakMailService::new_with_mm(mm, true);
 
         let tools = service.list_tools_filtered();
@@ -229,11 +235,7 @@ mod build_slot_rejection {
 
     #[tokio::test]
     async fn rejects_build_slot_tools_when_worktrees_disabled() {
-        let mm = Arc::new(
-            ModelManager::new(Arc::new(AppConfig::default()))
-                .await
-                .expect("Failed to create ModelManager"),
-        );
+        let (mm, _temp_dir) = create_test_mm().await;
         let service = MouchakMailService::new_with_mm(mm, false);
 
         for build_slot_tool in BUILD_SLOT_TOOLS {
@@ -256,11 +258,7 @@ mod build_slot_rejection {
 
     #[tokio::test]
     async fn allows_build_slot_tools_when_worktrees_enabled() {
-        let mm = Arc::new(
-            ModelManager::new(Arc::new(AppConfig::default()))
-                .await
-                .expect("Failed to create ModelManager"),
-        );
+        let (mm, _temp_dir) = create_test_mm().await;
         let service = MouchakMailService::new_with_mm(mm, true);
 
         for build_slot_tool in BUILD_SLOT_TOOLS {
@@ -276,11 +274,7 @@ mod build_slot_rejection {
 
     #[tokio::test]
     async fn allows_non_build_slot_tools_regardless_of_worktrees() {
-        let mm = Arc::new(
-            ModelManager::new(Arc::new(AppConfig::default()))
-                .await
-                .expect("Failed to create ModelManager"),
-        );
+        let (mm, _temp_dir) = create_test_mm().await;
         let service_disabled = MouchakMailService::new_with_mm(mm.clone(), false);
         let service_enabled = MouchakMailService::new_with_mm(mm, true);
 
@@ -1,12 +1,44 @@
 use axum::Router;
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, post, put};
 
 use crate::AppState;
 use crate::tools;
 
+pub mod approvals;
 pub mod attachments;
+pub mod avatar;
+pub mod broadcasts;
+pub mod ci;
+pub mod contact_policy;
+pub mod custom_fields;
+pub mod dead_letters;
+pub mod events;
 pub mod export;
+pub mod exports;
+pub mod external_tools;
+pub mod github;
+pub mod inbox_delta;
+pub mod inbox_stream;
+pub mod ingest;
+pub mod labels;
+pub mod me;
+pub mod outbox_review;
+pub mod preferences;
+pub mod public_ids;
+pub mod push;
+pub mod receipts;
+pub mod render;
+pub mod saved_searches;
+pub mod search;
+pub mod sla;
+pub mod team_graph;
+pub mod threads;
+pub mod triage;
 pub mod unified_inbox;
+pub mod usage;
+pub mod version;
+pub mod webhooks;
+pub mod ws;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -16,6 +48,138 @@ pub fn routes() -> Router<AppState> {
         // ..
         // Export
         .route("/api/export", post(export::export_mailbox))
+        // Bulk exports (async jobs)
+        .route(
+            "/api/exports",
+            get(exports::list_export_jobs).post(exports::create_export_job),
+        )
+        .route("/api/exports/{job_id}", get(exports::get_export_job))
+        .route(
+            "/api/exports/{job_id}/download",
+            get(exports::download_export),
+        )
+        // Project contact policies (admin)
+        .route(
+            "/api/admin/projects/{project_slug}/contact_policy",
+            get(contact_policy::get_contact_policy).post(contact_policy::set_contact_rule),
+        )
+        .route(
+            "/api/admin/projects/{project_slug}/contact_policy/{rule_id}",
+            delete(contact_policy::delete_contact_rule),
+        )
+        // Team graphs: agents, capabilities and contacts (admin)
+        .route(
+            "/api/admin/projects/{project_slug}/team_graph",
+            get(team_graph::export_team_graph).post(team_graph::import_team_graph),
+        )
+        // Agent SLAs (admin)
+        .route(
+            "/api/admin/projects/{project_slug}/slas",
+            get(sla::list_slas).post(sla::set_sla),
+        )
+        .route(
+            "/api/admin/projects/{project_slug}/slas/{sla_id}",
+            delete(sla::delete_sla),
+        )
+        // Approval-gated sends (admin)
+        .route(
+            "/api/admin/projects/{project_slug}/approval_rules",
+            get(approvals::list_approval_rules).post(approvals::create_approval_rule),
+        )
+        .route(
+            "/api/admin/projects/{project_slug}/approval_rules/{rule_id}",
+            delete(approvals::delete_approval_rule),
+        )
+        .route(
+            "/api/admin/projects/{project_slug}/pending_messages",
+            get(approvals::list_pending_messages),
+        )
+        .route(
+            "/api/admin/projects/{project_slug}/pending_messages/{message_id}",
+            post(approvals::decide_pending_message),
+        )
+        // Dead letters: undeliverable sends (admin)
+        .route(
+            "/api/admin/dead_letters",
+            get(dead_letters::list_dead_letters),
+        )
+        .route(
+            "/api/admin/dead_letters/{dead_letter_id}",
+            get(dead_letters::get_dead_letter).delete(dead_letters::discard_dead_letter),
+        )
+        .route(
+            "/api/admin/dead_letters/{dead_letter_id}/requeue",
+            post(dead_letters::requeue_dead_letter),
+        )
+        // Custom message fields (admin)
+        .route(
+            "/api/admin/projects/{project_slug}/custom_fields",
+            get(custom_fields::list_custom_fields).post(custom_fields::define_custom_field),
+        )
+        .route(
+            "/api/admin/projects/{project_slug}/custom_fields/{name}",
+            delete(custom_fields::delete_custom_field),
+        )
+        // External MCP tools (admin)
+        .route(
+            "/api/admin/external_tools",
+            get(external_tools::list_external_tools).post(external_tools::register_external_tool),
+        )
+        .route(
+            "/api/admin/external_tools/{name}",
+            delete(external_tools::delete_external_tool),
+        )
+        // GitHub cross-posting (admin)
+        .route(
+            "/api/admin/projects/{project_slug}/github",
+            get(github::get_github)
+                .put(github::set_github_repo)
+                .delete(github::delete_github_repo),
+        )
+        .route(
+            "/api/admin/projects/{project_slug}/github/threads",
+            post(github::mirror_thread),
+        )
+        .route(
+            "/api/admin/projects/{project_slug}/github/threads/{thread_id}",
+            delete(github::unlink_thread),
+        )
+        .route("/api/admin/github/sync", post(github::sync_github))
+        // CI build status ingestion
+        .route("/api/integrations/ci", post(ci::ingest_ci_event))
+        // Inbound email gateway
+        .route("/api/ingest/email", post(ingest::ingest_email))
+        // Event log
+        .route("/api/ws", get(ws::event_stream))
+        .route("/api/events", get(events::list_events))
+        .route("/api/events/export", get(events::export_events))
+        .route("/api/events/verify", get(events::verify_events))
+        // Web Push
+        .route("/api/push/vapid-public-key", get(push::vapid_public_key))
+        .route(
+            "/api/push/subscriptions",
+            post(push::subscribe).delete(push::unsubscribe),
+        )
+        // Webhooks
+        .route(
+            "/api/webhooks",
+            get(webhooks::list_webhooks).post(webhooks::create_webhook),
+        )
+        .route(
+            "/api/webhooks/{webhook_id}",
+            get(webhooks::get_webhook).delete(webhooks::delete_webhook),
+        )
+        .route(
+            "/api/webhooks/{webhook_id}/deliveries",
+            get(webhooks::list_deliveries),
+        )
+        // UI preferences
+        .route(
+            "/api/preferences",
+            get(preferences::get_preferences).put(preferences::update_preferences),
+        )
+        // Rendering
+        .route("/api/render/markdown", post(render::render_markdown))
         // Attachments
         .route("/api/health", get(tools::health_check))
         .route("/api/health_check", get(tools::health_check)) // Python alias
@@ -31,6 +195,22 @@ pub fn routes() -> Router<AppState> {
             get(tools::list_all_agents_for_project),
         )
         .route("/api/list_agents", get(tools::list_all_agents_for_project)) // Python alias
+        // Broadcast acknowledgment tracking
+        .route(
+            "/api/projects/{project_slug}/broadcasts",
+            get(broadcasts::list_broadcasts),
+        )
+        .route(
+            "/api/projects/{project_slug}/broadcasts/{message_id}",
+            get(broadcasts::get_broadcast_status),
+        )
+        // SLA compliance
+        .route(
+            "/api/projects/{project_slug}/sla_report",
+            get(sla::get_sla_report),
+        )
+        // Storage usage and quotas
+        .route("/api/projects/{project_slug}/usage", get(usage::get_usage))
         // Delete operations
         .route(
             "/api/projects/{project_slug}",
@@ -41,6 +221,17 @@ pub fn routes() -> Router<AppState> {
             delete(tools::delete_agent),
         )
         // Identity
+        .route("/api/me", get(me::me))
+        .route(
+            "/api/public-ids/{public_id}",
+            get(public_ids::resolve_public_id),
+        )
+        .route("/api/agents/{id}/avatar.svg", get(avatar::agent_avatar))
+        // `{id}` is the project slug here: the router needs one parameter name per position
+        .route(
+            "/api/agents/{id}/{agent_name}/inbox/stream",
+            get(inbox_stream::inbox_stream),
+        )
         .route("/api/agent/register", post(tools::register_agent))
         .route("/api/register_agent", post(tools::register_agent)) // Python alias
         .route("/api/agent/whois", post(tools::whois))
@@ -64,6 +255,43 @@ pub fn routes() -> Router<AppState> {
         .route("/api/acknowledge_message", post(tools::acknowledge_message)) // Python alias
         .route("/api/messages/search", post(tools::search_messages))
         .route("/api/search_messages", post(tools::search_messages)) // Python alias
+        .route("/api/search", get(tools::search))
+        .route(
+            "/api/projects/{project_slug}/search/facets",
+            get(search::search_facets),
+        )
+        .route(
+            "/api/projects/{project_slug}/labels",
+            get(labels::list_labels).post(labels::create_label),
+        )
+        .route(
+            "/api/projects/{project_slug}/labels/{name}",
+            put(labels::update_label).delete(labels::delete_label),
+        )
+        .route(
+            "/api/projects/{project_slug}/labels/{name}/messages",
+            get(labels::list_label_messages),
+        )
+        .route(
+            "/api/projects/{project_slug}/messages/{message_id}/labels",
+            post(labels::apply_label),
+        )
+        .route(
+            "/api/projects/{project_slug}/messages/{message_id}/labels/{name}",
+            delete(labels::remove_label),
+        )
+        .route(
+            "/api/projects/{project_slug}/saved-searches",
+            get(saved_searches::list_saved_searches).post(saved_searches::save_search),
+        )
+        .route(
+            "/api/projects/{project_slug}/saved-searches/{id}",
+            delete(saved_searches::delete_saved_search),
+        )
+        .route(
+            "/api/projects/{project_slug}/saved-searches/{id}/messages",
+            get(saved_searches::run_saved_search),
+        )
         // Pending Reviews (ack_required messages awaiting acknowledgment)
         .route(
             "/api/messages/pending-reviews",
@@ -71,6 +299,8 @@ pub fn routes() -> Router<AppState> {
         )
         .route("/api/pending_reviews", get(tools::list_pending_reviews)) // Python alias
         .route("/api/inbox", post(tools::list_inbox))
+        .route("/api/inbox/triage", post(triage::triage_step))
+        .route("/api/inbox/delta", post(inbox_delta::inbox_delta))
         .route("/api/fetch_inbox", post(tools::list_inbox)) // Python alias
         .route("/api/list_inbox", post(tools::list_inbox)) // Python alias
         .route("/api/get_inbox", post(tools::list_inbox)) // Python alias
@@ -78,12 +308,25 @@ pub fn routes() -> Router<AppState> {
         .route("/api/fetch_outbox", post(tools::list_outbox)) // Python alias
         .route("/api/list_outbox", post(tools::list_outbox)) // Python alias
         .route("/api/get_outbox", post(tools::list_outbox)) // Python alias
+        .route(
+            "/api/outbox/needs-attention",
+            post(outbox_review::outbox_needs_attention),
+        )
         .route("/api/messages/{message_id}", get(tools::get_message))
+        .route(
+            "/api/messages/{message_id}/receipts",
+            get(receipts::get_message_receipts),
+        )
         .route("/api/get_message/{message_id}", get(tools::get_message)) // Python alias
         .route("/api/thread", post(tools::get_thread))
         .route("/api/get_thread", post(tools::get_thread)) // Python alias
         .route("/api/threads", post(tools::list_threads))
         .route("/api/list_threads", post(tools::list_threads)) // Python alias
+        // Reply tree of a thread
+        .route(
+            "/api/threads/{thread_id}/tree",
+            get(threads::get_thread_tree),
+        )
         // File Reservations
         .route(
             "/api/file_reservations/paths",
@@ -102,6 +345,10 @@ pub fn routes() -> Router<AppState> {
             post(tools::list_file_reservations),
         ) // Python alias
         .route("/api/reservations", post(tools::list_file_reservations)) // Python alias (short)
+        .route(
+            "/api/file_reservations/check_paths",
+            post(tools::check_paths),
+        )
         // File Locks API (cross-project view for web UI dashboard)
         .route("/mail/api/locks", get(tools::list_all_locks))
         .route("/api/locks", get(tools::list_all_locks)) // Alias without /mail prefix
@@ -129,6 +376,10 @@ pub fn routes() -> Router<AppState> {
             "/api/force_release_reservation",
             post(tools::force_release_reservation),
         ) // Python alias (short)
+        .route(
+            "/api/file_reservations/release_set",
+            post(tools::release_reservation_set),
+        )
         .route(
             "/api/file_reservations/renew",
             post(tools::renew_file_reservation),
@@ -141,6 +392,29 @@ pub fn routes() -> Router<AppState> {
         .route("/api/project/info", post(tools::get_project_info))
         .route("/api/get_project_info", post(tools::get_project_info)) // Python alias
         .route("/api/project_info", post(tools::get_project_info)) // Python alias (short)
+        .route("/api/project/focus_window", post(tools::set_focus_window))
+        .route("/api/set_focus_window", post(tools::set_focus_window)) // MCP tool name alias
+        .route("/api/project/settings", post(tools::set_project_settings))
+        .route(
+            "/api/set_project_settings",
+            post(tools::set_project_settings),
+        ) // MCP tool name alias
+        .route(
+            "/api/message_templates/set",
+            post(tools::set_message_template),
+        )
+        .route(
+            "/api/set_message_template",
+            post(tools::set_message_template),
+        ) // MCP tool name alias
+        .route(
+            "/api/message_templates",
+            post(tools::list_message_templates),
+        )
+        .route(
+            "/api/list_message_templates",
+            post(tools::list_message_templates),
+        ) // MCP tool name alias
         .route("/api/quota/status", post(tools::get_quota_status))
         .route("/api/get_quota_status", post(tools::get_quota_status)) // Python alias
         .route("/api/agent/profile", post(tools::get_agent_profile))
@@ -176,6 +450,14 @@ pub fn routes() -> Router<AppState> {
             "/api/send_overseer_message",
             post(tools::send_overseer_message),
         ) // Python alias
+        .route(
+            "/api/projects/{project_slug}/overseer",
+            get(tools::list_overseer_messages),
+        )
+        .route(
+            "/api/overseer/mark_read",
+            post(tools::mark_overseer_message_read),
+        )
         // Macros
         .route("/api/macros/list", post(tools::list_macros))
         .route("/api/list_macros", post(tools::list_macros)) // Python alias
@@ -236,7 +518,10 @@ pub fn routes() -> Router<AppState> {
         ) // Python alias (short)
         // Attachments
         // Attachments
-        .route("/api/attachments", get(attachments::list_attachments))
+        .route(
+            "/api/attachments",
+            get(attachments::list_attachments).post(attachments::upload_attachment),
+        ) // POST streams the body into the attachment store
         .route("/api/attachments/add", post(attachments::add_attachment))
         .route("/api/add_attachment", post(attachments::add_attachment)) // Python alias
         .route("/api/attachments/get", get(attachments::get_attachment)) // Changed to GET
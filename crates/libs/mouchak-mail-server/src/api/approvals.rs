@@ -0,0 +1,178 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::approval::{
+    ApprovalBmc, ApprovalRule, ApprovalRuleForCreate, PendingApproval,
+};
+use mouchak_mail_core::model::listing::parse_duration_secs;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::{ApprovalRuleId, MessageId};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Default time a held message waits for a decision.
+pub const DEFAULT_APPROVAL_TIMEOUT: &str = "24h";
+
+#[derive(Deserialize, ToSchema)]
+pub struct ApprovalRulePayload {
+    /// Hold messages of this importance or higher: `low`, `normal`, `high` or `urgent`
+    pub min_importance: Option<String>,
+    /// Hold messages with at least this many to, cc and bcc recipients
+    pub min_recipients: Option<i64>,
+    /// Hold messages whose subject or body contains this text (ignoring case)
+    pub keyword: Option<String>,
+    /// Agents who may approve; empty lets any agent but the sender
+    #[serde(default)]
+    pub approvers: Vec<String>,
+    /// Reject undecided messages after this long, e.g. `30m`, `4h` (default 24h)
+    pub timeout: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ApprovalDecisionPayload {
+    /// `true` delivers the message, `false` drops it
+    pub approve: bool,
+    /// Why, kept with the decision
+    pub reason: Option<String>,
+}
+
+/// Lists a project's approval rules.
+#[utoipa::path(
+    get,
+    path = "/api/admin/projects/{project_slug}/approval_rules",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 200, description = "The project's approval rules", body = Vec<Object>)
+    )
+)]
+pub async fn list_approval_rules(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<Json<Vec<ApprovalRule>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let rules = ApprovalBmc::list_rules(&ctx, &state.mm, project.id).await?;
+    Ok(Json(rules))
+}
+
+/// Adds an approval rule; matching messages are held until decided.
+#[utoipa::path(
+    post,
+    path = "/api/admin/projects/{project_slug}/approval_rules",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = ApprovalRulePayload,
+    responses(
+        (status = 200, description = "The project's approval rules after the change", body = Vec<Object>),
+        (status = 400, description = "No condition, unknown importance or approver, or invalid timeout")
+    )
+)]
+pub async fn create_approval_rule(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(payload): Json<ApprovalRulePayload>,
+) -> crate::error::Result<Json<Vec<ApprovalRule>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let timeout = payload
+        .timeout
+        .as_deref()
+        .unwrap_or(DEFAULT_APPROVAL_TIMEOUT);
+    ApprovalBmc::create_rule(
+        &ctx,
+        &state.mm,
+        ApprovalRuleForCreate {
+            project_id: project.id,
+            min_importance: payload.min_importance,
+            min_recipients: payload.min_recipients,
+            keyword: payload.keyword,
+            approvers: payload.approvers,
+            timeout_seconds: parse_duration_secs(timeout)?,
+        },
+    )
+    .await?;
+    list_approval_rules(State(state), Path(project_slug)).await
+}
+
+/// Removes an approval rule; messages it already holds stay held.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/projects/{project_slug}/approval_rules/{rule_id}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("rule_id" = i64, Path, description = "Approval rule ID")
+    ),
+    responses(
+        (status = 204, description = "Rule removed"),
+        (status = 404, description = "No such rule in the project")
+    )
+)]
+pub async fn delete_approval_rule(
+    State(state): State<AppState>,
+    Path((project_slug, rule_id)): Path<(String, i64)>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    ApprovalBmc::delete_rule(&ctx, &state.mm, project.id, ApprovalRuleId::new(rule_id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists a project's messages waiting for approval.
+#[utoipa::path(
+    get,
+    path = "/api/admin/projects/{project_slug}/pending_messages",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 200, description = "Held messages, oldest first", body = Vec<Object>)
+    )
+)]
+pub async fn list_pending_messages(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<Json<Vec<PendingApproval>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let pending = ApprovalBmc::list_pending(&ctx, &state.mm, project.id).await?;
+    Ok(Json(pending))
+}
+
+/// Approves or rejects a held message as a human operator.
+#[utoipa::path(
+    post,
+    path = "/api/admin/projects/{project_slug}/pending_messages/{message_id}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("message_id" = i64, Path, description = "Held message ID")
+    ),
+    request_body = ApprovalDecisionPayload,
+    responses(
+        (status = 200, description = "The decided approval", body = Object),
+        (status = 400, description = "Message was already decided"),
+        (status = 404, description = "Message is not held in the project")
+    )
+)]
+pub async fn decide_pending_message(
+    State(state): State<AppState>,
+    Path((project_slug, message_id)): Path<(String, i64)>,
+    Json(payload): Json<ApprovalDecisionPayload>,
+) -> crate::error::Result<Json<PendingApproval>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    match ApprovalBmc::get(&ctx, &state.mm, MessageId::new(message_id)).await? {
+        Some(pending) if pending.project_id == project.id => {}
+        _ => return Err(mouchak_mail_core::Error::NotFound.into()),
+    }
+    let decided = ApprovalBmc::decide(
+        &ctx,
+        &state.mm,
+        MessageId::new(message_id),
+        None,
+        payload.approve,
+        payload.reason.as_deref(),
+    )
+    .await?;
+    Ok(Json(decided))
+}
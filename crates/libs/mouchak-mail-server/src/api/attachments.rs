@@ -1,6 +1,6 @@
 use crate::AppState;
 use crate::auth::AuthenticatedUser;
-use axum::http::header;
+use axum::http::{HeaderMap, header};
 use axum::{
     Extension, Json,
     body::Body,
@@ -8,13 +8,12 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use base64::Engine;
+use futures::TryStreamExt;
 use mouchak_mail_core::Ctx;
 use mouchak_mail_core::model::agent::AgentBmc;
-use mouchak_mail_core::model::attachment::{AttachmentBmc, AttachmentForCreate};
+use mouchak_mail_core::model::attachment::{AttachmentBmc, AttachmentUpload};
 use mouchak_mail_core::model::project::ProjectBmc;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tokio::fs;
 use tracing::warn;
 use utoipa::ToSchema;
 
@@ -33,8 +32,14 @@ pub struct AddAttachmentResponse {
     pub id: i64,
     pub filename: String,
     pub size: i64,
+    /// Hex BLAKE3 hash of the content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
 }
 
+/// Header carrying the hex BLAKE3 hash of downloaded content, when known.
+pub const CONTENT_HASH_HEADER: &str = "x-content-blake3";
+
 #[utoipa::path(
     post,
     path = "/api/attachments/add",
@@ -81,54 +86,130 @@ pub async fn add_attachment(
         .decode(b64)
         .map_err(|e| crate::ServerError::BadRequest(format!("Invalid base64: {}", e)))?;
 
-    let size = content.len() as i64;
-    // Max size check (10MB)
-    if size > 10 * 1024 * 1024 {
-        return Err(crate::ServerError::BadRequest(
-            "File too large (>10MB)".into(),
-        ));
-    }
-
-    // 3. Prepare Storage Logic
-    // Sanitize filename
+    // 3. Sanitize filename
     let filename = sanitize_filename::sanitize(&payload.filename);
     if filename.is_empty() {
         return Err(crate::ServerError::BadRequest("Invalid filename".into()));
     }
 
-    // Determine path: data/attachments/<project_id>/<uuid>_<filename>
-    let attachment_root = std::env::current_dir()?
-        .join("data")
-        .join("attachments")
-        .join(project.id.to_string());
-    fs::create_dir_all(&attachment_root).await?;
-
-    let unique_id = uuid::Uuid::new_v4();
-    let stored_filename = format!("{}_{}", unique_id, filename);
-    let stored_path = attachment_root.join(&stored_filename);
-
-    // 4. Write File
-    fs::write(&stored_path, &content).await?;
-
-    // 5. Create DB Record
-    // Infer media type manually or naive
+    // 4. Store content and create DB record
+    let size = content.len() as i64;
     let mime = mime_guess::from_path(&filename).first_or_octet_stream();
-
-    let id = AttachmentBmc::create(
+    let id = AttachmentBmc::store(
         &ctx,
         mm,
-        AttachmentForCreate {
+        AttachmentUpload {
             project_id: project.id.get(),
             agent_id: agent_id.map(|id| id.get()),
             filename: filename.clone(),
-            stored_path: stored_path.to_string_lossy().to_string(),
             media_type: mime.to_string(),
-            size_bytes: size,
         },
+        Box::new(std::io::Cursor::new(content)),
     )
     .await?;
 
-    Ok(Json(AddAttachmentResponse { id, filename, size }).into_response())
+    let attachment = AttachmentBmc::get(&ctx, mm, id).await?;
+    Ok(Json(AddAttachmentResponse {
+        id,
+        filename,
+        size,
+        content_hash: attachment.content_hash,
+    })
+    .into_response())
+}
+
+/// Path of streamed uploads, which are exempt from `MAX_REQUEST_SIZE_MB`.
+pub const UPLOAD_PATH: &str = "/api/attachments";
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct UploadAttachmentParams {
+    pub project_slug: String,
+    pub filename: String,
+    /// Optional agent name that uploaded this file.
+    #[serde(default)]
+    pub agent_name: Option<String>,
+}
+
+/// Streams the request body into the attachment store.
+///
+/// Unlike `/api/attachments/add` the content is sent as is rather than
+/// base64 in JSON, and is never held in memory as a whole. The media type
+/// comes from `Content-Type`, or from the filename when that is missing or
+/// `application/octet-stream`.
+#[utoipa::path(
+    post,
+    path = "/api/attachments",
+    params(UploadAttachmentParams),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream", description = "File content"),
+    responses(
+        (status = 200, description = "Attachment stored", body = AddAttachmentResponse),
+        (status = 400, description = "Invalid filename, or content larger than ATTACHMENTS_MAX_BYTES")
+    )
+)]
+pub async fn upload_attachment(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthenticatedUser>>,
+    Query(params): Query<UploadAttachmentParams>,
+    headers: HeaderMap,
+    body: Body,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &state.mm;
+
+    if auth_user.is_none() {
+        warn!(
+            "upload_attachment called without authenticated user for project: {}",
+            params.project_slug
+        );
+    }
+
+    let project = ProjectBmc::get_by_identifier(&ctx, mm, &params.project_slug).await?;
+    let agent_id = if let Some(ref agent_name) = params.agent_name {
+        let agent = AgentBmc::get_by_name(&ctx, mm, project.id, agent_name).await?;
+        Some(agent.id.get())
+    } else {
+        None
+    };
+
+    let filename = sanitize_filename::sanitize(&params.filename);
+    if filename.is_empty() {
+        return Err(crate::ServerError::BadRequest("Invalid filename".into()));
+    }
+    let media_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|ct| !ct.is_empty() && *ct != "application/octet-stream")
+        .map_or_else(
+            || {
+                mime_guess::from_path(&filename)
+                    .first_or_octet_stream()
+                    .to_string()
+            },
+            str::to_string,
+        );
+
+    let stream = body.into_data_stream().map_err(std::io::Error::other);
+    let id = AttachmentBmc::store(
+        &ctx,
+        mm,
+        AttachmentUpload {
+            project_id: project.id.get(),
+            agent_id,
+            filename,
+            media_type,
+        },
+        Box::new(tokio_util::io::StreamReader::new(stream)),
+    )
+    .await?;
+
+    let attachment = AttachmentBmc::get(&ctx, mm, id).await?;
+    Ok(Json(AddAttachmentResponse {
+        id,
+        filename: attachment.filename,
+        size: attachment.size_bytes,
+        content_hash: attachment.content_hash,
+    })
+    .into_response())
 }
 
 #[derive(Deserialize, utoipa::IntoParams)]
@@ -170,13 +251,8 @@ pub async fn list_attachments(
         None
     };
 
-    let items = AttachmentBmc::list_by_project_and_agent(
-        &ctx,
-        &state.mm,
-        project.id.get(),
-        agent_id.map(|id| id.get()),
-    )
-    .await?;
+    let items =
+        AttachmentBmc::list_by_project_and_agent(&ctx, &state.mm, project.id, agent_id).await?;
     Ok(Json(items).into_response())
 }
 
@@ -231,23 +307,28 @@ pub async fn get_attachment(
         );
     }
 
-    let path = PathBuf::from(&attachment.stored_path);
-    if !path.exists() {
-        return Err(crate::ServerError::NotFound(
-            "File on disk not found".into(),
-        ));
-    }
-
-    let file = tokio::fs::File::open(path).await?;
-    let stream = tokio_util::io::ReaderStream::new(file);
+    let reader = AttachmentBmc::open(&ctx, mm, &attachment)
+        .await
+        .map_err(|e| match e {
+            mouchak_mail_core::Error::NotFound => {
+                crate::ServerError::NotFound("Attachment content not found".into())
+            }
+            e => e.into(),
+        })?;
+    let stream = tokio_util::io::ReaderStream::new(reader);
     let body = Body::from_stream(stream);
 
-    let response = Response::builder()
+    let mut response = Response::builder()
         .header(header::CONTENT_TYPE, attachment.media_type)
+        .header(header::CONTENT_LENGTH, attachment.size_bytes)
         .header(
             header::CONTENT_DISPOSITION,
             format!("attachment; filename=\"{}\"", attachment.filename),
-        )
+        );
+    if let Some(hash) = attachment.content_hash {
+        response = response.header(CONTENT_HASH_HEADER, hash);
+    }
+    let response = response
         .body(body)
         .map_err(|e| crate::ServerError::Internal(format!("Failed to build response: {}", e)))?
         .into_response();
@@ -0,0 +1,74 @@
+use crate::AppState;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::types::AgentId;
+use mouchak_mail_core::utils::identicon::{DEFAULT_SIZE, identicon_hash, identicon_svg};
+use serde::Deserialize;
+
+/// Avatars only change if the agent is renamed, so let browsers keep them a day.
+const AVATAR_CACHE_CONTROL: &str = "public, max-age=86400";
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct AvatarParams {
+    /// Edge length in pixels (16-512, default 64)
+    pub size: Option<u32>,
+}
+
+/// Returns a deterministic identicon for an agent.
+///
+/// The image is derived from the agent's name and program. Responses carry
+/// an `ETag`, and a matching `If-None-Match` gets `304 Not Modified`.
+#[utoipa::path(
+    get,
+    path = "/api/agents/{id}/avatar.svg",
+    params(
+        ("id" = i64, Path, description = "Agent ID"),
+        AvatarParams
+    ),
+    responses(
+        (status = 200, description = "SVG identicon", content_type = "image/svg+xml", body = String),
+        (status = 304, description = "Cached identicon is current"),
+        (status = 404, description = "Agent not found")
+    )
+)]
+pub async fn agent_avatar(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(params): Query<AvatarParams>,
+    headers: HeaderMap,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let agent = AgentBmc::get(&ctx, &state.mm, AgentId::new(id)).await?;
+    let size = params.size.unwrap_or(DEFAULT_SIZE);
+
+    let etag = format!(
+        "\"{}-{}\"",
+        identicon_hash(&agent.name, &agent.program),
+        size
+    );
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag));
+
+    let builder = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::CACHE_CONTROL, AVATAR_CACHE_CONTROL);
+    let response = if not_modified {
+        builder.status(StatusCode::NOT_MODIFIED).body(Body::empty())
+    } else {
+        builder
+            .header(header::CONTENT_TYPE, "image/svg+xml")
+            .body(Body::from(identicon_svg(&agent.name, &agent.program, size)))
+    };
+
+    Ok(response
+        .map_err(|e| crate::ServerError::Internal(format!("Failed to build response: {}", e)))?
+        .into_response())
+}
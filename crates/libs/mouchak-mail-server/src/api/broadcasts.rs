@@ -0,0 +1,73 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::broadcast_status::{
+    BroadcastStatus, BroadcastStatusBmc, BroadcastSummary,
+};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::MessageId;
+use serde::Deserialize;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct BroadcastListParams {
+    /// Maximum messages to return (default 50)
+    pub limit: Option<i64>,
+}
+
+/// Lists a project's ack-required messages with how many recipients acked.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/broadcasts",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        BroadcastListParams
+    ),
+    responses(
+        (status = 200, description = "Ack-required messages, newest first", body = Vec<Object>)
+    )
+)]
+pub async fn list_broadcasts(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Query(params): Query<BroadcastListParams>,
+) -> crate::error::Result<Json<Vec<BroadcastSummary>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let summaries = BroadcastStatusBmc::list_for_project(
+        &ctx,
+        &state.mm,
+        project.id,
+        params.limit.unwrap_or(50),
+    )
+    .await?;
+    Ok(Json(summaries))
+}
+
+/// Shows which recipients of a message acked it, with time-to-ack stats.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/broadcasts/{message_id}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("message_id" = i64, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Acked and pending recipients", body = Object),
+        (status = 404, description = "No such message in the project")
+    )
+)]
+pub async fn get_broadcast_status(
+    State(state): State<AppState>,
+    Path((project_slug, message_id)): Path<(String, i64)>,
+) -> crate::error::Result<Json<BroadcastStatus>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let status = BroadcastStatusBmc::get(&ctx, &state.mm, MessageId::new(message_id)).await?;
+    if status.project_id != project.id {
+        return Err(mouchak_mail_core::Error::MessageNotFound(message_id).into());
+    }
+    Ok(Json(status))
+}
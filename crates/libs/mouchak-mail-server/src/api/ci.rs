@@ -0,0 +1,64 @@
+use crate::AppState;
+use axum::{Json, extract::State};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::ci_status::{CiDelivery, CiEvent, CiStatusBmc};
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CiEventPayload {
+    /// CI system, e.g. "github-actions", "buildkite"
+    pub provider: String,
+    /// Repository as `owner/name`
+    pub repo: String,
+    /// Full commit SHA
+    pub sha: String,
+    /// pending, running, success, failure, error or cancelled
+    pub status: String,
+    /// Link to the build
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Project to notify when no thread is linked to the commit; defaults
+    /// to projects mapped to `repo`
+    #[serde(default)]
+    pub project_slug: Option<String>,
+}
+
+/// Ingests a normalized CI build event.
+///
+/// The event is posted to every thread linked to its commit. Without a
+/// linked thread, a failing build goes to the overseer inbox instead.
+#[utoipa::path(
+    post,
+    path = "/api/integrations/ci",
+    request_body = CiEventPayload,
+    responses(
+        (status = 200, description = "Where the event was posted", body = Object),
+        (status = 400, description = "Malformed event, or no linked thread and no project to notify"),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn ingest_ci_event(
+    State(state): State<AppState>,
+    Json(payload): Json<CiEventPayload>,
+) -> crate::error::Result<Json<CiDelivery>> {
+    let ctx = Ctx::root_ctx();
+    let project_id = match &payload.project_slug {
+        Some(slug) => Some(
+            ProjectBmc::get_by_identifier(&ctx, &state.mm, slug)
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+    let event = CiEvent {
+        provider: payload.provider,
+        repo: payload.repo,
+        sha: payload.sha,
+        status: payload.status.parse()?,
+        url: payload.url,
+    };
+    let delivery = CiStatusBmc::ingest(&ctx, &state.mm, &event, project_id).await?;
+    Ok(Json(delivery))
+}
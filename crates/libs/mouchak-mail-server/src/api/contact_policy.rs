@@ -0,0 +1,112 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::project_contact_policy::{
+    ContactRuleAction, ContactRuleTarget, ProjectContactPolicyBmc, ProjectContactRule,
+    ProjectContactRuleForCreate,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct ContactRulePayload {
+    /// `allow` or `deny`
+    #[schema(value_type = String)]
+    pub action: ContactRuleAction,
+    /// `project` (slug, or `*` for any project) or `product` (product uid)
+    #[schema(value_type = String)]
+    pub target_kind: ContactRuleTarget,
+    pub target: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ContactPolicyResponse {
+    pub project_slug: String,
+    /// Cross-project contact not matched by an allow rule is denied
+    pub default_action: String,
+    #[schema(value_type = Vec<Object>)]
+    pub rules: Vec<ProjectContactRule>,
+}
+
+/// Lists which other projects may contact a project.
+#[utoipa::path(
+    get,
+    path = "/api/admin/projects/{project_slug}/contact_policy",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 200, description = "The project's contact rules", body = ContactPolicyResponse)
+    )
+)]
+pub async fn get_contact_policy(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<Json<ContactPolicyResponse>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let rules = ProjectContactPolicyBmc::list_rules(&ctx, &state.mm, project.id).await?;
+    Ok(Json(ContactPolicyResponse {
+        project_slug: project.slug,
+        default_action: ContactRuleAction::Deny.as_str().to_string(),
+        rules,
+    }))
+}
+
+/// Adds a contact rule, or changes the action of the rule for the same target.
+#[utoipa::path(
+    post,
+    path = "/api/admin/projects/{project_slug}/contact_policy",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = ContactRulePayload,
+    responses(
+        (status = 200, description = "The project's contact rules after the change", body = ContactPolicyResponse),
+        (status = 400, description = "Invalid rule")
+    )
+)]
+pub async fn set_contact_rule(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(payload): Json<ContactRulePayload>,
+) -> crate::error::Result<Json<ContactPolicyResponse>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    ProjectContactPolicyBmc::set_rule(
+        &ctx,
+        &state.mm,
+        ProjectContactRuleForCreate {
+            project_id: project.id,
+            action: payload.action,
+            target_kind: payload.target_kind,
+            target: payload.target,
+        },
+    )
+    .await?;
+    get_contact_policy(State(state), Path(project_slug)).await
+}
+
+/// Removes a contact rule.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/projects/{project_slug}/contact_policy/{rule_id}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("rule_id" = i64, Path, description = "Rule ID")
+    ),
+    responses(
+        (status = 204, description = "Rule removed"),
+        (status = 404, description = "No such rule in the project")
+    )
+)]
+pub async fn delete_contact_rule(
+    State(state): State<AppState>,
+    Path((project_slug, rule_id)): Path<(String, i64)>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    ProjectContactPolicyBmc::delete_rule(&ctx, &state.mm, project.id, rule_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
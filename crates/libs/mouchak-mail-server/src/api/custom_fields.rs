@@ -0,0 +1,96 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::custom_field::{CustomField, CustomFieldBmc, CustomFieldForCreate};
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CustomFieldPayload {
+    /// Lowercase letters, digits and underscores, e.g. `component`
+    pub name: String,
+    /// `string`, `number` or `boolean`
+    pub field_type: String,
+    /// Whether new messages must set the field (default: false)
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Lists a project's custom message fields.
+#[utoipa::path(
+    get,
+    path = "/api/admin/projects/{project_slug}/custom_fields",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 200, description = "The project's custom fields", body = Vec<Object>)
+    )
+)]
+pub async fn list_custom_fields(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<Json<Vec<CustomField>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let fields = CustomFieldBmc::list(&ctx, &state.mm, project.id).await?;
+    Ok(Json(fields))
+}
+
+/// Declares a custom field, or changes its type or requiredness.
+#[utoipa::path(
+    post,
+    path = "/api/admin/projects/{project_slug}/custom_fields",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = CustomFieldPayload,
+    responses(
+        (status = 200, description = "The project's custom fields after the change", body = Vec<Object>),
+        (status = 400, description = "Invalid name or type")
+    )
+)]
+pub async fn define_custom_field(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(payload): Json<CustomFieldPayload>,
+) -> crate::error::Result<Json<Vec<CustomField>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    CustomFieldBmc::define(
+        &ctx,
+        &state.mm,
+        CustomFieldForCreate {
+            project_id: project.id,
+            name: payload.name,
+            field_type: payload.field_type.parse()?,
+            required: payload.required,
+        },
+    )
+    .await?;
+    list_custom_fields(State(state), Path(project_slug)).await
+}
+
+/// Removes a custom field. Values stored on messages are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/projects/{project_slug}/custom_fields/{name}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("name" = String, Path, description = "Field name")
+    ),
+    responses(
+        (status = 204, description = "Field removed"),
+        (status = 404, description = "No such field in the project")
+    )
+)]
+pub async fn delete_custom_field(
+    State(state): State<AppState>,
+    Path((project_slug, name)): Path<(String, String)>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    CustomFieldBmc::delete(&ctx, &state.mm, project.id, &name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
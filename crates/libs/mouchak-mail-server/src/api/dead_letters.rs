@@ -0,0 +1,137 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::dead_letter::{
+    DeadLetter, DeadLetterBmc, DeadLetterFilter, DeadLetterRequeue,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct DeadLetterListParams {
+    /// Only sends addressed to this project
+    pub project: Option<String>,
+    /// Also list dead letters already delivered by a requeue (default false)
+    #[serde(default)]
+    pub include_requeued: bool,
+    /// Maximum dead letters to return (default 50)
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RequeuePayload {
+    /// Deliver in this project instead, e.g. the one it was adopted into
+    pub project_slug: Option<String>,
+    /// Send as this agent instead
+    pub sender_name: Option<String>,
+    /// Replacement recipients
+    pub recipient_names: Option<Vec<String>>,
+    /// Replacement CC recipients
+    pub cc_names: Option<Vec<String>>,
+    /// Replacement BCC recipients
+    pub bcc_names: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct RequeueResponse {
+    pub dead_letter_id: i64,
+    pub message_id: i64,
+}
+
+/// Lists sends that could not be delivered, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/admin/dead_letters",
+    params(DeadLetterListParams),
+    responses(
+        (status = 200, description = "Dead letters, oldest first", body = Vec<Object>)
+    )
+)]
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    Query(params): Query<DeadLetterListParams>,
+) -> crate::error::Result<Json<Vec<DeadLetter>>> {
+    let ctx = Ctx::root_ctx();
+    let filter = DeadLetterFilter {
+        project_slug: params.project,
+        include_requeued: params.include_requeued,
+        limit: params.limit,
+    };
+    let letters = DeadLetterBmc::list(&ctx, &state.mm, &filter).await?;
+    Ok(Json(letters))
+}
+
+/// Shows a dead letter with its envelope, body and last error.
+#[utoipa::path(
+    get,
+    path = "/api/admin/dead_letters/{dead_letter_id}",
+    params(("dead_letter_id" = i64, Path, description = "Dead letter ID")),
+    responses(
+        (status = 200, description = "The dead letter", body = Object),
+        (status = 404, description = "No such dead letter")
+    )
+)]
+pub async fn get_dead_letter(
+    State(state): State<AppState>,
+    Path(dead_letter_id): Path<i64>,
+) -> crate::error::Result<Json<DeadLetter>> {
+    let ctx = Ctx::root_ctx();
+    let letter = DeadLetterBmc::get(&ctx, &state.mm, dead_letter_id).await?;
+    Ok(Json(letter))
+}
+
+/// Delivers a dead letter again, replacing the project, sender or
+/// recipients given in the body (`{}` keeps them all).
+#[utoipa::path(
+    post,
+    path = "/api/admin/dead_letters/{dead_letter_id}/requeue",
+    params(("dead_letter_id" = i64, Path, description = "Dead letter ID")),
+    request_body = RequeuePayload,
+    responses(
+        (status = 200, description = "The message it was delivered as", body = Object),
+        (status = 400, description = "Already requeued"),
+        (status = 404, description = "No such dead letter, or its project or an agent still does not resolve")
+    )
+)]
+pub async fn requeue_dead_letter(
+    State(state): State<AppState>,
+    Path(dead_letter_id): Path<i64>,
+    Json(payload): Json<RequeuePayload>,
+) -> crate::error::Result<Json<RequeueResponse>> {
+    let ctx = Ctx::root_ctx();
+    let changes = DeadLetterRequeue {
+        project_slug: payload.project_slug,
+        sender_name: payload.sender_name,
+        to: payload.recipient_names,
+        cc: payload.cc_names,
+        bcc: payload.bcc_names,
+    };
+    let message_id = DeadLetterBmc::requeue(&ctx, &state.mm, dead_letter_id, changes).await?;
+    Ok(Json(RequeueResponse {
+        dead_letter_id,
+        message_id,
+    }))
+}
+
+/// Drops a dead letter without delivering it.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/dead_letters/{dead_letter_id}",
+    params(("dead_letter_id" = i64, Path, description = "Dead letter ID")),
+    responses(
+        (status = 204, description = "Dead letter dropped"),
+        (status = 404, description = "No such dead letter")
+    )
+)]
+pub async fn discard_dead_letter(
+    State(state): State<AppState>,
+    Path(dead_letter_id): Path<i64>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    DeadLetterBmc::discard(&ctx, &state.mm, dead_letter_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,92 @@
+use crate::AppState;
+use axum::http::header;
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::event_log::{ChainVerification, EventLogBmc, EventRecord};
+use serde::Deserialize;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct EventListParams {
+    /// Return records after this id (default 0)
+    pub after_id: Option<i64>,
+    /// Maximum records to return (default 100, max 1000)
+    pub limit: Option<i64>,
+}
+
+/// Lists event log records in chain order.
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    params(EventListParams),
+    responses(
+        (status = 200, description = "Event log records", body = Vec<EventRecord>)
+    )
+)]
+pub async fn list_events(
+    State(state): State<AppState>,
+    Query(params): Query<EventListParams>,
+) -> crate::error::Result<Json<Vec<EventRecord>>> {
+    let ctx = Ctx::root_ctx();
+    let records = EventLogBmc::list(
+        &ctx,
+        &state.mm,
+        params.after_id.unwrap_or(0),
+        params.limit.unwrap_or(100),
+    )
+    .await?;
+    Ok(Json(records))
+}
+
+/// Exports the whole event log as JSON Lines, one record per line.
+///
+/// The export can be checked offline with `mouchak-mail events verify-chain --file`.
+#[utoipa::path(
+    get,
+    path = "/api/events/export",
+    responses(
+        (status = 200, description = "Event log chain", content_type = "application/x-ndjson", body = String)
+    )
+)]
+pub async fn export_events(State(state): State<AppState>) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let records = EventLogBmc::export(&ctx, &state.mm).await?;
+
+    let mut body = String::new();
+    for record in &records {
+        body.push_str(&serde_json::to_string(record).map_err(|e| {
+            crate::ServerError::Internal(format!("Failed to serialize event: {}", e))
+        })?);
+        body.push('\n');
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"event-log.jsonl\"",
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Verifies the hash chain of the stored event log.
+#[utoipa::path(
+    get,
+    path = "/api/events/verify",
+    responses(
+        (status = 200, description = "Verification result", body = ChainVerification)
+    )
+)]
+pub async fn verify_events(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<ChainVerification>> {
+    let ctx = Ctx::root_ctx();
+    Ok(Json(EventLogBmc::verify(&ctx, &state.mm).await?))
+}
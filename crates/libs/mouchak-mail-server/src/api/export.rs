@@ -13,7 +13,7 @@ use utoipa::ToSchema;
 #[derive(Deserialize, ToSchema)]
 pub struct ExportPayload {
     pub project_slug: String,
-    pub format: String, // "json", "html", "md", "csv"
+    pub format: String, // "json", "html", "md", "csv", "mbox"
 }
 
 // Note: for now keeping handler signatures simple for utoipa
@@ -42,22 +42,14 @@ pub async fn export_mailbox(
         &payload.project_slug,
         format,
         ScrubMode::None,
-        false,
+        true,
     )
     .await?;
 
-    // Determine content type and extension
-    let (content_type, ext) = match format {
-        ExportFormat::Html => ("text/html", "html"),
-        ExportFormat::Json => ("application/json", "json"),
-        ExportFormat::Markdown => ("text/markdown", "md"),
-        ExportFormat::Csv => ("text/csv", "csv"),
-    };
-
-    let filename = format!("{}_mailbox.{}", payload.project_slug, ext);
+    let filename = format!("{}_mailbox.{}", payload.project_slug, format.extension());
 
     let response = Response::builder()
-        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_TYPE, format.media_type())
         .header(
             header::CONTENT_DISPOSITION,
             format!("attachment; filename=\"{}\"", filename),
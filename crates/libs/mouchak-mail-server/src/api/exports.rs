@@ -0,0 +1,197 @@
+use crate::AppState;
+use axum::body::Body;
+use axum::http::{StatusCode, header};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::export::{ExportFilter, ExportFormat, ScrubMode};
+use mouchak_mail_core::model::export_job::{
+    DEFAULT_LIST_LIMIT, ExportJob, ExportJobBmc, ExportJobForCreate, STATUS_COMPLETED,
+};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::ExportJobId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExportJobPayload {
+    pub project_slug: String,
+    /// "json" (default), "html", "md", "csv" or "mbox"
+    pub format: Option<String>,
+    /// "none" (default), "standard" or "aggressive"
+    pub scrub: Option<String>,
+    /// Include attachments in mbox exports; ignored when scrubbing
+    #[serde(default)]
+    pub include_attachments: bool,
+    /// Messages to include: since, until, thread_id, sender, importance, limit
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub filters: ExportFilter,
+}
+
+/// An export job and, once it completed, where to download its file.
+#[derive(Serialize)]
+pub struct ExportJobView {
+    #[serde(flatten)]
+    pub job: ExportJob,
+    pub download_url: Option<String>,
+}
+
+impl From<ExportJob> for ExportJobView {
+    fn from(job: ExportJob) -> Self {
+        let download_url =
+            (job.status == STATUS_COMPLETED).then(|| format!("/api/exports/{}/download", job.id));
+        Self { job, download_url }
+    }
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct ExportJobListParams {
+    /// Only jobs of this project
+    pub project: Option<String>,
+    /// Maximum jobs to return (default 50)
+    pub limit: Option<i64>,
+}
+
+/// Queues an export of a project's mailbox.
+///
+/// The export runs in the background; poll `GET /api/exports/{job_id}`
+/// until `status` is `completed`, then fetch `download_url`.
+#[utoipa::path(
+    post,
+    path = "/api/exports",
+    request_body = ExportJobPayload,
+    responses(
+        (status = 202, description = "The queued job", body = Object),
+        (status = 400, description = "Invalid filter, format or scrub mode"),
+        (status = 404, description = "No such project")
+    )
+)]
+pub async fn create_export_job(
+    State(state): State<AppState>,
+    Json(payload): Json<ExportJobPayload>,
+) -> crate::error::Result<(StatusCode, Json<ExportJobView>)> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &payload.project_slug).await?;
+    let job_c = ExportJobForCreate {
+        project_id: project.id,
+        format: match payload.format.as_deref() {
+            Some(name) => ExportFormat::from_name(name).ok_or_else(|| {
+                crate::ServerError::BadRequest(format!("Unknown export format: {}", name))
+            })?,
+            None => ExportFormat::Json,
+        },
+        scrub: match payload.scrub.as_deref() {
+            Some(name) => ScrubMode::from_name(name).ok_or_else(|| {
+                crate::ServerError::BadRequest(format!("Unknown scrub mode: {}", name))
+            })?,
+            None => ScrubMode::None,
+        },
+        include_attachments: payload.include_attachments,
+        filters: payload.filters,
+    };
+    let job = ExportJobBmc::create(&ctx, &state.mm, job_c).await?;
+    Ok((StatusCode::ACCEPTED, Json(job.into())))
+}
+
+/// Lists export jobs, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/exports",
+    params(ExportJobListParams),
+    responses(
+        (status = 200, description = "Export jobs", body = Vec<Object>),
+        (status = 404, description = "No such project")
+    )
+)]
+pub async fn list_export_jobs(
+    State(state): State<AppState>,
+    Query(params): Query<ExportJobListParams>,
+) -> crate::error::Result<Json<Vec<ExportJobView>>> {
+    let ctx = Ctx::root_ctx();
+    let project_id = match params.project.as_deref() {
+        Some(slug) => Some(
+            ProjectBmc::get_by_identifier(&ctx, &state.mm, slug)
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+    let jobs = ExportJobBmc::list(
+        &ctx,
+        &state.mm,
+        project_id,
+        params.limit.unwrap_or(DEFAULT_LIST_LIMIT),
+    )
+    .await?;
+    Ok(Json(jobs.into_iter().map(ExportJobView::from).collect()))
+}
+
+/// Shows the status of an export job.
+#[utoipa::path(
+    get,
+    path = "/api/exports/{job_id}",
+    params(("job_id" = i64, Path, description = "Export job ID")),
+    responses(
+        (status = 200, description = "The job, with download_url once completed", body = Object),
+        (status = 404, description = "No such job")
+    )
+)]
+pub async fn get_export_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<i64>,
+) -> crate::error::Result<Json<ExportJobView>> {
+    let ctx = Ctx::root_ctx();
+    let job = ExportJobBmc::get(&ctx, &state.mm, ExportJobId::new(job_id)).await?;
+    Ok(Json(job.into()))
+}
+
+/// Downloads the file of a completed export job.
+#[utoipa::path(
+    get,
+    path = "/api/exports/{job_id}/download",
+    params(("job_id" = i64, Path, description = "Export job ID")),
+    responses(
+        (status = 200, description = "The exported mailbox", body = String),
+        (status = 404, description = "No such job, or its file expired"),
+        (status = 409, description = "The job has not completed")
+    )
+)]
+pub async fn download_export(
+    State(state): State<AppState>,
+    Path(job_id): Path<i64>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let job = ExportJobBmc::get(&ctx, &state.mm, ExportJobId::new(job_id)).await?;
+    if job.status != STATUS_COMPLETED {
+        return Err(crate::ServerError::Conflict(format!(
+            "Export job {} is {}",
+            job.id, job.status
+        )));
+    }
+
+    let file = match tokio::fs::File::open(ExportJobBmc::file_path(&state.mm, &job)).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(crate::ServerError::NotFound("Export file not found".into()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let length = file.metadata().await?.len();
+    let body = Body::from_stream(tokio_util::io::ReaderStream::new(file));
+
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, job.export_format().media_type())
+        .header(header::CONTENT_LENGTH, length)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", job.filename()),
+        )
+        .body(body)
+        .map_err(|e| crate::ServerError::Internal(format!("Failed to build response: {}", e)))?;
+
+    Ok(response.into_response())
+}
@@ -0,0 +1,112 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::external_tool::{
+    ExternalTool, ExternalToolBmc, ExternalToolForCreate,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExternalToolPayload {
+    /// Tool name shown to MCP clients, e.g. `jira_search`
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// JSON schema of the arguments (default: any object)
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub input_schema: Option<Map<String, Value>>,
+    /// Endpoint receiving `POST {"tool", "arguments"}`
+    pub url: String,
+    /// Sent as the `Authorization` header, e.g. `Bearer <token>`
+    #[serde(default)]
+    pub auth_header: Option<String>,
+    /// Default: 10000, max 120000
+    #[serde(default)]
+    pub timeout_ms: Option<i64>,
+    /// Response bytes returned to the caller (default: 65536, max 1048576)
+    #[serde(default)]
+    pub max_result_bytes: Option<i64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// Lists registered external tools. Auth headers are never returned.
+#[utoipa::path(
+    get,
+    path = "/api/admin/external_tools",
+    responses(
+        (status = 200, description = "Registered external tools", body = Vec<Object>)
+    )
+)]
+pub async fn list_external_tools(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<ExternalTool>>> {
+    let ctx = Ctx::root_ctx();
+    let tools = ExternalToolBmc::list(&ctx, &state.mm).await?;
+    Ok(Json(tools))
+}
+
+/// Registers an external tool, replacing any tool with the same name.
+#[utoipa::path(
+    post,
+    path = "/api/admin/external_tools",
+    request_body = ExternalToolPayload,
+    responses(
+        (status = 200, description = "The registered tool", body = Object),
+        (status = 400, description = "Invalid name, URL, schema or limits")
+    )
+)]
+pub async fn register_external_tool(
+    State(state): State<AppState>,
+    Json(payload): Json<ExternalToolPayload>,
+) -> crate::error::Result<Json<ExternalTool>> {
+    let ctx = Ctx::root_ctx();
+    let name = payload.name.clone();
+    ExternalToolBmc::register(
+        &ctx,
+        &state.mm,
+        ExternalToolForCreate {
+            name: payload.name,
+            description: payload.description,
+            input_schema: payload.input_schema,
+            url: payload.url,
+            auth_header: payload.auth_header,
+            timeout_ms: payload.timeout_ms,
+            max_result_bytes: payload.max_result_bytes,
+            enabled: payload.enabled,
+        },
+    )
+    .await?;
+    let tool = ExternalToolBmc::get_by_name(&ctx, &state.mm, &name).await?;
+    Ok(Json(tool))
+}
+
+/// Removes an external tool.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/external_tools/{name}",
+    params(("name" = String, Path, description = "Tool name")),
+    responses(
+        (status = 204, description = "Tool removed"),
+        (status = 404, description = "No such tool")
+    )
+)]
+pub async fn delete_external_tool(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    ExternalToolBmc::delete(&ctx, &state.mm, &name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
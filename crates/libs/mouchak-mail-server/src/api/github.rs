@@ -0,0 +1,174 @@
+use crate::AppState;
+use crate::github;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::github_sync::{GithubSyncBmc, GithubThreadLink, ProjectGithubRepo};
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct GithubRepoPayload {
+    /// Repository as `owner/name`
+    pub repo: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MirrorThreadPayload {
+    pub thread_id: String,
+    /// Existing issue or PR to link; a new issue is opened when omitted
+    #[serde(default)]
+    pub issue_number: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GithubStatus {
+    #[schema(value_type = Option<Object>)]
+    pub repo: Option<ProjectGithubRepo>,
+    #[schema(value_type = Vec<Object>)]
+    pub threads: Vec<GithubThreadLink>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GithubSyncResult {
+    /// Messages posted plus comments imported
+    pub synced: usize,
+}
+
+/// Shows a project's repository mapping and mirrored threads.
+#[utoipa::path(
+    get,
+    path = "/api/admin/projects/{project_slug}/github",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 200, description = "Repository mapping and mirrored threads", body = GithubStatus)
+    )
+)]
+pub async fn get_github(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<Json<GithubStatus>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    Ok(Json(GithubStatus {
+        repo: GithubSyncBmc::get_repo(&ctx, &state.mm, project.id).await?,
+        threads: GithubSyncBmc::list_links(&ctx, &state.mm, Some(project.id)).await?,
+    }))
+}
+
+/// Maps a project to a GitHub repository.
+#[utoipa::path(
+    put,
+    path = "/api/admin/projects/{project_slug}/github",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = GithubRepoPayload,
+    responses(
+        (status = 200, description = "Repository mapping and mirrored threads", body = GithubStatus),
+        (status = 400, description = "Repository is not owner/name")
+    )
+)]
+pub async fn set_github_repo(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(payload): Json<GithubRepoPayload>,
+) -> crate::error::Result<Json<GithubStatus>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    GithubSyncBmc::set_repo(&ctx, &state.mm, project.id, &payload.repo).await?;
+    get_github(State(state), Path(project_slug)).await
+}
+
+/// Removes a project's repository mapping. Mirrored threads keep syncing.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/projects/{project_slug}/github",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 204, description = "Mapping removed"),
+        (status = 404, description = "Project has no mapping")
+    )
+)]
+pub async fn delete_github_repo(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    GithubSyncBmc::clear_repo(&ctx, &state.mm, project.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Mirrors a thread to a new or existing issue or PR, posting its messages.
+///
+/// Mirroring a thread that is already linked posts its new messages.
+#[utoipa::path(
+    post,
+    path = "/api/admin/projects/{project_slug}/github/threads",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = MirrorThreadPayload,
+    responses(
+        (status = 200, description = "The thread's link", body = Object),
+        (status = 400, description = "No repository mapped, issue already linked or GitHub rejected the request"),
+        (status = 404, description = "Thread not found")
+    )
+)]
+pub async fn mirror_thread(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(payload): Json<MirrorThreadPayload>,
+) -> crate::error::Result<Json<GithubThreadLink>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let link = github::mirror_thread(
+        &state.mm,
+        &state.mm.app_config.github,
+        project.id,
+        &payload.thread_id,
+        payload.issue_number,
+    )
+    .await?;
+    Ok(Json(link))
+}
+
+/// Stops syncing a thread. Posted comments and imported messages remain.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/projects/{project_slug}/github/threads/{thread_id}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("thread_id" = String, Path, description = "Thread ID")
+    ),
+    responses(
+        (status = 204, description = "Thread unlinked"),
+        (status = 404, description = "Thread is not mirrored")
+    )
+)]
+pub async fn unlink_thread(
+    State(state): State<AppState>,
+    Path((project_slug, thread_id)): Path<(String, String)>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    GithubSyncBmc::unlink_thread(&ctx, &state.mm, project.id, &thread_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Syncs all mirrored threads now instead of waiting for the next run.
+#[utoipa::path(
+    post,
+    path = "/api/admin/github/sync",
+    responses(
+        (status = 200, description = "Items synced", body = GithubSyncResult),
+        (status = 500, description = "GITHUB_TOKEN is not set")
+    )
+)]
+pub async fn sync_github(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<GithubSyncResult>> {
+    let synced = github::sync_all(&state.mm, &state.mm.app_config.github).await?;
+    Ok(Json(GithubSyncResult { synced }))
+}
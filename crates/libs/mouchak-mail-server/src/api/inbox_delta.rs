@@ -0,0 +1,52 @@
+use crate::AppState;
+use axum::{Json, extract::State};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::inbox_delta::{InboxDelta, InboxDeltaBmc};
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct InboxDeltaPayload {
+    pub project_slug: String,
+    pub agent_name: String,
+    /// `token` from the previous check; omit for an initial snapshot
+    pub since_token: Option<String>,
+    /// Maximum new messages to return (default 50, max 200)
+    pub limit: Option<i64>,
+}
+
+/// Returns what changed in an agent's inbox since a delta token.
+///
+/// Without `since_token` this returns the newest messages as a snapshot.
+/// Pass the returned `token` on the next call to get only new messages and
+/// read/ack changes; when `has_more` is set, call again right away.
+#[utoipa::path(
+    post,
+    path = "/api/inbox/delta",
+    request_body = InboxDeltaPayload,
+    responses(
+        (status = 200, description = "New messages and state changes since the token, with the next token"),
+        (status = 400, description = "Malformed token")
+    )
+)]
+pub async fn inbox_delta(
+    State(state): State<AppState>,
+    Json(payload): Json<InboxDeltaPayload>,
+) -> crate::error::Result<Json<InboxDelta>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &payload.project_slug).await?;
+    let agent = AgentBmc::get_by_name(&ctx, &state.mm, project.id, &payload.agent_name).await?;
+
+    let delta = InboxDeltaBmc::check(
+        &ctx,
+        &state.mm,
+        project.id,
+        agent.id,
+        payload.since_token.as_deref(),
+        payload.limit.unwrap_or(50),
+    )
+    .await?;
+    Ok(Json(delta))
+}
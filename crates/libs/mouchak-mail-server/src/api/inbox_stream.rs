@@ -0,0 +1,202 @@
+//! Server-Sent Events stream of one agent's inbox.
+//!
+//! `/api/agents/{project_slug}/{agent_name}/inbox/stream` lets a coding
+//! agent hold one long-lived request instead of calling `check_inbox` in a
+//! loop. Each SSE event carries an event log record (the shape returned by
+//! `/api/events`) as JSON data, with the record id as the SSE id:
+//!
+//! | SSE event | Sent when |
+//! |-----------|-----------|
+//! | `message-created` | A message reaches the agent as to, cc or bcc recipient, or is approved for it |
+//! | `message-read` | The agent reads (`message.read`) or acknowledges (`message.acknowledged`) a message |
+//! | `stream-lagged` | The client fell behind the server's buffer; `{"missed": N}` |
+//!
+//! Reconnecting clients send `Last-Event-ID` (or `after_id`) and get the
+//! records they missed replayed from the event log first.
+
+use crate::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::Stream;
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::ModelManager;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::event_log::{EventLogBmc, EventRecord, MAX_PAGE_SIZE};
+use mouchak_mail_core::model::message::MessageBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::MessageId;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::debug;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct InboxStreamParams {
+    /// Replay logged events after this id first; `Last-Event-ID` takes precedence
+    pub after_id: Option<i64>,
+}
+
+/// Streams message-created and message-read events of one agent as SSE.
+#[utoipa::path(
+    get,
+    path = "/api/agents/{project_slug}/{agent_name}/inbox/stream",
+    params(
+        ("project_slug" = String, Path, description = "Project slug or human key"),
+        ("agent_name" = String, Path, description = "Agent whose inbox to stream"),
+        InboxStreamParams
+    ),
+    responses(
+        (status = 200, description = "`text/event-stream` of `message-created` and `message-read` events", content_type = "text/event-stream"),
+        (status = 404, description = "Unknown project or agent")
+    )
+)]
+pub async fn inbox_stream(
+    State(state): State<AppState>,
+    Path((project_slug, agent_name)): Path<(String, String)>,
+    Query(params): Query<InboxStreamParams>,
+    headers: HeaderMap,
+) -> crate::error::Result<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let agent = AgentBmc::get_by_name(&ctx, &state.mm, project.id, &agent_name).await?;
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<i64>().ok());
+
+    let inbox = InboxEvents {
+        mm: state.mm.clone(),
+        project_id: project.id.get(),
+        agent: agent.name,
+        // Subscribe before replaying, so no record falls between the two
+        events: state.mm.event_bus().subscribe(),
+        replay_after: last_event_id.or(params.after_id),
+        replayed_id: 0,
+        backlog: VecDeque::new(),
+    };
+    let stream = futures::stream::unfold(inbox, |mut inbox| async move {
+        let event = inbox.next_event().await?;
+        Some((Ok(event), inbox))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Reads the event bus and picks out the records of one agent's inbox.
+struct InboxEvents {
+    mm: ModelManager,
+    project_id: i64,
+    agent: String,
+    events: broadcast::Receiver<Arc<EventRecord>>,
+    /// Logged records after this id still have to be replayed
+    replay_after: Option<i64>,
+    /// Live records at or before this id were already replayed
+    replayed_id: i64,
+    backlog: VecDeque<EventRecord>,
+}
+
+impl InboxEvents {
+    /// The next SSE event for the agent; `None` when the bus closes.
+    async fn next_event(&mut self) -> Option<Event> {
+        loop {
+            let record = match self.backlog.pop_front() {
+                Some(record) => Arc::new(record),
+                None if self.replay_after.is_some() => {
+                    self.fill_backlog().await;
+                    continue;
+                }
+                None => match self.events.recv().await {
+                    Ok(record) if record.id <= self.replayed_id => continue,
+                    Ok(record) => record,
+                    Err(RecvError::Lagged(missed)) => {
+                        let data = serde_json::json!({ "missed": missed });
+                        return Some(
+                            Event::default()
+                                .event("stream-lagged")
+                                .data(data.to_string()),
+                        );
+                    }
+                    Err(RecvError::Closed) => return None,
+                },
+            };
+            let Some(name) = self.event_name(&record).await else {
+                continue;
+            };
+            match serde_json::to_string(&*record) {
+                Ok(json) => {
+                    return Some(
+                        Event::default()
+                            .event(name)
+                            .id(record.id.to_string())
+                            .data(json),
+                    );
+                }
+                Err(e) => debug!(
+                    "Failed to encode {} for an inbox stream: {}",
+                    record.kind, e
+                ),
+            }
+        }
+    }
+
+    /// Loads the next page of logged records to replay.
+    async fn fill_backlog(&mut self) {
+        let Some(after_id) = self.replay_after else {
+            return;
+        };
+        let ctx = Ctx::root_ctx();
+        match EventLogBmc::list(&ctx, &self.mm, after_id, MAX_PAGE_SIZE).await {
+            Ok(page) => {
+                self.replay_after = match page.last() {
+                    Some(last) if (page.len() as i64) == MAX_PAGE_SIZE => Some(last.id),
+                    _ => None,
+                };
+                if let Some(last) = page.last() {
+                    self.replayed_id = last.id;
+                }
+                self.backlog.extend(page);
+            }
+            Err(e) => {
+                debug!("Inbox stream replay failed: {}", e);
+                self.replay_after = None;
+            }
+        }
+    }
+
+    /// The SSE event name of `record` if it concerns the agent's inbox.
+    async fn event_name(&self, record: &EventRecord) -> Option<&'static str> {
+        if record.project_id != Some(self.project_id) {
+            return None;
+        }
+        let by_agent = record.actor.as_deref() == Some(self.agent.as_str());
+        match record.kind.as_str() {
+            "message.read" | "message.acknowledged" if by_agent => Some("message-read"),
+            // The payload names only "to" recipients; cc and bcc are looked up.
+            // Messages held for approval reach inboxes once approved
+            "message.sent" if !by_agent => self.recipient_event(record).await,
+            "message.approved"
+                if record.payload.get("sender").and_then(|s| s.as_str())
+                    != Some(self.agent.as_str()) =>
+            {
+                self.recipient_event(record).await
+            }
+            _ => None,
+        }
+    }
+
+    /// `message-created` if the agent is a recipient of the record's message.
+    async fn recipient_event(&self, record: &EventRecord) -> Option<&'static str> {
+        let message_id = record.payload.get("message_id")?.as_i64()?;
+        let ctx = Ctx::root_ctx();
+        let recipients = MessageBmc::get_recipients(&ctx, &self.mm, MessageId::new(message_id))
+            .await
+            .ok()?;
+        recipients
+            .contains(&self.agent)
+            .then_some("message-created")
+    }
+}
@@ -0,0 +1,31 @@
+use crate::AppState;
+use axum::body::Bytes;
+use axum::{Json, extract::State};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::email_ingest::{EmailIngestBmc, InboundEmail, IngestedEmail};
+
+/// Ingests a raw email (RFC 5322), e.g. piped in by an MTA.
+///
+/// Replies to bridged mail land in their thread; other mail starts a
+/// thread in the project its subject is tagged with (`[project] ...`). The
+/// `From` address must be listed in `email_bridge.inbound_aliases`.
+#[utoipa::path(
+    post,
+    path = "/api/ingest/email",
+    request_body(content = String, content_type = "message/rfc822", description = "The raw mail"),
+    responses(
+        (status = 200, description = "Where the mail was posted, or why it was skipped", body = Object),
+        (status = 400, description = "Unreadable mail, unknown project, or no recipients"),
+        (status = 403, description = "The sender has no inbound alias"),
+        (status = 404, description = "Project or agent not found")
+    )
+)]
+pub async fn ingest_email(
+    State(state): State<AppState>,
+    body: Bytes,
+) -> crate::error::Result<Json<IngestedEmail>> {
+    let ctx = Ctx::root_ctx();
+    let email = InboundEmail::parse(&body)?;
+    let ingested = EmailIngestBmc::ingest(&ctx, &state.mm, &email).await?;
+    Ok(Json(ingested))
+}
@@ -0,0 +1,286 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::label::{Label, LabelBmc, LabelForCreate, LabelForUpdate};
+use mouchak_mail_core::model::message::Message;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::types::MessageId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Messages listed per label when no limit is given.
+const DEFAULT_LABEL_LIMIT: i64 = 50;
+
+/// Largest accepted message limit.
+const MAX_LABEL_LIMIT: i64 = 500;
+
+#[derive(Deserialize, ToSchema)]
+pub struct LabelPayload {
+    /// Lowercase letters, digits, `-`, `_` and `/`, e.g. `needs-review`
+    pub name: String,
+    /// `#rrggbb`; picked by name when omitted
+    pub color: Option<String>,
+    /// What the label means
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LabelUpdatePayload {
+    /// New name
+    pub name: Option<String>,
+    /// New `#rrggbb` color
+    pub color: Option<String>,
+    /// New description
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MessageLabelPayload {
+    /// Project label to apply
+    pub label: String,
+    /// Agent applying it, recorded in the event log
+    pub agent: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct LabelMessagesParams {
+    /// Newest messages returned (default 50, max 500)
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct RemoveLabelParams {
+    /// Agent removing the label, recorded in the event log
+    pub agent: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MessageLabels {
+    pub message_id: i64,
+    pub labels: Vec<String>,
+}
+
+/// Lists a project's labels with their message counts.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/labels",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 200, description = "The project's labels by name", body = Vec<Object>)
+    )
+)]
+pub async fn list_labels(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<Json<Vec<Label>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let labels = LabelBmc::list(&ctx, &state.mm, project.id).await?;
+    Ok(Json(labels))
+}
+
+/// Creates a label.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_slug}/labels",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = LabelPayload,
+    responses(
+        (status = 200, description = "The new label", body = Object),
+        (status = 400, description = "Invalid or duplicate name, or invalid color")
+    )
+)]
+pub async fn create_label(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(payload): Json<LabelPayload>,
+) -> crate::error::Result<Json<Label>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    LabelBmc::create(
+        &ctx,
+        &state.mm,
+        LabelForCreate {
+            project_id: project.id,
+            name: payload.name.clone(),
+            color: payload.color,
+            description: payload.description,
+        },
+    )
+    .await?;
+    let label = LabelBmc::get_by_name(&ctx, &state.mm, project.id, &payload.name).await?;
+    Ok(Json(label))
+}
+
+/// Renames, recolors or redescribes a label.
+#[utoipa::path(
+    put,
+    path = "/api/projects/{project_slug}/labels/{name}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("name" = String, Path, description = "Label name")
+    ),
+    request_body = LabelUpdatePayload,
+    responses(
+        (status = 200, description = "The updated label", body = Object),
+        (status = 400, description = "Invalid or duplicate name, or invalid color"),
+        (status = 404, description = "No such label in the project")
+    )
+)]
+pub async fn update_label(
+    State(state): State<AppState>,
+    Path((project_slug, name)): Path<(String, String)>,
+    Json(payload): Json<LabelUpdatePayload>,
+) -> crate::error::Result<Json<Label>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let label = LabelBmc::update(
+        &ctx,
+        &state.mm,
+        project.id,
+        &name,
+        LabelForUpdate {
+            name: payload.name,
+            color: payload.color,
+            description: payload.description,
+        },
+    )
+    .await?;
+    Ok(Json(label))
+}
+
+/// Deletes a label and removes it from every message.
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_slug}/labels/{name}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("name" = String, Path, description = "Label name")
+    ),
+    responses(
+        (status = 204, description = "Label deleted"),
+        (status = 404, description = "No such label in the project")
+    )
+)]
+pub async fn delete_label(
+    State(state): State<AppState>,
+    Path((project_slug, name)): Path<(String, String)>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    LabelBmc::delete(&ctx, &state.mm, project.id, &name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists the newest messages carrying a label.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/labels/{name}/messages",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("name" = String, Path, description = "Label name"),
+        LabelMessagesParams
+    ),
+    responses(
+        (status = 200, description = "Labeled messages, newest first", body = Vec<Object>),
+        (status = 404, description = "No such label in the project")
+    )
+)]
+pub async fn list_label_messages(
+    State(state): State<AppState>,
+    Path((project_slug, name)): Path<(String, String)>,
+    Query(params): Query<LabelMessagesParams>,
+) -> crate::error::Result<Json<Vec<Message>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_LABEL_LIMIT)
+        .clamp(1, MAX_LABEL_LIMIT);
+    let messages = LabelBmc::list_messages(&ctx, &state.mm, project.id, &name, limit).await?;
+    Ok(Json(messages))
+}
+
+/// Applies a project label to a message.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_slug}/messages/{message_id}/labels",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("message_id" = i64, Path, description = "Message ID")
+    ),
+    request_body = MessageLabelPayload,
+    responses(
+        (status = 200, description = "The message's labels", body = Object),
+        (status = 400, description = "Message belongs to another project"),
+        (status = 404, description = "Unknown message, label or agent")
+    )
+)]
+pub async fn apply_label(
+    State(state): State<AppState>,
+    Path((project_slug, message_id)): Path<(String, i64)>,
+    Json(payload): Json<MessageLabelPayload>,
+) -> crate::error::Result<Json<MessageLabels>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let agent = match &payload.agent {
+        Some(name) => Some(AgentBmc::get_by_name(&ctx, &state.mm, project.id, name).await?),
+        None => None,
+    };
+    LabelBmc::apply(
+        &ctx,
+        &state.mm,
+        project.id,
+        MessageId::new(message_id),
+        &payload.label,
+        agent.as_ref().map(|a| (a.id, a.name.as_str())),
+    )
+    .await?;
+    let labels = LabelBmc::names_for_message(&ctx, &state.mm, MessageId::new(message_id)).await?;
+    Ok(Json(MessageLabels { message_id, labels }))
+}
+
+/// Removes a label from a message.
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_slug}/messages/{message_id}/labels/{name}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("message_id" = i64, Path, description = "Message ID"),
+        ("name" = String, Path, description = "Label name"),
+        RemoveLabelParams
+    ),
+    responses(
+        (status = 200, description = "The message's remaining labels", body = Object),
+        (status = 400, description = "Message belongs to another project"),
+        (status = 404, description = "Unknown message, label or agent")
+    )
+)]
+pub async fn remove_label(
+    State(state): State<AppState>,
+    Path((project_slug, message_id, name)): Path<(String, i64, String)>,
+    Query(params): Query<RemoveLabelParams>,
+) -> crate::error::Result<Json<MessageLabels>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let agent = match &params.agent {
+        Some(agent) => Some(AgentBmc::get_by_name(&ctx, &state.mm, project.id, agent).await?),
+        None => None,
+    };
+    LabelBmc::remove(
+        &ctx,
+        &state.mm,
+        project.id,
+        MessageId::new(message_id),
+        &name,
+        agent.as_ref().map(|a| (a.id, a.name.as_str())),
+    )
+    .await?;
+    let labels = LabelBmc::names_for_message(&ctx, &state.mm, MessageId::new(message_id)).await?;
+    Ok(Json(MessageLabels { message_id, labels }))
+}
@@ -0,0 +1,108 @@
+use crate::AppState;
+use crate::auth::{AuthMethod, AuthenticatedUser, CapabilitiesConfig, ROUTE_CAPABILITIES};
+use crate::session::session_token;
+use axum::http::HeaderMap;
+use axum::{Extension, Json, extract::State};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::agent_capabilities::AgentCapabilityBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct MeResponse {
+    pub auth_method: AuthMethod,
+    /// Principal subject (absent for anonymous and shared bearer-token callers)
+    pub subject: Option<String>,
+    pub agent_name: Option<String>,
+    pub project_slug: Option<String>,
+    /// Whether RBAC capability checks are enforced
+    pub rbac_enabled: bool,
+    /// Capabilities the principal may exercise on capability-gated routes
+    pub roles: Vec<String>,
+    /// CSRF token to send as `X-CSRF-Token` on mutating requests (sessions only)
+    pub csrf_token: Option<String>,
+    /// Sessions end after this many seconds without requests (sessions only)
+    pub session_idle_timeout_seconds: Option<u64>,
+}
+
+/// Reports who the caller is authenticated as.
+///
+/// Browser sessions also receive their CSRF token here, which the web UI
+/// echoes on every mutating request.
+#[utoipa::path(
+    get,
+    path = "/api/me",
+    responses(
+        (status = 200, description = "Authenticated principal and effective roles", body = MeResponse)
+    )
+)]
+pub async fn me(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthenticatedUser>>,
+    auth_method: Option<Extension<AuthMethod>>,
+    headers: HeaderMap,
+) -> crate::error::Result<Json<MeResponse>> {
+    let user = auth_user.map(|Extension(user)| user);
+    let auth_method = auth_method.map_or(AuthMethod::Anonymous, |Extension(method)| method);
+    let rbac_enabled = CapabilitiesConfig::default().enabled;
+    let roles = effective_roles(&state, user.as_ref(), rbac_enabled).await?;
+
+    let session = match (auth_method, session_token(&headers)) {
+        (AuthMethod::Session, Some(token)) => state.sessions.get(&token).await,
+        _ => None,
+    };
+
+    Ok(Json(MeResponse {
+        auth_method,
+        subject: user.as_ref().map(|u| u.subject.clone()),
+        agent_name: user.as_ref().and_then(|u| u.agent_name.clone()),
+        project_slug: user.as_ref().and_then(|u| u.project_slug.clone()),
+        rbac_enabled,
+        roles,
+        session_idle_timeout_seconds: session
+            .as_ref()
+            .map(|_| state.sessions.idle_timeout().as_secs()),
+        csrf_token: session.map(|s| s.csrf_token),
+    }))
+}
+
+/// Capabilities the capabilities middleware would grant this principal.
+///
+/// Without RBAC every gated route is open. With RBAC only principals bound to
+/// an agent pass, using the union of that agent's grants across projects (as
+/// the middleware does).
+async fn effective_roles(
+    state: &AppState,
+    user: Option<&AuthenticatedUser>,
+    rbac_enabled: bool,
+) -> crate::error::Result<Vec<String>> {
+    if !rbac_enabled {
+        return Ok(ROUTE_CAPABILITIES.iter().map(|c| c.to_string()).collect());
+    }
+    let Some(user) = user else {
+        return Ok(Vec::new());
+    };
+    let Some(agent_name) = user.agent_name.as_deref() else {
+        return Ok(Vec::new());
+    };
+
+    let ctx = Ctx::root_ctx();
+    let projects = match user.project_slug.as_deref() {
+        Some(slug) => vec![ProjectBmc::get_by_slug(&ctx, &state.mm, slug).await?],
+        None => ProjectBmc::list_all(&ctx, &state.mm).await?,
+    };
+
+    let mut roles = BTreeSet::new();
+    for project in projects {
+        let Ok(agent) = AgentBmc::get_by_name(&ctx, &state.mm, project.id, agent_name).await else {
+            continue;
+        };
+        for capability in AgentCapabilityBmc::list_for_agent(&ctx, &state.mm, agent.id).await? {
+            roles.insert(capability.capability);
+        }
+    }
+    Ok(roles.into_iter().collect())
+}
@@ -0,0 +1,73 @@
+use crate::AppState;
+use axum::{Json, extract::State};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::listing::parse_duration_secs;
+use mouchak_mail_core::model::outbox_review::{
+    OutboxReviewBmc, OutboxReviewItem, ReviewThresholds,
+};
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Messages reviewed when no limit is given.
+const DEFAULT_REVIEW_LIMIT: i64 = 50;
+
+/// Largest accepted message limit.
+const MAX_REVIEW_LIMIT: i64 = 500;
+
+#[derive(Deserialize, ToSchema)]
+pub struct OutboxReviewPayload {
+    pub project_slug: String,
+    /// Sender whose outbox is reviewed
+    pub agent_name: String,
+    /// How long a recipient may leave a message unread, e.g. `4h` (default 4h)
+    pub unread_after: Option<String>,
+    /// How long a recipient may leave an ack-required message
+    /// unacknowledged, e.g. `1d` (default 24h)
+    pub unacked_after: Option<String>,
+    /// Messages returned (default 50, max 500)
+    pub limit: Option<i64>,
+}
+
+/// Lists an agent's sent messages whose recipients have not read or
+/// acknowledged them in time, oldest first.
+///
+/// Each message lists its pending recipients and suggested follow-ups:
+/// remind, request a reply deadline, escalate or reassign.
+#[utoipa::path(
+    post,
+    path = "/api/outbox/needs-attention",
+    request_body = OutboxReviewPayload,
+    responses(
+        (status = 200, description = "Sent messages with pending recipients and suggested follow-ups", body = Vec<Object>),
+        (status = 400, description = "Invalid threshold"),
+        (status = 404, description = "Unknown project or agent")
+    )
+)]
+pub async fn outbox_needs_attention(
+    State(state): State<AppState>,
+    Json(payload): Json<OutboxReviewPayload>,
+) -> crate::error::Result<Json<Vec<OutboxReviewItem>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &payload.project_slug).await?;
+    let agent = AgentBmc::get_by_name(&ctx, &state.mm, project.id, &payload.agent_name).await?;
+
+    let mut thresholds = ReviewThresholds::default();
+    if let Some(d) = payload.unread_after.as_deref() {
+        thresholds.unread_after_secs = parse_duration_secs(d)?;
+    }
+    if let Some(d) = payload.unacked_after.as_deref() {
+        thresholds.unacked_after_secs = parse_duration_secs(d)?;
+    }
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_REVIEW_LIMIT)
+        .clamp(1, MAX_REVIEW_LIMIT);
+    let limit = usize::try_from(limit).unwrap_or(1);
+
+    let items =
+        OutboxReviewBmc::needs_attention(&ctx, &state.mm, project.id, agent.id, thresholds, limit)
+            .await?;
+    Ok(Json(items))
+}
@@ -0,0 +1,116 @@
+use crate::AppState;
+use crate::auth::AuthenticatedUser;
+use crate::error::ServerError;
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::ui_preference::{PreferenceOwner, Preferences, UiPreferenceBmc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Owner used for user preferences when the request is not authenticated.
+const LOCAL_USER: &str = "local";
+
+/// Selects whose preferences to use.
+///
+/// With `project_slug` and `agent_name`, the agent's preferences; otherwise
+/// the authenticated user's (or the shared `local` user's without auth).
+#[derive(Deserialize, utoipa::IntoParams, ToSchema)]
+pub struct PreferenceOwnerParams {
+    pub project_slug: Option<String>,
+    pub agent_name: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdatePreferencesPayload {
+    #[serde(default)]
+    pub project_slug: Option<String>,
+    #[serde(default)]
+    pub agent_name: Option<String>,
+    /// Preferences to set; a `null` value removes the key
+    #[schema(value_type = Object)]
+    pub preferences: Preferences,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PreferencesResponse {
+    /// Owner key (`user:<subject>` or `agent:<id>`)
+    pub owner: String,
+    #[schema(value_type = Object)]
+    pub preferences: Preferences,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/preferences",
+    params(PreferenceOwnerParams),
+    responses(
+        (status = 200, description = "Stored preferences", body = PreferencesResponse)
+    )
+)]
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthenticatedUser>>,
+    Query(params): Query<PreferenceOwnerParams>,
+) -> crate::error::Result<Json<PreferencesResponse>> {
+    let ctx = Ctx::root_ctx();
+    let owner = resolve_owner(&state, auth_user, params).await?;
+    let preferences = UiPreferenceBmc::get(&ctx, &state.mm, &owner).await?;
+    Ok(Json(PreferencesResponse {
+        owner: owner.key(),
+        preferences,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/preferences",
+    request_body = UpdatePreferencesPayload,
+    responses(
+        (status = 200, description = "Preferences after the update", body = PreferencesResponse)
+    )
+)]
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    auth_user: Option<Extension<AuthenticatedUser>>,
+    Json(payload): Json<UpdatePreferencesPayload>,
+) -> crate::error::Result<Json<PreferencesResponse>> {
+    let ctx = Ctx::root_ctx();
+    let params = PreferenceOwnerParams {
+        project_slug: payload.project_slug,
+        agent_name: payload.agent_name,
+    };
+    let owner = resolve_owner(&state, auth_user, params).await?;
+    let preferences = UiPreferenceBmc::update(&ctx, &state.mm, &owner, payload.preferences).await?;
+    Ok(Json(PreferencesResponse {
+        owner: owner.key(),
+        preferences,
+    }))
+}
+
+async fn resolve_owner(
+    state: &AppState,
+    auth_user: Option<Extension<AuthenticatedUser>>,
+    params: PreferenceOwnerParams,
+) -> crate::error::Result<PreferenceOwner> {
+    let ctx = Ctx::root_ctx();
+    match (params.project_slug, params.agent_name) {
+        (Some(project_slug), Some(agent_name)) => {
+            let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+            let agent = AgentBmc::get_by_name(&ctx, &state.mm, project.id, &agent_name).await?;
+            Ok(PreferenceOwner::Agent(agent.id))
+        }
+        (None, None) => Ok(PreferenceOwner::User(
+            auth_user
+                .map(|Extension(user)| user.subject)
+                .unwrap_or_else(|| LOCAL_USER.to_string()),
+        )),
+        _ => Err(ServerError::BadRequest(
+            "project_slug and agent_name must be given together".to_string(),
+        )),
+    }
+}
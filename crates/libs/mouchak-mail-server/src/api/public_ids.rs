@@ -0,0 +1,27 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::public_id::{PublicIdBmc, PublicRef};
+
+/// Resolves a public id to the project, agent or message it names.
+#[utoipa::path(
+    get,
+    path = "/api/public-ids/{public_id}",
+    params(("public_id" = String, Path, description = "Public ULID of a project, agent or message")),
+    responses(
+        (status = 200, description = "Entity kind, integer id and project id of the record", body = Object),
+        (status = 400, description = "Not a ULID"),
+        (status = 404, description = "No record has this public id")
+    )
+)]
+pub async fn resolve_public_id(
+    State(state): State<AppState>,
+    Path(public_id): Path<String>,
+) -> crate::error::Result<Json<PublicRef>> {
+    let ctx = Ctx::root_ctx();
+    let resolved = PublicIdBmc::resolve(&ctx, &state.mm, &public_id).await?;
+    Ok(Json(resolved))
+}
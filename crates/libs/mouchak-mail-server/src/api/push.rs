@@ -0,0 +1,174 @@
+use crate::{AppState, ServerError};
+use axum::{Json, extract::State};
+use mouchak_mail_common::net::{self, IpNet};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::web_push::{PushSubscriptionForCreate, WebPushBmc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct VapidPublicKeyResponse {
+    /// `false` if the server is not sending push notifications
+    pub enabled: bool,
+    /// Base64url key to pass as `applicationServerKey` when subscribing
+    pub public_key: String,
+}
+
+/// Browser-issued subscription keys, as in `PushSubscription.toJSON()`.
+#[derive(Deserialize, ToSchema)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SubscribePushPayload {
+    /// Push service URL; must be `https://` on a public host
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+    /// Only notify about this project (all projects if omitted)
+    #[serde(default)]
+    pub project_slug: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SubscribePushResponse {
+    pub id: i64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UnsubscribePushPayload {
+    pub endpoint: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UnsubscribePushResponse {
+    pub deleted: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/push/vapid-public-key",
+    responses(
+        (status = 200, description = "VAPID public key", body = VapidPublicKeyResponse)
+    )
+)]
+pub async fn vapid_public_key(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<VapidPublicKeyResponse>> {
+    let ctx = Ctx::root_ctx();
+    let config = &state.mm.app_config.push;
+    let keys = crate::push::vapid_keys(&ctx, &state.mm, config).await?;
+    Ok(Json(VapidPublicKeyResponse {
+        enabled: config.enabled,
+        public_key: keys.public_key,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/push/subscriptions",
+    request_body = SubscribePushPayload,
+    responses(
+        (status = 200, description = "Subscription registered", body = SubscribePushResponse),
+        (status = 400, description = "Endpoint is not https:// or names a private host")
+    )
+)]
+pub async fn subscribe(
+    State(state): State<AppState>,
+    Json(payload): Json<SubscribePushPayload>,
+) -> crate::error::Result<Json<SubscribePushResponse>> {
+    validate_endpoint(&payload.endpoint, &state.auth_config.internal_networks)?;
+    let ctx = Ctx::root_ctx();
+    let project_id = match payload.project_slug.as_deref() {
+        Some(slug) => Some(
+            ProjectBmc::get_by_identifier(&ctx, &state.mm, slug)
+                .await?
+                .id
+                .get(),
+        ),
+        None => None,
+    };
+
+    let id = WebPushBmc::subscribe(
+        &ctx,
+        &state.mm,
+        PushSubscriptionForCreate {
+            endpoint: payload.endpoint,
+            p256dh: payload.keys.p256dh,
+            auth: payload.keys.auth,
+            project_id,
+            label: payload.label.unwrap_or_default(),
+        },
+    )
+    .await?;
+    Ok(Json(SubscribePushResponse { id }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/push/subscriptions",
+    request_body = UnsubscribePushPayload,
+    responses(
+        (status = 200, description = "Subscription removed", body = UnsubscribePushResponse)
+    )
+)]
+pub async fn unsubscribe(
+    State(state): State<AppState>,
+    Json(payload): Json<UnsubscribePushPayload>,
+) -> crate::error::Result<Json<UnsubscribePushResponse>> {
+    let ctx = Ctx::root_ctx();
+    let deleted = WebPushBmc::unsubscribe(&ctx, &state.mm, &payload.endpoint).await?;
+    Ok(Json(UnsubscribePushResponse { deleted }))
+}
+
+/// Checks that a subscription endpoint is an `https://` URL whose host is
+/// not loopback, private or in `internal_networks` (see
+/// [`net::check_public_host`]), so subscribers cannot make the server POST
+/// to internal services.
+fn validate_endpoint(endpoint: &str, internal_networks: &[IpNet]) -> crate::error::Result<()> {
+    let invalid = |reason: &str| {
+        ServerError::BadRequest(format!("Invalid push endpoint '{}': {}", endpoint, reason))
+    };
+    if !endpoint.starts_with("https://") {
+        return Err(invalid("must be an https:// URL"));
+    }
+    let host = net::url_host(endpoint).unwrap_or_default();
+    net::check_public_host(&host, internal_networks).map_err(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_endpoint() {
+        let internal = net::parse_networks("100.64.0.0/10").unwrap_or_default();
+        for ok in [
+            "https://fcm.googleapis.com/fcm/send/abc",
+            "https://updates.push.services.mozilla.com/wpush/v2/x",
+            "https://8.8.8.8:8443/push",
+            "https://[2001:4860::1]/push",
+        ] {
+            assert!(validate_endpoint(ok, &internal).is_ok(), "{}", ok);
+        }
+        for bad in [
+            "http://fcm.googleapis.com/fcm/send/abc",
+            "ftp://push.example.com/x",
+            "https://",
+            "https://localhost/push",
+            "https://api.localhost./push",
+            "https://127.0.0.1:8765/api/admin",
+            "https://user@10.0.0.5/x",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[::1]/push",
+            "https://[fd00::1]:443/push",
+            "https://100.64.1.1/push",
+        ] {
+            assert!(validate_endpoint(bad, &internal).is_err(), "{}", bad);
+        }
+    }
+}
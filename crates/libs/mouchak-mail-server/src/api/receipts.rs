@@ -0,0 +1,27 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::receipt::{MessageReceipts, ReceiptBmc};
+use mouchak_mail_core::types::MessageId;
+
+/// Lists when each recipient of a message read and acknowledged it.
+#[utoipa::path(
+    get,
+    path = "/api/messages/{message_id}/receipts",
+    params(("message_id" = i64, Path, description = "Message ID")),
+    responses(
+        (status = 200, description = "Per-recipient read and ack timestamps", body = Object),
+        (status = 404, description = "No such message")
+    )
+)]
+pub async fn get_message_receipts(
+    State(state): State<AppState>,
+    Path(message_id): Path<i64>,
+) -> crate::error::Result<Json<MessageReceipts>> {
+    let ctx = Ctx::root_ctx();
+    let receipts = ReceiptBmc::get(&ctx, &state.mm, MessageId::new(message_id)).await?;
+    Ok(Json(receipts))
+}
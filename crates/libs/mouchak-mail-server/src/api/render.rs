@@ -0,0 +1,35 @@
+use axum::Json;
+use mouchak_mail_core::utils::body_format::markdown_to_html;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct RenderMarkdownPayload {
+    #[serde(alias = "body_md")]
+    pub markdown: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RenderMarkdownResponse {
+    pub html: String,
+}
+
+/// Renders message markdown to sanitized HTML.
+///
+/// Uses the same renderer as HTML exports: raw HTML is escaped, unsafe URLs
+/// are stripped and fenced code blocks are syntax highlighted.
+#[utoipa::path(
+    post,
+    path = "/api/render/markdown",
+    request_body = RenderMarkdownPayload,
+    responses(
+        (status = 200, description = "Sanitized HTML", body = RenderMarkdownResponse)
+    )
+)]
+pub async fn render_markdown(
+    Json(payload): Json<RenderMarkdownPayload>,
+) -> crate::error::Result<Json<RenderMarkdownResponse>> {
+    Ok(Json(RenderMarkdownResponse {
+        html: markdown_to_html(&payload.markdown),
+    }))
+}
@@ -0,0 +1,196 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::saved_search::{
+    SavedSearch, SavedSearchBmc, SavedSearchCriteria, SavedSearchFolder, SavedSearchForCreate,
+    SavedSearchResult,
+};
+use mouchak_mail_core::types::{AgentId, ProjectId};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Messages returned per evaluation when no limit is given.
+const DEFAULT_FOLDER_LIMIT: i64 = 50;
+
+/// Largest accepted evaluation limit.
+const MAX_FOLDER_LIMIT: i64 = 500;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct SavedSearchListParams {
+    /// Also list the searches this agent owns
+    pub agent: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct SavedSearchRunParams {
+    /// Newest matches returned (default 50, max 500)
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SavedSearchPayload {
+    /// Folder name; saving an existing name replaces its criteria
+    pub name: String,
+    /// Owning agent; without one the search is shared by the project
+    pub agent: Option<String>,
+    /// Full-text query, as for `/api/search`
+    pub query: Option<String>,
+    /// Accepted importance levels: `low`, `normal`, `high`, `urgent`
+    #[serde(default)]
+    pub importance: Vec<String>,
+    /// Sender names or `capability:<name>` addresses
+    #[serde(default)]
+    pub from: Vec<String>,
+    /// Only messages the owner has not read
+    #[serde(default)]
+    pub unread: bool,
+    /// Only ack-required messages the owner has not acknowledged
+    #[serde(default)]
+    pub unacked: bool,
+    /// Only messages sent within this long, e.g. `24h`, `7d`
+    pub within: Option<String>,
+}
+
+async fn resolve_agent(
+    ctx: &Ctx,
+    state: &AppState,
+    project_id: ProjectId,
+    agent: Option<&str>,
+) -> crate::error::Result<Option<AgentId>> {
+    Ok(match agent {
+        Some(name) => Some(
+            AgentBmc::get_by_name(ctx, &state.mm, project_id, name)
+                .await?
+                .id,
+        ),
+        None => None,
+    })
+}
+
+/// Lists a project's shared saved searches, plus an agent's own, with
+/// their current match counts.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/saved-searches",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        SavedSearchListParams
+    ),
+    responses(
+        (status = 200, description = "Saved searches by name, with match counts", body = Vec<Object>),
+        (status = 404, description = "Unknown project or agent")
+    )
+)]
+pub async fn list_saved_searches(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Query(params): Query<SavedSearchListParams>,
+) -> crate::error::Result<Json<Vec<SavedSearchFolder>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let agent_id = resolve_agent(&ctx, &state, project.id, params.agent.as_deref()).await?;
+    let folders = SavedSearchBmc::folders(&ctx, &state.mm, project.id, agent_id).await?;
+    Ok(Json(folders))
+}
+
+/// Saves a search, or replaces the criteria of one with the same name and
+/// owner.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_slug}/saved-searches",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = SavedSearchPayload,
+    responses(
+        (status = 200, description = "The saved search", body = Object),
+        (status = 400, description = "Invalid name or criteria"),
+        (status = 404, description = "Unknown project or agent")
+    )
+)]
+pub async fn save_search(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(payload): Json<SavedSearchPayload>,
+) -> crate::error::Result<Json<SavedSearch>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let agent_id = resolve_agent(&ctx, &state, project.id, payload.agent.as_deref()).await?;
+    let id = SavedSearchBmc::save(
+        &ctx,
+        &state.mm,
+        SavedSearchForCreate {
+            project_id: project.id,
+            agent_id,
+            name: payload.name,
+            criteria: SavedSearchCriteria {
+                query: payload.query,
+                importance: payload.importance,
+                from: payload.from,
+                unread: payload.unread,
+                unacked: payload.unacked,
+                within: payload.within,
+            },
+        },
+    )
+    .await?;
+    let search = SavedSearchBmc::get(&ctx, &state.mm, project.id, id).await?;
+    Ok(Json(search))
+}
+
+/// Evaluates a saved search against the current mailbox.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/saved-searches/{id}/messages",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("id" = i64, Path, description = "Saved search id"),
+        SavedSearchRunParams
+    ),
+    responses(
+        (status = 200, description = "Match count and newest matching messages", body = Object),
+        (status = 404, description = "No such saved search in the project")
+    )
+)]
+pub async fn run_saved_search(
+    State(state): State<AppState>,
+    Path((project_slug, id)): Path<(String, i64)>,
+    Query(params): Query<SavedSearchRunParams>,
+) -> crate::error::Result<Json<SavedSearchResult>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let search = SavedSearchBmc::get(&ctx, &state.mm, project.id, id).await?;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_FOLDER_LIMIT)
+        .clamp(1, MAX_FOLDER_LIMIT);
+    let result = SavedSearchBmc::evaluate(&ctx, &state.mm, search, limit).await?;
+    Ok(Json(result))
+}
+
+/// Deletes a saved search.
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_slug}/saved-searches/{id}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("id" = i64, Path, description = "Saved search id")
+    ),
+    responses(
+        (status = 204, description = "Saved search deleted"),
+        (status = 404, description = "No such saved search in the project")
+    )
+)]
+pub async fn delete_saved_search(
+    State(state): State<AppState>,
+    Path((project_slug, id)): Path<(String, i64)>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    SavedSearchBmc::delete(&ctx, &state.mm, project.id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,65 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::custom_field::{CustomFieldBmc, parse_field_pairs};
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::search_facet::{DEFAULT_FACET_LIMIT, SearchFacetBmc, SearchFacets};
+use serde::Deserialize;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct SearchFacetsParams {
+    /// Search query, as for `/api/search`
+    #[serde(alias = "query")]
+    pub q: String,
+    /// Also match the content of text-like attachments
+    #[serde(default)]
+    pub include_attachments: bool,
+    /// Custom field filters as `name:value` pairs, comma-separated
+    pub fields: Option<String>,
+    /// Values kept per facet (default 20, max 100)
+    pub limit: Option<usize>,
+}
+
+/// Counts the messages matching a search by sender, importance, month and
+/// custom field value.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/search/facets",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        SearchFacetsParams
+    ),
+    responses(
+        (status = 200, description = "Total matches and per-facet counts", body = Object),
+        (status = 400, description = "Unknown custom field or invalid value")
+    )
+)]
+pub async fn search_facets(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Query(params): Query<SearchFacetsParams>,
+) -> crate::error::Result<Json<SearchFacets>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let filters = match params.fields.as_deref() {
+        Some(fields) => {
+            let pairs = parse_field_pairs(fields)?;
+            CustomFieldBmc::filters(&ctx, &state.mm, project.id, &pairs).await?
+        }
+        None => Vec::new(),
+    };
+    let facets = SearchFacetBmc::facets(
+        &ctx,
+        &state.mm,
+        project.id,
+        &params.q,
+        params.include_attachments,
+        &filters,
+        params.limit.unwrap_or(DEFAULT_FACET_LIMIT),
+    )
+    .await?;
+    Ok(Json(facets))
+}
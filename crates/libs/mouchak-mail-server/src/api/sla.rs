@@ -0,0 +1,127 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::listing::parse_duration_secs;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::sla::{ProjectSla, ProjectSlaForCreate, SlaBmc, SlaReport};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Default period of an SLA report.
+pub const DEFAULT_REPORT_PERIOD: &str = "7d";
+
+#[derive(Deserialize, ToSchema)]
+pub struct SlaPayload {
+    /// `low`, `normal`, `high` or `urgent`
+    pub importance: String,
+    /// Deadline for acknowledging, e.g. `1h`, `30m`, `2d`
+    pub ack_within: String,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct SlaReportParams {
+    /// Period to report on, e.g. `24h`, `7d` (default 7d)
+    pub period: Option<String>,
+}
+
+/// Lists a project's SLAs.
+#[utoipa::path(
+    get,
+    path = "/api/admin/projects/{project_slug}/slas",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 200, description = "The project's SLAs", body = Vec<Object>)
+    )
+)]
+pub async fn list_slas(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<Json<Vec<ProjectSla>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let slas = SlaBmc::list_slas(&ctx, &state.mm, project.id).await?;
+    Ok(Json(slas))
+}
+
+/// Defines the SLA for an importance level, or changes its deadline.
+#[utoipa::path(
+    post,
+    path = "/api/admin/projects/{project_slug}/slas",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = SlaPayload,
+    responses(
+        (status = 200, description = "The project's SLAs after the change", body = Vec<Object>),
+        (status = 400, description = "Invalid importance or deadline")
+    )
+)]
+pub async fn set_sla(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(payload): Json<SlaPayload>,
+) -> crate::error::Result<Json<Vec<ProjectSla>>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    SlaBmc::set_sla(
+        &ctx,
+        &state.mm,
+        ProjectSlaForCreate {
+            project_id: project.id,
+            importance: payload.importance,
+            ack_within_seconds: parse_duration_secs(&payload.ack_within)?,
+        },
+    )
+    .await?;
+    list_slas(State(state), Path(project_slug)).await
+}
+
+/// Removes an SLA.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/projects/{project_slug}/slas/{sla_id}",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        ("sla_id" = i64, Path, description = "SLA ID")
+    ),
+    responses(
+        (status = 204, description = "SLA removed"),
+        (status = 404, description = "No such SLA in the project")
+    )
+)]
+pub async fn delete_sla(
+    State(state): State<AppState>,
+    Path((project_slug, sla_id)): Path<(String, i64)>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    SlaBmc::delete_sla(&ctx, &state.mm, project.id, sla_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Reports SLA compliance per project and per agent over a period.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/sla_report",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        SlaReportParams
+    ),
+    responses(
+        (status = 200, description = "Compliance totals, per-agent compliance and violations", body = Object),
+        (status = 400, description = "Invalid period")
+    )
+)]
+pub async fn get_sla_report(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Query(params): Query<SlaReportParams>,
+) -> crate::error::Result<Json<SlaReport>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let period = parse_duration_secs(params.period.as_deref().unwrap_or(DEFAULT_REPORT_PERIOD))?;
+    let report = SlaBmc::report(&ctx, &state.mm, project.id, period).await?;
+    Ok(Json(report))
+}
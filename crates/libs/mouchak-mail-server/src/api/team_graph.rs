@@ -0,0 +1,49 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::team_graph::{TeamGraph, TeamGraphBmc, TeamGraphImportSummary};
+
+/// Exports a project's agents, capabilities and accepted contacts.
+#[utoipa::path(
+    get,
+    path = "/api/admin/projects/{project_slug}/team_graph",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    responses(
+        (status = 200, description = "The project's team graph", body = Object)
+    )
+)]
+pub async fn export_team_graph(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+) -> crate::error::Result<Json<TeamGraph>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let graph = TeamGraphBmc::export(&ctx, &state.mm, project.id).await?;
+    Ok(Json(graph))
+}
+
+/// Imports a team graph, adding missing agents, capabilities and contacts.
+#[utoipa::path(
+    post,
+    path = "/api/admin/projects/{project_slug}/team_graph",
+    params(("project_slug" = String, Path, description = "Project slug")),
+    request_body = Object,
+    responses(
+        (status = 200, description = "What was created and what was skipped", body = Object),
+        (status = 400, description = "Unsupported version or invalid agent name")
+    )
+)]
+pub async fn import_team_graph(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Json(graph): Json<TeamGraph>,
+) -> crate::error::Result<Json<TeamGraphImportSummary>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let summary = TeamGraphBmc::import(&ctx, &state.mm, project.id, &graph).await?;
+    Ok(Json(summary))
+}
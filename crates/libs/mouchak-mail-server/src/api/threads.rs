@@ -0,0 +1,39 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::reply_chain::{ReplyChainBmc, ThreadTree};
+use serde::Deserialize;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct ThreadTreeParams {
+    /// Project the thread belongs to
+    pub project_slug: String,
+}
+
+/// Returns a thread as a tree of replies, for rendering nested conversations.
+#[utoipa::path(
+    get,
+    path = "/api/threads/{thread_id}/tree",
+    params(
+        ("thread_id" = String, Path, description = "Thread ID"),
+        ThreadTreeParams
+    ),
+    responses(
+        (status = 200, description = "Root messages of the thread, each with its replies nested under `replies`", body = Object),
+        (status = 404, description = "Unknown project, or a thread without messages")
+    )
+)]
+pub async fn get_thread_tree(
+    State(state): State<AppState>,
+    Path(thread_id): Path<String>,
+    Query(params): Query<ThreadTreeParams>,
+) -> crate::error::Result<Json<ThreadTree>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &params.project_slug).await?;
+    let tree = ReplyChainBmc::thread_tree(&ctx, &state.mm, project.id, &thread_id).await?;
+    Ok(Json(tree))
+}
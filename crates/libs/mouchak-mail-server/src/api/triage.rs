@@ -0,0 +1,41 @@
+use crate::AppState;
+use axum::{Json, extract::State};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::triage::{TriageBmc, TriageQuery, TriageStep};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct TriagePayload {
+    pub project_slug: String,
+    pub agent_name: String,
+    #[serde(flatten)]
+    pub query: TriageQuery,
+}
+
+/// Moves a triage cursor through an agent's inbox.
+///
+/// Returns the next (older) or previous (newer) unread and/or
+/// pending-acknowledgement message relative to `from_message_id`, with
+/// counts of the matches left in that direction and overall.
+#[utoipa::path(
+    post,
+    path = "/api/inbox/triage",
+    request_body = TriagePayload,
+    responses(
+        (status = 200, description = "Message at the new cursor position (`item` is null at the end)")
+    )
+)]
+pub async fn triage_step(
+    State(state): State<AppState>,
+    Json(payload): Json<TriagePayload>,
+) -> crate::error::Result<Json<TriageStep>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &payload.project_slug).await?;
+    let agent = AgentBmc::get_by_name(&ctx, &state.mm, project.id, &payload.agent_name).await?;
+
+    let step = TriageBmc::step(&ctx, &state.mm, project.id, agent.id, &payload.query).await?;
+    Ok(Json(step))
+}
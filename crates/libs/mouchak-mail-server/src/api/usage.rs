@@ -0,0 +1,47 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::usage::UsageBmc;
+use serde::Deserialize;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct UsageParams {
+    /// Report only this agent's usage
+    pub agent_name: Option<String>,
+}
+
+/// Reports storage usage of a project and its agents, or of one agent.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_slug}/usage",
+    params(
+        ("project_slug" = String, Path, description = "Project slug"),
+        UsageParams
+    ),
+    responses(
+        (status = 200, description = "Project totals and per-agent usage (largest first), or one agent's usage, with quota limits", body = Object),
+        (status = 404, description = "Unknown project or agent")
+    )
+)]
+pub async fn get_usage(
+    State(state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Query(params): Query<UsageParams>,
+) -> crate::error::Result<Json<serde_json::Value>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &project_slug).await?;
+    let usage = match params.agent_name {
+        Some(agent_name) => {
+            let agent = AgentBmc::get_by_name(&ctx, &state.mm, project.id, &agent_name).await?;
+            let usage = UsageBmc::for_agent(&ctx, &state.mm, agent.id).await?;
+            serde_json::json!({ "usage": usage, "limits": UsageBmc::limits(&state.mm) })
+        }
+        None => serde_json::json!(UsageBmc::for_project(&ctx, &state.mm, project.id).await?),
+    };
+    Ok(Json(usage))
+}
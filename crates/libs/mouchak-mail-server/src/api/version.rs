@@ -0,0 +1,104 @@
+use axum::Json;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Version of the HTTP API served by this build.
+pub const API_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Oldest API version a UI may have been built against and still work.
+///
+/// Raise this when an API change breaks UIs built before it.
+pub const MIN_UI_API_VERSION: &str = "0.2.7";
+
+/// File in the embedded UI assets recording the API version it was built against.
+pub const UI_VERSION_FILE: &str = "ui-version.json";
+
+#[derive(Serialize, ToSchema)]
+pub struct VersionResponse {
+    /// Server API version (semver)
+    pub api_version: &'static str,
+    /// Oldest UI build (by the API version it targets) this server supports
+    pub min_ui_api_version: &'static str,
+}
+
+/// Reports the API version and the oldest compatible UI build.
+///
+/// The web UI calls this at load and warns when it was built against an
+/// API version outside `[min_ui_api_version, api_version]`.
+#[utoipa::path(
+    get,
+    path = "/api/version",
+    responses(
+        (status = 200, description = "API version handshake", body = VersionResponse)
+    )
+)]
+pub async fn version() -> Json<VersionResponse> {
+    Json(VersionResponse {
+        api_version: API_VERSION,
+        min_ui_api_version: MIN_UI_API_VERSION,
+    })
+}
+
+/// Parses `MAJOR.MINOR.PATCH`, ignoring any pre-release or build suffix.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()?;
+    let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(version)
+}
+
+/// Checks a UI built against `ui_api_version` against this server.
+///
+/// # Errors
+/// A description of the mismatch, suitable for logging.
+pub fn check_ui_compatibility(ui_api_version: &str) -> Result<(), String> {
+    let (Some(ui), Some(min), Some(api)) = (
+        parse_version(ui_api_version),
+        parse_version(MIN_UI_API_VERSION),
+        parse_version(API_VERSION),
+    ) else {
+        return Err(format!("unrecognized UI API version '{}'", ui_api_version));
+    };
+
+    if ui < min {
+        Err(format!(
+            "UI was built against API {} but this server requires at least {}; rebuild the UI",
+            ui_api_version, MIN_UI_API_VERSION
+        ))
+    } else if ui > api {
+        Err(format!(
+            "UI was built against API {} which is newer than this server's API {}",
+            ui_api_version, API_VERSION
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.2.7"), Some((0, 2, 7)));
+        assert_eq!(parse_version("v1.10.0-rc.1"), Some((1, 10, 0)));
+        assert_eq!(parse_version("1.2"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("dev"), None);
+    }
+
+    #[test]
+    fn test_ui_compatibility_window() {
+        assert!(check_ui_compatibility(API_VERSION).is_ok());
+        assert!(check_ui_compatibility(MIN_UI_API_VERSION).is_ok());
+        assert!(check_ui_compatibility("0.0.1").is_err());
+        assert!(check_ui_compatibility("999.0.0").is_err());
+        assert!(check_ui_compatibility("unknown").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::AppState;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::project::ProjectBmc;
+use mouchak_mail_core::model::webhook::{
+    DeliveryFilter, Webhook, WebhookBmc, WebhookDelivery, WebhookForCreate,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct WebhookPayload {
+    /// Project whose events are sent
+    pub project_slug: String,
+    /// http:// or https:// URL receiving the POSTs
+    pub url: String,
+    /// `message.created`, `message.acknowledged` and/or `reservation.expired`
+    pub events: Vec<String>,
+    /// HMAC key of at least 16 characters; generated when omitted
+    pub secret: Option<String>,
+    /// What the webhook is for
+    pub description: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    /// Signing secret, only shown here
+    pub secret: String,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct WebhookListParams {
+    /// Only webhooks of this project
+    pub project: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct DeliveryListParams {
+    /// Only deliveries with this status: pending, delivered or failed
+    pub status: Option<String>,
+    /// Maximum deliveries to return (default 50)
+    pub limit: Option<i64>,
+}
+
+/// Registers a webhook for a project's events.
+///
+/// Payloads are signed with the returned secret: the `X-Mouchak-Signature`
+/// header holds `sha256=` and the hex HMAC-SHA256 of the body.
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    request_body = WebhookPayload,
+    responses(
+        (status = 200, description = "The new webhook with its secret", body = Object),
+        (status = 400, description = "Invalid URL, event or secret"),
+        (status = 404, description = "No such project")
+    )
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Json(payload): Json<WebhookPayload>,
+) -> crate::error::Result<Json<CreatedWebhook>> {
+    let ctx = Ctx::root_ctx();
+    let project = ProjectBmc::get_by_identifier(&ctx, &state.mm, &payload.project_slug).await?;
+    let hook_c = WebhookForCreate {
+        project_id: project.id.get(),
+        url: payload.url,
+        events: payload.events,
+        secret: payload.secret,
+        description: payload.description.unwrap_or_default(),
+    };
+    let webhook = WebhookBmc::create(&ctx, &state.mm, hook_c).await?;
+    let secret = webhook.secret.clone();
+    Ok(Json(CreatedWebhook { webhook, secret }))
+}
+
+/// Lists webhooks, without their secrets.
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    params(WebhookListParams),
+    responses(
+        (status = 200, description = "Webhooks in creation order", body = Vec<Object>),
+        (status = 404, description = "No such project")
+    )
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Query(params): Query<WebhookListParams>,
+) -> crate::error::Result<Json<Vec<Webhook>>> {
+    let ctx = Ctx::root_ctx();
+    let project_id = match params.project.as_deref() {
+        Some(slug) => Some(
+            ProjectBmc::get_by_identifier(&ctx, &state.mm, slug)
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+    let webhooks = WebhookBmc::list(&ctx, &state.mm, project_id).await?;
+    Ok(Json(webhooks))
+}
+
+/// Shows a webhook, without its secret.
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{webhook_id}",
+    params(("webhook_id" = i64, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "The webhook", body = Object),
+        (status = 404, description = "No such webhook")
+    )
+)]
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<i64>,
+) -> crate::error::Result<Json<Webhook>> {
+    let ctx = Ctx::root_ctx();
+    let webhook = WebhookBmc::get(&ctx, &state.mm, webhook_id).await?;
+    Ok(Json(webhook))
+}
+
+/// Removes a webhook and its delivery log.
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{webhook_id}",
+    params(("webhook_id" = i64, Path, description = "Webhook ID")),
+    responses(
+        (status = 204, description = "Webhook removed"),
+        (status = 404, description = "No such webhook")
+    )
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<i64>,
+) -> crate::error::Result<StatusCode> {
+    let ctx = Ctx::root_ctx();
+    WebhookBmc::delete(&ctx, &state.mm, webhook_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists a webhook's deliveries with their attempts and last outcome,
+/// newest first.
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{webhook_id}/deliveries",
+    params(
+        ("webhook_id" = i64, Path, description = "Webhook ID"),
+        DeliveryListParams
+    ),
+    responses(
+        (status = 200, description = "Deliveries, newest first", body = Vec<Object>),
+        (status = 404, description = "No such webhook")
+    )
+)]
+pub async fn list_deliveries(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<i64>,
+    Query(params): Query<DeliveryListParams>,
+) -> crate::error::Result<Json<Vec<WebhookDelivery>>> {
+    let ctx = Ctx::root_ctx();
+    let filter = DeliveryFilter {
+        status: params.status,
+        limit: params.limit,
+    };
+    let deliveries = WebhookBmc::list_deliveries(&ctx, &state.mm, webhook_id, &filter).await?;
+    Ok(Json(deliveries))
+}
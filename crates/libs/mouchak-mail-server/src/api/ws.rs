@@ -0,0 +1,214 @@
+//! Real-time event stream over WebSocket.
+//!
+//! `/api/ws` pushes event log records (e.g. `message.sent`,
+//! `message.acknowledged`, `file_reservation.created`/`released`/`expired`)
+//! to the client as JSON text frames, one record per frame, in the shape
+//! returned by `/api/events`. Clients use it instead of polling
+//! `check_inbox`.
+//!
+//! With `after_id`, logged records after that id are replayed first, so a
+//! client that reconnects with the last id it saw misses nothing. A client
+//! that reads too slowly for the server's buffer receives
+//! `{"kind": "stream.lagged", "missed": N}` and can catch up from
+//! `/api/events`.
+
+use crate::AppState;
+use crate::error::ServerError;
+use axum::{
+    extract::{
+        Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use mouchak_mail_core::Ctx;
+use mouchak_mail_core::model::ModelManager;
+use mouchak_mail_core::model::agent::AgentBmc;
+use mouchak_mail_core::model::event_log::{EventLogBmc, EventRecord, MAX_PAGE_SIZE};
+use mouchak_mail_core::model::project::ProjectBmc;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::debug;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct EventStreamParams {
+    /// Only events of this project (slug or human key)
+    pub project: Option<String>,
+    /// Only events sent by, addressed to or acknowledging mail of this agent;
+    /// requires `project`
+    pub agent: Option<String>,
+    /// Comma-separated event kind prefixes, e.g. `message.,file_reservation.expired`
+    pub kinds: Option<String>,
+    /// Replay logged events after this id before streaming live ones
+    pub after_id: Option<i64>,
+}
+
+/// Which records a connection receives.
+struct EventFilter {
+    project_id: Option<i64>,
+    agent: Option<String>,
+    kinds: Vec<String>,
+}
+
+impl EventFilter {
+    fn matches(&self, record: &EventRecord) -> bool {
+        if self.project_id.is_some() && record.project_id != self.project_id {
+            return false;
+        }
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|k| record.kind.starts_with(k)) {
+            return false;
+        }
+        let Some(agent) = self.agent.as_deref() else {
+            return true;
+        };
+        let named = |key: &str| record.payload.get(key).and_then(|v| v.as_str()) == Some(agent);
+        record.actor.as_deref() == Some(agent)
+            || named("sender")
+            || record
+                .payload
+                .get("recipients")
+                .and_then(|v| v.as_array())
+                .is_some_and(|names| names.iter().any(|n| n.as_str() == Some(agent)))
+    }
+}
+
+/// Streams event log records to a WebSocket client.
+#[utoipa::path(
+    get,
+    path = "/api/ws",
+    params(EventStreamParams),
+    responses(
+        (status = 101, description = "Switching to WebSocket; each text frame is one event record"),
+        (status = 400, description = "`agent` given without `project`"),
+        (status = 404, description = "WebSocket events are disabled, or unknown project or agent")
+    )
+)]
+pub async fn event_stream(
+    State(state): State<AppState>,
+    Query(params): Query<EventStreamParams>,
+    ws: WebSocketUpgrade,
+) -> crate::error::Result<Response> {
+    let config = &state.mm.app_config.websocket;
+    if !config.enabled {
+        return Err(ServerError::not_found("WebSocket events are disabled"));
+    }
+    let ping_interval = Duration::from_secs(config.ping_interval_seconds.max(1));
+
+    let ctx = Ctx::root_ctx();
+    let project = match params.project.as_deref() {
+        Some(project) => Some(ProjectBmc::get_by_identifier(&ctx, &state.mm, project).await?),
+        None => None,
+    };
+    let agent = match (&project, params.agent) {
+        (Some(project), Some(agent)) => Some(
+            AgentBmc::get_by_name(&ctx, &state.mm, project.id, &agent)
+                .await?
+                .name,
+        ),
+        (None, Some(_)) => {
+            return Err(ServerError::bad_request("`agent` requires `project`"));
+        }
+        (_, None) => None,
+    };
+    let filter = EventFilter {
+        project_id: project.map(|p| p.id.get()),
+        agent,
+        kinds: params
+            .kinds
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(str::to_string)
+            .collect(),
+    };
+
+    // Subscribe before replaying, so no record falls between the two
+    let events = state.mm.event_bus().subscribe();
+    let mm = state.mm.clone();
+    Ok(ws.on_upgrade(move |socket| {
+        stream_events(socket, mm, filter, events, params.after_id, ping_interval)
+    }))
+}
+
+async fn stream_events(
+    mut socket: WebSocket,
+    mm: ModelManager,
+    filter: EventFilter,
+    mut events: broadcast::Receiver<Arc<EventRecord>>,
+    after_id: Option<i64>,
+    ping_interval: Duration,
+) {
+    // Live records at or before this id were already replayed
+    let mut replayed_id = 0;
+    if let Some(after_id) = after_id {
+        let ctx = Ctx::root_ctx();
+        replayed_id = after_id;
+        loop {
+            let page = match EventLogBmc::list(&ctx, &mm, replayed_id, MAX_PAGE_SIZE).await {
+                Ok(page) => page,
+                Err(e) => {
+                    debug!("Event replay failed: {}", e);
+                    break;
+                }
+            };
+            let done = (page.len() as i64) < MAX_PAGE_SIZE;
+            for record in page {
+                replayed_id = record.id;
+                if filter.matches(&record) && send_record(&mut socket, &record).await.is_err() {
+                    return;
+                }
+            }
+            if done {
+                break;
+            }
+        }
+    }
+
+    let mut ping = tokio::time::interval(ping_interval);
+    ping.tick().await;
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(record) => {
+                    if record.id <= replayed_id || !filter.matches(&record) {
+                        continue;
+                    }
+                    if send_record(&mut socket, &record).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    let notice = serde_json::json!({ "kind": "stream.lagged", "missed": missed });
+                    if socket.send(Message::Text(notice.to_string().into())).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                // Pings are answered automatically; clients have nothing else to say
+                Some(Ok(_)) => {}
+            },
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+async fn send_record(socket: &mut WebSocket, record: &EventRecord) -> Result<(), axum::Error> {
+    match serde_json::to_string(record) {
+        Ok(json) => socket.send(Message::Text(json.into())).await,
+        Err(e) => {
+            debug!("Failed to encode {} for a WebSocket: {}", record.kind, e);
+            Ok(())
+        }
+    }
+}
@@ -9,13 +9,16 @@ use jsonwebtoken::{DecodingKey, Validation, decode, decode_header};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
 use crate::AppState;
+use crate::client_ip::{self, IpNet, TrustedProxies};
+use mouchak_mail_core::model::api_token::{ApiToken, ApiTokenBmc, TOKEN_PREFIX};
+use mouchak_mail_core::model::project::ProjectBmc;
 
 /// Authenticated user information stored as request extension
 #[derive(Debug, Clone)]
@@ -28,6 +31,27 @@ pub struct AuthenticatedUser {
     pub project_slug: Option<String>,
 }
 
+/// How a request was authenticated, stored as request extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// Auth disabled or localhost bypass
+    Anonymous,
+    /// Browser session cookie (OIDC login)
+    Session,
+    /// Static bearer token
+    Bearer,
+    /// JWT validated against JWKS
+    Jwt,
+    /// Per-agent API token with scoped permissions
+    ApiToken,
+}
+
+/// Scopes of the API token a request was authenticated with, stored as
+/// request extension
+#[derive(Debug, Clone)]
+pub struct TokenScopes(pub Vec<String>);
+
 /// Authentication configuration
 #[derive(Debug, Clone)]
 pub struct AuthConfig {
@@ -36,7 +60,12 @@ pub struct AuthConfig {
     pub jwks_url: Option<String>,
     pub jwt_audience: Option<String>,
     pub jwt_issuer: Option<String>,
+    /// Let clients on loopback or `internal_networks` skip authentication
     pub allow_localhost: bool,
+    /// Reverse proxies whose `X-Forwarded-For` names the client
+    pub trusted_proxies: TrustedProxies,
+    /// Networks treated like localhost by the bypass
+    pub internal_networks: Vec<IpNet>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -59,9 +88,13 @@ impl AuthConfig {
         let jwks_url = std::env::var("HTTP_JWKS_URL").ok();
         let jwt_audience = std::env::var("HTTP_JWT_AUDIENCE").ok();
         let jwt_issuer = std::env::var("HTTP_JWT_ISSUER").ok();
+        // Opt-in: behind a reverse proxy every peer looks local
         let allow_localhost = std::env::var("HTTP_ALLOW_LOCALHOST_UNAUTHENTICATED")
             .map(|v| v.to_lowercase() == "true")
-            .unwrap_or(true);
+            .unwrap_or(false);
+        let trusted_proxies = TrustedProxies::from_env();
+        let internal_networks =
+            client_ip::networks_from_env(mouchak_mail_common::net::INTERNAL_NETWORKS_ENV);
 
         // Validation
         if mode == AuthMode::Bearer && bearer_token.is_none() {
@@ -70,6 +103,12 @@ impl AuthConfig {
         if mode == AuthMode::Jwt && jwks_url.is_none() {
             warn!("HTTP_AUTH_MODE=jwt but HTTP_JWKS_URL is not set. Auth will fail.");
         }
+        if allow_localhost && mode != AuthMode::None {
+            info!(
+                "Unauthenticated access allowed from localhost and {} internal networks",
+                internal_networks.len()
+            );
+        }
         if mode == AuthMode::Jwt {
             if jwt_audience.is_some() {
                 info!("JWT audience validation enabled");
@@ -86,6 +125,8 @@ impl AuthConfig {
             jwt_audience,
             jwt_issuer,
             allow_localhost,
+            trusted_proxies,
+            internal_networks,
         }
     }
 }
@@ -225,11 +266,8 @@ struct Claims {
 }
 
 /// Check if the IP address is localhost (127.0.0.1 or ::1)
-fn is_localhost(addr: &SocketAddr) -> bool {
-    match addr.ip() {
-        std::net::IpAddr::V4(ipv4) => ipv4.is_loopback(),
-        std::net::IpAddr::V6(ipv6) => ipv6.is_loopback(),
-    }
+fn is_localhost(ip: IpAddr) -> bool {
+    client_ip::is_loopback(ip)
 }
 
 /// Validate bearer token against expected value
@@ -287,13 +325,18 @@ fn should_bypass_auth(req: &Request<axum::body::Body>, auth_config: &AuthConfig)
     }
     if auth_config.allow_localhost
         && let Some(connect_info) = req.extensions().get::<ConnectInfo<SocketAddr>>()
-        && is_localhost(&connect_info.0)
     {
-        info!(
-            "Localhost bypass: allowing unauthenticated request from {}",
-            connect_info.0
-        );
-        return true;
+        // The peer is the proxy, not the client, behind a reverse proxy
+        let client = auth_config
+            .trusted_proxies
+            .client_ip(connect_info.0.ip(), req.headers());
+        if is_localhost(client) || client_ip::in_networks(&auth_config.internal_networks, client) {
+            info!(
+                "Localhost bypass: allowing unauthenticated request from {} (peer {})",
+                client, connect_info.0
+            );
+            return true;
+        }
     }
     false
 }
@@ -306,7 +349,27 @@ pub async fn auth_middleware(
 ) -> Result<Response, StatusCode> {
     let auth_config = &state.auth_config;
 
+    let mut req = req;
+
+    // Browser users logged in through OIDC carry a session cookie instead of a token
+    if let Some(token) = crate::session::session_token(req.headers())
+        && let Some(session) = state.sessions.get(&token).await
+    {
+        if crate::session::requires_csrf(req.method()) && !session.csrf_matches(req.headers()) {
+            warn!(
+                "Missing or invalid CSRF token for {} {}",
+                req.method(),
+                req.uri().path()
+            );
+            return Err(StatusCode::FORBIDDEN);
+        }
+        req.extensions_mut().insert(session.user);
+        req.extensions_mut().insert(AuthMethod::Session);
+        return Ok(next.run(req).await);
+    }
+
     if should_bypass_auth(&req, auth_config) {
+        req.extensions_mut().insert(AuthMethod::Anonymous);
         return Ok(next.run(req).await);
     }
 
@@ -319,9 +382,16 @@ pub async fn auth_middleware(
             StatusCode::UNAUTHORIZED
         })?;
 
+    // Per-agent API tokens work in both token modes; with auth off or from a
+    // bypassed localhost the request never gets here
+    if token.starts_with(TOKEN_PREFIX) {
+        return authenticate_api_token(&state, &token, req, next).await;
+    }
+
     match auth_config.mode {
         AuthMode::Bearer => {
             validate_bearer_token(&token, auth_config.bearer_token.as_ref())?;
+            req.extensions_mut().insert(AuthMethod::Bearer);
             Ok(next.run(req).await)
         }
         AuthMode::Jwt => {
@@ -330,22 +400,250 @@ pub async fn auth_middleware(
                 StatusCode::INTERNAL_SERVER_ERROR
             })?;
             let auth_user = validate_jwt_token(&token, jwks_client, auth_config).await?;
-            let mut req = req;
             req.extensions_mut().insert(auth_user);
+            req.extensions_mut().insert(AuthMethod::Jwt);
             Ok(next.run(req).await)
         }
         AuthMode::None => unreachable!(),
     }
 }
 
+/// Request fields naming the project a request acts in.
+const PROJECT_FIELDS: &[&str] = &["project_slug", "project", "project_key", "human_key"];
+
+/// Request fields naming the agent a request acts as.
+const AGENT_FIELDS: &[&str] = &["agent_name", "sender_name", "from_agent_name", "agent"];
+
+/// Authenticates a per-agent API token, checks that its scopes cover the
+/// route and that the request acts as the token's agent (see
+/// [`check_token_binding`]).
+async fn authenticate_api_token(
+    state: &AppState,
+    token: &str,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let ctx = mouchak_mail_core::Ctx::root_ctx();
+    let api_token = ApiTokenBmc::authenticate(&ctx, &state.mm, token)
+        .await
+        .map_err(|e| {
+            error!("API token lookup failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            warn!("Unknown, revoked or expired API token");
+            StatusCode::UNAUTHORIZED
+        })?;
+
+    let path = req.uri().path();
+    let scope = required_scope(path);
+    if !api_token.allows(scope) {
+        warn!(
+            "API token {} ({}) denied {} (missing scope: {})",
+            api_token.id, api_token.agent_name, path, scope
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let mut req = check_token_binding(state, &api_token, req).await?;
+
+    req.extensions_mut().insert(AuthenticatedUser {
+        subject: format!("token:{}", api_token.id),
+        agent_name: Some(api_token.agent_name),
+        project_slug: Some(api_token.project_slug),
+    });
+    req.extensions_mut().insert(TokenScopes(api_token.scopes));
+    req.extensions_mut().insert(AuthMethod::ApiToken);
+    Ok(next.run(req).await)
+}
+
+/// Checks that a token request acts in the token's project and as its
+/// agent, and returns the request with its body restored.
+///
+/// The project and agent are read from the matched path parameters, the
+/// query string and the top level of a JSON body ([`PROJECT_FIELDS`],
+/// [`AGENT_FIELDS`]). A request must name the token's project, and any agent
+/// it names must be the token's agent. Tokens with the bare `admin` scope are
+/// not bound.
+async fn check_token_binding(
+    state: &AppState,
+    api_token: &ApiToken,
+    mut req: Request<axum::body::Body>,
+) -> Result<Request<axum::body::Body>, StatusCode> {
+    if api_token.scopes.iter().any(|s| s == "admin") {
+        return Ok(req);
+    }
+
+    let mut fields = path_fields(&mut req).await;
+    fields.extend(
+        req.uri()
+            .query()
+            .map(|query| {
+                axum::extract::Query::<Vec<(String, String)>>::try_from_uri(req.uri())
+                    .map(|q| q.0)
+                    .unwrap_or_else(|_| {
+                        warn!("Unparsable query string: {}", query);
+                        Vec::new()
+                    })
+            })
+            .unwrap_or_default(),
+    );
+
+    let is_json = req
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let req = if is_json {
+        let (parts, body) = req.into_parts();
+        let bytes = axum::body::to_bytes(body, crate::get_request_body_limit())
+            .await
+            .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+        if let Ok(serde_json::Value::Object(map)) = serde_json::from_slice(&bytes) {
+            fields.extend(
+                map.into_iter()
+                    .filter_map(|(k, v)| v.as_str().map(|v| (k, v.to_string()))),
+            );
+        }
+        Request::from_parts(parts, axum::body::Body::from(bytes))
+    } else {
+        req
+    };
+
+    let projects: Vec<&str> = fields
+        .iter()
+        .filter(|(k, _)| PROJECT_FIELDS.contains(&k.as_str()))
+        .map(|(_, v)| v.as_str())
+        .collect();
+    if projects.is_empty() {
+        warn!(
+            "API token {} ({}) denied {}: request names no project",
+            api_token.id,
+            api_token.agent_name,
+            req.uri().path()
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let ctx = mouchak_mail_core::Ctx::root_ctx();
+    for project in projects {
+        let same = project == api_token.project_slug
+            || ProjectBmc::get_by_identifier(&ctx, &state.mm, project)
+                .await
+                .is_ok_and(|p| p.id == api_token.project_id);
+        if !same {
+            warn!(
+                "API token {} ({}) denied {}: issued for project {}, not {}",
+                api_token.id,
+                api_token.agent_name,
+                req.uri().path(),
+                api_token.project_slug,
+                project
+            );
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    if let Some((_, agent)) = fields.iter().find(|(k, v)| {
+        AGENT_FIELDS.contains(&k.as_str()) && !v.eq_ignore_ascii_case(&api_token.agent_name)
+    }) {
+        warn!(
+            "API token {} ({}) denied {}: cannot act as {}",
+            api_token.id,
+            api_token.agent_name,
+            req.uri().path(),
+            agent
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(req)
+}
+
+/// Returns the path parameters of the matched route, e.g. `project_slug` in
+/// `/api/projects/{project_slug}/…`.
+async fn path_fields(req: &mut Request<axum::body::Body>) -> Vec<(String, String)> {
+    use axum::RequestExt;
+    use axum::extract::{MatchedPath, RawPathParams};
+
+    let matched = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    let Ok(params) = req.extract_parts::<RawPathParams>().await else {
+        return Vec::new();
+    };
+    params
+        .iter()
+        .map(|(key, value)| {
+            // `{id}` is the project slug in `/api/agents/{id}/{agent_name}/…`
+            let key = if key == "id" && matched.starts_with("/api/agents/{id}/{agent_name}/") {
+                "project_slug"
+            } else {
+                key
+            };
+            (key.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+/// Returns the API token scope a route requires, derived from its
+/// capability in [`get_required_capability`]. Routes without a capability
+/// need `admin`, so new routes are closed to narrow tokens by default.
+pub fn required_scope(path: &str) -> &'static str {
+    match get_required_capability(path) {
+        Some("fetch_inbox") => "read:inbox",
+        Some("fetch_outbox") => "read:outbox",
+        Some("send_message" | "acknowledge_message") => "write:messages",
+        Some("file_reservation") => "write:reservations",
+        Some("build") => "write:builds",
+        _ if is_force_release(path) => "admin:reservations",
+        _ => "admin",
+    }
+}
+
+fn is_force_release(path: &str) -> bool {
+    matches!(
+        path.trim_end_matches('/'),
+        "/api/file_reservations/force_release" | "/api/force_release_file_reservation"
+    )
+}
+
+/// Every capability required by some route in [`get_required_capability`]
+pub const ROUTE_CAPABILITIES: &[&str] = &[
+    "acknowledge_message",
+    "admin",
+    "archive",
+    "build",
+    "fetch_inbox",
+    "fetch_outbox",
+    "file_reservation",
+    "overseer",
+    "send_message",
+];
+
 /// Route-to-capability mapping for RBAC enforcement
 /// Returns the required capability for a given route path, or None if no capability check needed
 pub fn get_required_capability(path: &str) -> Option<&'static str> {
     let normalized = path.trim_end_matches('/');
+    // Admin endpoints carry path parameters, so match them by prefix
+    if normalized.starts_with("/api/admin/") {
+        return Some("admin");
+    }
+    // Webhooks send project events to any URL and hand out signing secrets
+    if normalized == "/api/webhooks" || normalized.starts_with("/api/webhooks/") {
+        return Some("admin");
+    }
+    // `/api/agents/{project_slug}/{agent_name}/inbox/stream`
+    if normalized.starts_with("/api/agents/")
+        && normalized.ends_with("/inbox/stream")
+        && normalized.split('/').count() == 7
+    {
+        return Some("fetch_inbox");
+    }
     match normalized {
         // Messaging operations
         "/api/message/send" | "/api/send_message" => Some("send_message"),
         "/api/message/reply" | "/api/reply_message" => Some("send_message"),
+        "/api/ingest/email" => Some("send_message"),
         "/api/inbox" | "/api/fetch_inbox" | "/api/list_inbox" | "/api/get_inbox" => {
             Some("fetch_inbox")
         }
@@ -354,7 +652,7 @@ pub fn get_required_capability(path: &str) -> Option<&'static str> {
         }
         "/api/message/acknowledge" | "/api/acknowledge_message" => Some("acknowledge_message"),
         "/api/message/read" | "/api/mark_message_read" => Some("fetch_inbox"),
-        "/api/messages/search" | "/api/search_messages" => Some("fetch_inbox"),
+        "/api/messages/search" | "/api/search_messages" | "/api/search" => Some("fetch_inbox"),
         // File reservations
         "/api/file_reservations/paths" | "/api/file_reservation_paths" => Some("file_reservation"),
         "/api/file_reservations/list" | "/api/list_file_reservations" | "/api/reservations" => {
@@ -374,6 +672,10 @@ pub fn get_required_capability(path: &str) -> Option<&'static str> {
         // Overseer and archive
         "/api/overseer/send" | "/api/send_overseer_message" => Some("overseer"),
         "/api/archive/commit" | "/api/commit_archive" => Some("archive"),
+        // Event log
+        "/api/events" | "/api/events/export" | "/api/events/verify" => Some("admin"),
+        // Self-profiling (pprof feature)
+        "/debug/pprof/profile" | "/debug/pprof/tasks" => Some("admin"),
         _ => None,
     }
 }
@@ -452,7 +754,7 @@ pub async fn capabilities_middleware(
                 mouchak_mail_core::model::agent_capabilities::AgentCapabilityBmc::check(
                     &ctx,
                     mm,
-                    agent.id.get(),
+                    agent.id,
                     required_capability,
                 )
                 .await
@@ -498,6 +800,14 @@ mod tests {
         "OK"
     }
 
+    async fn echo(body: axum::body::Bytes) -> axum::body::Bytes {
+        body
+    }
+
+    async fn status(app: &Router, request: Request<Body>) -> StatusCode {
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
     /// Helper to generate RSA key pair and JWKS JSON
     fn generate_test_keys(kid: &str) -> (RsaPrivateKey, String) {
         let mut rng = OsRng;
@@ -569,6 +879,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let app_state = AppState {
             mm,
@@ -577,6 +889,8 @@ mod tests {
             auth_config,
             jwks_client: None,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -610,6 +924,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let app_state = AppState {
             mm,
@@ -618,6 +934,8 @@ mod tests {
             auth_config,
             jwks_client: None,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -657,6 +975,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let app_state = AppState {
             mm,
@@ -665,6 +985,8 @@ mod tests {
             auth_config,
             jwks_client: None,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -695,6 +1017,86 @@ mod tests {
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
+    #[tokio::test]
+    async fn test_auth_session_cookie_requires_csrf_for_mutations() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let repo_root = temp_dir.path().join("archive");
+        std::fs::create_dir_all(&repo_root).unwrap();
+
+        let db = libsql::Builder::new_local(db_path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        let app_config = Arc::new(AppConfig::default());
+        let mm = crate::ModelManager::new_for_test(conn, repo_root, app_config);
+
+        let auth_config = AuthConfig {
+            mode: AuthMode::Bearer,
+            bearer_token: Some("secret123".to_string()),
+            jwks_url: None,
+            jwt_audience: None,
+            jwt_issuer: None,
+            allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
+        };
+        let sessions = crate::session::SessionStore::default();
+        let token = sessions
+            .create(
+                AuthenticatedUser {
+                    subject: "alice".to_string(),
+                    agent_name: None,
+                    project_slug: None,
+                },
+                Duration::from_secs(60),
+            )
+            .await;
+        let csrf_token = sessions.get(&token).await.unwrap().csrf_token;
+        let app_state = AppState {
+            mm,
+            metrics_handle: crate::setup_metrics(),
+            start_time: std::time::Instant::now(),
+            auth_config,
+            jwks_client: None,
+            ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions,
+        };
+
+        let app = Router::new()
+            .route("/", get(handler).post(handler))
+            .layer(middleware::from_fn_with_state(app_state, auth_middleware));
+        let cookie = format!("{}={}", crate::session::SESSION_COOKIE, token);
+        let request = |method: &str, csrf: Option<&str>| {
+            let mut builder = Request::builder()
+                .method(method)
+                .uri("/")
+                .header("Cookie", &cookie);
+            if let Some(csrf) = csrf {
+                builder = builder.header(crate::session::CSRF_HEADER, csrf);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        // Reads only need the cookie
+        let response = app.clone().oneshot(request("GET", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Mutations also need the session's CSRF token
+        let response = app.clone().oneshot(request("POST", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(request("POST", Some("forged")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .oneshot(request("POST", Some(&csrf_token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn test_auth_jwt_success() {
         // 1. Generate RSA key pair and JWKS
@@ -731,6 +1133,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -741,6 +1145,8 @@ mod tests {
             auth_config,
             jwks_client,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -798,6 +1204,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -808,6 +1216,8 @@ mod tests {
             auth_config,
             jwks_client,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -864,6 +1274,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -874,6 +1286,8 @@ mod tests {
             auth_config,
             jwks_client,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -937,6 +1351,8 @@ mod tests {
             jwt_audience: Some("test-audience".to_string()),
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -947,6 +1363,8 @@ mod tests {
             auth_config,
             jwks_client,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -1010,6 +1428,8 @@ mod tests {
             jwt_audience: Some("expected-audience".to_string()),
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -1020,6 +1440,8 @@ mod tests {
             auth_config,
             jwks_client,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -1083,6 +1505,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: Some("https://test-issuer.example.com".to_string()),
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -1093,6 +1517,8 @@ mod tests {
             auth_config,
             jwks_client,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -1142,6 +1568,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -1152,6 +1580,8 @@ mod tests {
             auth_config,
             jwks_client,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         let app = Router::new()
@@ -1193,6 +1623,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: true, // Enable localhost bypass
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let app_state = AppState {
             mm,
@@ -1201,6 +1633,8 @@ mod tests {
             auth_config,
             jwks_client: None,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         // Create router with ConnectInfo support
@@ -1255,6 +1689,8 @@ mod tests {
             jwt_audience: None,
             jwt_issuer: None,
             allow_localhost: false, // Disable localhost bypass
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
         };
         let app_state = AppState {
             mm,
@@ -1263,6 +1699,8 @@ mod tests {
             auth_config,
             jwks_client: None,
             ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
         };
 
         // Create router with ConnectInfo support
@@ -1297,19 +1735,335 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_auth_api_token_scopes() {
+        use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+        use mouchak_mail_core::model::api_token::ApiTokenForCreate;
+        use mouchak_mail_core::model::project::ProjectBmc;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let repo_root = temp_dir.path().join("archive");
+        std::fs::create_dir_all(&repo_root).unwrap();
+
+        let db = libsql::Builder::new_local(db_path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        mouchak_mail_core::store::upgrade::migrate(&conn)
+            .await
+            .unwrap();
+        let app_config = Arc::new(AppConfig::default());
+        let mm = crate::ModelManager::new_for_test(conn, repo_root, app_config);
+
+        let ctx = mouchak_mail_core::Ctx::root_ctx();
+        let project_id = ProjectBmc::create(&ctx, &mm, "tokens", "/tokens")
+            .await
+            .unwrap();
+        let other_project_id = ProjectBmc::create(&ctx, &mm, "other", "/other")
+            .await
+            .unwrap();
+        let mut agent_ids = Vec::new();
+        for (project_id, name) in [
+            (project_id, "alice"),
+            (project_id, "bob"),
+            (other_project_id, "alice"),
+        ] {
+            let agent_id = AgentBmc::create(
+                &ctx,
+                &mm,
+                AgentForCreate {
+                    project_id,
+                    name: name.to_string(),
+                    program: "test".to_string(),
+                    model: "test".to_string(),
+                    task_description: String::new(),
+                },
+            )
+            .await
+            .unwrap();
+            agent_ids.push(agent_id);
+        }
+        let agent_id = agent_ids[0];
+        let issued = ApiTokenBmc::create(
+            &ctx,
+            &mm,
+            ApiTokenForCreate {
+                agent_id,
+                name: "ci".to_string(),
+                scopes: vec!["read:inbox".to_string()],
+                expires_ts: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let auth_config = AuthConfig {
+            mode: AuthMode::Bearer,
+            bearer_token: Some("secret123".to_string()),
+            jwks_url: None,
+            jwt_audience: None,
+            jwt_issuer: None,
+            allow_localhost: false,
+            trusted_proxies: Default::default(),
+            internal_networks: Vec::new(),
+        };
+        let app_state = AppState {
+            mm: mm.clone(),
+            metrics_handle: crate::setup_metrics(),
+            start_time: std::time::Instant::now(),
+            auth_config,
+            jwks_client: None,
+            ratelimit_config: crate::ratelimit::RateLimitConfig::new(),
+            oidc: None,
+            sessions: Default::default(),
+        };
+
+        let app = Router::new()
+            .route("/api/inbox", get(handler).post(echo))
+            .route("/api/message/send", get(handler))
+            .route("/api/projects", get(handler))
+            .route("/api/agents/{id}/{agent_name}/inbox/stream", get(handler))
+            .layer(middleware::from_fn_with_state(app_state, auth_middleware));
+        let request = |uri: &str, token: &str| {
+            Request::builder()
+                .uri(uri)
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let own_inbox = "/api/inbox?project_slug=tokens&agent_name=alice";
+
+        assert_eq!(
+            status(&app, request(own_inbox, &issued.secret)).await,
+            StatusCode::OK
+        );
+        // The project may be named by its human key
+        assert_eq!(
+            status(
+                &app,
+                request(
+                    "/api/inbox?project_key=/tokens&agent_name=alice",
+                    &issued.secret
+                )
+            )
+            .await,
+            StatusCode::OK
+        );
+
+        // read:inbox does not cover sending
+        assert_eq!(
+            status(
+                &app,
+                request(
+                    "/api/message/send?project_slug=tokens&sender_name=alice",
+                    &issued.secret
+                )
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        // Routes without a capability need admin
+        assert_eq!(
+            status(
+                &app,
+                request("/api/projects?project_slug=tokens", &issued.secret)
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+
+        assert_eq!(
+            status(&app, request("/api/inbox", "mmt_unknown")).await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        // Tokens keep reading while the server is read-only
+        mm.set_read_only(true).await.unwrap();
+        assert_eq!(
+            status(&app, request(own_inbox, &issued.secret)).await,
+            StatusCode::OK
+        );
+        mm.set_read_only(false).await.unwrap();
+
+        // A token only acts as its own agent in its own project
+        let json_request = |body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/inbox")
+                .header("Authorization", format!("Bearer {}", issued.secret))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let own = serde_json::json!({"project_slug": "tokens", "agent_name": "alice"});
+        let response = app
+            .clone()
+            .oneshot(json_request(own.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // The handler still receives the body
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            own
+        );
+
+        for (name, body) in [
+            (
+                "cross-agent",
+                serde_json::json!({"project_slug": "tokens", "agent_name": "bob"}),
+            ),
+            (
+                "cross-project",
+                serde_json::json!({"project_slug": "other", "agent_name": "alice"}),
+            ),
+            ("no project", serde_json::json!({"agent_name": "alice"})),
+        ] {
+            assert_eq!(
+                status(&app, json_request(body)).await,
+                StatusCode::FORBIDDEN,
+                "{}",
+                name
+            );
+        }
+        // Path parameters bind too
+        assert_eq!(
+            status(
+                &app,
+                request("/api/agents/tokens/alice/inbox/stream", &issued.secret)
+            )
+            .await,
+            StatusCode::OK
+        );
+        for uri in [
+            "/api/inbox?project_slug=tokens&agent_name=bob",
+            "/api/inbox?project_slug=other&agent_name=alice",
+            "/api/inbox?project_slug=tokens&project_slug=other&agent_name=alice",
+            "/api/agents/tokens/bob/inbox/stream",
+            "/api/agents/other/alice/inbox/stream",
+            "/api/agents/other/alice/inbox/stream?project_slug=tokens",
+        ] {
+            assert_eq!(
+                status(&app, request(uri, &issued.secret)).await,
+                StatusCode::FORBIDDEN,
+                "{}",
+                uri
+            );
+        }
+
+        // Admin tokens are not bound to an agent
+        let admin = ApiTokenBmc::create(
+            &ctx,
+            &mm,
+            ApiTokenForCreate {
+                agent_id,
+                name: "ops".to_string(),
+                scopes: vec!["admin".to_string()],
+                expires_ts: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            status(
+                &app,
+                request(
+                    "/api/inbox?project_slug=other&agent_name=alice",
+                    &admin.secret
+                )
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, request("/api/projects", &admin.secret)).await,
+            StatusCode::OK
+        );
+    }
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(required_scope("/api/inbox"), "read:inbox");
+        assert_eq!(required_scope("/api/outbox"), "read:outbox");
+        assert_eq!(required_scope("/api/message/reply"), "write:messages");
+        assert_eq!(required_scope("/api/ingest/email"), "write:messages");
+        assert_eq!(
+            required_scope("/api/file_reservations/paths"),
+            "write:reservations"
+        );
+        assert_eq!(
+            required_scope("/api/file_reservations/force_release/"),
+            "admin:reservations"
+        );
+        assert_eq!(required_scope("/api/admin/external_tools"), "admin");
+        assert_eq!(required_scope("/api/webhooks/3/deliveries"), "admin");
+        assert_eq!(
+            required_scope("/api/agents/tokens/alice/inbox/stream"),
+            "read:inbox"
+        );
+        assert_eq!(required_scope("/api/agents/tokens/inbox/stream"), "admin");
+        // Routes without a capability are closed to narrow tokens
+        assert_eq!(required_scope("/api/projects"), "admin");
+        assert_eq!(required_scope("/api/messages/7"), "admin");
+    }
+
+    #[test]
+    fn test_localhost_bypass_behind_trusted_proxy() {
+        let mut auth_config = AuthConfig {
+            mode: AuthMode::Bearer,
+            bearer_token: Some("secret123".to_string()),
+            jwks_url: None,
+            jwt_audience: None,
+            jwt_issuer: None,
+            allow_localhost: true,
+            trusted_proxies: TrustedProxies::new(client_ip::parse_networks("127.0.0.1").unwrap()),
+            internal_networks: client_ip::parse_networks("10.0.0.0/8").unwrap(),
+        };
+        // Every request arrives from the local proxy
+        let request = |forwarded_for: Option<&str>| {
+            let mut builder = Request::builder().uri("/");
+            if let Some(forwarded_for) = forwarded_for {
+                builder = builder.header("X-Forwarded-For", forwarded_for);
+            }
+            let mut req = builder.body(Body::empty()).unwrap();
+            req.extensions_mut()
+                .insert(ConnectInfo("127.0.0.1:4000".parse::<SocketAddr>().unwrap()));
+            req
+        };
+
+        assert!(should_bypass_auth(&request(None), &auth_config));
+        assert!(!should_bypass_auth(
+            &request(Some("203.0.113.5")),
+            &auth_config
+        ));
+        assert!(should_bypass_auth(&request(Some("10.2.3.4")), &auth_config));
+
+        // A spoofed header from an untrusted peer changes nothing
+        auth_config.trusted_proxies = TrustedProxies::default();
+        assert!(should_bypass_auth(
+            &request(Some("203.0.113.5")),
+            &auth_config
+        ));
+
+        auth_config.allow_localhost = false;
+        assert!(!should_bypass_auth(&request(None), &auth_config));
+    }
+
     #[test]
     fn test_is_localhost_ipv4() {
         use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
         let localhost = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
         assert!(
-            super::is_localhost(&localhost),
+            super::is_localhost(localhost.ip()),
             "127.0.0.1 should be localhost"
         );
 
         let external = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)), 8080);
         assert!(
-            !super::is_localhost(&external),
+            !super::is_localhost(external.ip()),
             "192.168.1.1 should not be localhost"
         );
     }
@@ -1319,14 +2073,17 @@ mod tests {
         use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 
         let localhost = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 8080);
-        assert!(super::is_localhost(&localhost), "::1 should be localhost");
+        assert!(
+            super::is_localhost(localhost.ip()),
+            "::1 should be localhost"
+        );
 
         let external = SocketAddr::new(
             IpAddr::V6(Ipv6Addr::new(2001, 0x0db8, 0, 0, 0, 0, 0, 1)),
             8080,
         );
         assert!(
-            !super::is_localhost(&external),
+            !super::is_localhost(external.ip()),
             "2001:db8::1 should not be localhost"
         );
     }
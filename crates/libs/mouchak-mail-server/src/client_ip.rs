@@ -0,0 +1,160 @@
+//! Client address resolution behind reverse proxies.
+//!
+//! The TCP peer of a request is only the client when nothing sits in
+//! between; behind a reverse proxy every request comes from the proxy, often
+//! `127.0.0.1`. [`TrustedProxies`] resolves the real client from
+//! `X-Forwarded-For`, but only across hops listed in `HTTP_TRUSTED_PROXIES`:
+//! the header is ignored on requests from anywhere else, so clients cannot
+//! spoof their address.
+//!
+//! Addresses are matched against [`IpNet`] lists (see
+//! [`mouchak_mail_common::net`]). The same lists drive the localhost auth
+//! bypass (`HTTP_INTERNAL_NETWORKS`) and rate limit exemptions
+//! (`RATE_LIMIT_EXEMPT_NETWORKS`).
+//!
+//! NIST Control: SC-7 (Boundary Protection)
+
+use axum::http::HeaderMap;
+use mouchak_mail_common::net::canonical;
+pub use mouchak_mail_common::net::{
+    IpNet, in_networks, is_loopback, is_private, networks_from_env, parse_networks,
+};
+use std::net::{IpAddr, SocketAddr};
+
+/// Environment variable listing the reverse proxies whose
+/// `X-Forwarded-For` is believed.
+pub const TRUSTED_PROXIES_ENV: &str = "HTTP_TRUSTED_PROXIES";
+
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Reverse proxies whose `X-Forwarded-For` header is believed.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    networks: Vec<IpNet>,
+}
+
+impl TrustedProxies {
+    pub fn new(networks: Vec<IpNet>) -> Self {
+        Self { networks }
+    }
+
+    /// Proxies listed in `HTTP_TRUSTED_PROXIES`; none when unset.
+    pub fn from_env() -> Self {
+        let networks = networks_from_env(TRUSTED_PROXIES_ENV);
+        if !networks.is_empty() {
+            tracing::info!(
+                "Trusting X-Forwarded-For from {}",
+                networks
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        Self::new(networks)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty()
+    }
+
+    /// Resolves the client of a request received from `peer`.
+    ///
+    /// Walks `X-Forwarded-For` from the nearest hop outwards while the hop
+    /// that added an entry is a trusted proxy, and returns the first address
+    /// not vouched for that way. Without trusted proxies this is `peer`.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let mut client = canonical(peer);
+        if self.networks.is_empty() {
+            return client;
+        }
+        // Repeated headers are one list, in order
+        let hops: Vec<&str> = headers
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        for hop in hops.iter().rev() {
+            if !in_networks(&self.networks, client) {
+                break;
+            }
+            match parse_hop(hop) {
+                Some(ip) => client = canonical(ip),
+                None => break,
+            }
+        }
+        client
+    }
+}
+
+/// Parses one `X-Forwarded-For` entry, which some proxies write with a port.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    let hop = hop.trim();
+    hop.parse::<IpAddr>()
+        .ok()
+        .or_else(|| hop.parse::<SocketAddr>().ok().map(|s| s.ip()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn forwarded(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(FORWARDED_FOR_HEADER, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_client_ip_ignores_forwarded_for_from_untrusted_peers() {
+        let headers = forwarded(&["198.51.100.1"]);
+        assert_eq!(
+            TrustedProxies::default().client_ip(ip("127.0.0.1"), &headers),
+            ip("127.0.0.1")
+        );
+
+        let proxies = TrustedProxies::new(parse_networks("10.0.0.5").unwrap());
+        assert_eq!(
+            proxies.client_ip(ip("203.0.113.7"), &headers),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn test_client_ip_walks_trusted_hops() {
+        let proxies = TrustedProxies::new(parse_networks("127.0.0.1, 10.0.0.0/8").unwrap());
+
+        // Client, then an internal load balancer, then the local proxy
+        let headers = forwarded(&["198.51.100.1, 10.0.0.4"]);
+        assert_eq!(
+            proxies.client_ip(ip("127.0.0.1"), &headers),
+            ip("198.51.100.1")
+        );
+
+        // A spoofed leftmost entry is not reached past an untrusted hop
+        let headers = forwarded(&["127.0.0.1", "203.0.113.9:4711, 10.0.0.4"]);
+        assert_eq!(
+            proxies.client_ip(ip("127.0.0.1"), &headers),
+            ip("203.0.113.9")
+        );
+
+        // Garbage stops the walk at the last trusted hop
+        let headers = forwarded(&["198.51.100.1, unknown"]);
+        assert_eq!(proxies.client_ip(ip("10.0.0.4"), &headers), ip("10.0.0.4"));
+
+        // No header: the proxy itself
+        assert_eq!(
+            proxies.client_ip(ip("127.0.0.1"), &HeaderMap::new()),
+            ip("127.0.0.1")
+        );
+    }
+}
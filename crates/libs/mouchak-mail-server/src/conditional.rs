@@ -0,0 +1,159 @@
+//! Conditional requests for polled listings.
+//!
+//! Inbox, thread and project listings carry a weak `ETag` built from the
+//! [`ChangeStamp`] of what they list, and a `Last-Modified` from its latest
+//! timestamp. When the client's `If-None-Match` (or, without one, its
+//! `If-Modified-Since`) still holds, handlers answer `304 Not Modified`
+//! before building the listing, which saves the listing queries as well as
+//! the bandwidth. The inbox and thread endpoints are POST for Python
+//! compatibility but only read, so they honor these headers too.
+//!
+//! `Cache-Control: no-cache` makes caches revalidate every time instead of
+//! guessing a freshness lifetime from `Last-Modified`.
+
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::NaiveDateTime;
+use mouchak_mail_core::model::listing::ChangeStamp;
+
+/// HTTP date format (IMF-fixdate); timestamps are stored in UTC.
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Validators of one listing response.
+#[derive(Debug, Clone)]
+pub struct Validators {
+    etag: String,
+    last_modified: Option<NaiveDateTime>,
+}
+
+impl Validators {
+    /// Validators for `stamp`; `variant` holds the request parameters that
+    /// select what the listing shows, such as the page or the reader.
+    pub fn new(stamp: &ChangeStamp, variant: &str) -> Self {
+        Self {
+            etag: format!("W/\"{}\"", stamp.digest(variant)),
+            last_modified: stamp.last_modified,
+        }
+    }
+
+    /// Whether the client's copy is still current.
+    ///
+    /// Entity tags compare weakly, as `If-None-Match` requires.
+    /// `If-Modified-Since` only counts when no `If-None-Match` is sent.
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        let tags: Vec<&str> = headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect();
+        if !tags.is_empty() {
+            let etag = opaque_tag(&self.etag);
+            return tags
+                .iter()
+                .any(|tag| *tag == "*" || opaque_tag(tag) == etag);
+        }
+        let since = headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| NaiveDateTime::parse_from_str(v.trim(), HTTP_DATE_FORMAT).ok());
+        matches!((self.last_modified, since), (Some(modified), Some(since)) if modified <= since)
+    }
+
+    /// An empty `304 Not Modified` carrying the validators.
+    pub fn not_modified(&self) -> Response {
+        self.apply(StatusCode::NOT_MODIFIED.into_response())
+    }
+
+    /// Adds the validators to a full response.
+    pub fn apply(&self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(modified) = self.last_modified
+            && let Ok(value) = HeaderValue::from_str(&modified.format(HTTP_DATE_FORMAT).to_string())
+        {
+            headers.insert(header::LAST_MODIFIED, value);
+        }
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        response
+    }
+}
+
+/// The quoted part of an entity tag, without the weak `W/` prefix.
+fn opaque_tag(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn validators() -> Validators {
+        Validators {
+            etag: "W/\"abc\"".to_string(),
+            last_modified: NaiveDateTime::parse_from_str(
+                "2025-03-01 12:00:00",
+                "%Y-%m-%d %H:%M:%S",
+            )
+            .ok(),
+        }
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let v = validators();
+        assert!(!v.matches(&HeaderMap::new()));
+        assert!(v.matches(&headers(&[(header::IF_NONE_MATCH, "W/\"abc\"")])));
+        // Weak comparison ignores the W/ prefix
+        assert!(v.matches(&headers(&[(header::IF_NONE_MATCH, "\"abc\"")])));
+        assert!(v.matches(&headers(&[(header::IF_NONE_MATCH, "\"x\", W/\"abc\"")])));
+        assert!(v.matches(&headers(&[(header::IF_NONE_MATCH, "*")])));
+        assert!(!v.matches(&headers(&[(header::IF_NONE_MATCH, "W/\"abd\"")])));
+        // If-None-Match wins over a matching If-Modified-Since
+        assert!(!v.matches(&headers(&[
+            (header::IF_NONE_MATCH, "W/\"old\""),
+            (header::IF_MODIFIED_SINCE, "Sat, 01 Mar 2025 12:00:00 GMT"),
+        ])));
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let v = validators();
+        let since = |date: &str| headers(&[(header::IF_MODIFIED_SINCE, date)]);
+        assert!(v.matches(&since("Sat, 01 Mar 2025 12:00:00 GMT")));
+        assert!(v.matches(&since("Sun, 02 Mar 2025 08:00:00 GMT")));
+        assert!(!v.matches(&since("Sat, 01 Mar 2025 11:59:59 GMT")));
+        assert!(!v.matches(&since("yesterday")));
+
+        let empty = Validators {
+            last_modified: None,
+            ..validators()
+        };
+        assert!(!empty.matches(&since("Sat, 01 Mar 2025 12:00:00 GMT")));
+    }
+
+    #[test]
+    fn test_not_modified_headers() {
+        let response = validators().not_modified();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let headers = response.headers();
+        assert_eq!(headers[header::ETAG], "W/\"abc\"");
+        assert_eq!(
+            headers[header::LAST_MODIFIED],
+            "Sat, 01 Mar 2025 12:00:00 GMT"
+        );
+        assert_eq!(headers[header::CACHE_CONTROL], "no-cache");
+    }
+}
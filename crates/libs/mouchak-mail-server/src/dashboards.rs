@@ -0,0 +1,421 @@
+//! Grafana dashboard and Prometheus alert rules for this server's metrics.
+//!
+//! Both are generated from the metric names in [`crate::telemetry`], so a
+//! renamed metric changes the dashboards with it. Exported by
+//! `mouchak-mail observability export-dashboards`.
+
+use crate::telemetry::{
+    ACK_BACKLOG, ACK_OLDEST_PENDING_AGE, DB_CONNECTIONS_CHECKED_OUT, DB_QUERY_DURATION,
+    DB_SLOW_QUERIES, DISK_FREE_BYTES, DISK_TOTAL_BYTES, ESCALATION_SWEEP_DURATION,
+    HTTP_REQUEST_DURATION, HTTP_REQUESTS, MCP_SSE_SESSIONS, QUEUE_DEPTH, READ_ONLY,
+    SCHEDULER_JOB_FAILURES, SCHEDULER_JOB_LAG,
+};
+use serde_json::{Value, json};
+
+/// File name of the generated Grafana dashboard.
+pub const DASHBOARD_FILE: &str = "grafana-dashboard.json";
+/// File name of the generated Prometheus rule file.
+pub const ALERTS_FILE: &str = "prometheus-alerts.yml";
+
+/// Share of 5xx responses that fires `MouchakHighErrorRate`.
+const ERROR_RATE_THRESHOLD: f64 = 0.05;
+/// p95 latency in seconds that fires `MouchakSlowRequests`.
+const LATENCY_P95_THRESHOLD_SECONDS: f64 = 1.0;
+/// Pending acknowledgments that, while still growing, fire `MouchakAckBacklogGrowing`.
+const ACK_BACKLOG_THRESHOLD: u64 = 50;
+/// Age of the oldest pending acknowledgment that fires `MouchakAckOverdue`.
+/// Matches the default escalation threshold of 24 hours.
+const ACK_OLDEST_AGE_THRESHOLD_SECONDS: u64 = 24 * 3600;
+/// Items in any queue that fire `MouchakQueueBacklog`.
+const QUEUE_DEPTH_THRESHOLD: u64 = 500;
+/// Seconds a periodic job may fall behind before `MouchakJobLagging` fires.
+const JOB_LAG_THRESHOLD_SECONDS: u64 = 300;
+/// Share of the data volume left free that fires `MouchakDiskSpaceLow`.
+const DISK_FREE_RATIO_THRESHOLD: f64 = 0.1;
+
+/// A Prometheus alerting rule.
+struct AlertRule {
+    name: &'static str,
+    expr: String,
+    for_duration: &'static str,
+    severity: &'static str,
+    summary: &'static str,
+    description: String,
+}
+
+/// A Grafana time series panel.
+struct Panel {
+    title: &'static str,
+    unit: &'static str,
+    targets: Vec<(String, &'static str)>,
+}
+
+fn error_ratio_expr() -> String {
+    format!(
+        r#"sum(rate({m}{{status=~"5.."}}[5m])) / clamp_min(sum(rate({m}[5m])), 1e-9)"#,
+        m = HTTP_REQUESTS
+    )
+}
+
+fn latency_expr(quantile: f64, by: &str) -> String {
+    format!(
+        "histogram_quantile({q}, sum by (le{by}) (rate({m}_bucket[5m])))",
+        q = quantile,
+        by = by,
+        m = HTTP_REQUEST_DURATION
+    )
+}
+
+fn alert_rules() -> Vec<AlertRule> {
+    vec![
+        AlertRule {
+            name: "MouchakHighErrorRate",
+            expr: format!("{} > {}", error_ratio_expr(), ERROR_RATE_THRESHOLD),
+            for_duration: "10m",
+            severity: "critical",
+            summary: "Mouchak is failing requests",
+            description: format!(
+                "More than {}% of HTTP requests returned 5xx over the last 10 minutes.",
+                ERROR_RATE_THRESHOLD * 100.0
+            ),
+        },
+        AlertRule {
+            name: "MouchakSlowRequests",
+            expr: format!(
+                "{} > {}",
+                latency_expr(0.95, ""),
+                LATENCY_P95_THRESHOLD_SECONDS
+            ),
+            for_duration: "15m",
+            severity: "warning",
+            summary: "Mouchak requests are slow",
+            description: format!(
+                "p95 HTTP latency has been above {}s for 15 minutes.",
+                LATENCY_P95_THRESHOLD_SECONDS
+            ),
+        },
+        AlertRule {
+            name: "MouchakAckBacklogGrowing",
+            expr: format!(
+                "{m} > {t} and deriv({m}[30m]) > 0",
+                m = ACK_BACKLOG,
+                t = ACK_BACKLOG_THRESHOLD
+            ),
+            for_duration: "30m",
+            severity: "warning",
+            summary: "Acknowledgments are piling up",
+            description: format!(
+                "More than {} acknowledgments are pending and the backlog is still growing.",
+                ACK_BACKLOG_THRESHOLD
+            ),
+        },
+        AlertRule {
+            name: "MouchakAckOverdue",
+            expr: format!(
+                "{} > {}",
+                ACK_OLDEST_PENDING_AGE, ACK_OLDEST_AGE_THRESHOLD_SECONDS
+            ),
+            for_duration: "15m",
+            severity: "warning",
+            summary: "An acknowledgment has been pending too long",
+            description: format!(
+                "The oldest pending acknowledgment is over {} hours old.",
+                ACK_OLDEST_AGE_THRESHOLD_SECONDS / 3600
+            ),
+        },
+        AlertRule {
+            name: "MouchakQueueBacklog",
+            expr: format!(
+                "max by (queue) ({}) > {}",
+                QUEUE_DEPTH, QUEUE_DEPTH_THRESHOLD
+            ),
+            for_duration: "15m",
+            severity: "warning",
+            summary: "A background queue is backing up",
+            description: format!(
+                "Queue {{{{ $labels.queue }}}} has held more than {} items for 15 minutes.",
+                QUEUE_DEPTH_THRESHOLD
+            ),
+        },
+        AlertRule {
+            name: "MouchakJobLagging",
+            expr: format!("{} > {}", SCHEDULER_JOB_LAG, JOB_LAG_THRESHOLD_SECONDS),
+            for_duration: "5m",
+            severity: "warning",
+            summary: "A periodic job is not running",
+            description: format!(
+                "Job {{{{ $labels.job }}}} is more than {} seconds behind its schedule.",
+                JOB_LAG_THRESHOLD_SECONDS
+            ),
+        },
+        AlertRule {
+            name: "MouchakJobFailing",
+            expr: format!("increase({}[30m]) > 0", SCHEDULER_JOB_FAILURES),
+            for_duration: "0m",
+            severity: "warning",
+            summary: "A periodic job is failing",
+            description: "Job {{ $labels.job }} returned errors in the last 30 minutes."
+                .to_string(),
+        },
+        AlertRule {
+            name: "MouchakDiskSpaceLow",
+            expr: format!(
+                "{} / clamp_min({}, 1) < {}",
+                DISK_FREE_BYTES, DISK_TOTAL_BYTES, DISK_FREE_RATIO_THRESHOLD
+            ),
+            for_duration: "15m",
+            severity: "warning",
+            summary: "The data volume is filling up",
+            description: format!(
+                "Less than {}% of the volume holding the data directory is free.",
+                DISK_FREE_RATIO_THRESHOLD * 100.0
+            ),
+        },
+        AlertRule {
+            name: "MouchakReadOnly",
+            expr: format!("{} > 0", READ_ONLY),
+            for_duration: "0m",
+            severity: "critical",
+            summary: "Mouchak is read-only",
+            description: "Writes are suspended because the data volume is nearly full. \
+                          They resume once space is freed."
+                .to_string(),
+        },
+    ]
+}
+
+fn panels() -> Vec<Panel> {
+    vec![
+        Panel {
+            title: "Request rate by status",
+            unit: "reqps",
+            targets: vec![(
+                format!("sum by (status) (rate({}[5m]))", HTTP_REQUESTS),
+                "{{status}}",
+            )],
+        },
+        Panel {
+            title: "Error rate (5xx)",
+            unit: "percentunit",
+            targets: vec![(error_ratio_expr(), "5xx ratio")],
+        },
+        Panel {
+            title: "p95 latency by route",
+            unit: "s",
+            targets: vec![(latency_expr(0.95, ", route"), "{{route}}")],
+        },
+        Panel {
+            title: "Ack backlog",
+            unit: "short",
+            targets: vec![(ACK_BACKLOG.to_string(), "pending")],
+        },
+        Panel {
+            title: "Oldest pending ack",
+            unit: "s",
+            targets: vec![(ACK_OLDEST_PENDING_AGE.to_string(), "age")],
+        },
+        Panel {
+            title: "Queue depths",
+            unit: "short",
+            targets: vec![(QUEUE_DEPTH.to_string(), "{{queue}}")],
+        },
+        Panel {
+            title: "Scheduler job lag",
+            unit: "s",
+            targets: vec![(SCHEDULER_JOB_LAG.to_string(), "{{job}}")],
+        },
+        Panel {
+            title: "Scheduler job failures",
+            unit: "short",
+            targets: vec![(
+                format!("increase({}[1h])", SCHEDULER_JOB_FAILURES),
+                "{{job}}",
+            )],
+        },
+        Panel {
+            title: "Escalation sweep duration (p95)",
+            unit: "s",
+            targets: vec![(
+                format!(
+                    "histogram_quantile(0.95, sum by (le) (rate({}_bucket[15m])))",
+                    ESCALATION_SWEEP_DURATION
+                ),
+                "p95",
+            )],
+        },
+        Panel {
+            title: "MCP SSE sessions",
+            unit: "short",
+            targets: vec![(MCP_SSE_SESSIONS.to_string(), "sessions")],
+        },
+        Panel {
+            title: "Data volume free space",
+            unit: "bytes",
+            targets: vec![
+                (DISK_FREE_BYTES.to_string(), "free"),
+                (DISK_TOTAL_BYTES.to_string(), "total"),
+            ],
+        },
+        Panel {
+            title: "Database hold time by BMC (p95)",
+            unit: "s",
+            targets: vec![(
+                format!(
+                    "histogram_quantile(0.95, sum by (le, bmc) (rate({}_bucket[5m])))",
+                    DB_QUERY_DURATION
+                ),
+                "{{bmc}}",
+            )],
+        },
+        Panel {
+            title: "Database connections checked out",
+            unit: "short",
+            targets: vec![(DB_CONNECTIONS_CHECKED_OUT.to_string(), "checked out")],
+        },
+        Panel {
+            title: "Slow database queries",
+            unit: "short",
+            targets: vec![(
+                format!("sum by (bmc) (increase({}[1h]))", DB_SLOW_QUERIES),
+                "{{bmc}}",
+            )],
+        },
+    ]
+}
+
+/// Grafana dashboard JSON, ready for "Import dashboard".
+///
+/// Panels query a `DS_PROMETHEUS` data source input chosen at import time.
+pub fn grafana_dashboard() -> Value {
+    let panels: Vec<Value> = panels()
+        .into_iter()
+        .enumerate()
+        .map(|(i, panel)| {
+            let targets: Vec<Value> = panel
+                .targets
+                .iter()
+                .zip('A'..)
+                .map(|((expr, legend), ref_id)| {
+                    json!({
+                        "datasource": { "type": "prometheus", "uid": "${DS_PROMETHEUS}" },
+                        "expr": expr,
+                        "legendFormat": legend,
+                        "refId": ref_id.to_string(),
+                    })
+                })
+                .collect();
+            json!({
+                "id": i + 1,
+                "type": "timeseries",
+                "title": panel.title,
+                "datasource": { "type": "prometheus", "uid": "${DS_PROMETHEUS}" },
+                "gridPos": { "h": 8, "w": 12, "x": (i % 2) * 12, "y": (i / 2) * 8 },
+                "fieldConfig": { "defaults": { "unit": panel.unit }, "overrides": [] },
+                "targets": targets,
+            })
+        })
+        .collect();
+
+    json!({
+        "__inputs": [{
+            "name": "DS_PROMETHEUS",
+            "label": "Prometheus",
+            "type": "datasource",
+            "pluginId": "prometheus",
+            "pluginName": "Prometheus",
+        }],
+        "title": "Mouchak Mail",
+        "uid": "mouchak-mail",
+        "tags": ["mouchak-mail"],
+        "timezone": "browser",
+        "schemaVersion": 39,
+        "refresh": "30s",
+        "time": { "from": "now-6h", "to": "now" },
+        "panels": panels,
+    })
+}
+
+/// Prometheus rule file with the alerting rules, as YAML.
+pub fn prometheus_alert_rules() -> String {
+    let mut out = String::from("groups:\n  - name: mouchak-mail\n    rules:\n");
+    for rule in alert_rules() {
+        out.push_str(&format!("      - alert: {}\n", rule.name));
+        out.push_str(&format!("        expr: {}\n", yaml_quote(&rule.expr)));
+        out.push_str(&format!("        for: {}\n", rule.for_duration));
+        out.push_str("        labels:\n");
+        out.push_str(&format!("          severity: {}\n", rule.severity));
+        out.push_str("        annotations:\n");
+        out.push_str(&format!(
+            "          summary: {}\n",
+            yaml_quote(rule.summary)
+        ));
+        out.push_str(&format!(
+            "          description: {}\n",
+            yaml_quote(&rule.description)
+        ));
+    }
+    out
+}
+
+/// Single-quoted YAML scalar, safe for PromQL and `{{ }}` templates.
+fn yaml_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::telemetry::metric;
+
+    /// Metric names referenced by a PromQL expression.
+    fn metric_names(expr: &str) -> Vec<String> {
+        expr.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .filter(|word| word.starts_with("mouchak_") || word.starts_with("http_"))
+            .map(|word| {
+                ["_bucket", "_sum", "_count"]
+                    .iter()
+                    .find_map(|suffix| word.strip_suffix(suffix))
+                    .filter(|base| metric(base).is_some())
+                    .unwrap_or(word)
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_every_referenced_metric_is_registered() {
+        let exprs = alert_rules().into_iter().map(|rule| rule.expr).chain(
+            panels()
+                .into_iter()
+                .flat_map(|p| p.targets.into_iter().map(|(expr, _)| expr)),
+        );
+        let mut checked = 0;
+        for expr in exprs {
+            for name in metric_names(&expr) {
+                assert!(
+                    metric(&name).is_some(),
+                    "{} in `{}` is not registered",
+                    name,
+                    expr
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn test_dashboard_and_rules_render() {
+        let dashboard = grafana_dashboard();
+        let panels = dashboard["panels"].as_array().expect("panels");
+        assert_eq!(panels.len(), super::panels().len());
+        assert_eq!(panels[0]["targets"][0]["refId"], "A");
+
+        let rules = prometheus_alert_rules();
+        assert!(rules.starts_with("groups:\n"));
+        assert!(rules.contains("      - alert: MouchakAckBacklogGrowing\n"));
+        assert!(rules.contains(r#"expr: 'sum(rate(http_requests_total{status=~"5.."}[5m]))"#));
+        assert!(rules.contains("description: 'Job {{ $labels.job }} returned errors"));
+    }
+}
@@ -0,0 +1,307 @@
+//! Outbound email bridge.
+//!
+//! Mails the messages [`EmailBridgeBmc::pending`] collects from opted-in
+//! projects to human mailboxes when `email_bridge` forwards them (by
+//! importance or subject keyword), sent through [`crate::smtp`].
+//!
+//! A message the relay refuses for good (`5xx`) is skipped with a warning.
+//! Any other failure stops the scan before that message, so it is retried
+//! on the next scan instead of being lost while the relay is down.
+
+use crate::ServerError;
+use crate::smtp::{self, Security, SmtpError};
+use chrono::{TimeZone, Utc};
+use lettre::Message;
+use lettre::message::header::{HeaderName, HeaderValue};
+use lettre::message::{Mailbox, SinglePart};
+use mouchak_mail_common::config::EmailBridgeConfig;
+use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::ModelManager;
+use mouchak_mail_core::model::email_bridge::{
+    BridgedMessage, EmailBridgeBmc, message_id_part, parse_addresses,
+};
+use mouchak_mail_core::model::email_ingest::parse_aliases;
+
+/// Maximum messages considered per scan.
+const MESSAGES_PER_SCAN: i64 = 50;
+
+/// Checks the bridge settings at startup, so a typo fails loudly instead of
+/// stalling every scan.
+pub fn validate(config: &EmailBridgeConfig) -> Result<(), ServerError> {
+    let invalid = |key: &str, e: mouchak_mail_core::Error| {
+        ServerError::ConfigError(format!("email_bridge.{}: {}", key, e))
+    };
+    parse_aliases(&config.inbound_aliases).map_err(|e| invalid("inbound_aliases", e))?;
+    if config.smtp_host().is_none() {
+        return Ok(());
+    }
+    Security::parse(&config.smtp_security).map_err(|e| ServerError::ConfigError(e.to_string()))?;
+    if parse_addresses(&config.from)
+        .map_err(|e| invalid("from", e))?
+        .len()
+        != 1
+    {
+        return Err(ServerError::ConfigError(
+            "email_bridge.from must be a single address".to_string(),
+        ));
+    }
+    parse_addresses(&config.to).map_err(|e| invalid("to", e))?;
+    Ok(())
+}
+
+/// Mails forwarded messages created since the last scan.
+///
+/// # Returns
+/// Number of messages accepted by the relay.
+pub async fn deliver_pending(
+    mm: &ModelManager,
+    config: &EmailBridgeConfig,
+) -> Result<usize, ServerError> {
+    let ctx = Ctx::root_ctx();
+    let batch = EmailBridgeBmc::pending(&ctx, mm, MESSAGES_PER_SCAN).await?;
+    let default_recipients = parse_addresses(&config.to)?;
+
+    let mut sent = 0;
+    for message in &batch.messages {
+        if !config.forwards(&message.importance, &message.subject) {
+            continue;
+        }
+        let recipients = if message.recipients.is_empty() {
+            &default_recipients
+        } else {
+            &message.recipients
+        };
+        if recipients.is_empty() {
+            tracing::warn!(
+                message = message.message_id,
+                project = %message.project_slug,
+                "Email bridge: no recipients; set the project's email_recipients or email_bridge.to"
+            );
+            continue;
+        }
+
+        let result = match compose(config, message, recipients) {
+            Ok(mail) => smtp::send_mail(config, &mail).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => sent += 1,
+            Err(e) if e.is_permanent() => {
+                tracing::warn!(
+                    message = message.message_id,
+                    "Email bridge: relay refused message: {}",
+                    e
+                );
+            }
+            Err(e) => {
+                // Everything before this message is done; retry from here
+                EmailBridgeBmc::advance_cursor(&ctx, mm, message.message_id - 1).await?;
+                return Err(ServerError::Internal(format!(
+                    "Email bridge: message {}: {}",
+                    message.message_id, e
+                )));
+            }
+        }
+    }
+
+    EmailBridgeBmc::advance_cursor(&ctx, mm, batch.scanned_to).await?;
+    Ok(sent)
+}
+
+/// Builds the mail for `message`: headers, then a UTF-8 text body.
+///
+/// The first message of a thread gets the thread's `Message-ID`; later ones
+/// point at it with `In-Reply-To` and `References`, so mail clients thread
+/// them even when their subjects differ.
+///
+/// # Errors
+/// Returns [`SmtpError::Recipient`] for a recipient that is not an address
+/// and [`SmtpError::Config`] for an invalid `email_bridge.from`.
+pub fn compose(
+    config: &EmailBridgeConfig,
+    message: &BridgedMessage,
+    recipients: &[String],
+) -> Result<Message, SmtpError> {
+    let from = config.from.trim();
+    let from = from.parse().map_err(|e| {
+        SmtpError::Config(format!("email_bridge.from '{}' is invalid: {}", from, e))
+    })?;
+    let domain = config.message_id_domain();
+    let slug = message_id_part(&message.project_slug);
+    let thread_ref = format!("<thread-{}.{}@{}>", message.thread_key(), slug, domain);
+
+    let mut builder = Message::builder()
+        .from(Mailbox::new(
+            Some(format!("{} via Mouchak Mail", message.sender_name)),
+            from,
+        ))
+        .subject(message.email_subject())
+        .date(Utc.from_utc_datetime(&message.created_ts).into());
+    for recipient in recipients {
+        let address = recipient
+            .parse()
+            .map_err(|_| SmtpError::Recipient(recipient.clone()))?;
+        builder = builder.to(Mailbox::new(None, address));
+    }
+    builder = if message.is_reply {
+        builder
+            .message_id(Some(format!(
+                "<msg-{}.{}@{}>",
+                message.message_id, slug, domain
+            )))
+            .in_reply_to(thread_ref.clone())
+            .references(thread_ref)
+    } else {
+        builder.message_id(Some(thread_ref))
+    };
+    for (name, value) in [
+        ("X-Mouchak-Project", slug),
+        ("X-Mouchak-Importance", message_id_part(&message.importance)),
+        ("Auto-Submitted", "auto-generated".to_string()),
+    ] {
+        builder = builder.raw_header(HeaderValue::new(
+            HeaderName::new_from_ascii_str(name),
+            value,
+        ));
+    }
+
+    let mut body = format!(
+        "{} wrote in {} ({}):\n\n{}\n",
+        message.sender_name, message.project_slug, message.importance, message.body_md
+    );
+    if let Some(thread_id) = &message.thread_id {
+        body.push_str(&format!(
+            "\n-- \nThread {} - message {}\n",
+            thread_id, message.message_id
+        ));
+    }
+    Ok(builder.singlepart(SinglePart::plain(body))?)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn message(is_reply: bool) -> BridgedMessage {
+        BridgedMessage {
+            message_id: 42,
+            project_id: 1,
+            project_slug: "backend".to_string(),
+            sender_name: "BlueLake".to_string(),
+            subject: if is_reply {
+                "Re: Prod down"
+            } else {
+                "Prod down"
+            }
+            .to_string(),
+            body_md: "Database is gone.\n.\nHelp".to_string(),
+            importance: "urgent".to_string(),
+            thread_id: Some("T-1".to_string()),
+            created_ts: NaiveDateTime::parse_from_str("2025-03-01 12:00:00", "%Y-%m-%d %H:%M:%S")
+                .unwrap(),
+            is_reply,
+            recipients: Vec::new(),
+        }
+    }
+
+    fn config() -> EmailBridgeConfig {
+        EmailBridgeConfig {
+            smtp_host: Some("smtp.example.com".to_string()),
+            from: "bridge@example.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn formatted(to: &[&str], is_reply: bool) -> String {
+        let to: Vec<String> = to.iter().map(|t| t.to_string()).collect();
+        let mail = compose(&config(), &message(is_reply), &to).unwrap();
+        String::from_utf8(mail.formatted()).unwrap()
+    }
+
+    fn header<'a>(mail: &'a str, name: &str) -> Option<&'a str> {
+        mail.split("\r\n\r\n")
+            .next()
+            .unwrap()
+            .lines()
+            .find_map(|l| l.strip_prefix(&format!("{}: ", name)))
+    }
+
+    #[test]
+    fn test_compose_thread_root() {
+        let mail = formatted(&["ops@example.com", "lead@example.com"], false);
+        assert_eq!(header(&mail, "Subject"), Some("[backend] Prod down"));
+        assert_eq!(
+            header(&mail, "To"),
+            Some("ops@example.com, lead@example.com")
+        );
+        assert_eq!(
+            header(&mail, "From"),
+            Some("\"BlueLake via Mouchak Mail\" <bridge@example.com>")
+        );
+        assert_eq!(
+            header(&mail, "Message-ID"),
+            Some("<thread-T-1.backend@example.com>")
+        );
+        assert_eq!(header(&mail, "In-Reply-To"), None);
+        assert_eq!(
+            header(&mail, "Date"),
+            Some("Sat, 01 Mar 2025 12:00:00 +0000")
+        );
+        assert_eq!(header(&mail, "Auto-Submitted"), Some("auto-generated"));
+
+        assert_eq!(
+            header(&mail, "Content-Type"),
+            Some("text/plain; charset=utf-8")
+        );
+
+        let body = mail.split_once("\r\n\r\n").unwrap().1;
+        assert!(body.starts_with("BlueLake wrote in backend (urgent):\r\n\r\nDatabase is gone."));
+        assert!(body.contains("Thread T-1 - message 42"));
+    }
+
+    #[test]
+    fn test_compose_reply_references_thread() {
+        let mail = formatted(&["ops@example.com"], true);
+        assert_eq!(header(&mail, "Subject"), Some("Re: [backend] Prod down"));
+        assert_eq!(
+            header(&mail, "Message-ID"),
+            Some("<msg-42.backend@example.com>")
+        );
+        assert_eq!(
+            header(&mail, "In-Reply-To"),
+            Some("<thread-T-1.backend@example.com>")
+        );
+        assert_eq!(
+            header(&mail, "References"),
+            Some("<thread-T-1.backend@example.com>")
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&EmailBridgeConfig::default()).is_ok());
+        assert!(validate(&config()).is_ok());
+        for bad in [
+            EmailBridgeConfig {
+                inbound_aliases: "lead@example.com".to_string(),
+                ..Default::default()
+            },
+            EmailBridgeConfig {
+                smtp_security: "ssl3".to_string(),
+                ..config()
+            },
+            EmailBridgeConfig {
+                from: "a@example.com,b@example.com".to_string(),
+                ..config()
+            },
+            EmailBridgeConfig {
+                to: "ops".to_string(),
+                ..config()
+            },
+        ] {
+            assert!(validate(&bad).is_err());
+        }
+    }
+}
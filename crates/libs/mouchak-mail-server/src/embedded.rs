@@ -8,3 +8,26 @@ use rust_embed::Embed;
 #[derive(Embed)]
 #[folder = "../../services/web-ui/build"]
 pub struct Assets;
+
+/// API version the embedded UI was built against, from its `ui-version.json`.
+pub fn ui_api_version() -> Option<String> {
+    let file = Assets::get(crate::api::version::UI_VERSION_FILE)?;
+    let json: serde_json::Value = serde_json::from_slice(&file.data).ok()?;
+    json.get("api_version")?.as_str().map(str::to_string)
+}
+
+/// Warns at startup when the embedded UI does not match this server's API.
+pub fn warn_on_version_mismatch() {
+    match ui_api_version() {
+        Some(ui_api_version) => {
+            if let Err(mismatch) = crate::api::version::check_ui_compatibility(&ui_api_version) {
+                tracing::warn!("Embedded web UI may not work: {}", mismatch);
+            }
+        }
+        None => tracing::warn!(
+            "Embedded web UI has no {}; it predates API {} and may not work with this server",
+            crate::api::version::UI_VERSION_FILE,
+            crate::api::version::MIN_UI_API_VERSION
+        ),
+    }
+}
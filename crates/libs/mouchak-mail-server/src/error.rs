@@ -26,6 +26,7 @@ pub enum ErrorCode {
     NotFound,
     Conflict,
     ValidationError,
+    RateLimited,
 
     // 5xx Server Errors
     InternalError,
@@ -45,6 +46,7 @@ impl ErrorCode {
             ErrorCode::NotFound => "NOT_FOUND",
             ErrorCode::Conflict => "CONFLICT",
             ErrorCode::ValidationError => "VALIDATION_ERROR",
+            ErrorCode::RateLimited => "RATE_LIMITED",
             ErrorCode::InternalError => "INTERNAL_ERROR",
             ErrorCode::DatabaseError => "DATABASE_ERROR",
             ErrorCode::ServiceUnavailable => "SERVICE_UNAVAILABLE",
@@ -165,6 +167,12 @@ fn is_unique_constraint_error(msg: &str) -> bool {
         || msg_lower.contains("already exists")
 }
 
+/// Checks if an error is a write refused while the server is read-only
+/// (`PRAGMA query_only`, set by the disk watcher).
+fn is_read_only_error(msg: &str) -> bool {
+    msg.to_lowercase().contains("readonly database")
+}
+
 /// Extracts a user-friendly message from a constraint error.
 fn extract_conflict_message(msg: &str) -> String {
     // Parse common patterns like "UNIQUE constraint failed: agents.project_id, agents.name"
@@ -205,10 +213,15 @@ fn sanitize_error_message(error: &mouchak_mail_core::Error) -> String {
         mouchak_mail_core::Error::InvalidInput(msg) => format!("Invalid input: {}", msg),
         mouchak_mail_core::Error::AuthError => "Authentication failed".to_string(),
         // For database errors, check if it's a unique constraint
-        mouchak_mail_core::Error::Libsql(e) => {
-            let msg = e.to_string();
+        mouchak_mail_core::Error::Libsql(_)
+        | mouchak_mail_core::Error::Postgres(_)
+        | mouchak_mail_core::Error::PostgresPool(_) => {
+            let msg = error.to_string();
             if is_unique_constraint_error(&msg) {
                 extract_conflict_message(&msg)
+            } else if is_read_only_error(&msg) {
+                "Server is read-only: disk space is low; writes resume once space is freed"
+                    .to_string()
             } else {
                 // Don't expose raw SQL errors
                 "Database operation failed".to_string()
@@ -221,6 +234,12 @@ fn sanitize_error_message(error: &mouchak_mail_core::Error) -> String {
         mouchak_mail_core::Error::Validation(ve) => ve.to_string(),
         mouchak_mail_core::Error::Image(_) => "Image processing failed".to_string(),
         mouchak_mail_core::Error::QuotaExceeded(msg) => format!("Quota exceeded: {}", msg),
+        mouchak_mail_core::Error::ContactDenied(msg) => format!("Contact denied: {}", msg),
+        mouchak_mail_core::Error::PluginRejected(msg) => format!("Rejected by plugin: {}", msg),
+        mouchak_mail_core::Error::DelegationDenied(msg) => format!("Delegation denied: {}", msg),
+        mouchak_mail_core::Error::ApprovalDenied(msg) => format!("Approval denied: {}", msg),
+        mouchak_mail_core::Error::UpgradeRequired(msg) => format!("Data upgrade required: {}", msg),
+        mouchak_mail_core::Error::ReadOnly(msg) => format!("Server is read-only: {}", msg),
         mouchak_mail_core::Error::EncryptionError(_) => "Encryption operation failed".to_string(),
         mouchak_mail_core::Error::DecryptionError(_) => "Decryption operation failed".to_string(),
     }
@@ -242,9 +261,14 @@ fn map_core_error_to_status(error: &mouchak_mail_core::Error) -> StatusCode {
         mouchak_mail_core::Error::AuthError => StatusCode::UNAUTHORIZED,
         mouchak_mail_core::Error::SerdeJson(_) => StatusCode::BAD_REQUEST,
 
-        mouchak_mail_core::Error::Libsql(e) => {
-            if is_unique_constraint_error(&e.to_string()) {
+        mouchak_mail_core::Error::Libsql(_)
+        | mouchak_mail_core::Error::Postgres(_)
+        | mouchak_mail_core::Error::PostgresPool(_) => {
+            let msg = error.to_string();
+            if is_unique_constraint_error(&msg) {
                 StatusCode::CONFLICT
+            } else if is_read_only_error(&msg) {
+                StatusCode::SERVICE_UNAVAILABLE
             } else {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
@@ -257,6 +281,13 @@ fn map_core_error_to_status(error: &mouchak_mail_core::Error) -> StatusCode {
         mouchak_mail_core::Error::Validation(_) => StatusCode::BAD_REQUEST,
         mouchak_mail_core::Error::Image(_) => StatusCode::BAD_REQUEST,
         mouchak_mail_core::Error::QuotaExceeded(_) => StatusCode::FORBIDDEN, // 403 Forbidden for quota issues
+        mouchak_mail_core::Error::ContactDenied(_) => StatusCode::FORBIDDEN,
+        mouchak_mail_core::Error::PluginRejected(_) => StatusCode::FORBIDDEN,
+        mouchak_mail_core::Error::DelegationDenied(_) => StatusCode::FORBIDDEN,
+        mouchak_mail_core::Error::ApprovalDenied(_) => StatusCode::FORBIDDEN,
+        mouchak_mail_core::Error::UpgradeRequired(_) | mouchak_mail_core::Error::ReadOnly(_) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
         mouchak_mail_core::Error::EncryptionError(_)
         | mouchak_mail_core::Error::DecryptionError(_) => StatusCode::INTERNAL_SERVER_ERROR,
     }
@@ -280,9 +311,14 @@ fn map_core_error_to_code(error: &mouchak_mail_core::Error) -> ErrorCode {
 
         mouchak_mail_core::Error::AuthError => ErrorCode::Unauthorized,
 
-        mouchak_mail_core::Error::Libsql(e) => {
-            if is_unique_constraint_error(&e.to_string()) {
+        mouchak_mail_core::Error::Libsql(_)
+        | mouchak_mail_core::Error::Postgres(_)
+        | mouchak_mail_core::Error::PostgresPool(_) => {
+            let msg = error.to_string();
+            if is_unique_constraint_error(&msg) {
                 ErrorCode::Conflict
+            } else if is_read_only_error(&msg) {
+                ErrorCode::ServiceUnavailable
             } else {
                 ErrorCode::DatabaseError
             }
@@ -293,7 +329,14 @@ fn map_core_error_to_code(error: &mouchak_mail_core::Error) -> ErrorCode {
         | mouchak_mail_core::Error::LockTimeout { .. } => ErrorCode::InternalError,
 
         mouchak_mail_core::Error::Image(_) => ErrorCode::ValidationError,
-        mouchak_mail_core::Error::QuotaExceeded(_) => ErrorCode::Forbidden,
+        mouchak_mail_core::Error::QuotaExceeded(_)
+        | mouchak_mail_core::Error::ContactDenied(_)
+        | mouchak_mail_core::Error::PluginRejected(_)
+        | mouchak_mail_core::Error::DelegationDenied(_)
+        | mouchak_mail_core::Error::ApprovalDenied(_) => ErrorCode::Forbidden,
+        mouchak_mail_core::Error::UpgradeRequired(_) | mouchak_mail_core::Error::ReadOnly(_) => {
+            ErrorCode::ServiceUnavailable
+        }
         mouchak_mail_core::Error::EncryptionError(_)
         | mouchak_mail_core::Error::DecryptionError(_) => ErrorCode::InternalError,
     }
@@ -383,6 +426,14 @@ mod tests {
         assert!(!is_unique_constraint_error("Some other error"));
     }
 
+    #[test]
+    fn test_read_only_detection() {
+        assert!(is_read_only_error(
+            "SQLite failure: `attempt to write a readonly database`"
+        ));
+        assert!(!is_read_only_error("database is locked"));
+    }
+
     #[test]
     fn test_conflict_message_extraction() {
         let msg = "UNIQUE constraint failed: agents.project_id, agents.name";
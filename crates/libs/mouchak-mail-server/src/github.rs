@@ -0,0 +1,286 @@
+//! GitHub cross-posting.
+//!
+//! Mirrors threads to issues or PRs of the repository mapped to their
+//! project and syncs them in both directions with [`GithubSyncBmc`]: new
+//! thread messages become issue comments and new issue comments become
+//! messages from the project's `github` agent. Uses the REST API with the
+//! configured token.
+
+use crate::ServerError;
+use mouchak_mail_common::config::GithubConfig;
+use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::ModelManager;
+use mouchak_mail_core::model::github_sync::{
+    GithubComment, GithubSyncBmc, GithubThreadLink, GithubThreadLinkForCreate, mirror_body,
+};
+use mouchak_mail_core::model::message::MessageBmc;
+use mouchak_mail_core::types::{MessageId, ProjectId};
+use reqwest::{Client, Method, RequestBuilder};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// Comments fetched per page.
+const COMMENTS_PER_PAGE: usize = 100;
+
+/// Time allowed for one API request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct IssueResponse {
+    number: i64,
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct UserResponse {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct CommentResponse {
+    id: i64,
+    user: Option<UserResponse>,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+}
+
+/// Minimal GitHub REST client for issues and their comments.
+pub struct GithubClient {
+    http: Client,
+    api_url: String,
+    token: String,
+}
+
+impl GithubClient {
+    /// Creates a client, or fails if no token is configured.
+    pub fn new(config: &GithubConfig) -> Result<Self, ServerError> {
+        let token = config.token().ok_or_else(|| {
+            ServerError::ConfigError("GitHub cross-posting requires GITHUB_TOKEN".to_string())
+        })?;
+        Ok(Self {
+            http: Client::new(),
+            api_url: config.api_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", self.api_url, path))
+            .timeout(REQUEST_TIMEOUT)
+            .bearer_auth(&self.token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "mouchak-mail")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+    }
+
+    async fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        what: &str,
+    ) -> Result<T, ServerError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ServerError::Internal(format!("GitHub {} failed: {}", what, e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ServerError::BadRequest(format!(
+                "GitHub {} failed with {}: {}",
+                what,
+                status,
+                body.chars().take(200).collect::<String>()
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| ServerError::Internal(format!("Invalid GitHub {} response: {}", what, e)))
+    }
+
+    async fn create_issue(
+        &self,
+        repo: &str,
+        title: &str,
+        body: &str,
+    ) -> Result<IssueResponse, ServerError> {
+        let request = self
+            .request(Method::POST, &format!("/repos/{}/issues", repo))
+            .json(&json!({ "title": title, "body": body }));
+        self.send(request, "issue creation").await
+    }
+
+    /// Works for pull requests too: every PR is an issue.
+    async fn get_issue(&self, repo: &str, number: i64) -> Result<IssueResponse, ServerError> {
+        let request = self.request(Method::GET, &format!("/repos/{}/issues/{}", repo, number));
+        self.send(request, "issue lookup").await
+    }
+
+    async fn create_comment(&self, repo: &str, number: i64, body: &str) -> Result<(), ServerError> {
+        let request = self
+            .request(
+                Method::POST,
+                &format!("/repos/{}/issues/{}/comments", repo, number),
+            )
+            .json(&json!({ "body": body }));
+        self.send::<serde_json::Value>(request, "comment").await?;
+        Ok(())
+    }
+
+    /// Lists all comments of an issue, oldest first.
+    async fn list_comments(
+        &self,
+        repo: &str,
+        number: i64,
+    ) -> Result<Vec<GithubComment>, ServerError> {
+        let mut comments = Vec::new();
+        for page in 1.. {
+            let request = self
+                .request(
+                    Method::GET,
+                    &format!("/repos/{}/issues/{}/comments", repo, number),
+                )
+                .query(&[("per_page", COMMENTS_PER_PAGE), ("page", page)]);
+            let batch: Vec<CommentResponse> = self.send(request, "comment listing").await?;
+            let last_page = batch.len() < COMMENTS_PER_PAGE;
+            comments.extend(batch.into_iter().map(|c| {
+                GithubComment {
+                    id: c.id,
+                    author: c
+                        .user
+                        .map(|u| u.login)
+                        .unwrap_or_else(|| "ghost".to_string()),
+                    body: c.body.unwrap_or_default(),
+                    html_url: c.html_url,
+                }
+            }));
+            if last_page {
+                break;
+            }
+        }
+        Ok(comments)
+    }
+}
+
+/// Mirrors a thread to GitHub and posts its messages.
+///
+/// With `issue_number`, the thread is linked to that existing issue or PR
+/// and all its messages are posted as comments. Otherwise a new issue is
+/// opened with the first message as its body. Mirroring an already linked
+/// thread only posts messages that are new since the last sync.
+pub async fn mirror_thread(
+    mm: &ModelManager,
+    config: &GithubConfig,
+    project_id: ProjectId,
+    thread_id: &str,
+    issue_number: Option<i64>,
+) -> Result<GithubThreadLink, ServerError> {
+    let ctx = Ctx::root_ctx();
+    let client = GithubClient::new(config)?;
+
+    let link = match GithubSyncBmc::get_link(&ctx, mm, project_id, thread_id).await? {
+        Some(link) => link,
+        None => {
+            let repo = GithubSyncBmc::get_repo(&ctx, mm, project_id)
+                .await?
+                .ok_or_else(|| {
+                    ServerError::BadRequest("Project has no GitHub repository".to_string())
+                })?
+                .repo;
+            let messages = MessageBmc::list_by_thread(&ctx, mm, project_id, thread_id).await?;
+            let Some(first) = messages.first() else {
+                return Err(ServerError::not_found(format!("Thread {}", thread_id)));
+            };
+
+            let (issue, last_message_id) = match issue_number {
+                Some(number) => (client.get_issue(&repo, number).await?, 0),
+                None => {
+                    let issue = client
+                        .create_issue(&repo, &first.subject, &mirror_body(first))
+                        .await?;
+                    (issue, first.id)
+                }
+            };
+            GithubSyncBmc::link_thread(
+                &ctx,
+                mm,
+                GithubThreadLinkForCreate {
+                    project_id,
+                    thread_id: thread_id.to_string(),
+                    repo,
+                    issue_number: issue.number,
+                    issue_url: issue.html_url,
+                    last_message_id,
+                },
+            )
+            .await?
+        }
+    };
+
+    post_new_messages(&ctx, mm, &client, &link).await?;
+    Ok(GithubSyncBmc::get_link(&ctx, mm, project_id, thread_id)
+        .await?
+        .unwrap_or(link))
+}
+
+/// Syncs every mirrored thread in both directions.
+///
+/// A thread that fails to sync is logged and retried on the next run.
+///
+/// # Returns
+/// Number of messages posted and comments imported.
+pub async fn sync_all(mm: &ModelManager, config: &GithubConfig) -> Result<usize, ServerError> {
+    let ctx = Ctx::root_ctx();
+    let client = GithubClient::new(config)?;
+    let mut synced = 0;
+    for link in GithubSyncBmc::list_links(&ctx, mm, None).await? {
+        match sync_link(&ctx, mm, &client, &link).await {
+            Ok(count) => synced += count,
+            Err(e) => tracing::warn!(
+                thread = %link.thread_id,
+                issue = %format!("{}#{}", link.repo, link.issue_number),
+                "GitHub sync failed: {}",
+                e
+            ),
+        }
+    }
+    Ok(synced)
+}
+
+async fn sync_link(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    client: &GithubClient,
+    link: &GithubThreadLink,
+) -> Result<usize, ServerError> {
+    let mut synced = post_new_messages(ctx, mm, client, link).await?;
+    for comment in client.list_comments(&link.repo, link.issue_number).await? {
+        if GithubSyncBmc::import_comment(ctx, mm, link, &comment)
+            .await?
+            .is_some()
+        {
+            synced += 1;
+        }
+    }
+    Ok(synced)
+}
+
+async fn post_new_messages(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    client: &GithubClient,
+    link: &GithubThreadLink,
+) -> Result<usize, ServerError> {
+    let messages = GithubSyncBmc::unposted_messages(ctx, mm, link).await?;
+    for message in &messages {
+        client
+            .create_comment(&link.repo, link.issue_number, &mirror_body(message))
+            .await?;
+        // Recorded per message, so a failure midway never reposts
+        GithubSyncBmc::mark_posted(ctx, mm, link.id, MessageId::new(message.id)).await?;
+    }
+    Ok(messages.len())
+}
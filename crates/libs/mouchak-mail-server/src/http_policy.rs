@@ -0,0 +1,328 @@
+//! CORS, Content-Security-Policy and response compression built from the
+//! `http` config section.
+//!
+//! All are validated when the server starts: a malformed origin or CSP
+//! source is a [`ServerError::ConfigError`] rather than a policy that
+//! silently allows more, or less, than intended.
+//!
+//! NIST Control: SC-8 (Transmission Confidentiality), SI-10 (Input Validation)
+
+use crate::error::ServerError;
+use axum::http::{Extensions, HeaderMap, HeaderName, HeaderValue, StatusCode, Version};
+use mouchak_mail_common::config::HttpConfig;
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// Directives of the default policy, each extended by `csp_extra_sources`.
+const CSP_DIRECTIVES: &[(&str, &str)] = &[
+    ("script-src", "'self'"),
+    ("connect-src", "'self'"),
+    ("style-src", "'self' 'unsafe-inline'"),
+];
+
+/// Splits a comma-separated config list, dropping empty entries.
+fn entries(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|e| !e.is_empty())
+}
+
+/// Checks that `origin` is a bare `scheme://host[:port]` origin.
+fn validate_origin(key: &str, origin: &str) -> Result<(), ServerError> {
+    let rest = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+        .ok_or_else(|| {
+            ServerError::ConfigError(format!("{}: '{}' is not an http(s) origin", key, origin))
+        })?;
+    if rest.is_empty() || rest.contains(['/', '?', '#', '@']) || !is_token(rest) {
+        return Err(ServerError::ConfigError(format!(
+            "{}: '{}' must be scheme://host[:port] without a path",
+            key, origin
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `s` is non-empty printable ASCII without spaces, quotes or
+/// separators that would break out of a header value.
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_graphic() && !matches!(b, b';' | b',' | b'"'))
+}
+
+/// Checks one CSP source expression: a host or scheme source, or a quoted
+/// keyword, hash or nonce such as `'self'` or `'sha256-...'`.
+fn validate_csp_source(key: &str, source: &str) -> Result<(), ServerError> {
+    let valid = match source.strip_prefix('\'') {
+        Some(quoted) => quoted
+            .strip_suffix('\'')
+            .is_some_and(|inner| is_token(inner) && !inner.contains('\'')),
+        None => is_token(source) && !source.contains('\''),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(ServerError::ConfigError(format!(
+            "{}: '{}' is not a CSP source",
+            key, source
+        )))
+    }
+}
+
+/// CORS layer allowing the configured origins.
+///
+/// # Errors
+/// `ConfigError` if an origin is malformed, or `*` is mixed with others.
+pub fn cors_layer(config: &HttpConfig) -> Result<CorsLayer, ServerError> {
+    const KEY: &str = "http.cors_allowed_origins";
+    let origins: Vec<&str> = entries(&config.cors_allowed_origins).collect();
+    let allow_origin = if origins.contains(&"*") {
+        if origins.len() > 1 {
+            return Err(ServerError::ConfigError(format!(
+                "{}: '*' cannot be combined with other origins",
+                KEY
+            )));
+        }
+        AllowOrigin::any()
+    } else {
+        let mut values = Vec::with_capacity(origins.len());
+        for origin in origins {
+            validate_origin(KEY, origin)?;
+            values.push(HeaderValue::from_str(origin).map_err(|_| {
+                ServerError::ConfigError(format!("{}: invalid origin '{}'", KEY, origin))
+            })?);
+        }
+        AllowOrigin::list(values)
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .expose_headers([
+            HeaderName::from_static(crate::tools::TOTAL_COUNT_HEADER),
+            HeaderName::from_static(crate::tools::NEXT_CURSOR_HEADER),
+            HeaderName::from_static(crate::api::attachments::CONTENT_HASH_HEADER),
+            HeaderName::from_static(crate::ratelimit::LIMIT_HEADER),
+            HeaderName::from_static(crate::ratelimit::REMAINING_HEADER),
+            HeaderName::from_static(crate::ratelimit::RESET_HEADER),
+            axum::http::header::RETRY_AFTER,
+        ]))
+}
+
+/// `Content-Security-Policy` header value.
+///
+/// `csp_extra_sources` extend every default directive; `frame_ancestors`
+/// replaces `'none'` in `frame-ancestors`.
+///
+/// # Errors
+/// `ConfigError` if a source or frame ancestor is malformed.
+pub fn content_security_policy(config: &HttpConfig) -> Result<HeaderValue, ServerError> {
+    let extra: Vec<&str> = entries(&config.csp_extra_sources).collect();
+    for source in &extra {
+        validate_csp_source("http.csp_extra_sources", source)?;
+    }
+    let ancestors: Vec<&str> = entries(&config.frame_ancestors).collect();
+    for ancestor in &ancestors {
+        validate_csp_source("http.frame_ancestors", ancestor)?;
+    }
+
+    let mut directives: Vec<String> = CSP_DIRECTIVES
+        .iter()
+        .map(|(name, sources)| {
+            let mut parts = vec![*name, *sources];
+            parts.extend(&extra);
+            parts.join(" ")
+        })
+        .collect();
+    directives.push(if ancestors.is_empty() {
+        "frame-ancestors 'none'".to_string()
+    } else {
+        format!("frame-ancestors {}", ancestors.join(" "))
+    });
+
+    HeaderValue::from_str(&directives.join("; "))
+        .map_err(|e| ServerError::ConfigError(format!("Invalid Content-Security-Policy: {}", e)))
+}
+
+/// `X-Frame-Options` header value: `DENY` unless frame ancestors are
+/// configured, which the header cannot express, so browsers fall back to
+/// the CSP `frame-ancestors` directive.
+pub fn frame_options(config: &HttpConfig) -> Option<HeaderValue> {
+    entries(&config.frame_ancestors)
+        .next()
+        .is_none()
+        .then(|| HeaderValue::from_static("DENY"))
+}
+
+/// Response compression negotiated from `Accept-Encoding`, offering the
+/// encodings in `http.compression`.
+///
+/// Responses under `http.compression_min_bytes`, images, event streams and
+/// protocol upgrades are sent as is. With no encodings the layer passes
+/// everything through.
+///
+/// # Errors
+/// `ConfigError` if an encoding is not `br` or `gzip`.
+pub fn compression_layer(
+    config: &HttpConfig,
+) -> Result<CompressionLayer<impl Predicate + use<>>, ServerError> {
+    let (mut br, mut gzip) = (false, false);
+    for encoding in entries(&config.compression) {
+        match encoding.to_ascii_lowercase().as_str() {
+            "br" => br = true,
+            "gzip" => gzip = true,
+            _ => {
+                return Err(ServerError::ConfigError(format!(
+                    "http.compression: '{}' is not br or gzip",
+                    encoding
+                )));
+            }
+        }
+    }
+
+    let mut layer = CompressionLayer::new();
+    if !br {
+        layer = layer.no_br();
+    }
+    if !gzip {
+        layer = layer.no_gzip();
+    }
+    let not_upgrade = |status: StatusCode, _: Version, _: &HeaderMap, _: &Extensions| {
+        status != StatusCode::SWITCHING_PROTOCOLS
+    };
+    Ok(layer.compress_when(
+        SizeAbove::new(config.compression_min_bytes)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::SSE)
+            .and(not_upgrade),
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Request, header};
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn config(origins: &str, extra: &str, ancestors: &str) -> HttpConfig {
+        HttpConfig {
+            cors_allowed_origins: origins.to_string(),
+            csp_extra_sources: extra.to_string(),
+            frame_ancestors: ancestors.to_string(),
+            ..Default::default()
+        }
+    }
+
+    async fn content_encoding(config: &HttpConfig, path: &str, accept: &str) -> Option<String> {
+        let app = Router::new()
+            .route("/large", get(|| async { "x".repeat(4096) }))
+            .route("/small", get(|| async { "ok" }))
+            .layer(compression_layer(config).unwrap());
+        let request = Request::get(path)
+            .header(header::ACCEPT_ENCODING, accept)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        response
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let config = HttpConfig::default();
+        assert!(cors_layer(&config).is_ok());
+        assert_eq!(
+            content_security_policy(&config).unwrap(),
+            "script-src 'self'; connect-src 'self'; style-src 'self' 'unsafe-inline'; frame-ancestors 'none'"
+        );
+        assert_eq!(frame_options(&config).unwrap(), "DENY");
+    }
+
+    #[test]
+    fn test_configured_policy() {
+        let config = config(
+            "https://app.example.com, http://localhost:5173",
+            "https://cdn.example.com, 'sha256-abc='",
+            "https://portal.example.com",
+        );
+        assert!(cors_layer(&config).is_ok());
+        assert_eq!(
+            content_security_policy(&config).unwrap(),
+            "script-src 'self' https://cdn.example.com 'sha256-abc='; \
+             connect-src 'self' https://cdn.example.com 'sha256-abc='; \
+             style-src 'self' 'unsafe-inline' https://cdn.example.com 'sha256-abc='; \
+             frame-ancestors https://portal.example.com"
+        );
+        assert!(frame_options(&config).is_none());
+    }
+
+    #[test]
+    fn test_invalid_policy_rejected() {
+        for origins in [
+            "*, https://app.example.com",
+            "app.example.com",
+            "https://app.example.com/ui",
+            "https://",
+            "https://app.example.com;evil",
+        ] {
+            assert!(cors_layer(&config(origins, "", "")).is_err(), "{}", origins);
+        }
+        for extra in [
+            "https://cdn.example.com; script-src *",
+            "'self",
+            "cdn.example.com'",
+        ] {
+            assert!(
+                content_security_policy(&config("*", extra, "")).is_err(),
+                "{}",
+                extra
+            );
+        }
+        assert!(content_security_policy(&config("*", "", "'none")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compression_negotiated() {
+        let config = HttpConfig::default();
+        assert_eq!(
+            content_encoding(&config, "/large", "br").await.as_deref(),
+            Some("br")
+        );
+        assert_eq!(
+            content_encoding(&config, "/large", "gzip").await.as_deref(),
+            Some("gzip")
+        );
+        assert_eq!(content_encoding(&config, "/large", "identity").await, None);
+        assert_eq!(content_encoding(&config, "/small", "gzip").await, None);
+
+        let gzip_only = HttpConfig {
+            compression: "gzip".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(content_encoding(&gzip_only, "/large", "br").await, None);
+
+        let disabled = HttpConfig {
+            compression: String::new(),
+            ..Default::default()
+        };
+        assert_eq!(
+            content_encoding(&disabled, "/large", "gzip, br").await,
+            None
+        );
+
+        let invalid = HttpConfig {
+            compression: "gzip,zstd".to_string(),
+            ..Default::default()
+        };
+        assert!(compression_layer(&invalid).is_err());
+    }
+}
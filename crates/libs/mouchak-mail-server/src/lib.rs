@@ -5,19 +5,32 @@ use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
 use std::net::SocketAddr;
 use std::sync::OnceLock;
 use std::time::Instant;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
 
 // Modules
 pub mod api;
 pub mod auth;
+pub mod client_ip;
+pub mod conditional;
+pub mod dashboards;
+pub mod email_bridge;
 pub mod error;
+pub mod github;
+pub mod http_policy;
+pub mod listener;
 pub mod mcp;
+pub mod oidc;
 pub mod openapi;
+#[cfg(feature = "pprof")]
+pub mod profiling;
+pub mod push;
 pub mod ratelimit;
+pub mod session;
+pub mod smtp;
+pub mod telemetry;
 pub mod tools;
+pub mod webhooks;
 
 #[cfg(feature = "with-web-ui")]
 pub mod embedded;
@@ -39,8 +52,23 @@ pub struct AppState {
     pub auth_config: AuthConfig,
     pub jwks_client: Option<JwksClient>,
     pub ratelimit_config: ratelimit::RateLimitConfig,
+    /// OIDC login for browser users, if configured
+    pub oidc: Option<oidc::OidcClient>,
+    pub sessions: session::SessionStore,
 }
 
+/// How often expired-but-watched reservations are swept for release receipts.
+const RESERVATION_RECEIPT_SCAN_SECONDS: u64 = 30;
+
+/// How often elapsed focus windows are swept for deferred deliveries.
+const FOCUS_WINDOW_DELIVERY_SCAN_SECONDS: u64 = 30;
+
+/// How often missed SLAs are swept into the overseer inbox.
+const SLA_VIOLATION_SCAN_SECONDS: u64 = 60;
+
+/// How often held messages are swept for approval timeouts.
+const APPROVAL_TIMEOUT_SCAN_SECONDS: u64 = 60;
+
 static METRICS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
 
 #[allow(clippy::expect_used)] // Metrics setup is infallible; panic acceptable during initialization
@@ -50,15 +78,32 @@ pub fn setup_metrics() -> PrometheusHandle {
             const EXPONENTIAL_SECONDS: &[f64] = &[
                 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
             ];
+            // Most queries finish well under the HTTP buckets
+            const DB_SECONDS: &[f64] = &[
+                0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0,
+            ];
 
-            PrometheusBuilder::new()
+            let handle = PrometheusBuilder::new()
                 .set_buckets_for_metric(
-                    Matcher::Full("http_request_duration_seconds".to_string()),
+                    Matcher::Full(telemetry::HTTP_REQUEST_DURATION.to_string()),
                     EXPONENTIAL_SECONDS,
                 )
                 .expect("Failed to set buckets")
+                .set_buckets_for_metric(
+                    Matcher::Full(telemetry::ESCALATION_SWEEP_DURATION.to_string()),
+                    EXPONENTIAL_SECONDS,
+                )
+                .expect("Failed to set buckets")
+                .set_buckets_for_metric(
+                    Matcher::Full(telemetry::DB_QUERY_DURATION.to_string()),
+                    DB_SECONDS,
+                )
+                .expect("Failed to set buckets")
                 .install_recorder()
-                .expect("Failed to install Prometheus recorder")
+                .expect("Failed to install Prometheus recorder");
+            telemetry::describe_metrics();
+            mouchak_mail_core::store::db_metrics::set_observer(telemetry::record_db_use);
+            handle
         })
         .clone()
 }
@@ -68,16 +113,41 @@ pub async fn run(
 ) -> std::result::Result<(), ServerError> {
     // Initialize tracing is handled by caller (main binary) now
 
+    // Validate HTTP policies before starting anything
+    let cors = http_policy::cors_layer(&config.http)?;
+    let content_security_policy = http_policy::content_security_policy(&config.http)?;
+    let compression = http_policy::compression_layer(&config.http)?;
+    listener::validate(&config.http)?;
+    email_bridge::validate(&config.email_bridge)?;
+
     // Initialize metrics
     let metrics_handle = setup_metrics();
 
     // Initialize ModelManager
     let mm = ModelManager::new(std::sync::Arc::new(config.clone())).await?;
 
+    // Background jobs report their liveness for the scheduler lag gauges
+    let scheduler = telemetry::Scheduler::default();
+
+    // Start Intent Recovery (settles sends and uploads a crash interrupted before this start)
+    {
+        use mouchak_mail_core::model::intent_journal::IntentJournalBmc;
+
+        let mm_clone = mm.clone();
+        let started = chrono::Utc::now().naive_utc();
+        tokio::spawn(async move {
+            let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+            if let Err(e) = IntentJournalBmc::recover(&ctx, &mm_clone, started).await {
+                tracing::error!("Intent Recovery Error: {}", e);
+            }
+        });
+    }
+
     // Start Escalation Background Service
     if config.escalation.escalation_enabled {
         let mm_clone = mm.clone();
         let config_clone = config.escalation.clone();
+        let job = scheduler.register("escalation", config_clone.scan_interval_seconds);
         tokio::spawn(async move {
             tracing::info!("Starting Escalation Background Service");
             loop {
@@ -89,15 +159,20 @@ pub async fn run(
                 // Use root context for background tasks
                 let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
 
-                match mouchak_mail_core::model::escalation::EscalationBmc::escalate_overdue(
+                let started = Instant::now();
+                let result = mouchak_mail_core::model::escalation::EscalationBmc::escalate_overdue(
                     &ctx,
                     &mm_clone,
                     config_clone.ack_ttl_seconds as i64,
                     config_clone.escalation_mode,
                     false, // Not a dry run
                 )
-                .await
-                {
+                .await;
+                metrics::histogram!(telemetry::ESCALATION_SWEEP_DURATION)
+                    .record(started.elapsed().as_secs_f64());
+                job.finished(result.is_ok());
+
+                match result {
                     Ok(results) => {
                         if !results.is_empty() {
                             tracing::info!(
@@ -114,8 +189,542 @@ pub async fn run(
         });
     }
 
+    // Start Reservation Release Receipt Service (covers TTL expiry; explicit releases notify inline)
+    {
+        use mouchak_mail_core::model::reservation_watcher::ReservationWatcherBmc;
+
+        let mm_clone = mm.clone();
+        let job = scheduler.register("reservation_receipts", RESERVATION_RECEIPT_SCAN_SECONDS);
+        tokio::spawn(async move {
+            tracing::info!("Starting Reservation Receipt Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    RESERVATION_RECEIPT_SCAN_SECONDS,
+                ))
+                .await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+
+                let result = ReservationWatcherBmc::notify_expired(&ctx, &mm_clone).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(sent) => {
+                        if sent > 0 {
+                            tracing::info!(
+                                "Reservation Receipt Service: Notified watchers of {} expired reservations",
+                                sent
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Reservation Receipt Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Focus Window Delivery Service (releases mail held by elapsed focus windows)
+    {
+        use mouchak_mail_core::model::focus_window::FocusWindowBmc;
+
+        let mm_clone = mm.clone();
+        let job = scheduler.register("focus_window_delivery", FOCUS_WINDOW_DELIVERY_SCAN_SECONDS);
+        tokio::spawn(async move {
+            tracing::info!("Starting Focus Window Delivery Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    FOCUS_WINDOW_DELIVERY_SCAN_SECONDS,
+                ))
+                .await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+
+                let result = FocusWindowBmc::deliver_due(&ctx, &mm_clone).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(delivered) => {
+                        if delivered > 0 {
+                            tracing::info!(
+                                "Focus Window Service: Delivered {} deferred messages",
+                                delivered
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Focus Window Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start SLA Violation Service (reports missed ack deadlines to the overseer)
+    {
+        use mouchak_mail_core::model::sla::SlaBmc;
+
+        let mm_clone = mm.clone();
+        let job = scheduler.register("sla_violations", SLA_VIOLATION_SCAN_SECONDS);
+        tokio::spawn(async move {
+            tracing::info!("Starting SLA Violation Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(SLA_VIOLATION_SCAN_SECONDS))
+                    .await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+
+                let result = SlaBmc::report_violations(&ctx, &mm_clone).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(reported) => {
+                        if reported > 0 {
+                            tracing::info!(
+                                "SLA Violation Service: Reported {} missed acknowledgments",
+                                reported
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("SLA Violation Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Approval Timeout Service (auto-rejects held messages nobody decided on)
+    {
+        use mouchak_mail_core::model::approval::ApprovalBmc;
+
+        let mm_clone = mm.clone();
+        let job = scheduler.register("approval_timeouts", APPROVAL_TIMEOUT_SCAN_SECONDS);
+        tokio::spawn(async move {
+            tracing::info!("Starting Approval Timeout Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    APPROVAL_TIMEOUT_SCAN_SECONDS,
+                ))
+                .await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+
+                let result = ApprovalBmc::expire_due(&ctx, &mm_clone).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(expired) => {
+                        if expired > 0 {
+                            tracing::info!(
+                                "Approval Timeout Service: Rejected {} unapproved messages",
+                                expired
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Approval Timeout Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Web Push Delivery Service (notifies subscribed browsers of urgent mail)
+    if config.push.enabled {
+        let mm_clone = mm.clone();
+        let push_config = config.push.clone();
+        let job = scheduler.register("web_push", push_config.scan_interval_seconds);
+        tokio::spawn(async move {
+            tracing::info!("Starting Web Push Delivery Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    push_config.scan_interval_seconds,
+                ))
+                .await;
+
+                let result = push::deliver_pending(&mm_clone, &push_config).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(sent) => {
+                        if sent > 0 {
+                            tracing::info!("Web Push Service: Sent {} notifications", sent);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Web Push Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start GitHub Sync Service (mirrored threads <-> issue comments)
+    if config.github.token().is_some() {
+        let mm_clone = mm.clone();
+        let github_config = config.github.clone();
+        let job = scheduler.register("github_sync", github_config.sync_interval_seconds);
+        tokio::spawn(async move {
+            tracing::info!("Starting GitHub Sync Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    github_config.sync_interval_seconds,
+                ))
+                .await;
+
+                let result = github::sync_all(&mm_clone, &github_config).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(synced) => {
+                        if synced > 0 {
+                            tracing::info!("GitHub Sync Service: Synced {} items", synced);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("GitHub Sync Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Webhook Delivery Service (signed event callbacks with retries)
+    if config.webhooks.enabled {
+        let mm_clone = mm.clone();
+        let webhooks_config = config.webhooks.clone();
+        let job = scheduler.register("webhooks", webhooks_config.scan_interval_seconds);
+        tokio::spawn(async move {
+            tracing::info!("Starting Webhook Delivery Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    webhooks_config.scan_interval_seconds,
+                ))
+                .await;
+
+                let result = webhooks::deliver_pending(&mm_clone, &webhooks_config).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(sent) => {
+                        if sent > 0 {
+                            tracing::info!("Webhook Service: Delivered {} events", sent);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Webhook Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Email Bridge Service (mails forwarded messages to human mailboxes over SMTP)
+    if config.email_bridge.smtp_host().is_some() {
+        let mm_clone = mm.clone();
+        let bridge_config = config.email_bridge.clone();
+        let job = scheduler.register("email_bridge", bridge_config.scan_interval_seconds);
+        tokio::spawn(async move {
+            tracing::info!("Starting Email Bridge Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    bridge_config.scan_interval_seconds,
+                ))
+                .await;
+
+                let result = email_bridge::deliver_pending(&mm_clone, &bridge_config).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(sent) => {
+                        if sent > 0 {
+                            tracing::info!("Email Bridge Service: Mailed {} messages", sent);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Email Bridge Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Export Worker (renders queued bulk exports, removes expired ones)
+    if config.exports.poll_interval_seconds > 0 {
+        use mouchak_mail_core::model::export_job::ExportJobBmc;
+
+        let mm_clone = mm.clone();
+        let interval = config.exports.poll_interval_seconds;
+        let job = scheduler.register("export_jobs", interval);
+        tokio::spawn(async move {
+            tracing::info!("Starting Export Worker Background Service");
+            let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+            match ExportJobBmc::requeue_interrupted(&ctx, &mm_clone).await {
+                Ok(requeued) if requeued > 0 => {
+                    tracing::info!("Export Worker: Requeued {} interrupted jobs", requeued);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!("Export Worker Error: {}", e);
+                }
+            }
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+
+                let result = ExportJobBmc::run_pending(&ctx, &mm_clone).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(ran) => {
+                        if ran > 0 {
+                            tracing::info!("Export Worker: Ran {} export jobs", ran);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Export Worker Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Ticket Refresh Service (re-fetches stale Jira/Linear ticket metadata)
+    if config.tickets.any_provider() {
+        let mm_clone = mm.clone();
+        let interval = config.tickets.refresh_interval_seconds;
+        let job = scheduler.register("ticket_refresh", interval);
+        tokio::spawn(async move {
+            tracing::info!("Starting Ticket Refresh Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+
+                let result =
+                    mouchak_mail_mcp::tools::tickets::refresh_stale_tickets(&mm_clone).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(refreshed) => {
+                        if refreshed > 0 {
+                            tracing::info!(
+                                "Ticket Refresh Service: Refreshed {} tickets",
+                                refreshed
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Ticket Refresh Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Archive GC Service (packs loose objects in the Git archive)
+    if config.archive_gc.interval_seconds > 0 {
+        use mouchak_mail_core::model::archive_gc::{ArchiveGcBmc, GcOptions};
+
+        let mm_clone = mm.clone();
+        let interval = config.archive_gc.interval_seconds;
+        let options = GcOptions {
+            min_loose_objects: config.archive_gc.min_loose_objects,
+            aggressive: config.archive_gc.aggressive,
+            dry_run: false,
+        };
+        let job = scheduler.register("archive_gc", interval);
+        tokio::spawn(async move {
+            tracing::info!("Starting Archive GC Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+                let result = ArchiveGcBmc::gc(&ctx, &mm_clone, &options).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(report) => {
+                        if report.compacted {
+                            tracing::info!(
+                                "Archive GC Service: Packed {} loose objects, reclaimed {} bytes",
+                                report.before.loose_objects,
+                                report.reclaimed_bytes
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Archive GC Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start DB Maintenance Service (integrity check, ANALYZE, VACUUM when bloated)
+    if config.db_maintenance.interval_seconds > 0 {
+        use mouchak_mail_core::model::db_maintenance::{DbMaintenanceBmc, MaintainOptions};
+
+        let mm_clone = mm.clone();
+        let interval = config.db_maintenance.interval_seconds;
+        let options = MaintainOptions {
+            vacuum_free_ratio: config.db_maintenance.vacuum_free_ratio,
+            ..Default::default()
+        };
+        let job = scheduler.register("db_maintenance", interval);
+        tokio::spawn(async move {
+            tracing::info!("Starting DB Maintenance Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+                let result = DbMaintenanceBmc::maintain(&ctx, &mm_clone, &options).await;
+                job.finished(result.as_ref().is_ok_and(|report| report.integrity_ok()));
+
+                match result {
+                    Ok(report) => {
+                        if report.vacuumed {
+                            tracing::info!(
+                                "DB Maintenance Service: Vacuumed, reclaimed {} bytes",
+                                report.reclaimed_bytes
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("DB Maintenance Service Error: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start Thread Archival Service (archives threads inactive for N days)
+    if config.thread_archival.inactive_days > 0 && config.thread_archival.interval_seconds > 0 {
+        use mouchak_mail_core::model::thread_archive::ThreadArchiveBmc;
+
+        let mm_clone = mm.clone();
+        let interval = config.thread_archival.interval_seconds;
+        let inactive_days = config.thread_archival.inactive_days;
+        let closing_summary = config.thread_archival.closing_summary;
+        let job = scheduler.register("thread_archival", interval);
+        tokio::spawn(async move {
+            tracing::info!("Starting Thread Archival Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+                let result = ThreadArchiveBmc::archive_inactive(
+                    &ctx,
+                    &mm_clone,
+                    inactive_days,
+                    closing_summary,
+                )
+                .await;
+                job.finished(result.is_ok());
+
+                if let Err(e) = result {
+                    tracing::error!("Thread Archival Service Error: {}", e);
+                }
+            }
+        });
+    }
+
+    // Start Retention Service (purges messages past their project's retention limits)
+    if config.retention.interval_seconds > 0 {
+        use mouchak_mail_core::model::retention::RetentionBmc;
+
+        let mm_clone = mm.clone();
+        let interval = config.retention.interval_seconds;
+        let job = scheduler.register("retention", interval);
+        tokio::spawn(async move {
+            tracing::info!("Starting Retention Background Service");
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+                let result = RetentionBmc::run(&ctx, &mm_clone, false).await;
+                job.finished(result.is_ok());
+
+                if let Err(e) = result {
+                    tracing::error!("Retention Service Error: {}", e);
+                }
+            }
+        });
+    }
+
+    // Start Reservation Expiry Announcer (file_reservation.expired events for
+    // /api/ws and reservation.expired webhooks)
+    if (config.websocket.enabled || config.webhooks.enabled)
+        && config.websocket.expiry_interval_seconds > 0
+    {
+        use mouchak_mail_core::model::file_reservation::FileReservationBmc;
+
+        let mm_clone = mm.clone();
+        let interval = config.websocket.expiry_interval_seconds;
+        let job = scheduler.register("reservation_expiry_events", interval);
+        tokio::spawn(async move {
+            tracing::info!("Starting Reservation Expiry Announcer");
+            let mut since = chrono::Utc::now().naive_utc();
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+                let until = chrono::Utc::now().naive_utc();
+                let result =
+                    FileReservationBmc::announce_expired(&ctx, &mm_clone, since, until).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(_) => since = until,
+                    Err(e) => tracing::error!("Reservation Expiry Announcer Error: {}", e),
+                }
+            }
+        });
+    }
+
+    // Start Disk Watch Service (alerts on low space, read-only before the disk fills)
+    if config.disk_watch.interval_seconds > 0 {
+        use mouchak_mail_core::model::disk_watch::{DiskThresholds, DiskWatcher};
+
+        let mm_clone = mm.clone();
+        let interval = config.disk_watch.interval_seconds;
+        let mut watcher = DiskWatcher::new(
+            mouchak_mail_common::paths::data_dir(),
+            DiskThresholds::from_config(&config.disk_watch),
+        );
+        let job = scheduler.register("disk_watch", interval);
+        tokio::spawn(async move {
+            tracing::info!("Starting Disk Watch Background Service");
+            loop {
+                let ctx = mouchak_mail_core::ctx::Ctx::root_ctx();
+                let result = watcher.check(&ctx, &mm_clone).await;
+                job.finished(result.is_ok());
+
+                match result {
+                    Ok(status) => {
+                        metrics::gauge!(telemetry::DISK_FREE_BYTES)
+                            .set(status.space.free_bytes as f64);
+                        metrics::gauge!(telemetry::DISK_TOTAL_BYTES)
+                            .set(status.space.total_bytes as f64);
+                        metrics::gauge!(telemetry::READ_ONLY).set(if status.read_only {
+                            1.0
+                        } else {
+                            0.0
+                        });
+                    }
+                    Err(e) => tracing::error!("Disk Watch Service Error: {}", e),
+                }
+
+                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+            }
+        });
+    }
+
     // Create MCP routes with shared ModelManager (clone before move)
-    let mcp_routes = mcp::mcp_routes(mm.clone());
+    let mcp_sessions = std::sync::Arc::new(mcp::LocalSessionManager::default());
+    let mcp_routes = mcp::mcp_routes(mm.clone(), mcp_sessions.clone());
+
+    // Sample queue depths, job lag and MCP sessions for /metrics
+    telemetry::spawn_sampler(mm.clone(), scheduler, mcp_sessions, config.push.enabled);
 
     // Initialize Auth
     let auth_config = AuthConfig::from_env();
@@ -126,6 +735,18 @@ pub async fn run(
         .as_ref()
         .map(|url| JwksClient::new(url.clone()));
 
+    let oidc = oidc::OidcConfig::from_env().map(|config| {
+        tracing::info!("OIDC login enabled (issuer: {})", config.issuer_url);
+        oidc::OidcClient::new(config)
+    });
+
+    let sessions = oidc
+        .as_ref()
+        .map_or_else(session::SessionStore::default, |client| {
+            session::SessionStore::new(client.config().session_idle_timeout)
+        });
+
+    let tool_call_log = mm.tool_call_log().cloned();
     let app_state = AppState {
         mm,
         metrics_handle,
@@ -133,16 +754,21 @@ pub async fn run(
         auth_config,
         jwks_client,
         ratelimit_config: ratelimit::RateLimitConfig::new(),
+        oidc,
+        sessions,
     };
 
     // Build our application with routes
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+
+    let api_routes = api::routes();
+    #[cfg(feature = "pprof")]
+    let api_routes = {
+        tracing::info!("Self-profiling enabled at /debug/pprof");
+        api_routes.merge(profiling::routes())
+    };
 
     let mut app = Router::new()
-        .merge(api::routes())
+        .merge(api_routes)
         .merge(mcp_routes)
         .route_layer(axum::middleware::from_fn_with_state(
             app_state.clone(),
@@ -151,6 +777,12 @@ pub async fn run(
         // Public routes (no auth)
         // .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", openapi::ApiDoc::openapi()))
         .route("/api-docs/openapi.json", get(openapi_json))
+        // Browser login (OIDC)
+        .route("/auth/login", get(oidc::login))
+        .route("/auth/callback", get(oidc::callback))
+        .route("/auth/logout", axum::routing::post(oidc::logout))
+        // Version handshake, checked by the web UI before login
+        .route("/api/version", get(api::version::version))
         .route("/health", get(health_handler))
         .route("/ready", get(ready_handler))
         .route("/metrics", get(metrics_handler))
@@ -165,17 +797,19 @@ pub async fn run(
             app_state.clone(),
             ratelimit::rate_limit_middleware,
         ))
+        // Outside rate limiting so rejected requests are counted too
+        .route_layer(axum::middleware::from_fn(telemetry::track_http_metrics))
         .layer(cors) // Enable CORS
+        // Negotiated br/gzip for JSON payloads and exports
+        .layer(compression)
         // 5. Security Headers (Hardening CSP/XSS Protection)
         .layer(SetResponseHeaderLayer::overriding(
             HeaderName::from_static("content-security-policy"),
-            HeaderValue::from_static(
-                "script-src 'self'; connect-src 'self'; style-src 'self' 'unsafe-inline'",
-            ),
+            content_security_policy,
         ))
         .layer(SetResponseHeaderLayer::overriding(
             HeaderName::from_static("x-frame-options"),
-            HeaderValue::from_static("DENY"),
+            http_policy::frame_options(&config.http),
         ))
         .layer(SetResponseHeaderLayer::overriding(
             HeaderName::from_static("x-content-type-options"),
@@ -197,13 +831,17 @@ pub async fn run(
             HeaderValue::from_static("camera=(), microphone=(), geolocation=()"),
         ))
         // 9. Request Body Size Limit (NIST SC-5 DoS Protection)
-        .layer(RequestBodyLimitLayer::new(get_request_body_limit()));
+        .layer(axum::middleware::from_fn_with_state(
+            get_request_body_limit(),
+            limit_request_body,
+        ));
 
     // Conditionally add embedded web UI routes
     #[cfg(feature = "with-web-ui")]
     if config.server.serve_ui {
         tracing::info!("Web UI enabled at /");
-        app = app.fallback(static_files::serve_embedded_file);
+        embedded::warn_on_version_mismatch();
+        app = app.fallback(oidc::login_required_ui);
     } else {
         app = app.route("/", get(root_handler));
     }
@@ -219,17 +857,18 @@ pub async fn run(
     tracing::info!("Mouchak Mail Server starting on {}", addr);
     tracing::info!("Health check: http://{}/health", addr);
 
-    // Axum 0.8+ serve with ConnectInfo for localhost bypass
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let tcp_listener = tokio::net::TcpListener::bind(addr).await?;
 
-    // Graceful shutdown - use into_make_service_with_connect_info to enable
-    // ConnectInfo<SocketAddr> extraction in middleware for localhost bypass
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(shutdown_signal())
-    .await?;
+    // HTTP/1.1 and h2c on one port; requests carry ConnectInfo<SocketAddr>
+    // for the localhost bypass. Graceful shutdown on SIGINT/SIGTERM
+    listener::serve(tcp_listener, app, &config.http, shutdown_signal()).await?;
+
+    // Commit tool calls still waiting for their batch
+    if let Some(log) = tool_call_log
+        && let Err(e) = log.flush().await
+    {
+        tracing::warn!("Failed to archive pending tool calls: {}", e);
+    }
 
     Ok(())
 }
@@ -268,7 +907,7 @@ async fn shutdown_signal() {
 /// Get the request body size limit from environment variable or use default
 /// Default: 1MB (1048576 bytes)
 /// Set MAX_REQUEST_SIZE_MB environment variable to override
-fn get_request_body_limit() -> usize {
+pub(crate) fn get_request_body_limit() -> usize {
     const DEFAULT_LIMIT_MB: usize = 1;
     const BYTES_PER_MB: usize = 1024 * 1024;
 
@@ -279,6 +918,37 @@ fn get_request_body_limit() -> usize {
         * BYTES_PER_MB
 }
 
+/// Rejects request bodies over `limit` bytes with 413.
+///
+/// Streamed attachment uploads are exempt: they may be much larger and are
+/// capped at `ATTACHMENTS_MAX_BYTES` while being stored.
+async fn limit_request_body(
+    State(limit): State<usize>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    if request.method() == axum::http::Method::POST
+        && request.uri().path() == api::attachments::UPLOAD_PATH
+    {
+        return next.run(request).await;
+    }
+
+    let declared_too_large = request
+        .headers()
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok())
+        .is_some_and(|len| len > limit);
+    if declared_too_large {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+    // Bodies without a Content-Length fail once they pass the limit
+    let (parts, body) = request.into_parts();
+    let body = axum::body::Body::new(http_body_util::Limited::new(body, limit));
+    next.run(axum::extract::Request::from_parts(parts, body))
+        .await
+}
+
 async fn root_handler() -> &'static str {
     "Mouchak Mail Server is running!"
 }
@@ -310,6 +980,8 @@ pub async fn health_handler(State(state): State<AppState>) -> impl IntoResponse
 struct ReadyResponse {
     status: &'static str,
     database: &'static str,
+    /// Writes are suspended because the disk is nearly full
+    read_only: bool,
 }
 
 #[utoipa::path(
@@ -330,6 +1002,7 @@ pub async fn ready_handler(State(state): State<AppState>) -> impl IntoResponse {
     let response = ReadyResponse {
         status: if is_ready { "ready" } else { "not_ready" },
         database: db_status,
+        read_only: state.mm.is_read_only(),
     };
 
     let status_code = if is_ready {
@@ -0,0 +1,203 @@
+//! Accept loop serving HTTP/1.1 and HTTP/2 with the connection settings of
+//! the `http` config section.
+//!
+//! `axum::serve` does not expose hyper's connection builder, so connections
+//! are served here by hyper-util's auto builder instead. It detects HTTP/2
+//! (h2c with prior knowledge) from the connection preface and serves both
+//! protocols on one port. Orchestrators that proxy hundreds of small tool
+//! calls a minute can then multiplex them over a single connection instead
+//! of opening one per call. With `http.http2` off, hyper's HTTP/1.1
+//! builder serves them instead, and h2c is refused.
+//!
+//! Every request carries `ConnectInfo<SocketAddr>` as with
+//! `into_make_service_with_connect_info`, which the localhost bypass and
+//! client address resolution rely on.
+
+use crate::error::ServerError;
+use axum::Router;
+use axum::extract::ConnectInfo;
+use axum::http::Request;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use mouchak_mail_common::config::HttpConfig;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tower::ServiceExt;
+
+/// Pause after a failed accept, e.g. when out of file descriptors.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Checks the connection settings, so a stream limit or ping timeout of 0
+/// fails at startup instead of stalling every HTTP/2 client.
+pub fn validate(config: &HttpConfig) -> Result<(), ServerError> {
+    if !config.http2 {
+        return Ok(());
+    }
+    if config.http2_max_concurrent_streams == 0 {
+        return Err(ServerError::ConfigError(
+            "http.http2_max_concurrent_streams must be at least 1".to_string(),
+        ));
+    }
+    if http2_ping_interval(config).is_some() && config.http2_keep_alive_timeout_seconds == 0 {
+        return Err(ServerError::ConfigError(
+            "http.http2_keep_alive_timeout_seconds must be at least 1 while pings are enabled"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// How often HTTP/2 connections are pinged: `http2_keep_alive_interval_seconds`,
+/// or the idle timeout when that is 0, so an idle timeout also closes HTTP/2
+/// connections whose peer stopped answering.
+fn http2_ping_interval(config: &HttpConfig) -> Option<Duration> {
+    [
+        config.http2_keep_alive_interval_seconds,
+        config.idle_timeout_seconds,
+    ]
+    .into_iter()
+    .find(|&seconds| seconds > 0)
+    .map(Duration::from_secs)
+}
+
+/// Serves the protocols a connection may speak.
+enum ConnectionBuilder {
+    /// HTTP/1.1 and HTTP/2, told apart by the connection preface
+    Auto(auto::Builder<TokioExecutor>),
+    /// HTTP/1.1 only; hyper-util's auto builder still accepts h2c once
+    /// upgrades are enabled, so hyper's own builder serves these
+    Http1(http1::Builder),
+}
+
+/// Builds the connection builder shared by all connections.
+fn connection_builder(config: &HttpConfig) -> ConnectionBuilder {
+    // An HTTP/1.1 connection is idle while it waits for the next request head
+    let header_read_timeout =
+        (config.idle_timeout_seconds > 0).then(|| Duration::from_secs(config.idle_timeout_seconds));
+    if !config.http2 {
+        let mut builder = http1::Builder::new();
+        builder.keep_alive(config.keep_alive);
+        if let Some(timeout) = header_read_timeout {
+            builder
+                .timer(TokioTimer::new())
+                .header_read_timeout(timeout);
+        }
+        return ConnectionBuilder::Http1(builder);
+    }
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder.http1().keep_alive(config.keep_alive);
+    if let Some(timeout) = header_read_timeout {
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .header_read_timeout(timeout);
+    }
+    builder
+        .http2()
+        .max_concurrent_streams(config.http2_max_concurrent_streams);
+    if let Some(interval) = http2_ping_interval(config) {
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .keep_alive_interval(interval)
+            .keep_alive_timeout(Duration::from_secs(config.http2_keep_alive_timeout_seconds));
+    }
+    ConnectionBuilder::Auto(builder)
+}
+
+/// Serves `app` on `listener` until `signal` completes, then stops
+/// accepting, lets open connections finish their requests and returns.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    config: &HttpConfig,
+    signal: impl Future<Output = ()>,
+) -> std::io::Result<()> {
+    let builder = Arc::new(connection_builder(config));
+    // Connections hold a receiver: dropped when they end, notified on shutdown
+    let (shutdown_tx, shutdown_rx) = watch::channel(());
+    let mut signal = std::pin::pin!(signal);
+
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("Failed to accept connection: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            },
+            () = &mut signal => break,
+        };
+        // Tool calls are small; don't hold them back to fill a segment
+        if let Err(e) = stream.set_nodelay(true) {
+            tracing::debug!("Failed to set TCP_NODELAY for {}: {}", remote_addr, e);
+        }
+
+        let app = app.clone();
+        let service = tower::service_fn(move |mut request: Request<Incoming>| {
+            request.extensions_mut().insert(ConnectInfo(remote_addr));
+            app.clone().oneshot(request)
+        });
+        let builder = builder.clone();
+        let shutdown = shutdown_rx.clone();
+        tokio::spawn(async move {
+            let io = TokioIo::new(stream);
+            let service = TowerToHyperService::new(service);
+            match &*builder {
+                ConnectionBuilder::Auto(builder) => {
+                    let connection = builder.serve_connection_with_upgrades(io, service);
+                    drive(connection, |c| c.graceful_shutdown(), shutdown, remote_addr).await;
+                }
+                ConnectionBuilder::Http1(builder) => {
+                    let connection = builder.serve_connection(io, service).with_upgrades();
+                    drive(connection, |c| c.graceful_shutdown(), shutdown, remote_addr).await;
+                }
+            }
+        });
+    }
+
+    drop(listener);
+    drop(shutdown_rx);
+    shutdown_tx.send_replace(());
+    shutdown_tx.closed().await;
+    Ok(())
+}
+
+/// Serves `connection` until it closes, shutting it down gracefully once
+/// `shutdown` is notified.
+async fn drive<C, E>(
+    connection: C,
+    graceful_shutdown: fn(Pin<&mut C>),
+    mut shutdown: watch::Receiver<()>,
+    remote_addr: SocketAddr,
+) where
+    C: Future<Output = Result<(), E>>,
+    E: std::fmt::Display,
+{
+    let mut connection = std::pin::pin!(connection);
+    let mut draining = false;
+    loop {
+        tokio::select! {
+            result = connection.as_mut() => {
+                if let Err(e) = result {
+                    tracing::debug!("Connection from {} closed: {}", remote_addr, e);
+                }
+                break;
+            }
+            _ = shutdown.changed(), if !draining => {
+                draining = true;
+                graceful_shutdown(connection.as_mut());
+            }
+        }
+    }
+}
@@ -11,9 +11,9 @@ use axum::{
 };
 use mouchak_mail_core::ModelManager;
 use mouchak_mail_mcp::tools::MouchakMailService;
-use rmcp::transport::streamable_http_server::{
-    session::local::LocalSessionManager,
-    tower::{StreamableHttpServerConfig, StreamableHttpService},
+pub use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::streamable_http_server::tower::{
+    StreamableHttpServerConfig, StreamableHttpService,
 };
 use std::sync::Arc;
 use tower::ServiceExt;
@@ -27,10 +27,11 @@ use crate::AppState;
 /// compatibility with clients like NTM that send tools/call without initialize.
 ///
 /// Set MOUCHAK_MCP_STATEFUL=true for SSE streaming (requires initialize handshake).
-fn create_mcp_service(mm: ModelManager) -> StreamableHttpService<MouchakMailService> {
-    // Create session manager for stateful connections
-    let session_manager = Arc::new(LocalSessionManager::default());
-
+/// Stateful sessions are tracked in `session_manager`.
+fn create_mcp_service(
+    mm: ModelManager,
+    session_manager: Arc<LocalSessionManager>,
+) -> StreamableHttpService<MouchakMailService> {
     let stateful_mode = std::env::var("MOUCHAK_MCP_STATEFUL")
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false);
@@ -68,8 +69,9 @@ fn create_mcp_service(mm: ModelManager) -> StreamableHttpService<MouchakMailServ
 ///
 /// This returns an Axum Router that handles both GET (SSE stream) and POST (tool calls)
 /// on the /mcp endpoint. Uses the ModelManager from AppState to share database connection.
-pub fn mcp_routes(mm: ModelManager) -> Router<AppState> {
-    let mcp_service = create_mcp_service(mm);
+/// Pass the session manager shared with the metrics sampler, which reports open sessions.
+pub fn mcp_routes(mm: ModelManager, session_manager: Arc<LocalSessionManager>) -> Router<AppState> {
+    let mcp_service = create_mcp_service(mm, session_manager);
 
     // Wrap the MCP service to convert body types
     let wrapped_service = tower::service_fn(move |req: Request<Body>| {
@@ -0,0 +1,454 @@
+//! OpenID Connect login for browser users.
+//!
+//! Implements the authorization-code flow with PKCE against any OIDC
+//! provider: `/auth/login` redirects to the provider, `/auth/callback`
+//! exchanges the code, validates the ID token against the provider's JWKS
+//! and starts a cookie [session](crate::session). Agents are unaffected and
+//! keep authenticating with bearer tokens.
+//!
+//! Enabled by setting `OIDC_ISSUER_URL`, `OIDC_CLIENT_ID` and
+//! `OIDC_REDIRECT_URL` (see [`OidcConfig::from_env`]).
+
+use crate::AppState;
+use crate::auth::{AuthenticatedUser, JwksClient};
+use crate::error::ServerError;
+use crate::session::{DEFAULT_IDLE_TIMEOUT, clear_session_cookie, session_cookie, session_token};
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::{Validation, decode, decode_header};
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+/// Time a user has to complete the login at the provider.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Default browser session lifetime (8 hours).
+const DEFAULT_SESSION_TTL_SECONDS: u64 = 8 * 60 * 60;
+
+/// OIDC provider and client settings.
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    /// Provider issuer URL (discovery document at `/.well-known/openid-configuration`)
+    pub issuer_url: String,
+    pub client_id: String,
+    /// Omit for public clients (PKCE only)
+    pub client_secret: Option<String>,
+    /// This server's `/auth/callback` URL as registered with the provider
+    pub redirect_url: String,
+    pub scopes: String,
+    pub session_ttl: Duration,
+    /// Sessions without requests for this long are ended
+    pub session_idle_timeout: Duration,
+    /// Mark the session cookie `Secure` (default: redirect URL is https)
+    pub cookie_secure: bool,
+}
+
+impl OidcConfig {
+    /// Reads OIDC settings from the environment.
+    ///
+    /// Returns `None` unless `OIDC_ISSUER_URL`, `OIDC_CLIENT_ID` and
+    /// `OIDC_REDIRECT_URL` are all set. Optional: `OIDC_CLIENT_SECRET`,
+    /// `OIDC_SCOPES` (default `openid email profile`),
+    /// `OIDC_SESSION_TTL_SECONDS`, `OIDC_SESSION_IDLE_SECONDS` (default 30
+    /// minutes) and `OIDC_COOKIE_SECURE`.
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let issuer_url = var("OIDC_ISSUER_URL")?;
+        let client_id = var("OIDC_CLIENT_ID")?;
+        let Some(redirect_url) = var("OIDC_REDIRECT_URL") else {
+            warn!("OIDC_ISSUER_URL is set but OIDC_REDIRECT_URL is not. OIDC login disabled.");
+            return None;
+        };
+        let cookie_secure = var("OIDC_COOKIE_SECURE")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or_else(|| redirect_url.starts_with("https://"));
+
+        Some(Self {
+            issuer_url: issuer_url.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret: var("OIDC_CLIENT_SECRET"),
+            redirect_url,
+            scopes: var("OIDC_SCOPES").unwrap_or_else(|| "openid email profile".to_string()),
+            session_ttl: Duration::from_secs(
+                var("OIDC_SESSION_TTL_SECONDS")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_SESSION_TTL_SECONDS),
+            ),
+            session_idle_timeout: var("OIDC_SESSION_IDLE_SECONDS")
+                .and_then(|v| v.parse().ok())
+                .map_or(DEFAULT_IDLE_TIMEOUT, Duration::from_secs),
+            cookie_secure,
+        })
+    }
+}
+
+/// Subset of the provider discovery document used by the flow.
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Clone)]
+struct Provider {
+    metadata: ProviderMetadata,
+    jwks: JwksClient,
+}
+
+/// A login started at `/auth/login`, keyed by its `state` parameter.
+struct PendingLogin {
+    nonce: String,
+    code_verifier: String,
+    return_to: String,
+    started: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+}
+
+/// OIDC relying party: discovery, pending logins and token validation.
+#[derive(Clone)]
+pub struct OidcClient {
+    config: Arc<OidcConfig>,
+    http: Client,
+    provider: Arc<RwLock<Option<Provider>>>,
+    pending: Arc<Mutex<HashMap<String, PendingLogin>>>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            http: Client::new(),
+            provider: Arc::new(RwLock::new(None)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn config(&self) -> &OidcConfig {
+        &self.config
+    }
+
+    /// Returns provider metadata, fetching the discovery document once.
+    async fn provider(&self) -> Result<Provider, ServerError> {
+        if let Some(provider) = self.provider.read().await.as_ref() {
+            return Ok(provider.clone());
+        }
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer_url
+        );
+        let metadata: ProviderMetadata = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| ServerError::Internal(format!("OIDC discovery failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| {
+                ServerError::Internal(format!("Invalid OIDC discovery document: {}", e))
+            })?;
+        info!("OIDC provider discovered: {}", metadata.issuer);
+
+        let provider = Provider {
+            jwks: JwksClient::new(metadata.jwks_uri.clone()),
+            metadata,
+        };
+        *self.provider.write().await = Some(provider.clone());
+        Ok(provider)
+    }
+
+    /// Starts a login and returns the provider URL to redirect the browser to.
+    pub async fn authorization_url(&self, return_to: &str) -> Result<String, ServerError> {
+        let provider = self.provider().await?;
+
+        let state = random_token();
+        let nonce = random_token();
+        let code_verifier = format!("{}{}", random_token(), random_token());
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let url = reqwest::Url::parse_with_params(
+            &provider.metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.config.client_id.as_str()),
+                ("redirect_uri", self.config.redirect_url.as_str()),
+                ("scope", self.config.scopes.as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| ServerError::ConfigError(format!("Invalid authorization endpoint: {}", e)))?;
+
+        let mut pending = self.pending.lock().await;
+        pending.retain(|_, login| login.started.elapsed() < LOGIN_TIMEOUT);
+        pending.insert(
+            state,
+            PendingLogin {
+                nonce,
+                code_verifier,
+                return_to: safe_return_to(return_to).to_string(),
+                started: Instant::now(),
+            },
+        );
+        Ok(url.into())
+    }
+
+    /// Completes a login from the provider callback.
+    ///
+    /// # Returns
+    /// The authenticated user and the path to send the browser back to.
+    ///
+    /// # Errors
+    /// `Unauthorized` for an unknown or expired `state`, a failed code
+    /// exchange or an invalid ID token.
+    pub async fn complete(
+        &self,
+        code: &str,
+        state: &str,
+    ) -> Result<(AuthenticatedUser, String), ServerError> {
+        let login = self
+            .pending
+            .lock()
+            .await
+            .remove(state)
+            .filter(|login| login.started.elapsed() < LOGIN_TIMEOUT)
+            .ok_or(ServerError::Unauthorized)?;
+        let provider = self.provider().await?;
+
+        let mut request = self.http.post(&provider.metadata.token_endpoint).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", login.code_verifier.as_str()),
+        ]);
+        if let Some(secret) = &self.config.client_secret {
+            request = request.basic_auth(&self.config.client_id, Some(secret));
+        }
+        let tokens: TokenResponse = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| {
+                warn!("OIDC code exchange failed: {}", e);
+                ServerError::Unauthorized
+            })?
+            .json()
+            .await
+            .map_err(|e| {
+                warn!("OIDC token response has no ID token: {}", e);
+                ServerError::Unauthorized
+            })?;
+
+        let claims = self.validate_id_token(&provider, &tokens.id_token).await?;
+        if claims.nonce.as_deref() != Some(login.nonce.as_str()) {
+            warn!("OIDC ID token nonce mismatch");
+            return Err(ServerError::Unauthorized);
+        }
+
+        info!(
+            "OIDC login for subject {} ({})",
+            claims.sub,
+            claims.email.as_deref().unwrap_or("no email")
+        );
+        Ok((
+            AuthenticatedUser {
+                subject: claims.sub,
+                agent_name: None,
+                project_slug: None,
+            },
+            login.return_to,
+        ))
+    }
+
+    async fn validate_id_token(
+        &self,
+        provider: &Provider,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, ServerError> {
+        let header = decode_header(id_token).map_err(|_| ServerError::Unauthorized)?;
+        let kid = header.kid.ok_or(ServerError::Unauthorized)?;
+        let key = provider
+            .jwks
+            .get_verifying_key(&kid)
+            .await
+            .ok_or(ServerError::Unauthorized)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_issuer(&[&provider.metadata.issuer]);
+        decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                warn!("OIDC ID token validation failed: {}", e);
+                ServerError::Unauthorized
+            })
+    }
+}
+
+/// Only allow same-site relative paths as post-login destinations.
+fn safe_return_to(path: &str) -> &str {
+    if path.starts_with('/') && !path.starts_with("//") && !path.contains('\\') {
+        path
+    } else {
+        "/"
+    }
+}
+
+/// Unguessable URL-safe token (122 random bits).
+fn random_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn oidc_client(state: &AppState) -> Result<&OidcClient, ServerError> {
+    state
+        .oidc
+        .as_ref()
+        .ok_or_else(|| ServerError::NotFound("OIDC login is not configured".to_string()))
+}
+
+/// `/auth/login` URL that returns to `return_to` after login.
+#[cfg(feature = "with-web-ui")]
+fn login_location(return_to: &str) -> String {
+    reqwest::Url::parse_with_params("http://localhost/auth/login", &[("return_to", return_to)])
+        .ok()
+        .and_then(|url| url.query().map(|q| format!("/auth/login?{}", q)))
+        .unwrap_or_else(|| "/auth/login".to_string())
+}
+
+fn redirect(location: &str) -> Response {
+    (StatusCode::SEE_OTHER, [(header::LOCATION, location)]).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct LoginParams {
+    pub return_to: Option<String>,
+}
+
+/// `GET /auth/login` - redirects the browser to the OIDC provider.
+pub async fn login(
+    State(state): State<AppState>,
+    Query(params): Query<LoginParams>,
+) -> crate::error::Result<Response> {
+    let client = oidc_client(&state)?;
+    let url = client
+        .authorization_url(params.return_to.as_deref().unwrap_or("/"))
+        .await?;
+    Ok(redirect(&url))
+}
+
+#[derive(Deserialize)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// `GET /auth/callback` - finishes the login and sets the session cookie.
+pub async fn callback(
+    State(state): State<AppState>,
+    Query(params): Query<CallbackParams>,
+) -> crate::error::Result<Response> {
+    let client = oidc_client(&state)?;
+    if let Some(error) = params.error {
+        return Err(ServerError::BadRequest(format!(
+            "OIDC login failed: {} {}",
+            error,
+            params.error_description.unwrap_or_default()
+        )));
+    }
+    let (Some(code), Some(login_state)) = (params.code, params.state) else {
+        return Err(ServerError::BadRequest(
+            "Missing code or state parameter".to_string(),
+        ));
+    };
+
+    let (user, return_to) = client.complete(&code, &login_state).await?;
+    let ttl = client.config().session_ttl;
+    let token = state.sessions.create(user, ttl).await;
+
+    let mut response = redirect(&return_to);
+    if let Ok(cookie) = session_cookie(&token, ttl, client.config().cookie_secure).parse() {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    Ok(response)
+}
+
+/// `POST /auth/logout` - ends the browser session.
+pub async fn logout(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Some(token) = session_token(&headers) {
+        state.sessions.remove(&token).await;
+    }
+    let secure = state
+        .oidc
+        .as_ref()
+        .is_some_and(|client| client.config().cookie_secure);
+
+    let mut response = redirect("/");
+    if let Ok(cookie) = clear_session_cookie(secure).parse() {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    response
+}
+
+/// Serves the web UI, sending browsers without a session to the OIDC login.
+#[cfg(feature = "with-web-ui")]
+pub async fn login_required_ui(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    uri: axum::http::Uri,
+) -> Response {
+    let has_session = match session_token(&headers) {
+        Some(token) => state.sessions.get(&token).await.is_some(),
+        None => false,
+    };
+    if !has_session && state.oidc.is_some() {
+        let return_to = uri.path_and_query().map_or("/", |pq| pq.as_str());
+        return redirect(&login_location(return_to));
+    }
+    crate::static_files::serve_embedded_file(uri)
+        .await
+        .into_response()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_return_to_rejects_offsite_targets() {
+        assert_eq!(safe_return_to("/inbox?project=x"), "/inbox?project=x");
+        assert_eq!(safe_return_to("//evil.example"), "/");
+        assert_eq!(safe_return_to("https://evil.example"), "/");
+        assert_eq!(safe_return_to("/\\evil.example"), "/");
+    }
+}
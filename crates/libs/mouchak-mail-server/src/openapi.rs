@@ -10,8 +10,114 @@ use utoipa::OpenApi;
         crate::api::attachments::add_attachment,
         crate::api::attachments::list_attachments,
         crate::api::attachments::get_attachment,
+        crate::api::attachments::upload_attachment,
+        // Agent avatars
+        crate::api::avatar::agent_avatar,
+        // Identity
+        crate::api::me::me,
+        // Version handshake
+        crate::api::version::version,
         // Export
         crate::api::export::export_mailbox,
+        crate::api::exports::create_export_job,
+        crate::api::exports::list_export_jobs,
+        crate::api::exports::get_export_job,
+        crate::api::exports::download_export,
+        // Project contact policies
+        crate::api::contact_policy::get_contact_policy,
+        crate::api::contact_policy::set_contact_rule,
+        crate::api::contact_policy::delete_contact_rule,
+        // Team graphs
+        crate::api::team_graph::export_team_graph,
+        crate::api::team_graph::import_team_graph,
+        // Agent SLAs
+        crate::api::sla::list_slas,
+        crate::api::sla::set_sla,
+        crate::api::sla::delete_sla,
+        crate::api::sla::get_sla_report,
+        // Approval-gated sends
+        crate::api::approvals::list_approval_rules,
+        crate::api::approvals::create_approval_rule,
+        crate::api::approvals::delete_approval_rule,
+        crate::api::approvals::list_pending_messages,
+        crate::api::approvals::decide_pending_message,
+        // Dead letters
+        crate::api::dead_letters::list_dead_letters,
+        crate::api::dead_letters::get_dead_letter,
+        crate::api::dead_letters::requeue_dead_letter,
+        crate::api::dead_letters::discard_dead_letter,
+        // Custom message fields
+        crate::api::custom_fields::list_custom_fields,
+        crate::api::custom_fields::define_custom_field,
+        crate::api::custom_fields::delete_custom_field,
+        // External MCP tools
+        crate::api::external_tools::list_external_tools,
+        crate::api::external_tools::register_external_tool,
+        crate::api::external_tools::delete_external_tool,
+        // GitHub cross-posting
+        crate::api::github::get_github,
+        crate::api::github::set_github_repo,
+        crate::api::github::delete_github_repo,
+        crate::api::github::mirror_thread,
+        crate::api::github::unlink_thread,
+        crate::api::github::sync_github,
+        // CI build status ingestion
+        crate::api::ci::ingest_ci_event,
+        // Inbound email gateway
+        crate::api::ingest::ingest_email,
+        // Event log
+        crate::api::events::list_events,
+        crate::api::events::export_events,
+        crate::api::events::verify_events,
+        crate::api::ws::event_stream,
+        // Web Push
+        crate::api::push::vapid_public_key,
+        crate::api::push::subscribe,
+        crate::api::push::unsubscribe,
+        // Webhooks
+        crate::api::webhooks::create_webhook,
+        crate::api::webhooks::list_webhooks,
+        crate::api::webhooks::get_webhook,
+        crate::api::webhooks::delete_webhook,
+        crate::api::webhooks::list_deliveries,
+        // UI preferences
+        crate::api::preferences::get_preferences,
+        crate::api::preferences::update_preferences,
+        // Search facets
+        crate::api::search::search_facets,
+        // Message labels
+        crate::api::labels::list_labels,
+        crate::api::labels::create_label,
+        crate::api::labels::update_label,
+        crate::api::labels::delete_label,
+        crate::api::labels::list_label_messages,
+        crate::api::labels::apply_label,
+        crate::api::labels::remove_label,
+        // Saved searches (smart folders)
+        crate::api::saved_searches::list_saved_searches,
+        crate::api::saved_searches::save_search,
+        crate::api::saved_searches::run_saved_search,
+        crate::api::saved_searches::delete_saved_search,
+        // Rendering
+        crate::api::render::render_markdown,
+        // Inbox triage
+        crate::api::triage::triage_step,
+        // Inbox deltas for polling agents, and the inbox stream replacing polling
+        crate::api::inbox_delta::inbox_delta,
+        crate::api::inbox_stream::inbox_stream,
+        // Broadcast acknowledgment tracking
+        crate::api::broadcasts::list_broadcasts,
+        crate::api::broadcasts::get_broadcast_status,
+        // Read receipts
+        crate::api::receipts::get_message_receipts,
+        // Public ids
+        crate::api::public_ids::resolve_public_id,
+        // Outbox review
+        crate::api::outbox_review::outbox_needs_attention,
+        // Storage usage
+        crate::api::usage::get_usage,
+        // Thread reply trees
+        crate::api::threads::get_thread_tree,
     ),
     components(
         schemas(
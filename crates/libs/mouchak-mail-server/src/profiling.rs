@@ -0,0 +1,176 @@
+//! On-demand self-profiling (`pprof` feature).
+//!
+//! - `GET /debug/pprof/profile?seconds=N&format=flamegraph|proto` samples the
+//!   CPU for `N` seconds (default 10, max 60) and returns an SVG flamegraph or
+//!   a pprof protobuf for `go tool pprof`
+//! - `GET /debug/pprof/tasks` dumps the Tokio runtime's tasks
+//!
+//! Task backtraces need a build with `RUSTFLAGS="--cfg tokio_unstable --cfg
+//! tokio_taskdump"`; other builds report runtime task and queue counts only.
+//!
+//! Both endpoints sit behind the auth middleware and require the `admin`
+//! capability when RBAC is enabled.
+
+use crate::{AppState, ServerError};
+use axum::Router;
+use axum::extract::Query;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use serde::Deserialize;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Profile length when `seconds` is not given.
+const DEFAULT_PROFILE_SECONDS: u64 = 10;
+
+/// Longest profile a request may ask for.
+const MAX_PROFILE_SECONDS: u64 = 60;
+
+/// Sampling frequency; 99 Hz avoids lockstep with periodic work.
+const PROFILE_FREQUENCY_HZ: i32 = 99;
+
+/// Only one CPU profile can run at a time.
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+struct ProfilingSlot;
+
+impl ProfilingSlot {
+    fn acquire() -> Option<Self> {
+        (!PROFILING.swap(true, Ordering::SeqCst)).then_some(Self)
+    }
+}
+
+impl Drop for ProfilingSlot {
+    fn drop(&mut self) {
+        PROFILING.store(false, Ordering::SeqCst);
+    }
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/debug/pprof/profile", get(cpu_profile))
+        .route("/debug/pprof/tasks", get(task_dump))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileParams {
+    /// Sampling duration in seconds
+    pub seconds: Option<u64>,
+    /// `flamegraph` (SVG, default) or `proto` (pprof protobuf)
+    pub format: Option<String>,
+}
+
+/// Samples the CPU and returns a flamegraph or pprof profile.
+pub async fn cpu_profile(Query(params): Query<ProfileParams>) -> crate::error::Result<Response> {
+    let seconds = params
+        .seconds
+        .unwrap_or(DEFAULT_PROFILE_SECONDS)
+        .clamp(1, MAX_PROFILE_SECONDS);
+    let proto = match params.format.as_deref() {
+        None | Some("flamegraph") | Some("svg") => false,
+        Some("proto") | Some("pprof") => true,
+        Some(other) => {
+            return Err(ServerError::BadRequest(format!(
+                "Unknown profile format '{}' (expected flamegraph or proto)",
+                other
+            )));
+        }
+    };
+    let slot = ProfilingSlot::acquire()
+        .ok_or_else(|| ServerError::Conflict("A CPU profile is already running".into()))?;
+
+    // The profiler guard is held on a blocking thread for the whole sample
+    let body = tokio::task::spawn_blocking(move || {
+        let _slot = slot;
+        record_profile(Duration::from_secs(seconds), proto)
+    })
+    .await
+    .map_err(|e| ServerError::Internal(format!("Profiler task failed: {}", e)))??;
+
+    let (content_type, filename) = if proto {
+        ("application/octet-stream", "profile.pb")
+    } else {
+        ("image/svg+xml", "flamegraph.svg")
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn record_profile(duration: Duration, proto: bool) -> crate::error::Result<Vec<u8>> {
+    use pprof::protos::Message;
+
+    let profiler_error = |e: pprof::Error| ServerError::Internal(format!("Profiler error: {}", e));
+
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(PROFILE_FREQUENCY_HZ)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(profiler_error)?;
+    std::thread::sleep(duration);
+    let report = guard.report().build().map_err(profiler_error)?;
+
+    let mut body = Vec::new();
+    if proto {
+        report
+            .pprof()
+            .map_err(profiler_error)?
+            .encode(&mut body)
+            .map_err(|e| ServerError::Internal(format!("Failed to encode profile: {}", e)))?;
+    } else {
+        report.flamegraph(&mut body).map_err(profiler_error)?;
+    }
+    Ok(body)
+}
+
+/// Dumps the runtime's tasks as plain text.
+pub async fn task_dump() -> impl IntoResponse {
+    let handle = tokio::runtime::Handle::current();
+    let metrics = handle.metrics();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "workers: {}", metrics.num_workers());
+    let _ = writeln!(out, "alive tasks: {}", metrics.num_alive_tasks());
+    let _ = writeln!(out, "global queue depth: {}", metrics.global_queue_depth());
+
+    #[cfg(all(tokio_unstable, tokio_taskdump))]
+    {
+        let dump = handle.dump().await;
+        for task in dump.tasks().iter() {
+            let _ = writeln!(out, "\ntask {}:\n{}", task.id(), task.trace());
+        }
+    }
+    #[cfg(not(all(tokio_unstable, tokio_taskdump)))]
+    {
+        let _ = writeln!(
+            out,
+            "\ntask backtraces unavailable: build with RUSTFLAGS=\"--cfg tokio_unstable --cfg tokio_taskdump\""
+        );
+    }
+
+    ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], out)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_one_profile_runs_at_a_time() {
+        let slot = ProfilingSlot::acquire().unwrap();
+        assert!(ProfilingSlot::acquire().is_none());
+        drop(slot);
+        assert!(ProfilingSlot::acquire().is_some());
+    }
+}
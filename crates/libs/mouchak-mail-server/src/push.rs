@@ -0,0 +1,177 @@
+//! Web Push delivery.
+//!
+//! Sends urgent-message notices from [`WebPushBmc::pending_notices`] to every
+//! subscribed browser, signed with the server's VAPID key and encrypted with
+//! `aes128gcm` (RFC 8291). The `web-push` crate only builds the request; it is
+//! sent with reqwest like the other outbound HTTP. Subscriptions the push
+//! service reports as gone are removed; other failures count towards the
+//! subscription's failure limit.
+
+use mouchak_mail_common::config::PushConfig;
+use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::ModelManager;
+use mouchak_mail_core::model::web_push::{
+    PushNotice, PushSubscription, VapidKeys, WebPushBmc, vapid_keys_from_private_key,
+};
+use mouchak_mail_core::types::ProjectId;
+use reqwest::{Client, StatusCode};
+use std::time::Duration;
+use web_push::request_builder::build_request;
+use web_push::{
+    ContentEncoding, SubscriptionInfo, URL_SAFE_NO_PAD, VapidSignatureBuilder, WebPushError,
+    WebPushMessageBuilder,
+};
+
+/// Maximum notices pushed per scan.
+const NOTICES_PER_SCAN: i64 = 50;
+
+/// How long push services keep an undelivered notice (seconds).
+const NOTICE_TTL_SECONDS: u32 = 6 * 60 * 60;
+
+/// Timeout for one request to a push service.
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns the VAPID key pair: the configured key if set, else the stored one.
+pub async fn vapid_keys(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    config: &PushConfig,
+) -> mouchak_mail_core::Result<VapidKeys> {
+    match config.vapid_private_key.as_deref() {
+        Some(key) if !key.trim().is_empty() => vapid_keys_from_private_key(key),
+        _ => WebPushBmc::vapid_keys(ctx, mm).await,
+    }
+}
+
+/// Pushes notices for urgent messages created since the last scan.
+///
+/// # Returns
+/// Number of notifications delivered.
+pub async fn deliver_pending(
+    mm: &ModelManager,
+    config: &PushConfig,
+) -> Result<usize, crate::ServerError> {
+    let ctx = Ctx::root_ctx();
+    let batch = WebPushBmc::pending_notices(&ctx, mm, NOTICES_PER_SCAN).await?;
+    let Some(scanned_to) = batch.scanned_to else {
+        return Ok(0);
+    };
+    if batch.notices.is_empty() {
+        WebPushBmc::advance_cursor(&ctx, mm, scanned_to).await?;
+        return Ok(0);
+    }
+
+    let keys = vapid_keys(&ctx, mm, config).await?;
+    let client = Client::builder()
+        .timeout(PUSH_TIMEOUT)
+        .build()
+        .map_err(|e| crate::ServerError::Internal(format!("Web Push client: {}", e)))?;
+
+    let mut delivered = 0;
+    for notice in &batch.notices {
+        let subscriptions =
+            WebPushBmc::list_subscriptions(&ctx, mm, Some(ProjectId::new(notice.project_id)))
+                .await?;
+        for sub in &subscriptions {
+            match send_notice(&client, &keys, &config.vapid_subject, sub, notice).await {
+                Ok(()) => {
+                    delivered += 1;
+                    WebPushBmc::record_delivery(&ctx, mm, sub.id, true).await?;
+                }
+                Err(e) if is_gone(&e) => {
+                    tracing::info!(subscription = sub.id, "Removing expired push subscription");
+                    WebPushBmc::remove(&ctx, mm, sub.id).await?;
+                }
+                Err(e) => {
+                    tracing::warn!(subscription = sub.id, "Web Push delivery failed: {}", e);
+                    WebPushBmc::record_delivery(&ctx, mm, sub.id, false).await?;
+                }
+            }
+        }
+    }
+
+    WebPushBmc::advance_cursor(&ctx, mm, scanned_to).await?;
+    Ok(delivered)
+}
+
+async fn send_notice(
+    client: &Client,
+    keys: &VapidKeys,
+    subject: &str,
+    sub: &PushSubscription,
+    notice: &PushNotice,
+) -> Result<(), WebPushError> {
+    let info = SubscriptionInfo::new(&sub.endpoint, &sub.p256dh, &sub.auth);
+
+    let mut signature =
+        VapidSignatureBuilder::from_base64(&keys.private_key, URL_SAFE_NO_PAD, &info)?;
+    signature.add_claim("sub", subject);
+
+    let payload = notice.payload().to_string();
+    let mut message = WebPushMessageBuilder::new(&info);
+    message.set_payload(ContentEncoding::Aes128Gcm, payload.as_bytes());
+    message.set_vapid_signature(signature.build()?);
+    message.set_ttl(NOTICE_TTL_SECONDS);
+
+    let request = build_request::<Vec<u8>>(message.build()?);
+    let mut builder = client.post(request.uri().to_string());
+    for (name, value) in request.headers() {
+        builder = builder.header(name.as_str(), value.as_bytes());
+    }
+    let response = builder
+        .body(request.into_body())
+        .send()
+        .await
+        .map_err(|e| WebPushError::Other(e.to_string()))?;
+    response_result(response.status())
+}
+
+/// Maps a push service response status like `web_push::request_builder::parse_response`.
+fn response_result(status: StatusCode) -> Result<(), WebPushError> {
+    match status {
+        s if s.is_success() => Ok(()),
+        s if s.is_server_error() => Err(WebPushError::ServerError(None)),
+        StatusCode::UNAUTHORIZED => Err(WebPushError::Unauthorized),
+        StatusCode::GONE => Err(WebPushError::EndpointNotValid),
+        StatusCode::NOT_FOUND => Err(WebPushError::EndpointNotFound),
+        StatusCode::PAYLOAD_TOO_LARGE => Err(WebPushError::PayloadTooLarge),
+        StatusCode::BAD_REQUEST => Err(WebPushError::BadRequest(None)),
+        s => Err(WebPushError::Other(s.to_string())),
+    }
+}
+
+/// Returns `true` if the push service no longer accepts the subscription.
+fn is_gone(error: &WebPushError) -> bool {
+    matches!(
+        error.short_description(),
+        "endpoint_not_valid" | "endpoint_not_found"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_result() {
+        assert!(response_result(StatusCode::CREATED).is_ok());
+        for gone in [StatusCode::GONE, StatusCode::NOT_FOUND] {
+            assert!(
+                response_result(gone).is_err_and(|e| is_gone(&e)),
+                "{}",
+                gone
+            );
+        }
+        for failed in [
+            StatusCode::UNAUTHORIZED,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::BAD_GATEWAY,
+        ] {
+            assert!(
+                response_result(failed).is_err_and(|e| !is_gone(&e)),
+                "{}",
+                failed
+            );
+        }
+    }
+}
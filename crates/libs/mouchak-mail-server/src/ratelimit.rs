@@ -1,15 +1,37 @@
+//! Request rate limiting.
+//!
+//! Every request is counted against a bucket keyed by the caller (see
+//! [`get_bucket_key`]); with `RATE_LIMIT_PER_TOOL=true`, REST tool endpoints
+//! (`/api/<tool_name>`) are also counted per tool category (see
+//! [`ToolRateLimits`]).
+//!
+//! A rejected request gets `429 Too Many Requests` with `RateLimit-Limit`,
+//! `RateLimit-Remaining`, `RateLimit-Reset` and `Retry-After` headers and a
+//! JSON body naming the limited dimension and `retry_after_ms`, so clients
+//! can wait exactly as long as needed.
+//!
+//! Clients are identified by address as resolved by
+//! [`TrustedProxies`](crate::client_ip::TrustedProxies); clients in
+//! `RATE_LIMIT_EXEMPT_NETWORKS` are not limited at all.
+
+use crate::client_ip::{self, IpNet, TrustedProxies};
+use crate::error::ErrorCode;
 use axum::extract::ConnectInfo;
 use axum::{
+    Json,
     extract::{Request, State},
-    http::StatusCode,
+    http::{HeaderName, HeaderValue, StatusCode, header},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
-use governor::{Quota, RateLimiter, clock::DefaultClock, state::keyed::DashMapStateStore};
+use governor::clock::{Clock, DefaultClock};
+use governor::{NotUntil, Quota, RateLimiter, state::keyed::DashMapStateStore};
+use serde::Serialize;
 use std::net::SocketAddr;
 use std::num::NonZeroU32;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, warn};
 
 /// Rate limiter keyed by composite identity string.
@@ -20,10 +42,128 @@ use tracing::{debug, warn};
 /// NIST Control: SC-5 (DoS Protection)
 type KeyedRateLimiter = RateLimiter<String, DashMapStateStore<String>, DefaultClock>;
 
+/// Requests the exhausted bucket allows in a burst.
+pub const LIMIT_HEADER: &str = "ratelimit-limit";
+/// Requests left in the bucket; always 0 on a rejection.
+pub const REMAINING_HEADER: &str = "ratelimit-remaining";
+/// Seconds until the bucket admits another request.
+pub const RESET_HEADER: &str = "ratelimit-reset";
+
+/// What a rejected request was counted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitDimension {
+    /// Unauthenticated caller, by client IP
+    Ip,
+    /// Authenticated caller, by JWT subject and IP
+    Agent,
+    /// Tool category of the endpoint, per caller
+    Tool,
+}
+
+impl LimitDimension {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitDimension::Ip => "ip",
+            LimitDimension::Agent => "agent",
+            LimitDimension::Tool => "tool",
+        }
+    }
+}
+
+/// A request over its rate limit.
+///
+/// # Fields
+///
+/// - `limit` - Requests the exhausted bucket allows in a burst
+/// - `retry_after` - Time until the bucket admits another request
+/// - `tool` - Tool name, when `dimension` is [`LimitDimension::Tool`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub dimension: LimitDimension,
+    pub limit: u32,
+    pub retry_after: Duration,
+    pub tool: Option<String>,
+}
+
+impl RateLimited {
+    fn new(
+        dimension: LimitDimension,
+        limit: u32,
+        not_until: &NotUntil<<DefaultClock as Clock>::Instant>,
+    ) -> Self {
+        Self {
+            dimension,
+            limit,
+            retry_after: not_until.wait_time_from(DefaultClock::default().now()),
+            tool: None,
+        }
+    }
+
+    /// `retry_after` in milliseconds, rounded up.
+    pub fn retry_after_ms(&self) -> u64 {
+        let micros = u64::try_from(self.retry_after.as_micros()).unwrap_or(u64::MAX);
+        micros.div_ceil(1000)
+    }
+}
+
+#[derive(Serialize)]
+struct RateLimitedBody {
+    code: &'static str,
+    error: String,
+    dimension: &'static str,
+    limit: u32,
+    retry_after_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool: Option<String>,
+}
+
+impl IntoResponse for RateLimited {
+    fn into_response(self) -> Response {
+        let retry_after_ms = self.retry_after_ms();
+        // Whole seconds for the headers, never 0 so clients do not spin
+        let reset_secs = retry_after_ms.div_ceil(1000).max(1);
+        let subject = match &self.tool {
+            Some(tool) => format!("'{}'", tool),
+            None => format!("this {}", self.dimension.as_str()),
+        };
+        let body = RateLimitedBody {
+            code: ErrorCode::RateLimited.as_str(),
+            error: format!(
+                "Rate limit of {} requests exceeded for {}; retry in {} ms",
+                self.limit, subject, retry_after_ms
+            ),
+            dimension: self.dimension.as_str(),
+            limit: self.limit,
+            retry_after_ms,
+            tool: self.tool,
+        };
+
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+        let headers = response.headers_mut();
+        for (name, value) in [
+            (HeaderName::from_static(LIMIT_HEADER), u64::from(self.limit)),
+            (HeaderName::from_static(REMAINING_HEADER), 0),
+            (HeaderName::from_static(RESET_HEADER), reset_secs),
+            (header::RETRY_AFTER, reset_secs),
+        ] {
+            headers.insert(name, HeaderValue::from(value));
+        }
+        response
+    }
+}
+
 #[derive(Clone)]
 pub struct RateLimitConfig {
     pub limiter: Arc<KeyedRateLimiter>,
     pub enabled: bool,
+    /// Requests a caller may make in a burst
+    pub burst: u32,
+    /// Per-tool limits for REST tool endpoints, when `RATE_LIMIT_PER_TOOL=true`
+    pub tools: Option<ToolRateLimits>,
+    /// Reverse proxies whose `X-Forwarded-For` names the client
+    pub trusted_proxies: TrustedProxies,
+    /// Clients never rate limited, from `RATE_LIMIT_EXEMPT_NETWORKS`
+    pub exempt_networks: Vec<IpNet>,
 }
 
 impl Default for RateLimitConfig {
@@ -63,10 +203,57 @@ impl RateLimitConfig {
             burst
         );
 
-        Self { limiter, enabled }
+        let tools = (std::env::var("RATE_LIMIT_PER_TOOL").as_deref() == Ok("true"))
+            .then(ToolRateLimits::new);
+
+        Self {
+            limiter,
+            enabled,
+            burst,
+            tools,
+            trusted_proxies: TrustedProxies::from_env(),
+            exempt_networks: client_ip::networks_from_env("RATE_LIMIT_EXEMPT_NETWORKS"),
+        }
+    }
+
+    /// Counts a request to `path` against the caller's bucket and, for tool
+    /// endpoints, its tool category.
+    ///
+    /// `bucket_key` comes from [`get_bucket_key`] for the client `ip`.
+    pub fn check(
+        &self,
+        bucket_key: &str,
+        ip: std::net::IpAddr,
+        path: &str,
+    ) -> Result<(), RateLimited> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let key = bucket_key.to_string();
+        if let Err(not_until) = self.limiter.check_key(&key) {
+            let dimension = if key == ip.to_string() {
+                LimitDimension::Ip
+            } else {
+                LimitDimension::Agent
+            };
+            return Err(RateLimited::new(dimension, self.burst, &not_until));
+        }
+        if let Some(tools) = &self.tools
+            && let Some(tool) = tool_name(path)
+        {
+            tools.limit_tool(tool, bucket_key)?;
+        }
+        Ok(())
     }
 }
 
+/// Tool name of a REST tool endpoint such as `/api/send_message`.
+fn tool_name(path: &str) -> Option<&str> {
+    let name = path.strip_prefix("/api/")?;
+    (!name.contains('/') && ToolCategory::from_tool_name(name) != ToolCategory::Default)
+        .then_some(name)
+}
+
 // ============================================================================
 // Per-Tool Rate Limiting (PORT-4.2)
 // ============================================================================
@@ -100,9 +287,11 @@ impl ToolCategory {
         const WRITE_TOOLS: &[&str] = &[
             "send_message",
             "reply_message",
+            "forward_message",
             "file_reservation_paths",
             "reserve_file",
             "release_reservation",
+            "release_reservation_set",
             "force_release_reservation",
             "renew_file_reservation",
             "acquire_build_slot",
@@ -113,37 +302,75 @@ impl ToolCategory {
             "create_agent_identity",
             "mark_message_read",
             "acknowledge_message",
+            "respond_deadline",
+            "approve_pending_message",
+            "requeue_dead_letter",
+            "watch_thread",
+            "unwatch_thread",
+            "apply_label",
+            "remove_label",
+            "save_search",
+            "delete_saved_search",
+            "link_ticket",
+            "link_commit",
             "request_contact",
             "respond_contact",
             "set_contact_policy",
+            "grant_delegation",
+            "revoke_delegation",
+            "import_team_graph",
             "register_macro",
             "unregister_macro",
             "invoke_macro",
             "ensure_project",
+            "create_project_from_template",
             "ensure_product",
             "link_project_to_product",
             "unlink_project_from_product",
             "install_precommit_guard",
             "uninstall_precommit_guard",
             "add_attachment",
+            "define_workflow",
+            "start_workflow",
+            "advance_workflow",
+            "set_project_settings",
+            "set_message_template",
         ];
 
         // Read tools - higher limits (100 rps)
         const READ_TOOLS: &[&str] = &[
             "fetch_inbox",
             "check_inbox",
+            "check_inbox_delta",
             "list_outbox",
             "get_message",
+            "get_broadcast_status",
+            "get_message_receipts",
+            "list_deadlines",
+            "list_pending_messages",
+            "list_dead_letters",
+            "get_sla_report",
+            "get_usage",
+            "get_workflow_status",
+            "list_custom_fields",
             "search_messages",
+            "search_facets",
+            "list_by_label",
+            "list_saved_searches",
+            "run_saved_search",
             "list_agents",
             "get_agent_profile",
             "whois",
             "list_threads",
             "get_thread",
+            "get_thread_tree",
             "summarize_thread",
             "summarize_threads",
             "list_file_reservations",
+            "check_paths",
             "list_contacts",
+            "list_delegations",
+            "export_team_graph",
             "list_macros",
             "list_projects",
             "get_project_info",
@@ -253,6 +480,13 @@ impl ToolRateLimits {
     /// * `Ok(())` if within limits
     /// * `Err(ToolCategory)` if rate limited, with the category that was exceeded
     pub fn check_tool(&self, tool_name: &str, bucket_key: &str) -> Result<(), ToolCategory> {
+        self.limit_tool(tool_name, bucket_key)
+            .map_err(|_| ToolCategory::from_tool_name(tool_name))
+    }
+
+    /// Like [`check_tool`](Self::check_tool), but a rejection tells how long
+    /// to back off.
+    pub fn limit_tool(&self, tool_name: &str, bucket_key: &str) -> Result<(), RateLimited> {
         if !self.enabled {
             return Ok(());
         }
@@ -266,14 +500,22 @@ impl ToolRateLimits {
 
         match limiter.check_key(&bucket_key.to_string()) {
             Ok(_) => Ok(()),
-            Err(_) => {
+            Err(not_until) => {
                 warn!(
                     tool = %tool_name,
                     category = ?category,
                     bucket_key = %bucket_key,
                     "Per-tool rate limit exceeded"
                 );
-                Err(category)
+                let limited = RateLimited::new(
+                    LimitDimension::Tool,
+                    self.rps_for_category(category),
+                    &not_until,
+                );
+                Err(RateLimited {
+                    tool: Some(tool_name.to_string()),
+                    ..limited
+                })
             }
         }
     }
@@ -353,33 +595,32 @@ pub async fn rate_limit_middleware(
     ConnectInfo(peer): ConnectInfo<SocketAddr>,
     req: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Response {
     if !config.enabled {
-        return Ok(next.run(req).await);
+        return next.run(req).await;
     }
 
     // Determine Client IP
-    // Prefer X-Forwarded-For header if present (standard for reverse proxies)
-    // Fallback to direct peer address (ConnectInfo)
-    let ip = if let Some(forwarded) = req.headers().get("x-forwarded-for") {
-        forwarded
-            .to_str()
-            .ok()
-            .and_then(|s| s.split(',').next()) // Take the first IP in the list
-            .and_then(|s| s.trim().parse::<std::net::IpAddr>().ok())
-            .unwrap_or(peer.ip())
-    } else {
-        peer.ip()
-    };
+    // X-Forwarded-For is only believed from trusted proxies, so clients
+    // cannot pick a fresh bucket per request
+    let ip = config.trusted_proxies.client_ip(peer.ip(), req.headers());
+    if client_ip::in_networks(&config.exempt_networks, ip) {
+        return next.run(req).await;
+    }
 
     // Get bucket key (includes JWT subject if present)
     let bucket_key = get_bucket_key(&req, ip);
 
-    match config.limiter.check_key(&bucket_key) {
-        Ok(_) => Ok(next.run(req).await),
-        Err(_) => {
-            warn!(bucket_key = %bucket_key, "RateLimit: exceeded quota");
-            Err(StatusCode::TOO_MANY_REQUESTS)
+    match config.check(&bucket_key, ip, req.uri().path()) {
+        Ok(()) => next.run(req).await,
+        Err(limited) => {
+            warn!(
+                bucket_key = %bucket_key,
+                dimension = limited.dimension.as_str(),
+                retry_after_ms = limited.retry_after_ms(),
+                "RateLimit: exceeded quota"
+            );
+            limited.into_response()
         }
     }
 }
@@ -530,6 +771,86 @@ mod tests {
         assert!(config.enabled);
     }
 
+    fn config_with_burst(burst: u32, tools: Option<ToolRateLimits>) -> RateLimitConfig {
+        let quota = Quota::per_minute(NonZeroU32::new(1).unwrap())
+            .allow_burst(NonZeroU32::new(burst).unwrap());
+        RateLimitConfig {
+            limiter: Arc::new(RateLimiter::keyed(quota)),
+            enabled: true,
+            burst,
+            tools,
+            trusted_proxies: TrustedProxies::default(),
+            exempt_networks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_check_names_dimension() {
+        let config = config_with_burst(1, None);
+        let ip: IpAddr = "10.0.0.2".parse().unwrap();
+
+        assert!(config.check("10.0.0.2", ip, "/api/health").is_ok());
+        let limited = config.check("10.0.0.2", ip, "/api/health").unwrap_err();
+        assert_eq!(limited.dimension, LimitDimension::Ip);
+        assert_eq!(limited.limit, 1);
+        assert!(limited.retry_after > Duration::from_secs(50));
+        assert!(limited.tool.is_none());
+
+        assert!(config.check("agent-7:10.0.0.2", ip, "/api/health").is_ok());
+        let limited = config
+            .check("agent-7:10.0.0.2", ip, "/api/health")
+            .unwrap_err();
+        assert_eq!(limited.dimension, LimitDimension::Agent);
+    }
+
+    #[test]
+    fn test_rate_limit_check_per_tool() {
+        let config = config_with_burst(100, Some(ToolRateLimits::new()));
+        let ip: IpAddr = "10.0.0.3".parse().unwrap();
+
+        for _ in 0..10 {
+            assert!(config.check("10.0.0.3", ip, "/api/send_message").is_ok());
+        }
+        let limited = config
+            .check("10.0.0.3", ip, "/api/send_message")
+            .unwrap_err();
+        assert_eq!(limited.dimension, LimitDimension::Tool);
+        assert_eq!(limited.limit, 10);
+        assert_eq!(limited.tool.as_deref(), Some("send_message"));
+
+        // Paths other than tool endpoints only count against the caller
+        assert!(config.check("10.0.0.3", ip, "/api/projects/x").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_response() {
+        let limited = RateLimited {
+            dimension: LimitDimension::Agent,
+            limit: 2000,
+            retry_after: Duration::from_micros(1_500_001),
+            tool: None,
+        };
+        assert_eq!(limited.retry_after_ms(), 1501);
+
+        let response = limited.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let headers = response.headers();
+        assert_eq!(headers[LIMIT_HEADER], "2000");
+        assert_eq!(headers[REMAINING_HEADER], "0");
+        assert_eq!(headers[RESET_HEADER], "2");
+        assert_eq!(headers[header::RETRY_AFTER], "2");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "RATE_LIMITED");
+        assert_eq!(body["dimension"], "agent");
+        assert_eq!(body["retry_after_ms"], 1501);
+        assert_eq!(body["limit"], 2000);
+        assert!(body.get("tool").is_none());
+    }
+
     // ========================================================================
     // Per-Tool Rate Limiting Tests (PORT-4.2)
     // ========================================================================
@@ -0,0 +1,235 @@
+//! Browser sessions.
+//!
+//! Browser users who log in through OIDC get an opaque session token in an
+//! `HttpOnly` cookie. The token maps to the authenticated principal in an
+//! in-memory [`SessionStore`]; agents never use sessions and keep sending
+//! bearer tokens.
+//!
+//! Sessions end after their absolute lifetime or after an idle timeout.
+//! Each session also carries a CSRF token: mutating requests authenticated by
+//! the cookie must echo it in the [`CSRF_HEADER`] header. The UI reads the
+//! token from `GET /api/me`.
+
+use crate::auth::AuthenticatedUser;
+use axum::http::{HeaderMap, Method, header};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Name of the session cookie.
+pub const SESSION_COOKIE: &str = "mouchak_session";
+
+/// Header carrying the session's CSRF token on mutating requests.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Default idle timeout for browser sessions.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// A logged-in browser session.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub user: AuthenticatedUser,
+    pub csrf_token: String,
+    pub expires_at: Instant,
+    pub last_seen: Instant,
+}
+
+impl Session {
+    fn is_live(&self, now: Instant, idle_timeout: Duration) -> bool {
+        self.expires_at > now && now.duration_since(self.last_seen) < idle_timeout
+    }
+
+    /// Checks the CSRF header of a request against this session's token.
+    pub fn csrf_matches(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get(CSRF_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| constant_time_eq(v.as_bytes(), self.csrf_token.as_bytes()))
+    }
+}
+
+/// In-memory store of browser sessions, keyed by session token.
+#[derive(Clone)]
+pub struct SessionStore {
+    sessions: Arc<RwLock<HashMap<String, Session>>>,
+    idle_timeout: Duration,
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDLE_TIMEOUT)
+    }
+}
+
+impl SessionStore {
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            idle_timeout,
+        }
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Starts a session for `user` and returns its token.
+    pub async fn create(&self, user: AuthenticatedUser, ttl: Duration) -> String {
+        let token = new_token();
+        let now = Instant::now();
+        let mut sessions = self.sessions.write().await;
+        sessions.retain(|_, s| s.is_live(now, self.idle_timeout));
+        sessions.insert(
+            token.clone(),
+            Session {
+                user,
+                csrf_token: new_token(),
+                expires_at: now + ttl,
+                last_seen: now,
+            },
+        );
+        token
+    }
+
+    /// Returns a live session and marks it as active.
+    ///
+    /// Sessions past their lifetime or idle timeout are dropped.
+    pub async fn get(&self, token: &str) -> Option<Session> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.get_mut(token)?;
+        if !session.is_live(now, self.idle_timeout) {
+            sessions.remove(token);
+            return None;
+        }
+        session.last_seen = now;
+        Some(session.clone())
+    }
+
+    /// Ends a session. Returns `false` if it did not exist.
+    pub async fn remove(&self, token: &str) -> bool {
+        self.sessions.write().await.remove(token).is_some()
+    }
+}
+
+/// Whether a request with this method needs a CSRF token when authenticated
+/// by the session cookie.
+pub fn requires_csrf(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Extracts the session token from the request's `Cookie` headers.
+pub fn session_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// `Set-Cookie` value carrying a session token.
+pub fn session_cookie(token: &str, max_age: Duration, secure: bool) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}",
+        SESSION_COOKIE,
+        token,
+        max_age.as_secs(),
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+/// `Set-Cookie` value that deletes the session cookie.
+pub fn clear_session_cookie(secure: bool) -> String {
+    session_cookie("", Duration::ZERO, secure)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Unguessable token (244 random bits).
+fn new_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn user(subject: &str) -> AuthenticatedUser {
+        AuthenticatedUser {
+            subject: subject.to_string(),
+            agent_name: None,
+            project_slug: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let store = SessionStore::default();
+        let token = store.create(user("alice"), Duration::from_secs(60)).await;
+        assert_eq!(token.len(), 64);
+        assert_eq!(store.get(&token).await.unwrap().user.subject, "alice");
+
+        assert!(store.remove(&token).await);
+        assert!(store.get(&token).await.is_none());
+
+        let expired = store.create(user("bob"), Duration::ZERO).await;
+        assert!(store.get(&expired).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_idle_timeout() {
+        let store = SessionStore::new(Duration::from_millis(50));
+        let token = store.create(user("alice"), Duration::from_secs(60)).await;
+        assert!(store.get(&token).await.is_some());
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(store.get(&token).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_csrf_token_validation() {
+        let store = SessionStore::default();
+        let token = store.create(user("alice"), Duration::from_secs(60)).await;
+        let session = store.get(&token).await.unwrap();
+        assert_ne!(session.csrf_token, token);
+
+        let mut headers = HeaderMap::new();
+        assert!(!session.csrf_matches(&headers));
+        headers.insert(CSRF_HEADER, HeaderValue::from_static("wrong"));
+        assert!(!session.csrf_matches(&headers));
+        headers.insert(CSRF_HEADER, session.csrf_token.parse().unwrap());
+        assert!(session.csrf_matches(&headers));
+
+        assert!(requires_csrf(&Method::POST));
+        assert!(requires_csrf(&Method::DELETE));
+        assert!(!requires_csrf(&Method::GET));
+    }
+
+    #[test]
+    fn test_session_token_from_cookie_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; mouchak_session=abc123"),
+        );
+        assert_eq!(session_token(&headers).as_deref(), Some("abc123"));
+
+        let cookie = session_cookie("abc123", Duration::from_secs(60), true);
+        assert!(cookie.contains("HttpOnly"));
+        assert!(cookie.ends_with("; Secure"));
+        assert!(clear_session_cookie(false).contains("Max-Age=0"));
+    }
+}
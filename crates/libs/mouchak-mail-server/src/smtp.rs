@@ -0,0 +1,137 @@
+//! SMTP submission for the email bridge, on top of `lettre`.
+//!
+//! Every message is handed to the relay in its own session (`STARTTLS`,
+//! implicit TLS or plain, per `email_bridge.smtp_security`), bounded by
+//! `email_bridge.timeout_seconds`. TLS certificates are checked against the
+//! Mozilla root store bundled with `webpki-roots`, so no system certificate
+//! store is needed.
+
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::extension::ClientId;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use mouchak_mail_common::config::EmailBridgeConfig;
+use std::time::Duration;
+use thiserror::Error;
+
+/// How the connection to the relay is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Security {
+    /// Plain connection upgraded with `STARTTLS`
+    StartTls,
+    /// TLS from the first byte (SMTPS)
+    Tls,
+    /// No encryption, for local relays only
+    None,
+}
+
+impl Security {
+    /// Parses `email_bridge.smtp_security`.
+    pub fn parse(value: &str) -> Result<Self, SmtpError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "starttls" => Ok(Self::StartTls),
+            "tls" | "ssl" | "smtps" => Ok(Self::Tls),
+            "none" | "plain" => Ok(Self::None),
+            other => Err(SmtpError::Config(format!(
+                "email_bridge.smtp_security must be starttls, tls or none, not '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Why a message could not be handed to the relay.
+#[derive(Debug, Error)]
+pub enum SmtpError {
+    #[error("{0}")]
+    Config(String),
+    #[error("invalid recipient '{0}'")]
+    Recipient(String),
+    #[error(transparent)]
+    Message(#[from] lettre::error::Error),
+    #[error(transparent)]
+    Send(#[from] lettre::transport::smtp::Error),
+    #[error("relay did not answer within the timeout")]
+    Timeout,
+}
+
+impl SmtpError {
+    /// Whether retrying the same message cannot succeed: the relay refused
+    /// it with a `5xx` reply, a recipient is not an address at all, or the
+    /// mail cannot be built.
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            Self::Recipient(_) | Self::Message(_) => true,
+            Self::Send(e) => e.is_permanent(),
+            Self::Config(_) | Self::Timeout => false,
+        }
+    }
+}
+
+/// Sends `message` to the recipients of its envelope through the
+/// configured relay.
+///
+/// # Errors
+/// Returns [`SmtpError::Timeout`] when the session takes longer than
+/// `timeout_seconds`, [`SmtpError::Send`] when the relay cannot be reached
+/// or refuses a command, and [`SmtpError::Config`] for unusable settings.
+pub async fn send_mail(config: &EmailBridgeConfig, message: &Message) -> Result<(), SmtpError> {
+    let host = config
+        .smtp_host()
+        .ok_or_else(|| SmtpError::Config("email_bridge.smtp_host is not set".to_string()))?;
+    let timeout = Duration::from_secs(config.timeout_seconds.max(1));
+    let transport = transport(config, host, timeout)?;
+
+    tokio::time::timeout(timeout, transport.send(message.clone()))
+        .await
+        .map_err(|_| SmtpError::Timeout)??;
+    Ok(())
+}
+
+fn transport(
+    config: &EmailBridgeConfig,
+    host: &str,
+    timeout: Duration,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, SmtpError> {
+    let builder = match Security::parse(&config.smtp_security)? {
+        Security::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+        Security::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+        Security::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+    };
+    let mut builder = builder
+        .port(config.smtp_port)
+        .timeout(Some(timeout))
+        .hello_name(ClientId::Domain(helo_name(&config.from).to_string()));
+    if let Some(username) = config.smtp_username.as_deref().filter(|u| !u.is_empty()) {
+        let password = config.smtp_password.clone().unwrap_or_default();
+        builder = builder.credentials(Credentials::new(username.to_string(), password));
+    }
+    Ok(builder.build())
+}
+
+/// Name announced in `EHLO`: the domain of the sender address.
+fn helo_name(from: &str) -> &str {
+    from.rsplit_once('@')
+        .map(|(_, domain)| domain.trim())
+        .filter(|d| !d.is_empty())
+        .unwrap_or("localhost")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_security_parse() {
+        assert_eq!(Security::parse("STARTTLS").unwrap(), Security::StartTls);
+        assert_eq!(Security::parse(" tls ").unwrap(), Security::Tls);
+        assert_eq!(Security::parse("none").unwrap(), Security::None);
+        assert!(Security::parse("ssl3").is_err());
+    }
+
+    #[test]
+    fn test_permanent_errors() {
+        assert!(SmtpError::Recipient("not an address".to_string()).is_permanent());
+        assert!(!SmtpError::Timeout.is_permanent());
+    }
+}
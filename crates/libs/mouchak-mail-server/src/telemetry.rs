@@ -0,0 +1,385 @@
+//! Operational metrics for requests, queues and background jobs.
+//!
+//! Served on `/metrics`:
+//!
+//! - `http_requests_total{method,route,status}` and
+//!   `http_request_duration_seconds{method,route}` - recorded by
+//!   [`track_http_metrics`] for every routed request
+//! - `mouchak_queue_depth{queue}` - items waiting in each queue:
+//!   `push_pending` (urgent mail not yet pushed), `push_retrying`
+//!   (subscriptions whose last push failed), `deferred_deliveries` (mail held
+//!   by focus windows) and `archive_commits` (Git archive backlog)
+//! - `mouchak_scheduler_job_lag_seconds{job}` - how far a periodic job is
+//!   behind its interval; grows without bound if the job hangs or dies
+//! - `mouchak_scheduler_job_last_run_timestamp_seconds{job}` - Unix time of
+//!   the last completed run
+//! - `mouchak_scheduler_job_failures_total{job}` - runs that returned an error
+//! - `mouchak_mcp_sse_sessions` - open stateful MCP (SSE) sessions
+//! - `mouchak_escalation_sweep_duration_seconds` - histogram of escalation
+//!   sweep durations
+//! - `mouchak_ack_backlog` / `mouchak_ack_oldest_pending_age_seconds` -
+//!   acknowledgments still owed, and how long the oldest has waited
+//! - `mouchak_disk_free_bytes` / `mouchak_disk_total_bytes` - space of the
+//!   volume holding the data directory, set by the disk watcher
+//! - `mouchak_read_only` - 1 while writes are suspended for lack of disk space
+//! - `mouchak_db_pool_size` / `mouchak_db_connections_checked_out` -
+//!   connections the store opens, and BMC calls holding one right now
+//! - `mouchak_db_query_duration_seconds{bmc}` - histogram of how long each
+//!   BMC module holds the connection per call, recorded by [`record_db_use`]
+//! - `mouchak_db_slow_queries_total{bmc}` - uses over `database.slow_query_ms`,
+//!   which are also logged
+//!
+//! Gauges are refreshed every [`SAMPLE_INTERVAL_SECONDS`] by [`spawn_sampler`].
+//! Every metric is listed in [`REGISTRY`], which the dashboard generator in
+//! [`crate::dashboards`] reads so panels and alerts use the real names.
+
+use crate::mcp::LocalSessionManager;
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use mouchak_mail_core::ModelManager;
+use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::focus_window::FocusWindowBmc;
+use mouchak_mail_core::model::message::MessageBmc;
+use mouchak_mail_core::model::web_push::WebPushBmc;
+use mouchak_mail_core::store::db_metrics::{self, DbUse};
+use mouchak_mail_core::store::git_store::pending_archive_commits;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const QUEUE_DEPTH: &str = "mouchak_queue_depth";
+pub const SCHEDULER_JOB_LAG: &str = "mouchak_scheduler_job_lag_seconds";
+pub const SCHEDULER_JOB_LAST_RUN: &str = "mouchak_scheduler_job_last_run_timestamp_seconds";
+pub const SCHEDULER_JOB_FAILURES: &str = "mouchak_scheduler_job_failures_total";
+pub const MCP_SSE_SESSIONS: &str = "mouchak_mcp_sse_sessions";
+pub const ESCALATION_SWEEP_DURATION: &str = "mouchak_escalation_sweep_duration_seconds";
+pub const HTTP_REQUESTS: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION: &str = "http_request_duration_seconds";
+pub const ACK_BACKLOG: &str = "mouchak_ack_backlog";
+pub const ACK_OLDEST_PENDING_AGE: &str = "mouchak_ack_oldest_pending_age_seconds";
+pub const DISK_FREE_BYTES: &str = "mouchak_disk_free_bytes";
+pub const DISK_TOTAL_BYTES: &str = "mouchak_disk_total_bytes";
+pub const READ_ONLY: &str = "mouchak_read_only";
+pub const DB_POOL_SIZE: &str = "mouchak_db_pool_size";
+pub const DB_CONNECTIONS_CHECKED_OUT: &str = "mouchak_db_connections_checked_out";
+pub const DB_QUERY_DURATION: &str = "mouchak_db_query_duration_seconds";
+pub const DB_SLOW_QUERIES: &str = "mouchak_db_slow_queries_total";
+
+/// Prometheus metric type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+/// A metric exported on `/metrics`.
+#[derive(Debug, Clone, Copy)]
+pub struct MetricDef {
+    pub name: &'static str,
+    pub kind: MetricKind,
+    pub help: &'static str,
+    pub labels: &'static [&'static str],
+}
+
+/// Every metric the server exports.
+pub const REGISTRY: &[MetricDef] = &[
+    MetricDef {
+        name: HTTP_REQUESTS,
+        kind: MetricKind::Counter,
+        help: "HTTP requests handled, by route and status code",
+        labels: &["method", "route", "status"],
+    },
+    MetricDef {
+        name: HTTP_REQUEST_DURATION,
+        kind: MetricKind::Histogram,
+        help: "HTTP request latency in seconds",
+        labels: &["method", "route"],
+    },
+    MetricDef {
+        name: QUEUE_DEPTH,
+        kind: MetricKind::Gauge,
+        help: "Items waiting in each background queue",
+        labels: &["queue"],
+    },
+    MetricDef {
+        name: SCHEDULER_JOB_LAG,
+        kind: MetricKind::Gauge,
+        help: "Seconds a periodic job is behind its interval",
+        labels: &["job"],
+    },
+    MetricDef {
+        name: SCHEDULER_JOB_LAST_RUN,
+        kind: MetricKind::Gauge,
+        help: "Unix time of the last completed run of a periodic job",
+        labels: &["job"],
+    },
+    MetricDef {
+        name: SCHEDULER_JOB_FAILURES,
+        kind: MetricKind::Counter,
+        help: "Runs of a periodic job that returned an error",
+        labels: &["job"],
+    },
+    MetricDef {
+        name: MCP_SSE_SESSIONS,
+        kind: MetricKind::Gauge,
+        help: "Open stateful MCP (SSE) sessions",
+        labels: &[],
+    },
+    MetricDef {
+        name: ESCALATION_SWEEP_DURATION,
+        kind: MetricKind::Histogram,
+        help: "Duration of escalation sweeps in seconds",
+        labels: &[],
+    },
+    MetricDef {
+        name: ACK_BACKLOG,
+        kind: MetricKind::Gauge,
+        help: "Acknowledgments still owed across all projects",
+        labels: &[],
+    },
+    MetricDef {
+        name: ACK_OLDEST_PENDING_AGE,
+        kind: MetricKind::Gauge,
+        help: "Seconds the oldest pending acknowledgment has waited",
+        labels: &[],
+    },
+    MetricDef {
+        name: DISK_FREE_BYTES,
+        kind: MetricKind::Gauge,
+        help: "Free bytes on the volume holding the data directory",
+        labels: &[],
+    },
+    MetricDef {
+        name: DISK_TOTAL_BYTES,
+        kind: MetricKind::Gauge,
+        help: "Size in bytes of the volume holding the data directory",
+        labels: &[],
+    },
+    MetricDef {
+        name: READ_ONLY,
+        kind: MetricKind::Gauge,
+        help: "1 while writes are suspended because the disk is nearly full",
+        labels: &[],
+    },
+    MetricDef {
+        name: DB_POOL_SIZE,
+        kind: MetricKind::Gauge,
+        help: "Database connections opened by the store",
+        labels: &[],
+    },
+    MetricDef {
+        name: DB_CONNECTIONS_CHECKED_OUT,
+        kind: MetricKind::Gauge,
+        help: "BMC calls currently holding a database connection",
+        labels: &[],
+    },
+    MetricDef {
+        name: DB_QUERY_DURATION,
+        kind: MetricKind::Histogram,
+        help: "Seconds a BMC call holds the database connection",
+        labels: &["bmc"],
+    },
+    MetricDef {
+        name: DB_SLOW_QUERIES,
+        kind: MetricKind::Counter,
+        help: "BMC database uses slower than database.slow_query_ms",
+        labels: &["bmc"],
+    },
+];
+
+/// Looks up a metric in [`REGISTRY`].
+pub fn metric(name: &str) -> Option<&'static MetricDef> {
+    REGISTRY.iter().find(|m| m.name == name)
+}
+
+/// Registers the help text of every metric with the recorder.
+pub fn describe_metrics() {
+    for def in REGISTRY {
+        match def.kind {
+            MetricKind::Counter => metrics::describe_counter!(def.name, def.help),
+            MetricKind::Gauge => metrics::describe_gauge!(def.name, def.help),
+            MetricKind::Histogram => metrics::describe_histogram!(def.name, def.help),
+        }
+    }
+}
+
+/// Records [`HTTP_REQUESTS`] and [`HTTP_REQUEST_DURATION`] for a request.
+///
+/// Installed as a route layer, so the `route` label is the matched route
+/// template (`/api/projects/{project_slug}`), never a raw path.
+pub async fn track_http_metrics(req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+    let started = Instant::now();
+
+    let response = next.run(req).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::counter!(HTTP_REQUESTS, "method" => method.clone(), "route" => route.clone(), "status" => status)
+        .increment(1);
+    metrics::histogram!(HTTP_REQUEST_DURATION, "method" => method, "route" => route)
+        .record(started.elapsed().as_secs_f64());
+    response
+}
+
+/// How often queue depths and job lag are sampled.
+pub const SAMPLE_INTERVAL_SECONDS: u64 = 15;
+
+/// Liveness of one periodic background job.
+#[derive(Clone)]
+pub struct ScheduledJob {
+    name: &'static str,
+    interval: Duration,
+    last_run: Arc<Mutex<Instant>>,
+}
+
+impl ScheduledJob {
+    /// Records a completed run.
+    pub fn finished(&self, ok: bool) {
+        if let Ok(mut last_run) = self.last_run.lock() {
+            *last_run = Instant::now();
+        }
+        if !ok {
+            metrics::counter!(SCHEDULER_JOB_FAILURES, "job" => self.name).increment(1);
+        }
+        metrics::gauge!(SCHEDULER_JOB_LAST_RUN, "job" => self.name)
+            .set(chrono::Utc::now().timestamp() as f64);
+    }
+
+    /// Time the job is overdue: time since its last run beyond one interval.
+    pub fn lag(&self) -> Duration {
+        self.last_run
+            .lock()
+            .map(|last_run| last_run.elapsed().saturating_sub(self.interval))
+            .unwrap_or_default()
+    }
+}
+
+/// Registry of the periodic jobs started by the server.
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    jobs: Arc<Mutex<Vec<ScheduledJob>>>,
+}
+
+impl Scheduler {
+    /// Registers a job that runs every `interval_seconds`.
+    ///
+    /// The interval starts now, matching loops that sleep before their first run.
+    pub fn register(&self, name: &'static str, interval_seconds: u64) -> ScheduledJob {
+        let job = ScheduledJob {
+            name,
+            interval: Duration::from_secs(interval_seconds),
+            last_run: Arc::new(Mutex::new(Instant::now())),
+        };
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.push(job.clone());
+        }
+        job
+    }
+
+    fn jobs(&self) -> Vec<ScheduledJob> {
+        self.jobs
+            .lock()
+            .map(|jobs| jobs.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Updates every gauge once.
+///
+/// Queue queries that fail (e.g. during a migration) leave the previous value.
+pub async fn sample(
+    mm: &ModelManager,
+    scheduler: &Scheduler,
+    mcp_sessions: &LocalSessionManager,
+    push_enabled: bool,
+) {
+    let ctx = Ctx::root_ctx();
+
+    if push_enabled {
+        match WebPushBmc::count_pending(&ctx, mm).await {
+            Ok(n) => metrics::gauge!(QUEUE_DEPTH, "queue" => "push_pending").set(n as f64),
+            Err(e) => tracing::debug!("Failed to sample push queue: {}", e),
+        }
+        match WebPushBmc::count_failing(&ctx, mm).await {
+            Ok(n) => metrics::gauge!(QUEUE_DEPTH, "queue" => "push_retrying").set(n as f64),
+            Err(e) => tracing::debug!("Failed to sample push retries: {}", e),
+        }
+    }
+    match FocusWindowBmc::count_all_deferred(&ctx, mm).await {
+        Ok(n) => metrics::gauge!(QUEUE_DEPTH, "queue" => "deferred_deliveries").set(n as f64),
+        Err(e) => tracing::debug!("Failed to sample deferred deliveries: {}", e),
+    }
+    metrics::gauge!(QUEUE_DEPTH, "queue" => "archive_commits")
+        .set(pending_archive_commits() as f64);
+
+    match MessageBmc::ack_backlog(&ctx, mm).await {
+        Ok(backlog) => {
+            metrics::gauge!(ACK_BACKLOG).set(backlog.pending as f64);
+            metrics::gauge!(ACK_OLDEST_PENDING_AGE).set(backlog.oldest_age_seconds as f64);
+        }
+        Err(e) => tracing::debug!("Failed to sample ack backlog: {}", e),
+    }
+
+    for job in scheduler.jobs() {
+        metrics::gauge!(SCHEDULER_JOB_LAG, "job" => job.name).set(job.lag().as_secs_f64());
+    }
+
+    let sessions = mcp_sessions.sessions.read().await.len();
+    metrics::gauge!(MCP_SSE_SESSIONS).set(sessions as f64);
+
+    metrics::gauge!(DB_POOL_SIZE).set(mm.db_pool_size() as f64);
+    metrics::gauge!(DB_CONNECTIONS_CHECKED_OUT).set(db_metrics::checked_out() as f64);
+}
+
+/// Records a finished BMC database use; installed as the
+/// [`db_metrics`] observer by [`crate::setup_metrics`].
+pub fn record_db_use(db_use: &DbUse) {
+    metrics::histogram!(DB_QUERY_DURATION, "bmc" => db_use.bmc)
+        .record(db_use.elapsed.as_secs_f64());
+    if db_use.slow {
+        metrics::counter!(DB_SLOW_QUERIES, "bmc" => db_use.bmc).increment(1);
+    }
+}
+
+/// Starts the background task that refreshes the gauges.
+pub fn spawn_sampler(
+    mm: ModelManager,
+    scheduler: Scheduler,
+    mcp_sessions: Arc<LocalSessionManager>,
+    push_enabled: bool,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(SAMPLE_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            sample(&mm, &scheduler, &mcp_sessions, push_enabled).await;
+        }
+    });
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lag_counts_time_past_interval() {
+        let scheduler = Scheduler::default();
+        let hourly = scheduler.register("hourly", 3600);
+        let constant = scheduler.register("constant", 0);
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(hourly.lag(), Duration::ZERO);
+        assert!(constant.lag() >= Duration::from_millis(20));
+
+        constant.finished(true);
+        assert!(constant.lag() < Duration::from_millis(20));
+        assert_eq!(scheduler.jobs().len(), 2);
+    }
+}
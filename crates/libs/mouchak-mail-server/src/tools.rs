@@ -1,16 +1,38 @@
 use axum::{
     Json,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
 };
 use chrono::Utc;
-use mouchak_mail_core::model::file_reservation::{FileReservationBmc, FileReservationForCreate};
-use mouchak_mail_core::{self, Ctx};
+use mouchak_mail_core::model::capability_routing::{CapabilityResolution, CapabilityRoutingBmc};
+use mouchak_mail_core::model::custom_field::{CustomFieldBmc, parse_field_pairs};
+use mouchak_mail_core::model::dead_letter::{DeadLetterBmc, DeadLetterForCreate};
+use mouchak_mail_core::model::file_reservation::FileReservationBmc;
+use mouchak_mail_core::model::focus_window::{
+    DEFAULT_ALLOWED_IMPORTANCE, FocusWindowBmc, FocusWindowForCreate,
+};
+use mouchak_mail_core::model::listing::{ListPage, ListQuery};
+use mouchak_mail_core::model::message::{SearchContextMessage, SearchHit};
+use mouchak_mail_core::model::message_catalog::{DEFAULT_LOCALE, MessageCatalogBmc};
+use mouchak_mail_core::model::project_settings::{
+    ProjectSettings, ProjectSettingsBmc, ProjectSettingsForUpdate,
+};
+use mouchak_mail_core::model::reservation_set::{
+    ReservationSetBmc, ReservationSetForCreate, ReservationSetOutcome,
+};
+use mouchak_mail_core::model::thread_read::ThreadReadBmc;
+use mouchak_mail_core::model::ticket::{Ticket, TicketBmc};
+use mouchak_mail_core::types::{AgentId, ProjectId};
+use mouchak_mail_core::utils::body_format::{BodyFormat, RenderFormat, render_body};
+use mouchak_mail_core::utils::clock;
+use mouchak_mail_core::utils::search_highlight::Snippet;
+use mouchak_mail_core::{self, Ctx, MessageId};
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
 use crate::AppState;
+use crate::conditional::Validators;
 
 // --- health_check ---
 #[derive(Serialize)]
@@ -102,6 +124,8 @@ pub struct EnsureProjectPayload {
 #[derive(Serialize)]
 pub struct EnsureProjectResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub slug: String,
     pub human_key: String,
 }
@@ -149,15 +173,12 @@ pub async fn ensure_project(
     };
 
     // Ensure built-in macros exist
-    mouchak_mail_core::model::macro_def::MacroDefBmc::ensure_builtin_macros(
-        &ctx,
-        mm,
-        project.id.get(),
-    )
-    .await?;
+    mouchak_mail_core::model::macro_def::MacroDefBmc::ensure_builtin_macros(&ctx, mm, project.id)
+        .await?;
 
     Ok(Json(EnsureProjectResponse {
         id: project.id.get(),
+        public_id: project.public_id,
         slug: project.slug,
         human_key: project.human_key,
     })
@@ -179,6 +200,8 @@ pub struct RegisterAgentPayload {
 #[derive(Serialize)]
 pub struct RegisterAgentResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub name: String,
     /// Project ID for e2e test compatibility
     pub project_id: i64,
@@ -218,6 +241,7 @@ pub async fn register_agent(
 
     Ok(Json(RegisterAgentResponse {
         id: agent.id.get(),
+        public_id: agent.public_id,
         name: agent.name,
         project_id: project.id.get(),
         program: agent.program,
@@ -246,16 +270,27 @@ pub struct SendMessagePayload {
     pub bcc_names: Option<Vec<String>>,
     pub subject: String,
     pub body_md: String,
+    /// Format of `body_md`: markdown (default), plain or json
+    #[serde(default)]
+    pub body_format: Option<String>,
     pub thread_id: Option<String>,
     pub importance: Option<String>,
     /// Whether recipients must acknowledge this message (default: false)
     #[serde(default)]
     pub ack_required: bool,
+    /// Custom field values, checked against the project's declarations
+    #[serde(default)]
+    pub custom_fields: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Agent to send as; `sender_name` must hold a delegation grant from it
+    #[serde(default)]
+    pub on_behalf_of: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct SendMessageResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub project_id: i64,
     pub sender_id: i64,
     pub sender_name: String,
@@ -265,15 +300,70 @@ pub struct SendMessageResponse {
     pub importance: String,
     pub ack_required: bool,
     pub created_ts: chrono::NaiveDateTime,
+    /// How `capability:<name>` recipients were resolved (omitted when unused)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub routing: Vec<CapabilityResolution>,
 }
 
+/// Sends a message; a send whose project or agents no longer resolve is
+/// kept as a dead letter and answered with a 404 naming it.
 pub async fn send_message(
     State(app_state): State<AppState>,
     Json(payload): Json<SendMessagePayload>,
+) -> crate::error::Result<Response> {
+    let envelope = DeadLetterForCreate {
+        project_slug: payload.project_slug.clone(),
+        sender_name: payload.sender_name.clone(),
+        on_behalf_of: payload.on_behalf_of.clone(),
+        to: payload.recipient_names.clone(),
+        cc: payload.cc_names.clone().unwrap_or_default(),
+        bcc: payload.bcc_names.clone().unwrap_or_default(),
+        subject: payload.subject.clone(),
+        body_md: payload.body_md.clone(),
+        thread_id: payload.thread_id.clone(),
+        importance: payload.importance.clone(),
+        ack_required: payload.ack_required,
+        error: String::new(),
+    };
+    match deliver_message(&app_state, payload).await {
+        Err(crate::ServerError::Database(e)) if DeadLetterBmc::is_undeliverable(&e) => {
+            let ctx = Ctx::root_ctx();
+            let error = e.to_string();
+            let dl_c = DeadLetterForCreate {
+                error: error.clone(),
+                ..envelope
+            };
+            let id = DeadLetterBmc::record(&ctx, &app_state.mm, dl_c).await?;
+            Err(crate::ServerError::NotFound(format!(
+                "{} (kept as dead letter {})",
+                error, id
+            )))
+        }
+        result => result,
+    }
+}
+
+async fn deliver_message(
+    app_state: &AppState,
+    payload: SendMessagePayload,
 ) -> crate::error::Result<Response> {
     let ctx = Ctx::root_ctx();
     let mm = &app_state.mm;
 
+    let body_format: BodyFormat = payload
+        .body_format
+        .as_deref()
+        .map(str::parse)
+        .transpose()?
+        .unwrap_or_default();
+    if body_format == BodyFormat::Json
+        && serde_json::from_str::<serde_json::Value>(&payload.body_md).is_err()
+    {
+        return Err(crate::ServerError::BadRequest(
+            "body_md is not valid JSON but body_format is 'json'".into(),
+        ));
+    }
+
     let project = mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(
         &ctx,
         mm,
@@ -288,46 +378,57 @@ pub async fn send_message(
     )
     .await?;
 
-    // Resolve "to" recipients
-    let mut recipient_ids = Vec::new();
-    for name in payload.recipient_names {
-        let agent =
-            mouchak_mail_core::model::agent::AgentBmc::get_by_name(&ctx, mm, project.id, &name)
-                .await?;
-        recipient_ids.push(agent.id.get());
-    }
+    let custom_fields = mouchak_mail_core::model::message::MessageBmc::validate_custom_fields(
+        &ctx,
+        mm,
+        project.id,
+        payload.custom_fields.as_ref(),
+    )
+    .await?;
 
-    // Resolve "cc" recipients
-    let cc_ids = if let Some(cc_names) = payload.cc_names {
-        let mut ids = Vec::new();
-        for name in cc_names {
-            let agent =
-                mouchak_mail_core::model::agent::AgentBmc::get_by_name(&ctx, mm, project.id, &name)
-                    .await?;
-            ids.push(agent.id.get());
+    // A delegate writes as the agent it acts for, which stays the sender
+    let author = match payload.on_behalf_of.as_deref() {
+        Some(name) => {
+            mouchak_mail_core::model::agent::AgentBmc::get_by_name(&ctx, mm, project.id, name)
+                .await?
         }
+        None => sender.clone(),
+    };
+
+    // Resolve recipients; `capability:<name>` addresses never resolve to the sender
+    let sender_id = Some(author.id);
+    let (recipient_ids, mut routing) = CapabilityRoutingBmc::resolve_recipients(
+        &ctx,
+        mm,
+        project.id,
+        &payload.recipient_names,
+        sender_id,
+    )
+    .await?;
+
+    let cc_ids = if let Some(cc_names) = payload.cc_names {
+        let (ids, cc_routing) =
+            CapabilityRoutingBmc::resolve_recipients(&ctx, mm, project.id, &cc_names, sender_id)
+                .await?;
+        routing.extend(cc_routing);
         Some(ids)
     } else {
         None
     };
 
-    // Resolve "bcc" recipients
     let bcc_ids = if let Some(bcc_names) = payload.bcc_names {
-        let mut ids = Vec::new();
-        for name in bcc_names {
-            let agent =
-                mouchak_mail_core::model::agent::AgentBmc::get_by_name(&ctx, mm, project.id, &name)
-                    .await?;
-            ids.push(agent.id.get());
-        }
+        let (ids, bcc_routing) =
+            CapabilityRoutingBmc::resolve_recipients(&ctx, mm, project.id, &bcc_names, sender_id)
+                .await?;
+        routing.extend(bcc_routing);
         Some(ids)
     } else {
         None
     };
 
     let msg_c = mouchak_mail_core::model::message::MessageForCreate {
-        project_id: project.id.get(),
-        sender_id: sender.id.get(),
+        project_id: project.id,
+        sender_id: author.id,
         recipient_ids,
         cc_ids,
         bcc_ids,
@@ -338,13 +439,34 @@ pub async fn send_message(
         ack_required: payload.ack_required,
     };
 
-    let message_id = mouchak_mail_core::model::message::MessageBmc::create(&ctx, mm, msg_c).await?;
+    let message_id =
+        mouchak_mail_core::model::message::MessageBmc::create_on_behalf(&ctx, mm, sender.id, msg_c)
+            .await?;
+    CapabilityRoutingBmc::record(&ctx, mm, message_id, &routing).await?;
+    if body_format != BodyFormat::Markdown {
+        mouchak_mail_core::model::message::MessageBmc::set_body_format(
+            &ctx,
+            mm,
+            message_id,
+            body_format,
+        )
+        .await?;
+    }
+    // Sender values win over fields added by post_receive plugins
+    mouchak_mail_core::model::message::MessageBmc::merge_custom_fields(
+        &ctx,
+        mm,
+        message_id,
+        &custom_fields,
+    )
+    .await?;
 
     // Fetch the full message to return
     let message = mouchak_mail_core::model::message::MessageBmc::get(&ctx, mm, message_id).await?;
 
     Ok(Json(SendMessageResponse {
         id: message.id,
+        public_id: message.public_id,
         project_id: message.project_id,
         sender_id: message.sender_id,
         sender_name: message.sender_name,
@@ -354,6 +476,7 @@ pub async fn send_message(
         importance: message.importance,
         ack_required: message.ack_required,
         created_ts: message.created_ts,
+        routing,
     })
     .into_response())
 }
@@ -365,6 +488,9 @@ pub struct ListInboxPayload {
     pub agent_name: String,
     #[serde(default = "default_limit")]
     pub limit: i64,
+    /// `x-next-cursor` of the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 fn default_limit() -> i64 {
@@ -374,13 +500,18 @@ fn default_limit() -> i64 {
 #[derive(Serialize)]
 pub struct InboxMessage {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub subject: String,
     pub sender_name: String,
     pub created_ts: chrono::NaiveDateTime,
 }
 
+/// Lists a page of an agent's inbox. Conditional on `If-None-Match` /
+/// `If-Modified-Since` (see [`crate::conditional`]).
 pub async fn list_inbox(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<ListInboxPayload>,
 ) -> crate::error::Result<Response> {
     let ctx = Ctx::root_ctx();
@@ -400,26 +531,46 @@ pub async fn list_inbox(
     )
     .await?;
 
-    let messages = mouchak_mail_core::model::message::MessageBmc::list_inbox_for_agent(
+    let stamp =
+        mouchak_mail_core::model::message::MessageBmc::inbox_stamp(&ctx, mm, project.id, agent.id)
+            .await?;
+    let validators = Validators::new(
+        &stamp,
+        &format!(
+            "inbox:{}:{}:{}:{}",
+            project.id.get(),
+            agent.id.get(),
+            payload.limit,
+            payload.cursor.as_deref().unwrap_or_default()
+        ),
+    );
+    if validators.matches(&headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let page = mouchak_mail_core::model::message::MessageBmc::list_inbox_page(
         &ctx,
         mm,
-        project.id.get(),
-        agent.id.get(),
+        project.id,
+        agent.id,
         payload.limit,
+        payload.cursor.as_deref(),
     )
     .await?;
 
-    let inbox_msgs: Vec<InboxMessage> = messages
+    let inbox_msgs: Vec<InboxMessage> = page
+        .items
         .into_iter()
         .map(|msg| InboxMessage {
             id: msg.id,
+            public_id: msg.public_id,
             subject: msg.subject,
             sender_name: msg.sender_name,
             created_ts: msg.created_ts,
         })
         .collect();
 
-    Ok(Json(inbox_msgs).into_response())
+    Ok(validators.apply(paged_json(inbox_msgs, page.total, page.next_cursor)))
 }
 
 // --- list_outbox ---
@@ -429,6 +580,9 @@ pub struct ListOutboxPayload {
     pub agent_name: String,
     #[serde(default = "default_limit")]
     pub limit: i64,
+    /// `x-next-cursor` of the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 pub async fn list_outbox(
@@ -452,56 +606,98 @@ pub async fn list_outbox(
     )
     .await?;
 
-    let messages = mouchak_mail_core::model::message::MessageBmc::list_outbox_for_agent(
+    let page = mouchak_mail_core::model::message::MessageBmc::list_outbox_page(
         &ctx,
         mm,
-        project.id.get(),
-        agent.id.get(),
+        project.id,
+        agent.id,
         payload.limit,
+        payload.cursor.as_deref(),
     )
     .await?;
 
-    let outbox_msgs: Vec<InboxMessage> = messages
+    let outbox_msgs: Vec<InboxMessage> = page
+        .items
         .into_iter()
         .map(|msg| InboxMessage {
             id: msg.id,
+            public_id: msg.public_id,
             subject: msg.subject,
             sender_name: msg.sender_name,
             created_ts: msg.created_ts,
         })
         .collect();
 
-    Ok(Json(outbox_msgs).into_response())
+    Ok(paged_json(outbox_msgs, page.total, page.next_cursor))
 }
 
 // --- list_all_projects ---
 #[derive(Serialize)]
 pub struct ProjectResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub slug: String,
     pub human_key: String,
     pub created_at: chrono::NaiveDateTime,
 }
 
+/// Header carrying the number of matches across all pages.
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+/// Header carrying the cursor of the next page, absent on the last page.
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Responds with a page as a plain JSON array, with paging info in headers.
+fn paged_json<T: Serialize>(items: Vec<T>, total: i64, next_cursor: Option<String>) -> Response {
+    let mut response = Json(items).into_response();
+    let headers = response.headers_mut();
+    headers.insert(TOTAL_COUNT_HEADER, total.into());
+    if let Some(cursor) = next_cursor.and_then(|c| HeaderValue::from_str(&c).ok()) {
+        headers.insert(NEXT_CURSOR_HEADER, cursor);
+    }
+    response
+}
+
+/// Lists projects. Query parameters page, filter and sort (see [`ListQuery`]).
+///
+/// Conditional unless filtered by `active_within` (see [`crate::conditional`]).
 pub async fn list_all_projects(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListQuery>,
 ) -> crate::error::Result<Response> {
     let ctx = Ctx::root_ctx();
     let mm = &app_state.mm;
 
-    let projects = mouchak_mail_core::model::project::ProjectBmc::list_all(&ctx, mm).await?;
+    let validators = mouchak_mail_core::model::project::ProjectBmc::list_stamp(&ctx, mm, &query)
+        .await?
+        .map(|stamp| Validators::new(&stamp, &format!("projects:{:?}", query)));
+    if let Some(validators) = &validators
+        && validators.matches(&headers)
+    {
+        return Ok(validators.not_modified());
+    }
+
+    let page = mouchak_mail_core::model::project::ProjectBmc::list_page(&ctx, mm, &query).await?;
 
-    let project_responses: Vec<ProjectResponse> = projects
+    let project_responses: Vec<ProjectResponse> = page
+        .items
         .into_iter()
         .map(|p| ProjectResponse {
             id: p.id.get(),
+            public_id: p.public_id,
             slug: p.slug,
             human_key: p.human_key,
             created_at: p.created_at,
         })
         .collect();
 
-    Ok(Json(project_responses).into_response())
+    let response = paged_json(project_responses, page.total, page.next_cursor);
+    Ok(match validators {
+        Some(validators) => validators.apply(response),
+        None => response,
+    })
 }
 
 // --- delete_project ---
@@ -566,6 +762,8 @@ pub struct ListAgentsPayload {
 #[derive(Serialize)]
 pub struct AgentResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub name: String,
     pub program: String,
     pub model: String,
@@ -574,9 +772,11 @@ pub struct AgentResponse {
     pub last_active_ts: chrono::NaiveDateTime,
 }
 
+/// Lists a project's agents. Query parameters page, filter and sort (see [`ListQuery`]).
 pub async fn list_all_agents_for_project(
     State(app_state): State<AppState>,
     Path(project_slug): Path<String>,
+    Query(query): Query<ListQuery>,
 ) -> crate::error::Result<Response> {
     let ctx = Ctx::root_ctx();
     let mm = &app_state.mm;
@@ -584,14 +784,15 @@ pub async fn list_all_agents_for_project(
     let project =
         mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(&ctx, mm, &project_slug)
             .await?;
-    let agents =
-        mouchak_mail_core::model::agent::AgentBmc::list_all_for_project(&ctx, mm, project.id)
-            .await?;
+    let page =
+        mouchak_mail_core::model::agent::AgentBmc::list_page(&ctx, mm, project.id, &query).await?;
 
-    let agent_responses: Vec<AgentResponse> = agents
+    let agent_responses: Vec<AgentResponse> = page
+        .items
         .into_iter()
         .map(|a| AgentResponse {
             id: a.id.get(),
+            public_id: a.public_id,
             name: a.name,
             program: a.program,
             model: a.model,
@@ -601,7 +802,7 @@ pub async fn list_all_agents_for_project(
         })
         .collect();
 
-    Ok(Json(agent_responses).into_response())
+    Ok(paged_json(agent_responses, page.total, page.next_cursor))
 }
 
 // --- get_message ---
@@ -615,45 +816,109 @@ pub struct GetMessagePayload {
 #[derive(Serialize)]
 pub struct MessageResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub project_id: i64,
     pub sender_id: i64,
     pub sender_name: String,
     pub thread_id: Option<String>,
     pub subject: String,
     pub body_md: String,
+    /// Size of the complete body when `body_md` is only a preview (omitted
+    /// otherwise, and with `full=true`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_body_bytes: Option<i64>,
+    pub body_format: BodyFormat,
+    /// Body rendered in the requested `format` (omitted for raw)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_rendered: Option<String>,
     pub importance: String,
     pub ack_required: bool,
     pub created_ts: chrono::NaiveDateTime,
     pub attachments: Vec<serde_json::Value>,
     pub recipients: Vec<String>,
+    /// Linked Jira/Linear tickets with their cached title and status
+    pub tickets: Vec<Ticket>,
+}
+
+#[derive(Deserialize)]
+pub struct GetMessageQuery {
+    /// raw (default), plain or html
+    pub format: Option<String>,
+    /// Return the complete body of an oversized message
+    #[serde(default)]
+    pub full: bool,
 }
 
 pub async fn get_message(
     State(app_state): State<AppState>,
     Path(message_id): Path<i64>,
+    Query(query): Query<GetMessageQuery>,
 ) -> crate::error::Result<Response> {
     let ctx = Ctx::root_ctx();
     let mm = &app_state.mm;
 
-    let message = mouchak_mail_core::model::message::MessageBmc::get(&ctx, mm, message_id).await?;
-    let recipients =
-        mouchak_mail_core::model::message::MessageBmc::get_recipients(&ctx, mm, message_id)
-            .await
-            .unwrap_or_default();
+    let render: RenderFormat = query
+        .format
+        .as_deref()
+        .map(str::parse)
+        .transpose()?
+        .unwrap_or_default();
+    let mut message =
+        mouchak_mail_core::model::message::MessageBmc::get(&ctx, mm, MessageId::new(message_id))
+            .await?;
+    let mut full_body_bytes = None;
+    if let Some(full) = mouchak_mail_core::model::message::MessageBmc::get_offloaded_body(
+        &ctx,
+        mm,
+        MessageId::new(message_id),
+    )
+    .await?
+    {
+        if query.full {
+            message.body_md = full.body_md;
+        } else {
+            full_body_bytes = Some(full.size_bytes);
+        }
+    }
+    let body_format = mouchak_mail_core::model::message::MessageBmc::get_body_format(
+        &ctx,
+        mm,
+        MessageId::new(message_id),
+    )
+    .await?;
+    let body_rendered =
+        (render != RenderFormat::Raw).then(|| render_body(&message.body_md, body_format, render));
+    let recipients = mouchak_mail_core::model::message::MessageBmc::get_recipients(
+        &ctx,
+        mm,
+        MessageId::new(message_id),
+    )
+    .await
+    .unwrap_or_default();
+    let tickets = TicketBmc::list_for_messages(&ctx, mm, &[message_id])
+        .await?
+        .remove(&message_id)
+        .unwrap_or_default();
 
     Ok(Json(MessageResponse {
         id: message.id,
+        public_id: message.public_id,
         project_id: message.project_id,
         sender_id: message.sender_id,
         sender_name: message.sender_name,
         thread_id: message.thread_id,
         subject: message.subject,
         body_md: message.body_md,
+        full_body_bytes,
+        body_format,
+        body_rendered,
         importance: message.importance,
         ack_required: message.ack_required,
         created_ts: message.created_ts,
         attachments: message.attachments,
         recipients,
+        tickets,
     })
     .into_response())
 }
@@ -683,45 +948,30 @@ pub struct FileReservationGranted {
     pub expires_ts: String,
 }
 
+/// A requested path that overlaps another agent's reservation.
 #[derive(Serialize)]
 pub struct FileReservationConflict {
+    /// Requested path that clashed
+    pub path: String,
+    pub reservation_id: i64,
     pub path_pattern: String,
+    /// Agent holding the clashing reservation
+    pub agent_name: String,
     pub exclusive: bool,
     pub expires_ts: String,
     pub conflict_type: String,
     pub message: String,
 }
 
+/// `granted` is empty whenever `conflicts` is not: paths are reserved all
+/// or nothing, grouped under `reservation_set_id`.
 #[derive(Serialize)]
 pub struct FileReservationPathsResponse {
+    pub reservation_set_id: Option<i64>,
     pub granted: Vec<FileReservationGranted>,
     pub conflicts: Vec<FileReservationConflict>,
-}
-
-fn find_path_conflicts(
-    path: &str,
-    agent_id: i64,
-    request_exclusive: bool,
-    active_reservations: &[mouchak_mail_core::model::file_reservation::FileReservation],
-) -> Vec<FileReservationConflict> {
-    active_reservations
-        .iter()
-        .filter(|res| {
-            res.agent_id.get() != agent_id
-                && (res.exclusive || request_exclusive)
-                && res.path_pattern == path
-        })
-        .map(|res| FileReservationConflict {
-            path_pattern: res.path_pattern.clone(),
-            exclusive: res.exclusive,
-            expires_ts: res.expires_ts.format("%Y-%m-%dT%H:%M:%S").to_string(),
-            conflict_type: "FILE_RESERVATION_CONFLICT".to_string(),
-            message: format!(
-                "Conflict with reservation held by agent ID {}",
-                res.agent_id.get()
-            ),
-        })
-        .collect()
+    /// Server clock, which `expires_ts` values are measured against
+    pub server_time: String,
 }
 
 pub async fn file_reservation_paths(
@@ -745,45 +995,105 @@ pub async fn file_reservation_paths(
     )
     .await?;
 
-    let active_reservations =
-        FileReservationBmc::list_active_for_project(&ctx, mm, project.id).await?;
-
-    let ttl = payload.ttl_seconds.unwrap_or(3600);
-    let now = chrono::Utc::now().naive_utc();
-    let expires_ts = now + chrono::Duration::seconds(ttl);
-
-    let mut granted = Vec::new();
-    let mut conflicts = Vec::new();
+    let expires_ts = clock::expires_in(payload.ttl_seconds.unwrap_or(3600))?;
+    let reason = payload.reason.unwrap_or_default();
 
-    for path in payload.paths {
-        conflicts.extend(find_path_conflicts(
-            &path,
-            agent.id.get(),
-            payload.exclusive,
-            &active_reservations,
-        ));
-
-        let fr_c = FileReservationForCreate {
+    let outcome = ReservationSetBmc::acquire(
+        &ctx,
+        mm,
+        ReservationSetForCreate {
             project_id: project.id,
             agent_id: agent.id,
-            path_pattern: path.clone(),
+            paths: payload.paths,
             exclusive: payload.exclusive,
-            reason: payload.reason.clone().unwrap_or_default(),
+            reason: reason.clone(),
             expires_ts,
-        };
+        },
+    )
+    .await?;
 
-        let id = FileReservationBmc::create(&ctx, mm, fr_c).await?;
+    let response = match outcome {
+        ReservationSetOutcome::Granted {
+            set_id,
+            paths,
+            reservation_ids,
+        } => FileReservationPathsResponse {
+            reservation_set_id: Some(set_id),
+            granted: paths
+                .into_iter()
+                .zip(reservation_ids)
+                .map(|(path, id)| FileReservationGranted {
+                    id,
+                    path_pattern: path,
+                    exclusive: payload.exclusive,
+                    reason: reason.clone(),
+                    expires_ts: expires_ts.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                })
+                .collect(),
+            conflicts: Vec::new(),
+            server_time: clock::server_time(),
+        },
+        ReservationSetOutcome::Conflicted { clashes } => FileReservationPathsResponse {
+            reservation_set_id: None,
+            granted: Vec::new(),
+            conflicts: clashes
+                .into_iter()
+                .map(|c| FileReservationConflict {
+                    message: format!(
+                        "{} overlaps {} held by agent '{}'",
+                        c.path, c.path_pattern, c.agent_name
+                    ),
+                    path: c.path,
+                    reservation_id: c.reservation_id,
+                    path_pattern: c.path_pattern,
+                    agent_name: c.agent_name,
+                    exclusive: c.exclusive,
+                    expires_ts: c.expires_ts.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                    conflict_type: "FILE_RESERVATION_CONFLICT".to_string(),
+                })
+                .collect(),
+            server_time: clock::server_time(),
+        },
+    };
 
-        granted.push(FileReservationGranted {
-            id,
-            path_pattern: path,
-            exclusive: payload.exclusive,
-            reason: payload.reason.clone().unwrap_or_default(),
-            expires_ts: expires_ts.format("%Y-%m-%dT%H:%M:%S").to_string(),
-        });
-    }
+    Ok(Json(response).into_response())
+}
 
-    Ok(Json(FileReservationPathsResponse { granted, conflicts }).into_response())
+// --- release_reservation_set ---
+#[derive(Deserialize)]
+pub struct ReleaseReservationSetPayload {
+    pub project_slug: String,
+    pub agent_name: String,
+    pub set_id: i64,
+}
+
+pub async fn release_reservation_set(
+    State(app_state): State<AppState>,
+    Json(payload): Json<ReleaseReservationSetPayload>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    let project = mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(
+        &ctx,
+        mm,
+        &payload.project_slug,
+    )
+    .await?;
+    let agent = mouchak_mail_core::model::agent::AgentBmc::get_by_name(
+        &ctx,
+        mm,
+        project.id,
+        &payload.agent_name,
+    )
+    .await?;
+
+    let released = ReservationSetBmc::release(&ctx, mm, payload.set_id, Some(agent.id)).await?;
+    Ok(Json(serde_json::json!({
+        "set_id": payload.set_id,
+        "released_count": released,
+    }))
+    .into_response())
 }
 
 // --- create_agent_identity ---
@@ -914,6 +1224,8 @@ pub struct WhoisPayload {
 #[derive(Serialize)]
 pub struct WhoisResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub name: String,
     pub program: String,
     pub model: String,
@@ -949,6 +1261,7 @@ pub async fn whois(
 
     Ok(Json(WhoisResponse {
         id: agent.id.get(),
+        public_id: agent.public_id,
         name: agent.name,
         program: agent.program,
         model: agent.model,
@@ -1003,7 +1316,7 @@ pub async fn list_file_reservations(
     let reservations = if payload.active_only.unwrap_or(true) {
         FileReservationBmc::list_active_for_project(&ctx, mm, project.id).await?
     } else {
-        FileReservationBmc::list_all_for_project(&ctx, mm, project.id.get()).await?
+        FileReservationBmc::list_all_for_project(&ctx, mm, project.id).await?
     };
 
     let now = chrono::Utc::now().naive_utc();
@@ -1038,6 +1351,46 @@ pub async fn list_file_reservations(
     Ok(Json(responses).into_response())
 }
 
+// --- check_paths ---
+#[derive(Deserialize)]
+pub struct CheckPathsPayload {
+    pub project_slug: String,
+    pub paths: Vec<String>,
+    /// Agent whose own reservations are ignored
+    #[serde(default)]
+    pub agent_name: Option<String>,
+}
+
+/// Returns one verdict per path against the project's active reservations,
+/// so hooks need a single request instead of matching client-side.
+pub async fn check_paths(
+    State(app_state): State<AppState>,
+    Json(payload): Json<CheckPathsPayload>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    let project = mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(
+        &ctx,
+        mm,
+        &payload.project_slug,
+    )
+    .await?;
+    let exclude_agent = match &payload.agent_name {
+        Some(name) => Some(
+            mouchak_mail_core::model::agent::AgentBmc::get_by_name(&ctx, mm, project.id, name)
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+
+    let verdicts =
+        FileReservationBmc::check_paths(&ctx, mm, project.id, exclude_agent, &payload.paths)
+            .await?;
+    Ok(Json(verdicts).into_response())
+}
+
 // --- list_all_locks ---
 // Returns all active file reservations across all projects (for web UI dashboard)
 #[derive(Serialize)]
@@ -1137,8 +1490,7 @@ pub async fn release_file_reservation(
 
     for path in &payload.paths {
         if let Some(id) =
-            FileReservationBmc::release_by_path(&ctx, mm, project.id.get(), agent.id.get(), path)
-                .await?
+            FileReservationBmc::release_by_path(&ctx, mm, project.id, agent.id, path).await?
         {
             released_ids.push(id);
         }
@@ -1156,10 +1508,20 @@ pub async fn release_file_reservation(
 pub struct GetThreadPayload {
     pub project_slug: String,
     pub thread_id: String,
+    /// Reading agent; the thread is marked read for it
+    #[serde(default)]
+    pub agent_name: Option<String>,
+    /// Only messages since the agent's last read (requires `agent_name`)
+    #[serde(default)]
+    pub unread_only: bool,
 }
 
+/// Lists the messages of a thread. Conditional on `If-None-Match` /
+/// `If-Modified-Since` (see [`crate::conditional`]); the reader's position
+/// is part of the tag, so a 304 leaves nothing to mark read.
 pub async fn get_thread(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<GetThreadPayload>,
 ) -> crate::error::Result<Response> {
     let ctx = Ctx::root_ctx();
@@ -1171,35 +1533,123 @@ pub async fn get_thread(
         &payload.project_slug,
     )
     .await?;
-    let messages = mouchak_mail_core::model::message::MessageBmc::list_by_thread(
+    let reader = match &payload.agent_name {
+        Some(name) => Some(
+            mouchak_mail_core::model::agent::AgentBmc::get_by_name(&ctx, mm, project.id, name)
+                .await?,
+        ),
+        None if payload.unread_only => {
+            return Err(crate::ServerError::BadRequest(
+                "unread_only requires agent_name".into(),
+            ));
+        }
+        None => None,
+    };
+
+    let stamp = mouchak_mail_core::model::message::MessageBmc::thread_stamp(
         &ctx,
         mm,
-        project.id.get(),
+        project.id,
         &payload.thread_id,
     )
     .await?;
+    let reader_id = reader.as_ref().map(|agent| agent.id);
+    let validators = |position: Option<i64>| {
+        Validators::new(
+            &stamp,
+            &format!(
+                "thread:{}:{}:{:?}:{:?}:{}",
+                project.id.get(),
+                payload.thread_id,
+                reader_id.map(|id| id.get()),
+                position,
+                payload.unread_only
+            ),
+        )
+    };
+    let current =
+        validators(read_position(&ctx, mm, project.id, reader_id, &payload.thread_id).await?);
+    if current.matches(&headers) {
+        return Ok(current.not_modified());
+    }
+
+    let messages = match &reader {
+        Some(agent) if payload.unread_only => {
+            ThreadReadBmc::list_unread(&ctx, mm, project.id, agent.id, &payload.thread_id).await?
+        }
+        _ => {
+            mouchak_mail_core::model::message::MessageBmc::list_by_thread(
+                &ctx,
+                mm,
+                project.id,
+                &payload.thread_id,
+            )
+            .await?
+        }
+    };
+    if let Some(agent) = &reader {
+        ThreadReadBmc::mark_all_read(&ctx, mm, project.id, agent.id, &payload.thread_id).await?;
+    }
+
+    let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+    let formats =
+        mouchak_mail_core::model::message::MessageBmc::get_body_formats(&ctx, mm, &ids).await?;
+    let mut tickets = TicketBmc::list_for_messages(&ctx, mm, &ids).await?;
+    let offloaded =
+        mouchak_mail_core::model::message::MessageBmc::get_offloaded_sizes(&ctx, mm, &ids).await?;
 
     let mut responses: Vec<MessageResponse> = Vec::with_capacity(messages.len());
     for msg in messages {
-        let recipients =
-            mouchak_mail_core::model::message::MessageBmc::get_recipients(&ctx, mm, msg.id).await?;
+        let recipients = mouchak_mail_core::model::message::MessageBmc::get_recipients(
+            &ctx,
+            mm,
+            MessageId::new(msg.id),
+        )
+        .await?;
         responses.push(MessageResponse {
             id: msg.id,
+            public_id: msg.public_id,
             project_id: msg.project_id,
             sender_id: msg.sender_id,
             sender_name: msg.sender_name,
             thread_id: msg.thread_id,
             subject: msg.subject,
             body_md: msg.body_md,
+            full_body_bytes: offloaded.get(&msg.id).copied(),
+            body_format: formats.get(&msg.id).copied().unwrap_or_default(),
+            body_rendered: None,
             importance: msg.importance,
             ack_required: msg.ack_required,
             created_ts: msg.created_ts,
             attachments: msg.attachments,
             recipients,
+            tickets: tickets.remove(&msg.id).unwrap_or_default(),
         });
     }
 
-    Ok(Json(responses).into_response())
+    // Tag what the next poll will see: the thread read up to its end
+    let validators = match reader_id {
+        Some(_) => {
+            validators(read_position(&ctx, mm, project.id, reader_id, &payload.thread_id).await?)
+        }
+        None => current,
+    };
+    Ok(validators.apply(Json(responses).into_response()))
+}
+
+/// The last message of the thread `reader` has read, if any.
+async fn read_position(
+    ctx: &Ctx,
+    mm: &mouchak_mail_core::model::ModelManager,
+    project_id: mouchak_mail_core::ProjectId,
+    reader: Option<mouchak_mail_core::AgentId>,
+    thread_id: &str,
+) -> crate::error::Result<Option<i64>> {
+    let Some(agent_id) = reader else {
+        return Ok(None);
+    };
+    let position = ThreadReadBmc::get_position(ctx, mm, project_id, agent_id, thread_id).await?;
+    Ok(position.map(|id| id.get()))
 }
 
 // --- reply_message ---
@@ -1234,25 +1684,25 @@ pub async fn reply_message(
     .await?;
 
     // Get original message to extract thread_id and original sender as recipient
-    let original_msg =
-        mouchak_mail_core::model::message::MessageBmc::get(&ctx, mm, payload.message_id).await?;
+    let original_msg = mouchak_mail_core::model::message::MessageBmc::get(
+        &ctx,
+        mm,
+        MessageId::new(payload.message_id),
+    )
+    .await?;
 
     // Reply goes to the original sender
-    let recipient_ids = vec![original_msg.sender_id];
+    let recipient_ids = vec![AgentId::new(original_msg.sender_id)];
 
     // Use existing thread_id or create reference to original
     let thread_id = original_msg.thread_id.clone();
 
-    // Create subject with Re: prefix if not already present
-    let subject = if original_msg.subject.starts_with("Re: ") {
-        original_msg.subject.clone()
-    } else {
-        format!("Re: {}", original_msg.subject)
-    };
+    // Create subject with a single canonical Re: prefix
+    let subject = mouchak_mail_core::utils::reply_subject(&original_msg.subject);
 
     let msg_c = mouchak_mail_core::model::message::MessageForCreate {
-        project_id: project.id.get(),
-        sender_id: sender.id.get(),
+        project_id: project.id,
+        sender_id: sender.id,
         recipient_ids,
         cc_ids: None,
         bcc_ids: None,
@@ -1264,12 +1714,28 @@ pub async fn reply_message(
     };
 
     let message_id = mouchak_mail_core::model::message::MessageBmc::create(&ctx, mm, msg_c).await?;
+    // Replies keep the thread's custom fields
+    mouchak_mail_core::model::message::MessageBmc::inherit_custom_fields(
+        &ctx,
+        mm,
+        MessageId::new(original_msg.id),
+        message_id,
+    )
+    .await?;
+    mouchak_mail_core::model::reply_chain::ReplyChainBmc::link(
+        &ctx,
+        mm,
+        message_id,
+        original_msg.id,
+    )
+    .await?;
 
     // Fetch the full message to return
     let message = mouchak_mail_core::model::message::MessageBmc::get(&ctx, mm, message_id).await?;
 
     Ok(Json(SendMessageResponse {
         id: message.id,
+        public_id: message.public_id,
         project_id: message.project_id,
         sender_id: message.sender_id,
         sender_name: message.sender_name,
@@ -1279,6 +1745,7 @@ pub async fn reply_message(
         importance: message.importance,
         ack_required: message.ack_required,
         created_ts: message.created_ts,
+        routing: Vec::new(),
     })
     .into_response())
 }
@@ -1290,6 +1757,15 @@ pub struct SearchMessagesPayload {
     pub query: String,
     #[serde(default = "default_search_limit")]
     pub limit: i64,
+    /// Surrounding thread messages to include before and after each hit
+    #[serde(default)]
+    pub context: usize,
+    /// Also match the content of text-like attachments
+    #[serde(default)]
+    pub include_attachments: bool,
+    /// Only match messages whose custom fields equal these values
+    #[serde(default)]
+    pub fields: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
 fn default_search_limit() -> i64 {
@@ -1299,12 +1775,44 @@ fn default_search_limit() -> i64 {
 #[derive(Serialize)]
 pub struct SearchMessageResult {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub subject: String,
     pub sender_name: String,
     pub thread_id: Option<String>,
-    pub body_md: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_md: Option<String>,
     pub importance: String,
     pub created_ts: chrono::NaiveDateTime,
+    pub field: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<String>,
+    pub snippet: Option<Snippet>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<SearchContextMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_fields: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl SearchMessageResult {
+    fn from_hit(hit: SearchHit, include_body: bool) -> Self {
+        let msg = hit.message;
+        Self {
+            id: msg.id,
+            public_id: msg.public_id,
+            subject: msg.subject,
+            sender_name: msg.sender_name,
+            thread_id: msg.thread_id,
+            body_md: include_body.then_some(msg.body_md),
+            importance: msg.importance,
+            created_ts: msg.created_ts,
+            field: hit.field,
+            attachment: hit.attachment,
+            snippet: hit.snippet,
+            context: hit.context,
+            custom_fields: hit.custom_fields,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -1327,33 +1835,100 @@ pub async fn search_messages(
         &payload.project_slug,
     )
     .await?;
+    let filters = match &payload.fields {
+        Some(fields) => CustomFieldBmc::filters(&ctx, mm, project.id, fields).await?,
+        None => Vec::new(),
+    };
+
+    let hits = mouchak_mail_core::model::message::MessageBmc::search_with_snippets(
+        &ctx,
+        mm,
+        project.id,
+        &payload.query,
+        payload.limit,
+        payload.context,
+        payload.include_attachments,
+        &filters,
+    )
+    .await?;
+
+    let results: Vec<SearchMessageResult> = hits
+        .into_iter()
+        .map(|hit| SearchMessageResult::from_hit(hit, true))
+        .collect();
+
+    let count = results.len();
+
+    Ok(Json(SearchMessagesResponse {
+        query: payload.query,
+        results,
+        count,
+    })
+    .into_response())
+}
+
+// --- search (GET) ---
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    #[serde(alias = "project_slug")]
+    pub project: String,
+    #[serde(alias = "query")]
+    pub q: String,
+    #[serde(default = "default_search_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub context: usize,
+    #[serde(default)]
+    pub include_attachments: bool,
+    /// Return full bodies alongside snippets (default: false)
+    #[serde(default)]
+    pub include_bodies: bool,
+    /// Custom field filters as `name:value` pairs, comma-separated
+    #[serde(default)]
+    pub fields: Option<String>,
+}
+
+/// Snippet-first search: returns highlighted excerpts without full bodies
+/// unless `include_bodies=true`.
+pub async fn search(
+    State(app_state): State<AppState>,
+    Query(params): Query<SearchQuery>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    let project =
+        mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(&ctx, mm, &params.project)
+            .await?;
+    let filters = match params.fields.as_deref() {
+        Some(fields) => {
+            let pairs = parse_field_pairs(fields)?;
+            CustomFieldBmc::filters(&ctx, mm, project.id, &pairs).await?
+        }
+        None => Vec::new(),
+    };
 
-    let messages = mouchak_mail_core::model::message::MessageBmc::search(
+    let hits = mouchak_mail_core::model::message::MessageBmc::search_with_snippets(
         &ctx,
         mm,
-        project.id.get(),
-        &payload.query,
-        payload.limit,
+        project.id,
+        &params.q,
+        params.limit,
+        params.context,
+        params.include_attachments,
+        &filters,
     )
     .await?;
 
-    let results: Vec<SearchMessageResult> = messages
+    let results: Vec<SearchMessageResult> = hits
         .into_iter()
-        .map(|msg| SearchMessageResult {
-            id: msg.id,
-            subject: msg.subject,
-            sender_name: msg.sender_name,
-            thread_id: msg.thread_id,
-            body_md: msg.body_md,
-            importance: msg.importance,
-            created_ts: msg.created_ts,
-        })
+        .map(|hit| SearchMessageResult::from_hit(hit, params.include_bodies))
         .collect();
 
     let count = results.len();
 
     Ok(Json(SearchMessagesResponse {
-        query: payload.query,
+        query: params.q,
         results,
         count,
     })
@@ -1400,6 +1975,7 @@ pub struct RenewFileReservationResponse {
     pub renewed: bool,
     pub reservation_id: i64,
     pub new_expires_ts: String,
+    pub server_time: String,
 }
 
 pub async fn renew_file_reservation(
@@ -1409,8 +1985,7 @@ pub async fn renew_file_reservation(
     let ctx = Ctx::root_ctx();
     let mm = &app_state.mm;
 
-    let ttl = payload.ttl_seconds.unwrap_or(3600);
-    let new_expires = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(ttl);
+    let new_expires = clock::expires_in(payload.ttl_seconds.unwrap_or(3600))?;
 
     FileReservationBmc::renew(&ctx, mm, payload.reservation_id, new_expires).await?;
 
@@ -1418,6 +1993,7 @@ pub async fn renew_file_reservation(
         renewed: true,
         reservation_id: payload.reservation_id,
         new_expires_ts: new_expires.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        server_time: clock::server_time(),
     })
     .into_response())
 }
@@ -1431,11 +2007,23 @@ pub struct GetProjectInfoPayload {
 #[derive(Serialize)]
 pub struct ProjectInfoResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub slug: String,
     pub human_key: String,
     pub created_at: chrono::NaiveDateTime,
     pub agent_count: usize,
     pub message_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_window: Option<FocusWindowInfo>,
+    pub settings: ProjectSettings,
+}
+
+#[derive(Serialize)]
+pub struct FocusWindowInfo {
+    #[serde(flatten)]
+    pub window: mouchak_mail_core::model::focus_window::FocusWindow,
+    pub deferred_count: i64,
 }
 
 pub async fn get_project_info(
@@ -1462,13 +2050,209 @@ pub async fn get_project_info(
     let message_count =
         mouchak_mail_core::model::project::ProjectBmc::count_messages(&ctx, mm, project.id).await?;
 
+    // Active focus window (quiet period), if any
+    let focus_window = match FocusWindowBmc::get_active(&ctx, mm, project.id).await? {
+        Some(window) => {
+            let deferred_count = FocusWindowBmc::count_deferred(&ctx, mm, window.id).await?;
+            Some(FocusWindowInfo {
+                window,
+                deferred_count,
+            })
+        }
+        None => None,
+    };
+
+    let settings = ProjectSettingsBmc::get(&ctx, mm, project.id).await?;
+
     Ok(Json(ProjectInfoResponse {
         id: project.id.get(),
+        public_id: project.public_id,
         slug: project.slug,
         human_key: project.human_key,
         created_at: project.created_at,
         agent_count,
         message_count: message_count as usize,
+        focus_window,
+        settings,
+    })
+    .into_response())
+}
+
+// --- set_project_settings ---
+#[derive(Deserialize)]
+pub struct SetProjectSettingsPayload {
+    pub project_slug: String,
+    #[serde(flatten)]
+    pub settings: ProjectSettingsForUpdate,
+}
+
+pub async fn set_project_settings(
+    State(app_state): State<AppState>,
+    Json(payload): Json<SetProjectSettingsPayload>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    let project = mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(
+        &ctx,
+        mm,
+        &payload.project_slug,
+    )
+    .await?;
+    let settings = ProjectSettingsBmc::update(&ctx, mm, project.id, payload.settings).await?;
+
+    Ok(Json(settings).into_response())
+}
+
+// --- set_message_template ---
+#[derive(Deserialize)]
+pub struct SetMessageTemplatePayload {
+    pub locale: String,
+    pub key: String,
+    /// Empty restores the built-in template
+    pub template: String,
+}
+
+#[derive(Serialize)]
+pub struct SetMessageTemplateResponse {
+    pub locale: String,
+    pub key: String,
+    /// `false` when an empty template found no custom one to remove
+    pub changed: bool,
+}
+
+pub async fn set_message_template(
+    State(app_state): State<AppState>,
+    Json(payload): Json<SetMessageTemplatePayload>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    let changed = if payload.template.trim().is_empty() {
+        MessageCatalogBmc::remove_template(&ctx, mm, &payload.locale, &payload.key).await?
+    } else {
+        MessageCatalogBmc::set_template(&ctx, mm, &payload.locale, &payload.key, &payload.template)
+            .await?;
+        true
+    };
+
+    Ok(Json(SetMessageTemplateResponse {
+        locale: payload.locale,
+        key: payload.key,
+        changed,
+    })
+    .into_response())
+}
+
+// --- list_message_templates ---
+#[derive(Deserialize)]
+pub struct ListMessageTemplatesPayload {
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Project whose locale to list when `locale` is not given
+    #[serde(default)]
+    pub project_slug: Option<String>,
+}
+
+pub async fn list_message_templates(
+    State(app_state): State<AppState>,
+    Json(payload): Json<ListMessageTemplatesPayload>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    let locale = match (payload.locale, payload.project_slug) {
+        (Some(locale), _) => locale,
+        (None, Some(slug)) => {
+            let project =
+                mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(&ctx, mm, &slug)
+                    .await?;
+            MessageCatalogBmc::project_locale(&ctx, mm, project.id).await?
+        }
+        (None, None) => DEFAULT_LOCALE.to_string(),
+    };
+    let templates = MessageCatalogBmc::list_templates(&ctx, mm, &locale).await?;
+
+    Ok(Json(templates).into_response())
+}
+
+// --- set_focus_window ---
+#[derive(Deserialize)]
+pub struct SetFocusWindowPayload {
+    pub project_slug: String,
+    /// Window length in minutes starting now; 0 ends the active window
+    #[serde(default)]
+    pub duration_minutes: Option<i64>,
+    #[serde(default)]
+    pub allowed_importance: Option<Vec<String>>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SetFocusWindowResponse {
+    pub project_slug: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_window: Option<mouchak_mail_core::model::focus_window::FocusWindow>,
+    pub delivered: usize,
+}
+
+pub async fn set_focus_window(
+    State(app_state): State<AppState>,
+    Json(payload): Json<SetFocusWindowPayload>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    let project = mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(
+        &ctx,
+        mm,
+        &payload.project_slug,
+    )
+    .await?;
+
+    let duration_minutes = payload.duration_minutes.unwrap_or(60);
+    if duration_minutes < 0 {
+        return Err(crate::ServerError::BadRequest(
+            "duration_minutes must not be negative".into(),
+        ));
+    }
+
+    if duration_minutes == 0 {
+        let mut delivered = 0;
+        let mut ended = None;
+        if let Some(window) = FocusWindowBmc::get_active(&ctx, mm, project.id).await? {
+            delivered = FocusWindowBmc::end(&ctx, mm, window.id).await?;
+            ended = Some(FocusWindowBmc::get(&ctx, mm, window.id).await?);
+        }
+        return Ok(Json(SetFocusWindowResponse {
+            project_slug: project.slug,
+            focus_window: ended,
+            delivered,
+        })
+        .into_response());
+    }
+
+    let starts_ts = Utc::now().naive_utc();
+    let fw_c = FocusWindowForCreate {
+        project_id: project.id,
+        starts_ts,
+        ends_ts: starts_ts + chrono::Duration::minutes(duration_minutes),
+        allowed_importance: payload.allowed_importance.unwrap_or_else(|| {
+            DEFAULT_ALLOWED_IMPORTANCE
+                .iter()
+                .map(|s| s.to_string())
+                .collect()
+        }),
+        reason: payload.reason.unwrap_or_default(),
+    };
+    let id = FocusWindowBmc::create(&ctx, mm, fw_c).await?;
+    let window = FocusWindowBmc::get(&ctx, mm, id).await?;
+
+    Ok(Json(SetFocusWindowResponse {
+        project_slug: project.slug,
+        focus_window: Some(window),
+        delivered: 0,
     })
     .into_response())
 }
@@ -1506,9 +2290,7 @@ pub async fn get_quota_status(
 
     let attachments_usage =
         mouchak_mail_core::model::attachment::AttachmentBmc::get_total_project_usage(
-            &ctx,
-            mm,
-            project.id.get(),
+            &ctx, mm, project.id,
         )
         .await?;
 
@@ -1518,12 +2300,9 @@ pub async fn get_quota_status(
             &ctx, mm, project.id, agent_name,
         )
         .await?;
-        let count = mouchak_mail_core::model::message::MessageBmc::get_inbox_count(
-            &ctx,
-            mm,
-            agent.id.get(),
-        )
-        .await?;
+        let count =
+            mouchak_mail_core::model::message::MessageBmc::get_inbox_count(&ctx, mm, agent.id)
+                .await?;
         agent_usage = Some(count);
     }
 
@@ -1543,6 +2322,8 @@ pub async fn get_quota_status(
 #[derive(Serialize)]
 pub struct AgentProfileResponse {
     pub id: i64,
+    /// Stable public ULID
+    pub public_id: String,
     pub name: String,
     pub program: String,
     pub model: String,
@@ -1595,6 +2376,7 @@ pub async fn get_agent_profile(
 
     Ok(Json(AgentProfileResponse {
         id: agent.id.get(),
+        public_id: agent.public_id,
         name: agent.name,
         program: agent.program,
         model: agent.model,
@@ -1650,8 +2432,8 @@ pub async fn mark_message_read(
     mouchak_mail_core::model::message::MessageBmc::mark_read(
         &ctx,
         mm,
-        payload.message_id,
-        agent.id.get(),
+        MessageId::new(payload.message_id),
+        agent.id,
     )
     .await?;
 
@@ -1700,8 +2482,8 @@ pub async fn acknowledge_message(
     mouchak_mail_core::model::message::MessageBmc::acknowledge(
         &ctx,
         mm,
-        payload.message_id,
-        agent.id.get(),
+        MessageId::new(payload.message_id),
+        agent.id,
     )
     .await?;
 
@@ -1718,6 +2500,15 @@ pub struct ListThreadsPayload {
     pub project_slug: String,
     #[serde(default = "default_threads_limit")]
     pub limit: i64,
+    /// Agent whose unread count to include per thread
+    #[serde(default)]
+    pub agent_name: Option<String>,
+    /// Also list threads archived for inactivity
+    #[serde(default)]
+    pub include_archived: bool,
+    /// `x-next-cursor` of the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 fn default_threads_limit() -> i64 {
@@ -1730,6 +2521,12 @@ pub struct ThreadSummaryResponse {
     pub subject: String,
     pub message_count: usize,
     pub last_message_ts: chrono::NaiveDateTime,
+    pub archived: bool,
+    /// Set when the request names an agent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unread_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_read_message_id: Option<i64>,
 }
 
 pub async fn list_threads(
@@ -1745,25 +2542,72 @@ pub async fn list_threads(
         &payload.project_slug,
     )
     .await?;
-    let threads = mouchak_mail_core::model::message::MessageBmc::list_threads(
-        &ctx,
-        mm,
-        project.id.get(),
-        payload.limit,
-    )
-    .await?;
 
-    let responses: Vec<ThreadSummaryResponse> = threads
-        .into_iter()
-        .map(|t| ThreadSummaryResponse {
-            thread_id: t.thread_id,
-            subject: t.subject,
-            message_count: t.message_count,
-            last_message_ts: t.last_message_ts,
-        })
-        .collect();
+    let cursor = payload.cursor.as_deref();
+    let page = match &payload.agent_name {
+        Some(name) => {
+            let agent =
+                mouchak_mail_core::model::agent::AgentBmc::get_by_name(&ctx, mm, project.id, name)
+                    .await?;
+            let page = ThreadReadBmc::list_threads_page(
+                &ctx,
+                mm,
+                project.id,
+                agent.id,
+                payload.limit,
+                cursor,
+                payload.include_archived,
+            )
+            .await?;
+            ListPage {
+                items: page
+                    .items
+                    .into_iter()
+                    .map(|t| ThreadSummaryResponse {
+                        thread_id: t.thread.thread_id,
+                        subject: t.thread.subject,
+                        message_count: t.thread.message_count,
+                        last_message_ts: t.thread.last_message_ts,
+                        archived: t.thread.archived,
+                        unread_count: Some(t.unread_count),
+                        last_read_message_id: t.last_read_message_id,
+                    })
+                    .collect(),
+                total: page.total,
+                next_cursor: page.next_cursor,
+            }
+        }
+        None => {
+            let page = mouchak_mail_core::model::message::MessageBmc::list_threads_page(
+                &ctx,
+                mm,
+                project.id,
+                payload.limit,
+                cursor,
+                payload.include_archived,
+            )
+            .await?;
+            ListPage {
+                items: page
+                    .items
+                    .into_iter()
+                    .map(|t| ThreadSummaryResponse {
+                        thread_id: t.thread_id,
+                        subject: t.subject,
+                        message_count: t.message_count,
+                        last_message_ts: t.last_message_ts,
+                        archived: t.archived,
+                        unread_count: None,
+                        last_read_message_id: None,
+                    })
+                    .collect(),
+                total: page.total,
+                next_cursor: page.next_cursor,
+            }
+        }
+    };
 
-    Ok(Json(responses).into_response())
+    Ok(paged_json(page.items, page.total, page.next_cursor))
 }
 
 // --- update_agent_profile ---
@@ -1967,10 +2811,7 @@ pub async fn list_contacts(
     .await?;
 
     let links = mouchak_mail_core::model::agent_link::AgentLinkBmc::list_contacts(
-        &ctx,
-        mm,
-        project.id.get(),
-        agent.id.get(),
+        &ctx, mm, project.id, agent.id,
     )
     .await?;
 
@@ -2067,6 +2908,7 @@ pub struct AcquireBuildSlotResponse {
     pub slot_id: i64,
     pub slot_name: String,
     pub expires_ts: String,
+    pub server_time: String,
 }
 
 pub async fn acquire_build_slot(
@@ -2099,12 +2941,13 @@ pub async fn acquire_build_slot(
 
     let slot_id =
         mouchak_mail_core::model::build_slot::BuildSlotBmc::acquire(&ctx, mm, slot_c).await?;
-    let expires = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(payload.ttl_seconds);
+    let expires = clock::expires_in(payload.ttl_seconds)?;
 
     Ok(Json(AcquireBuildSlotResponse {
         slot_id,
         slot_name: payload.slot_name,
         expires_ts: expires.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        server_time: clock::server_time(),
     })
     .into_response())
 }
@@ -2122,6 +2965,7 @@ pub struct RenewBuildSlotResponse {
     pub renewed: bool,
     pub slot_id: i64,
     pub new_expires_ts: String,
+    pub server_time: String,
 }
 
 pub async fn renew_build_slot(
@@ -2143,6 +2987,7 @@ pub async fn renew_build_slot(
         renewed: true,
         slot_id: payload.slot_id,
         new_expires_ts: new_expires.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        server_time: clock::server_time(),
     })
     .into_response())
 }
@@ -2232,6 +3077,127 @@ pub async fn send_overseer_message(
     .into_response())
 }
 
+// --- list_overseer_messages ---
+#[derive(Deserialize)]
+pub struct ListOverseerMessagesQuery {
+    #[serde(default)]
+    pub include_read: bool,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct OverseerMessageResponse {
+    pub id: i64,
+    pub sender_id: i64,
+    pub sender_name: String,
+    pub subject: String,
+    pub body_md: String,
+    pub importance: String,
+    pub created_ts: chrono::NaiveDateTime,
+    pub read_ts: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct ListOverseerMessagesResponse {
+    pub project_slug: String,
+    pub unread_count: i64,
+    pub messages: Vec<OverseerMessageResponse>,
+}
+
+pub async fn list_overseer_messages(
+    State(app_state): State<AppState>,
+    Path(project_slug): Path<String>,
+    Query(params): Query<ListOverseerMessagesQuery>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    let project =
+        mouchak_mail_core::model::project::ProjectBmc::get_by_identifier(&ctx, mm, &project_slug)
+            .await?;
+
+    let messages =
+        mouchak_mail_core::model::overseer_message::OverseerMessageBmc::list_for_project(
+            &ctx,
+            mm,
+            project.id,
+            params.include_read,
+            params.limit.unwrap_or(100),
+        )
+        .await?;
+
+    // Resolve sender names once per project
+    let agents =
+        mouchak_mail_core::model::agent::AgentBmc::list_all_for_project(&ctx, mm, project.id)
+            .await?;
+    let names: std::collections::HashMap<i64, String> =
+        agents.into_iter().map(|a| (a.id.get(), a.name)).collect();
+
+    // Counted apart from the page, which `limit` may cut short
+    let unread_count =
+        mouchak_mail_core::model::overseer_message::OverseerMessageBmc::count_unread(
+            &ctx, mm, project.id,
+        )
+        .await?;
+    let messages = messages
+        .into_iter()
+        .map(|m| OverseerMessageResponse {
+            id: m.id,
+            sender_id: m.sender_id,
+            sender_name: names
+                .get(&m.sender_id)
+                .cloned()
+                .unwrap_or_else(|| format!("agent#{}", m.sender_id)),
+            subject: m.subject,
+            body_md: m.body_md,
+            importance: m.importance,
+            created_ts: m.created_ts,
+            read_ts: m.read_ts,
+        })
+        .collect();
+
+    Ok(Json(ListOverseerMessagesResponse {
+        project_slug: project.slug,
+        unread_count,
+        messages,
+    })
+    .into_response())
+}
+
+// --- mark_overseer_message_read ---
+#[derive(Deserialize)]
+pub struct MarkOverseerMessageReadPayload {
+    pub message_id: i64,
+}
+
+#[derive(Serialize)]
+pub struct MarkOverseerMessageReadResponse {
+    pub marked: bool,
+    pub message_id: i64,
+}
+
+pub async fn mark_overseer_message_read(
+    State(app_state): State<AppState>,
+    Json(payload): Json<MarkOverseerMessageReadPayload>,
+) -> crate::error::Result<Response> {
+    let ctx = Ctx::root_ctx();
+    let mm = &app_state.mm;
+
+    mouchak_mail_core::model::overseer_message::OverseerMessageBmc::mark_read(
+        &ctx,
+        mm,
+        payload.message_id,
+    )
+    .await?;
+
+    Ok(Json(MarkOverseerMessageReadResponse {
+        marked: true,
+        message_id: payload.message_id,
+    })
+    .into_response())
+}
+
 // --- list_macros ---
 #[derive(Deserialize)]
 pub struct ListMacrosPayload {
@@ -2260,7 +3226,7 @@ pub async fn list_macros(
     )
     .await?;
     let macros =
-        mouchak_mail_core::model::macro_def::MacroDefBmc::list(&ctx, mm, project.id.get()).await?;
+        mouchak_mail_core::model::macro_def::MacroDefBmc::list(&ctx, mm, project.id).await?;
 
     let responses: Vec<MacroResponse> = macros
         .into_iter()
@@ -2350,7 +3316,7 @@ pub async fn unregister_macro(
     let deleted = mouchak_mail_core::model::macro_def::MacroDefBmc::delete(
         &ctx,
         mm,
-        project.id.get(),
+        project.id,
         &payload.name,
     )
     .await?;
@@ -2394,7 +3360,7 @@ pub async fn invoke_macro(
     let macro_def = mouchak_mail_core::model::macro_def::MacroDefBmc::get_by_name(
         &ctx,
         mm,
-        project.id.get(),
+        project.id,
         &payload.name,
     )
     .await?;
@@ -2556,11 +3522,7 @@ pub async fn macro_file_reservation_cycle(
         for pattern in &payload.patterns {
             if let Some(released_id) =
                 mouchak_mail_core::model::file_reservation::FileReservationBmc::release_by_path(
-                    &ctx,
-                    mm,
-                    project.id.get(),
-                    agent.id.get(),
-                    pattern,
+                    &ctx, mm, project.id, agent.id, pattern,
                 )
                 .await?
             {
@@ -2733,7 +3695,7 @@ pub async fn summarize_thread(
     let all_messages = mouchak_mail_core::model::message::MessageBmc::list_by_thread(
         &ctx,
         mm,
-        project.id.get(),
+        project.id,
         &payload.thread_id,
     )
     .await?;
@@ -2821,8 +3783,9 @@ pub async fn summarize_threads(
     let threads = mouchak_mail_core::model::message::MessageBmc::list_threads(
         &ctx,
         mm,
-        project.id.get(),
+        project.id,
         payload.limit,
+        false,
     )
     .await?;
 
@@ -2983,7 +3946,13 @@ pub async fn list_tool_metrics(
 
     let ctx = Ctx::root_ctx();
     let limit = params.limit.unwrap_or(50);
-    let metrics = ToolMetricBmc::list_recent(&ctx, &state.mm, params.project_id, limit).await?;
+    let metrics = ToolMetricBmc::list_recent(
+        &ctx,
+        &state.mm,
+        params.project_id.map(ProjectId::new),
+        limit,
+    )
+    .await?;
 
     Ok(Json(metrics).into_response())
 }
@@ -2995,7 +3964,8 @@ pub async fn get_tool_stats(
     use mouchak_mail_core::model::tool_metric::ToolMetricBmc;
 
     let ctx = Ctx::root_ctx();
-    let stats = ToolMetricBmc::get_stats(&ctx, &state.mm, params.project_id).await?;
+    let stats =
+        ToolMetricBmc::get_stats(&ctx, &state.mm, params.project_id.map(ProjectId::new)).await?;
 
     Ok(Json(stats).into_response())
 }
@@ -3016,7 +3986,8 @@ pub async fn list_activity(
 
     let ctx = Ctx::root_ctx();
     let limit = params.limit.unwrap_or(50);
-    let items = ActivityBmc::list_recent(&ctx, &state.mm, params.project_id, limit).await?;
+    let items =
+        ActivityBmc::list_recent(&ctx, &state.mm, ProjectId::new(params.project_id), limit).await?;
 
     Ok(Json(items).into_response())
 }
@@ -3086,9 +4057,7 @@ pub async fn list_project_siblings(
     .await?;
     let siblings =
         mouchak_mail_core::model::project_sibling_suggestion::ProjectSiblingSuggestionBmc::list(
-            &ctx,
-            mm,
-            project.id.get(),
+            &ctx, mm, project.id,
         )
         .await?;
 
@@ -3218,8 +4187,8 @@ pub async fn list_pending_reviews(
     let rows = mouchak_mail_core::model::message::MessageBmc::list_pending_reviews(
         &ctx,
         mm,
-        project_id.map(|p| p.get()),
-        sender_id.map(|s| s.get()),
+        project_id,
+        sender_id,
         params.limit,
     )
     .await?;
@@ -0,0 +1,171 @@
+//! Webhook delivery.
+//!
+//! Turns new event log records into deliveries with
+//! [`WebhookBmc::enqueue`] and POSTs the due ones to their webhook URLs,
+//! signed with the webhook's secret. A `2xx` answer delivers the payload;
+//! anything else, including a timeout, is recorded as a failed attempt and
+//! retried later (see [`WebhookBmc::record_attempt`]).
+//!
+//! Unless `webhooks.allow_private_urls` is set, a delivery whose host is, or
+//! resolves to, a loopback, private or `HTTP_INTERNAL_NETWORKS` address is
+//! refused, so a name pointed at an internal service after its webhook was
+//! created cannot reach it. Redirects are not followed, as their target
+//! would skip that check.
+
+use mouchak_mail_common::config::WebhooksConfig;
+use mouchak_mail_common::net::{self, IpNet};
+use mouchak_mail_core::ctx::Ctx;
+use mouchak_mail_core::model::ModelManager;
+use mouchak_mail_core::model::webhook::{
+    DELIVERY_HEADER, DeliveryAttempt, DueDelivery, EVENT_HEADER, SIGNATURE_HEADER,
+    STATUS_DELIVERED, WebhookBmc, sign,
+};
+use reqwest::Client;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Maximum deliveries attempted per scan.
+const DELIVERIES_PER_SCAN: i64 = 100;
+
+/// Longest response error text kept in the delivery log.
+const MAX_ERROR_LEN: usize = 500;
+
+/// Enqueues new events and attempts the due deliveries.
+///
+/// # Returns
+/// Number of deliveries accepted by their receivers.
+pub async fn deliver_pending(
+    mm: &ModelManager,
+    config: &WebhooksConfig,
+) -> Result<usize, crate::ServerError> {
+    let ctx = Ctx::root_ctx();
+    WebhookBmc::enqueue(&ctx, mm).await?;
+    let due = WebhookBmc::due(&ctx, mm, DELIVERIES_PER_SCAN).await?;
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let internal_networks = (!config.allow_private_urls)
+        .then(|| Arc::new(net::networks_from_env(net::INTERNAL_NETWORKS_ENV)));
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(config.timeout_seconds))
+        .redirect(reqwest::redirect::Policy::none());
+    if let Some(internal_networks) = &internal_networks {
+        builder = builder.dns_resolver(Arc::new(PublicResolver {
+            internal_networks: internal_networks.clone(),
+        }));
+    }
+    let client = builder
+        .build()
+        .map_err(|e| crate::ServerError::Internal(format!("Webhook client: {}", e)))?;
+
+    let mut delivered = 0;
+    for delivery in &due {
+        let attempt = send(
+            &client,
+            delivery,
+            internal_networks.as_deref().map(Vec::as_slice),
+        )
+        .await;
+        if let Some(error) = &attempt.error {
+            tracing::warn!(
+                webhook = delivery.webhook_id,
+                delivery = delivery.id,
+                "Webhook delivery failed: {}",
+                error
+            );
+        }
+        let status = WebhookBmc::record_attempt(&ctx, mm, delivery.id, &attempt, config).await?;
+        if status == STATUS_DELIVERED {
+            delivered += 1;
+        }
+    }
+    Ok(delivered)
+}
+
+/// Resolves webhook hosts, failing for names with a loopback, private or
+/// internal address.
+struct PublicResolver {
+    internal_networks: Arc<Vec<IpNet>>,
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let internal_networks = self.internal_networks.clone();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs
+                .iter()
+                .find(|addr| net::check_public_ip(addr.ip(), &internal_networks).is_err())
+            {
+                return Err(format!(
+                    "{} resolves to {}, a private or internal address",
+                    name.as_str(),
+                    addr.ip()
+                )
+                .into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// POSTs `delivery`; its host is checked against `internal_networks`
+/// unless that is `None`.
+async fn send(
+    client: &Client,
+    delivery: &DueDelivery,
+    internal_networks: Option<&[IpNet]>,
+) -> DeliveryAttempt {
+    // Addresses written in the URL are not resolved
+    if let Some(internal_networks) = internal_networks {
+        let host = net::url_host(&delivery.url).unwrap_or_default();
+        if let Err(reason) = net::check_public_host(&host, internal_networks) {
+            return DeliveryAttempt {
+                response_status: None,
+                error: Some(format!("Refused {}: {}", delivery.url, reason)),
+            };
+        }
+    }
+    let result = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(reqwest::header::USER_AGENT, "mouchak-mail")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(
+            SIGNATURE_HEADER,
+            sign(&delivery.secret, delivery.body.as_bytes()),
+        )
+        .body(delivery.body.clone())
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => DeliveryAttempt {
+            response_status: Some(i64::from(response.status().as_u16())),
+            error: None,
+        },
+        Ok(response) => {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            let mut error = format!("HTTP {}", status.as_u16());
+            if !text.trim().is_empty() {
+                error.push_str(": ");
+                error.extend(text.trim().chars().take(MAX_ERROR_LEN));
+            }
+            DeliveryAttempt {
+                response_status: Some(i64::from(status.as_u16())),
+                error: Some(error),
+            }
+        }
+        Err(e) => DeliveryAttempt {
+            response_status: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
@@ -8,9 +8,12 @@
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::model::ModelManager;
 use mouchak_mail_mcp::tools::MouchakMailService;
 use serde_json::json;
+use std::path::Path;
 use std::sync::Arc;
+use tempfile::TempDir;
 use tower::ServiceExt;
 
 use rmcp::transport::streamable_http_server::{
@@ -19,17 +22,19 @@ use rmcp::transport::streamable_http_server::{
 };
 
 /// Create MCP service with MouchakMailService
-fn create_test_mcp_service() -> StreamableHttpService<MouchakMailService> {
+fn create_test_mcp_service() -> (StreamableHttpService<MouchakMailService>, TempDir) {
     let session_manager = Arc::new(LocalSessionManager::default());
     let config = StreamableHttpServerConfig::default();
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_path_buf();
 
     // Use std::thread::scope to ensure the thread is joined (UBS: spawn without join fix)
-    let service_factory = || -> Result<MouchakMailService, std::io::Error> {
+    let service_factory = move || -> Result<MouchakMailService, std::io::Error> {
         let result = std::thread::scope(|scope| {
             scope
                 .spawn(|| {
                     let rt = tokio::runtime::Runtime::new().unwrap();
-                    rt.block_on(async { MouchakMailService::new().await })
+                    rt.block_on(test_service(&root))
                 })
                 .join()
                 .expect("MouchakMailService thread panicked")
@@ -38,7 +43,28 @@ fn create_test_mcp_service() -> StreamableHttpService<MouchakMailService> {
         result.map_err(|e| std::io::Error::other(e.to_string()))
     };
 
-    StreamableHttpService::new(service_factory, session_manager, config)
+    (
+        StreamableHttpService::new(service_factory, session_manager, config),
+        temp_dir,
+    )
+}
+
+/// MouchakMailService over a database and archive in `root`
+async fn test_service(root: &Path) -> mouchak_mail_core::Result<MouchakMailService> {
+    let archive_root = root.join("archive");
+    std::fs::create_dir_all(&archive_root)?;
+    let db = libsql::Builder::new_local(root.join("mouchak_mail.db"))
+        .build()
+        .await?;
+    let conn = db.connect()?;
+    mouchak_mail_core::store::upgrade::migrate(&conn).await?;
+    let config = AppConfig::default();
+    let worktrees_enabled = config.mcp.worktrees_active();
+    let mm = ModelManager::new_for_test(conn, archive_root, Arc::new(config));
+    Ok(MouchakMailService::new_with_mm(
+        Arc::new(mm),
+        worktrees_enabled,
+    ))
 }
 
 // ============================================================================
@@ -47,7 +73,7 @@ fn create_test_mcp_service() -> StreamableHttpService<MouchakMailService> {
 
 #[tokio::test]
 async fn test_sse_get_endpoint_returns_event_stream() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // GET request for SSE streaming should work
     let request = Request::builder()
@@ -74,7 +100,7 @@ async fn test_sse_get_endpoint_returns_event_stream() {
 
 #[tokio::test]
 async fn test_http_delete_method_rejected() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // DELETE should not be supported for MCP
     let request = Request::builder()
@@ -97,7 +123,7 @@ async fn test_http_delete_method_rejected() {
 
 #[tokio::test]
 async fn test_http_content_type_required_for_post() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // POST without Content-Type
     let request = Request::builder()
@@ -129,7 +155,7 @@ async fn test_http_content_type_required_for_post() {
 
 #[tokio::test]
 async fn test_session_id_header_accepted() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // First request to establish session
     let init_request = Request::builder()
@@ -189,7 +215,7 @@ async fn test_session_id_header_accepted() {
 
 #[tokio::test]
 async fn test_json_rpc_batch_request_handling() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // JSON-RPC batch request (array of requests)
     let batch_body = json!([
@@ -257,6 +283,8 @@ async fn test_jwt_without_kid_in_header() {
         jwt_audience: None,
         jwt_issuer: None,
         allow_localhost: false,
+        trusted_proxies: Default::default(),
+        internal_networks: Vec::new(),
     };
     let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -267,6 +295,8 @@ async fn test_jwt_without_kid_in_header() {
         auth_config,
         jwks_client,
         ratelimit_config: mouchak_mail_server::ratelimit::RateLimitConfig::new(),
+        oidc: None,
+        sessions: Default::default(),
     };
 
     let app = Router::new()
@@ -384,6 +414,8 @@ async fn test_jwt_with_wrong_issuer() {
         jwt_audience: None,
         jwt_issuer: Some("https://expected-issuer.example.com".to_string()), // Expecting different issuer
         allow_localhost: false,
+        trusted_proxies: Default::default(),
+        internal_networks: Vec::new(),
     };
     let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -394,6 +426,8 @@ async fn test_jwt_with_wrong_issuer() {
         auth_config,
         jwks_client,
         ratelimit_config: mouchak_mail_server::ratelimit::RateLimitConfig::new(),
+        oidc: None,
+        sessions: Default::default(),
     };
 
     let app = Router::new()
@@ -448,6 +482,8 @@ async fn test_jwt_empty_authorization_header() {
         jwt_audience: None,
         jwt_issuer: None,
         allow_localhost: false,
+        trusted_proxies: Default::default(),
+        internal_networks: Vec::new(),
     };
 
     let app_state = AppState {
@@ -457,6 +493,8 @@ async fn test_jwt_empty_authorization_header() {
         auth_config,
         jwks_client: None,
         ratelimit_config: mouchak_mail_server::ratelimit::RateLimitConfig::new(),
+        oidc: None,
+        sessions: Default::default(),
     };
 
     let app = Router::new()
@@ -524,6 +562,8 @@ async fn test_jwt_wrong_algorithm() {
         jwt_audience: None,
         jwt_issuer: None,
         allow_localhost: false,
+        trusted_proxies: Default::default(),
+        internal_networks: Vec::new(),
     };
     let jwks_client = Some(JwksClient::new(jwks_url));
 
@@ -534,6 +574,8 @@ async fn test_jwt_wrong_algorithm() {
         auth_config,
         jwks_client,
         ratelimit_config: mouchak_mail_server::ratelimit::RateLimitConfig::new(),
+        oidc: None,
+        sessions: Default::default(),
     };
 
     let app = Router::new()
@@ -823,3 +865,194 @@ async fn test_rps_for_category_returns_correct_values() {
     assert_eq!(limits.rps_for_category(ToolCategory::Read), 100);
     assert_eq!(limits.rps_for_category(ToolCategory::Default), 50);
 }
+
+/// Starts `listener::serve` on a local port with a handler echoing the
+/// client address; completing the returned sender shuts it down.
+async fn start_listener(
+    config: mouchak_mail_common::config::HttpConfig,
+) -> (
+    std::net::SocketAddr,
+    tokio::sync::oneshot::Sender<()>,
+    tokio::task::JoinHandle<std::io::Result<()>>,
+) {
+    use axum::{Router, extract::ConnectInfo, routing::get};
+    use std::net::SocketAddr;
+
+    async fn handler(ConnectInfo(addr): ConnectInfo<SocketAddr>) -> String {
+        addr.to_string()
+    }
+
+    let app = Router::new().route("/", get(handler));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        mouchak_mail_server::listener::serve(listener, app, &config, async {
+            stop_rx.await.ok();
+        })
+        .await
+    });
+    (addr, stop_tx, server)
+}
+
+#[tokio::test]
+async fn test_listener_serves_http2_and_http1() {
+    use axum::http::Version;
+    use http_body_util::BodyExt;
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+
+    let config = mouchak_mail_common::config::HttpConfig {
+        http2_max_concurrent_streams: 8,
+        ..Default::default()
+    };
+    let (addr, stop_tx, server) = start_listener(config).await;
+
+    // h2c with prior knowledge, several requests multiplexed on one connection
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let client_addr = stream.local_addr().unwrap();
+    let (sender, connection) =
+        hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+            .await
+            .unwrap();
+    tokio::spawn(connection);
+    let requests = (0..4).map(|_| {
+        let mut sender = sender.clone();
+        async move {
+            let request = Request::get(format!("http://{}/", addr))
+                .body(Body::empty())
+                .unwrap();
+            sender.send_request(request).await.unwrap()
+        }
+    });
+    for response in futures::future::join_all(requests).await {
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.version(), Version::HTTP_2);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, client_addr.to_string());
+    }
+    drop(sender);
+
+    // Plain HTTP/1.1 on the same port
+    let response = reqwest::get(format!("http://{}/", addr)).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.version(), reqwest::Version::HTTP_11);
+
+    stop_tx.send(()).unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(5), server)
+        .await
+        .expect("server did not shut down")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_listener_http2_disabled() {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+
+    let config = mouchak_mail_common::config::HttpConfig {
+        http2: false,
+        ..Default::default()
+    };
+    let (addr, stop_tx, server) = start_listener(config).await;
+
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let result = async {
+        let (mut sender, connection) =
+            hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+                .await?;
+        tokio::spawn(connection);
+        let request = Request::get(format!("http://{}/", addr))
+            .body(Body::empty())
+            .unwrap();
+        sender.send_request(request).await
+    }
+    .await;
+    assert!(result.is_err(), "h2c must be refused when http2 is off");
+
+    let response = reqwest::get(format!("http://{}/", addr)).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    stop_tx.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_listener_http2_idle_timeout_pings() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let config = mouchak_mail_common::config::HttpConfig {
+        idle_timeout_seconds: 1,
+        http2_keep_alive_interval_seconds: 0,
+        http2_keep_alive_timeout_seconds: 1,
+        ..Default::default()
+    };
+    let (addr, stop_tx, server) = start_listener(config).await;
+
+    // An h2c client that goes quiet after the preface and never answers pings
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\x00\x00\x00\x04\x00\x00\x00\x00\x00")
+        .await
+        .unwrap();
+    let frame_types = async {
+        let mut types = Vec::new();
+        let mut head = [0u8; 9];
+        while stream.read_exact(&mut head).await.is_ok() {
+            let len = u32::from_be_bytes([0, head[0], head[1], head[2]]) as usize;
+            let mut payload = vec![0u8; len];
+            if stream.read_exact(&mut payload).await.is_err() {
+                break;
+            }
+            types.push(head[3]);
+        }
+        types
+    };
+    let frame_types = tokio::time::timeout(std::time::Duration::from_secs(10), frame_types)
+        .await
+        .expect("idle HTTP/2 connection was not closed");
+    // PING frames are type 6
+    assert!(frame_types.contains(&6), "frames: {:?}", frame_types);
+
+    stop_tx.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[test]
+fn test_listener_validates_http2_limits() {
+    use mouchak_mail_common::config::HttpConfig;
+    use mouchak_mail_server::listener::validate;
+
+    assert!(validate(&HttpConfig::default()).is_ok());
+    let no_streams = HttpConfig {
+        http2_max_concurrent_streams: 0,
+        ..Default::default()
+    };
+    assert!(validate(&no_streams).is_err());
+    let no_ping_timeout = HttpConfig {
+        http2_keep_alive_timeout_seconds: 0,
+        ..Default::default()
+    };
+    assert!(validate(&no_ping_timeout).is_err());
+    // Pings off: the timeout is unused
+    let no_pings = HttpConfig {
+        http2_keep_alive_interval_seconds: 0,
+        http2_keep_alive_timeout_seconds: 0,
+        idle_timeout_seconds: 0,
+        ..Default::default()
+    };
+    assert!(validate(&no_pings).is_ok());
+    // Pings at the idle timeout still need a ping timeout
+    let idle_pings = HttpConfig {
+        http2_keep_alive_interval_seconds: 0,
+        http2_keep_alive_timeout_seconds: 0,
+        ..Default::default()
+    };
+    assert!(validate(&idle_pings).is_err());
+    // HTTP/2 off: its limits are not checked
+    let http1_only = HttpConfig {
+        http2: false,
+        http2_max_concurrent_streams: 0,
+        ..Default::default()
+    };
+    assert!(validate(&http1_only).is_ok());
+}
@@ -10,9 +10,13 @@
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use http_body_util::BodyExt;
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::model::ModelManager;
 use mouchak_mail_mcp::tools::MouchakMailService;
 use serde_json::{Value, json};
+use std::path::Path;
 use std::sync::Arc;
+use tempfile::TempDir;
 use tower::ServiceExt;
 
 use rmcp::transport::streamable_http_server::{
@@ -22,18 +26,20 @@ use rmcp::transport::streamable_http_server::{
 
 /// Create MCP service with MouchakMailService
 /// Uses spawn_blocking to avoid runtime-in-runtime issues
-fn create_test_mcp_service() -> StreamableHttpService<MouchakMailService> {
+fn create_test_mcp_service() -> (StreamableHttpService<MouchakMailService>, TempDir) {
     let session_manager = Arc::new(LocalSessionManager::default());
     let config = StreamableHttpServerConfig::default();
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_path_buf();
 
     // Use a sync factory that creates MouchakMailService using a scoped thread
     // (std::thread::scope guarantees the thread is joined when scope exits)
-    let service_factory = || -> Result<MouchakMailService, std::io::Error> {
+    let service_factory = move || -> Result<MouchakMailService, std::io::Error> {
         let result = std::thread::scope(|scope| {
             scope
                 .spawn(|| {
                     let rt = tokio::runtime::Runtime::new().unwrap();
-                    rt.block_on(async { MouchakMailService::new().await })
+                    rt.block_on(test_service(&root))
                 })
                 .join()
                 .expect("MouchakMailService thread panicked")
@@ -42,7 +48,28 @@ fn create_test_mcp_service() -> StreamableHttpService<MouchakMailService> {
         result.map_err(|e| std::io::Error::other(e.to_string()))
     };
 
-    StreamableHttpService::new(service_factory, session_manager, config)
+    (
+        StreamableHttpService::new(service_factory, session_manager, config),
+        temp_dir,
+    )
+}
+
+/// MouchakMailService over a database and archive in `root`
+async fn test_service(root: &Path) -> mouchak_mail_core::Result<MouchakMailService> {
+    let archive_root = root.join("archive");
+    std::fs::create_dir_all(&archive_root)?;
+    let db = libsql::Builder::new_local(root.join("mouchak_mail.db"))
+        .build()
+        .await?;
+    let conn = db.connect()?;
+    mouchak_mail_core::store::upgrade::migrate(&conn).await?;
+    let config = AppConfig::default();
+    let worktrees_enabled = config.mcp.worktrees_active();
+    let mm = ModelManager::new_for_test(conn, archive_root, Arc::new(config));
+    Ok(MouchakMailService::new_with_mm(
+        Arc::new(mm),
+        worktrees_enabled,
+    ))
 }
 
 /// Helper to build MCP JSON-RPC request
@@ -82,7 +109,7 @@ async fn post_mcp(
 
 #[tokio::test]
 async fn test_mcp_requires_accept_header() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Request without proper Accept header should fail
     let request = Request::builder()
@@ -112,7 +139,7 @@ async fn test_mcp_requires_accept_header() {
 
 #[tokio::test]
 async fn test_mcp_accepts_post_with_correct_headers() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     let request = Request::builder()
         .method("POST")
@@ -153,7 +180,7 @@ async fn test_mcp_accepts_post_with_correct_headers() {
 
 #[tokio::test]
 async fn test_mcp_requires_initialize_first() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Send tools/list without initialize - should fail with protocol error
     let (status, body) = post_mcp(&service, mcp_request("tools/list", None, 1)).await;
@@ -174,7 +201,7 @@ async fn test_mcp_requires_initialize_first() {
 
 #[tokio::test]
 async fn test_mcp_initialize_succeeds() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Initialize the MCP session
     let (init_status, init_body) = post_mcp(
@@ -227,7 +254,7 @@ async fn test_mcp_initialize_succeeds() {
 
 #[tokio::test]
 async fn test_mcp_json_rpc_format() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Send malformed JSON-RPC (missing required fields)
     let (status, body) = post_mcp(
@@ -249,7 +276,7 @@ async fn test_mcp_json_rpc_format() {
 
 #[tokio::test]
 async fn test_mcp_unknown_method() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     let (status, body) = post_mcp(
         &service,
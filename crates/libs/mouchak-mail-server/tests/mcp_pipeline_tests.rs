@@ -16,9 +16,13 @@
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use http_body_util::BodyExt;
+use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_core::model::ModelManager;
 use mouchak_mail_mcp::tools::MouchakMailService;
 use serde_json::{Value, json};
+use std::path::Path;
 use std::sync::Arc;
+use tempfile::TempDir;
 use tower::ServiceExt;
 
 use rmcp::transport::streamable_http_server::{
@@ -27,16 +31,18 @@ use rmcp::transport::streamable_http_server::{
 };
 
 /// Create MCP service with MouchakMailService
-fn create_test_mcp_service() -> StreamableHttpService<MouchakMailService> {
+fn create_test_mcp_service() -> (StreamableHttpService<MouchakMailService>, TempDir) {
     let session_manager = Arc::new(LocalSessionManager::default());
     let config = StreamableHttpServerConfig::default();
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().to_path_buf();
 
-    let service_factory = || -> Result<MouchakMailService, std::io::Error> {
+    let service_factory = move || -> Result<MouchakMailService, std::io::Error> {
         let result = std::thread::scope(|scope| {
             scope
                 .spawn(|| {
                     let rt = tokio::runtime::Runtime::new().unwrap();
-                    rt.block_on(async { MouchakMailService::new().await })
+                    rt.block_on(test_service(&root))
                 })
                 .join()
                 .expect("MouchakMailService thread panicked")
@@ -45,7 +51,28 @@ fn create_test_mcp_service() -> StreamableHttpService<MouchakMailService> {
         result.map_err(|e| std::io::Error::other(e.to_string()))
     };
 
-    StreamableHttpService::new(service_factory, session_manager, config)
+    (
+        StreamableHttpService::new(service_factory, session_manager, config),
+        temp_dir,
+    )
+}
+
+/// MouchakMailService over a database and archive in `root`
+async fn test_service(root: &Path) -> mouchak_mail_core::Result<MouchakMailService> {
+    let archive_root = root.join("archive");
+    std::fs::create_dir_all(&archive_root)?;
+    let db = libsql::Builder::new_local(root.join("mouchak_mail.db"))
+        .build()
+        .await?;
+    let conn = db.connect()?;
+    mouchak_mail_core::store::upgrade::migrate(&conn).await?;
+    let config = AppConfig::default();
+    let worktrees_enabled = config.mcp.worktrees_active();
+    let mm = ModelManager::new_for_test(conn, archive_root, Arc::new(config));
+    Ok(MouchakMailService::new_with_mm(
+        Arc::new(mm),
+        worktrees_enabled,
+    ))
 }
 
 /// Helper to build MCP JSON-RPC request
@@ -137,7 +164,7 @@ fn extract_tool_result(response: &Value) -> Option<&Value> {
 
 #[tokio::test]
 async fn test_pipeline_stage0_initialize_session() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     let (status, body, session_id) = post_mcp_with_session(
         &service,
@@ -189,7 +216,7 @@ async fn test_pipeline_stage0_initialize_session() {
 
 #[tokio::test]
 async fn test_pipeline_stage1_ensure_project() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // First initialize
     let (_, _, session_id) = post_mcp_with_session(
@@ -263,7 +290,7 @@ async fn test_pipeline_stage1_ensure_project() {
 
 #[tokio::test]
 async fn test_pipeline_stage2_register_agent_hyphenated_name() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Initialize session
     let (_, _, session_id) = post_mcp_with_session(
@@ -344,7 +371,7 @@ async fn test_pipeline_stage2_register_agent_hyphenated_name() {
 
 #[tokio::test]
 async fn test_pipeline_stage3_list_agents() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Initialize session
     let (_, _, session_id) = post_mcp_with_session(
@@ -438,7 +465,7 @@ async fn test_pipeline_stage3_list_agents() {
 
 #[tokio::test]
 async fn test_pipeline_stage4_fetch_inbox() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Initialize session
     let (_, _, session_id) = post_mcp_with_session(
@@ -532,7 +559,7 @@ async fn test_pipeline_stage4_fetch_inbox() {
 
 #[tokio::test]
 async fn test_pipeline_stage5_list_file_reservations() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Initialize session
     let (_, _, session_id) = post_mcp_with_session(
@@ -609,7 +636,7 @@ async fn test_pipeline_stage5_list_file_reservations() {
 
 #[tokio::test]
 async fn test_pipeline_stage6_send_message_broadcast() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Initialize session
     let (_, _, session_id) = post_mcp_with_session(
@@ -726,7 +753,7 @@ async fn test_pipeline_stage6_send_message_broadcast() {
 
 #[tokio::test]
 async fn test_full_mcp_pipeline() {
-    let service = create_test_mcp_service();
+    let (service, _temp_dir) = create_test_mcp_service();
 
     // Stage 0: Initialize
     println!("\n=== Stage 0: Initialize MCP Session ===");
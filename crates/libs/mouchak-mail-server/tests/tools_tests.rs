@@ -15,6 +15,7 @@ use http_body_util::BodyExt;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use mouchak_mail_common::config::AppConfig;
 use mouchak_mail_core::ModelManager;
+use mouchak_mail_core::types::{AgentId, MessageId};
 use mouchak_mail_server::auth::{AuthConfig, AuthMode};
 use mouchak_mail_server::ratelimit::RateLimitConfig;
 use mouchak_mail_server::{AppState, tools};
@@ -26,6 +27,11 @@ use tower::ServiceExt;
 
 /// Create a test AppState with isolated database
 async fn create_test_state() -> (AppState, TempDir) {
+    create_test_state_with_config(AppConfig::default()).await
+}
+
+/// Helper to create test state running with `app_config`
+async fn create_test_state_with_config(app_config: AppConfig) -> (AppState, TempDir) {
     use libsql::Builder;
 
     let temp_dir = TempDir::new().expect("Failed to create temp dir");
@@ -39,21 +45,11 @@ async fn create_test_state() -> (AppState, TempDir) {
 
     // Apply migrations
     let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
-    let schema = include_str!("../../../../migrations/001_initial_schema.sql");
-    conn.execute_batch(schema).await.unwrap();
-    let schema002 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-    conn.execute_batch(schema002).await.unwrap();
-    let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-    conn.execute_batch(schema3).await.unwrap();
-    let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-    conn.execute_batch(schema4).await.unwrap();
-    let schema5 = include_str!("../../../../migrations/005_attachments_agent.sql");
-    conn.execute_batch(schema5).await.unwrap();
-    let schema6 = include_str!("../../../../migrations/006_query_indexes.sql");
-    conn.execute_batch(schema6).await.unwrap();
-
-    let app_config = Arc::new(AppConfig::default());
-    let mm = ModelManager::new_for_test(conn, archive_root, app_config);
+    mouchak_mail_core::store::upgrade::migrate(&conn)
+        .await
+        .unwrap();
+
+    let mm = ModelManager::new_for_test(conn, archive_root, Arc::new(app_config));
 
     // Create metrics handle (use a test-only builder to avoid conflicts)
     let metrics_handle = PrometheusBuilder::new()
@@ -70,6 +66,8 @@ async fn create_test_state() -> (AppState, TempDir) {
         jwt_audience: None,
         jwt_issuer: None,
         allow_localhost: true,
+        trusted_proxies: Default::default(),
+        internal_networks: Vec::new(),
     };
 
     let state = AppState {
@@ -79,6 +77,8 @@ async fn create_test_state() -> (AppState, TempDir) {
         auth_config,
         jwks_client: None,
         ratelimit_config: RateLimitConfig::new(),
+        oidc: None,
+        sessions: Default::default(),
     };
 
     (state, temp_dir)
@@ -121,6 +121,30 @@ async fn get_json(app: Router, uri: &str) -> (StatusCode, Value) {
     (status, body_json)
 }
 
+/// Sends a request with an optional `If-None-Match`; returns the status,
+/// the `ETag` and whether a body came back.
+async fn conditional(
+    app: Router,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+    if_none_match: Option<&str>,
+) -> (StatusCode, String, bool) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json");
+    if let Some(etag) = if_none_match {
+        request = request.header("If-None-Match", etag);
+    }
+    let body = body.map_or_else(Body::empty, |b| Body::from(b.to_string()));
+    let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, etag, !body_bytes.is_empty())
+}
+
 // =============================================================================
 // Health Check Tests
 // =============================================================================
@@ -251,7 +275,87 @@ mod project_tests {
         let (status, body) = get_json(app2, "/api/projects").await;
 
         assert_eq!(status, StatusCode::OK);
-        assert!(body.as_array().unwrap().len() >= 1);
+        assert!(!body.as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_projects_conditional() {
+        let (state, _temp) = create_test_state().await;
+        let app = Router::new()
+            .route("/api/project/ensure", post(tools::ensure_project))
+            .route("/api/projects", get(tools::list_all_projects))
+            .with_state(state);
+        post_json(
+            app.clone(),
+            "/api/project/ensure",
+            json!({"human_key": "etag-project"}),
+        )
+        .await;
+
+        let (status, etag, _) = conditional(app.clone(), "GET", "/api/projects", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _, has_body) =
+            conditional(app.clone(), "GET", "/api/projects", None, Some(&etag)).await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+        assert!(!has_body);
+
+        post_json(
+            app.clone(),
+            "/api/project/ensure",
+            json!({"human_key": "etag-project-two"}),
+        )
+        .await;
+        let (status, _, has_body) =
+            conditional(app, "GET", "/api/projects", None, Some(&etag)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(has_body);
+    }
+
+    #[tokio::test]
+    async fn test_list_projects_paging() {
+        let (state, _temp) = create_test_state().await;
+
+        for key in ["page-a", "page-b", "page-c"] {
+            let app = Router::new()
+                .route("/api/project/ensure", post(tools::ensure_project))
+                .with_state(state.clone());
+            post_json(app, "/api/project/ensure", json!({ "human_key": key })).await;
+        }
+
+        let app = Router::new()
+            .route("/api/projects", get(tools::list_all_projects))
+            .with_state(state.clone());
+        let request = Request::builder()
+            .uri("/api/projects?sort=name&limit=2")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[tools::TOTAL_COUNT_HEADER], "3");
+        let cursor = response.headers()[tools::NEXT_CURSOR_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let first: Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(first.as_array().unwrap().len(), 2);
+
+        let app = Router::new()
+            .route("/api/projects", get(tools::list_all_projects))
+            .with_state(state.clone());
+        let (status, second) = get_json(
+            app,
+            &format!("/api/projects?sort=name&limit=2&cursor={}", cursor),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(second.as_array().unwrap().len(), 1);
+
+        let app = Router::new()
+            .route("/api/projects", get(tools::list_all_projects))
+            .with_state(state);
+        let (status, _) = get_json(app, "/api/projects?active_within=soon").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
@@ -336,6 +440,54 @@ mod agent_tests {
         assert_eq!(body["program"], "claude-code");
     }
 
+    #[tokio::test]
+    async fn test_agent_avatar_svg() {
+        let (state, _temp) = create_test_state().await;
+        let project_slug = setup_with_project(&state).await;
+
+        let app = Router::new()
+            .route("/api/agent/register", post(tools::register_agent))
+            .route(
+                "/api/agents/{id}/avatar.svg",
+                get(mouchak_mail_server::api::avatar::agent_avatar),
+            )
+            .with_state(state);
+
+        let (_, body) = post_json(
+            app.clone(),
+            "/api/agent/register",
+            json!({
+                "project_slug": project_slug,
+                "name": "AvatarAgent",
+                "program": "claude-code",
+                "model": "test"
+            }),
+        )
+        .await;
+        let uri = format!("/api/agents/{}/avatar.svg?size=32", body["id"]);
+
+        let request = Request::builder().uri(&uri).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "image/svg+xml");
+        let etag = response.headers()["etag"].clone();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let svg = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"width="32""#));
+
+        let request = Request::builder()
+            .uri(&uri)
+            .header("If-None-Match", etag)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let (status, _) = get_json(app, "/api/agents/9999/avatar.svg").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn test_whois_agent() {
         let (state, _temp) = create_test_state().await;
@@ -401,7 +553,7 @@ mod agent_tests {
 
         assert_eq!(status, StatusCode::OK);
         assert!(body["suggested_name"].is_string());
-        assert!(body["alternatives"].as_array().unwrap().len() > 0);
+        assert!(!body["alternatives"].as_array().unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -438,7 +590,7 @@ mod agent_tests {
 
         assert_eq!(status, StatusCode::OK);
         let agents = body.as_array().unwrap();
-        assert!(agents.len() >= 1);
+        assert!(!agents.is_empty());
         assert!(agents.iter().any(|a| a["name"] == "ListTestAgent"));
     }
 }
@@ -531,6 +683,188 @@ mod message_tests {
         assert_eq!(body["sender_name"], sender);
     }
 
+    #[tokio::test]
+    async fn test_undeliverable_send_is_dead_lettered() {
+        let (state, _temp) = create_test_state().await;
+        let (project_slug, sender, recipient) = setup_with_agents(&state).await;
+
+        let app = Router::new()
+            .route("/api/message/send", post(tools::send_message))
+            .route(
+                "/api/admin/dead_letters",
+                get(mouchak_mail_server::api::dead_letters::list_dead_letters),
+            )
+            .route(
+                "/api/admin/dead_letters/{dead_letter_id}/requeue",
+                post(mouchak_mail_server::api::dead_letters::requeue_dead_letter),
+            )
+            .with_state(state);
+
+        let (status, body) = post_json(
+            app.clone(),
+            "/api/message/send",
+            json!({
+                "project_slug": project_slug,
+                "sender_name": sender,
+                "recipient_names": ["RetiredAgent"],
+                "subject": "Hand-off",
+                "body_md": "Notes"
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.to_string().contains("dead letter"));
+
+        let (status, letters) = get_json(app.clone(), "/api/admin/dead_letters").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(letters.as_array().unwrap().len(), 1);
+        assert_eq!(letters[0]["to"], json!(["RetiredAgent"]));
+        assert_eq!(letters[0]["status"], "pending");
+        let id = letters[0]["id"].as_i64().unwrap();
+
+        let (status, body) = post_json(
+            app.clone(),
+            &format!("/api/admin/dead_letters/{}/requeue", id),
+            json!({ "recipient_names": [recipient] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["message_id"].as_i64().unwrap() > 0);
+
+        let (_, letters) = get_json(app, "/api/admin/dead_letters").await;
+        assert!(letters.as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_message_renders_requested_format() {
+        let (state, _temp) = create_test_state().await;
+        let (project_slug, sender, recipient) = setup_with_agents(&state).await;
+
+        let app = Router::new()
+            .route("/api/message/send", post(tools::send_message))
+            .with_state(state.clone());
+        let (status, sent) = post_json(
+            app,
+            "/api/message/send",
+            json!({
+                "project_slug": project_slug,
+                "sender_name": sender,
+                "recipient_names": [recipient],
+                "subject": "Rendered",
+                "body_md": "Deploy <script>x</script> is **done**"
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let id = sent["id"].as_i64().unwrap();
+
+        let app = Router::new()
+            .route("/api/messages/{message_id}", get(tools::get_message))
+            .with_state(state.clone());
+        let (status, body) = get_json(app, &format!("/api/messages/{}?format=html", id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["body_format"], "markdown");
+        let html = body["body_rendered"].as_str().unwrap();
+        assert!(html.contains("<strong>done</strong>"));
+        assert!(!html.contains("<script>"));
+
+        let app = Router::new()
+            .route("/api/messages/{message_id}", get(tools::get_message))
+            .with_state(state);
+        let (status, body) = get_json(app, &format!("/api/messages/{}", id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("body_rendered").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_inbox_triage_cursor() {
+        let (state, _temp) = create_test_state().await;
+        let (project_slug, sender, recipient) = setup_with_agents(&state).await;
+
+        let app = Router::new()
+            .route("/api/message/send", post(tools::send_message))
+            .route(
+                "/api/inbox/triage",
+                post(mouchak_mail_server::api::triage::triage_step),
+            )
+            .with_state(state);
+
+        let mut ids = Vec::new();
+        for subject in ["older", "newer"] {
+            let (status, sent) = post_json(
+                app.clone(),
+                "/api/message/send",
+                json!({
+                    "project_slug": project_slug,
+                    "sender_name": sender,
+                    "recipient_names": [recipient],
+                    "subject": subject,
+                    "body_md": "body"
+                }),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            ids.push(sent["id"].as_i64().unwrap());
+        }
+
+        let (status, body) = post_json(
+            app.clone(),
+            "/api/inbox/triage",
+            json!({"project_slug": project_slug, "agent_name": recipient, "state": "unread"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["item"]["id"], ids[1]);
+        assert_eq!(body["item"]["subject"], "newer");
+        assert_eq!(body["remaining"], 1);
+        assert_eq!(body["total"], 2);
+
+        let (_, body) = post_json(
+            app.clone(),
+            "/api/inbox/triage",
+            json!({
+                "project_slug": project_slug,
+                "agent_name": recipient,
+                "from_message_id": ids[0],
+                "direction": "next"
+            }),
+        )
+        .await;
+        assert!(body["item"].is_null());
+
+        let (status, _) = post_json(
+            app,
+            "/api/inbox/triage",
+            json!({"project_slug": project_slug, "agent_name": recipient, "state": "bogus"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_render_markdown_endpoint() {
+        let (state, _temp) = create_test_state().await;
+        let app = Router::new()
+            .route(
+                "/api/render/markdown",
+                post(mouchak_mail_server::api::render::render_markdown),
+            )
+            .with_state(state);
+        let (status, body) = post_json(
+            app,
+            "/api/render/markdown",
+            json!({
+                "body_md": "[x](javascript:alert(1)) <img src=x>\n\n```rust\nfn main() {}\n```"
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let html = body["html"].as_str().unwrap();
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("&lt;img src=x&gt;"));
+        assert!(html.contains("<code class=\"language-rust\">"));
+    }
+
     #[tokio::test]
     async fn test_send_message_with_cc_bcc() {
         let (state, _temp) = create_test_state().await;
@@ -614,10 +948,72 @@ mod message_tests {
 
         assert_eq!(status, StatusCode::OK);
         let messages = body.as_array().unwrap();
-        assert!(messages.len() >= 1);
+        assert!(!messages.is_empty());
         assert!(messages.iter().any(|m| m["subject"] == "Inbox Test"));
     }
 
+    #[tokio::test]
+    async fn test_list_inbox_conditional() {
+        let (state, _temp) = create_test_state().await;
+        let (project_slug, sender, recipient) = setup_with_agents(&state).await;
+
+        let app = Router::new()
+            .route("/api/message/send", post(tools::send_message))
+            .route("/api/inbox", post(tools::list_inbox))
+            .with_state(state);
+        let send = |subject: &str| {
+            post_json(
+                app.clone(),
+                "/api/message/send",
+                json!({
+                    "project_slug": project_slug,
+                    "sender_name": sender,
+                    "recipient_names": [recipient],
+                    "subject": subject,
+                    "body_md": "Poll me"
+                }),
+            )
+        };
+        let inbox = json!({ "project_slug": project_slug, "agent_name": recipient });
+        send("First").await;
+
+        let (status, etag, has_body) =
+            conditional(app.clone(), "POST", "/api/inbox", Some(inbox.clone()), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(has_body);
+        assert!(etag.starts_with("W/\""));
+
+        let (status, same, has_body) = conditional(
+            app.clone(),
+            "POST",
+            "/api/inbox",
+            Some(inbox.clone()),
+            Some(&etag),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+        assert_eq!(same, etag);
+        assert!(!has_body);
+
+        // Another page size is another listing
+        let (status, _, _) = conditional(
+            app.clone(),
+            "POST",
+            "/api/inbox",
+            Some(json!({ "project_slug": project_slug, "agent_name": recipient, "limit": 1 })),
+            Some(&etag),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        send("Second").await;
+        let (status, changed, has_body) =
+            conditional(app, "POST", "/api/inbox", Some(inbox), Some(&etag)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(changed, etag);
+        assert!(has_body);
+    }
+
     #[tokio::test]
     async fn test_list_outbox() {
         let (state, _temp) = create_test_state().await;
@@ -658,7 +1054,7 @@ mod message_tests {
 
         assert_eq!(status, StatusCode::OK);
         let messages = body.as_array().unwrap();
-        assert!(messages.len() >= 1);
+        assert!(!messages.is_empty());
     }
 
     #[tokio::test]
@@ -700,6 +1096,52 @@ mod message_tests {
 
         assert_eq!(status, StatusCode::OK);
         assert!(body["count"].as_i64().unwrap() >= 1);
+        let snippet = &body["results"][0]["snippet"];
+        assert_eq!(snippet["highlighted"], "**UniqueSearchKeyword123**");
+        assert_eq!(snippet["matches"][0]["start"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_search_omits_bodies_by_default() {
+        let (state, _temp) = create_test_state().await;
+        let (project_slug, sender, recipient) = setup_with_agents(&state).await;
+
+        let app = Router::new()
+            .route("/api/message/send", post(tools::send_message))
+            .with_state(state.clone());
+        post_json(
+            app,
+            "/api/message/send",
+            json!({
+                "project_slug": project_slug,
+                "sender_name": sender,
+                "recipient_names": [recipient],
+                "subject": "Build report",
+                "body_md": "The nightly build hit a flaky_gizmo failure in the parser suite."
+            }),
+        )
+        .await;
+
+        let app = Router::new()
+            .route("/api/search", get(tools::search))
+            .with_state(state);
+        let (status, body) = get_json(
+            app,
+            &format!("/api/search?project={}&q=flaky_gizmo", project_slug),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 1);
+        let hit = &body["results"][0];
+        assert!(hit.get("body_md").is_none());
+        assert_eq!(hit["field"], "body");
+        assert!(
+            hit["snippet"]["highlighted"]
+                .as_str()
+                .unwrap()
+                .contains("**flaky_gizmo**")
+        );
     }
 }
 
@@ -812,7 +1254,7 @@ mod file_reservation_tests {
         .await;
 
         assert_eq!(status, StatusCode::OK);
-        assert!(body.as_array().unwrap().len() >= 1);
+        assert!(!body.as_array().unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -894,7 +1336,7 @@ mod file_reservation_tests {
         let (status, body) = get_json(app, "/api/locks").await;
 
         assert_eq!(status, StatusCode::OK);
-        assert!(body.as_array().unwrap().len() >= 1);
+        assert!(!body.as_array().unwrap().is_empty());
     }
 }
 
@@ -977,13 +1419,69 @@ mod thread_tests {
         .await;
 
         assert_eq!(status, StatusCode::OK);
-        assert!(body.as_array().unwrap().len() >= 1);
+        assert!(!body.as_array().unwrap().is_empty());
     }
 
     #[tokio::test]
-    async fn test_list_threads() {
+    async fn test_get_thread_conditional() {
         let (state, _temp) = create_test_state().await;
-        let (project_slug, _) = setup_with_thread(&state).await;
+        let (project_slug, thread_id) = setup_with_thread(&state).await;
+
+        let app = Router::new()
+            .route("/api/thread", post(tools::get_thread))
+            .route("/api/message/send", post(tools::send_message))
+            .with_state(state);
+        let thread = json!({
+            "project_slug": project_slug,
+            "thread_id": thread_id,
+            "agent_name": "ThreadRecipient"
+        });
+
+        // The first read marks the thread read; its tag already accounts
+        // for that, so the next poll is a 304
+        let (status, etag, _) = conditional(
+            app.clone(),
+            "POST",
+            "/api/thread",
+            Some(thread.clone()),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _, has_body) = conditional(
+            app.clone(),
+            "POST",
+            "/api/thread",
+            Some(thread.clone()),
+            Some(&etag),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+        assert!(!has_body);
+
+        post_json(
+            app.clone(),
+            "/api/message/send",
+            json!({
+                "project_slug": project_slug,
+                "sender_name": "ThreadSender",
+                "recipient_names": ["ThreadRecipient"],
+                "subject": "Re: Thread Test",
+                "body_md": "Follow-up",
+                "thread_id": thread_id
+            }),
+        )
+        .await;
+        let (status, _, has_body) =
+            conditional(app, "POST", "/api/thread", Some(thread), Some(&etag)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(has_body);
+    }
+
+    #[tokio::test]
+    async fn test_list_threads() {
+        let (state, _temp) = create_test_state().await;
+        let (project_slug, _) = setup_with_thread(&state).await;
 
         let app = Router::new()
             .route("/api/threads", post(tools::list_threads))
@@ -999,7 +1497,7 @@ mod thread_tests {
         .await;
 
         assert_eq!(status, StatusCode::OK);
-        assert!(body.as_array().unwrap().len() >= 1);
+        assert!(!body.as_array().unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -1090,6 +1588,9 @@ mod build_slot_tests {
         assert_eq!(status, StatusCode::OK);
         assert!(body["slot_id"].as_i64().unwrap() > 0);
         assert_eq!(body["slot_name"], "test-slot");
+        // Same format as expires_ts, so the two compare as strings
+        let server_time = body["server_time"].as_str().unwrap();
+        assert!(body["expires_ts"].as_str().unwrap() > server_time);
     }
 
     #[tokio::test]
@@ -1333,7 +1834,7 @@ mod contact_tests {
         .await;
 
         assert_eq!(status, StatusCode::OK);
-        assert!(body.as_array().unwrap().len() >= 1);
+        assert!(!body.as_array().unwrap().is_empty());
     }
 }
 
@@ -1423,7 +1924,7 @@ mod macro_tests {
 
         assert_eq!(status, StatusCode::OK);
         // Should have built-in macros plus our test macro
-        assert!(body.as_array().unwrap().len() >= 1);
+        assert!(!body.as_array().unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -2124,6 +2625,85 @@ mod overseer_tests {
         assert!(body["sent"].as_bool().unwrap());
         assert!(body["message_id"].as_i64().unwrap() > 0);
     }
+
+    #[tokio::test]
+    async fn test_list_overseer_inbox() {
+        let (state, _temp) = create_test_state().await;
+
+        let app = Router::new()
+            .route("/api/project/ensure", post(tools::ensure_project))
+            .with_state(state.clone());
+        let (_, proj) = post_json(
+            app,
+            "/api/project/ensure",
+            json!({"human_key": "overseer-inbox-proj"}),
+        )
+        .await;
+        let project_slug = proj["slug"].as_str().unwrap().to_string();
+
+        let app = Router::new()
+            .route("/api/agent/register", post(tools::register_agent))
+            .with_state(state.clone());
+        post_json(
+            app,
+            "/api/agent/register",
+            json!({
+                "project_slug": project_slug,
+                "name": "Escalator",
+                "program": "test",
+                "model": "test"
+            }),
+        )
+        .await;
+
+        let app = Router::new()
+            .route("/api/overseer/send", post(tools::send_overseer_message))
+            .with_state(state.clone());
+        let (_, sent) = post_json(
+            app,
+            "/api/overseer/send",
+            json!({
+                "project_slug": project_slug,
+                "agent_name": "Escalator",
+                "subject": "Blocked on review",
+                "body_md": "Need a decision",
+                "importance": "high"
+            }),
+        )
+        .await;
+        let message_id = sent["message_id"].as_i64().unwrap();
+
+        let app = Router::new()
+            .route(
+                "/api/projects/{project_slug}/overseer",
+                get(tools::list_overseer_messages),
+            )
+            .route(
+                "/api/overseer/mark_read",
+                post(tools::mark_overseer_message_read),
+            )
+            .with_state(state);
+
+        let uri = format!("/api/projects/{}/overseer", project_slug);
+        let (status, body) = get_json(app.clone(), &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["unread_count"].as_u64().unwrap(), 1);
+        assert_eq!(body["messages"][0]["sender_name"], "Escalator");
+
+        let (status, _) = post_json(
+            app.clone(),
+            "/api/overseer/mark_read",
+            json!({"message_id": message_id}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, body) = get_json(app.clone(), &uri).await;
+        assert_eq!(body["messages"].as_array().unwrap().len(), 0);
+
+        let (_, body) = get_json(app, &format!("{}?include_read=true", uri)).await;
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+    }
 }
 
 // =============================================================================
@@ -2653,3 +3233,1105 @@ mod siblings_tests {
         assert!(body.is_array());
     }
 }
+
+// =============================================================================
+// Web Push Tests
+// =============================================================================
+
+mod push_tests {
+    use super::*;
+    use mouchak_mail_server::api::push;
+
+    #[tokio::test]
+    async fn test_push_subscription_lifecycle() {
+        let (state, _temp) = create_test_state().await;
+        let app = Router::new()
+            .route("/api/push/vapid-public-key", get(push::vapid_public_key))
+            .route(
+                "/api/push/subscriptions",
+                post(push::subscribe).delete(push::unsubscribe),
+            )
+            .with_state(state);
+
+        let (status, body) = get_json(app.clone(), "/api/push/vapid-public-key").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["enabled"], false);
+        assert_eq!(body["public_key"].as_str().unwrap().len(), 87);
+
+        let subscription = json!({
+            "endpoint": "https://push.example/sub-1",
+            "keys": {"p256dh": "BPkey", "auth": "secret"},
+            "label": "supervisor"
+        });
+        let (status, body) = post_json(app.clone(), "/api/push/subscriptions", subscription).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["id"].as_i64().is_some());
+
+        let (status, _) = post_json(
+            app.clone(),
+            "/api/push/subscriptions",
+            json!({
+                "endpoint": "https://push.example/sub-2",
+                "keys": {"p256dh": "BPkey", "auth": "secret"},
+                "project_slug": "no-such-project"
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // The server must not be made to POST to internal services
+        for endpoint in ["http://push.example/sub-3", "https://127.0.0.1:8765/api"] {
+            let (status, _) = post_json(
+                app.clone(),
+                "/api/push/subscriptions",
+                json!({
+                    "endpoint": endpoint,
+                    "keys": {"p256dh": "BPkey", "auth": "secret"}
+                }),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", endpoint);
+        }
+
+        let request = Request::builder()
+            .method("DELETE")
+            .uri("/api/push/subscriptions")
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                json!({"endpoint": "https://push.example/sub-1"}).to_string(),
+            ))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["deleted"], true);
+    }
+}
+
+// =============================================================================
+// UI Preferences Tests
+// =============================================================================
+
+mod preferences_tests {
+    use super::*;
+    use mouchak_mail_server::api::preferences;
+
+    async fn put_json(app: Router, uri: &str, body: Value) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method("PUT")
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_preferences_roundtrip() {
+        let (state, _temp) = create_test_state().await;
+        let app = Router::new()
+            .route(
+                "/api/preferences",
+                get(preferences::get_preferences).put(preferences::update_preferences),
+            )
+            .with_state(state);
+
+        let (status, body) = get_json(app.clone(), "/api/preferences").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["owner"], "user:local");
+        assert_eq!(body["preferences"], json!({}));
+
+        let (status, body) = put_json(
+            app.clone(),
+            "/api/preferences",
+            json!({"preferences": {"theme": "dark", "default_project": "backend"}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["preferences"]["theme"], "dark");
+
+        let (_, body) = put_json(
+            app.clone(),
+            "/api/preferences",
+            json!({"preferences": {"theme": null}}),
+        )
+        .await;
+        assert_eq!(body["preferences"], json!({"default_project": "backend"}));
+
+        let (status, _) = put_json(
+            app.clone(),
+            "/api/preferences",
+            json!({"preferences": {"not a key": 1}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = get_json(app, "/api/preferences?agent_name=BlueLake").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+// =============================================================================
+// Event Log Tests
+// =============================================================================
+
+mod event_log_tests {
+    use super::*;
+    use mouchak_mail_server::api::events;
+
+    #[tokio::test]
+    async fn test_event_log_export_and_verify() {
+        let (state, _temp) = create_test_state().await;
+        let app = Router::new()
+            .route("/api/project/ensure", post(tools::ensure_project))
+            .route("/api/events", get(events::list_events))
+            .route("/api/events/export", get(events::export_events))
+            .route("/api/events/verify", get(events::verify_events))
+            .with_state(state);
+
+        let (status, _) = post_json(
+            app.clone(),
+            "/api/project/ensure",
+            json!({ "human_key": "/audit/project" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = get_json(app.clone(), "/api/events").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["kind"], "project.created");
+        assert_eq!(body[0]["id"], 1);
+
+        let (status, body) = get_json(app.clone(), "/api/events/verify").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["valid"], true);
+        assert_eq!(body["records"], 1);
+
+        let request = Request::builder()
+            .uri("/api/events/export")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let lines: Vec<Value> = String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["hash"], body["head_hash"]);
+    }
+}
+
+// =============================================================================
+// Identity Tests
+// =============================================================================
+
+mod me_tests {
+    use super::*;
+    use mouchak_mail_server::api::me;
+    use mouchak_mail_server::auth::{AuthenticatedUser, ROUTE_CAPABILITIES, auth_middleware};
+    use mouchak_mail_server::session::SESSION_COOKIE;
+
+    #[tokio::test]
+    async fn test_me_reports_principal_and_csrf_token() {
+        let (state, _temp) = create_test_state().await;
+        let token = state
+            .sessions
+            .create(
+                AuthenticatedUser {
+                    subject: "alice@example.com".to_string(),
+                    agent_name: None,
+                    project_slug: None,
+                },
+                std::time::Duration::from_secs(60),
+            )
+            .await;
+        let app = Router::new()
+            .route("/api/me", get(me::me))
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state);
+
+        // Without auth the caller is anonymous and, without RBAC, unrestricted
+        let (status, body) = get_json(app.clone(), "/api/me").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["auth_method"], "anonymous");
+        assert_eq!(body["subject"], Value::Null);
+        assert_eq!(body["csrf_token"], Value::Null);
+        assert_eq!(
+            body["roles"].as_array().unwrap().len(),
+            ROUTE_CAPABILITIES.len()
+        );
+
+        let request = Request::builder()
+            .uri("/api/me")
+            .header("Cookie", format!("{}={}", SESSION_COOKIE, token))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["auth_method"], "session");
+        assert_eq!(body["subject"], "alice@example.com");
+        assert_eq!(body["csrf_token"].as_str().unwrap().len(), 64);
+        assert_eq!(body["session_idle_timeout_seconds"], 1800);
+    }
+}
+
+// =============================================================================
+// GitHub Cross-posting Tests
+// =============================================================================
+
+mod github_tests {
+    use super::*;
+    use axum::extract::State;
+    use mouchak_mail_common::config::GithubConfig;
+    use mouchak_mail_core::Ctx;
+    use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+    use mouchak_mail_core::model::github_sync::GithubSyncBmc;
+    use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+    use mouchak_mail_core::model::project::ProjectBmc;
+    use mouchak_mail_server::github;
+    use std::sync::Mutex;
+
+    /// Issue #7 of acme/widgets with one human comment; posted comments are
+    /// appended to the issue.
+    #[derive(Default)]
+    struct FakeGithub {
+        issues: Vec<Value>,
+        comments: Vec<Value>,
+    }
+
+    async fn start_fake_github() -> (String, Arc<Mutex<FakeGithub>>) {
+        let fake = Arc::new(Mutex::new(FakeGithub {
+            issues: Vec::new(),
+            comments: vec![json!({
+                "id": 50,
+                "user": { "login": "octocat" },
+                "body": "LGTM, ship it",
+                "html_url": "https://github.test/acme/widgets/issues/7#issuecomment-50",
+            })],
+        }));
+        let app = Router::new()
+            .route(
+                "/repos/acme/widgets/issues",
+                post(
+                    |State(fake): State<Arc<Mutex<FakeGithub>>>,
+                     axum::Json(body): axum::Json<Value>| async move {
+                        fake.lock().unwrap().issues.push(body);
+                        axum::Json(json!({
+                            "number": 7,
+                            "html_url": "https://github.test/acme/widgets/issues/7",
+                        }))
+                    },
+                ),
+            )
+            .route(
+                "/repos/acme/widgets/issues/7/comments",
+                get(|State(fake): State<Arc<Mutex<FakeGithub>>>| async move {
+                    axum::Json(Value::from(fake.lock().unwrap().comments.clone()))
+                })
+                .post(
+                    |State(fake): State<Arc<Mutex<FakeGithub>>>,
+                     axum::Json(body): axum::Json<Value>| async move {
+                        let mut fake = fake.lock().unwrap();
+                        let comment = json!({
+                            "id": 100 + fake.comments.len(),
+                            "user": { "login": "mouchak-bot" },
+                            "body": body["body"],
+                            "html_url": "https://github.test/acme/widgets/issues/7",
+                        });
+                        fake.comments.push(comment.clone());
+                        axum::Json(comment)
+                    },
+                ),
+            )
+            .with_state(fake.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, fake)
+    }
+
+    #[tokio::test]
+    async fn test_mirror_thread_and_sync_comments_back() {
+        let (state, _temp) = create_test_state().await;
+        let mm = &state.mm;
+        let ctx = Ctx::root_ctx();
+        let (api_url, fake) = start_fake_github().await;
+        let config = GithubConfig {
+            token: Some("test-token".to_string()),
+            api_url,
+            ..GithubConfig::default()
+        };
+
+        let project_id = ProjectBmc::create(&ctx, mm, "widgets", "/widgets")
+            .await
+            .unwrap();
+        let mut agents = Vec::new();
+        for name in ["BlueLake", "GreenCastle"] {
+            let id = AgentBmc::create(
+                &ctx,
+                mm,
+                AgentForCreate {
+                    project_id,
+                    name: name.to_string(),
+                    program: "test".to_string(),
+                    model: "test".to_string(),
+                    task_description: String::new(),
+                },
+            )
+            .await
+            .unwrap();
+            agents.push(id.get());
+        }
+        for (sender, recipient, body) in [
+            (agents[0], agents[1], "Parser rejects empty input"),
+            (agents[1], agents[0], "Fix is in review"),
+        ] {
+            MessageBmc::create(
+                &ctx,
+                mm,
+                MessageForCreate {
+                    project_id,
+                    sender_id: sender.into(),
+                    recipient_ids: vec![AgentId::new(recipient)],
+                    cc_ids: None,
+                    bcc_ids: None,
+                    subject: "Parser bug".to_string(),
+                    body_md: body.to_string(),
+                    thread_id: Some("T-1".to_string()),
+                    importance: None,
+                    ack_required: false,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        // No repository mapped yet
+        let err = github::mirror_thread(mm, &config, project_id, "T-1", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no GitHub repository"));
+
+        GithubSyncBmc::set_repo(&ctx, mm, project_id, "acme/widgets")
+            .await
+            .unwrap();
+        let link = github::mirror_thread(mm, &config, project_id, "T-1", None)
+            .await
+            .unwrap();
+        assert_eq!(link.issue_number, 7);
+        {
+            let fake = fake.lock().unwrap();
+            assert_eq!(fake.issues.len(), 1);
+            assert_eq!(fake.issues[0]["title"], "Parser bug");
+            assert!(
+                fake.issues[0]["body"]
+                    .as_str()
+                    .unwrap()
+                    .contains("Parser rejects empty input")
+            );
+            // The first message opened the issue, the reply became a comment
+            assert_eq!(fake.comments.len(), 2);
+            assert!(
+                fake.comments[1]["body"]
+                    .as_str()
+                    .unwrap()
+                    .contains("Fix is in review")
+            );
+        }
+
+        // Mirroring again neither reopens nor reposts
+        github::mirror_thread(mm, &config, project_id, "T-1", None)
+            .await
+            .unwrap();
+        assert_eq!(fake.lock().unwrap().issues.len(), 1);
+        assert_eq!(fake.lock().unwrap().comments.len(), 2);
+
+        // Only the human comment comes back, from the github agent
+        assert_eq!(github::sync_all(mm, &config).await.unwrap(), 1);
+        let messages = MessageBmc::list_by_thread(&ctx, mm, project_id, "T-1")
+            .await
+            .unwrap();
+        let imported = messages.last().unwrap();
+        assert_eq!(imported.sender_name, "github");
+        assert_eq!(imported.subject, "Re: Parser bug");
+        assert!(imported.body_md.contains("**@octocat**"));
+        assert!(imported.body_md.contains("LGTM, ship it"));
+
+        // Nothing new on either side, and the import is not posted back
+        assert_eq!(github::sync_all(mm, &config).await.unwrap(), 0);
+        assert_eq!(fake.lock().unwrap().comments.len(), 2);
+    }
+}
+
+// =============================================================================
+// CI Status Tests
+// =============================================================================
+
+mod ci_tests {
+    use super::*;
+    use mouchak_mail_server::api::ci;
+
+    #[tokio::test]
+    async fn test_ci_event_endpoint() {
+        let (state, _temp) = create_test_state().await;
+        let app = Router::new()
+            .route("/api/project/ensure", post(tools::ensure_project))
+            .route("/api/integrations/ci", post(ci::ingest_ci_event))
+            .with_state(state);
+
+        let (status, project) = post_json(
+            app.clone(),
+            "/api/project/ensure",
+            json!({ "human_key": "/ci/project" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let event = json!({
+            "provider": "buildkite",
+            "repo": "acme/widgets",
+            "sha": "0123456789abcdef0123456789abcdef01234567",
+            "status": "failure",
+            "url": "https://buildkite.com/acme/widgets/builds/9",
+            "project_slug": project["slug"],
+        });
+        let (status, body) = post_json(app.clone(), "/api/integrations/ci", event.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["duplicate"], false);
+        assert_eq!(body["overseer_messages"].as_array().unwrap().len(), 1);
+
+        let (status, body) = post_json(app.clone(), "/api/integrations/ci", event.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["duplicate"], true);
+
+        let mut bad = event.clone();
+        bad["status"] = json!("green");
+        let (status, _) = post_json(app.clone(), "/api/integrations/ci", bad).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let mut unknown = event;
+        unknown["project_slug"] = json!("no-such-project");
+        let (status, _) = post_json(app, "/api/integrations/ci", unknown).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
+
+// =============================================================================
+// Webhook Tests
+// =============================================================================
+
+mod webhook_tests {
+    use super::*;
+    use mouchak_mail_common::config::WebhooksConfig;
+    use mouchak_mail_core::Ctx;
+    use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+    use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+    use mouchak_mail_core::model::project::ProjectBmc;
+    use mouchak_mail_core::model::webhook::{DeliveryFilter, WebhookBmc, WebhookForCreate, sign};
+    use mouchak_mail_server::api::webhooks;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_signed_delivery_with_retry() {
+        // The receiver listens on loopback
+        let mut app_config = AppConfig::default();
+        app_config.webhooks.allow_private_urls = true;
+        let (state, _temp) = create_test_state_with_config(app_config).await;
+        let mm = state.mm.clone();
+        let app = Router::new()
+            .route(
+                "/api/webhooks",
+                get(webhooks::list_webhooks).post(webhooks::create_webhook),
+            )
+            .route(
+                "/api/webhooks/{webhook_id}/deliveries",
+                get(webhooks::list_deliveries),
+            )
+            .with_state(state);
+
+        let ctx = Ctx::root_ctx();
+        let project = ProjectBmc::create(&ctx, &mm, "hooks", "/hooks")
+            .await
+            .unwrap();
+        let mut agents = Vec::new();
+        for name in ["BlueLake", "GreenCastle"] {
+            let agent_c = AgentForCreate {
+                project_id: project,
+                name: name.into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            };
+            agents.push(AgentBmc::create(&ctx, &mm, agent_c).await.unwrap());
+        }
+
+        // The receiver is down for the first attempt
+        let receiver = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .mount(&receiver)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&receiver)
+            .await;
+
+        let secret = "0123456789abcdef";
+        let (status, webhook) = post_json(
+            app.clone(),
+            "/api/webhooks",
+            json!({
+                "project_slug": "hooks",
+                "url": format!("{}/hook", receiver.uri()),
+                "events": ["message.created"],
+                "secret": secret,
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(webhook["secret"], secret);
+        let (status, listed) = get_json(app.clone(), "/api/webhooks?project=hooks").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(listed[0].get("secret").is_none());
+
+        let msg_c = MessageForCreate {
+            project_id: project,
+            sender_id: agents[0],
+            recipient_ids: vec![AgentId::new(agents[1].get())],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Deploy".into(),
+            body_md: "Shipping now".into(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        };
+        let message_id = MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+
+        // Retry right away instead of backing off
+        let config = WebhooksConfig {
+            backoff_base_seconds: 0,
+            allow_private_urls: true,
+            ..Default::default()
+        };
+        let deliveries_uri = format!("/api/webhooks/{}/deliveries", webhook["id"]);
+        assert_eq!(webhooks_deliver(&mm, &config).await, 0);
+        let (_, deliveries) = get_json(app.clone(), &deliveries_uri).await;
+        assert_eq!(deliveries[0]["status"], "pending");
+        assert_eq!(deliveries[0]["attempts"], 1);
+        assert_eq!(deliveries[0]["response_status"], 500);
+
+        assert_eq!(webhooks_deliver(&mm, &config).await, 1);
+        let (_, deliveries) = get_json(app.clone(), &deliveries_uri).await;
+        assert_eq!(deliveries[0]["status"], "delivered");
+        assert_eq!(deliveries[0]["attempts"], 2);
+        assert_eq!(deliveries[0]["response_status"], 204);
+
+        let requests = receiver.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let request = &requests[1];
+        assert_eq!(request.headers["x-mouchak-event"], "message.created");
+        assert_eq!(
+            request.headers["x-mouchak-signature"].to_str().unwrap(),
+            sign(secret, &request.body)
+        );
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["event"], "message.created");
+        assert_eq!(body["data"]["message_id"], message_id.get());
+        // Retries resend the same delivery
+        assert_eq!(requests[0].body, request.body);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_refuses_internal_hosts() {
+        let receiver = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&receiver)
+            .await;
+
+        // Created while private URLs were allowed, delivered after they no
+        // longer are
+        let mut app_config = AppConfig::default();
+        app_config.webhooks.allow_private_urls = true;
+        let (state, _temp) = create_test_state_with_config(app_config).await;
+        let mm = state.mm.clone();
+        let ctx = Ctx::root_ctx();
+        let project = ProjectBmc::create(&ctx, &mm, "hooks", "/hooks")
+            .await
+            .unwrap();
+        let mut agents = Vec::new();
+        for name in ["BlueLake", "GreenCastle"] {
+            let agent_c = AgentForCreate {
+                project_id: project,
+                name: name.into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            };
+            agents.push(AgentBmc::create(&ctx, &mm, agent_c).await.unwrap());
+        }
+        let webhook = WebhookBmc::create(
+            &ctx,
+            &mm,
+            WebhookForCreate {
+                project_id: project.get(),
+                url: format!("{}/hook", receiver.uri()),
+                events: vec!["message.created".into()],
+                secret: None,
+                description: String::new(),
+            },
+        )
+        .await
+        .unwrap();
+        let msg_c = MessageForCreate {
+            project_id: project,
+            sender_id: agents[0],
+            recipient_ids: vec![AgentId::new(agents[1].get())],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Deploy".into(),
+            body_md: "Shipping now".into(),
+            thread_id: None,
+            importance: None,
+            ack_required: false,
+        };
+        MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+
+        assert_eq!(webhooks_deliver(&mm, &WebhooksConfig::default()).await, 0);
+        let deliveries =
+            WebhookBmc::list_deliveries(&ctx, &mm, webhook.id, &DeliveryFilter::default())
+                .await
+                .unwrap();
+        let error = deliveries[0].error.as_deref().unwrap_or_default();
+        assert!(error.contains("private or internal"), "{}", error);
+        assert!(receiver.received_requests().await.unwrap().is_empty());
+    }
+
+    async fn webhooks_deliver(mm: &ModelManager, config: &WebhooksConfig) -> usize {
+        mouchak_mail_server::webhooks::deliver_pending(mm, config)
+            .await
+            .unwrap()
+    }
+}
+
+mod email_bridge_tests {
+    use super::*;
+    use mouchak_mail_common::config::EmailBridgeConfig;
+    use mouchak_mail_core::Ctx;
+    use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+    use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+    use mouchak_mail_core::model::project::ProjectBmc;
+    use mouchak_mail_core::model::project_settings::{
+        ProjectSettingsBmc, ProjectSettingsForUpdate,
+    };
+    use mouchak_mail_server::email_bridge;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Serves one SMTP session per greeting and returns the commands and the
+    /// message data of the last one.
+    ///
+    /// A `4xx` greeting ends its session right away.
+    async fn fake_relay(listener: TcpListener, greetings: &[&str]) -> (Vec<String>, String) {
+        let mut transcript = (Vec::new(), String::new());
+        for greeting in greetings {
+            transcript = serve_session(&listener, greeting).await;
+        }
+        transcript
+    }
+
+    async fn serve_session(listener: &TcpListener, greeting: &str) -> (Vec<String>, String) {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(socket);
+        stream.write_all(greeting.as_bytes()).await.unwrap();
+        let mut commands = Vec::new();
+        let mut data = String::new();
+        if greeting.starts_with('4') {
+            return (commands, data);
+        }
+        loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await.unwrap() == 0 {
+                break;
+            }
+            let command = line.trim_end().to_string();
+            let reply: &[u8] = if command.starts_with("EHLO") {
+                b"250-relay.test\r\n250 AUTH PLAIN\r\n"
+            } else if command.starts_with("AUTH") {
+                b"235 ok\r\n"
+            } else if command == "DATA" {
+                b"354 go ahead\r\n"
+            } else if command == "QUIT" {
+                b"221 bye\r\n"
+            } else {
+                b"250 ok\r\n"
+            };
+            stream.write_all(reply).await.unwrap();
+            if command == "DATA" {
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    if line == ".\r\n" {
+                        break;
+                    }
+                    data.push_str(&line);
+                }
+                stream.write_all(b"250 queued\r\n").await.unwrap();
+            }
+            let quit = command == "QUIT";
+            commands.push(command);
+            if quit {
+                break;
+            }
+        }
+        (commands, data)
+    }
+
+    #[tokio::test]
+    async fn test_urgent_mail_reaches_relay() {
+        let (state, _temp) = create_test_state().await;
+        let mm = state.mm.clone();
+        let ctx = Ctx::root_ctx();
+        let project = ProjectBmc::create(&ctx, &mm, "ops", "/ops").await.unwrap();
+        let mut agents = Vec::new();
+        for name in ["BlueLake", "GreenCastle"] {
+            let agent_c = AgentForCreate {
+                project_id: project,
+                name: name.into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            };
+            agents.push(AgentBmc::create(&ctx, &mm, agent_c).await.unwrap());
+        }
+        let settings_u = ProjectSettingsForUpdate {
+            email_bridge: Some(true),
+            email_recipients: Some("oncall@example.com".to_string()),
+            ..Default::default()
+        };
+        ProjectSettingsBmc::update(&ctx, &mm, project, settings_u)
+            .await
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = EmailBridgeConfig {
+            smtp_host: Some("127.0.0.1".to_string()),
+            smtp_port: listener.local_addr().unwrap().port(),
+            smtp_security: "none".to_string(),
+            smtp_username: Some("bridge".to_string()),
+            smtp_password: Some("hunter2".to_string()),
+            from: "bridge@example.com".to_string(),
+            timeout_seconds: 5,
+            ..Default::default()
+        };
+        // The first scan starts the cursor; nothing to send yet
+        assert_eq!(
+            email_bridge::deliver_pending(&mm, &config).await.unwrap(),
+            0
+        );
+
+        for (subject, importance) in [("Prod down", "urgent"), ("Lunch", "normal")] {
+            let msg_c = MessageForCreate {
+                project_id: project,
+                sender_id: agents[0],
+                recipient_ids: vec![AgentId::new(agents[1].get())],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.into(),
+                body_md: "Database is unreachable".into(),
+                thread_id: Some("INC-7".into()),
+                importance: Some(importance.into()),
+                ack_required: false,
+            };
+            MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+        }
+
+        let relay = tokio::spawn(async move {
+            fake_relay(listener, &["421 try later\r\n", "220 relay.test ESMTP\r\n"]).await
+        });
+        // The relay is busy: nothing is lost, the next scan retries
+        assert!(email_bridge::deliver_pending(&mm, &config).await.is_err());
+        assert_eq!(
+            email_bridge::deliver_pending(&mm, &config).await.unwrap(),
+            1
+        );
+        let (commands, data) = relay.await.unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                "EHLO example.com".to_string(),
+                // base64 of "\0bridge\0hunter2"
+                "AUTH PLAIN AGJyaWRnZQBodW50ZXIy".to_string(),
+                "MAIL FROM:<bridge@example.com>".to_string(),
+                "RCPT TO:<oncall@example.com>".to_string(),
+                "DATA".to_string(),
+                "QUIT".to_string(),
+            ]
+        );
+        assert!(data.contains("Subject: [ops] Prod down\r\n"));
+        assert!(data.contains("To: oncall@example.com\r\n"));
+        assert!(data.contains("X-Mouchak-Importance: urgent\r\n"));
+
+        // Everything has been handled
+        assert_eq!(
+            email_bridge::deliver_pending(&mm, &config).await.unwrap(),
+            0
+        );
+    }
+}
+
+// =============================================================================
+// Bulk Export Job Tests
+// =============================================================================
+
+mod export_job_tests {
+    use super::*;
+    use mouchak_mail_core::Ctx;
+    use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+    use mouchak_mail_core::model::export_job::ExportJobBmc;
+    use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+    use mouchak_mail_core::model::project::ProjectBmc;
+    use mouchak_mail_server::api::exports;
+
+    #[tokio::test]
+    async fn test_queue_poll_and_download() {
+        let (state, _temp) = create_test_state().await;
+        let mm = state.mm.clone();
+        let app = Router::new()
+            .route(
+                "/api/exports",
+                get(exports::list_export_jobs).post(exports::create_export_job),
+            )
+            .route("/api/exports/{job_id}", get(exports::get_export_job))
+            .route(
+                "/api/exports/{job_id}/download",
+                get(exports::download_export),
+            )
+            .with_state(state);
+
+        let ctx = Ctx::root_ctx();
+        let project = ProjectBmc::create(&ctx, &mm, "bulk", "/bulk")
+            .await
+            .unwrap();
+        let mut agents = Vec::new();
+        for name in ["BlueLake", "GreenCastle"] {
+            let agent_c = AgentForCreate {
+                project_id: project,
+                name: name.into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            };
+            agents.push(AgentBmc::create(&ctx, &mm, agent_c).await.unwrap());
+        }
+        for (subject, thread_id) in [("Plan", "T-1"), ("Re: Plan", "T-1"), ("Lunch", "T-2")] {
+            let msg_c = MessageForCreate {
+                project_id: project,
+                sender_id: agents[0],
+                recipient_ids: vec![AgentId::new(agents[1].get())],
+                cc_ids: None,
+                bcc_ids: None,
+                subject: subject.into(),
+                body_md: "Details inside".into(),
+                thread_id: Some(thread_id.into()),
+                importance: None,
+                ack_required: false,
+            };
+            MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+        }
+
+        let (status, job) = post_json(
+            app.clone(),
+            "/api/exports",
+            json!({
+                "project_slug": "bulk",
+                "format": "json",
+                "filters": {"thread_id": "T-1"},
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(job["status"], "queued");
+        assert!(job["download_url"].is_null());
+        let job_id = job["id"].as_i64().unwrap();
+
+        let uri = format!("/api/exports/{}/download", job_id);
+        let (status, _) = get_json(app.clone(), &uri).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        // The worker picks the job up
+        assert_eq!(ExportJobBmc::run_pending(&ctx, &mm).await.unwrap(), 1);
+
+        let (status, job) = get_json(app.clone(), &format!("/api/exports/{}", job_id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(job["status"], "completed");
+        assert_eq!(job["message_count"], 2);
+        assert_eq!(job["download_url"], uri);
+
+        let (status, exported) = get_json(app.clone(), &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        let subjects: Vec<&str> = exported
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["subject"].as_str().unwrap())
+            .collect();
+        assert_eq!(subjects.len(), 2);
+        assert!(!subjects.contains(&"Lunch"));
+
+        let (status, listed) = get_json(app.clone(), "/api/exports?project=bulk").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed[0]["id"], job_id);
+
+        let (status, _) = post_json(
+            app.clone(),
+            "/api/exports",
+            json!({"project_slug": "bulk", "filters": {"since": "yesterday"}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = post_json(
+            app.clone(),
+            "/api/exports",
+            json!({"project_slug": "bulk", "format": "pdf"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = post_json(
+            app,
+            "/api/exports",
+            json!({"project_slug": "bulk", "scrub": "light"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+// =============================================================================
+// Inbound Email Gateway Tests
+// =============================================================================
+
+mod email_ingest_tests {
+    use super::*;
+    use mouchak_mail_core::Ctx;
+    use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
+    use mouchak_mail_core::model::message::{MessageBmc, MessageForCreate};
+    use mouchak_mail_core::model::project::ProjectBmc;
+    use mouchak_mail_server::api::ingest;
+
+    async fn post_mail(app: Router, raw: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/ingest/email")
+            .header("Content-Type", "message/rfc822")
+            .body(Body::from(raw.replace('\n', "\r\n")))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (
+            status,
+            serde_json::from_slice(&body_bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reply_posted_into_thread() {
+        let mut config = AppConfig::default();
+        config.email_bridge.inbound_aliases = "lead@example.com=Lead".into();
+        let (state, _temp) = create_test_state_with_config(config).await;
+        let mm = state.mm.clone();
+        let app = Router::new()
+            .route("/api/ingest/email", post(ingest::ingest_email))
+            .with_state(state);
+
+        let ctx = Ctx::root_ctx();
+        let project = ProjectBmc::create(&ctx, &mm, "ops", "/ops").await.unwrap();
+        let mut agents = Vec::new();
+        for name in ["BlueLake", "Lead"] {
+            let agent_c = AgentForCreate {
+                project_id: project,
+                name: name.into(),
+                program: "test".into(),
+                model: "test".into(),
+                task_description: String::new(),
+            };
+            agents.push(AgentBmc::create(&ctx, &mm, agent_c).await.unwrap());
+        }
+        let msg_c = MessageForCreate {
+            project_id: project,
+            sender_id: agents[0],
+            recipient_ids: vec![AgentId::new(agents[1].get())],
+            cc_ids: None,
+            bcc_ids: None,
+            subject: "Prod down".into(),
+            body_md: "Database is unreachable".into(),
+            thread_id: Some("T-9".into()),
+            importance: Some("urgent".into()),
+            ack_required: false,
+        };
+        MessageBmc::create(&ctx, &mm, msg_c).await.unwrap();
+
+        let raw = "From: Lead <lead@example.com>
+To: mouchak-mail@localhost
+Subject: Re: [ops] Prod down
+Message-ID: <reply-9@mail.example.com>
+In-Reply-To: <thread-T-9.ops@localhost>
+Content-Type: text/plain; charset=utf-8
+
+On it.
+
+> Database is unreachable
+";
+        let (status, ingested) = post_mail(app.clone(), raw).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ingested["thread_id"], "T-9");
+        assert_eq!(ingested["duplicate"], false);
+        let message_id = ingested["message_id"].as_i64().unwrap();
+        let message = MessageBmc::get(&ctx, &mm, message_id.into()).await.unwrap();
+        assert_eq!(message.sender_name, "Lead");
+        assert_eq!(message.body_md, "On it.");
+        assert_eq!(
+            MessageBmc::get_recipients(&ctx, &mm, MessageId::new(message_id))
+                .await
+                .unwrap(),
+            vec!["BlueLake"]
+        );
+
+        let (status, again) = post_mail(app.clone(), raw).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(again["duplicate"], true);
+        assert_eq!(again["message_id"], message_id);
+
+        let (status, _) = post_mail(
+            app.clone(),
+            &raw.replace("lead@example.com", "mallory@example.com"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = post_mail(app, "Subject: no sender\n\nHello\n").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
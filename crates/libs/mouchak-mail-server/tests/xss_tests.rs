@@ -58,6 +58,10 @@ const XSS_VECTORS: &[(&str, &str)] = &[
     // Markdown-specific (if markdown rendering is enabled)
     ("md_link", "[XSS](javascript:alert(1))"),
     ("md_img", "![XSS](javascript:alert(1))"),
+    (
+        "md_img_data",
+        "![XSS](data:image/svg+xml,<svg onload=alert(1)>)",
+    ),
 ];
 
 /// Dangerous substrings that should NEVER appear unescaped in HTML output
@@ -299,6 +303,39 @@ mod tests {
             );
         }
     }
+
+    /// Test that the server-side markdown renderer neutralizes every vector
+    #[test]
+    fn test_markdown_renderer_sanitizes_xss_vectors() {
+        use mouchak_mail_core::utils::body_format::markdown_to_html;
+
+        for (name, vector) in XSS_VECTORS {
+            let rendered = markdown_to_html(vector);
+            // Markdown images render as <img>, so only their attributes are checked
+            for tag in DANGEROUS_UNESCAPED_TAGS.iter().filter(|t| **t != "<img ") {
+                assert!(
+                    !rendered.contains(tag),
+                    "XSS vulnerability: found unescaped '{}' in {} output",
+                    tag,
+                    name
+                );
+            }
+            for attr in [
+                "href=\"javascript:",
+                "src=\"javascript:",
+                "href=\"data:",
+                "src=\"data:",
+            ] {
+                assert!(
+                    !rendered.contains(attr),
+                    "Vector '{}' rendered with {}: {}",
+                    name,
+                    attr,
+                    rendered
+                );
+            }
+        }
+    }
 }
 
 // ============================================================================
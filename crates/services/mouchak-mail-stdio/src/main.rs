@@ -1,6 +1,6 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use mouchak_mail_common::config::{AppConfig, McpConfig};
+use mouchak_mail_common::config::AppConfig;
 use mouchak_mail_mcp::{run_sse, run_stdio, tools::get_tool_schemas};
 use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
@@ -17,10 +17,12 @@ struct Cli {
 enum Commands {
     /// Run the MCP server over stdio (default)
     Serve {
-        #[arg(short, long, env = "MOUCHAK_MCP__TRANSPORT", default_value = "stdio")]
-        transport: String,
-        #[arg(short, long, env = "MOUCHAK_MCP__PORT", default_value = "3000")]
-        port: u16,
+        /// Transport: stdio or sse (default: mcp.transport, "stdio")
+        #[arg(short, long)]
+        transport: Option<String>,
+        /// SSE port (default: mcp.port, 3000)
+        #[arg(short, long)]
+        port: Option<u16>,
         #[arg(long, env = "MOUCHAK_MAIL_HOST", default_value = "127.0.0.1")]
         host: String,
     },
@@ -43,19 +45,19 @@ fn setup_logging() -> Result<()> {
     Ok(())
 }
 
-async fn handle_serve(transport: String, port: u16) -> Result<()> {
+async fn handle_serve(transport: Option<String>, port: Option<u16>) -> Result<()> {
     setup_logging()?;
 
-    let mut mcp_config = McpConfig::from_env();
-    mcp_config.transport = transport.clone();
-    mcp_config.port = port;
-
-    let config = AppConfig {
-        mcp: mcp_config,
-        ..Default::default()
-    };
+    let mut loader = AppConfig::loader();
+    if let Some(transport) = transport {
+        loader = loader.cli_override("mcp.transport", "--transport", transport);
+    }
+    if let Some(p) = port {
+        loader = loader.cli_override("mcp.port", "--port", i64::from(p));
+    }
+    let config = loader.load()?;
 
-    if transport == "sse" {
+    if config.mcp.transport == "sse" {
         run_sse(config).await
     } else {
         run_stdio(config).await
@@ -94,8 +96,8 @@ fn handle_tools() {
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     let cmd = cli.command.unwrap_or(Commands::Serve {
-        transport: "stdio".to_string(),
-        port: 3000,
+        transport: None,
+        port: None,
         host: "127.0.0.1".to_string(),
     });
 
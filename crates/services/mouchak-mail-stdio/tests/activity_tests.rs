@@ -25,15 +25,9 @@ mod tests {
         let conn = db.connect().unwrap();
 
         let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
-        // Apply all schemas
-        let schema1 = include_str!("../../../../migrations/001_initial_schema.sql");
-        conn.execute_batch(schema1).await.unwrap();
-        let schema2 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-        conn.execute_batch(schema2).await.unwrap();
-        let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-        conn.execute_batch(schema3).await.unwrap();
-        let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-        conn.execute_batch(schema4).await.unwrap();
+        mouchak_mail_core::store::upgrade::migrate(&conn)
+            .await
+            .unwrap();
 
         let app_config = Arc::new(AppConfig::default());
         let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -65,9 +59,9 @@ mod tests {
         // 3. Send Message (Activity 2)
         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
         let msg_c = MessageForCreate {
-            project_id: pid.into(),
-            sender_id: aid.into(),
-            recipient_ids: vec![aid.into()],
+            project_id: pid,
+            sender_id: aid,
+            recipient_ids: vec![aid],
             cc_ids: None,
             bcc_ids: None,
             subject: "Act Msg".into(),
@@ -92,9 +86,7 @@ mod tests {
         ToolMetricBmc::create(&ctx, &mm, metric).await.unwrap();
 
         // 5. Query Activity
-        let activities = ActivityBmc::list_recent(&ctx, &mm, pid.into(), 10)
-            .await
-            .unwrap();
+        let activities = ActivityBmc::list_recent(&ctx, &mm, pid, 10).await.unwrap();
 
         // Verify count
         assert_eq!(activities.len(), 3);
@@ -28,14 +28,9 @@ mod tools_tests {
 
         // Apply migrations
         let _ = conn.execute("PRAGMA journal_mode=WAL;", ()).await;
-        let schema = include_str!("../../../../migrations/001_initial_schema.sql");
-        conn.execute_batch(schema).await.unwrap();
-        let schema002 = include_str!("../../../../migrations/002_agent_capabilities.sql");
-        conn.execute_batch(schema002).await.unwrap();
-        let schema3 = include_str!("../../../../migrations/003_tool_metrics.sql");
-        conn.execute_batch(schema3).await.unwrap();
-        let schema4 = include_str!("../../../../migrations/004_attachments.sql");
-        conn.execute_batch(schema4).await.unwrap();
+        mouchak_mail_core::store::upgrade::migrate(&conn)
+            .await
+            .unwrap();
 
         let app_config = Arc::new(AppConfig::default());
         let mm = ModelManager::new_for_test(conn, archive_root, app_config);
@@ -285,7 +280,7 @@ mod tools_tests {
         assert!(slot_id > 0);
 
         // Verify slot exists via list_active
-        let active = BuildSlotBmc::list_active(&ctx, &mm, project_id.into())
+        let active = BuildSlotBmc::list_active(&ctx, &mm, project_id)
             .await
             .expect("Failed to list active slots");
         assert_eq!(active.len(), 1);
@@ -297,7 +292,7 @@ mod tools_tests {
             .expect("Failed to release slot");
 
         // Verify released
-        let active_after = BuildSlotBmc::list_active(&ctx, &mm, project_id.into())
+        let active_after = BuildSlotBmc::list_active(&ctx, &mm, project_id)
             .await
             .unwrap();
         assert_eq!(active_after.len(), 0);
@@ -309,6 +304,10 @@ mod tools_tests {
         use mouchak_mail_core::model::agent::{AgentBmc, AgentForCreate};
         use mouchak_mail_core::model::agent_link::{AgentLinkBmc, AgentLinkForCreate};
         use mouchak_mail_core::model::project::ProjectBmc;
+        use mouchak_mail_core::model::project_contact_policy::{
+            ContactRuleAction, ContactRuleTarget, ProjectContactPolicyBmc,
+            ProjectContactRuleForCreate,
+        };
 
         let (mm, _temp) = create_test_mm().await;
         let ctx = Ctx::root_ctx();
@@ -339,6 +338,20 @@ mod tools_tests {
         };
         let agent_b_id = AgentBmc::create(&ctx, &mm, agent_b_c).await.unwrap();
 
+        // Project B must allow contact from project A
+        ProjectContactPolicyBmc::set_rule(
+            &ctx,
+            &mm,
+            ProjectContactRuleForCreate {
+                project_id: project_b_id,
+                action: ContactRuleAction::Allow,
+                target_kind: ContactRuleTarget::Project,
+                target: "project-a".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
         // Request contact using struct
         let link_c = AgentLinkForCreate {
             a_project_id: project_a_id.into(),
@@ -353,10 +366,9 @@ mod tools_tests {
         assert!(link_id > 0);
 
         // Check pending requests for Agent B
-        let pending =
-            AgentLinkBmc::list_pending_requests(&ctx, &mm, project_b_id.into(), agent_b_id.into())
-                .await
-                .expect("Failed to list pending requests");
+        let pending = AgentLinkBmc::list_pending_requests(&ctx, &mm, project_b_id, agent_b_id)
+            .await
+            .expect("Failed to list pending requests");
         assert_eq!(pending.len(), 1);
         assert_eq!(pending[0].status, "pending");
 
@@ -366,10 +378,9 @@ mod tools_tests {
             .expect("Failed to accept contact");
 
         // Verify accepted - check contacts list
-        let contacts =
-            AgentLinkBmc::list_contacts(&ctx, &mm, project_a_id.into(), agent_a_id.into())
-                .await
-                .expect("Failed to list contacts");
+        let contacts = AgentLinkBmc::list_contacts(&ctx, &mm, project_a_id, agent_a_id)
+            .await
+            .expect("Failed to list contacts");
         assert_eq!(contacts.len(), 1);
         assert_eq!(contacts[0].status, "accepted");
     }
@@ -407,7 +418,7 @@ mod tools_tests {
         assert!(macro_id > 0);
 
         // List macros
-        let macros = MacroDefBmc::list(&ctx, &mm, project_id.into())
+        let macros = MacroDefBmc::list(&ctx, &mm, project_id)
             .await
             .expect("Failed to list macros");
         assert_eq!(macros.len(), 6);
@@ -439,12 +450,12 @@ mod tools_tests {
             .unwrap();
 
         // Link both projects to the product
-        let link_a = ProductBmc::link_project(&ctx, &mm, product.id, project_a_id.into())
+        let link_a = ProductBmc::link_project(&ctx, &mm, product.id, project_a_id)
             .await
             .expect("Failed to link frontend");
         assert!(link_a > 0);
 
-        let link_b = ProductBmc::link_project(&ctx, &mm, product.id, project_b_id.into())
+        let link_b = ProductBmc::link_project(&ctx, &mm, product.id, project_b_id)
             .await
             .expect("Failed to link backend");
         assert!(link_b > 0);
@@ -465,7 +476,7 @@ mod tools_tests {
         assert_eq!(all_products[0].project_ids.len(), 2);
 
         // Unlink a project
-        let unlinked = ProductBmc::unlink_project(&ctx, &mm, product.id, project_a_id.into())
+        let unlinked = ProductBmc::unlink_project(&ctx, &mm, product.id, project_a_id)
             .await
             .expect("Failed to unlink");
         assert!(unlinked);
@@ -514,9 +525,9 @@ mod tools_tests {
 
         // Send a message
         let msg_c = MessageForCreate {
-            project_id: project_id.into(),
-            sender_id: sender_id.into(),
-            recipient_ids: vec![recipient_id.into()],
+            project_id,
+            sender_id,
+            recipient_ids: vec![recipient_id],
             cc_ids: None,
             bcc_ids: None,
             subject: "Test Export Message".to_string(),
@@ -599,9 +610,9 @@ mod tools_tests {
         let agent_id = AgentBmc::create(&ctx, &mm, agent_c).await.unwrap();
 
         let msg_c = MessageForCreate {
-            project_id: project_id.into(),
-            sender_id: agent_id.into(),
-            recipient_ids: vec![agent_id.into()], // self
+            project_id,
+            sender_id: agent_id,
+            recipient_ids: vec![agent_id], // self
             cc_ids: None,
             bcc_ids: None,
             subject: "Archive Me".to_string(),
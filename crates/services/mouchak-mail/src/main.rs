@@ -1,5 +1,5 @@
 use clap::{Args, CommandFactory, Parser, Subcommand};
-use mouchak_mail_common::config::AppConfig;
+use mouchak_mail_common::config::{AppConfig, ConfigLoader};
 use mouchak_mail_mcp::{docs::generate_markdown_docs, run_sse, run_stdio, tools::get_tool_schemas};
 use std::io::Write;
 use std::net::TcpListener;
@@ -49,6 +49,15 @@ struct Cli {
     /// Show examples for a flag or subcommand
     #[arg(long, global = true, num_args = 0.., allow_hyphen_values = true)]
     robot_examples: Option<Vec<String>>,
+
+    /// Keep config, database, archive and logs under one directory
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        env = "MOUCHAK_MAIL_PORTABLE_DIR"
+    )]
+    portable: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -105,11 +114,49 @@ enum Commands {
     /// Product management
     Products(ProductsArgs),
 
+    /// Project onboarding
+    Projects(ProjectsArgs),
+
     /// Pre-commit guard management
     Guard(GuardArgs),
 
     /// Mail/project status information
     Mail(MailArgs),
+
+    /// Tamper-evident event log (export, verification)
+    Events(EventsArgs),
+
+    /// Per-agent API tokens with scoped permissions
+    Token(TokenArgs),
+
+    /// Monitoring assets for the metrics on /metrics
+    Observability(ObservabilityArgs),
+
+    /// Database maintenance (integrity check, ANALYZE, VACUUM)
+    Db(DbArgs),
+
+    /// Message retention policies
+    Retention(RetentionArgs),
+
+    /// Remove stored attachment content no attachment references anymore
+    GcAttachments {
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+        /// Keep blobs used within this many seconds
+        #[arg(long, default_value_t = 3600)]
+        grace_seconds: i64,
+    },
+
+    /// Upgrade the data directory to this version's format
+    Upgrade {
+        /// Report what the upgrade would change without changing anything
+        #[arg(long)]
+        check: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Args)]
@@ -127,6 +174,12 @@ enum ConfigCommands {
     },
     /// Show the current binding port
     ShowPort,
+    /// Show every setting and the layer it came from (default, file, env, cli)
+    Sources {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Args)]
@@ -214,6 +267,37 @@ enum ProductsCommands {
     },
 }
 
+#[derive(Args)]
+struct ProjectsArgs {
+    #[command(subcommand)]
+    command: ProjectsCommands,
+}
+
+#[derive(Subcommand)]
+enum ProjectsCommands {
+    /// Create a project, optionally from a template bundle
+    Create {
+        /// Project slug
+        slug: String,
+        /// Human key, usually the project's absolute path
+        human_key: String,
+        /// Apply a template bundle from the data dir (`templates/<name>.json`)
+        #[arg(long)]
+        template: Option<String>,
+    },
+    /// Emit a ready-to-commit onboarding bundle (identity, hooks, AGENTS.md, MCP config)
+    InitBundle {
+        /// Project identifier (slug/key)
+        project: String,
+        /// Output directory (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        output: String,
+        /// Server URL embedded in the bundle (default: MOUCHAK_MAIL_URL or http://localhost:8765)
+        #[arg(long)]
+        server_url: Option<String>,
+    },
+}
+
 #[derive(Args)]
 struct ShareArgs {
     #[command(subcommand)]
@@ -377,6 +461,36 @@ struct MailArgs {
     command: MailCommands,
 }
 
+#[derive(Args)]
+struct EventsArgs {
+    #[command(subcommand)]
+    command: EventsCommands,
+}
+
+#[derive(Args)]
+struct TokenArgs {
+    #[command(subcommand)]
+    command: TokenCommands,
+}
+
+#[derive(Args)]
+struct ObservabilityArgs {
+    #[command(subcommand)]
+    command: ObservabilityCommands,
+}
+
+#[derive(Args)]
+struct DbArgs {
+    #[command(subcommand)]
+    command: DbCommands,
+}
+
+#[derive(Args)]
+struct RetentionArgs {
+    #[command(subcommand)]
+    command: RetentionCommands,
+}
+
 #[derive(Args)]
 struct SummarizeArgs {
     /// Project slug or path
@@ -437,6 +551,21 @@ enum ArchiveCommands {
         #[arg(long)]
         yes: bool,
     },
+    /// Pack loose objects in the Git archive and report space reclaimed
+    Gc {
+        /// Use `git gc --aggressive` (much slower, slightly smaller packs)
+        #[arg(long)]
+        aggressive: bool,
+        /// Only report object counts and sizes
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip unless at least this many loose objects exist
+        #[arg(long, default_value_t = 0)]
+        min_loose_objects: u64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -464,6 +593,166 @@ enum GuardCommands {
 enum MailCommands {
     /// Show mail/project status information
     Status,
+    /// Show the overseer inbox (escalations, guard violations)
+    Overseer {
+        /// Project slug (defaults to MOUCHAK_MAIL_PROJECT_SLUG)
+        #[arg(long, short)]
+        project: Option<String>,
+        /// Include messages already marked read
+        #[arg(long)]
+        all: bool,
+        /// Maximum number of messages to show
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print an agent's new messages as they arrive (reads the server's inbox stream)
+    Watch {
+        /// Project slug or human key
+        #[arg(long)]
+        project: String,
+        /// Agent whose inbox to watch
+        #[arg(long)]
+        agent: String,
+        /// Server URL
+        #[arg(
+            long,
+            env = "MOUCHAK_MAIL_URL",
+            default_value = "http://localhost:8765"
+        )]
+        url: String,
+        /// Bearer token for servers with HTTP_AUTH_MODE=bearer
+        #[arg(long, env = "HTTP_BEARER_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        /// Show a desktop notification for each new message
+        #[arg(long)]
+        notify: bool,
+    },
+    /// Import messages from an mbox file into a project
+    ImportMbox {
+        /// Path to the mbox file
+        file: String,
+        /// Project slug or human key
+        #[arg(long)]
+        project: String,
+        /// Map an address or display name to an agent: sender=agent (repeatable)
+        #[arg(long = "map", value_name = "SENDER=AGENT")]
+        map: Vec<String>,
+        /// Create placeholder agents for addresses that are not mapped
+        #[arg(long)]
+        create_agents: bool,
+    },
+    /// Export a project's mailbox
+    Export {
+        /// Project slug or human key
+        project: String,
+        /// Format: json, html, markdown, csv, mbox or eml
+        #[arg(long, default_value = "json")]
+        format: String,
+        /// Scrub mode: none, standard or aggressive
+        #[arg(long, default_value = "none")]
+        scrub: String,
+        /// Output file (default: stdout); for eml, the directory to write one file per message into
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Escalate messages whose acknowledgement is overdue
+    EscalateOverdue {
+        /// Hours after which an unacknowledged message is overdue
+        #[arg(long, default_value_t = 24)]
+        hours: i64,
+        /// Report what would be escalated without escalating
+        #[arg(long)]
+        dry_run: bool,
+        /// Escalation mode: log, file_reservation, overseer or remind (default: ACK_ESCALATION_MODE)
+        #[arg(long)]
+        mode: Option<String>,
+    },
+    /// Populate a reproducible demo dataset
+    Seed {
+        /// Number of projects to create
+        #[arg(long, default_value_t = 3)]
+        projects: usize,
+        /// Agents per project
+        #[arg(long, default_value_t = 5)]
+        agents: usize,
+        /// Messages per project
+        #[arg(long, default_value_t = 200)]
+        messages: usize,
+        /// RNG seed; identical seeds produce identical datasets
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum EventsCommands {
+    /// Export the event log chain as JSON Lines
+    Export {
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Verify the hash chain and report the first tampered record
+    VerifyChain {
+        /// Verify an exported JSON Lines file instead of the database
+        #[arg(long)]
+        file: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommands {
+    /// Issue a token to an agent; the secret is shown only once
+    Create {
+        /// Project slug or human key
+        #[arg(short, long)]
+        project: String,
+        /// Agent the token acts as
+        #[arg(short, long)]
+        agent: String,
+        /// Scopes such as read:inbox, write:messages, admin:reservations or admin
+        #[arg(short, long = "scope", required = true)]
+        scopes: Vec<String>,
+        /// What the token is for
+        #[arg(short, long, default_value = "")]
+        name: String,
+        /// Expire the token after this long (e.g. 30d, 12h)
+        #[arg(long)]
+        expires_in: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Revoke a token so it no longer authenticates
+    Revoke {
+        /// Token id
+        id: i64,
+    },
+    /// List tokens, including revoked and expired ones
+    List {
+        /// Only tokens of this project's agents
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ObservabilityCommands {
+    /// Write a Grafana dashboard and Prometheus alert rules for this server
+    ExportDashboards {
+        /// Output directory
+        #[arg(short, long, default_value = "observability")]
+        output: String,
+    },
 }
 
 #[derive(Args)]
@@ -472,6 +761,59 @@ struct ServeArgs {
     command: ServeCommands,
 }
 
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Check integrity, run ANALYZE and VACUUM when free pages pile up
+    Maintain {
+        /// Free page ratio (0.0-1.0) at which to vacuum [default: DB_VACUUM_FREE_RATIO]
+        #[arg(long)]
+        free_ratio: Option<f64>,
+        /// Vacuum regardless of the free page ratio
+        #[arg(long)]
+        vacuum: bool,
+        /// Only check integrity and report sizes
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum RetentionCommands {
+    /// Purge messages past their project's retention limits
+    Run {
+        /// Only list what would be purged
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the retention limits in effect for a project
+    Show {
+        /// Project slug or human key
+        project: String,
+    },
+    /// Override retention limits for a project (0 turns a limit off)
+    Set {
+        /// Project slug or human key
+        project: String,
+        /// Days a message is kept
+        #[arg(long)]
+        max_age_days: Option<u64>,
+        /// Messages the project keeps
+        #[arg(long)]
+        max_messages: Option<u64>,
+        /// archive (keep git archive copies) or delete
+        #[arg(long)]
+        action: Option<mouchak_mail_common::config::RetentionAction>,
+    },
+    /// Return a project to the server-wide retention config
+    Clear {
+        /// Project slug or human key
+        project: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum ServeCommands {
     /// Start the HTTP API Server
@@ -487,13 +829,16 @@ enum ServeCommands {
     },
     /// Start the MCP Server (Stdio or SSE)
     Mcp {
-        #[arg(long, default_value = "stdio")]
-        transport: String,
-        #[arg(short, long, default_value = "3000")]
-        port: u16,
+        /// Transport: stdio or sse (default: mcp.transport, "stdio")
+        #[arg(long)]
+        transport: Option<String>,
+        /// SSE port (default: mcp.port, 3000)
+        #[arg(short, long)]
+        port: Option<u16>,
     },
 }
 
+/// Logs to stderr and, in portable mode, to daily files in `<dir>/logs`.
 fn setup_tracing(json_logs: bool) -> anyhow::Result<()> {
     use tracing_subscriber::{
         EnvFilter, Layer, fmt, layer::SubscriberExt, util::SubscriberInitExt,
@@ -508,15 +853,35 @@ fn setup_tracing(json_logs: bool) -> anyhow::Result<()> {
         fmt::layer().pretty().with_writer(std::io::stderr).boxed()
     };
 
+    let file_layer = match mouchak_mail_common::paths::log_dir() {
+        Some(dir) => {
+            std::fs::create_dir_all(&dir)?;
+            let appender = tracing_appender::rolling::daily(dir, "mouchak-mail.log");
+            let layer = fmt::layer().with_ansi(false).with_writer(appender);
+            Some(if json_logs {
+                layer.json().boxed()
+            } else {
+                layer.boxed()
+            })
+        }
+        None => None,
+    };
+
     tracing_subscriber::registry()
         .with(env_filter)
         .with(layer)
+        .with(file_layer)
         .try_init()?;
     Ok(())
 }
 
 fn load_config() -> AppConfig {
-    AppConfig::load().unwrap_or_else(|e| {
+    load_config_with(AppConfig::loader())
+}
+
+/// Loads config with CLI flag overrides applied on top of files and env.
+fn load_config_with(loader: ConfigLoader) -> AppConfig {
+    loader.load().unwrap_or_else(|e| {
         tracing::warn!("Failed to load config file: {}. Using defaults.", e);
         AppConfig::default()
     })
@@ -593,17 +958,16 @@ pub fn validate_port(port: u16) -> Result<(), PortInUseError> {
     }
 }
 
-async fn handle_serve_http(
-    port: Option<u16>,
-    with_ui: bool,
-    no_ui: bool,
-    mut config: AppConfig,
-) -> anyhow::Result<()> {
+async fn handle_serve_http(port: Option<u16>, with_ui: bool, no_ui: bool) -> anyhow::Result<()> {
+    let mut loader = AppConfig::loader();
     if let Some(p) = port {
-        config.server.port = p;
+        loader = loader.cli_override("server.port", "--port", i64::from(p));
     }
-    // --no-ui takes precedence, otherwise use --with-ui value
-    config.server.serve_ui = !no_ui && with_ui;
+    // --no-ui takes precedence; otherwise server.serve_ui from config applies
+    if no_ui || !with_ui {
+        loader = loader.cli_override("server.serve_ui", "--no-ui", false);
+    }
+    let config = load_config_with(loader);
 
     // Validate port availability before starting server
     if let Err(e) = validate_port(config.server.port) {
@@ -616,15 +980,17 @@ async fn handle_serve_http(
     Ok(())
 }
 
-async fn handle_serve_mcp(
-    transport: String,
-    port: u16,
-    mut config: AppConfig,
-) -> anyhow::Result<()> {
-    config.mcp.transport = transport.clone();
-    config.mcp.port = port;
-    info!("Starting MCP Server ({})", transport);
-    if transport == "sse" {
+async fn handle_serve_mcp(transport: Option<String>, port: Option<u16>) -> anyhow::Result<()> {
+    let mut loader = AppConfig::loader();
+    if let Some(transport) = transport {
+        loader = loader.cli_override("mcp.transport", "--transport", transport);
+    }
+    if let Some(p) = port {
+        loader = loader.cli_override("mcp.port", "--port", i64::from(p));
+    }
+    let config = load_config_with(loader);
+    info!("Starting MCP Server ({})", config.mcp.transport);
+    if config.mcp.transport == "sse" {
         run_sse(config).await?;
     } else {
         run_stdio(config).await?;
@@ -922,11 +1288,7 @@ async fn handle_service_status(port: u16) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn handle_service_start(
-    port: u16,
-    background: bool,
-    config: AppConfig,
-) -> anyhow::Result<()> {
+async fn handle_service_start(port: u16, background: bool) -> anyhow::Result<()> {
     if background {
         println!("Starting server on port {} (background)...", port);
         // Validate port first
@@ -960,12 +1322,12 @@ async fn handle_service_start(
             Err(e) => anyhow::bail!("Failed to spawn server: {}", e),
         }
     } else {
-        handle_serve_http(Some(port), true, false, config).await?;
+        handle_serve_http(Some(port), true, false).await?;
     }
     Ok(())
 }
 
-async fn handle_service_restart(port: u16, config: AppConfig) -> anyhow::Result<()> {
+async fn handle_service_restart(port: u16) -> anyhow::Result<()> {
     println!("Restarting server on port {}...", port);
 
     // Stop existing
@@ -977,7 +1339,7 @@ async fn handle_service_restart(port: u16, config: AppConfig) -> anyhow::Result<
     }
 
     // Start in background (default for restart)
-    handle_service_start(port, true, config).await?;
+    handle_service_start(port, true).await?;
 
     Ok(())
 }
@@ -1061,6 +1423,7 @@ async fn handle_export_static_data(
     use mouchak_mail_core::model::export::{ScrubMode, Scrubber};
     use mouchak_mail_core::model::message::MessageBmc;
     use mouchak_mail_core::model::project::ProjectBmc;
+    use mouchak_mail_core::utils::diagram::markdown_to_html_with_diagrams;
     use serde_json::json;
     use std::collections::HashMap;
     use std::fs;
@@ -1168,6 +1531,8 @@ async fn handle_export_static_data(
         let messages = MessageBmc::list_recent(&ctx, &mm, project.id, msg_limit).await?;
 
         for msg in messages {
+            let body_md = scrubber.scrub_body(&msg.body_md);
+            let body_rendered = markdown_to_html_with_diagrams(&body_md).await;
             let msg_json = json!({
                 "id": msg.id,
                 "project_id": msg.project_id,
@@ -1176,7 +1541,8 @@ async fn handle_export_static_data(
                 "sender_name": scrubber.scrub_name(&msg.sender_name),
                 "thread_id": msg.thread_id,
                 "subject": scrubber.scrub(&msg.subject),
-                "body_md": scrubber.scrub_body(&msg.body_md),
+                "body_rendered": body_rendered,
+                "body_md": body_md,
                 "importance": msg.importance,
                 "ack_required": msg.ack_required,
                 "created_ts": msg.created_ts.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
@@ -1968,12 +2334,11 @@ async fn enable_github_pages(
 fn handle_config_command(cmd: ConfigCommands) -> anyhow::Result<()> {
     match cmd {
         ConfigCommands::SetPort { port } => {
-            let home =
-                std::env::var("HOME").map_err(|_| anyhow::anyhow!("HOME env var not set"))?;
-            let config_dir = PathBuf::from(&home).join(".mouchak-mail");
-            let config_path = config_dir.join("config.toml");
-
-            std::fs::create_dir_all(&config_dir)?;
+            let config_path = mouchak_mail_common::paths::user_config_file()
+                .ok_or_else(|| anyhow::anyhow!("HOME env var not set"))?;
+            if let Some(config_dir) = config_path.parent() {
+                std::fs::create_dir_all(config_dir)?;
+            }
 
             let content = if config_path.exists() {
                 std::fs::read_to_string(&config_path)?
@@ -2001,6 +2366,18 @@ fn handle_config_command(cmd: ConfigCommands) -> anyhow::Result<()> {
             let config = load_config();
             println!("{}", config.server.port);
         }
+        ConfigCommands::Sources { json } => {
+            let resolved = AppConfig::loader().resolve()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&resolved.sources)?);
+            } else {
+                println!("{:<36} {:<28} SOURCE", "KEY", "VALUE");
+                println!("{}", "-".repeat(90));
+                for entry in &resolved.sources {
+                    println!("{:<36} {:<28} {}", entry.key, entry.value, entry.source);
+                }
+            }
+        }
     }
     Ok(())
 }
@@ -2026,7 +2403,7 @@ fn handle_robot_status(format: &str) -> u8 {
     let mut exit_code = 0;
 
     // 1. Database Check
-    let db_path = std::path::Path::new("data/mouchak_mail.db");
+    let db_path = mouchak_mail_common::paths::db_file();
     checks.insert(
         "database".to_string(),
         CheckResult {
@@ -2042,7 +2419,7 @@ fn handle_robot_status(format: &str) -> u8 {
     );
 
     // 2. Git Archive Check
-    let archive_path = std::path::Path::new("data/archive");
+    let archive_path = mouchak_mail_common::paths::archive_root();
     checks.insert(
         "git_archive".to_string(),
         CheckResult {
@@ -2212,50 +2589,6 @@ async fn handle_guard_status() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Check if a file path matches a reservation pattern.
-///
-/// Pattern matching rules:
-/// - `*` matches all paths
-/// - `src/**` matches all paths under `src/` directory
-/// - `src/main.rs` matches exactly that path or as suffix with path separator
-/// - Empty patterns never match
-fn path_matches_pattern(path: &str, pattern: &str) -> bool {
-    if pattern.is_empty() {
-        return false;
-    }
-
-    // Wildcard matches everything
-    if pattern == "*" {
-        return true;
-    }
-
-    // Double-star suffix (e.g., "src/**") - must have at least one char before /**
-    // Matches paths UNDER the directory, not the directory itself
-    if pattern.len() > 3 && pattern.ends_with("/**") {
-        let prefix = &pattern[..pattern.len() - 3];
-        // Path must start with prefix and have a path separator after it
-        return path.starts_with(prefix)
-            && path.len() > prefix.len()
-            && path[prefix.len()..].starts_with('/');
-    }
-
-    // Exact prefix match
-    if let Some(rest) = path.strip_prefix(pattern) {
-        // Must be exact match or followed by path separator
-        return rest.is_empty() || rest.starts_with('/');
-    }
-
-    // Check if pattern appears as a path segment (not just substring)
-    let search = format!("/{}/", pattern);
-    if path.contains(&search) {
-        return true;
-    }
-
-    // Check if pattern is at the end after a path separator
-    let suffix = format!("/{}", pattern);
-    path.ends_with(&suffix)
-}
-
 async fn handle_guard_check(
     stdin_nul: bool,
     advisory: bool,
@@ -2309,10 +2642,15 @@ async fn handle_guard_check(
         .build()
         .unwrap_or_else(|_| reqwest::Client::new());
 
-    let reservations_result = client
-        .post(format!("{}/api/file_reservations/list", url))
+    // Matching happens server-side against compiled patterns; our own
+    // reservations (AGENT_NAME, as in the git hooks) never block us
+    let agent_name = std::env::var("AGENT_NAME").ok().filter(|a| !a.is_empty());
+    let verdicts_result = client
+        .post(format!("{}/api/file_reservations/check_paths", url))
         .json(&serde_json::json!({
-            "project_slug": project_slug
+            "project_slug": project_slug,
+            "paths": paths,
+            "agent_name": agent_name,
         }))
         .send()
         .await;
@@ -2320,30 +2658,29 @@ async fn handle_guard_check(
     // (path, agent_name, pattern) tuples for conflicting paths
     let mut conflicting_paths: Vec<(String, String, String)> = Vec::new();
 
-    match reservations_result {
+    match verdicts_result {
         Ok(resp) if resp.status().is_success() => match resp.json::<serde_json::Value>().await {
             Ok(json) => {
-                if let Some(reservations) = json.get("reservations").and_then(|r| r.as_array()) {
-                    for path in &paths {
-                        for reservation in reservations {
-                            let pattern = reservation
-                                .get("path_pattern")
-                                .and_then(|p| p.as_str())
-                                .unwrap_or("");
-                            let agent_name = reservation
-                                .get("agent_name")
-                                .and_then(|a| a.as_str())
-                                .unwrap_or("unknown");
-
-                            if path_matches_pattern(path, pattern) {
-                                conflicting_paths.push((
-                                    path.clone(),
-                                    agent_name.to_string(),
-                                    pattern.to_string(),
-                                ));
-                                break;
-                            }
-                        }
+                for verdict in json.as_array().into_iter().flatten() {
+                    let path = verdict.get("path").and_then(|p| p.as_str()).unwrap_or("");
+                    let conflict = verdict
+                        .get("conflicts")
+                        .and_then(|c| c.as_array())
+                        .and_then(|c| c.first());
+                    if let Some(conflict) = conflict {
+                        let pattern = conflict
+                            .get("path_pattern")
+                            .and_then(|p| p.as_str())
+                            .unwrap_or("");
+                        let agent_name = conflict
+                            .get("agent_name")
+                            .and_then(|a| a.as_str())
+                            .unwrap_or("unknown");
+                        conflicting_paths.push((
+                            path.to_string(),
+                            agent_name.to_string(),
+                            pattern.to_string(),
+                        ));
                     }
                 }
             }
@@ -2421,6 +2758,12 @@ async fn main() -> anyhow::Result<()> {
 
     let cli = Cli::parse();
 
+    // Before anything resolves a path
+    if let Some(dir) = &cli.portable {
+        mouchak_mail_common::paths::set_portable_dir(dir)
+            .map_err(|e| anyhow::anyhow!("Invalid --portable {}: {}", dir.display(), e))?;
+    }
+
     if cli.robot_help {
         handle_robot_help(&cli.format);
         return Ok(());
@@ -2445,7 +2788,6 @@ async fn main() -> anyhow::Result<()> {
     }
 
     setup_tracing(cli.log_format == "json")?;
-    let config = load_config();
 
     match cli.command {
         Some(Commands::Serve(args)) => match args.command {
@@ -2453,10 +2795,8 @@ async fn main() -> anyhow::Result<()> {
                 port,
                 with_ui,
                 no_ui,
-            } => handle_serve_http(port, with_ui, no_ui, config).await?,
-            ServeCommands::Mcp { transport, port } => {
-                handle_serve_mcp(transport, port, config).await?
-            }
+            } => handle_serve_http(port, with_ui, no_ui).await?,
+            ServeCommands::Mcp { transport, port } => handle_serve_mcp(transport, port).await?,
         },
         Some(Commands::Health { url }) => handle_health(url).await?,
         Some(Commands::Config(args)) => handle_config_command(args.command)?,
@@ -2467,11 +2807,11 @@ async fn main() -> anyhow::Result<()> {
         },
         Some(Commands::Service(args)) => match args.command {
             ServiceCommands::Start { port, background } => {
-                handle_service_start(port, background, config).await?
+                handle_service_start(port, background).await?
             }
             ServiceCommands::Stop { port } => handle_service_stop(port)?,
             ServiceCommands::Status { port } => handle_service_status(port).await?,
-            ServiceCommands::Restart { port } => handle_service_restart(port, config).await?,
+            ServiceCommands::Restart { port } => handle_service_restart(port).await?,
         },
         Some(Commands::Share(args)) => match args.command {
             ShareCommands::Keypair { output } => handle_share_keypair(output)?,
@@ -2521,8 +2861,19 @@ async fn main() -> anyhow::Result<()> {
         Some(Commands::Archive(args)) => handle_archive_command(args.command).await?,
         Some(Commands::Summarize(args)) => handle_summarize(args).await?,
         Some(Commands::Products(args)) => handle_products(args).await?,
+        Some(Commands::Projects(args)) => handle_projects(args).await?,
         Some(Commands::Guard(args)) => handle_guard(args).await?,
         Some(Commands::Mail(args)) => handle_mail(args).await?,
+        Some(Commands::Events(args)) => handle_events(args).await?,
+        Some(Commands::Token(args)) => handle_token(args).await?,
+        Some(Commands::Observability(args)) => handle_observability(args)?,
+        Some(Commands::Db(args)) => handle_db(args).await?,
+        Some(Commands::Retention(args)) => handle_retention(args).await?,
+        Some(Commands::GcAttachments {
+            dry_run,
+            grace_seconds,
+        }) => handle_gc_attachments(dry_run, grace_seconds).await?,
+        Some(Commands::Upgrade { check, json }) => handle_upgrade(check, json).await?,
         Some(Commands::Version) => println!("mouchak-mail v{}", env!("CARGO_PKG_VERSION")),
         None => {
             Cli::command().print_help()?;
@@ -2581,6 +2932,7 @@ async fn handle_archive_save(
     include_git: bool,
 ) -> anyhow::Result<()> {
     use chrono::Utc;
+    use mouchak_mail_common::paths;
     use std::fs;
     use std::io::Write;
 
@@ -2599,9 +2951,9 @@ async fn handle_archive_save(
         .compression_method(zip::CompressionMethod::Deflated);
 
     // Add database file
-    let db_path = std::path::Path::new("data/mouchak_mail.db");
+    let db_path = paths::db_file();
     if db_path.exists() {
-        let content = fs::read(db_path)?;
+        let content = fs::read(&db_path)?;
         zip.start_file("mouchak_mail.db", options)?;
         zip.write_all(&content)?;
         println!("✓ Added database to archive");
@@ -2611,9 +2963,9 @@ async fn handle_archive_save(
 
     // Add git storage if requested (use data/archive which is the actual path)
     if include_git {
-        let git_storage = std::path::Path::new("data/archive");
+        let git_storage = paths::archive_root();
         if git_storage.exists() {
-            add_directory_to_zip(&mut zip, git_storage, "git_storage", options)?;
+            add_directory_to_zip(&mut zip, &git_storage, "git_storage", options)?;
             println!("✓ Added git storage to archive");
         }
     }
@@ -2702,6 +3054,7 @@ fn handle_archive_list(archives_dir: &std::path::Path, json: bool) -> anyhow::Re
 
 /// Restore from a backup archive
 fn handle_archive_restore(file: &str, yes: bool) -> anyhow::Result<()> {
+    use mouchak_mail_common::paths;
     use std::fs;
     use std::io::Write;
 
@@ -2726,12 +3079,11 @@ fn handle_archive_restore(file: &str, yes: bool) -> anyhow::Result<()> {
 
     // Restore database
     if let Ok(mut db_file) = archive.by_name("mouchak_mail.db") {
-        let db_path = std::path::Path::new("data/mouchak_mail.db");
-        fs::create_dir_all("data")?;
+        fs::create_dir_all(paths::data_dir())?;
         let mut content = Vec::new();
         use std::io::Read;
         db_file.read_to_end(&mut content)?;
-        fs::write(db_path, content)?;
+        fs::write(paths::db_file(), content)?;
         println!("✓ Restored database");
     }
 
@@ -2743,7 +3095,7 @@ fn handle_archive_restore(file: &str, yes: bool) -> anyhow::Result<()> {
         if name.starts_with(git_prefix) && !file.is_dir() {
             // Map git_storage/ to data/archive/
             let relative_path = name.strip_prefix(git_prefix).unwrap_or(&name);
-            let dest_path = std::path::Path::new("data/archive").join(relative_path);
+            let dest_path = paths::archive_root().join(relative_path);
             if let Some(parent) = dest_path.parent() {
                 fs::create_dir_all(parent)?;
             }
@@ -2766,6 +3118,7 @@ async fn handle_archive_clear_and_reset(
     label: Option<String>,
     yes: bool,
 ) -> anyhow::Result<()> {
+    use mouchak_mail_common::paths;
     use std::fs;
     use std::io::Write;
 
@@ -2788,23 +3141,23 @@ async fn handle_archive_clear_and_reset(
     }
 
     // Remove database
-    let db_path = std::path::Path::new("data/mouchak_mail.db");
+    let db_path = paths::db_file();
     if db_path.exists() {
-        fs::remove_file(db_path)?;
+        fs::remove_file(&db_path)?;
         println!("✓ Removed database");
     }
 
     // Remove git storage (data/archive)
-    let git_storage = std::path::Path::new("data/archive");
+    let git_storage = paths::archive_root();
     if git_storage.exists() {
-        fs::remove_dir_all(git_storage)?;
+        fs::remove_dir_all(&git_storage)?;
         println!("✓ Removed git storage");
     }
 
     // Remove attachments
-    let attachments = std::path::Path::new("data/attachments");
+    let attachments = paths::attachments_dir();
     if attachments.exists() {
-        fs::remove_dir_all(attachments)?;
+        fs::remove_dir_all(&attachments)?;
         println!("✓ Removed attachments");
     }
 
@@ -2813,7 +3166,7 @@ async fn handle_archive_clear_and_reset(
 }
 
 async fn handle_archive_command(cmd: ArchiveCommands) -> anyhow::Result<()> {
-    let archives_dir = std::path::Path::new("data/archives");
+    let archives_dir = &mouchak_mail_common::paths::backups_dir();
 
     match cmd {
         ArchiveCommands::Save { label, include_git } => {
@@ -2826,52 +3179,301 @@ async fn handle_archive_command(cmd: ArchiveCommands) -> anyhow::Result<()> {
             label,
             yes,
         } => handle_archive_clear_and_reset(archives_dir, archive, label, yes).await,
+        ArchiveCommands::Gc {
+            aggressive,
+            dry_run,
+            min_loose_objects,
+            json,
+        } => handle_archive_gc(aggressive, dry_run, min_loose_objects, json).await,
     }
 }
 
-/// Helper to add a directory recursively to a ZIP archive
-fn add_directory_to_zip<W: std::io::Write + std::io::Seek>(
-    zip: &mut zip::ZipWriter<W>,
-    dir: &std::path::Path,
-    prefix: &str,
-    options: zip::write::SimpleFileOptions,
+/// Compact the Git archive
+async fn handle_archive_gc(
+    aggressive: bool,
+    dry_run: bool,
+    min_loose_objects: u64,
+    json: bool,
 ) -> anyhow::Result<()> {
-    use std::fs;
-    use std::io::Read;
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::archive_gc::{ArchiveGcBmc, GcOptions};
 
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        let Some(file_name) = path.file_name() else {
-            continue;
-        };
-        let name = format!("{}/{}", prefix, file_name.to_string_lossy());
+    let config = load_config();
+    let mm = ModelManager::new(std::sync::Arc::new(config)).await?;
+    let options = GcOptions {
+        min_loose_objects,
+        aggressive,
+        dry_run,
+    };
+    let report = ArchiveGcBmc::gc(&Ctx::root_ctx(), &mm, &options).await?;
 
-        if path.is_dir() {
-            add_directory_to_zip(zip, &path, &name, options)?;
-        } else {
-            let mut file = fs::File::open(&path)?;
-            let mut content = Vec::new();
-            file.read_to_end(&mut content)?;
-            zip.start_file(&name, options)?;
-            std::io::Write::write_all(zip, &content)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    println!(
+        "Before: {} loose objects ({}), {} packs ({})",
+        report.before.loose_objects,
+        format_bytes(report.before.loose_bytes),
+        report.before.packs,
+        format_bytes(report.before.pack_bytes)
+    );
+    match &report.skipped {
+        Some(reason) => println!("Not compacted: {}", reason),
+        None => {
+            println!(
+                "After:  {} loose objects ({}), {} packs ({})",
+                report.after.loose_objects,
+                format_bytes(report.after.loose_bytes),
+                report.after.packs,
+                format_bytes(report.after.pack_bytes)
+            );
+            println!(
+                "Reclaimed {} in {} ms",
+                format_bytes(report.reclaimed_bytes),
+                report.duration_ms
+            );
         }
     }
-
     Ok(())
 }
 
-/// Read metadata.json from a zip archive, returning empty JSON object on any error.
-fn read_archive_metadata(path: &std::path::Path) -> serde_json::Value {
-    let Ok(file) = std::fs::File::open(path) else {
-        return serde_json::json!({});
-    };
-    let Ok(mut zip) = zip::ZipArchive::new(file) else {
-        return serde_json::json!({});
-    };
-    let Ok(mut meta_file) = zip.by_name("metadata.json") else {
-        return serde_json::json!({});
-    };
+// --- Database Command Handler ---
+
+async fn handle_db(args: DbArgs) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::db_maintenance::{DbMaintenanceBmc, MaintainOptions};
+
+    match args.command {
+        DbCommands::Maintain {
+            free_ratio,
+            vacuum,
+            dry_run,
+            json,
+        } => {
+            let config = load_config();
+            let options = MaintainOptions {
+                vacuum_free_ratio: free_ratio.unwrap_or(config.db_maintenance.vacuum_free_ratio),
+                force_vacuum: vacuum,
+                dry_run,
+            };
+            let mm = ModelManager::new(std::sync::Arc::new(config)).await?;
+            let report = DbMaintenanceBmc::maintain(&Ctx::root_ctx(), &mm, &options).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!(
+                    "Before: {} ({} free, {:.1}%)",
+                    format_bytes(report.before.size_bytes().max(0) as u64),
+                    format_bytes(report.before.free_bytes().max(0) as u64),
+                    report.before.free_ratio() * 100.0
+                );
+                if !report.integrity_ok() {
+                    println!("Integrity check FAILED; nothing was changed:");
+                    for problem in &report.integrity {
+                        println!("  {}", problem);
+                    }
+                } else if dry_run {
+                    println!("Integrity check passed (dry run, nothing changed)");
+                } else {
+                    println!(
+                        "After:  {} ({} free)",
+                        format_bytes(report.after.size_bytes().max(0) as u64),
+                        format_bytes(report.after.free_bytes().max(0) as u64)
+                    );
+                    println!(
+                        "Integrity ok, analyzed, {} in {} ms",
+                        if report.vacuumed {
+                            format!(
+                                "vacuumed (reclaimed {})",
+                                format_bytes(report.reclaimed_bytes as u64)
+                            )
+                        } else {
+                            "not vacuumed".to_string()
+                        },
+                        report.duration_ms
+                    );
+                }
+            }
+            if !report.integrity_ok() {
+                anyhow::bail!("Database integrity check failed");
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+// --- Retention Command Handler ---
+
+async fn handle_retention(args: RetentionArgs) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::project::ProjectBmc;
+    use mouchak_mail_core::model::retention::{
+        RetentionBmc, RetentionPolicy, RetentionPolicyForUpdate,
+    };
+
+    fn print_policy(slug: &str, policy: &RetentionPolicy) {
+        let or_none = |limit: Option<u64>| limit.map_or("none".to_string(), |v| v.to_string());
+        println!("Retention for '{}':", slug);
+        println!("  max age (days): {}", or_none(policy.max_age_days));
+        println!("  max messages:   {}", or_none(policy.max_messages));
+        println!("  action:         {}", policy.action.as_str());
+        match policy.updated_ts {
+            Some(ts) => println!("  project policy set {}", ts),
+            None => println!("  from server config"),
+        }
+    }
+
+    let ctx = Ctx::root_ctx();
+    let mm = ModelManager::new(std::sync::Arc::new(load_config())).await?;
+
+    match args.command {
+        RetentionCommands::Run { dry_run } => {
+            let report = RetentionBmc::run(&ctx, &mm, dry_run).await?;
+            let verb = if dry_run { "Would purge" } else { "Purged" };
+            println!("{} {} messages", verb, report.purged());
+            for purge in &report.projects {
+                println!(
+                    "  {}: {} messages ({})",
+                    purge.project_slug,
+                    purge.messages.len(),
+                    purge.action.as_str()
+                );
+                if dry_run {
+                    for message in &purge.messages {
+                        println!(
+                            "    #{} {} [{:?}] {}",
+                            message.id, message.created_ts, message.reason, message.subject
+                        );
+                    }
+                }
+                if let Some(path) = &purge.audit_path {
+                    println!("    audit: {}", path);
+                }
+            }
+        }
+        RetentionCommands::Show { project } => {
+            let project = ProjectBmc::get_by_identifier(&ctx, &mm, &project).await?;
+            let policy = RetentionBmc::get_policy(&ctx, &mm, project.id).await?;
+            print_policy(&project.slug, &policy);
+        }
+        RetentionCommands::Set {
+            project,
+            max_age_days,
+            max_messages,
+            action,
+        } => {
+            let project = ProjectBmc::get_by_identifier(&ctx, &mm, &project).await?;
+            let policy = RetentionBmc::set_policy(
+                &ctx,
+                &mm,
+                project.id,
+                RetentionPolicyForUpdate {
+                    max_age_days,
+                    max_messages,
+                    action,
+                },
+            )
+            .await?;
+            print_policy(&project.slug, &policy);
+        }
+        RetentionCommands::Clear { project } => {
+            let project = ProjectBmc::get_by_identifier(&ctx, &mm, &project).await?;
+            if RetentionBmc::clear_policy(&ctx, &mm, project.id).await? {
+                println!("Cleared retention policy of '{}'", project.slug);
+            } else {
+                println!("'{}' has no retention policy of its own", project.slug);
+            }
+        }
+    }
+    Ok(())
+}
+
+// --- Attachment GC Command Handler ---
+
+async fn handle_gc_attachments(dry_run: bool, grace_seconds: i64) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::attachment_blob::AttachmentBlobBmc;
+
+    let mm = ModelManager::new(std::sync::Arc::new(load_config())).await?;
+    let report = AttachmentBlobBmc::gc(&Ctx::root_ctx(), &mm, grace_seconds, dry_run).await?;
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    println!(
+        "{} {} unreferenced attachment blobs ({})",
+        verb,
+        report.blobs,
+        format_bytes(report.bytes.max(0) as u64)
+    );
+    if report.failed > 0 {
+        println!("  failed (kept for the next run): {}", report.failed);
+    }
+    Ok(())
+}
+
+/// Helper to add a directory recursively to a ZIP archive
+fn add_directory_to_zip<W: std::io::Write + std::io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    dir: &std::path::Path,
+    prefix: &str,
+    options: zip::write::SimpleFileOptions,
+) -> anyhow::Result<()> {
+    use std::fs;
+    use std::io::Read;
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let Some(file_name) = path.file_name() else {
+            continue;
+        };
+        let name = format!("{}/{}", prefix, file_name.to_string_lossy());
+
+        if path.is_dir() {
+            add_directory_to_zip(zip, &path, &name, options)?;
+        } else {
+            let mut file = fs::File::open(&path)?;
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            zip.start_file(&name, options)?;
+            std::io::Write::write_all(zip, &content)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read metadata.json from a zip archive, returning empty JSON object on any error.
+fn read_archive_metadata(path: &std::path::Path) -> serde_json::Value {
+    let Ok(file) = std::fs::File::open(path) else {
+        return serde_json::json!({});
+    };
+    let Ok(mut zip) = zip::ZipArchive::new(file) else {
+        return serde_json::json!({});
+    };
+    let Ok(mut meta_file) = zip.by_name("metadata.json") else {
+        return serde_json::json!({});
+    };
 
     let mut content = String::new();
     use std::io::Read as _;
@@ -2917,10 +3519,7 @@ async fn handle_products(args: ProductsArgs) -> anyhow::Result<()> {
             .await?;
 
             mouchak_mail_core::model::product::ProductBmc::link_project(
-                &ctx,
-                &mm,
-                product.id,
-                project.id.get(),
+                &ctx, &mm, product.id, project.id,
             )
             .await?;
             println!(
@@ -2970,7 +3569,11 @@ async fn handle_products(args: ProductsArgs) -> anyhow::Result<()> {
             let mut all_matches = Vec::new();
             for pid in project_ids {
                 if let Ok(messages) = mouchak_mail_core::model::message::MessageBmc::search(
-                    &ctx, &mm, pid, &query, limit,
+                    &ctx,
+                    &mm,
+                    mouchak_mail_core::ProjectId::new(pid),
+                    &query,
+                    limit,
                 )
                 .await
                 {
@@ -2979,7 +3582,7 @@ async fn handle_products(args: ProductsArgs) -> anyhow::Result<()> {
             }
 
             // Sort by date desc
-            all_matches.sort_by(|a, b| b.created_ts.cmp(&a.created_ts));
+            all_matches.sort_by_key(|m| std::cmp::Reverse(m.created_ts));
             all_matches.truncate(limit as usize);
 
             println!(
@@ -3044,8 +3647,8 @@ async fn handle_products(args: ProductsArgs) -> anyhow::Result<()> {
                             mouchak_mail_core::model::message::MessageBmc::list_inbox_for_agent(
                                 &ctx,
                                 &mm,
-                                pid,
-                                agent_obj.id.get(),
+                                mouchak_mail_core::ProjectId::from(pid),
+                                agent_obj.id,
                                 50,
                             )
                             .await
@@ -3097,7 +3700,7 @@ async fn handle_products(args: ProductsArgs) -> anyhow::Result<()> {
                 let project_id = mouchak_mail_core::ProjectId::from(pid);
                 let project = ProjectBmc::get(&ctx, &mm, project_id).await?;
 
-                match MessageBmc::list_by_thread(&ctx, &mm, pid, &thread_id).await {
+                match MessageBmc::list_by_thread(&ctx, &mm, project_id, &thread_id).await {
                     Ok(messages) if !messages.is_empty() => {
                         project_sources.push(project.slug.clone());
                         all_messages.extend(messages);
@@ -3118,7 +3721,7 @@ async fn handle_products(args: ProductsArgs) -> anyhow::Result<()> {
                     thread_id, product.name
                 );
             } else {
-                all_messages.sort_by(|a, b| a.created_ts.cmp(&b.created_ts));
+                all_messages.sort_by_key(|a| a.created_ts);
                 all_messages.truncate(per_thread_limit as usize);
 
                 let mut participants: Vec<String> =
@@ -3153,6 +3756,84 @@ async fn handle_products(args: ProductsArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+// --- Projects Command Handler ---
+
+async fn handle_projects(args: ProjectsArgs) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::onboarding::OnboardingBmc;
+
+    let ctx = Ctx::root_ctx();
+    let mm = ModelManager::new(std::sync::Arc::new(load_config())).await?;
+    match args.command {
+        ProjectsCommands::Create {
+            slug,
+            human_key,
+            template,
+        } => handle_projects_create(&ctx, &mm, &slug, &human_key, template.as_deref()).await?,
+        ProjectsCommands::InitBundle {
+            project,
+            output,
+            server_url,
+        } => {
+            let server_url = server_url
+                .or_else(|| std::env::var("MOUCHAK_MAIL_URL").ok())
+                .unwrap_or_else(|| "http://localhost:8765".to_string());
+            let bundle =
+                OnboardingBmc::write_bundle(&ctx, &mm, &project, Path::new(&output), &server_url)
+                    .await?;
+            println!(
+                "Wrote onboarding bundle for '{}' to {}:",
+                bundle.project_slug,
+                bundle.output_dir.display()
+            );
+            for file in &bundle.files {
+                println!("  {}", file);
+            }
+            println!("Next: git config core.hooksPath .githooks && git add . && git commit");
+        }
+    }
+    Ok(())
+}
+
+async fn handle_projects_create(
+    ctx: &mouchak_mail_core::ctx::Ctx,
+    mm: &mouchak_mail_core::model::ModelManager,
+    slug: &str,
+    human_key: &str,
+    template: Option<&str>,
+) -> anyhow::Result<()> {
+    use mouchak_mail_core::model::project::ProjectBmc;
+    use mouchak_mail_core::model::project_template::ProjectTemplateBmc;
+
+    let Some(name) = template else {
+        let id = ProjectBmc::create(ctx, mm, slug, human_key).await?;
+        println!("Created project '{}' with ID {}", slug, id);
+        return Ok(());
+    };
+    let template = ProjectTemplateBmc::load(name)?;
+    let summary =
+        ProjectTemplateBmc::instantiate(ctx, mm, name, &template, slug, human_key).await?;
+    println!(
+        "Created project '{}' with ID {} from template '{}'",
+        slug, summary.project_id, name
+    );
+    println!(
+        "  {} agents, {} capabilities, {} contacts, {} macros, {} labels, {} SLAs, {} welcome messages",
+        summary.team.agents_created.len(),
+        summary.team.capabilities_granted.len(),
+        summary.team.contacts_created.len(),
+        summary.macros_created.len(),
+        summary.labels_created.len(),
+        summary.slas_set.len(),
+        summary.welcome_messages_sent.len()
+    );
+    for reason in summary.team.skipped.iter().chain(&summary.skipped) {
+        println!("  skipped {}", reason);
+    }
+    Ok(())
+}
+
 async fn handle_mail_status() -> anyhow::Result<()> {
     println!("Mail Status");
     println!("===========");
@@ -3252,87 +3933,715 @@ async fn handle_mail_status() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn handle_mail(args: MailArgs) -> anyhow::Result<()> {
-    match args.command {
-        MailCommands::Status => handle_mail_status().await,
+async fn handle_mail_overseer(
+    project: Option<String>,
+    all: bool,
+    limit: i64,
+    json: bool,
+) -> anyhow::Result<()> {
+    let project_slug = project
+        .or_else(|| std::env::var("MOUCHAK_MAIL_PROJECT_SLUG").ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!("No project given; pass --project or set MOUCHAK_MAIL_PROJECT_SLUG")
+        })?;
+    let url = std::env::var("MOUCHAK_MAIL_URL").unwrap_or_else(|_| "http://localhost:8765".into());
+
+    let resp = reqwest::Client::new()
+        .get(format!("{}/api/projects/{}/overseer", url, project_slug))
+        .query(&[
+            ("include_read", all.to_string()),
+            ("limit", limit.to_string()),
+        ])
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        anyhow::bail!("Failed to fetch overseer inbox: HTTP {}", resp.status());
+    }
+    let body: serde_json::Value = resp.json().await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&body)?);
+        return Ok(());
     }
+
+    let messages = body
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .cloned()
+        .unwrap_or_default();
+    println!("Overseer Inbox: {}", project_slug);
+    println!("===============");
+    println!(
+        "{} unread, {} shown",
+        body.get("unread_count")
+            .and_then(|c| c.as_u64())
+            .unwrap_or(0),
+        messages.len()
+    );
+
+    for msg in &messages {
+        let field = |key: &str| msg.get(key).and_then(|v| v.as_str()).unwrap_or("");
+        let marker = if msg.get("read_ts").is_some_and(|r| r.is_null()) {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{} #{} [{}] {} — {} ({})",
+            marker,
+            msg.get("id").and_then(|v| v.as_i64()).unwrap_or(0),
+            field("importance"),
+            field("subject"),
+            field("sender_name"),
+            field("created_ts"),
+        );
+    }
+
+    Ok(())
 }
 
-#[cfg(test)]
-mod guard_pattern_tests {
-    use super::*;
+/// Follows an agent's inbox stream (`/api/agents/{project}/{agent}/inbox/stream`)
+/// and prints each new message until interrupted. Dropped connections are
+/// resumed with `Last-Event-ID`, so no message is missed.
+async fn handle_mail_watch(
+    project: &str,
+    agent: &str,
+    url: &str,
+    token: Option<&str>,
+    notify: bool,
+) -> anyhow::Result<()> {
+    let stream_url = format!(
+        "{}/api/agents/{}/{}/inbox/stream",
+        url.trim_end_matches('/'),
+        project,
+        agent
+    );
+    let client = reqwest::Client::new();
+    let mut last_event_id: Option<String> = None;
+    println!(
+        "Watching inbox of '{}' in project '{}' (Ctrl-C to stop)",
+        agent, project
+    );
 
-    #[test]
-    fn test_empty_pattern_never_matches() {
-        assert!(!path_matches_pattern("src/main.rs", ""));
-        assert!(!path_matches_pattern("", ""));
+    loop {
+        let mut request = client
+            .get(&stream_url)
+            .header("Accept", "text/event-stream");
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(id) = &last_event_id {
+            request = request.header("Last-Event-ID", id.as_str());
+        }
+
+        let result = tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            result = request.send() => result,
+        };
+        match result {
+            Ok(mut resp) if resp.status().is_success() => {
+                let mut buffer = String::new();
+                loop {
+                    let chunk = tokio::select! {
+                        _ = tokio::signal::ctrl_c() => return Ok(()),
+                        chunk = resp.chunk() => chunk,
+                    };
+                    match chunk {
+                        Ok(Some(bytes)) => {
+                            buffer.push_str(&String::from_utf8_lossy(&bytes).replace("\r\n", "\n"));
+                            while let Some(end) = buffer.find("\n\n") {
+                                let block: String = buffer.drain(..end + 2).collect();
+                                handle_watch_event(&block, agent, notify, &mut last_event_id);
+                            }
+                        }
+                        Ok(None) => {
+                            eprintln!("Inbox stream closed by the server, reconnecting");
+                            break;
+                        }
+                        Err(e) => {
+                            eprintln!("Inbox stream interrupted: {}, reconnecting", e);
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(resp) => {
+                let status = resp.status();
+                // An unknown agent or a rejected token will not fix itself
+                if status.is_client_error() {
+                    anyhow::bail!("Failed to open inbox stream: HTTP {}", status);
+                }
+                eprintln!("Failed to open inbox stream: HTTP {}, retrying", status);
+            }
+            Err(e) => eprintln!("Failed to reach {}: {}, retrying", url, e),
+        }
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = tokio::time::sleep(std::time::Duration::from_secs(2)) => {}
+        }
     }
+}
 
-    #[test]
-    fn test_wildcard_matches_everything() {
-        assert!(path_matches_pattern("src/main.rs", "*"));
-        assert!(path_matches_pattern("", "*"));
-        assert!(path_matches_pattern("any/path/here", "*"));
+/// Handles one SSE event block of the inbox stream.
+fn handle_watch_event(block: &str, agent: &str, notify: bool, last_event_id: &mut Option<String>) {
+    let mut event = "message";
+    let mut data = String::new();
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            event = value.trim();
+        } else if let Some(value) = line.strip_prefix("id:") {
+            *last_event_id = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+        }
     }
 
-    #[test]
-    fn test_double_star_suffix() {
-        // src/** should match anything under src/
-        assert!(path_matches_pattern("src/main.rs", "src/**"));
-        assert!(path_matches_pattern("src/lib/mod.rs", "src/**"));
-        assert!(path_matches_pattern("src/", "src/**"));
+    match event {
+        "message-created" => {
+            let Ok(record) = serde_json::from_str::<serde_json::Value>(&data) else {
+                return;
+            };
+            let payload = &record["payload"];
+            // Approval events name the sender in the payload, not as actor
+            let sender = payload
+                .get("sender")
+                .or_else(|| record.get("actor"))
+                .and_then(|s| s.as_str())
+                .unwrap_or("unknown");
+            let subject = payload
+                .get("subject")
+                .and_then(|s| s.as_str())
+                .unwrap_or("(approved message)");
+            let importance = payload
+                .get("importance")
+                .and_then(|i| i.as_str())
+                .filter(|i| *i != "normal")
+                .map(|i| format!(" [{}]", i))
+                .unwrap_or_default();
+            println!(
+                "[{}] #{} from {}{}: {}",
+                record["created_ts"].as_str().unwrap_or(""),
+                payload["message_id"],
+                sender,
+                importance,
+                subject
+            );
+            if notify {
+                desktop_notify(&format!("{} from {}", agent, sender), subject);
+            }
+        }
+        "stream-lagged" => {
+            eprintln!("Inbox stream fell behind the server; some messages were not shown");
+        }
+        _ => {}
+    }
+}
 
-        // Should not match src itself without trailing slash
-        assert!(!path_matches_pattern("src", "src/**"));
+/// Shows a desktop notification; failures (no notifier installed) are ignored.
+fn desktop_notify(title: &str, body: &str) {
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("osascript")
+        .arg("-e")
+        .arg(format!(
+            "display notification {:?} with title {:?}",
+            body, title
+        ))
+        .spawn();
+
+    #[cfg(target_os = "linux")]
+    let result = std::process::Command::new("notify-send")
+        .args([title, body])
+        .spawn();
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    let result: std::io::Result<()> = {
+        let _ = (title, body);
+        Ok(())
+    };
 
-        // Should not match other directories
-        assert!(!path_matches_pattern("src_backup/main.rs", "src/**"));
-        assert!(!path_matches_pattern("other/src/main.rs", "src/**"));
+    if let Err(e) = result {
+        tracing::debug!("Desktop notification failed: {}", e);
     }
+}
 
-    #[test]
-    fn test_double_star_edge_cases() {
-        // Pattern exactly "/**" should not match (needs prefix)
-        assert!(!path_matches_pattern("any/path", "/**"));
+async fn handle_mail_import_mbox(
+    file: &str,
+    project: &str,
+    map: &[String],
+    create_agents: bool,
+) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::mbox_import::{MboxImportBmc, MboxImportOptions};
+    use mouchak_mail_core::model::project::ProjectBmc;
 
-        // Pattern "**" alone is not a wildcard (only * is)
-        assert!(!path_matches_pattern("any/path", "**"));
+    let mut sender_map = std::collections::HashMap::new();
+    for entry in map {
+        let (sender, agent) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid --map '{}': expected sender=agent", entry))?;
+        sender_map.insert(sender.trim().to_string(), agent.trim().to_string());
     }
+    // Raw bytes: each message is decoded by its own declared charset
+    let mbox = std::fs::read(file)?;
 
-    #[test]
-    fn test_exact_prefix_match() {
-        // Exact match
-        assert!(path_matches_pattern("src/main.rs", "src/main.rs"));
+    let ctx = Ctx::root_ctx();
+    let mm = ModelManager::new(std::sync::Arc::new(load_config())).await?;
+    let project = ProjectBmc::get_by_identifier(&ctx, &mm, project).await?;
+    let summary = MboxImportBmc::import(
+        &ctx,
+        &mm,
+        project.id,
+        &mbox,
+        MboxImportOptions {
+            sender_map,
+            create_agents,
+        },
+    )
+    .await?;
+
+    println!(
+        "Imported {} messages into {} threads of project '{}'",
+        summary.imported.len(),
+        summary.threads,
+        project.slug
+    );
+    if !summary.agents_created.is_empty() {
+        println!(
+            "  placeholder agents: {}",
+            summary.agents_created.join(", ")
+        );
+    }
+    if !summary.unmapped_addresses.is_empty() {
+        println!(
+            "  unmapped recipients (dropped): {}",
+            summary.unmapped_addresses.join(", ")
+        );
+    }
+    if summary.attachments_skipped > 0 {
+        println!("  attachments skipped: {}", summary.attachments_skipped);
+    }
+    for reason in &summary.skipped {
+        println!("  skipped {}", reason);
+    }
+    Ok(())
+}
+
+async fn handle_mail_seed(
+    projects: usize,
+    agents: usize,
+    messages: usize,
+    seed: u64,
+) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::seed::{SeedBmc, SeedConfig};
 
-        // Prefix with path separator
-        assert!(path_matches_pattern("src/main/file.rs", "src/main"));
+    let ctx = Ctx::root_ctx();
+    let mm = ModelManager::new(std::sync::Arc::new(load_config())).await?;
+    let config = SeedConfig {
+        projects,
+        agents,
+        messages,
+        seed,
+    };
+    let summary = SeedBmc::seed(&ctx, &mm, &config).await?;
 
-        // Should not match partial directory names
-        assert!(!path_matches_pattern("src/main_backup/file.rs", "src/main"));
+    println!("Seeded dataset (seed: {}):", seed);
+    for slug in &summary.project_slugs {
+        println!("  project: {}", slug);
     }
+    println!("  agents:       {}", summary.agents);
+    println!("  messages:     {}", summary.messages);
+    println!("  threads:      {}", summary.threads);
+    println!("  acks:         {}", summary.acks);
+    println!("  reservations: {}", summary.reservations);
+    println!("  attachments:  {}", summary.attachments);
+    Ok(())
+}
 
-    #[test]
-    fn test_path_segment_matching() {
-        // Pattern appears as path segment in middle
-        assert!(path_matches_pattern("foo/src/main.rs", "src"));
+async fn handle_mail_export(
+    project: &str,
+    format: &str,
+    scrub: &str,
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::export::{ExportBmc, ExportFormat, ScrubMode};
+
+    let format_enum: ExportFormat = format
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid format '{}'", format))?;
+    let scrub_mode: ScrubMode = scrub
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid scrub mode '{}'", scrub))?;
+    let ctx = Ctx::root_ctx();
+    let mm = ModelManager::new(std::sync::Arc::new(load_config())).await?;
+
+    if format.eq_ignore_ascii_case("eml") {
+        let dir =
+            output.ok_or_else(|| anyhow::anyhow!("--output <DIR> is required for eml export"))?;
+        let files = ExportBmc::export_eml(&ctx, &mm, project, scrub_mode, true).await?;
+        std::fs::create_dir_all(dir)?;
+        for file in &files {
+            std::fs::write(Path::new(dir).join(&file.filename), &file.content)?;
+        }
+        println!("Exported {} messages to {}", files.len(), dir);
+        return Ok(());
+    }
 
-        // Pattern at end after path separator
-        assert!(path_matches_pattern("foo/bar/src", "src"));
+    let exported =
+        ExportBmc::export_mailbox(&ctx, &mm, project, format_enum, scrub_mode, true).await?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, &exported.content)?;
+            println!("Exported to {}", path);
+        }
+        None => println!("{}", exported.content),
+    }
+    Ok(())
+}
+
+async fn handle_mail_escalate_overdue(
+    hours: i64,
+    dry_run: bool,
+    mode: Option<&str>,
+) -> anyhow::Result<()> {
+    use mouchak_mail_common::config::EscalationMode;
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::escalation::EscalationBmc;
+
+    let app_config = load_config();
+    let escalation_mode = match mode.map(str::to_lowercase).as_deref() {
+        None => app_config.escalation.escalation_mode,
+        Some("log") => EscalationMode::Log,
+        Some("file_reservation") => EscalationMode::FileReservation,
+        Some("overseer") => EscalationMode::Overseer,
+        Some("remind") => EscalationMode::Remind,
+        Some(other) => anyhow::bail!(
+            "Invalid mode '{}': expected log, file_reservation, overseer or remind",
+            other
+        ),
+    };
+    let ctx = Ctx::root_ctx();
+    let mm = ModelManager::new(std::sync::Arc::new(app_config)).await?;
 
-        // Should not match as substring within segment
-        assert!(!path_matches_pattern("mysrc/main.rs", "src"));
-        assert!(!path_matches_pattern("src_old/main.rs", "src"));
+    println!(
+        "Checking for overdue acks (threshold: {} hours, mode: {:?}, dry_run: {})...",
+        hours, escalation_mode, dry_run
+    );
+    let results =
+        EscalationBmc::escalate_overdue(&ctx, &mm, hours, escalation_mode, dry_run).await?;
+    println!("Processed {} overdue messages.", results.len());
+    for result in results {
+        let status = if result.success { "OK" } else { "FAILED" };
+        println!(
+            "  [{}] Message {}: {} - {}",
+            status,
+            result.message_id,
+            result.action_taken,
+            result.details.as_deref().unwrap_or("(no details)")
+        );
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_no_false_positives() {
-        // These should NOT match
-        assert!(!path_matches_pattern("src_backup/file.rs", "src"));
-        assert!(!path_matches_pattern("other_src/file.rs", "src"));
-        assert!(!path_matches_pattern("resource/file.rs", "src"));
+async fn handle_mail(args: MailArgs) -> anyhow::Result<()> {
+    match args.command {
+        MailCommands::Status => handle_mail_status().await,
+        MailCommands::Overseer {
+            project,
+            all,
+            limit,
+            json,
+        } => handle_mail_overseer(project, all, limit, json).await,
+        MailCommands::Watch {
+            project,
+            agent,
+            url,
+            token,
+            notify,
+        } => handle_mail_watch(&project, &agent, &url, token.as_deref(), notify).await,
+        MailCommands::ImportMbox {
+            file,
+            project,
+            map,
+            create_agents,
+        } => handle_mail_import_mbox(&file, &project, &map, create_agents).await,
+        MailCommands::Export {
+            project,
+            format,
+            scrub,
+            output,
+        } => handle_mail_export(&project, &format, &scrub, output.as_deref()).await,
+        MailCommands::EscalateOverdue {
+            hours,
+            dry_run,
+            mode,
+        } => handle_mail_escalate_overdue(hours, dry_run, mode.as_deref()).await,
+        MailCommands::Seed {
+            projects,
+            agents,
+            messages,
+            seed,
+        } => handle_mail_seed(projects, agents, messages, seed).await,
+    }
+}
+
+// --- Observability Command Handler ---
+
+fn handle_observability(args: ObservabilityArgs) -> anyhow::Result<()> {
+    use mouchak_mail_server::dashboards;
+
+    match args.command {
+        ObservabilityCommands::ExportDashboards { output } => {
+            let dir = PathBuf::from(&output);
+            std::fs::create_dir_all(&dir)?;
+
+            let dashboard = dir.join(dashboards::DASHBOARD_FILE);
+            std::fs::write(
+                &dashboard,
+                serde_json::to_string_pretty(&dashboards::grafana_dashboard())?,
+            )?;
+            let alerts = dir.join(dashboards::ALERTS_FILE);
+            std::fs::write(&alerts, dashboards::prometheus_alert_rules())?;
+
+            println!("Grafana dashboard: {}", dashboard.display());
+            println!("Prometheus alert rules: {}", alerts.display());
+            Ok(())
+        }
     }
 }
 
+// --- Event Log Command Handler ---
+
+async fn handle_events(args: EventsArgs) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::event_log::{EventLogBmc, EventRecord, verify_chain};
+
+    let ctx = Ctx::root_ctx();
+    match args.command {
+        EventsCommands::Export { output } => {
+            let config = load_config();
+            let mm = ModelManager::new(std::sync::Arc::new(config)).await?;
+            let records = EventLogBmc::export(&ctx, &mm).await?;
+
+            let mut out: Box<dyn Write> = match &output {
+                Some(path) => Box::new(std::fs::File::create(path)?),
+                None => Box::new(std::io::stdout()),
+            };
+            for record in &records {
+                writeln!(out, "{}", serde_json::to_string(record)?)?;
+            }
+            if let Some(path) = output {
+                eprintln!("Exported {} event(s) to {}", records.len(), path);
+            }
+            Ok(())
+        }
+        EventsCommands::VerifyChain { file, json } => {
+            let verification = match &file {
+                Some(path) => {
+                    let content = std::fs::read_to_string(path)?;
+                    let records = content
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .enumerate()
+                        .map(|(i, line)| {
+                            serde_json::from_str::<EventRecord>(line).map_err(|e| {
+                                anyhow::anyhow!("{}: invalid record on line {}: {}", path, i + 1, e)
+                            })
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    verify_chain(&records)
+                }
+                None => {
+                    let config = load_config();
+                    let mm = ModelManager::new(std::sync::Arc::new(config)).await?;
+                    EventLogBmc::verify(&ctx, &mm).await?
+                }
+            };
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&verification)?);
+            } else if verification.valid {
+                println!("Event log chain is intact");
+                println!("  Records:   {}", verification.records);
+                println!("  Head hash: {}", verification.head_hash);
+            } else {
+                println!("Event log chain is BROKEN");
+                println!(
+                    "  Valid records: {} (head hash {})",
+                    verification.records, verification.head_hash
+                );
+                if let Some(id) = verification.first_invalid_id {
+                    println!("  First invalid record: {}", id);
+                }
+                if let Some(error) = &verification.error {
+                    println!("  Reason: {}", error);
+                }
+            }
+
+            if !verification.valid {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
+
+// --- API Token Command Handler ---
+
+async fn handle_token(args: TokenArgs) -> anyhow::Result<()> {
+    use mouchak_mail_core::ctx::Ctx;
+    use mouchak_mail_core::model::ModelManager;
+    use mouchak_mail_core::model::agent::AgentBmc;
+    use mouchak_mail_core::model::api_token::{ApiTokenBmc, ApiTokenForCreate};
+    use mouchak_mail_core::model::listing::parse_duration_secs;
+    use mouchak_mail_core::model::project::ProjectBmc;
+
+    let ctx = Ctx::root_ctx();
+    let config = load_config();
+    let mm = ModelManager::new(std::sync::Arc::new(config)).await?;
+    match args.command {
+        TokenCommands::Create {
+            project,
+            agent,
+            scopes,
+            name,
+            expires_in,
+            json,
+        } => {
+            let project = ProjectBmc::get_by_identifier(&ctx, &mm, &project).await?;
+            let agent = AgentBmc::get_by_name(&ctx, &mm, project.id, &agent).await?;
+            let expires_ts = match expires_in {
+                Some(duration) => Some(
+                    chrono::Utc::now().naive_utc()
+                        + chrono::Duration::seconds(parse_duration_secs(&duration)?),
+                ),
+                None => None,
+            };
+            let issued = ApiTokenBmc::create(
+                &ctx,
+                &mm,
+                ApiTokenForCreate {
+                    agent_id: agent.id,
+                    name,
+                    scopes,
+                    expires_ts,
+                },
+            )
+            .await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&issued)?);
+            } else {
+                println!(
+                    "Issued token {} for {} in {}",
+                    issued.token.id, issued.token.agent_name, issued.token.project_slug
+                );
+                println!("  Scopes:  {}", issued.token.scopes.join(" "));
+                if let Some(expires_ts) = issued.token.expires_ts {
+                    println!("  Expires: {}", expires_ts);
+                }
+                println!("  Token:   {}", issued.secret);
+                println!("Store it now; it cannot be shown again.");
+            }
+        }
+        TokenCommands::Revoke { id } => {
+            let token = ApiTokenBmc::revoke(&ctx, &mm, id).await?;
+            println!(
+                "Revoked token {} ({}) of {}",
+                token.id, token.token_prefix, token.agent_name
+            );
+        }
+        TokenCommands::List { project, json } => {
+            let project_id = match project {
+                Some(project) => Some(ProjectBmc::get_by_identifier(&ctx, &mm, &project).await?.id),
+                None => None,
+            };
+            let tokens = ApiTokenBmc::list(&ctx, &mm, project_id).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tokens)?);
+            } else if tokens.is_empty() {
+                println!("No API tokens");
+            } else {
+                let now = chrono::Utc::now().naive_utc();
+                for token in &tokens {
+                    let status = if token.revoked_ts.is_some() {
+                        "revoked"
+                    } else if token.expires_ts.is_some_and(|ts| ts <= now) {
+                        "expired"
+                    } else {
+                        "active"
+                    };
+                    println!(
+                        "{:>5}  {}...  {:<8} {}/{}  [{}]  {}",
+                        token.id,
+                        token.token_prefix,
+                        status,
+                        token.project_slug,
+                        token.agent_name,
+                        token.scopes.join(" "),
+                        token.name
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_upgrade(check: bool, json: bool) -> anyhow::Result<()> {
+    use mouchak_mail_core::store::{check_data_format, upgrade_data_format};
+
+    let config = load_config();
+    if check {
+        let plan = check_data_format(&config.database).await?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+        } else {
+            println!("{}", plan.describe());
+        }
+        // Non-zero when the data is not usable as-is, for scripted upgrades
+        if plan.is_newer() || (plan.needs_upgrade() && !plan.fresh) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let (plan, snapshot) = upgrade_data_format(&config.database).await?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "plan": plan,
+                "snapshot": snapshot,
+            }))?
+        );
+    } else if plan.needs_upgrade() && !plan.fresh {
+        println!(
+            "Upgraded {} from data format {} to {}",
+            plan.db_path.display(),
+            plan.current_version,
+            plan.target_version
+        );
+        if let Some(snapshot) = snapshot {
+            println!("  Snapshot: {}", snapshot.display());
+        }
+    } else {
+        println!("{}", plan.describe());
+    }
+    Ok(())
+}
+
 // =============================================================================
 // Tests for robot-* flag handlers (TDD - mouchak-mail-rs-vgs4)
 // =============================================================================
@@ -3707,3 +5016,30 @@ mod github_pages_tests {
         };
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod watch_tests {
+    use super::handle_watch_event;
+
+    #[test]
+    fn test_watch_event_tracks_last_event_id() {
+        let mut last_event_id = None;
+        let block = "event: message-created\nid: 42\ndata: {\"id\":42,\"created_ts\":\"2026-01-01T00:00:00\",\"actor\":\"BlueLake\",\"payload\":{\"message_id\":7,\"subject\":\"Hi\",\"importance\":\"high\"}}\n\n";
+        handle_watch_event(block, "GreenCastle", false, &mut last_event_id);
+        assert_eq!(last_event_id.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn test_watch_event_without_id_keeps_last_event_id() {
+        let mut last_event_id = Some("5".to_string());
+        handle_watch_event(
+            "event: stream-lagged\ndata: {\"missed\":3}\n\n",
+            "GreenCastle",
+            false,
+            &mut last_event_id,
+        );
+        handle_watch_event(":keep-alive\n\n", "GreenCastle", false, &mut last_event_id);
+        assert_eq!(last_event_id.as_deref(), Some("5"));
+    }
+}
@@ -125,6 +125,26 @@ pub(crate) static EXAMPLE_REGISTRY: Lazy<HashMap<&'static str, ExampleEntry>> =
         },
     );
 
+    m.insert(
+        "--portable",
+        ExampleEntry {
+            description: "Keep config, database, archive and logs under one directory",
+            target_type: "flag",
+            param_type: Some("Path"),
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail --portable /media/usb/mouchak serve http",
+                    "Run from a USB stick",
+                ),
+                example(
+                    "mouchak-mail --portable .mouchak serve mcp",
+                    "Per-repository sandbox",
+                ),
+            ],
+        },
+    );
+
     m.insert(
         "--log-format",
         ExampleEntry {
@@ -228,6 +248,10 @@ pub(crate) static EXAMPLE_REGISTRY: Lazy<HashMap<&'static str, ExampleEntry>> =
             examples: vec![
                 example("mouchak-mail config set-port 9000", "Set custom port"),
                 example("mouchak-mail config show-port", "Show current port"),
+                example(
+                    "mouchak-mail config sources",
+                    "Show where each setting came from",
+                ),
             ],
         },
     );
@@ -260,6 +284,26 @@ pub(crate) static EXAMPLE_REGISTRY: Lazy<HashMap<&'static str, ExampleEntry>> =
         },
     );
 
+    m.insert(
+        "config sources",
+        ExampleEntry {
+            description: "Show every setting and the layer it came from (default, file, env, cli)",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail config sources",
+                    "Table of key, value and source",
+                ),
+                example(
+                    "mouchak-mail config sources --json",
+                    "Machine-readable output",
+                ),
+            ],
+        },
+    );
+
     m.insert(
         "schema",
         ExampleEntry {
@@ -583,6 +627,99 @@ pub(crate) static EXAMPLE_REGISTRY: Lazy<HashMap<&'static str, ExampleEntry>> =
         },
     );
 
+    m.insert(
+        "db maintain",
+        ExampleEntry {
+            description: "Check integrity, ANALYZE and VACUUM the database",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail db maintain",
+                    "Vacuum if free pages exceed 20%",
+                ),
+                example(
+                    "mouchak-mail db maintain --dry-run --json",
+                    "Integrity check and sizes only",
+                ),
+                example("mouchak-mail db maintain --vacuum", "Always vacuum"),
+            ],
+        },
+    );
+
+    m.insert(
+        "retention",
+        ExampleEntry {
+            description: "Purge messages past their project's retention limits and manage per-project policies",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail retention run --dry-run",
+                    "List what would be purged",
+                ),
+                example(
+                    "mouchak-mail retention show my-project",
+                    "Show the limits in effect",
+                ),
+                example(
+                    "mouchak-mail retention set my-project --max-age-days 90 --action archive",
+                    "Override the server-wide limits",
+                ),
+                example(
+                    "mouchak-mail retention clear my-project",
+                    "Return to the server-wide config",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "gc-attachments",
+        ExampleEntry {
+            description: "Remove attachment blobs no attachment references anymore",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail gc-attachments --dry-run",
+                    "List what would be removed",
+                ),
+                example(
+                    "mouchak-mail gc-attachments --grace-seconds 0",
+                    "Remove every unreferenced blob now",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "archive gc",
+        ExampleEntry {
+            description: "Pack loose objects in the Git archive",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail archive gc",
+                    "Compact and report space reclaimed",
+                ),
+                example(
+                    "mouchak-mail archive gc --dry-run --json",
+                    "Show object counts only",
+                ),
+                example(
+                    "mouchak-mail archive gc --min-loose-objects 1000",
+                    "Skip small archives",
+                ),
+            ],
+        },
+    );
+
     m.insert(
         "archive clear-and-reset",
         ExampleEntry {
@@ -811,6 +948,232 @@ pub(crate) static EXAMPLE_REGISTRY: Lazy<HashMap<&'static str, ExampleEntry>> =
         },
     );
 
+    m.insert(
+        "events",
+        ExampleEntry {
+            description: "Tamper-evident event log",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail events export --output events.jsonl",
+                    "Export the hash chain",
+                ),
+                example("mouchak-mail events verify-chain", "Check for tampering"),
+            ],
+        },
+    );
+
+    m.insert(
+        "events verify-chain",
+        ExampleEntry {
+            description: "Verify the event log hash chain",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example("mouchak-mail events verify-chain", "Verify the database"),
+                example(
+                    "mouchak-mail events verify-chain --file events.jsonl",
+                    "Verify an export",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "token",
+        ExampleEntry {
+            description: "Per-agent API tokens with scoped permissions",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail token create -p my-project -a alice -s read:inbox -s write:messages",
+                    "Issue a token",
+                ),
+                example("mouchak-mail token list --project my-project", "List tokens"),
+                example("mouchak-mail token revoke 3", "Revoke a token"),
+            ],
+        },
+    );
+
+    m.insert(
+        "token create",
+        ExampleEntry {
+            description: "Issue an API token to an agent (shown once)",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail token create -p my-project -a ci-bot -s admin:reservations --expires-in 30d",
+                    "Expiring token for force-releasing reservations",
+                ),
+                example(
+                    "mouchak-mail token create -p my-project -a alice -s admin --json",
+                    "Full-access token as JSON",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "upgrade",
+        ExampleEntry {
+            description: "Upgrade the data directory format (snapshots first)",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example("mouchak-mail upgrade --check", "Report what would change"),
+                example("mouchak-mail upgrade", "Snapshot and upgrade now"),
+            ],
+        },
+    );
+
+    m.insert(
+        "projects create",
+        ExampleEntry {
+            description: "Create a project, optionally from a template bundle",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail projects create my-project /path/to/repo",
+                    "An empty project",
+                ),
+                example(
+                    "mouchak-mail projects create my-project /path/to/repo --template backend-team",
+                    "From data/templates/backend-team.json",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "projects init-bundle",
+        ExampleEntry {
+            description: "Write a ready-to-commit onboarding bundle for a repo",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail projects init-bundle my-project",
+                    "Into the current directory",
+                ),
+                example(
+                    "mouchak-mail projects init-bundle my-project -o ../repo --server-url http://mail:8765",
+                    "Into another repo, for a remote server",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "mail watch",
+        ExampleEntry {
+            description: "Print an agent's new messages as they arrive, from the server's inbox stream",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail mail watch --project my-project --agent BlueLake",
+                    "Follow an agent's inbox",
+                ),
+                example(
+                    "mouchak-mail mail watch --project my-project --agent BlueLake --notify",
+                    "Also show desktop notifications",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "mail import-mbox",
+        ExampleEntry {
+            description: "Import messages from an mbox file into a project",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail mail import-mbox thread.mbox --project my-project --map alice@example.com=Alice",
+                    "Map a sender to an existing agent",
+                ),
+                example(
+                    "mouchak-mail mail import-mbox thread.mbox --project my-project --create-agents",
+                    "Create placeholder agents for unmapped addresses",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "mail export",
+        ExampleEntry {
+            description: "Export a project's mailbox",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail mail export my-project --format mbox -o my-project.mbox",
+                    "As an mboxrd mailbox",
+                ),
+                example(
+                    "mouchak-mail mail export my-project --format eml -o eml/ --scrub standard",
+                    "One scrubbed .eml file per message",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "mail escalate-overdue",
+        ExampleEntry {
+            description: "Escalate messages whose acknowledgement is overdue",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail mail escalate-overdue --dry-run",
+                    "List messages unacknowledged for 24 hours",
+                ),
+                example(
+                    "mouchak-mail mail escalate-overdue --hours 4 --mode remind",
+                    "Remind recipients after 4 hours",
+                ),
+            ],
+        },
+    );
+
+    m.insert(
+        "mail seed",
+        ExampleEntry {
+            description: "Populate a reproducible demo dataset",
+            target_type: "subcommand",
+            param_type: None,
+            default: None,
+            examples: vec![
+                example(
+                    "mouchak-mail mail seed",
+                    "3 projects, 5 agents, 200 messages each",
+                ),
+                example(
+                    "mouchak-mail mail seed --projects 1 --messages 1000 --seed 7",
+                    "One larger project",
+                ),
+            ],
+        },
+    );
+
     m.insert(
         "mail status",
         ExampleEntry {
@@ -0,0 +1,50 @@
+#![allow(clippy::unwrap_used)]
+#![allow(deprecated)] // cargo_bin is still valid for our use case
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+/// The binary, keeping its database and archive in `data` (portable mode)
+fn mouchak_mail(data: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mouchak-mail").unwrap();
+    cmd.env("MOUCHAK_MAIL_PORTABLE_DIR", data.path());
+    cmd
+}
+
+#[test]
+fn test_create_export_and_escalate() {
+    let data = TempDir::new().unwrap();
+
+    mouchak_mail(&data)
+        .args(["projects", "create", "cli-demo", "/tmp/cli-demo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created project 'cli-demo'"));
+
+    let mbox = data.path().join("cli-demo.mbox");
+    mouchak_mail(&data)
+        .args(["mail", "export", "cli-demo", "--format", "mbox", "-o"])
+        .arg(&mbox)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported to"));
+    assert!(mbox.exists());
+
+    mouchak_mail(&data)
+        .args(["mail", "export", "cli-demo", "--format", "eml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--output <DIR> is required"));
+
+    mouchak_mail(&data)
+        .args(["mail", "escalate-overdue", "--dry-run", "--mode", "remind"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Processed 0 overdue messages."));
+    mouchak_mail(&data)
+        .args(["mail", "escalate-overdue", "--mode", "shout"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid mode 'shout'"));
+}
@@ -4,14 +4,22 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use std::time::Duration;
+use tempfile::TempDir;
+
+/// The binary, keeping its database and archive in `data` (portable mode)
+fn mouchak_mail(data: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mouchak-mail").unwrap();
+    cmd.env("MOUCHAK_MAIL_PORTABLE_DIR", data.path());
+    cmd
+}
 
 #[test]
 fn test_service_lifecycle() {
     let port = 9099; // Use a high port unlikely to be used
+    let data = TempDir::new().unwrap();
 
     // 1. Ensure clean state (stop any leftover)
-    let _ = Command::cargo_bin("mouchak-mail")
-        .unwrap()
+    let _ = mouchak_mail(&data)
         .arg("service")
         .arg("stop")
         .arg("--port")
@@ -19,7 +27,7 @@ fn test_service_lifecycle() {
         .ok();
 
     // 2. Start server using SERVICE START (background)
-    let mut cmd = Command::cargo_bin("mouchak-mail").unwrap();
+    let mut cmd = mouchak_mail(&data);
     cmd.arg("service")
         .arg("start")
         .arg("--port")
@@ -33,7 +41,7 @@ fn test_service_lifecycle() {
     std::thread::sleep(Duration::from_secs(2));
 
     // 3. Check Status
-    let mut cmd = Command::cargo_bin("mouchak-mail").unwrap();
+    let mut cmd = mouchak_mail(&data);
     cmd.arg("service")
         .arg("status")
         .arg("--port")
@@ -43,7 +51,7 @@ fn test_service_lifecycle() {
         .stdout(predicate::str::contains("Server RUNNING"));
 
     // 4. Restart Server (should succeed and keep running)
-    let mut cmd = Command::cargo_bin("mouchak-mail").unwrap();
+    let mut cmd = mouchak_mail(&data);
     cmd.arg("service")
         .arg("restart")
         .arg("--port")
@@ -55,7 +63,7 @@ fn test_service_lifecycle() {
     // Give it time to restart
     std::thread::sleep(Duration::from_secs(2));
 
-    let mut cmd = Command::cargo_bin("mouchak-mail").unwrap();
+    let mut cmd = mouchak_mail(&data);
     cmd.arg("service")
         .arg("status")
         .arg("--port")
@@ -65,7 +73,7 @@ fn test_service_lifecycle() {
         .stdout(predicate::str::contains("Server RUNNING"));
 
     // 5. Stop Server
-    let mut cmd = Command::cargo_bin("mouchak-mail").unwrap();
+    let mut cmd = mouchak_mail(&data);
     cmd.arg("service")
         .arg("stop")
         .arg("--port")
@@ -76,7 +84,7 @@ fn test_service_lifecycle() {
 
     // 6. Verify Stopped
     std::thread::sleep(Duration::from_secs(1));
-    let mut cmd = Command::cargo_bin("mouchak-mail").unwrap();
+    let mut cmd = mouchak_mail(&data);
     cmd.arg("service")
         .arg("status")
         .arg("--port")
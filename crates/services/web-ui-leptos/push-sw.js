@@ -0,0 +1,38 @@
+// Mouchak Mail Web Push service worker.
+// Shows notifications pushed by the server for urgent messages and focuses
+// (or opens) the UI on the message when one is clicked.
+
+self.addEventListener('push', function (event) {
+    let data = {};
+    try {
+        data = event.data ? event.data.json() : {};
+    } catch (e) {
+        data = { title: 'Mouchak Mail', body: event.data ? event.data.text() : '' };
+    }
+
+    event.waitUntil(
+        self.registration.showNotification(data.title || 'Mouchak Mail', {
+            body: data.body || '',
+            tag: data.tag,
+            requireInteraction: true,
+            data: { url: data.url || '/' }
+        })
+    );
+});
+
+self.addEventListener('notificationclick', function (event) {
+    event.notification.close();
+    const url = new URL(event.notification.data.url, self.location.origin).href;
+
+    event.waitUntil(
+        clients.matchAll({ type: 'window', includeUncontrolled: true }).then(function (windows) {
+            for (const client of windows) {
+                if ('focus' in client) {
+                    client.navigate(url);
+                    return client.focus();
+                }
+            }
+            return clients.openWindow(url);
+        })
+    );
+});
@@ -1,7 +1,8 @@
 //! HTTP client for Mouchak Mail API.
 
-use gloo_net::http::Request;
+use gloo_net::http::{Request, RequestBuilder};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 
 /// Get the API base URL.
 ///
@@ -55,6 +56,55 @@ impl From<gloo_net::Error> for ApiError {
     }
 }
 
+thread_local! {
+    /// CSRF token of the browser session; `Some(None)` once known to be absent.
+    static CSRF_TOKEN: RefCell<Option<Option<String>>> = const { RefCell::new(None) };
+}
+
+/// Identity of the caller (from GET /api/me).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Me {
+    pub auth_method: String,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub csrf_token: Option<String>,
+}
+
+/// Get the caller's identity and effective roles.
+pub async fn get_me() -> Result<Me, ApiError> {
+    let url = format!("{}/api/me", api_base_url());
+    let response = Request::get(&url).send().await?;
+
+    if response.ok() {
+        let me: Me = response.json().await?;
+        CSRF_TOKEN.with(|t| *t.borrow_mut() = Some(me.csrf_token.clone()));
+        Ok(me)
+    } else {
+        Err(ApiError {
+            message: format!("Failed to get identity: {}", response.status()),
+        })
+    }
+}
+
+/// Adds the session's CSRF token to a mutating request.
+///
+/// The token is fetched once from `/api/me`. Without a browser session there
+/// is no token and the request is sent unchanged.
+async fn with_csrf(request: RequestBuilder) -> RequestBuilder {
+    let cached = CSRF_TOKEN.with(|t| t.borrow().clone());
+    let token = match cached {
+        Some(token) => token,
+        None => get_me().await.ok().and_then(|me| me.csrf_token),
+    };
+    match token {
+        Some(token) => request.header("X-CSRF-Token", &token),
+        None => request,
+    }
+}
+
 /// Health check response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthResponse {
@@ -119,6 +169,9 @@ pub struct Message {
     pub thread_id: Option<String>,
     pub subject: String,
     pub body_md: String,
+    /// Server-rendered, sanitized HTML body (when requested with `format=html`)
+    #[serde(default)]
+    pub body_rendered: Option<String>,
     pub importance: String,
     #[serde(default)]
     pub ack_required: bool,
@@ -168,7 +221,8 @@ pub async fn ensure_project(human_key: &str) -> Result<Project, ApiError> {
 
     let payload = CreateProjectPayload { human_key };
 
-    let response = Request::post(&url)
+    let response = with_csrf(Request::post(&url))
+        .await
         .header("Content-Type", "application/json")
         .json(&payload)?
         .send()
@@ -261,7 +315,8 @@ pub async fn register_agent(
         task_description,
     };
 
-    let response = Request::post(&url)
+    let response = with_csrf(Request::post(&url))
+        .await
         .header("Content-Type", "application/json")
         .json(&payload)?
         .send()
@@ -309,7 +364,8 @@ pub async fn get_inbox(
         "limit": 50
     });
 
-    let response = Request::post(&url)
+    let response = with_csrf(Request::post(&url))
+        .await
         .header("Content-Type", "application/json")
         .body(payload.to_string())
         .map_err(|e| ApiError {
@@ -329,7 +385,7 @@ pub async fn get_inbox(
 
 /// Get a single message by ID.
 pub async fn get_message(id: &str) -> Result<Message, ApiError> {
-    let url = format!("{}/api/messages/{}", api_base_url(), id);
+    let url = format!("{}/api/messages/{}?format=html", api_base_url(), id);
     let response = Request::get(&url).send().await?;
 
     if response.ok() {
@@ -378,7 +434,8 @@ pub async fn send_message(
         importance: Some(importance),
     };
 
-    let response = Request::post(&url)
+    let response = with_csrf(Request::post(&url))
+        .await
         .header("Content-Type", "application/json")
         .json(&payload)?
         .send()
@@ -475,6 +532,46 @@ pub async fn search_messages(project_slug: &str, query: &str) -> Result<Vec<Mess
     }
 }
 
+/// Number of search matches sharing one facet value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Search match counts grouped by sender, importance, month and custom field.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchFacets {
+    pub total: i64,
+    #[serde(default)]
+    pub sender: Vec<FacetCount>,
+    #[serde(default)]
+    pub importance: Vec<FacetCount>,
+    #[serde(default)]
+    pub month: Vec<FacetCount>,
+    #[serde(default)]
+    pub fields: std::collections::BTreeMap<String, Vec<FacetCount>>,
+}
+
+/// Get facet counts for a search.
+pub async fn get_search_facets(project_slug: &str, query: &str) -> Result<SearchFacets, ApiError> {
+    let url = format!(
+        "{}/api/projects/{}/search/facets?q={}",
+        api_base_url(),
+        urlencoding::encode(project_slug),
+        urlencoding::encode(query)
+    );
+    let response = Request::get(&url).send().await?;
+
+    if response.ok() {
+        Ok(response.json().await?)
+    } else {
+        Err(ApiError {
+            message: format!("Failed to get search facets: {}", response.status()),
+        })
+    }
+}
+
 /// File reservation response from API.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileReservationResponse {
@@ -500,7 +597,8 @@ pub async fn get_file_reservations(
         project_slug: &'a str,
     }
 
-    let response = Request::post(&url)
+    let response = with_csrf(Request::post(&url))
+        .await
         .header("Content-Type", "application/json")
         .json(&Payload { project_slug })?
         .send()
@@ -545,7 +643,8 @@ pub async fn mark_read(
         is_read: bool,
     }
 
-    let response = Request::post(&url)
+    let response = with_csrf(Request::post(&url))
+        .await
         .header("Content-Type", "application/json")
         .json(&Payload {
             project_slug,
@@ -823,3 +922,57 @@ pub async fn get_archive_activity() -> Result<ActivitySummary, ApiError> {
         })
     }
 }
+
+/// UI preferences (from GET/PUT /api/preferences).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiPreferences {
+    pub owner: String,
+    #[serde(default)]
+    pub preferences: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Get the current user's UI preferences.
+pub async fn get_preferences() -> Result<UiPreferences, ApiError> {
+    let url = format!("{}/api/preferences", api_base_url());
+    let response = Request::get(&url).send().await?;
+
+    if response.ok() {
+        Ok(response.json().await?)
+    } else {
+        Err(ApiError {
+            message: format!("Failed to get preferences: {}", response.status()),
+        })
+    }
+}
+
+/// Update the current user's UI preferences.
+///
+/// # Arguments
+/// * `changes` - Preferences to set; `null` values remove the key
+pub async fn update_preferences(
+    changes: serde_json::Map<String, serde_json::Value>,
+) -> Result<UiPreferences, ApiError> {
+    let url = format!("{}/api/preferences", api_base_url());
+
+    #[derive(Serialize)]
+    struct Payload {
+        preferences: serde_json::Map<String, serde_json::Value>,
+    }
+
+    let response = with_csrf(Request::put(&url))
+        .await
+        .header("Content-Type", "application/json")
+        .json(&Payload {
+            preferences: changes,
+        })?
+        .send()
+        .await?;
+
+    if response.ok() {
+        Ok(response.json().await?)
+    } else {
+        Err(ApiError {
+            message: format!("Failed to update preferences: {}", response.status()),
+        })
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! Displays a circular avatar with initials and a background color
 //! derived from a hash of the agent's name for consistent coloring.
+//! When the agent ID is known, the server-generated identicon is shown instead.
 
+use crate::api::client::api_base_url;
 use leptos::prelude::*;
 
 /// Color palette for agent avatars (WCAG AA compliant with white text)
@@ -77,6 +79,7 @@ impl AvatarSize {
 /// # Props
 /// - `name`: Agent name (used for initials and color hash)
 /// - `size`: Size variant (Sm, Default, Lg, Xl)
+/// - `agent_id`: Agent ID; shows the identicon from `/api/agents/{id}/avatar.svg`
 /// - `class`: Additional CSS classes
 ///
 /// # Example
@@ -91,6 +94,9 @@ pub fn AgentAvatar(
     /// Size variant
     #[prop(default = AvatarSize::Default)]
     size: AvatarSize,
+    /// Agent ID for the server-generated identicon
+    #[prop(optional, into)]
+    agent_id: Option<i64>,
     /// Additional CSS classes
     #[prop(optional, into)]
     class: Option<String>,
@@ -104,6 +110,18 @@ pub fn AgentAvatar(
         class.unwrap_or_default()
     );
 
+    if let Some(id) = agent_id {
+        return view! {
+            <img
+                class={final_class}
+                src={format!("{}/api/agents/{}/avatar.svg?size=96", api_base_url(), id)}
+                alt={format!("Avatar for {}", name)}
+                title={name.clone()}
+            />
+        }
+        .into_any();
+    }
+
     view! {
         <div
             class={final_class}
@@ -115,6 +133,7 @@ pub fn AgentAvatar(
             {initials}
         </div>
     }
+    .into_any()
 }
 
 #[cfg(test)]
@@ -105,6 +105,7 @@ pub fn InlineMessageDetail(
                     if let Some(msg) = message.get() {
                         let subject = msg.subject.clone();
                         let body = msg.body_md.clone();
+                        let body_html = msg.body_rendered.clone();
                         let created = msg.created_ts.clone();
                         let importance = msg.importance.clone();
                         let ack_required = msg.ack_required;
@@ -157,11 +158,19 @@ pub fn InlineMessageDetail(
 
                                 // Message Body
                                 <div class="p-6">
-                                    <div class="prose dark:prose-invert max-w-none">
-                                        <pre class="whitespace-pre-wrap font-sans text-foreground bg-transparent p-0 overflow-visible text-sm">
-                                            {body}
-                                        </pre>
-                                    </div>
+                                    {match body_html {
+                                        // Sanitized server-side; safe to inject
+                                        Some(html) => view! {
+                                            <div class="prose dark:prose-invert max-w-none text-sm" inner_html=html></div>
+                                        }.into_any(),
+                                        None => view! {
+                                            <div class="prose dark:prose-invert max-w-none">
+                                                <pre class="whitespace-pre-wrap font-sans text-foreground bg-transparent p-0 overflow-visible text-sm">
+                                                    {body}
+                                                </pre>
+                                            </div>
+                                        }.into_any(),
+                                    }}
                                 </div>
 
                                 // Open in full view link - shadcn link pattern
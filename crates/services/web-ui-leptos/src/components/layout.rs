@@ -1,7 +1,8 @@
 //! Main layout component with navigation.
 //! Digital Correspondence design - postal aesthetics meets terminal precision.
 
-use super::{Button, ButtonSize, ButtonVariant};
+use super::{Button, ButtonSize, ButtonVariant, PushToggle};
+use crate::api::client;
 use leptos::prelude::*;
 use leptos_router::components::Outlet;
 use leptos_router::hooks::use_location;
@@ -9,7 +10,7 @@ use leptos_router::hooks::use_location;
 /// Main layout wrapper with navigation and content outlet.
 #[component]
 pub fn Layout() -> impl IntoView {
-    // Dark mode signal - persisted to localStorage
+    // Dark mode signal - cached in localStorage, stored server-side as the "theme" preference
     let (dark_mode, set_dark_mode) = signal(false);
 
     // Mobile navigation state
@@ -27,6 +28,31 @@ pub fn Layout() -> impl IntoView {
         {
             set_dark_mode.set(true);
         }
+
+        // Server-side preference wins over the local cache
+        leptos::task::spawn_local(async move {
+            if let Ok(prefs) = client::get_preferences().await {
+                match prefs.preferences.get("theme").and_then(|v| v.as_str()) {
+                    Some("dark") => set_dark_mode.set(true),
+                    Some("light") => set_dark_mode.set(false),
+                    _ => {}
+                }
+            }
+        });
+    });
+
+    let toggle_dark_mode = Callback::new(move |_| {
+        set_dark_mode.update(|v| *v = !*v);
+        let theme = if dark_mode.get_untracked() {
+            "dark"
+        } else {
+            "light"
+        };
+        leptos::task::spawn_local(async move {
+            let mut changes = serde_json::Map::new();
+            changes.insert("theme".to_string(), theme.into());
+            let _ = client::update_preferences(changes).await;
+        });
     });
 
     // Toggle dark mode class on document and save preference
@@ -100,11 +126,14 @@ pub fn Layout() -> impl IntoView {
                                 <span class="text-xs font-medium">"Online"</span>
                             </div>
 
+                            // Urgent message push notifications
+                            <PushToggle />
+
                             // Dark mode toggle
                             <Button
                                 variant=ButtonVariant::Ghost
                                 size=ButtonSize::Icon
-                                on_click=Callback::new(move |_| set_dark_mode.update(|v| *v = !*v))
+                                on_click=toggle_dark_mode
                                 title={if dark_mode.get() { "Switch to light mode".to_string() } else { "Switch to dark mode".to_string() }}
                                 class="border border-border rounded-full hover:bg-accent".to_string()
                             >
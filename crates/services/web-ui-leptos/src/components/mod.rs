@@ -21,6 +21,7 @@ pub mod overseer_composer;
 pub mod pagination;
 pub mod progress;
 pub mod project_card;
+pub mod push_toggle;
 pub mod select;
 pub mod separator;
 pub mod skeleton;
@@ -53,6 +54,7 @@ pub use message_detail_header::MessageDetailHeader;
 pub use overseer_composer::{OverseerComposeProps, OverseerComposer};
 pub use pagination::Pagination;
 pub use project_card::{ProjectCard, ProjectStatus, determine_project_status};
+pub use push_toggle::PushToggle;
 pub use select::{Select, SelectIcon, SelectOption};
 pub use separator::{Orientation, Separator};
 pub use skeleton::{
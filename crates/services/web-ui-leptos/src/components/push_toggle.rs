@@ -0,0 +1,70 @@
+//! Web Push toggle button.
+//!
+//! Subscribes the browser to push notifications for urgent messages via the
+//! `mouchakEnablePush` helper in `index.html`, which registers the
+//! `push-sw.js` service worker and posts the subscription to the server.
+
+use crate::components::{Button, ButtonSize, ButtonVariant};
+use leptos::prelude::*;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = window, js_name = mouchakEnablePush, catch)]
+    fn enable_push() -> Result<js_sys::Promise, JsValue>;
+}
+
+/// Push subscription state shown by [`PushToggle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PushState {
+    Off,
+    Pending,
+    On,
+    /// Unsupported browser, permission denied or push disabled on the server
+    Unavailable,
+}
+
+/// Header button that enables urgent-message push notifications.
+#[component]
+pub fn PushToggle() -> impl IntoView {
+    let state = RwSignal::new(PushState::Off);
+
+    let on_click = Callback::new(move |_| {
+        if matches!(state.get_untracked(), PushState::Pending | PushState::On) {
+            return;
+        }
+        state.set(PushState::Pending);
+        leptos::task::spawn_local(async move {
+            let subscribed = match enable_push() {
+                Ok(promise) => wasm_bindgen_futures::JsFuture::from(promise)
+                    .await
+                    .ok()
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                Err(_) => false,
+            };
+            state.set(if subscribed {
+                PushState::On
+            } else {
+                PushState::Unavailable
+            });
+        });
+    });
+
+    view! {
+        <Button
+            variant=ButtonVariant::Ghost
+            size=ButtonSize::Icon
+            on_click=on_click
+            title="Notify me of urgent messages".to_string()
+            aria_label="Enable urgent message notifications".to_string()
+            class="border border-border rounded-full hover:bg-accent".to_string()
+        >
+            {move || match state.get() {
+                PushState::On => view! { <i data-lucide="bell-ring" class="icon-lg text-primary"></i> }.into_any(),
+                PushState::Unavailable => view! { <i data-lucide="bell-off" class="icon-lg text-muted-foreground"></i> }.into_any(),
+                _ => view! { <i data-lucide="bell" class="icon-lg text-muted-foreground"></i> }.into_any(),
+            }}
+        </Button>
+    }
+}
@@ -242,7 +242,7 @@ pub fn Agents() -> impl IntoView {
                                                 <div class="flex items-start justify-between mb-4">
                                                     <div class="flex items-center gap-3">
                                                         <div class="group-hover:scale-105 transition-transform">
-                                                            <AgentAvatar name=name_for_avatar size=AvatarSize::Lg />
+                                                            <AgentAvatar name=name_for_avatar size=AvatarSize::Lg agent_id=agent.id />
                                                         </div>
                                                         <div>
                                                             <h3 class="font-display font-semibold text-charcoal-800 dark:text-cream-100">{name.clone()}</h3>
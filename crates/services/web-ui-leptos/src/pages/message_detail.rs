@@ -116,6 +116,7 @@ pub fn MessageDetail() -> impl IntoView {
                 } else if let Some(msg) = message.get() {
                     let subject = msg.subject.clone();
                     let body = msg.body_md.clone();
+                    let body_html = msg.body_rendered.clone();
                     let created = msg.created_ts.clone();
                     let importance = msg.importance.clone();
                     let ack_required = msg.ack_required;
@@ -184,11 +185,19 @@ pub fn MessageDetail() -> impl IntoView {
 
                             // Message Body
                             <div class="p-6">
-                                <div class="prose dark:prose-invert max-w-none">
-                                    <pre class="whitespace-pre-wrap font-sans text-charcoal-700 dark:text-charcoal-300 bg-transparent p-0 overflow-visible">
-                                        {body}
-                                    </pre>
-                                </div>
+                                {match body_html {
+                                    // Sanitized server-side; safe to inject
+                                    Some(html) => view! {
+                                        <div class="prose dark:prose-invert max-w-none" inner_html=html></div>
+                                    }.into_any(),
+                                    None => view! {
+                                        <div class="prose dark:prose-invert max-w-none">
+                                            <pre class="whitespace-pre-wrap font-sans text-charcoal-700 dark:text-charcoal-300 bg-transparent p-0 overflow-visible">
+                                                {body}
+                                            </pre>
+                                        </div>
+                                    }.into_any(),
+                                }}
                             </div>
 
                             // Message Metadata
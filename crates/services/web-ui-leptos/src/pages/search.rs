@@ -1,9 +1,9 @@
 //! Search results page with FTS5 highlighting.
 //!
 //! Displays search results with query term highlighting,
-//! filter chips, and debounced search-as-you-type.
+//! filter chips, facet counts, and debounced search-as-you-type.
 
-use crate::api::client::{self, Message, Project};
+use crate::api::client::{self, FacetCount, Message, Project, SearchFacets};
 use crate::components::{Badge, BadgeVariant, Card, CardContent, Input, Pagination, Skeleton};
 use leptos::prelude::*;
 use leptos_router::hooks::use_query_map;
@@ -29,6 +29,7 @@ pub fn Search() -> impl IntoView {
     let loading = RwSignal::new(false);
     let error = RwSignal::new(Option::<String>::None);
     let has_searched = RwSignal::new(false);
+    let facets = RwSignal::new(Option::<SearchFacets>::None);
 
     // Pagination state
     let has_more = RwSignal::new(false);
@@ -67,6 +68,7 @@ pub fn Search() -> impl IntoView {
 
         if query.is_empty() {
             results.set(vec![]);
+            facets.set(None);
             has_searched.set(false);
             return;
         }
@@ -100,8 +102,18 @@ pub fn Search() -> impl IntoView {
                 Err(e) => {
                     error.set(Some(e.message));
                     loading.set(false);
+                    return;
                 }
             }
+
+            // Facets count every match, not just the returned page
+            match client::get_search_facets(&search_project, &query).await {
+                Ok(f) => {
+                    total_count.set(f.total.max(results.get_untracked().len() as i64));
+                    facets.set(Some(f));
+                }
+                Err(_) => facets.set(None),
+            }
         });
     });
 
@@ -227,6 +239,12 @@ pub fn Search() -> impl IntoView {
                 </div>
             })}
 
+            // Facet counts
+            {move || {
+                let f = facets.get().filter(|f| f.total > 0)?;
+                (!loading.get()).then(|| view! { <SearchFacetPanel facets=f /> })
+            }}
+
             // Loading state
             {move || loading.get().then(|| view! {
                 <div class="space-y-4">
@@ -311,6 +329,38 @@ pub fn Search() -> impl IntoView {
     }
 }
 
+/// Match counts by sender, importance, month and custom field.
+#[component]
+fn SearchFacetPanel(facets: SearchFacets) -> impl IntoView {
+    let mut groups: Vec<(String, Vec<FacetCount>)> = vec![
+        ("Sender".to_string(), facets.sender),
+        ("Importance".to_string(), facets.importance),
+        ("Month".to_string(), facets.month),
+    ];
+    groups.extend(facets.fields);
+    groups.retain(|(_, counts)| !counts.is_empty());
+
+    view! {
+        <Card>
+            <CardContent class="p-4 space-y-2".to_string()>
+                <p class="text-sm font-medium text-foreground">
+                    {format!("{} matching messages", facets.total)}
+                </p>
+                {groups.into_iter().map(|(name, counts)| view! {
+                    <div class="flex flex-wrap items-center gap-2" role="list" aria-label=name.clone()>
+                        <span class="text-xs text-muted-foreground w-24">{name.clone()}</span>
+                        {counts.into_iter().map(|c| view! {
+                            <Badge variant=BadgeVariant::Outline>
+                                {format!("{} ({})", c.value, c.count)}
+                            </Badge>
+                        }).collect::<Vec<_>>()}
+                    </div>
+                }).collect::<Vec<_>>()}
+            </CardContent>
+        </Card>
+    }
+}
+
 /// Individual search result item with highlighting.
 #[component]
 fn SearchResultItem(message: Message, query: String) -> impl IntoView {
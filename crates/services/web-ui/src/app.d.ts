@@ -8,6 +8,9 @@ declare global {
 		// interface PageState {}
 		// interface Platform {}
 	}
+
+	/** API version the UI was built against (defined in vite.config.ts) */
+	const __UI_API_VERSION__: string;
 }
 
 export {};
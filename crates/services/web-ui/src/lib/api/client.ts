@@ -526,6 +526,15 @@ export async function checkHealth(): Promise<{ status: string }> {
 	return request<{ status: string }>('/health');
 }
 
+export interface VersionInfo {
+	api_version: string;
+	min_ui_api_version: string;
+}
+
+export async function getVersion(): Promise<VersionInfo> {
+	return request<VersionInfo>('/version');
+}
+
 // ============================================================================
 // Dashboard Stats
 // ============================================================================
@@ -0,0 +1,52 @@
+/**
+ * UI/API version handshake.
+ *
+ * The UI records the API version it was built against; the server reports
+ * its own API version and the oldest UI build it supports.
+ *
+ * @module utils/version
+ */
+
+import { getVersion } from '$lib/api/client';
+
+export const UI_API_VERSION = __UI_API_VERSION__;
+
+function parseVersion(version: string): [number, number, number] | null {
+	const match = version.trim().match(/^v?(\d+)\.(\d+)\.(\d+)/);
+	return match ? [Number(match[1]), Number(match[2]), Number(match[3])] : null;
+}
+
+function compareVersions(a: [number, number, number], b: [number, number, number]): number {
+	for (let i = 0; i < 3; i++) {
+		if (a[i] !== b[i]) return a[i] - b[i];
+	}
+	return 0;
+}
+
+/**
+ * Checks this UI against the server.
+ *
+ * @returns A description of the mismatch, or null when compatible (or when
+ * the server predates the handshake and cannot be checked).
+ */
+export async function checkApiCompatibility(): Promise<string | null> {
+	let server;
+	try {
+		server = await getVersion();
+	} catch {
+		return null;
+	}
+
+	const ui = parseVersion(UI_API_VERSION);
+	const min = parseVersion(server.min_ui_api_version);
+	const api = parseVersion(server.api_version);
+	if (!ui || !min || !api) return null;
+
+	if (compareVersions(ui, min) < 0) {
+		return `This UI was built for API ${UI_API_VERSION}, but the server (API ${server.api_version}) requires ${server.min_ui_api_version} or newer. Reload to fetch the current UI, or rebuild it.`;
+	}
+	if (compareVersions(ui, api) > 0) {
+		return `This UI was built for API ${UI_API_VERSION}, which is newer than the server's API ${server.api_version}. Upgrade the server.`;
+	}
+	return null;
+}
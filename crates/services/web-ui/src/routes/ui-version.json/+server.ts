@@ -0,0 +1,9 @@
+// Prerendered to build/ui-version.json so the server can check, at startup,
+// which API version the embedded UI was built against.
+import { json } from '@sveltejs/kit';
+
+export const prerender = true;
+
+export function GET() {
+	return json({ api_version: __UI_API_VERSION__ });
+}
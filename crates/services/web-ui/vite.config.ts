@@ -1,8 +1,19 @@
 import { sveltekit } from '@sveltejs/kit/vite';
 import { SvelteKitPWA } from '@vite-pwa/sveltekit';
 import { defineConfig } from 'vite';
+import { readFileSync } from 'node:fs';
+
+// API version this UI is built against (the server crate's version).
+// Checked against /api/version at load and embedded as ui-version.json.
+const apiVersion =
+	readFileSync(new URL('../../libs/mouchak-mail-server/Cargo.toml', import.meta.url), 'utf-8').match(
+		/^version\s*=\s*"([^"]+)"/m
+	)?.[1] ?? '0.0.0';
 
 export default defineConfig({
+	define: {
+		__UI_API_VERSION__: JSON.stringify(apiVersion)
+	},
 	resolve: {
 		conditions: ['svelte', 'browser', 'import', 'default']
 	},
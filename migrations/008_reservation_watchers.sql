@@ -0,0 +1,12 @@
+-- File reservation release watchers (idempotent migration)
+-- Agents waiting on a reserved path; notified_ts is set once a release receipt is sent
+CREATE TABLE IF NOT EXISTS file_reservation_watchers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    reservation_id INTEGER NOT NULL REFERENCES file_reservations(id),
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    notified_ts DATETIME,
+    UNIQUE(reservation_id, agent_id)
+);
+CREATE INDEX IF NOT EXISTS idx_reservation_watchers_pending
+    ON file_reservation_watchers(reservation_id) WHERE notified_ts IS NULL;
@@ -0,0 +1,27 @@
+-- Project focus windows (idempotent migration)
+-- Time-boxed quiet periods during which low-importance deliveries are deferred
+CREATE TABLE IF NOT EXISTS project_focus_windows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    starts_ts DATETIME NOT NULL,
+    ends_ts DATETIME NOT NULL,
+    allowed_importance TEXT NOT NULL DEFAULT 'high,urgent',
+    reason TEXT NOT NULL DEFAULT '',
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ended_ts DATETIME
+);
+CREATE INDEX IF NOT EXISTS idx_focus_windows_project ON project_focus_windows(project_id, ends_ts);
+
+-- Recipient rows held back by a focus window; copied to message_recipients on delivery
+CREATE TABLE IF NOT EXISTS deferred_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    focus_window_id INTEGER NOT NULL REFERENCES project_focus_windows(id),
+    message_id INTEGER NOT NULL REFERENCES messages(id),
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    recipient_type TEXT NOT NULL DEFAULT 'to',
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_ts DATETIME,
+    UNIQUE(message_id, agent_id)
+);
+CREATE INDEX IF NOT EXISTS idx_deferred_deliveries_pending
+    ON deferred_deliveries(focus_window_id) WHERE delivered_ts IS NULL;
@@ -0,0 +1,36 @@
+-- Searchable attachment text (idempotent migration)
+-- Text-like attachment contents are extracted here and indexed for full-text search;
+-- binary and oversized files are never stored
+CREATE TABLE IF NOT EXISTS attachment_texts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    message_id INTEGER NOT NULL REFERENCES messages(id),
+    attachment_ref TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    content TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    truncated INTEGER NOT NULL DEFAULT 0,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(message_id, attachment_ref)
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_texts_project ON attachment_texts(project_id);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS attachment_texts_fts USING fts5(
+    filename,
+    content,
+    content='attachment_texts',
+    content_rowid='id'
+);
+
+CREATE TRIGGER IF NOT EXISTS attachment_texts_ai AFTER INSERT ON attachment_texts BEGIN
+  INSERT INTO attachment_texts_fts(rowid, filename, content) VALUES (new.id, new.filename, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS attachment_texts_ad AFTER DELETE ON attachment_texts BEGIN
+  INSERT INTO attachment_texts_fts(attachment_texts_fts, rowid, filename, content) VALUES('delete', old.id, old.filename, old.content);
+END;
+
+-- Drop indexed text along with its message
+CREATE TRIGGER IF NOT EXISTS messages_ad_attachment_texts AFTER DELETE ON messages BEGIN
+  DELETE FROM attachment_texts WHERE message_id = old.id;
+END;
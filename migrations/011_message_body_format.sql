@@ -0,0 +1,10 @@
+-- Message body formats (idempotent migration)
+-- Only non-Markdown bodies get a row; a missing row means 'markdown'
+CREATE TABLE IF NOT EXISTS message_body_formats (
+    message_id INTEGER PRIMARY KEY REFERENCES messages(id),
+    body_format TEXT NOT NULL CHECK (body_format IN ('markdown', 'plain', 'json'))
+);
+
+CREATE TRIGGER IF NOT EXISTS messages_ad_body_format AFTER DELETE ON messages BEGIN
+  DELETE FROM message_body_formats WHERE message_id = old.id;
+END;
@@ -0,0 +1,23 @@
+-- Web Push notifications (idempotent migration)
+-- Browser push subscriptions; a NULL project_id subscribes to every project
+CREATE TABLE IF NOT EXISTS web_push_subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    endpoint TEXT NOT NULL UNIQUE,
+    p256dh TEXT NOT NULL,
+    auth TEXT NOT NULL,
+    project_id INTEGER REFERENCES projects(id),
+    label TEXT NOT NULL DEFAULT '',
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_push_ts DATETIME,
+    failure_count INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS idx_web_push_subscriptions_project ON web_push_subscriptions(project_id);
+
+-- Single-row push state: VAPID key pair and the last message considered for push
+CREATE TABLE IF NOT EXISTS web_push_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    vapid_public_key TEXT NOT NULL,
+    vapid_private_key TEXT NOT NULL,
+    last_message_id INTEGER NOT NULL DEFAULT 0,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,10 @@
+-- UI preferences (idempotent migration)
+-- Key-value UI state per owner: 'user:<subject>' or 'agent:<agent id>'.
+-- Values are JSON documents.
+CREATE TABLE IF NOT EXISTS ui_preferences (
+    owner TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (owner, key)
+);
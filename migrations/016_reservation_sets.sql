@@ -0,0 +1,17 @@
+-- Reservation sets (idempotent migration)
+-- Multi-path reservations acquired all-or-nothing and released together;
+-- each member is an ordinary file_reservations row.
+CREATE TABLE IF NOT EXISTS reservation_sets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    reason TEXT NOT NULL DEFAULT '',
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    released_ts DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS reservation_set_members (
+    set_id INTEGER NOT NULL REFERENCES reservation_sets(id),
+    reservation_id INTEGER NOT NULL UNIQUE REFERENCES file_reservations(id),
+    PRIMARY KEY (set_id, reservation_id)
+);
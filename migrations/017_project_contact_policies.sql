@@ -0,0 +1,15 @@
+-- Project contact policies (idempotent migration)
+-- Allow/deny rules controlling which other projects may initiate contact
+-- with a project. Cross-project contact is denied unless a rule allows it.
+CREATE TABLE IF NOT EXISTS project_contact_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    action TEXT NOT NULL CHECK (action IN ('allow', 'deny')),
+    target_kind TEXT NOT NULL CHECK (target_kind IN ('project', 'product')),
+    target TEXT NOT NULL,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (project_id, target_kind, target)
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_contact_rules_project
+    ON project_contact_rules(project_id);
@@ -0,0 +1,18 @@
+-- Agent SLAs (idempotent migration)
+-- A project's acknowledgment deadlines per message importance, and the
+-- recipients already reported to the overseer for missing one.
+CREATE TABLE IF NOT EXISTS project_slas (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    importance TEXT NOT NULL CHECK (importance IN ('low', 'normal', 'high', 'urgent')),
+    ack_within_seconds INTEGER NOT NULL CHECK (ack_within_seconds > 0),
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (project_id, importance)
+);
+
+CREATE TABLE IF NOT EXISTS sla_violations (
+    message_id INTEGER NOT NULL REFERENCES messages(id),
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    reported_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (message_id, agent_id)
+);
@@ -0,0 +1,37 @@
+-- Thread workflows (idempotent migration)
+-- Named state machines defined per project, attached to threads, with the
+-- history of every transition.
+CREATE TABLE IF NOT EXISTS workflows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    name TEXT NOT NULL,
+    -- JSON: {"states": [{"name", "responsible"}], "transitions": [{"from", "to"}]}
+    definition TEXT NOT NULL,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (project_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS thread_workflows (
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    thread_id TEXT NOT NULL,
+    workflow_id INTEGER NOT NULL REFERENCES workflows(id),
+    state TEXT NOT NULL,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (project_id, thread_id)
+);
+
+CREATE TABLE IF NOT EXISTS workflow_transitions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    thread_id TEXT NOT NULL,
+    -- NULL when the workflow was started
+    from_state TEXT,
+    to_state TEXT NOT NULL,
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    note TEXT,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_workflow_transitions_thread
+    ON workflow_transitions(project_id, thread_id);
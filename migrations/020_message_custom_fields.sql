@@ -0,0 +1,21 @@
+-- Per-project custom message fields (idempotent migration)
+-- Projects declare typed fields; messages store their values as a JSON object.
+CREATE TABLE IF NOT EXISTS project_custom_fields (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL CHECK (field_type IN ('string', 'number', 'boolean')),
+    required INTEGER NOT NULL DEFAULT 0,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (project_id, name)
+);
+
+-- Only messages with field values get a row
+CREATE TABLE IF NOT EXISTS message_custom_fields (
+    message_id INTEGER PRIMARY KEY REFERENCES messages(id),
+    fields TEXT NOT NULL CHECK (json_valid(fields))
+);
+
+CREATE TRIGGER IF NOT EXISTS messages_ad_custom_fields AFTER DELETE ON messages BEGIN
+  DELETE FROM message_custom_fields WHERE message_id = old.id;
+END;
@@ -0,0 +1,16 @@
+-- External HTTP tools proxied through MCP (idempotent migration)
+-- Operators register endpoints that appear as extra MCP tools.
+CREATE TABLE IF NOT EXISTS external_tools (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    input_schema TEXT NOT NULL CHECK (json_valid(input_schema)),
+    url TEXT NOT NULL,
+    -- Sent as-is in the Authorization header; never returned by the API
+    auth_header TEXT,
+    timeout_ms INTEGER NOT NULL DEFAULT 10000,
+    max_result_bytes INTEGER NOT NULL DEFAULT 65536,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,26 @@
+-- GitHub issue/PR cross-posting (idempotent migration)
+-- Projects map to a repository; mirrored threads link to one issue or PR.
+CREATE TABLE IF NOT EXISTS project_github_repos (
+    project_id INTEGER PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+    -- "owner/name"
+    repo TEXT NOT NULL,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS github_thread_links (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    thread_id TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    issue_number INTEGER NOT NULL,
+    issue_url TEXT NOT NULL,
+    -- Newest thread message posted to GitHub
+    last_message_id INTEGER NOT NULL DEFAULT 0,
+    -- Newest GitHub comment imported into the thread
+    last_comment_id INTEGER NOT NULL DEFAULT 0,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    synced_ts DATETIME,
+    UNIQUE (project_id, thread_id),
+    UNIQUE (repo, issue_number)
+);
@@ -0,0 +1,17 @@
+-- Localized system messages (idempotent migration)
+-- Locale of a project's system messages; projects without a row use 'en'
+CREATE TABLE IF NOT EXISTS project_locales (
+    project_id INTEGER PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+    -- BCP 47 tag, e.g. 'de' or 'pt-BR'
+    locale TEXT NOT NULL,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Custom templates; they take precedence over the built-in catalog of their locale
+CREATE TABLE IF NOT EXISTS message_templates (
+    locale TEXT NOT NULL,
+    key TEXT NOT NULL,
+    template TEXT NOT NULL,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (locale, key)
+);
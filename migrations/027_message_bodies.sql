@@ -0,0 +1,14 @@
+-- Offloaded message bodies (idempotent migration)
+-- Bodies over the inline size cap are kept here; messages.body_md holds a preview.
+-- A missing row means the inline body is complete
+CREATE TABLE IF NOT EXISTS message_bodies (
+    message_id INTEGER PRIMARY KEY REFERENCES messages(id),
+    body_md TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    sha256 TEXT NOT NULL,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER IF NOT EXISTS messages_ad_bodies AFTER DELETE ON messages BEGIN
+  DELETE FROM message_bodies WHERE message_id = old.id;
+END;
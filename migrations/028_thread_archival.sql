@@ -0,0 +1,16 @@
+-- Archived (inactive) threads (idempotent migration)
+-- A row marks a thread archived: hidden from the default thread listing.
+-- Any new message in the thread deletes the row, reactivating it
+CREATE TABLE IF NOT EXISTS archived_threads (
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    thread_id TEXT NOT NULL,
+    last_message_ts DATETIME NOT NULL,
+    summary_message_id INTEGER,
+    archived_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (project_id, thread_id)
+);
+
+CREATE TRIGGER IF NOT EXISTS messages_ai_thread_reactivate AFTER INSERT ON messages
+WHEN new.thread_id IS NOT NULL BEGIN
+  DELETE FROM archived_threads WHERE project_id = new.project_id AND thread_id = new.thread_id;
+END;
@@ -0,0 +1,17 @@
+-- Thread watchers (idempotent migration)
+-- A watcher gets a copy of each new message in the thread without being
+-- addressed. Copies are delivered as bcc recipients and listed in
+-- watcher_deliveries so they can be told apart from addressed mail
+CREATE TABLE IF NOT EXISTS thread_watchers (
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    thread_id TEXT NOT NULL,
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (project_id, thread_id, agent_id)
+);
+
+CREATE TABLE IF NOT EXISTS watcher_deliveries (
+    message_id INTEGER NOT NULL REFERENCES messages(id),
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    PRIMARY KEY (message_id, agent_id)
+);
@@ -0,0 +1,17 @@
+-- Delegation grants (idempotent migration)
+-- A grantor lets a delegate send messages on its behalf. Messages sent that
+-- way keep the grantor as sender and name the delegate in message_delegations
+CREATE TABLE IF NOT EXISTS delegation_grants (
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    grantor_id INTEGER NOT NULL REFERENCES agents(id),
+    delegate_id INTEGER NOT NULL REFERENCES agents(id),
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (grantor_id, delegate_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_delegation_grants_project ON delegation_grants(project_id);
+
+CREATE TABLE IF NOT EXISTS message_delegations (
+    message_id INTEGER PRIMARY KEY REFERENCES messages(id),
+    delegate_id INTEGER NOT NULL REFERENCES agents(id)
+);
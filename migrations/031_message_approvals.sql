@@ -0,0 +1,36 @@
+-- Approval-gated sends (idempotent migration)
+-- Messages matching one of a project's approval rules are stored but their
+-- recipient rows are held in held_deliveries until an approver decides.
+-- pending_approvals keeps every decision, including timeouts, for audit
+CREATE TABLE IF NOT EXISTS approval_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    min_importance TEXT CHECK (min_importance IN ('low', 'normal', 'high', 'urgent')),
+    min_recipients INTEGER,
+    keyword TEXT,
+    approvers TEXT NOT NULL DEFAULT '',
+    timeout_seconds INTEGER NOT NULL CHECK (timeout_seconds > 0),
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_approval_rules_project ON approval_rules(project_id);
+
+CREATE TABLE IF NOT EXISTS pending_approvals (
+    message_id INTEGER PRIMARY KEY REFERENCES messages(id),
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    rule_id INTEGER NOT NULL,
+    approvers TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'rejected')),
+    expires_ts DATETIME NOT NULL,
+    decided_by TEXT,
+    decided_ts DATETIME,
+    reason TEXT,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_pending_approvals_status ON pending_approvals(status, expires_ts);
+
+CREATE TABLE IF NOT EXISTS held_deliveries (
+    message_id INTEGER NOT NULL REFERENCES messages(id),
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    recipient_type TEXT NOT NULL DEFAULT 'to',
+    PRIMARY KEY (message_id, agent_id)
+);
@@ -0,0 +1,30 @@
+-- Ranked message search (idempotent migration)
+-- Indexes subject and body together so searches match either and can be
+-- ranked with bm25(). Prefix indexes keep `term*` queries fast.
+-- messages_fts (body only) stays in place for existing readers
+CREATE VIRTUAL TABLE IF NOT EXISTS message_search USING fts5(
+    subject,
+    body_md,
+    content='messages',
+    content_rowid='id',
+    prefix='2 3'
+);
+
+CREATE TRIGGER IF NOT EXISTS message_search_ai AFTER INSERT ON messages BEGIN
+  INSERT INTO message_search(rowid, subject, body_md) VALUES (new.id, new.subject, new.body_md);
+END;
+
+CREATE TRIGGER IF NOT EXISTS message_search_ad AFTER DELETE ON messages BEGIN
+  INSERT INTO message_search(message_search, rowid, subject, body_md) VALUES ('delete', old.id, old.subject, old.body_md);
+END;
+
+CREATE TRIGGER IF NOT EXISTS message_search_au AFTER UPDATE OF subject, body_md ON messages BEGIN
+  INSERT INTO message_search(message_search, rowid, subject, body_md) VALUES ('delete', old.id, old.subject, old.body_md);
+  INSERT INTO message_search(rowid, subject, body_md) VALUES (new.id, new.subject, new.body_md);
+END;
+
+-- Index messages that predate this table; a no-op once anything is indexed
+INSERT INTO message_search(message_search)
+SELECT 'rebuild'
+WHERE NOT EXISTS (SELECT 1 FROM message_search_docsize)
+  AND EXISTS (SELECT 1 FROM messages);
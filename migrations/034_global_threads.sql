@@ -0,0 +1,59 @@
+-- Global thread ids (idempotent migration)
+-- Thread ids are project-scoped strings, so the same id in two projects may
+-- name unrelated threads. Each (project, thread) maps to a ULID; a message
+-- sent with a global thread id as its thread id joins that global thread,
+-- which is how one conversation spans the projects of a product
+CREATE TABLE IF NOT EXISTS global_threads (
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    thread_id TEXT NOT NULL,
+    global_thread_id TEXT NOT NULL,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (project_id, thread_id)
+);
+CREATE INDEX IF NOT EXISTS idx_global_threads_global ON global_threads(global_thread_id);
+CREATE INDEX IF NOT EXISTS idx_global_threads_thread ON global_threads(thread_id);
+
+-- Map threads that predate this table; a no-op once anything is mapped.
+-- Before global ids, product tools merged equal thread ids across a
+-- product's projects, so those keep sharing one global thread. Each ULID
+-- takes its thread's first message time and 16 random base32 digits
+WITH pairs AS MATERIALIZED (
+    SELECT m.project_id, m.thread_id, MIN(m.created_ts) AS first_ts,
+           COALESCE(
+               (SELECT 'product:' || MIN(l.product_id) FROM product_project_links AS l
+                WHERE l.project_id = m.project_id),
+               'project:' || m.project_id
+           ) AS scope
+    FROM messages AS m
+    WHERE m.thread_id IS NOT NULL
+      AND NOT EXISTS (SELECT 1 FROM global_threads)
+    GROUP BY m.project_id, m.thread_id
+),
+threads AS MATERIALIZED (
+    SELECT scope, thread_id,
+           MAX(CAST((julianday(MIN(first_ts)) - 2440587.5) * 86400000 AS INTEGER), 0) AS ms,
+           '0123456789ABCDEFGHJKMNPQRSTVWXYZ' AS a
+    FROM pairs
+    GROUP BY scope, thread_id
+),
+ids AS MATERIALIZED (
+    SELECT scope, thread_id,
+           substr(a, ((ms >> 45) & 7) + 1, 1) || substr(a, ((ms >> 40) & 31) + 1, 1) ||
+           substr(a, ((ms >> 35) & 31) + 1, 1) || substr(a, ((ms >> 30) & 31) + 1, 1) ||
+           substr(a, ((ms >> 25) & 31) + 1, 1) || substr(a, ((ms >> 20) & 31) + 1, 1) ||
+           substr(a, ((ms >> 15) & 31) + 1, 1) || substr(a, ((ms >> 10) & 31) + 1, 1) ||
+           substr(a, ((ms >> 5) & 31) + 1, 1) || substr(a, (ms & 31) + 1, 1) ||
+           substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+           substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+           substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+           substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+           substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+           substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+           substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+           substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) AS global_thread_id
+    FROM threads
+)
+INSERT INTO global_threads (project_id, thread_id, global_thread_id, created_ts)
+SELECT p.project_id, p.thread_id, i.global_thread_id, p.first_ts
+FROM pairs AS p
+JOIN ids AS i ON i.scope = p.scope AND i.thread_id = p.thread_id;
@@ -0,0 +1,20 @@
+-- Message labels (idempotent migration)
+-- Colored labels are declared per project and applied to messages by
+-- agents, for triage beyond importance levels
+CREATE TABLE IF NOT EXISTS labels (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    name TEXT NOT NULL,
+    color TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (project_id, name)
+);
+CREATE TABLE IF NOT EXISTS message_labels (
+    message_id INTEGER NOT NULL REFERENCES messages(id),
+    label_id INTEGER NOT NULL REFERENCES labels(id),
+    applied_by INTEGER REFERENCES agents(id),
+    applied_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (message_id, label_id)
+);
+CREATE INDEX IF NOT EXISTS idx_message_labels_label ON message_labels(label_id, message_id);
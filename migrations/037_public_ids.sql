@@ -0,0 +1,85 @@
+-- Public ids (idempotent migration)
+-- Integer keys leak creation order and collide between databases, so every
+-- project, agent and message also gets a ULID that external systems can
+-- reference. Ids are assigned by trigger on insert and never change
+CREATE TABLE IF NOT EXISTS public_ids (
+    entity TEXT NOT NULL CHECK (entity IN ('project', 'agent', 'message')),
+    entity_id INTEGER NOT NULL,
+    public_id TEXT NOT NULL UNIQUE,
+    PRIMARY KEY (entity, entity_id)
+);
+
+-- A fresh ULID: the current millisecond and 16 random base32 digits
+CREATE VIEW IF NOT EXISTS new_public_id AS
+SELECT substr(a, ((ms >> 45) & 7) + 1, 1) || substr(a, ((ms >> 40) & 31) + 1, 1) ||
+       substr(a, ((ms >> 35) & 31) + 1, 1) || substr(a, ((ms >> 30) & 31) + 1, 1) ||
+       substr(a, ((ms >> 25) & 31) + 1, 1) || substr(a, ((ms >> 20) & 31) + 1, 1) ||
+       substr(a, ((ms >> 15) & 31) + 1, 1) || substr(a, ((ms >> 10) & 31) + 1, 1) ||
+       substr(a, ((ms >> 5) & 31) + 1, 1) || substr(a, (ms & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) AS public_id
+FROM (
+    SELECT MAX(CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER), 0) AS ms,
+           '0123456789ABCDEFGHJKMNPQRSTVWXYZ' AS a
+);
+
+CREATE TRIGGER IF NOT EXISTS projects_ai_public_id AFTER INSERT ON projects BEGIN
+  INSERT OR IGNORE INTO public_ids (entity, entity_id, public_id)
+  SELECT 'project', new.id, public_id FROM new_public_id;
+END;
+CREATE TRIGGER IF NOT EXISTS agents_ai_public_id AFTER INSERT ON agents BEGIN
+  INSERT OR IGNORE INTO public_ids (entity, entity_id, public_id)
+  SELECT 'agent', new.id, public_id FROM new_public_id;
+END;
+CREATE TRIGGER IF NOT EXISTS messages_ai_public_id AFTER INSERT ON messages BEGIN
+  INSERT OR IGNORE INTO public_ids (entity, entity_id, public_id)
+  SELECT 'message', new.id, public_id FROM new_public_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_ad_public_id AFTER DELETE ON projects BEGIN
+  DELETE FROM public_ids WHERE entity = 'project' AND entity_id = old.id;
+END;
+CREATE TRIGGER IF NOT EXISTS agents_ad_public_id AFTER DELETE ON agents BEGIN
+  DELETE FROM public_ids WHERE entity = 'agent' AND entity_id = old.id;
+END;
+CREATE TRIGGER IF NOT EXISTS messages_ad_public_id AFTER DELETE ON messages BEGIN
+  DELETE FROM public_ids WHERE entity = 'message' AND entity_id = old.id;
+END;
+
+-- Give rows that predate this table an id, dated by their creation time
+INSERT INTO public_ids (entity, entity_id, public_id)
+SELECT entity, entity_id,
+       substr(a, ((ms >> 45) & 7) + 1, 1) || substr(a, ((ms >> 40) & 31) + 1, 1) ||
+       substr(a, ((ms >> 35) & 31) + 1, 1) || substr(a, ((ms >> 30) & 31) + 1, 1) ||
+       substr(a, ((ms >> 25) & 31) + 1, 1) || substr(a, ((ms >> 20) & 31) + 1, 1) ||
+       substr(a, ((ms >> 15) & 31) + 1, 1) || substr(a, ((ms >> 10) & 31) + 1, 1) ||
+       substr(a, ((ms >> 5) & 31) + 1, 1) || substr(a, (ms & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1) ||
+       substr(a, (random() & 31) + 1, 1) || substr(a, (random() & 31) + 1, 1)
+FROM (
+    SELECT entity, entity_id,
+           MAX(CAST((COALESCE(julianday(created), julianday('now')) - 2440587.5) * 86400000 AS INTEGER), 0) AS ms,
+           '0123456789ABCDEFGHJKMNPQRSTVWXYZ' AS a
+    FROM (
+        SELECT 'project' AS entity, id AS entity_id, created_at AS created FROM projects
+        UNION ALL
+        SELECT 'agent', id, inception_ts FROM agents
+        UNION ALL
+        SELECT 'message', id, created_ts FROM messages
+    ) AS r
+    WHERE NOT EXISTS (
+        SELECT 1 FROM public_ids AS x WHERE x.entity = r.entity AND x.entity_id = r.entity_id
+    )
+);
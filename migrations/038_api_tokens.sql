@@ -0,0 +1,21 @@
+-- Per-agent API tokens (idempotent migration)
+-- Bearer tokens issued to one agent and limited to scopes such as
+-- read:inbox; only the SHA-256 hash of each token is stored
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    agent_id INTEGER NOT NULL REFERENCES agents(id),
+    name TEXT NOT NULL DEFAULT '',
+    token_hash TEXT NOT NULL UNIQUE,
+    token_prefix TEXT NOT NULL,
+    scopes TEXT NOT NULL,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_ts DATETIME,
+    last_used_ts DATETIME,
+    revoked_ts DATETIME
+);
+CREATE INDEX IF NOT EXISTS idx_api_tokens_agent ON api_tokens(agent_id);
+
+-- A deleted agent's tokens go with it
+CREATE TRIGGER IF NOT EXISTS agents_ad_api_tokens AFTER DELETE ON agents BEGIN
+    DELETE FROM api_tokens WHERE agent_id = OLD.id;
+END;
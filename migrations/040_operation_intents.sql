@@ -0,0 +1,13 @@
+-- Write-ahead journal of multi-step operations (idempotent migration)
+-- An operation records its intent before its first write and advances the
+-- stage as steps land; the row is removed once the operation finished.
+-- Rows left behind by a crash are completed or rolled back on startup
+CREATE TABLE IF NOT EXISTS operation_intents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    stage TEXT NOT NULL,
+    payload TEXT NOT NULL DEFAULT '{}',
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_operation_intents_created ON operation_intents(created_ts);
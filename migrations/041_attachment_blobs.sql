@@ -0,0 +1,37 @@
+-- Content-addressed attachment storage (idempotent migration)
+-- Attachment content is stored once per BLAKE3 hash. Every attachment row
+-- with content in the blob store links to its blob, and the blob counts
+-- the links so unreferenced content can be reclaimed by gc-attachments.
+-- Attachments stored before this migration have no link
+CREATE TABLE IF NOT EXISTS attachment_blobs (
+    hash TEXT PRIMARY KEY,
+    location TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    ref_count INTEGER NOT NULL DEFAULT 0,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_blobs_unreferenced ON attachment_blobs(ref_count, last_used_ts);
+
+CREATE TABLE IF NOT EXISTS attachment_hashes (
+    attachment_id INTEGER PRIMARY KEY REFERENCES attachments(id),
+    hash TEXT NOT NULL REFERENCES attachment_blobs(hash)
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_hashes_hash ON attachment_hashes(hash);
+
+-- Reference counts follow the links
+CREATE TRIGGER IF NOT EXISTS attachment_hashes_ai_ref AFTER INSERT ON attachment_hashes BEGIN
+    UPDATE attachment_blobs
+    SET ref_count = ref_count + 1, last_used_ts = CURRENT_TIMESTAMP
+    WHERE hash = NEW.hash;
+END;
+CREATE TRIGGER IF NOT EXISTS attachment_hashes_ad_ref AFTER DELETE ON attachment_hashes BEGIN
+    UPDATE attachment_blobs
+    SET ref_count = ref_count - 1, last_used_ts = CURRENT_TIMESTAMP
+    WHERE hash = OLD.hash;
+END;
+
+-- A deleted attachment releases its blob
+CREATE TRIGGER IF NOT EXISTS attachments_ad_hash AFTER DELETE ON attachments BEGIN
+    DELETE FROM attachment_hashes WHERE attachment_id = OLD.id;
+END;
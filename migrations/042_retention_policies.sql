@@ -0,0 +1,13 @@
+-- Per-project message retention (idempotent migration)
+-- A NULL column inherits the server-wide `retention` config; 0 turns the
+-- limit off for the project. Projects without a row inherit everything
+CREATE TABLE IF NOT EXISTS retention_policies (
+    project_id INTEGER PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+    -- Days a message is kept
+    max_age_days INTEGER,
+    -- Messages the project keeps, oldest purged first
+    max_messages INTEGER,
+    -- 'archive' keeps the git archive copies, 'delete' removes them too
+    action TEXT CHECK (action IN ('archive', 'delete')),
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
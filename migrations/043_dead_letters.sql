@@ -0,0 +1,30 @@
+-- Dead letters: sends that could not be delivered (idempotent migration)
+-- The project, sender or a recipient did not resolve, e.g. a deleted agent
+-- or a project adopted into another. The envelope is kept by name, since
+-- the project itself may be gone, so operators can requeue it
+CREATE TABLE IF NOT EXISTS dead_letters (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_slug TEXT NOT NULL,
+    sender_name TEXT NOT NULL,
+    on_behalf_of TEXT,
+    -- Comma-separated recipient names or addresses
+    to_names TEXT NOT NULL DEFAULT '',
+    cc_names TEXT NOT NULL DEFAULT '',
+    bcc_names TEXT NOT NULL DEFAULT '',
+    subject TEXT NOT NULL,
+    body_md TEXT NOT NULL,
+    thread_id TEXT,
+    importance TEXT,
+    ack_required INTEGER NOT NULL DEFAULT 0,
+    -- Why the last delivery attempt failed
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'requeued')),
+    -- Message created by a successful requeue
+    message_id INTEGER REFERENCES messages(id) ON DELETE SET NULL,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_dead_letters_status ON dead_letters(status, id);
+CREATE INDEX IF NOT EXISTS idx_dead_letters_project ON dead_letters(project_slug, status);
@@ -0,0 +1,16 @@
+-- Outbound email bridge (idempotent migration)
+-- Projects that forward matching messages to human mailboxes over SMTP
+CREATE TABLE IF NOT EXISTS email_bridge_projects (
+    project_id INTEGER PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+    -- Comma-separated addresses; empty uses email_bridge.to
+    recipients TEXT NOT NULL DEFAULT '',
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Single-row bridge state: the last message considered for forwarding
+CREATE TABLE IF NOT EXISTS email_bridge_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    last_message_id INTEGER NOT NULL DEFAULT 0,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
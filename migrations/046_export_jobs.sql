@@ -0,0 +1,24 @@
+-- Asynchronous bulk exports (idempotent migration)
+-- A job renders a project's messages in the background; the finished file
+-- is kept under <data>/exports until the job expires
+CREATE TABLE IF NOT EXISTS export_jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    -- html, json, markdown, csv or mbox
+    format TEXT NOT NULL,
+    -- none, standard or aggressive
+    scrub TEXT NOT NULL DEFAULT 'none',
+    include_attachments INTEGER NOT NULL DEFAULT 0,
+    -- JSON message filters (since, until, thread_id, sender, importance, limit)
+    filters TEXT NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    message_count INTEGER,
+    size_bytes INTEGER,
+    error TEXT,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_ts DATETIME,
+    completed_ts DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_export_jobs_status ON export_jobs(status, id);
+CREATE INDEX IF NOT EXISTS idx_export_jobs_project ON export_jobs(project_id, id);
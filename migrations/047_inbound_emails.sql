@@ -0,0 +1,12 @@
+-- Inbound email gateway (idempotent migration)
+-- Mail ingested through /api/ingest/email, by its Message-ID, so a
+-- redelivered mail posts nothing and replies to it find its thread
+CREATE TABLE IF NOT EXISTS inbound_emails (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    email_message_id TEXT NOT NULL UNIQUE,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    message_id INTEGER NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_inbound_emails_project ON inbound_emails(project_id);
@@ -0,0 +1,11 @@
+-- Parked messages (idempotent migration)
+-- Messages a cursor scan (email bridge, Web Push) met while they were held
+-- for approval or deferred by a focus window; passed on once delivered
+CREATE TABLE IF NOT EXISTS parked_messages (
+    consumer TEXT NOT NULL,
+    message_id INTEGER NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    -- Set once a scan has passed the delivered message on
+    released INTEGER NOT NULL DEFAULT 0,
+    created_ts DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (consumer, message_id)
+);
@@ -0,0 +1,221 @@
+-- Mouchak Mail initial schema migration (Postgres)
+-- Columns hold what they hold in SQLite: INTEGER and BOOLEAN columns are
+-- BIGINT, DATETIME columns TEXT as CURRENT_TIMESTAMP writes it. Foreign
+-- keys are left out, as SQLite runs without foreign_keys enforcement and
+-- the BMCs order their deletes for that.
+
+-- CURRENT_TIMESTAMP as SQLite writes it: 'YYYY-MM-DD HH:MM:SS' in UTC
+CREATE OR REPLACE FUNCTION now_text() RETURNS TEXT AS $$
+    SELECT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')
+$$ LANGUAGE SQL STABLE;
+
+-- SQLite's json_valid()
+CREATE OR REPLACE FUNCTION json_valid(value TEXT) RETURNS BOOLEAN AS $$
+BEGIN
+    PERFORM value::jsonb;
+    RETURN TRUE;
+EXCEPTION WHEN others THEN
+    RETURN FALSE;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- RFC 7396 merge of patch into target, as SQLite's json_patch() does it
+CREATE OR REPLACE FUNCTION jsonb_merge_patch(target JSONB, patch JSONB) RETURNS JSONB AS $$
+DECLARE
+    merged JSONB;
+    entry RECORD;
+BEGIN
+    IF patch IS NULL OR jsonb_typeof(patch) <> 'object' THEN
+        RETURN patch;
+    END IF;
+    merged := CASE WHEN jsonb_typeof(target) = 'object' THEN target ELSE '{}' END;
+    FOR entry IN SELECT key, value FROM jsonb_each(patch) LOOP
+        IF jsonb_typeof(entry.value) = 'null' THEN
+            merged := merged - entry.key;
+        ELSE
+            merged := jsonb_set(merged, ARRAY[entry.key],
+                jsonb_merge_patch(merged -> entry.key, entry.value));
+        END IF;
+    END LOOP;
+    RETURN merged;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- SQLite's json_patch() on JSON text
+CREATE OR REPLACE FUNCTION json_patch(target TEXT, patch TEXT) RETURNS TEXT AS $$
+    SELECT CAST(jsonb_merge_patch(CAST(target AS JSONB), CAST(patch AS JSONB)) AS TEXT)
+$$ LANGUAGE SQL IMMUTABLE;
+
+-- SQLite's datetime() on 'now' or a timestamp, with an optional
+-- modifier such as '-24 hours'
+CREATE OR REPLACE FUNCTION datetime(value TEXT, modifier TEXT DEFAULT '0 seconds') RETURNS TEXT AS $$
+    SELECT to_char(
+        CASE WHEN value = 'now' THEN now() AT TIME ZONE 'UTC' ELSE CAST(value AS TIMESTAMP) END
+            + CAST(modifier AS INTERVAL),
+        'YYYY-MM-DD HH24:MI:SS')
+$$ LANGUAGE SQL STABLE;
+
+-- SQLite's strftime('%s', value): seconds since the epoch, the only
+-- format the queries use, as a number so differences can be taken
+CREATE OR REPLACE FUNCTION strftime(format TEXT, value TEXT) RETURNS BIGINT AS $$
+    SELECT CASE WHEN format = '%s' THEN CAST(extract(epoch FROM
+        CASE WHEN value = 'now' THEN now() AT TIME ZONE 'UTC' ELSE CAST(value AS TIMESTAMP) END
+    ) AS BIGINT) END
+$$ LANGUAGE SQL STABLE;
+
+-- SQLite's group_concat() with the default ',' separator
+CREATE OR REPLACE FUNCTION group_concat_step(acc TEXT, value ANYELEMENT) RETURNS TEXT AS $$
+    SELECT CASE
+        WHEN value IS NULL THEN acc
+        WHEN acc IS NULL THEN CAST(value AS TEXT)
+        ELSE acc || ',' || CAST(value AS TEXT)
+    END
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE OR REPLACE AGGREGATE group_concat(ANYELEMENT) (SFUNC = group_concat_step, STYPE = TEXT);
+
+-- Create projects table
+CREATE TABLE IF NOT EXISTS projects (
+    id BIGSERIAL PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    human_key TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT now_text()
+);
+
+-- Create products table
+CREATE TABLE IF NOT EXISTS products (
+    id BIGSERIAL PRIMARY KEY,
+    product_uid TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT now_text()
+);
+
+-- Create product_project_links table
+CREATE TABLE IF NOT EXISTS product_project_links (
+    id BIGSERIAL PRIMARY KEY,
+    product_id BIGINT NOT NULL,
+    project_id BIGINT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (product_id, project_id)
+);
+
+-- Create agents table
+CREATE TABLE IF NOT EXISTS agents (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    program TEXT NOT NULL,
+    model TEXT NOT NULL,
+    task_description TEXT NOT NULL DEFAULT '',
+    inception_ts TEXT NOT NULL DEFAULT now_text(),
+    last_active_ts TEXT NOT NULL DEFAULT now_text(),
+    attachments_policy TEXT NOT NULL DEFAULT 'auto',
+    contact_policy TEXT NOT NULL DEFAULT 'auto',
+    UNIQUE (project_id, name)
+);
+
+-- Create messages table
+CREATE TABLE IF NOT EXISTS messages (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    sender_id BIGINT NOT NULL,
+    thread_id TEXT,
+    subject TEXT NOT NULL,
+    body_md TEXT NOT NULL,
+    importance TEXT NOT NULL DEFAULT 'normal',
+    ack_required BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    attachments TEXT NOT NULL DEFAULT '[]'
+);
+
+-- messages_fts has no Postgres counterpart: message search indexes
+-- subject and body together (see 033_message_search.sql)
+
+-- Create message_recipients table
+CREATE TABLE IF NOT EXISTS message_recipients (
+    message_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    recipient_type TEXT NOT NULL DEFAULT 'to',
+    read_ts TEXT,
+    ack_ts TEXT,
+    PRIMARY KEY (message_id, agent_id),
+    CHECK (recipient_type IN ('to', 'cc', 'bcc'))
+);
+
+-- Create file_reservations table
+CREATE TABLE IF NOT EXISTS file_reservations (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    path_pattern TEXT NOT NULL,
+    exclusive BIGINT NOT NULL DEFAULT 1,
+    reason TEXT NOT NULL DEFAULT '',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    expires_ts TEXT NOT NULL,
+    released_ts TEXT
+);
+
+-- Create agent_links table
+CREATE TABLE IF NOT EXISTS agent_links (
+    id BIGSERIAL PRIMARY KEY,
+    a_project_id BIGINT NOT NULL,
+    a_agent_id BIGINT NOT NULL,
+    b_project_id BIGINT NOT NULL,
+    b_agent_id BIGINT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    reason TEXT NOT NULL DEFAULT '',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    updated_ts TEXT NOT NULL DEFAULT now_text(),
+    expires_ts TEXT,
+    UNIQUE (a_project_id, a_agent_id, b_project_id, b_agent_id)
+);
+
+-- Create project_sibling_suggestions table
+CREATE TABLE IF NOT EXISTS project_sibling_suggestions (
+    id BIGSERIAL PRIMARY KEY,
+    project_a_id BIGINT NOT NULL,
+    project_b_id BIGINT NOT NULL,
+    score DOUBLE PRECISION NOT NULL DEFAULT 0.0,
+    status TEXT NOT NULL DEFAULT 'suggested',
+    rationale TEXT NOT NULL DEFAULT '',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    evaluated_ts TEXT NOT NULL DEFAULT now_text(),
+    confirmed_ts TEXT,
+    dismissed_ts TEXT,
+    UNIQUE (project_a_id, project_b_id)
+);
+
+-- Create build_slots table
+CREATE TABLE IF NOT EXISTS build_slots (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    slot_name TEXT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    expires_ts TEXT NOT NULL,
+    released_ts TEXT
+);
+
+-- Create macros table
+CREATE TABLE IF NOT EXISTS macros (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    steps TEXT NOT NULL DEFAULT '[]',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    updated_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (project_id, name)
+);
+
+-- Create overseer_messages table
+CREATE TABLE IF NOT EXISTS overseer_messages (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    sender_id BIGINT NOT NULL,
+    subject TEXT NOT NULL,
+    body_md TEXT NOT NULL,
+    importance TEXT NOT NULL DEFAULT 'normal',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    read_ts TEXT
+);
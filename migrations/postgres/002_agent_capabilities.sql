@@ -0,0 +1,11 @@
+-- Agent Capabilities table (idempotent migration)
+CREATE TABLE IF NOT EXISTS agent_capabilities (
+    id BIGSERIAL PRIMARY KEY,
+    agent_id BIGINT NOT NULL,
+    capability TEXT NOT NULL,
+    granted_at TEXT NOT NULL,
+    granted_by BIGINT,
+    expires_at TEXT,
+    UNIQUE (agent_id, capability)
+);
+CREATE INDEX IF NOT EXISTS idx_agent_capabilities_agent_id ON agent_capabilities(agent_id);
@@ -0,0 +1,15 @@
+-- Attachments table (idempotent migration)
+CREATE TABLE IF NOT EXISTS attachments (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    agent_id BIGINT,
+    filename TEXT NOT NULL,
+    stored_path TEXT NOT NULL,
+    media_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    created_ts TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_project ON attachments(project_id);
+CREATE INDEX IF NOT EXISTS idx_attachments_agent ON attachments(agent_id);
+CREATE INDEX IF NOT EXISTS idx_attachments_project_agent ON attachments(project_id, agent_id);
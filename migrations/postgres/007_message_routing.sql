@@ -0,0 +1,12 @@
+-- Capability routing resolutions (idempotent migration)
+-- Records how `capability:<name>` addresses were resolved to recipients at send time
+CREATE TABLE IF NOT EXISTS message_routing (
+    id BIGSERIAL PRIMARY KEY,
+    message_id BIGINT NOT NULL,
+    address TEXT NOT NULL,
+    capability TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    resolved_agent_ids TEXT NOT NULL DEFAULT '[]',
+    created_ts TEXT NOT NULL DEFAULT now_text()
+);
+CREATE INDEX IF NOT EXISTS idx_message_routing_message ON message_routing(message_id);
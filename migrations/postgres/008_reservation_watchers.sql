@@ -0,0 +1,12 @@
+-- File reservation release watchers (idempotent migration)
+-- Agents waiting on a reserved path; notified_ts is set once a release receipt is sent
+CREATE TABLE IF NOT EXISTS file_reservation_watchers (
+    id BIGSERIAL PRIMARY KEY,
+    reservation_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    notified_ts TEXT,
+    UNIQUE(reservation_id, agent_id)
+);
+CREATE INDEX IF NOT EXISTS idx_reservation_watchers_pending
+    ON file_reservation_watchers(reservation_id) WHERE notified_ts IS NULL;
@@ -0,0 +1,27 @@
+-- Project focus windows (idempotent migration)
+-- Time-boxed quiet periods during which low-importance deliveries are deferred
+CREATE TABLE IF NOT EXISTS project_focus_windows (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    starts_ts TEXT NOT NULL,
+    ends_ts TEXT NOT NULL,
+    allowed_importance TEXT NOT NULL DEFAULT 'high,urgent',
+    reason TEXT NOT NULL DEFAULT '',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    ended_ts TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_focus_windows_project ON project_focus_windows(project_id, ends_ts);
+
+-- Recipient rows held back by a focus window; copied to message_recipients on delivery
+CREATE TABLE IF NOT EXISTS deferred_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    focus_window_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    recipient_type TEXT NOT NULL DEFAULT 'to',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    delivered_ts TEXT,
+    UNIQUE(message_id, agent_id)
+);
+CREATE INDEX IF NOT EXISTS idx_deferred_deliveries_pending
+    ON deferred_deliveries(focus_window_id) WHERE delivered_ts IS NULL;
@@ -0,0 +1,36 @@
+-- Searchable attachment text (idempotent migration)
+-- Text-like attachment contents are extracted here and indexed for full-text search;
+-- binary and oversized files are never stored
+CREATE TABLE IF NOT EXISTS attachment_texts (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    attachment_ref TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    content TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    truncated BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE(message_id, attachment_ref)
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_texts_project ON attachment_texts(project_id);
+
+-- Search document over filename and content; queries use the same expression
+-- so the planner picks up the index
+CREATE OR REPLACE FUNCTION attachment_text_document(filename TEXT, content TEXT) RETURNS tsvector AS $$
+    SELECT to_tsvector('simple', filename || ' ' || content)
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX IF NOT EXISTS idx_attachment_texts_search
+    ON attachment_texts USING GIN (attachment_text_document(filename, content));
+
+-- Drop indexed text along with its message
+CREATE OR REPLACE FUNCTION messages_ad_attachment_texts() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM attachment_texts WHERE message_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER messages_ad_attachment_texts AFTER DELETE ON messages
+FOR EACH ROW EXECUTE FUNCTION messages_ad_attachment_texts();
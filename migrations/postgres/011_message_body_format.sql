@@ -0,0 +1,16 @@
+-- Message body formats (idempotent migration)
+-- Only non-Markdown bodies get a row; a missing row means 'markdown'
+CREATE TABLE IF NOT EXISTS message_body_formats (
+    message_id BIGINT PRIMARY KEY,
+    body_format TEXT NOT NULL CHECK (body_format IN ('markdown', 'plain', 'json'))
+);
+
+CREATE OR REPLACE FUNCTION messages_ad_body_format() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM message_body_formats WHERE message_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER messages_ad_body_format AFTER DELETE ON messages
+FOR EACH ROW EXECUTE FUNCTION messages_ad_body_format();
@@ -0,0 +1,35 @@
+-- Append-only event log (idempotent migration)
+-- Each record stores the hash of the previous record, so any modification,
+-- insertion or deletion of historical records breaks the chain.
+CREATE TABLE IF NOT EXISTS event_log (
+    id BIGSERIAL PRIMARY KEY,
+    created_ts TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    project_id BIGINT,
+    actor TEXT,
+    payload TEXT NOT NULL DEFAULT '{}',
+    prev_hash TEXT NOT NULL,
+    hash TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_log_project ON event_log(project_id, id);
+
+-- Reject updates and deletes at the SQL level; the hash chain catches
+-- tampering that bypasses these triggers.
+CREATE OR REPLACE FUNCTION event_log_no_update() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'event_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER event_log_no_update BEFORE UPDATE ON event_log
+FOR EACH ROW EXECUTE FUNCTION event_log_no_update();
+
+CREATE OR REPLACE FUNCTION event_log_no_delete() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'event_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER event_log_no_delete BEFORE DELETE ON event_log
+FOR EACH ROW EXECUTE FUNCTION event_log_no_delete();
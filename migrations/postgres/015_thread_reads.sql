@@ -0,0 +1,11 @@
+-- Per-agent read position in each thread (idempotent migration)
+-- last_read_message_id is the newest message of the thread the agent has
+-- seen; later messages in the thread are unread for that agent.
+CREATE TABLE IF NOT EXISTS thread_reads (
+    project_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    thread_id TEXT NOT NULL,
+    last_read_message_id BIGINT NOT NULL,
+    updated_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (project_id, agent_id, thread_id)
+);
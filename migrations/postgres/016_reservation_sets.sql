@@ -0,0 +1,17 @@
+-- Reservation sets (idempotent migration)
+-- Multi-path reservations acquired all-or-nothing and released together;
+-- each member is an ordinary file_reservations row.
+CREATE TABLE IF NOT EXISTS reservation_sets (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    reason TEXT NOT NULL DEFAULT '',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    released_ts TEXT
+);
+
+CREATE TABLE IF NOT EXISTS reservation_set_members (
+    set_id BIGINT NOT NULL,
+    reservation_id BIGINT NOT NULL UNIQUE,
+    PRIMARY KEY (set_id, reservation_id)
+);
@@ -0,0 +1,15 @@
+-- Project contact policies (idempotent migration)
+-- Allow/deny rules controlling which other projects may initiate contact
+-- with a project. Cross-project contact is denied unless a rule allows it.
+CREATE TABLE IF NOT EXISTS project_contact_rules (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('allow', 'deny')),
+    target_kind TEXT NOT NULL CHECK (target_kind IN ('project', 'product')),
+    target TEXT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (project_id, target_kind, target)
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_contact_rules_project
+    ON project_contact_rules(project_id);
@@ -0,0 +1,18 @@
+-- Agent SLAs (idempotent migration)
+-- A project's acknowledgment deadlines per message importance, and the
+-- recipients already reported to the overseer for missing one.
+CREATE TABLE IF NOT EXISTS project_slas (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    importance TEXT NOT NULL CHECK (importance IN ('low', 'normal', 'high', 'urgent')),
+    ack_within_seconds BIGINT NOT NULL CHECK (ack_within_seconds > 0),
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (project_id, importance)
+);
+
+CREATE TABLE IF NOT EXISTS sla_violations (
+    message_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    reported_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (message_id, agent_id)
+);
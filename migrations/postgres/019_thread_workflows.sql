@@ -0,0 +1,37 @@
+-- Thread workflows (idempotent migration)
+-- Named state machines defined per project, attached to threads, with the
+-- history of every transition.
+CREATE TABLE IF NOT EXISTS workflows (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    -- JSON: {"states": [{"name", "responsible"}], "transitions": [{"from", "to"}]}
+    definition TEXT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    updated_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (project_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS thread_workflows (
+    project_id BIGINT NOT NULL,
+    thread_id TEXT NOT NULL,
+    workflow_id BIGINT NOT NULL,
+    state TEXT NOT NULL,
+    updated_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (project_id, thread_id)
+);
+
+CREATE TABLE IF NOT EXISTS workflow_transitions (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    thread_id TEXT NOT NULL,
+    -- NULL when the workflow was started
+    from_state TEXT,
+    to_state TEXT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    note TEXT,
+    created_ts TEXT NOT NULL DEFAULT now_text()
+);
+
+CREATE INDEX IF NOT EXISTS idx_workflow_transitions_thread
+    ON workflow_transitions(project_id, thread_id);
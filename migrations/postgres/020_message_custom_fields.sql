@@ -0,0 +1,27 @@
+-- Per-project custom message fields (idempotent migration)
+-- Projects declare typed fields; messages store their values as a JSON object.
+CREATE TABLE IF NOT EXISTS project_custom_fields (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL CHECK (field_type IN ('string', 'number', 'boolean')),
+    required BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (project_id, name)
+);
+
+-- Only messages with field values get a row
+CREATE TABLE IF NOT EXISTS message_custom_fields (
+    message_id BIGINT PRIMARY KEY,
+    fields TEXT NOT NULL CHECK (json_valid(fields))
+);
+
+CREATE OR REPLACE FUNCTION messages_ad_custom_fields() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM message_custom_fields WHERE message_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER messages_ad_custom_fields AFTER DELETE ON messages
+FOR EACH ROW EXECUTE FUNCTION messages_ad_custom_fields();
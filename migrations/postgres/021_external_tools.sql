@@ -0,0 +1,16 @@
+-- External HTTP tools proxied through MCP (idempotent migration)
+-- Operators register endpoints that appear as extra MCP tools.
+CREATE TABLE IF NOT EXISTS external_tools (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    input_schema TEXT NOT NULL CHECK (json_valid(input_schema)),
+    url TEXT NOT NULL,
+    -- Sent as-is in the Authorization header; never returned by the API
+    auth_header TEXT,
+    timeout_ms BIGINT NOT NULL DEFAULT 10000,
+    max_result_bytes BIGINT NOT NULL DEFAULT 65536,
+    enabled BIGINT NOT NULL DEFAULT 1,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    updated_ts TEXT NOT NULL DEFAULT now_text()
+);
@@ -0,0 +1,26 @@
+-- GitHub issue/PR cross-posting (idempotent migration)
+-- Projects map to a repository; mirrored threads link to one issue or PR.
+CREATE TABLE IF NOT EXISTS project_github_repos (
+    project_id BIGINT PRIMARY KEY,
+    -- "owner/name"
+    repo TEXT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    updated_ts TEXT NOT NULL DEFAULT now_text()
+);
+
+CREATE TABLE IF NOT EXISTS github_thread_links (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    thread_id TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    issue_number BIGINT NOT NULL,
+    issue_url TEXT NOT NULL,
+    -- Newest thread message posted to GitHub
+    last_message_id BIGINT NOT NULL DEFAULT 0,
+    -- Newest GitHub comment imported into the thread
+    last_comment_id BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    synced_ts TEXT,
+    UNIQUE (project_id, thread_id),
+    UNIQUE (repo, issue_number)
+);
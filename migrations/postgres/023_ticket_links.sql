@@ -0,0 +1,26 @@
+-- Jira/Linear ticket linking (idempotent migration)
+-- Tickets are cached once per provider and key and linked to any number of messages.
+CREATE TABLE IF NOT EXISTS tickets (
+    id BIGSERIAL PRIMARY KEY,
+    -- "jira" or "linear"
+    provider TEXT NOT NULL,
+    ticket_key TEXT NOT NULL,
+    -- Metadata as of the last successful fetch
+    title TEXT,
+    status TEXT,
+    url TEXT,
+    -- Why the last fetch failed, cleared by the next successful one
+    fetch_error TEXT,
+    fetched_ts TEXT,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (provider, ticket_key)
+);
+
+CREATE TABLE IF NOT EXISTS message_tickets (
+    message_id BIGINT NOT NULL,
+    ticket_id BIGINT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (message_id, ticket_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_tickets_ticket ON message_tickets(ticket_id);
@@ -0,0 +1,29 @@
+-- CI status ingestion (idempotent migration)
+-- Commits linked to threads route build events to those threads.
+CREATE TABLE IF NOT EXISTS commit_links (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    thread_id TEXT NOT NULL,
+    -- Lowercase SHA or SHA prefix (7-40 hex characters)
+    sha TEXT NOT NULL,
+    -- "owner/name"; NULL matches events from any repository
+    repo TEXT,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (project_id, thread_id, sha)
+);
+
+CREATE INDEX IF NOT EXISTS idx_commit_links_sha ON commit_links(sha);
+
+-- Every ingested build event; identical redeliveries are recognized and skipped
+CREATE TABLE IF NOT EXISTS ci_events (
+    id BIGSERIAL PRIMARY KEY,
+    provider TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    sha TEXT NOT NULL,
+    status TEXT NOT NULL,
+    url TEXT NOT NULL DEFAULT '',
+    -- Messages posted to threads plus overseer messages
+    delivered BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (provider, repo, sha, status, url)
+);
@@ -0,0 +1,15 @@
+-- Per-project time zone and business hours (idempotent migration)
+-- Projects without a row use UTC and no business hours (around the clock)
+CREATE TABLE IF NOT EXISTS project_settings (
+    project_id BIGINT PRIMARY KEY,
+    -- IANA time zone name, e.g. 'Europe/Berlin'
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    -- Local 'HH:MM' bounds of the working day; both NULL means no business hours
+    business_start TEXT,
+    business_end TEXT,
+    -- Comma-separated working days, e.g. 'mon,tue,wed,thu,fri'
+    business_days TEXT NOT NULL DEFAULT 'mon,tue,wed,thu,fri',
+    -- 1 = defer low-importance mail outside business hours
+    quiet_hours BIGINT NOT NULL DEFAULT 0,
+    updated_ts TEXT NOT NULL DEFAULT now_text()
+);
@@ -0,0 +1,20 @@
+-- Offloaded message bodies (idempotent migration)
+-- Bodies over the inline size cap are kept here; messages.body_md holds a preview.
+-- A missing row means the inline body is complete
+CREATE TABLE IF NOT EXISTS message_bodies (
+    message_id BIGINT PRIMARY KEY,
+    body_md TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    sha256 TEXT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text()
+);
+
+CREATE OR REPLACE FUNCTION messages_ad_bodies() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM message_bodies WHERE message_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER messages_ad_bodies AFTER DELETE ON messages
+FOR EACH ROW EXECUTE FUNCTION messages_ad_bodies();
@@ -0,0 +1,21 @@
+-- Archived (inactive) threads (idempotent migration)
+-- A row marks a thread archived: hidden from the default thread listing.
+-- Any new message in the thread deletes the row, reactivating it
+CREATE TABLE IF NOT EXISTS archived_threads (
+    project_id BIGINT NOT NULL,
+    thread_id TEXT NOT NULL,
+    last_message_ts TEXT NOT NULL,
+    summary_message_id BIGINT,
+    archived_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (project_id, thread_id)
+);
+
+CREATE OR REPLACE FUNCTION messages_ai_thread_reactivate() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM archived_threads WHERE project_id = NEW.project_id AND thread_id = NEW.thread_id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER messages_ai_thread_reactivate AFTER INSERT ON messages
+FOR EACH ROW WHEN (NEW.thread_id IS NOT NULL) EXECUTE FUNCTION messages_ai_thread_reactivate();
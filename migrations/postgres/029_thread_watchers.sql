@@ -0,0 +1,17 @@
+-- Thread watchers (idempotent migration)
+-- A watcher gets a copy of each new message in the thread without being
+-- addressed. Copies are delivered as bcc recipients and listed in
+-- watcher_deliveries so they can be told apart from addressed mail
+CREATE TABLE IF NOT EXISTS thread_watchers (
+    project_id BIGINT NOT NULL,
+    thread_id TEXT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (project_id, thread_id, agent_id)
+);
+
+CREATE TABLE IF NOT EXISTS watcher_deliveries (
+    message_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    PRIMARY KEY (message_id, agent_id)
+);
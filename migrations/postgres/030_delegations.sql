@@ -0,0 +1,17 @@
+-- Delegation grants (idempotent migration)
+-- A grantor lets a delegate send messages on its behalf. Messages sent that
+-- way keep the grantor as sender and name the delegate in message_delegations
+CREATE TABLE IF NOT EXISTS delegation_grants (
+    project_id BIGINT NOT NULL,
+    grantor_id BIGINT NOT NULL,
+    delegate_id BIGINT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (grantor_id, delegate_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_delegation_grants_project ON delegation_grants(project_id);
+
+CREATE TABLE IF NOT EXISTS message_delegations (
+    message_id BIGINT PRIMARY KEY,
+    delegate_id BIGINT NOT NULL
+);
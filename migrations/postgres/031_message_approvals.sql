@@ -0,0 +1,36 @@
+-- Approval-gated sends (idempotent migration)
+-- Messages matching one of a project's approval rules are stored but their
+-- recipient rows are held in held_deliveries until an approver decides.
+-- pending_approvals keeps every decision, including timeouts, for audit
+CREATE TABLE IF NOT EXISTS approval_rules (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    min_importance TEXT CHECK (min_importance IN ('low', 'normal', 'high', 'urgent')),
+    min_recipients BIGINT,
+    keyword TEXT,
+    approvers TEXT NOT NULL DEFAULT '',
+    timeout_seconds BIGINT NOT NULL CHECK (timeout_seconds > 0),
+    created_ts TEXT NOT NULL DEFAULT now_text()
+);
+CREATE INDEX IF NOT EXISTS idx_approval_rules_project ON approval_rules(project_id);
+
+CREATE TABLE IF NOT EXISTS pending_approvals (
+    message_id BIGINT PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    rule_id BIGINT NOT NULL,
+    approvers TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'rejected')),
+    expires_ts TEXT NOT NULL,
+    decided_by TEXT,
+    decided_ts TEXT,
+    reason TEXT,
+    created_ts TEXT NOT NULL DEFAULT now_text()
+);
+CREATE INDEX IF NOT EXISTS idx_pending_approvals_status ON pending_approvals(status, expires_ts);
+
+CREATE TABLE IF NOT EXISTS held_deliveries (
+    message_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    recipient_type TEXT NOT NULL DEFAULT 'to',
+    PRIMARY KEY (message_id, agent_id)
+);
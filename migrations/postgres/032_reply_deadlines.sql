@@ -0,0 +1,14 @@
+-- Reply deadlines (idempotent migration)
+-- A sender can ask the recipients of a message to respond by a given time.
+-- Each recipient accepts the requested deadline or proposes another one;
+-- agreed deadlines replace the fixed threshold of ack escalation
+CREATE TABLE IF NOT EXISTS reply_deadlines (
+    message_id BIGINT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    requested_ts TEXT NOT NULL,
+    deadline_ts TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'requested' CHECK (status IN ('requested', 'accepted', 'proposed')),
+    responded_ts TEXT,
+    PRIMARY KEY (message_id, agent_id)
+);
+CREATE INDEX IF NOT EXISTS idx_reply_deadlines_agent ON reply_deadlines(agent_id, deadline_ts);
@@ -0,0 +1,11 @@
+-- Ranked message search (idempotent migration)
+-- Indexes subject and body together so searches match either and can be
+-- ranked with ts_rank(). Subject terms weigh A, body terms B, mirroring the
+-- bm25(message_search, 2.0, 1.0) column weights on SQLite
+CREATE OR REPLACE FUNCTION message_search_document(subject TEXT, body_md TEXT) RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('simple', subject), 'A')
+        || setweight(to_tsvector('simple', body_md), 'B')
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX IF NOT EXISTS idx_messages_search
+    ON messages USING GIN (message_search_document(subject, body_md));
@@ -0,0 +1,59 @@
+-- Global thread ids (idempotent migration)
+-- Thread ids are project-scoped strings, so the same id in two projects may
+-- name unrelated threads. Each (project, thread) maps to a ULID; a message
+-- sent with a global thread id as its thread id joins that global thread,
+-- which is how one conversation spans the projects of a product
+CREATE TABLE IF NOT EXISTS global_threads (
+    project_id BIGINT NOT NULL,
+    thread_id TEXT NOT NULL,
+    global_thread_id TEXT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (project_id, thread_id)
+);
+CREATE INDEX IF NOT EXISTS idx_global_threads_global ON global_threads(global_thread_id);
+CREATE INDEX IF NOT EXISTS idx_global_threads_thread ON global_threads(thread_id);
+
+-- A ULID for the given Unix millisecond: 10 time digits and 16 random
+-- base32 digits
+CREATE OR REPLACE FUNCTION ulid_at(ms BIGINT) RETURNS TEXT AS $$
+DECLARE
+    a CONSTANT TEXT := '0123456789ABCDEFGHJKMNPQRSTVWXYZ';
+    id TEXT := substr(a, ((GREATEST(ms, 0) >> 45) & 7)::INT + 1, 1);
+BEGIN
+    FOR i IN REVERSE 8..0 LOOP
+        id := id || substr(a, ((GREATEST(ms, 0) >> (i * 5)) & 31)::INT + 1, 1);
+    END LOOP;
+    FOR i IN 1..16 LOOP
+        id := id || substr(a, floor(random() * 32)::INT + 1, 1);
+    END LOOP;
+    RETURN id;
+END;
+$$ LANGUAGE plpgsql VOLATILE;
+
+-- Map threads that predate this table; a no-op once anything is mapped.
+-- Before global ids, product tools merged equal thread ids across a
+-- product's projects, so those keep sharing one global thread. Each ULID
+-- takes its thread's first message time
+WITH pairs AS MATERIALIZED (
+    SELECT m.project_id, m.thread_id, MIN(m.created_ts) AS first_ts,
+           COALESCE(
+               (SELECT 'product:' || MIN(l.product_id) FROM product_project_links AS l
+                WHERE l.project_id = m.project_id),
+               'project:' || m.project_id
+           ) AS scope
+    FROM messages AS m
+    WHERE m.thread_id IS NOT NULL
+      AND NOT EXISTS (SELECT 1 FROM global_threads)
+    GROUP BY m.project_id, m.thread_id
+),
+ids AS MATERIALIZED (
+    SELECT scope, thread_id,
+           ulid_at(CAST(EXTRACT(EPOCH FROM CAST(MIN(first_ts) AS TIMESTAMP)) * 1000 AS BIGINT))
+               AS global_thread_id
+    FROM pairs
+    GROUP BY scope, thread_id
+)
+INSERT INTO global_threads (project_id, thread_id, global_thread_id, created_ts)
+SELECT p.project_id, p.thread_id, i.global_thread_id, p.first_ts
+FROM pairs AS p
+JOIN ids AS i ON i.scope = p.scope AND i.thread_id = p.thread_id;
@@ -0,0 +1,15 @@
+-- Saved searches (idempotent migration)
+-- Named message queries stored per project, optionally owned by one agent,
+-- and evaluated on demand so UIs can show them as smart folders.
+-- criteria is a JSON object (see model/saved_search.rs)
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    agent_id BIGINT,
+    name TEXT NOT NULL,
+    criteria TEXT NOT NULL DEFAULT '{}',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    updated_ts TEXT NOT NULL DEFAULT now_text()
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_saved_searches_name
+    ON saved_searches(project_id, COALESCE(agent_id, 0), name);
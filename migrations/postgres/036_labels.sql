@@ -0,0 +1,20 @@
+-- Message labels (idempotent migration)
+-- Colored labels are declared per project and applied to messages by
+-- agents, for triage beyond importance levels
+CREATE TABLE IF NOT EXISTS labels (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    color TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    UNIQUE (project_id, name)
+);
+CREATE TABLE IF NOT EXISTS message_labels (
+    message_id BIGINT NOT NULL,
+    label_id BIGINT NOT NULL,
+    applied_by BIGINT,
+    applied_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (message_id, label_id)
+);
+CREATE INDEX IF NOT EXISTS idx_message_labels_label ON message_labels(label_id, message_id);
@@ -0,0 +1,96 @@
+-- Public ids (idempotent migration)
+-- Integer keys leak creation order and collide between databases, so every
+-- project, agent and message also gets a ULID that external systems can
+-- reference. Ids are assigned by trigger on insert and never change
+CREATE TABLE IF NOT EXISTS public_ids (
+    entity TEXT NOT NULL CHECK (entity IN ('project', 'agent', 'message')),
+    entity_id BIGINT NOT NULL,
+    public_id TEXT NOT NULL UNIQUE,
+    PRIMARY KEY (entity, entity_id)
+);
+
+-- A fresh ULID for the current millisecond (ulid_at is in 034_global_threads.sql)
+CREATE OR REPLACE FUNCTION new_public_id() RETURNS TEXT AS $$
+    SELECT ulid_at(CAST(EXTRACT(EPOCH FROM clock_timestamp()) * 1000 AS BIGINT))
+$$ LANGUAGE SQL VOLATILE;
+
+CREATE OR REPLACE FUNCTION projects_ai_public_id() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO public_ids (entity, entity_id, public_id)
+    VALUES ('project', NEW.id, new_public_id())
+    ON CONFLICT DO NOTHING;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER projects_ai_public_id AFTER INSERT ON projects
+FOR EACH ROW EXECUTE FUNCTION projects_ai_public_id();
+
+CREATE OR REPLACE FUNCTION agents_ai_public_id() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO public_ids (entity, entity_id, public_id)
+    VALUES ('agent', NEW.id, new_public_id())
+    ON CONFLICT DO NOTHING;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER agents_ai_public_id AFTER INSERT ON agents
+FOR EACH ROW EXECUTE FUNCTION agents_ai_public_id();
+
+CREATE OR REPLACE FUNCTION messages_ai_public_id() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO public_ids (entity, entity_id, public_id)
+    VALUES ('message', NEW.id, new_public_id())
+    ON CONFLICT DO NOTHING;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER messages_ai_public_id AFTER INSERT ON messages
+FOR EACH ROW EXECUTE FUNCTION messages_ai_public_id();
+
+CREATE OR REPLACE FUNCTION projects_ad_public_id() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM public_ids WHERE entity = 'project' AND entity_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER projects_ad_public_id AFTER DELETE ON projects
+FOR EACH ROW EXECUTE FUNCTION projects_ad_public_id();
+
+CREATE OR REPLACE FUNCTION agents_ad_public_id() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM public_ids WHERE entity = 'agent' AND entity_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER agents_ad_public_id AFTER DELETE ON agents
+FOR EACH ROW EXECUTE FUNCTION agents_ad_public_id();
+
+CREATE OR REPLACE FUNCTION messages_ad_public_id() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM public_ids WHERE entity = 'message' AND entity_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER messages_ad_public_id AFTER DELETE ON messages
+FOR EACH ROW EXECUTE FUNCTION messages_ad_public_id();
+
+-- Give rows that predate this table an id, dated by their creation time
+INSERT INTO public_ids (entity, entity_id, public_id)
+SELECT entity, entity_id,
+       ulid_at(CAST(EXTRACT(EPOCH FROM COALESCE(CAST(created AS TIMESTAMP), now())) * 1000 AS BIGINT))
+FROM (
+    SELECT 'project' AS entity, id AS entity_id, created_at AS created FROM projects
+    UNION ALL
+    SELECT 'agent', id, inception_ts FROM agents
+    UNION ALL
+    SELECT 'message', id, created_ts FROM messages
+) AS r
+WHERE NOT EXISTS (
+    SELECT 1 FROM public_ids AS x WHERE x.entity = r.entity AND x.entity_id = r.entity_id
+);
@@ -0,0 +1,27 @@
+-- Per-agent API tokens (idempotent migration)
+-- Bearer tokens issued to one agent and limited to scopes such as
+-- read:inbox; only the SHA-256 hash of each token is stored
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id BIGSERIAL PRIMARY KEY,
+    agent_id BIGINT NOT NULL,
+    name TEXT NOT NULL DEFAULT '',
+    token_hash TEXT NOT NULL UNIQUE,
+    token_prefix TEXT NOT NULL,
+    scopes TEXT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    expires_ts TEXT,
+    last_used_ts TEXT,
+    revoked_ts TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_api_tokens_agent ON api_tokens(agent_id);
+
+-- A deleted agent's tokens go with it
+CREATE OR REPLACE FUNCTION agents_ad_api_tokens() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM api_tokens WHERE agent_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER agents_ad_api_tokens AFTER DELETE ON agents
+FOR EACH ROW EXECUTE FUNCTION agents_ad_api_tokens();
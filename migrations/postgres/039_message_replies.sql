@@ -0,0 +1,20 @@
+-- Explicit reply chains (idempotent migration)
+-- A reply records the message it answers, so a thread can be shown as a
+-- tree of replies instead of a flat list. Messages sent into a thread
+-- without replying to one message have no row here
+CREATE TABLE IF NOT EXISTS message_replies (
+    message_id BIGINT PRIMARY KEY,
+    in_reply_to BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_message_replies_parent ON message_replies(in_reply_to);
+
+-- A deleted message takes its own link and its replies' links with it
+CREATE OR REPLACE FUNCTION messages_ad_replies() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM message_replies WHERE message_id = OLD.id OR in_reply_to = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER messages_ad_replies AFTER DELETE ON messages
+FOR EACH ROW EXECUTE FUNCTION messages_ad_replies();
@@ -0,0 +1,56 @@
+-- Content-addressed attachment storage (idempotent migration)
+-- Attachment content is stored once per BLAKE3 hash. Every attachment row
+-- with content in the blob store links to its blob, and the blob counts
+-- the links so unreferenced content can be reclaimed by gc-attachments.
+-- Attachments stored before this migration have no link
+CREATE TABLE IF NOT EXISTS attachment_blobs (
+    hash TEXT PRIMARY KEY,
+    location TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    ref_count BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    last_used_ts TEXT NOT NULL DEFAULT now_text()
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_blobs_unreferenced ON attachment_blobs(ref_count, last_used_ts);
+
+CREATE TABLE IF NOT EXISTS attachment_hashes (
+    attachment_id BIGINT PRIMARY KEY,
+    hash TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_hashes_hash ON attachment_hashes(hash);
+
+-- Reference counts follow the links
+CREATE OR REPLACE FUNCTION attachment_hashes_ai_ref() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE attachment_blobs
+    SET ref_count = ref_count + 1, last_used_ts = now_text()
+    WHERE hash = NEW.hash;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER attachment_hashes_ai_ref AFTER INSERT ON attachment_hashes
+FOR EACH ROW EXECUTE FUNCTION attachment_hashes_ai_ref();
+
+CREATE OR REPLACE FUNCTION attachment_hashes_ad_ref() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE attachment_blobs
+    SET ref_count = ref_count - 1, last_used_ts = now_text()
+    WHERE hash = OLD.hash;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER attachment_hashes_ad_ref AFTER DELETE ON attachment_hashes
+FOR EACH ROW EXECUTE FUNCTION attachment_hashes_ad_ref();
+
+-- A deleted attachment releases its blob
+CREATE OR REPLACE FUNCTION attachments_ad_hash() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM attachment_hashes WHERE attachment_id = OLD.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER attachments_ad_hash AFTER DELETE ON attachments
+FOR EACH ROW EXECUTE FUNCTION attachments_ad_hash();
@@ -0,0 +1,42 @@
+-- Webhooks: HTTP callbacks for project events (idempotent migration)
+-- A webhook follows the event log from `last_event_id` on; every matching
+-- record becomes one delivery, retried with backoff until it is delivered
+-- or runs out of attempts
+CREATE TABLE IF NOT EXISTS webhooks (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    url TEXT NOT NULL,
+    -- Comma-separated webhook event names, e.g. 'message.created'
+    events TEXT NOT NULL,
+    -- HMAC-SHA256 key signing every payload
+    secret TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    active BIGINT NOT NULL DEFAULT 1,
+    -- Last event log record turned into deliveries
+    last_event_id BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    updated_ts TEXT NOT NULL DEFAULT now_text()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhooks_project ON webhooks(project_id, active);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook_id BIGINT NOT NULL,
+    event_id BIGINT NOT NULL,
+    event TEXT NOT NULL,
+    -- JSON body POSTed to the webhook URL
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed')),
+    attempts BIGINT NOT NULL DEFAULT 0,
+    next_attempt_ts TEXT NOT NULL DEFAULT now_text(),
+    -- HTTP status and error of the last attempt
+    response_status BIGINT,
+    error TEXT,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    delivered_ts TEXT,
+    UNIQUE (webhook_id, event_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_ts);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, id);
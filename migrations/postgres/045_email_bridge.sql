@@ -0,0 +1,16 @@
+-- Outbound email bridge (idempotent migration)
+-- Projects that forward matching messages to human mailboxes over SMTP
+CREATE TABLE IF NOT EXISTS email_bridge_projects (
+    project_id BIGINT PRIMARY KEY,
+    -- Comma-separated addresses; empty uses email_bridge.to
+    recipients TEXT NOT NULL DEFAULT '',
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    updated_ts TEXT NOT NULL DEFAULT now_text()
+);
+
+-- Single-row bridge state: the last message considered for forwarding
+CREATE TABLE IF NOT EXISTS email_bridge_state (
+    id BIGINT PRIMARY KEY CHECK (id = 1),
+    last_message_id BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text()
+);
@@ -0,0 +1,24 @@
+-- Asynchronous bulk exports (idempotent migration)
+-- A job renders a project's messages in the background; the finished file
+-- is kept under <data>/exports until the job expires
+CREATE TABLE IF NOT EXISTS export_jobs (
+    id BIGSERIAL PRIMARY KEY,
+    project_id BIGINT NOT NULL,
+    -- html, json, markdown, csv or mbox
+    format TEXT NOT NULL,
+    -- none, standard or aggressive
+    scrub TEXT NOT NULL DEFAULT 'none',
+    include_attachments BIGINT NOT NULL DEFAULT 0,
+    -- JSON message filters (since, until, thread_id, sender, importance, limit)
+    filters TEXT NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    message_count BIGINT,
+    size_bytes BIGINT,
+    error TEXT,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    started_ts TEXT,
+    completed_ts TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_export_jobs_status ON export_jobs(status, id);
+CREATE INDEX IF NOT EXISTS idx_export_jobs_project ON export_jobs(project_id, id);
@@ -0,0 +1,12 @@
+-- Inbound email gateway (idempotent migration)
+-- Mail ingested through /api/ingest/email, by its Message-ID, so a
+-- redelivered mail posts nothing and replies to it find its thread
+CREATE TABLE IF NOT EXISTS inbound_emails (
+    id BIGSERIAL PRIMARY KEY,
+    email_message_id TEXT NOT NULL UNIQUE,
+    project_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    created_ts TEXT NOT NULL DEFAULT now_text()
+);
+
+CREATE INDEX IF NOT EXISTS idx_inbound_emails_project ON inbound_emails(project_id);
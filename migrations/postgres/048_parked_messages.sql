@@ -0,0 +1,11 @@
+-- Parked messages (idempotent migration)
+-- Messages a cursor scan (email bridge, Web Push) met while they were held
+-- for approval or deferred by a focus window; passed on once delivered
+CREATE TABLE IF NOT EXISTS parked_messages (
+    consumer TEXT NOT NULL,
+    message_id BIGINT NOT NULL,
+    -- Set once a scan has passed the delivered message on
+    released BIGINT NOT NULL DEFAULT 0,
+    created_ts TEXT NOT NULL DEFAULT now_text(),
+    PRIMARY KEY (consumer, message_id)
+);